@@ -63,6 +63,7 @@ fn main() {
         // Check main source files
         let src_files = [
             frontend_dir.join("src/main.ts"),
+            frontend_dir.join("src/escape.js"),
             frontend_dir.join("index.html"),
             frontend_dir.join("package.json"),
             frontend_dir.join("vite.config.js"),
@@ -116,6 +117,12 @@ fn main() {
 
     // Set environment variable for the template path
     println!("cargo:rustc-env=TEMPLATE_PATH={}", template_path.display());
+
+    // Target triple, used by the updater to pick the matching release asset
+    println!(
+        "cargo:rustc-env=BUILD_TARGET={}",
+        env::var("TARGET").unwrap()
+    );
     println!(
         "cargo:warning=Using template at: {}",
         template_path.display()
@@ -0,0 +1,17 @@
+// Characters with a meaning in HTML text or quoted attribute values
+const HTML_ESCAPES = {
+  '&': '&amp;',
+  '<': '&lt;',
+  '>': '&gt;',
+  '"': '&quot;',
+  "'": '&#39;',
+};
+
+/**
+ * Escape text for use in HTML content and quoted attribute values
+ * @param {string} text
+ * @returns {string}
+ */
+export function escapeHtml(text) {
+  return String(text).replace(/[&<>"']/g, char => HTML_ESCAPES[char]);
+}
@@ -1,3 +1,5 @@
+import { escapeHtml } from './escape.js';
+
 // Types for the analysis data
 interface AnalysisData {
   metadata: {
@@ -9,6 +11,11 @@ interface AnalysisData {
     version?: string;
     publisher?: string;
     description?: string;
+    provenance?: {
+      product_name?: FieldProvenance;
+      product_version?: FieldProvenance;
+      manufacturer?: FieldProvenance;
+    };
     [key: string]: any;
   };
   files: Array<{
@@ -17,35 +24,230 @@ interface AnalysisData {
     type?: string;
     is_directory?: boolean;
     icon_class?: string;
+    is_speculative?: boolean;
   }>;
+  dynamic_analysis?: boolean;
   registry_operations?: Array<{
     operation: string;
     key: string;
     value?: string;
+    process_id?: number | null;
+  }>;
+  registry_groups?: RegistryGroups | null;
+  // Captured operations are serialized as { Create: { path, ... } } etc.
+  file_operations?: Array<Record<string, { path?: string; from_path?: string; to_path?: string; process_id?: number | null }>>;
+  process_operations?: Array<{
+    operation_type: 'Create' | 'Terminate';
+    process_id: number;
+    process_name: string;
+    command_line?: string | null;
+    parent_process_id?: number | null;
+  }>;
+  network_operations?: Array<{
+    operation_type: string;
+    remote_address: string;
+    protocol: string;
+    process_id?: number | null;
+  }>;
+  // WMI operations are serialized as { ProcessCreate: { command_line, ... } } etc.
+  wmi_operations?: Array<Record<string, { command_line?: string; query?: string; filter_name?: string; consumer?: string; process_id?: number | null }>>;
+  powershell_script_blocks?: Array<{
+    script_block_id: string;
+    text: string;
+    path?: string | null;
+    process_id?: number | null;
+  }>;
+  process_attribution?: ProcessAttribution | null;
+  vendor?: VendorInfo | null;
+  resource_usage?: ResourceUsage | null;
+  attack?: AttackSummary | null;
+  repackaging?: RepackagingAdvice | null;
+  analyzer_capabilities?: AnalyzerCapabilities | null;
+  dependencies?: DependencyGraph | null;
+  strings?: StringAppendix | null;
+  previews?: FilePreview[];
+}
+
+// Registry operations by hive and area, as indexes into registry_operations
+interface RegistryGroups {
+  hives: Array<{
+    hive: string;
+    count: number;
+    areas: Array<{
+      area: string;
+      operations: number[];
+    }>;
+  }>;
+}
+
+interface FilePreview {
+  path: string;
+  kind: 'config' | 'manifest' | 'license';
+  size: number;
+  content: string;
+  truncated: boolean;
+}
+
+interface FieldProvenance {
+  source: string;
+  confidence: 'low' | 'medium' | 'high';
+}
+
+interface VendorInfo {
+  vendor: string;
+  homepage?: string | null;
+  category?: string | null;
+  matched_by: 'upgrade_code' | 'product_name' | 'publisher';
+}
+
+interface RepackagingAdvice {
+  recommendations: Array<{
+    strategy: string;
+    title: string;
+    detail: string;
+  }>;
+}
+
+interface AttackSummary {
+  findings: Array<{
+    finding: string;
+    evidence: string;
+    techniques: string[];
+  }>;
+  matrix: Array<{
+    tactic: string;
+    technique_id: string;
+    technique_name: string;
+    findings: string[];
+  }>;
+}
+
+interface StringAppendix {
+  sources: string[];
+  strings: Array<{
+    value: string;
+    encoding: 'ascii' | 'utf16' | 'binary';
+    source: string;
+    offset: number;
+    occurrences: number;
+    ioc?: 'url' | 'registry_key' | 'mutex' | 'file_path' | 'crypto_constant' | null;
+  }>;
+  filtered: number;
+  omitted: number;
+}
+
+interface ResourceSample {
+  elapsed_ms: number;
+  cpu_time_ms: number;
+  memory_bytes: number;
+  disk_bytes_written: number;
+  handle_count: number;
+  process_count: number;
+}
+
+interface ResourceUsage {
+  interval_ms: number;
+  samples: ResourceSample[];
+}
+
+interface ProcessAttribution {
+  installer_root?: number | null;
+  installer_tree: number[];
+  msiexec_service: number[];
+  wmi_processes?: number[];
+}
+
+interface ActivityRow {
+  kind: 'File' | 'Registry' | 'Process' | 'Network' | 'WMI' | 'PowerShell';
+  action: string;
+  target: string;
+  pid: number | null;
+}
+
+interface DependencyGraph {
+  nodes: Array<{
+    id: string;
+    name: string;
+    version?: string | null;
+    is_root: boolean;
+  }>;
+  edges: Array<{
+    from: string;
+    to: string;
+    version_spec?: string | null;
+    condition?: string | null;
+    optional: boolean;
   }>;
 }
 
+// Report data that is not embedded as plain JSON (injected by Rust)
+interface AnalysisSource {
+  kind: 'gzip' | 'external';
+  // Holds combined data of several artifacts
+  combined: boolean;
+  // Base64 of the gzip-compressed JSON
+  data?: string;
+  // Data file next to the report
+  url?: string;
+}
+
+type SupportLevel = 'unsupported' | 'heuristic' | 'partial' | 'full';
+
+interface AnalyzerCapabilities {
+  metadata: SupportLevel;
+  file_extraction: SupportLevel;
+  registry_operations: SupportLevel;
+  install_scripts: SupportLevel;
+  signatures: SupportLevel;
+  accuracy: SupportLevel;
+  heuristic: boolean;
+}
+
 interface FileNode {
   name: string;
   path: string;
   size: number;
   is_directory: boolean;
   icon_class: string;
+  is_speculative?: boolean;
   children?: FileNode[];
 }
 
+interface TreemapRect {
+  node: FileNode;
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+}
+
 // Global variable to store analysis data
 let analysisData: AnalysisData | null = null;
 
+// Treemap state: root nodes and the directories the user has zoomed into
+let treemapRoot: FileNode[] = [];
+let treemapPath: FileNode[] = [];
+
 // Initialize the application
-function init() {
-  // Try to get data from global variable (injected by Rust)
-  if (typeof (window as any).ANALYSIS_DATA !== 'undefined') {
-    analysisData = (window as any).ANALYSIS_DATA;
-    renderReport();
+async function init() {
+  // Compressed and external data is loaded before rendering
+  const source: AnalysisSource | undefined = (window as any).ANALYSIS_SOURCE;
+  if (source) {
+    try {
+      applySourceData(source, await readSourceData(source));
+      showInjectedData();
+    } catch (error) {
+      console.error('Failed to load report data:', error);
+      if (source.kind === 'external') {
+        // Browsers refuse to fetch files next to a report opened from disk
+        promptForDataFile(source);
+      } else {
+        showToast('Failed to decompress report data', 'error');
+        loadSampleData();
+      }
+    }
   } else {
-    // Fallback: try to load from a JSON file or show placeholder
-    loadSampleData();
+    showInjectedData();
   }
 
   // Setup event listeners
@@ -59,6 +261,73 @@ function init() {
   }
 }
 
+// Render the data injected by Rust
+function showInjectedData() {
+  // Combined reports inject every artifact; show the first and offer a switcher
+  const artifacts = (window as any).ANALYSIS_ARTIFACTS;
+  if (Array.isArray(artifacts) && artifacts.length > 1) {
+    renderArtifactSelector(artifacts);
+  }
+
+  // Try to get data from global variable (injected by Rust)
+  if (typeof (window as any).ANALYSIS_DATA !== 'undefined') {
+    analysisData = (window as any).ANALYSIS_DATA;
+    renderReport();
+  } else {
+    // Fallback: try to load from a JSON file or show placeholder
+    loadSampleData();
+  }
+}
+
+// Read the JSON of compressed or external report data
+async function readSourceData(source: AnalysisSource): Promise<string> {
+  if (source.kind === 'gzip') {
+    const bytes = Uint8Array.from(atob(source.data || ''), c => c.charCodeAt(0));
+    const stream = new Blob([bytes]).stream().pipeThrough(new DecompressionStream('gzip'));
+    return new Response(stream).text();
+  }
+
+  const response = await fetch(source.url || '');
+  if (!response.ok) {
+    throw new Error(`HTTP ${response.status}`);
+  }
+  return response.text();
+}
+
+// Hand loaded report data over like data injected inline
+function applySourceData(source: AnalysisSource, json: string) {
+  const data = JSON.parse(json);
+  if (source.combined) {
+    (window as any).ANALYSIS_ARTIFACTS = data.artifacts;
+    (window as any).ANALYSIS_DATA = data.artifacts[0];
+  } else {
+    (window as any).ANALYSIS_DATA = data;
+  }
+}
+
+// Let the user pick the data file the browser refused to load
+function promptForDataFile(source: AnalysisSource) {
+  const section = document.getElementById('data-file-section');
+  const input = document.getElementById('dataFileInput') as HTMLInputElement | null;
+  loadSampleData();
+  if (!section || !input) return;
+
+  section.style.display = '';
+  updateElementText('data-file-name', source.url || '');
+  input.addEventListener('change', async () => {
+    const file = input.files?.[0];
+    if (!file) return;
+    try {
+      applySourceData(source, await file.text());
+      section.style.display = 'none';
+      showInjectedData();
+    } catch (error) {
+      console.error('Failed to read report data:', error);
+      showToast('Not a data file of this report', 'error');
+    }
+  });
+}
+
 // Load sample data for development (fallback when no real data is injected)
 function loadSampleData() {
   analysisData = {
@@ -78,6 +347,26 @@ function loadSampleData() {
   renderReport();
 }
 
+// Render the artifact switcher of a combined multi-artifact report
+function renderArtifactSelector(artifacts: AnalysisData[]) {
+  const section = document.getElementById('artifact-selector-section');
+  const select = document.getElementById('artifactSelect') as HTMLSelectElement | null;
+  if (!section || !select) return;
+
+  section.style.display = '';
+  updateElementText('artifact-count', artifacts.length.toString());
+  select.innerHTML = artifacts
+    .map((artifact, index) => `<option value="${index}">${escapeHtml(artifact.metadata.original_filename)}</option>`)
+    .join('');
+
+  select.addEventListener('change', () => {
+    analysisData = artifacts[Number(select.value)];
+    const searchInput = document.getElementById('searchInput') as HTMLInputElement | null;
+    if (searchInput) searchInput.value = '';
+    renderReport();
+  });
+}
+
 // Render the complete report
 function renderReport() {
   if (!analysisData) return;
@@ -85,7 +374,15 @@ function renderReport() {
   renderBasicInfo();
   renderSummary();
   renderFileTree();
+  renderTreemap();
+  renderDependencyGraph();
   renderRegistryOperations();
+  renderRepackaging();
+  renderAttack();
+  renderResources();
+  renderActivity();
+  renderPreviews();
+  renderStrings();
 }
 
 // Render basic information section
@@ -109,6 +406,79 @@ function renderBasicInfo() {
   updateElementText('file-size', formatFileSize(metadata.file_size));
   updateElementText('file-hash', metadata.file_hash);
   updateElementText('description', metadata.description || 'N/A');
+  markProvenance('filename', metadata.provenance?.product_name);
+  markProvenance('version', metadata.provenance?.product_version);
+  markProvenance('publisher', metadata.provenance?.manufacturer);
+  renderVendor();
+
+  renderCapabilities();
+}
+
+// Show the canonical vendor from the vendor knowledge base
+function renderVendor() {
+  const row = document.getElementById('vendor-row');
+  const element = document.getElementById('vendor');
+  if (!row || !element || !analysisData) return;
+
+  const vendor = analysisData.vendor;
+  row.style.display = vendor ? '' : 'none';
+  if (!vendor) return;
+  element.textContent = vendor.category ? `${vendor.vendor} (${vendor.category})` : vendor.vendor;
+  element.title = vendor.homepage ? `Homepage: ${vendor.homepage}` : '';
+}
+
+// Show where an identity value comes from and flag guessed values
+function markProvenance(id: string, provenance?: FieldProvenance) {
+  const element = document.getElementById(id);
+  if (!element) return;
+
+  const sources: Record<string, string> = {
+    msi_property: 'MSI property',
+    manifest: 'package manifest',
+    installer_data: 'installer setup data',
+    pe_version_info: 'PE version info',
+    content_scan: 'file content scan',
+    filename_heuristic: 'file name',
+    placeholder: 'placeholder',
+  };
+  element.classList.toggle('low-confidence', provenance?.confidence === 'low');
+  element.title = provenance
+    ? `Source: ${sources[provenance.source] || provenance.source} (${provenance.confidence} confidence)`
+    : '';
+}
+
+// Render analyzer capabilities and warn about synthesized entries
+function renderCapabilities() {
+  const caps = analysisData?.analyzer_capabilities;
+  const row = document.getElementById('capabilities-row');
+  const warning = document.getElementById('heuristic-warning');
+  if (!caps || !row) return;
+
+  const badgeClasses: Record<SupportLevel, string> = {
+    full: 'bg-success',
+    partial: 'bg-info',
+    heuristic: 'bg-warning text-dark',
+    unsupported: 'bg-secondary'
+  };
+
+  const accuracyElement = document.getElementById('accuracy');
+  if (accuracyElement) {
+    accuracyElement.className = `badge ${badgeClasses[caps.accuracy] || 'bg-secondary'}`;
+    accuracyElement.textContent = caps.accuracy;
+  }
+
+  updateElementText('capabilities', [
+    `Metadata: ${caps.metadata}`,
+    `Files: ${caps.file_extraction}`,
+    `Registry: ${caps.registry_operations}`,
+    `Scripts: ${caps.install_scripts}`,
+    `Signatures: ${caps.signatures}`
+  ].join(' · '));
+
+  row.style.display = '';
+  if (warning) {
+    warning.style.display = caps.heuristic ? '' : 'none';
+  }
 }
 
 // Render summary section
@@ -167,6 +537,7 @@ function buildFileTree(files: any[]): FileNode[] {
       size: file.size,
       is_directory: file.is_directory || false,
       icon_class: file.icon_class || getFileIcon(file.path, file.is_directory),
+      is_speculative: file.is_speculative || false,
       children: file.is_directory ? [] : undefined
     };
 
@@ -204,13 +575,252 @@ function calculateDirectorySizes(nodes: FileNode[]): void {
   });
 }
 
+// Render size treemap section
+function renderTreemap() {
+  if (!analysisData) return;
+
+  treemapRoot = buildFileTree([...(analysisData.files || [])]);
+  treemapPath = [];
+  drawTreemap();
+}
+
+// Draw the treemap for the currently selected directory
+function drawTreemap() {
+  const container = document.getElementById('treemapContainer');
+  const breadcrumb = document.getElementById('treemapBreadcrumb');
+  if (!container || !breadcrumb) return;
+
+  // Update breadcrumb navigation
+  const crumbs = ['All Files', ...treemapPath.map(node => node.name)];
+  breadcrumb.innerHTML = crumbs.map((name, index) => `
+    ${index > 0 ? '<span>/</span>' : ''}
+    <button class="treemap-crumb" data-depth="${index}">${escapeHtml(name)}</button>
+  `).join('');
+  breadcrumb.querySelectorAll('.treemap-crumb').forEach(crumb => {
+    crumb.addEventListener('click', () => {
+      const depth = Number((crumb as HTMLElement).dataset.depth);
+      if (depth < treemapPath.length) {
+        treemapPath = treemapPath.slice(0, depth);
+        drawTreemap();
+      }
+    });
+  });
+
+  const current = treemapPath.length > 0
+    ? treemapPath[treemapPath.length - 1].children || []
+    : treemapRoot;
+  const nodes = current
+    .filter(node => node.size > 0)
+    .sort((a, b) => b.size - a.size);
+
+  container.innerHTML = '';
+  if (nodes.length === 0) {
+    container.innerHTML = '<div class="treemap-empty">No size information available</div>';
+    return;
+  }
+
+  const total = nodes.reduce((sum, node) => sum + node.size, 0);
+  const rects = squarify(nodes, 0, 0, container.clientWidth, container.clientHeight);
+
+  rects.forEach((rect, index) => {
+    const cell = document.createElement('div');
+    cell.className = 'treemap-cell';
+    if (rect.node.is_directory) {
+      cell.classList.add('is-directory');
+    }
+
+    Object.assign(cell.style, {
+      left: `${rect.x}px`,
+      top: `${rect.y}px`,
+      width: `${rect.width}px`,
+      height: `${rect.height}px`,
+      backgroundColor: getTreemapColor(rect.node, index)
+    });
+
+    const percent = (rect.node.size / total * 100).toFixed(1);
+    cell.title = `${rect.node.path}\n${formatFileSize(rect.node.size)} (${percent}%)`;
+
+    // Only label cells that are large enough to hold readable text
+    if (rect.width > 48 && rect.height > 18) {
+      cell.innerHTML = `
+        <span class="treemap-cell-label"><i class="${rect.node.icon_class} me-1"></i>${escapeHtml(rect.node.name)}</span>
+        ${rect.height > 34 ? `<span class="treemap-cell-size">${formatFileSize(rect.node.size)} · ${percent}%</span>` : ''}
+      `;
+    }
+
+    cell.addEventListener('click', () => {
+      if (rect.node.is_directory && rect.node.children && rect.node.children.length > 0) {
+        treemapPath = [...treemapPath, rect.node];
+        drawTreemap();
+      }
+    });
+
+    cell.addEventListener('dblclick', (e) => {
+      e.preventDefault();
+      copyToClipboard(rect.node.path);
+    });
+
+    container.appendChild(cell);
+  });
+}
+
+// Lay out nodes (sorted by size, descending) using the squarified treemap algorithm
+function squarify(nodes: FileNode[], x: number, y: number, width: number, height: number): TreemapRect[] {
+  const rects: TreemapRect[] = [];
+  const total = nodes.reduce((sum, node) => sum + node.size, 0);
+  if (total === 0 || width <= 0 || height <= 0) return rects;
+
+  // Scale sizes to pixel areas
+  const scale = (width * height) / total;
+  let remaining = nodes.map(node => ({ node, area: node.size * scale }));
+
+  while (remaining.length > 0) {
+    const side = Math.min(width, height);
+    let row = [remaining[0]];
+    let rowArea = remaining[0].area;
+    let index = 1;
+
+    // Grow the row while it improves the worst aspect ratio
+    while (index < remaining.length) {
+      const candidate = [...row, remaining[index]];
+      const candidateArea = rowArea + remaining[index].area;
+      if (worstRatio(candidate.map(r => r.area), candidateArea, side) >
+          worstRatio(row.map(r => r.area), rowArea, side)) {
+        break;
+      }
+      row = candidate;
+      rowArea = candidateArea;
+      index++;
+    }
+
+    // Place the row along the shorter side of the free rectangle
+    const thickness = rowArea / side;
+    let offset = 0;
+    row.forEach(item => {
+      const length = item.area / thickness;
+      if (width >= height) {
+        rects.push({ node: item.node, x, y: y + offset, width: thickness, height: length });
+      } else {
+        rects.push({ node: item.node, x: x + offset, y, width: length, height: thickness });
+      }
+      offset += length;
+    });
+
+    if (width >= height) {
+      x += thickness;
+      width -= thickness;
+    } else {
+      y += thickness;
+      height -= thickness;
+    }
+
+    remaining = remaining.slice(index);
+  }
+
+  return rects;
+}
+
+// Worst aspect ratio of a row of areas laid out along a side
+function worstRatio(areas: number[], rowArea: number, side: number): number {
+  const max = Math.max(...areas);
+  const min = Math.min(...areas);
+  const sideSquared = side * side;
+  const rowSquared = rowArea * rowArea;
+  return Math.max((sideSquared * max) / rowSquared, rowSquared / (sideSquared * min));
+}
+
+// Pick a treemap cell color based on the node type
+function getTreemapColor(node: FileNode, index: number): string {
+  if (node.is_directory) {
+    const palette = ['#2563eb', '#7c3aed', '#0891b2', '#4f46e5', '#0d9488', '#9333ea'];
+    return palette[index % palette.length];
+  }
+
+  const ext = node.name.split('.').pop()?.toLowerCase();
+  switch (ext) {
+    case 'exe': case 'msi': return '#dc2626';
+    case 'dll': case 'so': case 'dylib': case 'sys': return '#d97706';
+    case 'pak': case 'dat': case 'bin': case 'asar': return '#059669';
+    case 'zip': case 'rar': case '7z': case 'cab': return '#0284c7';
+    default: return '#64748b';
+  }
+}
+
+// Render the declared dependency graph as a radial SVG diagram
+function renderDependencyGraph() {
+  const graph = analysisData?.dependencies;
+  const section = document.getElementById('dependency-section');
+  const container = document.getElementById('dependencyGraph');
+  if (!section || !container) return;
+
+  if (!graph || graph.edges.length === 0) {
+    section.style.display = 'none';
+    return;
+  }
+
+  section.style.display = '';
+  updateElementText('dependency-count', graph.edges.length.toString());
+
+  const width = container.clientWidth || 800;
+  const height = 420;
+  const cx = width / 2;
+  const cy = height / 2;
+  const radius = Math.max(80, Math.min(width, height) / 2 - 60);
+
+  // Analyzed package in the center, dependencies on a circle around it
+  const root = graph.nodes.find(node => node.is_root);
+  const others = graph.nodes.filter(node => !node.is_root);
+  const positions = new Map<string, { x: number; y: number }>();
+  if (root) positions.set(root.id, { x: cx, y: cy });
+  others.forEach((node, index) => {
+    const angle = (2 * Math.PI * index) / others.length - Math.PI / 2;
+    positions.set(node.id, { x: cx + radius * Math.cos(angle), y: cy + radius * Math.sin(angle) });
+  });
+
+  const edges = graph.edges.map(edge => {
+    const from = positions.get(edge.from);
+    const to = positions.get(edge.to);
+    if (!from || !to) return '';
+    const label = [edge.version_spec, edge.condition].filter(Boolean).join(' ; ');
+    return `<line class="dep-edge${edge.optional ? ' optional' : ''}" data-to="${escapeHtml(edge.to)}" x1="${from.x}" y1="${from.y}" x2="${to.x}" y2="${to.y}"><title>${escapeHtml(label || 'any version')}</title></line>`;
+  }).join('');
+
+  const nodes = graph.nodes.map(node => {
+    const pos = positions.get(node.id)!;
+    const requirement = graph.edges
+      .filter(edge => edge.to === node.id)
+      .map(edge => edge.version_spec)
+      .filter(Boolean)
+      .join(', ');
+    const label = node.is_root && node.version ? `${node.name} ${node.version}` : node.name;
+    const title = requirement ? `${node.name} ${requirement}` : label;
+    return `<g class="dep-node${node.is_root ? ' root' : ''}" data-id="${escapeHtml(node.id)}" data-name="${escapeHtml(node.name)}" transform="translate(${pos.x},${pos.y})"><circle r="${node.is_root ? 10 : 7}"></circle><text y="${node.is_root ? -16 : 20}" text-anchor="middle">${escapeHtml(label)}</text><title>${escapeHtml(title)}</title></g>`;
+  }).join('');
+
+  container.innerHTML = `<svg width="${width}" height="${height}" viewBox="0 0 ${width} ${height}">${edges}${nodes}</svg>`;
+
+  // Highlight incoming edges on hover, copy the package name on click
+  container.querySelectorAll<SVGGElement>('.dep-node').forEach(element => {
+    const id = element.dataset.id || '';
+    element.addEventListener('mouseenter', () => {
+      container.querySelectorAll<SVGLineElement>('.dep-edge').forEach(edge => {
+        if (edge.dataset.to === id) edge.classList.add('active');
+      });
+    });
+    element.addEventListener('mouseleave', () => {
+      container.querySelectorAll('.dep-edge.active').forEach(edge => edge.classList.remove('active'));
+    });
+    element.addEventListener('click', () => copyToClipboard(element.dataset.name || ''));
+  });
+}
+
 // Render registry operations section
 function renderRegistryOperations() {
   if (!analysisData) return;
 
   const operations = analysisData.registry_operations || [];
   const registrySection = document.getElementById('registry-section');
-  const registryTableBody = document.getElementById('registry-table-body');
+  const registryGroups = document.getElementById('registry-groups');
   const registryCount = document.getElementById('registry-count');
 
   if (operations.length === 0) {
@@ -230,20 +840,389 @@ function renderRegistryOperations() {
     registryCount.textContent = operations.length.toString();
   }
 
-  // Render table rows
-  if (registryTableBody) {
-    registryTableBody.innerHTML = operations.map(op => `
+  if (!registryGroups) return;
+
+  const hiveLabels: Record<string, string> = {
+    local_machine: 'HKEY_LOCAL_MACHINE',
+    current_user: 'HKEY_CURRENT_USER',
+    classes_root: 'HKEY_CLASSES_ROOT',
+    users: 'HKEY_USERS',
+    current_config: 'HKEY_CURRENT_CONFIG',
+    other: 'Other',
+  };
+  const areaLabels: Record<string, string> = {
+    uninstall: 'Uninstall entries',
+    services: 'Services',
+    run_keys: 'Run keys',
+    file_associations: 'File associations',
+    com: 'COM registrations',
+    other: 'Other',
+  };
+  // Areas that start programs are expanded
+  const openAreas = ['services', 'run_keys'];
+  // Reports without groups list all operations in one
+  const hives = analysisData.registry_groups?.hives || [{
+    hive: 'other',
+    count: operations.length,
+    areas: [{ area: 'other', operations: operations.map((_, index) => index) }],
+  }];
+
+  const renderRow = (op: typeof operations[number]) => `
+    <tr>
+      <td><span class="badge bg-primary">${escapeHtml(op.operation)}</span></td>
+      <td><code style="font-size: 0.85rem; word-break: break-all;">${escapeHtml(op.key)}</code></td>
+      <td style="max-width: 200px; overflow: hidden; text-overflow: ellipsis; white-space: nowrap;" title="${op.value ? escapeHtml(op.value) : '-'}">${op.value ? escapeHtml(op.value) : '-'}</td>
+      <td>
+        <button class="copy-btn" data-copy="${escapeHtml(op.key)}">
+          <i class="fas fa-copy"></i>
+        </button>
+      </td>
+    </tr>
+  `;
+
+  registryGroups.innerHTML = hives.map(hive => `
+    <details class="registry-hive mb-3" open>
+      <summary>
+        <strong>${escapeHtml(hiveLabels[hive.hive] || hive.hive)}</strong>
+        <span class="badge bg-secondary ms-2">${hive.count}</span>
+      </summary>
+      ${hive.areas.map(area => `
+        <details class="registry-area ms-3 mt-2"${openAreas.includes(area.area) ? ' open' : ''}>
+          <summary>
+            ${escapeHtml(areaLabels[area.area] || area.area)}
+            <span class="badge bg-light text-dark ms-2">${area.operations.length}</span>
+          </summary>
+          <div class="table-responsive">
+            <table class="table table-striped table-sm mt-2">
+              <thead>
+                <tr>
+                  <th style="width: 15%;">Operation</th>
+                  <th style="width: 50%;">Key Path</th>
+                  <th style="width: 25%;">Value</th>
+                  <th style="width: 10%;">Actions</th>
+                </tr>
+              </thead>
+              <tbody>
+                ${area.operations.map(index => operations[index]).filter(op => op).map(renderRow).join('')}
+              </tbody>
+            </table>
+          </div>
+        </details>
+      `).join('')}
+    </details>
+  `).join('');
+  registryGroups.querySelectorAll<HTMLElement>('.copy-btn[data-copy]').forEach(button => {
+    button.addEventListener('click', () => copyToClipboard(button.dataset.copy || ''));
+  });
+}
+
+// Render the repackaging recommendations
+function renderRepackaging() {
+  const recommendations = analysisData?.repackaging?.recommendations || [];
+  const section = document.getElementById('repackaging-section');
+  if (!section) return;
+
+  if (recommendations.length === 0) {
+    section.style.display = 'none';
+    return;
+  }
+  section.style.display = 'block';
+
+  const list = document.getElementById('repackaging-list');
+  if (list) {
+    list.innerHTML = recommendations.map(recommendation => `
+      <li class="list-group-item">
+        <span class="badge bg-secondary me-2">${escapeHtml(recommendation.strategy.replace(/_/g, ' '))}</span>
+        <strong>${escapeHtml(recommendation.title)}</strong>
+        ${recommendation.detail ? `<div class="small text-muted text-break">${escapeHtml(recommendation.detail)}</div>` : ''}
+      </li>
+    `).join('');
+  }
+}
+
+// Render the ATT&CK matrix and the tagged findings
+function renderAttack() {
+  const attack = analysisData?.attack;
+  const section = document.getElementById('attack-section');
+  if (!section) return;
+
+  if (!attack || attack.matrix.length === 0) {
+    section.style.display = 'none';
+    return;
+  }
+  section.style.display = 'block';
+  updateElementText('attack-count', attack.matrix.length.toString());
+
+  // The matrix is ordered by tactic, so each tactic is one run of entries
+  const tactics: Array<{ tactic: string; entries: AttackSummary['matrix'] }> = [];
+  attack.matrix.forEach(entry => {
+    const last = tactics[tactics.length - 1];
+    if (last && last.tactic === entry.tactic) {
+      last.entries.push(entry);
+    } else {
+      tactics.push({ tactic: entry.tactic, entries: [entry] });
+    }
+  });
+  const matrix = document.getElementById('attack-matrix');
+  if (matrix) {
+    matrix.innerHTML = tactics.map(({ tactic, entries }) => `
+      <div class="col-md-4 col-lg-2">
+        <div class="attack-tactic-title">${escapeHtml(tactic)}</div>
+        ${entries.map(entry => `
+          <div class="attack-technique" title="${escapeHtml(entry.findings.join(', '))}">
+            <strong>${escapeHtml(entry.technique_id)}</strong><br>${escapeHtml(entry.technique_name)}
+          </div>
+        `).join('')}
+      </div>
+    `).join('');
+  }
+
+  const tbody = document.getElementById('attack-table-body');
+  if (tbody) {
+    tbody.innerHTML = attack.findings.map(finding => `
+      <tr>
+        <td>${escapeHtml(finding.finding)}</td>
+        <td class="text-break"><small>${escapeHtml(finding.evidence)}</small></td>
+        <td>${finding.techniques.map(id => `<span class="badge bg-danger me-1">${escapeHtml(id)}</span>`).join('')}</td>
+      </tr>
+    `).join('');
+  }
+}
+
+// Render resource usage of the installer process tree over time
+function renderResources() {
+  const usage = analysisData?.resource_usage;
+  const section = document.getElementById('resources-section');
+  if (!section) return;
+
+  const samples = usage?.samples || [];
+  if (samples.length === 0) {
+    section.style.display = 'none';
+    return;
+  }
+  section.style.display = 'block';
+
+  const duration = samples[samples.length - 1].elapsed_ms;
+  updateElementText('resources-duration', formatDuration(duration));
+  const findings = analysisData?.metadata.properties?.resource_findings;
+  if (findings) {
+    const element = document.getElementById('resources-findings');
+    if (element) {
+      element.className = 'text-danger';
+      element.textContent = findings;
+    }
+  }
+
+  // CPU time is cumulative; plot the utilization between consecutive samples
+  const cpu = samples.map((sample, index) => {
+    const previous = samples[index - 1];
+    if (!previous || sample.elapsed_ms <= previous.elapsed_ms) return 0;
+    return (sample.cpu_time_ms - previous.cpu_time_ms) * 100 / (sample.elapsed_ms - previous.elapsed_ms);
+  });
+  const elapsed = samples.map(sample => sample.elapsed_ms);
+  drawResourceChart('resourceCpu', elapsed, cpu, value => `${Math.round(value)}%`);
+  drawResourceChart('resourceMemory', elapsed, samples.map(sample => sample.memory_bytes), formatFileSize);
+  drawResourceChart('resourceDisk', elapsed, samples.map(sample => sample.disk_bytes_written), formatFileSize);
+  drawResourceChart('resourceHandles', elapsed, samples.map(sample => sample.handle_count), value => Math.round(value).toString());
+}
+
+// Draw one resource series as an SVG line chart with its peak and duration
+function drawResourceChart(id: string, elapsed: number[], values: number[], format: (value: number) => string) {
+  const container = document.getElementById(id);
+  if (!container) return;
+
+  const width = container.clientWidth || 400;
+  const height = 160;
+  const padding = { left: 8, right: 8, top: 18, bottom: 16 };
+  const duration = Math.max(elapsed[elapsed.length - 1], 1);
+  const peak = Math.max(...values, 1);
+  const x = (ms: number) => padding.left + (ms / duration) * (width - padding.left - padding.right);
+  const y = (value: number) => height - padding.bottom - (value / peak) * (height - padding.top - padding.bottom);
+
+  const points = values.map((value, index) => `${x(elapsed[index]).toFixed(1)},${y(value).toFixed(1)}`);
+  const baseline = height - padding.bottom;
+  const area = `${x(elapsed[0]).toFixed(1)},${baseline} ${points.join(' ')} ${x(duration).toFixed(1)},${baseline}`;
+
+  container.innerHTML = `<svg width="${width}" height="${height}" viewBox="0 0 ${width} ${height}">
+    <polygon class="resource-area" points="${area}"></polygon>
+    <polyline class="resource-line" points="${points.join(' ')}"></polyline>
+    <text class="resource-axis" x="${padding.left}" y="12">peak ${escapeHtml(format(Math.max(...values)))}</text>
+    <text class="resource-axis" x="${padding.left}" y="${height - 3}">0s</text>
+    <text class="resource-axis" x="${width - padding.right}" y="${height - 3}" text-anchor="end">${formatDuration(duration)}</text>
+  </svg>`;
+}
+
+// Format milliseconds as e.g. 2m 05s
+function formatDuration(ms: number): string {
+  const seconds = Math.round(ms / 1000);
+  if (seconds < 60) return `${seconds}s`;
+  const minutes = Math.floor(seconds / 60);
+  return `${minutes}m ${(seconds % 60).toString().padStart(2, '0')}s`;
+}
+
+// Flatten the captured operations of a dynamic analysis into table rows
+function collectActivity(data: AnalysisData): ActivityRow[] {
+  const files: ActivityRow[] = (data.file_operations || []).flatMap(op =>
+    Object.entries(op).map(([action, details]) => ({
+      kind: 'File' as const,
+      action,
+      target: details.path || `${details.from_path} \u2192 ${details.to_path}`,
+      pid: details.process_id ?? null,
+    }))
+  );
+  const registry: ActivityRow[] = (data.registry_operations || []).map(op => ({
+    kind: 'Registry',
+    action: op.operation,
+    target: op.value ? `${op.key} = ${op.value}` : op.key,
+    pid: op.process_id ?? null,
+  }));
+  const processes: ActivityRow[] = (data.process_operations || []).map(op => ({
+    kind: 'Process',
+    action: op.operation_type,
+    target: op.command_line || op.process_name,
+    pid: op.process_id,
+  }));
+  const network: ActivityRow[] = (data.network_operations || []).map(op => ({
+    kind: 'Network',
+    action: op.operation_type,
+    target: `${op.remote_address} (${op.protocol})`,
+    pid: op.process_id ?? null,
+  }));
+  const wmi: ActivityRow[] = (data.wmi_operations || []).flatMap(op =>
+    Object.entries(op).map(([action, details]) => ({
+      kind: 'WMI' as const,
+      action,
+      target: details.command_line || (details.consumer ? `${details.filter_name} \u2192 ${details.consumer}` : details.query || ''),
+      pid: details.process_id ?? null,
+    }))
+  );
+  const powershell: ActivityRow[] = (data.powershell_script_blocks || []).map(block => ({
+    kind: 'PowerShell',
+    action: 'ScriptBlock',
+    target: block.path ? `${block.path}: ${block.text}` : block.text,
+    pid: block.process_id ?? null,
+  }));
+  return [...files, ...registry, ...processes, ...network, ...wmi, ...powershell];
+}
+
+// Render captured operations, narrowed down by the process attribution filters
+function renderActivity() {
+  const section = document.getElementById('activity-section');
+  const tableBody = document.getElementById('activity-table-body');
+  if (!section || !tableBody) return;
+
+  const rows = analysisData?.dynamic_analysis ? collectActivity(analysisData) : [];
+  if (rows.length === 0) {
+    section.style.display = 'none';
+    return;
+  }
+  section.style.display = 'block';
+
+  const attribution = analysisData?.process_attribution;
+  const installerTree = new Set(attribution?.installer_tree || []);
+  const msiexecService = new Set(attribution?.msiexec_service || []);
+  const scope = (document.getElementById('activityScope') as HTMLSelectElement | null)?.value || 'all';
+  const excludeMsiexec = (document.getElementById('excludeMsiexec') as HTMLInputElement | null)?.checked ?? false;
+
+  const visible = rows.filter(row => {
+    if (row.pid !== null && excludeMsiexec && msiexecService.has(row.pid)) return false;
+    if (scope === 'installer') {
+      return row.pid !== null && (installerTree.has(row.pid) || msiexecService.has(row.pid));
+    }
+    return true;
+  });
+
+  updateElementText('activity-count', `${visible.length} of ${rows.length}`);
+  (['File', 'Registry', 'Process', 'Network', 'WMI', 'PowerShell'] as const).forEach(kind => {
+    const count = visible.filter(row => row.kind === kind).length;
+    updateElementText(`activity-count-${kind.toLowerCase()}`, count.toString());
+  });
+
+  tableBody.innerHTML = visible.length === 0
+    ? '<tr><td colspan="4" class="text-muted">No operations match the filters</td></tr>'
+    : visible.map(row => `
+      <tr>
+        <td><span class="badge bg-secondary">${row.kind}</span></td>
+        <td>${escapeHtml(row.action)}</td>
+        <td><code style="font-size: 0.85rem; word-break: break-all;">${escapeHtml(row.target)}</code></td>
+        <td>${row.pid ?? '-'}</td>
+      </tr>
+    `).join('');
+}
+
+// Render the previews of small text files packaged in the installer
+function renderPreviews() {
+  const previews = analysisData?.previews || [];
+  const section = document.getElementById('previews-section');
+  const list = document.getElementById('previews-list');
+  if (!section || !list) return;
+  if (previews.length === 0) {
+    section.style.display = 'none';
+    return;
+  }
+  section.style.display = 'block';
+  updateElementText('previews-count', previews.length.toString());
+
+  const kindLabels: Record<string, string> = {
+    config: 'Configuration',
+    manifest: 'Manifest',
+    license: 'License',
+  };
+  list.innerHTML = previews.map(preview => `
+    <details class="mb-2">
+      <summary>
+        <span class="badge bg-secondary me-2">${escapeHtml(kindLabels[preview.kind] || preview.kind)}</span>
+        <code>${escapeHtml(preview.path)}</code>
+        <span class="text-muted small ms-2">${formatFileSize(preview.size)}${preview.truncated ? ' \u00b7 truncated' : ''}</span>
+      </summary>
+      <pre class="file-preview">${escapeHtml(preview.content)}</pre>
+    </details>
+  `).join('');
+}
+
+// Render the extracted strings appendix, indicators first
+function renderStrings() {
+  const appendix = analysisData?.strings;
+  const section = document.getElementById('strings-section');
+  const tableBody = document.getElementById('strings-table-body');
+  if (!section || !tableBody) return;
+  if (!appendix) {
+    section.style.display = 'none';
+    return;
+  }
+  section.style.display = 'block';
+
+  const iocLabels: Record<string, string> = {
+    url: 'URL',
+    registry_key: 'Registry key',
+    mutex: 'Mutex',
+    file_path: 'Path',
+    crypto_constant: 'Crypto constant',
+  };
+  const indicators = appendix.strings.filter(s => s.ioc);
+  const others = appendix.strings.filter(s => !s.ioc);
+
+  updateElementText('strings-count', appendix.strings.length.toString());
+  updateElementText('strings-summary', [
+    `${appendix.sources.length} source(s)`,
+    `${indicators.length} indicators`,
+    `${appendix.filtered} filtered as noise`,
+    ...(appendix.omitted > 0 ? [`${appendix.omitted} omitted`] : []),
+  ].join(' \u00b7 '));
+
+  tableBody.innerHTML = indicators.length === 0
+    ? '<tr><td colspan="3" class="text-muted">No indicators found</td></tr>'
+    : indicators.map(s => `
       <tr>
-        <td><span class="badge bg-primary">${escapeHtml(op.operation)}</span></td>
-        <td><code style="font-size: 0.85rem; word-break: break-all;">${escapeHtml(op.key)}</code></td>
-        <td style="max-width: 200px; overflow: hidden; text-overflow: ellipsis; white-space: nowrap;" title="${op.value ? escapeHtml(op.value) : '-'}">${op.value ? escapeHtml(op.value) : '-'}</td>
-        <td>
-          <button class="copy-btn" onclick="copyToClipboard('${escapeHtml(op.key)}')">
-            <i class="fas fa-copy"></i>
-          </button>
-        </td>
+        <td><span class="badge bg-warning text-dark">${escapeHtml(iocLabels[s.ioc || ''] || s.ioc || '')}</span></td>
+        <td><code style="font-size: 0.85rem; word-break: break-all;">${escapeHtml(s.value)}</code></td>
+        <td title="offset 0x${s.offset.toString(16)}">${escapeHtml(s.source)}</td>
       </tr>
     `).join('');
+
+  const otherStrings = document.getElementById('strings-other');
+  if (otherStrings) {
+    otherStrings.textContent = others.map(s => s.value).join('\n');
   }
 }
 
@@ -253,6 +1232,20 @@ function setupEventListeners() {
   if (searchInput) {
     searchInput.addEventListener('input', handleFileSearch);
   }
+
+  // Process attribution filters of the captured activity
+  document.getElementById('activityScope')?.addEventListener('change', renderActivity);
+  document.getElementById('excludeMsiexec')?.addEventListener('change', renderActivity);
+
+  // Re-layout the treemap and dependency graph when the viewport changes
+  let resizeTimer: number | undefined;
+  window.addEventListener('resize', () => {
+    window.clearTimeout(resizeTimer);
+    resizeTimer = window.setTimeout(() => {
+      drawTreemap();
+      renderDependencyGraph();
+    }, 150);
+  });
 }
 
 // Handle file search
@@ -313,10 +1306,14 @@ function createFinderColumn(items: FileNode[], path: string[]): HTMLElement {
     if (item.children && item.children.length > 0) {
       itemElement.classList.add('has-children');
     }
+    if (item.is_speculative) {
+      itemElement.classList.add('speculative');
+    }
 
+    const title = item.is_speculative ? `${item.path} (speculative entry)` : item.path;
     itemElement.innerHTML = `
       <span class="finder-icon"><i class="${item.icon_class}"></i></span>
-      <span class="finder-label" title="${escapeHtml(item.path)}">${escapeHtml(item.name)}</span>
+      <span class="finder-label" title="${escapeHtml(title)}">${escapeHtml(item.name)}</span>
       <span class="finder-size">${formatFileSize(item.size)}</span>
     `;
 
@@ -344,6 +1341,8 @@ function createFinderColumn(items: FileNode[], path: string[]): HTMLElement {
         const newColumn = createFinderColumn(item.children, newPath);
         container.appendChild(newColumn);
       }
+
+      previewFile(item);
     });
 
     // Add right-click context menu for copying path
@@ -364,6 +1363,52 @@ function createFinderColumn(items: FileNode[], path: string[]): HTMLElement {
   return column;
 }
 
+// Text files shown in the preview pane
+const PREVIEW_EXTENSIONS = new Set([
+  'txt', 'md', 'rst', 'log', 'ini', 'cfg', 'conf', 'toml', 'yaml', 'yml', 'json', 'xml',
+  'manifest', 'config', 'csv', 'ps1', 'psm1', 'bat', 'cmd', 'vbs', 'js', 'py', 'sh', 'nsi',
+  'iss', 'reg', 'inf', 'html', 'htm', 'css',
+]);
+// Bytes of a file fetched for the preview
+const PREVIEW_LIMIT = 64 * 1024;
+// Latest preview request, earlier responses arriving late are dropped
+let previewRequest = 0;
+
+// Preview a text file, read from the installer by the API server the report is served by
+async function previewFile(item: FileNode) {
+  const preview = document.getElementById('filePreview');
+  if (!preview) return;
+  preview.style.display = 'none';
+  const request = ++previewRequest;
+
+  // Previews embedded in the report work without a server
+  const embedded = analysisData?.previews?.find(preview => preview.path === item.path);
+  if (embedded) {
+    preview.textContent = embedded.content + (embedded.truncated ? '\n… (preview truncated)' : '');
+    preview.style.display = 'block';
+    return;
+  }
+
+  // Reports served at /results/<id>/html can read packaged files, saved reports cannot
+  const served = location.protocol.startsWith('http') && location.pathname.endsWith('/html');
+  const extension = item.name.split('.').pop()?.toLowerCase() ?? '';
+  if (!served || item.is_directory || item.is_speculative || !PREVIEW_EXTENSIONS.has(extension)) {
+    return;
+  }
+
+  try {
+    const response = await fetch(`files?path=${encodeURIComponent(item.path)}&limit=${PREVIEW_LIMIT}`);
+    if (!response.ok) return;
+    const text = await response.text();
+    if (request !== previewRequest) return;
+    const truncated = item.size > PREVIEW_LIMIT ? `\n… (first ${formatFileSize(PREVIEW_LIMIT)} of ${formatFileSize(item.size)})` : '';
+    preview.textContent = text + truncated;
+    preview.style.display = 'block';
+  } catch (error) {
+    console.warn('File preview unavailable:', error);
+  }
+}
+
 // Get appropriate icon for file type
 function getFileIcon(path: string, isDirectory: boolean): string {
   if (isDirectory) return 'fas fa-folder';
@@ -391,13 +1436,6 @@ function updateElementText(id: string, text: string) {
   }
 }
 
-// Escape HTML to prevent XSS
-function escapeHtml(text: string): string {
-  const div = document.createElement('div');
-  div.textContent = text;
-  return div.innerHTML;
-}
-
 // Utility functions
 function formatFileSize(bytes: number): string {
   const sizes = ['Bytes', 'KB', 'MB', 'GB'];
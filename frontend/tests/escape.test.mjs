@@ -0,0 +1,18 @@
+import assert from 'node:assert/strict';
+import test from 'node:test';
+import { escapeHtml } from '../src/escape.js';
+
+test('escapes markup characters', () => {
+  assert.equal(escapeHtml('<b>A & B</b>'), '&lt;b&gt;A &amp; B&lt;/b&gt;');
+});
+
+test('escapes quotes so values cannot leave attributes', () => {
+  const value = `x" onmouseover="alert(1)' onclick='alert(2)`;
+  const escaped = escapeHtml(value);
+  assert.ok(!escaped.includes('"'));
+  assert.ok(!escaped.includes("'"));
+  assert.equal(
+    escaped,
+    'x&quot; onmouseover=&quot;alert(1)&#39; onclick=&#39;alert(2)',
+  );
+});
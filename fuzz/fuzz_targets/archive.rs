@@ -0,0 +1,11 @@
+//! ZIP and 7z archives, and MSIX packages built on ZIP
+
+#![no_main]
+
+use installer_analyzer::analyzers::{ArchiveAnalyzer, MsixAnalyzer};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    installer_analyzer_fuzz::exercise(&ArchiveAnalyzer::new(), data, "portable.zip");
+    installer_analyzer_fuzz::exercise(&MsixAnalyzer::new(), data, "package.msix");
+});
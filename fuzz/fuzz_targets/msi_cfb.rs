@@ -0,0 +1,10 @@
+//! MSI packages: compound file reader, string pool, schema and table queries
+
+#![no_main]
+
+use installer_analyzer::analyzers::MsiAnalyzer;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    installer_analyzer_fuzz::exercise(&MsiAnalyzer::new(), data, "setup.msi");
+});
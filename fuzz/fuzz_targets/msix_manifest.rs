@@ -0,0 +1,10 @@
+//! AppxManifest.xml of MSIX and AppX packages
+
+#![no_main]
+
+use installer_analyzer::analyzers::msix::MsixParser;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = MsixParser::new().parse_manifest_content(&String::from_utf8_lossy(data));
+});
@@ -0,0 +1,12 @@
+//! NSIS installers: signature detection and header parsing
+
+#![no_main]
+
+use installer_analyzer::analyzers::nsis::parser::NsisParser;
+use installer_analyzer::analyzers::NsisAnalyzer;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = NsisParser::new().parse_header(data);
+    installer_analyzer_fuzz::exercise(&NsisAnalyzer::new(), data, "setup.exe");
+});
@@ -0,0 +1,16 @@
+//! PE images: architecture and version information of setup executables
+
+#![no_main]
+
+use installer_analyzer::analyzers::architecture::PeArchitecture;
+use installer_analyzer::analyzers::common::MetadataExtractor;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = PeArchitecture::from_pe(data);
+    installer_analyzer_fuzz::with_file(data, "setup.exe", |path| {
+        let _ = installer_analyzer_fuzz::block_on(MetadataExtractor::extract_enhanced_metadata(
+            path, None,
+        ));
+    });
+});
@@ -0,0 +1,43 @@
+//! Helpers shared by the fuzz targets
+//!
+//! Most analyzers read installers from disk, so inputs are written to a
+//! temporary file named like the format they should be detected as.
+
+use installer_analyzer::analyzers::InstallerAnalyzer;
+use std::future::Future;
+use std::path::Path;
+use tempfile::TempDir;
+
+thread_local! {
+    static RUNTIME: tokio::runtime::Runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .expect("Failed to create runtime");
+    static DIR: TempDir = tempfile::tempdir().expect("Failed to create temporary directory");
+}
+
+/// Run a future of the analyzers to completion
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    RUNTIME.with(|runtime| runtime.block_on(future))
+}
+
+/// Write `data` to a temporary file named `file_name` and call `f` with its path
+pub fn with_file<T>(data: &[u8], file_name: &str, f: impl FnOnce(&Path) -> T) -> T {
+    DIR.with(|dir| {
+        let path = dir.path().join(file_name);
+        std::fs::write(&path, data).expect("Failed to write input");
+        f(&path)
+    })
+}
+
+/// Run every stage of `analyzer` on `data`, errors are expected and ignored
+pub fn exercise(analyzer: &dyn InstallerAnalyzer, data: &[u8], file_name: &str) {
+    with_file(data, file_name, |path| {
+        block_on(async {
+            let _ = analyzer.can_analyze(path).await;
+            let _ = analyzer.extract_metadata(path).await;
+            let _ = analyzer.extract_files(path).await;
+            let _ = analyzer.extract_registry_operations(path).await;
+            let _ = analyzer.extract_dependencies(path).await;
+        })
+    })
+}
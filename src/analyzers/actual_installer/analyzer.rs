@@ -0,0 +1,187 @@
+//! Actual Installer analyzer implementation
+
+use super::parser::{is_actual_installer_file, ACTUAL_INSTALLER_PATTERNS};
+use crate::analyzers::carving::{overlay_offset, Overlay};
+use crate::analyzers::common::{self, VersionStrings};
+use crate::analyzers::{DetectionContext, InstallerAnalyzer};
+use crate::core::{
+    AnalyzerCapabilities, FileEntry, InstallerFormat, InstallerMetadata, MetadataField,
+    MetadataSource, RegistryOperation, Result, SupportLevel,
+};
+use async_trait::async_trait;
+use chrono::Utc;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Bytes of the stub searched for its version resource
+const STUB_READ_SIZE: usize = 2 * 1024 * 1024;
+
+/// Actual Installer setup analyzer
+///
+/// Files are listed from archives carved from the overlay; data compressed
+/// with the stub's own method is reported in the payload layout only.
+pub struct ActualInstallerAnalyzer;
+
+impl ActualInstallerAnalyzer {
+    /// Create a new Actual Installer analyzer
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Extract metadata from the stub's version resource and the overlay
+    async fn extract_actual_installer_metadata(
+        &self,
+        file_path: &Path,
+    ) -> Result<InstallerMetadata> {
+        let file_size = common::get_file_size(file_path).await?;
+        let file_hash = common::calculate_file_hash(file_path).await?;
+        let stub = common::read_file_content_range(file_path, 0, STUB_READ_SIZE).await?;
+        let stub = &stub[..overlay_offset(&stub).unwrap_or(stub.len())];
+        let version = VersionStrings::read(stub);
+
+        let mut properties = HashMap::new();
+        properties.insert("installer_type".to_string(), "Actual Installer".to_string());
+        let layout = match Overlay::read(file_path).await? {
+            Some(overlay) => overlay.describe(),
+            None => "no overlay (data kept in resources)".to_string(),
+        };
+        properties.insert("actual_installer_payload_layout".to_string(), layout);
+        if let Some(description) = version.file_description.clone() {
+            properties.insert("file_description".to_string(), description);
+        }
+
+        let product_name = version.product_name.clone().or_else(|| {
+            file_path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .map(|s| s.to_string())
+        });
+
+        let mut metadata = InstallerMetadata {
+            format: InstallerFormat::ActualInstaller,
+            product_name,
+            product_version: version.version().map(str::to_string),
+            manufacturer: version.company_name,
+            file_size,
+            file_hash,
+            created_at: Utc::now(),
+            properties,
+            provenance: Default::default(),
+            format_details: None,
+        }
+        .with_provenance(MetadataSource::PeVersionInfo);
+        if version.product_name.is_none() {
+            metadata.set_provenance(
+                MetadataField::ProductName,
+                MetadataSource::FilenameHeuristic,
+            );
+        }
+        Ok(metadata)
+    }
+
+    /// List the files of archives carved from the overlay
+    async fn extract_actual_installer_files(&self, file_path: &Path) -> Result<Vec<FileEntry>> {
+        let files = match Overlay::read(file_path).await? {
+            Some(overlay) => overlay.archive_files(file_path).await?,
+            None => Vec::new(),
+        };
+        tracing::info!("Found {} files in Actual Installer overlay", files.len());
+        Ok(files)
+    }
+}
+
+#[async_trait]
+impl InstallerAnalyzer for ActualInstallerAnalyzer {
+    async fn can_analyze(&self, file_path: &Path) -> Result<bool> {
+        self.can_analyze_with(&DetectionContext::new(file_path).await?)
+            .await
+    }
+
+    async fn can_analyze_with(&self, context: &DetectionContext) -> Result<bool> {
+        is_actual_installer_file(context).await
+    }
+
+    fn detection_patterns(&self) -> Vec<&'static str> {
+        ACTUAL_INSTALLER_PATTERNS.to_vec()
+    }
+
+    fn format(&self) -> InstallerFormat {
+        InstallerFormat::ActualInstaller
+    }
+
+    fn capabilities(&self) -> AnalyzerCapabilities {
+        AnalyzerCapabilities {
+            metadata: SupportLevel::Partial,
+            file_extraction: SupportLevel::Partial,
+            registry_operations: SupportLevel::Unsupported,
+            install_scripts: SupportLevel::Unsupported,
+            signatures: SupportLevel::Unsupported,
+        }
+    }
+
+    async fn extract_metadata(&self, file_path: &Path) -> Result<InstallerMetadata> {
+        common::validate_file(file_path).await?;
+
+        self.extract_actual_installer_metadata(file_path).await
+    }
+
+    async fn extract_files(&self, file_path: &Path) -> Result<Vec<FileEntry>> {
+        common::validate_file(file_path).await?;
+
+        self.extract_actual_installer_files(file_path).await
+    }
+
+    async fn extract_registry_operations(
+        &self,
+        file_path: &Path,
+    ) -> Result<Vec<RegistryOperation>> {
+        common::validate_file(file_path).await?;
+
+        // The setup script is compressed with the project data
+        Ok(Vec::new())
+    }
+}
+
+impl Default for ActualInstallerAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzers::actual_installer::parser::tests::zip_archive;
+    use crate::analyzers::carving::tests::pe_stub;
+    use crate::analyzers::common::version_info::tests::version_entry;
+
+    #[tokio::test]
+    async fn test_analyze_setup() {
+        let mut data = pe_stub(0x200);
+        let entries = [
+            version_entry("ProductName", "Fabrikam Notes"),
+            version_entry("FileVersion", "2.0.5"),
+            version_entry("CompanyName", "Fabrikam"),
+        ]
+        .concat();
+        data[0x200..0x200 + entries.len()].copy_from_slice(&entries);
+        data.extend(b"Actual Installer");
+        data.extend(zip_archive());
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("FabrikamNotesSetup.exe");
+        std::fs::write(&path, &data).unwrap();
+
+        let analyzer = ActualInstallerAnalyzer::new();
+        assert!(analyzer.can_analyze(&path).await.unwrap());
+        let metadata = analyzer.extract_metadata(&path).await.unwrap();
+        assert_eq!(metadata.format, InstallerFormat::ActualInstaller);
+        assert_eq!(metadata.product_name.as_deref(), Some("Fabrikam Notes"));
+        assert_eq!(metadata.product_version.as_deref(), Some("2.0.5"));
+        assert_eq!(metadata.manufacturer.as_deref(), Some("Fabrikam"));
+        assert!(metadata.properties["actual_installer_payload_layout"].contains("zip at 0x410"));
+
+        let files = analyzer.extract_files(&path).await.unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, Path::new("app/contoso.exe"));
+    }
+}
@@ -0,0 +1,7 @@
+//! Actual Installer format analyzer for Softeza setup executables
+
+pub mod analyzer;
+pub mod parser;
+
+// Re-export main components
+pub use analyzer::ActualInstallerAnalyzer;
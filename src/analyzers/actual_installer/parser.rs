@@ -0,0 +1,38 @@
+//! Actual Installer setup detection
+//!
+//! Actual Installer setups are a Softeza stub with the compressed project
+//! data appended as an overlay. The overlay has no documented directory, so
+//! its layout is described by the containers carved from it: archives,
+//! cabinets and executables are found by their signatures, compressed data
+//! by its entropy.
+
+use crate::analyzers::DetectionContext;
+use crate::core::Result;
+
+/// Actual Installer strings searched for during detection
+pub(crate) const ACTUAL_INSTALLER_PATTERNS: [&str; 3] =
+    ["Actual Installer", "Softeza", "actualinstaller.com"];
+
+/// Check if the file under detection is an Actual Installer setup
+pub async fn is_actual_installer_file(context: &DetectionContext) -> Result<bool> {
+    if !context.is_pe() {
+        return Ok(false);
+    }
+
+    let matches = context.find_patterns(&ACTUAL_INSTALLER_PATTERNS).await?;
+    Ok(!matches.is_empty())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::io::{Cursor, Write};
+
+    pub(crate) fn zip_archive() -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .start_file("app/contoso.exe", zip::write::FileOptions::default())
+            .unwrap();
+        writer.write_all(b"MZ application").unwrap();
+        writer.finish().unwrap().into_inner()
+    }
+}
@@ -0,0 +1,249 @@
+//! Advanced Installer analyzer implementation
+
+use super::parser::{
+    is_advanced_installer_file, AdvancedInstallerPayload, ADVANCED_INSTALLER_PATTERNS,
+};
+use crate::analyzers::carving::overlay_offset;
+use crate::analyzers::common::{self, VersionStrings};
+use crate::analyzers::{DetectionContext, InstallerAnalyzer};
+use crate::core::{
+    AnalyzerCapabilities, FileEntry, InstallerFormat, InstallerMetadata, MetadataField,
+    MetadataSource, RegistryOperation, Result, SupportLevel,
+};
+use crate::utils::format_file_size;
+use async_trait::async_trait;
+use chrono::Utc;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Bytes of the stub searched for its version resource
+const STUB_READ_SIZE: usize = 2 * 1024 * 1024;
+
+/// Advanced Installer bootstrapper analyzer
+///
+/// Files are read from the table of the appended payload. The MSI package
+/// among them carries the registry operations; it can be extracted and
+/// analyzed with `--extract-msi`.
+pub struct AdvancedInstallerAnalyzer;
+
+impl AdvancedInstallerAnalyzer {
+    /// Create a new Advanced Installer analyzer
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Extract metadata from the stub's version resource and the payload table
+    async fn extract_advanced_installer_metadata(
+        &self,
+        file_path: &Path,
+    ) -> Result<InstallerMetadata> {
+        let file_size = common::get_file_size(file_path).await?;
+        let file_hash = common::calculate_file_hash(file_path).await?;
+        let stub = common::read_file_content_range(file_path, 0, STUB_READ_SIZE).await?;
+        let stub = &stub[..overlay_offset(&stub).unwrap_or(stub.len())];
+        let version = VersionStrings::read(stub);
+
+        let mut properties = HashMap::new();
+        properties.insert(
+            "installer_type".to_string(),
+            "Advanced Installer".to_string(),
+        );
+        match AdvancedInstallerPayload::read(file_path).await? {
+            Some(payload) => {
+                properties.insert(
+                    "advinst_payload_files".to_string(),
+                    payload.files.len().to_string(),
+                );
+                properties.insert(
+                    "advinst_payload_layout".to_string(),
+                    format!(
+                        "ADVINSTSFX footer at 0x{:x}, {} files ({})",
+                        payload.footer_offset,
+                        payload.files.len(),
+                        format_file_size(payload.files.iter().map(|file| file.size).sum())
+                    ),
+                );
+                let packages: Vec<&str> = payload
+                    .files
+                    .iter()
+                    .map(|file| file.name.as_str())
+                    .filter(|name| name.to_ascii_lowercase().ends_with(".msi"))
+                    .collect();
+                if !packages.is_empty() {
+                    properties.insert("advinst_msi_packages".to_string(), packages.join(", "));
+                }
+            }
+            None => {
+                properties.insert(
+                    "advinst_payload_layout".to_string(),
+                    "no ADVINSTSFX footer (web or external-files setup)".to_string(),
+                );
+            }
+        }
+        if let Some(description) = version.file_description.clone() {
+            properties.insert("file_description".to_string(), description);
+        }
+
+        let product_name = version.product_name.clone().or_else(|| {
+            file_path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .map(|s| s.to_string())
+        });
+
+        let mut metadata = InstallerMetadata {
+            format: InstallerFormat::AdvancedInstaller,
+            product_name,
+            product_version: version.version().map(str::to_string),
+            manufacturer: version.company_name,
+            file_size,
+            file_hash,
+            created_at: Utc::now(),
+            properties,
+            provenance: Default::default(),
+            format_details: None,
+        }
+        .with_provenance(MetadataSource::PeVersionInfo);
+        if version.product_name.is_none() {
+            metadata.set_provenance(
+                MetadataField::ProductName,
+                MetadataSource::FilenameHeuristic,
+            );
+        }
+        Ok(metadata)
+    }
+}
+
+#[async_trait]
+impl InstallerAnalyzer for AdvancedInstallerAnalyzer {
+    async fn can_analyze(&self, file_path: &Path) -> Result<bool> {
+        self.can_analyze_with(&DetectionContext::new(file_path).await?)
+            .await
+    }
+
+    async fn can_analyze_with(&self, context: &DetectionContext) -> Result<bool> {
+        is_advanced_installer_file(context).await
+    }
+
+    fn detection_patterns(&self) -> Vec<&'static str> {
+        ADVANCED_INSTALLER_PATTERNS.to_vec()
+    }
+
+    fn format(&self) -> InstallerFormat {
+        InstallerFormat::AdvancedInstaller
+    }
+
+    fn capabilities(&self) -> AnalyzerCapabilities {
+        AnalyzerCapabilities {
+            metadata: SupportLevel::Partial,
+            file_extraction: SupportLevel::Partial,
+            registry_operations: SupportLevel::Unsupported,
+            install_scripts: SupportLevel::Unsupported,
+            signatures: SupportLevel::Unsupported,
+        }
+    }
+
+    async fn extract_metadata(&self, file_path: &Path) -> Result<InstallerMetadata> {
+        common::validate_file(file_path).await?;
+
+        self.extract_advanced_installer_metadata(file_path).await
+    }
+
+    async fn extract_files(&self, file_path: &Path) -> Result<Vec<FileEntry>> {
+        common::validate_file(file_path).await?;
+
+        let files = AdvancedInstallerPayload::read(file_path)
+            .await?
+            .map(|payload| payload.file_entries())
+            .unwrap_or_default();
+        tracing::info!("Found {} files in Advanced Installer payload", files.len());
+        Ok(files)
+    }
+
+    async fn extract_registry_operations(
+        &self,
+        file_path: &Path,
+    ) -> Result<Vec<RegistryOperation>> {
+        common::validate_file(file_path).await?;
+
+        // Registry operations are in the MSI package, not the bootstrapper
+        Ok(Vec::new())
+    }
+
+    async fn read_file_content(
+        &self,
+        file_path: &Path,
+        entry_path: &Path,
+    ) -> Result<Option<Vec<u8>>> {
+        let Some(payload) = AdvancedInstallerPayload::read(file_path).await? else {
+            return Ok(None);
+        };
+        match payload.file(entry_path) {
+            Some(file) => Ok(Some(payload.read_file(file_path, file).await?)),
+            None => Ok(None),
+        }
+    }
+}
+
+impl Default for AdvancedInstallerAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzers::advanced_installer::parser::tests::bootstrapper;
+    use crate::analyzers::common::version_info::tests::version_entry;
+
+    #[tokio::test]
+    async fn test_analyze_bootstrapper() {
+        let mut stub = b"MZ".to_vec();
+        stub.extend(version_entry("ProductName", "Contoso Tools"));
+        stub.extend(version_entry("ProductVersion", "3.1.0"));
+        stub.extend(version_entry("CompanyName", "Contoso Ltd."));
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("setup.exe");
+        std::fs::write(
+            &path,
+            bootstrapper(&stub, &[("Contoso.msi", b"package", false)]),
+        )
+        .unwrap();
+
+        let analyzer = AdvancedInstallerAnalyzer::new();
+        let metadata = analyzer.extract_metadata(&path).await.unwrap();
+        assert_eq!(metadata.format, InstallerFormat::AdvancedInstaller);
+        assert_eq!(metadata.product_name.as_deref(), Some("Contoso Tools"));
+        assert_eq!(metadata.product_version.as_deref(), Some("3.1.0"));
+        assert_eq!(metadata.manufacturer.as_deref(), Some("Contoso Ltd."));
+        assert_eq!(metadata.properties["advinst_msi_packages"], "Contoso.msi");
+    }
+
+    #[tokio::test]
+    async fn test_detect_before_installshield() {
+        // Bootstrapper stubs mention Setup.exe, an InstallShield marker
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("setup.exe");
+        std::fs::write(
+            &path,
+            bootstrapper(b"MZ Setup.exe", &[("Contoso.msi", b"package", false)]),
+        )
+        .unwrap();
+
+        let analyzer = crate::analyzers::AnalyzerFactory::create_analyzer(&path)
+            .await
+            .unwrap();
+        assert_eq!(analyzer.format(), InstallerFormat::AdvancedInstaller);
+        let files = analyzer.extract_files(&path).await.unwrap();
+        assert_eq!(files[0].path, Path::new("Contoso.msi"));
+        assert_eq!(
+            analyzer
+                .read_file_content(&path, Path::new("Contoso.msi"))
+                .await
+                .unwrap()
+                .as_deref(),
+            Some(&b"package"[..])
+        );
+    }
+}
@@ -0,0 +1,8 @@
+//! Advanced Installer format analyzer for Caphyon EXE bootstrappers
+
+pub mod analyzer;
+pub mod parser;
+
+// Re-export main components
+pub use analyzer::AdvancedInstallerAnalyzer;
+pub use parser::{AdvancedInstallerFile, AdvancedInstallerPayload};
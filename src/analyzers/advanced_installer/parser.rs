@@ -0,0 +1,308 @@
+//! Advanced Installer bootstrapper parser
+//!
+//! Advanced Installer builds EXE setups from a Caphyon stub with the files of
+//! the project appended: the MSI package, its cabinets, prerequisites and
+//! bootstrapper resources. A footer marked `ADVINSTSFX` at the end of the
+//! file counts the appended files and points to a table describing them.
+//! Each table entry holds the size and offset of the file, whether its first
+//! 512 bytes are stored inverted, and the UTF-16 file name.
+
+use crate::analyzers::{common, DetectionContext};
+use crate::core::{FileAttributes, FileEntry, Result};
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, SeekFrom};
+
+/// Advanced Installer strings searched for during detection
+pub(crate) const ADVANCED_INSTALLER_PATTERNS: [&str; 3] =
+    ["ADVINSTSFX", "Caphyon", "Advanced Installer"];
+
+/// Signature of the footer
+const FOOTER_SIGNATURE: &[u8] = b"ADVINSTSFX";
+/// Offset of the signature in the footer
+const SIGNATURE_OFFSET: usize = 0x14;
+/// Bytes at the end of the file searched for the footer
+const FOOTER_SEARCH_SIZE: u64 = 4096;
+/// Size of a table entry without its name
+const ENTRY_HEADER_SIZE: usize = 24;
+/// Largest file table that is read
+const MAX_TABLE_SIZE: u64 = 16 * 1024 * 1024;
+/// Longest file name, in UTF-16 units
+const MAX_NAME_LENGTH: usize = 1024;
+/// Encoding flag of files whose first bytes are inverted
+const INVERTED_FILE: u32 = 2;
+/// Bytes inverted at the start of such files
+const INVERTED_PREFIX: usize = 0x200;
+
+/// File appended to the bootstrapper
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdvancedInstallerFile {
+    pub name: String,
+    pub offset: u64,
+    pub size: u64,
+    /// First [`INVERTED_PREFIX`] bytes are stored XOR 0xFF
+    pub inverted: bool,
+}
+
+/// Files appended to an Advanced Installer bootstrapper
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AdvancedInstallerPayload {
+    /// Offset of the footer
+    pub footer_offset: u64,
+    pub files: Vec<AdvancedInstallerFile>,
+}
+
+impl AdvancedInstallerPayload {
+    /// Read the file table of a bootstrapper
+    ///
+    /// Returns `None` when the file has no footer or its table doesn't fit
+    /// the file.
+    pub async fn read(file_path: &Path) -> Result<Option<Self>> {
+        let file_size = common::get_file_size(file_path).await?;
+        let tail_start = file_size.saturating_sub(FOOTER_SEARCH_SIZE);
+        let tail = read_range(file_path, tail_start, (file_size - tail_start) as usize).await?;
+        let Some(signature) = tail
+            .windows(FOOTER_SIGNATURE.len())
+            .rposition(|window| window == FOOTER_SIGNATURE)
+        else {
+            return Ok(None);
+        };
+        let Some(footer_start) = signature.checked_sub(SIGNATURE_OFFSET) else {
+            return Ok(None);
+        };
+
+        let footer = &tail[footer_start..];
+        let footer_offset = tail_start + footer_start as u64;
+        let file_count = read_u32(footer, 0x04) as usize;
+        let table_offset = read_u32(footer, 0x0C) as u64;
+        if table_offset >= footer_offset || footer_offset - table_offset > MAX_TABLE_SIZE {
+            return Ok(None);
+        }
+
+        let table = read_range(
+            file_path,
+            table_offset,
+            (footer_offset - table_offset) as usize,
+        )
+        .await?;
+        Ok(
+            parse_table(&table, file_count, table_offset).map(|files| Self {
+                footer_offset,
+                files,
+            }),
+        )
+    }
+
+    /// Find a file by its path in the file list
+    pub fn file(&self, path: &Path) -> Option<&AdvancedInstallerFile> {
+        self.files.iter().find(|file| Path::new(&file.name) == path)
+    }
+
+    /// File entries of the appended files
+    pub fn file_entries(&self) -> Vec<FileEntry> {
+        self.files
+            .iter()
+            .map(|file| {
+                let lower = file.name.to_ascii_lowercase();
+                FileEntry {
+                    path: PathBuf::from(&file.name),
+                    target_path: None,
+                    size: file.size,
+                    hash: None,
+                    attributes: FileAttributes {
+                        executable: lower.ends_with(".exe") || lower.ends_with(".dll"),
+                        ..Default::default()
+                    },
+                    compression: None,
+                    is_speculative: false,
+                    architecture: None,
+                    modified: None,
+                    source: None,
+                }
+            })
+            .collect()
+    }
+
+    /// Read the content of an appended file, undoing the inversion
+    pub async fn read_file(
+        &self,
+        file_path: &Path,
+        file: &AdvancedInstallerFile,
+    ) -> Result<Vec<u8>> {
+        let mut content = read_range(file_path, file.offset, file.size as usize).await?;
+        if file.inverted {
+            for byte in content.iter_mut().take(INVERTED_PREFIX) {
+                *byte ^= 0xFF;
+            }
+        }
+        Ok(content)
+    }
+}
+
+/// Check if the file under detection is an Advanced Installer bootstrapper
+///
+/// The footer signature identifies one; without it, both the vendor and the
+/// product name must appear, as tools of other vendors may mention either.
+pub async fn is_advanced_installer_file(context: &DetectionContext) -> Result<bool> {
+    if !context.is_pe() {
+        return Ok(false);
+    }
+
+    let matches = context.find_patterns(&ADVANCED_INSTALLER_PATTERNS).await?;
+    Ok(matches.iter().any(|pattern| pattern == "ADVINSTSFX") || matches.len() >= 2)
+}
+
+/// Entries of a file table, all of which must lie before the table
+fn parse_table(
+    table: &[u8],
+    file_count: usize,
+    table_offset: u64,
+) -> Option<Vec<AdvancedInstallerFile>> {
+    if file_count == 0 || file_count > table.len() / ENTRY_HEADER_SIZE {
+        return None;
+    }
+
+    let mut files = Vec::with_capacity(file_count);
+    let mut position = 0;
+    for _ in 0..file_count {
+        let header = table.get(position..position + ENTRY_HEADER_SIZE)?;
+        let size = read_u32(header, 0x0C) as u64;
+        let offset = read_u32(header, 0x10) as u64;
+        let name_length = read_u32(header, 0x14) as usize;
+        if name_length == 0 || name_length > MAX_NAME_LENGTH || offset + size > table_offset {
+            return None;
+        }
+
+        let name_start = position + ENTRY_HEADER_SIZE;
+        let units: Vec<u16> = table
+            .get(name_start..name_start + name_length * 2)?
+            .chunks_exact(2)
+            .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+            .take_while(|&unit| unit != 0)
+            .collect();
+        let name = String::from_utf16(&units).ok()?;
+        if name.is_empty() || name.chars().any(char::is_control) {
+            return None;
+        }
+
+        files.push(AdvancedInstallerFile {
+            name,
+            offset,
+            size,
+            inverted: read_u32(header, 0x08) == INVERTED_FILE,
+        });
+        position = name_start + name_length * 2;
+    }
+    Some(files)
+}
+
+/// Read exactly `size` bytes at `offset`
+async fn read_range(file_path: &Path, offset: u64, size: usize) -> Result<Vec<u8>> {
+    let mut file = tokio::fs::File::open(file_path).await?;
+    file.seek(SeekFrom::Start(offset)).await?;
+    let mut buffer = vec![0u8; size];
+    file.read_exact(&mut buffer).await?;
+    Ok(buffer)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// `stub` with `files` appended, each given by name, content and whether
+    /// it is stored inverted
+    pub(crate) fn bootstrapper(stub: &[u8], files: &[(&str, &[u8], bool)]) -> Vec<u8> {
+        let mut data = stub.to_vec();
+        let mut table = Vec::new();
+        for (name, content, inverted) in files {
+            let offset = data.len() as u32;
+            let mut stored = content.to_vec();
+            if *inverted {
+                for byte in stored.iter_mut().take(INVERTED_PREFIX) {
+                    *byte ^= 0xFF;
+                }
+            }
+            data.extend(&stored);
+
+            let name: Vec<u16> = name.encode_utf16().collect();
+            table.extend([0u8; 8]);
+            table.extend((if *inverted { INVERTED_FILE } else { 0 }).to_le_bytes());
+            table.extend((content.len() as u32).to_le_bytes());
+            table.extend(offset.to_le_bytes());
+            table.extend((name.len() as u32).to_le_bytes());
+            table.extend(name.iter().flat_map(|unit| unit.to_le_bytes()));
+        }
+        let table_offset = data.len() as u32;
+        data.extend(table);
+
+        let mut footer = vec![0u8; 0x48];
+        footer[0x04..0x08].copy_from_slice(&(files.len() as u32).to_le_bytes());
+        footer[0x0C..0x10].copy_from_slice(&table_offset.to_le_bytes());
+        footer[SIGNATURE_OFFSET..SIGNATURE_OFFSET + FOOTER_SIGNATURE.len()]
+            .copy_from_slice(FOOTER_SIGNATURE);
+        data.extend(footer);
+        data
+    }
+
+    #[tokio::test]
+    async fn test_read_payload() {
+        let package = vec![0xD0u8; 1024];
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("setup.exe");
+        std::fs::write(
+            &path,
+            bootstrapper(
+                b"MZ Caphyon stub",
+                &[
+                    ("Contoso.msi", &package, true),
+                    ("Prerequisites\\vc_redist.x64.exe", b"MZ redist", false),
+                ],
+            ),
+        )
+        .unwrap();
+
+        let payload = AdvancedInstallerPayload::read(&path)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(payload.files.len(), 2);
+        assert_eq!(payload.files[0].name, "Contoso.msi");
+        assert!(payload.files[0].inverted);
+        assert_eq!(payload.files[1].size, 9);
+
+        let entries = payload.file_entries();
+        assert!(!entries[0].attributes.executable);
+        assert!(entries[1].attributes.executable);
+
+        // The inverted prefix is restored when reading
+        let file = payload.file(Path::new("Contoso.msi")).unwrap();
+        assert_eq!(payload.read_file(&path, file).await.unwrap(), package);
+
+        // Without a footer there is no payload
+        std::fs::write(&path, b"MZ Caphyon stub").unwrap();
+        assert!(AdvancedInstallerPayload::read(&path)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_reject_inconsistent_table() {
+        let data = bootstrapper(b"MZ Caphyon stub", &[("a.msi", b"package", false)]);
+        let table_offset = data.len() - 0x48 - (ENTRY_HEADER_SIZE + 10);
+        let table = &data[table_offset..data.len() - 0x48];
+        assert!(parse_table(table, 1, table_offset as u64).is_some());
+
+        // More files than the table holds, files past the table
+        assert!(parse_table(table, 2, table_offset as u64).is_none());
+        assert!(parse_table(table, 1, 16).is_none());
+    }
+}
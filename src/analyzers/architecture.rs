@@ -0,0 +1,545 @@
+//! Architecture detection of installer stubs and PE payloads
+//!
+//! The machine type of every PE file that can be read from the installer is
+//! recorded on its file entry. The results are aggregated into the Windows
+//! architectures the package can run on, taking into account that ARM64
+//! Windows emulates x86 and x64 user-mode code but not kernel drivers.
+//!
+//! Packaged files are read concurrently: PE payloads are classified, and
+//! every file without a hash gets the SHA256 of its content, streamed for
+//! files that are not classified.
+
+use crate::analyzers::{common, InstallerAnalyzer};
+use crate::core::{AnalysisResult, AnalyzerError, Result};
+use crate::utils::progress::{self, Unit};
+use byteorder::{ByteOrder, LittleEndian};
+use futures_util::{stream, StreamExt};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncReadExt;
+
+/// Extensions of files that are PE images
+const PE_EXTENSIONS: &[&str] = &[
+    "exe", "dll", "sys", "ocx", "cpl", "scr", "drv", "efi", "pyd", "node",
+];
+/// Upper bound of payloads read per installer
+const MAX_PAYLOADS: usize = 1000;
+/// Upper bound of other files read per installer to hash them
+const MAX_HASHED_FILES: usize = 10_000;
+/// Upper bound of payloads read and classified at the same time
+const MAX_CONCURRENT_PAYLOADS: usize = 16;
+
+/// CLR header flag: the image contains only IL code
+const COMIMAGE_FLAGS_ILONLY: u32 = 0x1;
+/// CLR header flag: the image must run as a 32-bit process
+const COMIMAGE_FLAGS_32BITREQUIRED: u32 = 0x2;
+
+/// Architecture a PE image targets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PeArchitecture {
+    X86,
+    X64,
+    Arm64,
+    Arm,
+    Ia64,
+    /// IL-only .NET assembly that runs natively on any architecture
+    AnyCpu,
+}
+
+impl PeArchitecture {
+    /// Value used in file entries and analysis properties
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::X86 => "x86",
+            Self::X64 => "x64",
+            Self::Arm64 => "arm64",
+            Self::Arm => "arm",
+            Self::Ia64 => "ia64",
+            Self::AnyCpu => "anycpu",
+        }
+    }
+
+    /// Detect the architecture from the start of a PE image
+    ///
+    /// `data` may be truncated; .NET assemblies are then reported by their
+    /// machine type only.
+    pub fn from_pe(data: &[u8]) -> Option<Self> {
+        if data.len() < 0x40 || &data[..2] != b"MZ" {
+            return None;
+        }
+        let pe_offset = LittleEndian::read_u32(&data[0x3c..]) as usize;
+        if data.get(pe_offset..pe_offset + 4)? != b"PE\0\0" {
+            return None;
+        }
+        let coff = data.get(pe_offset + 4..pe_offset + 24)?;
+
+        let architecture = match LittleEndian::read_u16(&coff[0..]) {
+            0x014c => Self::X86,
+            0x8664 => Self::X64,
+            0xaa64 => Self::Arm64,
+            0x01c0 | 0x01c2 | 0x01c4 => Self::Arm,
+            0x0200 => Self::Ia64,
+            _ => return None,
+        };
+
+        if architecture == Self::X86 {
+            if let Some(flags) = clr_flags(data, pe_offset, coff) {
+                if flags & COMIMAGE_FLAGS_ILONLY != 0 && flags & COMIMAGE_FLAGS_32BITREQUIRED == 0 {
+                    return Some(Self::AnyCpu);
+                }
+            }
+        }
+        Some(architecture)
+    }
+}
+
+impl fmt::Display for PeArchitecture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Flags of the CLR header of a .NET image, `None` for native images
+fn clr_flags(data: &[u8], pe_offset: usize, coff: &[u8]) -> Option<u32> {
+    let section_count = LittleEndian::read_u16(&coff[2..]) as usize;
+    let optional_size = LittleEndian::read_u16(&coff[16..]) as usize;
+    let optional = pe_offset + 24;
+
+    // Data directory 14 is the CLR runtime header
+    let directories = match LittleEndian::read_u16(data.get(optional..optional + 2)?) {
+        0x10b => optional + 96,
+        0x20b => optional + 112,
+        _ => return None,
+    };
+    let directory = data.get(directories + 14 * 8..directories + 15 * 8)?;
+    let clr_rva = LittleEndian::read_u32(&directory[0..]);
+    if clr_rva == 0 || directories + 15 * 8 > optional + optional_size {
+        return None;
+    }
+
+    let sections = optional + optional_size;
+    let clr_offset = (0..section_count).find_map(|index| {
+        let section = data.get(sections + index * 40..sections + (index + 1) * 40)?;
+        let virtual_address = LittleEndian::read_u32(&section[12..]);
+        let raw_size = LittleEndian::read_u32(&section[16..]);
+        let raw_offset = LittleEndian::read_u32(&section[20..]);
+        // Sections near the end of the address space must not wrap around
+        let offset = clr_rva.checked_sub(virtual_address)?;
+        (offset < raw_size).then(|| offset as usize + raw_offset as usize)
+    })?;
+
+    data.get(clr_offset + 16..clr_offset + 20)
+        .map(LittleEndian::read_u32)
+}
+
+/// Check whether a packaged file is a PE image by extension
+pub fn is_pe_payload(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| PE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// Architecture and SHA256 of a payload
+fn classify_payload(content: &[u8]) -> (Option<PeArchitecture>, String) {
+    let hash = format!("{:x}", Sha256::digest(content));
+    (PeArchitecture::from_pe(content), hash)
+}
+
+/// Architecture and SHA256 of a packaged file, `None` if it cannot be read
+///
+/// Only PE payloads are held in memory for classification, other files are
+/// hashed as they are read.
+async fn read_payload(
+    analyzer: &dyn InstallerAnalyzer,
+    file_path: &Path,
+    path: &Path,
+) -> Result<Option<(Option<PeArchitecture>, String)>> {
+    if is_pe_payload(path) {
+        let Some(content) = analyzer.read_file_content(file_path, path).await? else {
+            return Ok(None);
+        };
+        let classified = tokio::task::spawn_blocking(move || classify_payload(&content))
+            .await
+            .map_err(|e| AnalyzerError::generic(format!("Failed to classify payload: {}", e)))?;
+        return Ok(Some(classified));
+    }
+
+    let Some(mut reader) = analyzer.open_file(file_path, path).await? else {
+        return Ok(None);
+    };
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let read = reader.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(Some((None, format!("{:x}", hasher.finalize()))))
+}
+
+/// Payloads read at the same time, bounded by the available cores
+fn payload_concurrency() -> usize {
+    std::thread::available_parallelism()
+        .map(|cores| cores.get())
+        .unwrap_or(1)
+        .min(MAX_CONCURRENT_PAYLOADS)
+}
+
+fn is_driver(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("sys"))
+}
+
+/// Architectures of an installer stub and its PE payloads
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ArchitectureReport {
+    /// Architecture of the installer itself, when it is a PE image
+    pub stub: Option<PeArchitecture>,
+    /// Packaged PE files with their architecture
+    pub payloads: Vec<(PathBuf, PeArchitecture)>,
+}
+
+impl ArchitectureReport {
+    /// Detect the architecture of the installer and each readable PE payload
+    ///
+    /// File entries of the result get their `architecture` and missing `hash`
+    /// filled in. Unless `read_payloads` is set only the installer itself is
+    /// classified and no files are read.
+    pub async fn detect(
+        analyzer: &dyn InstallerAnalyzer,
+        file_path: &Path,
+        result: &mut AnalysisResult,
+        read_payloads: bool,
+    ) -> Result<Self> {
+        let mut report = Self::default();
+        if common::is_pe_file(file_path).await? {
+            let header = common::read_file_header(file_path, 64 * 1024).await?;
+            report.stub = PeArchitecture::from_pe(&header);
+        }
+        if !read_payloads {
+            return Ok(report);
+        }
+
+        // PE payloads come first, so other files never crowd them out
+        let packaged = || {
+            result
+                .files
+                .iter()
+                .enumerate()
+                .filter(|(_, file)| !file.is_speculative)
+        };
+        let payloads = packaged()
+            .filter(|(_, file)| is_pe_payload(&file.path))
+            .map(|(index, _)| index)
+            .take(MAX_PAYLOADS);
+        let unhashed = packaged()
+            .filter(|(_, file)| file.hash.is_none() && !is_pe_payload(&file.path))
+            .map(|(index, _)| index)
+            .take(MAX_HASHED_FILES);
+        let candidates: Vec<usize> = payloads.chain(unhashed).collect();
+        progress::begin(Unit::Files, candidates.len() as u64);
+        let files = &result.files;
+        let classified = stream::iter(candidates)
+            .map(|index| async move {
+                let path = &files[index].path;
+                match read_payload(analyzer, file_path, path).await {
+                    Ok(classified) => classified.map(|classified| (index, classified)),
+                    Err(e) => {
+                        tracing::debug!("Failed to read {}: {}", path.display(), e);
+                        None
+                    }
+                }
+            })
+            .buffered(payload_concurrency())
+            .filter_map(|classified| async move {
+                progress::advance(1);
+                classified
+            })
+            .collect::<Vec<_>>()
+            .await;
+
+        for (index, (architecture, hash)) in classified {
+            let file = &mut result.files[index];
+            file.hash.get_or_insert(hash);
+            if let Some(architecture) = architecture {
+                file.architecture = Some(architecture.to_string());
+                report.payloads.push((file.path.clone(), architecture));
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Native architectures of the payloads, excluding AnyCPU assemblies
+    pub fn payload_architectures(&self) -> Vec<PeArchitecture> {
+        let mut architectures: Vec<PeArchitecture> = self
+            .payloads
+            .iter()
+            .map(|(_, architecture)| *architecture)
+            .filter(|architecture| *architecture != PeArchitecture::AnyCpu)
+            .collect();
+        architectures.sort();
+        architectures.dedup();
+        architectures
+    }
+
+    /// Payloads target more than one native architecture
+    pub fn is_mixed(&self) -> bool {
+        self.payload_architectures().len() > 1
+    }
+
+    /// Payloads that cannot run on ARM64 Windows, even under emulation
+    pub fn arm64_incompatible(&self) -> Vec<&Path> {
+        self.payloads
+            .iter()
+            .filter(|(path, architecture)| match architecture {
+                PeArchitecture::Arm64 | PeArchitecture::AnyCpu => false,
+                PeArchitecture::Ia64 => true,
+                // Kernel drivers are never emulated
+                _ => is_driver(path),
+            })
+            .map(|(path, _)| path.as_path())
+            .collect()
+    }
+
+    /// Windows architectures every detected binary can run on
+    pub fn supported_architectures(&self) -> Vec<String> {
+        let mut binaries = self.payload_architectures();
+        if let Some(stub) = self.stub.filter(|stub| *stub != PeArchitecture::AnyCpu) {
+            binaries.push(stub);
+        }
+        if binaries.is_empty() {
+            return Vec::new();
+        }
+        if binaries.contains(&PeArchitecture::Ia64) {
+            return vec![PeArchitecture::Ia64.to_string()];
+        }
+
+        let mut supported = Vec::new();
+        if binaries.iter().all(|b| *b == PeArchitecture::X86) {
+            supported.push("x86".to_string());
+        }
+        if binaries
+            .iter()
+            .all(|b| matches!(b, PeArchitecture::X86 | PeArchitecture::X64))
+        {
+            supported.push("x64".to_string());
+        }
+        if self.arm64_incompatible().is_empty() {
+            if binaries.iter().all(|b| *b == PeArchitecture::Arm64) {
+                supported.push("arm64".to_string());
+            } else {
+                supported.push("arm64 (emulation)".to_string());
+            }
+        }
+        supported
+    }
+
+    /// Record the architecture summary as analysis properties
+    pub fn insert_properties(&self, result: &mut AnalysisResult) {
+        let properties = &mut result.metadata.properties;
+        if let Some(stub) = self.stub {
+            properties.insert("installer_architecture".to_string(), stub.to_string());
+        }
+
+        if !self.payloads.is_empty() {
+            let mut counts: BTreeMap<PeArchitecture, usize> = BTreeMap::new();
+            for (_, architecture) in &self.payloads {
+                *counts.entry(*architecture).or_default() += 1;
+            }
+            let counts: Vec<String> = counts
+                .iter()
+                .map(|(architecture, count)| format!("{} ({})", architecture, count))
+                .collect();
+            properties.insert("payload_architectures".to_string(), counts.join(", "));
+            properties.insert(
+                "mixed_architecture".to_string(),
+                self.is_mixed().to_string(),
+            );
+        }
+
+        let incompatible = self.arm64_incompatible();
+        if !incompatible.is_empty() {
+            let mut paths: Vec<String> = incompatible
+                .iter()
+                .take(10)
+                .map(|path| path.to_string_lossy().to_string())
+                .collect();
+            if incompatible.len() > 10 {
+                paths.push(format!("and {} more", incompatible.len() - 10));
+            }
+            properties.insert("arm64_incompatible".to_string(), paths.join(", "));
+        }
+
+        let supported = self.supported_architectures();
+        if !supported.is_empty() {
+            properties.insert("supported_architectures".to_string(), supported.join(", "));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal PE header, optionally with a CLR header in a single section
+    fn pe_image(machine: u16, clr_flags: Option<u32>) -> Vec<u8> {
+        let mut image = vec![0u8; 0x400];
+        image[..2].copy_from_slice(b"MZ");
+        image[0x3c..0x40].copy_from_slice(&0x80u32.to_le_bytes());
+        image[0x80..0x84].copy_from_slice(b"PE\0\0");
+        image[0x84..0x86].copy_from_slice(&machine.to_le_bytes());
+        image[0x86..0x88].copy_from_slice(&1u16.to_le_bytes());
+        image[0x94..0x96].copy_from_slice(&224u16.to_le_bytes());
+
+        let optional = 0x98;
+        image[optional..optional + 2].copy_from_slice(&0x10bu16.to_le_bytes());
+        if let Some(flags) = clr_flags {
+            // CLR header at RVA 0x2008, mapped from a section at RVA 0x2000 / offset 0x200
+            let directory = optional + 96 + 14 * 8;
+            image[directory..directory + 4].copy_from_slice(&0x2008u32.to_le_bytes());
+            let section = optional + 224;
+            image[section + 12..section + 16].copy_from_slice(&0x2000u32.to_le_bytes());
+            image[section + 16..section + 20].copy_from_slice(&0x200u32.to_le_bytes());
+            image[section + 20..section + 24].copy_from_slice(&0x200u32.to_le_bytes());
+            image[0x208 + 16..0x208 + 20].copy_from_slice(&flags.to_le_bytes());
+        }
+        image
+    }
+
+    #[test]
+    fn test_pe_architecture() {
+        assert_eq!(
+            PeArchitecture::from_pe(&pe_image(0x8664, None)),
+            Some(PeArchitecture::X64)
+        );
+        assert_eq!(
+            PeArchitecture::from_pe(&pe_image(0xaa64, None)),
+            Some(PeArchitecture::Arm64)
+        );
+        assert_eq!(
+            PeArchitecture::from_pe(&pe_image(0x014c, Some(COMIMAGE_FLAGS_ILONLY))),
+            Some(PeArchitecture::AnyCpu)
+        );
+        assert_eq!(
+            PeArchitecture::from_pe(&pe_image(
+                0x014c,
+                Some(COMIMAGE_FLAGS_ILONLY | COMIMAGE_FLAGS_32BITREQUIRED)
+            )),
+            Some(PeArchitecture::X86)
+        );
+        assert_eq!(PeArchitecture::from_pe(b"MZ not a PE image"), None);
+    }
+
+    #[test]
+    fn test_pe_architecture_of_malformed_sections() {
+        // Section at the top of the address space, with a raw size reaching past it
+        let mut image = pe_image(0x014c, Some(COMIMAGE_FLAGS_ILONLY));
+        let section = 0x98 + 224;
+        image[section + 12..section + 16].copy_from_slice(&0xffff_f000u32.to_le_bytes());
+        image[section + 16..section + 20].copy_from_slice(&0x2000u32.to_le_bytes());
+        image[section + 20..section + 24].copy_from_slice(&0xffff_ffffu32.to_le_bytes());
+        let directory = 0x98 + 96 + 14 * 8;
+        image[directory..directory + 4].copy_from_slice(&0xffff_f008u32.to_le_bytes());
+        assert_eq!(PeArchitecture::from_pe(&image), Some(PeArchitecture::X86));
+    }
+
+    #[tokio::test]
+    async fn test_detect_classifies_and_hashes_payloads() {
+        use crate::analyzers::ArchiveAnalyzer;
+        use std::io::Write;
+
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("portable.zip");
+        let mut writer = zip::ZipWriter::new(std::fs::File::create(&archive).unwrap());
+        let machines = [0x8664u16, 0x014c, 0xaa64];
+        for (index, machine) in machines.iter().cycle().take(40).enumerate() {
+            writer
+                .start_file(format!("bin/tool{:02}.dll", index), Default::default())
+                .unwrap();
+            writer.write_all(&pe_image(*machine, None)).unwrap();
+        }
+        writer.start_file("readme.txt", Default::default()).unwrap();
+        writer.write_all(b"not a payload").unwrap();
+        writer.finish().unwrap();
+
+        let analyzer = ArchiveAnalyzer::new();
+        let mut result = AnalysisResult {
+            session_id: uuid::Uuid::new_v4(),
+            source_file_path: Some(archive.clone()),
+            metadata: analyzer.extract_metadata(&archive).await.unwrap(),
+            files: analyzer.extract_files(&archive).await.unwrap(),
+            analyzed_at: chrono::Utc::now(),
+            ..Default::default()
+        };
+        let file = |result: &AnalysisResult, name: &str| {
+            let file = result.files.iter().find(|file| file.path.ends_with(name));
+            file.unwrap().clone()
+        };
+        let known = "0".repeat(64);
+        let index = result
+            .files
+            .iter()
+            .position(|file| file.path.ends_with("tool01.dll"))
+            .unwrap();
+        result.files[index].hash = Some(known.clone());
+
+        let report = ArchitectureReport::detect(&analyzer, &archive, &mut result, true)
+            .await
+            .unwrap();
+        assert_eq!(report.payloads.len(), 40);
+        assert_eq!(report.payloads[0].1, PeArchitecture::X64);
+        assert_eq!(report.payloads[2].1, PeArchitecture::Arm64);
+        assert!(report.is_mixed());
+
+        let x64_hash = format!("{:x}", Sha256::digest(pe_image(0x8664, None)));
+        let tool = file(&result, "tool00.dll");
+        assert_eq!(tool.hash.as_deref(), Some(x64_hash.as_str()));
+        let tool = file(&result, "tool01.dll");
+        assert_eq!(tool.hash.as_deref(), Some(known.as_str()));
+        assert_eq!(tool.architecture.as_deref(), Some("x86"));
+        let readme_hash = format!("{:x}", Sha256::digest(b"not a payload"));
+        let readme = file(&result, "readme.txt");
+        assert_eq!(readme.hash.as_deref(), Some(readme_hash.as_str()));
+        assert_eq!(readme.architecture, None);
+    }
+
+    #[test]
+    fn test_supported_architectures() {
+        let report = ArchitectureReport {
+            stub: Some(PeArchitecture::X86),
+            payloads: vec![(PathBuf::from("app.dll"), PeArchitecture::AnyCpu)],
+        };
+        assert_eq!(
+            report.supported_architectures(),
+            vec!["x86", "x64", "arm64 (emulation)"]
+        );
+        assert!(!report.is_mixed());
+
+        let report = ArchitectureReport {
+            stub: Some(PeArchitecture::X86),
+            payloads: vec![
+                (PathBuf::from("bin/app.exe"), PeArchitecture::X64),
+                (PathBuf::from("bin/helper32.exe"), PeArchitecture::X86),
+                (PathBuf::from("drivers/filter.sys"), PeArchitecture::X64),
+            ],
+        };
+        assert!(report.is_mixed());
+        assert_eq!(
+            report.arm64_incompatible(),
+            vec![Path::new("drivers/filter.sys")]
+        );
+        assert_eq!(report.supported_architectures(), vec!["x64"]);
+
+        let report = ArchitectureReport {
+            stub: None,
+            payloads: vec![(PathBuf::from("app.exe"), PeArchitecture::Arm64)],
+        };
+        assert_eq!(report.supported_architectures(), vec!["arm64"]);
+    }
+}
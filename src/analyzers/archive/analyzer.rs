@@ -1,8 +1,12 @@
 //! Archive analyzer implementation
 
 use super::parser::{ArchiveFormat, ArchiveParser};
-use crate::analyzers::{common, InstallerAnalyzer};
-use crate::core::{FileEntry, InstallerFormat, InstallerMetadata, RegistryOperation, Result};
+use crate::analyzers::common::{self, EntryReader};
+use crate::analyzers::{DetectionContext, InstallerAnalyzer};
+use crate::core::{
+    AnalyzerCapabilities, FileEntry, InstallerFormat, InstallerMetadata, MetadataField,
+    MetadataSource, RegistryOperation, Result, SupportLevel,
+};
 use async_trait::async_trait;
 use chrono::Utc;
 use std::path::Path;
@@ -20,9 +24,9 @@ impl ArchiveAnalyzer {
         }
     }
 
-    /// Check if file is a supported archive format
-    async fn is_supported_archive(file_path: &Path) -> Result<bool> {
-        ArchiveParser::is_archive_file(file_path).await
+    /// Check if the file under detection is a supported archive format
+    fn is_supported_archive(context: &DetectionContext) -> bool {
+        context.archive_format() != ArchiveFormat::Unknown
     }
 
     /// Extract metadata from archive
@@ -65,7 +69,11 @@ impl ArchiveAnalyzer {
             file_hash,
             created_at: Utc::now(),
             properties,
-        })
+            provenance: Default::default(),
+            format_details: None,
+        }
+        .with_provenance(MetadataSource::FilenameHeuristic)
+        .with_field_provenance(MetadataField::Manufacturer, MetadataSource::Placeholder))
     }
 
     /// Extract files from archive
@@ -90,11 +98,13 @@ impl ArchiveAnalyzer {
 #[async_trait]
 impl InstallerAnalyzer for ArchiveAnalyzer {
     async fn can_analyze(&self, file_path: &Path) -> Result<bool> {
-        // Validate file accessibility
-        common::validate_file(file_path).await?;
+        self.can_analyze_with(&DetectionContext::new(file_path).await?)
+            .await
+    }
 
+    async fn can_analyze_with(&self, context: &DetectionContext) -> Result<bool> {
         // Check if it's a supported archive format
-        Self::is_supported_archive(file_path).await
+        Ok(Self::is_supported_archive(context))
     }
 
     fn format(&self) -> InstallerFormat {
@@ -102,6 +112,16 @@ impl InstallerAnalyzer for ArchiveAnalyzer {
         InstallerFormat::Unknown
     }
 
+    fn capabilities(&self) -> AnalyzerCapabilities {
+        AnalyzerCapabilities {
+            metadata: SupportLevel::Partial,
+            file_extraction: SupportLevel::Full,
+            registry_operations: SupportLevel::Unsupported,
+            install_scripts: SupportLevel::Unsupported,
+            signatures: SupportLevel::Unsupported,
+        }
+    }
+
     async fn extract_metadata(&self, file_path: &Path) -> Result<InstallerMetadata> {
         // Validate file first
         common::validate_file(file_path).await?;
@@ -125,6 +145,23 @@ impl InstallerAnalyzer for ArchiveAnalyzer {
 
         self.extract_archive_registry(file_path).await
     }
+
+    async fn read_file_content(
+        &self,
+        file_path: &Path,
+        entry_path: &Path,
+    ) -> Result<Option<Vec<u8>>> {
+        // Validate file first
+        common::validate_file(file_path).await?;
+
+        self.parser.read_file_content(file_path, entry_path).await
+    }
+
+    async fn open_file(&self, file_path: &Path, entry_path: &Path) -> Result<Option<EntryReader>> {
+        common::validate_file(file_path).await?;
+
+        self.parser.open_file(file_path, entry_path).await
+    }
 }
 
 impl Default for ArchiveAnalyzer {
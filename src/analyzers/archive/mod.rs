@@ -2,7 +2,9 @@
 
 pub mod analyzer;
 pub mod parser;
+pub mod portable;
 
 // Re-export main components
 pub use analyzer::ArchiveAnalyzer;
 pub use parser::{ArchiveEntry, ArchiveFormat, ArchiveParser};
+pub use portable::PortableAppReport;
@@ -1,10 +1,15 @@
 //! Archive data structure parser
 
+use crate::analyzers::common::streaming::{self, EntryReader};
 use crate::core::{AnalyzerError, FileAttributes, FileEntry, Result};
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use zip::ZipArchive;
 
+/// Largest buffer allocated up front for an entry, based on its declared size
+const MAX_PREALLOCATION: u64 = 16 * 1024 * 1024;
+
 /// Supported archive formats
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ArchiveFormat {
@@ -24,6 +29,8 @@ pub struct ArchiveEntry {
     pub compressed_size: u64,
     pub is_directory: bool,
     pub compression_method: Option<String>,
+    /// Modification time of the entry
+    pub modified: Option<DateTime<Utc>>,
 }
 
 /// Archive data parser
@@ -38,11 +45,15 @@ impl ArchiveParser {
     /// Detect archive format from file header
     pub async fn detect_format(file_path: &Path) -> Result<ArchiveFormat> {
         let header = crate::analyzers::common::read_file_header(file_path, 8).await?;
+        Ok(Self::format_from_header(&header))
+    }
 
+    /// Archive format told by the first bytes of a file
+    pub fn format_from_header(header: &[u8]) -> ArchiveFormat {
         if header.len() >= 4 {
             // ZIP signature: PK (0x504B)
             if header[0] == 0x50 && header[1] == 0x4B {
-                return Ok(ArchiveFormat::Zip);
+                return ArchiveFormat::Zip;
             }
 
             // 7z signature: 7z¼¯' (0x377ABCAF271C)
@@ -54,11 +65,11 @@ impl ArchiveParser {
                 && header[4] == 0x27
                 && header[5] == 0x1C
             {
-                return Ok(ArchiveFormat::SevenZ);
+                return ArchiveFormat::SevenZ;
             }
         }
 
-        Ok(ArchiveFormat::Unknown)
+        ArchiveFormat::Unknown
     }
 
     /// Check if file is a supported archive format
@@ -86,6 +97,7 @@ impl ArchiveParser {
                 compressed_size: zip_file.compressed_size(),
                 is_directory: zip_file.is_dir(),
                 compression_method: Some(format!("{:?}", zip_file.compression())),
+                modified: Self::zip_entry_time(&zip_file),
             };
 
             entries.push(entry);
@@ -94,6 +106,126 @@ impl ArchiveParser {
         Ok(entries)
     }
 
+    /// Modification time of a ZIP entry
+    ///
+    /// The extended timestamp extra field holds a UTC Unix time; without it
+    /// the DOS date/time is used, which has no time zone and is taken as UTC.
+    fn zip_entry_time(zip_file: &zip::read::ZipFile) -> Option<DateTime<Utc>> {
+        let extra = zip_file.extra_data();
+        let mut position = 0;
+        while position + 4 <= extra.len() {
+            let id = u16::from_le_bytes([extra[position], extra[position + 1]]);
+            let size = u16::from_le_bytes([extra[position + 2], extra[position + 3]]) as usize;
+            let Some(data) = extra.get(position + 4..position + 4 + size) else {
+                break;
+            };
+            // Extended timestamp: flags, then the modification time if bit 0 is set
+            if id == 0x5455 && data.len() >= 5 && data[0] & 0x01 != 0 {
+                let mtime = i32::from_le_bytes([data[1], data[2], data[3], data[4]]);
+                return Utc.timestamp_opt(mtime as i64, 0).single();
+            }
+            position += 4 + size;
+        }
+
+        let time = zip_file.last_modified();
+        let naive =
+            NaiveDate::from_ymd_opt(time.year() as i32, time.month() as u32, time.day() as u32)?
+                .and_hms_opt(
+                    time.hour() as u32,
+                    time.minute() as u32,
+                    time.second() as u32,
+                )?;
+        Some(Utc.from_utc_datetime(&naive))
+    }
+
+    /// Read the content of a single ZIP entry, if the archive contains it
+    pub fn read_zip_entry(&self, file_path: &Path, entry_name: &str) -> Result<Option<Vec<u8>>> {
+        use std::io::Read;
+
+        let file = std::fs::File::open(file_path)?;
+        let mut archive = ZipArchive::new(file)
+            .map_err(|e| AnalyzerError::generic(format!("Failed to open ZIP archive: {}", e)))?;
+
+        let mut zip_file = match archive.by_name(entry_name) {
+            Ok(zip_file) => zip_file,
+            Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+            Err(e) => {
+                return Err(AnalyzerError::generic(format!(
+                    "Failed to read ZIP entry {}: {}",
+                    entry_name, e
+                )))
+            }
+        };
+
+        // The declared size is only a hint, a malformed archive may claim gigabytes
+        let mut content = Vec::with_capacity(zip_file.size().min(MAX_PREALLOCATION) as usize);
+        zip_file.read_to_end(&mut content)?;
+        Ok(Some(content))
+    }
+
+    /// Read the content of a file packaged in the archive
+    ///
+    /// Only ZIP archives support content extraction; other formats return `None`.
+    pub async fn read_file_content(
+        &self,
+        file_path: &Path,
+        entry_path: &Path,
+    ) -> Result<Option<Vec<u8>>> {
+        match Self::detect_format(file_path).await? {
+            ArchiveFormat::Zip => {
+                let entry_name = entry_path.to_string_lossy().replace('\\', "/");
+                self.read_zip_entry(file_path, &entry_name)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Open a ZIP entry for reading, decompressing it as it is read
+    pub fn open_zip_entry(
+        &self,
+        file_path: &Path,
+        entry_name: &str,
+    ) -> Result<Option<EntryReader>> {
+        let file = std::fs::File::open(file_path)?;
+        let mut archive = ZipArchive::new(file)
+            .map_err(|e| AnalyzerError::generic(format!("Failed to open ZIP archive: {}", e)))?;
+
+        // Missing entries are reported before handing out a reader
+        match archive.by_name(entry_name) {
+            Ok(_) => {}
+            Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+            Err(e) => {
+                return Err(AnalyzerError::generic(format!(
+                    "Failed to read ZIP entry {}: {}",
+                    entry_name, e
+                )))
+            }
+        }
+        let entry_name = entry_name.to_string();
+        Ok(Some(streaming::spawn_reader(move |writer| {
+            let mut zip_file = archive.by_name(&entry_name)?;
+            std::io::copy(&mut zip_file, writer)?;
+            Ok(())
+        })))
+    }
+
+    /// Open a file packaged in the archive for reading
+    ///
+    /// Like [`ArchiveParser::read_file_content`], only ZIP archives are supported.
+    pub async fn open_file(
+        &self,
+        file_path: &Path,
+        entry_path: &Path,
+    ) -> Result<Option<EntryReader>> {
+        match Self::detect_format(file_path).await? {
+            ArchiveFormat::Zip => {
+                let entry_name = entry_path.to_string_lossy().replace('\\', "/");
+                self.open_zip_entry(file_path, &entry_name)
+            }
+            _ => Ok(None),
+        }
+    }
+
     /// Extract file list from 7z archive
     pub fn extract_7z_files(&self, file_path: &Path) -> Result<Vec<ArchiveEntry>> {
         // For now, we'll provide a simplified implementation
@@ -111,6 +243,7 @@ impl ArchiveParser {
             compressed_size: file_size,
             is_directory: false,
             compression_method: Some("7Z-LZMA".to_string()),
+            modified: None,
         }];
 
         Ok(entries)
@@ -147,6 +280,10 @@ impl ArchiveParser {
                         executable: entry.name.ends_with(".exe") || entry.name.ends_with(".dll"),
                     },
                     compression: entry.compression_method,
+                    is_speculative: false,
+                    architecture: None,
+                    modified: entry.modified,
+                    source: None,
                 });
             }
         }
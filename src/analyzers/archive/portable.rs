@@ -0,0 +1,457 @@
+//! Portable applications distributed as plain archives
+//!
+//! Many Windows applications ship as a ZIP to extract and run in place. The
+//! layout tells how: the executable named after the product (or owning the
+//! most companion files) is the one to launch, `.ini`/`.config`/`.json`
+//! files next to it are its configuration, and marker files like
+//! `Portable` or a PortableApps.com `App\AppInfo` directory make it keep its
+//! settings beside itself instead of in the user profile. 7z archives are not
+//! listed and get no profile.
+
+use crate::analyzers::architecture::PeArchitecture;
+use crate::analyzers::archive::{ArchiveFormat, ArchiveParser};
+use crate::analyzers::common::FilenameParser;
+use crate::analyzers::InstallerAnalyzer;
+use crate::core::{
+    AnalysisResult, FileEntry, FormatDetails, InstallerFormat, PortableAppProfile, Result,
+};
+use std::path::Path;
+
+/// Largest primary executable read to find its architecture
+const MAX_EXECUTABLE_SIZE: u64 = 64 * 1024 * 1024;
+/// Configuration files listed in analysis properties
+const MAX_LISTED: usize = 20;
+
+/// Executables that support the application rather than being it
+const HELPER_NAMES: [&str; 12] = [
+    "setup", "install", "uninst", "update", "crash", "helper", "host", "redist", "elevat",
+    "reporter", "service", "launcher",
+];
+
+/// File extensions of configuration files
+const CONFIG_EXTENSIONS: [&str; 11] = [
+    ".ini",
+    ".config",
+    ".cfg",
+    ".conf",
+    ".json",
+    ".xml",
+    ".yaml",
+    ".yml",
+    ".toml",
+    ".settings",
+    ".properties",
+];
+
+/// Files whose presence switches an application to portable mode
+const PORTABLE_MARKERS: [&str; 7] = [
+    "portable",
+    "portable.txt",
+    "portable.dat",
+    "portable.ini",
+    "_portable",
+    ".portable",
+    "portable_mode",
+];
+
+/// Directories applications keep their settings and data in
+const DATA_DIRS: [&str; 6] = ["data", "settings", "userdata", "profile", "config", "user"];
+
+/// Files identifying the runtime of an application, by path suffix
+const RUNTIME_FILES: [(&str, &str); 7] = [
+    (".exe.config", ".NET Framework"),
+    (".runtimeconfig.json", ".NET"),
+    ("resources/app.asar", "Electron"),
+    ("jre/bin/java.exe", "Java"),
+    ("qt5core.dll", "Qt"),
+    ("qt6core.dll", "Qt"),
+    ("python3.dll", "Python"),
+];
+
+/// Portable application profile of a plain archive
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PortableAppReport {
+    pub profile: Option<PortableAppProfile>,
+}
+
+impl PortableAppReport {
+    /// Profile the application in a plain ZIP archive
+    ///
+    /// Installers in archive formats (wheels, MSIX) and archives without a
+    /// launchable executable get no profile.
+    pub async fn detect(
+        analyzer: &dyn InstallerAnalyzer,
+        file_path: &Path,
+        result: &AnalysisResult,
+    ) -> Result<Self> {
+        if result.metadata.format != InstallerFormat::Unknown
+            || ArchiveParser::detect_format(file_path).await? != ArchiveFormat::Zip
+        {
+            return Ok(Self::default());
+        }
+        let product_name = result.metadata.product_name.clone().or_else(|| {
+            let file_name = file_path.file_name()?.to_string_lossy();
+            FilenameParser::parse(&file_name).product_name
+        });
+        let Some(mut profile) = profile_layout(&result.files, product_name.as_deref()) else {
+            return Ok(Self::default());
+        };
+
+        let primary = result.files.iter().find(|file| {
+            file.path.to_string_lossy().replace('\\', "/") == profile.primary_executable
+        });
+        if let Some(file) = primary.filter(|file| file.size <= MAX_EXECUTABLE_SIZE) {
+            if let Some(data) = analyzer.read_file_content(file_path, &file.path).await? {
+                profile.architecture = PeArchitecture::from_pe(&data).map(|a| a.to_string());
+            }
+        }
+        Ok(Self {
+            profile: Some(profile),
+        })
+    }
+
+    /// Add the profile to the analysis properties and format details
+    pub fn insert_properties(&self, result: &mut AnalysisResult) {
+        let Some(profile) = &self.profile else {
+            return;
+        };
+        let properties = &mut result.metadata.properties;
+        properties.insert("portable_app".to_string(), "true".to_string());
+        properties.insert(
+            "portable_primary_executable".to_string(),
+            profile.primary_executable.clone(),
+        );
+        properties.insert(
+            "portable_launch_command".to_string(),
+            profile.launch_command.clone(),
+        );
+        properties.insert(
+            "portable_working_directory".to_string(),
+            profile.working_directory.clone(),
+        );
+        properties.insert(
+            "portable_writes_beside_itself".to_string(),
+            profile.writes_beside_itself.clone(),
+        );
+        if !profile.write_indicators.is_empty() {
+            properties.insert(
+                "portable_write_indicators".to_string(),
+                profile.write_indicators.join("; "),
+            );
+        }
+        if !profile.config_files.is_empty() {
+            let listed: Vec<&str> = profile
+                .config_files
+                .iter()
+                .take(MAX_LISTED)
+                .map(String::as_str)
+                .collect();
+            properties.insert("portable_config_files".to_string(), listed.join(", "));
+        }
+        if let Some(runtime) = &profile.runtime {
+            properties.insert("portable_runtime".to_string(), runtime.clone());
+        }
+        if let Some(architecture) = &profile.architecture {
+            properties.insert("portable_architecture".to_string(), architecture.clone());
+        }
+        // Plain archives have no other format details
+        result.metadata.format_details = Some(FormatDetails::PortableApp(profile.clone()));
+    }
+}
+
+/// Profile the files of an archive, if it holds an application to launch
+pub fn profile_layout(
+    files: &[FileEntry],
+    product_name: Option<&str>,
+) -> Option<PortableAppProfile> {
+    let paths: Vec<String> = files
+        .iter()
+        .filter(|file| !file.is_speculative)
+        .map(|file| file.path.to_string_lossy().replace('\\', "/"))
+        .collect();
+    let app_dir = app_dir(&paths)?;
+    let in_app_dir = |path: &&String| path.starts_with(&app_dir);
+    let relative = |path: &str| path[app_dir.len()..].to_string();
+
+    let executables: Vec<&String> = paths
+        .iter()
+        .filter(in_app_dir)
+        .filter(|path| is_executable(path) && !relative(path).contains('/'))
+        .collect();
+    let primary = executables
+        .iter()
+        .filter(|path| !is_helper(path))
+        .max_by_key(|path| {
+            let size = files
+                .iter()
+                .find(|file| file.path.to_string_lossy().replace('\\', "/") == ***path)
+                .map_or(0, |file| file.size);
+            (executable_score(path, &paths, product_name), size)
+        })
+        .or_else(|| executables.first())?
+        .to_string();
+
+    let config_files: Vec<String> = paths
+        .iter()
+        .filter(in_app_dir)
+        .filter(|path| is_config(&relative(path)))
+        .cloned()
+        .collect();
+    let runtime = RUNTIME_FILES.iter().find_map(|(suffix, runtime)| {
+        paths
+            .iter()
+            .any(|path| path.to_lowercase().ends_with(suffix))
+            .then(|| runtime.to_string())
+    });
+    let (writes_beside_itself, write_indicators) = write_indicators(&paths, &app_dir, &primary);
+
+    let working_directory = if app_dir.is_empty() {
+        "{extract_dir}".to_string()
+    } else {
+        format!(
+            "{{extract_dir}}\\{}",
+            app_dir.trim_end_matches('/').replace('/', "\\")
+        )
+    };
+    Some(PortableAppProfile {
+        app_dir: app_dir.trim_end_matches('/').to_string(),
+        launch_command: format!("\"{{extract_dir}}\\{}\"", primary.replace('/', "\\")),
+        working_directory,
+        other_executables: executables
+            .iter()
+            .filter(|path| ***path != primary)
+            .map(|path| path.to_string())
+            .collect(),
+        primary_executable: primary,
+        architecture: None,
+        runtime,
+        config_files,
+        writes_beside_itself: writes_beside_itself.to_string(),
+        write_indicators,
+    })
+}
+
+/// Directory holding the application executables, with a trailing `/`
+///
+/// Archives often wrap everything in one folder; PortableApps.com packages
+/// keep the application under `App\<Name>`.
+fn app_dir(paths: &[String]) -> Option<String> {
+    let has_executable = |dir: &str| {
+        paths.iter().any(|path| {
+            path.strip_prefix(dir)
+                .is_some_and(|rest| !rest.contains('/') && is_executable(rest))
+        })
+    };
+    let top = common_dir(paths);
+    let mut candidates = vec![top.clone(), format!("{}bin/", top)];
+    // App/<Name>/ of the PortableApps.com format
+    candidates.extend(paths.iter().filter_map(|path| {
+        let rest = path.strip_prefix(&format!("{}App/", top))?;
+        let (name, _) = rest.split_once('/')?;
+        (name != "AppInfo").then(|| format!("{}App/{}/", top, name))
+    }));
+    candidates.into_iter().find(|dir| has_executable(dir))
+}
+
+/// Directory every path is under, with a trailing `/`, or empty
+fn common_dir(paths: &[String]) -> String {
+    let Some((first, _)) = paths.first().and_then(|path| path.split_once('/')) else {
+        return String::new();
+    };
+    let prefix = format!("{}/", first);
+    if paths.iter().all(|path| path.starts_with(&prefix)) {
+        prefix
+    } else {
+        String::new()
+    }
+}
+
+fn is_executable(path: &str) -> bool {
+    path.to_lowercase().ends_with(".exe")
+}
+
+fn file_stem(path: &str) -> &str {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    name.split_once('.').map_or(name, |(stem, _)| stem)
+}
+
+/// Letters and digits of a name, lowercased, to compare `Share X` with `ShareX.exe`
+fn normalized(name: &str) -> String {
+    name.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+fn is_helper(path: &str) -> bool {
+    let stem = file_stem(path).to_lowercase();
+    HELPER_NAMES.iter().any(|helper| stem.contains(helper))
+}
+
+/// How likely an executable is the application itself
+///
+/// The product name weighs most, then companion files named after the
+/// executable, like `ShareX.HelpersLib.dll` or `ShareX.exe.config`.
+fn executable_score(path: &str, paths: &[String], product_name: Option<&str>) -> u32 {
+    let stem = file_stem(path);
+    let name = normalized(stem);
+    let mut score = 0;
+    if let Some(product) = product_name.map(normalized).filter(|p| !p.is_empty()) {
+        if name == product {
+            score += 100;
+        } else if name.contains(&product) || product.contains(&name) {
+            score += 50;
+        }
+    }
+    let dir = &path[..path.len() - path.rsplit('/').next().unwrap_or_default().len()];
+    let companions = paths
+        .iter()
+        .filter(|other| *other != path)
+        .filter_map(|other| other.strip_prefix(dir))
+        .filter(|other| !other.contains('/') && other.starts_with(&format!("{}.", stem)))
+        .count();
+    score + companions.min(20) as u32
+}
+
+fn is_config(relative: &str) -> bool {
+    let lower = relative.to_lowercase();
+    // Top of the application directory or a settings directory below it
+    let in_place = match lower.split_once('/') {
+        None => true,
+        Some((dir, rest)) => DATA_DIRS.contains(&dir) && !rest.contains('/'),
+    };
+    in_place
+        && CONFIG_EXTENSIONS
+            .iter()
+            .any(|extension| lower.ends_with(extension))
+        && !lower.ends_with(".deps.json")
+}
+
+/// Whether the application keeps settings beside itself, with the evidence
+fn write_indicators(paths: &[String], app_dir: &str, primary: &str) -> (&'static str, Vec<String>) {
+    let top = common_dir(paths);
+    let mut certain = Vec::new();
+    let mut likely = Vec::new();
+    for path in paths {
+        let Some(relative) = path.strip_prefix(app_dir) else {
+            if let Some(rest) = path.strip_prefix(&format!("{}App/AppInfo/", top)) {
+                if rest.eq_ignore_ascii_case("appinfo.ini") {
+                    certain.push("PortableApps.com Format layout, settings go to Data".to_string());
+                }
+            }
+            continue;
+        };
+        let lower = relative.to_lowercase();
+        if PORTABLE_MARKERS.contains(&lower.as_str()) {
+            certain.push(format!("Portable mode marker file {}", relative));
+        }
+        if let Some((dir, _)) = relative.split_once('/') {
+            let indicator = format!("Settings directory {}", dir);
+            if DATA_DIRS.contains(&dir.to_lowercase().as_str()) && !likely.contains(&indicator) {
+                likely.push(indicator);
+            }
+        } else if lower == format!("{}.ini", file_stem(primary).to_lowercase()) {
+            likely.push(format!("Settings file {} beside the executable", relative));
+        }
+    }
+    let verdict = if !certain.is_empty() {
+        "yes"
+    } else if !likely.is_empty() {
+        "likely"
+    } else {
+        "unknown"
+    };
+    certain.extend(likely);
+    (verdict, certain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::FileAttributes;
+    use std::path::PathBuf;
+
+    fn files(entries: &[(&str, u64)]) -> Vec<FileEntry> {
+        entries
+            .iter()
+            .map(|(path, size)| FileEntry {
+                path: PathBuf::from(path),
+                target_path: Some(PathBuf::from(path)),
+                size: *size,
+                hash: None,
+                attributes: FileAttributes::default(),
+                compression: None,
+                is_speculative: false,
+                architecture: None,
+                modified: None,
+                source: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_profile_sharex_layout() {
+        // Trimmed from ShareX-17.1.0-portable.zip
+        let files = files(&[
+            ("ffmpeg.exe", 126_967_296),
+            ("Portable", 0),
+            ("Recorder-devices-setup.exe", 1_892_753),
+            ("ShareX.exe", 2_317_824),
+            ("ShareX.exe.config", 1_315),
+            ("ShareX.HelpersLib.dll", 1_195_520),
+            ("ShareX_NativeMessagingHost.exe", 6_144),
+            ("host-manifest-chrome.json", 224),
+            ("exiftool_files/perl.exe", 39_936),
+        ]);
+        let profile = profile_layout(&files, Some("ShareX")).unwrap();
+        assert_eq!(profile.primary_executable, "ShareX.exe");
+        assert_eq!(profile.app_dir, "");
+        assert_eq!(profile.launch_command, "\"{extract_dir}\\ShareX.exe\"");
+        assert_eq!(profile.runtime.as_deref(), Some(".NET Framework"));
+        assert_eq!(
+            profile.config_files,
+            ["ShareX.exe.config", "host-manifest-chrome.json"]
+        );
+        assert_eq!(profile.writes_beside_itself, "yes");
+        assert_eq!(
+            profile.write_indicators,
+            ["Portable mode marker file Portable"]
+        );
+        assert_eq!(profile.other_executables.len(), 3);
+
+        // Companion files pick the application without a product name
+        let profile = profile_layout(&files, None).unwrap();
+        assert_eq!(profile.primary_executable, "ShareX.exe");
+    }
+
+    #[test]
+    fn test_profile_wrapped_and_portableapps_layouts() {
+        let files = files(&[
+            ("Tool-2.0/bin/tool.exe", 1000),
+            ("Tool-2.0/bin/tool.ini", 10),
+            ("Tool-2.0/README.txt", 10),
+        ]);
+        let profile = profile_layout(&files, Some("Tool")).unwrap();
+        assert_eq!(profile.app_dir, "Tool-2.0/bin");
+        assert_eq!(profile.working_directory, "{extract_dir}\\Tool-2.0\\bin");
+        assert_eq!(profile.writes_beside_itself, "likely");
+
+        let files = files_portableapps();
+        let profile = profile_layout(&files, None).unwrap();
+        assert_eq!(
+            profile.primary_executable,
+            "NotepadPortable/App/Notepad/notepad.exe"
+        );
+        assert_eq!(profile.writes_beside_itself, "yes");
+
+        assert!(profile_layout(&self::files(&[("docs/readme.txt", 1)]), None).is_none());
+    }
+
+    fn files_portableapps() -> Vec<FileEntry> {
+        files(&[
+            ("NotepadPortable/App/AppInfo/appinfo.ini", 100),
+            ("NotepadPortable/App/Notepad/notepad.exe", 1000),
+            ("NotepadPortable/Other/Source/readme.txt", 10),
+        ])
+    }
+}
@@ -0,0 +1,231 @@
+//! Authenticode signatures of PE images
+//!
+//! Signed EXE installers carry a PKCS#7 signature in the certificate table of
+//! the PE image. The image digest in the signed content is compared with the
+//! image and the signature is verified with the certificate it carries; the
+//! certificate is not validated against a trust store. The subject and
+//! thumbprint of the signing certificate are what publisher allow and deny
+//! lists of policies are matched against.
+
+use crate::analyzers::common::pkcs7::{IntegrityStatus, SignedMessage};
+use crate::analyzers::driver::catalog::{authenticode_digest, indirect_data};
+use crate::core::Result;
+use crate::utils::format_file_size;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Largest image read into memory for the signature check
+const MAX_READ_SIZE: u64 = 512 * 1024 * 1024;
+/// Size of the `WIN_CERTIFICATE` header before the signature
+const WIN_CERTIFICATE_HEADER_SIZE: usize = 8;
+/// `WIN_CERT_TYPE_PKCS_SIGNED_DATA`
+const WIN_CERT_TYPE_PKCS_SIGNED_DATA: u16 = 2;
+
+/// Outcome of checking the Authenticode signature of a PE image
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthenticodeReport {
+    pub status: IntegrityStatus,
+    /// Subject of the signing certificate
+    pub signer: Option<String>,
+    /// SHA-1 thumbprint of the signing certificate
+    pub signer_thumbprint: Option<String>,
+    pub issues: Vec<String>,
+}
+
+impl AuthenticodeReport {
+    /// Check the signature of an installer, `None` if it is no PE image or too large to read
+    pub async fn detect(input: &Path) -> Result<Option<Self>> {
+        let size = tokio::fs::metadata(input).await?.len();
+        if size > MAX_READ_SIZE {
+            tracing::info!(
+                "Skipping signature check of {} ({})",
+                input.display(),
+                format_file_size(size)
+            );
+            return Ok(None);
+        }
+        let data = tokio::fs::read(input).await?;
+        Ok(pe_header(&data).map(|_| Self::check(&data)))
+    }
+
+    /// Verify the embedded signature of a PE image
+    pub fn check(data: &[u8]) -> Self {
+        let mut report = Self {
+            status: IntegrityStatus::Missing,
+            signer: None,
+            signer_thumbprint: None,
+            issues: Vec::new(),
+        };
+        let Some(certificate) = certificate_table(data) else {
+            return report;
+        };
+
+        let signature = certificate
+            .get(WIN_CERTIFICATE_HEADER_SIZE..)
+            .filter(|_| read_u16(certificate, 6) == Some(WIN_CERT_TYPE_PKCS_SIGNED_DATA))
+            .and_then(der_element);
+        let Some(signature) = signature else {
+            report.status = IntegrityStatus::Invalid;
+            report
+                .issues
+                .push("Certificate table holds no PKCS#7 signature".to_string());
+            return report;
+        };
+        let message = match SignedMessage::parse(signature) {
+            Ok(message) => message,
+            Err(e) => {
+                report.status = e.status();
+                report.issues.push(e.describe("Authenticode signature"));
+                return report;
+            }
+        };
+
+        report.signer = message.signer();
+        report.signer_thumbprint = message.signer_thumbprint();
+        let algorithm = message.algorithm;
+        match indirect_data(&message.content) {
+            Some(member) => {
+                let algorithm = member.algorithm.unwrap_or(algorithm);
+                if authenticode_digest(data, algorithm).as_deref() != Some(member.digest.as_slice())
+                {
+                    report
+                        .issues
+                        .push("Image does not match the signed Authenticode digest".to_string());
+                }
+            }
+            None => report
+                .issues
+                .push("Signed content holds no image digest".to_string()),
+        }
+        if let Err(e) = message.verify(&mut report.issues) {
+            report.status = e.status();
+            report.issues.push(e.describe("Authenticode signature"));
+            return report;
+        }
+
+        report.status = if report.issues.is_empty() {
+            IntegrityStatus::Valid
+        } else {
+            IntegrityStatus::Invalid
+        };
+        report
+    }
+
+    /// Record the outcome as `pe_signature_*` and `pe_signer*` analysis properties
+    pub fn insert_properties(&self, properties: &mut HashMap<String, String>) {
+        properties.insert("pe_signature_status".to_string(), self.status.to_string());
+        if let Some(signer) = &self.signer {
+            properties.insert("pe_signer".to_string(), signer.clone());
+        }
+        if let Some(thumbprint) = &self.signer_thumbprint {
+            properties.insert("pe_signer_thumbprint".to_string(), thumbprint.clone());
+        }
+        if !self.issues.is_empty() {
+            properties.insert("pe_signature_issues".to_string(), self.issues.join("; "));
+        }
+    }
+}
+
+/// Offset of the `PE\0\0` signature, `None` if the data is no PE image
+fn pe_header(data: &[u8]) -> Option<usize> {
+    let pe = read_u32(data, 0x3c)? as usize;
+    (data.get(pe..pe.checked_add(4)?)? == b"PE\0\0").then_some(pe)
+}
+
+/// Certificate table of a PE image, `None` for unsigned images
+fn certificate_table(data: &[u8]) -> Option<&[u8]> {
+    let optional_header = pe_header(data)? + 24;
+    let data_directories = match read_u16(data, optional_header)? {
+        0x10b => optional_header + 96,
+        0x20b => optional_header + 112,
+        _ => return None,
+    };
+    let certificate_entry = data_directories + 4 * 8;
+    let offset = read_u32(data, certificate_entry)? as usize;
+    let size = read_u32(data, certificate_entry + 4)? as usize;
+    if size == 0 {
+        return None;
+    }
+    data.get(offset..offset.checked_add(size)?.min(data.len()))
+}
+
+/// DER element at the start of `data`, without the padding after it
+fn der_element(data: &[u8]) -> Option<&[u8]> {
+    let first = *data.get(1)?;
+    let (header, length) = if first & 0x80 == 0 {
+        (2, first as usize)
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count == 0 || count > 4 {
+            return None;
+        }
+        let length = data
+            .get(2..2 + count)?
+            .iter()
+            .fold(0usize, |length, &b| (length << 8) | b as usize);
+        (2 + count, length)
+    };
+    data.get(..header.checked_add(length)?)
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        data.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzers::driver::catalog::tests::pe_image;
+    use std::io::Write;
+
+    #[test]
+    fn test_check_signature() {
+        // A certificate table without a PKCS#7 structure
+        let mut certificate = 16u32.to_le_bytes().to_vec();
+        certificate.extend(0x0200u16.to_le_bytes());
+        certificate.extend(WIN_CERT_TYPE_PKCS_SIGNED_DATA.to_le_bytes());
+        certificate.extend([0x30, 0x03, 0x02, 0x01, 0x01, 0x00, 0x00, 0x00]);
+        let report = AuthenticodeReport::check(&pe_image(&certificate));
+        assert_eq!(report.status, IntegrityStatus::Invalid);
+        assert_eq!(report.signer, None);
+        assert_eq!(report.issues.len(), 1);
+
+        assert_eq!(
+            der_element(&[0x30, 0x03, 0x02, 0x01, 0x01, 0x00]),
+            Some(&[0x30, 0x03, 0x02, 0x01, 0x01][..])
+        );
+        assert_eq!(
+            AuthenticodeReport::check(b"MZ").status,
+            IntegrityStatus::Missing
+        );
+    }
+
+    #[tokio::test]
+    async fn test_detect_only_checks_pe_images() {
+        let mut image = tempfile::NamedTempFile::new().unwrap();
+        image.write_all(&pe_image(b"")).unwrap();
+        let report = AuthenticodeReport::detect(image.path())
+            .await
+            .unwrap()
+            .unwrap();
+        let mut properties = HashMap::new();
+        report.insert_properties(&mut properties);
+        assert_eq!(properties["pe_signature_status"], "missing");
+        assert!(!properties.contains_key("pe_signer_thumbprint"));
+
+        let mut package = tempfile::NamedTempFile::new().unwrap();
+        package.write_all(b"PK\x03\x04 not a PE image").unwrap();
+        assert_eq!(
+            AuthenticodeReport::detect(package.path()).await.unwrap(),
+            None
+        );
+    }
+}
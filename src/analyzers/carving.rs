@@ -0,0 +1,707 @@
+//! Carving of embedded payloads
+//!
+//! Format parsers only expose what their format declares. Installers can
+//! carry more: archives, cabinets or executables stored in resource sections
+//! or appended to the stub. These are found by their magic bytes and sized
+//! from their own headers; remaining regions of near-random content are
+//! reported by their entropy. Carved containers can optionally be written
+//! out and analyzed in turn.
+
+use crate::analyzers::{AnalyzerFactory, ArchiveAnalyzer, InstallerAnalyzer};
+use crate::core::{AnalysisResult, FileEntry, InstallerFormat, Result};
+use crate::utils::{format_file_size, Workspace, WorkspaceArea};
+use regex::bytes::Regex;
+use std::collections::VecDeque;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Largest file that is scanned
+const MAX_SCAN_SIZE: u64 = 512 * 1024 * 1024;
+/// Block size of the entropy scan
+const ENTROPY_BLOCK: usize = 64 * 1024;
+/// Entropy in bits per byte above which a block counts as compressed or encrypted
+const HIGH_ENTROPY: f64 = 7.5;
+/// Smallest high-entropy region that is reported
+const MIN_HIGH_ENTROPY_SIZE: usize = 4 * ENTROPY_BLOCK;
+/// Nesting depth of recursive analysis
+const MAX_CARVE_DEPTH: usize = 3;
+/// Largest number of payloads analyzed recursively
+const MAX_NESTED_PAYLOADS: usize = 32;
+
+/// Kind of an embedded region
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmbeddedKind {
+    Zip,
+    SevenZip,
+    Cabinet,
+    Pe,
+    /// Compressed or encrypted data without a known signature
+    HighEntropy,
+}
+
+impl EmbeddedKind {
+    /// Value used in analysis properties
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Zip => "zip",
+            Self::SevenZip => "7z",
+            Self::Cabinet => "cab",
+            Self::Pe => "pe",
+            Self::HighEntropy => "high-entropy data",
+        }
+    }
+
+    /// Extension of a carved file, which also guides format detection
+    fn extension(&self) -> &'static str {
+        match self {
+            Self::Zip => "zip",
+            Self::SevenZip => "7z",
+            Self::Cabinet => "cab",
+            Self::Pe => "exe",
+            Self::HighEntropy => "bin",
+        }
+    }
+}
+
+impl fmt::Display for EmbeddedKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Region of a file holding an embedded payload
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmbeddedPayload {
+    pub offset: u64,
+    pub size: u64,
+    pub kind: EmbeddedKind,
+    /// Shannon entropy of the region in bits per byte
+    pub entropy: f64,
+}
+
+impl EmbeddedPayload {
+    /// Find embedded payloads in file content
+    ///
+    /// A payload at offset 0 is the file itself and is skipped, as are
+    /// payloads lying inside another payload; those are found when the outer
+    /// payload is analyzed recursively.
+    pub fn scan(data: &[u8]) -> Vec<Self> {
+        let signatures = Regex::new(r"(?-u)PK\x05\x06|7z\xBC\xAF'\x1C|MSCF\x00\x00\x00\x00|MZ")
+            .expect("valid signature pattern");
+
+        let mut candidates: Vec<(usize, usize, EmbeddedKind)> = signatures
+            .find_iter(data)
+            .filter_map(|found| match found.as_bytes() {
+                b"PK\x05\x06" => zip_region(data, found.start()),
+                b"MZ" => pe_region(data, found.start()),
+                b"MSCF\0\0\0\0" => cabinet_region(data, found.start()),
+                _ => seven_zip_region(data, found.start()),
+            })
+            .filter(|(start, _, _)| *start > 0)
+            .collect();
+        candidates.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+
+        let mut payloads: Vec<Self> = Vec::new();
+        for (start, end, kind) in candidates {
+            let contained = payloads
+                .iter()
+                .any(|p| start >= p.offset as usize && end <= (p.offset + p.size) as usize);
+            if !contained {
+                payloads.push(Self {
+                    offset: start as u64,
+                    size: (end - start) as u64,
+                    kind,
+                    entropy: entropy(&data[start..end]),
+                });
+            }
+        }
+
+        let high_entropy = high_entropy_regions(data, &payloads);
+        payloads.extend(high_entropy);
+        payloads.sort_by_key(|payload| payload.offset);
+        payloads
+    }
+
+    /// Whether the payload can be analyzed as a file of its own
+    pub fn is_container(&self) -> bool {
+        self.kind != EmbeddedKind::HighEntropy
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "{} at 0x{:x} ({}, entropy {:.2})",
+            self.kind,
+            self.offset,
+            format_file_size(self.size),
+            self.entropy
+        )
+    }
+}
+
+/// Analysis of a carved payload
+#[derive(Debug, Clone)]
+pub struct NestedAnalysis {
+    /// Offsets leading to the payload, e.g. `0x1a00/0x400` for a payload inside a payload
+    pub location: String,
+    pub kind: EmbeddedKind,
+    /// Detected installer format, `None` when no analyzer accepted the payload
+    pub format: Option<InstallerFormat>,
+    pub files: Vec<FileEntry>,
+    pub error: Option<String>,
+}
+
+/// Embedded payloads of an installer and, optionally, their analyses
+#[derive(Debug, Clone, Default)]
+pub struct EmbeddedPayloadReport {
+    pub payloads: Vec<EmbeddedPayload>,
+    pub nested: Vec<NestedAnalysis>,
+}
+
+impl EmbeddedPayloadReport {
+    /// Scan an installer, analyzing carved containers if `recursive` is set
+    pub async fn detect(file_path: &Path, recursive: bool) -> Result<Self> {
+        let size = tokio::fs::metadata(file_path).await?.len();
+        if size > MAX_SCAN_SIZE {
+            tracing::info!(
+                "Skipping embedded payload scan of {} ({})",
+                file_path.display(),
+                format_file_size(size)
+            );
+            return Ok(Self::default());
+        }
+
+        let data = tokio::fs::read(file_path).await?;
+        let payloads = EmbeddedPayload::scan(&data);
+        let nested = if recursive && payloads.iter().any(EmbeddedPayload::is_container) {
+            Self::analyze_nested(&data, &payloads).await?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self { payloads, nested })
+    }
+
+    /// Write carved containers to scratch files and analyze them, breadth first
+    async fn analyze_nested(
+        data: &[u8],
+        payloads: &[EmbeddedPayload],
+    ) -> Result<Vec<NestedAnalysis>> {
+        let scratch_dir = Workspace::new().scratch_dir(WorkspaceArea::Extraction)?;
+        let mut queue: VecDeque<(String, EmbeddedKind, Vec<u8>, usize)> = payloads
+            .iter()
+            .filter(|payload| payload.is_container())
+            .map(|payload| {
+                let range = payload.offset as usize..(payload.offset + payload.size) as usize;
+                (
+                    format!("0x{:x}", payload.offset),
+                    payload.kind,
+                    data[range].to_vec(),
+                    1,
+                )
+            })
+            .collect();
+
+        let mut nested = Vec::new();
+        while let Some((location, kind, content, depth)) = queue.pop_front() {
+            if nested.len() >= MAX_NESTED_PAYLOADS {
+                tracing::warn!("Stopped analyzing embedded payloads after {}", nested.len());
+                break;
+            }
+
+            let path =
+                scratch_dir
+                    .path()
+                    .join(format!("payload{}.{}", nested.len(), kind.extension()));
+            tokio::fs::write(&path, &content).await?;
+            nested.push(Self::analyze_payload(&path, location.clone(), kind).await);
+
+            if depth < MAX_CARVE_DEPTH {
+                for payload in EmbeddedPayload::scan(&content) {
+                    if payload.is_container() {
+                        let range =
+                            payload.offset as usize..(payload.offset + payload.size) as usize;
+                        queue.push_back((
+                            format!("{}/0x{:x}", location, payload.offset),
+                            payload.kind,
+                            content[range].to_vec(),
+                            depth + 1,
+                        ));
+                    }
+                }
+            }
+        }
+
+        Ok(nested)
+    }
+
+    /// Analyze one carved payload; failures are recorded rather than returned
+    async fn analyze_payload(path: &Path, location: String, kind: EmbeddedKind) -> NestedAnalysis {
+        let mut analysis = NestedAnalysis {
+            location,
+            kind,
+            format: None,
+            files: Vec::new(),
+            error: None,
+        };
+
+        let analyzer = match AnalyzerFactory::create_analyzer(path).await {
+            // Plain archives are not registered for detection
+            Err(_) if matches!(kind, EmbeddedKind::Zip | EmbeddedKind::SevenZip) => {
+                Ok(Box::new(ArchiveAnalyzer::new()) as Box<dyn InstallerAnalyzer>)
+            }
+            analyzer => analyzer,
+        };
+        match analyzer {
+            Ok(analyzer) => {
+                analysis.format = Some(analyzer.format());
+                match analyzer.extract_files(path).await {
+                    Ok(files) => {
+                        analysis.files = files
+                            .into_iter()
+                            .filter(|file| !file.is_speculative)
+                            .collect()
+                    }
+                    Err(e) => analysis.error = Some(e.to_string()),
+                }
+            }
+            Err(e) => analysis.error = Some(e.to_string()),
+        }
+        analysis
+    }
+
+    /// Record the payloads as analysis properties and add files of analyzed payloads
+    ///
+    /// Files of analyzed payloads are listed under `embedded/<location>/`.
+    pub fn insert_properties(&self, result: &mut AnalysisResult) {
+        if self.payloads.is_empty() {
+            return;
+        }
+
+        let properties = &mut result.metadata.properties;
+        let descriptions: Vec<String> = self
+            .payloads
+            .iter()
+            .take(20)
+            .map(EmbeddedPayload::describe)
+            .collect();
+        properties.insert(
+            "embedded_payload_count".to_string(),
+            self.payloads.len().to_string(),
+        );
+        properties.insert("embedded_payloads".to_string(), descriptions.join("; "));
+
+        if self.nested.is_empty() {
+            return;
+        }
+        let analyses: Vec<String> = self
+            .nested
+            .iter()
+            .map(|analysis| match (&analysis.format, &analysis.error) {
+                (Some(format), None) => format!(
+                    "{}: {} ({} files)",
+                    analysis.location,
+                    // Plain archives have no installer format of their own
+                    if *format == InstallerFormat::Unknown {
+                        analysis.kind.as_str()
+                    } else {
+                        format.name()
+                    },
+                    analysis.files.len()
+                ),
+                (_, Some(error)) => format!("{}: {}", analysis.location, error),
+                (None, None) => format!("{}: not analyzed", analysis.location),
+            })
+            .collect();
+        properties.insert("embedded_payload_analysis".to_string(), analyses.join("; "));
+
+        for analysis in &self.nested {
+            let prefix = PathBuf::from("embedded").join(&analysis.location);
+            result
+                .files
+                .extend(analysis.files.iter().map(|file| FileEntry {
+                    path: prefix.join(&file.path),
+                    ..file.clone()
+                }));
+        }
+    }
+}
+
+/// Data appended to a PE stub and the containers carved from it
+///
+/// Setup tools that append their data without a documented directory are
+/// described by the layout of their overlay.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Overlay {
+    /// End of the stub's PE image
+    pub offset: u64,
+    pub size: u64,
+    /// Containers in the overlay, at offsets relative to the file
+    pub containers: Vec<EmbeddedPayload>,
+}
+
+impl Overlay {
+    /// Locate the overlay of a setup and carve it
+    ///
+    /// Returns `None` for files without an overlay and for files too large
+    /// to scan.
+    pub async fn read(file_path: &Path) -> Result<Option<Self>> {
+        let size = tokio::fs::metadata(file_path).await?.len();
+        if size > MAX_SCAN_SIZE {
+            tracing::info!(
+                "Skipping overlay scan of {} ({})",
+                file_path.display(),
+                format_file_size(size)
+            );
+            return Ok(None);
+        }
+        let data = tokio::fs::read(file_path).await?;
+        Ok(Self::parse(&data))
+    }
+
+    /// Locate and carve the overlay of a setup read into memory
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let offset = overlay_offset(data).filter(|&offset| offset < data.len())?;
+        let containers = EmbeddedPayload::scan(data)
+            .into_iter()
+            .filter(|payload| payload.offset >= offset as u64)
+            .collect();
+        Some(Self {
+            offset: offset as u64,
+            size: (data.len() - offset) as u64,
+            containers,
+        })
+    }
+
+    /// Whether a container can be listed as an archive of setup files
+    pub fn is_archive(payload: &EmbeddedPayload) -> bool {
+        matches!(
+            payload.kind,
+            EmbeddedKind::Zip | EmbeddedKind::SevenZip | EmbeddedKind::Cabinet
+        )
+    }
+
+    /// One-line description of the overlay and its containers
+    pub fn describe(&self) -> String {
+        let mut layout = format!(
+            "overlay at 0x{:x} ({})",
+            self.offset,
+            format_file_size(self.size)
+        );
+        for payload in &self.containers {
+            layout.push_str(&format!(
+                "; {} at 0x{:x} ({})",
+                payload.kind,
+                payload.offset,
+                format_file_size(payload.size)
+            ));
+        }
+        layout
+    }
+
+    /// Files of the archives at the top level of the overlay
+    pub async fn archive_files(&self, file_path: &Path) -> Result<Vec<FileEntry>> {
+        let archives: Vec<String> = self
+            .containers
+            .iter()
+            .filter(|container| Self::is_archive(container))
+            .map(|container| format!("0x{:x}", container.offset))
+            .collect();
+        if archives.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let report = EmbeddedPayloadReport::detect(file_path, true).await?;
+        Ok(report
+            .nested
+            .into_iter()
+            .filter(|analysis| archives.contains(&analysis.location))
+            .flat_map(|analysis| analysis.files)
+            .collect())
+    }
+}
+
+/// End of the PE image at the start of `data`, where appended payloads begin
+pub(crate) fn overlay_offset(data: &[u8]) -> Option<usize> {
+    pe_region(data, 0).map(|(_, end, _)| end)
+}
+
+/// Shannon entropy in bits per byte
+pub(crate) fn entropy(data: &[u8]) -> f64 {
+    if data.is_empty() {
+        return 0.0;
+    }
+    let mut counts = [0usize; 256];
+    for &byte in data {
+        counts[byte as usize] += 1;
+    }
+    let length = data.len() as f64;
+    counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / length;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Runs of high-entropy blocks outside the given payloads
+fn high_entropy_regions(data: &[u8], payloads: &[EmbeddedPayload]) -> Vec<EmbeddedPayload> {
+    let covered = |start: usize, end: usize| {
+        payloads
+            .iter()
+            .any(|p| start < (p.offset + p.size) as usize && end > p.offset as usize)
+    };
+
+    let mut regions = Vec::new();
+    let mut run: Option<(usize, usize)> = None;
+    // The first block holds the container's own headers
+    for start in (ENTROPY_BLOCK..data.len()).step_by(ENTROPY_BLOCK) {
+        let end = (start + ENTROPY_BLOCK).min(data.len());
+        let high = !covered(start, end) && entropy(&data[start..end]) >= HIGH_ENTROPY;
+        run = match (run, high) {
+            (Some((run_start, _)), true) => Some((run_start, end)),
+            (None, true) => Some((start, end)),
+            (Some(finished), false) => {
+                regions.push(finished);
+                None
+            }
+            (None, false) => None,
+        };
+    }
+    regions.extend(run);
+
+    regions
+        .into_iter()
+        .filter(|(start, end)| end - start >= MIN_HIGH_ENTROPY_SIZE)
+        .map(|(start, end)| EmbeddedPayload {
+            offset: start as u64,
+            size: (end - start) as u64,
+            kind: EmbeddedKind::HighEntropy,
+            entropy: entropy(&data[start..end]),
+        })
+        .collect()
+}
+
+/// ZIP archive ending in the end of central directory record at `eocd`
+fn zip_region(data: &[u8], eocd: usize) -> Option<(usize, usize, EmbeddedKind)> {
+    let record = data.get(eocd..eocd + 22)?;
+    let directory_size = read_u32(record, 12) as usize;
+    let directory_offset = read_u32(record, 16) as usize;
+    let comment_length = read_u16(record, 20) as usize;
+
+    // Offsets are relative to the start of the archive
+    let start = eocd
+        .checked_sub(directory_size)?
+        .checked_sub(directory_offset)?;
+    let end = eocd + 22 + comment_length;
+    (data.get(start..start + 4)? == b"PK\x03\x04" && end <= data.len()).then_some((
+        start,
+        end,
+        EmbeddedKind::Zip,
+    ))
+}
+
+/// 7z archive, sized by the next header location in its signature header
+fn seven_zip_region(data: &[u8], start: usize) -> Option<(usize, usize, EmbeddedKind)> {
+    let header = data.get(start..start + 32)?;
+    let next_header_offset = read_u64(header, 12);
+    let next_header_size = read_u64(header, 20);
+    let size = 32u64
+        .checked_add(next_header_offset)?
+        .checked_add(next_header_size)?;
+    let end = start.checked_add(usize::try_from(size).ok()?)?;
+    (next_header_size > 0 && end <= data.len()).then_some((start, end, EmbeddedKind::SevenZip))
+}
+
+/// Cabinet, sized by `cbCabinet`
+fn cabinet_region(data: &[u8], start: usize) -> Option<(usize, usize, EmbeddedKind)> {
+    let header = data.get(start..start + 36)?;
+    let size = read_u32(header, 8) as usize;
+    let files_offset = read_u32(header, 16) as usize;
+    let end = start.checked_add(size)?;
+    // Only version 1.3 cabinets exist
+    (header[24] == 3 && header[25] == 1 && files_offset < size && end <= data.len()).then_some((
+        start,
+        end,
+        EmbeddedKind::Cabinet,
+    ))
+}
+
+/// PE image, sized by its headers and the raw data of its sections
+fn pe_region(data: &[u8], start: usize) -> Option<(usize, usize, EmbeddedKind)> {
+    let image = data.get(start..)?;
+    let pe_offset = read_u32(image.get(..0x40)?, 0x3c) as usize;
+    if pe_offset >= 0x1000 || image.get(pe_offset..pe_offset + 4)? != b"PE\0\0" {
+        return None;
+    }
+
+    let coff = image.get(pe_offset + 4..pe_offset + 24)?;
+    let section_count = read_u16(coff, 2) as usize;
+    let optional_header_size = read_u16(coff, 16) as usize;
+    let optional_header = image.get(pe_offset + 24..pe_offset + 24 + optional_header_size)?;
+    let headers_size = read_u32(optional_header.get(..64)?, 60) as usize;
+    let table = pe_offset + 24 + optional_header_size;
+
+    let mut size = headers_size;
+    for index in 0..section_count {
+        let section = image.get(table + index * 40..table + (index + 1) * 40)?;
+        let raw_size = read_u32(section, 16) as usize;
+        let raw_offset = read_u32(section, 20) as usize;
+        if raw_size > 0 {
+            size = size.max(raw_offset.checked_add(raw_size)?);
+        }
+    }
+    (size > 0 && size <= image.len()).then_some((start, start + size, EmbeddedKind::Pe))
+}
+
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&data[offset..offset + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+
+    /// Smallest PE image: headers and one section of `section_size` bytes
+    pub(crate) fn pe_stub(section_size: u32) -> Vec<u8> {
+        let mut image = vec![0u8; 0x200];
+        image[..2].copy_from_slice(b"MZ");
+        image[0x3c..0x40].copy_from_slice(&0x40u32.to_le_bytes());
+        image[0x40..0x44].copy_from_slice(b"PE\0\0");
+        // One section, a 0xE0-byte optional header
+        image[0x46..0x48].copy_from_slice(&1u16.to_le_bytes());
+        image[0x54..0x56].copy_from_slice(&0xE0u16.to_le_bytes());
+        // SizeOfHeaders
+        image[0x58 + 60..0x58 + 64].copy_from_slice(&0x200u32.to_le_bytes());
+        let section = 0x58 + 0xE0;
+        image[section..section + 5].copy_from_slice(b".text");
+        image[section + 16..section + 20].copy_from_slice(&section_size.to_le_bytes());
+        image[section + 20..section + 24].copy_from_slice(&0x200u32.to_le_bytes());
+        image.resize(0x200 + section_size as usize, 0);
+        image
+    }
+
+    fn zip_archive() -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .start_file("payload.txt", zip::write::FileOptions::default())
+            .unwrap();
+        writer.write_all(b"hidden payload").unwrap();
+        writer.finish().unwrap().into_inner()
+    }
+
+    /// Pseudo-random bytes standing in for compressed data
+    fn noise(size: usize) -> Vec<u8> {
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        (0..size)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 24) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_scan_embedded_payloads() {
+        let zip = zip_archive();
+        let mut data = b"MZ stub without a PE header".to_vec();
+        data.resize(ENTROPY_BLOCK, 0);
+        let zip_offset = data.len();
+        data.extend(&zip);
+        data.resize(2 * ENTROPY_BLOCK, 0);
+        data.extend(noise(MIN_HIGH_ENTROPY_SIZE));
+
+        let payloads = EmbeddedPayload::scan(&data);
+        assert_eq!(payloads.len(), 2);
+        assert_eq!(payloads[0].kind, EmbeddedKind::Zip);
+        assert_eq!(payloads[0].offset, zip_offset as u64);
+        assert_eq!(payloads[0].size, zip.len() as u64);
+        assert_eq!(payloads[1].kind, EmbeddedKind::HighEntropy);
+        assert_eq!(payloads[1].offset, 2 * ENTROPY_BLOCK as u64);
+        assert!(payloads[1].entropy > HIGH_ENTROPY);
+
+        // The file itself is not an embedded payload
+        assert!(EmbeddedPayload::scan(&zip).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_analyze_embedded_payloads() {
+        let mut installer = tempfile::NamedTempFile::new().unwrap();
+        installer.write_all(b"MZ setup stub").unwrap();
+        installer.write_all(&zip_archive()).unwrap();
+
+        let report = EmbeddedPayloadReport::detect(installer.path(), true)
+            .await
+            .unwrap();
+        assert_eq!(report.payloads.len(), 1);
+        assert_eq!(report.nested.len(), 1);
+        assert_eq!(report.nested[0].location, "0xd");
+        assert_eq!(report.nested[0].files.len(), 1);
+
+        let mut result = AnalysisResult {
+            session_id: uuid::Uuid::new_v4(),
+            metadata: crate::core::InstallerMetadata {
+                created_at: chrono::Utc::now(),
+                ..Default::default()
+            },
+            analyzed_at: chrono::Utc::now(),
+            ..Default::default()
+        };
+        report.insert_properties(&mut result);
+        let properties = &result.metadata.properties;
+        assert_eq!(properties["embedded_payload_count"], "1");
+        assert_eq!(
+            properties["embedded_payload_analysis"],
+            "0xd: zip (1 files)"
+        );
+        assert_eq!(
+            result.files[0].path,
+            PathBuf::from("embedded/0xd/payload.txt")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_overlay_layout() {
+        let mut data = pe_stub(0x200);
+        let stub_size = data.len();
+        data.extend(b"setup data");
+        let zip_offset = data.len();
+        data.extend(zip_archive());
+
+        let overlay = Overlay::parse(&data).unwrap();
+        assert_eq!(overlay.offset, stub_size as u64);
+        assert_eq!(overlay.size, (data.len() - stub_size) as u64);
+        assert_eq!(overlay.containers.len(), 1);
+        assert_eq!(overlay.containers[0].offset, zip_offset as u64);
+        assert!(Overlay::is_archive(&overlay.containers[0]));
+        assert!(overlay.describe().starts_with("overlay at 0x400"));
+
+        let mut setup = tempfile::NamedTempFile::new().unwrap();
+        setup.write_all(&data).unwrap();
+        let files = overlay.archive_files(setup.path()).await.unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, PathBuf::from("payload.txt"));
+
+        // A stub without appended data has no overlay
+        assert!(Overlay::parse(&pe_stub(0x200)).is_none());
+    }
+}
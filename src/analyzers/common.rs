@@ -1,63 +1,37 @@
 //! Common utilities for analyzers
 
+pub mod filename;
+pub mod hashing;
+pub mod lzma;
 pub mod metadata_extractor;
-
+pub mod patterns;
+pub mod pkcs7;
+pub mod scanner;
+pub mod streaming;
+pub mod version_info;
+pub mod xpress;
+
+use crate::analyzers::LegacyTool;
 use crate::core::{AnalyzerError, InstallerFormat, Result};
-use sha2::{Digest, Sha256};
+use chrono::{DateTime, Utc};
 use std::path::Path;
 use tokio::io::{AsyncReadExt, AsyncSeekExt, SeekFrom};
 
 // Re-export for convenience
-pub use metadata_extractor::{EnhancedMetadata, FilenameParser, MetadataExtractor};
+pub use filename::{FilenameParser, ParsedFilename};
+pub use hashing::{FileDigests, HashAlgorithm};
+pub use metadata_extractor::{EnhancedMetadata, MetadataExtractor};
+pub use patterns::{PatternMatches, PatternSet};
+pub use streaming::EntryReader;
+pub use version_info::VersionStrings;
 
 /// Calculate SHA-256 hash of a file with progress logging for large files
 pub async fn calculate_file_hash(file_path: &Path) -> Result<String> {
-    let file_size = get_file_size(file_path).await?;
-
-    // For large files (>50MB), use chunked reading with progress
-    if file_size > 50 * 1024 * 1024 {
-        calculate_file_hash_chunked(file_path, file_size).await
-    } else {
-        let data = tokio::fs::read(file_path).await?;
-        let mut hasher = Sha256::new();
-        hasher.update(&data);
-        let result = hasher.finalize();
-        Ok(format!("{:x}", result))
-    }
-}
-
-/// Calculate hash for large files using chunked reading
-async fn calculate_file_hash_chunked(file_path: &Path, file_size: u64) -> Result<String> {
-    const CHUNK_SIZE: usize = 8 * 1024 * 1024; // 8MB chunks
-
-    tracing::info!(
-        "Calculating hash for large file ({:.1} MB)...",
-        file_size as f64 / 1024.0 / 1024.0
-    );
-
-    let mut file = tokio::fs::File::open(file_path).await?;
-    let mut hasher = Sha256::new();
-    let mut buffer = vec![0u8; CHUNK_SIZE];
-    let mut processed = 0u64;
-
-    loop {
-        let bytes_read = file.read(&mut buffer).await?;
-        if bytes_read == 0 {
-            break;
-        }
-
-        hasher.update(&buffer[..bytes_read]);
-        processed += bytes_read as u64;
-
-        // Log progress every 50MB
-        if processed % (50 * 1024 * 1024) == 0 || processed == file_size {
-            let progress = (processed as f64 / file_size as f64) * 100.0;
-            tracing::info!("Hash calculation progress: {:.1}%", progress);
-        }
-    }
-
-    let result = hasher.finalize();
-    Ok(format!("{:x}", result))
+    let digests = FileDigests::compute(file_path, &[HashAlgorithm::Sha256]).await?;
+    Ok(digests
+        .get(HashAlgorithm::Sha256)
+        .unwrap_or_default()
+        .to_string())
 }
 
 /// Get file size
@@ -107,6 +81,8 @@ pub fn detect_format_by_extension(file_path: &Path) -> Option<crate::core::Insta
             None
         }
         "whl" => Some(crate::core::InstallerFormat::PythonWheel),
+        "iso" | "wim" => Some(crate::core::InstallerFormat::DiskImage),
+        "inf" => Some(crate::core::InstallerFormat::DriverPackage),
         _ => None,
     }
 }
@@ -143,64 +119,62 @@ pub async fn read_file_content_range(file_path: &Path, start: u64, size: usize)
     Ok(buffer)
 }
 
-/// Search for patterns in file content using chunked reading for memory efficiency
+/// Patterns found in file content, in the order they were given
+///
+/// The file is read once for all patterns, and only until each was found.
 pub async fn search_file_content(file_path: &Path, patterns: &[&str]) -> Result<Vec<String>> {
+    Ok(scan_file_patterns(file_path, patterns).await?.found())
+}
+
+/// Presence of each of `patterns` in file content, with the first match offsets
+pub async fn scan_file_patterns(file_path: &Path, patterns: &[&str]) -> Result<PatternMatches> {
+    PatternSet::new(patterns)?
+        .first_matches_only()
+        .scan_file(file_path)
+        .await
+}
+
+/// Collect distinct matches of a pattern in file content, up to `limit` matches
+///
+/// NUL bytes are removed before matching, so ASCII text stored as UTF-16LE
+/// matches the same pattern as plain ASCII text.
+pub async fn search_file_matches(
+    file_path: &Path,
+    pattern: &regex::bytes::Regex,
+    limit: usize,
+) -> Result<Vec<String>> {
     const CHUNK_SIZE: usize = 1024 * 1024; // 1MB chunks
-    const OVERLAP_SIZE: usize = 1024; // Overlap to catch patterns across chunk boundaries
+    const OVERLAP_SIZE: usize = 1024; // Overlap to catch matches across chunk boundaries
 
     let mut file = tokio::fs::File::open(file_path).await?;
-    let file_size = get_file_size(file_path).await?;
-    let mut found_patterns = Vec::new();
-    let mut position = 0u64;
-    let mut overlap_buffer = Vec::new();
-
-    while position < file_size {
-        // Calculate chunk size for this iteration
-        let remaining = file_size - position;
-        let current_chunk_size = std::cmp::min(CHUNK_SIZE, remaining as usize);
-
-        // Read chunk
-        let mut chunk = vec![0u8; current_chunk_size];
-        file.seek(SeekFrom::Start(position)).await?;
-        let bytes_read = file.read(&mut chunk).await?;
-        chunk.truncate(bytes_read);
+    let mut matches: Vec<String> = Vec::new();
+    let mut overlap_buffer: Vec<u8> = Vec::new();
+    let mut chunk = vec![0u8; CHUNK_SIZE];
 
-        // Combine with overlap from previous chunk
-        let mut search_buffer = overlap_buffer.clone();
-        search_buffer.extend_from_slice(&chunk);
+    loop {
+        let bytes_read = file.read(&mut chunk).await?;
+        if bytes_read == 0 {
+            break;
+        }
 
-        // Convert to string for pattern matching (handle invalid UTF-8 gracefully)
-        let search_text = String::from_utf8_lossy(&search_buffer);
+        let mut search_buffer = std::mem::take(&mut overlap_buffer);
+        search_buffer.extend(chunk[..bytes_read].iter().filter(|&&b| b != 0));
 
-        // Search for each pattern
-        for pattern in patterns {
-            if search_text.contains(pattern) && !found_patterns.contains(&pattern.to_string()) {
-                found_patterns.push(pattern.to_string());
-                tracing::debug!("Found pattern '{}' at position ~{}", pattern, position);
+        for found in pattern.find_iter(&search_buffer) {
+            let text = String::from_utf8_lossy(found.as_bytes()).to_string();
+            if !matches.contains(&text) {
+                matches.push(text);
+                if matches.len() >= limit {
+                    return Ok(matches);
+                }
             }
         }
 
-        // Prepare overlap for next iteration
-        if bytes_read == current_chunk_size && (position + current_chunk_size as u64) < file_size {
-            let overlap_start = if chunk.len() > OVERLAP_SIZE {
-                chunk.len() - OVERLAP_SIZE
-            } else {
-                0
-            };
-            overlap_buffer = chunk[overlap_start..].to_vec();
-        } else {
-            overlap_buffer.clear();
-        }
-
-        position += current_chunk_size as u64;
-
-        // Break early if all patterns found
-        if found_patterns.len() == patterns.len() {
-            break;
-        }
+        let overlap_start = search_buffer.len().saturating_sub(OVERLAP_SIZE);
+        overlap_buffer = search_buffer[overlap_start..].to_vec();
     }
 
-    Ok(found_patterns)
+    Ok(matches)
 }
 
 /// Detect installer format by analyzing file content
@@ -229,15 +203,34 @@ async fn detect_pe_installer_format(file_path: &Path) -> Result<InstallerFormat>
     let inno_patterns = ["Inno Setup Setup Data", "JR.Inno.Setup", "InnoSetupVersion"];
     let installshield_patterns = ["InstallShield", "InstallScript"];
     let wix_patterns = ["Windows Installer XML", "WiX Toolset"];
+    let advanced_installer_patterns = ["ADVINSTSFX"];
+    let actual_installer_patterns = ["Actual Installer", "Softeza"];
 
     // Search for patterns in the file
     let nsis_matches = search_file_content(file_path, &nsis_patterns).await?;
     let inno_matches = search_file_content(file_path, &inno_patterns).await?;
     let installshield_matches = search_file_content(file_path, &installshield_patterns).await?;
     let wix_matches = search_file_content(file_path, &wix_patterns).await?;
-
-    // Determine format based on found patterns
-    if !nsis_matches.is_empty() {
+    let advanced_installer_matches =
+        search_file_content(file_path, &advanced_installer_patterns).await?;
+    let actual_installer_matches =
+        search_file_content(file_path, &actual_installer_patterns).await?;
+
+    // Determine format based on found patterns; bootstrapper tools first, as
+    // their stubs may carry markers of the other formats
+    if !advanced_installer_matches.is_empty() {
+        tracing::info!(
+            "Detected Advanced Installer bootstrapper: found patterns {:?}",
+            advanced_installer_matches
+        );
+        Ok(InstallerFormat::AdvancedInstaller)
+    } else if !actual_installer_matches.is_empty() {
+        tracing::info!(
+            "Detected Actual Installer setup: found patterns {:?}",
+            actual_installer_matches
+        );
+        Ok(InstallerFormat::ActualInstaller)
+    } else if !nsis_matches.is_empty() {
         tracing::info!("Detected NSIS installer: found patterns {:?}", nsis_matches);
         Ok(InstallerFormat::NSIS)
     } else if !inno_matches.is_empty() {
@@ -256,6 +249,19 @@ async fn detect_pe_installer_format(file_path: &Path) -> Result<InstallerFormat>
         tracing::info!("Detected WiX installer: found patterns {:?}", wix_matches);
         Ok(InstallerFormat::WiX)
     } else {
+        // Legacy tools are only told by their signature strings
+        for tool in LegacyTool::ALL {
+            let matches = search_file_content(file_path, tool.signatures()).await?;
+            if !matches.is_empty() {
+                tracing::info!(
+                    "Detected {} setup: found patterns {:?}",
+                    tool.name(),
+                    matches
+                );
+                return Ok(tool.format());
+            }
+        }
+
         // If no specific patterns found, classify as unknown format
         tracing::warn!(
             "No specific installer patterns found in PE file: {}",
@@ -291,6 +297,16 @@ pub async fn is_archive_file(file_path: &Path) -> Result<bool> {
     Ok(false)
 }
 
+/// Windows FILETIME, in 100 ns intervals since 1601
+pub fn filetime(value: u64) -> Option<DateTime<Utc>> {
+    const UNIX_EPOCH_INTERVALS: u64 = 116_444_736_000_000_000;
+    let since_epoch = value.checked_sub(UNIX_EPOCH_INTERVALS)?;
+    DateTime::from_timestamp(
+        (since_epoch / 10_000_000) as i64,
+        (since_epoch % 10_000_000) as u32 * 100,
+    )
+}
+
 /// Detect archive format from file header
 pub async fn detect_archive_format(file_path: &Path) -> Result<String> {
     let header = read_file_header(file_path, 8).await?;
@@ -0,0 +1,585 @@
+//! Product information from installer file names
+//!
+//! File names are split into tokens at `_`, `-`, `+`, spaces and brackets.
+//! Dots only separate tokens around numbers, so `Node.js` stays one word
+//! while `Gitify.Setup.6.3.0` yields a name and a version. Each token is
+//! then classified from the tables below: versions, architectures, locales,
+//! release channels, build numbers or hashes, and decoration such as
+//! `setup` or `signed`. The product name is made of the remaining words
+//! before the version.
+
+/// Installer and archive extensions removed before tokenizing
+const EXTENSIONS: &[&str] = &[
+    "exe",
+    "msi",
+    "msix",
+    "msixbundle",
+    "appx",
+    "appxbundle",
+    "msp",
+    "zip",
+    "7z",
+    "whl",
+    "iso",
+    "img",
+    "cab",
+];
+
+/// Architecture tokens and their normalized names
+const ARCHITECTURES: &[(&str, &str)] = &[
+    ("x64", "x64"),
+    ("amd64", "x64"),
+    ("win64", "x64"),
+    ("64bit", "x64"),
+    ("x86", "x86"),
+    ("i386", "x86"),
+    ("i686", "x86"),
+    ("ia32", "x86"),
+    ("win32", "x86"),
+    ("32bit", "x86"),
+    ("arm64", "arm64"),
+    ("aarch64", "arm64"),
+    ("arm", "arm"),
+    ("armhf", "arm"),
+    ("armv7", "arm"),
+];
+
+/// Release channels, optionally followed by a number (`rc1`, `beta.2`)
+const CHANNELS: &[&str] = &[
+    "alpha", "beta", "rc", "preview", "pre", "nightly", "canary", "dev", "insider", "insiders",
+    "snapshot",
+];
+
+/// ISO 639-1 codes recognized as the language of a locale
+const LANGUAGES: &[&str] = &[
+    "ar", "bg", "cs", "da", "de", "el", "en", "es", "et", "fa", "fi", "fr", "he", "hi", "hr", "hu",
+    "id", "it", "ja", "ko", "lt", "lv", "ms", "nb", "nl", "no", "pl", "pt", "ro", "ru", "sk", "sl",
+    "sr", "sv", "th", "tr", "uk", "vi", "zh",
+];
+
+/// Script subtags recognized in place of a region (`zh-Hans`)
+const SCRIPTS: &[&str] = &["Hans", "Hant", "Latn", "Cyrl"];
+
+/// Three-letter Windows language codes, recognized in upper case only
+const WINDOWS_LANGUAGES: &[&str] = &[
+    "CHS", "CHT", "CSY", "DEU", "ENU", "ESN", "FRA", "ITA", "JPN", "KOR", "NLD", "PLK", "PTB",
+    "RUS", "SVE", "TRK",
+];
+
+/// Decoration that is not part of the product name
+const NOISE: &[&str] = &[
+    "setup",
+    "installer",
+    "install",
+    "portable",
+    "standalone",
+    "signed",
+    "unsigned",
+    "offline",
+    "online",
+    "web",
+    "full",
+    "release",
+    "final",
+    "latest",
+    "win",
+    "windows",
+];
+
+/// Suffixes of CamelCase names that mark the setup program, e.g. `AppSetup`
+const NAME_SUFFIXES: &[&str] = &["Setup", "Installer", "Install"];
+
+/// Filename parser for extracting metadata from filenames
+pub struct FilenameParser;
+
+impl FilenameParser {
+    /// Parse filename to extract product information
+    pub fn parse(filename: &str) -> ParsedFilename {
+        let mut result = ParsedFilename::default();
+        let tokens = classify(tokenize(strip_extension(filename)));
+
+        let first_version = tokens
+            .iter()
+            .position(|token| token.kind == TokenKind::Version);
+        let words_before_version = tokens[..first_version.unwrap_or(0)]
+            .iter()
+            .any(|token| token.kind == TokenKind::Word);
+        let name_end = if words_before_version {
+            first_version.unwrap_or(tokens.len())
+        } else {
+            tokens.len()
+        };
+        let name: Vec<&str> = tokens[..name_end]
+            .iter()
+            .filter(|token| token.kind == TokenKind::Word)
+            .map(|token| token.text.as_str())
+            .collect();
+        if !name.is_empty() {
+            result.product_name = Some(name.join(" "));
+        }
+
+        for token in tokens {
+            let field = match token.kind {
+                TokenKind::Version => &mut result.version,
+                TokenKind::Arch => &mut result.architecture,
+                TokenKind::Locale => &mut result.locale,
+                TokenKind::Channel => &mut result.channel,
+                TokenKind::Build => &mut result.build,
+                TokenKind::Word | TokenKind::Noise => continue,
+            };
+            field.get_or_insert(token.text);
+        }
+
+        result
+    }
+}
+
+/// Parsed filename information
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ParsedFilename {
+    pub product_name: Option<String>,
+    pub version: Option<String>,
+    pub company: Option<String>,
+    /// Normalized architecture: `x64`, `x86`, `arm64` or `arm`
+    pub architecture: Option<String>,
+    /// Locale such as `zh-CN`, or a Windows language code such as `ENU`
+    pub locale: Option<String>,
+    /// Release channel such as `beta` or `rc1`
+    pub channel: Option<String>,
+    /// Build number or source revision hash
+    pub build: Option<String>,
+}
+
+/// Kind of a file name token
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TokenKind {
+    Word,
+    Version,
+    Arch,
+    Locale,
+    Channel,
+    Build,
+    Noise,
+}
+
+/// Token of a file name
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Token {
+    text: String,
+    kind: TokenKind,
+}
+
+impl Token {
+    fn new(text: impl Into<String>, kind: TokenKind) -> Self {
+        Self {
+            text: text.into(),
+            kind,
+        }
+    }
+}
+
+/// Remove a known installer or archive extension
+fn strip_extension(filename: &str) -> &str {
+    match filename.rsplit_once('.') {
+        Some((stem, extension))
+            if !stem.is_empty()
+                && EXTENSIONS
+                    .iter()
+                    .any(|known| known.eq_ignore_ascii_case(extension)) =>
+        {
+            stem
+        }
+        _ => filename,
+    }
+}
+
+/// Split a file name into words and versions
+///
+/// Text after a `+` is semantic version build metadata and becomes a build
+/// token as a whole.
+fn tokenize(name: &str) -> Vec<Token> {
+    let (name, build_metadata) = match name.split_once('+') {
+        Some((name, build)) if !build.is_empty() => (name, Some(build)),
+        _ => (name, None),
+    };
+
+    // `x86_64` would otherwise be split into an architecture and a number
+    let name = replace_ignore_case(name, &["x86_64", "x86-64"], "x64");
+
+    let mut tokens = Vec::new();
+    for chunk in name
+        .split(|c: char| c.is_whitespace() || matches!(c, '_' | '-' | '(' | ')' | '[' | ']'))
+        .filter(|chunk| !chunk.is_empty())
+    {
+        split_dotted(chunk, &mut tokens);
+    }
+    if let Some(build) = build_metadata {
+        tokens.push(Token::new(build, TokenKind::Build));
+    }
+    tokens
+}
+
+/// Replace each of `patterns`, ignoring ASCII case
+fn replace_ignore_case(text: &str, patterns: &[&str], replacement: &str) -> String {
+    let mut result = text.to_string();
+    for pattern in patterns {
+        while let Some(start) = result.to_ascii_lowercase().find(pattern) {
+            result.replace_range(start..start + pattern.len(), replacement);
+        }
+    }
+    result
+}
+
+/// Split a chunk at dots around numbers
+///
+/// Runs of numeric parts form a version (`6.3.0`, `v1.2`); a lone number
+/// stays a word. Letters after the last number of a version, as in
+/// `1.2.3rc1`, become a separate token. Other parts keep their dots.
+fn split_dotted(chunk: &str, tokens: &mut Vec<Token>) {
+    let parts: Vec<&str> = chunk.split('.').filter(|part| !part.is_empty()).collect();
+    let mut words: Vec<&str> = Vec::new();
+    let mut index = 0;
+    while index < parts.len() {
+        let part = parts[index];
+        let is_start = part
+            .strip_prefix(['v', 'V'])
+            .unwrap_or(part)
+            .bytes()
+            .all(|b| b.is_ascii_digit())
+            && part.bytes().any(|b| b.is_ascii_digit());
+        if !is_start {
+            words.push(part);
+            index += 1;
+            continue;
+        }
+
+        let mut numbers = vec![part.trim_start_matches(['v', 'V'])];
+        let mut suffix = None;
+        let mut end = index + 1;
+        while let Some(next) = parts.get(end) {
+            let digits = next.bytes().take_while(u8::is_ascii_digit).count();
+            if digits == 0 {
+                break;
+            }
+            numbers.push(&next[..digits]);
+            end += 1;
+            if digits < next.len() {
+                suffix = Some(&next[digits..]);
+                break;
+            }
+        }
+
+        push_words(&mut words, tokens);
+        let prefixed = part.len() > numbers[0].len();
+        if numbers.len() > 1 || prefixed {
+            tokens.push(Token::new(numbers.join("."), TokenKind::Version));
+        } else {
+            tokens.push(Token::new(part, TokenKind::Word));
+        }
+        if let Some(suffix) = suffix {
+            tokens.push(Token::new(suffix, TokenKind::Word));
+        }
+        index = end;
+    }
+    push_words(&mut words, tokens);
+}
+
+/// Emit pending word parts, splitting off the ones that are classified
+fn push_words(words: &mut Vec<&str>, tokens: &mut Vec<Token>) {
+    let mut pending: Vec<&str> = Vec::new();
+    for word in words.drain(..) {
+        if word_kind(word).is_some() {
+            if !pending.is_empty() {
+                tokens.push(Token::new(pending.join("."), TokenKind::Word));
+                pending.clear();
+            }
+            tokens.push(Token::new(word, TokenKind::Word));
+        } else {
+            pending.push(word);
+        }
+    }
+    if !pending.is_empty() {
+        tokens.push(Token::new(pending.join("."), TokenKind::Word));
+    }
+}
+
+/// Kind of a word found in the tables, if any
+fn word_kind(word: &str) -> Option<TokenKind> {
+    let lower = word.to_ascii_lowercase();
+    if architecture(&lower).is_some() {
+        Some(TokenKind::Arch)
+    } else if channel(&lower).is_some() {
+        Some(TokenKind::Channel)
+    } else if NOISE.contains(&lower.as_str()) {
+        Some(TokenKind::Noise)
+    } else if WINDOWS_LANGUAGES.contains(&word) {
+        Some(TokenKind::Locale)
+    } else if build(&lower).is_some() {
+        Some(TokenKind::Build)
+    } else {
+        None
+    }
+}
+
+/// Normalized architecture of a lower-case token
+fn architecture(lower: &str) -> Option<&'static str> {
+    ARCHITECTURES
+        .iter()
+        .find(|(token, _)| *token == lower)
+        .map(|(_, name)| *name)
+}
+
+/// Channel of a lower-case token, with its number if any
+fn channel(lower: &str) -> Option<String> {
+    let name_end = lower
+        .find(|c: char| c.is_ascii_digit())
+        .unwrap_or(lower.len());
+    let (name, number) = lower.split_at(name_end);
+    (CHANNELS.contains(&name) && number.bytes().all(|b| b.is_ascii_digit()))
+        .then(|| lower.to_string())
+}
+
+/// Build number of `build123`, or a source revision hash (`g` prefixed for
+/// `git describe` output)
+fn build(lower: &str) -> Option<String> {
+    if let Some(number) = lower.strip_prefix("build") {
+        if !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()) {
+            return Some(number.to_string());
+        }
+    }
+    let hash = match lower.strip_prefix('g') {
+        Some(hash) if is_hash(hash) => hash,
+        _ => lower,
+    };
+    is_hash(hash).then(|| hash.to_string())
+}
+
+/// Whether a token looks like an abbreviated or full commit hash
+fn is_hash(text: &str) -> bool {
+    (7..=40).contains(&text.len())
+        && text.bytes().all(|b| b.is_ascii_hexdigit())
+        && text.bytes().any(|b| b.is_ascii_digit())
+        && text.bytes().any(|b| b.is_ascii_alphabetic())
+}
+
+/// Locale formed by two tokens such as `zh-CN`, `pt_BR` or `zh-Hans`
+fn locale(language: &str, region: &str) -> Option<String> {
+    let language = language.to_ascii_lowercase();
+    if !LANGUAGES.contains(&language.as_str()) {
+        return None;
+    }
+    let region = match region.len() {
+        2 if region.bytes().all(|b| b.is_ascii_alphabetic()) => region.to_ascii_uppercase(),
+        3 if region.bytes().all(|b| b.is_ascii_digit()) => region.to_string(),
+        4 => SCRIPTS
+            .iter()
+            .find(|script| script.eq_ignore_ascii_case(region))?
+            .to_string(),
+        _ => return None,
+    };
+    Some(format!("{}-{}", language, region))
+}
+
+/// Classify the words of a token list
+///
+/// A bare language code is only taken as a locale after the version, where
+/// file names put it; before that it is more likely part of the name.
+fn classify(tokens: Vec<Token>) -> Vec<Token> {
+    let mut classified: Vec<Token> = Vec::with_capacity(tokens.len());
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        if token.kind != TokenKind::Word {
+            classified.push(token);
+            continue;
+        }
+        let lower = token.text.to_ascii_lowercase();
+
+        if let Some(next) = tokens.peek() {
+            if let Some(locale) = locale(&token.text, &next.text) {
+                tokens.next();
+                classified.push(Token::new(locale, TokenKind::Locale));
+                continue;
+            }
+        }
+
+        let after_version = classified
+            .iter()
+            .any(|token| token.kind == TokenKind::Version);
+        let next_number = tokens
+            .peek()
+            .filter(|next| next.kind == TokenKind::Word)
+            .filter(|next| next.text.bytes().all(|b| b.is_ascii_digit()))
+            .map(|next| next.text.clone());
+
+        let classified_token = if let Some(name) = architecture(&lower) {
+            Token::new(name, TokenKind::Arch)
+        } else if let Some(channel) = channel(&lower) {
+            match next_number.filter(|_| channel.bytes().all(|b| !b.is_ascii_digit())) {
+                Some(number) => {
+                    tokens.next();
+                    Token::new(format!("{}.{}", channel, number), TokenKind::Channel)
+                }
+                None => Token::new(channel, TokenKind::Channel),
+            }
+        } else if lower == "build" && next_number.is_some() {
+            tokens.next();
+            Token::new(next_number.unwrap_or_default(), TokenKind::Build)
+        } else if let Some(build) = build(&lower) {
+            Token::new(build, TokenKind::Build)
+        } else if NOISE.contains(&lower.as_str()) {
+            Token::new(token.text, TokenKind::Noise)
+        } else if WINDOWS_LANGUAGES.contains(&token.text.as_str())
+            || (after_version && LANGUAGES.contains(&lower.as_str()))
+        {
+            Token::new(token.text, TokenKind::Locale)
+        } else {
+            Token::new(strip_name_suffix(&token.text), TokenKind::Word)
+        };
+        classified.push(classified_token);
+    }
+    classified
+}
+
+/// Remove a CamelCase setup suffix: `VSCodeUserSetup` names `VSCodeUser`
+fn strip_name_suffix(word: &str) -> &str {
+    NAME_SUFFIXES
+        .iter()
+        .find_map(|suffix| {
+            word.strip_suffix(suffix).filter(|name| {
+                name.chars()
+                    .last()
+                    .is_some_and(|c| c.is_ascii_alphanumeric())
+            })
+        })
+        .unwrap_or(word)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// File names and their expected fields, see [`describe`]
+    const CASES: &[(&str, &str)] = &[
+        // Plain names and versions
+        ("ExampleApp-1.2.3-setup.exe", "name=ExampleApp version=1.2.3"),
+        ("Gitify.Setup.6.3.0", "name=Gitify version=6.3.0"),
+        ("Gitify.Setup.6.3.0.exe", "name=Gitify version=6.3.0"),
+        ("ShareX-17.1.0-portable.zip", "name=ShareX version=17.1.0"),
+        ("Firefox Setup 120.0.1.exe", "name=Firefox version=120.0.1"),
+        ("node-v20.10.0-x64.msi", "name=node version=20.10.0 arch=x64"),
+        ("npp.8.6.Installer.x64.exe", "name=npp version=8.6 arch=x64"),
+        ("putty-64bit-0.80-installer.msi", "name=putty version=0.80 arch=x64"),
+        ("Tool_v2.exe", "name=Tool version=2"),
+        ("7-Zip", "name=7 Zip"),
+        ("Office 365 2.0", "name=Office 365 version=2.0"),
+        ("Setup_Contoso_Suite_4.0.0.1", "name=Contoso Suite version=4.0.0.1"),
+        ("1.0.0-ExampleApp.exe", "name=ExampleApp version=1.0.0"),
+        ("setup.exe", ""),
+        // Embedded dots in names
+        ("Node.js-20.0.0.msi", "name=Node.js version=20.0.0"),
+        (
+            "Microsoft.WindowsTerminal_1.18.3181.0_x64.msixbundle",
+            "name=Microsoft.WindowsTerminal version=1.18.3181.0 arch=x64",
+        ),
+        (
+            "Paint.NET.5.0.11.install.x64",
+            "name=Paint.NET version=5.0.11 arch=x64",
+        ),
+        // CamelCase setup suffixes
+        (
+            "VSCodeUserSetup-x64-1.85.1.exe",
+            "name=VSCodeUser version=1.85.1 arch=x64",
+        ),
+        ("DemoInstaller_3.1", "name=Demo version=3.1"),
+        (
+            "Win32DiskImager-1.0.0-install",
+            "name=Win32DiskImager version=1.0.0",
+        ),
+        // Architectures
+        ("python-3.12.0-amd64.exe", "name=python version=3.12.0 arch=x64"),
+        ("vlc-3.0.20-win64.exe", "name=vlc version=3.0.20 arch=x64"),
+        ("vlc-3.0.20-win32.exe", "name=vlc version=3.0.20 arch=x86"),
+        ("app_1.0_x86_64", "name=app version=1.0 arch=x64"),
+        ("app-1.0-X86-64", "name=app version=1.0 arch=x64"),
+        ("app-1.0-i686.exe", "name=app version=1.0 arch=x86"),
+        ("app-1.0-aarch64", "name=app version=1.0 arch=arm64"),
+        ("App_ARM64_2.0", "name=App version=2.0 arch=arm64"),
+        // Locales
+        (
+            "app_2.3.4_zh-CN_arm64_signed.exe",
+            "name=app version=2.3.4 arch=arm64 locale=zh-CN",
+        ),
+        ("App-1.0-en_US.msi", "name=App version=1.0 locale=en-US"),
+        ("App-1.0-pt-br.msi", "name=App version=1.0 locale=pt-BR"),
+        ("App-1.0-zh-Hans.exe", "name=App version=1.0 locale=zh-Hans"),
+        ("App-1.0-es-419.exe", "name=App version=1.0 locale=es-419"),
+        ("App_1.0_ENU.exe", "name=App version=1.0 locale=ENU"),
+        ("App-1.0-de.exe", "name=App version=1.0 locale=de"),
+        ("It-Tools-1.0.exe", "name=It Tools version=1.0"),
+        ("My-ID-Card-1.0.exe", "name=My ID Card version=1.0"),
+        // Channels
+        ("App-1.2.3-beta.exe", "name=App version=1.2.3 channel=beta"),
+        ("App-1.2.3-beta.2.exe", "name=App version=1.2.3 channel=beta.2"),
+        (
+            "App-1.2.3-RC1-x64.exe",
+            "name=App version=1.2.3 arch=x64 channel=rc1",
+        ),
+        ("App-1.2.3rc1.exe", "name=App version=1.2.3 channel=rc1"),
+        (
+            "App_Nightly_2024.05.01",
+            "name=App version=2024.05.01 channel=nightly",
+        ),
+        (
+            "App-Insiders-1.86.0",
+            "name=App version=1.86.0 channel=insiders",
+        ),
+        // Builds
+        ("App-1.2.3+build.5.exe", "name=App version=1.2.3 build=build.5"),
+        ("App-1.2.3-build1234.exe", "name=App version=1.2.3 build=1234"),
+        ("App 1.2.3 Build 1234.exe", "name=App version=1.2.3 build=1234"),
+        ("App-1.2.3-a1b2c3d.exe", "name=App version=1.2.3 build=a1b2c3d"),
+        (
+            "App-1.2.3-14-g9f8e7d6c.exe",
+            "name=App version=1.2.3 build=9f8e7d6c",
+        ),
+        ("App-1.2.3-deadbeef", "name=App version=1.2.3"),
+        // Everything at once
+        (
+            "Contoso.Viewer_3.0.1-beta.3_en-US_x64_0a1b2c3d4e5f_signed.msi",
+            "name=Contoso.Viewer version=3.0.1 arch=x64 locale=en-US channel=beta.3 build=0a1b2c3d4e5f",
+        ),
+    ];
+
+    /// Fields of a parse result as `key=value` pairs
+    fn describe(parsed: &ParsedFilename) -> String {
+        [
+            ("name", &parsed.product_name),
+            ("version", &parsed.version),
+            ("arch", &parsed.architecture),
+            ("locale", &parsed.locale),
+            ("channel", &parsed.channel),
+            ("build", &parsed.build),
+        ]
+        .iter()
+        .filter_map(|(key, value)| value.as_ref().map(|value| format!("{}={}", key, value)))
+        .collect::<Vec<_>>()
+        .join(" ")
+    }
+
+    #[test]
+    fn test_parse_table() {
+        for (filename, expected) in CASES {
+            let parsed = FilenameParser::parse(filename);
+            assert_eq!(describe(&parsed), *expected, "{}", filename);
+            assert_eq!(parsed.company, None);
+        }
+    }
+
+    #[test]
+    fn test_extension_only_stripped_when_known() {
+        assert_eq!(strip_extension("App-1.0.MSI"), "App-1.0");
+        assert_eq!(strip_extension("App-1.0.7z"), "App-1.0");
+        assert_eq!(strip_extension("Node.js"), "Node.js");
+        assert_eq!(strip_extension(".exe"), ".exe");
+    }
+}
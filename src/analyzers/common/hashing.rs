@@ -0,0 +1,288 @@
+//! File digests with a selectable set of hash algorithms
+//!
+//! SHA-256 identifies installers throughout reports and the results
+//! database. BLAKE3, SHA-1 and MD5 can be added per run to match manifests
+//! and feeds that list other digests. All selected digests are computed
+//! while reading the file once. SHA-256 and SHA-1 use the SHA extensions of
+//! the CPU where available, BLAKE3 spreads large chunks over all cores.
+
+use crate::core::{AnalysisResult, AnalyzerError, Result};
+use crate::utils::progress::{self, ProgressReader};
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::io::Read;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Bytes read from the file at a time
+const CHUNK_SIZE: usize = 8 * 1024 * 1024;
+
+/// Files above this size log their hashing progress
+const PROGRESS_THRESHOLD: u64 = 50 * 1024 * 1024;
+
+/// Supported hash algorithms
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HashAlgorithm {
+    Sha256,
+    Blake3,
+    /// For legacy manifests only, SHA-1 is broken
+    Sha1,
+    /// For legacy manifests only, MD5 is broken
+    Md5,
+}
+
+impl HashAlgorithm {
+    pub const ALL: [HashAlgorithm; 4] = [Self::Sha256, Self::Blake3, Self::Sha1, Self::Md5];
+
+    /// Lowercase name, as accepted on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha256",
+            Self::Blake3 => "blake3",
+            Self::Sha1 => "sha1",
+            Self::Md5 => "md5",
+        }
+    }
+
+    fn hasher(&self) -> Hasher {
+        match self {
+            Self::Sha256 => Hasher::Sha256(Sha256::new()),
+            Self::Blake3 => Hasher::Blake3(Box::default()),
+            Self::Sha1 => Hasher::Sha1(Sha1::new()),
+            Self::Md5 => Hasher::Md5(Md5::new()),
+        }
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = String;
+
+    fn from_str(name: &str) -> std::result::Result<Self, Self::Err> {
+        let name = name.trim().to_lowercase().replace('-', "");
+        Self::ALL
+            .into_iter()
+            .find(|algorithm| algorithm.name() == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::ALL.iter().map(HashAlgorithm::name).collect();
+                format!(
+                    "unknown hash algorithm '{}', expected one of {}",
+                    name,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// Running digest of one algorithm
+enum Hasher {
+    Sha256(Sha256),
+    Blake3(Box<blake3::Hasher>),
+    Sha1(Sha1),
+    Md5(Md5),
+}
+
+impl Hasher {
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Sha256(hasher) => hasher.update(data),
+            // Worth the thread pool for full chunks only
+            Self::Blake3(hasher) if data.len() == CHUNK_SIZE => {
+                hasher.update_rayon(data);
+            }
+            Self::Blake3(hasher) => {
+                hasher.update(data);
+            }
+            Self::Sha1(hasher) => hasher.update(data),
+            Self::Md5(hasher) => hasher.update(data),
+        }
+    }
+
+    fn finalize(self) -> String {
+        match self {
+            Self::Sha256(hasher) => hex::encode(hasher.finalize()),
+            Self::Blake3(hasher) => hasher.finalize().to_hex().to_string(),
+            Self::Sha1(hasher) => hex::encode(hasher.finalize()),
+            Self::Md5(hasher) => hex::encode(hasher.finalize()),
+        }
+    }
+}
+
+/// Hex digests of a file, one per selected algorithm
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileDigests {
+    digests: Vec<(HashAlgorithm, String)>,
+}
+
+impl FileDigests {
+    /// Hash `file_path` with every algorithm of `algorithms` in one read
+    pub async fn compute(file_path: &Path, algorithms: &[HashAlgorithm]) -> Result<Self> {
+        let algorithms = algorithms.to_vec();
+        let file_path = file_path.to_path_buf();
+        let sink = progress::current();
+        tokio::task::spawn_blocking(move || {
+            let file = std::fs::File::open(&file_path)?;
+            let file_size = file.metadata()?.len();
+            if file_size > PROGRESS_THRESHOLD {
+                tracing::info!(
+                    "Calculating hash for large file ({:.1} MB)...",
+                    file_size as f64 / 1024.0 / 1024.0
+                );
+            }
+            let reader = ProgressReader::new(file, file_size, sink);
+            Ok(Self::from_reader(reader, file_size, &algorithms)?)
+        })
+        .await
+        .map_err(|e| AnalyzerError::generic(format!("Hashing task failed: {}", e)))?
+    }
+
+    /// Hash everything `reader` yields, `size` is only used for progress logging
+    pub fn from_reader(
+        mut reader: impl Read,
+        size: u64,
+        algorithms: &[HashAlgorithm],
+    ) -> std::io::Result<Self> {
+        let mut selected: Vec<HashAlgorithm> = Vec::new();
+        for algorithm in algorithms {
+            if !selected.contains(algorithm) {
+                selected.push(*algorithm);
+            }
+        }
+        let mut hashers: Vec<Hasher> = selected.iter().map(HashAlgorithm::hasher).collect();
+
+        let mut buffer = vec![0u8; CHUNK_SIZE];
+        let mut processed = 0u64;
+        loop {
+            let bytes_read = read_chunk(&mut reader, &mut buffer)?;
+            if bytes_read == 0 {
+                break;
+            }
+            let chunk = &buffer[..bytes_read];
+            if let [hasher] = hashers.as_mut_slice() {
+                hasher.update(chunk);
+            } else {
+                // Every algorithm digests the chunk on its own thread
+                std::thread::scope(|scope| {
+                    for hasher in hashers.iter_mut() {
+                        scope.spawn(move || hasher.update(chunk));
+                    }
+                });
+            }
+
+            processed += bytes_read as u64;
+            if size > PROGRESS_THRESHOLD && processed % PROGRESS_THRESHOLD < CHUNK_SIZE as u64 {
+                let progress = (processed as f64 / size as f64) * 100.0;
+                tracing::info!("Hash calculation progress: {:.1}%", progress.min(100.0));
+            }
+        }
+
+        Ok(Self {
+            digests: selected
+                .into_iter()
+                .zip(hashers)
+                .map(|(algorithm, hasher)| (algorithm, hasher.finalize()))
+                .collect(),
+        })
+    }
+
+    /// Hex digest of `algorithm`, if it was selected
+    pub fn get(&self, algorithm: HashAlgorithm) -> Option<&str> {
+        self.digests
+            .iter()
+            .find(|(known, _)| *known == algorithm)
+            .map(|(_, digest)| digest.as_str())
+    }
+
+    /// Digests in the order the algorithms were given
+    pub fn iter(&self) -> impl Iterator<Item = (HashAlgorithm, &str)> {
+        self.digests
+            .iter()
+            .map(|(algorithm, digest)| (*algorithm, digest.as_str()))
+    }
+
+    /// Add the digests as `file_hash_<algorithm>` properties
+    ///
+    /// SHA-256 is the `file_hash` of the metadata already.
+    pub fn insert_properties(&self, result: &mut AnalysisResult) {
+        for (algorithm, digest) in self.iter() {
+            if algorithm != HashAlgorithm::Sha256 {
+                result
+                    .metadata
+                    .properties
+                    .insert(format!("file_hash_{}", algorithm), digest.to_string());
+            }
+        }
+    }
+}
+
+/// Fill `buffer` as far as `reader` allows, so chunks stay full until the end
+fn read_chunk(reader: &mut impl Read, buffer: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(bytes_read) => filled += bytes_read,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_digests_in_one_pass() {
+        let digests = FileDigests::from_reader(&b"abc"[..], 3, &HashAlgorithm::ALL).unwrap();
+        assert_eq!(
+            digests.get(HashAlgorithm::Sha256),
+            Some("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+        );
+        assert_eq!(
+            digests.get(HashAlgorithm::Blake3),
+            Some("6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85")
+        );
+        assert_eq!(
+            digests.get(HashAlgorithm::Sha1),
+            Some("a9993e364706816aba3e25717850c26c9cd0d89d")
+        );
+        assert_eq!(
+            digests.get(HashAlgorithm::Md5),
+            Some("900150983cd24fb0d6963f7d28e17f72")
+        );
+    }
+
+    #[test]
+    fn test_multi_chunk_digests_match_single_algorithm() {
+        let content: Vec<u8> = (0..CHUNK_SIZE * 2 + 17).map(|i| (i % 251) as u8).collect();
+        let size = content.len() as u64;
+        let all = FileDigests::from_reader(content.as_slice(), size, &HashAlgorithm::ALL).unwrap();
+        for algorithm in HashAlgorithm::ALL {
+            let single =
+                FileDigests::from_reader(content.as_slice(), size, &[algorithm, algorithm])
+                    .unwrap();
+            assert_eq!(single.iter().count(), 1);
+            assert_eq!(single.get(algorithm), all.get(algorithm));
+        }
+        assert_eq!(
+            all.get(HashAlgorithm::Blake3).unwrap(),
+            blake3::hash(&content).to_hex().as_str()
+        );
+    }
+
+    #[test]
+    fn test_parse_algorithm_names() {
+        assert_eq!("SHA-256".parse(), Ok(HashAlgorithm::Sha256));
+        assert_eq!("blake3".parse(), Ok(HashAlgorithm::Blake3));
+        assert!("crc32".parse::<HashAlgorithm>().is_err());
+    }
+}
@@ -0,0 +1,362 @@
+//! LZMA decoder for installer headers
+//!
+//! InnoSetup and NSIS compress their setup headers with LZMA (the `.lzma`
+//! algorithm, not the `.xz` container). Streams start with the 5-byte
+//! properties header and have no size field; they end at an end marker or
+//! where the input ends.
+
+use crate::core::{AnalyzerError, Result};
+
+const NUM_STATES: usize = 12;
+const POS_STATES_MAX: usize = 1 << 4;
+const MATCH_MIN_LEN: usize = 2;
+const END_POS_MODEL_INDEX: u32 = 14;
+const NUM_FULL_DISTANCES: usize = 1 << (END_POS_MODEL_INDEX >> 1);
+const NUM_ALIGN_BITS: u32 = 4;
+const PROB_INIT: u16 = 1 << 10;
+
+/// Decompress an LZMA stream, stopping once `limit` bytes were produced
+pub fn decompress(data: &[u8], limit: usize) -> Result<Vec<u8>> {
+    let invalid = |what: &str| AnalyzerError::parse_error(format!("Invalid LZMA stream: {}", what));
+    let (properties, stream) = data
+        .split_first_chunk::<5>()
+        .ok_or_else(|| invalid("missing properties"))?;
+    let mut properties_byte = properties[0] as u32;
+    if properties_byte >= 9 * 5 * 5 {
+        return Err(invalid("bad properties"));
+    }
+    let lc = properties_byte % 9;
+    properties_byte /= 9;
+    let lp = properties_byte % 5;
+    let pb = properties_byte / 5;
+
+    let mut decoder = Decoder::new(lc, lp, pb);
+    let mut range = RangeDecoder::new(stream).ok_or_else(|| invalid("bad range coder start"))?;
+    decoder
+        .decode(&mut range, limit)
+        .ok_or_else(|| invalid("corrupt data"))?;
+    Ok(decoder.output)
+}
+
+struct RangeDecoder<'a> {
+    data: &'a [u8],
+    position: usize,
+    range: u32,
+    code: u32,
+}
+
+impl<'a> RangeDecoder<'a> {
+    fn new(data: &'a [u8]) -> Option<Self> {
+        if data.len() < 5 || data[0] != 0 {
+            return None;
+        }
+        let code = u32::from_be_bytes([data[1], data[2], data[3], data[4]]);
+        Some(Self {
+            data,
+            position: 5,
+            range: u32::MAX,
+            code,
+        })
+    }
+
+    /// More input was needed than there is, the last decoded symbol is void
+    fn overran(&self) -> bool {
+        self.position > self.data.len()
+    }
+
+    fn normalize(&mut self) {
+        if self.range < 1 << 24 {
+            self.range <<= 8;
+            // Reading past the end yields zeros, see `overran`
+            let byte = self.data.get(self.position).copied().unwrap_or(0);
+            self.position += 1;
+            self.code = (self.code << 8) | byte as u32;
+        }
+    }
+
+    fn decode_bit(&mut self, probability: &mut u16) -> u32 {
+        let bound = (self.range >> 11) * *probability as u32;
+        let bit = if self.code < bound {
+            *probability += ((1 << 11) - *probability) >> 5;
+            self.range = bound;
+            0
+        } else {
+            *probability -= *probability >> 5;
+            self.code -= bound;
+            self.range -= bound;
+            1
+        };
+        self.normalize();
+        bit
+    }
+
+    fn decode_direct_bits(&mut self, count: u32) -> u32 {
+        let mut result = 0u32;
+        for _ in 0..count {
+            self.range >>= 1;
+            self.code = self.code.wrapping_sub(self.range);
+            let mask = 0u32.wrapping_sub(self.code >> 31);
+            self.code = self.code.wrapping_add(self.range & mask);
+            self.normalize();
+            result = (result << 1).wrapping_add(mask.wrapping_add(1));
+        }
+        result
+    }
+
+    fn decode_tree(&mut self, probabilities: &mut [u16], bits: u32) -> u32 {
+        let mut index = 1usize;
+        for _ in 0..bits {
+            index = (index << 1) + self.decode_bit(&mut probabilities[index]) as usize;
+        }
+        index as u32 - (1 << bits)
+    }
+
+    fn decode_reverse_tree(&mut self, probabilities: &mut [u16], bits: u32) -> u32 {
+        let mut index = 1usize;
+        let mut symbol = 0u32;
+        for bit_index in 0..bits {
+            let bit = self.decode_bit(&mut probabilities[index]);
+            index = (index << 1) + bit as usize;
+            symbol |= bit << bit_index;
+        }
+        symbol
+    }
+}
+
+struct LengthDecoder {
+    choice: u16,
+    choice2: u16,
+    low: [[u16; 1 << 3]; POS_STATES_MAX],
+    mid: [[u16; 1 << 3]; POS_STATES_MAX],
+    high: [u16; 1 << 8],
+}
+
+impl LengthDecoder {
+    fn new() -> Self {
+        Self {
+            choice: PROB_INIT,
+            choice2: PROB_INIT,
+            low: [[PROB_INIT; 1 << 3]; POS_STATES_MAX],
+            mid: [[PROB_INIT; 1 << 3]; POS_STATES_MAX],
+            high: [PROB_INIT; 1 << 8],
+        }
+    }
+
+    fn decode(&mut self, range: &mut RangeDecoder, pos_state: usize) -> usize {
+        if range.decode_bit(&mut self.choice) == 0 {
+            return range.decode_tree(&mut self.low[pos_state], 3) as usize;
+        }
+        if range.decode_bit(&mut self.choice2) == 0 {
+            return 8 + range.decode_tree(&mut self.mid[pos_state], 3) as usize;
+        }
+        16 + range.decode_tree(&mut self.high, 8) as usize
+    }
+}
+
+struct Decoder {
+    lc: u32,
+    lp: u32,
+    pb: u32,
+    output: Vec<u8>,
+    literals: Vec<u16>,
+    pos_slots: [[u16; 1 << 6]; 4],
+    pos_decoders: [u16; 1 + NUM_FULL_DISTANCES - END_POS_MODEL_INDEX as usize],
+    align: [u16; 1 << NUM_ALIGN_BITS],
+    is_match: [u16; NUM_STATES * POS_STATES_MAX],
+    is_rep: [u16; NUM_STATES],
+    is_rep_g0: [u16; NUM_STATES],
+    is_rep_g1: [u16; NUM_STATES],
+    is_rep_g2: [u16; NUM_STATES],
+    is_rep0_long: [u16; NUM_STATES * POS_STATES_MAX],
+    lengths: LengthDecoder,
+    rep_lengths: LengthDecoder,
+}
+
+impl Decoder {
+    fn new(lc: u32, lp: u32, pb: u32) -> Self {
+        Self {
+            lc,
+            lp,
+            pb,
+            output: Vec::new(),
+            literals: vec![PROB_INIT; 0x300 << (lc + lp)],
+            pos_slots: [[PROB_INIT; 1 << 6]; 4],
+            pos_decoders: [PROB_INIT; 1 + NUM_FULL_DISTANCES - END_POS_MODEL_INDEX as usize],
+            align: [PROB_INIT; 1 << NUM_ALIGN_BITS],
+            is_match: [PROB_INIT; NUM_STATES * POS_STATES_MAX],
+            is_rep: [PROB_INIT; NUM_STATES],
+            is_rep_g0: [PROB_INIT; NUM_STATES],
+            is_rep_g1: [PROB_INIT; NUM_STATES],
+            is_rep_g2: [PROB_INIT; NUM_STATES],
+            is_rep0_long: [PROB_INIT; NUM_STATES * POS_STATES_MAX],
+            lengths: LengthDecoder::new(),
+            rep_lengths: LengthDecoder::new(),
+        }
+    }
+
+    /// Decode until the end marker, the end of the input or `limit`; `None`
+    /// for corrupt data
+    ///
+    /// Streams without end marker end with the input. Symbols that needed
+    /// bytes past it are dropped, like the reference decoder does.
+    fn decode(&mut self, range: &mut RangeDecoder, limit: usize) -> Option<()> {
+        let mut state = 0usize;
+        let mut reps = [0usize; 4];
+        while self.output.len() < limit {
+            let decoded = self.output.len();
+            let end_marker = self.decode_symbol(range, &mut state, &mut reps, limit)?;
+            if range.overran() {
+                self.output.truncate(decoded);
+                break;
+            }
+            if end_marker {
+                break;
+            }
+        }
+        Some(())
+    }
+
+    /// Decode a literal or a match, `true` for the end marker
+    fn decode_symbol(
+        &mut self,
+        range: &mut RangeDecoder,
+        state: &mut usize,
+        reps: &mut [usize; 4],
+        limit: usize,
+    ) -> Option<bool> {
+        let pos_state = self.output.len() & ((1 << self.pb) - 1);
+
+        if range.decode_bit(&mut self.is_match[(*state << 4) + pos_state]) == 0 {
+            self.decode_literal(range, *state, reps[0]);
+            *state = match *state {
+                0..=3 => 0,
+                4..=9 => *state - 3,
+                _ => *state - 6,
+            };
+            return Some(false);
+        }
+
+        let length = if range.decode_bit(&mut self.is_rep[*state]) != 0 {
+            if self.output.is_empty() {
+                return None;
+            }
+            if range.decode_bit(&mut self.is_rep_g0[*state]) == 0 {
+                if range.decode_bit(&mut self.is_rep0_long[(*state << 4) + pos_state]) == 0 {
+                    *state = if *state < 7 { 9 } else { 11 };
+                    let byte = self.output[self.output.len() - reps[0] - 1];
+                    self.output.push(byte);
+                    return Some(false);
+                }
+            } else {
+                let distance = if range.decode_bit(&mut self.is_rep_g1[*state]) == 0 {
+                    reps[1]
+                } else {
+                    let distance = if range.decode_bit(&mut self.is_rep_g2[*state]) == 0 {
+                        reps[2]
+                    } else {
+                        let distance = reps[3];
+                        reps[3] = reps[2];
+                        distance
+                    };
+                    reps[2] = reps[1];
+                    distance
+                };
+                reps[1] = reps[0];
+                reps[0] = distance;
+            }
+            *state = if *state < 7 { 8 } else { 11 };
+            self.rep_lengths.decode(range, pos_state)
+        } else {
+            reps[3] = reps[2];
+            reps[2] = reps[1];
+            reps[1] = reps[0];
+            let length = self.lengths.decode(range, pos_state);
+            *state = if *state < 7 { 7 } else { 10 };
+            let distance = self.decode_distance(range, length);
+            if distance == u32::MAX {
+                return Some(true);
+            }
+            reps[0] = distance as usize;
+            length
+        };
+
+        if reps[0] >= self.output.len() {
+            // Corrupt, unless the input ran out while decoding the distance
+            return range.overran().then_some(false);
+        }
+        let start = self.output.len() - reps[0] - 1;
+        let count = (length + MATCH_MIN_LEN).min(limit - self.output.len());
+        for index in start..start + count {
+            let byte = self.output[index];
+            self.output.push(byte);
+        }
+        Some(false)
+    }
+
+    fn decode_literal(&mut self, range: &mut RangeDecoder, state: usize, rep0: usize) {
+        let previous = self.output.last().copied().unwrap_or(0) as usize;
+        let position = self.output.len() & ((1 << self.lp) - 1);
+        let literal_state = (position << self.lc) + (previous >> (8 - self.lc));
+        let probabilities = &mut self.literals[0x300 * literal_state..0x300 * (literal_state + 1)];
+
+        let mut symbol = 1usize;
+        if state >= 7 {
+            let mut match_byte = self.output[self.output.len() - rep0 - 1] as usize;
+            while symbol < 0x100 {
+                let match_bit = (match_byte >> 7) & 1;
+                match_byte <<= 1;
+                let bit =
+                    range.decode_bit(&mut probabilities[((1 + match_bit) << 8) + symbol]) as usize;
+                symbol = (symbol << 1) | bit;
+                if match_bit != bit {
+                    break;
+                }
+            }
+        }
+        while symbol < 0x100 {
+            symbol = (symbol << 1) | range.decode_bit(&mut probabilities[symbol]) as usize;
+        }
+        self.output.push((symbol - 0x100) as u8);
+    }
+
+    fn decode_distance(&mut self, range: &mut RangeDecoder, length: usize) -> u32 {
+        let length_state = length.min(3);
+        let pos_slot = range.decode_tree(&mut self.pos_slots[length_state], 6);
+        if pos_slot < 4 {
+            return pos_slot;
+        }
+
+        let direct_bits = (pos_slot >> 1) - 1;
+        let mut distance = (2 | (pos_slot & 1)) << direct_bits;
+        if pos_slot < END_POS_MODEL_INDEX {
+            let offset = (distance - pos_slot) as usize;
+            distance += range.decode_reverse_tree(&mut self.pos_decoders[offset..], direct_bits);
+        } else {
+            distance = distance.wrapping_add(
+                range.decode_direct_bits(direct_bits - NUM_ALIGN_BITS) << NUM_ALIGN_BITS,
+            );
+            distance =
+                distance.wrapping_add(range.decode_reverse_tree(&mut self.align, NUM_ALIGN_BITS));
+        }
+        distance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decompress_with_end_marker() {
+        // `lzma.compress(b"hello hello hello world", format=lzma.FORMAT_ALONE)`
+        // of Python without the 8-byte size field of the .lzma header
+        let stream =
+            hex::decode("5d0000800000341949ee8de950960806f6e8929579b3ffff0e740000").unwrap();
+        assert_eq!(
+            decompress(&stream, usize::MAX).unwrap(),
+            b"hello hello hello world"
+        );
+        assert_eq!(decompress(&stream, 5).unwrap(), b"hello");
+        assert!(decompress(&stream[..3], usize::MAX).is_err());
+    }
+}
@@ -1,4 +1,6 @@
+use super::FilenameParser;
 use crate::core::error::Result;
+use crate::core::{FieldProvenance, MetadataField, MetadataProvenance, MetadataSource};
 use regex::Regex;
 use std::collections::HashMap;
 use std::path::Path;
@@ -138,6 +140,8 @@ pub struct EnhancedMetadata {
     pub original_filename: Option<String>,
     pub legal_copyright: Option<String>,
     pub confidence_score: f32,
+    /// Source of each identity field
+    pub provenance: MetadataProvenance,
 }
 
 impl EnhancedMetadata {
@@ -147,6 +151,7 @@ impl EnhancedMetadata {
             if name != "Unknown Application" && name != "Unknown" {
                 self.product_name = Some(name.clone());
                 self.confidence_score += 0.4;
+                self.record(MetadataField::ProductName, MetadataSource::InstallerData);
             }
         }
 
@@ -157,6 +162,7 @@ impl EnhancedMetadata {
             if version != "1.0.0" && version != "1.0.0.0" {
                 self.product_version = Some(version.clone());
                 self.confidence_score += 0.3;
+                self.record(MetadataField::ProductVersion, MetadataSource::InstallerData);
             }
         }
 
@@ -164,6 +170,7 @@ impl EnhancedMetadata {
             if company != "Unknown Publisher" && company != "Unknown" {
                 self.manufacturer = Some(company.clone());
                 self.confidence_score += 0.2;
+                self.record(MetadataField::Manufacturer, MetadataSource::InstallerData);
             }
         }
 
@@ -184,18 +191,25 @@ impl EnhancedMetadata {
                 if !name.contains("Installer") && !name.contains("Setup") {
                     self.product_name = Some(name.clone());
                     self.confidence_score += 0.3;
+                    self.record(MetadataField::ProductName, MetadataSource::PeVersionInfo);
                 }
             }
         }
 
         if self.product_version.is_none() {
-            if let Some(version) = pe_info
+            let version = pe_info
                 .get("ProductVersion")
                 .or_else(|| pe_info.get("FileVersion"))
-                .or_else(|| pe_info.get("ExtractedVersion"))
-            {
+                .map(|version| (version, MetadataSource::PeVersionInfo))
+                .or_else(|| {
+                    pe_info
+                        .get("ExtractedVersion")
+                        .map(|version| (version, MetadataSource::ContentScan))
+                });
+            if let Some((version, source)) = version {
                 self.product_version = Some(version.clone());
                 self.confidence_score += 0.2;
+                self.record(MetadataField::ProductVersion, source);
             }
         }
 
@@ -203,6 +217,7 @@ impl EnhancedMetadata {
             if let Some(company) = pe_info.get("CompanyName") {
                 self.manufacturer = Some(company.clone());
                 self.confidence_score += 0.2;
+                self.record(MetadataField::Manufacturer, MetadataSource::PeVersionInfo);
             }
         }
 
@@ -229,16 +244,28 @@ impl EnhancedMetadata {
             if self.product_name.is_none() && parsed.product_name.is_some() {
                 self.product_name = parsed.product_name;
                 self.confidence_score += 0.2;
+                self.record(
+                    MetadataField::ProductName,
+                    MetadataSource::FilenameHeuristic,
+                );
             }
 
             if self.product_version.is_none() && parsed.version.is_some() {
                 self.product_version = parsed.version;
                 self.confidence_score += 0.1;
+                self.record(
+                    MetadataField::ProductVersion,
+                    MetadataSource::FilenameHeuristic,
+                );
             }
 
             if self.manufacturer.is_none() && parsed.company.is_some() {
                 self.manufacturer = parsed.company;
                 self.confidence_score += 0.1;
+                self.record(
+                    MetadataField::Manufacturer,
+                    MetadataSource::FilenameHeuristic,
+                );
             }
         }
     }
@@ -247,17 +274,26 @@ impl EnhancedMetadata {
     pub fn apply_smart_defaults(&mut self) {
         if self.product_name.is_none() {
             self.product_name = Some("Unknown Application".to_string());
+            self.record(MetadataField::ProductName, MetadataSource::Placeholder);
         }
 
         if self.product_version.is_none() {
             self.product_version = Some("Unknown".to_string());
+            self.record(MetadataField::ProductVersion, MetadataSource::Placeholder);
         }
 
         if self.manufacturer.is_none() {
             self.manufacturer = Some("Unknown Publisher".to_string());
+            self.record(MetadataField::Manufacturer, MetadataSource::Placeholder);
         }
     }
 
+    /// Record the source of a field that was just set
+    fn record(&mut self, field: MetadataField, source: MetadataSource) {
+        self.provenance
+            .set(field, Some(FieldProvenance::from_source(source)));
+    }
+
     /// Check if metadata is incomplete
     pub fn is_incomplete(&self) -> bool {
         self.product_name.is_none()
@@ -267,99 +303,59 @@ impl EnhancedMetadata {
     }
 }
 
-/// Filename parser for extracting metadata from filenames
-pub struct FilenameParser;
-
-impl FilenameParser {
-    /// Parse filename to extract product information
-    pub fn parse(filename: &str) -> ParsedFilename {
-        let mut result = ParsedFilename::default();
-
-        // Remove file extension first
-        let name_without_ext = filename
-            .strip_suffix(".exe")
-            .unwrap_or(filename)
-            .strip_suffix(".msi")
-            .unwrap_or(filename)
-            .strip_suffix(".zip")
-            .unwrap_or(filename)
-            .strip_suffix(".whl")
-            .unwrap_or(filename);
-
-        // Try to extract version using regex (before cleaning)
-        let version_patterns = [
-            r"(\d+\.\d+\.\d+\.\d+)", // 1.2.3.4
-            r"(\d+\.\d+\.\d+)",      // 1.2.3
-            r"v(\d+\.\d+\.\d+)",     // v1.2.3
-            r"v(\d+\.\d+)",          // v1.2
-            r"(\d+\.\d+)",           // 1.2 (last resort)
-        ];
-
-        let mut version_found = false;
-        let mut product_name_base = name_without_ext.to_string();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::MetadataConfidence;
 
-        for pattern in &version_patterns {
-            if let Ok(regex) = Regex::new(pattern) {
-                if let Some(captures) = regex.captures(name_without_ext) {
-                    if let Some(version_match) = captures.get(1) {
-                        result.version = Some(version_match.as_str().to_string());
-                        // Remove version from product name
-                        product_name_base = regex.replace(name_without_ext, "").to_string();
-                        version_found = true;
-                        break;
-                    }
-                }
-            }
-        }
+    #[test]
+    fn test_provenance_of_each_source() {
+        let mut metadata = EnhancedMetadata::default();
+        metadata.apply_existing_metadata(HashMap::from([(
+            "ProductName".to_string(),
+            "Example App".to_string(),
+        )]));
+        metadata.apply_pe_metadata(HashMap::from([
+            ("CompanyName".to_string(), "Contoso".to_string()),
+            ("ExtractedVersion".to_string(), "2.1.0".to_string()),
+        ]));
+        metadata.apply_smart_defaults();
 
-        // Clean up common patterns from product name
-        let cleaned = product_name_base
-            .replace("Setup", "")
-            .replace("setup", "")
-            .replace("Installer", "")
-            .replace("installer", "")
-            .replace("-portable", "")
-            .replace("-x64", "")
-            .replace("-x86", "")
-            .replace("_x64", "")
-            .replace("_x86", "")
-            .replace("-win32", "")
-            .replace("-win64", "")
-            .replace("_", " ")
-            .replace("-", " ");
-
-        // Clean up multiple spaces and trim, but preserve dots in reasonable places
-        let final_name = cleaned
-            .split_whitespace()
-            .filter(|s| !s.is_empty())
-            .collect::<Vec<&str>>()
-            .join(" ")
-            .trim()
-            .to_string();
-
-        if !final_name.is_empty() {
-            result.product_name = Some(final_name);
-        } else if !version_found {
-            // If no version found and name is empty after cleaning, use original
-            result.product_name = Some(name_without_ext.to_string());
-        }
+        let source = |field| metadata.provenance.get(field).map(|p| p.source);
+        assert_eq!(
+            source(MetadataField::ProductName),
+            Some(MetadataSource::InstallerData)
+        );
+        assert_eq!(
+            source(MetadataField::ProductVersion),
+            Some(MetadataSource::ContentScan)
+        );
+        assert_eq!(
+            metadata.provenance.manufacturer.map(|p| p.confidence),
+            Some(MetadataConfidence::Medium)
+        );
+        assert_eq!(
+            metadata.provenance.low_confidence_fields(),
+            vec![MetadataField::ProductVersion]
+        );
+    }
 
-        // Try to detect architecture
-        if filename.contains("x64") || filename.contains("64") {
-            result.architecture = Some("x64".to_string());
-        } else if filename.contains("x86") || filename.contains("32") {
-            result.architecture = Some("x86".to_string());
-        }
+    #[test]
+    fn test_filename_and_placeholder_provenance() {
+        let mut metadata = EnhancedMetadata::default();
+        metadata.apply_filename_metadata(Path::new("ExampleApp-1.2.3-setup.exe"));
+        metadata.apply_smart_defaults();
 
-        result
+        assert_eq!(metadata.product_version.as_deref(), Some("1.2.3"));
+        assert_eq!(
+            metadata.provenance.product_version.map(|p| p.source),
+            Some(MetadataSource::FilenameHeuristic)
+        );
+        assert_eq!(metadata.manufacturer.as_deref(), Some("Unknown Publisher"));
+        assert_eq!(
+            metadata.provenance.manufacturer.map(|p| p.source),
+            Some(MetadataSource::Placeholder)
+        );
+        assert_eq!(metadata.provenance.low_confidence_fields().len(), 3);
     }
 }
-
-/// Parsed filename information
-#[derive(Debug, Default)]
-pub struct ParsedFilename {
-    pub product_name: Option<String>,
-    pub version: Option<String>,
-    pub company: Option<String>,
-    pub architecture: Option<String>,
-}
@@ -0,0 +1,248 @@
+//! Single-pass search for many byte patterns in a file
+//!
+//! Detectors look for dozens of marker strings in the same installer. A
+//! [`PatternSet`] compiles them into one Aho-Corasick automaton and streams
+//! the file through it once, in fixed-size chunks, instead of rescanning the
+//! content for every pattern. Matches spanning a chunk boundary are found by
+//! carrying the tail of each chunk over to the next one.
+
+use crate::core::{AnalyzerError, Result};
+use aho_corasick::AhoCorasick;
+use std::io::Read;
+use std::path::Path;
+
+/// Bytes read from the file at a time
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Offsets recorded per pattern, further matches are only counted
+pub const MAX_OFFSETS_PER_PATTERN: usize = 64;
+
+/// Patterns compiled for searching together
+#[derive(Debug, Clone)]
+pub struct PatternSet {
+    patterns: Vec<String>,
+    automaton: AhoCorasick,
+    /// Stop at the first match of every pattern
+    first_matches_only: bool,
+}
+
+impl PatternSet {
+    /// Compile `patterns`, matched case-sensitively as raw bytes
+    ///
+    /// Empty patterns are ignored, they would match at every offset.
+    pub fn new<I, P>(patterns: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<str>,
+    {
+        let mut unique: Vec<String> = Vec::new();
+        for pattern in patterns {
+            let pattern = pattern.as_ref();
+            if !pattern.is_empty() && !unique.iter().any(|known| known == pattern) {
+                unique.push(pattern.to_string());
+            }
+        }
+        let automaton = AhoCorasick::new(&unique).map_err(|e| {
+            AnalyzerError::generic(format!("Failed to compile search patterns: {}", e))
+        })?;
+
+        Ok(Self {
+            patterns: unique,
+            automaton,
+            first_matches_only: false,
+        })
+    }
+
+    /// Only record the first match of each pattern, and stop reading once
+    /// every pattern was found
+    pub fn first_matches_only(mut self) -> Self {
+        self.first_matches_only = true;
+        self
+    }
+
+    /// Compiled patterns, in the order they were given
+    pub fn patterns(&self) -> &[String] {
+        &self.patterns
+    }
+
+    /// Search the content of `file_path`
+    pub async fn scan_file(&self, file_path: &Path) -> Result<PatternMatches> {
+        let set = self.clone();
+        let file_path = file_path.to_path_buf();
+        tokio::task::spawn_blocking(move || {
+            let file = std::fs::File::open(&file_path)?;
+            Ok(set.scan_reader(file)?)
+        })
+        .await
+        .map_err(|e| AnalyzerError::generic(format!("Pattern search task failed: {}", e)))?
+    }
+
+    /// Search everything `reader` yields
+    pub fn scan_reader(&self, mut reader: impl Read) -> std::io::Result<PatternMatches> {
+        let mut matches = PatternMatches::new(&self.patterns);
+        if self.patterns.is_empty() {
+            return Ok(matches);
+        }
+        let carry_size = self.patterns.iter().map(String::len).max().unwrap_or(1) - 1;
+
+        let mut buffer: Vec<u8> = Vec::with_capacity(carry_size + CHUNK_SIZE);
+        let mut chunk = vec![0u8; CHUNK_SIZE];
+        // Offset of the start of `buffer` in the content
+        let mut base = 0u64;
+        loop {
+            let bytes_read = match reader.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(bytes_read) => bytes_read,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            let carried = buffer.len();
+            buffer.extend_from_slice(&chunk[..bytes_read]);
+
+            for found in self.automaton.find_overlapping_iter(&buffer) {
+                let pattern = found.pattern().as_usize();
+                // Matches within the carried tail were seen with the last chunk
+                if found.end() <= carried
+                    || (self.first_matches_only && matches.counts[pattern] > 0)
+                {
+                    continue;
+                }
+                matches.record(pattern, base + found.start() as u64);
+            }
+            if self.first_matches_only && matches.all_found() {
+                break;
+            }
+
+            let keep = buffer.len().min(carry_size);
+            base += (buffer.len() - keep) as u64;
+            buffer.drain(..buffer.len() - keep);
+        }
+
+        Ok(matches)
+    }
+}
+
+/// Matches of a [`PatternSet`] in some content
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PatternMatches {
+    patterns: Vec<String>,
+    /// Offsets of the first matches, per pattern
+    offsets: Vec<Vec<u64>>,
+    /// Number of matches, per pattern
+    counts: Vec<usize>,
+}
+
+impl PatternMatches {
+    fn new(patterns: &[String]) -> Self {
+        Self {
+            patterns: patterns.to_vec(),
+            offsets: vec![Vec::new(); patterns.len()],
+            counts: vec![0; patterns.len()],
+        }
+    }
+
+    fn record(&mut self, pattern: usize, offset: u64) {
+        self.counts[pattern] += 1;
+        let offsets = &mut self.offsets[pattern];
+        if offsets.len() < MAX_OFFSETS_PER_PATTERN {
+            offsets.push(offset);
+        }
+    }
+
+    fn all_found(&self) -> bool {
+        self.counts.iter().all(|&count| count > 0)
+    }
+
+    /// No pattern was found
+    pub fn is_empty(&self) -> bool {
+        self.counts.iter().all(|&count| count == 0)
+    }
+
+    /// `pattern` was part of the search
+    pub fn searched(&self, pattern: &str) -> bool {
+        self.index(pattern).is_some()
+    }
+
+    /// `pattern` was found at least once
+    pub fn contains(&self, pattern: &str) -> bool {
+        self.count(pattern) > 0
+    }
+
+    /// Number of matches of `pattern`, at most 1 for
+    /// [`PatternSet::first_matches_only`] sets
+    pub fn count(&self, pattern: &str) -> usize {
+        self.index(pattern).map_or(0, |index| self.counts[index])
+    }
+
+    /// Offsets of the first [`MAX_OFFSETS_PER_PATTERN`] matches of `pattern`
+    pub fn offsets(&self, pattern: &str) -> &[u64] {
+        self.index(pattern)
+            .map_or(&[], |index| self.offsets[index].as_slice())
+    }
+
+    /// Patterns that were found, in the order they were given
+    pub fn found(&self) -> Vec<String> {
+        self.patterns
+            .iter()
+            .zip(&self.counts)
+            .filter(|(_, &count)| count > 0)
+            .map(|(pattern, _)| pattern.clone())
+            .collect()
+    }
+
+    /// First of `candidates` that was found, with its associated value
+    pub fn first_of<T: Clone>(&self, candidates: &[(&str, T)]) -> Option<T> {
+        candidates
+            .iter()
+            .find(|(pattern, _)| self.contains(pattern))
+            .map(|(_, value)| value.clone())
+    }
+
+    fn index(&self, pattern: &str) -> Option<usize> {
+        self.patterns.iter().position(|known| known == pattern)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_across_chunk_boundaries() {
+        let set = PatternSet::new(["Nullsoft", "soft", "Inno Setup", "missing", ""]).unwrap();
+        assert_eq!(set.patterns().len(), 4);
+
+        let mut content = vec![0u8; CHUNK_SIZE - 4];
+        content.extend_from_slice(b"Nullsoft");
+        content.extend(vec![0xffu8; CHUNK_SIZE]);
+        content.extend_from_slice(b"Inno Setup, Nullsoft");
+
+        let matches = set.scan_reader(content.as_slice()).unwrap();
+        let first = (CHUNK_SIZE - 4) as u64;
+        let second = (2 * CHUNK_SIZE + 4) as u64;
+        assert_eq!(matches.offsets("Nullsoft"), &[first, second + 12]);
+        // Overlapping matches of other patterns are reported too
+        assert_eq!(matches.offsets("soft"), &[first + 4, second + 16]);
+        assert_eq!(matches.offsets("Inno Setup"), &[second]);
+        assert!(!matches.contains("missing"));
+        assert_eq!(matches.found(), vec!["Nullsoft", "soft", "Inno Setup"]);
+        assert_eq!(
+            matches.first_of(&[("missing", 1), ("Inno Setup", 2), ("soft", 3)]),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn test_first_matches_only() {
+        let set = PatternSet::new(["ab"]).unwrap().first_matches_only();
+        let content = b"ab".repeat(CHUNK_SIZE);
+        let matches = set.scan_reader(content.as_slice()).unwrap();
+        assert_eq!(matches.offsets("ab"), &[0]);
+        assert_eq!(matches.count("ab"), 1);
+
+        let set = PatternSet::new(["ab"]).unwrap();
+        let matches = set.scan_reader(content.as_slice()).unwrap();
+        assert_eq!(matches.count("ab"), CHUNK_SIZE);
+        assert_eq!(matches.offsets("ab").len(), MAX_OFFSETS_PER_PATTERN);
+    }
+}
@@ -0,0 +1,336 @@
+//! PKCS#7 signed data verification
+//!
+//! MSIX `AppxSignature.p7x` files and driver catalogs wrap their content in
+//! PKCS#7 signed data. The message digest attribute of the first signer is
+//! compared with the signed content and the RSA signature over the signed
+//! attributes is verified with the signing certificate carried in the data.
+//! The certificate is not validated against a trust store.
+
+use cms::cert::CertificateChoices;
+use cms::content_info::ContentInfo;
+use cms::signed_data::{SignedData, SignerIdentifier, SignerInfo};
+use der::asn1::{ObjectIdentifier, OctetString};
+use der::{Decode, Encode};
+use rsa::pkcs8::DecodePublicKey;
+use rsa::{Pkcs1v15Sign, RsaPublicKey};
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use x509_cert::Certificate;
+
+const OID_SIGNED_DATA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.7.2");
+const OID_MESSAGE_DIGEST: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.4");
+const OID_RSA_ENCRYPTION: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.1");
+const OID_SHA1: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.14.3.2.26");
+const OID_SHA256: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.1");
+const OID_SHA384: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.2");
+const OID_SHA512: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.3");
+
+/// DER `DigestInfo` prefix of SHA-1 PKCS#1 v1.5 signatures
+const SHA1_DIGEST_INFO_PREFIX: [u8; 15] = [
+    0x30, 0x21, 0x30, 0x09, 0x06, 0x05, 0x2b, 0x0e, 0x03, 0x02, 0x1a, 0x05, 0x00, 0x04, 0x14,
+];
+
+/// Outcome of one integrity check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntegrityStatus {
+    /// The data matches what the package claims
+    Valid,
+    /// The data does not match: the package was modified or is corrupt
+    Invalid,
+    /// The check uses algorithms that are not supported
+    Unverified,
+    /// The package carries no such integrity data
+    Missing,
+}
+
+impl IntegrityStatus {
+    /// Value used in analysis properties
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Valid => "valid",
+            Self::Invalid => "invalid",
+            Self::Unverified => "unverified",
+            Self::Missing => "missing",
+        }
+    }
+}
+
+impl std::fmt::Display for IntegrityStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Why signed data could not be verified
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureError {
+    /// The structure is broken or incomplete
+    Malformed(String),
+    /// The signature uses algorithms that are not supported
+    Unsupported(String),
+}
+
+impl SignatureError {
+    /// Status of a check stopped by this error
+    pub fn status(&self) -> IntegrityStatus {
+        match self {
+            Self::Malformed(_) => IntegrityStatus::Invalid,
+            Self::Unsupported(_) => IntegrityStatus::Unverified,
+        }
+    }
+
+    /// Problem description for the signature file `name`
+    pub fn describe(&self, name: &str) -> String {
+        match self {
+            Self::Malformed(detail) => format!("{} is malformed: {}", name, detail),
+            Self::Unsupported(message) => message.clone(),
+        }
+    }
+}
+
+/// Digest algorithms of signatures and the data they cover
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DigestAlgorithm {
+    Sha1,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl DigestAlgorithm {
+    pub fn from_oid(oid: &ObjectIdentifier) -> Option<Self> {
+        match *oid {
+            OID_SHA1 => Some(Self::Sha1),
+            OID_SHA256 => Some(Self::Sha256),
+            OID_SHA384 => Some(Self::Sha384),
+            OID_SHA512 => Some(Self::Sha512),
+            _ => None,
+        }
+    }
+
+    pub fn output_len(&self) -> usize {
+        match self {
+            Self::Sha1 => 20,
+            Self::Sha256 => 32,
+            Self::Sha384 => 48,
+            Self::Sha512 => 64,
+        }
+    }
+
+    pub fn hasher(&self) -> Hasher {
+        match self {
+            Self::Sha1 => Hasher::Sha1(Sha1::new()),
+            Self::Sha256 => Hasher::Sha256(Sha256::new()),
+            Self::Sha384 => Hasher::Sha384(Sha384::new()),
+            Self::Sha512 => Hasher::Sha512(Sha512::new()),
+        }
+    }
+
+    pub fn digest(&self, data: &[u8]) -> Vec<u8> {
+        let mut hasher = self.hasher();
+        hasher.update(data);
+        hasher.finalize()
+    }
+
+    /// PKCS#1 v1.5 padding scheme for signatures over this digest
+    fn pkcs1v15(&self) -> Pkcs1v15Sign {
+        match self {
+            Self::Sha1 => Pkcs1v15Sign {
+                hash_len: Some(20),
+                prefix: SHA1_DIGEST_INFO_PREFIX.into(),
+            },
+            Self::Sha256 => Pkcs1v15Sign::new::<Sha256>(),
+            Self::Sha384 => Pkcs1v15Sign::new::<Sha384>(),
+            Self::Sha512 => Pkcs1v15Sign::new::<Sha512>(),
+        }
+    }
+}
+
+/// Incremental hasher for any [`DigestAlgorithm`]
+pub enum Hasher {
+    Sha1(Sha1),
+    Sha256(Sha256),
+    Sha384(Sha384),
+    Sha512(Sha512),
+}
+
+impl Hasher {
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Sha1(hasher) => hasher.update(data),
+            Self::Sha256(hasher) => hasher.update(data),
+            Self::Sha384(hasher) => hasher.update(data),
+            Self::Sha512(hasher) => hasher.update(data),
+        }
+    }
+
+    pub fn finalize(self) -> Vec<u8> {
+        match self {
+            Self::Sha1(hasher) => hasher.finalize().to_vec(),
+            Self::Sha256(hasher) => hasher.finalize().to_vec(),
+            Self::Sha384(hasher) => hasher.finalize().to_vec(),
+            Self::Sha512(hasher) => hasher.finalize().to_vec(),
+        }
+    }
+}
+
+/// Signed data with its content and first signer
+pub struct SignedMessage {
+    signed_data: SignedData,
+    /// Type of the signed content
+    pub content_type: ObjectIdentifier,
+    /// Signed content, without the tag and length of its outer element
+    pub content: Vec<u8>,
+    /// Digest algorithm of the first signer
+    pub algorithm: DigestAlgorithm,
+}
+
+impl SignedMessage {
+    /// Parse DER-encoded PKCS#7 signed data
+    pub fn parse(der_data: &[u8]) -> std::result::Result<Self, SignatureError> {
+        let malformed = SignatureError::Malformed;
+
+        let content_info = ContentInfo::from_der(der_data).map_err(|e| malformed(e.to_string()))?;
+        if content_info.content_type != OID_SIGNED_DATA {
+            return Err(malformed("not a PKCS#7 signed data structure".to_string()));
+        }
+        let signed_data = content_info
+            .content
+            .to_der()
+            .and_then(|content| SignedData::from_der(&content))
+            .map_err(|e| malformed(e.to_string()))?;
+
+        let content_type = signed_data.encap_content_info.econtent_type;
+        let content = signed_data
+            .encap_content_info
+            .econtent
+            .as_ref()
+            .ok_or_else(|| malformed("no signed content".to_string()))?
+            .value()
+            .to_vec();
+
+        let signer_info = signed_data
+            .signer_infos
+            .0
+            .iter()
+            .next()
+            .ok_or_else(|| malformed("no signer".to_string()))?;
+        let algorithm =
+            DigestAlgorithm::from_oid(&signer_info.digest_alg.oid).ok_or_else(|| {
+                SignatureError::Unsupported(format!(
+                    "Unsupported signature digest algorithm {}",
+                    signer_info.digest_alg.oid
+                ))
+            })?;
+
+        Ok(Self {
+            signed_data,
+            content_type,
+            content,
+            algorithm,
+        })
+    }
+
+    fn signer_info(&self) -> &SignerInfo {
+        // Checked to exist when parsing
+        self.signed_data
+            .signer_infos
+            .0
+            .iter()
+            .next()
+            .expect("signed data without signer")
+    }
+
+    /// Subject of the signing certificate
+    pub fn signer(&self) -> Option<String> {
+        self.signer_certificate()
+            .map(|certificate| certificate.tbs_certificate.subject.to_string())
+    }
+
+    /// SHA-1 thumbprint of the signing certificate, in upper case hex as Windows shows it
+    pub fn signer_thumbprint(&self) -> Option<String> {
+        let der = self.signer_certificate()?.to_der().ok()?;
+        Some(hex::encode_upper(Sha1::digest(der)))
+    }
+
+    /// Verify the message digest and the RSA signature
+    ///
+    /// Mismatches are collected in `problems`; an `Err` means the signature
+    /// cannot be evaluated.
+    pub fn verify(&self, problems: &mut Vec<String>) -> std::result::Result<(), SignatureError> {
+        let malformed = |detail: &str| SignatureError::Malformed(detail.to_string());
+        let signer_info = self.signer_info();
+
+        // The signed attributes carry the digest of the signed content
+        let signed_attrs = signer_info
+            .signed_attrs
+            .as_ref()
+            .ok_or_else(|| malformed("no signed attributes"))?;
+        let message_digest = signed_attrs
+            .iter()
+            .find(|attr| attr.oid == OID_MESSAGE_DIGEST)
+            .and_then(|attr| attr.values.iter().next())
+            .and_then(|value| value.to_der().ok())
+            .and_then(|value| OctetString::from_der(&value).ok())
+            .ok_or_else(|| malformed("no message digest attribute"))?;
+        if message_digest.as_bytes() != self.algorithm.digest(&self.content) {
+            problems.push("Signed content does not match the signature message digest".to_string());
+        }
+
+        let certificate = self
+            .signer_certificate()
+            .ok_or_else(|| malformed("signing certificate not included"))?;
+        let public_key_info = &certificate.tbs_certificate.subject_public_key_info;
+        if public_key_info.algorithm.oid != OID_RSA_ENCRYPTION {
+            return Err(SignatureError::Unsupported(format!(
+                "Unsupported signing key algorithm {}",
+                public_key_info.algorithm.oid
+            )));
+        }
+        let public_key = public_key_info
+            .to_der()
+            .ok()
+            .and_then(|spki| RsaPublicKey::from_public_key_der(&spki).ok())
+            .ok_or_else(|| malformed("invalid RSA public key"))?;
+        let signed_attrs_der = signed_attrs
+            .to_der()
+            .map_err(|e| SignatureError::Malformed(e.to_string()))?;
+        if public_key
+            .verify(
+                self.algorithm.pkcs1v15(),
+                &self.algorithm.digest(&signed_attrs_der),
+                signer_info.signature.as_bytes(),
+            )
+            .is_err()
+        {
+            problems
+                .push("RSA signature does not verify against the signing certificate".to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Certificate referenced by the signer identifier
+    fn signer_certificate(&self) -> Option<&Certificate> {
+        let SignerIdentifier::IssuerAndSerialNumber(issuer_and_serial) = &self.signer_info().sid
+        else {
+            return None;
+        };
+
+        self.signed_data
+            .certificates
+            .as_ref()?
+            .0
+            .iter()
+            .find_map(|choice| match choice {
+                CertificateChoices::Certificate(certificate)
+                    if certificate.tbs_certificate.issuer == issuer_and_serial.issuer
+                        && certificate.tbs_certificate.serial_number
+                            == issuer_and_serial.serial_number =>
+                {
+                    Some(certificate)
+                }
+                _ => None,
+            })
+    }
+}
@@ -0,0 +1,225 @@
+//! Content scan of an installer and its payloads shared by detectors
+//!
+//! Several detectors search the bytes of the installer and of its packaged
+//! binaries and scripts. Instead of each reading every source again, the
+//! installer and each payload are read once here and handed to every
+//! [`SourceScanner`] that wants them.
+
+use crate::analyzers::InstallerAnalyzer;
+use crate::core::{FileEntry, Result};
+use crate::utils::progress::{self, Unit};
+use std::path::Path;
+
+/// Source name of the installer itself
+pub const INSTALLER_SOURCE: &str = "installer";
+
+/// Largest source that is scanned
+pub const MAX_SOURCE_SIZE: u64 = 256 * 1024 * 1024;
+/// Default upper bound of payloads fed to a scanner
+pub const MAX_PAYLOADS: usize = 200;
+
+/// Detector fed with the content of the installer and its payloads
+pub trait SourceScanner: Send {
+    /// Whether the packaged file at `path` should be scanned
+    fn wants(&self, path: &Path) -> bool;
+
+    /// Upper bound of payloads fed to the scanner
+    fn max_payloads(&self) -> usize {
+        MAX_PAYLOADS
+    }
+
+    /// Scan the content of one source, `installer` or a packaged file path
+    fn add_source(&mut self, source: &str, data: &[u8]);
+}
+
+/// Feed the installer file to every scanner
+pub async fn scan_installer(
+    file_path: &Path,
+    scanners: &mut [&mut dyn SourceScanner],
+) -> Result<()> {
+    let size = tokio::fs::metadata(file_path).await?.len();
+    if size > MAX_SOURCE_SIZE {
+        tracing::info!("Skipping content scan of {} bytes installer", size);
+        return Ok(());
+    }
+    let data = tokio::fs::read(file_path).await?;
+    for scanner in scanners.iter_mut() {
+        scanner.add_source(INSTALLER_SOURCE, &data);
+    }
+    Ok(())
+}
+
+/// Feed the installer and the readable payloads each scanner wants
+///
+/// Every source is read at most once, whatever the number of scanners.
+pub async fn scan_sources(
+    analyzer: &dyn InstallerAnalyzer,
+    file_path: &Path,
+    files: &[FileEntry],
+    scanners: &mut [&mut dyn SourceScanner],
+) -> Result<()> {
+    scan_installer(file_path, scanners).await?;
+
+    // Payloads in listing order, with the scanners that still take them
+    let mut fed = vec![0usize; scanners.len()];
+    let mut payloads: Vec<(&FileEntry, Vec<usize>)> = Vec::new();
+    for file in files
+        .iter()
+        .filter(|file| !file.is_speculative && file.size <= MAX_SOURCE_SIZE)
+    {
+        let wanted: Vec<usize> = (0..scanners.len())
+            .filter(|&i| fed[i] < scanners[i].max_payloads() && scanners[i].wants(&file.path))
+            .collect();
+        if wanted.is_empty() {
+            continue;
+        }
+        for &i in &wanted {
+            fed[i] += 1;
+        }
+        payloads.push((file, wanted));
+    }
+
+    progress::begin(Unit::Files, payloads.len() as u64);
+    for (file, wanted) in payloads {
+        match analyzer.read_file_content(file_path, &file.path).await {
+            Ok(Some(content)) => {
+                let source = file.path.to_string_lossy().replace('\\', "/");
+                for i in wanted {
+                    scanners[i].add_source(&source, &content);
+                }
+            }
+            Ok(None) => {}
+            Err(e) => tracing::debug!("Failed to read {}: {}", file.path.display(), e),
+        }
+        progress::advance(1);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{FileAttributes, InstallerFormat, InstallerMetadata, RegistryOperation};
+    use async_trait::async_trait;
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Scanner recording the sources it was given
+    struct Recorder {
+        extension: &'static str,
+        limit: usize,
+        sources: Vec<String>,
+    }
+
+    impl SourceScanner for Recorder {
+        fn wants(&self, path: &Path) -> bool {
+            path.extension().is_some_and(|ext| ext == self.extension)
+        }
+
+        fn max_payloads(&self) -> usize {
+            self.limit
+        }
+
+        fn add_source(&mut self, source: &str, _data: &[u8]) {
+            self.sources.push(source.to_string());
+        }
+    }
+
+    /// Analyzer serving every packaged file and counting the reads
+    #[derive(Default)]
+    struct Payloads {
+        reads: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl InstallerAnalyzer for Payloads {
+        async fn can_analyze(&self, _file_path: &Path) -> Result<bool> {
+            Ok(true)
+        }
+
+        fn format(&self) -> InstallerFormat {
+            InstallerFormat::Unknown
+        }
+
+        async fn extract_metadata(&self, _file_path: &Path) -> Result<InstallerMetadata> {
+            Ok(InstallerMetadata::default())
+        }
+
+        async fn extract_files(&self, _file_path: &Path) -> Result<Vec<FileEntry>> {
+            Ok(Vec::new())
+        }
+
+        async fn extract_registry_operations(
+            &self,
+            _file_path: &Path,
+        ) -> Result<Vec<RegistryOperation>> {
+            Ok(Vec::new())
+        }
+
+        async fn read_file_content(
+            &self,
+            _file_path: &Path,
+            entry_path: &Path,
+        ) -> Result<Option<Vec<u8>>> {
+            self.reads.fetch_add(1, Ordering::Relaxed);
+            Ok(Some(entry_path.to_string_lossy().into_owned().into_bytes()))
+        }
+    }
+
+    fn entry(path: &str) -> FileEntry {
+        FileEntry {
+            path: PathBuf::from(path),
+            target_path: None,
+            size: 1,
+            hash: None,
+            attributes: FileAttributes::default(),
+            compression: None,
+            is_speculative: false,
+            architecture: None,
+            modified: None,
+            source: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scan_sources_reads_each_source_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let installer = dir.path().join("setup.exe");
+        std::fs::write(&installer, b"MZ").unwrap();
+        let files = [
+            entry("bin/a.exe"),
+            entry("bin/b.exe"),
+            entry("scripts/run.ps1"),
+            entry("readme.txt"),
+        ];
+        let analyzer = Payloads::default();
+        let mut binaries = Recorder {
+            extension: "exe",
+            limit: MAX_PAYLOADS,
+            sources: Vec::new(),
+        };
+        let mut first_binary = Recorder {
+            extension: "exe",
+            limit: 1,
+            sources: Vec::new(),
+        };
+        let mut scripts = Recorder {
+            extension: "ps1",
+            limit: MAX_PAYLOADS,
+            sources: Vec::new(),
+        };
+
+        scan_sources(
+            &analyzer,
+            &installer,
+            &files,
+            &mut [&mut binaries, &mut first_binary, &mut scripts],
+        )
+        .await
+        .unwrap();
+        assert_eq!(analyzer.reads.load(Ordering::Relaxed), 3);
+        assert_eq!(binaries.sources, ["installer", "bin/a.exe", "bin/b.exe"]);
+        assert_eq!(first_binary.sources, ["installer", "bin/a.exe"]);
+        assert_eq!(scripts.sources, ["installer", "scripts/run.ps1"]);
+    }
+}
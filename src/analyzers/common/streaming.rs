@@ -0,0 +1,150 @@
+//! Async readers over packaged files
+//!
+//! Archive readers such as the `zip` crate are synchronous and borrow the
+//! archive while an entry is read, so an entry cannot be handed out as a
+//! reader on its own. [`spawn_reader`] runs the synchronous read on a
+//! blocking thread instead and passes the content through a bounded channel:
+//! the entry is decompressed as the caller reads, at most a few chunks
+//! ahead, and reading stops when the caller drops the reader.
+
+use std::io::{self, BufWriter, Write};
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+use tokio::io::{AsyncRead, ReadBuf};
+use tokio::sync::mpsc;
+
+/// Size of the chunks passed to the reader
+const CHUNK_SIZE: usize = 64 * 1024;
+/// Chunks buffered ahead of the reader
+const CHUNKS_AHEAD: usize = 4;
+
+/// Reader over the content of a file packaged in an installer
+pub type EntryReader = Pin<Box<dyn AsyncRead + Send>>;
+
+/// Reader over content already in memory
+pub fn buffered_reader(content: Vec<u8>) -> EntryReader {
+    Box::pin(io::Cursor::new(content))
+}
+
+/// Reader over the content `produce` writes on a blocking thread
+///
+/// An error returned by `produce` is returned by the read that reaches it.
+pub fn spawn_reader<F>(produce: F) -> EntryReader
+where
+    F: FnOnce(&mut dyn Write) -> io::Result<()> + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel(CHUNKS_AHEAD);
+    tokio::task::spawn_blocking(move || {
+        let mut writer = BufWriter::with_capacity(
+            CHUNK_SIZE,
+            ChunkWriter {
+                sender: sender.clone(),
+            },
+        );
+        let written = produce(&mut writer).and_then(|_| writer.flush());
+        // Content buffered before a failure is not passed on
+        drop(writer.into_parts());
+        match written {
+            // A dropped reader closes the channel, which is not an error
+            Err(e) if e.kind() != io::ErrorKind::BrokenPipe => {
+                let _ = sender.blocking_send(Err(e));
+            }
+            _ => {}
+        }
+    });
+    Box::pin(ChunkReader {
+        receiver,
+        chunk: Vec::new(),
+        position: 0,
+    })
+}
+
+/// Sending half, one chunk per write
+struct ChunkWriter {
+    sender: mpsc::Sender<io::Result<Vec<u8>>>,
+}
+
+impl Write for ChunkWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.sender
+            .blocking_send(Ok(buf.to_vec()))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "Reader was dropped"))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Receiving half, handing out the chunks in order
+struct ChunkReader {
+    receiver: mpsc::Receiver<io::Result<Vec<u8>>>,
+    chunk: Vec<u8>,
+    position: usize,
+}
+
+impl AsyncRead for ChunkReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        loop {
+            if this.position < this.chunk.len() {
+                let available = &this.chunk[this.position..];
+                let count = available.len().min(buf.remaining());
+                buf.put_slice(&available[..count]);
+                this.position += count;
+                return Poll::Ready(Ok(()));
+            }
+            match ready!(this.receiver.poll_recv(cx)) {
+                Some(Ok(chunk)) => {
+                    this.chunk = chunk;
+                    this.position = 0;
+                }
+                Some(Err(e)) => return Poll::Ready(Err(e)),
+                // Producer finished
+                None => return Poll::Ready(Ok(())),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+
+    #[tokio::test]
+    async fn test_spawn_reader() {
+        let content: Vec<u8> = (0..300_000u32).map(|i| i as u8).collect();
+        let expected = content.clone();
+        let mut reader = spawn_reader(move |writer| writer.write_all(&content));
+        let mut read = Vec::new();
+        reader.read_to_end(&mut read).await.unwrap();
+        assert_eq!(read, expected);
+
+        let mut failing = spawn_reader(|writer| {
+            writer.write_all(b"partial")?;
+            Err(io::Error::new(io::ErrorKind::InvalidData, "corrupt entry"))
+        });
+        let error = failing.read_to_end(&mut Vec::new()).await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_reader_stops_producer() {
+        let (done, finished) = tokio::sync::oneshot::channel();
+        let mut reader = spawn_reader(move |writer| {
+            let result = (0..1000).try_for_each(|_| writer.write_all(&[0; CHUNK_SIZE]));
+            let _ = done.send(result.is_err());
+            result
+        });
+        let mut first = [0; 16];
+        reader.read_exact(&mut first).await.unwrap();
+        drop(reader);
+        assert!(finished.await.unwrap());
+    }
+}
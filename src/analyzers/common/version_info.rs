@@ -0,0 +1,145 @@
+//! String values of PE version resources
+//!
+//! The `StringFileInfo` block of a version resource holds `String`
+//! structures: a length, the length of the value in UTF-16 units, a type of
+//! 1 for text, the NUL-terminated UTF-16 key, padding to a 32-bit boundary
+//! and the NUL-terminated UTF-16 value. Setup stubs built by installer
+//! authoring tools copy the product name, version and company of the
+//! project into it.
+
+/// Keys read by [`VersionStrings::read`]
+const KEYS: [&str; 5] = [
+    "ProductName",
+    "ProductVersion",
+    "FileVersion",
+    "CompanyName",
+    "FileDescription",
+];
+
+/// Version resource strings found in a PE image
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VersionStrings {
+    pub product_name: Option<String>,
+    pub product_version: Option<String>,
+    pub file_version: Option<String>,
+    pub company_name: Option<String>,
+    pub file_description: Option<String>,
+}
+
+impl VersionStrings {
+    /// Read the well-known keys from the image in `data`
+    pub fn read(data: &[u8]) -> Self {
+        let [product_name, product_version, file_version, company_name, file_description] =
+            KEYS.map(|key| version_string(data, key));
+        Self {
+            product_name,
+            product_version,
+            file_version,
+            company_name,
+            file_description,
+        }
+    }
+
+    /// Product version, or the file version when the product has none
+    pub fn version(&self) -> Option<&str> {
+        self.product_version
+            .as_deref()
+            .or(self.file_version.as_deref())
+    }
+}
+
+/// Value of the first `String` structure with `key` in `data`
+///
+/// Occurrences of the key that are not preceded by a text `String` header
+/// with a value, e.g. in code or other resources, are skipped.
+pub fn version_string(data: &[u8], key: &str) -> Option<String> {
+    let mut needle: Vec<u8> = key.encode_utf16().flat_map(u16::to_le_bytes).collect();
+    needle.extend([0, 0]);
+
+    let mut offset = 0;
+    while let Some(found) = data
+        .get(offset..)?
+        .windows(needle.len())
+        .position(|window| window == needle)
+    {
+        let key_start = offset + found;
+        offset = key_start + 1;
+        if key_start < 6 || read_u16(data, key_start - 2) != 1 {
+            continue;
+        }
+        let value_units = read_u16(data, key_start - 4) as usize;
+        if value_units == 0 {
+            continue;
+        }
+
+        // The value is aligned to 32 bits from the start of the structure
+        let header_start = key_start - 6;
+        let value_start =
+            header_start + (key_start + needle.len() - header_start).next_multiple_of(4);
+        let Some(units) = data.get(value_start..value_start + value_units * 2) else {
+            continue;
+        };
+        let value: Vec<u16> = units
+            .chunks_exact(2)
+            .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+            .take_while(|&unit| unit != 0)
+            .collect();
+        let value = String::from_utf16_lossy(&value).trim().to_string();
+        if !value.is_empty() {
+            return Some(value);
+        }
+    }
+    None
+}
+
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// `String` structure of a version resource, padded to 32 bits
+    pub(crate) fn version_entry(key: &str, value: &str) -> Vec<u8> {
+        let utf16 = |text: &str| -> Vec<u8> {
+            text.encode_utf16()
+                .chain([0])
+                .flat_map(u16::to_le_bytes)
+                .collect()
+        };
+        let mut entry = vec![0, 0];
+        entry.extend((value.encode_utf16().count() as u16 + 1).to_le_bytes());
+        entry.extend(1u16.to_le_bytes());
+        entry.extend(utf16(key));
+        entry.resize(entry.len().next_multiple_of(4), 0);
+        entry.extend(utf16(value));
+        entry.resize(entry.len().next_multiple_of(4), 0);
+        let length = entry.len() as u16;
+        entry[..2].copy_from_slice(&length.to_le_bytes());
+        entry
+    }
+
+    #[test]
+    fn test_read_version_strings() {
+        let mut data = b"MZ".to_vec();
+        data.extend([0; 2]);
+        // A bare key in other data is not a version string
+        data.extend(
+            "ProductName\0"
+                .encode_utf16()
+                .flat_map(u16::to_le_bytes)
+                .collect::<Vec<u8>>(),
+        );
+        data.extend(version_entry("CompanyName", "Contoso Ltd."));
+        data.extend(version_entry("ProductName", "Contoso Tools"));
+        data.extend(version_entry("FileVersion", "4.2.0.17"));
+
+        let strings = VersionStrings::read(&data);
+        assert_eq!(strings.product_name.as_deref(), Some("Contoso Tools"));
+        assert_eq!(strings.company_name.as_deref(), Some("Contoso Ltd."));
+        assert_eq!(strings.product_version, None);
+        assert_eq!(strings.version(), Some("4.2.0.17"));
+        assert_eq!(version_string(&data, "FileDescription"), None);
+    }
+}
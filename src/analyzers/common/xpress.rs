@@ -0,0 +1,214 @@
+//! XPRESS Huffman decoder for WIM resources
+//!
+//! WIM images captured with fast compression store every chunk of a
+//! resource as an XPRESS Huffman block: a table of 512 4-bit code lengths
+//! followed by a bitstream of 16-bit little-endian words. Literals are
+//! symbols below 256, the others encode a match length and the bit length
+//! of its offset. Chunks are independent, so each is decoded on its own.
+
+use crate::core::{AnalyzerError, Result};
+
+const NUM_SYMBOLS: usize = 512;
+const MAX_CODE_LENGTH: u32 = 15;
+const MIN_MATCH_LENGTH: usize = 3;
+
+/// Decompress one chunk that expands to `output_size` bytes
+pub fn decompress(data: &[u8], output_size: usize) -> Result<Vec<u8>> {
+    let invalid =
+        |what: &str| AnalyzerError::parse_error(format!("Invalid XPRESS chunk: {}", what));
+    let (table, stream) = data
+        .split_first_chunk::<{ NUM_SYMBOLS / 2 }>()
+        .ok_or_else(|| invalid("missing code lengths"))?;
+    let mut lengths = [0u8; NUM_SYMBOLS];
+    for (i, byte) in table.iter().enumerate() {
+        lengths[2 * i] = byte & 0x0f;
+        lengths[2 * i + 1] = byte >> 4;
+    }
+    let code = HuffmanCode::new(&lengths).ok_or_else(|| invalid("empty Huffman code"))?;
+
+    let mut input = BitReader::new(stream);
+    let mut output = Vec::with_capacity(output_size);
+    while output.len() < output_size {
+        let symbol = code
+            .decode(&mut input)
+            .ok_or_else(|| invalid("bad Huffman code"))?;
+        if symbol < 256 {
+            output.push(symbol as u8);
+            continue;
+        }
+
+        let symbol = symbol - 256;
+        let mut length = symbol & 0x0f;
+        let offset_bits = (symbol >> 4) as u32;
+        input.ensure(16);
+        let offset = (1usize << offset_bits) | input.pop(offset_bits) as usize;
+        if length == 0x0f {
+            length += input.read_byte().ok_or_else(|| invalid("truncated"))? as usize;
+            if length == 0x0f + 0xff {
+                length = input.read_u16().ok_or_else(|| invalid("truncated"))? as usize;
+            }
+        }
+        length += MIN_MATCH_LENGTH;
+
+        if offset > output.len() {
+            return Err(invalid("match before start"));
+        }
+        let start = output.len() - offset;
+        for i in 0..length.min(output_size - output.len()) {
+            let byte = output[start + i];
+            output.push(byte);
+        }
+    }
+
+    Ok(output)
+}
+
+/// Canonical Huffman code, decoded one length at a time
+struct HuffmanCode {
+    /// Number of codes of each length
+    counts: [u16; MAX_CODE_LENGTH as usize + 1],
+    /// First code of each length
+    first: [u32; MAX_CODE_LENGTH as usize + 1],
+    /// Index in `symbols` of the first symbol of each length
+    start: [u16; MAX_CODE_LENGTH as usize + 1],
+    /// Symbols ordered by code length, then value
+    symbols: Vec<u16>,
+}
+
+impl HuffmanCode {
+    fn new(lengths: &[u8]) -> Option<Self> {
+        let mut counts = [0u16; MAX_CODE_LENGTH as usize + 1];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+
+        let mut first = [0u32; MAX_CODE_LENGTH as usize + 1];
+        let mut start = [0u16; MAX_CODE_LENGTH as usize + 1];
+        let mut code = 0u32;
+        let mut index = 0u16;
+        for length in 1..=MAX_CODE_LENGTH as usize {
+            code = (code + counts[length - 1] as u32) << 1;
+            first[length] = code;
+            start[length] = index;
+            index += counts[length];
+        }
+        if index == 0 {
+            return None;
+        }
+
+        let mut symbols = Vec::with_capacity(index as usize);
+        for length in 1..=MAX_CODE_LENGTH as u8 {
+            symbols.extend(
+                lengths
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, &l)| l == length)
+                    .map(|(symbol, _)| symbol as u16),
+            );
+        }
+
+        Some(Self {
+            counts,
+            first,
+            start,
+            symbols,
+        })
+    }
+
+    fn decode(&self, input: &mut BitReader) -> Option<usize> {
+        input.ensure(16);
+        let bits = input.peek(MAX_CODE_LENGTH);
+        for length in 1..=MAX_CODE_LENGTH {
+            let code = bits >> (MAX_CODE_LENGTH - length);
+            let index = code.wrapping_sub(self.first[length as usize]);
+            if index < self.counts[length as usize] as u32 {
+                input.pop(length);
+                let position = self.start[length as usize] as usize + index as usize;
+                return Some(self.symbols[position] as usize);
+            }
+        }
+        None
+    }
+}
+
+/// Most significant bit first reader over 16-bit little-endian words
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    buffer: u32,
+    bits: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            position: 0,
+            buffer: 0,
+            bits: 0,
+        }
+    }
+
+    /// Make at least `count` bits available, up to 16; zeros past the end
+    fn ensure(&mut self, count: u32) {
+        if self.bits < count {
+            let word = match self.data.get(self.position..self.position + 2) {
+                Some(word) => {
+                    self.position += 2;
+                    u16::from_le_bytes([word[0], word[1]])
+                }
+                None => 0,
+            };
+            self.buffer |= (word as u32) << (16 - self.bits);
+            self.bits += 16;
+        }
+    }
+
+    fn peek(&self, count: u32) -> u32 {
+        self.buffer >> (32 - count)
+    }
+
+    fn pop(&mut self, count: u32) -> u32 {
+        if count == 0 {
+            return 0;
+        }
+        let value = self.peek(count);
+        self.buffer <<= count;
+        self.bits -= count;
+        value
+    }
+
+    /// Byte stored in the stream between bitstream words
+    fn read_byte(&mut self) -> Option<u8> {
+        let byte = *self.data.get(self.position)?;
+        self.position += 1;
+        Some(byte)
+    }
+
+    fn read_u16(&mut self) -> Option<u16> {
+        let word = self.data.get(self.position..self.position + 2)?;
+        self.position += 2;
+        Some(u16::from_le_bytes([word[0], word[1]]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decompress_literals_and_match() {
+        // 'a' = 0, 'b' = 10, match of length 3 at offset 1 = 11
+        let mut chunk = vec![0u8; NUM_SYMBOLS / 2];
+        chunk[b'a' as usize / 2] |= 1 << 4;
+        chunk[b'b' as usize / 2] |= 2;
+        chunk[256 / 2] |= 2;
+        // 0 10 11, then an offset of 1 without extra bits
+        chunk.extend([0x00, 0x58, 0x00, 0x00]);
+
+        assert_eq!(decompress(&chunk, 5).unwrap(), b"abbbb");
+        assert!(decompress(&chunk[..100], 5).is_err());
+        assert!(decompress(&[0u8; 260], 5).is_err());
+    }
+}
@@ -0,0 +1,157 @@
+//! Shared state of installer format detection
+//!
+//! Every registered analyzer is asked whether it can handle a file, and most
+//! of them look at the same things: the first bytes, whether it is a PE image
+//! and which marker strings it contains. A [`DetectionContext`] reads the
+//! header once and searches the markers of all analyzers in a single pass,
+//! so detection reads the file once instead of once per analyzer. Markers
+//! that were not part of that pass are searched on first use and cached.
+
+use crate::analyzers::archive::{ArchiveFormat, ArchiveParser};
+use crate::analyzers::common::{self, PatternMatches};
+use crate::core::Result;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Bytes of the file start kept for signature checks
+pub const HEADER_SIZE: usize = 4096;
+
+/// File under detection with everything read from it so far
+#[derive(Debug)]
+pub struct DetectionContext {
+    path: PathBuf,
+    file_size: u64,
+    header: Vec<u8>,
+    /// Results of every pattern search made for the file
+    scans: Mutex<Vec<PatternMatches>>,
+}
+
+impl DetectionContext {
+    /// Read the header of `file_path`
+    pub async fn new(file_path: &Path) -> Result<Self> {
+        Self::with_patterns(file_path, &[]).await
+    }
+
+    /// Read the header of `file_path` and search it for `patterns` right away
+    pub async fn with_patterns(file_path: &Path, patterns: &[&str]) -> Result<Self> {
+        common::validate_file(file_path).await?;
+        let context = Self {
+            path: file_path.to_path_buf(),
+            file_size: common::get_file_size(file_path).await?,
+            header: common::read_file_header(file_path, HEADER_SIZE).await?,
+            scans: Mutex::new(Vec::new()),
+        };
+        if !patterns.is_empty() {
+            let matches = common::scan_file_patterns(file_path, patterns).await?;
+            context.scans().push(matches);
+        }
+        Ok(context)
+    }
+
+    /// File under detection
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Extension of the file, as written
+    pub fn extension(&self) -> Option<&str> {
+        self.path.extension().and_then(|ext| ext.to_str())
+    }
+
+    pub fn file_size(&self) -> u64 {
+        self.file_size
+    }
+
+    /// First [`HEADER_SIZE`] bytes of the file, fewer for smaller files
+    pub fn header(&self) -> &[u8] {
+        &self.header
+    }
+
+    /// The file starts with the `MZ` signature of PE images
+    pub fn is_pe(&self) -> bool {
+        self.header.starts_with(b"MZ")
+    }
+
+    /// Archive format told by the file signature
+    pub fn archive_format(&self) -> ArchiveFormat {
+        ArchiveParser::format_from_header(&self.header)
+    }
+
+    /// Which of `patterns` the file contains, in the order they were given
+    ///
+    /// Only patterns no earlier search covered are searched for.
+    pub async fn find_patterns(&self, patterns: &[&str]) -> Result<Vec<String>> {
+        let missing: Vec<&str> = {
+            let scans = self.scans();
+            patterns
+                .iter()
+                .copied()
+                .filter(|pattern| !scans.iter().any(|scan| scan.searched(pattern)))
+                .collect()
+        };
+        if !missing.is_empty() {
+            let matches = common::scan_file_patterns(&self.path, &missing).await?;
+            self.scans().push(matches);
+        }
+
+        let scans = self.scans();
+        Ok(patterns
+            .iter()
+            .filter(|pattern| scans.iter().any(|scan| scan.contains(pattern)))
+            .map(|pattern| pattern.to_string())
+            .collect())
+    }
+
+    /// Number of pattern searches made over the file
+    pub fn scan_count(&self) -> usize {
+        self.scans().len()
+    }
+
+    fn scans(&self) -> std::sync::MutexGuard<'_, Vec<PatternMatches>> {
+        self.scans
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzers::AnalyzerFactory;
+    use crate::core::InstallerFormat;
+
+    #[tokio::test]
+    async fn test_analyzers_share_one_scan() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("setup.exe");
+        let mut content = b"MZ".to_vec();
+        content.extend(vec![0u8; 8192]);
+        content.extend_from_slice(b"Nullsoft Install System v3.08");
+        std::fs::write(&path, content).unwrap();
+
+        let analyzers = AnalyzerFactory::get_all_analyzers();
+        let patterns: Vec<&str> = analyzers
+            .iter()
+            .flat_map(|analyzer| analyzer.detection_patterns())
+            .collect();
+        let context = DetectionContext::with_patterns(&path, &patterns)
+            .await
+            .unwrap();
+
+        let mut formats = Vec::new();
+        for analyzer in &analyzers {
+            if analyzer.can_analyze_with(&context).await.unwrap() {
+                formats.push(analyzer.format());
+            }
+        }
+        assert_eq!(formats, vec![InstallerFormat::NSIS]);
+        assert!(context.is_pe());
+        assert_eq!(context.scan_count(), 1);
+
+        // Patterns outside the first pass are searched once, then cached
+        let found = context.find_patterns(&["v3.08", "Nullsoft"]).await.unwrap();
+        assert_eq!(found, vec!["v3.08", "Nullsoft"]);
+        context.find_patterns(&["v3.08"]).await.unwrap();
+        assert_eq!(context.scan_count(), 2);
+    }
+}
@@ -0,0 +1,508 @@
+//! Windows driver package analyzer implementation
+//!
+//! Driver packages are distributed as a cabinet holding the INF, its catalog
+//! and the driver binaries, or as loose files next to the INF. A loose INF
+//! is analyzed together with the files it references in its directory.
+
+use super::catalog::CatalogCheck;
+use super::inf::{decode, DriverInf, InfFile};
+use crate::analyzers::multipart::parse_cabinet_files;
+use crate::analyzers::wix::burn::extract_cabinet;
+use crate::analyzers::{common, DetectionContext, InstallerAnalyzer};
+use crate::core::{
+    AnalyzerCapabilities, AnalyzerError, FileAttributes, FileEntry, InstallerFormat,
+    InstallerMetadata, MetadataSource, RegistryOperation, Result, SupportLevel,
+};
+use async_trait::async_trait;
+use chrono::Utc;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// Package a reading belongs to, with its modification time
+type PackageKey = (PathBuf, Option<SystemTime>);
+
+/// Largest package that is read into memory
+const MAX_PACKAGE_SIZE: u64 = 256 * 1024 * 1024;
+/// Part of a cabinet searched for its file table during detection
+const CABINET_TABLE_PROBE: usize = 64 * 1024;
+
+/// File of a driver package
+#[derive(Debug, Clone)]
+struct PackageFile {
+    name: String,
+    size: u64,
+    /// Referenced by the INF but not found next to it
+    missing: bool,
+}
+
+/// Driver package read from a cabinet or an INF directory
+#[derive(Debug, Clone)]
+struct DriverPackage {
+    /// Name of the INF in the package
+    inf_name: String,
+    /// Other INF files of the package, which are not analyzed
+    other_infs: Vec<String>,
+    driver: DriverInf,
+    files: Vec<PackageFile>,
+    catalog: CatalogCheck,
+    from_cabinet: bool,
+}
+
+impl DriverPackage {
+    /// Read a cabinet or a loose INF with the files it references
+    fn read(file_path: &Path) -> Result<Self> {
+        let size = std::fs::metadata(file_path)?.len();
+        if size > MAX_PACKAGE_SIZE {
+            return Err(AnalyzerError::generic(format!(
+                "Driver package is too large to analyze ({} bytes)",
+                size
+            )));
+        }
+        let data = std::fs::read(file_path)?;
+
+        if data.starts_with(b"MSCF") {
+            Self::from_files(extract_cabinet(&data)?, true)
+        } else {
+            let name = file_path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let driver = DriverInf::from_inf(&InfFile::parse(&decode(&data)));
+            let mut files = vec![(name, data)];
+            let mut missing = Vec::new();
+            let directory = file_path.parent().unwrap_or(Path::new("."));
+            let siblings = sibling_files(directory);
+            for referenced in driver.package_files() {
+                match siblings.get(&referenced.to_lowercase()) {
+                    Some(path) if std::fs::metadata(path)?.len() <= MAX_PACKAGE_SIZE => {
+                        let name = path
+                            .file_name()
+                            .map(|name| name.to_string_lossy().into_owned())
+                            .unwrap_or_default();
+                        files.push((name, std::fs::read(path)?));
+                    }
+                    _ => missing.push(referenced.to_string()),
+                }
+            }
+            let mut package = Self::from_files(files, false)?;
+            package
+                .files
+                .extend(missing.into_iter().map(|name| PackageFile {
+                    name,
+                    size: 0,
+                    missing: true,
+                }));
+            Ok(package)
+        }
+    }
+
+    /// Analyze the files of a package: the first INF, its catalog and the rest
+    fn from_files(files: Vec<(String, Vec<u8>)>, from_cabinet: bool) -> Result<Self> {
+        let mut infs = files.iter().filter(|(name, _)| has_extension(name, "inf"));
+        let (inf_name, inf_data) = infs
+            .next()
+            .ok_or_else(|| AnalyzerError::parse_error("Driver package contains no INF file"))?;
+        let other_infs = infs.map(|(name, _)| name.clone()).collect();
+        let driver = DriverInf::from_inf(&InfFile::parse(&decode(inf_data)));
+
+        // The catalog named by the INF, else any catalog of the package
+        let catalog = driver
+            .catalog_files
+            .iter()
+            .find_map(|catalog| {
+                files
+                    .iter()
+                    .find(|(name, _)| file_name(name).eq_ignore_ascii_case(catalog))
+            })
+            .or_else(|| files.iter().find(|(name, _)| has_extension(name, "cat")));
+        let covered: Vec<(String, Vec<u8>)> = files
+            .iter()
+            .filter(|(name, _)| !has_extension(name, "cat"))
+            .cloned()
+            .collect();
+        let mut check = CatalogCheck::verify(
+            catalog.map(|(name, data)| (name.as_str(), data.as_slice())),
+            &covered,
+        );
+        if catalog.is_none() {
+            if let Some(named) = driver.catalog_files.first() {
+                check
+                    .issues
+                    .push(format!("{} named by the INF is not in the package", named));
+            }
+        }
+
+        Ok(Self {
+            inf_name: inf_name.clone(),
+            other_infs,
+            files: files
+                .iter()
+                .map(|(name, data)| PackageFile {
+                    name: name.clone(),
+                    size: data.len() as u64,
+                    missing: false,
+                })
+                .collect(),
+            driver,
+            catalog: check,
+            from_cabinet,
+        })
+    }
+}
+
+/// Driver package (INF, catalog and binaries) analyzer
+pub struct DriverPackageAnalyzer {
+    /// Package analyzed last, shared by the analysis stages
+    package: Mutex<Option<(PackageKey, Arc<DriverPackage>)>>,
+}
+
+impl DriverPackageAnalyzer {
+    /// Create a new driver package analyzer
+    pub fn new() -> Self {
+        Self {
+            package: Mutex::new(None),
+        }
+    }
+
+    /// Read the package at `file_path`, reusing it while the file is unchanged
+    fn package(&self, file_path: &Path) -> Result<Arc<DriverPackage>> {
+        let modified = std::fs::metadata(file_path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        let key = (file_path.to_path_buf(), modified);
+
+        let mut package = self
+            .package
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some((read, cached)) = package.as_ref() {
+            if *read == key {
+                return Ok(cached.clone());
+            }
+        }
+        let read = Arc::new(DriverPackage::read(file_path)?);
+        *package = Some((key, read.clone()));
+        Ok(read)
+    }
+
+    /// Check for a cabinet listing an INF, or INF text with a `[Version]` section
+    async fn is_driver_package(file_path: &Path, header: &[u8]) -> Result<bool> {
+        if header.starts_with(b"MSCF") {
+            let size = common::get_file_size(file_path).await?;
+            let table = common::read_file_content_range(
+                file_path,
+                0,
+                (size as usize).min(CABINET_TABLE_PROBE),
+            )
+            .await?;
+            return Ok(parse_cabinet_files(&table).is_ok_and(|files| {
+                files
+                    .iter()
+                    .any(|file| has_extension(&file.path.to_string_lossy(), "inf"))
+            }));
+        }
+
+        let is_inf = file_path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("inf"));
+        Ok(is_inf && decode(header).to_lowercase().contains("[version]"))
+    }
+
+    /// Extract metadata from the INF `[Version]` section and the catalog
+    async fn extract_package_metadata(&self, file_path: &Path) -> Result<InstallerMetadata> {
+        let file_size = common::get_file_size(file_path).await?;
+        let file_hash = common::calculate_file_hash(file_path).await?;
+        let package = self.package(file_path)?;
+        let driver = &package.driver;
+
+        let mut properties = HashMap::new();
+        properties.insert("format_version".to_string(), "INF".to_string());
+        properties.insert("file_type".to_string(), "Driver Package".to_string());
+        properties.insert("driver_inf".to_string(), package.inf_name.clone());
+        if !package.other_infs.is_empty() {
+            properties.insert(
+                "driver_other_infs".to_string(),
+                package.other_infs.join(", "),
+            );
+        }
+        for (name, value) in [
+            ("driver_class", &driver.class),
+            ("driver_class_guid", &driver.class_guid),
+            ("driver_provider", &driver.provider),
+            ("driver_date", &driver.driver_date),
+            ("driver_version", &driver.driver_version),
+        ] {
+            if let Some(value) = value {
+                properties.insert(name.to_string(), value.clone());
+            }
+        }
+        if !driver.catalog_files.is_empty() {
+            properties.insert(
+                "driver_catalog".to_string(),
+                driver.catalog_files.join(", "),
+            );
+        }
+        if !driver.architectures.is_empty() {
+            properties.insert(
+                "driver_architectures".to_string(),
+                driver.architectures.join(", "),
+            );
+        }
+        if !driver.devices.is_empty() {
+            properties.insert(
+                "driver_device_count".to_string(),
+                driver.devices.len().to_string(),
+            );
+            properties.insert(
+                "driver_hardware_ids".to_string(),
+                driver.hardware_ids().join(", "),
+            );
+        }
+        if !driver.services.is_empty() {
+            properties.insert("driver_services".to_string(), driver.describe_services());
+            let boot_start: Vec<&str> = driver
+                .services
+                .iter()
+                .filter(|service| service.is_boot_critical())
+                .map(|service| service.name.as_str())
+                .collect();
+            properties.insert(
+                "driver_boot_start".to_string(),
+                (!boot_start.is_empty()).to_string(),
+            );
+        }
+        properties.insert(
+            "driver_pnp_lockdown".to_string(),
+            driver.pnp_lockdown.to_string(),
+        );
+        package.catalog.insert_properties(&mut properties);
+
+        Ok(InstallerMetadata {
+            format: InstallerFormat::DriverPackage,
+            product_name: driver
+                .devices
+                .first()
+                .map(|device| device.description.clone())
+                .or_else(|| driver.class.clone()),
+            product_version: driver.driver_version.clone(),
+            manufacturer: driver.provider.clone(),
+            file_size,
+            file_hash,
+            created_at: Utc::now(),
+            properties,
+            provenance: Default::default(),
+            format_details: None,
+        }
+        .with_provenance(MetadataSource::Manifest))
+    }
+
+    /// List the package files with the destination the INF copies them to
+    fn extract_package_files(&self, file_path: &Path) -> Result<Vec<FileEntry>> {
+        let package = self.package(file_path)?;
+        tracing::info!(
+            "Found {} files in driver package {}",
+            package.files.len(),
+            package.inf_name
+        );
+
+        Ok(package
+            .files
+            .iter()
+            .map(|file| FileEntry {
+                path: PathBuf::from(&file.name),
+                target_path: package
+                    .driver
+                    .destination_of(file_name(&file.name))
+                    .map(PathBuf::from),
+                size: file.size,
+                hash: None,
+                attributes: FileAttributes {
+                    executable: ["sys", "dll", "exe"]
+                        .iter()
+                        .any(|extension| has_extension(&file.name, extension)),
+                    ..FileAttributes::default()
+                },
+                compression: package.from_cabinet.then(|| "CAB".to_string()),
+                is_speculative: file.missing,
+                architecture: None,
+                modified: None,
+                source: None,
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl InstallerAnalyzer for DriverPackageAnalyzer {
+    async fn can_analyze(&self, file_path: &Path) -> Result<bool> {
+        let header = common::read_file_header(file_path, 4096).await?;
+        Self::is_driver_package(file_path, &header).await
+    }
+
+    async fn can_analyze_with(&self, context: &DetectionContext) -> Result<bool> {
+        Self::is_driver_package(context.path(), context.header()).await
+    }
+
+    fn format(&self) -> InstallerFormat {
+        InstallerFormat::DriverPackage
+    }
+
+    fn capabilities(&self) -> AnalyzerCapabilities {
+        AnalyzerCapabilities {
+            metadata: SupportLevel::Full,
+            file_extraction: SupportLevel::Full,
+            registry_operations: SupportLevel::Partial,
+            install_scripts: SupportLevel::Unsupported,
+            signatures: SupportLevel::Partial,
+        }
+    }
+
+    async fn extract_metadata(&self, file_path: &Path) -> Result<InstallerMetadata> {
+        // Validate file first
+        common::validate_file(file_path).await?;
+
+        self.extract_package_metadata(file_path).await
+    }
+
+    async fn extract_files(&self, file_path: &Path) -> Result<Vec<FileEntry>> {
+        // Validate file first
+        common::validate_file(file_path).await?;
+
+        self.extract_package_files(file_path)
+    }
+
+    async fn extract_registry_operations(
+        &self,
+        file_path: &Path,
+    ) -> Result<Vec<RegistryOperation>> {
+        // Validate file first
+        common::validate_file(file_path).await?;
+
+        // AddReg values and the service keys of AddService
+        Ok(self.package(file_path)?.driver.registry_operations.clone())
+    }
+}
+
+impl Default for DriverPackageAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Files of a directory by lowercase name
+fn sibling_files(directory: &Path) -> HashMap<String, PathBuf> {
+    std::fs::read_dir(directory)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_file()))
+        .map(|entry| {
+            (
+                entry.file_name().to_string_lossy().to_lowercase(),
+                entry.path(),
+            )
+        })
+        .collect()
+}
+
+/// Last component of a package path
+fn file_name(path: &str) -> &str {
+    path.rsplit(['/', '\\']).next().unwrap_or(path)
+}
+
+fn has_extension(name: &str, extension: &str) -> bool {
+    name.rsplit_once('.')
+        .is_some_and(|(_, ext)| ext.eq_ignore_ascii_case(extension))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzers::driver::catalog::tests::pe_image;
+    use crate::analyzers::driver::inf::tests::SAMPLE_INF;
+    use crate::analyzers::wix::burn::tests::mszip_cabinet;
+
+    #[tokio::test]
+    async fn test_analyze_loose_inf() {
+        let directory = tempfile::tempdir().unwrap();
+        let inf = directory.path().join("sample.inf");
+        std::fs::write(&inf, SAMPLE_INF).unwrap();
+        std::fs::write(directory.path().join("Sample.sys"), pe_image(b"")).unwrap();
+
+        let analyzer = DriverPackageAnalyzer::new();
+        assert!(analyzer.can_analyze(&inf).await.unwrap());
+
+        let metadata = analyzer.extract_metadata(&inf).await.unwrap();
+        assert_eq!(metadata.format, InstallerFormat::DriverPackage);
+        assert_eq!(metadata.manufacturer.as_deref(), Some("Example Corp"));
+        assert_eq!(metadata.product_version.as_deref(), Some("2.4.1.0"));
+        assert_eq!(
+            metadata.product_name.as_deref(),
+            Some("Example Gigabit Adapter")
+        );
+        let properties = &metadata.properties;
+        assert_eq!(properties["driver_class"], "Net");
+        assert_eq!(properties["driver_architectures"], "x64, arm64");
+        assert_eq!(properties["driver_boot_start"], "false");
+        assert_eq!(properties["catalog_signature_status"], "missing");
+        assert_eq!(
+            properties["catalog_issues"],
+            "sample.cat named by the INF is not in the package"
+        );
+
+        let files = analyzer.extract_files(&inf).await.unwrap();
+        assert_eq!(files.len(), 3);
+        let sys = files
+            .iter()
+            .find(|file| file.path == Path::new("Sample.sys"));
+        assert_eq!(
+            sys.unwrap().target_path.as_deref(),
+            Some(Path::new("%SystemRoot%\\System32\\drivers\\sample.sys"))
+        );
+        assert!(files
+            .iter()
+            .any(|file| file.path == Path::new("sample.cat") && file.is_speculative));
+
+        let operations = analyzer.extract_registry_operations(&inf).await.unwrap();
+        assert!(!operations.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_analyze_cabinet_package() {
+        let sys = pe_image(b"");
+        let cabinet = mszip_cabinet(&[
+            ("sample.inf", SAMPLE_INF.as_bytes()),
+            ("sample.cat", b"not a catalog"),
+            ("sample.sys", &sys),
+        ]);
+        let mut file = tempfile::Builder::new().suffix(".cab").tempfile().unwrap();
+        std::io::Write::write_all(&mut file, &cabinet).unwrap();
+
+        let analyzer = DriverPackageAnalyzer::new();
+        assert!(analyzer.can_analyze(file.path()).await.unwrap());
+        let metadata = analyzer.extract_metadata(file.path()).await.unwrap();
+        assert_eq!(metadata.properties["driver_inf"], "sample.inf");
+        assert_eq!(metadata.properties["catalog_signature_status"], "invalid");
+
+        let files = analyzer.extract_files(file.path()).await.unwrap();
+        assert_eq!(files.len(), 3);
+        assert!(files
+            .iter()
+            .all(|file| file.compression.as_deref() == Some("CAB")));
+        assert!(files[2].attributes.executable);
+    }
+
+    #[tokio::test]
+    async fn test_reject_other_inf_files() {
+        let directory = tempfile::tempdir().unwrap();
+        let inf = directory.path().join("autorun.inf");
+        std::fs::write(&inf, "[autorun]\r\nopen=setup.exe\r\n").unwrap();
+        assert!(!DriverPackageAnalyzer::new()
+            .can_analyze(&inf)
+            .await
+            .unwrap());
+    }
+}
@@ -0,0 +1,594 @@
+//! Driver catalog (`.cat`) verification
+//!
+//! A catalog is PKCS#7 signed data around a certificate trust list (CTL).
+//! Each trusted subject of the list carries the hash of one package file in
+//! an `SPC_INDIRECT_DATA` attribute, and usually its name in a `File`
+//! name-value attribute. Portable executables are hashed the Authenticode
+//! way, other files as a whole. The package files are hashed and looked up
+//! in the list, and the catalog signature is verified with the certificate
+//! it carries; the certificate is not validated against a trust store.
+
+use crate::analyzers::common::pkcs7::{DigestAlgorithm, IntegrityStatus, SignedMessage};
+use der::asn1::ObjectIdentifier;
+use std::collections::HashMap;
+
+const OID_CTL: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.1");
+const OID_SPC_INDIRECT_DATA: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.2.1.4");
+const OID_SPC_PE_IMAGE_DATA: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.2.1.15");
+const OID_CAT_NAME_VALUE: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.12.2.1");
+
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_OID: u8 = 0x06;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_SET: u8 = 0x31;
+
+/// Issues listed in analysis properties; the rest are only counted
+const MAX_LISTED_ISSUES: usize = 10;
+
+/// Package file hash listed in a catalog
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CatalogMember {
+    /// File name from the `File` attribute
+    pub name: Option<String>,
+    /// Whether the hash is an Authenticode PE image hash
+    pub is_pe_image: bool,
+    pub algorithm: Option<DigestAlgorithm>,
+    pub digest: Vec<u8>,
+}
+
+/// Result of verifying a driver catalog against the package files
+#[derive(Debug, Clone)]
+pub struct CatalogCheck {
+    /// Name of the catalog in the package
+    pub catalog: Option<String>,
+    pub status: IntegrityStatus,
+    /// Subject of the signing certificate
+    pub signer: Option<String>,
+    /// SHA-1 thumbprint of the signing certificate
+    pub signer_thumbprint: Option<String>,
+    pub member_count: usize,
+    /// Package files whose hash is listed in the catalog
+    pub files_verified: usize,
+    pub issues: Vec<String>,
+}
+
+impl CatalogCheck {
+    /// Verify `catalog` and check that it covers every file of `files`
+    ///
+    /// `files` are the package files other than catalogs, by name.
+    pub fn verify(catalog: Option<(&str, &[u8])>, files: &[(String, Vec<u8>)]) -> Self {
+        let Some((name, data)) = catalog else {
+            return Self {
+                catalog: None,
+                status: IntegrityStatus::Missing,
+                signer: None,
+                signer_thumbprint: None,
+                member_count: 0,
+                files_verified: 0,
+                issues: Vec::new(),
+            };
+        };
+
+        let mut check = Self {
+            catalog: Some(name.to_string()),
+            status: IntegrityStatus::Invalid,
+            signer: None,
+            signer_thumbprint: None,
+            member_count: 0,
+            files_verified: 0,
+            issues: Vec::new(),
+        };
+        let mut problems = Vec::new();
+        check.status = match check.check_catalog(name, data, files, &mut problems) {
+            Ok(()) if problems.is_empty() => IntegrityStatus::Valid,
+            Ok(()) => IntegrityStatus::Invalid,
+            Err((status, message)) => {
+                problems.push(message);
+                status
+            }
+        };
+        check.issues = problems;
+        check
+    }
+
+    /// Run the catalog checks
+    ///
+    /// Mismatches are collected in `problems`; an `Err` stops the check when
+    /// the catalog cannot be evaluated any further.
+    fn check_catalog(
+        &mut self,
+        name: &str,
+        data: &[u8],
+        files: &[(String, Vec<u8>)],
+        problems: &mut Vec<String>,
+    ) -> std::result::Result<(), (IntegrityStatus, String)> {
+        let message = SignedMessage::parse(data).map_err(|e| (e.status(), e.describe(name)))?;
+        if message.content_type != OID_CTL {
+            return Err((
+                IntegrityStatus::Invalid,
+                format!("{} is not a certificate trust list", name),
+            ));
+        }
+
+        let members = catalog_members(&message.content);
+        self.member_count = members.len();
+        self.files_verified = check_members(&members, files, problems);
+
+        self.signer = message.signer();
+        self.signer_thumbprint = message.signer_thumbprint();
+        message
+            .verify(problems)
+            .map_err(|e| (e.status(), e.describe(name)))
+    }
+
+    /// Record the results as `catalog_*` analysis properties
+    pub fn insert_properties(&self, properties: &mut HashMap<String, String>) {
+        properties.insert(
+            "catalog_signature_status".to_string(),
+            self.status.to_string(),
+        );
+        if let Some(signer) = &self.signer {
+            properties.insert("catalog_signer".to_string(), signer.clone());
+        }
+        if let Some(thumbprint) = &self.signer_thumbprint {
+            properties.insert("catalog_signer_thumbprint".to_string(), thumbprint.clone());
+        }
+        if self.catalog.is_some() {
+            properties.insert(
+                "catalog_member_count".to_string(),
+                self.member_count.to_string(),
+            );
+            properties.insert(
+                "catalog_files_verified".to_string(),
+                self.files_verified.to_string(),
+            );
+        }
+
+        if !self.issues.is_empty() {
+            let mut listed = self.issues[..self.issues.len().min(MAX_LISTED_ISSUES)].join("; ");
+            if self.issues.len() > MAX_LISTED_ISSUES {
+                listed.push_str(&format!(
+                    "; and {} more",
+                    self.issues.len() - MAX_LISTED_ISSUES
+                ));
+            }
+            properties.insert("catalog_issues".to_string(), listed);
+        }
+    }
+}
+
+/// Look up every package file in the catalog, returning how many matched
+fn check_members(
+    members: &[CatalogMember],
+    files: &[(String, Vec<u8>)],
+    problems: &mut Vec<String>,
+) -> usize {
+    let mut verified = 0;
+    for (name, content) in files {
+        // Hashes are computed once per algorithm used in the catalog
+        let mut digests: HashMap<(DigestAlgorithm, bool), Option<Vec<u8>>> = HashMap::new();
+        let mut digest_of = |algorithm: DigestAlgorithm, is_pe_image: bool| {
+            digests
+                .entry((algorithm, is_pe_image))
+                .or_insert_with(|| {
+                    if is_pe_image {
+                        authenticode_digest(content, algorithm)
+                    } else {
+                        Some(algorithm.digest(content))
+                    }
+                })
+                .clone()
+        };
+
+        let listed = members.iter().any(|member| {
+            member.algorithm.is_some_and(|algorithm| {
+                digest_of(algorithm, member.is_pe_image).as_deref() == Some(&member.digest[..])
+            })
+        });
+        if listed {
+            verified += 1;
+            continue;
+        }
+
+        let named = members.iter().any(|member| {
+            member
+                .name
+                .as_deref()
+                .is_some_and(|member| member.eq_ignore_ascii_case(file_name(name)))
+        });
+        problems.push(if named {
+            format!("{} does not match its catalog hash", name)
+        } else {
+            format!("{} is not listed in the catalog", name)
+        });
+    }
+    verified
+}
+
+/// Last component of a package path
+fn file_name(path: &str) -> &str {
+    path.rsplit(['/', '\\']).next().unwrap_or(path)
+}
+
+/// Trusted subjects of the CTL content of a catalog
+///
+/// `content` is the signed content without its outer SEQUENCE header.
+pub fn catalog_members(content: &[u8]) -> Vec<CatalogMember> {
+    // The subject list is the SEQUENCE whose items start with an OCTET STRING
+    let subjects = der_items(content).find(|&(tag, value)| {
+        tag == TAG_SEQUENCE
+            && der_items(value).next().is_some_and(|(tag, subject)| {
+                tag == TAG_SEQUENCE
+                    && der_items(subject)
+                        .next()
+                        .is_some_and(|(tag, _)| tag == TAG_OCTET_STRING)
+            })
+    });
+    let Some((_, subjects)) = subjects else {
+        return Vec::new();
+    };
+
+    der_items(subjects)
+        .filter(|&(tag, _)| tag == TAG_SEQUENCE)
+        .filter_map(|(_, subject)| {
+            let mut member = CatalogMember {
+                name: None,
+                is_pe_image: false,
+                algorithm: None,
+                digest: Vec::new(),
+            };
+            let (_, attributes) = der_items(subject).find(|&(tag, _)| tag == TAG_SET)?;
+            for (_, attribute) in der_items(attributes).filter(|&(tag, _)| tag == TAG_SEQUENCE) {
+                let mut parts = der_items(attribute);
+                let (Some((TAG_OID, oid)), Some((TAG_SET, values))) = (parts.next(), parts.next())
+                else {
+                    continue;
+                };
+                let Ok(oid) = ObjectIdentifier::from_bytes(oid) else {
+                    continue;
+                };
+                let Some((_, value)) = der_items(values).next() else {
+                    continue;
+                };
+                if oid == OID_SPC_INDIRECT_DATA {
+                    read_indirect_data(value, &mut member);
+                } else if oid == OID_CAT_NAME_VALUE {
+                    if let Some((tag, text)) = read_name_value(value) {
+                        if tag.eq_ignore_ascii_case("File") {
+                            member.name = Some(text);
+                        }
+                    }
+                }
+            }
+            (!member.digest.is_empty()).then_some(member)
+        })
+        .collect()
+}
+
+/// Digest of an Authenticode signature's `SpcIndirectDataContent`
+///
+/// `content` is the signed content without its outer SEQUENCE header.
+pub fn indirect_data(content: &[u8]) -> Option<CatalogMember> {
+    let mut member = CatalogMember {
+        name: None,
+        is_pe_image: false,
+        algorithm: None,
+        digest: Vec::new(),
+    };
+    read_indirect_data(content, &mut member);
+    (!member.digest.is_empty()).then_some(member)
+}
+
+/// Data type and digest of an `SpcIndirectDataContent`
+fn read_indirect_data(value: &[u8], member: &mut CatalogMember) {
+    let mut parts = der_items(value);
+    if let Some((TAG_SEQUENCE, data)) = parts.next() {
+        member.is_pe_image = der_items(data).next().is_some_and(|(tag, oid)| {
+            tag == TAG_OID && ObjectIdentifier::from_bytes(oid).ok() == Some(OID_SPC_PE_IMAGE_DATA)
+        });
+    }
+    // DigestInfo ::= SEQUENCE { AlgorithmIdentifier, OCTET STRING }
+    let Some((TAG_SEQUENCE, digest_info)) = parts.next() else {
+        return;
+    };
+    let mut digest_parts = der_items(digest_info);
+    if let Some((TAG_SEQUENCE, algorithm)) = digest_parts.next() {
+        member.algorithm = der_items(algorithm)
+            .next()
+            .filter(|&(tag, _)| tag == TAG_OID)
+            .and_then(|(_, oid)| ObjectIdentifier::from_bytes(oid).ok())
+            .and_then(|oid| DigestAlgorithm::from_oid(&oid));
+    }
+    if let Some((TAG_OCTET_STRING, digest)) = digest_parts.next() {
+        member.digest = digest.to_vec();
+    }
+}
+
+/// Tag and text of a `CatNameValue { BMPString, INTEGER, OCTET STRING }`
+fn read_name_value(value: &[u8]) -> Option<(String, String)> {
+    let mut parts = der_items(value);
+    let (_, tag) = parts.next()?;
+    let (_, text) = parts.find(|&(tag, _)| tag == TAG_OCTET_STRING)?;
+    let tag: Vec<u16> = tag
+        .chunks_exact(2)
+        .map(|unit| u16::from_be_bytes([unit[0], unit[1]]))
+        .collect();
+    let text: Vec<u16> = text
+        .chunks_exact(2)
+        .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+        .take_while(|&unit| unit != 0)
+        .collect();
+    Some((
+        String::from_utf16_lossy(&tag),
+        String::from_utf16_lossy(&text),
+    ))
+}
+
+/// DER elements of `data` as `(tag, value)` pairs, stopping at malformed data
+fn der_items(mut data: &[u8]) -> impl Iterator<Item = (u8, &[u8])> {
+    std::iter::from_fn(move || {
+        let (&tag, rest) = data.split_first()?;
+        let (&first, rest) = rest.split_first()?;
+        let (length, rest) = if first & 0x80 == 0 {
+            (first as usize, rest)
+        } else {
+            let count = (first & 0x7f) as usize;
+            if count == 0 || count > 4 || rest.len() < count {
+                data = &[];
+                return None;
+            }
+            let length = rest[..count]
+                .iter()
+                .fold(0usize, |length, &b| (length << 8) | b as usize);
+            (length, &rest[count..])
+        };
+        if rest.len() < length {
+            data = &[];
+            return None;
+        }
+        let (value, rest) = rest.split_at(length);
+        data = rest;
+        Some((tag, value))
+    })
+}
+
+/// Authenticode hash of a PE file
+///
+/// The file is hashed without its checksum, the certificate table directory
+/// entry and the certificate table. Sections are hashed in file order rather
+/// than sorted by the section table, which gives the same result for images
+/// laid out by linkers.
+pub fn authenticode_digest(data: &[u8], algorithm: DigestAlgorithm) -> Option<Vec<u8>> {
+    if data.len() < 0x40 || &data[..2] != b"MZ" {
+        return None;
+    }
+    let pe = u32::from_le_bytes(data[0x3c..0x40].try_into().ok()?) as usize;
+    if data.get(pe..pe + 4)? != b"PE\0\0" {
+        return None;
+    }
+    let optional_header = pe + 24;
+    let magic = u16::from_le_bytes(
+        data.get(optional_header..optional_header + 2)?
+            .try_into()
+            .ok()?,
+    );
+    let data_directories = match magic {
+        0x10b => optional_header + 96,
+        0x20b => optional_header + 112,
+        _ => return None,
+    };
+    let checksum = optional_header + 64;
+    let certificate_entry = data_directories + 4 * 8;
+    let entry = data.get(certificate_entry..certificate_entry + 8)?;
+    let table_offset = u32::from_le_bytes(entry[..4].try_into().ok()?) as usize;
+    let table_size = u32::from_le_bytes(entry[4..].try_into().ok()?) as usize;
+
+    let mut hasher = algorithm.hasher();
+    hasher.update(&data[..checksum]);
+    hasher.update(&data[checksum + 4..certificate_entry]);
+    if table_size > 0 && table_offset >= certificate_entry + 8 && table_offset <= data.len() {
+        hasher.update(&data[certificate_entry + 8..table_offset]);
+        let table_end = table_offset.saturating_add(table_size).min(data.len());
+        hasher.update(&data[table_end..]);
+    } else {
+        hasher.update(&data[certificate_entry + 8..]);
+    }
+    Some(hasher.finalize())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    fn der(tag: u8, value: &[u8]) -> Vec<u8> {
+        let mut element = vec![tag];
+        if value.len() < 0x80 {
+            element.push(value.len() as u8);
+        } else {
+            element.push(0x82);
+            element.extend_from_slice(&(value.len() as u16).to_be_bytes());
+        }
+        element.extend_from_slice(value);
+        element
+    }
+
+    fn oid(oid: ObjectIdentifier) -> Vec<u8> {
+        der(TAG_OID, oid.as_bytes())
+    }
+
+    /// Trusted subject listing `digest` (SHA-256) for `name`
+    pub(crate) fn subject(name: &str, is_pe_image: bool, digest: &[u8]) -> Vec<u8> {
+        let data_type = if is_pe_image {
+            OID_SPC_PE_IMAGE_DATA
+        } else {
+            ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.2.1.25")
+        };
+        let sha256 = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.1");
+        let indirect = der(
+            TAG_SEQUENCE,
+            &[
+                der(TAG_SEQUENCE, &oid(data_type)),
+                der(
+                    TAG_SEQUENCE,
+                    &[
+                        der(TAG_SEQUENCE, &[oid(sha256), vec![0x05, 0x00]].concat()),
+                        der(TAG_OCTET_STRING, digest),
+                    ]
+                    .concat(),
+                ),
+            ]
+            .concat(),
+        );
+        let tag: Vec<u8> = "File".encode_utf16().flat_map(u16::to_be_bytes).collect();
+        let text: Vec<u8> = name
+            .encode_utf16()
+            .chain([0])
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        let name_value = der(
+            TAG_SEQUENCE,
+            &[
+                der(0x1e, &tag),
+                der(0x02, &[0x01]),
+                der(TAG_OCTET_STRING, &text),
+            ]
+            .concat(),
+        );
+        let attribute = |id: ObjectIdentifier, value: Vec<u8>| {
+            der(TAG_SEQUENCE, &[oid(id), der(TAG_SET, &value)].concat())
+        };
+        der(
+            TAG_SEQUENCE,
+            &[
+                der(TAG_OCTET_STRING, b"0123456789ABCDEF"),
+                der(
+                    TAG_SET,
+                    &[
+                        attribute(OID_CAT_NAME_VALUE, name_value),
+                        attribute(OID_SPC_INDIRECT_DATA, indirect),
+                    ]
+                    .concat(),
+                ),
+            ]
+            .concat(),
+        )
+    }
+
+    /// CTL content, without its outer SEQUENCE header, listing `subjects`
+    pub(crate) fn ctl_content(subjects: &[Vec<u8>]) -> Vec<u8> {
+        let sha1 = ObjectIdentifier::new_unwrap("1.3.14.3.2.26");
+        [
+            der(
+                TAG_SEQUENCE,
+                &oid(ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.12.1.1")),
+            ),
+            der(TAG_OCTET_STRING, &[0xab; 16]),
+            der(0x17, b"240517120000Z"),
+            der(TAG_SEQUENCE, &[oid(sha1), vec![0x05, 0x00]].concat()),
+            der(TAG_SEQUENCE, &subjects.concat()),
+        ]
+        .concat()
+    }
+
+    /// Minimal PE32 image with a certificate table
+    pub(crate) fn pe_image(certificate: &[u8]) -> Vec<u8> {
+        let mut image = vec![0u8; 0x200];
+        image[..2].copy_from_slice(b"MZ");
+        image[0x3c] = 0x80;
+        image[0x80..0x84].copy_from_slice(b"PE\0\0");
+        image[0x98..0x9a].copy_from_slice(&0x10bu16.to_le_bytes());
+        image.extend_from_slice(b"section data");
+        let table_offset = image.len() as u32;
+        image.extend_from_slice(certificate);
+        let entry = 0x98 + 96 + 32;
+        image[entry..entry + 4].copy_from_slice(&table_offset.to_le_bytes());
+        image[entry + 4..entry + 8].copy_from_slice(&(certificate.len() as u32).to_le_bytes());
+        image
+    }
+
+    #[test]
+    fn test_authenticode_digest_ignores_signature() {
+        let algorithm = DigestAlgorithm::Sha256;
+        let mut signed = pe_image(b"first signature");
+        let other = pe_image(b"other signature");
+        assert_eq!(
+            authenticode_digest(&signed, algorithm),
+            authenticode_digest(&other, algorithm)
+        );
+        // The checksum is not covered, the code is
+        signed[0x98 + 64] = 0x55;
+        assert_eq!(
+            authenticode_digest(&signed, algorithm),
+            authenticode_digest(&other, algorithm)
+        );
+        signed[0x200] = b'S';
+        assert_ne!(
+            authenticode_digest(&signed, algorithm),
+            authenticode_digest(&other, algorithm)
+        );
+        assert!(authenticode_digest(b"not a PE file", algorithm).is_none());
+    }
+
+    #[test]
+    fn test_catalog_members_cover_files() {
+        let algorithm = DigestAlgorithm::Sha256;
+        let inf = b"[Version]\r\n".to_vec();
+        let sys = pe_image(b"signature");
+        let content = ctl_content(&[
+            subject("sample.inf", false, &algorithm.digest(&inf)),
+            subject(
+                "sample.sys",
+                true,
+                &authenticode_digest(&sys, algorithm).unwrap(),
+            ),
+        ]);
+
+        let members = catalog_members(&content);
+        assert_eq!(members.len(), 2);
+        assert_eq!(members[0].name.as_deref(), Some("sample.inf"));
+        assert!(!members[0].is_pe_image);
+        assert!(members[1].is_pe_image);
+        assert_eq!(members[1].algorithm, Some(algorithm));
+
+        let mut problems = Vec::new();
+        let files = vec![
+            ("sample.inf".to_string(), inf),
+            ("sample.sys".to_string(), sys),
+        ];
+        assert_eq!(check_members(&members, &files, &mut problems), 2);
+        assert!(problems.is_empty());
+
+        let files = vec![
+            (
+                "sample.inf".to_string(),
+                b"[Version]\r\nClass=Net\r\n".to_vec(),
+            ),
+            ("extra.dll".to_string(), b"MZ".to_vec()),
+        ];
+        assert_eq!(check_members(&members, &files, &mut problems), 0);
+        assert_eq!(
+            problems,
+            vec![
+                "sample.inf does not match its catalog hash",
+                "extra.dll is not listed in the catalog"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_verify_catalog_status() {
+        let missing = CatalogCheck::verify(None, &[]);
+        assert_eq!(missing.status, IntegrityStatus::Missing);
+
+        let broken = CatalogCheck::verify(Some(("sample.cat", b"not signed data")), &[]);
+        assert_eq!(broken.status, IntegrityStatus::Invalid);
+        assert!(broken.issues[0].starts_with("sample.cat is malformed"));
+
+        let mut properties = HashMap::new();
+        broken.insert_properties(&mut properties);
+        assert_eq!(properties["catalog_signature_status"], "invalid");
+        assert_eq!(properties["catalog_member_count"], "0");
+    }
+}
@@ -0,0 +1,976 @@
+//! INF driver setup information
+//!
+//! An INF is a list of sections of `key = value, value` lines. `[Version]`
+//! names the device class, provider, driver version and catalog. The
+//! `[Manufacturer]` section points to model sections that map hardware IDs
+//! to an install (DDInstall) section per platform; that section copies
+//! files, adds registry values and, in its `.Services` section, installs
+//! the driver service. `%token%` references are replaced from `[Strings]`.
+
+use crate::core::{RegistryOperation, RegistryValue, RegistryValueType};
+use chrono::Utc;
+use std::collections::HashMap;
+
+/// Hardware IDs listed in analysis properties
+pub const MAX_LISTED_HARDWARE_IDS: usize = 20;
+
+/// Architectures of the platform decorations of model sections
+const ARCHITECTURES: [(&str, &str); 5] = [
+    ("ntamd64", "x64"),
+    ("ntx86", "x86"),
+    ("ntarm64", "arm64"),
+    ("ntarm", "arm"),
+    ("ntia64", "ia64"),
+];
+
+/// One line of a section
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InfLine {
+    /// Text left of `=`, if the line has one
+    pub key: Option<String>,
+    /// Comma-separated values, unquoted and with strings resolved
+    pub values: Vec<String>,
+}
+
+impl InfLine {
+    fn value(&self, index: usize) -> Option<&str> {
+        self.values
+            .get(index)
+            .map(String::as_str)
+            .filter(|value| !value.is_empty())
+    }
+}
+
+/// Sections of an INF file
+#[derive(Debug, Clone, Default)]
+pub struct InfFile {
+    /// Lines by lowercase section name; repeated sections are merged
+    sections: HashMap<String, Vec<InfLine>>,
+    /// Section names in file order, as written
+    names: Vec<String>,
+}
+
+impl InfFile {
+    /// Parse INF text, decoded with [`decode`]
+    pub fn parse(text: &str) -> Self {
+        let mut raw: Vec<(String, Vec<String>)> = Vec::new();
+        let mut pending = String::new();
+        for line in text.lines() {
+            let line = strip_comment(line);
+            // A trailing backslash continues the line
+            if let Some(continued) = line.trim_end().strip_suffix('\\') {
+                pending.push_str(continued);
+                continue;
+            }
+            pending.push_str(&line);
+            let line = std::mem::take(&mut pending);
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(name) = line
+                .strip_prefix('[')
+                .and_then(|rest| rest.split(']').next())
+            {
+                raw.push((name.trim().to_string(), Vec::new()));
+            } else if let Some((_, lines)) = raw.last_mut() {
+                lines.push(line.to_string());
+            }
+        }
+
+        // Strings are resolved in every other section
+        let strings: HashMap<String, String> = raw
+            .iter()
+            .filter(|(name, _)| name.eq_ignore_ascii_case("Strings"))
+            .flat_map(|(_, lines)| lines.iter())
+            .filter_map(|line| {
+                let (key, value) = split_key(line)?;
+                let value = split_values(value).into_iter().next().unwrap_or_default();
+                Some((key.to_lowercase(), value))
+            })
+            .collect();
+
+        let mut inf = Self::default();
+        for (name, lines) in raw {
+            let parsed = lines.iter().map(|line| {
+                let (key, values) = match split_key(line) {
+                    Some((key, values)) => (Some(resolve(key, &strings)), values),
+                    None => (None, line.as_str()),
+                };
+                InfLine {
+                    key,
+                    values: split_values(values)
+                        .iter()
+                        .map(|value| resolve(value, &strings))
+                        .collect(),
+                }
+            });
+            let key = name.to_lowercase();
+            if !inf.sections.contains_key(&key) {
+                inf.names.push(name);
+            }
+            inf.sections.entry(key).or_default().extend(parsed);
+        }
+        inf
+    }
+
+    /// Lines of a section, compared without case
+    pub fn section(&self, name: &str) -> Option<&[InfLine]> {
+        self.sections.get(&name.to_lowercase()).map(Vec::as_slice)
+    }
+
+    pub fn has_section(&self, name: &str) -> bool {
+        self.sections.contains_key(&name.to_lowercase())
+    }
+
+    /// Values of all lines with `key` in a section
+    fn entries<'a>(&'a self, section: &str, key: &'a str) -> impl Iterator<Item = &'a InfLine> {
+        self.section(section)
+            .unwrap_or_default()
+            .iter()
+            .filter(move |line| {
+                line.key
+                    .as_deref()
+                    .is_some_and(|name| name.eq_ignore_ascii_case(key))
+            })
+    }
+
+    /// First value of `key` in a section
+    pub fn value(&self, section: &str, key: &str) -> Option<String> {
+        self.entries(section, key)
+            .next()
+            .and_then(|line| line.value(0))
+            .map(str::to_string)
+    }
+
+    /// Section names in file order
+    pub fn section_names(&self) -> &[String] {
+        &self.names
+    }
+}
+
+/// Text of an INF file: UTF-16LE or UTF-8 by byte order mark, else Windows-1252
+pub fn decode(data: &[u8]) -> String {
+    if let Some(utf16) = data.strip_prefix(&[0xff, 0xfe]) {
+        let units: Vec<u16> = utf16
+            .chunks_exact(2)
+            .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    } else if let Some(utf8) = data.strip_prefix(&[0xef, 0xbb, 0xbf]) {
+        String::from_utf8_lossy(utf8).into_owned()
+    } else {
+        match std::str::from_utf8(data) {
+            Ok(text) => text.to_string(),
+            Err(_) => data.iter().map(|&b| b as char).collect(),
+        }
+    }
+}
+
+/// Remove a `;` comment outside of quotes
+fn strip_comment(line: &str) -> String {
+    let mut quoted = false;
+    for (index, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ';' if !quoted => return line[..index].to_string(),
+            _ => {}
+        }
+    }
+    line.to_string()
+}
+
+/// Split `key = values` at the first `=` outside of quotes
+fn split_key(line: &str) -> Option<(&str, &str)> {
+    let mut quoted = false;
+    for (index, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '=' if !quoted => return Some((line[..index].trim(), &line[index + 1..])),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Split comma-separated values, removing quotes (`""` is a literal quote)
+fn split_values(text: &str) -> Vec<String> {
+    let mut values = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                values.last_mut().unwrap().push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => values.push(String::new()),
+            _ => values.last_mut().unwrap().push(c),
+        }
+    }
+    values
+        .into_iter()
+        .map(|value| value.trim().to_string())
+        .collect()
+}
+
+/// Replace `%token%` references from `[Strings]`; `%%` is a literal `%`
+fn resolve(text: &str, strings: &HashMap<String, String>) -> String {
+    if !text.contains('%') {
+        return text.to_string();
+    }
+    let mut resolved = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('%') {
+        resolved.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after.find('%') {
+            Some(0) => {
+                resolved.push('%');
+                rest = &after[1..];
+            }
+            Some(end) => {
+                let token = &after[..end];
+                match strings.get(&token.to_lowercase()) {
+                    Some(value) => resolved.push_str(value),
+                    // Numeric tokens like %10% are directory IDs
+                    None => resolved.push_str(&format!("%{}%", token)),
+                }
+                rest = &after[end + 1..];
+            }
+            None => {
+                resolved.push('%');
+                rest = after;
+            }
+        }
+    }
+    resolved.push_str(rest);
+    resolved
+}
+
+/// Device supported by the driver
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DriverDevice {
+    pub description: String,
+    pub hardware_id: String,
+    /// DDInstall section used for the device
+    pub install_section: String,
+    /// Architecture of the model section, `None` for undecorated sections
+    pub architecture: Option<String>,
+}
+
+/// Service installed by `AddService`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DriverService {
+    pub name: String,
+    pub display_name: Option<String>,
+    pub service_type: Option<u32>,
+    pub start_type: Option<u32>,
+    pub error_control: Option<u32>,
+    pub binary: Option<String>,
+}
+
+impl DriverService {
+    /// Kind of service, from `ServiceType`
+    pub fn kind(&self) -> &'static str {
+        match self.service_type {
+            Some(1) => "kernel driver",
+            Some(2) => "file system driver",
+            Some(0x10) | Some(0x20) => "service",
+            _ => "unknown type",
+        }
+    }
+
+    /// When the service starts, from `StartType`
+    pub fn start(&self) -> &'static str {
+        match self.start_type {
+            Some(0) => "boot start",
+            Some(1) => "system start",
+            Some(2) => "auto start",
+            Some(3) => "demand start",
+            Some(4) => "disabled",
+            _ => "unknown start",
+        }
+    }
+
+    /// Loaded before the file system or at system start
+    pub fn is_boot_critical(&self) -> bool {
+        matches!(self.start_type, Some(0) | Some(1))
+    }
+
+    fn describe(&self) -> String {
+        let mut text = format!("{} ({}, {})", self.name, self.kind(), self.start());
+        if let Some(binary) = &self.binary {
+            text.push_str(&format!(" {}", binary));
+        }
+        text
+    }
+}
+
+/// File copied by a `CopyFiles` directive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DriverFile {
+    /// Name of the installed file
+    pub name: String,
+    /// Name in the package, when it differs
+    pub source: Option<String>,
+    /// Destination directory
+    pub destination: String,
+}
+
+/// Install semantics of a driver INF
+#[derive(Debug, Clone, Default)]
+pub struct DriverInf {
+    pub class: Option<String>,
+    pub class_guid: Option<String>,
+    pub provider: Option<String>,
+    pub driver_date: Option<String>,
+    pub driver_version: Option<String>,
+    /// Catalog files named by `CatalogFile` and its decorated variants
+    pub catalog_files: Vec<String>,
+    pub pnp_lockdown: bool,
+    pub devices: Vec<DriverDevice>,
+    pub architectures: Vec<String>,
+    pub services: Vec<DriverService>,
+    pub files: Vec<DriverFile>,
+    /// Files listed in `[SourceDisksFiles]` and its decorated variants
+    pub source_files: Vec<String>,
+    pub registry_operations: Vec<RegistryOperation>,
+}
+
+impl DriverInf {
+    /// Read the install semantics of a parsed INF
+    pub fn from_inf(inf: &InfFile) -> Self {
+        let mut driver = Self {
+            class: inf.value("Version", "Class"),
+            class_guid: inf.value("Version", "ClassGuid"),
+            provider: inf.value("Version", "Provider"),
+            pnp_lockdown: inf.value("Version", "PnpLockdown").as_deref() == Some("1"),
+            ..Self::default()
+        };
+        if let Some(line) = inf.entries("Version", "DriverVer").next() {
+            driver.driver_date = line.value(0).map(str::to_string);
+            driver.driver_version = line.value(1).map(str::to_string);
+        }
+        for line in inf.section("Version").unwrap_or_default() {
+            let is_catalog = line.key.as_deref().is_some_and(|key| {
+                let key = key.to_lowercase();
+                key == "catalogfile" || key.starts_with("catalogfile.")
+            });
+            if let Some(catalog) = line.value(0).filter(|_| is_catalog) {
+                if !driver
+                    .catalog_files
+                    .iter()
+                    .any(|known| known.eq_ignore_ascii_case(catalog))
+                {
+                    driver.catalog_files.push(catalog.to_string());
+                }
+            }
+        }
+
+        for name in inf.section_names() {
+            if !name.to_lowercase().starts_with("sourcedisksfiles") {
+                continue;
+            }
+            for line in inf.section(name).unwrap_or_default() {
+                if let Some(file) = line.key.as_deref().filter(|file| {
+                    !driver
+                        .source_files
+                        .iter()
+                        .any(|known| known.eq_ignore_ascii_case(file))
+                }) {
+                    driver.source_files.push(file.to_string());
+                }
+            }
+        }
+
+        // Install sections of all devices, with their platform decoration
+        let mut install_sections: Vec<(String, Option<String>)> = Vec::new();
+        for manufacturer in inf.section("Manufacturer").unwrap_or_default() {
+            let Some(models) = manufacturer.value(0) else {
+                continue;
+            };
+            let decorations: Vec<&str> = manufacturer.values[1..]
+                .iter()
+                .map(String::as_str)
+                .filter(|decoration| !decoration.is_empty())
+                .collect();
+            let mut model_sections: Vec<(String, Option<&str>)> = decorations
+                .iter()
+                .map(|decoration| (format!("{}.{}", models, decoration), Some(*decoration)))
+                .collect();
+            model_sections.push((models.to_string(), None));
+
+            for (section, decoration) in model_sections {
+                let architecture = decoration.and_then(architecture_of);
+                if let Some(architecture) = &architecture {
+                    if !driver.architectures.contains(architecture) {
+                        driver.architectures.push(architecture.clone());
+                    }
+                }
+                for model in inf.section(&section).unwrap_or_default() {
+                    let (Some(description), Some(install), Some(hardware_id)) =
+                        (model.key.as_deref(), model.value(0), model.value(1))
+                    else {
+                        continue;
+                    };
+                    let install = ddinstall_section(inf, install, decoration);
+                    driver.devices.push(DriverDevice {
+                        description: description.to_string(),
+                        hardware_id: hardware_id.to_string(),
+                        install_section: install.clone(),
+                        architecture: architecture.clone(),
+                    });
+                    if !install_sections.iter().any(|(known, _)| *known == install) {
+                        install_sections.push((install, architecture.clone()));
+                    }
+                }
+            }
+        }
+        // Drivers without devices, like file system filters, use DefaultInstall
+        if install_sections.is_empty() {
+            for name in inf.section_names() {
+                let lower = name.to_lowercase();
+                if lower == "defaultinstall"
+                    || (lower.starts_with("defaultinstall.")
+                        && !lower.ends_with(".services")
+                        && !lower.ends_with(".hw"))
+                {
+                    let architecture = lower.split('.').nth(1).and_then(architecture_of);
+                    install_sections.push((name.clone(), architecture));
+                }
+            }
+        }
+
+        for (section, _) in &install_sections {
+            driver.read_install_section(inf, section);
+        }
+        driver
+    }
+
+    /// Files, registry values and services of a DDInstall section
+    fn read_install_section(&mut self, inf: &InfFile, section: &str) {
+        for line in inf.entries(section, "CopyFiles") {
+            for list in line.values.iter().filter(|value| !value.is_empty()) {
+                self.read_copy_files(inf, list);
+            }
+        }
+        for suffix in ["", ".HW"] {
+            for line in inf.entries(&format!("{}{}", section, suffix), "AddReg") {
+                for list in line.values.iter().filter(|value| !value.is_empty()) {
+                    self.read_add_reg(inf, list, "HKR");
+                }
+            }
+        }
+        for line in inf.entries(&format!("{}.Services", section), "AddService") {
+            let (Some(name), Some(install)) = (line.value(0), line.value(2)) else {
+                continue;
+            };
+            if self.services.iter().any(|service| service.name == name) {
+                continue;
+            }
+            let number = |key: &str| {
+                inf.value(install, key)
+                    .and_then(|value| parse_number(&value))
+            };
+            let service = DriverService {
+                name: name.to_string(),
+                display_name: inf.value(install, "DisplayName"),
+                service_type: number("ServiceType"),
+                start_type: number("StartType"),
+                error_control: number("ErrorControl"),
+                binary: inf
+                    .value(install, "ServiceBinary")
+                    .map(|path| expand_dirids(&path)),
+            };
+            self.add_service_registry(&service);
+            let key = format!(
+                "HKEY_LOCAL_MACHINE\\SYSTEM\\CurrentControlSet\\Services\\{}",
+                name
+            );
+            for add_reg in inf.entries(install, "AddReg") {
+                for list in add_reg.values.iter().filter(|value| !value.is_empty()) {
+                    self.read_add_reg(inf, list, &key);
+                }
+            }
+            self.services.push(service);
+        }
+    }
+
+    /// Files of a CopyFiles section, or a single `@file`
+    fn read_copy_files(&mut self, inf: &InfFile, list: &str) {
+        if let Some(file) = list.strip_prefix('@') {
+            self.push_file(file, None, destination(inf, None));
+            return;
+        }
+        let directory = destination(inf, Some(list));
+        for line in inf.section(list).unwrap_or_default() {
+            // Lines are `destination[,source[,temporary[,flags]]]`
+            let Some(name) = line.key.as_deref().or_else(|| line.value(0)) else {
+                continue;
+            };
+            let source = if line.key.is_some() {
+                None
+            } else {
+                line.value(1).map(str::to_string)
+            };
+            self.push_file(name, source, directory.clone());
+        }
+    }
+
+    fn push_file(&mut self, name: &str, source: Option<String>, destination: String) {
+        let file = DriverFile {
+            name: name.to_string(),
+            source: source.filter(|source| !source.eq_ignore_ascii_case(name)),
+            destination,
+        };
+        if !self.files.contains(&file) {
+            self.files.push(file);
+        }
+    }
+
+    /// Registry values of an AddReg section, `HKR` standing for `relative_root`
+    fn read_add_reg(&mut self, inf: &InfFile, list: &str, relative_root: &str) {
+        let timestamp = Utc::now();
+        for line in inf.section(list).unwrap_or_default() {
+            // Lines are `root,[subkey],[value name],[flags],[value...]`
+            let values: Vec<&str> = line
+                .key
+                .iter()
+                .map(String::as_str)
+                .chain(line.values.iter().map(String::as_str))
+                .collect();
+            let Some(root) = values.first() else {
+                continue;
+            };
+            let root = match root.to_uppercase().as_str() {
+                "HKCR" => "HKEY_CLASSES_ROOT",
+                "HKCU" => "HKEY_CURRENT_USER",
+                "HKLM" => "HKEY_LOCAL_MACHINE",
+                "HKU" => "HKEY_USERS",
+                "HKR" => relative_root,
+                _ => continue,
+            };
+            let subkey = values.get(1).copied().unwrap_or_default();
+            let key_path = if subkey.is_empty() {
+                root.to_string()
+            } else {
+                format!("{}\\{}", root, subkey)
+            };
+            let name = values.get(2).copied().unwrap_or_default();
+            let flags = values
+                .get(3)
+                .and_then(|flags| parse_number(flags))
+                .unwrap_or(0);
+            let data: Vec<&str> = values.iter().skip(4).copied().collect();
+
+            // FLG_ADDREG_KEYONLY, or a key without value name or data
+            if flags & 0x10 != 0 || (name.is_empty() && data.is_empty()) {
+                self.registry_operations.push(RegistryOperation::CreateKey {
+                    key_path,
+                    timestamp,
+                    process_id: None,
+                });
+                continue;
+            }
+            // FLG_ADDREG_DELVAL
+            if flags & 0x04 != 0 {
+                self.registry_operations
+                    .push(RegistryOperation::DeleteValue {
+                        key_path,
+                        value_name: name.to_string(),
+                        timestamp,
+                        process_id: None,
+                    });
+                continue;
+            }
+            let (value_type, value_data) = registry_value(flags, &data);
+            self.registry_operations.push(RegistryOperation::SetValue {
+                key_path,
+                value_name: name.to_string(),
+                value_type,
+                value_data,
+                timestamp,
+                process_id: None,
+            });
+        }
+    }
+
+    /// Values Windows writes to the service key
+    fn add_service_registry(&mut self, service: &DriverService) {
+        let timestamp = Utc::now();
+        let key_path = format!(
+            "HKEY_LOCAL_MACHINE\\SYSTEM\\CurrentControlSet\\Services\\{}",
+            service.name
+        );
+        self.registry_operations.push(RegistryOperation::CreateKey {
+            key_path: key_path.clone(),
+            timestamp,
+            process_id: None,
+        });
+        let mut set = |name: &str, value_type: RegistryValueType, value_data: RegistryValue| {
+            self.registry_operations.push(RegistryOperation::SetValue {
+                key_path: key_path.clone(),
+                value_name: name.to_string(),
+                value_type,
+                value_data,
+                timestamp,
+                process_id: None,
+            });
+        };
+        if let Some(binary) = &service.binary {
+            set(
+                "ImagePath",
+                RegistryValueType::ExpandString,
+                RegistryValue::String(binary.clone()),
+            );
+        }
+        for (name, value) in [
+            ("Type", service.service_type),
+            ("Start", service.start_type),
+            ("ErrorControl", service.error_control),
+        ] {
+            if let Some(value) = value {
+                set(name, RegistryValueType::DWord, RegistryValue::DWord(value));
+            }
+        }
+        if let Some(display_name) = &service.display_name {
+            set(
+                "DisplayName",
+                RegistryValueType::String,
+                RegistryValue::String(display_name.clone()),
+            );
+        }
+    }
+
+    /// Names of the files the package should contain besides the INF
+    pub fn package_files(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        let copied = self
+            .files
+            .iter()
+            .map(|file| file.source.as_deref().unwrap_or(&file.name));
+        for name in self
+            .catalog_files
+            .iter()
+            .chain(&self.source_files)
+            .map(String::as_str)
+            .chain(copied)
+        {
+            if !names.iter().any(|known| known.eq_ignore_ascii_case(name)) {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    /// Destination of a package file copied by the INF
+    pub fn destination_of(&self, name: &str) -> Option<String> {
+        self.files
+            .iter()
+            .find(|file| {
+                file.source
+                    .as_deref()
+                    .unwrap_or(&file.name)
+                    .eq_ignore_ascii_case(name)
+            })
+            .map(|file| format!("{}\\{}", file.destination, file.name))
+    }
+
+    /// Services as `name (kind, start) binary` descriptions
+    pub fn describe_services(&self) -> String {
+        self.services
+            .iter()
+            .map(DriverService::describe)
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+
+    /// Distinct hardware IDs, up to [`MAX_LISTED_HARDWARE_IDS`]
+    pub fn hardware_ids(&self) -> Vec<&str> {
+        let mut ids: Vec<&str> = Vec::new();
+        for device in &self.devices {
+            if !ids
+                .iter()
+                .any(|id| id.eq_ignore_ascii_case(&device.hardware_id))
+            {
+                ids.push(&device.hardware_id);
+            }
+        }
+        ids.truncate(MAX_LISTED_HARDWARE_IDS);
+        ids
+    }
+}
+
+fn architecture_of(decoration: &str) -> Option<String> {
+    let decoration = decoration.to_lowercase();
+    ARCHITECTURES
+        .iter()
+        .find(|(prefix, _)| decoration.starts_with(prefix))
+        .map(|(_, architecture)| architecture.to_string())
+}
+
+/// DDInstall section Windows picks for a platform: decorated, `.NT`, or plain
+fn ddinstall_section(inf: &InfFile, install: &str, decoration: Option<&str>) -> String {
+    let platform = decoration.and_then(|decoration| {
+        // Model decorations may carry OS versions: NTamd64.10.0...16299
+        decoration.split('.').next()
+    });
+    platform
+        .map(|platform| format!("{}.{}", install, platform))
+        .into_iter()
+        .chain([format!("{}.NT", install)])
+        .find(|section| inf.has_section(section))
+        .unwrap_or_else(|| install.to_string())
+}
+
+/// Destination of a CopyFiles list from `[DestinationDirs]`
+fn destination(inf: &InfFile, list: Option<&str>) -> String {
+    let entry = list
+        .and_then(|list| inf.entries("DestinationDirs", list).next())
+        .or_else(|| inf.entries("DestinationDirs", "DefaultDestDir").next());
+    let Some(entry) = entry else {
+        // Without DestinationDirs files go to the drivers directory
+        return dirid_path(12).to_string();
+    };
+    let base = entry
+        .value(0)
+        .and_then(parse_number)
+        .map(|dirid| dirid_path(dirid).to_string())
+        .unwrap_or_else(|| entry.value(0).unwrap_or_default().to_string());
+    match entry.value(1) {
+        Some(subdirectory) => format!("{}\\{}", base, subdirectory),
+        None => base,
+    }
+}
+
+/// Directory of a well-known INF directory ID
+fn dirid_path(dirid: u32) -> &'static str {
+    match dirid {
+        10 => "%SystemRoot%",
+        11 => "%SystemRoot%\\System32",
+        12 => "%SystemRoot%\\System32\\drivers",
+        13 => "%SystemRoot%\\System32\\DriverStore\\FileRepository",
+        17 => "%SystemRoot%\\INF",
+        18 => "%SystemRoot%\\Help",
+        20 => "%SystemRoot%\\Fonts",
+        24 => "%SystemDrive%",
+        54 => "%SystemDrive%",
+        16422 => "%ProgramFiles%",
+        16426 => "%ProgramFiles(x86)%",
+        16427 => "%CommonProgramFiles%",
+        _ => "%DIRID%",
+    }
+}
+
+/// Replace leading `%10%` style directory IDs of a path
+fn expand_dirids(path: &str) -> String {
+    if let Some(rest) = path.strip_prefix('%') {
+        if let Some((dirid, tail)) = rest.split_once('%') {
+            if let Ok(dirid) = dirid.parse::<u32>() {
+                return format!("{}{}", dirid_path(dirid), tail);
+            }
+        }
+    }
+    path.to_string()
+}
+
+/// Decimal or `0x` hexadecimal number
+fn parse_number(text: &str) -> Option<u32> {
+    let text = text.trim();
+    match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
+
+/// Value of an AddReg line from its `FLG_ADDREG_TYPE_*` flags
+fn registry_value(flags: u32, data: &[&str]) -> (RegistryValueType, RegistryValue) {
+    let text = || data.first().copied().unwrap_or_default().to_string();
+    match flags & 0xffff_0001 {
+        0x0001_0000 => (
+            RegistryValueType::MultiString,
+            RegistryValue::MultiString(data.iter().map(|value| value.to_string()).collect()),
+        ),
+        0x0002_0000 => (
+            RegistryValueType::ExpandString,
+            RegistryValue::String(text()),
+        ),
+        0x0001_0001 => {
+            let value = data.first().and_then(|value| parse_number(value));
+            match value {
+                // DWORDs may also be written as four binary bytes
+                Some(value) if data.len() == 1 => {
+                    (RegistryValueType::DWord, RegistryValue::DWord(value))
+                }
+                _ => {
+                    let bytes = binary_bytes(data);
+                    let mut value = [0u8; 4];
+                    for (target, byte) in value.iter_mut().zip(&bytes) {
+                        *target = *byte;
+                    }
+                    (
+                        RegistryValueType::DWord,
+                        RegistryValue::DWord(u32::from_le_bytes(value)),
+                    )
+                }
+            }
+        }
+        0x0000_0001 | 0x0002_0001 => (
+            RegistryValueType::Binary,
+            RegistryValue::Binary(binary_bytes(data)),
+        ),
+        0x000b_0001 => (
+            RegistryValueType::QWord,
+            RegistryValue::QWord(
+                data.first()
+                    .and_then(|value| value.parse().ok())
+                    .unwrap_or_default(),
+            ),
+        ),
+        _ => (RegistryValueType::String, RegistryValue::String(text())),
+    }
+}
+
+/// Hexadecimal bytes of a binary value
+fn binary_bytes(data: &[&str]) -> Vec<u8> {
+    data.iter()
+        .filter_map(|byte| u8::from_str_radix(byte.trim_start_matches("0x"), 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) const SAMPLE_INF: &str = r#"
+; Sample network adapter driver
+[Version]
+Signature   = "$WINDOWS NT$"
+Class       = Net
+ClassGuid   = {4d36e972-e325-11ce-bfc1-08002be10318}
+Provider    = %ManufacturerName%
+CatalogFile = sample.cat
+DriverVer   = 05/17/2024,2.4.1.0
+PnpLockdown = 1
+
+[DestinationDirs]
+DefaultDestDir = 13
+Sample_CopyFiles = 12
+
+[SourceDisksNames]
+1 = %DiskName%,,,""
+
+[SourceDisksFiles]
+sample.sys = 1
+
+[Manufacturer]
+%ManufacturerName% = Standard,NTamd64,NTarm64
+
+[Standard.NTamd64]
+%Sample.DeviceDesc% = Sample_Device, PCI\VEN_1234&DEV_5678
+%Sample.DeviceDesc% = Sample_Device, PCI\VEN_1234&DEV_5679
+
+[Standard.NTarm64]
+%Sample.DeviceDesc% = Sample_Device, PCI\VEN_1234&DEV_5678
+
+[Sample_Device.NT]
+CopyFiles = Sample_CopyFiles
+AddReg    = Sample_AddReg
+
+[Sample_CopyFiles]
+sample.sys
+
+[Sample_AddReg]
+HKR, Ndi, Service, 0, "Sample"
+HKR, Ndi\params, "Speed", 0x00010001, 1000
+HKLM, "Software\Example Corp\Sample", "Path", 0x00020000, "%%SystemRoot%%\sample"
+
+[Sample_Device.NT.Services]
+AddService = Sample, 0x00000002, Sample_Service
+
+[Sample_Service]
+DisplayName   = %Sample.ServiceDesc%
+ServiceType   = 1               ; SERVICE_KERNEL_DRIVER
+StartType     = 3               ; SERVICE_DEMAND_START
+ErrorControl  = 1
+ServiceBinary = %12%\sample.sys
+
+[Strings]
+ManufacturerName   = "Example Corp"
+Sample.DeviceDesc  = "Example Gigabit Adapter"
+Sample.ServiceDesc = "Example ""Gigabit"" Service"
+DiskName           = "Sample Installation Disk"
+"#;
+
+    #[test]
+    fn test_parse_inf_lines() {
+        let inf = InfFile::parse(SAMPLE_INF);
+        assert_eq!(
+            inf.value("version", "provider").as_deref(),
+            Some("Example Corp")
+        );
+        assert_eq!(
+            inf.value("Strings", "Sample.ServiceDesc").as_deref(),
+            Some("Example \"Gigabit\" Service")
+        );
+        let add_reg = inf.section("Sample_AddReg").unwrap();
+        assert_eq!(add_reg[2].values[4], "%SystemRoot%\\sample");
+        assert_eq!(decode(b"\xff\xfe[\0V\0]\0"), "[V]");
+    }
+
+    #[test]
+    fn test_driver_install_semantics() {
+        let driver = DriverInf::from_inf(&InfFile::parse(SAMPLE_INF));
+        assert_eq!(driver.class.as_deref(), Some("Net"));
+        assert_eq!(driver.driver_version.as_deref(), Some("2.4.1.0"));
+        assert_eq!(driver.driver_date.as_deref(), Some("05/17/2024"));
+        assert_eq!(driver.catalog_files, vec!["sample.cat"]);
+        assert_eq!(driver.package_files(), vec!["sample.cat", "sample.sys"]);
+        assert_eq!(
+            driver.destination_of("SAMPLE.SYS").as_deref(),
+            Some("%SystemRoot%\\System32\\drivers\\sample.sys")
+        );
+        assert!(driver.pnp_lockdown);
+        assert_eq!(driver.architectures, vec!["x64", "arm64"]);
+        assert_eq!(driver.devices.len(), 3);
+        assert_eq!(driver.devices[0].install_section, "Sample_Device.NT");
+        assert_eq!(
+            driver.hardware_ids(),
+            vec!["PCI\\VEN_1234&DEV_5678", "PCI\\VEN_1234&DEV_5679"]
+        );
+
+        assert_eq!(
+            driver.files,
+            vec![DriverFile {
+                name: "sample.sys".to_string(),
+                source: None,
+                destination: "%SystemRoot%\\System32\\drivers".to_string(),
+            }]
+        );
+        assert_eq!(
+            driver.describe_services(),
+            "Sample (kernel driver, demand start) %SystemRoot%\\System32\\drivers\\sample.sys"
+        );
+
+        let values: Vec<(&str, &str)> = driver
+            .registry_operations
+            .iter()
+            .filter_map(|operation| match operation {
+                RegistryOperation::SetValue {
+                    key_path,
+                    value_name,
+                    ..
+                } => Some((key_path.as_str(), value_name.as_str())),
+                _ => None,
+            })
+            .collect();
+        assert!(values.contains(&("HKR\\Ndi\\params", "Speed")));
+        assert!(values.contains(&("HKEY_LOCAL_MACHINE\\Software\\Example Corp\\Sample", "Path")));
+        assert!(values.contains(&(
+            "HKEY_LOCAL_MACHINE\\SYSTEM\\CurrentControlSet\\Services\\Sample",
+            "ImagePath"
+        )));
+    }
+}
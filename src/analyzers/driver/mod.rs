@@ -0,0 +1,9 @@
+//! Driver package analyzer: INF, catalog and binaries, loose or in a cabinet
+
+pub mod analyzer;
+pub mod catalog;
+pub mod inf;
+
+pub use analyzer::DriverPackageAnalyzer;
+pub use catalog::{CatalogCheck, CatalogMember};
+pub use inf::{DriverDevice, DriverFile, DriverInf, DriverService, InfFile};
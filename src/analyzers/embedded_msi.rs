@@ -0,0 +1,395 @@
+//! MSI packages embedded in EXE wrappers
+//!
+//! Many setup executables are bootstrappers around a single MSI package:
+//! WiX Burn bundles, InstallShield Basic MSI setups and Advanced Installer
+//! EXEs. The package does the actual install, so it is the more useful
+//! artifact to analyze. Packages are found as packaged `.msi` entries the
+//! wrapper's analyzer can read, or carved from the wrapper as compound files
+//! carrying the Windows Installer class ID.
+
+use crate::analyzers::InstallerAnalyzer;
+use crate::core::{AnalysisResult, FileEntry, InstallerFormat, Result};
+use crate::utils::{entry_file_name, file_name_component, format_file_size};
+use std::path::{Path, PathBuf};
+
+/// Largest wrapper that is scanned
+const MAX_SCAN_SIZE: u64 = 512 * 1024 * 1024;
+/// Compound file signature
+const COMPOUND_FILE_SIGNATURE: [u8; 8] = [0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+/// Root storage class ID of an installation package, {000C1084-0000-0000-C000-000000000046}
+///
+/// Transforms and patches use different class IDs and are not packages.
+const MSI_PACKAGE_CLSID: [u8; 16] = [
+    0x84, 0x10, 0x0C, 0x00, 0x00, 0x00, 0x00, 0x00, 0xC0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46,
+];
+/// Entries of the FAT sector list kept in the header
+const HEADER_DIFAT_ENTRIES: usize = 109;
+/// Sector IDs at or above this value are markers, not sectors
+const MAX_REGULAR_SECTOR: u32 = 0xFFFF_FFFA;
+const FREE_SECTOR: u32 = 0xFFFF_FFFF;
+
+/// Where an embedded package was found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EmbeddedMsiSource {
+    /// Entry of the wrapper's file list
+    Packaged(PathBuf),
+    /// Compound file carved from the wrapper at this offset
+    Carved(u64),
+}
+
+impl std::fmt::Display for EmbeddedMsiSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Packaged(path) => write!(f, "{}", path.display()),
+            Self::Carved(offset) => write!(f, "0x{:x}", offset),
+        }
+    }
+}
+
+/// MSI package found in a wrapper
+#[derive(Debug, Clone)]
+pub struct EmbeddedMsi {
+    /// File name the package is extracted under
+    pub name: String,
+    pub source: EmbeddedMsiSource,
+    pub content: Vec<u8>,
+}
+
+impl EmbeddedMsi {
+    pub fn size(&self) -> u64 {
+        self.content.len() as u64
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "{} at {} ({})",
+            self.name,
+            self.source,
+            format_file_size(self.size())
+        )
+    }
+}
+
+/// MSI packages embedded in an installer
+#[derive(Debug, Clone, Default)]
+pub struct EmbeddedMsiReport {
+    pub packages: Vec<EmbeddedMsi>,
+}
+
+impl EmbeddedMsiReport {
+    /// Find the MSI packages of a wrapper
+    ///
+    /// An installer that is a compound file itself is a package, not a
+    /// wrapper, and has none.
+    pub async fn detect(
+        analyzer: &dyn InstallerAnalyzer,
+        file_path: &Path,
+        files: &[FileEntry],
+    ) -> Result<Self> {
+        let size = tokio::fs::metadata(file_path).await?.len();
+        if size > MAX_SCAN_SIZE {
+            tracing::info!(
+                "Skipping embedded MSI scan of {} ({})",
+                file_path.display(),
+                format_file_size(size)
+            );
+            return Ok(Self::default());
+        }
+        let data = tokio::fs::read(file_path).await?;
+        if data.starts_with(&COMPOUND_FILE_SIGNATURE) {
+            return Ok(Self::default());
+        }
+
+        let mut packages = Vec::new();
+        let candidates = files.iter().filter(|file| {
+            !file.is_speculative
+                && file.size <= MAX_SCAN_SIZE
+                && file
+                    .path
+                    .extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("msi"))
+        });
+        for file in candidates {
+            match analyzer.read_file_content(file_path, &file.path).await {
+                Ok(Some(content)) if is_msi_package(&content) => packages.push(EmbeddedMsi {
+                    name: file_name_component(&entry_file_name(&file.path)),
+                    source: EmbeddedMsiSource::Packaged(file.path.clone()),
+                    content,
+                }),
+                Ok(_) => {}
+                Err(e) => tracing::debug!("Failed to read {}: {}", file.path.display(), e),
+            }
+        }
+
+        for (start, end) in scan(&data) {
+            let content = &data[start..end];
+            // Packages stored uncompressed are listed and carved alike
+            if packages.iter().any(|package| package.content == content) {
+                continue;
+            }
+            packages.push(EmbeddedMsi {
+                name: format!("embedded_0x{:x}.msi", start),
+                source: EmbeddedMsiSource::Carved(start as u64),
+                content: content.to_vec(),
+            });
+        }
+
+        Ok(Self { packages })
+    }
+
+    /// Package analyzed in place of the wrapper, the largest one
+    ///
+    /// Bootstrappers chaining a redistributable carry the product package
+    /// next to smaller runtime packages.
+    pub fn primary(&self) -> Option<&EmbeddedMsi> {
+        self.packages.iter().max_by_key(|package| package.size())
+    }
+
+    /// Write the packages to `output_dir`, returning the path of the primary one
+    pub async fn save(&self, output_dir: &Path) -> Result<Option<PathBuf>> {
+        tokio::fs::create_dir_all(output_dir).await?;
+        let primary = self.primary().map(|package| package.source.clone());
+        let mut primary_path = None;
+        for (index, package) in self.packages.iter().enumerate() {
+            // Packages of different sources may share a name
+            let taken = self.packages[..index]
+                .iter()
+                .any(|other| other.name.eq_ignore_ascii_case(&package.name));
+            let name = if taken {
+                format!("{}_{}", index, package.name)
+            } else {
+                package.name.clone()
+            };
+            let path = output_dir.join(name);
+            tokio::fs::write(&path, &package.content).await?;
+            if primary.as_ref() == Some(&package.source) {
+                primary_path = Some(path);
+            }
+        }
+        Ok(primary_path)
+    }
+
+    /// Record the packages as analysis properties
+    pub fn insert_properties(&self, result: &mut AnalysisResult) {
+        if self.packages.is_empty() {
+            return;
+        }
+
+        let descriptions: Vec<String> = self.packages.iter().map(EmbeddedMsi::describe).collect();
+        let properties = &mut result.metadata.properties;
+        properties.insert(
+            "embedded_msi_count".to_string(),
+            self.packages.len().to_string(),
+        );
+        properties.insert("embedded_msis".to_string(), descriptions.join("; "));
+    }
+
+    /// Record the wrapper of an analyzed primary package as its container
+    pub fn insert_container_properties(
+        &self,
+        result: &mut AnalysisResult,
+        container: &Path,
+        container_format: &InstallerFormat,
+    ) {
+        self.insert_properties(result);
+        let Some(primary) = self.primary() else {
+            return;
+        };
+
+        let properties = &mut result.metadata.properties;
+        properties.insert("container_file".to_string(), entry_file_name(container));
+        properties.insert(
+            "container_format".to_string(),
+            container_format.name().to_string(),
+        );
+        properties.insert("unwrapped_msi".to_string(), primary.name.clone());
+    }
+}
+
+/// Whether `data` is an installation package
+fn is_msi_package(data: &[u8]) -> bool {
+    package_region(data, 0).is_some()
+}
+
+/// Regions of installation packages embedded in `data` after its start
+fn scan(data: &[u8]) -> Vec<(usize, usize)> {
+    let mut regions: Vec<(usize, usize)> = Vec::new();
+    let mut offset = 1;
+    while let Some(found) = find(&data[offset..], &COMPOUND_FILE_SIGNATURE) {
+        let start = offset + found;
+        match package_region(data, start) {
+            Some(end) => {
+                regions.push((start, end));
+                offset = end;
+            }
+            None => offset = start + 1,
+        }
+    }
+    regions
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// End of the installation package starting at `start`
+///
+/// A compound file is sized by its last allocated sector, found in the
+/// sector allocation table; the root storage class ID tells packages from
+/// transforms, patches and Office documents.
+fn package_region(data: &[u8], start: usize) -> Option<usize> {
+    let file = data.get(start..)?;
+    let header = file.get(..512)?;
+    if header[..8] != COMPOUND_FILE_SIGNATURE || read_u16(header, 0x1C) != 0xFFFE {
+        return None;
+    }
+    let sector_size = match (read_u16(header, 0x1A), read_u16(header, 0x1E)) {
+        (3, 9) => 512,
+        (4, 12) => 4096,
+        _ => return None,
+    };
+    let sector = |id: u32| -> Option<&[u8]> {
+        let offset = (id as usize + 1).checked_mul(sector_size)?;
+        file.get(offset..offset.checked_add(sector_size)?)
+    };
+
+    // The header lists the first FAT sectors, a DIFAT chain the remaining ones
+    let fat_sector_count = read_u32(header, 0x2C) as usize;
+    let mut fat_sectors: Vec<u32> = (0..HEADER_DIFAT_ENTRIES.min(fat_sector_count))
+        .map(|index| read_u32(header, 0x4C + index * 4))
+        .collect();
+    let mut difat_sector = read_u32(header, 0x44);
+    while fat_sectors.len() < fat_sector_count && difat_sector < MAX_REGULAR_SECTOR {
+        let entries = sector(difat_sector)?;
+        let per_sector = sector_size / 4 - 1;
+        fat_sectors.extend(
+            (0..per_sector)
+                .map(|index| read_u32(entries, index * 4))
+                .take(fat_sector_count - fat_sectors.len()),
+        );
+        difat_sector = read_u32(entries, per_sector * 4);
+    }
+    if fat_sectors.len() < fat_sector_count {
+        return None;
+    }
+
+    let mut last_allocated = None;
+    for (fat_index, &fat_sector) in fat_sectors.iter().enumerate() {
+        let entries = sector(fat_sector)?;
+        for index in 0..sector_size / 4 {
+            if read_u32(entries, index * 4) != FREE_SECTOR {
+                last_allocated = Some(fat_index * sector_size / 4 + index);
+            }
+        }
+    }
+    let size = (last_allocated? + 2).checked_mul(sector_size)?;
+    if size > file.len() {
+        return None;
+    }
+
+    let root = sector(read_u32(header, 0x30))?;
+    (root[0x50..0x60] == MSI_PACKAGE_CLSID).then_some(start + size)
+}
+
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzers::ArchiveAnalyzer;
+    use std::io::Write;
+
+    /// Compound file of a header, one FAT sector and one directory sector
+    fn compound_file(clsid: [u8; 16]) -> Vec<u8> {
+        let mut data = vec![0u8; 3 * 512];
+        data[..8].copy_from_slice(&COMPOUND_FILE_SIGNATURE);
+        data[0x18..0x1A].copy_from_slice(&0x3Eu16.to_le_bytes());
+        data[0x1A..0x1C].copy_from_slice(&3u16.to_le_bytes());
+        data[0x1C..0x1E].copy_from_slice(&0xFFFEu16.to_le_bytes());
+        data[0x1E..0x20].copy_from_slice(&9u16.to_le_bytes());
+        data[0x20..0x22].copy_from_slice(&6u16.to_le_bytes());
+        data[0x2C..0x30].copy_from_slice(&1u32.to_le_bytes());
+        data[0x30..0x34].copy_from_slice(&1u32.to_le_bytes());
+        data[0x38..0x3C].copy_from_slice(&4096u32.to_le_bytes());
+        data[0x3C..0x40].copy_from_slice(&0xFFFF_FFFEu32.to_le_bytes());
+        data[0x44..0x48].copy_from_slice(&0xFFFF_FFFEu32.to_le_bytes());
+        data[0x4C..0x200].fill(0xFF);
+        data[0x4C..0x50].copy_from_slice(&0u32.to_le_bytes());
+
+        // Sector 0 holds the FAT, sector 1 the directory
+        let fat = &mut data[512..1024];
+        fat.fill(0xFF);
+        fat[..4].copy_from_slice(&0xFFFF_FFFDu32.to_le_bytes());
+        fat[4..8].copy_from_slice(&0xFFFF_FFFEu32.to_le_bytes());
+        let root = &mut data[1024..1536];
+        for (index, unit) in "Root Entry".encode_utf16().enumerate() {
+            root[index * 2..index * 2 + 2].copy_from_slice(&unit.to_le_bytes());
+        }
+        root[0x40..0x42].copy_from_slice(&22u16.to_le_bytes());
+        root[0x42] = 5;
+        root[0x50..0x60].copy_from_slice(&clsid);
+        data
+    }
+
+    #[test]
+    fn test_scan_embedded_packages() {
+        let package = compound_file(MSI_PACKAGE_CLSID);
+        assert!(is_msi_package(&package));
+
+        // A transform has a different class ID
+        let mut transform_clsid = MSI_PACKAGE_CLSID;
+        transform_clsid[0] = 0x82;
+        let transform = compound_file(transform_clsid);
+        assert!(!is_msi_package(&transform));
+
+        let mut wrapper = b"MZ bootstrapper stub".to_vec();
+        wrapper.extend(&transform);
+        let offset = wrapper.len();
+        wrapper.extend(&package);
+        wrapper.extend(b"trailing overlay");
+        assert_eq!(scan(&wrapper), vec![(offset, offset + package.len())]);
+
+        // A package cut short is not carved
+        assert!(scan(&wrapper[..offset + 1024]).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_detect_and_save_packages() {
+        let package = compound_file(MSI_PACKAGE_CLSID);
+        let mut wrapper = tempfile::NamedTempFile::new().unwrap();
+        wrapper.write_all(b"MZ setup stub").unwrap();
+        wrapper.write_all(&package).unwrap();
+
+        let analyzer = ArchiveAnalyzer::new();
+        let report = EmbeddedMsiReport::detect(&analyzer, wrapper.path(), &[])
+            .await
+            .unwrap();
+        assert_eq!(report.packages.len(), 1);
+        assert_eq!(report.packages[0].name, "embedded_0xd.msi");
+        assert_eq!(report.packages[0].source, EmbeddedMsiSource::Carved(0xd));
+
+        let dir = tempfile::tempdir().unwrap();
+        let primary = report.save(dir.path()).await.unwrap().unwrap();
+        assert_eq!(primary, dir.path().join("embedded_0xd.msi"));
+        assert_eq!(std::fs::read(&primary).unwrap(), package);
+
+        // The package itself is not a wrapper
+        let report = EmbeddedMsiReport::detect(&analyzer, &primary, &[])
+            .await
+            .unwrap();
+        assert!(report.packages.is_empty());
+    }
+}
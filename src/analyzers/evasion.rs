@@ -0,0 +1,277 @@
+//! Detection of anti-VM and anti-sandbox checks
+//!
+//! Installers that refuse to run, or behave differently, in analysis
+//! environments reveal themselves through the artifacts they look for: guest
+//! tools processes and drivers, hypervisor CPUID vendor strings, sandbox
+//! DLLs, analysis tools, VM MAC address prefixes and well-known sandbox
+//! account names. API usage such as remote debugger checks and hardware WMI
+//! queries is weaker evidence since legitimate software uses it too.
+
+use crate::analyzers::architecture::is_pe_payload;
+use crate::analyzers::common::scanner::SourceScanner;
+use crate::core::AnalysisResult;
+use regex::bytes::Regex;
+use std::fmt;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Class of an anti-analysis indicator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EvasionCategory {
+    VmArtifact,
+    HypervisorVendor,
+    SandboxArtifact,
+    AnalysisTool,
+    VmMacPrefix,
+    SandboxIdentity,
+    DebuggerCheck,
+    HardwareQuery,
+}
+
+impl EvasionCategory {
+    /// Display name of the category
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::VmArtifact => "VM artifact",
+            Self::HypervisorVendor => "Hypervisor CPUID vendor",
+            Self::SandboxArtifact => "Sandbox artifact",
+            Self::AnalysisTool => "Analysis tool",
+            Self::VmMacPrefix => "VM MAC prefix",
+            Self::SandboxIdentity => "Sandbox machine/user name",
+            Self::DebuggerCheck => "Debugger check",
+            Self::HardwareQuery => "Hardware query",
+        }
+    }
+
+    /// Whether the category alone points to an anti-analysis check
+    pub fn is_strong(&self) -> bool {
+        !matches!(self, Self::DebuggerCheck | Self::HardwareQuery)
+    }
+}
+
+impl fmt::Display for EvasionCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Strings that indicate anti-analysis checks, by category
+const EVASION_STRINGS: &[(&str, EvasionCategory)] = &[
+    ("VBoxService", EvasionCategory::VmArtifact),
+    ("VBoxTray", EvasionCategory::VmArtifact),
+    ("VBoxGuest", EvasionCategory::VmArtifact),
+    ("VBoxMouse", EvasionCategory::VmArtifact),
+    ("VBOX__", EvasionCategory::VmArtifact),
+    ("VirtualBox Guest Additions", EvasionCategory::VmArtifact),
+    ("vmtoolsd", EvasionCategory::VmArtifact),
+    ("vmwaretray", EvasionCategory::VmArtifact),
+    ("vmhgfs", EvasionCategory::VmArtifact),
+    ("vmmouse", EvasionCategory::VmArtifact),
+    ("VMware Tools", EvasionCategory::VmArtifact),
+    ("qemu-ga", EvasionCategory::VmArtifact),
+    ("prl_tools", EvasionCategory::VmArtifact),
+    ("vmsrvc", EvasionCategory::VmArtifact),
+    ("xenservice", EvasionCategory::VmArtifact),
+    ("VMwareVMware", EvasionCategory::HypervisorVendor),
+    ("KVMKVMKVM", EvasionCategory::HypervisorVendor),
+    ("XenVMMXenVMM", EvasionCategory::HypervisorVendor),
+    ("VBoxVBoxVBox", EvasionCategory::HypervisorVendor),
+    ("TCGTCGTCGTCG", EvasionCategory::HypervisorVendor),
+    ("prl hyperv", EvasionCategory::HypervisorVendor),
+    ("SbieDll.dll", EvasionCategory::SandboxArtifact),
+    ("cuckoomon", EvasionCategory::SandboxArtifact),
+    ("api_log.dll", EvasionCategory::SandboxArtifact),
+    ("dir_watch.dll", EvasionCategory::SandboxArtifact),
+    ("snxhk.dll", EvasionCategory::SandboxArtifact),
+    ("cmdvrt32.dll", EvasionCategory::SandboxArtifact),
+    ("wine_get_unix_file_name", EvasionCategory::SandboxArtifact),
+    ("ollydbg.exe", EvasionCategory::AnalysisTool),
+    ("x64dbg.exe", EvasionCategory::AnalysisTool),
+    ("x32dbg.exe", EvasionCategory::AnalysisTool),
+    ("idaq.exe", EvasionCategory::AnalysisTool),
+    ("wireshark.exe", EvasionCategory::AnalysisTool),
+    ("procmon.exe", EvasionCategory::AnalysisTool),
+    ("Procmon64.exe", EvasionCategory::AnalysisTool),
+    ("processhacker.exe", EvasionCategory::AnalysisTool),
+    ("fiddler.exe", EvasionCategory::AnalysisTool),
+    ("00:0C:29", EvasionCategory::VmMacPrefix),
+    ("00:50:56", EvasionCategory::VmMacPrefix),
+    ("08:00:27", EvasionCategory::VmMacPrefix),
+    ("00:05:69", EvasionCategory::VmMacPrefix),
+    ("TEQUILABOOMBOOM", EvasionCategory::SandboxIdentity),
+    ("MALTEST", EvasionCategory::SandboxIdentity),
+    ("CurrentUser-PC", EvasionCategory::SandboxIdentity),
+    ("CheckRemoteDebuggerPresent", EvasionCategory::DebuggerCheck),
+    ("NtQueryInformationProcess", EvasionCategory::DebuggerCheck),
+    ("Win32_ComputerSystem", EvasionCategory::HardwareQuery),
+    ("Win32_BIOS", EvasionCategory::HardwareQuery),
+    ("Win32_DiskDrive", EvasionCategory::HardwareQuery),
+    ("SystemBiosVersion", EvasionCategory::HardwareQuery),
+];
+
+/// How likely the installer checks for an analysis environment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EvasionVerdict {
+    None,
+    Possible,
+    Likely,
+}
+
+impl EvasionVerdict {
+    /// Value used in analysis properties
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Possible => "possible",
+            Self::Likely => "likely",
+        }
+    }
+}
+
+impl fmt::Display for EvasionVerdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// String found in the installer or one of its payloads
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvasionIndicator {
+    pub category: EvasionCategory,
+    pub pattern: &'static str,
+    /// `installer` or the path of the packaged file
+    pub source: String,
+}
+
+/// Anti-analysis indicators of an installer
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EvasionReport {
+    pub indicators: Vec<EvasionIndicator>,
+}
+
+impl EvasionReport {
+    /// Scan content of one source
+    pub fn scan(source: &str, data: &[u8]) -> Self {
+        let mut report = Self::default();
+        report.add_source(source, data);
+        report
+    }
+
+    /// Verdict from the indicators: two strong ones make a check likely
+    pub fn verdict(&self) -> EvasionVerdict {
+        let strong = self
+            .indicators
+            .iter()
+            .filter(|indicator| indicator.category.is_strong())
+            .count();
+        match (strong, self.indicators.len()) {
+            (2.., _) => EvasionVerdict::Likely,
+            (_, 1..) => EvasionVerdict::Possible,
+            _ => EvasionVerdict::None,
+        }
+    }
+
+    /// Record the verdict and its evidence as analysis properties
+    pub fn insert_properties(&self, result: &mut AnalysisResult) {
+        let properties = &mut result.metadata.properties;
+        properties.insert(
+            "anti_analysis_checks".to_string(),
+            self.verdict().as_str().to_string(),
+        );
+        if self.indicators.is_empty() {
+            return;
+        }
+
+        let mut indicators: Vec<&EvasionIndicator> = self.indicators.iter().collect();
+        indicators.sort_by_key(|indicator| indicator.category);
+        let descriptions: Vec<String> = indicators
+            .iter()
+            .take(15)
+            .map(|indicator| {
+                format!(
+                    "{}: {} ({})",
+                    indicator.category, indicator.pattern, indicator.source
+                )
+            })
+            .collect();
+        properties.insert(
+            "anti_analysis_indicators".to_string(),
+            descriptions.join("; "),
+        );
+    }
+}
+
+impl SourceScanner for EvasionReport {
+    fn wants(&self, path: &Path) -> bool {
+        is_pe_payload(path)
+    }
+
+    fn add_source(&mut self, source: &str, data: &[u8]) {
+        for found in matcher().find_iter(data) {
+            let text: String = found
+                .as_bytes()
+                .iter()
+                .filter(|&&byte| byte != 0)
+                .map(|&byte| byte as char)
+                .collect();
+            let Some((pattern, category)) = EVASION_STRINGS.iter().find(|(p, _)| *p == text) else {
+                continue;
+            };
+            if !self
+                .indicators
+                .iter()
+                .any(|indicator| indicator.pattern == *pattern)
+            {
+                self.indicators.push(EvasionIndicator {
+                    category: *category,
+                    pattern,
+                    source: source.to_string(),
+                });
+            }
+        }
+    }
+}
+
+/// Matcher of all indicator strings in ASCII and UTF-16LE
+fn matcher() -> &'static Regex {
+    static MATCHER: OnceLock<Regex> = OnceLock::new();
+    MATCHER.get_or_init(|| {
+        let alternatives: Vec<String> = EVASION_STRINGS
+            .iter()
+            .flat_map(|(pattern, _)| {
+                let wide: String = pattern.chars().flat_map(|c| [c, '\0']).collect();
+                [regex::escape(pattern), regex::escape(&wide)]
+            })
+            .collect();
+        Regex::new(&format!("(?-u){}", alternatives.join("|"))).expect("valid evasion pattern")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_anti_analysis_checks() {
+        let clean = EvasionReport::scan("installer", b"MZ ordinary setup, IsDebuggerPresent");
+        assert_eq!(clean.verdict(), EvasionVerdict::None);
+
+        let weak = EvasionReport::scan("installer", b"SELECT * FROM Win32_ComputerSystem");
+        assert_eq!(weak.verdict(), EvasionVerdict::Possible);
+
+        let mut data = b"\0VMwareVMware\0SbieDll.dll\0".to_vec();
+        data.extend("VBoxService.exe".encode_utf16().flat_map(u16::to_le_bytes));
+        let report = EvasionReport::scan("bin/check.dll", &data);
+        assert_eq!(report.verdict(), EvasionVerdict::Likely);
+        let categories: Vec<EvasionCategory> =
+            report.indicators.iter().map(|i| i.category).collect();
+        assert_eq!(
+            categories,
+            vec![
+                EvasionCategory::HypervisorVendor,
+                EvasionCategory::SandboxArtifact,
+                EvasionCategory::VmArtifact
+            ]
+        );
+    }
+}
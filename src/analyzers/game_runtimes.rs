@@ -0,0 +1,560 @@
+//! Detection of game runtimes, platform SDKs and anti-cheat software
+//!
+//! Game installers chain runtime redistributables (DirectX, Visual C++,
+//! PhysX, OpenAL), ship the SDKs of the store they were built for and often
+//! install anti-cheat software. Components are recognized by their file
+//! names in the package, by the services they register and by their names
+//! embedded in the installer. Anti-cheat products with a kernel-mode driver
+//! are flagged explicitly since the driver runs with full system privileges
+//! and is loaded whenever the game starts.
+
+use crate::analyzers::common;
+use crate::core::{AnalysisResult, RegistryOperation, RegistryValue, Result};
+use regex::bytes::Regex;
+use std::fmt;
+use std::path::Path;
+
+/// Distinct component names taken from installer strings
+const MAX_STRING_MATCHES: usize = 64;
+
+/// Kind of game ecosystem component
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum GameComponentKind {
+    /// Redistributable runtime installed next to the game
+    Runtime,
+    /// Store or launcher SDK the game is built against
+    Platform,
+    /// Anti-cheat client, service or driver
+    AntiCheat,
+}
+
+impl GameComponentKind {
+    /// Display name of the kind
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Runtime => "Runtime",
+            Self::Platform => "Platform",
+            Self::AntiCheat => "Anti-cheat",
+        }
+    }
+}
+
+impl fmt::Display for GameComponentKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// File names of components (lowercase), with their kind and product
+///
+/// Names ending in `*` match as prefixes.
+const COMPONENT_FILES: &[(&str, GameComponentKind, &str)] = &[
+    (
+        "dxsetup.exe",
+        GameComponentKind::Runtime,
+        "DirectX End-User Runtime",
+    ),
+    (
+        "dxwebsetup.exe",
+        GameComponentKind::Runtime,
+        "DirectX End-User Runtime",
+    ),
+    (
+        "dxupdate.cab",
+        GameComponentKind::Runtime,
+        "DirectX End-User Runtime",
+    ),
+    ("vcredist_x86.exe", GameComponentKind::Runtime, VCREDIST),
+    ("vcredist_x64.exe", GameComponentKind::Runtime, VCREDIST),
+    ("vc_redist.x86.exe", GameComponentKind::Runtime, VCREDIST),
+    ("vc_redist.x64.exe", GameComponentKind::Runtime, VCREDIST),
+    ("vc_redist.arm64.exe", GameComponentKind::Runtime, VCREDIST),
+    ("physx*", GameComponentKind::Runtime, "NVIDIA PhysX"),
+    ("oalinst.exe", GameComponentKind::Runtime, "OpenAL"),
+    (
+        "xnafx40_redist.msi",
+        GameComponentKind::Runtime,
+        "XNA Framework 4.0",
+    ),
+    (
+        "ue4prereqsetup_x64.exe",
+        GameComponentKind::Runtime,
+        "Unreal Engine Prerequisites",
+    ),
+    (
+        "ueprereqsetup_x64.exe",
+        GameComponentKind::Runtime,
+        "Unreal Engine Prerequisites",
+    ),
+    ("steam_api.dll", GameComponentKind::Platform, "Steamworks"),
+    ("steam_api64.dll", GameComponentKind::Platform, "Steamworks"),
+    (
+        "installscript.vdf",
+        GameComponentKind::Platform,
+        "Steamworks",
+    ),
+    (
+        "eossdk-win64-shipping.dll",
+        GameComponentKind::Platform,
+        "Epic Online Services",
+    ),
+    (
+        "eossdk-win32-shipping.dll",
+        GameComponentKind::Platform,
+        "Epic Online Services",
+    ),
+    ("galaxy.dll", GameComponentKind::Platform, "GOG Galaxy"),
+    ("galaxy64.dll", GameComponentKind::Platform, "GOG Galaxy"),
+    (
+        "uplay_r1_loader.dll",
+        GameComponentKind::Platform,
+        "Ubisoft Connect",
+    ),
+    (
+        "uplay_r1_loader64.dll",
+        GameComponentKind::Platform,
+        "Ubisoft Connect",
+    ),
+    (
+        "upc_r2_loader64.dll",
+        GameComponentKind::Platform,
+        "Ubisoft Connect",
+    ),
+    (
+        "easyanticheat_setup.exe",
+        GameComponentKind::AntiCheat,
+        EASY_ANTI_CHEAT,
+    ),
+    (
+        "easyanticheat_eos_setup.exe",
+        GameComponentKind::AntiCheat,
+        EASY_ANTI_CHEAT,
+    ),
+    (
+        "easyanticheat_x64.dll",
+        GameComponentKind::AntiCheat,
+        EASY_ANTI_CHEAT,
+    ),
+    (
+        "easyanticheat_x86.dll",
+        GameComponentKind::AntiCheat,
+        EASY_ANTI_CHEAT,
+    ),
+    (
+        "easyanticheat.sys",
+        GameComponentKind::AntiCheat,
+        EASY_ANTI_CHEAT,
+    ),
+    (
+        "easyanticheat_eos.sys",
+        GameComponentKind::AntiCheat,
+        EASY_ANTI_CHEAT,
+    ),
+    ("beservice.exe", GameComponentKind::AntiCheat, BATTLEYE),
+    ("beservice_x64.exe", GameComponentKind::AntiCheat, BATTLEYE),
+    ("beclient.dll", GameComponentKind::AntiCheat, BATTLEYE),
+    ("beclient_x64.dll", GameComponentKind::AntiCheat, BATTLEYE),
+    ("bedaisy.sys", GameComponentKind::AntiCheat, BATTLEYE),
+    ("vgk.sys", GameComponentKind::AntiCheat, "Riot Vanguard"),
+    ("vgc.exe", GameComponentKind::AntiCheat, "Riot Vanguard"),
+    ("xhunter1.sys", GameComponentKind::AntiCheat, "XIGNCODE3"),
+    (
+        "gameguard.des",
+        GameComponentKind::AntiCheat,
+        "nProtect GameGuard",
+    ),
+    ("faceit.sys", GameComponentKind::AntiCheat, "FACEIT"),
+    ("faceitservice.exe", GameComponentKind::AntiCheat, "FACEIT"),
+    ("mhyprot2.sys", GameComponentKind::AntiCheat, "mhyprot"),
+    ("mhyprot3.sys", GameComponentKind::AntiCheat, "mhyprot"),
+    ("pnkbstra.exe", GameComponentKind::AntiCheat, "PunkBuster"),
+    ("pnkbstrb.exe", GameComponentKind::AntiCheat, "PunkBuster"),
+];
+
+const VCREDIST: &str = "Visual C++ Redistributable";
+const EASY_ANTI_CHEAT: &str = "EasyAntiCheat";
+const BATTLEYE: &str = "BattlEye";
+
+/// Anti-cheat products that load a kernel-mode driver
+const KERNEL_ANTI_CHEAT: [&str; 7] = [
+    EASY_ANTI_CHEAT,
+    BATTLEYE,
+    "Riot Vanguard",
+    "XIGNCODE3",
+    "nProtect GameGuard",
+    "FACEIT",
+    "mhyprot",
+];
+
+/// Service names of anti-cheat software (lowercase), with their product
+const ANTI_CHEAT_SERVICES: &[(&str, &str)] = &[
+    ("easyanticheat", EASY_ANTI_CHEAT),
+    ("easyanticheat_eos", EASY_ANTI_CHEAT),
+    ("beservice", BATTLEYE),
+    ("bedaisy", BATTLEYE),
+    ("vgk", "Riot Vanguard"),
+    ("vgc", "Riot Vanguard"),
+    ("xhunter1", "XIGNCODE3"),
+    ("faceit", "FACEIT"),
+    ("mhyprot2", "mhyprot"),
+    ("mhyprot3", "mhyprot"),
+];
+
+/// Component found in the installer, with the evidence for it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameComponent {
+    pub kind: GameComponentKind,
+    pub product: &'static str,
+    /// File names, service keys or strings the component was recognized by
+    pub evidence: Vec<String>,
+    /// Kernel-mode drivers of the component that are packaged or registered
+    pub drivers: Vec<String>,
+}
+
+impl GameComponent {
+    /// Whether the product loads a kernel-mode anti-cheat driver
+    pub fn is_kernel_anti_cheat(&self) -> bool {
+        self.kind == GameComponentKind::AntiCheat && KERNEL_ANTI_CHEAT.contains(&self.product)
+    }
+
+    fn describe(&self) -> String {
+        format!("{} ({})", self.product, self.evidence.join(", "))
+    }
+}
+
+/// Game runtimes, platform SDKs and anti-cheat software of an installer
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GameRuntimeReport {
+    pub components: Vec<GameComponent>,
+}
+
+impl GameRuntimeReport {
+    /// Inspect an analysis result and the strings of the installer it came from
+    pub async fn detect(file_path: &Path, result: &AnalysisResult) -> Result<Self> {
+        let mut report = Self::from_result(result);
+        let strings =
+            common::search_file_matches(file_path, &Self::matcher(), MAX_STRING_MATCHES).await?;
+        for found in strings {
+            report.add_file_name(&found, &format!("\"{}\"", found));
+        }
+        Ok(report)
+    }
+
+    /// Components among packaged files and registered services
+    pub fn from_result(result: &AnalysisResult) -> Self {
+        let mut report = Self::default();
+        for file in &result.files {
+            let path = file.path.to_string_lossy().replace('/', "\\");
+            let name = path.rsplit('\\').next().unwrap_or(&path);
+            report.add_file_name(name, name);
+        }
+        report.add_registry_operations(&result.registry_operations);
+        report
+    }
+
+    /// Case-insensitive matcher of the exact component file names
+    fn matcher() -> Regex {
+        let alternatives: Vec<String> = COMPONENT_FILES
+            .iter()
+            .filter(|(name, _, _)| !name.ends_with('*'))
+            .map(|(name, _, _)| regex::escape(name))
+            .collect();
+        Regex::new(&format!(r"(?i-u)\b(?:{})\b", alternatives.join("|")))
+            .expect("valid component pattern")
+    }
+
+    /// Record the component a file name belongs to, if any
+    fn add_file_name(&mut self, name: &str, evidence: &str) {
+        let lower = name.to_lowercase();
+        let Some((pattern, kind, product)) =
+            COMPONENT_FILES
+                .iter()
+                .find(|(pattern, _, _)| match pattern.strip_suffix('*') {
+                    Some(prefix) => {
+                        lower.starts_with(prefix)
+                            && (lower.ends_with(".exe") || lower.ends_with(".msi"))
+                    }
+                    None => lower == *pattern,
+                })
+        else {
+            return;
+        };
+        let driver = pattern.ends_with(".sys").then(|| name.to_string());
+        self.push(*kind, product, evidence.to_string(), driver);
+    }
+
+    /// Anti-cheat services, and kernel drivers among them
+    fn add_registry_operations(&mut self, operations: &[RegistryOperation]) {
+        for operation in operations {
+            let RegistryOperation::SetValue {
+                key_path,
+                value_name,
+                value_data,
+                ..
+            } = operation
+            else {
+                continue;
+            };
+            let lower = key_path.to_lowercase();
+            let Some((_, service)) = lower.split_once("\\currentcontrolset\\services\\") else {
+                continue;
+            };
+            let Some((_, product)) = ANTI_CHEAT_SERVICES
+                .iter()
+                .find(|(name, _)| *name == service)
+            else {
+                continue;
+            };
+            // SERVICE_KERNEL_DRIVER and SERVICE_FILE_SYSTEM_DRIVER
+            let is_driver = value_name.eq_ignore_ascii_case("Type")
+                && matches!(
+                    value_data,
+                    RegistryValue::DWord(1) | RegistryValue::DWord(2)
+                );
+            let service_name = key_path.rsplit('\\').next().unwrap_or(key_path);
+            self.push(
+                GameComponentKind::AntiCheat,
+                product,
+                format!("service {}", service_name),
+                is_driver.then(|| format!("{} service", service_name)),
+            );
+        }
+    }
+
+    fn push(
+        &mut self,
+        kind: GameComponentKind,
+        product: &'static str,
+        evidence: String,
+        driver: Option<String>,
+    ) {
+        let index = match self
+            .components
+            .iter()
+            .position(|component| component.product == product)
+        {
+            Some(index) => index,
+            None => {
+                self.components.push(GameComponent {
+                    kind,
+                    product,
+                    evidence: Vec::new(),
+                    drivers: Vec::new(),
+                });
+                self.components.len() - 1
+            }
+        };
+        let component = &mut self.components[index];
+        if !component
+            .evidence
+            .iter()
+            .any(|known| known.eq_ignore_ascii_case(&evidence))
+        {
+            component.evidence.push(evidence);
+        }
+        if let Some(driver) = driver {
+            if !component
+                .drivers
+                .iter()
+                .any(|known| known.eq_ignore_ascii_case(&driver))
+            {
+                component.drivers.push(driver);
+            }
+        }
+    }
+
+    /// Anti-cheat products that load a kernel-mode driver
+    pub fn kernel_anti_cheat(&self) -> Vec<&GameComponent> {
+        self.components
+            .iter()
+            .filter(|component| component.is_kernel_anti_cheat())
+            .collect()
+    }
+
+    /// Why the installer deserves attention, for kernel anti-cheat
+    pub fn warning(&self) -> Option<String> {
+        let products: Vec<&str> = self
+            .kernel_anti_cheat()
+            .iter()
+            .map(|component| component.product)
+            .collect();
+        (!products.is_empty()).then(|| {
+            format!(
+                "Kernel-mode anti-cheat: {} loads a driver with full system privileges",
+                products.join(", ")
+            )
+        })
+    }
+
+    /// Record the components by kind as analysis properties
+    pub fn insert_properties(&self, result: &mut AnalysisResult) {
+        if self.components.is_empty() {
+            return;
+        }
+
+        let properties = &mut result.metadata.properties;
+        for (kind, name) in [
+            (GameComponentKind::Runtime, "game_runtimes"),
+            (GameComponentKind::Platform, "game_platforms"),
+            (GameComponentKind::AntiCheat, "game_anti_cheat"),
+        ] {
+            let components: Vec<String> = self
+                .components
+                .iter()
+                .filter(|component| component.kind == kind)
+                .map(GameComponent::describe)
+                .collect();
+            if !components.is_empty() {
+                properties.insert(name.to_string(), components.join("; "));
+            }
+        }
+        let redistributables = self
+            .components
+            .iter()
+            .find(|component| component.product == VCREDIST)
+            .map_or(0, |component| component.evidence.len());
+        if redistributables > 1 {
+            properties.insert(
+                "game_vcredist_count".to_string(),
+                redistributables.to_string(),
+            );
+        }
+
+        let kernel = self.kernel_anti_cheat();
+        properties.insert(
+            "game_kernel_anti_cheat".to_string(),
+            (!kernel.is_empty()).to_string(),
+        );
+        let drivers: Vec<String> = kernel
+            .iter()
+            .flat_map(|component| {
+                component
+                    .drivers
+                    .iter()
+                    .map(move |driver| format!("{} ({})", driver, component.product))
+            })
+            .collect();
+        if !drivers.is_empty() {
+            properties.insert("game_kernel_drivers".to_string(), drivers.join(", "));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        FileAttributes, FileEntry, InstallerFormat, InstallerMetadata, RegistryValueType,
+    };
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    fn result(files: &[&str]) -> AnalysisResult {
+        AnalysisResult {
+            session_id: uuid::Uuid::new_v4(),
+            metadata: InstallerMetadata {
+                format: InstallerFormat::InnoSetup,
+                created_at: chrono::Utc::now(),
+                ..Default::default()
+            },
+            files: files
+                .iter()
+                .map(|path| FileEntry {
+                    path: PathBuf::from(path),
+                    target_path: None,
+                    size: 1,
+                    hash: None,
+                    attributes: FileAttributes::default(),
+                    compression: None,
+                    is_speculative: false,
+                    architecture: None,
+                    modified: None,
+                    source: None,
+                })
+                .collect(),
+            analyzed_at: chrono::Utc::now(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_packaged_components() {
+        let mut result = result(&[
+            "_CommonRedist/DirectX/Jun2010/DXSETUP.exe",
+            "_CommonRedist/vcredist/2010/vcredist_x86.exe",
+            "_CommonRedist/vcredist/2022/VC_redist.x64.exe",
+            "_CommonRedist/PhysX/PhysX-9.13.0604-SystemSoftware.msi",
+            "Game/steam_api64.dll",
+            "Game/BattlEye/BEService_x64.exe",
+            "Game/game.exe",
+        ]);
+        result
+            .registry_operations
+            .push(RegistryOperation::SetValue {
+                key_path: "HKEY_LOCAL_MACHINE\\SYSTEM\\CurrentControlSet\\Services\\BEDaisy"
+                    .to_string(),
+                value_name: "Type".to_string(),
+                value_type: RegistryValueType::DWord,
+                value_data: RegistryValue::DWord(1),
+                timestamp: chrono::Utc::now(),
+                process_id: None,
+            });
+        let report = GameRuntimeReport::from_result(&result);
+
+        let products: Vec<&str> = report.components.iter().map(|c| c.product).collect();
+        assert_eq!(
+            products,
+            vec![
+                "DirectX End-User Runtime",
+                VCREDIST,
+                "NVIDIA PhysX",
+                "Steamworks",
+                BATTLEYE
+            ]
+        );
+        assert_eq!(
+            report.warning().as_deref(),
+            Some("Kernel-mode anti-cheat: BattlEye loads a driver with full system privileges")
+        );
+
+        report.insert_properties(&mut result);
+        let properties = &result.metadata.properties;
+        assert_eq!(properties["game_vcredist_count"], "2");
+        assert_eq!(properties["game_kernel_anti_cheat"], "true");
+        assert_eq!(
+            properties["game_kernel_drivers"],
+            "BEDaisy service (BattlEye)"
+        );
+        assert_eq!(
+            properties["game_anti_cheat"],
+            "BattlEye (BEService_x64.exe, service BEDaisy)"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_detect_embedded_names() {
+        let mut installer = tempfile::NamedTempFile::new().unwrap();
+        installer.write_all(b"MZ setup\0").unwrap();
+        let text: Vec<u8> = "{app}\\EasyAntiCheat\\EasyAntiCheat_EOS_Setup.exe install"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        installer.write_all(&text).unwrap();
+
+        let report = GameRuntimeReport::detect(installer.path(), &result(&["game.exe"]))
+            .await
+            .unwrap();
+        assert_eq!(report.components.len(), 1);
+        assert_eq!(report.components[0].product, EASY_ANTI_CHEAT);
+        assert!(report.components[0].is_kernel_anti_cheat());
+        assert!(report.components[0].drivers.is_empty());
+
+        let mut plain = tempfile::NamedTempFile::new().unwrap();
+        plain.write_all(b"MZ plain setup").unwrap();
+        let none = GameRuntimeReport::detect(plain.path(), &result(&[]))
+            .await
+            .unwrap();
+        assert!(none.components.is_empty());
+        assert!(none.warning().is_none());
+    }
+}
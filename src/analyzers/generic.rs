@@ -0,0 +1,256 @@
+//! Fallback analysis of files no format analyzer recognizes
+//!
+//! Triage pipelines still need a report for unrecognized files: their hashes
+//! and size, the version resource, architecture and link time of PE images,
+//! the status of an embedded Authenticode signature and the entropy of the
+//! content. Strings, URLs and embedded payloads are added by the analysis
+//! stages that run for every format.
+
+use crate::analyzers::architecture::PeArchitecture;
+use crate::analyzers::authenticode::AuthenticodeReport;
+use crate::analyzers::carving::entropy;
+use crate::analyzers::common::{self, EntryReader, VersionStrings};
+use crate::analyzers::{ArchiveAnalyzer, DetectionContext, InstallerAnalyzer};
+use crate::core::{
+    AnalyzerCapabilities, FileEntry, InstallerFormat, InstallerMetadata, MetadataSource,
+    RegistryOperation, Result, SupportLevel,
+};
+use crate::utils::format_file_size;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Largest file read into memory for the signature and entropy checks
+const MAX_READ_SIZE: u64 = 512 * 1024 * 1024;
+
+/// Analyzer used when no registered analyzer matches a file
+///
+/// It is not registered for detection; [`AnalyzerFactory::create_analyzer_or_fallback`]
+/// returns it for unrecognized files.
+///
+/// [`AnalyzerFactory::create_analyzer_or_fallback`]: crate::analyzers::AnalyzerFactory::create_analyzer_or_fallback
+pub struct GenericAnalyzer;
+
+impl GenericAnalyzer {
+    /// Create a new generic analyzer
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Extract hashes, PE metadata, signature status and entropy
+    async fn extract_generic_metadata(&self, file_path: &Path) -> Result<InstallerMetadata> {
+        let file_size = common::get_file_size(file_path).await?;
+        let file_hash = common::calculate_file_hash(file_path).await?;
+        let context = DetectionContext::new(file_path).await?;
+
+        let mut properties = HashMap::new();
+        properties.insert("analysis_fallback".to_string(), "generic".to_string());
+        properties.insert("file_type".to_string(), file_type(&context).to_string());
+
+        let mut version = VersionStrings::default();
+        if file_size > MAX_READ_SIZE {
+            tracing::info!(
+                "Skipping signature and entropy checks of {} ({})",
+                file_path.display(),
+                format_file_size(file_size)
+            );
+        } else {
+            let data = tokio::fs::read(file_path).await?;
+            properties.insert("file_entropy".to_string(), format!("{:.2}", entropy(&data)));
+            if context.is_pe() {
+                version = VersionStrings::read(&data);
+                insert_pe_properties(&data, &mut properties);
+            }
+        }
+        if let Some(description) = version.file_description.clone() {
+            properties.insert("file_description".to_string(), description);
+        }
+
+        Ok(InstallerMetadata {
+            format: InstallerFormat::Unknown,
+            product_name: version.product_name.clone(),
+            product_version: version.version().map(str::to_string),
+            manufacturer: version.company_name,
+            file_size,
+            file_hash,
+            created_at: Utc::now(),
+            properties,
+            provenance: Default::default(),
+            format_details: None,
+        }
+        .with_provenance(MetadataSource::PeVersionInfo))
+    }
+}
+
+#[async_trait]
+impl InstallerAnalyzer for GenericAnalyzer {
+    async fn can_analyze(&self, _file_path: &Path) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn format(&self) -> InstallerFormat {
+        InstallerFormat::Unknown
+    }
+
+    fn capabilities(&self) -> AnalyzerCapabilities {
+        AnalyzerCapabilities {
+            metadata: SupportLevel::Partial,
+            file_extraction: SupportLevel::Partial,
+            registry_operations: SupportLevel::Unsupported,
+            install_scripts: SupportLevel::Unsupported,
+            signatures: SupportLevel::Partial,
+        }
+    }
+
+    async fn extract_metadata(&self, file_path: &Path) -> Result<InstallerMetadata> {
+        common::validate_file(file_path).await?;
+
+        self.extract_generic_metadata(file_path).await
+    }
+
+    async fn extract_files(&self, file_path: &Path) -> Result<Vec<FileEntry>> {
+        common::validate_file(file_path).await?;
+
+        // Plain archives are not registered for detection, but can be listed
+        if common::is_archive_file(file_path).await? {
+            return ArchiveAnalyzer::new().extract_files(file_path).await;
+        }
+        Ok(Vec::new())
+    }
+
+    async fn extract_registry_operations(
+        &self,
+        file_path: &Path,
+    ) -> Result<Vec<RegistryOperation>> {
+        common::validate_file(file_path).await?;
+
+        Ok(Vec::new())
+    }
+
+    async fn read_file_content(
+        &self,
+        file_path: &Path,
+        entry_path: &Path,
+    ) -> Result<Option<Vec<u8>>> {
+        if common::is_archive_file(file_path).await? {
+            return ArchiveAnalyzer::new()
+                .read_file_content(file_path, entry_path)
+                .await;
+        }
+        Ok(None)
+    }
+
+    async fn open_file(&self, file_path: &Path, entry_path: &Path) -> Result<Option<EntryReader>> {
+        if common::is_archive_file(file_path).await? {
+            return ArchiveAnalyzer::new()
+                .open_file(file_path, entry_path)
+                .await;
+        }
+        Ok(None)
+    }
+}
+
+impl Default for GenericAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Kind of file told by its signature
+fn file_type(context: &DetectionContext) -> &'static str {
+    let header = context.header();
+    if context.is_pe() {
+        "PE image"
+    } else if header.starts_with(b"PK\x03\x04") {
+        "ZIP archive"
+    } else if header.starts_with(b"7z\xBC\xAF\x27\x1C") {
+        "7-Zip archive"
+    } else if header.starts_with(b"MSCF") {
+        "cabinet"
+    } else if header.starts_with(&[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1]) {
+        "compound file"
+    } else if header.starts_with(b"\x7FELF") {
+        "ELF image"
+    } else if header.starts_with(b"#!") {
+        "script"
+    } else {
+        "data"
+    }
+}
+
+/// Record the architecture, link time and signature of a PE image
+fn insert_pe_properties(data: &[u8], properties: &mut HashMap<String, String>) {
+    if let Some(architecture) = PeArchitecture::from_pe(data) {
+        properties.insert("pe_architecture".to_string(), architecture.to_string());
+    }
+    // Reproducible builds store a hash here, which reads as an arbitrary date
+    if let Some(linked) =
+        pe_timestamp(data).and_then(|seconds| DateTime::from_timestamp(seconds, 0))
+    {
+        properties.insert("pe_timestamp".to_string(), linked.to_rfc3339());
+    }
+
+    AuthenticodeReport::check(data).insert_properties(properties);
+}
+
+/// `TimeDateStamp` of the COFF header, if set
+fn pe_timestamp(data: &[u8]) -> Option<i64> {
+    let pe = read_u32(data, 0x3c)? as usize;
+    if data.get(pe..pe + 4)? != b"PE\0\0" {
+        return None;
+    }
+    let timestamp = read_u32(data, pe + 8)?;
+    (timestamp != 0).then_some(timestamp as i64)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzers::driver::catalog::tests::pe_image;
+    use std::io::Write;
+
+    #[tokio::test]
+    async fn test_analyze_unrecognized_pe() {
+        let mut image = pe_image(b"");
+        // i386 machine, link time 2024-05-17 12:00:00 UTC
+        image[0x84..0x86].copy_from_slice(&0x014cu16.to_le_bytes());
+        image[0x88..0x8c].copy_from_slice(&1_715_947_200u32.to_le_bytes());
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&image).unwrap();
+
+        let analyzer = crate::analyzers::AnalyzerFactory::create_analyzer_or_fallback(file.path())
+            .await
+            .unwrap();
+        assert_eq!(analyzer.format(), InstallerFormat::Unknown);
+        let metadata = analyzer.extract_metadata(file.path()).await.unwrap();
+        let properties = &metadata.properties;
+        assert_eq!(properties["analysis_fallback"], "generic");
+        assert_eq!(properties["file_type"], "PE image");
+        assert_eq!(properties["pe_architecture"], "x86");
+        assert_eq!(properties["pe_timestamp"], "2024-05-17T12:00:00+00:00");
+        assert_eq!(properties["pe_signature_status"], "missing");
+        assert!(properties.contains_key("file_entropy"));
+        assert!(analyzer
+            .extract_files(file.path())
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_fallback_keeps_errors_of_recognized_files() {
+        let missing = Path::new("missing-installer.exe");
+        assert!(
+            crate::analyzers::AnalyzerFactory::create_analyzer_or_fallback(missing)
+                .await
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,287 @@
+//! ISO and WIM image analyzer implementation
+
+use super::listing::{ImageListing, ImageReader};
+use crate::analyzers::{common, DetectionContext, InstallerAnalyzer};
+use crate::core::{
+    AnalyzerCapabilities, AnalyzerError, FileAttributes, FileEntry, InstallerFormat,
+    InstallerMetadata, MetadataSource, RegistryOperation, Result, SupportLevel,
+};
+use crate::utils::format_file_size;
+use async_trait::async_trait;
+use chrono::Utc;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// Image a listing belongs to, with its modification time
+type ListingKey = (PathBuf, Option<SystemTime>);
+
+/// Offset of the standard identifier of the first ISO 9660 volume descriptor
+const ISO_IDENTIFIER_OFFSET: u64 = 0x8001;
+
+/// Disk image (ISO 9660, UDF and WIM) analyzer
+pub struct DiskImageAnalyzer {
+    /// Listing of the image analyzed last, shared by the analysis stages
+    listing: Mutex<Option<(ListingKey, Arc<ImageListing>)>>,
+}
+
+impl DiskImageAnalyzer {
+    /// Create a new disk image analyzer
+    pub fn new() -> Self {
+        Self {
+            listing: Mutex::new(None),
+        }
+    }
+
+    /// Read the listing of `file_path`, reusing it while the image is unchanged
+    fn listing(&self, file_path: &Path) -> Result<Arc<ImageListing>> {
+        let modified = std::fs::metadata(file_path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        let key = (file_path.to_path_buf(), modified);
+
+        let mut listing = self
+            .listing
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some((read, cached)) = listing.as_ref() {
+            if *read == key {
+                return Ok(cached.clone());
+            }
+        }
+        let read = Arc::new(ImageListing::read(file_path)?);
+        *listing = Some((key, read.clone()));
+        Ok(read)
+    }
+
+    /// Check for the WIM magic or the ISO 9660 volume descriptor identifier
+    async fn has_image_signature(file_path: &Path, header: &[u8]) -> Result<bool> {
+        if header.starts_with(b"MSWIM\0\0\0") {
+            return Ok(true);
+        }
+        if common::get_file_size(file_path).await? < ISO_IDENTIFIER_OFFSET + 5 {
+            return Ok(false);
+        }
+        let identifier =
+            common::read_file_content_range(file_path, ISO_IDENTIFIER_OFFSET, 5).await?;
+        Ok(identifier == b"CD001" || identifier == b"BEA01")
+    }
+
+    /// Extract metadata from the volume descriptors or the WIM XML
+    async fn extract_image_metadata(&self, file_path: &Path) -> Result<InstallerMetadata> {
+        let file_size = common::get_file_size(file_path).await?;
+        let file_hash = common::calculate_file_hash(file_path).await?;
+        let listing = self.listing(file_path)?;
+
+        let mut properties: HashMap<String, String> = listing.properties.iter().cloned().collect();
+        properties.insert("format_version".to_string(), listing.kind.to_string());
+        properties.insert("file_type".to_string(), "Disk Image".to_string());
+        properties.insert("image_type".to_string(), listing.kind.to_string());
+        if let Some(label) = &listing.label {
+            properties.insert("volume_label".to_string(), label.clone());
+        }
+        if let Some(created) = listing.created {
+            properties.insert("image_created".to_string(), created.to_rfc3339());
+        }
+        let (count, total) = listing
+            .files()
+            .fold((0usize, 0u64), |(count, total), entry| {
+                (count + 1, total + entry.size)
+            });
+        properties.insert("image_file_count".to_string(), count.to_string());
+        properties.insert("image_content_size".to_string(), format_file_size(total));
+
+        Ok(InstallerMetadata {
+            format: InstallerFormat::DiskImage,
+            product_name: listing.label.clone(),
+            product_version: properties.get("wim_windows_version").cloned(),
+            manufacturer: properties.get("iso_publisher").cloned(),
+            file_size,
+            file_hash,
+            created_at: Utc::now(),
+            properties,
+            provenance: Default::default(),
+            format_details: None,
+        }
+        .with_provenance(MetadataSource::InstallerData))
+    }
+
+    /// List the files of the image
+    fn extract_image_files(&self, file_path: &Path) -> Result<Vec<FileEntry>> {
+        let listing = self.listing(file_path)?;
+        tracing::info!(
+            "Found {} files in {} image",
+            listing.files().count(),
+            listing.kind
+        );
+
+        Ok(listing
+            .files()
+            .map(|entry| {
+                let path = PathBuf::from(&entry.path);
+                let executable = path
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| {
+                        matches!(
+                            ext.to_ascii_lowercase().as_str(),
+                            "exe" | "dll" | "msi" | "bat" | "cmd"
+                        )
+                    });
+                FileEntry {
+                    path,
+                    target_path: None,
+                    size: entry.size,
+                    hash: None,
+                    attributes: FileAttributes {
+                        executable,
+                        ..FileAttributes::default()
+                    },
+                    compression: None,
+                    is_speculative: false,
+                    architecture: None,
+                    modified: entry.modified,
+                    source: None,
+                }
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl InstallerAnalyzer for DiskImageAnalyzer {
+    async fn can_analyze(&self, file_path: &Path) -> Result<bool> {
+        let header = common::read_file_header(file_path, 8).await?;
+        Self::has_image_signature(file_path, &header).await
+    }
+
+    async fn can_analyze_with(&self, context: &DetectionContext) -> Result<bool> {
+        Self::has_image_signature(context.path(), context.header()).await
+    }
+
+    fn format(&self) -> InstallerFormat {
+        InstallerFormat::DiskImage
+    }
+
+    fn capabilities(&self) -> AnalyzerCapabilities {
+        AnalyzerCapabilities {
+            metadata: SupportLevel::Partial,
+            file_extraction: SupportLevel::Full,
+            registry_operations: SupportLevel::Unsupported,
+            install_scripts: SupportLevel::Unsupported,
+            signatures: SupportLevel::Unsupported,
+        }
+    }
+
+    async fn extract_metadata(&self, file_path: &Path) -> Result<InstallerMetadata> {
+        // Validate file first
+        common::validate_file(file_path).await?;
+
+        self.extract_image_metadata(file_path).await
+    }
+
+    async fn extract_files(&self, file_path: &Path) -> Result<Vec<FileEntry>> {
+        // Validate file first
+        common::validate_file(file_path).await?;
+
+        self.extract_image_files(file_path)
+    }
+
+    async fn extract_registry_operations(
+        &self,
+        _file_path: &Path,
+    ) -> Result<Vec<RegistryOperation>> {
+        // Images carry no install scripts of their own
+        Ok(Vec::new())
+    }
+
+    async fn read_file_content(
+        &self,
+        file_path: &Path,
+        entry_path: &Path,
+    ) -> Result<Option<Vec<u8>>> {
+        // Validate file first
+        common::validate_file(file_path).await?;
+
+        let listing = self.listing(file_path)?;
+        let Some(entry) = listing.find(entry_path) else {
+            return Ok(None);
+        };
+        let mut reader = ImageReader::open(file_path)?;
+        listing
+            .read_entry(&mut reader, entry)
+            .map(Some)
+            .map_err(|e| {
+                AnalyzerError::generic(format!("Failed to read {}: {}", entry_path.display(), e))
+            })
+    }
+}
+
+impl Default for DiskImageAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzers::image::{iso, wim};
+    use std::io::Write;
+
+    fn image_file(data: &[u8], extension: &str) -> tempfile::NamedTempFile {
+        let mut file = tempfile::Builder::new()
+            .suffix(extension)
+            .tempfile()
+            .unwrap();
+        file.write_all(data).unwrap();
+        file
+    }
+
+    #[tokio::test]
+    async fn test_analyze_iso_image() {
+        let file = image_file(&iso::tests::iso_image(true), ".iso");
+        let analyzer = DiskImageAnalyzer::new();
+        assert!(analyzer.can_analyze(file.path()).await.unwrap());
+
+        let metadata = analyzer.extract_metadata(file.path()).await.unwrap();
+        assert_eq!(metadata.format, InstallerFormat::DiskImage);
+        assert_eq!(metadata.product_name.as_deref(), Some("PRODUCT_MEDIA"));
+        assert_eq!(metadata.properties["image_file_count"], "3");
+
+        let files = analyzer.extract_files(file.path()).await.unwrap();
+        assert_eq!(files.len(), 3);
+        assert!(files[0].attributes.executable);
+        let content = analyzer
+            .read_file_content(file.path(), Path::new("DOCS\\read me.txt"))
+            .await
+            .unwrap();
+        assert_eq!(content.as_deref(), Some(&b"hello"[..]));
+        assert!(analyzer
+            .read_file_content(file.path(), Path::new("missing.txt"))
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_analyze_wim_image() {
+        let file = image_file(&wim::tests::wim_image(), ".wim");
+        let analyzer = DiskImageAnalyzer::new();
+        assert!(analyzer.can_analyze(file.path()).await.unwrap());
+
+        let metadata = analyzer.extract_metadata(file.path()).await.unwrap();
+        assert_eq!(metadata.product_version.as_deref(), Some("10.0.19041"));
+        assert_eq!(metadata.properties["image_type"], "WIM");
+    }
+
+    #[tokio::test]
+    async fn test_reject_other_files() {
+        let file = image_file(&[0u8; 0x9000], ".bin");
+        assert!(!DiskImageAnalyzer::new()
+            .can_analyze(file.path())
+            .await
+            .unwrap());
+    }
+}
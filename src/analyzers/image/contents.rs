@@ -0,0 +1,295 @@
+//! Analysis of installers stored on product media
+//!
+//! Product DVDs and ISO downloads usually hold a bootstrapper next to MSI
+//! packages or other installers. Files that look like installers are read
+//! out of the image into a scratch directory and analyzed in turn, so the
+//! report covers what the media would install and not just its file list.
+
+use crate::analyzers::{AnalyzerFactory, InstallerAnalyzer};
+use crate::core::{AnalysisResult, FileEntry, InstallerFormat, Result};
+use crate::utils::{format_file_size, Workspace, WorkspaceArea};
+use std::path::{Path, PathBuf};
+
+/// Largest number of contained installers that are analyzed
+const MAX_CONTAINED_INSTALLERS: usize = 16;
+/// Largest contained installer that is read out of the image
+const MAX_INSTALLER_SIZE: u64 = 1024 * 1024 * 1024;
+
+/// Installer read from an image and its analysis
+#[derive(Debug, Clone)]
+pub struct ContainedInstaller {
+    /// Path of the installer in the image
+    pub path: PathBuf,
+    pub size: u64,
+    pub format: Option<InstallerFormat>,
+    pub product_name: Option<String>,
+    pub product_version: Option<String>,
+    pub files: Vec<FileEntry>,
+    pub error: Option<String>,
+}
+
+impl ContainedInstaller {
+    fn describe(&self) -> String {
+        let path = self.path.display();
+        match (&self.format, &self.error) {
+            (Some(format), None) => {
+                let product = [
+                    self.product_name.as_deref(),
+                    self.product_version.as_deref(),
+                ]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+                .join(" ");
+                if product.is_empty() {
+                    format!("{}: {} ({} files)", path, format, self.files.len())
+                } else {
+                    format!(
+                        "{}: {} {} ({} files)",
+                        path,
+                        format,
+                        product,
+                        self.files.len()
+                    )
+                }
+            }
+            (_, Some(error)) => format!("{}: {}", path, error),
+            (None, None) => format!("{}: not analyzed", path),
+        }
+    }
+}
+
+/// Installers found in a disk image
+#[derive(Debug, Clone, Default)]
+pub struct ContainedInstallerReport {
+    pub installers: Vec<ContainedInstaller>,
+    /// Candidates left out by the size or count limits
+    pub skipped: Vec<PathBuf>,
+}
+
+impl ContainedInstallerReport {
+    /// Read installer candidates among `files` out of the image and analyze them
+    pub async fn detect(
+        analyzer: &dyn InstallerAnalyzer,
+        image_path: &Path,
+        files: &[FileEntry],
+    ) -> Result<Self> {
+        let mut report = Self::default();
+        let candidates: Vec<&FileEntry> = files
+            .iter()
+            .filter(|file| is_installer(&file.path))
+            .collect();
+        if candidates.is_empty() {
+            return Ok(report);
+        }
+
+        let scratch_dir = Workspace::new().scratch_dir(WorkspaceArea::Extraction)?;
+        for file in candidates {
+            if report.installers.len() >= MAX_CONTAINED_INSTALLERS || file.size > MAX_INSTALLER_SIZE
+            {
+                tracing::info!(
+                    "Not analyzing {} ({}) of the image",
+                    file.path.display(),
+                    format_file_size(file.size)
+                );
+                report.skipped.push(file.path.clone());
+                continue;
+            }
+
+            let mut installer = ContainedInstaller {
+                path: file.path.clone(),
+                size: file.size,
+                format: None,
+                product_name: None,
+                product_version: None,
+                files: Vec::new(),
+                error: None,
+            };
+            // Each installer keeps its name in a directory of its own
+            let directory = scratch_dir.path().join(report.installers.len().to_string());
+            let name = file.path.file_name().unwrap_or_default();
+            let path = directory.join(name);
+            match analyzer.read_file_content(image_path, &file.path).await {
+                Ok(Some(content)) => {
+                    tokio::fs::create_dir_all(&directory).await?;
+                    tokio::fs::write(&path, content).await?;
+                    Self::analyze_installer(&path, &mut installer).await;
+                }
+                Ok(None) => installer.error = Some("not found in the image".to_string()),
+                Err(e) => installer.error = Some(e.to_string()),
+            }
+            report.installers.push(installer);
+        }
+        Ok(report)
+    }
+
+    /// Analyze one installer; failures are recorded rather than returned
+    async fn analyze_installer(path: &Path, installer: &mut ContainedInstaller) {
+        let analyzer = match AnalyzerFactory::create_analyzer(path).await {
+            Ok(analyzer) => analyzer,
+            Err(e) => {
+                installer.error = Some(e.to_string());
+                return;
+            }
+        };
+        installer.format = Some(analyzer.format());
+        match tokio::try_join!(
+            analyzer.extract_metadata(path),
+            analyzer.extract_files(path)
+        ) {
+            Ok((metadata, files)) => {
+                installer.product_name = metadata.product_name;
+                installer.product_version = metadata.product_version;
+                installer.files = files
+                    .into_iter()
+                    .filter(|file| !file.is_speculative)
+                    .collect();
+            }
+            Err(e) => installer.error = Some(e.to_string()),
+        }
+    }
+
+    /// Record the installers as analysis properties and add their files
+    ///
+    /// Files of an analyzed installer are listed below its path in the image.
+    pub fn insert_properties(&self, result: &mut AnalysisResult) {
+        if self.installers.is_empty() && self.skipped.is_empty() {
+            return;
+        }
+
+        let properties = &mut result.metadata.properties;
+        properties.insert(
+            "image_installer_count".to_string(),
+            self.installers.len().to_string(),
+        );
+        let descriptions: Vec<String> = self
+            .installers
+            .iter()
+            .map(ContainedInstaller::describe)
+            .collect();
+        properties.insert("image_installers".to_string(), descriptions.join("; "));
+        if !self.skipped.is_empty() {
+            let skipped: Vec<String> = self
+                .skipped
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect();
+            properties.insert(
+                "image_installers_not_analyzed".to_string(),
+                skipped.join(", "),
+            );
+        }
+
+        for installer in &self.installers {
+            result
+                .files
+                .extend(installer.files.iter().map(|file| FileEntry {
+                    path: installer.path.join(&file.path),
+                    ..file.clone()
+                }));
+        }
+    }
+}
+
+/// Packages by extension, and executables named like a setup program
+fn is_installer(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+    let name = name.to_ascii_lowercase();
+    let Some((stem, extension)) = name.rsplit_once('.') else {
+        return false;
+    };
+    match extension {
+        "msi" | "msix" | "msixbundle" | "appx" | "appxbundle" | "whl" => true,
+        "exe" => {
+            stem.starts_with("setup")
+                || stem.starts_with("install")
+                || stem.ends_with("setup")
+                || stem.ends_with("installer")
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzers::image::{iso, DiskImageAnalyzer};
+    use crate::core::{FileAttributes, InstallerMetadata};
+    use chrono::Utc;
+    use std::io::Write;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_is_installer() {
+        for path in [
+            "Setup.exe",
+            "x64/Product.msi",
+            "sources/setupprep.exe",
+            "Tools/WebInstaller.exe",
+            "app.msixbundle",
+        ] {
+            assert!(is_installer(Path::new(path)), "{}", path);
+        }
+        for path in ["autorun.inf", "tools/helper.exe", "setup", "docs/setup.txt"] {
+            assert!(!is_installer(Path::new(path)), "{}", path);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_detect_contained_installers() {
+        let mut image = tempfile::Builder::new().suffix(".iso").tempfile().unwrap();
+        image.write_all(&iso::tests::iso_image(true)).unwrap();
+        let analyzer = DiskImageAnalyzer::new();
+        let files = analyzer.extract_files(image.path()).await.unwrap();
+
+        let report = ContainedInstallerReport::detect(&analyzer, image.path(), &files)
+            .await
+            .unwrap();
+        // The stub executable is read out but not recognized as an installer
+        assert_eq!(report.installers.len(), 1);
+        assert_eq!(report.installers[0].path, Path::new("Setup.exe"));
+        assert!(report.installers[0].error.is_some());
+
+        let mut result = AnalysisResult {
+            session_id: Uuid::new_v4(),
+            metadata: InstallerMetadata {
+                format: InstallerFormat::DiskImage,
+                created_at: Utc::now(),
+                ..Default::default()
+            },
+            files,
+            analyzed_at: Utc::now(),
+            ..Default::default()
+        };
+        let mut report = report;
+        report.installers[0].error = None;
+        report.installers[0].format = Some(InstallerFormat::MSI);
+        report.installers[0].product_name = Some("Product".to_string());
+        report.installers[0].files.push(FileEntry {
+            path: PathBuf::from("bin/app.exe"),
+            target_path: None,
+            size: 1,
+            hash: None,
+            attributes: FileAttributes::default(),
+            compression: None,
+            is_speculative: false,
+            architecture: None,
+            modified: None,
+            source: None,
+        });
+        report.insert_properties(&mut result);
+
+        let properties = &result.metadata.properties;
+        assert_eq!(properties["image_installer_count"], "1");
+        assert_eq!(
+            properties["image_installers"],
+            "Setup.exe: MSI Product (1 files)"
+        );
+        assert!(result
+            .files
+            .iter()
+            .any(|file| file.path == Path::new("Setup.exe/bin/app.exe")));
+    }
+}
@@ -0,0 +1,411 @@
+//! ISO 9660 file system with Joliet extensions
+//!
+//! Volume descriptors start at sector 16. The primary descriptor lists names
+//! in the restricted ISO character set; a Joliet supplementary descriptor
+//! holds the same tree with UCS-2 names and is preferred when present. Files
+//! of 4 GiB and more are split into several records flagged as multi-extent.
+
+use super::listing::{
+    join_path, read_u16, read_u32, EntryData, ImageEntry, ImageKind, ImageListing, ImageReader,
+    MAX_DEPTH, MAX_ENTRIES,
+};
+use crate::core::{AnalyzerError, Result};
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use std::collections::HashSet;
+
+const SECTOR_SIZE: u64 = 2048;
+/// Sector of the first volume descriptor
+const FIRST_DESCRIPTOR: u64 = 16;
+/// Volume descriptors read before giving up on a terminator
+const MAX_DESCRIPTORS: u64 = 64;
+/// Largest directory that is read
+const MAX_DIRECTORY_SIZE: u32 = 16 * 1024 * 1024;
+
+const TYPE_PRIMARY: u8 = 1;
+const TYPE_SUPPLEMENTARY: u8 = 2;
+const TYPE_TERMINATOR: u8 = 255;
+
+const FLAG_DIRECTORY: u8 = 0x02;
+const FLAG_MULTI_EXTENT: u8 = 0x80;
+
+/// The image has an ISO 9660 volume descriptor
+pub fn is_iso(reader: &mut ImageReader) -> bool {
+    reader
+        .read_at(FIRST_DESCRIPTOR * SECTOR_SIZE + 1, 5)
+        .is_ok_and(|id| id == b"CD001")
+}
+
+/// Read the directory tree of an ISO 9660 image
+pub fn read(reader: &mut ImageReader) -> Result<ImageListing> {
+    let mut primary = None;
+    let mut joliet = None;
+    for index in 0..MAX_DESCRIPTORS {
+        let sector = reader.read_at(
+            (FIRST_DESCRIPTOR + index) * SECTOR_SIZE,
+            SECTOR_SIZE as usize,
+        )?;
+        if &sector[1..6] != b"CD001" {
+            break;
+        }
+        match sector[0] {
+            TYPE_PRIMARY if primary.is_none() => primary = Some(sector),
+            TYPE_SUPPLEMENTARY if is_joliet(&sector) => joliet = Some(sector),
+            TYPE_TERMINATOR => break,
+            _ => {}
+        }
+    }
+    let primary = primary
+        .ok_or_else(|| AnalyzerError::parse_error("No ISO 9660 primary volume descriptor found"))?;
+
+    let (descriptor, kind) = match &joliet {
+        Some(joliet) => (joliet, ImageKind::Joliet),
+        None => (&primary, ImageKind::Iso9660),
+    };
+    let joliet_names = kind == ImageKind::Joliet;
+    let block_size = match read_u16(descriptor, 128) {
+        0 => SECTOR_SIZE,
+        size => size as u64,
+    };
+    let root = DirectoryRecord::parse(&descriptor[156..190], joliet_names)
+        .ok_or_else(|| AnalyzerError::parse_error("Invalid ISO 9660 root directory record"))?;
+
+    let mut walker = Walker {
+        reader,
+        block_size,
+        joliet_names,
+        visited: HashSet::new(),
+        entries: Vec::new(),
+    };
+    walker.walk(&root, "", 0)?;
+    let entries = walker.entries;
+
+    let label = descriptor_text(&descriptor[40..72], joliet_names);
+    let mut properties = Vec::new();
+    for (key, range) in [("iso_publisher", 318..446), ("iso_application", 574..702)] {
+        // Names starting with `_` refer to a file instead
+        if let Some(value) = descriptor_text(&descriptor[range], joliet_names)
+            .filter(|value| !value.starts_with('_'))
+        {
+            properties.push((key.to_string(), value));
+        }
+    }
+
+    Ok(ImageListing {
+        kind,
+        label,
+        created: decimal_datetime(&primary[813..830]),
+        entries,
+        properties,
+    })
+}
+
+/// Supplementary descriptor with a Joliet UCS-2 escape sequence
+fn is_joliet(descriptor: &[u8]) -> bool {
+    matches!(&descriptor[88..91], b"%/@" | b"%/C" | b"%/E")
+}
+
+/// Directory record of a file or directory
+#[derive(Debug, Clone)]
+struct DirectoryRecord {
+    extent: u32,
+    size: u32,
+    recorded: Option<DateTime<Utc>>,
+    flags: u8,
+    /// `None` for the `.` and `..` entries
+    name: Option<String>,
+}
+
+impl DirectoryRecord {
+    fn parse(record: &[u8], joliet_names: bool) -> Option<Self> {
+        if record.len() < 34 {
+            return None;
+        }
+        let name_length = record[32] as usize;
+        let raw_name = record.get(33..33 + name_length)?;
+        let name = match raw_name {
+            [0] | [1] => None,
+            _ => Some(decode_name(raw_name, joliet_names)),
+        };
+        Some(Self {
+            extent: read_u32(record, 2),
+            size: read_u32(record, 10),
+            recorded: recording_datetime(&record[18..25]),
+            flags: record[25],
+            name,
+        })
+    }
+
+    fn is_directory(&self) -> bool {
+        self.flags & FLAG_DIRECTORY != 0
+    }
+}
+
+struct Walker<'a> {
+    reader: &'a mut ImageReader,
+    block_size: u64,
+    joliet_names: bool,
+    /// Directory extents already read, guarding against loops
+    visited: HashSet<u32>,
+    entries: Vec<ImageEntry>,
+}
+
+impl Walker<'_> {
+    fn walk(&mut self, directory: &DirectoryRecord, path: &str, depth: usize) -> Result<()> {
+        if depth > MAX_DEPTH || !self.visited.insert(directory.extent) {
+            return Ok(());
+        }
+        let size = directory.size.min(MAX_DIRECTORY_SIZE) as usize;
+        let data = self
+            .reader
+            .read_at(directory.extent as u64 * self.block_size, size)?;
+
+        let mut subdirectories = Vec::new();
+        let mut position = 0;
+        while position < data.len() && self.entries.len() < MAX_ENTRIES {
+            let length = data[position] as usize;
+            if length == 0 {
+                // Records do not cross sectors, the rest of the sector is padding
+                position = (position / SECTOR_SIZE as usize + 1) * SECTOR_SIZE as usize;
+                continue;
+            }
+            let Some(record) = data
+                .get(position..position + length)
+                .and_then(|record| DirectoryRecord::parse(record, self.joliet_names))
+            else {
+                break;
+            };
+            position += length;
+            let Some(name) = record.name.clone() else {
+                continue;
+            };
+            let entry_path = join_path(path, &name);
+
+            if record.is_directory() {
+                self.entries.push(ImageEntry {
+                    path: entry_path.clone(),
+                    size: 0,
+                    modified: record.recorded,
+                    is_directory: true,
+                    data: EntryData::Empty,
+                });
+                subdirectories.push((record, entry_path));
+                continue;
+            }
+
+            let extent = (record.extent as u64 * self.block_size, record.size as u64);
+            // Later parts of a multi-extent file follow the first record
+            if let Some(previous) = self
+                .entries
+                .last_mut()
+                .filter(|previous| previous.path == entry_path && !previous.is_directory)
+            {
+                if let EntryData::Extents(extents) = &mut previous.data {
+                    extents.push(extent);
+                    previous.size += record.size as u64;
+                    continue;
+                }
+            }
+            self.entries.push(ImageEntry {
+                path: entry_path,
+                size: record.size as u64,
+                modified: record.recorded,
+                is_directory: false,
+                data: if record.size == 0 && record.flags & FLAG_MULTI_EXTENT == 0 {
+                    EntryData::Empty
+                } else {
+                    EntryData::Extents(vec![extent])
+                },
+            });
+        }
+
+        for (record, entry_path) in subdirectories {
+            self.walk(&record, &entry_path, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// File name without its `;1` version and the dot of names without extension
+fn decode_name(raw: &[u8], joliet_names: bool) -> String {
+    let name = if joliet_names {
+        let units: Vec<u16> = raw
+            .chunks_exact(2)
+            .map(|unit| u16::from_be_bytes([unit[0], unit[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    } else {
+        String::from_utf8_lossy(raw).to_string()
+    };
+    let name = name.split(';').next().unwrap_or_default();
+    let name = name.strip_suffix('.').unwrap_or(name);
+    name.to_string()
+}
+
+/// Padded text field of a volume descriptor
+fn descriptor_text(field: &[u8], joliet_names: bool) -> Option<String> {
+    let text = if joliet_names {
+        let units: Vec<u16> = field
+            .chunks_exact(2)
+            .map(|unit| u16::from_be_bytes([unit[0], unit[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    } else {
+        String::from_utf8_lossy(field).to_string()
+    };
+    let text = text
+        .trim_matches(|c: char| c == ' ' || c == '\0')
+        .to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+/// Seven-byte recording date of a directory record
+fn recording_datetime(field: &[u8]) -> Option<DateTime<Utc>> {
+    let local = NaiveDate::from_ymd_opt(1900 + field[0] as i32, field[1] as u32, field[2] as u32)?
+        .and_hms_opt(field[3] as u32, field[4] as u32, field[5] as u32)?;
+    let offset = Duration::minutes(field[6] as i8 as i64 * 15);
+    Some(Utc.from_utc_datetime(&(local - offset)))
+}
+
+/// Seventeen-byte `YYYYMMDDHHMMSScc` date of a volume descriptor
+fn decimal_datetime(field: &[u8]) -> Option<DateTime<Utc>> {
+    let digits = std::str::from_utf8(&field[..16]).ok()?;
+    let number = |range: std::ops::Range<usize>| digits.get(range)?.parse::<u32>().ok();
+    let local = NaiveDate::from_ymd_opt(number(0..4)? as i32, number(4..6)?, number(6..8)?)?
+        .and_hms_opt(number(8..10)?, number(10..12)?, number(12..14)?)?;
+    let offset = Duration::minutes(field[16] as i8 as i64 * 15);
+    Some(Utc.from_utc_datetime(&(local - offset)))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// Directory record with a Joliet or ISO name
+    fn record(name: &[u8], extent: u32, size: u32, flags: u8) -> Vec<u8> {
+        let mut record = vec![0u8; 33];
+        record[2..6].copy_from_slice(&extent.to_le_bytes());
+        record[6..10].copy_from_slice(&extent.to_be_bytes());
+        record[10..14].copy_from_slice(&size.to_le_bytes());
+        record[14..18].copy_from_slice(&size.to_be_bytes());
+        record[18..25].copy_from_slice(&[124, 5, 17, 10, 30, 0, 8]);
+        record[25] = flags;
+        record[32] = name.len() as u8;
+        record.extend_from_slice(name);
+        if record.len() % 2 == 1 {
+            record.push(0);
+        }
+        record[0] = record.len() as u8;
+        record
+    }
+
+    fn ucs2(name: &str) -> Vec<u8> {
+        name.encode_utf16().flat_map(u16::to_be_bytes).collect()
+    }
+
+    /// ISO with `SETUP.EXE;1` and `DOCS/README.TXT;1`, and a Joliet tree when `joliet`
+    pub(crate) fn iso_image(joliet: bool) -> Vec<u8> {
+        let mut image = vec![0u8; 30 * SECTOR_SIZE as usize];
+        let sector = |n: usize| n * SECTOR_SIZE as usize;
+        let put = |image: &mut Vec<u8>, at: usize, data: &[u8]| {
+            image[at..at + data.len()].copy_from_slice(data);
+        };
+
+        let mut descriptors = vec![(16, TYPE_PRIMARY, 20u32, false)];
+        if joliet {
+            descriptors.push((17, TYPE_SUPPLEMENTARY, 23, true));
+        }
+        for (at, kind, root, wide) in &descriptors {
+            let at = sector(*at);
+            image[at] = *kind;
+            put(&mut image, at + 1, b"CD001\x01");
+            let label = if *wide {
+                ucs2("PRODUCT_MEDIA")
+            } else {
+                b"PRODUCT_MEDIA".to_vec()
+            };
+            put(&mut image, at + 40, &label);
+            put(&mut image, at + 128, &2048u16.to_le_bytes());
+            put(
+                &mut image,
+                at + 156,
+                &record(&[0], *root, 2048, FLAG_DIRECTORY),
+            );
+            put(&mut image, at + 318, b"Example Corp");
+            put(&mut image, at + 813, b"2024051710300000\x08");
+            if *wide {
+                put(&mut image, at + 88, b"%/E");
+            }
+        }
+        let terminator = sector(descriptors.len() + 16);
+        image[terminator] = TYPE_TERMINATOR;
+        put(&mut image, terminator + 1, b"CD001\x01");
+
+        // Primary tree at sectors 20-22, Joliet tree at 23-24
+        let trees: Vec<(usize, bool)> = if joliet {
+            vec![(20, false), (23, true)]
+        } else {
+            vec![(20, false)]
+        };
+        for (root, wide) in trees {
+            let name = |iso: &str, long: &str| {
+                if wide {
+                    ucs2(long)
+                } else {
+                    iso.as_bytes().to_vec()
+                }
+            };
+            let mut directory = record(&[0], root as u32, 2048, FLAG_DIRECTORY);
+            directory.extend(record(&[1], root as u32, 2048, FLAG_DIRECTORY));
+            directory.extend(record(
+                &name("DOCS", "docs"),
+                root as u32 + 1,
+                2048,
+                FLAG_DIRECTORY,
+            ));
+            directory.extend(record(&name("SETUP.EXE;1", "Setup.exe;1"), 26, 6, 0));
+            put(&mut image, sector(root), &directory);
+
+            let mut docs = record(&[0], root as u32 + 1, 2048, FLAG_DIRECTORY);
+            docs.extend(record(&[1], root as u32, 2048, FLAG_DIRECTORY));
+            docs.extend(record(&name("README.TXT;1", "Read me.txt;1"), 27, 5, 0));
+            docs.extend(record(&name("EMPTY.;1", "empty;1"), 0, 0, 0));
+            put(&mut image, sector(root + 1), &docs);
+        }
+        put(&mut image, sector(26), b"MZ\x90\0\x03\0");
+        put(&mut image, sector(27), b"hello");
+        image
+    }
+
+    #[test]
+    fn test_read_iso_image() {
+        for joliet in [false, true] {
+            let mut file = tempfile::NamedTempFile::new().unwrap();
+            file.write_all(&iso_image(joliet)).unwrap();
+            let mut reader = ImageReader::open(file.path()).unwrap();
+            assert!(is_iso(&mut reader));
+            let listing = read(&mut reader).unwrap();
+
+            let paths: Vec<&str> = listing.files().map(|entry| entry.path.as_str()).collect();
+            if joliet {
+                assert_eq!(listing.kind, ImageKind::Joliet);
+                assert_eq!(paths, vec!["Setup.exe", "docs/Read me.txt", "docs/empty"]);
+            } else {
+                assert_eq!(listing.kind, ImageKind::Iso9660);
+                assert_eq!(paths, vec!["SETUP.EXE", "DOCS/README.TXT", "DOCS/EMPTY"]);
+            }
+            assert_eq!(listing.label.as_deref(), Some("PRODUCT_MEDIA"));
+            assert_eq!(
+                listing.created,
+                Some(Utc.with_ymd_and_hms(2024, 5, 17, 8, 30, 0).unwrap())
+            );
+
+            let readme = &listing.files().nth(1).unwrap().clone();
+            assert_eq!(
+                readme.modified,
+                Some(Utc.with_ymd_and_hms(2024, 5, 17, 8, 30, 0).unwrap())
+            );
+            assert_eq!(listing.read_entry(&mut reader, readme).unwrap(), b"hello");
+        }
+    }
+}
@@ -0,0 +1,190 @@
+//! Files of a disk image and reading their content
+//!
+//! Images are read in place through positioned reads, without mounting.
+//! Each file system reader produces an [`ImageListing`] whose entries know
+//! where their data is: extents of the image for ISO 9660 and UDF, a blob
+//! resource for WIM.
+
+use super::{iso, udf, wim};
+use crate::core::{AnalyzerError, Result};
+use chrono::{DateTime, Utc};
+use std::fmt;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Largest number of entries read from an image
+pub const MAX_ENTRIES: usize = 1_000_000;
+/// Deepest directory nesting that is followed
+pub const MAX_DEPTH: usize = 64;
+
+/// File system or container format of an image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageKind {
+    Iso9660,
+    /// ISO 9660 with Joliet Unicode names
+    Joliet,
+    Udf,
+    Wim,
+}
+
+impl ImageKind {
+    /// Value used in analysis properties
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Iso9660 => "ISO 9660",
+            Self::Joliet => "ISO 9660 (Joliet)",
+            Self::Udf => "UDF",
+            Self::Wim => "WIM",
+        }
+    }
+}
+
+impl fmt::Display for ImageKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Positioned reads from an image file
+pub struct ImageReader {
+    file: std::fs::File,
+    size: u64,
+}
+
+impl ImageReader {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = std::fs::File::open(path)?;
+        let size = file.metadata()?.len();
+        Ok(Self { file, size })
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Read `length` bytes at `offset`, failing if the image ends before
+    pub fn read_at(&mut self, offset: u64, length: usize) -> Result<Vec<u8>> {
+        if offset.saturating_add(length as u64) > self.size {
+            return Err(AnalyzerError::parse_error(format!(
+                "Read of {} bytes at {} is beyond the end of the image",
+                length, offset
+            )));
+        }
+        let mut buffer = vec![0u8; length];
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.read_exact(&mut buffer)?;
+        Ok(buffer)
+    }
+}
+
+/// Where the content of an entry is stored
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntryData {
+    /// Byte ranges of the image, as offset and length
+    Extents(Vec<(u64, u64)>),
+    /// Blob resource of a WIM
+    Wim(wim::WimBlob),
+    /// Empty file or directory
+    Empty,
+}
+
+/// File or directory in an image
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageEntry {
+    /// Path from the image root, separated by `/`
+    pub path: String,
+    pub size: u64,
+    pub modified: Option<DateTime<Utc>>,
+    pub is_directory: bool,
+    pub data: EntryData,
+}
+
+/// Everything read from an image
+#[derive(Debug, Clone)]
+pub struct ImageListing {
+    pub kind: ImageKind,
+    /// Volume label, or the name of the first image of a WIM
+    pub label: Option<String>,
+    pub created: Option<DateTime<Utc>>,
+    pub entries: Vec<ImageEntry>,
+    /// Format-specific analysis properties
+    pub properties: Vec<(String, String)>,
+}
+
+impl ImageListing {
+    /// Read the listing of the image at `path`
+    ///
+    /// UDF is preferred over ISO 9660 on bridge discs, since Windows media
+    /// only list their files in the UDF tree.
+    pub fn read(path: &Path) -> Result<Self> {
+        let mut reader = ImageReader::open(path)?;
+        if wim::is_wim(&mut reader) {
+            return wim::read(&mut reader);
+        }
+        if udf::has_udf(&mut reader) {
+            match udf::read(&mut reader) {
+                Ok(listing) => return Ok(listing),
+                Err(e) => tracing::debug!("UDF file system not read, using ISO 9660: {}", e),
+            }
+        }
+        iso::read(&mut reader)
+    }
+
+    /// Entry at `path`, compared without case and with either separator
+    pub fn find(&self, path: &Path) -> Option<&ImageEntry> {
+        let wanted = path.to_string_lossy().replace('\\', "/");
+        let wanted = wanted.trim_start_matches('/');
+        self.entries
+            .iter()
+            .find(|entry| !entry.is_directory && entry.path.eq_ignore_ascii_case(wanted))
+    }
+
+    /// Content of a file entry
+    pub fn read_entry(&self, reader: &mut ImageReader, entry: &ImageEntry) -> Result<Vec<u8>> {
+        match &entry.data {
+            EntryData::Extents(extents) => {
+                let mut content = Vec::with_capacity(entry.size as usize);
+                for (offset, length) in extents {
+                    content.extend(reader.read_at(*offset, *length as usize)?);
+                }
+                content.truncate(entry.size as usize);
+                Ok(content)
+            }
+            EntryData::Wim(blob) => wim::read_blob(reader, blob),
+            EntryData::Empty => Ok(Vec::new()),
+        }
+    }
+
+    /// Files of the listing, without directories
+    pub fn files(&self) -> impl Iterator<Item = &ImageEntry> {
+        self.entries.iter().filter(|entry| !entry.is_directory)
+    }
+}
+
+/// Join a directory path and a name
+pub(crate) fn join_path(parent: &str, name: &str) -> String {
+    if parent.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", parent, name)
+    }
+}
+
+pub(crate) fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+pub(crate) fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+pub(crate) fn read_u64(data: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&data[offset..offset + 8]);
+    u64::from_le_bytes(bytes)
+}
@@ -0,0 +1,12 @@
+//! Disk image analyzer: ISO 9660, UDF and WIM product media
+
+pub mod analyzer;
+pub mod contents;
+pub mod iso;
+pub mod listing;
+pub mod udf;
+pub mod wim;
+
+pub use analyzer::DiskImageAnalyzer;
+pub use contents::ContainedInstallerReport;
+pub use listing::{ImageEntry, ImageKind, ImageListing};
@@ -0,0 +1,557 @@
+//! UDF file system
+//!
+//! Windows installation media and large product DVDs list their files in UDF
+//! only; the ISO 9660 tree of such bridge discs holds a placeholder. The
+//! anchor at sector 256 points to the volume descriptor sequence, whose
+//! partition and logical volume descriptors lead to the file set descriptor
+//! and the root directory. Type 1 partition maps (UDF 1.02 to 2.01) are
+//! supported, the metadata partitions of UDF 2.50 and later are not.
+
+use super::listing::{
+    join_path, read_u16, read_u32, read_u64, EntryData, ImageEntry, ImageKind, ImageListing,
+    ImageReader, MAX_DEPTH, MAX_ENTRIES,
+};
+use crate::core::{AnalyzerError, Result};
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use std::collections::HashSet;
+
+const SECTOR_SIZE: u64 = 2048;
+const ANCHOR_SECTOR: u64 = 256;
+/// Sectors of the volume recognition sequence that are searched
+const RECOGNITION_SECTORS: std::ops::Range<u64> = 16..32;
+/// Descriptors read from the volume descriptor sequence
+const MAX_VOLUME_DESCRIPTORS: u64 = 64;
+/// Largest directory that is read
+const MAX_DIRECTORY_SIZE: u64 = 16 * 1024 * 1024;
+
+const TAG_PRIMARY_VOLUME: u16 = 1;
+const TAG_ANCHOR: u16 = 2;
+const TAG_PARTITION: u16 = 5;
+const TAG_LOGICAL_VOLUME: u16 = 6;
+const TAG_TERMINATOR: u16 = 8;
+const TAG_FILE_SET: u16 = 256;
+const TAG_FILE_IDENTIFIER: u16 = 257;
+const TAG_FILE_ENTRY: u16 = 261;
+const TAG_EXTENDED_FILE_ENTRY: u16 = 266;
+
+const FILE_TYPE_DIRECTORY: u8 = 4;
+
+const CHARACTERISTIC_DIRECTORY: u8 = 0x02;
+const CHARACTERISTIC_DELETED: u8 = 0x04;
+const CHARACTERISTIC_PARENT: u8 = 0x08;
+
+/// The volume recognition sequence announces an NSR (UDF) descriptor
+pub fn has_udf(reader: &mut ImageReader) -> bool {
+    RECOGNITION_SECTORS.into_iter().any(|sector| {
+        reader
+            .read_at(sector * SECTOR_SIZE + 1, 5)
+            .is_ok_and(|id| id == b"NSR02" || id == b"NSR03")
+    })
+}
+
+/// Read the directory tree of a UDF image
+pub fn read(reader: &mut ImageReader) -> Result<ImageListing> {
+    let anchor = read_sector(reader, ANCHOR_SECTOR)?;
+    if read_u16(&anchor, 0) != TAG_ANCHOR {
+        return Err(AnalyzerError::parse_error(
+            "UDF anchor volume descriptor not found",
+        ));
+    }
+    let sequence_length = read_u32(&anchor, 16) as u64;
+    let sequence_start = read_u32(&anchor, 20) as u64;
+
+    let mut partitions: Vec<(u16, u64)> = Vec::new();
+    let mut logical_volume = None;
+    let mut primary_volume = None;
+    for index in 0..(sequence_length / SECTOR_SIZE).min(MAX_VOLUME_DESCRIPTORS) {
+        let descriptor = read_sector(reader, sequence_start + index)?;
+        match read_u16(&descriptor, 0) {
+            TAG_PRIMARY_VOLUME => primary_volume = Some(descriptor),
+            TAG_PARTITION => {
+                partitions.push((read_u16(&descriptor, 22), read_u32(&descriptor, 188) as u64))
+            }
+            TAG_LOGICAL_VOLUME => logical_volume = Some(descriptor),
+            TAG_TERMINATOR => break,
+            _ => {}
+        }
+    }
+    let logical_volume = logical_volume
+        .ok_or_else(|| AnalyzerError::parse_error("UDF logical volume descriptor not found"))?;
+    let block_size = read_u32(&logical_volume, 212) as u64;
+    if block_size != SECTOR_SIZE {
+        return Err(AnalyzerError::parse_error(format!(
+            "UDF logical block size {} is not supported",
+            block_size
+        )));
+    }
+
+    // Partition reference numbers index the partition maps
+    let map_count = read_u32(&logical_volume, 268) as usize;
+    let mut partition_starts = Vec::new();
+    let mut position = 440;
+    for _ in 0..map_count {
+        let Some(map) = logical_volume.get(position..position + 2) else {
+            break;
+        };
+        let (map_type, map_length) = (map[0], map[1] as usize);
+        if map_type != 1 {
+            return Err(AnalyzerError::parse_error(
+                "UDF virtual, sparable and metadata partitions are not supported",
+            ));
+        }
+        let number = read_u16(&logical_volume, position + 4);
+        let start = partitions
+            .iter()
+            .find(|(partition, _)| *partition == number)
+            .map(|(_, start)| *start)
+            .ok_or_else(|| {
+                AnalyzerError::parse_error(format!("UDF partition {} not found", number))
+            })?;
+        partition_starts.push(start);
+        position += map_length.max(6);
+    }
+
+    let mut walker = Walker {
+        reader,
+        partition_starts,
+        visited: HashSet::new(),
+        entries: Vec::new(),
+    };
+    let file_set = walker.long_ad(&logical_volume[248..264]);
+    let file_set = read_sector(walker.reader, file_set.sector)?;
+    if read_u16(&file_set, 0) != TAG_FILE_SET {
+        return Err(AnalyzerError::parse_error(
+            "UDF file set descriptor not found",
+        ));
+    }
+    let root = walker.long_ad(&file_set[400..416]);
+    walker.walk(root, "", 0)?;
+
+    let label = dstring(&logical_volume[84..212]).or_else(|| {
+        primary_volume
+            .as_ref()
+            .and_then(|pvd| dstring(&pvd[24..56]))
+    });
+    let created = primary_volume
+        .as_ref()
+        .and_then(|pvd| timestamp(&pvd[376..388]));
+
+    Ok(ImageListing {
+        kind: ImageKind::Udf,
+        label,
+        created,
+        entries: walker.entries,
+        properties: Vec::new(),
+    })
+}
+
+fn read_sector(reader: &mut ImageReader, sector: u64) -> Result<Vec<u8>> {
+    reader.read_at(sector * SECTOR_SIZE, SECTOR_SIZE as usize)
+}
+
+/// Absolute location of a file entry
+#[derive(Debug, Clone, Copy)]
+struct IcbLocation {
+    sector: u64,
+    partition: usize,
+}
+
+struct Walker<'a> {
+    reader: &'a mut ImageReader,
+    /// First sector of each partition, by partition reference number
+    partition_starts: Vec<u64>,
+    /// File entries of directories already read, guarding against loops
+    visited: HashSet<u64>,
+    entries: Vec<ImageEntry>,
+}
+
+impl Walker<'_> {
+    fn long_ad(&self, ad: &[u8]) -> IcbLocation {
+        let partition = read_u16(ad, 8) as usize;
+        IcbLocation {
+            sector: self.partition_start(partition) + read_u32(ad, 4) as u64,
+            partition,
+        }
+    }
+
+    fn partition_start(&self, partition: usize) -> u64 {
+        self.partition_starts.get(partition).copied().unwrap_or(0)
+    }
+
+    fn walk(&mut self, directory: IcbLocation, path: &str, depth: usize) -> Result<()> {
+        if depth > MAX_DEPTH || !self.visited.insert(directory.sector) {
+            return Ok(());
+        }
+        let entry = self.file_entry(directory)?;
+        let data = match &entry.data {
+            EntryData::Extents(extents) => {
+                let mut data = Vec::new();
+                for (offset, length) in extents {
+                    let remaining = MAX_DIRECTORY_SIZE.saturating_sub(data.len() as u64);
+                    data.extend(
+                        self.reader
+                            .read_at(*offset, (*length).min(remaining) as usize)?,
+                    );
+                }
+                data.truncate(entry.size.min(MAX_DIRECTORY_SIZE) as usize);
+                data
+            }
+            _ => Vec::new(),
+        };
+
+        let mut subdirectories = Vec::new();
+        let mut position = 0;
+        while position + 38 <= data.len() && self.entries.len() < MAX_ENTRIES {
+            let descriptor = &data[position..];
+            if read_u16(descriptor, 0) != TAG_FILE_IDENTIFIER {
+                break;
+            }
+            let characteristics = descriptor[18];
+            let name_length = descriptor[19] as usize;
+            let implementation_length = read_u16(descriptor, 36) as usize;
+            let name_start = 38 + implementation_length;
+            let length = (name_start + name_length + 3) & !3;
+            let Some(raw_name) = descriptor.get(name_start..name_start + name_length) else {
+                break;
+            };
+            let icb = self.long_ad(&descriptor[20..36]);
+            position += length;
+
+            if characteristics & (CHARACTERISTIC_PARENT | CHARACTERISTIC_DELETED) != 0 {
+                continue;
+            }
+            let entry_path = join_path(path, &dchars(raw_name));
+            if characteristics & CHARACTERISTIC_DIRECTORY != 0 {
+                let modified = self.file_entry(icb).ok().and_then(|entry| entry.modified);
+                self.entries.push(ImageEntry {
+                    path: entry_path.clone(),
+                    size: 0,
+                    modified,
+                    is_directory: true,
+                    data: EntryData::Empty,
+                });
+                subdirectories.push((icb, entry_path));
+            } else {
+                let mut entry = self.file_entry(icb)?;
+                entry.path = entry_path;
+                self.entries.push(entry);
+            }
+        }
+
+        for (icb, entry_path) in subdirectories {
+            self.walk(icb, &entry_path, depth + 1)?;
+        }
+        Ok(())
+    }
+
+    /// Read a (extended) file entry with the extents of its data
+    fn file_entry(&mut self, location: IcbLocation) -> Result<ImageEntry> {
+        let sector = read_sector(self.reader, location.sector)?;
+        let (modified_at, extended_attributes_at) = match read_u16(&sector, 0) {
+            TAG_FILE_ENTRY => (84, 168),
+            TAG_EXTENDED_FILE_ENTRY => (92, 208),
+            _ => {
+                return Err(AnalyzerError::parse_error(format!(
+                    "UDF file entry not found at sector {}",
+                    location.sector
+                )))
+            }
+        };
+        let is_directory = sector[27] == FILE_TYPE_DIRECTORY;
+        let descriptor_type = read_u16(&sector, 34) & 0x07;
+        let size = read_u64(&sector, 56);
+        let extended_attributes_length = read_u32(&sector, extended_attributes_at) as usize;
+        let descriptors_length = read_u32(&sector, extended_attributes_at + 4) as usize;
+        let descriptors_start = extended_attributes_at + 8 + extended_attributes_length;
+        let descriptors = sector
+            .get(descriptors_start..descriptors_start + descriptors_length)
+            .ok_or_else(|| AnalyzerError::parse_error("UDF allocation descriptors overflow"))?;
+
+        let mut extents = Vec::new();
+        match descriptor_type {
+            // short_ad and long_ad; unrecorded extents read as missing data
+            0 | 1 => {
+                let ad_size = if descriptor_type == 0 { 8 } else { 16 };
+                for ad in descriptors.chunks_exact(ad_size) {
+                    let length = (read_u32(ad, 0) & 0x3fff_ffff) as u64;
+                    let extent_type = read_u32(ad, 0) >> 30;
+                    if length == 0 {
+                        break;
+                    }
+                    if extent_type != 0 {
+                        continue;
+                    }
+                    let start = if ad_size == 8 {
+                        self.partition_start(location.partition) + read_u32(ad, 4) as u64
+                    } else {
+                        self.long_ad(ad).sector
+                    };
+                    extents.push((start * SECTOR_SIZE, length));
+                }
+            }
+            // Data embedded in the entry itself
+            3 => extents.push((
+                location.sector * SECTOR_SIZE + descriptors_start as u64,
+                descriptors_length as u64,
+            )),
+            _ => {
+                return Err(AnalyzerError::parse_error(
+                    "UDF extended allocation descriptors are not supported",
+                ))
+            }
+        }
+
+        Ok(ImageEntry {
+            path: String::new(),
+            size,
+            modified: timestamp(&sector[modified_at..modified_at + 12]),
+            is_directory,
+            data: if extents.is_empty() || size == 0 {
+                EntryData::Empty
+            } else {
+                EntryData::Extents(extents)
+            },
+        })
+    }
+}
+
+/// Compressed Unicode name: a compression ID of 8 (Latin-1) or 16 (UTF-16BE)
+fn dchars(raw: &[u8]) -> String {
+    match raw.split_first() {
+        Some((16, units)) => {
+            let units: Vec<u16> = units
+                .chunks_exact(2)
+                .map(|unit| u16::from_be_bytes([unit[0], unit[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        Some((_, bytes)) => bytes.iter().map(|&b| b as char).collect(),
+        None => String::new(),
+    }
+}
+
+/// Fixed-size d-string whose last byte holds the used length
+fn dstring(field: &[u8]) -> Option<String> {
+    let (&length, data) = field.split_last()?;
+    let text = dchars(data.get(..length as usize)?);
+    let text = text.trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+/// ECMA-167 timestamp with its time zone offset in minutes
+fn timestamp(field: &[u8]) -> Option<DateTime<Utc>> {
+    let type_and_zone = read_u16(field, 0);
+    let local = NaiveDate::from_ymd_opt(
+        read_u16(field, 2) as i16 as i32,
+        field[4] as u32,
+        field[5] as u32,
+    )?
+    .and_hms_opt(field[6] as u32, field[7] as u32, field[8] as u32)?;
+    // 12-bit signed offset, -2047 when unspecified
+    let zone = ((type_and_zone & 0x0fff) as i16) << 4 >> 4;
+    let offset = if type_and_zone >> 12 == 1 && zone != -2047 {
+        Duration::minutes(zone as i64)
+    } else {
+        Duration::zero()
+    };
+    Some(Utc.from_utc_datetime(&(local - offset)))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const PARTITION_START: usize = 272;
+
+    fn tag(sector: &mut [u8], id: u16) {
+        sector[0..2].copy_from_slice(&id.to_le_bytes());
+    }
+
+    fn timestamp_bytes() -> [u8; 12] {
+        // 2024-05-17 10:30:00 at UTC+2
+        let mut field = [0u8; 12];
+        field[0..2].copy_from_slice(&(0x1000u16 | 120).to_le_bytes());
+        field[2..4].copy_from_slice(&2024u16.to_le_bytes());
+        field[4..9].copy_from_slice(&[5, 17, 10, 30, 0]);
+        field
+    }
+
+    fn long_ad(length: u32, block: u32) -> Vec<u8> {
+        let mut ad = length.to_le_bytes().to_vec();
+        ad.extend(block.to_le_bytes());
+        ad.extend([0u8; 8]);
+        ad
+    }
+
+    fn file_identifier(name: &str, block: u32, characteristics: u8) -> Vec<u8> {
+        let mut raw_name = Vec::new();
+        if !name.is_empty() {
+            raw_name.push(8);
+            raw_name.extend(name.as_bytes());
+        }
+        let mut descriptor = vec![0u8; 16];
+        tag(&mut descriptor, TAG_FILE_IDENTIFIER);
+        descriptor.extend(1u16.to_le_bytes());
+        descriptor.push(characteristics);
+        descriptor.push(raw_name.len() as u8);
+        descriptor.extend(long_ad(2048, block));
+        descriptor.extend(0u16.to_le_bytes());
+        descriptor.extend(raw_name);
+        while !descriptor.len().is_multiple_of(4) {
+            descriptor.push(0);
+        }
+        descriptor
+    }
+
+    /// File entry with short_ad extents, or embedded data when `embedded` is set
+    fn file_entry(
+        extended: bool,
+        file_type: u8,
+        size: u64,
+        extents: &[(u32, u32)],
+        embedded: Option<&[u8]>,
+    ) -> Vec<u8> {
+        let mut sector = vec![0u8; SECTOR_SIZE as usize];
+        let (modified_at, attributes_at) = if extended { (92, 208) } else { (84, 168) };
+        tag(
+            &mut sector,
+            if extended {
+                TAG_EXTENDED_FILE_ENTRY
+            } else {
+                TAG_FILE_ENTRY
+            },
+        );
+        sector[27] = file_type;
+        sector[56..64].copy_from_slice(&size.to_le_bytes());
+        sector[modified_at..modified_at + 12].copy_from_slice(&timestamp_bytes());
+        let descriptors: Vec<u8> = match embedded {
+            Some(data) => {
+                sector[34] = 3;
+                data.to_vec()
+            }
+            None => extents
+                .iter()
+                .flat_map(|(length, block)| {
+                    length.to_le_bytes().into_iter().chain(block.to_le_bytes())
+                })
+                .collect(),
+        };
+        sector[attributes_at + 4..attributes_at + 8]
+            .copy_from_slice(&(descriptors.len() as u32).to_le_bytes());
+        sector[attributes_at + 8..attributes_at + 8 + descriptors.len()]
+            .copy_from_slice(&descriptors);
+        sector
+    }
+
+    /// UDF image with `setup.exe` and `sub/readme.txt`
+    pub(crate) fn udf_image() -> Vec<u8> {
+        let mut image = vec![0u8; (PARTITION_START + 8) * SECTOR_SIZE as usize];
+        let mut put = |sector: usize, data: &[u8]| {
+            let at = sector * SECTOR_SIZE as usize;
+            image[at..at + data.len()].copy_from_slice(data);
+        };
+
+        put(16, b"\0BEA01\x01");
+        put(17, b"\0NSR02\x01");
+        put(18, b"\0TEA01\x01");
+
+        let mut anchor = vec![0u8; 32];
+        tag(&mut anchor, TAG_ANCHOR);
+        anchor[16..20].copy_from_slice(&(4 * SECTOR_SIZE as u32).to_le_bytes());
+        anchor[20..24].copy_from_slice(&32u32.to_le_bytes());
+        put(ANCHOR_SECTOR as usize, &anchor);
+
+        let mut primary = vec![0u8; 512];
+        tag(&mut primary, TAG_PRIMARY_VOLUME);
+        primary[376..388].copy_from_slice(&timestamp_bytes());
+        put(32, &primary);
+
+        let mut partition = vec![0u8; 512];
+        tag(&mut partition, TAG_PARTITION);
+        partition[188..192].copy_from_slice(&(PARTITION_START as u32).to_le_bytes());
+        put(33, &partition);
+
+        let mut logical = vec![0u8; 512];
+        tag(&mut logical, TAG_LOGICAL_VOLUME);
+        logical[84] = 8;
+        logical[85..90].copy_from_slice(b"MEDIA");
+        logical[211] = 6;
+        logical[212..216].copy_from_slice(&2048u32.to_le_bytes());
+        logical[248..264].copy_from_slice(&long_ad(2048, 0));
+        logical[268..272].copy_from_slice(&1u32.to_le_bytes());
+        logical[440..446].copy_from_slice(&[1, 6, 1, 0, 0, 0]);
+        put(34, &logical);
+
+        let mut terminator = vec![0u8; 16];
+        tag(&mut terminator, TAG_TERMINATOR);
+        put(35, &terminator);
+
+        // Partition blocks: 0 file set, 1 root, 2 root data, 3 setup.exe,
+        // 4 its data, 5 sub (embedded), 6 readme.txt (embedded)
+        let mut file_set = vec![0u8; 512];
+        tag(&mut file_set, TAG_FILE_SET);
+        file_set[400..416].copy_from_slice(&long_ad(2048, 1));
+        put(PARTITION_START, &file_set);
+
+        let mut root = file_identifier("", 1, CHARACTERISTIC_DIRECTORY | CHARACTERISTIC_PARENT);
+        root.extend(file_identifier("setup.exe", 3, 0));
+        root.extend(file_identifier("sub", 5, CHARACTERISTIC_DIRECTORY));
+        root.extend(file_identifier("gone.txt", 6, CHARACTERISTIC_DELETED));
+        put(
+            PARTITION_START + 1,
+            &file_entry(
+                false,
+                FILE_TYPE_DIRECTORY,
+                root.len() as u64,
+                &[(root.len() as u32, 2)],
+                None,
+            ),
+        );
+        put(PARTITION_START + 2, &root);
+        put(
+            PARTITION_START + 3,
+            &file_entry(false, 5, 6, &[(6, 4)], None),
+        );
+        put(PARTITION_START + 4, b"MZ\x90\0\x03\0");
+
+        let mut sub = file_identifier("", 1, CHARACTERISTIC_DIRECTORY | CHARACTERISTIC_PARENT);
+        sub.extend(file_identifier("readme.txt", 6, 0));
+        put(
+            PARTITION_START + 5,
+            &file_entry(true, FILE_TYPE_DIRECTORY, sub.len() as u64, &[], Some(&sub)),
+        );
+        put(
+            PARTITION_START + 6,
+            &file_entry(false, 5, 5, &[], Some(b"hello")),
+        );
+        image
+    }
+
+    #[test]
+    fn test_read_udf_image() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&udf_image()).unwrap();
+        let mut reader = ImageReader::open(file.path()).unwrap();
+        assert!(has_udf(&mut reader));
+
+        let listing = read(&mut reader).unwrap();
+        assert_eq!(listing.kind, ImageKind::Udf);
+        assert_eq!(listing.label.as_deref(), Some("MEDIA"));
+        let paths: Vec<&str> = listing.files().map(|entry| entry.path.as_str()).collect();
+        assert_eq!(paths, vec!["setup.exe", "sub/readme.txt"]);
+
+        let setup = listing.files().next().unwrap().clone();
+        assert_eq!(setup.size, 6);
+        assert_eq!(
+            setup.modified,
+            Some(Utc.with_ymd_and_hms(2024, 5, 17, 8, 30, 0).unwrap())
+        );
+        assert_eq!(
+            listing.read_entry(&mut reader, &setup).unwrap(),
+            b"MZ\x90\0\x03\0"
+        );
+        let readme = listing.files().nth(1).unwrap().clone();
+        assert_eq!(listing.read_entry(&mut reader, &readme).unwrap(), b"hello");
+    }
+}
@@ -0,0 +1,606 @@
+//! Windows Imaging (WIM) archives
+//!
+//! A WIM stores each distinct file content once as a blob, found by its
+//! SHA-1 in the blob table. Every image has a metadata resource holding the
+//! security data and a tree of directory entries that reference blobs by
+//! hash, and the XML resource describes the images. Uncompressed and XPRESS
+//! resources are read; LZX, LZMS and solid resources are reported without a
+//! listing. Only the first image is listed, since the editions of a Windows
+//! `install.wim` share nearly all of their files.
+
+use super::listing::{
+    join_path, read_u16, read_u32, read_u64, EntryData, ImageEntry, ImageKind, ImageListing,
+    ImageReader, MAX_DEPTH, MAX_ENTRIES,
+};
+use crate::analyzers::common::{filetime, xpress};
+use crate::core::{AnalyzerError, Result};
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+
+const MAGIC: &[u8; 8] = b"MSWIM\0\0\0";
+const HEADER_SIZE: usize = 208;
+const DEFAULT_CHUNK_SIZE: u32 = 32768;
+const BLOB_ENTRY_SIZE: usize = 50;
+/// Largest metadata or XML resource that is read
+const MAX_METADATA_SIZE: u64 = 512 * 1024 * 1024;
+
+const HEADER_FLAG_COMPRESSION: u32 = 0x0000_0002;
+const HEADER_FLAG_XPRESS: u32 = 0x0002_0000;
+const HEADER_FLAG_LZX: u32 = 0x0004_0000;
+const HEADER_FLAG_LZMS: u32 = 0x0008_0000;
+
+const RESOURCE_FLAG_METADATA: u8 = 0x02;
+const RESOURCE_FLAG_COMPRESSED: u8 = 0x04;
+const RESOURCE_FLAG_SOLID: u8 = 0x10;
+
+const ATTRIBUTE_DIRECTORY: u32 = 0x10;
+
+/// Compression of the resources of a WIM
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WimCompression {
+    None,
+    Xpress,
+    Lzx,
+    Lzms,
+}
+
+impl WimCompression {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Xpress => "XPRESS",
+            Self::Lzx => "LZX",
+            Self::Lzms => "LZMS",
+        }
+    }
+}
+
+/// Resource holding the content of a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WimBlob {
+    /// Offset of the resource in the WIM
+    pub offset: u64,
+    /// Size of the resource in the WIM
+    pub stored_size: u64,
+    /// Size of the content
+    pub size: u64,
+    pub flags: u8,
+    pub compression: WimCompression,
+    pub chunk_size: u32,
+}
+
+impl WimBlob {
+    fn parse(header: &[u8], compression: WimCompression, chunk_size: u32) -> Self {
+        let mut stored_size = [0u8; 8];
+        stored_size[..7].copy_from_slice(&header[..7]);
+        Self {
+            offset: read_u64(header, 8),
+            stored_size: u64::from_le_bytes(stored_size),
+            size: read_u64(header, 16),
+            flags: header[7],
+            compression,
+            chunk_size,
+        }
+    }
+
+    fn is_compressed(&self) -> bool {
+        self.flags & RESOURCE_FLAG_COMPRESSED != 0
+    }
+
+    /// Whether the content can be decoded by [`read_blob`]
+    fn is_readable(&self) -> bool {
+        self.flags & RESOURCE_FLAG_SOLID == 0
+            && (!self.is_compressed()
+                || matches!(
+                    self.compression,
+                    WimCompression::None | WimCompression::Xpress
+                ))
+    }
+}
+
+/// Whether the image starts with the WIM magic
+pub fn is_wim(reader: &mut ImageReader) -> bool {
+    reader
+        .read_at(0, MAGIC.len())
+        .is_ok_and(|magic| magic == MAGIC)
+}
+
+/// Read the images and the file tree of the first image of a WIM
+pub fn read(reader: &mut ImageReader) -> Result<ImageListing> {
+    let header = reader.read_at(0, HEADER_SIZE)?;
+    if &header[..8] != MAGIC {
+        return Err(AnalyzerError::parse_error("Not a WIM archive"));
+    }
+    let flags = read_u32(&header, 16);
+    let compression = if flags & HEADER_FLAG_COMPRESSION == 0 {
+        WimCompression::None
+    } else if flags & HEADER_FLAG_XPRESS != 0 {
+        WimCompression::Xpress
+    } else if flags & HEADER_FLAG_LZX != 0 {
+        WimCompression::Lzx
+    } else if flags & HEADER_FLAG_LZMS != 0 {
+        WimCompression::Lzms
+    } else {
+        WimCompression::None
+    };
+    let chunk_size = match read_u32(&header, 20) {
+        0 => DEFAULT_CHUNK_SIZE,
+        size => size,
+    };
+    let part_number = read_u16(&header, 40);
+    let total_parts = read_u16(&header, 42);
+    let image_count = read_u32(&header, 44);
+    let resource =
+        |offset: usize| WimBlob::parse(&header[offset..offset + 24], compression, chunk_size);
+    let blob_table = resource(48);
+    let xml = resource(72);
+
+    let mut properties = vec![
+        ("wim_image_count".to_string(), image_count.to_string()),
+        (
+            "wim_compression".to_string(),
+            compression.as_str().to_string(),
+        ),
+    ];
+    if total_parts > 1 {
+        properties.push((
+            "wim_split_part".to_string(),
+            format!("{} of {}", part_number, total_parts),
+        ));
+    }
+
+    // Blob table: content resources by hash and metadata resources in order
+    let table = read_limited(reader, &blob_table)?;
+    let mut blobs: HashMap<[u8; 20], WimBlob> = HashMap::new();
+    let mut metadata = Vec::new();
+    for entry in table.chunks_exact(BLOB_ENTRY_SIZE) {
+        let blob = WimBlob::parse(&entry[..24], compression, chunk_size);
+        if blob.flags & RESOURCE_FLAG_METADATA != 0 {
+            metadata.push(blob);
+        } else {
+            let mut hash = [0u8; 20];
+            hash.copy_from_slice(&entry[30..50]);
+            blobs.insert(hash, blob);
+        }
+    }
+
+    let description = if xml.size > 0 {
+        let xml = read_limited(reader, &xml)?;
+        let units: Vec<u16> = xml
+            .chunks_exact(2)
+            .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    } else {
+        String::new()
+    };
+    let images = describe_images(&description);
+    let label = images.first().and_then(|image| image.name.clone());
+    let created = images.first().and_then(|image| image.created);
+    let names: Vec<&str> = images
+        .iter()
+        .filter_map(|image| image.name.as_deref())
+        .collect();
+    if !names.is_empty() {
+        properties.push(("wim_images".to_string(), names.join("; ")));
+    }
+    if let Some(version) = images.first().and_then(|image| image.version.clone()) {
+        properties.push(("wim_windows_version".to_string(), version));
+    }
+
+    let mut entries = Vec::new();
+    match metadata.first() {
+        Some(resource) if resource.is_readable() => {
+            let data = read_limited(reader, resource)?;
+            let mut walker = Walker {
+                data: &data,
+                blobs: &blobs,
+                visited: HashSet::new(),
+                entries: Vec::new(),
+            };
+            // The security data is followed by the root directory entry
+            let security_length = match data.get(..4) {
+                Some(length) => (read_u32(length, 0) as usize).max(8),
+                None => 8,
+            };
+            let root = (security_length + 7) & !7;
+            if let Some(root) = walker.dentry(root) {
+                if root.subdirectory != 0 {
+                    walker.walk(root.subdirectory as usize, "", 0);
+                }
+            }
+            entries = walker.entries;
+            properties.push(("wim_listed_image".to_string(), "1".to_string()));
+        }
+        Some(_) => properties.push((
+            "wim_note".to_string(),
+            format!(
+                "Files not listed: {} compressed resources are not supported",
+                compression.as_str()
+            ),
+        )),
+        None => {}
+    }
+
+    Ok(ImageListing {
+        kind: ImageKind::Wim,
+        label,
+        created,
+        entries,
+        properties,
+    })
+}
+
+/// Content of a blob, decompressed chunk by chunk
+pub fn read_blob(reader: &mut ImageReader, blob: &WimBlob) -> Result<Vec<u8>> {
+    if !blob.is_readable() {
+        return Err(AnalyzerError::parse_error(format!(
+            "WIM resources with {} compression are not supported",
+            blob.compression.as_str()
+        )));
+    }
+    if !blob.is_compressed() {
+        return reader.read_at(blob.offset, blob.size as usize);
+    }
+
+    let chunk_size = blob.chunk_size as u64;
+    let chunk_count = blob.size.div_ceil(chunk_size);
+    if chunk_count == 0 {
+        return Ok(Vec::new());
+    }
+    // Offsets of chunks after the first, relative to the end of the table
+    let entry_size = if blob.size > u32::MAX as u64 { 8 } else { 4 };
+    let table_size = (chunk_count - 1) * entry_size;
+    let table = reader.read_at(blob.offset, table_size as usize)?;
+    let mut starts = vec![0u64];
+    starts.extend(table.chunks_exact(entry_size as usize).map(|entry| {
+        if entry_size == 8 {
+            read_u64(entry, 0)
+        } else {
+            read_u32(entry, 0) as u64
+        }
+    }));
+    let data_size = blob.stored_size.saturating_sub(table_size);
+
+    let mut content = Vec::with_capacity(blob.size as usize);
+    for (index, start) in starts.iter().enumerate() {
+        let end = starts.get(index + 1).copied().unwrap_or(data_size);
+        if end < *start || end > data_size {
+            return Err(AnalyzerError::parse_error("Invalid WIM chunk table"));
+        }
+        let expected = chunk_size.min(blob.size - index as u64 * chunk_size) as usize;
+        let chunk = reader.read_at(blob.offset + table_size + start, (end - start) as usize)?;
+        if chunk.len() == expected {
+            content.extend(chunk);
+        } else {
+            content.extend(xpress::decompress(&chunk, expected)?);
+        }
+    }
+    Ok(content)
+}
+
+fn read_limited(reader: &mut ImageReader, blob: &WimBlob) -> Result<Vec<u8>> {
+    if blob.size > MAX_METADATA_SIZE {
+        return Err(AnalyzerError::parse_error(format!(
+            "WIM resource of {} bytes is too large",
+            blob.size
+        )));
+    }
+    read_blob(reader, blob)
+}
+
+/// Image described in the XML resource
+#[derive(Debug, Default)]
+struct ImageDescription {
+    name: Option<String>,
+    version: Option<String>,
+    created: Option<DateTime<Utc>>,
+}
+
+fn describe_images(xml: &str) -> Vec<ImageDescription> {
+    let image = Regex::new(r"(?s)<IMAGE\b[^>]*>(.*?)</IMAGE>").unwrap();
+    let element = |block: &str, name: &str| {
+        Regex::new(&format!(r"(?s)<{0}>\s*(.*?)\s*</{0}>", name))
+            .unwrap()
+            .captures(block)
+            .map(|captures| captures[1].to_string())
+    };
+    image
+        .captures_iter(xml)
+        .map(|captures| {
+            let block = &captures[1];
+            let version = element(block, "VERSION").and_then(|version| {
+                let major = element(&version, "MAJOR")?;
+                let minor = element(&version, "MINOR")?;
+                let build = element(&version, "BUILD")?;
+                Some(format!("{}.{}.{}", major, minor, build))
+            });
+            let created = element(block, "CREATIONTIME").and_then(|time| {
+                let part = |name| {
+                    element(&time, name)
+                        .and_then(|hex| u32::from_str_radix(hex.trim_start_matches("0x"), 16).ok())
+                };
+                filetime(((part("HIGHPART")? as u64) << 32) | part("LOWPART")? as u64)
+            });
+            ImageDescription {
+                name: element(block, "NAME").filter(|name| !name.is_empty()),
+                version,
+                created,
+            }
+        })
+        .collect()
+}
+
+/// Directory entry of a metadata resource
+struct Dentry {
+    length: u64,
+    attributes: u32,
+    subdirectory: u64,
+    modified: Option<DateTime<Utc>>,
+    hash: [u8; 20],
+    name: String,
+    /// Offset of the next sibling
+    next: usize,
+}
+
+struct Walker<'a> {
+    data: &'a [u8],
+    blobs: &'a HashMap<[u8; 20], WimBlob>,
+    /// Directory lists already read, guarding against loops
+    visited: HashSet<usize>,
+    entries: Vec<ImageEntry>,
+}
+
+impl Walker<'_> {
+    fn dentry(&self, offset: usize) -> Option<Dentry> {
+        let data = self.data.get(offset..)?;
+        let length = read_u64(data.get(..8)?, 0);
+        if length < 8 || data.len() < 102 {
+            return None;
+        }
+        let mut hash = [0u8; 20];
+        hash.copy_from_slice(&data[64..84]);
+        let stream_count = read_u16(data, 96) as usize;
+        let name_length = read_u16(data, 100) as usize;
+        let name: Vec<u16> = data
+            .get(102..102 + name_length)?
+            .chunks_exact(2)
+            .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+            .collect();
+
+        // Alternate data streams follow the entry; an unnamed one holds
+        // the file content in images captured by newer tools
+        let mut next = offset.checked_add((length as usize + 7) & !7)?;
+        for _ in 0..stream_count {
+            let stream = self.data.get(next..next + 38)?;
+            let stream_length = read_u64(stream, 0) as usize;
+            if read_u16(stream, 36) == 0 && hash == [0u8; 20] {
+                hash.copy_from_slice(&stream[16..36]);
+            }
+            next = next.checked_add((stream_length.max(8) + 7) & !7)?;
+        }
+
+        Some(Dentry {
+            length,
+            attributes: read_u32(data, 8),
+            subdirectory: read_u64(data, 16),
+            modified: filetime(read_u64(data, 56)),
+            hash,
+            name: String::from_utf16_lossy(&name),
+            next,
+        })
+    }
+
+    fn walk(&mut self, offset: usize, path: &str, depth: usize) {
+        if depth > MAX_DEPTH || !self.visited.insert(offset) {
+            return;
+        }
+        let mut subdirectories = Vec::new();
+        let mut position = offset;
+        while self.entries.len() < MAX_ENTRIES {
+            let Some(dentry) = self.dentry(position) else {
+                break;
+            };
+            position = dentry.next;
+            if dentry.length < 102 || dentry.name.is_empty() {
+                continue;
+            }
+            let entry_path = join_path(path, &dentry.name);
+            if dentry.attributes & ATTRIBUTE_DIRECTORY != 0 {
+                self.entries.push(ImageEntry {
+                    path: entry_path.clone(),
+                    size: 0,
+                    modified: dentry.modified,
+                    is_directory: true,
+                    data: EntryData::Empty,
+                });
+                if dentry.subdirectory != 0 {
+                    subdirectories.push((dentry.subdirectory as usize, entry_path));
+                }
+            } else {
+                let blob = self.blobs.get(&dentry.hash).cloned();
+                self.entries.push(ImageEntry {
+                    path: entry_path,
+                    size: blob.as_ref().map_or(0, |blob| blob.size),
+                    modified: dentry.modified,
+                    is_directory: false,
+                    data: blob.map_or(EntryData::Empty, EntryData::Wim),
+                });
+            }
+        }
+
+        for (offset, entry_path) in subdirectories {
+            self.walk(offset, &entry_path, depth + 1);
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::io::Write;
+
+    /// 2024-05-17 08:30:00 UTC
+    const MODIFIED: u64 = 133_604_082_000_000_000;
+
+    fn resource_header(offset: u64, size: u64, flags: u8) -> Vec<u8> {
+        let mut header = size.to_le_bytes()[..7].to_vec();
+        header.push(flags);
+        header.extend(offset.to_le_bytes());
+        header.extend(size.to_le_bytes());
+        header
+    }
+
+    fn dentry(name: &str, attributes: u32, subdirectory: u64, hash: [u8; 20]) -> Vec<u8> {
+        let name: Vec<u8> = name.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let mut entry = vec![0u8; 102];
+        entry[8..12].copy_from_slice(&attributes.to_le_bytes());
+        entry[16..24].copy_from_slice(&subdirectory.to_le_bytes());
+        entry[56..64].copy_from_slice(&MODIFIED.to_le_bytes());
+        entry[64..84].copy_from_slice(&hash);
+        entry[100..102].copy_from_slice(&(name.len() as u16).to_le_bytes());
+        entry.extend(name);
+        entry.extend([0, 0]);
+        let length = entry.len() as u64;
+        entry[..8].copy_from_slice(&length.to_le_bytes());
+        while !entry.len().is_multiple_of(8) {
+            entry.push(0);
+        }
+        entry
+    }
+
+    /// Uncompressed WIM with `setup.exe` and `sources/readme.txt`
+    pub(crate) fn wim_image() -> Vec<u8> {
+        let setup = b"MZ\x90\0\x03\0".to_vec();
+        let readme = b"hello".to_vec();
+
+        // Security data of 8 bytes, the root at 8, then the list of its
+        // children and the list of `sources`, each ended by 8 zero bytes
+        let mut root = dentry("", ATTRIBUTE_DIRECTORY, 0, [0; 20]);
+        let children_at = 8 + root.len() as u64;
+        let setup_entry = dentry("setup.exe", 0x20, 0, [1; 20]);
+        let sources_children_at = children_at + setup_entry.len() as u64 + 120 + 8;
+        let sources_entry = dentry("sources", ATTRIBUTE_DIRECTORY, sources_children_at, [0; 20]);
+        root[16..24].copy_from_slice(&children_at.to_le_bytes());
+
+        let mut metadata = 8u32.to_le_bytes().to_vec();
+        metadata.extend([0u8; 4]);
+        metadata.extend(root);
+        metadata.extend(setup_entry);
+        metadata.extend(sources_entry);
+        metadata.extend([0u8; 8]);
+        assert_eq!(metadata.len() as u64, sources_children_at);
+        metadata.extend(dentry("readme.txt", 0x20, 0, [2; 20]));
+        metadata.extend([0u8; 8]);
+
+        let xml: Vec<u8> = "<WIM><IMAGE INDEX=\"1\"><CREATIONTIME><HIGHPART>0x01DAA82E</HIGHPART>\
+             <LOWPART>0x6C5F0800</LOWPART></CREATIONTIME><WINDOWS><VERSION><MAJOR>10</MAJOR>\
+             <MINOR>0</MINOR><BUILD>19041</BUILD></VERSION></WINDOWS>\
+             <NAME>Windows 10 Pro</NAME></IMAGE></WIM>"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+
+        let mut image = vec![0u8; HEADER_SIZE];
+        let mut place = |data: &[u8]| {
+            let offset = image.len() as u64;
+            image.extend(data);
+            offset
+        };
+        let setup_at = place(&setup);
+        let readme_at = place(&readme);
+        let metadata_at = place(&metadata);
+        let xml_at = place(&xml);
+
+        let mut table = Vec::new();
+        for (offset, size, flags, hash) in [
+            (setup_at, setup.len(), 0, [1u8; 20]),
+            (readme_at, readme.len(), 0, [2u8; 20]),
+            (
+                metadata_at,
+                metadata.len(),
+                RESOURCE_FLAG_METADATA,
+                [3u8; 20],
+            ),
+        ] {
+            table.extend(resource_header(offset, size as u64, flags));
+            table.extend(1u16.to_le_bytes());
+            table.extend(1u32.to_le_bytes());
+            table.extend(hash);
+        }
+        let table_at = place(&table);
+
+        image[..8].copy_from_slice(MAGIC);
+        image[8..12].copy_from_slice(&(HEADER_SIZE as u32).to_le_bytes());
+        image[40..42].copy_from_slice(&1u16.to_le_bytes());
+        image[42..44].copy_from_slice(&1u16.to_le_bytes());
+        image[44..48].copy_from_slice(&1u32.to_le_bytes());
+        image[48..72].copy_from_slice(&resource_header(table_at, table.len() as u64, 0));
+        image[72..96].copy_from_slice(&resource_header(xml_at, xml.len() as u64, 0));
+        image
+    }
+
+    #[test]
+    fn test_read_wim_image() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&wim_image()).unwrap();
+        let mut reader = ImageReader::open(file.path()).unwrap();
+        assert!(is_wim(&mut reader));
+
+        let listing = read(&mut reader).unwrap();
+        assert_eq!(listing.kind, ImageKind::Wim);
+        assert_eq!(listing.label.as_deref(), Some("Windows 10 Pro"));
+        assert!(listing.created.is_some());
+        assert!(listing
+            .properties
+            .contains(&("wim_windows_version".to_string(), "10.0.19041".to_string())));
+
+        let paths: Vec<&str> = listing.files().map(|entry| entry.path.as_str()).collect();
+        assert_eq!(paths, vec!["setup.exe", "sources/readme.txt"]);
+        let setup = listing.files().next().unwrap().clone();
+        assert_eq!(
+            setup.modified,
+            Some(Utc.with_ymd_and_hms(2024, 5, 17, 8, 30, 0).unwrap())
+        );
+        assert_eq!(
+            listing.read_entry(&mut reader, &setup).unwrap(),
+            b"MZ\x90\0\x03\0"
+        );
+        let readme = listing.files().nth(1).unwrap().clone();
+        assert_eq!(listing.read_entry(&mut reader, &readme).unwrap(), b"hello");
+    }
+
+    #[test]
+    fn test_read_compressed_blob() {
+        // Two chunks of 4 bytes: the first stored raw, the second as XPRESS
+        let mut xpress_chunk = vec![0u8; 256];
+        xpress_chunk[b'a' as usize / 2] |= 1 << 4;
+        xpress_chunk[b'b' as usize / 2] |= 2;
+        xpress_chunk[256 / 2] |= 2;
+        xpress_chunk.extend([0x00, 0x58, 0x00, 0x00]);
+        let mut resource = 4u32.to_le_bytes().to_vec();
+        resource.extend(b"wxyz");
+        resource.extend(&xpress_chunk);
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&resource).unwrap();
+        let mut reader = ImageReader::open(file.path()).unwrap();
+        let blob = WimBlob {
+            offset: 0,
+            stored_size: resource.len() as u64,
+            size: 8,
+            flags: RESOURCE_FLAG_COMPRESSED,
+            compression: WimCompression::Xpress,
+            chunk_size: 4,
+        };
+        assert_eq!(read_blob(&mut reader, &blob).unwrap(), b"wxyzabbb");
+
+        let lzx = WimBlob {
+            compression: WimCompression::Lzx,
+            ..blob
+        };
+        assert!(read_blob(&mut reader, &lzx).is_err());
+    }
+}
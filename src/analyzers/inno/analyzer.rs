@@ -1,12 +1,25 @@
 //! InnoSetup analyzer implementation
 
 use super::parser::InnoParser;
-use crate::analyzers::{common, InstallerAnalyzer};
-use crate::core::{FileEntry, InstallerFormat, InstallerMetadata, RegistryOperation, Result};
+use super::setup::InnoSetupInfo;
+use crate::analyzers::{common, DetectionContext, InstallerAnalyzer};
+use crate::core::{
+    AnalyzerCapabilities, FileEntry, InstallerFormat, InstallerMetadata, RegistryOperation, Result,
+    SupportLevel,
+};
 use async_trait::async_trait;
 use chrono::Utc;
 use std::path::Path;
 
+/// InnoSetup-specific strings searched for during detection
+pub(crate) const INNO_PATTERNS: [&str; 5] = [
+    "Inno Setup Setup Data",
+    "JR.Inno.Setup",
+    "InnoSetupVersion",
+    "Inno Setup",
+    "Jordan Russell", // InnoSetup creator
+];
+
 /// InnoSetup installer analyzer
 pub struct InnoAnalyzer {
     parser: InnoParser,
@@ -20,29 +33,20 @@ impl InnoAnalyzer {
         }
     }
 
-    /// Check if file contains InnoSetup signatures
-    async fn has_inno_signature(file_path: &Path) -> Result<bool> {
+    /// Check if the file under detection contains InnoSetup signatures
+    async fn has_inno_signature(context: &DetectionContext) -> Result<bool> {
         // First check if it's a PE file
-        if !common::is_pe_file(file_path).await? {
+        if !context.is_pe() {
             return Ok(false);
         }
 
-        // Search for InnoSetup-specific patterns
-        let inno_patterns = [
-            "Inno Setup Setup Data",
-            "JR.Inno.Setup",
-            "InnoSetupVersion",
-            "Inno Setup",
-            "Jordan Russell", // InnoSetup creator
-        ];
-
-        let matches = common::search_file_content(file_path, &inno_patterns).await?;
+        let matches = context.find_patterns(&INNO_PATTERNS).await?;
         let has_inno = !matches.is_empty();
 
         if has_inno {
             tracing::info!(
                 "InnoSetup signatures found in {}: {:?}",
-                file_path.display(),
+                context.path().display(),
                 matches
             );
         }
@@ -56,7 +60,15 @@ impl InnoAnalyzer {
         let file_hash = common::calculate_file_hash(file_path).await?;
 
         // Extract metadata using parser
-        let parser_metadata = self.parser.extract_metadata(file_path)?;
+        let mut parser_metadata = self.parser.extract_metadata(file_path)?;
+        match InnoSetupInfo::read(file_path).await {
+            Ok(Some(info)) => {
+                tracing::info!("InnoSetup compiler version: {}", info.version);
+                info.insert_properties(&mut parser_metadata);
+            }
+            Ok(None) => tracing::debug!("No InnoSetup loader offset table found"),
+            Err(e) => tracing::warn!("Failed to read InnoSetup setup data: {}", e),
+        }
 
         // Use enhanced metadata extractor for better results
         let enhanced_metadata = common::MetadataExtractor::extract_enhanced_metadata(
@@ -110,6 +122,8 @@ impl InnoAnalyzer {
             file_hash,
             created_at: Utc::now(),
             properties,
+            provenance: enhanced_metadata.provenance,
+            format_details: None,
         })
     }
 
@@ -120,7 +134,19 @@ impl InnoAnalyzer {
             file_path.display()
         );
 
-        let files = self.parser.extract_files(file_path)?;
+        // The `[Files]` entries are only read for known header layouts
+        let files = match InnoSetupInfo::read(file_path).await {
+            Ok(Some(info)) => info.file_entries(),
+            Ok(None) => None,
+            Err(e) => {
+                tracing::warn!("Failed to read InnoSetup setup data: {}", e);
+                None
+            }
+        };
+        let files = match files {
+            Some(files) => files,
+            None => self.parser.extract_files(file_path)?,
+        };
 
         tracing::info!("Found {} files in InnoSetup installer", files.len());
 
@@ -148,26 +174,41 @@ impl InnoAnalyzer {
 #[async_trait]
 impl InstallerAnalyzer for InnoAnalyzer {
     async fn can_analyze(&self, file_path: &Path) -> Result<bool> {
+        self.can_analyze_with(&DetectionContext::new(file_path).await?)
+            .await
+    }
+
+    async fn can_analyze_with(&self, context: &DetectionContext) -> Result<bool> {
         // Check file extension first for quick filtering
-        if let Some(ext) = file_path.extension() {
-            if ext.to_str() != Some("exe") {
-                return Ok(false);
-            }
-        } else {
+        if !context
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("exe"))
+        {
             return Ok(false);
         }
 
-        // Validate file accessibility
-        common::validate_file(file_path).await?;
-
         // Check for InnoSetup signatures
-        Self::has_inno_signature(file_path).await
+        Self::has_inno_signature(context).await
+    }
+
+    fn detection_patterns(&self) -> Vec<&'static str> {
+        INNO_PATTERNS.to_vec()
     }
 
     fn format(&self) -> InstallerFormat {
         InstallerFormat::InnoSetup
     }
 
+    fn capabilities(&self) -> AnalyzerCapabilities {
+        AnalyzerCapabilities {
+            metadata: SupportLevel::Partial,
+            file_extraction: SupportLevel::Partial,
+            registry_operations: SupportLevel::Heuristic,
+            install_scripts: SupportLevel::Unsupported,
+            signatures: SupportLevel::Unsupported,
+        }
+    }
+
     async fn extract_metadata(&self, file_path: &Path) -> Result<InstallerMetadata> {
         // Validate file first
         common::validate_file(file_path).await?;
@@ -2,6 +2,7 @@
 
 pub mod analyzer;
 pub mod parser;
+pub mod setup;
 
 // Re-export main analyzer
 pub use analyzer::InnoAnalyzer;
@@ -123,6 +123,10 @@ impl InnoParser {
                     executable: true,
                 },
                 compression: Some("InnoSetup".to_string()),
+                is_speculative: false,
+                architecture: None,
+                modified: None,
+                source: None,
             });
         }
 
@@ -158,6 +162,10 @@ impl InnoParser {
                     executable: *executable,
                 },
                 compression: Some("InnoSetup LZMA".to_string()),
+                is_speculative: true,
+                architecture: None,
+                modified: None,
+                source: None,
             });
         }
 
@@ -176,6 +184,7 @@ impl InnoParser {
         operations.push(RegistryOperation::CreateKey {
             key_path: "HKEY_LOCAL_MACHINE\\SOFTWARE\\MyApplication".to_string(),
             timestamp: now,
+            process_id: None,
         });
 
         operations.push(RegistryOperation::SetValue {
@@ -184,6 +193,7 @@ impl InnoParser {
             value_type: RegistryValueType::String,
             value_data: RegistryValue::String("{app}".to_string()),
             timestamp: now,
+            process_id: None,
         });
 
         operations.push(RegistryOperation::SetValue {
@@ -192,6 +202,7 @@ impl InnoParser {
             value_type: RegistryValueType::String,
             value_data: RegistryValue::String("My Application".to_string()),
             timestamp: now,
+            process_id: None,
         });
 
         operations.push(RegistryOperation::SetValue {
@@ -200,6 +211,7 @@ impl InnoParser {
             value_type: RegistryValueType::String,
             value_data: RegistryValue::String("{app}\\unins000.exe".to_string()),
             timestamp: now,
+            process_id: None,
         });
 
         Ok(operations)
@@ -216,8 +228,6 @@ impl InnoParser {
 
         // Add InnoSetup-specific metadata
         metadata.insert("installer_type".to_string(), "InnoSetup".to_string());
-        metadata.insert("format_version".to_string(), "InnoSetup 6.x".to_string());
-        metadata.insert("compression".to_string(), "LZMA2".to_string());
 
         Ok(metadata)
     }
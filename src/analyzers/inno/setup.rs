@@ -0,0 +1,1264 @@
+//! InnoSetup loader and setup header structures
+//!
+//! The setup loader keeps an offset table in RCDATA resource 11111 that
+//! points at the setup data appended to the executable. That data starts
+//! with an ID naming the compiler version, followed by two compressed
+//! blocks: the setup header with all script entries, then the locations of
+//! the file data. The header layout changes with almost every compiler
+//! release, its fields and the `[Files]` entries are only read for the
+//! layouts of 5.5.0 up to 6.3.0.
+
+use crate::analyzers::common::{self, lzma};
+use crate::core::{FileAttributes, FileEntry, Result};
+use byteorder::{ByteOrder, LittleEndian};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+const RT_RCDATA: u32 = 10;
+const OFFSET_TABLE_RESOURCE: u32 = 11111;
+
+/// IDs of the offset table of the 5.1.5 and later loaders
+const OFFSET_TABLE_IDS: [&[u8; 12]; 2] = [b"rDlPtS\xcd\xe6\xd7{\x0b*", b"nS5W7dT\x83\xaa\x1b\x0fj"];
+const OFFSET_TABLE_SIZE: usize = 44;
+
+/// Size of the version ID in front of the setup data
+const VERSION_ID_SIZE: usize = 64;
+const BLOCK_HEADER_SIZE: usize = 9;
+/// Compressed blocks are split into chunks, each with its own CRC-32
+const BLOCK_CHUNK_SIZE: usize = 4096;
+/// Stored blocks larger than this are not read
+const MAX_BLOCK_SIZE: u32 = 256 * 1024 * 1024;
+/// Decompressed bytes of the first block searched for the setup header
+const MAX_HEADER_SIZE: usize = 16 * 1024 * 1024;
+
+/// Header layouts known, from the first version on up to the last one excluded
+const FIRST_KNOWN_LAYOUT: (u32, u32, u32) = (5, 5, 0);
+const FIRST_UNKNOWN_LAYOUT: (u32, u32, u32) = (6, 3, 0);
+
+/// Kinds of script entries counted in the setup header, from languages to
+/// uninstall run entries
+const ENTRY_KINDS: usize = 16;
+/// Index of the `[Files]` entry count
+const FILE_ENTRIES: usize = 7;
+/// Index of the file location count
+const FILE_LOCATIONS: usize = 8;
+/// Script entries hold a minimum and an only below Windows version
+const WINDOWS_VERSION_RANGE_SIZE: usize = 20;
+/// Components, tasks, languages, check, after and before install
+const CONDITION_STRINGS: usize = 6;
+/// Location of files that are not packaged, the uninstaller and external files
+const NO_LOCATION: u32 = u32::MAX;
+
+/// Size of a file location entry of the known layouts
+const FILE_LOCATION_SIZE: usize = 74;
+const FILE_LOCATION_CHUNK_ENCRYPTED: u16 = 1 << 6;
+const FILE_LOCATION_CHUNK_COMPRESSED: u16 = 1 << 7;
+
+/// Compression of InnoSetup data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InnoCompression {
+    Stored,
+    Zlib,
+    Bzip2,
+    Lzma,
+    Lzma2,
+}
+
+impl InnoCompression {
+    /// Name as written for the `Compression` directive
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Stored => "none",
+            Self::Zlib => "zip",
+            Self::Bzip2 => "bzip",
+            Self::Lzma => "lzma",
+            Self::Lzma2 => "lzma2",
+        }
+    }
+
+    fn from_header(value: u8) -> Option<Self> {
+        [
+            Self::Stored,
+            Self::Zlib,
+            Self::Bzip2,
+            Self::Lzma,
+            Self::Lzma2,
+        ]
+        .get(value as usize)
+        .copied()
+    }
+}
+
+/// Privileges the installer asks for, the `PrivilegesRequired` directive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InnoPrivileges {
+    None,
+    PowerUser,
+    Admin,
+    Lowest,
+}
+
+impl InnoPrivileges {
+    /// Name as written for the `PrivilegesRequired` directive
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::PowerUser => "poweruser",
+            Self::Admin => "admin",
+            Self::Lowest => "lowest",
+        }
+    }
+
+    fn from_header(value: u8) -> Option<Self> {
+        [Self::None, Self::PowerUser, Self::Admin, Self::Lowest]
+            .get(value as usize)
+            .copied()
+    }
+}
+
+/// Compiler version and features of an InnoSetup installer
+///
+/// The fields read from the setup header are `None` for versions whose
+/// header layout is not known, or when the header did not pass the checks
+/// of its layout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InnoSetupInfo {
+    /// Compiler version as written in the setup data ID, like `6.2.2`
+    pub version: String,
+    /// Built by the Unicode compiler, always the case since 6.0
+    pub unicode: bool,
+    /// Compression of the setup header
+    pub header_compression: Option<InnoCompression>,
+    /// Compression of the packaged files
+    pub compression: Option<InnoCompression>,
+    /// Several files share one compressed chunk
+    pub solid_compression: Option<bool>,
+    /// A password is asked for before installing
+    pub password: Option<bool>,
+    /// The packaged files are encrypted
+    pub encrypted: Option<bool>,
+    /// The script has a `[Code]` section, compiled to Pascal Script
+    pub code_script: Option<bool>,
+    pub privileges_required: Option<InnoPrivileges>,
+    /// Packaged files from the `[Files]` entries
+    pub files: Option<Vec<InnoFile>>,
+}
+
+/// File packaged in an InnoSetup installer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InnoFile {
+    /// Target as written in the script, like `{app}\bin\App.exe`
+    pub destination: String,
+    /// Size once installed
+    pub size: u64,
+    /// Windows file attributes set on install
+    pub attributes: u32,
+    /// The chunk holding the file is compressed
+    pub compressed: bool,
+    /// Last write time recorded by the compiler
+    pub modified: Option<DateTime<Utc>>,
+    /// Disk slice file holding the file, for installers spanning disks
+    pub slice: Option<String>,
+}
+
+impl InnoSetupInfo {
+    /// Read the setup data of an InnoSetup installer
+    ///
+    /// `None` if the file has no offset table of a 5.1.5 or later loader.
+    pub async fn read(file_path: &Path) -> Result<Option<Self>> {
+        let Some(table) = read_offset_table(file_path).await? else {
+            return Ok(None);
+        };
+        let setup_offset = LittleEndian::read_u32(&table[32..]) as u64;
+
+        let id = common::read_file_content_range(file_path, setup_offset, VERSION_ID_SIZE).await?;
+        let Some((version, numbers, unicode)) = parse_version_id(&id) else {
+            tracing::debug!("No InnoSetup setup data ID at offset {}", setup_offset);
+            return Ok(None);
+        };
+        let mut info = Self {
+            version,
+            unicode,
+            header_compression: None,
+            compression: None,
+            solid_compression: None,
+            password: None,
+            encrypted: None,
+            code_script: None,
+            privileges_required: None,
+            files: None,
+        };
+        if numbers < FIRST_KNOWN_LAYOUT || numbers >= FIRST_UNKNOWN_LAYOUT {
+            tracing::info!(
+                "InnoSetup {} setup header layout is not known, only the version is reported",
+                info.version
+            );
+            return Ok(Some(info));
+        }
+
+        let first_offset = setup_offset + VERSION_ID_SIZE as u64;
+        let Some((header_data, compressed, first_size)) =
+            read_block(file_path, first_offset, MAX_HEADER_SIZE).await?
+        else {
+            return Ok(Some(info));
+        };
+        info.header_compression = Some(if compressed {
+            InnoCompression::Lzma
+        } else {
+            InnoCompression::Stored
+        });
+        let Some(header) = SetupHeader::parse(&header_data, numbers, unicode) else {
+            tracing::warn!(
+                "InnoSetup {} setup header does not match its known layout",
+                info.version
+            );
+            return Ok(Some(info));
+        };
+        info.compression = Some(header.compression);
+        info.password = Some(header.password);
+        info.encrypted = Some(header.encryption_used);
+        info.code_script = Some(header.code_script);
+        info.privileges_required = Some(header.privileges_required);
+
+        // The file locations tell whether files share chunks
+        let second_offset = first_offset + first_size;
+        let expected_size = header.entry_counts[FILE_LOCATIONS] * FILE_LOCATION_SIZE;
+        if let Some((locations, _, _)) =
+            read_block(file_path, second_offset, expected_size + 1).await?
+        {
+            if locations.len() == expected_size {
+                let locations: Vec<&[u8]> = locations.chunks(FILE_LOCATION_SIZE).collect();
+                // The first file of a chunk starts at offset 0 within it
+                info.solid_compression = Some(
+                    locations
+                        .iter()
+                        .any(|location| LittleEndian::read_u64(&location[12..]) != 0),
+                );
+                info.encrypted = Some(
+                    header.encryption_used
+                        || locations.iter().any(|location| {
+                            LittleEndian::read_u16(&location[72..]) & FILE_LOCATION_CHUNK_ENCRYPTED
+                                != 0
+                        }),
+                );
+
+                // Without an offset, the file data is stored in disk slices
+                let spanned = LittleEndian::read_u32(&table[36..]) == 0;
+                let slice_name = |slice: u32| {
+                    spanned
+                        .then(|| slice_file_name(file_path, slice, header.slices_per_disk))
+                        .flatten()
+                };
+                info.files = parse_files(
+                    &header_data[header.entries_offset..],
+                    &header.entry_counts,
+                    unicode,
+                    &locations,
+                    slice_name,
+                );
+                if info.files.is_none() {
+                    tracing::warn!(
+                        "InnoSetup {} [Files] entries do not match their known layout",
+                        info.version
+                    );
+                }
+            } else {
+                tracing::warn!(
+                    "InnoSetup file locations take {} bytes, expected {}",
+                    locations.len(),
+                    expected_size
+                );
+            }
+        }
+
+        Ok(Some(info))
+    }
+
+    /// Add the version and the features known as `inno_*` properties
+    pub fn insert_properties(&self, properties: &mut HashMap<String, String>) {
+        properties.insert("inno_version".to_string(), self.version.clone());
+        properties.insert("inno_unicode".to_string(), self.unicode.to_string());
+        properties.insert(
+            "format_version".to_string(),
+            format!("InnoSetup {}", self.version),
+        );
+
+        let compressions = [
+            ("inno_header_compression", self.header_compression),
+            ("inno_compression", self.compression),
+        ];
+        for (key, compression) in compressions {
+            if let Some(compression) = compression {
+                properties.insert(key.to_string(), compression.name().to_string());
+            }
+        }
+        let flags = [
+            ("inno_solid_compression", self.solid_compression),
+            ("inno_password", self.password),
+            ("inno_encrypted", self.encrypted),
+            ("inno_code_script", self.code_script),
+        ];
+        for (key, flag) in flags {
+            if let Some(flag) = flag {
+                properties.insert(key.to_string(), flag.to_string());
+            }
+        }
+        if let Some(privileges) = self.privileges_required {
+            properties.insert(
+                "inno_privileges_required".to_string(),
+                privileges.name().to_string(),
+            );
+        }
+    }
+
+    /// Packaged files as report entries, with their `{app}`-style target
+    ///
+    /// `None` when the `[Files]` entries could not be read.
+    pub fn file_entries(&self) -> Option<Vec<FileEntry>> {
+        let files = self.files.as_ref()?;
+        Some(
+            files
+                .iter()
+                .map(|file| {
+                    // `{app}\bin\App.exe` is listed as `app/bin/App.exe`
+                    let path = file.destination.replace(['{', '}'], "").replace('\\', "/");
+                    let lowercase = path.to_lowercase();
+                    let compression = match self.compression {
+                        Some(compression) if file.compressed => compression,
+                        _ => InnoCompression::Stored,
+                    };
+                    FileEntry {
+                        path: PathBuf::from(path),
+                        target_path: Some(PathBuf::from(&file.destination)),
+                        size: file.size,
+                        hash: None,
+                        attributes: FileAttributes {
+                            readonly: file.attributes & 1 != 0,
+                            hidden: file.attributes & 2 != 0,
+                            system: file.attributes & 4 != 0,
+                            executable: lowercase.ends_with(".exe") || lowercase.ends_with(".dll"),
+                        },
+                        compression: Some(compression.name().to_string()),
+                        is_speculative: false,
+                        architecture: None,
+                        modified: file.modified,
+                        source: file.slice.clone(),
+                    }
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Fields of the setup header used for fingerprinting and to read the
+/// script entries
+#[derive(Debug)]
+struct SetupHeader {
+    compression: InnoCompression,
+    privileges_required: InnoPrivileges,
+    password: bool,
+    encryption_used: bool,
+    code_script: bool,
+    entry_counts: [usize; ENTRY_KINDS],
+    slices_per_disk: u32,
+    /// Offset of the script entries following the header
+    entries_offset: usize,
+}
+
+impl SetupHeader {
+    /// Parse the header of a known layout, `None` if it does not fit
+    fn parse(data: &[u8], version: (u32, u32, u32), unicode: bool) -> Option<Self> {
+        let mut reader = Reader { data, position: 0 };
+
+        // AppName up to SetupMutex and ChangesAssociations
+        let string_count = if version >= (5, 6, 1) {
+            30
+        } else if version >= (5, 5, 6) {
+            28
+        } else {
+            27
+        };
+        for _ in 0..string_count {
+            reader.string()?;
+        }
+        // License, info before and info after texts, then the compiled code
+        for _ in 0..3 {
+            reader.string()?;
+        }
+        let compiled_code = reader.string()?;
+        if !unicode {
+            // Lead bytes of the code page
+            reader.take(32)?;
+        }
+
+        let mut entry_counts = [0; ENTRY_KINDS];
+        for count in &mut entry_counts {
+            *count = LittleEndian::read_u32(reader.take(4)?) as usize;
+        }
+        reader.take(WINDOWS_VERSION_RANGE_SIZE)?;
+        if version >= (6, 0, 0) {
+            // Wizard style, size percents and image alpha format
+            reader.take(10)?;
+        } else if version >= (5, 5, 7) {
+            // Back colors and image alpha format
+            reader.take(9)?;
+        } else {
+            // Back colors and wizard image back color
+            reader.take(12)?;
+        }
+        let password_hash = reader.take(20)?;
+        // Password salt and extra disk space required
+        reader.take(8 + 8)?;
+        let slices_per_disk = LittleEndian::read_u32(reader.take(4)?);
+        // Uninstall log mode and directory exists warning
+        reader.take(2)?;
+        let privileges_required = InnoPrivileges::from_header(reader.byte()?)?;
+        if version >= (6, 0, 0) {
+            // Privileges required overrides allowed
+            reader.byte()?;
+        }
+        // Show language dialog and language detection method
+        reader.take(2)?;
+        let compression = InnoCompression::from_header(reader.byte()?)?;
+        // Architectures allowed and installed in 64-bit mode, disabled
+        // pages, uninstall display size
+        reader.take(2 + 2 + 8)?;
+
+        let names = option_names(version, unicode);
+        let options = reader.take(names.len().div_ceil(8))?;
+        let option = |name: &str| {
+            let index = names.iter().position(|known| *known == name)?;
+            Some(options[index / 8] & (1 << (index % 8)) != 0)
+        };
+        let password = option("Password")?;
+        let encryption_used = option("EncryptionUsed")?;
+
+        // Only a matching layout gives a hash exactly when there is a
+        // password, and Pascal Script code where there is code
+        let has_hash = password_hash.iter().any(|&byte| byte != 0);
+        if password != has_hash
+            || (encryption_used && !password)
+            || !(compiled_code.is_empty() || compiled_code.starts_with(b"IFPS"))
+        {
+            return None;
+        }
+
+        Some(Self {
+            compression,
+            privileges_required,
+            password,
+            encryption_used,
+            code_script: !compiled_code.is_empty(),
+            entry_counts,
+            slices_per_disk,
+            entries_offset: reader.position,
+        })
+    }
+}
+
+/// Packaged files from the `[Files]` entries and the file locations
+///
+/// The entries in front of them are skipped. `None` if an entry does not fit
+/// the known layouts or points at a missing location.
+fn parse_files(
+    entries: &[u8],
+    entry_counts: &[usize; ENTRY_KINDS],
+    unicode: bool,
+    locations: &[&[u8]],
+    slice_name: impl Fn(u32) -> Option<String>,
+) -> Option<Vec<InnoFile>> {
+    let mut reader = Reader {
+        data: entries,
+        position: 0,
+    };
+
+    // Strings and fixed size of the languages, custom messages,
+    // permissions, types, components, tasks and directories
+    let language_size = if unicode { 21 } else { 25 };
+    let skipped = [
+        (10, language_size),
+        (2, 4),
+        (1, 0),
+        (4, WINDOWS_VERSION_RANGE_SIZE + 1 + 1 + 8),
+        (5, 8 + 4 + 1 + WINDOWS_VERSION_RANGE_SIZE + 1 + 8),
+        (6, 4 + 1 + WINDOWS_VERSION_RANGE_SIZE + 1),
+        (
+            1 + CONDITION_STRINGS,
+            4 + WINDOWS_VERSION_RANGE_SIZE + 2 + 1,
+        ),
+    ];
+    for (kind, (strings, size)) in skipped.into_iter().enumerate() {
+        for _ in 0..entry_counts[kind] {
+            for _ in 0..strings {
+                reader.string()?;
+            }
+            reader.take(size)?;
+        }
+    }
+
+    let mut files = Vec::new();
+    for _ in 0..entry_counts[FILE_ENTRIES] {
+        // Source, only kept for external files
+        reader.string()?;
+        let destination = decode_string(reader.string()?, unicode)?;
+        // Font name, strong assembly name and conditions
+        for _ in 0..2 + CONDITION_STRINGS {
+            reader.string()?;
+        }
+        reader.take(WINDOWS_VERSION_RANGE_SIZE)?;
+        let location = LittleEndian::read_u32(reader.take(4)?);
+        let attributes = LittleEndian::read_u32(reader.take(4)?);
+        // External size, permission, flags and file type
+        reader.take(8 + 2 + 4 + 1)?;
+
+        if location == NO_LOCATION {
+            continue;
+        }
+        let location = locations.get(location as usize)?;
+        if destination.is_empty() {
+            return None;
+        }
+        let flags = LittleEndian::read_u16(&location[72..]);
+        files.push(InnoFile {
+            destination,
+            size: LittleEndian::read_u64(&location[20..]),
+            attributes,
+            compressed: flags & FILE_LOCATION_CHUNK_COMPRESSED != 0,
+            modified: common::filetime(LittleEndian::read_u64(&location[56..])),
+            slice: slice_name(LittleEndian::read_u32(location)),
+        });
+    }
+    Some(files)
+}
+
+/// Name of disk slice `slice` next to the installer, like `setup-1.bin`, or
+/// `setup-1a.bin` with several slices per disk
+///
+/// `None` for more than the 26 slices per disk the compiler allows.
+fn slice_file_name(file_path: &Path, slice: u32, slices_per_disk: u32) -> Option<String> {
+    let stem = file_path.file_stem()?.to_string_lossy();
+    match slices_per_disk {
+        0 | 1 => Some(format!("{}-{}.bin", stem, slice.checked_add(1)?)),
+        _ => {
+            let letter = char::from_digit(10 + slice % slices_per_disk, 36)?;
+            Some(format!(
+                "{}-{}{}.bin",
+                stem,
+                slice / slices_per_disk + 1,
+                letter
+            ))
+        }
+    }
+}
+
+/// Text of a script string, UTF-16 in Unicode builds and Windows-1252,
+/// read as Latin-1, otherwise
+fn decode_string(bytes: &[u8], unicode: bool) -> Option<String> {
+    if !unicode {
+        return Some(bytes.iter().map(|&byte| char::from(byte)).collect());
+    }
+    if !bytes.len().is_multiple_of(2) {
+        return None;
+    }
+    let units = bytes
+        .chunks_exact(2)
+        .map(|unit| u16::from_le_bytes([unit[0], unit[1]]));
+    char::decode_utf16(units).map(|c| c.ok()).collect()
+}
+
+/// Setup header options in the order of their bits
+fn option_names(version: (u32, u32, u32), unicode: bool) -> Vec<&'static str> {
+    let mut names = vec![
+        "DisableStartupPrompt",
+        "CreateAppDir",
+        "AllowNoIcons",
+        "AlwaysRestart",
+        "AlwaysUsePersonalGroup",
+    ];
+    if version < (6, 0, 0) {
+        names.extend([
+            "WindowVisible",
+            "WindowShowCaption",
+            "WindowResizable",
+            "WindowStartMaximized",
+        ]);
+    }
+    names.extend([
+        "EnableDirDoesntExistWarning",
+        "Password",
+        "AllowRootDirectory",
+        "DisableFinishedPage",
+    ]);
+    if version < (5, 6, 1) {
+        names.push("ChangesAssociations");
+    }
+    names.push("UsePreviousAppDir");
+    if version < (6, 0, 0) {
+        names.push("BackColorHorizontal");
+    }
+    names.extend([
+        "UsePreviousGroup",
+        "UpdateUninstallLogAppName",
+        "UsePreviousSetupType",
+        "DisableReadyMemo",
+        "AlwaysShowComponentsList",
+        "FlatComponentsList",
+        "ShowComponentSizes",
+        "UsePreviousTasks",
+        "DisableReadyPage",
+        "AlwaysShowDirOnReadyPage",
+        "AlwaysShowGroupOnReadyPage",
+        "AllowUNCPath",
+        "UserInfoPage",
+        "UsePreviousUserInfo",
+        "UninstallRestartComputer",
+        "RestartIfNeededByRun",
+        "ShowTasksTreeLines",
+        "AllowCancelDuringInstall",
+        "WizardImageStretch",
+        "AppendDefaultDirName",
+        "AppendDefaultGroupName",
+        "EncryptionUsed",
+    ]);
+    if version < (5, 6, 1) {
+        names.push("ChangesEnvironment");
+    }
+    if !unicode {
+        names.push("ShowUndisplayableLanguages");
+    }
+    names.extend([
+        "SetupLogging",
+        "SignedUninstaller",
+        "UsePreviousLanguage",
+        "DisableWelcomePage",
+        "CloseApplications",
+        "RestartApplications",
+        "AllowNetworkDrive",
+    ]);
+    if version >= (5, 5, 7) {
+        names.push("ForceCloseApplications");
+    }
+    if version >= (6, 0, 0) {
+        names.extend([
+            "AppNameHasConsts",
+            "UsePreviousPrivileges",
+            "WizardResizable",
+        ]);
+    }
+    names
+}
+
+/// Cursor over little-endian header data
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, size: usize) -> Option<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.position..self.position.checked_add(size)?)?;
+        self.position += size;
+        Some(bytes)
+    }
+
+    fn byte(&mut self) -> Option<u8> {
+        Some(self.take(1)?[0])
+    }
+
+    /// String with its size in bytes in front, UTF-16 in Unicode builds
+    fn string(&mut self) -> Option<&'a [u8]> {
+        let size = LittleEndian::read_u32(self.take(4)?) as usize;
+        self.take(size)
+    }
+}
+
+/// Compiler version and Unicode flag from an ID like
+/// `Inno Setup Setup Data (5.5.7) (u)`
+fn parse_version_id(id: &[u8]) -> Option<(String, (u32, u32, u32), bool)> {
+    let id = id.split(|&byte| byte == 0).next()?;
+    let id = std::str::from_utf8(id).ok()?;
+    let rest = id.strip_prefix("Inno Setup Setup Data (")?;
+    let (version, flags) = rest.split_once(')')?;
+
+    let mut numbers = version.split('.').map(|part| part.parse::<u32>().ok());
+    let major = numbers.next()??;
+    let minor = numbers.next()??;
+    let patch = numbers.next().flatten().unwrap_or(0);
+    let unicode = flags.contains("(u)") || flags.contains("(U)") || major >= 6;
+    Some((version.to_string(), (major, minor, patch), unicode))
+}
+
+/// Read the loader offset table from the resources of the executable
+async fn read_offset_table(file_path: &Path) -> Result<Option<Vec<u8>>> {
+    let header = common::read_file_header(file_path, 4096).await?;
+    let Some(sections) = pe_sections(&header) else {
+        return Ok(None);
+    };
+    let Some((resource_rva, _)) = data_directory(&header, 2) else {
+        return Ok(None);
+    };
+    let Some(section) = sections
+        .iter()
+        .find(|section| section.contains(resource_rva))
+    else {
+        return Ok(None);
+    };
+
+    let resources = common::read_file_content_range(
+        file_path,
+        section.raw_offset as u64,
+        section.raw_size.min(MAX_BLOCK_SIZE) as usize,
+    )
+    .await?;
+    let base = (resource_rva - section.virtual_address) as usize;
+    let Some((data_rva, size)) = find_resource(&resources, base, RT_RCDATA, OFFSET_TABLE_RESOURCE)
+    else {
+        return Ok(None);
+    };
+    let Some(offset) = sections
+        .iter()
+        .find_map(|section| section.file_offset(data_rva))
+    else {
+        return Ok(None);
+    };
+    if (size as usize) < OFFSET_TABLE_SIZE {
+        return Ok(None);
+    }
+
+    let table = common::read_file_content_range(file_path, offset, OFFSET_TABLE_SIZE).await?;
+    if table.len() < OFFSET_TABLE_SIZE
+        || !OFFSET_TABLE_IDS.iter().any(|id| table.starts_with(*id))
+        || crc32fast::hash(&table[..40]) != LittleEndian::read_u32(&table[40..])
+    {
+        tracing::debug!("Invalid InnoSetup loader offset table");
+        return Ok(None);
+    }
+    let table_version = LittleEndian::read_u32(&table[12..]);
+    if table_version != 1 {
+        tracing::info!(
+            "Unsupported InnoSetup offset table version {}",
+            table_version
+        );
+        return Ok(None);
+    }
+    Ok(Some(table))
+}
+
+/// Read the compressed block at `offset`
+///
+/// Returns the content, up to `limit` bytes of it, whether it was
+/// compressed and the size of the block in the file. `None` if the block
+/// is damaged.
+async fn read_block(
+    file_path: &Path,
+    offset: u64,
+    limit: usize,
+) -> Result<Option<(Vec<u8>, bool, u64)>> {
+    let header = common::read_file_content_range(file_path, offset, BLOCK_HEADER_SIZE).await?;
+    if header.len() < BLOCK_HEADER_SIZE
+        || crc32fast::hash(&header[4..]) != LittleEndian::read_u32(&header)
+    {
+        tracing::warn!("Damaged InnoSetup block header at offset {}", offset);
+        return Ok(None);
+    }
+    let stored_size = LittleEndian::read_u32(&header[4..]);
+    let compressed = header[8] != 0;
+    if stored_size > MAX_BLOCK_SIZE {
+        tracing::warn!("InnoSetup block of {} bytes is too large", stored_size);
+        return Ok(None);
+    }
+
+    let stored = common::read_file_content_range(
+        file_path,
+        offset + BLOCK_HEADER_SIZE as u64,
+        stored_size as usize,
+    )
+    .await?;
+    let mut content = Vec::with_capacity(stored.len());
+    for chunk in stored.chunks(4 + BLOCK_CHUNK_SIZE) {
+        let Some((crc, data)) = chunk.split_first_chunk::<4>() else {
+            return Ok(None);
+        };
+        if crc32fast::hash(data) != u32::from_le_bytes(*crc) {
+            tracing::warn!("InnoSetup block chunk CRC mismatch at offset {}", offset);
+            return Ok(None);
+        }
+        content.extend_from_slice(data);
+    }
+
+    if compressed {
+        content = lzma::decompress(&content, limit)?;
+    } else {
+        content.truncate(limit);
+    }
+    Ok(Some((
+        content,
+        compressed,
+        BLOCK_HEADER_SIZE as u64 + stored_size as u64,
+    )))
+}
+
+/// Section of a PE image
+struct PeSection {
+    virtual_address: u32,
+    virtual_size: u32,
+    raw_offset: u32,
+    raw_size: u32,
+}
+
+impl PeSection {
+    fn contains(&self, rva: u32) -> bool {
+        rva >= self.virtual_address
+            && rva - self.virtual_address < self.virtual_size.max(self.raw_size)
+    }
+
+    fn file_offset(&self, rva: u32) -> Option<u64> {
+        let offset = rva.checked_sub(self.virtual_address)?;
+        (offset < self.raw_size).then(|| self.raw_offset as u64 + offset as u64)
+    }
+}
+
+/// Offset of the optional header, with its size
+fn optional_header(header: &[u8]) -> Option<(usize, usize)> {
+    if header.len() < 0x40 || &header[..2] != b"MZ" {
+        return None;
+    }
+    let pe_offset = LittleEndian::read_u32(&header[0x3c..]) as usize;
+    if header.get(pe_offset..pe_offset.checked_add(4)?)? != b"PE\0\0" {
+        return None;
+    }
+    let coff = header.get(pe_offset + 4..pe_offset + 24)?;
+    Some((pe_offset + 24, LittleEndian::read_u16(&coff[16..]) as usize))
+}
+
+fn pe_sections(header: &[u8]) -> Option<Vec<PeSection>> {
+    let (optional, optional_size) = optional_header(header)?;
+    let section_count = LittleEndian::read_u16(header.get(optional - 18..)?) as usize;
+    let table = optional + optional_size;
+    (0..section_count)
+        .map(|index| {
+            let entry = header.get(table + index * 40..table + (index + 1) * 40)?;
+            Some(PeSection {
+                virtual_size: LittleEndian::read_u32(&entry[8..]),
+                virtual_address: LittleEndian::read_u32(&entry[12..]),
+                raw_size: LittleEndian::read_u32(&entry[16..]),
+                raw_offset: LittleEndian::read_u32(&entry[20..]),
+            })
+        })
+        .collect()
+}
+
+/// RVA and size of data directory `index`
+fn data_directory(header: &[u8], index: usize) -> Option<(u32, u32)> {
+    let (optional, optional_size) = optional_header(header)?;
+    let directories = match LittleEndian::read_u16(header.get(optional..optional + 2)?) {
+        0x10b => optional + 96,
+        0x20b => optional + 112,
+        _ => return None,
+    };
+    if directories + (index + 1) * 8 > optional + optional_size {
+        return None;
+    }
+    let directory = header.get(directories + index * 8..directories + (index + 1) * 8)?;
+    let rva = LittleEndian::read_u32(directory);
+    (rva != 0).then(|| (rva, LittleEndian::read_u32(&directory[4..])))
+}
+
+/// RVA and size of the first language of resource `name` of type `kind`
+///
+/// `resources` holds the section with the resource directory at `base`.
+fn find_resource(resources: &[u8], base: usize, kind: u32, name: u32) -> Option<(u32, u32)> {
+    // Offsets within the directory are relative to its root
+    let directory = resources.get(base..)?;
+    let entry = |offset: usize, id: Option<u32>| -> Option<u32> {
+        let table = directory.get(offset..offset + 16)?;
+        let count = LittleEndian::read_u16(&table[12..]) as usize
+            + LittleEndian::read_u16(&table[14..]) as usize;
+        (0..count).find_map(|index| {
+            let entry = directory.get(offset + 16 + index * 8..offset + 24 + index * 8)?;
+            let entry_id = LittleEndian::read_u32(entry);
+            id.is_none_or(|id| id == entry_id)
+                .then(|| LittleEndian::read_u32(&entry[4..]))
+        })
+    };
+    let subdirectory =
+        |value: u32| (value & 0x8000_0000 != 0).then_some((value & 0x7fff_ffff) as usize);
+
+    let names = subdirectory(entry(0, Some(kind))?)?;
+    let languages = subdirectory(entry(names, Some(name))?)?;
+    let data = entry(languages, None)?;
+    if data & 0x8000_0000 != 0 {
+        return None;
+    }
+    let data = directory.get(data as usize..data as usize + 8)?;
+    Some((
+        LittleEndian::read_u32(data),
+        LittleEndian::read_u32(&data[4..]),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string(data: &mut Vec<u8>, value: &[u8]) {
+        data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        data.extend_from_slice(value);
+    }
+
+    fn stored_block(content: &[u8]) -> Vec<u8> {
+        let mut stored = Vec::new();
+        for chunk in content.chunks(BLOCK_CHUNK_SIZE) {
+            stored.extend_from_slice(&crc32fast::hash(chunk).to_le_bytes());
+            stored.extend_from_slice(chunk);
+        }
+        let mut header = (stored.len() as u32).to_le_bytes().to_vec();
+        header.push(0);
+        let mut block = crc32fast::hash(&header).to_le_bytes().to_vec();
+        block.extend(header);
+        block.extend(stored);
+        block
+    }
+
+    fn utf16(value: &str) -> Vec<u8> {
+        value.encode_utf16().flat_map(u16::to_le_bytes).collect()
+    }
+
+    /// Setup header of a 6.2 Unicode compiler with one of each entry
+    fn setup_header(code: &[u8], encrypted: bool, file_locations: u32) -> Vec<u8> {
+        let mut counts = [1; ENTRY_KINDS];
+        counts[FILE_LOCATIONS] = file_locations;
+        setup_header_with_entries(code, encrypted, counts, &[0x5a; 100])
+    }
+
+    fn setup_header_with_entries(
+        code: &[u8],
+        encrypted: bool,
+        counts: [u32; ENTRY_KINDS],
+        entries: &[u8],
+    ) -> Vec<u8> {
+        let mut header = Vec::new();
+        string(&mut header, &utf16("Demo"));
+        for _ in 1..30 {
+            string(&mut header, b"");
+        }
+        for _ in 0..3 {
+            string(&mut header, b"");
+        }
+        string(&mut header, code);
+        for count in counts {
+            header.extend_from_slice(&count.to_le_bytes());
+        }
+        header.extend([0u8; 20 + 10]);
+        // Password hash and salt
+        header.extend([if encrypted { 0xab } else { 0 }; 20]);
+        header.extend([0u8; 8 + 8]);
+        // One slice per disk, uninstall log mode and directory exists warning
+        header.extend(1u32.to_le_bytes());
+        header.extend([0u8; 2]);
+        // Lowest privileges, overrides, language settings, LZMA2
+        header.extend([3, 0, 0, 0, 4]);
+        header.extend([0u8; 2 + 2 + 8]);
+
+        let names = option_names((6, 2, 2), true);
+        let mut options = vec![0u8; names.len().div_ceil(8)];
+        if encrypted {
+            for name in ["Password", "EncryptionUsed"] {
+                let index = names.iter().position(|known| *known == name).unwrap();
+                options[index / 8] |= 1 << (index % 8);
+            }
+        }
+        header.extend(options);
+        // Entries follow the header
+        header.extend_from_slice(entries);
+        header
+    }
+
+    /// Entries with empty strings and zeroed fields, up to the directories
+    fn skipped_entries(unicode: bool) -> Vec<u8> {
+        let mut entries = Vec::new();
+        let language_size = if unicode { 21 } else { 25 };
+        for (strings, size) in [
+            (10, language_size),
+            (2, 4),
+            (1, 0),
+            (4, 30),
+            (5, 42),
+            (6, 26),
+            (7, 27),
+        ] {
+            for _ in 0..strings {
+                string(&mut entries, b"");
+            }
+            entries.resize(entries.len() + size, 0);
+        }
+        entries
+    }
+
+    fn file_entry(entries: &mut Vec<u8>, destination: &[u8], location: u32, attributes: u32) {
+        // Source, destination, font, assembly name and conditions
+        string(entries, b"");
+        string(entries, destination);
+        for _ in 0..8 {
+            string(entries, b"");
+        }
+        entries.extend([0u8; 20]);
+        entries.extend(location.to_le_bytes());
+        entries.extend(attributes.to_le_bytes());
+        entries.extend([0u8; 8 + 2 + 4 + 1]);
+    }
+
+    fn file_location(chunk_suboffset: u64, flags: u16) -> Vec<u8> {
+        let mut location = vec![0u8; FILE_LOCATION_SIZE];
+        location[12..20].copy_from_slice(&chunk_suboffset.to_le_bytes());
+        location[72..].copy_from_slice(&flags.to_le_bytes());
+        location
+    }
+
+    fn packaged_location(slice: u32, size: u64, filetime: u64, flags: u16) -> Vec<u8> {
+        let mut location = file_location(0, flags);
+        location[..4].copy_from_slice(&slice.to_le_bytes());
+        location[20..28].copy_from_slice(&size.to_le_bytes());
+        location[56..64].copy_from_slice(&filetime.to_le_bytes());
+        location
+    }
+
+    /// Loader with the offset table in its resources, followed by setup data
+    fn installer(setup_data: &[u8]) -> Vec<u8> {
+        let mut image = vec![0u8; 0x400];
+        image[..2].copy_from_slice(b"MZ");
+        image[0x3c..0x40].copy_from_slice(&0x40u32.to_le_bytes());
+        image[0x40..0x44].copy_from_slice(b"PE\0\0");
+        // i386, one section, PE32 optional header with 16 directories
+        image[0x44..0x46].copy_from_slice(&0x14cu16.to_le_bytes());
+        image[0x46..0x48].copy_from_slice(&1u16.to_le_bytes());
+        image[0x54..0x56].copy_from_slice(&0xe0u16.to_le_bytes());
+        image[0x58..0x5a].copy_from_slice(&0x10bu16.to_le_bytes());
+        let resource_directory = 0x58 + 96 + 2 * 8;
+        image[resource_directory..resource_directory + 4].copy_from_slice(&0x1000u32.to_le_bytes());
+        image[resource_directory + 4..resource_directory + 8]
+            .copy_from_slice(&0x100u32.to_le_bytes());
+        let section = 0x58 + 0xe0;
+        image[section..section + 5].copy_from_slice(b".rsrc");
+        for (offset, value) in [(8, 0x200u32), (12, 0x1000), (16, 0x200), (20, 0x200)] {
+            image[section + offset..section + offset + 4].copy_from_slice(&value.to_le_bytes());
+        }
+
+        // Type, name and language directories, each with one ID entry
+        let rsrc = 0x200;
+        for (directory, id, value) in [
+            (0x00, RT_RCDATA, 0x8000_0018u32),
+            (0x18, OFFSET_TABLE_RESOURCE, 0x8000_0030),
+            (0x30, 0, 0x48),
+        ] {
+            let at = rsrc + directory;
+            image[at + 14..at + 16].copy_from_slice(&1u16.to_le_bytes());
+            image[at + 16..at + 20].copy_from_slice(&id.to_le_bytes());
+            image[at + 20..at + 24].copy_from_slice(&value.to_le_bytes());
+        }
+        image[rsrc + 0x48..rsrc + 0x4c].copy_from_slice(&0x1058u32.to_le_bytes());
+        image[rsrc + 0x4c..rsrc + 0x50].copy_from_slice(&(OFFSET_TABLE_SIZE as u32).to_le_bytes());
+
+        let mut table = OFFSET_TABLE_IDS[0].to_vec();
+        for value in [1u32, 0, 0, 0, 0, image.len() as u32, 0] {
+            table.extend_from_slice(&value.to_le_bytes());
+        }
+        table.extend_from_slice(&crc32fast::hash(&table).to_le_bytes());
+        image[rsrc + 0x58..rsrc + 0x58 + OFFSET_TABLE_SIZE].copy_from_slice(&table);
+
+        image.extend_from_slice(setup_data);
+        image
+    }
+
+    fn setup_data(id: &str, blocks: &[Vec<u8>]) -> Vec<u8> {
+        let mut data = id.as_bytes().to_vec();
+        data.resize(VERSION_ID_SIZE, 0);
+        for block in blocks {
+            data.extend_from_slice(block);
+        }
+        data
+    }
+
+    #[tokio::test]
+    async fn test_read_setup_header_and_file_locations() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("setup.exe");
+        let locations = [
+            file_location(0, 1 << 7),
+            file_location(4096, 1 << 7 | 1 << 6),
+        ]
+        .concat();
+        std::fs::write(
+            &path,
+            installer(&setup_data(
+                "Inno Setup Setup Data (6.2.2)",
+                &[
+                    stored_block(&setup_header(b"IFPS\x17\x00", true, 2)),
+                    stored_block(&locations),
+                ],
+            )),
+        )
+        .unwrap();
+
+        let info = InnoSetupInfo::read(&path).await.unwrap().unwrap();
+        assert_eq!(info.version, "6.2.2");
+        assert!(info.unicode);
+        assert_eq!(info.header_compression, Some(InnoCompression::Stored));
+        assert_eq!(info.compression, Some(InnoCompression::Lzma2));
+        assert_eq!(info.privileges_required, Some(InnoPrivileges::Lowest));
+        assert_eq!(info.password, Some(true));
+        assert_eq!(info.encrypted, Some(true));
+        assert_eq!(info.code_script, Some(true));
+        assert_eq!(info.solid_compression, Some(true));
+
+        let mut properties = HashMap::new();
+        info.insert_properties(&mut properties);
+        assert_eq!(properties["inno_version"], "6.2.2");
+        assert_eq!(properties["format_version"], "InnoSetup 6.2.2");
+        assert_eq!(properties["inno_compression"], "lzma2");
+        assert_eq!(properties["inno_privileges_required"], "lowest");
+    }
+
+    #[tokio::test]
+    async fn test_read_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mysetup.exe");
+        let mut entries = skipped_entries(true);
+        file_entry(&mut entries, &utf16("{app}\\App.exe"), 0, 0);
+        // The uninstaller is not packaged
+        file_entry(&mut entries, &utf16("{app}\\unins000.exe"), NO_LOCATION, 0);
+        file_entry(&mut entries, &utf16("{commonappdata}\\App\\App.ini"), 1, 1);
+        let mut counts = [1; ENTRY_KINDS];
+        counts[FILE_ENTRIES] = 3;
+        counts[FILE_LOCATIONS] = 2;
+        // 2024-01-01 00:00:00 UTC
+        let filetime = 133_485_408_000_000_000;
+        let locations = [
+            packaged_location(0, 1_048_576, filetime, 1 << 7),
+            packaged_location(1, 120, filetime, 0),
+        ]
+        .concat();
+        std::fs::write(
+            &path,
+            installer(&setup_data(
+                "Inno Setup Setup Data (6.2.2)",
+                &[
+                    stored_block(&setup_header_with_entries(b"", false, counts, &entries)),
+                    stored_block(&locations),
+                ],
+            )),
+        )
+        .unwrap();
+
+        let info = InnoSetupInfo::read(&path).await.unwrap().unwrap();
+        let files = info.files.as_ref().unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].destination, "{app}\\App.exe");
+        assert_eq!(files[0].size, 1_048_576);
+        assert_eq!(
+            files[0].modified.unwrap().to_rfc3339(),
+            "2024-01-01T00:00:00+00:00"
+        );
+        // No data offset in the loader table, the files are in disk slices
+        assert_eq!(files[1].slice.as_deref(), Some("mysetup-2.bin"));
+
+        let entries = info.file_entries().unwrap();
+        assert_eq!(entries[0].path, PathBuf::from("app/App.exe"));
+        assert_eq!(
+            entries[0].target_path,
+            Some(PathBuf::from("{app}\\App.exe"))
+        );
+        assert_eq!(entries[0].compression.as_deref(), Some("lzma2"));
+        assert!(entries[0].attributes.executable);
+        assert_eq!(entries[1].path, PathBuf::from("commonappdata/App/App.ini"));
+        assert_eq!(entries[1].compression.as_deref(), Some("none"));
+        assert!(entries[1].attributes.readonly);
+        assert_eq!(entries[1].source.as_deref(), Some("mysetup-2.bin"));
+
+        // Entries that run past the header do not fit the layout
+        std::fs::write(
+            &path,
+            installer(&setup_data(
+                "Inno Setup Setup Data (6.2.2)",
+                &[
+                    stored_block(&setup_header_with_entries(
+                        b"",
+                        false,
+                        counts,
+                        &skipped_entries(true),
+                    )),
+                    stored_block(&locations),
+                ],
+            )),
+        )
+        .unwrap();
+        let info = InnoSetupInfo::read(&path).await.unwrap().unwrap();
+        assert_eq!(info.compression, Some(InnoCompression::Lzma2));
+        assert_eq!(info.files, None);
+        assert!(info.file_entries().is_none());
+    }
+
+    #[test]
+    fn test_slice_file_name() {
+        let path = Path::new("dist/mysetup.exe");
+        assert_eq!(
+            slice_file_name(path, 0, 1).as_deref(),
+            Some("mysetup-1.bin")
+        );
+        assert_eq!(
+            slice_file_name(path, 3, 2).as_deref(),
+            Some("mysetup-2b.bin")
+        );
+        assert_eq!(slice_file_name(path, 30, 40), None);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_or_mismatched_layouts_report_the_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("setup.exe");
+
+        // Code that is no Pascal Script means the layout was misread
+        let header = stored_block(&setup_header(b"garbage", false, 0));
+        std::fs::write(
+            &path,
+            installer(&setup_data(
+                "Inno Setup Setup Data (6.1.0)",
+                std::slice::from_ref(&header),
+            )),
+        )
+        .unwrap();
+        let info = InnoSetupInfo::read(&path).await.unwrap().unwrap();
+        assert_eq!(info.version, "6.1.0");
+        assert_eq!(info.header_compression, Some(InnoCompression::Stored));
+        assert_eq!(info.compression, None);
+        assert_eq!(info.code_script, None);
+
+        std::fs::write(
+            &path,
+            installer(&setup_data("Inno Setup Setup Data (6.4.3)", &[header])),
+        )
+        .unwrap();
+        let info = InnoSetupInfo::read(&path).await.unwrap().unwrap();
+        assert_eq!(info.version, "6.4.3");
+        assert_eq!(info.header_compression, None);
+
+        std::fs::write(&path, b"MZ not an installer").unwrap();
+        assert_eq!(InnoSetupInfo::read(&path).await.unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_version_id() {
+        assert_eq!(
+            parse_version_id(b"Inno Setup Setup Data (5.5.7) (u)\0\0"),
+            Some(("5.5.7".to_string(), (5, 5, 7), true))
+        );
+        assert_eq!(
+            parse_version_id(b"Inno Setup Setup Data (5.5.0)"),
+            Some(("5.5.0".to_string(), (5, 5, 0), false))
+        );
+        assert_eq!(parse_version_id(b"Nullsoft"), None);
+    }
+}
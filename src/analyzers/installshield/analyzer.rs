@@ -1,8 +1,11 @@
 //! InstallShield analyzer implementation
 
-use super::parser::InstallShieldParser;
-use crate::analyzers::{common, InstallerAnalyzer};
-use crate::core::{FileEntry, InstallerFormat, InstallerMetadata, RegistryOperation, Result};
+use super::parser::{InstallShieldParser, INSTALLSHIELD_PATTERNS};
+use crate::analyzers::{common, DetectionContext, InstallerAnalyzer};
+use crate::core::{
+    AnalyzerCapabilities, FileEntry, InstallerFormat, InstallerMetadata, MetadataField,
+    MetadataSource, RegistryOperation, Result, SupportLevel,
+};
 use async_trait::async_trait;
 use chrono::Utc;
 use std::path::Path;
@@ -20,11 +23,6 @@ impl InstallShieldAnalyzer {
         }
     }
 
-    /// Check if file is an InstallShield installer
-    async fn is_installshield_file(file_path: &Path) -> Result<bool> {
-        InstallShieldParser::is_installshield_file(file_path).await
-    }
-
     /// Extract metadata from InstallShield file
     async fn extract_installshield_metadata(&self, file_path: &Path) -> Result<InstallerMetadata> {
         let file_size = common::get_file_size(file_path).await?;
@@ -36,6 +34,8 @@ impl InstallShieldAnalyzer {
         // Extract InstallShield metadata for product info
         let installshield_metadata = self.parser.extract_metadata(file_path).await?;
 
+        let name_from_filename = installshield_metadata.product_name.is_none();
+        let manufacturer_unknown = installshield_metadata.company_name.is_none();
         let product_name = installshield_metadata.product_name.or_else(|| {
             // Fallback to filename parsing
             file_path
@@ -49,7 +49,7 @@ impl InstallShieldAnalyzer {
             .company_name
             .or_else(|| Some("Unknown".to_string()));
 
-        Ok(InstallerMetadata {
+        let mut metadata = InstallerMetadata {
             format: InstallerFormat::InstallShield,
             product_name,
             product_version,
@@ -58,7 +58,20 @@ impl InstallShieldAnalyzer {
             file_hash,
             created_at: Utc::now(),
             properties,
-        })
+            provenance: Default::default(),
+            format_details: None,
+        }
+        .with_provenance(MetadataSource::InstallerData);
+        if name_from_filename {
+            metadata.set_provenance(
+                MetadataField::ProductName,
+                MetadataSource::FilenameHeuristic,
+            );
+        }
+        if manufacturer_unknown {
+            metadata.set_provenance(MetadataField::Manufacturer, MetadataSource::Placeholder);
+        }
+        Ok(metadata)
     }
 
     /// Extract files from InstallShield package
@@ -107,6 +120,7 @@ impl InstallShieldAnalyzer {
                 value_type: crate::core::RegistryValueType::String,
                 value_data: crate::core::RegistryValue::String("[Placeholder]".to_string()),
                 timestamp: Utc::now(),
+                process_id: None,
             });
         }
 
@@ -122,17 +136,33 @@ impl InstallShieldAnalyzer {
 #[async_trait]
 impl InstallerAnalyzer for InstallShieldAnalyzer {
     async fn can_analyze(&self, file_path: &Path) -> Result<bool> {
-        // Validate file accessibility
-        common::validate_file(file_path).await?;
+        self.can_analyze_with(&DetectionContext::new(file_path).await?)
+            .await
+    }
 
+    async fn can_analyze_with(&self, context: &DetectionContext) -> Result<bool> {
         // Check if it's an InstallShield installer
-        Self::is_installshield_file(file_path).await
+        InstallShieldParser::is_installshield_file(context).await
+    }
+
+    fn detection_patterns(&self) -> Vec<&'static str> {
+        INSTALLSHIELD_PATTERNS.to_vec()
     }
 
     fn format(&self) -> InstallerFormat {
         InstallerFormat::InstallShield
     }
 
+    fn capabilities(&self) -> AnalyzerCapabilities {
+        AnalyzerCapabilities {
+            metadata: SupportLevel::Partial,
+            file_extraction: SupportLevel::Heuristic,
+            registry_operations: SupportLevel::Heuristic,
+            install_scripts: SupportLevel::Unsupported,
+            signatures: SupportLevel::Unsupported,
+        }
+    }
+
     async fn extract_metadata(&self, file_path: &Path) -> Result<InstallerMetadata> {
         // Validate file first
         common::validate_file(file_path).await?;
@@ -1,10 +1,22 @@
 //! InstallShield data structure parser
 
-use crate::analyzers::common;
+use crate::analyzers::{common, DetectionContext};
 use crate::core::{FileAttributes, FileEntry, Result};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
+/// InstallShield strings searched for during detection
+pub(crate) const INSTALLSHIELD_PATTERNS: [&str; 8] = [
+    "InstallShield",
+    "InstallScript",
+    "Stirling Technologies",
+    "Macrovision",
+    "Flexera Software",
+    "InstallShield Setup Launcher",
+    "InstallShield Wizard",
+    "Setup.exe",
+];
+
 /// InstallShield version information
 #[derive(Debug, Clone)]
 pub enum InstallShieldVersion {
@@ -47,26 +59,15 @@ impl InstallShieldParser {
         Self
     }
 
-    /// Check if file is an InstallShield installer
-    pub async fn is_installshield_file(file_path: &Path) -> Result<bool> {
+    /// Check if the file under detection is an InstallShield installer
+    pub async fn is_installshield_file(context: &DetectionContext) -> Result<bool> {
         // First check if it's a PE file
-        if !common::is_pe_file(file_path).await? {
+        if !context.is_pe() {
             return Ok(false);
         }
 
         // Check for InstallShield patterns
-        let installshield_patterns = [
-            "InstallShield",
-            "InstallScript",
-            "Stirling Technologies",
-            "Macrovision",
-            "Flexera Software",
-            "InstallShield Setup Launcher",
-            "InstallShield Wizard",
-            "Setup.exe",
-        ];
-
-        let matches = common::search_file_content(file_path, &installshield_patterns).await?;
+        let matches = context.find_patterns(&INSTALLSHIELD_PATTERNS).await?;
         Ok(!matches.is_empty())
     }
 
@@ -92,13 +93,6 @@ impl InstallShieldParser {
             ("InstallShield 2011", InstallShieldVersion::V2009),
         ];
 
-        for (pattern, version) in &version_patterns {
-            let matches = common::search_file_content(file_path, &[pattern]).await?;
-            if !matches.is_empty() {
-                return Ok(version.clone());
-            }
-        }
-
         // Check for legacy patterns
         let legacy_patterns = [
             "InstallShield 3",
@@ -109,8 +103,20 @@ impl InstallShieldParser {
             "InstallShield Express",
         ];
 
-        let legacy_matches = common::search_file_content(file_path, &legacy_patterns).await?;
-        if !legacy_matches.is_empty() {
+        // One pass over the file for all versions
+        let patterns: Vec<&str> = version_patterns
+            .iter()
+            .map(|(pattern, _)| *pattern)
+            .chain(legacy_patterns)
+            .collect();
+        let matches = common::scan_file_patterns(file_path, &patterns).await?;
+        if let Some(version) = matches.first_of(&version_patterns) {
+            return Ok(version);
+        }
+        if legacy_patterns
+            .iter()
+            .any(|pattern| matches.contains(pattern))
+        {
             return Ok(InstallShieldVersion::Legacy);
         }
 
@@ -210,14 +216,14 @@ impl InstallShieldParser {
             ("Suite", "Suite Project"),
         ];
 
-        for (pattern, setup_type) in &setup_patterns {
-            let matches = common::search_file_content(file_path, &[pattern]).await?;
-            if !matches.is_empty() {
-                return Ok(Some(setup_type.to_string()));
-            }
-        }
-
-        Ok(Some("Standard".to_string()))
+        let patterns: Vec<&str> = setup_patterns.iter().map(|(pattern, _)| *pattern).collect();
+        let matches = common::scan_file_patterns(file_path, &patterns).await?;
+        Ok(Some(
+            matches
+                .first_of(&setup_patterns)
+                .unwrap_or("Standard")
+                .to_string(),
+        ))
     }
 
     /// Detect compression method used
@@ -229,14 +235,17 @@ impl InstallShieldParser {
             ("Cabinet", "Microsoft Cabinet"),
         ];
 
-        for (pattern, method) in &compression_patterns {
-            let matches = common::search_file_content(file_path, &[pattern]).await?;
-            if !matches.is_empty() {
-                return Ok(Some(method.to_string()));
-            }
-        }
-
-        Ok(Some("Proprietary".to_string()))
+        let patterns: Vec<&str> = compression_patterns
+            .iter()
+            .map(|(pattern, _)| *pattern)
+            .collect();
+        let matches = common::scan_file_patterns(file_path, &patterns).await?;
+        Ok(Some(
+            matches
+                .first_of(&compression_patterns)
+                .unwrap_or("Proprietary")
+                .to_string(),
+        ))
     }
 
     /// Extract files from InstallShield package (basic implementation)
@@ -260,6 +269,10 @@ impl InstallShieldParser {
                 executable: true,
             },
             compression: Some("InstallShield".to_string()),
+            is_speculative: false,
+            architecture: None,
+            modified: None,
+            source: None,
         }];
 
         // Add some common files that InstallShield packages typically contain
@@ -288,6 +301,10 @@ impl InstallShieldParser {
                     executable: *executable,
                 },
                 compression: Some("InstallShield".to_string()),
+                is_speculative: true,
+                architecture: None,
+                modified: None,
+                source: None,
             });
         }
 
@@ -0,0 +1,205 @@
+//! Legacy setup tool analyzer implementation
+
+use super::parser::{is_legacy_installer_file, LegacyTool};
+use crate::analyzers::carving::{overlay_offset, Overlay};
+use crate::analyzers::common::{self, VersionStrings};
+use crate::analyzers::{DetectionContext, InstallerAnalyzer};
+use crate::core::{
+    AnalyzerCapabilities, FileEntry, InstallerFormat, InstallerMetadata, MetadataField,
+    MetadataSource, RegistryOperation, Result, SupportLevel,
+};
+use async_trait::async_trait;
+use chrono::Utc;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Bytes of the stub searched for its version resource
+const STUB_READ_SIZE: usize = 2 * 1024 * 1024;
+/// Bytes at the start of the overlay checked for a signature
+const OVERLAY_SIGNATURE_SIZE: usize = 16;
+
+/// Analyzer of setups built with a legacy authoring tool
+///
+/// One instance handles one tool. Files are listed from archives carved from
+/// the overlay; data compressed with the tool's own method is reported in the
+/// payload layout only.
+pub struct LegacyInstallerAnalyzer {
+    tool: LegacyTool,
+}
+
+impl LegacyInstallerAnalyzer {
+    /// Create a new analyzer for setups built with `tool`
+    pub fn new(tool: LegacyTool) -> Self {
+        Self { tool }
+    }
+
+    /// Extract metadata from the stub's version resource and the overlay
+    async fn extract_legacy_metadata(&self, file_path: &Path) -> Result<InstallerMetadata> {
+        let file_size = common::get_file_size(file_path).await?;
+        let file_hash = common::calculate_file_hash(file_path).await?;
+        let stub = common::read_file_content_range(file_path, 0, STUB_READ_SIZE).await?;
+        let stub = &stub[..overlay_offset(&stub).unwrap_or(stub.len())];
+        let version = VersionStrings::read(stub);
+
+        let mut properties = HashMap::new();
+        properties.insert("installer_type".to_string(), self.tool.name().to_string());
+        let markers = common::search_file_content(file_path, self.tool.patterns()).await?;
+        if !markers.is_empty() {
+            properties.insert("legacy_markers".to_string(), markers.join(", "));
+        }
+        let layout = match Overlay::read(file_path).await? {
+            Some(overlay) => {
+                let start = common::read_file_content_range(
+                    file_path,
+                    overlay.offset,
+                    OVERLAY_SIGNATURE_SIZE,
+                )
+                .await?;
+                if let Some(generation) = self.tool.overlay_generation(&start) {
+                    properties.insert("legacy_generation".to_string(), generation.to_string());
+                }
+                overlay.describe()
+            }
+            None => "no overlay (data kept in resources or external files)".to_string(),
+        };
+        properties.insert("legacy_payload_layout".to_string(), layout);
+        if let Some(description) = version.file_description.clone() {
+            properties.insert("file_description".to_string(), description);
+        }
+
+        let product_name = version.product_name.clone().or_else(|| {
+            file_path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .map(|s| s.to_string())
+        });
+
+        let mut metadata = InstallerMetadata {
+            format: self.tool.format(),
+            product_name,
+            product_version: version.version().map(str::to_string),
+            manufacturer: version.company_name,
+            file_size,
+            file_hash,
+            created_at: Utc::now(),
+            properties,
+            provenance: Default::default(),
+            format_details: None,
+        }
+        .with_provenance(MetadataSource::PeVersionInfo);
+        if version.product_name.is_none() {
+            metadata.set_provenance(
+                MetadataField::ProductName,
+                MetadataSource::FilenameHeuristic,
+            );
+        }
+        Ok(metadata)
+    }
+}
+
+#[async_trait]
+impl InstallerAnalyzer for LegacyInstallerAnalyzer {
+    async fn can_analyze(&self, file_path: &Path) -> Result<bool> {
+        self.can_analyze_with(&DetectionContext::new(file_path).await?)
+            .await
+    }
+
+    async fn can_analyze_with(&self, context: &DetectionContext) -> Result<bool> {
+        is_legacy_installer_file(context, self.tool).await
+    }
+
+    fn detection_patterns(&self) -> Vec<&'static str> {
+        self.tool.patterns().to_vec()
+    }
+
+    fn format(&self) -> InstallerFormat {
+        self.tool.format()
+    }
+
+    fn capabilities(&self) -> AnalyzerCapabilities {
+        AnalyzerCapabilities {
+            metadata: SupportLevel::Partial,
+            file_extraction: SupportLevel::Partial,
+            registry_operations: SupportLevel::Unsupported,
+            install_scripts: SupportLevel::Unsupported,
+            signatures: SupportLevel::Unsupported,
+        }
+    }
+
+    async fn extract_metadata(&self, file_path: &Path) -> Result<InstallerMetadata> {
+        common::validate_file(file_path).await?;
+
+        self.extract_legacy_metadata(file_path).await
+    }
+
+    async fn extract_files(&self, file_path: &Path) -> Result<Vec<FileEntry>> {
+        common::validate_file(file_path).await?;
+
+        let files = match Overlay::read(file_path).await? {
+            Some(overlay) => overlay.archive_files(file_path).await?,
+            None => Vec::new(),
+        };
+        tracing::info!(
+            "Found {} files in {} overlay",
+            files.len(),
+            self.tool.name()
+        );
+        Ok(files)
+    }
+
+    async fn extract_registry_operations(
+        &self,
+        file_path: &Path,
+    ) -> Result<Vec<RegistryOperation>> {
+        common::validate_file(file_path).await?;
+
+        // The setup script is compressed with the project data
+        Ok(Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzers::carving::tests::pe_stub;
+    use crate::analyzers::common::version_info::tests::version_entry;
+
+    #[tokio::test]
+    async fn test_analyze_setup_factory() {
+        let mut data = pe_stub(0x200);
+        let entries = [
+            version_entry("ProductName", "Northwind Inventory"),
+            version_entry("FileVersion", "5.4.1"),
+            version_entry("CompanyName", "Northwind Traders"),
+            version_entry("FileDescription", "Setup Factory 8.0 Runtime"),
+        ]
+        .concat();
+        data[0x200..0x200 + entries.len()].copy_from_slice(&entries);
+        data.extend([
+            0xE0, 0xE0, 0xE1, 0xE1, 0xE2, 0xE2, 0xE3, 0xE3, 0xE4, 0xE4, 0xE5, 0xE5, 0xE6, 0xE6,
+            0xE7, 0xE7,
+        ]);
+        data.extend(b"irsetup.exe");
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("NorthwindSetup.exe");
+        std::fs::write(&path, &data).unwrap();
+
+        let analyzer = crate::analyzers::AnalyzerFactory::create_analyzer(&path)
+            .await
+            .unwrap();
+        assert_eq!(analyzer.format(), InstallerFormat::SetupFactory);
+        let metadata = analyzer.extract_metadata(&path).await.unwrap();
+        assert_eq!(metadata.format, InstallerFormat::SetupFactory);
+        assert_eq!(
+            metadata.product_name.as_deref(),
+            Some("Northwind Inventory")
+        );
+        assert_eq!(metadata.product_version.as_deref(), Some("5.4.1"));
+        assert_eq!(metadata.manufacturer.as_deref(), Some("Northwind Traders"));
+        let properties = &metadata.properties;
+        assert_eq!(properties["installer_type"], "Setup Factory");
+        assert_eq!(properties["legacy_generation"], "Setup Factory 8 or later");
+        assert!(properties["legacy_payload_layout"].starts_with("overlay at 0x400"));
+        assert!(analyzer.extract_files(&path).await.unwrap().is_empty());
+    }
+}
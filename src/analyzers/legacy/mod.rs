@@ -0,0 +1,8 @@
+//! Legacy setup tool analyzer for Setup Factory, Wise and Ghost Installer executables
+
+pub mod analyzer;
+pub mod parser;
+
+// Re-export main components
+pub use analyzer::LegacyInstallerAnalyzer;
+pub use parser::LegacyTool;
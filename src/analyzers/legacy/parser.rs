@@ -0,0 +1,151 @@
+//! Legacy setup tool detection
+//!
+//! Setup Factory, Wise Installation System and Ghost Installer build setups
+//! from a stub with the project data appended, compressed with the tool's
+//! own method. None of them keeps a documented directory, so setups are told
+//! apart by the strings of their stub and described by the layout of their
+//! overlay. Setup Factory 7 and later also start the overlay with a run of
+//! signature bytes.
+
+use crate::analyzers::DetectionContext;
+use crate::core::{InstallerFormat, Result};
+
+/// Overlay signature of Setup Factory 7
+const SETUP_FACTORY_7_SIGNATURE: [u8; 8] = [0xE0, 0xE1, 0xE2, 0xE3, 0xE4, 0xE5, 0xE6, 0xE7];
+/// Overlay signature of Setup Factory 8 and later, each byte doubled
+const SETUP_FACTORY_8_SIGNATURE: [u8; 16] = [
+    0xE0, 0xE0, 0xE1, 0xE1, 0xE2, 0xE2, 0xE3, 0xE3, 0xE4, 0xE4, 0xE5, 0xE5, 0xE6, 0xE6, 0xE7, 0xE7,
+];
+
+/// Authoring tool of a legacy setup
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LegacyTool {
+    /// Indigo Rose Setup Factory
+    SetupFactory,
+    /// Wise Installation System (WiseScript)
+    Wise,
+    /// Ethalone Ghost Installer
+    GhostInstaller,
+}
+
+impl LegacyTool {
+    /// All legacy tools, in detection order
+    pub const ALL: [Self; 3] = [Self::SetupFactory, Self::Wise, Self::GhostInstaller];
+
+    /// Installer format of setups built with the tool
+    pub fn format(self) -> InstallerFormat {
+        match self {
+            Self::SetupFactory => InstallerFormat::SetupFactory,
+            Self::Wise => InstallerFormat::Wise,
+            Self::GhostInstaller => InstallerFormat::GhostInstaller,
+        }
+    }
+
+    /// Product name of the tool
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::SetupFactory => "Setup Factory",
+            Self::Wise => "Wise Installation System",
+            Self::GhostInstaller => "Ghost Installer",
+        }
+    }
+
+    /// Strings that identify a setup of the tool on their own
+    pub(crate) fn signatures(self) -> &'static [&'static str] {
+        match self {
+            Self::SetupFactory => &["Setup Factory", "irsetup.exe"],
+            Self::Wise => &["WiseMain", "Wise Installation"],
+            Self::GhostInstaller => &["Ghost Installer"],
+        }
+    }
+
+    /// All strings searched for during detection: the signatures followed
+    /// by vendor strings, two of which are needed without a signature
+    pub(crate) fn patterns(self) -> &'static [&'static str] {
+        match self {
+            Self::SetupFactory => &["Setup Factory", "irsetup.exe", "Indigo Rose", "irsetup.dat"],
+            Self::Wise => &[
+                "WiseMain",
+                "Wise Installation",
+                "Wise Solutions",
+                "UNWISE.EXE",
+            ],
+            Self::GhostInstaller => &["Ghost Installer", "Ethalone", "ethalone.com"],
+        }
+    }
+
+    /// Generation of the tool told by the start of the overlay, if it has a signature
+    pub fn overlay_generation(self, overlay: &[u8]) -> Option<&'static str> {
+        match self {
+            Self::SetupFactory if overlay.starts_with(&SETUP_FACTORY_8_SIGNATURE) => {
+                Some("Setup Factory 8 or later")
+            }
+            Self::SetupFactory if overlay.starts_with(&SETUP_FACTORY_7_SIGNATURE) => {
+                Some("Setup Factory 7")
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Check if the file under detection is a setup built with `tool`
+///
+/// Vendor strings also appear in other products of the vendor, so one of
+/// them alone doesn't identify a setup.
+pub async fn is_legacy_installer_file(
+    context: &DetectionContext,
+    tool: LegacyTool,
+) -> Result<bool> {
+    if !context.is_pe() {
+        return Ok(false);
+    }
+
+    let matches = context.find_patterns(tool.patterns()).await?;
+    Ok(matches
+        .iter()
+        .any(|pattern| tool.signatures().contains(&pattern.as_str()))
+        || matches.len() >= 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    async fn detect(content: &[u8], tool: LegacyTool) -> bool {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(content).unwrap();
+        let context = DetectionContext::new(file.path()).await.unwrap();
+        is_legacy_installer_file(&context, tool).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_detect_legacy_tools() {
+        assert!(detect(b"MZ stub WiseMain", LegacyTool::Wise).await);
+        assert!(detect(b"MZ stub Wise Solutions UNWISE.EXE", LegacyTool::Wise).await);
+        assert!(detect(b"MZ Indigo Rose irsetup.dat", LegacyTool::SetupFactory).await);
+        assert!(detect(b"MZ Ghost Installer", LegacyTool::GhostInstaller).await);
+
+        // A vendor string alone, or markers outside a PE image, are not enough
+        assert!(!detect(b"MZ Indigo Rose AutoPlay", LegacyTool::SetupFactory).await);
+        assert!(!detect(b"PK WiseMain", LegacyTool::Wise).await);
+    }
+
+    #[test]
+    fn test_setup_factory_generation() {
+        let tool = LegacyTool::SetupFactory;
+        assert_eq!(
+            tool.overlay_generation(&SETUP_FACTORY_8_SIGNATURE),
+            Some("Setup Factory 8 or later")
+        );
+        assert_eq!(
+            tool.overlay_generation(&SETUP_FACTORY_7_SIGNATURE),
+            Some("Setup Factory 7")
+        );
+        assert_eq!(tool.overlay_generation(b"\x1f\x8b"), None);
+        assert_eq!(
+            LegacyTool::Wise.overlay_generation(&SETUP_FACTORY_7_SIGNATURE),
+            None
+        );
+    }
+}
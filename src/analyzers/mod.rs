@@ -1,27 +1,67 @@
 //! Static analyzer implementations for various installer formats
 
-use crate::core::{FileEntry, InstallerFormat, InstallerMetadata, RegistryOperation, Result};
+use crate::core::{
+    AnalyzerCapabilities, FileEntry, InstallerFormat, InstallerMetadata, PackageDependency,
+    RegistryOperation, Result,
+};
 use async_trait::async_trait;
-use std::path::Path;
+use common::EntryReader;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
 
+pub mod actual_installer;
+pub mod advanced_installer;
+pub mod architecture;
 pub mod archive;
+pub mod authenticode;
+pub mod carving;
 pub mod common;
+pub mod detection;
+pub mod driver;
+pub mod embedded_msi;
+pub mod evasion;
+pub mod game_runtimes;
+pub mod generic;
+pub mod image;
 pub mod inno;
 pub mod installshield;
+pub mod legacy;
 pub mod msi;
 pub mod msix;
+pub mod multipart;
 pub mod nsis;
+pub mod office_addins;
+pub mod previews;
+pub mod reboot;
+pub mod registry;
+pub mod requirements;
+pub mod spyware;
 pub mod squirrel;
+pub mod strings;
+pub mod sync_objects;
+pub mod timestamps;
+pub mod uac_bypass;
+pub mod web_installer;
 pub mod wheel;
 pub mod wix;
 
 // Re-export analyzers
+pub use actual_installer::ActualInstallerAnalyzer;
+pub use advanced_installer::AdvancedInstallerAnalyzer;
 pub use archive::ArchiveAnalyzer;
+pub use detection::DetectionContext;
+pub use driver::DriverPackageAnalyzer;
+pub use generic::GenericAnalyzer;
+pub use image::DiskImageAnalyzer;
 pub use inno::InnoAnalyzer;
 pub use installshield::InstallShieldAnalyzer;
+pub use legacy::{LegacyInstallerAnalyzer, LegacyTool};
 pub use msi::MsiAnalyzer;
 pub use msix::MsixAnalyzer;
 pub use nsis::NsisAnalyzer;
+pub use registry::AnalyzerRegistry;
 pub use squirrel::SquirrelAnalyzer;
 pub use wheel::WheelAnalyzer;
 pub use wix::WixAnalyzer;
@@ -30,7 +70,7 @@ pub use wix::WixAnalyzer;
 pub use common::{
     calculate_file_hash, detect_archive_format, detect_format_by_extension,
     detect_installer_format, get_file_size, is_archive_file, is_pe_file, read_file_content_range,
-    read_file_header, search_file_content, validate_file,
+    read_file_header, scan_file_patterns, search_file_content, search_file_matches, validate_file,
 };
 
 /// Main trait for installer analyzers
@@ -39,9 +79,35 @@ pub trait InstallerAnalyzer: Send + Sync {
     /// Detect if this analyzer can handle the given file
     async fn can_analyze(&self, file_path: &Path) -> Result<bool>;
 
+    /// Detect if this analyzer can handle the file of a detection context
+    ///
+    /// Built-in analyzers answer from the header and pattern searches shared
+    /// through the context. Analyzers that don't override this read the file
+    /// themselves through [`InstallerAnalyzer::can_analyze`].
+    async fn can_analyze_with(&self, context: &DetectionContext) -> Result<bool> {
+        self.can_analyze(context.path()).await
+    }
+
+    /// Marker strings `can_analyze_with` searches for
+    ///
+    /// The factory searches the markers of all analyzers in one pass before
+    /// asking them, markers not declared here are searched separately.
+    fn detection_patterns(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+
     /// Get the installer format this analyzer handles
     fn format(&self) -> InstallerFormat;
 
+    /// Describe what this analyzer can extract and how reliably
+    ///
+    /// Analyzers that synthesize entries from common patterns instead of
+    /// parsing the installer must report `SupportLevel::Heuristic` so that
+    /// consumers can tell real extraction from placeholder output.
+    fn capabilities(&self) -> AnalyzerCapabilities {
+        AnalyzerCapabilities::default()
+    }
+
     /// Extract metadata from the installer
     async fn extract_metadata(&self, file_path: &Path) -> Result<InstallerMetadata>;
 
@@ -52,20 +118,94 @@ pub trait InstallerAnalyzer: Send + Sync {
     async fn extract_registry_operations(&self, file_path: &Path)
         -> Result<Vec<RegistryOperation>>;
 
-    /// Perform complete analysis
-    async fn analyze(
+    /// Extract dependencies declared by the package
+    ///
+    /// Only package formats with dependency metadata (e.g. wheels, MSIX)
+    /// override this; installers without such metadata have none.
+    async fn extract_dependencies(&self, _file_path: &Path) -> Result<Vec<PackageDependency>> {
+        Ok(Vec::new())
+    }
+
+    /// Read the content of a file packaged in the installer
+    ///
+    /// Returns `None` when the format doesn't support extracting file
+    /// contents or the installer doesn't contain the entry.
+    async fn read_file_content(
         &self,
-        file_path: &Path,
-    ) -> Result<(InstallerMetadata, Vec<FileEntry>, Vec<RegistryOperation>)> {
-        let metadata = self.extract_metadata(file_path).await?;
-        let files = self.extract_files(file_path).await?;
-        let registry_ops = self.extract_registry_operations(file_path).await?;
+        _file_path: &Path,
+        _entry_path: &Path,
+    ) -> Result<Option<Vec<u8>>> {
+        Ok(None)
+    }
+
+    /// Open a file packaged in the installer for reading
+    ///
+    /// Formats that can decompress a single entry stream it as it is read,
+    /// so previews and extraction of one file don't unpack the rest. Others
+    /// read the entry through [`InstallerAnalyzer::read_file_content`].
+    /// Returns `None` in the same cases.
+    async fn open_file(&self, file_path: &Path, entry_path: &Path) -> Result<Option<EntryReader>> {
+        Ok(self
+            .read_file_content(file_path, entry_path)
+            .await?
+            .map(common::streaming::buffered_reader))
+    }
+}
+
+/// Future of one extraction stage, borrowing the analyzer and the input
+pub type StageFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + Send + 'a>>;
 
-        Ok((metadata, files, registry_ops))
+/// Run one extraction stage of `analyzer` on the blocking thread pool
+///
+/// Analyzers parse synchronously inside their async methods, so stages
+/// joined on one task would still run one after another. A panic in the
+/// stage is resumed in the caller.
+pub async fn spawn_stage<T, F>(
+    analyzer: Arc<dyn InstallerAnalyzer>,
+    file_path: PathBuf,
+    stage: F,
+) -> Result<T>
+where
+    T: Send + 'static,
+    F: for<'a> FnOnce(&'a dyn InstallerAnalyzer, &'a Path) -> StageFuture<'a, T> + Send + 'static,
+{
+    let runtime = tokio::runtime::Handle::current();
+    let task =
+        tokio::task::spawn_blocking(move || runtime.block_on(stage(analyzer.as_ref(), &file_path)));
+    match task.await {
+        Ok(result) => result,
+        Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+        Err(e) => Err(crate::core::AnalyzerError::generic(format!(
+            "Analysis stage failed: {}",
+            e
+        ))),
     }
 }
 
+/// Perform complete analysis, running the independent stages in parallel
+pub async fn analyze(
+    analyzer: Arc<dyn InstallerAnalyzer>,
+    file_path: &Path,
+) -> Result<(InstallerMetadata, Vec<FileEntry>, Vec<RegistryOperation>)> {
+    let path = file_path.to_path_buf();
+    tokio::try_join!(
+        spawn_stage(analyzer.clone(), path.clone(), |analyzer, path| {
+            analyzer.extract_metadata(path)
+        }),
+        spawn_stage(analyzer.clone(), path.clone(), |analyzer, path| {
+            analyzer.extract_files(path)
+        }),
+        spawn_stage(analyzer, path, |analyzer, path| {
+            analyzer.extract_registry_operations(path)
+        }),
+    )
+}
+
 /// Factory for creating appropriate analyzers
+///
+/// Detection and lookup are driven by the global [`AnalyzerRegistry`], so
+/// analyzers registered through [`AnalyzerFactory::register_analyzer`] take
+/// part in format detection alongside the built-in ones.
 pub struct AnalyzerFactory;
 
 impl AnalyzerFactory {
@@ -73,66 +213,23 @@ impl AnalyzerFactory {
     pub async fn create_analyzer(file_path: &Path) -> Result<Box<dyn InstallerAnalyzer>> {
         tracing::debug!("Attempting to find analyzer for: {}", file_path.display());
 
-        // Try WiX analyzer first (MSI variant with specific characteristics)
-        let wix_analyzer = WixAnalyzer::new();
-        if wix_analyzer.can_analyze(file_path).await? {
-            tracing::info!("Selected WiX analyzer for: {}", file_path.display());
-            return Ok(Box::new(wix_analyzer));
-        }
-
-        // Try MSI analyzer (general MSI format)
-        let msi_analyzer = MsiAnalyzer::new();
-        if msi_analyzer.can_analyze(file_path).await? {
-            tracing::info!("Selected MSI analyzer for: {}", file_path.display());
-            return Ok(Box::new(msi_analyzer));
-        }
-
-        // Try Python Wheel analyzer (specific file extension)
-        let wheel_analyzer = WheelAnalyzer::new();
-        if wheel_analyzer.can_analyze(file_path).await? {
-            tracing::info!(
-                "Selected Python Wheel analyzer for: {}",
-                file_path.display()
-            );
-            return Ok(Box::new(wheel_analyzer));
-        }
-
-        // Try MSIX/AppX analyzer (specific file extension)
-        let msix_analyzer = MsixAnalyzer::new();
-        if msix_analyzer.can_analyze(file_path).await? {
-            tracing::info!("Selected MSIX/AppX analyzer for: {}", file_path.display());
-            return Ok(Box::new(msix_analyzer));
-        }
-
-        // Try InstallShield analyzer (PE-based detection)
-        let installshield_analyzer = InstallShieldAnalyzer::new();
-        if installshield_analyzer.can_analyze(file_path).await? {
-            tracing::info!(
-                "Selected InstallShield analyzer for: {}",
-                file_path.display()
-            );
-            return Ok(Box::new(installshield_analyzer));
-        }
-
-        // Try Squirrel analyzer (NSIS variant for Electron apps)
-        let squirrel_analyzer = SquirrelAnalyzer::new();
-        if squirrel_analyzer.can_analyze(file_path).await? {
-            tracing::info!("Selected Squirrel analyzer for: {}", file_path.display());
-            return Ok(Box::new(squirrel_analyzer));
-        }
-
-        // Try NSIS analyzer (general NSIS format)
-        let nsis_analyzer = NsisAnalyzer::new();
-        if nsis_analyzer.can_analyze(file_path).await? {
-            tracing::info!("Selected NSIS analyzer for: {}", file_path.display());
-            return Ok(Box::new(nsis_analyzer));
-        }
-
-        // Try InnoSetup analyzer
-        let inno_analyzer = InnoAnalyzer::new();
-        if inno_analyzer.can_analyze(file_path).await? {
-            tracing::info!("Selected InnoSetup analyzer for: {}", file_path.display());
-            return Ok(Box::new(inno_analyzer));
+        // Analyzers are tried in registration order, most specific first,
+        // sharing one read of the file
+        let analyzers = Self::get_all_analyzers();
+        let patterns: Vec<&str> = analyzers
+            .iter()
+            .flat_map(|analyzer| analyzer.detection_patterns())
+            .collect();
+        let context = DetectionContext::with_patterns(file_path, &patterns).await?;
+        for analyzer in analyzers {
+            if analyzer.can_analyze_with(&context).await? {
+                tracing::info!(
+                    "Selected {} analyzer for: {}",
+                    analyzer.format(),
+                    file_path.display()
+                );
+                return Ok(analyzer);
+            }
         }
 
         tracing::warn!("No suitable analyzer found for: {}", file_path.display());
@@ -142,46 +239,57 @@ impl AnalyzerFactory {
         )))
     }
 
+    /// Create an analyzer for the given file, falling back to the generic analyzer
+    ///
+    /// Files no registered analyzer recognizes are analyzed by
+    /// [`GenericAnalyzer`] instead of failing; other errors are returned.
+    pub async fn create_analyzer_or_fallback(
+        file_path: &Path,
+    ) -> Result<Box<dyn InstallerAnalyzer>> {
+        match Self::create_analyzer(file_path).await {
+            Err(crate::core::AnalyzerError::UnsupportedFormat { .. }) => {
+                tracing::info!(
+                    "Falling back to generic analysis of: {}",
+                    file_path.display()
+                );
+                Ok(Box::new(GenericAnalyzer::new()))
+            }
+            analyzer => analyzer,
+        }
+    }
+
+    /// Register an additional analyzer for a format
+    ///
+    /// The analyzer is tried before all built-in analyzers during detection.
+    /// Use `InstallerFormat::Other` for formats without a dedicated variant.
+    pub fn register_analyzer<F>(format: InstallerFormat, constructor: F)
+    where
+        F: Fn() -> Box<dyn InstallerAnalyzer> + Send + Sync + 'static,
+    {
+        AnalyzerRegistry::global()
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .register(format, constructor);
+    }
+
     /// Get all available analyzers
     pub fn get_all_analyzers() -> Vec<Box<dyn InstallerAnalyzer>> {
-        vec![
-            Box::new(WixAnalyzer::new()),
-            Box::new(MsiAnalyzer::new()),
-            Box::new(WheelAnalyzer::new()),
-            Box::new(MsixAnalyzer::new()),
-            Box::new(InstallShieldAnalyzer::new()),
-            Box::new(SquirrelAnalyzer::new()),
-            Box::new(NsisAnalyzer::new()),
-            Box::new(InnoAnalyzer::new()),
-        ]
+        Self::registry().create_all()
     }
 
     /// Get analyzer by format
-    pub fn get_analyzer_by_format(format: InstallerFormat) -> Option<Box<dyn InstallerAnalyzer>> {
-        match format {
-            InstallerFormat::MSI => Some(Box::new(MsiAnalyzer::new())),
-            InstallerFormat::WiX => Some(Box::new(WixAnalyzer::new())),
-            InstallerFormat::PythonWheel => Some(Box::new(WheelAnalyzer::new())),
-            InstallerFormat::MSIX => Some(Box::new(MsixAnalyzer::new())),
-            InstallerFormat::InstallShield => Some(Box::new(InstallShieldAnalyzer::new())),
-            InstallerFormat::NSIS => Some(Box::new(NsisAnalyzer::new())),
-            InstallerFormat::Squirrel => Some(Box::new(SquirrelAnalyzer::new())),
-            InstallerFormat::InnoSetup => Some(Box::new(InnoAnalyzer::new())),
-            _ => None,
-        }
+    pub fn get_analyzer_by_format(format: &InstallerFormat) -> Option<Box<dyn InstallerAnalyzer>> {
+        Self::registry().create(format)
     }
 
     /// Get supported formats
     pub fn get_supported_formats() -> Vec<InstallerFormat> {
-        vec![
-            InstallerFormat::MSI,
-            InstallerFormat::WiX,
-            InstallerFormat::PythonWheel,
-            InstallerFormat::MSIX,
-            InstallerFormat::InstallShield,
-            InstallerFormat::NSIS,
-            InstallerFormat::Squirrel,
-            InstallerFormat::InnoSetup,
-        ]
+        Self::registry().formats()
+    }
+
+    fn registry() -> std::sync::RwLockReadGuard<'static, AnalyzerRegistry> {
+        AnalyzerRegistry::global()
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
     }
 }
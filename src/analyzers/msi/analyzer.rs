@@ -1,36 +1,63 @@
 //! Complete MSI analyzer implementation
 
 use crate::analyzers::msi::database::MsiDatabase;
+use crate::analyzers::msi::streams;
 use crate::analyzers::msi::tables::MsiTables;
-use crate::analyzers::{common, InstallerAnalyzer};
-use crate::core::{FileEntry, InstallerFormat, InstallerMetadata, RegistryOperation, Result};
+use crate::analyzers::{common, DetectionContext, InstallerAnalyzer};
+use crate::core::{
+    AnalyzerCapabilities, FileEntry, FormatDetails, InstallerFormat, InstallerMetadata,
+    MetadataSource, MsiDetails, RegistryOperation, Result, SupportLevel,
+};
 use async_trait::async_trait;
 use chrono::Utc;
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// Package an opened database belongs to, with its modification time
+type DatabaseKey = (PathBuf, Option<SystemTime>);
 
 /// MSI format analyzer
-pub struct MsiAnalyzer;
+pub struct MsiAnalyzer {
+    /// Database of the package analyzed last, shared by the analysis stages
+    database: Mutex<Option<(DatabaseKey, Arc<MsiDatabase>)>>,
+}
 
 impl MsiAnalyzer {
     /// Create a new MSI analyzer
     pub fn new() -> Self {
-        Self
+        Self {
+            database: Mutex::new(None),
+        }
     }
 
-    /// Check if file has MSI signature
-    async fn has_msi_signature(file_path: &Path) -> Result<bool> {
-        let header = common::read_file_header(file_path, 8).await?;
+    /// Open the database of `file_path`, reusing it while the package is unchanged
+    pub(crate) fn database(&self, file_path: &Path) -> Result<Arc<MsiDatabase>> {
+        let modified = std::fs::metadata(file_path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        let key = (file_path.to_path_buf(), modified);
+
+        let mut database = self
+            .database
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some((opened, db)) = database.as_ref() {
+            if *opened == key {
+                return Ok(db.clone());
+            }
+        }
+        let db = Arc::new(MsiDatabase::open(file_path)?);
+        *database = Some((key, db.clone()));
+        Ok(db)
+    }
 
+    /// Check if a file header has the MSI signature
+    fn has_msi_signature(header: &[u8]) -> bool {
         // MSI files start with the OLE compound document signature
         // D0CF11E0A1B11AE1 (little-endian)
-        if header.len() >= 8 {
-            let signature = &header[0..8];
-            let msi_signature = [0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
-            Ok(signature == msi_signature)
-        } else {
-            Ok(false)
-        }
+        header.starts_with(&[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1])
     }
 
     /// Extract metadata from MSI properties
@@ -40,7 +67,7 @@ impl MsiAnalyzer {
 
         // Try to open MSI database and extract properties
         let (product_name, product_version, manufacturer, mut properties) =
-            match MsiDatabase::open(file_path) {
+            match self.database(file_path) {
                 Ok(db) => match MsiTables::query_properties(&db) {
                     Ok(props) => {
                         let mut prop_map = HashMap::new();
@@ -71,6 +98,52 @@ impl MsiAnalyzer {
                 }
             };
 
+        let reboot_actions = self.reboot_actions(file_path);
+        if !reboot_actions.is_empty() {
+            properties.insert("msi_reboot_actions".to_string(), reboot_actions.join(", "));
+        }
+        let launch_conditions = self.launch_conditions(file_path);
+        if !launch_conditions.is_empty() {
+            properties.insert(
+                "msi_launch_conditions".to_string(),
+                launch_conditions.join("; "),
+            );
+        }
+
+        let embedded_streams = match self.database(file_path) {
+            Ok(db) => streams::embedded_streams(&db),
+            Err(_) => Vec::new(),
+        };
+        let custom_action_code: Vec<String> = embedded_streams
+            .iter()
+            .filter(|stream| stream.is_custom_action_code())
+            .map(|stream| {
+                format!(
+                    "{} ({}, {})",
+                    stream.name,
+                    stream.kind,
+                    stream.custom_actions.join(", ")
+                )
+            })
+            .collect();
+        if !custom_action_code.is_empty() {
+            properties.insert(
+                "msi_custom_action_binaries".to_string(),
+                custom_action_code.join("; "),
+            );
+        }
+
+        let details = MsiDetails {
+            product_code: properties.get("ProductCode").cloned(),
+            upgrade_code: properties.get("UpgradeCode").cloned(),
+            product_language: properties.get("ProductLanguage").cloned(),
+            all_users: properties.get("ALLUSERS").cloned(),
+            reboot_actions,
+            launch_conditions,
+            embedded_streams,
+            wix: None,
+        };
+
         // Add format information
         properties.insert("format_version".to_string(), "MSI".to_string());
         properties.insert(
@@ -87,12 +160,56 @@ impl MsiAnalyzer {
             file_hash,
             created_at: Utc::now(),
             properties,
-        })
+            provenance: Default::default(),
+            format_details: Some(FormatDetails::Msi(details)),
+        }
+        .with_provenance(MetadataSource::MsiProperty))
+    }
+
+    /// Reboot actions scheduled by InstallExecuteSequence, with their conditions
+    fn reboot_actions(&self, file_path: &Path) -> Vec<String> {
+        let Ok(db) = self.database(file_path) else {
+            return Vec::new();
+        };
+        let actions = match MsiTables::query_sequence_actions(&db, "InstallExecuteSequence") {
+            Ok(actions) => actions,
+            Err(e) => {
+                tracing::debug!("Failed to query InstallExecuteSequence: {}", e);
+                return Vec::new();
+            }
+        };
+
+        actions
+            .into_iter()
+            .filter(|entry| matches!(entry.action.as_str(), "ForceReboot" | "ScheduleReboot"))
+            .map(|entry| match entry.condition {
+                Some(condition) if !condition.trim().is_empty() => {
+                    format!("{} (if {})", entry.action, condition.trim())
+                }
+                _ => entry.action,
+            })
+            .collect()
+    }
+
+    /// Launch conditions as `Description [Condition]` entries
+    fn launch_conditions(&self, file_path: &Path) -> Vec<String> {
+        let Ok(db) = self.database(file_path) else {
+            return Vec::new();
+        };
+        // Most packages have no LaunchCondition table at all
+        let Ok(conditions) = MsiTables::query_launch_conditions(&db) else {
+            return Vec::new();
+        };
+
+        conditions
+            .into_iter()
+            .map(|entry| format!("{} [{}]", entry.description.trim(), entry.condition.trim()))
+            .collect()
     }
 
     /// Extract files from MSI database
     async fn extract_msi_files(&self, file_path: &Path) -> Result<Vec<FileEntry>> {
-        let db = MsiDatabase::open(file_path)?;
+        let db = self.database(file_path)?;
 
         // Query File and Directory tables with progress logging
         tracing::info!("Querying MSI File table...");
@@ -111,14 +228,27 @@ impl MsiAnalyzer {
         if files.len() > 1000 {
             tracing::info!("Processing large file dataset ({} files)...", files.len());
         }
-        let file_entries = MsiTables::convert_to_file_entries(files, directories);
+        // Files in external cabinets are stored next to the package
+        let media = MsiTables::query_media(&db).unwrap_or_else(|e| {
+            tracing::debug!("Failed to query MSI Media table: {}", e);
+            Vec::new()
+        });
+        let sources: Vec<Option<String>> = files
+            .iter()
+            .map(|file| MsiTables::file_cabinet(&media, file.sequence).map(str::to_string))
+            .collect();
+
+        let mut file_entries = MsiTables::convert_to_file_entries(files, directories);
+        for (entry, source) in file_entries.iter_mut().zip(sources) {
+            entry.source = source;
+        }
 
         Ok(file_entries)
     }
 
     /// Extract registry operations from MSI database
     async fn extract_msi_registry(&self, file_path: &Path) -> Result<Vec<RegistryOperation>> {
-        let db = MsiDatabase::open(file_path)?;
+        let db = self.database(file_path)?;
 
         // Query Registry table with progress logging
         tracing::info!("Querying MSI Registry table...");
@@ -142,22 +272,29 @@ impl MsiAnalyzer {
 #[async_trait]
 impl InstallerAnalyzer for MsiAnalyzer {
     async fn can_analyze(&self, file_path: &Path) -> Result<bool> {
-        // Check file extension first
-        if let Some(ext) = file_path.extension() {
-            if ext.to_str() == Some("msi") {
-                // Validate it's actually an MSI file
-                return Self::has_msi_signature(file_path).await;
-            }
-        }
+        // The signature decides, whatever the extension
+        let header = common::read_file_header(file_path, 8).await?;
+        Ok(Self::has_msi_signature(&header))
+    }
 
-        // If no .msi extension, check signature anyway
-        Self::has_msi_signature(file_path).await
+    async fn can_analyze_with(&self, context: &DetectionContext) -> Result<bool> {
+        Ok(Self::has_msi_signature(context.header()))
     }
 
     fn format(&self) -> InstallerFormat {
         InstallerFormat::MSI
     }
 
+    fn capabilities(&self) -> AnalyzerCapabilities {
+        AnalyzerCapabilities {
+            metadata: SupportLevel::Full,
+            file_extraction: SupportLevel::Full,
+            registry_operations: SupportLevel::Full,
+            install_scripts: SupportLevel::Unsupported,
+            signatures: SupportLevel::Unsupported,
+        }
+    }
+
     async fn extract_metadata(&self, file_path: &Path) -> Result<InstallerMetadata> {
         // Validate file first
         common::validate_file(file_path).await?;
@@ -5,11 +5,12 @@ use std::ffi::OsStr;
 use std::os::windows::ffi::OsStrExt;
 use std::os::windows::ffi::OsStringExt;
 use std::path::Path;
-use windows::core::{PCWSTR, PWSTR};
+use windows::core::{PCWSTR, PSTR, PWSTR};
 use windows::Win32::Foundation::ERROR_SUCCESS;
 use windows::Win32::System::ApplicationInstallationAndServicing::{
-    MsiCloseHandle, MsiDatabaseOpenViewW, MsiOpenDatabaseW, MsiRecordGetInteger,
-    MsiRecordGetStringW, MsiViewClose, MsiViewExecute, MsiViewFetch, MSIDBOPEN_READONLY, MSIHANDLE,
+    MsiCloseHandle, MsiDatabaseOpenViewW, MsiOpenDatabaseW, MsiRecordDataSize, MsiRecordGetInteger,
+    MsiRecordGetStringW, MsiRecordReadStream, MsiViewClose, MsiViewExecute, MsiViewFetch,
+    MSIDBOPEN_READONLY, MSIHANDLE,
 };
 
 /// MSI Database wrapper
@@ -80,6 +81,24 @@ impl MsiDatabase {
         })
     }
 
+    /// Content of the binary stream of a table row, like `Binary.SetupDll`
+    ///
+    /// Only tables whose primary key is a `Name` column, like `Binary` and
+    /// `Icon`, are supported.
+    pub fn read_binary(&self, table: &str, name: &str) -> Result<Vec<u8>> {
+        if name.contains('\'') || table.contains('`') {
+            return Err(AnalyzerError::parse_error(format!(
+                "Invalid MSI stream name: {}.{}",
+                table, name
+            )));
+        }
+        let query = format!("SELECT `Data` FROM `{}` WHERE `Name` = '{}'", table, name);
+        let record = self.execute_query(&query)?.fetch()?.ok_or_else(|| {
+            AnalyzerError::parse_error(format!("MSI database has no stream for {}.{}", table, name))
+        })?;
+        record.read_stream(1)
+    }
+
     /// Get the handle for direct API calls
     pub fn handle(&self) -> MSIHANDLE {
         self.handle
@@ -201,6 +220,25 @@ impl MsiRecord {
         }
     }
 
+    /// Read the stream of a binary field
+    pub fn read_stream(&self, field: u32) -> Result<Vec<u8>> {
+        let size = unsafe { MsiRecordDataSize(self.handle, field) };
+        let mut data = vec![0u8; size as usize];
+        let mut read = size;
+        unsafe {
+            let result =
+                MsiRecordReadStream(self.handle, field, PSTR(data.as_mut_ptr()), &mut read);
+            if result != ERROR_SUCCESS.0 {
+                return Err(AnalyzerError::windows_api_error(format!(
+                    "Failed to read stream from record: error code {}",
+                    result
+                )));
+            }
+        }
+        data.truncate(read as usize);
+        Ok(data)
+    }
+
     /// Check if a field is null
     pub fn is_null(&self, field: u32) -> bool {
         unsafe {
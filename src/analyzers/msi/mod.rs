@@ -1,8 +1,16 @@
 //! MSI (Microsoft Installer) format analyzer
 
 pub mod analyzer;
+#[cfg(windows)]
 pub mod database;
+#[cfg(not(windows))]
+pub mod portable;
+pub mod streams;
 pub mod tables;
 
+// Other platforms read packages without the Windows Installer API
+#[cfg(not(windows))]
+pub use portable as database;
+
 // Re-export main analyzer
 pub use analyzer::MsiAnalyzer;
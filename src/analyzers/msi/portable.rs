@@ -0,0 +1,563 @@
+//! MSI database access without the Windows Installer API
+//!
+//! An MSI package is a compound file whose tables are stored as streams:
+//! `_StringPool` and `_StringData` hold the shared strings, `_Tables` and
+//! `_Columns` the schema, and every table is stored column by column, with
+//! strings as references into the pool and integers offset by their sign bit.
+//! This reader implements the read-only subset of the API of the Windows
+//! backend that the MSI analyzer uses, so packages can be analyzed on Linux
+//! and macOS. Queries are limited to ``SELECT `A`, `B` FROM `Table` ``.
+//!
+//! The package is memory-mapped and opened once. Table streams are read on
+//! first use and kept, so repeated queries of a table don't read it again.
+
+use crate::core::{AnalyzerError, Result};
+use memmap2::Mmap;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Cursor, Read};
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Value the Windows Installer API returns for null integer fields
+const MSI_NULL_INTEGER: i32 = i32::MIN;
+
+/// Column type bit of string columns
+const TYPE_STRING: i32 = 0x0800;
+/// Column type bits of binary stream columns, which are stored as 2 bytes
+const TYPE_BINARY: i32 = 0x0900;
+const TYPE_NULLABLE: i32 = 0x1000;
+
+/// Column of a table as declared in `_Columns`
+#[derive(Debug, Clone)]
+struct Column {
+    name: String,
+    column_type: i32,
+}
+
+impl Column {
+    fn is_string(&self) -> bool {
+        self.column_type & TYPE_STRING != 0
+    }
+
+    fn is_binary(&self) -> bool {
+        self.column_type & !TYPE_NULLABLE == TYPE_BINARY
+    }
+
+    /// Bytes a value of the column takes in the table stream
+    fn width(&self, string_ref_size: usize) -> usize {
+        if self.is_binary() {
+            2
+        } else if self.is_string() {
+            string_ref_size
+        } else if self.column_type & 0xff == 4 {
+            4
+        } else {
+            2
+        }
+    }
+}
+
+/// Field value of a record
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Null,
+    Integer(i32),
+    String(String),
+}
+
+/// Content of a table stream, shared between queries
+type Stream = Arc<Vec<u8>>;
+
+/// MSI Database wrapper
+pub struct MsiDatabase {
+    file: Mutex<cfb::CompoundFile<Cursor<Mmap>>>,
+    /// Streams read so far, `None` for tables without a stream
+    streams: Mutex<HashMap<String, Option<Stream>>>,
+    strings: Vec<String>,
+    string_ref_size: usize,
+    tables: HashMap<String, Vec<Column>>,
+}
+
+impl MsiDatabase {
+    /// Open an MSI database file
+    pub fn open(file_path: &Path) -> Result<Self> {
+        let file = File::open(file_path)?;
+        // SAFETY: the package is only read. Like every reader of the file,
+        // the analysis sees inconsistent data if it is modified meanwhile.
+        let map = unsafe { Mmap::map(&file)? };
+        let file = cfb::CompoundFile::open(Cursor::new(map)).map_err(|e| {
+            AnalyzerError::invalid_format(format!("Not an MSI compound file: {}", e))
+        })?;
+        let mut db = Self {
+            file: Mutex::new(file),
+            streams: Mutex::new(HashMap::new()),
+            strings: Vec::new(),
+            string_ref_size: 2,
+            tables: HashMap::new(),
+        };
+        db.load_strings()?;
+        db.load_schema()?;
+        Ok(db)
+    }
+
+    /// Execute a SQL query on the database
+    pub fn execute_query(&self, query: &str) -> Result<MsiView> {
+        let (names, table) = parse_select(query).ok_or_else(|| {
+            AnalyzerError::parse_error(format!("Unsupported MSI query: {}", query))
+        })?;
+        let columns = self
+            .tables
+            .get(&table)
+            .ok_or_else(|| AnalyzerError::parse_error(format!("Unknown MSI table: {}", table)))?;
+        let selected = names
+            .iter()
+            .map(|name| {
+                columns
+                    .iter()
+                    .position(|column| &column.name == name)
+                    .ok_or_else(|| {
+                        AnalyzerError::parse_error(format!(
+                            "Unknown column {} of MSI table {}",
+                            name, table
+                        ))
+                    })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // Tables without rows have no stream
+        let data = self.read_stream(&table)?.unwrap_or_default();
+        let widths: Vec<usize> = columns
+            .iter()
+            .map(|column| column.width(self.string_ref_size))
+            .collect();
+        let row_size: usize = widths.iter().sum();
+        let row_count = data.len().checked_div(row_size).unwrap_or(0);
+
+        // Values are stored column by column
+        let mut values = vec![Vec::with_capacity(columns.len()); row_count];
+        let mut offset = 0;
+        for (column, width) in columns.iter().zip(&widths) {
+            for row in values.iter_mut() {
+                let raw = read_uint(&data[offset..offset + width]);
+                offset += width;
+                row.push(self.decode(column, *width, raw));
+            }
+        }
+
+        let records = values
+            .into_iter()
+            .map(|row| MsiRecord {
+                values: selected.iter().map(|&index| row[index].clone()).collect(),
+            })
+            .collect();
+        Ok(MsiView {
+            records,
+            next: Cell::new(0),
+        })
+    }
+
+    /// Content of the binary stream of a table row, like `Binary.SetupDll`
+    ///
+    /// Only tables whose primary key is a `Name` column, like `Binary` and
+    /// `Icon`, are supported, as with the Windows backend.
+    pub fn read_binary(&self, table: &str, name: &str) -> Result<Vec<u8>> {
+        let path = format!("/{}", encode_stream_name(&format!("{}.{}", table, name)));
+        let mut file = lock(&self.file);
+        if !file.is_stream(&path) {
+            return Err(AnalyzerError::parse_error(format!(
+                "MSI database has no stream for {}.{}",
+                table, name
+            )));
+        }
+        let mut data = Vec::new();
+        file.open_stream(&path)?.read_to_end(&mut data)?;
+        Ok(data)
+    }
+
+    fn decode(&self, column: &Column, width: usize, raw: u32) -> Value {
+        if raw == 0 || column.is_binary() {
+            return Value::Null;
+        }
+        if column.is_string() {
+            return self
+                .strings
+                .get(raw as usize - 1)
+                .map_or(Value::Null, |string| Value::String(string.clone()));
+        }
+        let value = match width {
+            4 => (raw ^ 0x8000_0000) as i32,
+            _ => raw as i32 - 0x8000,
+        };
+        Value::Integer(value)
+    }
+
+    /// Read the shared strings; string IDs start at 1
+    fn load_strings(&mut self) -> Result<()> {
+        let pool = self.required_stream("_StringPool")?;
+        let data = self.required_stream("_StringData")?;
+        if pool.len() < 4 {
+            return Err(AnalyzerError::invalid_format("Truncated MSI string pool"));
+        }
+        // The high bit of the code page marks 3-byte string references
+        if read_uint(&pool[2..4]) & 0x8000 != 0 {
+            self.string_ref_size = 3;
+        }
+
+        let mut entries = pool[4..]
+            .chunks_exact(4)
+            .map(|entry| (read_uint(&entry[..2]), read_uint(&entry[2..])));
+        let mut offset = 0usize;
+        while let Some((mut length, references)) = entries.next() {
+            // Strings over 64 KiB continue their length in the next entry
+            if length == 0 && references != 0 {
+                let Some((low, _)) = entries.next() else {
+                    break;
+                };
+                length = (references << 16) | low;
+            }
+            let end = offset.saturating_add(length as usize).min(data.len());
+            self.strings.push(decode_string(&data[offset..end]));
+            offset = end;
+        }
+        Ok(())
+    }
+
+    /// Read the columns of every table from `_Tables` and `_Columns`
+    fn load_schema(&mut self) -> Result<()> {
+        let tables = self.required_stream("_Tables")?;
+        let size = self.string_ref_size;
+        for reference in tables.chunks_exact(size) {
+            let name = self.string(read_uint(reference));
+            self.tables.entry(name).or_default();
+        }
+
+        // Columns of `_Columns`: Table, Number, Name, Type
+        let columns = self.required_stream("_Columns")?;
+        let row_count = columns.len() / (2 * size + 4);
+        let field = |start: usize, width: usize, row: usize| {
+            let offset = start * row_count + row * width;
+            read_uint(&columns[offset..offset + width])
+        };
+        let mut declared = Vec::with_capacity(row_count);
+        for row in 0..row_count {
+            let table = self.string(field(0, size, row));
+            let number = (field(size, 2, row) ^ 0x8000) as i32;
+            let name = self.string(field(size + 2, size, row));
+            let column_type = (field(2 * size + 2, 2, row) ^ 0x8000) as i32;
+            declared.push((table, number, name, column_type));
+        }
+        declared.sort_by_key(|(table, number, _, _)| (table.clone(), *number));
+        for (table, _, name, column_type) in declared {
+            self.tables
+                .entry(table)
+                .or_default()
+                .push(Column { name, column_type });
+        }
+        Ok(())
+    }
+
+    fn string(&self, id: u32) -> String {
+        (id as usize)
+            .checked_sub(1)
+            .and_then(|index| self.strings.get(index))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn required_stream(&self, name: &str) -> Result<Stream> {
+        self.read_stream(name)?.ok_or_else(|| {
+            AnalyzerError::invalid_format(format!("MSI database has no {} stream", name))
+        })
+    }
+
+    /// Content of the stream of a table, if the package has one
+    fn read_stream(&self, table: &str) -> Result<Option<Stream>> {
+        if let Some(stream) = lock(&self.streams).get(table) {
+            return Ok(stream.clone());
+        }
+
+        let path = format!("/{}", encode_table_name(table));
+        let stream = {
+            let mut file = lock(&self.file);
+            if file.is_stream(&path) {
+                let mut data = Vec::new();
+                file.open_stream(&path)?.read_to_end(&mut data)?;
+                Some(Arc::new(data))
+            } else {
+                None
+            }
+        };
+        lock(&self.streams).insert(table.to_string(), stream.clone());
+        Ok(stream)
+    }
+}
+
+/// Records selected by a query
+pub struct MsiView {
+    records: Vec<MsiRecord>,
+    next: Cell<usize>,
+}
+
+impl MsiView {
+    /// Fetch the next record from the view
+    pub fn fetch(&self) -> Result<Option<MsiRecord>> {
+        let index = self.next.get();
+        self.next.set(index + 1);
+        Ok(self.records.get(index).cloned())
+    }
+
+    /// Collect all records from the view
+    pub fn collect_records(&self) -> Result<Vec<MsiRecord>> {
+        let mut records = Vec::new();
+
+        while let Some(record) = self.fetch()? {
+            records.push(record);
+        }
+
+        Ok(records)
+    }
+}
+
+/// MSI Record wrapper
+#[derive(Debug, Clone)]
+pub struct MsiRecord {
+    values: Vec<Value>,
+}
+
+impl MsiRecord {
+    /// Get string value from a field
+    pub fn get_string(&self, field: u32) -> Result<String> {
+        Ok(match self.value(field) {
+            Value::String(value) => value.clone(),
+            Value::Integer(value) => value.to_string(),
+            Value::Null => String::new(),
+        })
+    }
+
+    /// Get integer value from a field
+    pub fn get_integer(&self, field: u32) -> Result<i32> {
+        Ok(match self.value(field) {
+            Value::Integer(value) => *value,
+            Value::String(value) => value.parse().unwrap_or(MSI_NULL_INTEGER),
+            Value::Null => MSI_NULL_INTEGER,
+        })
+    }
+
+    /// Check if a field is null
+    pub fn is_null(&self, field: u32) -> bool {
+        *self.value(field) == Value::Null
+    }
+
+    /// Value of a 1-based field
+    fn value(&self, field: u32) -> &Value {
+        (field as usize)
+            .checked_sub(1)
+            .and_then(|index| self.values.get(index))
+            .unwrap_or(&Value::Null)
+    }
+}
+
+/// Column names and table of a ``SELECT `A`, `B` FROM `Table` `` query
+fn parse_select(query: &str) -> Option<(Vec<String>, String)> {
+    let query = query.trim();
+    let upper = query.to_ascii_uppercase();
+    if !upper.starts_with("SELECT ") {
+        return None;
+    }
+    let from = upper.find(" FROM ")?;
+    let columns: Vec<String> = query["SELECT ".len()..from]
+        .split(',')
+        .map(|column| column.trim().trim_matches('`').to_string())
+        .collect();
+    let table = query[from + " FROM ".len()..].trim().trim_matches('`');
+    if table.is_empty()
+        || table.contains(char::is_whitespace)
+        || columns.iter().any(String::is_empty)
+    {
+        return None;
+    }
+    Some((columns, table.to_string()))
+}
+
+/// Compound file stream name of a table
+///
+/// Table names are compressed into the CJK range: pairs of characters from
+/// `[0-9A-Za-z._]` share one code unit, and a prefix marks table streams.
+fn encode_table_name(table: &str) -> String {
+    let mut encoded = String::from('\u{4840}');
+    encoded.push_str(&encode_stream_name(table));
+    encoded
+}
+
+/// Compressed compound file name of a stream, without the table prefix
+fn encode_stream_name(name: &str) -> String {
+    let mut encoded = String::new();
+    let mut chars = name.chars().peekable();
+    while let Some(c) = chars.next() {
+        let Some(first) = base64_index(c) else {
+            encoded.push(c);
+            continue;
+        };
+        let unit = match chars.peek().copied().and_then(base64_index) {
+            Some(second) => {
+                chars.next();
+                0x3800 + (second << 6) + first
+            }
+            None => 0x4800 + first,
+        };
+        encoded.extend(char::from_u32(unit));
+    }
+    encoded
+}
+
+fn base64_index(c: char) -> Option<u32> {
+    match c {
+        '0'..='9' => Some(c as u32 - '0' as u32),
+        'A'..='Z' => Some(c as u32 - 'A' as u32 + 10),
+        'a'..='z' => Some(c as u32 - 'a' as u32 + 36),
+        '.' => Some(62),
+        '_' => Some(63),
+        _ => None,
+    }
+}
+
+/// Little-endian unsigned integer of up to 4 bytes
+/// Lock `mutex`, a panic of another query leaves the data valid
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn read_uint(bytes: &[u8]) -> u32 {
+    bytes
+        .iter()
+        .rev()
+        .fold(0, |value, &byte| (value << 8) | u32::from(byte))
+}
+
+/// Decode a pool string, which is UTF-8 or in the package's ANSI code page
+fn decode_string(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(string) => string.to_string(),
+        // Latin-1 is close enough to Windows-1252 for names and versions
+        Err(_) => bytes.iter().map(|&byte| char::from(byte)).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn write_stream(file: &mut cfb::CompoundFile<File>, table: &str, data: &[u8]) {
+        let path = format!("/{}", encode_table_name(table));
+        file.create_stream(&path).unwrap().write_all(data).unwrap();
+    }
+
+    /// Package with a Property table (Property, Value) and one 4-byte
+    /// integer column, holding two rows
+    fn write_package(path: &Path) {
+        let strings = [
+            "Property",
+            "Value",
+            "Size",
+            "ProductName",
+            "Demo App",
+            "ProductVersion",
+            "1.2.3",
+        ];
+        let mut pool = vec![0xe4, 0x04, 0x00, 0x00];
+        for string in strings {
+            pool.extend((string.len() as u16).to_le_bytes());
+            pool.extend(1u16.to_le_bytes());
+        }
+        let u16s = |values: &[u16]| -> Vec<u8> {
+            values
+                .iter()
+                .flat_map(|value| value.to_le_bytes())
+                .collect()
+        };
+
+        let mut file = cfb::create(path).unwrap();
+        write_stream(&mut file, "_StringPool", &pool);
+        write_stream(&mut file, "_StringData", strings.concat().as_bytes());
+        write_stream(&mut file, "_Tables", &u16s(&[1]));
+        // Table, Number, Name and Type of three columns, column by column
+        write_stream(
+            &mut file,
+            "_Columns",
+            &u16s(&[
+                1,
+                1,
+                1,
+                0x8003,
+                0x8001,
+                0x8002,
+                3,
+                1,
+                2,
+                0x8000 | 0x1104,
+                0x8000 | 0x1d48,
+                0x8000 | 0x0f48,
+            ]),
+        );
+        let mut rows = u16s(&[4, 6, 5, 7]);
+        rows.extend((0x8000_0000u32 ^ 42).to_le_bytes());
+        rows.extend(0u32.to_le_bytes());
+        write_stream(&mut file, "Property", &rows);
+        let path = format!("/{}", encode_stream_name("Binary.SetupDll"));
+        file.create_stream(&path).unwrap().write_all(b"MZ").unwrap();
+        file.flush().unwrap();
+    }
+
+    #[test]
+    fn test_query_portable_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("demo.msi");
+        write_package(&path);
+
+        let db = MsiDatabase::open(&path).unwrap();
+        let records = db
+            .execute_query("SELECT `Value`, `Property`, `Size` FROM `Property`")
+            .unwrap()
+            .collect_records()
+            .unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].get_string(1).unwrap(), "Demo App");
+        assert_eq!(records[0].get_string(2).unwrap(), "ProductName");
+        assert_eq!(records[0].get_integer(3).unwrap(), 42);
+        assert_eq!(records[1].get_string(1).unwrap(), "1.2.3");
+        assert!(records[1].is_null(3));
+        assert_eq!(records[1].get_integer(3).unwrap(), MSI_NULL_INTEGER);
+
+        // The table stream is read once and shared by later queries
+        let stream = db.read_stream("Property").unwrap().unwrap();
+        assert!(Arc::ptr_eq(
+            &stream,
+            &db.read_stream("Property").unwrap().unwrap()
+        ));
+        assert!(db.read_stream("Registry").unwrap().is_none());
+        assert!(lock(&db.streams).contains_key("Registry"));
+
+        assert_eq!(db.read_binary("Binary", "SetupDll").unwrap(), b"MZ");
+        assert!(db.read_binary("Binary", "Missing").is_err());
+
+        assert!(db.execute_query("SELECT `Action` FROM `File`").is_err());
+        assert!(db
+            .execute_query("SELECT `Value` FROM `Property` WHERE `Property` = 'x'")
+            .is_err());
+    }
+
+    #[test]
+    fn test_encode_table_name() {
+        assert_eq!(
+            encode_table_name("_Tables")
+                .encode_utf16()
+                .collect::<Vec<_>>(),
+            vec![0x4840, 0x3f7f, 0x4164, 0x422f, 0x4836]
+        );
+    }
+}
@@ -0,0 +1,193 @@
+//! Streams of the `Binary` and `Icon` tables
+//!
+//! Packages embed the code of custom actions, UI bitmaps and shortcut icons
+//! as binary streams keyed by name. Custom actions of types 1 (DLL), 2 (EXE),
+//! 5 (JScript) and 6 (VBScript) with a source type of 0 name a `Binary` row
+//! as their `Source`; those streams run during installation and are worth a
+//! closer look, so they are listed with the actions using them.
+
+use crate::analyzers::msi::database::MsiDatabase;
+use crate::analyzers::msi::tables::MsiTables;
+use crate::core::{MsiStream, Result};
+use crate::utils::file_name_component;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Tables holding named binary streams
+const STREAM_TABLES: [&str; 2] = ["Binary", "Icon"];
+
+/// Custom action types whose code is a `Binary` row, by the low 6 bits
+const BINARY_SOURCE_TYPES: [i32; 4] = [1, 2, 5, 6];
+
+/// Streams of the `Binary` and `Icon` tables with the custom actions using them
+///
+/// Packages without these tables have no streams; streams that cannot be
+/// read are skipped.
+pub fn embedded_streams(db: &MsiDatabase) -> Vec<MsiStream> {
+    let actions = binary_custom_actions(db);
+    let mut streams = Vec::new();
+    for table in STREAM_TABLES {
+        for name in stream_names(db, table) {
+            let data = match db.read_binary(table, &name) {
+                Ok(data) => data,
+                Err(e) => {
+                    tracing::debug!("Failed to read MSI stream {}.{}: {}", table, name, e);
+                    continue;
+                }
+            };
+            let custom_actions = match table {
+                "Binary" => actions.get(&name).cloned().unwrap_or_default(),
+                _ => Vec::new(),
+            };
+            streams.push(MsiStream {
+                table: table.to_string(),
+                size: data.len() as u64,
+                sha256: format!("{:x}", Sha256::digest(&data)),
+                kind: stream_kind(&data).to_string(),
+                name,
+                custom_actions,
+            });
+        }
+    }
+    streams
+}
+
+/// Write the streams of the `Binary` and `Icon` tables to `<dir>/<table>/<name>`
+///
+/// Returns the number of streams written.
+pub fn dump_streams(db: &MsiDatabase, dir: &Path) -> Result<usize> {
+    let mut written = 0;
+    for table in STREAM_TABLES {
+        for name in stream_names(db, table) {
+            let data = db.read_binary(table, &name)?;
+            let table_dir = dir.join(table);
+            std::fs::create_dir_all(&table_dir)?;
+            std::fs::write(table_dir.join(file_name_component(&name)), data)?;
+            written += 1;
+        }
+    }
+    Ok(written)
+}
+
+/// Names of the rows of `table`, none if the package has no such table
+fn stream_names(db: &MsiDatabase, table: &str) -> Vec<String> {
+    let query = format!("SELECT `Name` FROM `{}`", table);
+    let Ok(records) = db
+        .execute_query(&query)
+        .and_then(|view| view.collect_records())
+    else {
+        return Vec::new();
+    };
+    records
+        .iter()
+        .filter_map(|record| record.get_string(1).ok())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// Custom actions running code from a `Binary` row, by row name
+fn binary_custom_actions(db: &MsiDatabase) -> HashMap<String, Vec<String>> {
+    let mut actions: HashMap<String, Vec<String>> = HashMap::new();
+    // Most packages without custom actions have no CustomAction table
+    let Ok(entries) = MsiTables::query_custom_actions(db) else {
+        return actions;
+    };
+    for entry in entries {
+        if !is_binary_source(entry.action_type) {
+            continue;
+        }
+        if let Some(source) = entry.source {
+            actions.entry(source).or_default().push(entry.action);
+        }
+    }
+    actions
+}
+
+/// Whether a custom action type runs code stored in the `Binary` table
+fn is_binary_source(action_type: i32) -> bool {
+    BINARY_SOURCE_TYPES.contains(&(action_type & 0x3f))
+}
+
+/// Content type of a stream from its header
+fn stream_kind(data: &[u8]) -> &'static str {
+    if data.starts_with(b"MZ") {
+        return match pe_characteristics(data) {
+            Some(characteristics) if characteristics & 0x2000 != 0 => "dll",
+            Some(_) => "exe",
+            None => "dos",
+        };
+    }
+    if data.starts_with(&[0, 0, 1, 0]) {
+        "ico"
+    } else if data.starts_with(b"\x89PNG") {
+        "png"
+    } else if data.starts_with(b"BM") {
+        "bmp"
+    } else if data.starts_with(b"GIF8") {
+        "gif"
+    } else if data.starts_with(&[0xff, 0xd8, 0xff]) {
+        "jpeg"
+    } else if data.starts_with(b"MSCF") {
+        "cab"
+    } else if data.starts_with(b"PK\x03\x04") {
+        "zip"
+    } else if data.starts_with(&[0xd0, 0xcf, 0x11, 0xe0]) {
+        "compound"
+    } else if !data.is_empty() && std::str::from_utf8(data).is_ok() {
+        "text"
+    } else {
+        "data"
+    }
+}
+
+/// `Characteristics` of the COFF header of a PE image
+fn pe_characteristics(data: &[u8]) -> Option<u16> {
+    let offset = u32::from_le_bytes(data.get(0x3c..0x40)?.try_into().ok()?) as usize;
+    if data.get(offset..offset.checked_add(4)?)? != b"PE\0\0" {
+        return None;
+    }
+    let field = offset.checked_add(22)?;
+    Some(u16::from_le_bytes(
+        data.get(field..field + 2)?.try_into().ok()?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pe_image(characteristics: u16) -> Vec<u8> {
+        let mut image = vec![0u8; 0x100];
+        image[..2].copy_from_slice(b"MZ");
+        image[0x3c..0x40].copy_from_slice(&0x80u32.to_le_bytes());
+        image[0x80..0x84].copy_from_slice(b"PE\0\0");
+        image[0x96..0x98].copy_from_slice(&characteristics.to_le_bytes());
+        image
+    }
+
+    #[test]
+    fn test_stream_kind() {
+        assert_eq!(stream_kind(&pe_image(0x0102)), "exe");
+        assert_eq!(stream_kind(&pe_image(0x2102)), "dll");
+        assert_eq!(stream_kind(b"MZ"), "dos");
+        assert_eq!(stream_kind(&[0, 0, 1, 0, 1, 0]), "ico");
+        assert_eq!(stream_kind(b"BM\x36\x00"), "bmp");
+        assert_eq!(stream_kind(b"function main() {}"), "text");
+        assert_eq!(stream_kind(&[0xfe, 0xff, 0x00]), "data");
+    }
+
+    #[test]
+    fn test_binary_source_types() {
+        // DLL and EXE from the Binary table, with in-script execution flags
+        assert!(is_binary_source(1));
+        assert!(is_binary_source(1 | 0x0c00));
+        assert!(is_binary_source(2));
+        assert!(is_binary_source(6));
+        // Installed file (17), property (50) and directory (34) sources
+        assert!(!is_binary_source(17));
+        assert!(!is_binary_source(50));
+        assert!(!is_binary_source(34));
+        assert!(!is_binary_source(51));
+    }
+}
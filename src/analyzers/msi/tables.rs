@@ -47,6 +47,58 @@ pub struct RegistryEntry {
     pub component: String,
 }
 
+/// MSI LaunchCondition table entry
+#[derive(Debug, Clone)]
+pub struct LaunchConditionEntry {
+    pub condition: String,
+    pub description: String,
+}
+
+/// MSI Upgrade table entry
+#[derive(Debug, Clone)]
+pub struct UpgradeEntry {
+    pub upgrade_code: String,
+    pub version_min: Option<String>,
+    pub version_max: Option<String>,
+    pub attributes: i32,
+    pub action_property: String,
+}
+
+/// MSI CustomAction table entry
+#[derive(Debug, Clone)]
+pub struct CustomActionEntry {
+    pub action: String,
+    pub action_type: i32,
+    pub source: Option<String>,
+}
+
+/// MSI Media table entry
+#[derive(Debug, Clone)]
+pub struct MediaEntry {
+    pub disk_id: i32,
+    /// Sequence number of the last file on this media
+    pub last_sequence: i32,
+    /// Cabinet holding the files; `#name` for streams stored in the package
+    pub cabinet: Option<String>,
+}
+
+impl MediaEntry {
+    /// Name of the cabinet file next to the package, for external cabinets
+    pub fn external_cabinet(&self) -> Option<&str> {
+        self.cabinet
+            .as_deref()
+            .filter(|cabinet| !cabinet.is_empty() && !cabinet.starts_with('#'))
+    }
+}
+
+/// MSI sequence table entry (InstallExecuteSequence, InstallUISequence, ...)
+#[derive(Debug, Clone)]
+pub struct SequenceEntry {
+    pub action: String,
+    pub condition: Option<String>,
+    pub sequence: Option<i32>,
+}
+
 /// MSI table queries and parsers
 pub struct MsiTables;
 
@@ -183,6 +235,146 @@ impl MsiTables {
         Ok(registry_entries)
     }
 
+    /// Query the LaunchCondition table
+    pub fn query_launch_conditions(db: &MsiDatabase) -> Result<Vec<LaunchConditionEntry>> {
+        let view = db.execute_query("SELECT `Condition`, `Description` FROM `LaunchCondition`")?;
+        let records = view.collect_records()?;
+
+        let mut conditions = Vec::new();
+        for record in records {
+            let condition = record.get_string(1)?;
+            let description = record.get_string(2)?;
+
+            conditions.push(LaunchConditionEntry {
+                condition,
+                description,
+            });
+        }
+
+        Ok(conditions)
+    }
+
+    /// Query the Upgrade table
+    pub fn query_upgrades(db: &MsiDatabase) -> Result<Vec<UpgradeEntry>> {
+        let view = db.execute_query(
+            "SELECT `UpgradeCode`, `VersionMin`, `VersionMax`, `Attributes`, `ActionProperty` FROM `Upgrade`",
+        )?;
+        let records = view.collect_records()?;
+
+        let mut upgrades = Vec::new();
+        for record in records {
+            let optional = |field| -> Result<Option<String>> {
+                Ok(Some(record.get_string(field)?).filter(|value| !value.is_empty()))
+            };
+            upgrades.push(UpgradeEntry {
+                upgrade_code: record.get_string(1)?,
+                version_min: optional(2)?,
+                version_max: optional(3)?,
+                attributes: if record.is_null(4) {
+                    0
+                } else {
+                    record.get_integer(4)?
+                },
+                action_property: record.get_string(5)?,
+            });
+        }
+
+        Ok(upgrades)
+    }
+
+    /// Query the CustomAction table
+    pub fn query_custom_actions(db: &MsiDatabase) -> Result<Vec<CustomActionEntry>> {
+        let view = db.execute_query("SELECT `Action`, `Type`, `Source` FROM `CustomAction`")?;
+        let records = view.collect_records()?;
+
+        let mut actions = Vec::new();
+        for record in records {
+            let source = if record.is_null(3) {
+                None
+            } else {
+                Some(record.get_string(3)?)
+            };
+            actions.push(CustomActionEntry {
+                action: record.get_string(1)?,
+                action_type: record.get_integer(2)?,
+                source,
+            });
+        }
+
+        Ok(actions)
+    }
+
+    /// Number of rows of `table`, counted by its `key` column
+    pub fn count_rows(db: &MsiDatabase, table: &str, key: &str) -> Result<usize> {
+        let query = format!("SELECT `{}` FROM `{}`", key, table);
+        Ok(db.execute_query(&query)?.collect_records()?.len())
+    }
+
+    /// Query the Media table, ordered by last sequence number
+    pub fn query_media(db: &MsiDatabase) -> Result<Vec<MediaEntry>> {
+        let view = db.execute_query("SELECT `DiskId`, `LastSequence`, `Cabinet` FROM `Media`")?;
+        let records = view.collect_records()?;
+
+        let mut media = Vec::new();
+        for record in records {
+            let disk_id = record.get_integer(1)?;
+            let last_sequence = record.get_integer(2)?;
+            let cabinet = if record.is_null(3) {
+                None
+            } else {
+                Some(record.get_string(3)?)
+            };
+
+            media.push(MediaEntry {
+                disk_id,
+                last_sequence,
+                cabinet,
+            });
+        }
+        media.sort_by_key(|entry| entry.last_sequence);
+
+        Ok(media)
+    }
+
+    /// External cabinet holding a file, found from its sequence number
+    pub fn file_cabinet(media: &[MediaEntry], sequence: Option<i32>) -> Option<&str> {
+        let sequence = sequence?;
+        media
+            .iter()
+            .find(|entry| entry.last_sequence >= sequence)
+            .and_then(MediaEntry::external_cabinet)
+    }
+
+    /// Query the actions of a sequence table such as `InstallExecuteSequence`
+    pub fn query_sequence_actions(db: &MsiDatabase, table: &str) -> Result<Vec<SequenceEntry>> {
+        let query = format!("SELECT `Action`, `Condition`, `Sequence` FROM `{}`", table);
+        let view = db.execute_query(&query)?;
+        let records = view.collect_records()?;
+
+        let mut actions = Vec::new();
+        for record in records {
+            let action = record.get_string(1)?;
+            let condition = if record.is_null(2) {
+                None
+            } else {
+                Some(record.get_string(2)?)
+            };
+            let sequence = if record.is_null(3) {
+                None
+            } else {
+                Some(record.get_integer(3)?)
+            };
+
+            actions.push(SequenceEntry {
+                action,
+                condition,
+                sequence,
+            });
+        }
+
+        Ok(actions)
+    }
+
     /// Convert MSI file entries to our FileEntry format
     pub fn convert_to_file_entries(
         files: Vec<FileTableEntry>,
@@ -225,6 +417,10 @@ impl MsiTables {
                 hash: None, // Would need to extract from CAB to calculate
                 attributes,
                 compression: Some("CAB".to_string()),
+                is_speculative: false,
+                architecture: None,
+                modified: None,
+                source: None,
             });
         }
 
@@ -336,6 +532,7 @@ impl MsiTables {
                         value_type,
                         value_data,
                         timestamp: Utc::now(),
+                        process_id: None,
                     });
                 }
             } else {
@@ -343,6 +540,7 @@ impl MsiTables {
                 operations.push(RegistryOperation::CreateKey {
                     key_path,
                     timestamp: Utc::now(),
+                    process_id: None,
                 });
             }
         }
@@ -443,6 +641,26 @@ mod tests {
         assert_eq!(file_path, "Program Files\\MyApp\\myapp.exe");
     }
 
+    #[test]
+    fn test_file_cabinet_from_media() {
+        let media = |disk_id, last_sequence, cabinet: &str| MediaEntry {
+            disk_id,
+            last_sequence,
+            cabinet: Some(cabinet.to_string()),
+        };
+        let media = vec![
+            media(1, 10, "#product.cab"),
+            media(2, 25, "Data2.cab"),
+            media(3, 30, ""),
+        ];
+
+        assert_eq!(MsiTables::file_cabinet(&media, Some(4)), None);
+        assert_eq!(MsiTables::file_cabinet(&media, Some(11)), Some("Data2.cab"));
+        assert_eq!(MsiTables::file_cabinet(&media, Some(25)), Some("Data2.cab"));
+        assert_eq!(MsiTables::file_cabinet(&media, Some(28)), None);
+        assert_eq!(MsiTables::file_cabinet(&media, None), None);
+    }
+
     #[test]
     fn test_convert_to_file_entries_with_hierarchy() {
         let files = vec![FileTableEntry {
@@ -1,8 +1,13 @@
 //! MSIX/AppX analyzer implementation
 
 use super::parser::MsixParser;
-use crate::analyzers::{common, InstallerAnalyzer};
-use crate::core::{FileEntry, InstallerFormat, InstallerMetadata, RegistryOperation, Result};
+use crate::analyzers::archive::ArchiveFormat;
+use crate::analyzers::common::{self, EntryReader};
+use crate::analyzers::{DetectionContext, InstallerAnalyzer};
+use crate::core::{
+    AnalyzerCapabilities, FileEntry, FormatDetails, InstallerFormat, InstallerMetadata,
+    MetadataField, MetadataSource, PackageDependency, RegistryOperation, Result, SupportLevel,
+};
 use async_trait::async_trait;
 use chrono::Utc;
 use std::path::Path;
@@ -20,9 +25,12 @@ impl MsixAnalyzer {
         }
     }
 
-    /// Check if file is a MSIX/AppX package
-    async fn is_msix_file(file_path: &Path) -> Result<bool> {
-        MsixParser::is_msix_file(file_path).await
+    /// Check if the file under detection is a MSIX/AppX package
+    fn is_msix_file(context: &DetectionContext) -> bool {
+        let is_package = context
+            .extension()
+            .is_some_and(|ext| matches!(ext.to_lowercase().as_str(), "msix" | "appx"));
+        is_package && context.archive_format() == ArchiveFormat::Zip
     }
 
     /// Extract metadata from MSIX/AppX file
@@ -34,13 +42,20 @@ impl MsixAnalyzer {
         let properties = self.parser.extract_msix_properties(file_path).await?;
 
         // Extract manifest metadata for product info
-        let (product_name, product_version, manufacturer) =
+        let (product_name, product_version, manufacturer, format_details, source) =
             match self.parser.extract_manifest(file_path) {
                 Ok(manifest) => {
                     let product_name = Some(manifest.display_name.clone());
                     let product_version = Some(manifest.identity_version.clone());
                     let manufacturer = Some(manifest.publisher_display_name.clone());
-                    (product_name, product_version, manufacturer)
+                    let details = FormatDetails::Msix(manifest.details());
+                    (
+                        product_name,
+                        product_version,
+                        manufacturer,
+                        Some(details),
+                        MetadataSource::Manifest,
+                    )
                 }
                 Err(_) => {
                     // Fallback to filename parsing
@@ -48,11 +63,17 @@ impl MsixAnalyzer {
                         .file_stem()
                         .and_then(|s| s.to_str())
                         .map(|s| s.to_string());
-                    (product_name, None, Some("Unknown".to_string()))
+                    (
+                        product_name,
+                        None,
+                        Some("Unknown".to_string()),
+                        None,
+                        MetadataSource::FilenameHeuristic,
+                    )
                 }
             };
 
-        Ok(InstallerMetadata {
+        let mut metadata = InstallerMetadata {
             format: InstallerFormat::MSIX,
             product_name,
             product_version,
@@ -61,7 +82,14 @@ impl MsixAnalyzer {
             file_hash,
             created_at: Utc::now(),
             properties,
-        })
+            provenance: Default::default(),
+            format_details,
+        }
+        .with_provenance(source);
+        if source == MetadataSource::FilenameHeuristic {
+            metadata.set_provenance(MetadataField::Manufacturer, MetadataSource::Placeholder);
+        }
+        Ok(metadata)
     }
 
     /// Extract files from MSIX/AppX package
@@ -88,22 +116,51 @@ impl MsixAnalyzer {
         // handled by the Windows deployment infrastructure at runtime.
         Ok(Vec::new())
     }
+
+    /// Extract PackageDependency entries from the AppxManifest
+    async fn extract_msix_dependencies(&self, file_path: &Path) -> Result<Vec<PackageDependency>> {
+        let manifest = self.parser.extract_manifest(file_path)?;
+
+        Ok(manifest
+            .dependencies
+            .into_iter()
+            .map(|dep| PackageDependency {
+                name: dep.name,
+                version_spec: dep.min_version.map(|version| format!(">={}", version)),
+                condition: None,
+                optional: false,
+            })
+            .collect())
+    }
 }
 
 #[async_trait]
 impl InstallerAnalyzer for MsixAnalyzer {
     async fn can_analyze(&self, file_path: &Path) -> Result<bool> {
-        // Validate file accessibility
-        common::validate_file(file_path).await?;
+        self.can_analyze_with(&DetectionContext::new(file_path).await?)
+            .await
+    }
 
+    async fn can_analyze_with(&self, context: &DetectionContext) -> Result<bool> {
         // Check if it's a MSIX/AppX package
-        Self::is_msix_file(file_path).await
+        Ok(Self::is_msix_file(context))
     }
 
     fn format(&self) -> InstallerFormat {
         InstallerFormat::MSIX
     }
 
+    fn capabilities(&self) -> AnalyzerCapabilities {
+        AnalyzerCapabilities {
+            metadata: SupportLevel::Partial,
+            file_extraction: SupportLevel::Full,
+            registry_operations: SupportLevel::Unsupported,
+            install_scripts: SupportLevel::Unsupported,
+            // Signatures are verified, the certificate chain is not
+            signatures: SupportLevel::Partial,
+        }
+    }
+
     async fn extract_metadata(&self, file_path: &Path) -> Result<InstallerMetadata> {
         // Validate file first
         common::validate_file(file_path).await?;
@@ -127,6 +184,30 @@ impl InstallerAnalyzer for MsixAnalyzer {
 
         self.extract_msix_registry(file_path).await
     }
+
+    async fn extract_dependencies(&self, file_path: &Path) -> Result<Vec<PackageDependency>> {
+        // Validate file first
+        common::validate_file(file_path).await?;
+
+        self.extract_msix_dependencies(file_path).await
+    }
+
+    async fn read_file_content(
+        &self,
+        file_path: &Path,
+        entry_path: &Path,
+    ) -> Result<Option<Vec<u8>>> {
+        // Validate file first
+        common::validate_file(file_path).await?;
+
+        self.parser.read_file_content(file_path, entry_path).await
+    }
+
+    async fn open_file(&self, file_path: &Path, entry_path: &Path) -> Result<Option<EntryReader>> {
+        common::validate_file(file_path).await?;
+
+        self.parser.open_file(file_path, entry_path).await
+    }
 }
 
 impl Default for MsixAnalyzer {
@@ -0,0 +1,243 @@
+//! Risk classification of MSIX/AppX capabilities
+//!
+//! Capabilities declare what a packaged app may do outside its app container.
+//! Each known capability is assigned a risk tier with a short explanation of
+//! the access it grants; unknown capabilities are treated as low risk.
+
+use std::fmt;
+
+/// Risk tier of a capability, ordered from least to most risky
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CapabilityRisk {
+    Low,
+    Medium,
+    High,
+}
+
+impl CapabilityRisk {
+    /// Value used in analysis properties
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+        }
+    }
+}
+
+impl fmt::Display for CapabilityRisk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Classified capability
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapabilityAssessment {
+    pub name: String,
+    pub risk: CapabilityRisk,
+    /// What the capability allows the app to do
+    pub explanation: &'static str,
+}
+
+/// Known capabilities with their risk tier and explanation
+const KNOWN_CAPABILITIES: &[(&str, CapabilityRisk, &str)] = &[
+    // Escape the app container or change the system
+    (
+        "runFullTrust",
+        CapabilityRisk::High,
+        "Runs as a full-trust desktop process outside the app container sandbox",
+    ),
+    (
+        "allowElevation",
+        CapabilityRisk::High,
+        "Can start processes elevated to administrator",
+    ),
+    (
+        "broadFileSystemAccess",
+        CapabilityRisk::High,
+        "Reads and writes every file the user can access",
+    ),
+    (
+        "unvirtualizedResources",
+        CapabilityRisk::High,
+        "Writes to the real registry and file system instead of virtualized copies",
+    ),
+    (
+        "packagedServices",
+        CapabilityRisk::High,
+        "Installs Windows services with the package",
+    ),
+    (
+        "localSystemServices",
+        CapabilityRisk::High,
+        "Installs services that run as LocalSystem",
+    ),
+    (
+        "customInstallActions",
+        CapabilityRisk::High,
+        "Runs custom executables during installation",
+    ),
+    (
+        "packageManagement",
+        CapabilityRisk::High,
+        "Installs, updates and removes other packages",
+    ),
+    (
+        "inputInjectionBrokered",
+        CapabilityRisk::High,
+        "Injects keyboard, mouse and touch input into other apps",
+    ),
+    (
+        "appLicensing",
+        CapabilityRisk::High,
+        "Manages licenses of other apps",
+    ),
+    // Access to user data, devices or other apps
+    (
+        "documentsLibrary",
+        CapabilityRisk::Medium,
+        "Reads and writes the user's Documents library",
+    ),
+    (
+        "picturesLibrary",
+        CapabilityRisk::Medium,
+        "Reads and writes the user's Pictures library",
+    ),
+    (
+        "videosLibrary",
+        CapabilityRisk::Medium,
+        "Reads and writes the user's Videos library",
+    ),
+    (
+        "musicLibrary",
+        CapabilityRisk::Medium,
+        "Reads and writes the user's Music library",
+    ),
+    (
+        "removableStorage",
+        CapabilityRisk::Medium,
+        "Reads and writes files on removable drives",
+    ),
+    (
+        "enterpriseAuthentication",
+        CapabilityRisk::Medium,
+        "Authenticates to intranet resources with the user's domain credentials",
+    ),
+    (
+        "sharedUserCertificates",
+        CapabilityRisk::Medium,
+        "Uses software and smart card certificates of the user",
+    ),
+    (
+        "userAccountInformation",
+        CapabilityRisk::Medium,
+        "Reads the user's name and account picture",
+    ),
+    (
+        "contacts",
+        CapabilityRisk::Medium,
+        "Reads the user's contacts",
+    ),
+    (
+        "appointments",
+        CapabilityRisk::Medium,
+        "Reads the user's calendar",
+    ),
+    ("webcam", CapabilityRisk::Medium, "Records from the camera"),
+    (
+        "microphone",
+        CapabilityRisk::Medium,
+        "Records from the microphone",
+    ),
+    (
+        "location",
+        CapabilityRisk::Medium,
+        "Reads the device location",
+    ),
+    (
+        "appDiagnostic",
+        CapabilityRisk::Medium,
+        "Reads diagnostic information about other running apps",
+    ),
+    (
+        "extendedExecutionUnconstrained",
+        CapabilityRisk::Medium,
+        "Keeps running in the background without time limits",
+    ),
+    (
+        "extendedBackgroundTaskTime",
+        CapabilityRisk::Medium,
+        "Runs background tasks without time limits",
+    ),
+    (
+        "internetClientServer",
+        CapabilityRisk::Medium,
+        "Accepts inbound connections from the internet",
+    ),
+    (
+        "privateNetworkClientServer",
+        CapabilityRisk::Medium,
+        "Accepts inbound connections from home and work networks",
+    ),
+    // Common, narrowly scoped access
+    (
+        "internetClient",
+        CapabilityRisk::Low,
+        "Makes outbound internet connections",
+    ),
+];
+
+/// Classify a capability by name (case-insensitive)
+pub fn assess_capability(name: &str) -> CapabilityAssessment {
+    let (risk, explanation) = KNOWN_CAPABILITIES
+        .iter()
+        .find(|(known, _, _)| known.eq_ignore_ascii_case(name))
+        .map(|(_, risk, explanation)| (*risk, *explanation))
+        .unwrap_or((CapabilityRisk::Low, "No elevated access known"));
+
+    CapabilityAssessment {
+        name: name.to_string(),
+        risk,
+        explanation,
+    }
+}
+
+/// Classify a comma-separated capability list, most risky first
+pub fn assess_capabilities(capabilities: &str) -> Vec<CapabilityAssessment> {
+    let mut assessments: Vec<CapabilityAssessment> = capabilities
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(assess_capability)
+        .collect();
+    // Stable sort keeps manifest order within a tier
+    assessments.sort_by_key(|assessment| std::cmp::Reverse(assessment.risk));
+    assessments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assess_capabilities() {
+        let assessments =
+            assess_capabilities("internetClient, webcam, RunFullTrust, customCapability");
+        let tiers: Vec<(&str, CapabilityRisk)> = assessments
+            .iter()
+            .map(|assessment| (assessment.name.as_str(), assessment.risk))
+            .collect();
+        assert_eq!(
+            tiers,
+            vec![
+                ("RunFullTrust", CapabilityRisk::High),
+                ("webcam", CapabilityRisk::Medium),
+                ("internetClient", CapabilityRisk::Low),
+                ("customCapability", CapabilityRisk::Low),
+            ]
+        );
+        assert!(assessments[0].explanation.contains("full-trust"));
+        assert!(assess_capabilities("").is_empty());
+    }
+}
@@ -0,0 +1,747 @@
+//! MSIX/AppX package integrity validation
+//!
+//! An MSIX package protects its content in two layers:
+//!
+//! - `AppxBlockMap.xml` lists a hash for every 64 KiB block of every packaged
+//!   file. The blocks are recomputed from the package content, and files that
+//!   are missing from or not covered by the block map are reported.
+//! - `AppxSignature.p7x` is an Authenticode PKCS#7 signature. Its signed
+//!   content carries digests of the package parts: the local ZIP records
+//!   (`AXPC`), `[Content_Types].xml` (`AXCT`), the block map (`AXBM`) and the
+//!   code integrity catalog (`AXCI`). These digests, the signed attributes and
+//!   the RSA signature are verified.
+//!
+//! The central directory digest (`AXCD`) is not recomputed, and the signing
+//! certificate is not validated against a trust store: a valid signature means
+//! the package is unmodified since the certificate it carries signed it, not
+//! that the signer is trusted.
+
+pub use crate::analyzers::common::pkcs7::IntegrityStatus;
+use crate::analyzers::common::pkcs7::{DigestAlgorithm, SignatureError, SignedMessage};
+use crate::core::{AnalyzerError, Result};
+use base64::Engine;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use zip::ZipArchive;
+
+const BLOCK_MAP: &str = "AppxBlockMap.xml";
+const SIGNATURE: &str = "AppxSignature.p7x";
+const CONTENT_TYPES: &str = "[Content_Types].xml";
+const CODE_INTEGRITY: &str = "AppxMetadata/CodeIntegrity.cat";
+
+/// Package parts that are not listed in the block map by design
+const BLOCK_MAP_EXEMPT: [&str; 4] = [BLOCK_MAP, SIGNATURE, CONTENT_TYPES, CODE_INTEGRITY];
+
+/// Uncompressed size of a block map block
+const BLOCK_SIZE: usize = 64 * 1024;
+
+/// Prefix of `AppxSignature.p7x` in front of the PKCS#7 data
+const P7X_MAGIC: &[u8] = b"PKCX";
+
+/// Marker in the signed content that starts the package part digests
+const APPX_DIGESTS_MAGIC: &[u8] = b"APPX";
+
+/// Issues listed in analysis properties; the rest are only counted
+const MAX_LISTED_ISSUES: usize = 10;
+
+/// Result of verifying `AppxSignature.p7x`
+#[derive(Debug, Clone)]
+pub struct SignatureCheck {
+    pub status: IntegrityStatus,
+    /// Subject of the signing certificate
+    pub signer: Option<String>,
+    /// SHA-1 thumbprint of the signing certificate
+    pub signer_thumbprint: Option<String>,
+    /// Whether the signer matches the manifest Identity Publisher
+    pub publisher_matches: Option<bool>,
+}
+
+/// Result of verifying `AppxBlockMap.xml` against the package content
+#[derive(Debug, Clone)]
+pub struct BlockMapCheck {
+    pub status: IntegrityStatus,
+    /// Files whose blocks all matched
+    pub files_verified: usize,
+}
+
+/// Integrity of an MSIX/AppX package
+#[derive(Debug, Clone)]
+pub struct PackageIntegrity {
+    pub signature: SignatureCheck,
+    pub block_map: BlockMapCheck,
+    /// Problems found by both checks
+    pub issues: Vec<String>,
+}
+
+impl PackageIntegrity {
+    /// Verify the block map and signature of a package
+    ///
+    /// `identity_publisher` is the manifest Publisher, which Windows requires
+    /// to equal the subject of the signing certificate.
+    pub fn verify(file_path: &Path, identity_publisher: Option<&str>) -> Result<Self> {
+        let file = File::open(file_path)?;
+        let mut archive = ZipArchive::new(file)
+            .map_err(|e| AnalyzerError::generic(format!("Failed to open MSIX/AppX file: {}", e)))?;
+        let entries = PackageEntries::from_archive(&mut archive)?;
+        let mut issues = Vec::new();
+
+        let block_map_xml = entries.read_part(&mut archive, BLOCK_MAP);
+        let block_map = match &block_map_xml {
+            Some(xml) => verify_block_map(
+                &mut archive,
+                &entries,
+                &String::from_utf8_lossy(xml),
+                &mut issues,
+            ),
+            None => {
+                issues.push(format!("{} is missing", BLOCK_MAP));
+                BlockMapCheck {
+                    status: IntegrityStatus::Missing,
+                    files_verified: 0,
+                }
+            }
+        };
+
+        let signature = match entries.read_part(&mut archive, SIGNATURE) {
+            Some(p7x) => {
+                let context = SignatureContext {
+                    file_path,
+                    archive: &mut archive,
+                    entries: &entries,
+                    block_map_xml: block_map_xml.as_deref(),
+                    identity_publisher,
+                };
+                verify_signature(context, &p7x, &mut issues)
+            }
+            None => SignatureCheck {
+                status: IntegrityStatus::Missing,
+                signer: None,
+                signer_thumbprint: None,
+                publisher_matches: None,
+            },
+        };
+
+        let integrity = Self {
+            signature,
+            block_map,
+            issues,
+        };
+        if integrity.is_tampered() {
+            tracing::warn!(
+                "MSIX/AppX package {} failed integrity checks: {}",
+                file_path.display(),
+                integrity.issues.join("; ")
+            );
+        }
+
+        Ok(integrity)
+    }
+
+    /// Whether the package content no longer matches its integrity data
+    pub fn is_tampered(&self) -> bool {
+        self.signature.status == IntegrityStatus::Invalid
+            || self.block_map.status == IntegrityStatus::Invalid
+    }
+
+    /// Record the results as `msix_*` analysis properties
+    pub fn insert_properties(&self, properties: &mut HashMap<String, String>) {
+        properties.insert(
+            "msix_signature_status".to_string(),
+            self.signature.status.to_string(),
+        );
+        if let Some(signer) = &self.signature.signer {
+            properties.insert("msix_signer".to_string(), signer.clone());
+        }
+        if let Some(thumbprint) = &self.signature.signer_thumbprint {
+            properties.insert("msix_signer_thumbprint".to_string(), thumbprint.clone());
+        }
+        if let Some(matches) = self.signature.publisher_matches {
+            properties.insert(
+                "msix_signer_matches_publisher".to_string(),
+                matches.to_string(),
+            );
+        }
+        properties.insert(
+            "msix_block_map_status".to_string(),
+            self.block_map.status.to_string(),
+        );
+        properties.insert(
+            "msix_block_map_files_verified".to_string(),
+            self.block_map.files_verified.to_string(),
+        );
+        properties.insert("msix_tampered".to_string(), self.is_tampered().to_string());
+
+        if !self.issues.is_empty() {
+            let mut listed = self.issues[..self.issues.len().min(MAX_LISTED_ISSUES)].join("; ");
+            if self.issues.len() > MAX_LISTED_ISSUES {
+                listed.push_str(&format!(
+                    "; and {} more",
+                    self.issues.len() - MAX_LISTED_ISSUES
+                ));
+            }
+            properties.insert("msix_integrity_issues".to_string(), listed);
+        }
+    }
+}
+
+/// ZIP entry of the package with its position in the archive
+struct PackageEntry {
+    index: usize,
+    /// Part name: percent-decoded, `/` separated
+    name: String,
+    header_start: u64,
+    is_dir: bool,
+}
+
+/// Entries of the package, looked up by case-insensitive part name
+struct PackageEntries {
+    entries: Vec<PackageEntry>,
+    by_name: HashMap<String, usize>,
+    /// Offset of the central directory, where the local records end
+    central_directory_start: u64,
+}
+
+impl PackageEntries {
+    fn from_archive(archive: &mut ZipArchive<File>) -> Result<Self> {
+        let mut entries = Vec::with_capacity(archive.len());
+        let mut central_directory_start = u64::MAX;
+
+        for index in 0..archive.len() {
+            let entry = archive.by_index_raw(index).map_err(|e| {
+                AnalyzerError::generic(format!("Failed to read zip entry {}: {}", index, e))
+            })?;
+            central_directory_start = central_directory_start.min(entry.central_header_start());
+            entries.push(PackageEntry {
+                index,
+                name: percent_decode(entry.name()),
+                header_start: entry.header_start(),
+                is_dir: entry.is_dir(),
+            });
+        }
+        entries.sort_by_key(|entry| entry.header_start);
+
+        let by_name = entries
+            .iter()
+            .enumerate()
+            .map(|(position, entry)| (entry.name.to_lowercase(), position))
+            .collect();
+
+        Ok(Self {
+            entries,
+            by_name,
+            central_directory_start,
+        })
+    }
+
+    fn get(&self, name: &str) -> Option<&PackageEntry> {
+        self.by_name
+            .get(&name.to_lowercase())
+            .map(|&position| &self.entries[position])
+    }
+
+    /// Read a whole part; unreadable parts are treated as absent
+    fn read_part(&self, archive: &mut ZipArchive<File>, name: &str) -> Option<Vec<u8>> {
+        let entry = self.get(name)?;
+        let mut zip_file = archive.by_index(entry.index).ok()?;
+        let mut content = Vec::new();
+        zip_file.read_to_end(&mut content).ok()?;
+        Some(content)
+    }
+}
+
+/// Digest algorithm of a block map `HashMethod` URI
+fn block_map_algorithm(uri: &str) -> Option<DigestAlgorithm> {
+    match uri {
+        "http://www.w3.org/2001/04/xmlenc#sha256" => Some(DigestAlgorithm::Sha256),
+        "http://www.w3.org/2001/04/xmldsig-more#sha384" => Some(DigestAlgorithm::Sha384),
+        "http://www.w3.org/2001/04/xmlenc#sha512" => Some(DigestAlgorithm::Sha512),
+        _ => None,
+    }
+}
+
+/// Compare every file listed in the block map with the package content
+fn verify_block_map(
+    archive: &mut ZipArchive<File>,
+    entries: &PackageEntries,
+    xml: &str,
+    issues: &mut Vec<String>,
+) -> BlockMapCheck {
+    let hash_method = xml_attribute(xml, "HashMethod").unwrap_or_default();
+    let Some(algorithm) = block_map_algorithm(&hash_method) else {
+        issues.push(format!(
+            "Unsupported block map hash method '{}'",
+            hash_method
+        ));
+        return BlockMapCheck {
+            status: IntegrityStatus::Unverified,
+            files_verified: 0,
+        };
+    };
+
+    let file_regex = Regex::new(r"(?s)<File\s([^>]*?)(?:/>|>(.*?)</File>)").unwrap();
+    let block_regex = Regex::new(r"<Block\s[^>]*>").unwrap();
+
+    let mut listed = HashSet::new();
+    let mut files_verified = 0;
+    let mut valid = true;
+
+    for file in file_regex.captures_iter(xml) {
+        let Some(name) = xml_attribute(&file[1], "Name") else {
+            continue;
+        };
+        let name = unescape_xml(&name).replace('\\', "/");
+        listed.insert(name.to_lowercase());
+
+        let size: u64 = xml_attribute(&file[1], "Size")
+            .and_then(|size| size.parse().ok())
+            .unwrap_or(0);
+        let expected: Vec<Vec<u8>> = block_regex
+            .find_iter(file.get(2).map_or("", |body| body.as_str()))
+            .map(|block| {
+                xml_attribute(block.as_str(), "Hash")
+                    .and_then(|hash| base64::engine::general_purpose::STANDARD.decode(hash).ok())
+                    .unwrap_or_default()
+            })
+            .collect();
+
+        let Some(entry) = entries.get(&name) else {
+            issues.push(format!(
+                "{} is listed in the block map but missing from the package",
+                name
+            ));
+            valid = false;
+            continue;
+        };
+
+        match hash_blocks(archive, entry.index, algorithm) {
+            Ok((actual_size, _)) if actual_size != size => {
+                issues.push(format!(
+                    "{} is {} bytes but the block map records {}",
+                    name, actual_size, size
+                ));
+                valid = false;
+            }
+            Ok((_, hashes)) if hashes != expected => {
+                issues.push(format!("{} does not match its block map hashes", name));
+                valid = false;
+            }
+            Ok(_) => files_verified += 1,
+            Err(e) => {
+                issues.push(format!("{} could not be read: {}", name, e));
+                valid = false;
+            }
+        }
+    }
+
+    for entry in &entries.entries {
+        let exempt = BLOCK_MAP_EXEMPT
+            .iter()
+            .any(|part| part.eq_ignore_ascii_case(&entry.name));
+        if !entry.is_dir && !exempt && !listed.contains(&entry.name.to_lowercase()) {
+            issues.push(format!("{} is not covered by the block map", entry.name));
+            valid = false;
+        }
+    }
+
+    BlockMapCheck {
+        status: if valid {
+            IntegrityStatus::Valid
+        } else {
+            IntegrityStatus::Invalid
+        },
+        files_verified,
+    }
+}
+
+/// Size and per-block hashes of a packaged file
+fn hash_blocks(
+    archive: &mut ZipArchive<File>,
+    index: usize,
+    algorithm: DigestAlgorithm,
+) -> std::io::Result<(u64, Vec<Vec<u8>>)> {
+    let mut zip_file = archive.by_index(index)?;
+    let mut buffer = vec![0u8; BLOCK_SIZE];
+    let mut hashes = Vec::new();
+    let mut size = 0u64;
+
+    loop {
+        // Fill a whole block; reads may return less than requested
+        let mut filled = 0;
+        while filled < BLOCK_SIZE {
+            let read = zip_file.read(&mut buffer[filled..])?;
+            if read == 0 {
+                break;
+            }
+            filled += read;
+        }
+        if filled == 0 {
+            break;
+        }
+        size += filled as u64;
+        hashes.push(algorithm.digest(&buffer[..filled]));
+        if filled < BLOCK_SIZE {
+            break;
+        }
+    }
+
+    Ok((size, hashes))
+}
+
+/// Inputs of the signature check besides the signature itself
+struct SignatureContext<'a> {
+    file_path: &'a Path,
+    archive: &'a mut ZipArchive<File>,
+    entries: &'a PackageEntries,
+    block_map_xml: Option<&'a [u8]>,
+    identity_publisher: Option<&'a str>,
+}
+
+/// Verify `AppxSignature.p7x`, recording every problem found
+fn verify_signature(
+    mut context: SignatureContext<'_>,
+    p7x: &[u8],
+    issues: &mut Vec<String>,
+) -> SignatureCheck {
+    let mut check = SignatureCheck {
+        status: IntegrityStatus::Invalid,
+        signer: None,
+        signer_thumbprint: None,
+        publisher_matches: None,
+    };
+
+    let mut problems = Vec::new();
+    check.status = match check_signature(&mut context, p7x, &mut check, &mut problems) {
+        Ok(()) if problems.is_empty() => IntegrityStatus::Valid,
+        Ok(()) => IntegrityStatus::Invalid,
+        Err((status, message)) => {
+            problems.push(message);
+            status
+        }
+    };
+
+    issues.extend(problems);
+    check
+}
+
+/// Run the signature checks
+///
+/// Mismatches are collected in `problems`; an `Err` stops the check when the
+/// signature cannot be evaluated any further.
+fn check_signature(
+    context: &mut SignatureContext<'_>,
+    p7x: &[u8],
+    check: &mut SignatureCheck,
+    problems: &mut Vec<String>,
+) -> std::result::Result<(), (IntegrityStatus, String)> {
+    let failed = |error: SignatureError| (error.status(), error.describe(SIGNATURE));
+
+    let der_data = p7x
+        .strip_prefix(P7X_MAGIC)
+        .ok_or_else(|| failed(SignatureError::Malformed("missing PKCX header".to_string())))?;
+    let message = SignedMessage::parse(der_data).map_err(failed)?;
+
+    check_package_digests(context, &message.content, message.algorithm, problems)?;
+
+    check.signer = message.signer();
+    check.signer_thumbprint = message.signer_thumbprint();
+    message.verify(problems).map_err(failed)?;
+
+    if let (Some(publisher), Some(subject)) = (context.identity_publisher, &check.signer) {
+        let matches = distinguished_names_match(subject, publisher);
+        check.publisher_matches = Some(matches);
+        if !matches {
+            problems.push(format!(
+                "Signer '{}' does not match the manifest publisher '{}'",
+                subject, publisher
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Compare the package part digests in the signed content with the package
+fn check_package_digests(
+    context: &mut SignatureContext<'_>,
+    signed_content: &[u8],
+    algorithm: DigestAlgorithm,
+    problems: &mut Vec<String>,
+) -> std::result::Result<(), (IntegrityStatus, String)> {
+    let digests = appx_digests(signed_content, algorithm.output_len()).ok_or_else(|| {
+        (
+            IntegrityStatus::Invalid,
+            format!("{} contains no package digests", SIGNATURE),
+        )
+    })?;
+
+    for (tag, expected) in &digests {
+        let (part, actual) = match tag.as_str() {
+            "AXPC" => (
+                "Package content",
+                local_records_digest(context, algorithm)
+                    .map_err(|e| (IntegrityStatus::Invalid, e.to_string()))?,
+            ),
+            "AXBM" => (
+                BLOCK_MAP,
+                context
+                    .block_map_xml
+                    .map(|xml| algorithm.digest(xml))
+                    .unwrap_or_default(),
+            ),
+            "AXCT" => (
+                CONTENT_TYPES,
+                context
+                    .entries
+                    .read_part(context.archive, CONTENT_TYPES)
+                    .map(|content| algorithm.digest(&content))
+                    .unwrap_or_default(),
+            ),
+            "AXCI" => (
+                CODE_INTEGRITY,
+                context
+                    .entries
+                    .read_part(context.archive, CODE_INTEGRITY)
+                    .map(|content| algorithm.digest(&content))
+                    .unwrap_or_default(),
+            ),
+            // AXCD covers the central directory as it was before the
+            // signature was added, which would have to be reconstructed
+            _ => continue,
+        };
+
+        if &actual != expected {
+            problems.push(format!("{} ({}) does not match the signature", part, tag));
+        }
+    }
+
+    Ok(())
+}
+
+/// Tagged digests following the `APPX` marker in the signed content
+fn appx_digests(signed_content: &[u8], digest_len: usize) -> Option<Vec<(String, Vec<u8>)>> {
+    let start = signed_content
+        .windows(APPX_DIGESTS_MAGIC.len())
+        .position(|window| window == APPX_DIGESTS_MAGIC)?;
+    let mut rest = &signed_content[start + APPX_DIGESTS_MAGIC.len()..];
+
+    let mut digests = Vec::new();
+    while rest.len() >= 4 + digest_len && rest.starts_with(b"AX") {
+        let tag = String::from_utf8_lossy(&rest[..4]).to_string();
+        digests.push((tag, rest[4..4 + digest_len].to_vec()));
+        rest = &rest[4 + digest_len..];
+    }
+
+    (!digests.is_empty()).then_some(digests)
+}
+
+/// Digest of the raw local ZIP records of every part except the signature
+fn local_records_digest(
+    context: &SignatureContext<'_>,
+    algorithm: DigestAlgorithm,
+) -> std::io::Result<Vec<u8>> {
+    let mut file = File::open(context.file_path)?;
+    let mut hasher = algorithm.hasher();
+    let mut buffer = vec![0u8; BLOCK_SIZE];
+    let entries = &context.entries.entries;
+
+    for (position, entry) in entries.iter().enumerate() {
+        if entry.name.eq_ignore_ascii_case(SIGNATURE) {
+            continue;
+        }
+
+        // A record runs up to the next record or the central directory, which
+        // includes any data descriptor after the entry data
+        let end = entries
+            .get(position + 1)
+            .map_or(context.entries.central_directory_start, |next| {
+                next.header_start
+            });
+        file.seek(SeekFrom::Start(entry.header_start))?;
+        let mut remaining = end.saturating_sub(entry.header_start);
+        while remaining > 0 {
+            let chunk = remaining.min(BLOCK_SIZE as u64) as usize;
+            file.read_exact(&mut buffer[..chunk])?;
+            hasher.update(&buffer[..chunk]);
+            remaining -= chunk as u64;
+        }
+    }
+
+    Ok(hasher.finalize())
+}
+
+/// Compare distinguished names regardless of attribute order, spacing and case
+fn distinguished_names_match(left: &str, right: &str) -> bool {
+    fn normalize(name: &str) -> Vec<String> {
+        let mut parts: Vec<String> = name
+            .split(',')
+            .map(|part| part.trim().to_lowercase().replace(" = ", "="))
+            .filter(|part| !part.is_empty())
+            .collect();
+        parts.sort();
+        parts
+    }
+
+    normalize(left) == normalize(right)
+}
+
+/// Value of an attribute within an XML tag
+fn xml_attribute(xml: &str, name: &str) -> Option<String> {
+    let pattern = format!(r#"(?:^|\s){}\s*=\s*"([^"]*)""#, regex::escape(name));
+    Regex::new(&pattern)
+        .ok()?
+        .captures(xml)
+        .map(|captures| captures[1].to_string())
+}
+
+/// Resolve the predefined XML entities
+fn unescape_xml(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Decode `%XX` escapes used in ZIP names of OPC packages
+fn percent_decode(name: &str) -> String {
+    let bytes = name.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or_default();
+            if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::{Digest, Sha256};
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    fn signed_sample() -> Option<PathBuf> {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/data/SignedSample.msix");
+        path.exists().then_some(path)
+    }
+
+    fn write_package(path: &Path, parts: &[(&str, &[u8])]) {
+        let mut writer = zip::ZipWriter::new(File::create(path).unwrap());
+        for (name, content) in parts {
+            writer
+                .start_file(*name, zip::write::FileOptions::default())
+                .unwrap();
+            writer.write_all(content).unwrap();
+        }
+        writer.finish().unwrap();
+    }
+
+    #[test]
+    fn test_signed_package_is_valid() {
+        let Some(path) = signed_sample() else {
+            println!("Skipping signed MSIX test: file not found");
+            return;
+        };
+
+        let integrity =
+            PackageIntegrity::verify(&path, Some("CN=Contoso Test, O=Contoso")).unwrap();
+        assert_eq!(integrity.signature.status, IntegrityStatus::Valid);
+        assert_eq!(integrity.signature.publisher_matches, Some(true));
+        assert_eq!(integrity.block_map.status, IntegrityStatus::Valid);
+        assert_eq!(integrity.block_map.files_verified, 3);
+        assert!(!integrity.is_tampered());
+        assert!(integrity.issues.is_empty());
+
+        let integrity = PackageIntegrity::verify(&path, Some("CN=Fabrikam")).unwrap();
+        assert_eq!(integrity.signature.status, IntegrityStatus::Invalid);
+        assert_eq!(integrity.signature.publisher_matches, Some(false));
+    }
+
+    #[test]
+    fn test_modified_package_is_tampered() {
+        let Some(path) = signed_sample() else {
+            println!("Skipping signed MSIX test: file not found");
+            return;
+        };
+
+        // Repackage with one altered file, keeping block map and signature
+        let mut archive = ZipArchive::new(File::open(&path).unwrap()).unwrap();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let tampered_path = temp_dir.path().join("tampered.msix");
+        let mut writer = zip::ZipWriter::new(File::create(&tampered_path).unwrap());
+        for index in 0..archive.len() {
+            let mut entry = archive.by_index(index).unwrap();
+            let mut content = Vec::new();
+            entry.read_to_end(&mut content).unwrap();
+            if entry.name() == "SignedSample.exe" {
+                content.extend_from_slice(b"payload");
+            }
+            writer
+                .start_file(entry.name(), zip::write::FileOptions::default())
+                .unwrap();
+            writer.write_all(&content).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let integrity = PackageIntegrity::verify(&tampered_path, None).unwrap();
+        assert!(integrity.is_tampered());
+        assert_eq!(integrity.block_map.status, IntegrityStatus::Invalid);
+        assert_eq!(integrity.signature.status, IntegrityStatus::Invalid);
+        assert!(integrity
+            .issues
+            .iter()
+            .any(|issue| issue.contains("SignedSample.exe")));
+        assert!(integrity.issues.iter().any(|issue| issue.contains("AXPC")));
+    }
+
+    #[test]
+    fn test_unsigned_block_map_checks() {
+        let content = b"hello world";
+        let hash = base64::engine::general_purpose::STANDARD.encode(Sha256::digest(content));
+        let block_map = format!(
+            r#"<BlockMap HashMethod="http://www.w3.org/2001/04/xmlenc#sha256"><File Name="Assets\app.txt" Size="11" LfhSize="44"><Block Hash="{}"/></File><File Name="gone.dll" Size="0" LfhSize="38"/></BlockMap>"#,
+            hash
+        );
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("unsigned.msix");
+        write_package(
+            &path,
+            &[
+                ("Assets/app.txt", content),
+                ("extra%20file.txt", b"injected"),
+                (BLOCK_MAP, block_map.as_bytes()),
+            ],
+        );
+
+        let integrity = PackageIntegrity::verify(&path, None).unwrap();
+        assert_eq!(integrity.signature.status, IntegrityStatus::Missing);
+        assert_eq!(integrity.block_map.status, IntegrityStatus::Invalid);
+        assert_eq!(integrity.block_map.files_verified, 1);
+        assert_eq!(
+            integrity.issues,
+            vec![
+                "gone.dll is listed in the block map but missing from the package",
+                "extra file.txt is not covered by the block map",
+            ]
+        );
+
+        let mut properties = HashMap::new();
+        integrity.insert_properties(&mut properties);
+        assert_eq!(properties["msix_tampered"], "true");
+        assert_eq!(properties["msix_signature_status"], "missing");
+    }
+}
@@ -1,8 +1,16 @@
 //! MSIX/AppX format analyzer for modern Windows applications
 
 pub mod analyzer;
+pub mod capabilities;
+pub mod integrity;
 pub mod parser;
 
 // Re-export main components
 pub use analyzer::MsixAnalyzer;
-pub use parser::{AppxCapability, AppxDependency, AppxManifest, MsixParser};
+pub use capabilities::{
+    assess_capabilities, assess_capability, CapabilityAssessment, CapabilityRisk,
+};
+pub use integrity::{IntegrityStatus, PackageIntegrity};
+pub use parser::{
+    AppxCapability, AppxDependency, AppxManifest, AppxTargetDeviceFamily, MsixParser,
+};
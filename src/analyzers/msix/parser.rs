@@ -1,7 +1,11 @@
 //! MSIX/AppX data structure parser
 
+use super::capabilities::{assess_capabilities, CapabilityRisk};
+use super::integrity::PackageIntegrity;
 use crate::analyzers::archive::{ArchiveFormat, ArchiveParser};
-use crate::core::{AnalyzerError, FileEntry, Result};
+use crate::analyzers::common::EntryReader;
+use crate::core::{AnalyzerError, FileEntry, MsixDetails, Result};
+use regex::Regex;
 use std::collections::HashMap;
 use std::io::Read;
 use std::path::Path;
@@ -16,6 +20,14 @@ pub struct AppxDependency {
     pub max_version_tested: Option<String>,
 }
 
+/// MSIX/AppX target device family (`<TargetDeviceFamily>`)
+#[derive(Debug, Clone)]
+pub struct AppxTargetDeviceFamily {
+    pub name: String,
+    pub min_version: Option<String>,
+    pub max_version_tested: Option<String>,
+}
+
 /// MSIX/AppX capability information
 #[derive(Debug, Clone)]
 pub struct AppxCapability {
@@ -37,11 +49,42 @@ pub struct AppxManifest {
     pub background_color: Option<String>,
     pub min_version: Option<String>,
     pub max_version_tested: Option<String>,
+    pub target_device_families: Vec<AppxTargetDeviceFamily>,
     pub dependencies: Vec<AppxDependency>,
     pub capabilities: Vec<AppxCapability>,
     pub applications: Vec<String>, // Application IDs
 }
 
+impl AppxManifest {
+    /// Typed identity and requirements for `InstallerMetadata::format_details`
+    pub fn details(&self) -> MsixDetails {
+        MsixDetails {
+            identity_name: self.identity_name.clone(),
+            identity_publisher: self.identity_publisher.clone(),
+            identity_version: self.identity_version.clone(),
+            processor_architecture: self.identity_processor_architecture.clone(),
+            min_version: self.min_version.clone(),
+            max_version_tested: self.max_version_tested.clone(),
+            target_device_families: self
+                .target_device_families
+                .iter()
+                .map(|family| family.name.clone())
+                .collect(),
+            capabilities: self
+                .capabilities
+                .iter()
+                .map(|capability| capability.name.clone())
+                .collect(),
+            dependencies: self
+                .dependencies
+                .iter()
+                .map(|dependency| dependency.name.clone())
+                .collect(),
+            applications: self.applications.clone(),
+        }
+    }
+}
+
 /// MSIX/AppX data parser
 pub struct MsixParser {
     archive_parser: ArchiveParser,
@@ -100,7 +143,7 @@ impl MsixParser {
     }
 
     /// Parse AppxManifest.xml content (simplified XML parsing)
-    fn parse_manifest_content(&self, content: &str) -> Result<AppxManifest> {
+    pub fn parse_manifest_content(&self, content: &str) -> Result<AppxManifest> {
         // This is a simplified XML parser for demonstration
         // In a production environment, you'd want to use a proper XML parser like quick-xml
 
@@ -116,6 +159,7 @@ impl MsixParser {
             background_color: None,
             min_version: None,
             max_version_tested: None,
+            target_device_families: Vec::new(),
             dependencies: Vec::new(),
             capabilities: Vec::new(),
             applications: Vec::new(),
@@ -157,6 +201,33 @@ impl MsixParser {
             }
         }
 
+        // Extract target device families; the first one sets the package OS range
+        let mut search_pos = 0;
+        while let Some(family_start) = content[search_pos..].find("<TargetDeviceFamily") {
+            let abs_start = search_pos + family_start;
+            let Some(family_end) = content[abs_start..].find('>') else {
+                break;
+            };
+            let family_section = &content[abs_start..abs_start + family_end];
+
+            if let Some(name) = self.extract_xml_attribute(family_section, "Name") {
+                manifest
+                    .target_device_families
+                    .push(AppxTargetDeviceFamily {
+                        name,
+                        min_version: self.extract_xml_attribute(family_section, "MinVersion"),
+                        max_version_tested: self
+                            .extract_xml_attribute(family_section, "MaxVersionTested"),
+                    });
+            }
+
+            search_pos = abs_start + family_end;
+        }
+        if let Some(family) = manifest.target_device_families.first() {
+            manifest.min_version = family.min_version.clone();
+            manifest.max_version_tested = family.max_version_tested.clone();
+        }
+
         // Extract Dependencies (simplified)
         let mut search_pos = 0;
         while let Some(dep_start) = content[search_pos..].find("<PackageDependency") {
@@ -181,29 +252,20 @@ impl MsixParser {
             }
         }
 
-        // Extract Capabilities (simplified)
-        let capability_patterns = ["<Capability", "<DeviceCapability", "<RestrictedCapability"];
-
-        for pattern in &capability_patterns {
-            search_pos = 0;
-            while let Some(cap_start) = content[search_pos..].find(pattern) {
-                let abs_start = search_pos + cap_start;
-                if let Some(cap_end) = content[abs_start..].find("/>") {
-                    let cap_section = &content[abs_start..abs_start + cap_end];
-
-                    if let Some(name) = self.extract_xml_attribute(cap_section, "Name") {
-                        let capability = AppxCapability {
-                            name,
-                            capability_type: pattern.trim_start_matches('<').to_string(),
-                        };
-                        manifest.capabilities.push(capability);
-                    }
-
-                    search_pos = abs_start + cap_end;
-                } else {
-                    break;
-                }
-            }
+        // Extract Capabilities, including namespaced ones such as `rescap:Capability`
+        let capability_pattern = Regex::new(
+            r#"<(?:(\w+):)?(Capability|DeviceCapability|CustomCapability)\s[^>]*?Name="([^"]+)""#,
+        )
+        .expect("valid capability pattern");
+        for captures in capability_pattern.captures_iter(content) {
+            let capability_type = match captures.get(1).map(|m| m.as_str()) {
+                Some("rescap") => "RestrictedCapability".to_string(),
+                _ => captures[2].to_string(),
+            };
+            manifest.capabilities.push(AppxCapability {
+                name: captures[3].to_string(),
+                capability_type,
+            });
         }
 
         Ok(manifest)
@@ -241,6 +303,26 @@ impl MsixParser {
         self.parse_manifest_content(&content)
     }
 
+    /// Read the content of a file packaged in the MSIX package
+    pub async fn read_file_content(
+        &self,
+        file_path: &Path,
+        entry_path: &Path,
+    ) -> Result<Option<Vec<u8>>> {
+        self.archive_parser
+            .read_file_content(file_path, entry_path)
+            .await
+    }
+
+    /// Open a file packaged in the package for reading
+    pub async fn open_file(
+        &self,
+        file_path: &Path,
+        entry_path: &Path,
+    ) -> Result<Option<EntryReader>> {
+        self.archive_parser.open_file(file_path, entry_path).await
+    }
+
     /// Extract files from MSIX/AppX using archive parser
     pub async fn extract_files(&self, file_path: &Path) -> Result<Vec<FileEntry>> {
         self.archive_parser.extract_files(file_path).await
@@ -286,6 +368,29 @@ impl MsixParser {
                 if let Some(min_version) = manifest.min_version {
                     properties.insert("msix_min_version".to_string(), min_version);
                 }
+                if let Some(max_version_tested) = manifest.max_version_tested {
+                    properties.insert("msix_max_version_tested".to_string(), max_version_tested);
+                }
+                if !manifest.target_device_families.is_empty() {
+                    let families: Vec<String> = manifest
+                        .target_device_families
+                        .iter()
+                        .map(|family| {
+                            let mut entry = family.name.clone();
+                            if let Some(min_version) = &family.min_version {
+                                entry.push_str(&format!(" {}", min_version));
+                            }
+                            if let Some(max_version_tested) = &family.max_version_tested {
+                                entry.push_str(&format!(" (tested {})", max_version_tested));
+                            }
+                            entry
+                        })
+                        .collect();
+                    properties.insert(
+                        "msix_target_device_families".to_string(),
+                        families.join(", "),
+                    );
+                }
 
                 properties.insert(
                     "msix_dependencies_count".to_string(),
@@ -295,6 +400,33 @@ impl MsixParser {
                     "msix_capabilities_count".to_string(),
                     manifest.capabilities.len().to_string(),
                 );
+                if !manifest.capabilities.is_empty() {
+                    let capabilities = manifest
+                        .capabilities
+                        .iter()
+                        .map(|capability| capability.name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ");
+
+                    let assessments = assess_capabilities(&capabilities);
+                    if let Some(highest) = assessments.first() {
+                        properties
+                            .insert("msix_capability_risk".to_string(), highest.risk.to_string());
+                    }
+                    let high_risk: Vec<&str> = assessments
+                        .iter()
+                        .filter(|assessment| assessment.risk == CapabilityRisk::High)
+                        .map(|assessment| assessment.name.as_str())
+                        .collect();
+                    if !high_risk.is_empty() {
+                        properties.insert(
+                            "msix_high_risk_capabilities".to_string(),
+                            high_risk.join(", "),
+                        );
+                    }
+
+                    properties.insert("msix_capabilities".to_string(), capabilities);
+                }
                 properties.insert(
                     "msix_applications_count".to_string(),
                     manifest.applications.len().to_string(),
@@ -306,6 +438,16 @@ impl MsixParser {
             }
         }
 
+        // Verify the block map and package signature
+        let identity_publisher = properties.get("msix_identity_publisher").cloned();
+        match PackageIntegrity::verify(file_path, identity_publisher.as_deref()) {
+            Ok(integrity) => integrity.insert_properties(&mut properties),
+            Err(e) => {
+                tracing::warn!("Failed to verify MSIX package integrity: {}", e);
+                properties.insert("msix_integrity_error".to_string(), e.to_string());
+            }
+        }
+
         properties.insert("package_type".to_string(), "MSIX/AppX Package".to_string());
 
         Ok(properties)
@@ -0,0 +1,647 @@
+//! Installers split across several files
+//!
+//! Large installers ship their payload in files next to the setup program:
+//! InstallShield `data1.cab`/`data1.hdr` and the package of Basic MSI
+//! projects, InnoSetup disk slices (`setup-1.bin`) and the external cabinets
+//! an MSI package lists in its Media table. These siblings are found in the
+//! directory of the installer. The file lists of Microsoft cabinets and
+//! sibling packages are read, and every file is attributed to the part it
+//! is stored in through [`FileEntry::source`].
+
+use crate::analyzers::{common, InstallerAnalyzer, MsiAnalyzer};
+use crate::core::{AnalysisResult, FileAttributes, FileEntry, InstallerFormat, Result};
+use crate::utils::format_file_size;
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Largest cabinet header and file table that is read
+const MAX_CABINET_TABLE: u64 = 16 * 1024 * 1024;
+/// Parts listed in analysis properties
+const MAX_LISTED_PARTS: usize = 20;
+
+/// `iFolder` of cabinet files continued from the previous cabinet
+const CONTINUED_FROM_PREV: u16 = 0xfffd;
+/// `iFolder` of cabinet files continued from the previous and to the next cabinet
+const CONTINUED_PREV_AND_NEXT: u16 = 0xffff;
+
+/// Kind of a sibling file of an installer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartKind {
+    /// Microsoft cabinet
+    Cabinet,
+    /// InstallShield cabinet (`ISc(` signature)
+    InstallShieldCabinet,
+    /// InstallShield cabinet header, holding the file table of the cabinets
+    InstallShieldHeader,
+    /// InnoSetup disk slice
+    DiskSlice,
+    /// Windows Installer package
+    Package,
+}
+
+impl PartKind {
+    /// Value used in analysis properties
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Cabinet => "cab",
+            Self::InstallShieldCabinet => "InstallShield cab",
+            Self::InstallShieldHeader => "InstallShield header",
+            Self::DiskSlice => "disk slice",
+            Self::Package => "msi",
+        }
+    }
+}
+
+impl fmt::Display for PartKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Sibling file holding part of an installer
+#[derive(Debug, Clone)]
+pub struct InstallerPart {
+    /// File name, as found in the directory
+    pub name: String,
+    pub path: PathBuf,
+    pub size: u64,
+    pub kind: PartKind,
+    /// Files stored in the part that the installer itself does not list
+    pub files: Vec<FileEntry>,
+    /// Files of the installer's own listing that are stored in the part
+    pub attributed_files: usize,
+    pub error: Option<String>,
+}
+
+impl InstallerPart {
+    async fn new(directory: &Path, name: &str, kind: PartKind) -> Result<Self> {
+        let path = directory.join(name);
+        Ok(Self {
+            name: name.to_string(),
+            size: common::get_file_size(&path).await?,
+            path,
+            kind,
+            files: Vec::new(),
+            attributed_files: 0,
+            error: None,
+        })
+    }
+
+    fn describe(&self) -> String {
+        let mut description = format!(
+            "{} ({}, {}",
+            self.name,
+            self.kind,
+            format_file_size(self.size)
+        );
+        let file_count = self.files.len() + self.attributed_files;
+        if file_count > 0 {
+            description.push_str(&format!(", {} files", file_count));
+        }
+        if let Some(error) = &self.error {
+            description.push_str(&format!(", {}", error));
+        }
+        description.push(')');
+        description
+    }
+}
+
+/// Sibling parts of an installer and the files stored in them
+#[derive(Debug, Clone, Default)]
+pub struct MultiPartReport {
+    pub parts: Vec<InstallerPart>,
+    /// Parts the installer refers to that are not in its directory
+    pub missing: Vec<String>,
+}
+
+impl MultiPartReport {
+    /// Find the parts of an installer of `format` next to `file_path`
+    ///
+    /// `result` is the analysis of the installer itself, whose files may
+    /// already be attributed to external cabinets.
+    pub async fn detect(
+        format: &InstallerFormat,
+        file_path: &Path,
+        result: &AnalysisResult,
+    ) -> Result<Self> {
+        let Some(directory) = file_path.parent() else {
+            return Ok(Self::default());
+        };
+        let directory = if directory.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            directory
+        };
+        let own_name = file_path.file_name().and_then(|name| name.to_str());
+        let mut siblings: Vec<String> = Vec::new();
+        let mut entries = tokio::fs::read_dir(directory).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            if !entry.file_type().await?.is_file() {
+                continue;
+            }
+            if let Some(name) = entry.file_name().to_str() {
+                if Some(name) != own_name {
+                    siblings.push(name.to_string());
+                }
+            }
+        }
+        siblings.sort();
+
+        // Cabinets the installer's own file listing is attributed to
+        let mut referenced: Vec<String> = Vec::new();
+        for source in result.files.iter().filter_map(|file| file.source.as_ref()) {
+            if !referenced.contains(source) {
+                referenced.push(source.clone());
+            }
+        }
+
+        let mut report = Self::default();
+        match format {
+            InstallerFormat::MSI | InstallerFormat::WiX => {
+                for cabinet in &referenced {
+                    match find_sibling(&siblings, cabinet) {
+                        Some(name) => {
+                            let mut part =
+                                InstallerPart::new(directory, name, PartKind::Cabinet).await?;
+                            part.attributed_files = count_attributed(result, cabinet);
+                            report.parts.push(part);
+                        }
+                        None => report.missing.push(cabinet.clone()),
+                    }
+                }
+            }
+            InstallerFormat::InstallShield => {
+                report.add_installshield_parts(directory, &siblings).await?;
+            }
+            InstallerFormat::InnoSetup => {
+                let stem = file_path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .unwrap_or_default();
+                for name in siblings.iter().filter(|name| is_disk_slice(stem, name)) {
+                    let mut part = InstallerPart::new(directory, name, PartKind::DiskSlice).await?;
+                    part.attributed_files = count_attributed(result, name);
+                    report.parts.push(part);
+                }
+            }
+            _ => {}
+        }
+
+        Ok(report)
+    }
+
+    /// InstallShield cabinets, their headers and the packages of Basic MSI projects
+    async fn add_installshield_parts(
+        &mut self,
+        directory: &Path,
+        siblings: &[String],
+    ) -> Result<()> {
+        // Other files in e.g. a download folder are not part of the installer
+        let setup_ini = find_sibling(siblings, "setup.ini");
+        if setup_ini.is_none() && !siblings.iter().any(|name| is_installshield_data(name)) {
+            return Ok(());
+        }
+        let package = match setup_ini {
+            Some(name) => {
+                let ini = tokio::fs::read(directory.join(name)).await?;
+                // Often saved as UTF-16
+                ini_value(
+                    &String::from_utf8_lossy(&ini).replace('\0', ""),
+                    "PackageName",
+                )
+            }
+            None => None,
+        };
+
+        for name in siblings {
+            let extension = Path::new(name)
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .map(|extension| extension.to_lowercase());
+            let kind = match extension.as_deref() {
+                Some("msi")
+                    if package
+                        .as_deref()
+                        .is_none_or(|package| package.eq_ignore_ascii_case(name)) =>
+                {
+                    PartKind::Package
+                }
+                Some("hdr") => PartKind::InstallShieldHeader,
+                Some("cab") => {
+                    let header = common::read_file_header(&directory.join(name), 4).await?;
+                    if header.starts_with(b"ISc(") {
+                        PartKind::InstallShieldCabinet
+                    } else if header.starts_with(b"MSCF") {
+                        PartKind::Cabinet
+                    } else {
+                        continue;
+                    }
+                }
+                _ => continue,
+            };
+            self.parts
+                .push(InstallerPart::new(directory, name, kind).await?);
+        }
+
+        // Packages list the files of the cabinets they use themselves
+        let mut packaged: Vec<String> = Vec::new();
+        for part in self.parts.iter_mut() {
+            if part.kind != PartKind::Package {
+                continue;
+            }
+            match MsiAnalyzer::new().extract_files(&part.path).await {
+                Ok(files) => {
+                    for mut file in files {
+                        match &file.source {
+                            Some(cabinet) => packaged.push(cabinet.to_lowercase()),
+                            None => file.source = Some(part.name.clone()),
+                        }
+                        part.files.push(file);
+                    }
+                }
+                Err(e) => part.error = Some(e.to_string()),
+            }
+        }
+
+        let packaged_counts: Vec<usize> = self
+            .parts
+            .iter()
+            .map(|part| packaged_files(&self.parts, &part.name))
+            .collect();
+        for (part, packaged_count) in self.parts.iter_mut().zip(packaged_counts) {
+            if part.kind != PartKind::Cabinet {
+                continue;
+            }
+            if packaged.contains(&part.name.to_lowercase()) {
+                part.attributed_files = packaged_count;
+                continue;
+            }
+            match list_cabinet(&part.path, part.size).await {
+                Ok(files) => {
+                    part.files = files
+                        .into_iter()
+                        .map(|file| FileEntry {
+                            source: Some(part.name.clone()),
+                            ..file
+                        })
+                        .collect()
+                }
+                Err(e) => part.error = Some(e.to_string()),
+            }
+        }
+        Ok(())
+    }
+
+    /// Record the parts as analysis properties and add the files stored in them
+    pub fn insert_properties(&self, result: &mut AnalysisResult) {
+        if self.parts.is_empty() && self.missing.is_empty() {
+            return;
+        }
+
+        let properties = &mut result.metadata.properties;
+        if !self.parts.is_empty() {
+            let descriptions: Vec<String> = self
+                .parts
+                .iter()
+                .take(MAX_LISTED_PARTS)
+                .map(InstallerPart::describe)
+                .collect();
+            properties.insert(
+                "installer_part_count".to_string(),
+                self.parts.len().to_string(),
+            );
+            properties.insert("installer_parts".to_string(), descriptions.join("; "));
+            let total: u64 =
+                result.metadata.file_size + self.parts.iter().map(|part| part.size).sum::<u64>();
+            properties.insert("installer_total_size".to_string(), format_file_size(total));
+        }
+        if !self.missing.is_empty() {
+            properties.insert(
+                "installer_missing_parts".to_string(),
+                self.missing.join(", "),
+            );
+        }
+
+        for part in &self.parts {
+            result.files.extend(part.files.iter().cloned());
+        }
+    }
+}
+
+/// Sibling named `name`, compared without case as Windows does
+fn find_sibling<'a>(siblings: &'a [String], name: &str) -> Option<&'a str> {
+    siblings
+        .iter()
+        .find(|sibling| sibling.eq_ignore_ascii_case(name))
+        .map(String::as_str)
+}
+
+/// Files of `result` stored in the cabinet `name`
+fn count_attributed(result: &AnalysisResult, name: &str) -> usize {
+    result
+        .files
+        .iter()
+        .filter(|file| file.source.as_deref() == Some(name))
+        .count()
+}
+
+/// Files of sibling packages stored in the cabinet `name`
+fn packaged_files(parts: &[InstallerPart], name: &str) -> usize {
+    parts
+        .iter()
+        .flat_map(|part| &part.files)
+        .filter(|file| {
+            file.source
+                .as_deref()
+                .is_some_and(|source| source.eq_ignore_ascii_case(name))
+        })
+        .count()
+}
+
+/// `data<n>.cab` or `data<n>.hdr`, the payload of InstallScript projects
+fn is_installshield_data(name: &str) -> bool {
+    let name = name.to_lowercase();
+    name.strip_prefix("data")
+        .and_then(|rest| {
+            rest.strip_suffix(".cab")
+                .or_else(|| rest.strip_suffix(".hdr"))
+        })
+        .is_some_and(|number| !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Value of `key` in an INI file, from any section
+fn ini_value(ini: &str, key: &str) -> Option<String> {
+    ini.lines().find_map(|line| {
+        let (name, value) = line.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case(key)
+            .then(|| value.trim().to_string())
+    })
+}
+
+/// `<stem>-<n>.bin` or `<stem>-<n><letter>.bin`, the slices of an InnoSetup installer
+fn is_disk_slice(stem: &str, name: &str) -> bool {
+    let name = name.to_lowercase();
+    let Some(slice) = name
+        .strip_prefix(&stem.to_lowercase())
+        .and_then(|rest| rest.strip_prefix('-'))
+        .and_then(|rest| rest.strip_suffix(".bin"))
+    else {
+        return false;
+    };
+    let number = slice.trim_end_matches(|c: char| c.is_ascii_lowercase());
+    !number.is_empty()
+        && number.chars().all(|c| c.is_ascii_digit())
+        && slice.len() - number.len() <= 1
+}
+
+/// Files of a Microsoft cabinet, read from its file table without decompressing
+async fn list_cabinet(path: &Path, size: u64) -> Result<Vec<FileEntry>> {
+    let data =
+        common::read_file_content_range(path, 0, size.min(MAX_CABINET_TABLE) as usize).await?;
+    parse_cabinet_files(&data)
+}
+
+/// Parse the CFFILE entries of a cabinet
+pub(crate) fn parse_cabinet_files(data: &[u8]) -> Result<Vec<FileEntry>> {
+    if data.len() < 36 || !data.starts_with(b"MSCF") {
+        return Err(crate::core::AnalyzerError::parse_error(
+            "Not a Microsoft cabinet",
+        ));
+    }
+    let files_offset = read_u32(data, 16) as usize;
+    let file_count = read_u16(data, 28) as usize;
+
+    let mut files = Vec::new();
+    let mut position = files_offset;
+    for _ in 0..file_count {
+        let Some(entry) = data.get(position..position + 16) else {
+            return Err(crate::core::AnalyzerError::parse_error(
+                "Cabinet file table is truncated",
+            ));
+        };
+        let size = read_u32(entry, 0) as u64;
+        let folder = read_u16(entry, 8);
+        let date = read_u16(entry, 10);
+        let time = read_u16(entry, 12);
+        let attributes = read_u16(entry, 14);
+
+        let name_start = position + 16;
+        let Some(name_length) = data[name_start..].iter().position(|&b| b == 0) else {
+            return Err(crate::core::AnalyzerError::parse_error(
+                "Cabinet file name is truncated",
+            ));
+        };
+        let name = String::from_utf8_lossy(&data[name_start..name_start + name_length]);
+        position = name_start + name_length + 1;
+
+        // Files spanning cabinets are listed by the cabinet they start in
+        if matches!(folder, CONTINUED_FROM_PREV | CONTINUED_PREV_AND_NEXT) {
+            continue;
+        }
+        let lower = name.to_lowercase();
+        files.push(FileEntry {
+            path: PathBuf::from(name.as_ref()),
+            target_path: None,
+            size,
+            hash: None,
+            attributes: FileAttributes {
+                readonly: attributes & 0x01 != 0,
+                hidden: attributes & 0x02 != 0,
+                system: attributes & 0x04 != 0,
+                executable: lower.ends_with(".exe") || lower.ends_with(".dll"),
+            },
+            compression: Some("CAB".to_string()),
+            is_speculative: false,
+            architecture: None,
+            modified: dos_time(date, time),
+            source: None,
+        });
+    }
+
+    Ok(files)
+}
+
+/// DOS date and time of a cabinet entry, taken as UTC
+fn dos_time(date: u16, time: u16) -> Option<DateTime<Utc>> {
+    let naive = NaiveDate::from_ymd_opt(
+        1980 + (date >> 9) as i32,
+        ((date >> 5) & 0x0f) as u32,
+        (date & 0x1f) as u32,
+    )?
+    .and_hms_opt(
+        (time >> 11) as u32,
+        ((time >> 5) & 0x3f) as u32,
+        (time & 0x1f) as u32 * 2,
+    )?;
+    Some(Utc.from_utc_datetime(&naive))
+}
+
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::InstallerMetadata;
+
+    /// Cabinet header and file table, without folders or data
+    fn cabinet(files: &[(&str, u32, u16)]) -> Vec<u8> {
+        let mut cabinet = b"MSCF".to_vec();
+        cabinet.extend(0u32.to_le_bytes());
+        cabinet.extend(0u32.to_le_bytes());
+        cabinet.extend(0u32.to_le_bytes());
+        cabinet.extend(44u32.to_le_bytes());
+        cabinet.extend(0u32.to_le_bytes());
+        cabinet.extend([3, 1]);
+        cabinet.extend(1u16.to_le_bytes());
+        cabinet.extend((files.len() as u16).to_le_bytes());
+        cabinet.extend([0u8; 6]);
+        cabinet.extend([0u8; 8]);
+        for (name, size, folder) in files {
+            cabinet.extend(size.to_le_bytes());
+            cabinet.extend(0u32.to_le_bytes());
+            cabinet.extend(folder.to_le_bytes());
+            // 2024-05-17 10:30:00
+            cabinet.extend(((44u16 << 9) | (5 << 5) | 17).to_le_bytes());
+            cabinet.extend(((10u16 << 11) | (30 << 5)).to_le_bytes());
+            cabinet.extend(0x20u16.to_le_bytes());
+            cabinet.extend(name.as_bytes());
+            cabinet.push(0);
+        }
+        cabinet
+    }
+
+    fn result(format: InstallerFormat, files: Vec<FileEntry>) -> AnalysisResult {
+        AnalysisResult {
+            session_id: uuid::Uuid::new_v4(),
+            metadata: InstallerMetadata {
+                format,
+                file_size: 1024,
+                created_at: Utc::now(),
+                ..Default::default()
+            },
+            files,
+            analyzed_at: Utc::now(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_cabinet_files() {
+        let files = parse_cabinet_files(&cabinet(&[
+            ("app.exe", 4096, 0),
+            ("tail.dat", 10, CONTINUED_FROM_PREV),
+            ("docs\\readme.txt", 12, 0),
+        ]))
+        .unwrap();
+
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].size, 4096);
+        assert!(files[0].attributes.executable);
+        assert_eq!(files[1].path, PathBuf::from("docs\\readme.txt"));
+        assert_eq!(
+            files[1].modified,
+            Some(Utc.with_ymd_and_hms(2024, 5, 17, 10, 30, 0).unwrap())
+        );
+        assert!(parse_cabinet_files(b"ISc(").is_err());
+    }
+
+    #[test]
+    fn test_disk_slice_names() {
+        assert!(is_disk_slice("mysetup", "mysetup-1.bin"));
+        assert!(is_disk_slice("MySetup", "mysetup-12b.BIN"));
+        assert!(!is_disk_slice("mysetup", "mysetup-.bin"));
+        assert!(!is_disk_slice("mysetup", "mysetup-1ab.bin"));
+        assert!(!is_disk_slice("mysetup", "other-1.bin"));
+        assert!(is_installshield_data("Data12.CAB"));
+        assert!(!is_installshield_data("database.cab"));
+        assert_eq!(
+            ini_value("[Startup]\r\nPackageName=Product.msi\r\n", "packagename").as_deref(),
+            Some("Product.msi")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_detect_installer_parts() {
+        let dir = tempfile::tempdir().unwrap();
+        let setup = dir.path().join("setup.exe");
+        std::fs::write(&setup, b"MZ").unwrap();
+        std::fs::write(
+            dir.path().join("Data1.cab"),
+            cabinet(&[("app.exe", 4096, 0), ("app.dll", 2048, 0)]),
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("data2.cab"), b"ISc(\x0c\x00\x00\x01").unwrap();
+        std::fs::write(dir.path().join("data1.hdr"), b"ISc(").unwrap();
+        std::fs::write(dir.path().join("readme.txt"), b"notes").unwrap();
+
+        let mut installshield = result(InstallerFormat::InstallShield, Vec::new());
+        let report =
+            MultiPartReport::detect(&InstallerFormat::InstallShield, &setup, &installshield)
+                .await
+                .unwrap();
+        let kinds: Vec<(&str, PartKind)> = report
+            .parts
+            .iter()
+            .map(|part| (part.name.as_str(), part.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("Data1.cab", PartKind::Cabinet),
+                ("data1.hdr", PartKind::InstallShieldHeader),
+                ("data2.cab", PartKind::InstallShieldCabinet),
+            ]
+        );
+
+        report.insert_properties(&mut installshield);
+        assert_eq!(installshield.files.len(), 2);
+        assert_eq!(installshield.files[0].source.as_deref(), Some("Data1.cab"));
+        let properties = &installshield.metadata.properties;
+        assert_eq!(properties["installer_part_count"], "3");
+        assert!(properties["installer_parts"].starts_with("Data1.cab (cab, "));
+        assert!(properties["installer_parts"].contains("2 files"));
+
+        // Unrelated cabinets and packages in a folder without InstallShield data
+        let downloads = tempfile::tempdir().unwrap();
+        let other_setup = downloads.path().join("setup.exe");
+        std::fs::write(&other_setup, b"MZ").unwrap();
+        std::fs::write(downloads.path().join("tool.msi"), b"").unwrap();
+        let report = MultiPartReport::detect(
+            &InstallerFormat::InstallShield,
+            &other_setup,
+            &result(InstallerFormat::InstallShield, Vec::new()),
+        )
+        .await
+        .unwrap();
+        assert!(report.parts.is_empty());
+
+        // MSI files attributed to external cabinets by the Media table
+        let package = dir.path().join("product.msi");
+        std::fs::write(&package, b"").unwrap();
+        let mut listed = parse_cabinet_files(&cabinet(&[("a", 1, 0), ("b", 1, 0)])).unwrap();
+        listed[0].source = Some("data1.cab".to_string());
+        listed[1].source = Some("Data3.cab".to_string());
+        let msi = result(InstallerFormat::MSI, listed);
+        let report = MultiPartReport::detect(&InstallerFormat::MSI, &package, &msi)
+            .await
+            .unwrap();
+        assert_eq!(report.parts.len(), 1);
+        assert_eq!(report.parts[0].name, "Data1.cab");
+        assert_eq!(report.parts[0].attributed_files, 1);
+        assert!(report.parts[0].files.is_empty());
+        assert_eq!(report.missing, vec!["Data3.cab"]);
+    }
+}
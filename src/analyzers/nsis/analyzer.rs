@@ -1,12 +1,23 @@
 //! NSIS analyzer implementation
 
 use super::parser::NsisParser;
-use crate::analyzers::{common, InstallerAnalyzer};
-use crate::core::{FileEntry, InstallerFormat, InstallerMetadata, RegistryOperation, Result};
+use crate::analyzers::{common, DetectionContext, InstallerAnalyzer};
+use crate::core::{
+    AnalyzerCapabilities, FileEntry, InstallerFormat, InstallerMetadata, RegistryOperation, Result,
+    SupportLevel,
+};
 use async_trait::async_trait;
 use chrono::Utc;
 use std::path::Path;
 
+/// NSIS-specific strings searched for during detection
+pub(crate) const NSIS_PATTERNS: [&str; 4] = [
+    "Nullsoft.NSIS.exehead",
+    "NullsoftInst",
+    "NSIS Error",
+    "Nullsoft Install System",
+];
+
 /// NSIS installer analyzer
 pub struct NsisAnalyzer {
     parser: NsisParser,
@@ -20,28 +31,20 @@ impl NsisAnalyzer {
         }
     }
 
-    /// Check if file contains NSIS signatures
-    async fn has_nsis_signature(file_path: &Path) -> Result<bool> {
+    /// Check if the file under detection contains NSIS signatures
+    async fn has_nsis_signature(context: &DetectionContext) -> Result<bool> {
         // First check if it's a PE file
-        if !common::is_pe_file(file_path).await? {
+        if !context.is_pe() {
             return Ok(false);
         }
 
-        // Search for NSIS-specific patterns
-        let nsis_patterns = [
-            "Nullsoft.NSIS.exehead",
-            "NullsoftInst",
-            "NSIS Error",
-            "Nullsoft Install System",
-        ];
-
-        let matches = common::search_file_content(file_path, &nsis_patterns).await?;
+        let matches = context.find_patterns(&NSIS_PATTERNS).await?;
         let has_nsis = !matches.is_empty();
 
         if has_nsis {
             tracing::info!(
                 "NSIS signatures found in {}: {:?}",
-                file_path.display(),
+                context.path().display(),
                 matches
             );
         }
@@ -109,6 +112,8 @@ impl NsisAnalyzer {
             file_hash,
             created_at: Utc::now(),
             properties,
+            provenance: enhanced_metadata.provenance,
+            format_details: None,
         })
     }
 
@@ -147,26 +152,41 @@ impl NsisAnalyzer {
 #[async_trait]
 impl InstallerAnalyzer for NsisAnalyzer {
     async fn can_analyze(&self, file_path: &Path) -> Result<bool> {
+        self.can_analyze_with(&DetectionContext::new(file_path).await?)
+            .await
+    }
+
+    async fn can_analyze_with(&self, context: &DetectionContext) -> Result<bool> {
         // Check file extension first for quick filtering
-        if let Some(ext) = file_path.extension() {
-            if ext.to_str() != Some("exe") {
-                return Ok(false);
-            }
-        } else {
+        if !context
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("exe"))
+        {
             return Ok(false);
         }
 
-        // Validate file accessibility
-        common::validate_file(file_path).await?;
-
         // Check for NSIS signatures
-        Self::has_nsis_signature(file_path).await
+        Self::has_nsis_signature(context).await
+    }
+
+    fn detection_patterns(&self) -> Vec<&'static str> {
+        NSIS_PATTERNS.to_vec()
     }
 
     fn format(&self) -> InstallerFormat {
         InstallerFormat::NSIS
     }
 
+    fn capabilities(&self) -> AnalyzerCapabilities {
+        AnalyzerCapabilities {
+            metadata: SupportLevel::Partial,
+            file_extraction: SupportLevel::Heuristic,
+            registry_operations: SupportLevel::Heuristic,
+            install_scripts: SupportLevel::Unsupported,
+            signatures: SupportLevel::Unsupported,
+        }
+    }
+
     async fn extract_metadata(&self, file_path: &Path) -> Result<InstallerMetadata> {
         // Validate file first
         common::validate_file(file_path).await?;
@@ -116,6 +116,10 @@ impl NsisParser {
                     executable: true,
                 },
                 compression: Some("NSIS".to_string()),
+                is_speculative: false,
+                architecture: None,
+                modified: None,
+                source: None,
             });
         }
 
@@ -150,6 +154,10 @@ impl NsisParser {
                     executable: *executable,
                 },
                 compression: Some("NSIS".to_string()),
+                is_speculative: true,
+                architecture: None,
+                modified: None,
+                source: None,
             });
         }
 
@@ -168,6 +176,7 @@ impl NsisParser {
         operations.push(RegistryOperation::CreateKey {
             key_path: "HKEY_LOCAL_MACHINE\\SOFTWARE\\MyApp".to_string(),
             timestamp: now,
+            process_id: None,
         });
 
         operations.push(RegistryOperation::SetValue {
@@ -176,6 +185,7 @@ impl NsisParser {
             value_type: RegistryValueType::String,
             value_data: RegistryValue::String("$INSTDIR".to_string()),
             timestamp: now,
+            process_id: None,
         });
 
         operations.push(RegistryOperation::SetValue {
@@ -186,6 +196,7 @@ impl NsisParser {
             value_type: RegistryValueType::String,
             value_data: RegistryValue::String("My Application".to_string()),
             timestamp: now,
+            process_id: None,
         });
 
         Ok(operations)
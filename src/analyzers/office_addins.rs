@@ -0,0 +1,753 @@
+//! Detection of Office add-ins delivered by installers
+//!
+//! Add-ins show up as packaged files (VSTO deployment manifests, XLL
+//! libraries, Excel add-in workbooks, macro-enabled templates and web add-in
+//! manifests) and as the registry values Office reads at startup: the
+//! `Addins\<ProgId>` keys of each application, Excel `OPEN` values and
+//! policy keys under `Software\Policies\Microsoft\Office`. Findings flag what
+//! makes Office load code without the user opening a document: startup load
+//! behavior, templates dropped into startup folders, policy-enforced add-ins
+//! and weakened macro or add-in security.
+
+use crate::analyzers::InstallerAnalyzer;
+use crate::core::{AnalysisResult, FileEntry, RegistryOperation, RegistryValue, Result};
+use regex::Regex;
+use std::fmt;
+use std::path::Path;
+
+/// Largest manifest that is read to name an add-in
+const MAX_MANIFEST_SIZE: u64 = 1024 * 1024;
+/// Add-ins and findings listed in analysis properties
+const MAX_LISTED: usize = 15;
+
+/// `LoadBehavior` bit that loads an add-in when the application starts
+const LOAD_AT_STARTUP: u32 = 0x2;
+
+/// Folders whose templates and add-ins Office opens at startup
+const STARTUP_FOLDERS: [&str; 4] = [
+    "\\microsoft\\word\\startup\\",
+    "\\microsoft\\excel\\xlstart\\",
+    "\\startup\\",
+    "\\xlstart\\",
+];
+
+/// Security values under Office policy keys, with what setting them does
+const POLICY_SECURITY_VALUES: &[(&str, &str)] = &[
+    ("VBAWarnings", "changes the macro warning level"),
+    (
+        "AccessVBOM",
+        "trusts access to the VBA project object model",
+    ),
+    ("RequireAddinSig", "changes add-in signature requirements"),
+    (
+        "NoTBPromptUnsignedAddin",
+        "suppresses prompts for unsigned add-ins",
+    ),
+    ("DisableAllAddins", "changes whether add-ins are disabled"),
+    (
+        "BlockContentExecutionFromInternet",
+        "changes macro blocking for files from the internet",
+    ),
+];
+
+/// Kind of Office add-in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AddinKind {
+    /// Visual Studio Tools for Office add-in
+    Vsto,
+    /// COM add-in registered by ProgId
+    Com,
+    /// Excel native add-in library
+    Xll,
+    /// Excel add-in workbook (`.xla`, `.xlam`)
+    ExcelAddin,
+    /// PowerPoint add-in (`.ppa`, `.ppam`)
+    PowerPointAddin,
+    /// Macro-enabled template (`.dotm`, `.xltm`, `.potm`, ...)
+    MacroTemplate,
+    /// Web add-in manifest
+    WebAddin,
+}
+
+impl AddinKind {
+    /// Display name of the kind
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Vsto => "VSTO",
+            Self::Com => "COM",
+            Self::Xll => "XLL",
+            Self::ExcelAddin => "Excel add-in",
+            Self::PowerPointAddin => "PowerPoint add-in",
+            Self::MacroTemplate => "Macro-enabled template",
+            Self::WebAddin => "Web add-in",
+        }
+    }
+
+    /// Kind of a packaged file by extension
+    fn from_path(path: &str) -> Option<Self> {
+        let (_, extension) = path.rsplit_once('.')?;
+        match extension.to_ascii_lowercase().as_str() {
+            "vsto" => Some(Self::Vsto),
+            "xll" => Some(Self::Xll),
+            "xla" | "xlam" => Some(Self::ExcelAddin),
+            "ppa" | "ppam" => Some(Self::PowerPointAddin),
+            "dot" | "dotm" | "xlt" | "xltm" | "potm" => Some(Self::MacroTemplate),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for AddinKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Add-in found in the package or its registrations
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OfficeAddin {
+    pub kind: AddinKind,
+    pub name: String,
+    /// Office application that loads the add-in
+    pub application: Option<String>,
+    pub load_behavior: Option<u32>,
+    /// Packaged file or registry key the add-in was found in
+    pub source: String,
+}
+
+impl OfficeAddin {
+    /// Whether the add-in loads when its application starts
+    pub fn loads_at_startup(&self) -> bool {
+        self.load_behavior
+            .is_some_and(|behavior| behavior & LOAD_AT_STARTUP != 0)
+    }
+
+    fn describe(&self) -> String {
+        let mut details = Vec::new();
+        if let Some(application) = &self.application {
+            details.push(application.clone());
+        }
+        if self.loads_at_startup() {
+            details.push("loads at startup".to_string());
+        }
+        if details.is_empty() {
+            format!("{} {}", self.kind, self.name)
+        } else {
+            format!("{} {} ({})", self.kind, self.name, details.join(", "))
+        }
+    }
+}
+
+/// Class of an add-in finding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AddinFindingCategory {
+    /// Registry setting under `Software\Policies\Microsoft\Office`
+    Policy,
+    /// Add-in loaded when the application starts
+    AutoLoad,
+    /// Template or add-in placed in a startup folder
+    StartupFolder,
+    /// Trust granted without prompting the user
+    TrustBypass,
+}
+
+impl AddinFindingCategory {
+    /// Display name of the category
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Policy => "Office policy",
+            Self::AutoLoad => "Auto-load",
+            Self::StartupFolder => "Startup folder",
+            Self::TrustBypass => "Trust bypass",
+        }
+    }
+}
+
+impl fmt::Display for AddinFindingCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Setting or file that makes Office load code on its own
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddinFinding {
+    pub category: AddinFindingCategory,
+    pub description: String,
+}
+
+/// Office add-ins of an installer and the findings about them
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OfficeAddinReport {
+    pub addins: Vec<OfficeAddin>,
+    pub findings: Vec<AddinFinding>,
+}
+
+impl OfficeAddinReport {
+    /// Inspect the packaged files and registry operations of an analysis
+    ///
+    /// VSTO and web add-in manifests are read from the installer to name
+    /// the add-ins they describe.
+    pub async fn detect(
+        analyzer: &dyn InstallerAnalyzer,
+        file_path: &Path,
+        result: &AnalysisResult,
+    ) -> Result<Self> {
+        let mut report = Self::default();
+        for file in result.files.iter().filter(|file| !file.is_speculative) {
+            let path = file.path.to_string_lossy().replace('/', "\\");
+            let is_manifest =
+                AddinKind::from_path(&path) == Some(AddinKind::Vsto) || is_web_manifest_name(&path);
+            let manifest = if is_manifest && file.size <= MAX_MANIFEST_SIZE {
+                match analyzer.read_file_content(file_path, &file.path).await {
+                    Ok(content) => content,
+                    Err(e) => {
+                        tracing::debug!("Failed to read {}: {}", file.path.display(), e);
+                        None
+                    }
+                }
+            } else {
+                None
+            };
+            report.add_file(file, manifest.as_deref());
+        }
+        report.add_registry_operations(&result.registry_operations);
+        Ok(report)
+    }
+
+    /// Add-ins and startup folder drops among packaged files
+    pub fn add_file(&mut self, file: &FileEntry, manifest: Option<&[u8]>) {
+        let path = file.path.to_string_lossy().replace('/', "\\");
+        let name = path.rsplit('\\').next().unwrap_or(&path).to_string();
+        let manifest = manifest.map(String::from_utf8_lossy);
+
+        let mut addin = match AddinKind::from_path(&path) {
+            Some(kind) => OfficeAddin {
+                kind,
+                name: name.clone(),
+                application: None,
+                load_behavior: None,
+                source: path.clone(),
+            },
+            // Web add-in manifests are XML files like any other
+            None => match manifest.as_deref().and_then(web_addin_name) {
+                Some(display_name) => OfficeAddin {
+                    kind: AddinKind::WebAddin,
+                    name: display_name,
+                    application: manifest.as_deref().and_then(web_addin_host),
+                    load_behavior: None,
+                    source: path.clone(),
+                },
+                None => return,
+            },
+        };
+        if addin.kind == AddinKind::Vsto {
+            if let Some(manifest) = manifest.as_deref() {
+                read_vsto_manifest(manifest, &mut addin);
+            }
+        }
+
+        let target = file
+            .target_path
+            .as_ref()
+            .map(|target| target.to_string_lossy().replace('/', "\\"))
+            .unwrap_or_else(|| path.clone());
+        let in_startup_folder = {
+            let target = format!("\\{}", target.to_lowercase());
+            STARTUP_FOLDERS.iter().any(|folder| target.contains(folder))
+        };
+        if in_startup_folder
+            && matches!(
+                addin.kind,
+                AddinKind::MacroTemplate | AddinKind::ExcelAddin | AddinKind::PowerPointAddin
+            )
+        {
+            self.push_finding(
+                AddinFindingCategory::StartupFolder,
+                format!("{} is opened at application startup from {}", name, target),
+            );
+        }
+        self.push_addin(addin);
+    }
+
+    /// Add-in registrations and Office settings among registry operations
+    pub fn add_registry_operations(&mut self, operations: &[RegistryOperation]) {
+        let addin_key = Regex::new(
+            r"(?i)\\Software\\(?:Wow6432Node\\)?(Policies\\)?Microsoft\\Office\\(?:[\d.]+\\)?([^\\]+)\\Addins\\([^\\]+)$",
+        )
+        .expect("valid add-in key pattern");
+        let options_key = Regex::new(
+            r"(?i)\\Software\\(?:Wow6432Node\\)?(Policies\\)?Microsoft\\Office\\[\d.]+\\Excel\\Options$",
+        )
+        .expect("valid options key pattern");
+        let policy_key = Regex::new(r"(?i)\\Software\\Policies\\Microsoft\\Office\\")
+            .expect("valid policy key pattern");
+        let open_value = Regex::new(r"(?i)^OPEN\d*$").expect("valid OPEN value pattern");
+
+        for operation in operations {
+            let RegistryOperation::SetValue {
+                key_path,
+                value_name,
+                value_data,
+                ..
+            } = operation
+            else {
+                continue;
+            };
+            let key = format!("\\{}", key_path);
+            let is_policy = policy_key.is_match(&key);
+
+            if let Some(captures) = addin_key.captures(&key) {
+                let application = captures[2].to_string();
+                let prog_id = captures[3].to_string();
+                self.add_registration(&prog_id, &application, key_path, value_name, value_data);
+                if is_policy && value_name.eq_ignore_ascii_case("LoadBehavior") {
+                    self.push_finding(
+                        AddinFindingCategory::Policy,
+                        format!(
+                            "{} add-in {} is enforced by policy ({})",
+                            application, prog_id, key_path
+                        ),
+                    );
+                }
+            } else if options_key.is_match(&key) && open_value.is_match(value_name) {
+                // OPEN values hold `/R "path"`: Excel opens the file at startup
+                let text = registry_text(value_data).unwrap_or_default();
+                let path = text
+                    .split('"')
+                    .find(|part| part.contains('.'))
+                    .unwrap_or(&text)
+                    .trim()
+                    .to_string();
+                let name = path.rsplit('\\').next().unwrap_or(&path).to_string();
+                self.push_addin(OfficeAddin {
+                    kind: AddinKind::from_path(&name).unwrap_or(AddinKind::ExcelAddin),
+                    name: name.clone(),
+                    application: Some("Excel".to_string()),
+                    load_behavior: Some(LOAD_AT_STARTUP),
+                    source: key_path.clone(),
+                });
+                let category = if is_policy {
+                    AddinFindingCategory::Policy
+                } else {
+                    AddinFindingCategory::AutoLoad
+                };
+                self.push_finding(
+                    category,
+                    format!(
+                        "Excel opens {} at startup ({}\\{})",
+                        path, key_path, value_name
+                    ),
+                );
+            } else if key
+                .to_lowercase()
+                .contains("\\resiliency\\donotdisableaddinlist")
+            {
+                let category = if is_policy {
+                    AddinFindingCategory::Policy
+                } else {
+                    AddinFindingCategory::TrustBypass
+                };
+                self.push_finding(
+                    category,
+                    format!(
+                        "Add-in {} is exempt from being disabled by Office",
+                        value_name
+                    ),
+                );
+            } else if key.to_lowercase().contains("\\trusted locations\\")
+                && value_name.eq_ignore_ascii_case("Path")
+            {
+                let category = if is_policy {
+                    AddinFindingCategory::Policy
+                } else {
+                    AddinFindingCategory::TrustBypass
+                };
+                self.push_finding(
+                    category,
+                    format!(
+                        "Adds trusted location {}",
+                        registry_text(value_data).unwrap_or_default()
+                    ),
+                );
+            } else if key
+                .to_lowercase()
+                .contains("\\microsoft\\vsto\\security\\inclusion\\")
+                && value_name.eq_ignore_ascii_case("Url")
+            {
+                self.push_finding(
+                    AddinFindingCategory::TrustBypass,
+                    format!(
+                        "Adds {} to the VSTO inclusion list, which skips the trust prompt",
+                        registry_text(value_data).unwrap_or_default()
+                    ),
+                );
+            } else if is_policy {
+                if let Some((name, meaning)) = POLICY_SECURITY_VALUES
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(value_name))
+                {
+                    let value = registry_text(value_data).unwrap_or_default();
+                    self.push_finding(
+                        AddinFindingCategory::Policy,
+                        format!("{}={} {} ({})", name, value, meaning, key_path),
+                    );
+                }
+            }
+        }
+
+        // Startup findings once all values of the registrations are known
+        let startup: Vec<String> = self
+            .addins
+            .iter()
+            .filter(|addin| {
+                matches!(addin.kind, AddinKind::Vsto | AddinKind::Com) && addin.loads_at_startup()
+            })
+            .map(|addin| {
+                format!(
+                    "{} add-in {} loads when {} starts",
+                    addin.kind,
+                    addin.name,
+                    addin.application.as_deref().unwrap_or("Office")
+                )
+            })
+            .collect();
+        for description in startup {
+            self.push_finding(AddinFindingCategory::AutoLoad, description);
+        }
+    }
+
+    /// Merge one value of an `Addins\<ProgId>` key into its add-in
+    fn add_registration(
+        &mut self,
+        prog_id: &str,
+        application: &str,
+        key_path: &str,
+        value_name: &str,
+        value_data: &RegistryValue,
+    ) {
+        let position = self
+            .addins
+            .iter()
+            .position(|addin| addin.source.eq_ignore_ascii_case(key_path));
+        let index = position.unwrap_or_else(|| {
+            self.addins.push(OfficeAddin {
+                kind: AddinKind::Com,
+                name: prog_id.to_string(),
+                application: Some(application.to_string()),
+                load_behavior: None,
+                source: key_path.to_string(),
+            });
+            self.addins.len() - 1
+        });
+        let addin = &mut self.addins[index];
+        match value_name.to_ascii_lowercase().as_str() {
+            "loadbehavior" => addin.load_behavior = registry_number(value_data),
+            // VSTO add-ins point to their deployment manifest
+            "manifest" => addin.kind = AddinKind::Vsto,
+            _ => {}
+        }
+    }
+
+    fn push_addin(&mut self, addin: OfficeAddin) {
+        let known = self
+            .addins
+            .iter()
+            .any(|known| known.kind == addin.kind && known.name.eq_ignore_ascii_case(&addin.name));
+        if !known {
+            self.addins.push(addin);
+        }
+    }
+
+    fn push_finding(&mut self, category: AddinFindingCategory, description: String) {
+        let finding = AddinFinding {
+            category,
+            description,
+        };
+        if !self.findings.contains(&finding) {
+            self.findings.push(finding);
+        }
+    }
+
+    /// Whether Office loads add-in code without the user opening a document
+    pub fn auto_loads(&self) -> bool {
+        self.findings.iter().any(|finding| {
+            matches!(
+                finding.category,
+                AddinFindingCategory::AutoLoad | AddinFindingCategory::StartupFolder
+            )
+        }) || self.addins.iter().any(OfficeAddin::loads_at_startup)
+    }
+
+    /// Record the add-ins and findings as analysis properties
+    pub fn insert_properties(&self, result: &mut AnalysisResult) {
+        if self.addins.is_empty() && self.findings.is_empty() {
+            return;
+        }
+
+        let properties = &mut result.metadata.properties;
+        properties.insert(
+            "office_addin_count".to_string(),
+            self.addins.len().to_string(),
+        );
+        if !self.addins.is_empty() {
+            let addins: Vec<String> = self
+                .addins
+                .iter()
+                .take(MAX_LISTED)
+                .map(OfficeAddin::describe)
+                .collect();
+            properties.insert("office_addins".to_string(), addins.join("; "));
+        }
+        properties.insert(
+            "office_addin_autoload".to_string(),
+            self.auto_loads().to_string(),
+        );
+        if !self.findings.is_empty() {
+            let mut findings: Vec<&AddinFinding> = self.findings.iter().collect();
+            findings.sort_by_key(|finding| finding.category);
+            let descriptions: Vec<String> = findings
+                .iter()
+                .take(MAX_LISTED)
+                .map(|finding| format!("{}: {}", finding.category, finding.description))
+                .collect();
+            properties.insert("office_addin_findings".to_string(), descriptions.join("; "));
+        }
+    }
+}
+
+/// Text of a string registry value
+fn registry_text(value: &RegistryValue) -> Option<String> {
+    match value {
+        RegistryValue::String(text) => Some(text.clone()),
+        RegistryValue::DWord(number) => Some(number.to_string()),
+        RegistryValue::QWord(number) => Some(number.to_string()),
+        RegistryValue::MultiString(texts) => Some(texts.join(", ")),
+        RegistryValue::Binary(_) => None,
+    }
+}
+
+/// Number of a DWORD value, or of a string holding one
+fn registry_number(value: &RegistryValue) -> Option<u32> {
+    match value {
+        RegistryValue::DWord(number) => Some(*number),
+        RegistryValue::QWord(number) => u32::try_from(*number).ok(),
+        RegistryValue::String(text) => text.trim_start_matches('#').trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// XML files that may be web add-in manifests
+fn is_web_manifest_name(path: &str) -> bool {
+    let name = path.rsplit('\\').next().unwrap_or(path).to_lowercase();
+    name.ends_with(".xml") && name.contains("manifest")
+}
+
+/// Display name of a web add-in manifest (`<OfficeApp>`)
+fn web_addin_name(manifest: &str) -> Option<String> {
+    if !manifest.contains("<OfficeApp") {
+        return None;
+    }
+    let start = manifest.find("<DisplayName")?;
+    xml_attribute(&manifest[start..], "DefaultValue")
+}
+
+/// First host application of a web add-in manifest
+fn web_addin_host(manifest: &str) -> Option<String> {
+    let start = manifest.find("<Host ")?;
+    let host = xml_attribute(&manifest[start..], "Name")?;
+    Some(
+        match host.as_str() {
+            "Workbook" => "Excel",
+            "Document" => "Word",
+            "Presentation" => "PowerPoint",
+            "Mailbox" => "Outlook",
+            other => other,
+        }
+        .to_string(),
+    )
+}
+
+/// Name, application and load behavior of a VSTO deployment manifest
+fn read_vsto_manifest(manifest: &str, addin: &mut OfficeAddin) {
+    if let Some(start) = manifest.find("friendlyName>") {
+        let name = &manifest[start + "friendlyName>".len()..];
+        if let Some(end) = name.find('<') {
+            let name = name[..end].trim();
+            if !name.is_empty() {
+                addin.name = name.to_string();
+            }
+        }
+    }
+    if let Some(start) = manifest.find("appAddIn ") {
+        let element = &manifest[start..];
+        addin.application = xml_attribute(element, "application");
+        addin.load_behavior =
+            xml_attribute(element, "loadBehavior").and_then(|value| value.parse().ok());
+    }
+}
+
+/// Unescaped value of the first `name` attribute in `element`
+fn xml_attribute(element: &str, name: &str) -> Option<String> {
+    let end_of_tag = element.find('>').unwrap_or(element.len());
+    let element = &element[..end_of_tag];
+    let pattern = format!(" {}=\"", name);
+    let start = element.find(&pattern)? + pattern.len();
+    let end = element[start..].find('"')?;
+    Some(
+        element[start..start + end]
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&apos;", "'")
+            .replace("&amp;", "&"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{FileAttributes, InstallerFormat, InstallerMetadata, RegistryValueType};
+    use std::path::PathBuf;
+
+    fn file(path: &str, target: Option<&str>) -> FileEntry {
+        FileEntry {
+            path: PathBuf::from(path),
+            target_path: target.map(PathBuf::from),
+            size: 1,
+            hash: None,
+            attributes: FileAttributes::default(),
+            compression: None,
+            is_speculative: false,
+            architecture: None,
+            modified: None,
+            source: None,
+        }
+    }
+
+    fn empty_result() -> AnalysisResult {
+        AnalysisResult {
+            session_id: uuid::Uuid::new_v4(),
+            metadata: InstallerMetadata {
+                format: InstallerFormat::MSI,
+                created_at: chrono::Utc::now(),
+                ..Default::default()
+            },
+            analyzed_at: chrono::Utc::now(),
+            ..Default::default()
+        }
+    }
+
+    fn set_value(key_path: &str, value_name: &str, value_data: RegistryValue) -> RegistryOperation {
+        RegistryOperation::SetValue {
+            key_path: key_path.to_string(),
+            value_name: value_name.to_string(),
+            value_type: RegistryValueType::String,
+            value_data,
+            timestamp: chrono::Utc::now(),
+            process_id: None,
+        }
+    }
+
+    #[test]
+    fn test_packaged_addins() {
+        let mut report = OfficeAddinReport::default();
+        let vsto = br#"<asmv1:assembly><assemblyIdentity name="Contoso.vsto" />
+<vstov4:appAddIn application="Outlook" loadBehavior="3" keyName="ContosoAddIn">
+<vstov4:friendlyName>Contoso Mail Tools</vstov4:friendlyName></vstov4:appAddIn></asmv1:assembly>"#;
+        report.add_file(&file("bin/Contoso.vsto", None), Some(vsto));
+        report.add_file(&file("bin/calc.xll", None), None);
+        report.add_file(
+            &file(
+                "Normal.dotm",
+                Some("%APPDATA%\\Microsoft\\Word\\STARTUP\\Normal.dotm"),
+            ),
+            None,
+        );
+        let web = br#"<OfficeApp xmlns="http://schemas.microsoft.com/office/appforoffice/1.1">
+<DisplayName DefaultValue="Contoso Charts"/><Hosts><Host Name="Workbook"/></Hosts></OfficeApp>"#;
+        report.add_file(&file("web/manifest.xml", None), Some(web));
+        report.add_file(&file("docs/readme.xml", None), None);
+
+        let kinds: Vec<AddinKind> = report.addins.iter().map(|addin| addin.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                AddinKind::Vsto,
+                AddinKind::Xll,
+                AddinKind::MacroTemplate,
+                AddinKind::WebAddin
+            ]
+        );
+        assert_eq!(
+            report.addins[0].describe(),
+            "VSTO Contoso Mail Tools (Outlook, loads at startup)"
+        );
+        assert_eq!(
+            report.addins[3].describe(),
+            "Web add-in Contoso Charts (Excel)"
+        );
+        assert_eq!(report.findings.len(), 1);
+        assert_eq!(
+            report.findings[0].category,
+            AddinFindingCategory::StartupFolder
+        );
+        assert!(report.auto_loads());
+    }
+
+    #[test]
+    fn test_registry_findings() {
+        let mut report = OfficeAddinReport::default();
+        report.add_registry_operations(&[
+            set_value(
+                "HKEY_CURRENT_USER\\Software\\Microsoft\\Office\\Excel\\Addins\\Contoso.Connect",
+                "LoadBehavior",
+                RegistryValue::String("#3".to_string()),
+            ),
+            set_value(
+                "HKEY_CURRENT_USER\\Software\\Microsoft\\Office\\Excel\\Addins\\Contoso.Connect",
+                "Manifest",
+                RegistryValue::String("file:///C:/Contoso/Contoso.vsto|vstolocal".to_string()),
+            ),
+            set_value(
+                "HKEY_LOCAL_MACHINE\\Software\\Policies\\Microsoft\\Office\\16.0\\Word\\Addins\\Contoso.Word",
+                "LoadBehavior",
+                RegistryValue::DWord(3),
+            ),
+            set_value(
+                "HKEY_CURRENT_USER\\Software\\Microsoft\\Office\\16.0\\Excel\\Options",
+                "OPEN1",
+                RegistryValue::String("/R \"C:\\Contoso\\calc.xll\"".to_string()),
+            ),
+            set_value(
+                "HKEY_CURRENT_USER\\Software\\Policies\\Microsoft\\Office\\16.0\\Excel\\Security",
+                "VBAWarnings",
+                RegistryValue::DWord(1),
+            ),
+            set_value(
+                "HKEY_CURRENT_USER\\Software\\Microsoft\\Office\\16.0\\Outlook\\Resiliency\\DoNotDisableAddinList",
+                "Contoso.Connect",
+                RegistryValue::DWord(1),
+            ),
+        ]);
+
+        assert_eq!(report.addins.len(), 3);
+        assert_eq!(report.addins[0].kind, AddinKind::Vsto);
+        assert_eq!(report.addins[0].load_behavior, Some(3));
+        assert_eq!(report.addins[2].kind, AddinKind::Xll);
+
+        let mut result = empty_result();
+        report.insert_properties(&mut result);
+        let properties = &result.metadata.properties;
+        assert_eq!(properties["office_addin_count"], "3");
+        assert_eq!(properties["office_addin_autoload"], "true");
+        let findings = &properties["office_addin_findings"];
+        assert!(
+            findings.starts_with("Office policy: Word add-in Contoso.Word is enforced by policy")
+        );
+        assert!(findings.contains("Office policy: VBAWarnings=1 changes the macro warning level"));
+        assert!(findings.contains("Auto-load: Excel opens C:\\Contoso\\calc.xll at startup"));
+        assert!(findings.contains("Auto-load: VSTO add-in Contoso.Connect loads when Excel starts"));
+        assert!(findings.contains("Trust bypass: Add-in Contoso.Connect is exempt"));
+    }
+}
@@ -0,0 +1,225 @@
+//! Previews of small text files for the report
+//!
+//! Reviewers often only need a configuration default, a manifest entry or
+//! the license text of an installer. Small files of those kinds are read one
+//! at a time through [`InstallerAnalyzer::open_file`], without extracting the
+//! rest of the installer, and their beginning is kept in the report.
+
+use crate::analyzers::InstallerAnalyzer;
+use crate::core::{FileEntry, FilePreview, PreviewKind};
+use std::path::Path;
+use tokio::io::AsyncReadExt;
+
+/// Largest packaged file that is previewed
+const MAX_FILE_SIZE: u64 = 256 * 1024;
+/// Bytes of a file kept in its preview
+const MAX_PREVIEW_BYTES: u64 = 16 * 1024;
+/// Upper bound of previews per installer
+const MAX_PREVIEWS: usize = 20;
+
+/// Extensions of configuration files
+const CONFIG_EXTENSIONS: &[&str] = &[
+    "ini",
+    "cfg",
+    "conf",
+    "config",
+    "toml",
+    "yaml",
+    "yml",
+    "properties",
+];
+/// Names of JSON files holding settings, other JSON files are usually data
+const CONFIG_NAMES: &[&str] = &["appsettings.json", "config.json", "settings.json"];
+/// Names of manifests without a telling extension
+const MANIFEST_NAMES: &[&str] = &["appxmanifest.xml", "manifest.json", "manifest.mf"];
+/// File name stems of license files
+const LICENSE_STEMS: &[&str] = &["license", "licence", "copying", "eula", "notice"];
+
+/// Kind of preview a packaged file gets, `None` for files not previewed
+pub fn preview_kind(path: &Path) -> Option<PreviewKind> {
+    let name = path.file_name()?.to_str()?.to_lowercase();
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) => (stem, extension),
+        None => (name.as_str(), ""),
+    };
+
+    if CONFIG_EXTENSIONS.contains(&extension) || CONFIG_NAMES.contains(&name.as_str()) {
+        Some(PreviewKind::Config)
+    } else if matches!(extension, "manifest" | "nuspec") || MANIFEST_NAMES.contains(&name.as_str())
+    {
+        Some(PreviewKind::Manifest)
+    } else if matches!(extension, "" | "txt" | "md")
+        && LICENSE_STEMS
+            .iter()
+            .any(|license| stem.split(['-', '_', '.']).next() == Some(license))
+    {
+        Some(PreviewKind::License)
+    } else {
+        None
+    }
+}
+
+/// Previews of the small configuration, manifest and license files in `files`
+///
+/// Files that cannot be read or are not text are left out.
+pub async fn collect_previews(
+    analyzer: &dyn InstallerAnalyzer,
+    file_path: &Path,
+    files: &[FileEntry],
+) -> Vec<FilePreview> {
+    let mut candidates: Vec<(PreviewKind, &FileEntry)> = files
+        .iter()
+        .filter(|file| !file.is_speculative && file.size > 0 && file.size <= MAX_FILE_SIZE)
+        .filter_map(|file| Some((preview_kind(&file.path)?, file)))
+        .collect();
+    candidates.sort_by(|(a_kind, a), (b_kind, b)| a_kind.cmp(b_kind).then(a.path.cmp(&b.path)));
+
+    let mut previews = Vec::new();
+    for (kind, file) in candidates {
+        if previews.len() >= MAX_PREVIEWS {
+            break;
+        }
+        let mut reader = match analyzer.open_file(file_path, &file.path).await {
+            Ok(Some(reader)) => reader.take(MAX_PREVIEW_BYTES + 1),
+            Ok(None) => continue,
+            Err(e) => {
+                tracing::debug!("Failed to open {}: {}", file.path.display(), e);
+                continue;
+            }
+        };
+        let mut content = Vec::new();
+        if let Err(e) = reader.read_to_end(&mut content).await {
+            tracing::debug!("Failed to read {}: {}", file.path.display(), e);
+            continue;
+        }
+        let truncated = content.len() as u64 > MAX_PREVIEW_BYTES;
+        content.truncate(MAX_PREVIEW_BYTES as usize);
+        if let Some(text) = decode_text(&content, truncated) {
+            previews.push(FilePreview {
+                path: file.path.to_string_lossy().replace('\\', "/"),
+                kind,
+                size: file.size,
+                content: text,
+                truncated,
+            });
+        }
+    }
+    previews
+}
+
+/// Text of a file in UTF-8 or, with a byte order mark, UTF-16
+///
+/// A character cut off by the preview limit is dropped; `None` for binary
+/// content.
+fn decode_text(content: &[u8], truncated: bool) -> Option<String> {
+    let mut text: String = if let Some(utf16) = content.strip_prefix(b"\xff\xfe") {
+        let units = utf16
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]));
+        char::decode_utf16(units)
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect()
+    } else if let Some(utf16) = content.strip_prefix(b"\xfe\xff") {
+        let units = utf16
+            .chunks_exact(2)
+            .map(|c| u16::from_be_bytes([c[0], c[1]]));
+        char::decode_utf16(units)
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect()
+    } else {
+        let content = content.strip_prefix(b"\xef\xbb\xbf").unwrap_or(content);
+        match std::str::from_utf8(content) {
+            Ok(text) => text.to_string(),
+            // Incomplete character at the preview limit
+            Err(e) if truncated && e.error_len().is_none() => {
+                String::from_utf8_lossy(&content[..e.valid_up_to()]).to_string()
+            }
+            Err(_) => String::from_utf8_lossy(content).to_string(),
+        }
+    };
+    if truncated && text.ends_with(char::REPLACEMENT_CHARACTER) {
+        text.pop();
+    }
+    let binary = text.chars().any(|c| c.is_control() && !c.is_whitespace());
+    (!binary).then_some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_preview_kind() {
+        let kind = |path: &str| preview_kind(Path::new(path));
+        assert_eq!(kind("App/settings.ini"), Some(PreviewKind::Config));
+        assert_eq!(kind("bin/App.exe.config"), Some(PreviewKind::Config));
+        assert_eq!(kind("appsettings.json"), Some(PreviewKind::Config));
+        assert_eq!(kind("AppxManifest.xml"), Some(PreviewKind::Manifest));
+        assert_eq!(kind("App.exe.manifest"), Some(PreviewKind::Manifest));
+        assert_eq!(kind("LICENSE"), Some(PreviewKind::License));
+        assert_eq!(kind("docs/License-MIT.txt"), Some(PreviewKind::License));
+        assert_eq!(kind("licenses.json"), None);
+        assert_eq!(kind("data/strings.json"), None);
+        assert_eq!(kind("App.exe"), None);
+    }
+
+    #[test]
+    fn test_decode_text() {
+        assert_eq!(
+            decode_text(b"\xef\xbb\xbf[General]\r\n", false).as_deref(),
+            Some("[General]\r\n")
+        );
+        let utf16: Vec<u8> = b"\xff\xfe"
+            .iter()
+            .copied()
+            .chain("Key=Wert".encode_utf16().flat_map(u16::to_le_bytes))
+            .collect();
+        assert_eq!(decode_text(&utf16, false).as_deref(), Some("Key=Wert"));
+        // "ü" cut in half by the preview limit
+        assert_eq!(decode_text(b"Gr\xc3", true).as_deref(), Some("Gr"));
+        assert!(decode_text(b"MZ\x90\x00\x03", false).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_collect_previews() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("app.zip");
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&archive).unwrap());
+        let options = zip::write::FileOptions::default();
+        let entries: [(&str, Vec<u8>); 4] = [
+            ("App/App.exe", b"MZ\x90\x00".to_vec()),
+            ("App/App.ini", b"[Update]\nAutoUpdate=1\n".to_vec()),
+            ("App/LICENSE.txt", "x".repeat(20_000).into_bytes()),
+            ("App/data.cfg", b"\x00\x01\x02binary".to_vec()),
+        ];
+        let mut files = Vec::new();
+        for (name, content) in &entries {
+            zip.start_file(*name, options).unwrap();
+            std::io::Write::write_all(&mut zip, content).unwrap();
+            files.push(FileEntry {
+                path: PathBuf::from(name),
+                target_path: None,
+                size: content.len() as u64,
+                hash: None,
+                attributes: Default::default(),
+                compression: None,
+                is_speculative: false,
+                architecture: None,
+                modified: None,
+                source: None,
+            });
+        }
+        zip.finish().unwrap();
+
+        let analyzer = crate::analyzers::archive::ArchiveAnalyzer::new();
+        let previews = collect_previews(&analyzer, &archive, &files).await;
+        assert_eq!(previews.len(), 2);
+        assert_eq!(previews[0].path, "App/App.ini");
+        assert_eq!(previews[0].content, "[Update]\nAutoUpdate=1\n");
+        assert!(!previews[0].truncated);
+        assert_eq!(previews[1].kind, PreviewKind::License);
+        assert_eq!(previews[1].content.len(), MAX_PREVIEW_BYTES as usize);
+        assert!(previews[1].truncated);
+    }
+}
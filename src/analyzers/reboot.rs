@@ -0,0 +1,267 @@
+//! Detection of installers that force or schedule a reboot
+//!
+//! Evidence is collected from the analysis result (MSI reboot actions and the
+//! `REBOOT` property, `PendingFileRenameOperations` registry writes) and from
+//! strings embedded in the installer. Observed writes and unconditional
+//! actions make a reboot certain; conditional actions and embedded strings
+//! only make it likely.
+
+use crate::analyzers::common;
+use crate::core::{AnalysisResult, RegistryOperation, Result};
+use std::fmt;
+use std::path::Path;
+
+/// Registry value `MoveFileEx(MOVEFILE_DELAY_UNTIL_REBOOT)` appends to
+const PENDING_RENAME_VALUE: &str = "PendingFileRenameOperations";
+
+/// Strings that show the installer deals with reboots, with their meaning
+const REBOOT_STRINGS: &[(&str, &str)] = &[
+    (
+        PENDING_RENAME_VALUE,
+        "References the pending file rename queue",
+    ),
+    (
+        "MOVEFILE_DELAY_UNTIL_REBOOT",
+        "Replaces files with MoveFileEx delayed until reboot",
+    ),
+    ("reboot required", "Reports that a reboot is required"),
+    ("Reboot required", "Reports that a reboot is required"),
+    ("reboot is required", "Reports that a reboot is required"),
+    ("restart is required", "Reports that a restart is required"),
+    ("restart your computer", "Asks the user to restart"),
+    ("Restart your computer", "Asks the user to restart"),
+    ("RebootRequired", "Reports that a reboot is required"),
+];
+
+/// How certain it is that installing requires a reboot
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RebootVerdict {
+    No,
+    Likely,
+    Yes,
+}
+
+impl RebootVerdict {
+    /// Value used in analysis properties
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::No => "no",
+            Self::Likely => "likely",
+            Self::Yes => "yes",
+        }
+    }
+}
+
+impl fmt::Display for RebootVerdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Single piece of evidence for a reboot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RebootIndicator {
+    /// Verdict this indicator supports on its own
+    pub verdict: RebootVerdict,
+    pub description: String,
+}
+
+/// Reboot verdict with the evidence behind it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RebootAssessment {
+    pub verdict: RebootVerdict,
+    pub indicators: Vec<RebootIndicator>,
+}
+
+impl RebootAssessment {
+    /// Assess an analysis result together with the installer it came from
+    pub async fn detect(file_path: &Path, result: &AnalysisResult) -> Result<Self> {
+        let mut indicators = Self::result_indicators(result);
+        indicators.extend(Self::string_indicators(file_path).await?);
+        Ok(Self::from_indicators(indicators))
+    }
+
+    /// Build an assessment from collected indicators
+    pub fn from_indicators(indicators: Vec<RebootIndicator>) -> Self {
+        let verdict = indicators
+            .iter()
+            .map(|indicator| indicator.verdict)
+            .max()
+            .unwrap_or(RebootVerdict::No);
+        Self {
+            verdict,
+            indicators,
+        }
+    }
+
+    /// Indicators found in metadata and recorded registry operations
+    pub fn result_indicators(result: &AnalysisResult) -> Vec<RebootIndicator> {
+        let mut indicators = Vec::new();
+        let properties = &result.metadata.properties;
+
+        if let Some(actions) = properties.get("msi_reboot_actions") {
+            for action in actions.split(", ") {
+                // Conditional actions only run when their condition holds
+                let verdict = if action.contains("(if ") {
+                    RebootVerdict::Likely
+                } else {
+                    RebootVerdict::Yes
+                };
+                indicators.push(RebootIndicator {
+                    verdict,
+                    description: format!("MSI action {}", action),
+                });
+            }
+        }
+
+        if let Some(reboot) = properties.get("REBOOT") {
+            if reboot.eq_ignore_ascii_case("Force") || reboot.eq_ignore_ascii_case("F") {
+                indicators.push(RebootIndicator {
+                    verdict: RebootVerdict::Yes,
+                    description: "MSI property REBOOT=Force".to_string(),
+                });
+            }
+        }
+
+        for operation in &result.registry_operations {
+            if let RegistryOperation::SetValue {
+                key_path,
+                value_name,
+                ..
+            } = operation
+            {
+                if value_name.eq_ignore_ascii_case(PENDING_RENAME_VALUE)
+                    || value_name.eq_ignore_ascii_case("PendingFileRenameOperations2")
+                {
+                    indicators.push(RebootIndicator {
+                        verdict: RebootVerdict::Yes,
+                        description: format!("Writes {}\\{}", key_path, value_name),
+                    });
+                }
+            }
+        }
+
+        indicators
+    }
+
+    /// Indicators from reboot-related strings in the installer (ASCII and UTF-16)
+    async fn string_indicators(file_path: &Path) -> Result<Vec<RebootIndicator>> {
+        let wide: Vec<String> = REBOOT_STRINGS
+            .iter()
+            .map(|(pattern, _)| pattern.chars().flat_map(|c| [c, '\0']).collect())
+            .collect();
+        let patterns: Vec<&str> = REBOOT_STRINGS
+            .iter()
+            .map(|(pattern, _)| *pattern)
+            .chain(wide.iter().map(String::as_str))
+            .collect();
+        let found = common::search_file_content(file_path, &patterns).await?;
+
+        Ok(REBOOT_STRINGS
+            .iter()
+            .filter(|(pattern, _)| found.iter().any(|f| f.replace('\0', "") == *pattern))
+            .map(|(pattern, meaning)| RebootIndicator {
+                verdict: RebootVerdict::Likely,
+                description: format!("{} (\"{}\")", meaning, pattern),
+            })
+            .collect())
+    }
+
+    /// Record the verdict and its evidence as analysis properties
+    pub fn insert_properties(&self, result: &mut AnalysisResult) {
+        let properties = &mut result.metadata.properties;
+        properties.insert(
+            "reboot_required".to_string(),
+            self.verdict.as_str().to_string(),
+        );
+        if !self.indicators.is_empty() {
+            let indicators: Vec<&str> = self
+                .indicators
+                .iter()
+                .take(10)
+                .map(|indicator| indicator.description.as_str())
+                .collect();
+            properties.insert("reboot_indicators".to_string(), indicators.join("; "));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{InstallerFormat, InstallerMetadata, RegistryValue, RegistryValueType};
+    use std::collections::HashMap;
+    use std::io::Write;
+
+    fn result(properties: &[(&str, &str)]) -> AnalysisResult {
+        AnalysisResult {
+            session_id: uuid::Uuid::new_v4(),
+            metadata: InstallerMetadata {
+                format: InstallerFormat::MSI,
+                product_name: None,
+                product_version: None,
+                manufacturer: None,
+                file_size: 0,
+                file_hash: String::new(),
+                created_at: chrono::Utc::now(),
+                properties: properties
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect::<HashMap<_, _>>(),
+                provenance: Default::default(),
+                format_details: None,
+            },
+            analyzed_at: chrono::Utc::now(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_detect_reboot_verdicts() {
+        let mut clean = tempfile::NamedTempFile::new().unwrap();
+        clean.write_all(b"MZ plain installer payload").unwrap();
+        let assessment = RebootAssessment::detect(clean.path(), &result(&[]))
+            .await
+            .unwrap();
+        assert_eq!(assessment.verdict, RebootVerdict::No);
+
+        // UTF-16 string embedded in a resource
+        let mut wide = tempfile::NamedTempFile::new().unwrap();
+        let text: Vec<u8> = "A reboot is required"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        wide.write_all(&text).unwrap();
+        let assessment = RebootAssessment::detect(wide.path(), &result(&[]))
+            .await
+            .unwrap();
+        assert_eq!(assessment.verdict, RebootVerdict::Likely);
+        assert_eq!(assessment.indicators.len(), 1);
+
+        let conditional = result(&[("msi_reboot_actions", "ScheduleReboot (if NOT Installed)")]);
+        let assessment =
+            RebootAssessment::from_indicators(RebootAssessment::result_indicators(&conditional));
+        assert_eq!(assessment.verdict, RebootVerdict::Likely);
+
+        let mut forced = result(&[("msi_reboot_actions", "ForceReboot"), ("REBOOT", "Force")]);
+        forced
+            .registry_operations
+            .push(RegistryOperation::SetValue {
+                key_path: "HKLM\\SYSTEM\\CurrentControlSet\\Control\\Session Manager".to_string(),
+                value_name: PENDING_RENAME_VALUE.to_string(),
+                value_type: RegistryValueType::MultiString,
+                value_data: RegistryValue::MultiString(Vec::new()),
+                timestamp: chrono::Utc::now(),
+                process_id: None,
+            });
+        let assessment = RebootAssessment::detect(clean.path(), &forced)
+            .await
+            .unwrap();
+        assert_eq!(assessment.verdict, RebootVerdict::Yes);
+        assert_eq!(assessment.indicators.len(), 3);
+
+        assessment.insert_properties(&mut forced);
+        assert_eq!(forced.metadata.properties["reboot_required"], "yes");
+        assert!(forced.metadata.properties["reboot_indicators"].contains("ForceReboot"));
+    }
+}
@@ -0,0 +1,177 @@
+//! Analyzer registry driving format detection and lookup
+//!
+//! Analyzers are registered together with the format they handle. The order of
+//! registration is the order in which analyzers are tried during detection, so
+//! more specific analyzers (e.g. WiX before generic MSI) must come first.
+
+use crate::analyzers::{
+    ActualInstallerAnalyzer, AdvancedInstallerAnalyzer, DiskImageAnalyzer, DriverPackageAnalyzer,
+    InnoAnalyzer, InstallShieldAnalyzer, InstallerAnalyzer, LegacyInstallerAnalyzer, LegacyTool,
+    MsiAnalyzer, MsixAnalyzer, NsisAnalyzer, SquirrelAnalyzer, WheelAnalyzer, WixAnalyzer,
+};
+use crate::core::InstallerFormat;
+use std::sync::{Arc, OnceLock, RwLock};
+
+/// Constructor creating a fresh analyzer instance
+pub type AnalyzerConstructor = Arc<dyn Fn() -> Box<dyn InstallerAnalyzer> + Send + Sync>;
+
+/// A registered analyzer and the format it handles
+#[derive(Clone)]
+pub struct AnalyzerRegistration {
+    pub format: InstallerFormat,
+    constructor: AnalyzerConstructor,
+}
+
+impl AnalyzerRegistration {
+    /// Create a new analyzer instance
+    pub fn create(&self) -> Box<dyn InstallerAnalyzer> {
+        (self.constructor)()
+    }
+}
+
+/// Ordered collection of analyzer registrations
+#[derive(Clone, Default)]
+pub struct AnalyzerRegistry {
+    registrations: Vec<AnalyzerRegistration>,
+}
+
+impl AnalyzerRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a registry containing all built-in analyzers in detection order
+    pub fn with_builtin_analyzers() -> Self {
+        let mut registry = Self::new();
+        registry.push(InstallerFormat::DiskImage, || {
+            Box::new(DiskImageAnalyzer::new())
+        });
+        registry.push(InstallerFormat::WiX, || Box::new(WixAnalyzer::new()));
+        registry.push(InstallerFormat::MSI, || Box::new(MsiAnalyzer::new()));
+        registry.push(InstallerFormat::PythonWheel, || {
+            Box::new(WheelAnalyzer::new())
+        });
+        registry.push(InstallerFormat::MSIX, || Box::new(MsixAnalyzer::new()));
+        registry.push(InstallerFormat::DriverPackage, || {
+            Box::new(DriverPackageAnalyzer::new())
+        });
+        // Bootstrapper stubs of these tools also match InstallShield and NSIS markers
+        registry.push(InstallerFormat::AdvancedInstaller, || {
+            Box::new(AdvancedInstallerAnalyzer::new())
+        });
+        registry.push(InstallerFormat::ActualInstaller, || {
+            Box::new(ActualInstallerAnalyzer::new())
+        });
+        for tool in LegacyTool::ALL {
+            registry.push(tool.format(), move || {
+                Box::new(LegacyInstallerAnalyzer::new(tool))
+            });
+        }
+        registry.push(InstallerFormat::InstallShield, || {
+            Box::new(InstallShieldAnalyzer::new())
+        });
+        registry.push(InstallerFormat::Squirrel, || {
+            Box::new(SquirrelAnalyzer::new())
+        });
+        registry.push(InstallerFormat::NSIS, || Box::new(NsisAnalyzer::new()));
+        registry.push(InstallerFormat::InnoSetup, || Box::new(InnoAnalyzer::new()));
+        registry
+    }
+
+    /// Get the process-wide registry used by `AnalyzerFactory`
+    pub fn global() -> &'static RwLock<AnalyzerRegistry> {
+        static REGISTRY: OnceLock<RwLock<AnalyzerRegistry>> = OnceLock::new();
+        REGISTRY.get_or_init(|| RwLock::new(Self::with_builtin_analyzers()))
+    }
+
+    /// Register an analyzer with the highest detection priority
+    ///
+    /// Custom analyzers are tried before all previously registered analyzers,
+    /// so plugins can claim files that a built-in analyzer would also accept.
+    pub fn register<F>(&mut self, format: InstallerFormat, constructor: F)
+    where
+        F: Fn() -> Box<dyn InstallerAnalyzer> + Send + Sync + 'static,
+    {
+        self.registrations.insert(
+            0,
+            AnalyzerRegistration {
+                format,
+                constructor: Arc::new(constructor),
+            },
+        );
+    }
+
+    /// Register an analyzer with the lowest detection priority
+    pub fn push<F>(&mut self, format: InstallerFormat, constructor: F)
+    where
+        F: Fn() -> Box<dyn InstallerAnalyzer> + Send + Sync + 'static,
+    {
+        self.registrations.push(AnalyzerRegistration {
+            format,
+            constructor: Arc::new(constructor),
+        });
+    }
+
+    /// Get all registrations in detection order
+    pub fn registrations(&self) -> &[AnalyzerRegistration] {
+        &self.registrations
+    }
+
+    /// Get all registered formats in detection order, without duplicates
+    pub fn formats(&self) -> Vec<InstallerFormat> {
+        let mut formats: Vec<InstallerFormat> = Vec::new();
+        for registration in &self.registrations {
+            if !formats.contains(&registration.format) {
+                formats.push(registration.format.clone());
+            }
+        }
+        formats
+    }
+
+    /// Create the highest-priority analyzer registered for a format
+    pub fn create(&self, format: &InstallerFormat) -> Option<Box<dyn InstallerAnalyzer>> {
+        self.registrations
+            .iter()
+            .find(|registration| &registration.format == format)
+            .map(AnalyzerRegistration::create)
+    }
+
+    /// Create one instance of every registered analyzer in detection order
+    pub fn create_all(&self) -> Vec<Box<dyn InstallerAnalyzer>> {
+        self.registrations
+            .iter()
+            .map(AnalyzerRegistration::create)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_registry_order() {
+        let registry = AnalyzerRegistry::with_builtin_analyzers();
+        let formats = registry.formats();
+
+        assert_eq!(formats.len(), 15);
+        assert_eq!(formats[0], InstallerFormat::DiskImage);
+        assert_eq!(formats[1], InstallerFormat::WiX);
+        assert_eq!(formats[2], InstallerFormat::MSI);
+        assert!(!formats.contains(&InstallerFormat::Unknown));
+    }
+
+    #[test]
+    fn test_register_custom_format() {
+        let mut registry = AnalyzerRegistry::with_builtin_analyzers();
+        let custom = InstallerFormat::other("CustomMsi");
+        registry.register(custom.clone(), || Box::new(MsiAnalyzer::new()));
+
+        assert_eq!(registry.formats()[0], custom);
+        assert!(registry.create(&custom).is_some());
+        assert!(registry
+            .create(&InstallerFormat::other("Missing"))
+            .is_none());
+    }
+}
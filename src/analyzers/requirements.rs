@@ -0,0 +1,517 @@
+//! Extraction of prerequisites and system requirements
+//!
+//! Requirements are collected from what the installer declares: MSI launch
+//! conditions, MSIX target device families and framework dependencies, Burn
+//! bundle conditions and chained prerequisites, the minimum OS version of a
+//! setup executable, and references to Visual C++ and .NET runtimes.
+
+use crate::analyzers::common;
+use crate::analyzers::wix::BurnManifest;
+use crate::core::{AnalysisResult, InstallerFormat, Result};
+use byteorder::{ByteOrder, LittleEndian};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Runtime references searched for in installer content
+const RUNTIME_PATTERN: &str = r"(?i)\.NETFramework,Version=v\d+(?:\.\d+)+|(?:dotnet|windowsdesktop|aspnetcore)-runtime-\d+\.\d+(?:\.\d+)?|Microsoft Visual C\+\+ 20\d\d(?:-20\d\d)? (?:x86 |x64 )?Redistributable|vc_?redist\.(?:x86|x64|arm64)\.exe|ndp4\d{1,2}-";
+
+/// .NET Framework 4.5+ release keys (`NETFRAMEWORK45` values) and versions
+const NETFX_RELEASES: &[(u32, &str)] = &[
+    (378389, "4.5"),
+    (378675, "4.5.1"),
+    (379893, "4.5.2"),
+    (393295, "4.6"),
+    (394254, "4.6.1"),
+    (394802, "4.6.2"),
+    (460798, "4.7"),
+    (461308, "4.7.1"),
+    (461808, "4.7.2"),
+    (528040, "4.8"),
+    (533320, "4.8.1"),
+];
+
+/// Prerequisites and system requirements declared by an installer
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SystemRequirements {
+    /// Minimum (or maximum tested) operating system versions
+    pub operating_system: Vec<String>,
+    /// Required processor architecture or OS bitness
+    pub architecture: Vec<String>,
+    /// Runtimes and frameworks that must be present or are installed first
+    pub runtimes: Vec<String>,
+    /// Raw conditions checked before installing, with their messages
+    pub conditions: Vec<String>,
+    /// Sources that could not be read
+    pub notes: Vec<String>,
+}
+
+impl SystemRequirements {
+    /// Extract requirements from an analysis result and the installer it came from
+    pub async fn detect(file_path: &Path, result: &AnalysisResult) -> Result<Self> {
+        let mut requirements = Self::from_result(result);
+
+        if common::is_pe_file(file_path).await? {
+            let header = common::read_file_header(file_path, 4096).await?;
+            requirements.add_pe_header(&header);
+
+            match BurnManifest::read(file_path).await {
+                Ok(Some(manifest)) => requirements.add_burn_manifest(&manifest),
+                Ok(None) => {}
+                Err(e) => requirements
+                    .notes
+                    .push(format!("Burn bundle manifest not read: {}", e)),
+            }
+        }
+        if result.metadata.format == InstallerFormat::InnoSetup {
+            requirements.notes.push(
+                "InnoSetup MinVersion is stored in the compressed setup header and was not read"
+                    .to_string(),
+            );
+        }
+
+        let pattern = regex::bytes::Regex::new(RUNTIME_PATTERN).expect("valid runtime pattern");
+        for reference in common::search_file_matches(file_path, &pattern, 32).await? {
+            if let Some(runtime) = runtime_name(&reference) {
+                push_unique(
+                    &mut requirements.runtimes,
+                    format!("{} (referenced by the installer)", runtime),
+                );
+            }
+        }
+
+        Ok(requirements)
+    }
+
+    /// Requirements recorded in the properties and dependencies of a result
+    pub fn from_result(result: &AnalysisResult) -> Self {
+        let mut requirements = Self::default();
+        let properties = &result.metadata.properties;
+
+        if let Some(conditions) = properties.get("msi_launch_conditions") {
+            for entry in conditions.split("; ") {
+                requirements.add_condition(entry, "MSI launch condition");
+                push_unique(&mut requirements.conditions, entry.to_string());
+            }
+        }
+
+        if let Some(families) = properties.get("msix_target_device_families") {
+            let family_pattern = Regex::new(r"^(\S+)(?: (\S+))?(?: \(tested ([^)]+)\))?$")
+                .expect("valid device family pattern");
+            for family in families.split(", ") {
+                let Some(captures) = family_pattern.captures(family) else {
+                    continue;
+                };
+                let name = &captures[1];
+                let mut requirement = match captures.get(2).map(|m| m.as_str()) {
+                    Some(min_version)
+                        if name == "Windows.Desktop" || name == "Windows.Universal" =>
+                    {
+                        format!("{} or later ({})", windows_build_name(min_version), name)
+                    }
+                    Some(min_version) => format!("{} {} or later", name, min_version),
+                    None => name.to_string(),
+                };
+                if let Some(max_tested) = captures.get(3) {
+                    requirement.push_str(&format!(", tested up to {}", max_tested.as_str()));
+                }
+                push_unique(&mut requirements.operating_system, requirement);
+            }
+        }
+        if let Some(architecture) = properties.get("msix_processor_architecture") {
+            if !architecture.eq_ignore_ascii_case("neutral") {
+                push_unique(
+                    &mut requirements.architecture,
+                    format!("{} (package architecture)", architecture),
+                );
+            }
+        }
+
+        // MSIX package dependencies are frameworks such as VCLibs or the .NET Native runtime
+        if result.metadata.format == InstallerFormat::MSIX {
+            if let Some(graph) = &result.dependencies {
+                for edge in &graph.edges {
+                    let name = graph
+                        .nodes
+                        .iter()
+                        .find(|node| node.id == edge.to)
+                        .map_or(edge.to.as_str(), |node| node.name.as_str());
+                    let runtime = match &edge.version_spec {
+                        Some(spec) => format!("{} {} (package dependency)", name, spec),
+                        None => format!("{} (package dependency)", name),
+                    };
+                    push_unique(&mut requirements.runtimes, runtime);
+                }
+            }
+        }
+
+        requirements
+    }
+
+    /// Interpret OS, bitness and .NET checks of an MSI or Burn condition
+    fn add_condition(&mut self, condition: &str, source: &str) {
+        let version_nt = Regex::new(r#"(?i)\bVersionNT(?:64)?\s*(>=|>)\s*"?(v?)(\d+)(?:\.(\d+))?"#)
+            .expect("valid VersionNT pattern");
+        for captures in version_nt.captures_iter(condition) {
+            let number: u32 = captures[3].parse().unwrap_or_default();
+            // MSI uses 601 for Windows 7, Burn uses v6.1
+            let (major, minor) = match captures.get(4) {
+                Some(minor) => (number, minor.as_str().parse().unwrap_or_default()),
+                None if captures[2].is_empty() && number >= 100 => (number / 100, number % 100),
+                None => (number, 0),
+            };
+            let name = windows_name(major, minor);
+            let requirement = if &captures[1] == ">" {
+                format!("Later than {} ({})", name, source)
+            } else {
+                format!("{} or later ({})", name, source)
+            };
+            push_unique(&mut self.operating_system, requirement);
+        }
+
+        let bitness =
+            Regex::new(r"(?i)(NOT\s+)?\bVersionNT64\b").expect("valid VersionNT64 pattern");
+        for captures in bitness.captures_iter(condition) {
+            let architecture = if captures.get(1).is_some() {
+                "32-bit Windows"
+            } else {
+                "64-bit Windows"
+            };
+            push_unique(
+                &mut self.architecture,
+                format!("{} ({})", architecture, source),
+            );
+        }
+
+        if let Some(runtime) = netfx_condition_name(condition) {
+            push_unique(&mut self.runtimes, format!("{} ({})", runtime, source));
+        }
+    }
+
+    /// Prerequisites and conditions of a Burn bundle
+    fn add_burn_manifest(&mut self, manifest: &BurnManifest) {
+        for condition in &manifest.conditions {
+            self.add_condition(&condition.condition, "bundle condition");
+            push_unique(
+                &mut self.conditions,
+                format!("{} [{}]", condition.message, condition.condition),
+            );
+        }
+
+        // Packages with a detect condition are prerequisites installed only when missing
+        for package in &manifest.packages {
+            if let Some(detect) = &package.detect_condition {
+                let runtime = netfx_condition_name(detect)
+                    .map(|name| format!("{} via {}", name, package.id))
+                    .unwrap_or_else(|| package.id.clone());
+                push_unique(
+                    &mut self.runtimes,
+                    format!(
+                        "{} (bundle prerequisite, detected by [{}])",
+                        runtime, detect
+                    ),
+                );
+            }
+            if let Some(install) = &package.install_condition {
+                push_unique(
+                    &mut self.conditions,
+                    format!("Installs {} only if [{}]", package.id, install),
+                );
+            }
+        }
+    }
+
+    /// Minimum OS version and architecture from a setup executable's PE header
+    fn add_pe_header(&mut self, header: &[u8]) {
+        if header.len() < 0x40 {
+            return;
+        }
+        let pe_offset = LittleEndian::read_u32(&header[0x3c..]) as usize;
+        let Some(coff) = header.get(pe_offset + 4..pe_offset + 24) else {
+            return;
+        };
+        if header.get(pe_offset..pe_offset + 4) != Some(b"PE\0\0".as_slice()) {
+            return;
+        }
+
+        match LittleEndian::read_u16(&coff[0..]) {
+            0x8664 => push_unique(&mut self.architecture, "x64 (setup executable)".to_string()),
+            0xaa64 => push_unique(
+                &mut self.architecture,
+                "ARM64 (setup executable)".to_string(),
+            ),
+            _ => {}
+        }
+
+        // The loader refuses executables whose subsystem version is newer than the OS
+        let optional = pe_offset + 24;
+        if LittleEndian::read_u16(&coff[16..]) < 52 {
+            return;
+        }
+        let Some(versions) = header.get(optional + 48..optional + 52) else {
+            return;
+        };
+        let major = LittleEndian::read_u16(&versions[0..]) as u32;
+        let minor = LittleEndian::read_u16(&versions[2..]) as u32;
+        if major >= 5 {
+            push_unique(
+                &mut self.operating_system,
+                format!("{} or later (setup executable)", windows_name(major, minor)),
+            );
+        }
+    }
+
+    /// Check whether no requirement was found
+    pub fn is_empty(&self) -> bool {
+        self.operating_system.is_empty()
+            && self.architecture.is_empty()
+            && self.runtimes.is_empty()
+            && self.conditions.is_empty()
+    }
+
+    /// Record the requirements as analysis properties
+    pub fn insert_properties(&self, result: &mut AnalysisResult) {
+        let properties = &mut result.metadata.properties;
+        for (key, values) in [
+            ("requirements_os", &self.operating_system),
+            ("requirements_architecture", &self.architecture),
+            ("requirements_runtimes", &self.runtimes),
+            ("requirements_conditions", &self.conditions),
+            ("requirements_notes", &self.notes),
+        ] {
+            if !values.is_empty() {
+                properties.insert(key.to_string(), values.join("; "));
+            }
+        }
+    }
+
+    /// Read requirements recorded by [`SystemRequirements::insert_properties`]
+    pub fn from_properties(properties: &HashMap<String, String>) -> Self {
+        let list = |key: &str| {
+            properties
+                .get(key)
+                .map(|value| value.split("; ").map(str::to_string).collect())
+                .unwrap_or_default()
+        };
+        Self {
+            operating_system: list("requirements_os"),
+            architecture: list("requirements_architecture"),
+            runtimes: list("requirements_runtimes"),
+            conditions: list("requirements_conditions"),
+            notes: list("requirements_notes"),
+        }
+    }
+}
+
+fn push_unique(list: &mut Vec<String>, value: String) {
+    if !list.contains(&value) {
+        list.push(value);
+    }
+}
+
+/// Marketing name of a Windows NT version
+fn windows_name(major: u32, minor: u32) -> String {
+    match (major, minor) {
+        (5, 0) => "Windows 2000".to_string(),
+        (5, 1) => "Windows XP".to_string(),
+        (5, 2) => "Windows Server 2003".to_string(),
+        (6, 0) => "Windows Vista".to_string(),
+        (6, 1) => "Windows 7".to_string(),
+        (6, 2) => "Windows 8".to_string(),
+        (6, 3) => "Windows 8.1".to_string(),
+        (10, 0) => "Windows 10".to_string(),
+        _ => format!("Windows NT {}.{}", major, minor),
+    }
+}
+
+/// Name of a Windows 10/11 version such as `10.0.17763.0`
+fn windows_build_name(version: &str) -> String {
+    let build: Option<u32> = version.split('.').nth(2).and_then(|b| b.parse().ok());
+    match build {
+        Some(build) if build >= 22000 => format!("Windows 11 (build {})", build),
+        Some(build) => format!("Windows 10 (build {})", build),
+        None => format!("Windows {}", version),
+    }
+}
+
+/// .NET Framework version checked by a condition (WiX NetFx properties)
+fn netfx_condition_name(condition: &str) -> Option<String> {
+    let release = Regex::new(r#"(?i)\bNETFRAMEWORK45\s*>=\s*"?#?(\d+)"#).expect("valid pattern");
+    if let Some(captures) = release.captures(condition) {
+        let key: u32 = captures[1].parse().ok()?;
+        let version = NETFX_RELEASES
+            .iter()
+            .rev()
+            .find(|(release_key, _)| *release_key <= key)
+            .map_or("4.5", |(_, version)| *version);
+        return Some(format!(".NET Framework {}", version));
+    }
+
+    let property =
+        Regex::new(r"(?i)\b(?:WIX_IS_)?NETFRAMEWORK_?(\d)(\d)(\d?)").expect("valid pattern");
+    let captures = property.captures(condition)?;
+    let mut version = format!("{}.{}", &captures[1], &captures[2]);
+    if !captures[3].is_empty() {
+        version.push_str(&format!(".{}", &captures[3]));
+    }
+    Some(format!(".NET Framework {}", version))
+}
+
+/// Runtime named by a reference found in installer content
+fn runtime_name(reference: &str) -> Option<String> {
+    let lower = reference.to_lowercase();
+    if let Some(version) = lower.strip_prefix(".netframework,version=v") {
+        return Some(format!(".NET Framework {}", version));
+    }
+    for (prefix, name) in [
+        ("dotnet-runtime-", ".NET Runtime"),
+        ("windowsdesktop-runtime-", ".NET Desktop Runtime"),
+        ("aspnetcore-runtime-", "ASP.NET Core Runtime"),
+    ] {
+        if let Some(version) = lower.strip_prefix(prefix) {
+            return Some(format!("{} {}", name, version));
+        }
+    }
+    if let Some(digits) = lower.strip_prefix("ndp") {
+        let digits = digits.trim_end_matches('-');
+        let version: Vec<String> = digits.chars().map(String::from).collect();
+        return Some(format!(".NET Framework {}", version.join(".")));
+    }
+    if lower.starts_with("vc") && lower.ends_with(".exe") {
+        let architecture = lower.split('.').nth(1)?;
+        return Some(format!("Visual C++ Redistributable ({})", architecture));
+    }
+    if lower.starts_with("microsoft visual c++") {
+        let version = reference.split_whitespace().nth(3)?;
+        return Some(format!("Visual C++ {} Redistributable", version));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{DependencyGraph, InstallerMetadata, PackageDependency};
+    use std::io::Write;
+
+    fn result(format: InstallerFormat, properties: &[(&str, &str)]) -> AnalysisResult {
+        AnalysisResult {
+            session_id: uuid::Uuid::new_v4(),
+            metadata: InstallerMetadata {
+                format,
+                product_name: Some("App".to_string()),
+                created_at: chrono::Utc::now(),
+                properties: properties
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+                ..Default::default()
+            },
+            analyzed_at: chrono::Utc::now(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_requirements_from_result() {
+        let msi = result(
+            InstallerFormat::MSI,
+            &[(
+                "msi_launch_conditions",
+                "Requires Windows 7 [VersionNT >= 601]; 64-bit only [VersionNT64]; Install .NET 4.7.2 [NETFRAMEWORK45 >= \"#461808\"]",
+            )],
+        );
+        let requirements = SystemRequirements::from_result(&msi);
+        assert_eq!(
+            requirements.operating_system,
+            vec!["Windows 7 or later (MSI launch condition)"]
+        );
+        assert_eq!(
+            requirements.architecture,
+            vec!["64-bit Windows (MSI launch condition)"]
+        );
+        assert_eq!(
+            requirements.runtimes,
+            vec![".NET Framework 4.7.2 (MSI launch condition)"]
+        );
+        assert_eq!(requirements.conditions.len(), 3);
+
+        let mut msix = result(
+            InstallerFormat::MSIX,
+            &[
+                (
+                    "msix_target_device_families",
+                    "Windows.Desktop 10.0.17763.0 (tested 10.0.22621.0)",
+                ),
+                ("msix_processor_architecture", "x64"),
+            ],
+        );
+        msix.dependencies = Some(DependencyGraph::from_dependencies(
+            "App",
+            None,
+            &[PackageDependency {
+                name: "Microsoft.VCLibs.140.00".to_string(),
+                version_spec: Some(">=14.0.24217.0".to_string()),
+                condition: None,
+                optional: false,
+            }],
+        ));
+        let requirements = SystemRequirements::from_result(&msix);
+        assert_eq!(
+            requirements.operating_system,
+            vec!["Windows 10 (build 17763) or later (Windows.Desktop), tested up to 10.0.22621.0"]
+        );
+        assert_eq!(
+            requirements.runtimes,
+            vec!["Microsoft.VCLibs.140.00 >=14.0.24217.0 (package dependency)"]
+        );
+
+        let mut roundtrip = msix.clone();
+        requirements.insert_properties(&mut roundtrip);
+        assert_eq!(
+            SystemRequirements::from_properties(&roundtrip.metadata.properties),
+            requirements
+        );
+    }
+
+    #[tokio::test]
+    async fn test_detect_pe_and_runtime_references() {
+        // PE32+ x64 header requiring subsystem 6.1, with UTF-16 and ASCII runtime references
+        let mut data = vec![0u8; 0x200];
+        data[..2].copy_from_slice(b"MZ");
+        data[0x3c..0x40].copy_from_slice(&0x80u32.to_le_bytes());
+        data[0x80..0x84].copy_from_slice(b"PE\0\0");
+        data[0x84..0x86].copy_from_slice(&0x8664u16.to_le_bytes());
+        data[0x94..0x96].copy_from_slice(&240u16.to_le_bytes());
+        data[0x98 + 48..0x98 + 50].copy_from_slice(&6u16.to_le_bytes());
+        data[0x98 + 50..0x98 + 52].copy_from_slice(&1u16.to_le_bytes());
+        data.extend(
+            "Downloading windowsdesktop-runtime-8.0.1"
+                .encode_utf16()
+                .flat_map(u16::to_le_bytes),
+        );
+        data.extend(b" VC_redist.x64.exe /quiet ");
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&data).unwrap();
+        let requirements =
+            SystemRequirements::detect(file.path(), &result(InstallerFormat::NSIS, &[]))
+                .await
+                .unwrap();
+
+        assert_eq!(
+            requirements.operating_system,
+            vec!["Windows 7 or later (setup executable)"]
+        );
+        assert_eq!(requirements.architecture, vec!["x64 (setup executable)"]);
+        assert_eq!(
+            requirements.runtimes,
+            vec![
+                ".NET Desktop Runtime 8.0.1 (referenced by the installer)",
+                "Visual C++ Redistributable (x64) (referenced by the installer)",
+            ]
+        );
+        assert!(requirements.notes.is_empty());
+    }
+}
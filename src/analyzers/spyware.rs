@@ -0,0 +1,303 @@
+//! Detection of spyware indicators
+//!
+//! Installers rarely need to read the clipboard, hook the keyboard or
+//! capture the screen, but keyloggers, clipboard stealers and screen
+//! grabbers bundled with them do. Payloads are flagged when they import or
+//! resolve such APIs by name, and calls observed while the installer ran in
+//! the sandbox are merged in. `SetWindowsHookEx` also installs mouse and
+//! window hooks, so observed calls only count with a keyboard hook type.
+
+use crate::analyzers::architecture::is_pe_payload;
+use crate::analyzers::common::scanner::SourceScanner;
+use crate::core::{AnalysisResult, ApiCall};
+use regex::bytes::Regex;
+use std::fmt;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Capability an indicator points to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SpywareCapability {
+    ClipboardRead,
+    KeyboardHook,
+    ScreenCapture,
+}
+
+impl SpywareCapability {
+    /// Display name of the capability
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ClipboardRead => "Clipboard read",
+            Self::KeyboardHook => "Keyboard hook",
+            Self::ScreenCapture => "Screen capture",
+        }
+    }
+}
+
+impl fmt::Display for SpywareCapability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// APIs that indicate a capability, by exported name
+const SPYWARE_APIS: &[(&str, SpywareCapability)] = &[
+    ("GetClipboardData", SpywareCapability::ClipboardRead),
+    (
+        "AddClipboardFormatListener",
+        SpywareCapability::ClipboardRead,
+    ),
+    ("SetClipboardViewer", SpywareCapability::ClipboardRead),
+    ("SetWindowsHookExA", SpywareCapability::KeyboardHook),
+    ("SetWindowsHookExW", SpywareCapability::KeyboardHook),
+    ("PrintWindow", SpywareCapability::ScreenCapture),
+    // System.Drawing.Graphics.CopyFromScreen of .NET payloads
+    ("CopyFromScreen", SpywareCapability::ScreenCapture),
+];
+
+/// Hook types of `SetWindowsHookEx` that capture keystrokes
+const KEYBOARD_HOOKS: &[&str] = &["WH_KEYBOARD", "WH_KEYBOARD_LL", "2", "13"];
+
+/// How an indicator was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpywareOrigin {
+    /// Imported or referenced by name in a payload
+    Static,
+    /// Called while the installer ran
+    Runtime,
+    /// Referenced statically and called at runtime
+    Both,
+}
+
+/// API usage that points to a spyware capability
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpywareIndicator {
+    pub capability: SpywareCapability,
+    pub api: &'static str,
+    /// `installer`, the path of the packaged file, or the calling process
+    pub source: String,
+    pub origin: SpywareOrigin,
+}
+
+/// Spyware indicators of an installer
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SpywareReport {
+    pub indicators: Vec<SpywareIndicator>,
+}
+
+impl SpywareReport {
+    /// Scan content of one source
+    pub fn scan(source: &str, data: &[u8]) -> Self {
+        let mut report = Self::default();
+        report.add_source(source, data);
+        report
+    }
+
+    fn add(&mut self, indicator: SpywareIndicator) {
+        match self
+            .indicators
+            .iter_mut()
+            .find(|existing| existing.api == indicator.api)
+        {
+            Some(existing) if existing.origin != indicator.origin => {
+                existing.origin = SpywareOrigin::Both;
+            }
+            Some(_) => {}
+            None => self.indicators.push(indicator),
+        }
+    }
+
+    /// Merge API calls captured at runtime
+    pub fn merge_observed(&mut self, calls: &[ApiCall]) {
+        for call in calls {
+            let Some((api, capability)) = SPYWARE_APIS.iter().find(|(api, _)| *api == call.api)
+            else {
+                continue;
+            };
+            let keyboard = call
+                .argument
+                .as_deref()
+                .is_none_or(|hook| KEYBOARD_HOOKS.contains(&hook));
+            if *capability == SpywareCapability::KeyboardHook && !keyboard {
+                continue;
+            }
+            self.add(SpywareIndicator {
+                capability: *capability,
+                api,
+                source: call.process_name.clone(),
+                origin: SpywareOrigin::Runtime,
+            });
+        }
+    }
+
+    /// Capabilities the indicators point to
+    pub fn capabilities(&self) -> Vec<SpywareCapability> {
+        let mut capabilities: Vec<SpywareCapability> = self
+            .indicators
+            .iter()
+            .map(|indicator| indicator.capability)
+            .collect();
+        capabilities.sort();
+        capabilities.dedup();
+        capabilities
+    }
+
+    fn describe_capabilities(&self) -> String {
+        let capabilities: Vec<&str> = self
+            .capabilities()
+            .iter()
+            .map(SpywareCapability::as_str)
+            .collect();
+        capabilities.join(", ")
+    }
+
+    /// Warning for installers with spyware indicators
+    pub fn warning(&self) -> Option<String> {
+        (!self.indicators.is_empty()).then(|| {
+            format!(
+                "Spyware indicators in installer payloads: {}",
+                self.describe_capabilities()
+            )
+        })
+    }
+
+    /// Record the capabilities and their evidence as analysis properties
+    pub fn insert_properties(&self, result: &mut AnalysisResult) {
+        if self.indicators.is_empty() {
+            return;
+        }
+
+        let mut indicators: Vec<&SpywareIndicator> = self.indicators.iter().collect();
+        indicators.sort_by_key(|indicator| indicator.capability);
+        let descriptions: Vec<String> = indicators
+            .iter()
+            .take(15)
+            .map(|indicator| {
+                let origin = match indicator.origin {
+                    SpywareOrigin::Static => "",
+                    SpywareOrigin::Runtime => ", runtime",
+                    SpywareOrigin::Both => ", static and runtime",
+                };
+                format!(
+                    "{}: {} ({}{})",
+                    indicator.capability, indicator.api, indicator.source, origin
+                )
+            })
+            .collect();
+        let properties = &mut result.metadata.properties;
+        properties.insert(
+            "spyware_capabilities".to_string(),
+            self.describe_capabilities(),
+        );
+        properties.insert("spyware_indicators".to_string(), descriptions.join("; "));
+    }
+}
+
+impl SourceScanner for SpywareReport {
+    fn wants(&self, path: &Path) -> bool {
+        is_pe_payload(path)
+    }
+
+    fn add_source(&mut self, source: &str, data: &[u8]) {
+        for captures in matcher().captures_iter(data) {
+            let name = String::from_utf8_lossy(&captures[1]);
+            if let Some((api, capability)) = SPYWARE_APIS.iter().find(|(api, _)| *api == name) {
+                self.add(SpywareIndicator {
+                    capability: *capability,
+                    api,
+                    source: source.to_string(),
+                    origin: SpywareOrigin::Static,
+                });
+            }
+        }
+    }
+}
+
+/// Matcher of the API names as whole NUL-terminated strings, as found in
+/// import tables, `GetProcAddress` arguments and .NET metadata
+fn matcher() -> &'static Regex {
+    static MATCHER: OnceLock<Regex> = OnceLock::new();
+    MATCHER.get_or_init(|| {
+        let names: Vec<String> = SPYWARE_APIS
+            .iter()
+            .map(|(api, _)| regex::escape(api))
+            .collect();
+        Regex::new(&format!(
+            r"(?-u)(?:^|[^A-Za-z0-9_])({})\x00",
+            names.join("|")
+        ))
+        .expect("valid spyware pattern")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(api: &str, argument: Option<&str>) -> ApiCall {
+        ApiCall {
+            api: api.to_string(),
+            argument: argument.map(str::to_string),
+            process_name: "agent.exe".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_detect_spyware_imports() {
+        let clean = SpywareReport::scan(
+            "installer",
+            b"USER32.dll\0\x12\x01SetClipboardData\0MyGetClipboardDataHelper\0",
+        );
+        assert!(clean.indicators.is_empty());
+        assert!(clean.warning().is_none());
+
+        let binary = b"USER32.dll\0\x41\x02GetClipboardData\0\x9c\x03SetWindowsHookExW\0\
+                       System.Drawing\0CopyFromScreen\0GetClipboardData\0";
+        let report = SpywareReport::scan("bin/agent.exe", binary);
+        let apis: Vec<&str> = report.indicators.iter().map(|i| i.api).collect();
+        assert_eq!(
+            apis,
+            vec!["GetClipboardData", "SetWindowsHookExW", "CopyFromScreen"]
+        );
+        assert_eq!(
+            report.warning().unwrap(),
+            "Spyware indicators in installer payloads: Clipboard read, Keyboard hook, Screen capture"
+        );
+    }
+
+    #[test]
+    fn test_merge_observed_calls() {
+        let mut report = SpywareReport::scan("bin/agent.exe", b"SetWindowsHookExW\0");
+        report.merge_observed(&[
+            call("SetWindowsHookExW", Some("WH_KEYBOARD_LL")),
+            call("SetWindowsHookExA", Some("WH_CBT")),
+            call("GetClipboardData", None),
+            call("CreateFileW", None),
+        ]);
+        let found: Vec<(&str, SpywareOrigin)> = report
+            .indicators
+            .iter()
+            .map(|indicator| (indicator.api, indicator.origin))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("SetWindowsHookExW", SpywareOrigin::Both),
+                ("GetClipboardData", SpywareOrigin::Runtime)
+            ]
+        );
+
+        let mut result = AnalysisResult::default();
+        report.insert_properties(&mut result);
+        let properties = &result.metadata.properties;
+        assert_eq!(
+            properties["spyware_capabilities"],
+            "Clipboard read, Keyboard hook"
+        );
+        assert_eq!(
+            properties["spyware_indicators"],
+            "Clipboard read: GetClipboardData (agent.exe, runtime); \
+             Keyboard hook: SetWindowsHookExW (bin/agent.exe, static and runtime)"
+        );
+    }
+}
@@ -1,11 +1,37 @@
 //! Squirrel analyzer implementation
 
-use crate::analyzers::{common, InstallerAnalyzer, NsisAnalyzer};
-use crate::core::{FileEntry, InstallerFormat, InstallerMetadata, RegistryOperation, Result};
+use crate::analyzers::squirrel::releases::SquirrelPackage;
+use crate::analyzers::{common, DetectionContext, InstallerAnalyzer, NsisAnalyzer};
+use crate::core::{
+    AnalyzerCapabilities, FileEntry, InstallerFormat, InstallerMetadata, RegistryOperation, Result,
+    SupportLevel,
+};
 use async_trait::async_trait;
 use chrono::Utc;
 use std::path::Path;
 
+/// Squirrel-specific strings searched for during detection
+const SQUIRREL_PATTERNS: [&str; 18] = [
+    "Squirrel",
+    "electron-builder",
+    "electron-updater",
+    "Update.exe",
+    "SquirrelSetup",
+    "app-update.yml",
+    "latest.yml",
+    "RELEASES",
+    "nupkg",
+    "Electron",
+    "electron.exe",
+    "resources\\app.asar",
+    "resources/app.asar",
+    "autoUpdater",
+    "checkForUpdates",
+    "quitAndInstall",
+    "GitHub\\SquirrelTemp",
+    "GitHub/SquirrelTemp",
+];
+
 /// Squirrel installer analyzer
 ///
 /// Squirrel is used by Electron applications for auto-updating.
@@ -22,37 +48,15 @@ impl SquirrelAnalyzer {
         }
     }
 
-    /// Check if file is a Squirrel installer
-    async fn is_squirrel_installer(file_path: &Path) -> Result<bool> {
+    /// Check if the file under detection is a Squirrel installer
+    async fn is_squirrel_installer(&self, context: &DetectionContext) -> Result<bool> {
         // First check if it's an NSIS file
-        let nsis_analyzer = NsisAnalyzer::new();
-        if !nsis_analyzer.can_analyze(file_path).await? {
+        if !self.nsis_analyzer.can_analyze_with(context).await? {
             return Ok(false);
         }
 
         // Check for Squirrel-specific patterns
-        let squirrel_patterns = [
-            "Squirrel",
-            "electron-builder",
-            "electron-updater",
-            "Update.exe",
-            "SquirrelSetup",
-            "app-update.yml",
-            "latest.yml",
-            "RELEASES",
-            "nupkg",
-            "Electron",
-            "electron.exe",
-            "resources\\app.asar",
-            "resources/app.asar",
-            "autoUpdater",
-            "checkForUpdates",
-            "quitAndInstall",
-            "GitHub\\SquirrelTemp",
-            "GitHub/SquirrelTemp",
-        ];
-
-        let matches = common::search_file_content(file_path, &squirrel_patterns).await?;
+        let matches = context.find_patterns(&SQUIRREL_PATTERNS).await?;
         Ok(!matches.is_empty())
     }
 
@@ -68,6 +72,21 @@ impl SquirrelAnalyzer {
         let squirrel_properties = self.extract_squirrel_properties(file_path).await?;
         metadata.properties.extend(squirrel_properties);
 
+        if let Some(package) = SquirrelPackage::read(file_path).await? {
+            package.insert_properties(&mut metadata.properties);
+            if let Some(nuspec) = &package.nuspec {
+                if metadata.product_name.is_none() {
+                    metadata.product_name = nuspec.title.clone().or(Some(nuspec.id.clone()));
+                }
+                if metadata.product_version.is_none() {
+                    metadata.product_version = Some(nuspec.version.clone());
+                }
+                if metadata.manufacturer.is_none() {
+                    metadata.manufacturer = nuspec.authors.clone();
+                }
+            }
+        }
+
         Ok(metadata)
     }
 
@@ -115,15 +134,8 @@ impl SquirrelAnalyzer {
 
         // This is a simplified detection - in reality, you'd need to parse
         // the package.json or version info from the embedded resources
-        for pattern in &version_patterns {
-            let matches = common::search_file_content(file_path, &[pattern]).await?;
-            if !matches.is_empty() {
-                // Try to extract version number (simplified)
-                return Ok(Some("Unknown".to_string()));
-            }
-        }
-
-        Ok(None)
+        let matches = common::scan_file_patterns(file_path, &version_patterns).await?;
+        Ok((!matches.is_empty()).then(|| "Unknown".to_string()))
     }
 
     /// Detect Squirrel type
@@ -135,14 +147,14 @@ impl SquirrelAnalyzer {
             ("autoUpdater", "Electron autoUpdater"),
         ];
 
-        for (pattern, squirrel_type) in &type_patterns {
-            let matches = common::search_file_content(file_path, &[pattern]).await?;
-            if !matches.is_empty() {
-                return Ok(Some(squirrel_type.to_string()));
-            }
-        }
-
-        Ok(Some("Generic Squirrel".to_string()))
+        let patterns: Vec<&str> = type_patterns.iter().map(|(pattern, _)| *pattern).collect();
+        let matches = common::scan_file_patterns(file_path, &patterns).await?;
+        Ok(Some(
+            matches
+                .first_of(&type_patterns)
+                .unwrap_or("Generic Squirrel")
+                .to_string(),
+        ))
     }
 
     /// Detect update mechanism
@@ -156,14 +168,12 @@ impl SquirrelAnalyzer {
             ("nupkg", "NuGet packages"),
         ];
 
-        for (pattern, mechanism) in &mechanism_patterns {
-            let matches = common::search_file_content(file_path, &[pattern]).await?;
-            if !matches.is_empty() {
-                return Ok(Some(mechanism.to_string()));
-            }
-        }
-
-        Ok(None)
+        let patterns: Vec<&str> = mechanism_patterns
+            .iter()
+            .map(|(pattern, _)| *pattern)
+            .collect();
+        let matches = common::scan_file_patterns(file_path, &patterns).await?;
+        Ok(matches.first_of(&mechanism_patterns).map(str::to_string))
     }
 
     /// Detect application framework
@@ -177,14 +187,17 @@ impl SquirrelAnalyzer {
             ("node_modules", "Node.js"),
         ];
 
-        for (pattern, framework) in &framework_patterns {
-            let matches = common::search_file_content(file_path, &[pattern]).await?;
-            if !matches.is_empty() {
-                return Ok(Some(framework.to_string()));
-            }
-        }
-
-        Ok(Some("Electron".to_string()))
+        let patterns: Vec<&str> = framework_patterns
+            .iter()
+            .map(|(pattern, _)| *pattern)
+            .collect();
+        let matches = common::scan_file_patterns(file_path, &patterns).await?;
+        Ok(Some(
+            matches
+                .first_of(&framework_patterns)
+                .unwrap_or("Electron")
+                .to_string(),
+        ))
     }
 
     /// Extract Squirrel-specific files
@@ -192,6 +205,16 @@ impl SquirrelAnalyzer {
         // Start with NSIS file extraction
         let mut files = self.nsis_analyzer.extract_files(file_path).await?;
 
+        // Files of the full package replace the guessed ones
+        let package_files = SquirrelPackage::read(file_path)
+            .await?
+            .map(|package| package.file_entries())
+            .unwrap_or_default();
+        if !package_files.is_empty() {
+            files.extend(package_files);
+            return Ok(files);
+        }
+
         // Add common Squirrel/Electron files that might be present
         let squirrel_files = [
             ("Update.exe", 1024 * 1024, true),
@@ -220,6 +243,10 @@ impl SquirrelAnalyzer {
                     executable: *executable,
                 },
                 compression: Some("NSIS".to_string()),
+                is_speculative: true,
+                architecture: None,
+                modified: None,
+                source: None,
             });
         }
 
@@ -234,7 +261,10 @@ impl SquirrelAnalyzer {
             .extract_registry_operations(file_path)
             .await?;
 
-        // Add common Squirrel registry entries
+        // Add common Squirrel registry entries, under the package ID when known
+        let app_id = SquirrelPackage::read(file_path)
+            .await?
+            .and_then(|package| package.package_id().map(str::to_string));
         let squirrel_registry_ops = [
             ("HKEY_CURRENT_USER\\Software\\Microsoft\\Windows\\CurrentVersion\\Uninstall\\[AppId]", "DisplayName"),
             ("HKEY_CURRENT_USER\\Software\\Microsoft\\Windows\\CurrentVersion\\Uninstall\\[AppId]", "DisplayVersion"),
@@ -246,12 +276,17 @@ impl SquirrelAnalyzer {
         ];
 
         for (key_path, value_name) in &squirrel_registry_ops {
+            let key_path = match &app_id {
+                Some(id) => key_path.replace("[AppId]", id).replace("[AppName]", id),
+                None => key_path.to_string(),
+            };
             operations.push(RegistryOperation::SetValue {
-                key_path: key_path.to_string(),
+                key_path,
                 value_name: value_name.to_string(),
                 value_type: crate::core::RegistryValueType::String,
                 value_data: crate::core::RegistryValue::String("[Squirrel Value]".to_string()),
                 timestamp: Utc::now(),
+                process_id: None,
             });
         }
 
@@ -262,17 +297,35 @@ impl SquirrelAnalyzer {
 #[async_trait]
 impl InstallerAnalyzer for SquirrelAnalyzer {
     async fn can_analyze(&self, file_path: &Path) -> Result<bool> {
-        // Validate file accessibility
-        common::validate_file(file_path).await?;
+        self.can_analyze_with(&DetectionContext::new(file_path).await?)
+            .await
+    }
 
+    async fn can_analyze_with(&self, context: &DetectionContext) -> Result<bool> {
         // Check if it's a Squirrel installer
-        Self::is_squirrel_installer(file_path).await
+        self.is_squirrel_installer(context).await
+    }
+
+    fn detection_patterns(&self) -> Vec<&'static str> {
+        let mut patterns = self.nsis_analyzer.detection_patterns();
+        patterns.extend(SQUIRREL_PATTERNS);
+        patterns
     }
 
     fn format(&self) -> InstallerFormat {
         InstallerFormat::Squirrel
     }
 
+    fn capabilities(&self) -> AnalyzerCapabilities {
+        AnalyzerCapabilities {
+            metadata: SupportLevel::Partial,
+            file_extraction: SupportLevel::Heuristic,
+            registry_operations: SupportLevel::Heuristic,
+            install_scripts: SupportLevel::Unsupported,
+            signatures: SupportLevel::Unsupported,
+        }
+    }
+
     async fn extract_metadata(&self, file_path: &Path) -> Result<InstallerMetadata> {
         // Validate file first
         common::validate_file(file_path).await?;
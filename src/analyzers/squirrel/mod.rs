@@ -1,6 +1,8 @@
 //! Squirrel format analyzer for Electron application installers
 
 pub mod analyzer;
+pub mod releases;
 
 // Re-export main components
 pub use analyzer::SquirrelAnalyzer;
+pub use releases::{ReleaseEntry, SquirrelPackage};
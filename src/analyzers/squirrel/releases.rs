@@ -0,0 +1,503 @@
+//! Squirrel.Windows release packages
+//!
+//! `Setup.exe` of Squirrel.Windows carries a ZIP archive with `Update.exe`,
+//! a `RELEASES` file and the full NuGet package of the current version. The
+//! `RELEASES` file lists one package per line as `<SHA1> <file name> <size>`,
+//! with file names `<id>-<version>-full.nupkg` or `-delta.nupkg`; a release
+//! directory next to the installer holds the same file covering every
+//! published version. The application files are the `lib/<framework>/`
+//! entries of the full package, and `Update.exe --install` puts them under
+//! `%LocalAppData%\<id>\app-<version>`.
+
+use crate::analyzers::carving::{EmbeddedKind, EmbeddedPayload};
+use crate::core::{AnalyzerError, FileAttributes, FileEntry, Result};
+use crate::utils::format_file_size;
+use std::collections::HashMap;
+use std::io::{Cursor, Read, Seek};
+use std::path::{Path, PathBuf};
+use zip::ZipArchive;
+
+/// Name of the release list, in the setup archive and in release directories
+const RELEASES_FILE: &str = "RELEASES";
+
+/// What `Update.exe` does with the packages, for the report
+const UPDATE_BEHAVIOR: &str =
+    "Update.exe --install extracts the full package to app-<version> and \
+runs the app with --squirrel-install and --squirrel-firstrun; updates are applied side by side as \
+app-<new version> from full or delta packages, shortcuts start the app through \
+Update.exe --processStart, and --uninstall runs --squirrel-uninstall before removing the directory";
+
+/// Package listed in a `RELEASES` file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReleaseEntry {
+    /// Hex-encoded SHA1 of the package
+    pub sha1: String,
+    pub file_name: String,
+    pub size: u64,
+    /// Package ID and version from the file name
+    pub package_id: Option<String>,
+    pub version: Option<String>,
+    /// Package holds the differences to the previous version only
+    pub is_delta: bool,
+}
+
+impl ReleaseEntry {
+    /// Parse a `<SHA1> <file name> <size>` line
+    ///
+    /// Staged rollouts prefix lines with a `# <percentage>%` comment, which
+    /// is skipped.
+    fn parse_line(line: &str) -> Option<Self> {
+        let line = match line.trim().strip_prefix('#') {
+            Some(staged) => staged.trim_start().split_once(' ')?.1,
+            None => line.trim(),
+        };
+        let mut fields = line.split_whitespace();
+        let sha1 = fields.next()?;
+        let file_name = fields.next()?;
+        let size = fields.next()?.parse().ok()?;
+        if sha1.len() != 40 || !sha1.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+
+        // Strip a URL of packages hosted elsewhere
+        let file_name = file_name.rsplit('/').next().unwrap_or(file_name);
+        let stem = file_name.strip_suffix(".nupkg")?;
+        let (stem, is_delta) = match stem.strip_suffix("-delta") {
+            Some(stem) => (stem, true),
+            None => (stem.strip_suffix("-full").unwrap_or(stem), false),
+        };
+        // IDs may contain dashes, versions start with a digit
+        let (package_id, version) = stem
+            .match_indices('-')
+            .find(|(index, _)| stem[index + 1..].starts_with(|c: char| c.is_ascii_digit()))
+            .map(|(index, _)| (stem[..index].to_string(), stem[index + 1..].to_string()))
+            .unzip();
+
+        Some(Self {
+            sha1: sha1.to_lowercase(),
+            file_name: file_name.to_string(),
+            size,
+            package_id,
+            version,
+            is_delta,
+        })
+    }
+
+    /// Version for ordering; unparsable versions sort first
+    fn semver(&self) -> Option<semver::Version> {
+        semver::Version::parse(self.version.as_deref()?).ok()
+    }
+}
+
+/// Parse the lines of a `RELEASES` file, oldest version first
+pub fn parse_releases(content: &str) -> Vec<ReleaseEntry> {
+    let mut releases: Vec<ReleaseEntry> = content
+        .lines()
+        .filter_map(ReleaseEntry::parse_line)
+        .collect();
+    // Full packages sort before the delta of the same version
+    releases.sort_by(|a, b| {
+        a.semver()
+            .cmp(&b.semver())
+            .then(a.is_delta.cmp(&b.is_delta))
+    });
+    releases
+}
+
+/// Metadata of the `.nuspec` in a NuGet package
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NuspecMetadata {
+    pub id: String,
+    pub version: String,
+    pub title: Option<String>,
+    pub authors: Option<String>,
+    pub description: Option<String>,
+}
+
+impl NuspecMetadata {
+    /// Parse a `.nuspec` manifest, which must name the ID and version
+    pub fn parse(xml: &str) -> Option<Self> {
+        Some(Self {
+            id: element_text(xml, "id")?,
+            version: element_text(xml, "version")?,
+            title: element_text(xml, "title"),
+            authors: element_text(xml, "authors"),
+            description: element_text(xml, "description"),
+        })
+    }
+}
+
+/// Full package of a Squirrel installer and its release chain
+#[derive(Debug, Clone, Default)]
+pub struct SquirrelPackage {
+    /// Packages of the `RELEASES` file, oldest first
+    pub releases: Vec<ReleaseEntry>,
+    /// `RELEASES` was read from a release directory next to the installer
+    pub releases_beside_installer: bool,
+    pub nuspec: Option<NuspecMetadata>,
+    /// Full package the application files were read from, if beside the installer
+    pub package_file: Option<String>,
+    /// Application files relative to `app-<version>`, with their sizes
+    pub app_files: Vec<(String, u64)>,
+    /// Size of `Update.exe` in the setup archive
+    pub update_exe_size: Option<u64>,
+}
+
+impl SquirrelPackage {
+    /// Read the setup archive of `file_path` and a release directory beside it
+    ///
+    /// Returns `None` for installers with neither.
+    pub async fn read(file_path: &Path) -> Result<Option<Self>> {
+        let data = tokio::fs::read(file_path).await?;
+        let mut package = EmbeddedPayload::scan(&data)
+            .into_iter()
+            .filter(|payload| payload.kind == EmbeddedKind::Zip)
+            .find_map(|payload| {
+                let start = payload.offset as usize;
+                Self::from_setup_zip(&data[start..start + payload.size as usize])
+            });
+
+        let Some(dir) = file_path.parent() else {
+            return Ok(package);
+        };
+        let releases_path = dir.join(RELEASES_FILE);
+        if releases_path.is_file() {
+            let content = tokio::fs::read(&releases_path).await?;
+            let releases = parse_releases(&String::from_utf8_lossy(&content));
+            if !releases.is_empty() {
+                let package = package.get_or_insert_with(Self::default);
+                package.releases = releases;
+                package.releases_beside_installer = true;
+                if package.nuspec.is_none() {
+                    package.read_full_package_beside(dir).await?;
+                }
+            }
+        }
+        Ok(package)
+    }
+
+    /// Package of a setup archive holding a `RELEASES` file
+    pub fn from_setup_zip(data: &[u8]) -> Option<Self> {
+        let mut archive = ZipArchive::new(Cursor::new(data)).ok()?;
+        let releases = parse_releases(&read_entry_text(&mut archive, RELEASES_FILE)?);
+        let mut package = Self {
+            update_exe_size: archive.by_name("Update.exe").ok().map(|file| file.size()),
+            ..Self::default()
+        };
+
+        let full = releases
+            .iter()
+            .rev()
+            .find(|release| !release.is_delta)
+            .map(|release| release.file_name.clone());
+        if let Some(nupkg) = full.and_then(|name| read_entry(&mut archive, &name)) {
+            if let Err(e) = package.read_nupkg(&nupkg) {
+                tracing::debug!("Failed to read Squirrel package: {}", e);
+            }
+        }
+        package.releases = releases;
+        Some(package)
+    }
+
+    /// Read the newest full package of a release directory
+    async fn read_full_package_beside(&mut self, dir: &Path) -> Result<()> {
+        let Some(full) = self.releases.iter().rev().find(|release| !release.is_delta) else {
+            return Ok(());
+        };
+        let path = dir.join(&full.file_name);
+        if !path.is_file() {
+            return Ok(());
+        }
+        let file_name = full.file_name.clone();
+        self.read_nupkg(&tokio::fs::read(&path).await?)?;
+        self.package_file = Some(file_name);
+        Ok(())
+    }
+
+    /// Read the manifest and application files of a full package
+    fn read_nupkg(&mut self, nupkg: &[u8]) -> Result<()> {
+        let mut archive = ZipArchive::new(Cursor::new(nupkg))
+            .map_err(|e| AnalyzerError::parse_error(format!("Invalid Squirrel package: {}", e)))?;
+        // Entries in archive order, with their sizes
+        let entries: Vec<(String, u64)> = (0..archive.len())
+            .filter_map(|index| {
+                let file = archive.by_index(index).ok()?;
+                Some((file.name().to_string(), file.size()))
+            })
+            .collect();
+        self.nuspec = entries
+            .iter()
+            .find(|(name, _)| !name.contains('/') && name.ends_with(".nuspec"))
+            .and_then(|(name, _)| read_entry_text(&mut archive, name))
+            .and_then(|xml| NuspecMetadata::parse(&xml));
+
+        for (name, size) in entries {
+            // lib/<framework>/<path>; Squirrel packs a single framework
+            let Some(relative) = name
+                .strip_prefix("lib/")
+                .and_then(|rest| rest.split_once('/'))
+                .map(|(_, relative)| relative)
+            else {
+                continue;
+            };
+            if !relative.is_empty() && !relative.ends_with('/') {
+                self.app_files.push((relative.to_string(), size));
+            }
+        }
+        Ok(())
+    }
+
+    /// Package ID, from the manifest or the release file names
+    pub fn package_id(&self) -> Option<&str> {
+        self.nuspec
+            .as_ref()
+            .map(|nuspec| nuspec.id.as_str())
+            .or_else(|| self.releases.iter().find_map(|r| r.package_id.as_deref()))
+    }
+
+    /// Version the installer installs
+    pub fn version(&self) -> Option<&str> {
+        self.nuspec
+            .as_ref()
+            .map(|nuspec| nuspec.version.as_str())
+            .or_else(|| {
+                self.releases
+                    .iter()
+                    .rev()
+                    .find_map(|r| r.version.as_deref())
+            })
+    }
+
+    /// Root directory of the installation, `%LocalAppData%\<id>`
+    pub fn install_dir(&self) -> Option<String> {
+        Some(format!("%LocalAppData%\\{}", self.package_id()?))
+    }
+
+    /// Directory of the installed version, `%LocalAppData%\<id>\app-<version>`
+    pub fn app_dir(&self) -> Option<String> {
+        Some(format!("{}\\app-{}", self.install_dir()?, self.version()?))
+    }
+
+    /// Packages in version order, like `1.0.0 full -> 1.1.0 delta`
+    pub fn release_chain(&self) -> String {
+        self.releases
+            .iter()
+            .map(|release| {
+                format!(
+                    "{} {} ({})",
+                    release.version.as_deref().unwrap_or(&release.file_name),
+                    if release.is_delta { "delta" } else { "full" },
+                    format_file_size(release.size)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" -> ")
+    }
+
+    /// Properties describing the package, its release chain and `Update.exe`
+    pub fn insert_properties(&self, properties: &mut HashMap<String, String>) {
+        let mut insert = |key: &str, value: String| {
+            properties.insert(key.to_string(), value);
+        };
+        if let Some(id) = self.package_id() {
+            insert("squirrel_package_id", id.to_string());
+        }
+        if let Some(version) = self.version() {
+            insert("squirrel_package_version", version.to_string());
+        }
+        if let Some(nuspec) = &self.nuspec {
+            if let Some(authors) = &nuspec.authors {
+                insert("squirrel_package_authors", authors.clone());
+            }
+        }
+        if !self.releases.is_empty() {
+            let deltas = self.releases.iter().filter(|r| r.is_delta).count();
+            insert("squirrel_release_chain", self.release_chain());
+            insert(
+                "squirrel_full_packages",
+                (self.releases.len() - deltas).to_string(),
+            );
+            insert("squirrel_delta_packages", deltas.to_string());
+            insert(
+                "squirrel_releases_source",
+                if self.releases_beside_installer {
+                    "release directory"
+                } else {
+                    "setup archive"
+                }
+                .to_string(),
+            );
+        }
+        if let Some(dir) = self.app_dir() {
+            insert("squirrel_install_path", dir);
+        }
+        if let Some(dir) = self.install_dir() {
+            insert("squirrel_update_exe", format!("{}\\Update.exe", dir));
+            insert("squirrel_update_behavior", UPDATE_BEHAVIOR.to_string());
+        }
+    }
+
+    /// Files installed from the package, with `Update.exe`
+    ///
+    /// Empty when the full package could not be read.
+    pub fn file_entries(&self) -> Vec<FileEntry> {
+        let (Some(install_dir), Some(app_dir)) = (self.install_dir(), self.app_dir()) else {
+            return Vec::new();
+        };
+        let entry = |path: String, target: String, size: u64, source: Option<String>| {
+            let executable = path.to_lowercase().ends_with(".exe");
+            FileEntry {
+                path: PathBuf::from(path),
+                target_path: Some(PathBuf::from(target)),
+                size,
+                hash: None,
+                attributes: FileAttributes {
+                    executable,
+                    ..FileAttributes::default()
+                },
+                compression: Some("nupkg".to_string()),
+                is_speculative: false,
+                architecture: None,
+                modified: None,
+                source,
+            }
+        };
+
+        let mut files = Vec::new();
+        if let Some(size) = self.update_exe_size {
+            files.push(entry(
+                "Update.exe".to_string(),
+                format!("{}\\Update.exe", install_dir),
+                size,
+                None,
+            ));
+        }
+        for (relative, size) in &self.app_files {
+            files.push(entry(
+                relative.clone(),
+                format!("{}\\{}", app_dir, relative.replace('/', "\\")),
+                *size,
+                self.package_file.clone(),
+            ));
+        }
+        files
+    }
+}
+
+/// Content of an archive entry, `None` if missing or unreadable
+fn read_entry<R: Read + Seek>(archive: &mut ZipArchive<R>, name: &str) -> Option<Vec<u8>> {
+    let mut file = archive.by_name(name).ok()?;
+    let mut content = Vec::new();
+    file.read_to_end(&mut content).ok()?;
+    Some(content)
+}
+
+fn read_entry_text<R: Read + Seek>(archive: &mut ZipArchive<R>, name: &str) -> Option<String> {
+    let content = read_entry(archive, name)?;
+    Some(String::from_utf8_lossy(&content).into_owned())
+}
+
+/// Unescaped, trimmed text of the first `<name>` element, if not empty
+fn element_text(xml: &str, name: &str) -> Option<String> {
+    let open = format!("<{}>", name);
+    let start = xml.find(&open)? + open.len();
+    let end = xml[start..].find(&format!("</{}>", name))?;
+    let text = xml[start..start + end]
+        .trim()
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&");
+    (!text.is_empty()).then_some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const SHA1: &str = "94689fede03fed7ab59c24337673a27837f0c3ec";
+
+    fn zip(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in entries {
+            writer
+                .start_file(*name, zip::write::FileOptions::default())
+                .unwrap();
+            writer.write_all(content).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_parse_releases() {
+        let content = format!(
+            "{sha} My-App-1.10.0-delta.nupkg 2048\n\
+             {sha} My-App-1.2.0-full.nupkg 4096\n\
+             # 20% {sha} My-App-1.10.0-full.nupkg 8192\n\
+             not a release line\n",
+            sha = SHA1.to_uppercase()
+        );
+        let releases = parse_releases(&content);
+        assert_eq!(releases.len(), 3);
+        assert_eq!(releases[0].version.as_deref(), Some("1.2.0"));
+        assert_eq!(releases[0].package_id.as_deref(), Some("My-App"));
+        assert_eq!(releases[0].sha1, SHA1);
+        assert!(!releases[1].is_delta);
+        assert_eq!(releases[1].size, 8192);
+        assert!(releases[2].is_delta);
+    }
+
+    #[test]
+    fn test_setup_zip() {
+        let nuspec = b"<?xml version=\"1.0\"?><package><metadata><id>MyApp</id>\
+            <version>2.1.0</version><authors>Me &amp; You</authors><description /></metadata></package>";
+        let nupkg = zip(&[
+            ("MyApp.nuspec", nuspec),
+            ("lib/net45/MyApp.exe", b"MZ"),
+            ("lib/net45/resources/app.asar", b"asar"),
+            ("package/services/metadata/core-properties/1.psmdcp", b""),
+        ]);
+        let releases = format!("{} MyApp-2.1.0-full.nupkg {}", SHA1, nupkg.len());
+        let setup = zip(&[
+            ("Update.exe", b"MZ.."),
+            ("RELEASES", releases.as_bytes()),
+            ("MyApp-2.1.0-full.nupkg", &nupkg),
+        ]);
+
+        let package = SquirrelPackage::from_setup_zip(&setup).unwrap();
+        let nuspec = package.nuspec.as_ref().unwrap();
+        assert_eq!(nuspec.authors.as_deref(), Some("Me & You"));
+        assert!(nuspec.description.is_none());
+        assert_eq!(
+            package.app_dir().as_deref(),
+            Some("%LocalAppData%\\MyApp\\app-2.1.0")
+        );
+        assert!(package.release_chain().starts_with("2.1.0 full ("));
+
+        let files = package.file_entries();
+        assert_eq!(files.len(), 3);
+        assert_eq!(
+            files[0].target_path.as_deref(),
+            Some(Path::new("%LocalAppData%\\MyApp\\Update.exe"))
+        );
+        assert_eq!(files[0].size, 4);
+        assert!(files[1].attributes.executable);
+        assert_eq!(
+            files[2].target_path.as_deref(),
+            Some(Path::new(
+                "%LocalAppData%\\MyApp\\app-2.1.0\\resources\\app.asar"
+            ))
+        );
+        assert!(files.iter().all(|file| !file.is_speculative));
+
+        let mut properties = HashMap::new();
+        package.insert_properties(&mut properties);
+        assert_eq!(properties["squirrel_package_id"], "MyApp");
+        assert_eq!(properties["squirrel_delta_packages"], "0");
+        assert_eq!(properties["squirrel_releases_source"], "setup archive");
+
+        assert!(SquirrelPackage::from_setup_zip(&zip(&[("other.txt", b"")])).is_none());
+    }
+}
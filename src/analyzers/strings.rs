@@ -0,0 +1,331 @@
+//! String extraction with noise filtering and IOC classification
+//!
+//! Like `strings`, runs of printable ASCII and UTF-16LE characters are
+//! pulled from the installer and from its key payloads (packaged executables
+//! and scripts). Runs that look like padding or random bytes are dropped,
+//! duplicates are merged, and the rest is classified as an indicator where it
+//! looks like a URL, registry key, mutex name or file path. Cryptographic
+//! tables compiled into binaries are found by their byte patterns.
+
+use crate::analyzers::architecture::is_pe_payload;
+use crate::analyzers::common::scanner::SourceScanner;
+use crate::core::{ExtractedString, IocKind, StringAppendix, StringEncoding};
+use regex::Regex;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Shortest run of printable characters that counts as a string
+const MIN_LENGTH: usize = 6;
+/// Longer strings are cut to this many characters
+const MAX_LENGTH: usize = 512;
+/// Upper bound of payloads scanned per installer
+const MAX_PAYLOADS: usize = 50;
+/// Upper bound of strings kept in the appendix; indicators are always kept
+const MAX_STRINGS: usize = 5000;
+
+/// Extensions of scripts scanned next to PE payloads
+const SCRIPT_EXTENSIONS: &[&str] = &["ps1", "psm1", "bat", "cmd", "vbs", "js", "wsf", "hta"];
+
+/// Byte patterns of well-known cryptographic tables and initial states
+const CRYPTO_CONSTANTS: &[(&str, &[u8])] = &[
+    (
+        "AES S-box",
+        b"\x63\x7c\x77\x7b\xf2\x6b\x6f\xc5\x30\x01\x67\x2b",
+    ),
+    (
+        "AES inverse S-box",
+        b"\x52\x09\x6a\xd5\x30\x36\xa5\x38\xbf\x40\xa3\x9e",
+    ),
+    (
+        "MD5/SHA-1 initial state",
+        b"\x01\x23\x45\x67\x89\xab\xcd\xef\xfe\xdc\xba\x98",
+    ),
+    (
+        "SHA-256 round constants",
+        b"\x98\x2f\x8a\x42\x91\x44\x37\x71\xcf\xfb\xc0\xb5",
+    ),
+    (
+        "SHA-512 initial state",
+        b"\x08\xc9\xbc\xf3\x67\xe6\x09\x6a\x3b\xa7\xca\x84",
+    ),
+    (
+        "Blowfish P-array",
+        b"\x88\x6a\x3f\x24\xd3\x08\xa3\x85\x2e\x8a\x19\x13",
+    ),
+    (
+        "CRC-32 table",
+        b"\x00\x00\x00\x00\x96\x30\x07\x77\x2c\x61\x0e\xee",
+    ),
+];
+
+/// Classifier of strings into indicator classes
+struct IocClassifier {
+    patterns: Vec<(IocKind, Regex)>,
+}
+
+impl IocClassifier {
+    fn new() -> Self {
+        let patterns = [
+            (IocKind::Url, r"(?i)\b(?:https?|ftps?|wss?)://[\w.-]+"),
+            (
+                IocKind::RegistryKey,
+                r"(?i)\b(?:HKEY_[A-Z_]+|HKLM|HKCU|HKCR|HKU)\\|^(?:SOFTWARE|SYSTEM)\\(?:Microsoft|Classes|Policies|Wow6432Node|CurrentControlSet)\\",
+            ),
+            (IocKind::Mutex, r"^(?:Global|Local)\\[^\\\s]{3,}$"),
+            (
+                IocKind::FilePath,
+                r"(?i)(?:^|[^\w])[a-z]:\\[^\\]|%[a-z_]+%\\|^\\\\[\w.$-]+\\|^/(?:usr|etc|bin|tmp|var|opt|home)/",
+            ),
+            (IocKind::CryptoConstant, r"^-----BEGIN [A-Z ]+-----"),
+        ];
+        Self {
+            patterns: patterns
+                .into_iter()
+                .map(|(kind, pattern)| (kind, Regex::new(pattern).expect("valid IOC pattern")))
+                .collect(),
+        }
+    }
+
+    fn classify(&self, value: &str) -> Option<IocKind> {
+        self.patterns
+            .iter()
+            .find(|(_, pattern)| pattern.is_match(value))
+            .map(|(kind, _)| *kind)
+    }
+}
+
+/// Accumulates strings of several sources into one deduplicated appendix
+pub struct StringCollector {
+    classifier: IocClassifier,
+    appendix: StringAppendix,
+    /// Position of each value in the appendix
+    index: HashMap<String, usize>,
+}
+
+impl Default for StringCollector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StringCollector {
+    pub fn new() -> Self {
+        Self {
+            classifier: IocClassifier::new(),
+            appendix: StringAppendix::default(),
+            index: HashMap::new(),
+        }
+    }
+
+    fn add(
+        &mut self,
+        source: &str,
+        offset: usize,
+        value: String,
+        encoding: StringEncoding,
+        ioc: Option<IocKind>,
+    ) {
+        if let Some(&position) = self.index.get(&value) {
+            self.appendix.strings[position].occurrences += 1;
+            return;
+        }
+        self.index
+            .insert(value.clone(), self.appendix.strings.len());
+        self.appendix.strings.push(ExtractedString {
+            value,
+            encoding,
+            source: source.to_string(),
+            offset: offset as u64,
+            occurrences: 1,
+            ioc,
+        });
+    }
+
+    /// Order indicators first and apply the size limit
+    pub fn finish(self) -> StringAppendix {
+        let mut appendix = self.appendix;
+        // Stable sort keeps the order of appearance within each class
+        appendix.strings.sort_by_key(|s| (s.ioc.is_none(), s.ioc));
+
+        let indicators = appendix.indicators().count();
+        let limit = MAX_STRINGS.max(indicators);
+        if appendix.strings.len() > limit {
+            appendix.omitted = appendix.strings.len() - limit;
+            appendix.strings.truncate(limit);
+        }
+        appendix
+    }
+}
+
+impl SourceScanner for StringCollector {
+    fn wants(&self, path: &Path) -> bool {
+        is_pe_payload(path) || is_script(path)
+    }
+
+    fn max_payloads(&self) -> usize {
+        MAX_PAYLOADS
+    }
+
+    /// Extract the strings and crypto constants of one source
+    fn add_source(&mut self, source: &str, data: &[u8]) {
+        self.appendix.sources.push(source.to_string());
+
+        for (offset, value, encoding) in printable_runs(data) {
+            let ioc = self.classifier.classify(&value);
+            if ioc.is_none() && is_noise(&value) {
+                self.appendix.filtered += 1;
+                continue;
+            }
+            self.add(source, offset, value, encoding, ioc);
+        }
+
+        for (name, pattern) in CRYPTO_CONSTANTS {
+            if let Some(offset) = find(data, pattern) {
+                self.add(
+                    source,
+                    offset,
+                    name.to_string(),
+                    StringEncoding::Binary,
+                    Some(IocKind::CryptoConstant),
+                );
+            }
+        }
+    }
+}
+
+fn is_script(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| SCRIPT_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+fn is_printable(byte: u8) -> bool {
+    (0x20..0x7f).contains(&byte) || byte == b'\t'
+}
+
+/// Runs of printable ASCII and UTF-16LE characters with their offsets
+pub(crate) fn printable_runs(data: &[u8]) -> Vec<(usize, String, StringEncoding)> {
+    let mut runs = Vec::new();
+    let mut push = |start: usize, text: String, encoding: StringEncoding| {
+        if text.len() >= MIN_LENGTH {
+            let value: String = text.trim().chars().take(MAX_LENGTH).collect();
+            if value.len() >= MIN_LENGTH {
+                runs.push((start, value, encoding));
+            }
+        }
+    };
+
+    let mut start = 0;
+    let mut text = String::new();
+    for (offset, &byte) in data.iter().enumerate() {
+        if is_printable(byte) {
+            if text.is_empty() {
+                start = offset;
+            }
+            text.push(byte as char);
+        } else if !text.is_empty() {
+            push(start, std::mem::take(&mut text), StringEncoding::Ascii);
+        }
+    }
+    if !text.is_empty() {
+        push(start, std::mem::take(&mut text), StringEncoding::Ascii);
+    }
+
+    // UTF-16LE text of Latin characters, at even and odd alignment
+    for alignment in 0..2 {
+        let mut offset = alignment;
+        while offset + 1 < data.len() {
+            let (low, high) = (data[offset], data[offset + 1]);
+            if high == 0 && is_printable(low) {
+                if text.is_empty() {
+                    start = offset;
+                }
+                text.push(low as char);
+            } else if !text.is_empty() {
+                push(start, std::mem::take(&mut text), StringEncoding::Utf16);
+            }
+            offset += 2;
+        }
+        if !text.is_empty() {
+            push(start, std::mem::take(&mut text), StringEncoding::Utf16);
+        }
+    }
+
+    runs
+}
+
+/// Check whether a string is likely padding, code bytes or random data
+fn is_noise(value: &str) -> bool {
+    let length = value.chars().count();
+    let alphanumeric = value.chars().filter(char::is_ascii_alphanumeric).count();
+    let mut distinct: Vec<char> = value.chars().collect();
+    distinct.sort_unstable();
+    distinct.dedup();
+
+    // Text has words: a run of three letters and mostly alphanumerics
+    let has_word = value
+        .as_bytes()
+        .windows(3)
+        .any(|w| w.iter().all(u8::is_ascii_alphabetic));
+
+    alphanumeric * 10 < length * 6 || distinct.len() < 4 || !has_word
+}
+
+fn find(data: &[u8], pattern: &[u8]) -> Option<usize> {
+    data.windows(pattern.len())
+        .position(|window| window == pattern)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzers::common::scanner::INSTALLER_SOURCE;
+
+    #[test]
+    fn test_extract_and_classify_strings() {
+        let mut data = b"\x00\x01http://updates.example.com/setup.exe\x00\xff".to_vec();
+        data.extend(b"SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Run\x00");
+        data.extend(b"Global\\ExampleSetupMutex\x00");
+        data.extend(b"%APPDATA%\\Example\\agent.dll\x00");
+        data.extend(b"Welcome to the Example setup\x00");
+        // Noise: padding and code bytes
+        data.extend(b"AAAAAAAAAAAA\x00$!#@%^&*()_+\x00\x00\x00");
+        data.extend(
+            "Another installation is running"
+                .encode_utf16()
+                .flat_map(u16::to_le_bytes),
+        );
+        data.extend([0, 0]);
+        data.extend(b"\x63\x7c\x77\x7b\xf2\x6b\x6f\xc5\x30\x01\x67\x2b");
+
+        let mut collector = StringCollector::new();
+        collector.add_source(INSTALLER_SOURCE, &data);
+        collector.add_source("bin/agent.dll", b"Welcome to the Example setup");
+        let appendix = collector.finish();
+
+        let kinds: Vec<(Option<IocKind>, &str)> = appendix
+            .strings
+            .iter()
+            .map(|s| (s.ioc, s.value.as_str()))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (Some(IocKind::Url), "http://updates.example.com/setup.exe"),
+                (
+                    Some(IocKind::RegistryKey),
+                    "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Run"
+                ),
+                (Some(IocKind::Mutex), "Global\\ExampleSetupMutex"),
+                (Some(IocKind::FilePath), "%APPDATA%\\Example\\agent.dll"),
+                (Some(IocKind::CryptoConstant), "AES S-box"),
+                (None, "Welcome to the Example setup"),
+                (None, "Another installation is running"),
+            ]
+        );
+        assert_eq!(appendix.strings[5].occurrences, 2);
+        assert_eq!(appendix.strings[6].encoding, StringEncoding::Utf16);
+        assert_eq!(appendix.filtered, 2);
+        assert_eq!(appendix.sources, vec!["installer", "bin/agent.dll"]);
+    }
+}
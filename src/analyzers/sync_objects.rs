@@ -0,0 +1,317 @@
+//! Extraction of named synchronization objects
+//!
+//! Mutex, event, semaphore and named pipe names are common threat-intel
+//! indicators, and a mutex held by another process is what makes a setup
+//! fail with "another instance is running". Names are taken from the
+//! installer and its packaged binaries and scripts: prefixed object names
+//! (`Global\`, `Local\`, `\\.\pipe\`), string arguments of scripted calls
+//! such as NSIS `System::Call 'kernel32::CreateMutex(i 0, i 0, t "name")'`,
+//! Inno Setup `AppMutex` directives and, in binaries that import a mutex
+//! API, strings named like a mutex. Objects observed while the installer
+//! ran in the sandbox are merged in.
+
+use crate::analyzers::architecture::is_pe_payload;
+use crate::analyzers::common::scanner::SourceScanner;
+use crate::analyzers::strings::printable_runs;
+use crate::core::{AnalysisResult, SyncObject, SyncObjectKind, SyncObjectOrigin};
+use regex::Regex;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// APIs that create or open named objects, by object kind
+const SYNC_APIS: &[(&str, SyncObjectKind)] = &[
+    ("CreateMutex", SyncObjectKind::Mutex),
+    ("OpenMutex", SyncObjectKind::Mutex),
+    ("CreateEvent", SyncObjectKind::Event),
+    ("OpenEvent", SyncObjectKind::Event),
+    ("CreateSemaphore", SyncObjectKind::Semaphore),
+    ("OpenSemaphore", SyncObjectKind::Semaphore),
+    ("CreateNamedPipe", SyncObjectKind::NamedPipe),
+    ("CallNamedPipe", SyncObjectKind::NamedPipe),
+    ("WaitNamedPipe", SyncObjectKind::NamedPipe),
+];
+
+/// Messages shown when a single-instance mutex is already held
+const INSTANCE_MESSAGES: &[&str] = &[
+    "already running",
+    "another instance",
+    "Another instance",
+    "another copy",
+];
+
+/// Patterns that yield object names from strings
+struct NamePatterns {
+    script_call: Regex,
+    inno_directive: Regex,
+    pipe: Regex,
+    prefixed: Regex,
+    mutex_like: Regex,
+}
+
+impl NamePatterns {
+    /// Patterns compiled on first use
+    fn get() -> &'static Self {
+        static PATTERNS: OnceLock<NamePatterns> = OnceLock::new();
+        PATTERNS.get_or_init(Self::new)
+    }
+
+    fn new() -> Self {
+        Self {
+            script_call: Regex::new(
+                r#"(?i)\b(?:Create|Open)(Mutex|Event|Semaphore)(?:Ex)?[AW]?\s*\([^"']*["']([^"']{3,})["']"#,
+            )
+            .expect("valid script call pattern"),
+            inno_directive: Regex::new(r"(?i)^\s*(?:App|Setup)Mutex\s*=\s*(.+)$")
+                .expect("valid directive pattern"),
+            pipe: Regex::new(r"(?i)^\\\\[.\w-]+\\pipe\\\S+$").expect("valid pipe pattern"),
+            prefixed: Regex::new(r"^(?:Global|Local|Session\\\d+)\\([^\\\s]{3,})$")
+                .expect("valid prefixed name pattern"),
+            mutex_like: Regex::new(r"^[\w.{}-]*(?i:mutex|mtx)[\w.{}-]*$")
+                .expect("valid mutex name pattern"),
+        }
+    }
+}
+
+/// Named synchronization objects of an installer
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncObjectReport {
+    pub objects: Vec<SyncObject>,
+    /// The installer tells users that another instance is running
+    pub instance_message: bool,
+}
+
+impl SyncObjectReport {
+    /// Scan content of one source
+    pub fn scan(source: &str, data: &[u8]) -> Self {
+        let mut report = Self::default();
+        report.add_source(source, data);
+        report
+    }
+
+    /// Object names in one string, given the object APIs its source references
+    fn names(run: &str, apis: &[SyncObjectKind]) -> Vec<(SyncObjectKind, String)> {
+        let patterns = NamePatterns::get();
+        let mut names: Vec<(SyncObjectKind, String)> = patterns
+            .script_call
+            .captures_iter(run)
+            .map(|captures| {
+                let kind = match captures[1].to_lowercase().as_str() {
+                    "event" => SyncObjectKind::Event,
+                    "semaphore" => SyncObjectKind::Semaphore,
+                    _ => SyncObjectKind::Mutex,
+                };
+                (kind, captures[2].to_string())
+            })
+            .collect();
+        if !names.is_empty() {
+            return names;
+        }
+
+        if let Some(captures) = patterns.inno_directive.captures(run) {
+            names.extend(
+                captures[1]
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .map(|name| (SyncObjectKind::Mutex, name.to_string())),
+            );
+        } else if patterns.pipe.is_match(run) {
+            names.push((SyncObjectKind::NamedPipe, run.to_string()));
+        } else if let Some(captures) = patterns.prefixed.captures(run) {
+            names.push((Self::kind_of(&captures[1], apis), run.to_string()));
+        } else if apis.contains(&SyncObjectKind::Mutex)
+            && patterns.mutex_like.is_match(run)
+            && !SYNC_APIS.iter().any(|(api, _)| run.contains(api))
+            && !run.starts_with("ReleaseMutex")
+        {
+            names.push((SyncObjectKind::Mutex, run.to_string()));
+        }
+        names
+    }
+
+    /// Kind of a prefixed object, from its name or else the APIs its source uses
+    fn kind_of(name: &str, apis: &[SyncObjectKind]) -> SyncObjectKind {
+        let name = name.to_lowercase();
+        if name.contains("mutex") || name.contains("mtx") {
+            SyncObjectKind::Mutex
+        } else if name.contains("event") {
+            SyncObjectKind::Event
+        } else if name.contains("semaphore") {
+            SyncObjectKind::Semaphore
+        } else if !apis.contains(&SyncObjectKind::Mutex) && apis.contains(&SyncObjectKind::Event) {
+            SyncObjectKind::Event
+        } else {
+            SyncObjectKind::Mutex
+        }
+    }
+
+    fn add(&mut self, object: SyncObject) {
+        match self
+            .objects
+            .iter_mut()
+            .find(|o| o.kind == object.kind && o.name == object.name)
+        {
+            Some(existing) if existing.origin != object.origin => {
+                existing.origin = SyncObjectOrigin::Both;
+            }
+            Some(_) => {}
+            None => self.objects.push(object),
+        }
+    }
+
+    /// Merge objects captured at runtime
+    pub fn merge_observed(&mut self, observed: &[SyncObject]) {
+        for object in observed {
+            self.add(SyncObject {
+                origin: SyncObjectOrigin::Runtime,
+                ..object.clone()
+            });
+        }
+    }
+
+    /// Mutexes that likely keep a second setup instance from starting
+    pub fn single_instance_mutexes(&self) -> Vec<&str> {
+        if !self.instance_message {
+            return Vec::new();
+        }
+        self.objects
+            .iter()
+            .filter(|object| object.kind == SyncObjectKind::Mutex)
+            .map(|object| object.name.as_str())
+            .collect()
+    }
+
+    /// Record the objects as analysis properties
+    pub fn insert_properties(&self, result: &mut AnalysisResult) {
+        if self.objects.is_empty() {
+            return;
+        }
+
+        let properties = &mut result.metadata.properties;
+        let descriptions: Vec<String> = self
+            .objects
+            .iter()
+            .take(20)
+            .map(|object| {
+                let origin = match object.origin {
+                    SyncObjectOrigin::Static => "",
+                    SyncObjectOrigin::Runtime => ", runtime",
+                    SyncObjectOrigin::Both => ", static and runtime",
+                };
+                format!(
+                    "{} {} ({}{})",
+                    object.kind.name(),
+                    object.name,
+                    object.source,
+                    origin
+                )
+            })
+            .collect();
+        properties.insert(
+            "sync_object_count".to_string(),
+            self.objects.len().to_string(),
+        );
+        properties.insert("sync_objects".to_string(), descriptions.join("; "));
+
+        let mutexes = self.single_instance_mutexes();
+        if !mutexes.is_empty() {
+            properties.insert("single_instance_mutexes".to_string(), mutexes.join(", "));
+        }
+    }
+}
+
+impl SourceScanner for SyncObjectReport {
+    fn wants(&self, path: &Path) -> bool {
+        is_pe_payload(path) || is_script(path)
+    }
+
+    fn add_source(&mut self, source: &str, data: &[u8]) {
+        let runs: Vec<String> = printable_runs(data)
+            .into_iter()
+            .map(|(_, value, _)| value)
+            .collect();
+        let apis: Vec<SyncObjectKind> = SYNC_APIS
+            .iter()
+            .filter(|(api, _)| runs.iter().any(|run| run.contains(api)))
+            .map(|(_, kind)| *kind)
+            .collect();
+
+        for run in &runs {
+            if INSTANCE_MESSAGES
+                .iter()
+                .any(|message| run.contains(message))
+            {
+                self.instance_message = true;
+            }
+            for (kind, name) in Self::names(run, &apis) {
+                self.add(SyncObject {
+                    kind,
+                    name,
+                    source: source.to_string(),
+                    origin: SyncObjectOrigin::Static,
+                });
+            }
+        }
+    }
+}
+
+fn is_script(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            matches!(
+                ext.to_lowercase().as_str(),
+                "nsi" | "nsh" | "iss" | "ps1" | "bat" | "cmd" | "vbs" | "js"
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzers::common::scanner::INSTALLER_SOURCE;
+
+    #[test]
+    fn test_scan_sync_objects() {
+        let mut binary = b"KERNEL32.dll\0CreateMutexW\0CreateEventW\0\0".to_vec();
+        binary.extend(
+            "Global\\ExampleSetupMutex\0"
+                .encode_utf16()
+                .flat_map(u16::to_le_bytes),
+        );
+        binary.extend(b"\0\0Local\\ExampleReadyEvent\0\\\\.\\pipe\\example-updater\0");
+        binary.extend(b"EXAMPLE_SINGLE_MTX\0Setup is already running.\0");
+
+        let report = SyncObjectReport::scan("setup.exe", &binary);
+        let objects: Vec<(SyncObjectKind, &str)> = report
+            .objects
+            .iter()
+            .map(|object| (object.kind, object.name.as_str()))
+            .collect();
+        assert!(objects.contains(&(SyncObjectKind::Mutex, "Global\\ExampleSetupMutex")));
+        assert!(objects.contains(&(SyncObjectKind::Event, "Local\\ExampleReadyEvent")));
+        assert!(objects.contains(&(SyncObjectKind::NamedPipe, "\\\\.\\pipe\\example-updater")));
+        assert!(objects.contains(&(SyncObjectKind::Mutex, "EXAMPLE_SINGLE_MTX")));
+        assert_eq!(objects.len(), 4);
+        assert_eq!(report.single_instance_mutexes().len(), 2);
+
+        // NSIS script call and Inno Setup directive
+        let script =
+            b"System::Call 'kernel32::CreateMutex(i 0, i 0, t \"MyAppInstaller\") i .r1 ?e'\n\
+                       AppMutex=MyAppRunning,Global\\MyAppRunning\n";
+        let mut report = SyncObjectReport::scan(INSTALLER_SOURCE, script);
+        let names: Vec<&str> = report.objects.iter().map(|o| o.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["MyAppInstaller", "MyAppRunning", "Global\\MyAppRunning"]
+        );
+
+        report.merge_observed(&[SyncObject {
+            kind: SyncObjectKind::Mutex,
+            name: "MyAppInstaller".to_string(),
+            source: "setup.exe".to_string(),
+            origin: SyncObjectOrigin::Runtime,
+        }]);
+        assert_eq!(report.objects[0].origin, SyncObjectOrigin::Both);
+    }
+}
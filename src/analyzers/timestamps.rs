@@ -0,0 +1,227 @@
+//! Detection of anomalous file timestamps
+//!
+//! Archive-based formats (wheels, MSIX/AppX, ZIP) keep a modification time
+//! per entry. Timestamps in the future, before 1990, or identical across the
+//! whole package suggest that it was repacked, built with faked times, or
+//! tampered with. Reproducible builds also pin every timestamp, so these
+//! findings are indicators to review rather than proof.
+
+use crate::core::{AnalysisResult, FileEntry};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use std::fmt;
+
+/// Minimum number of timestamped files before identical timestamps are reported
+const MIN_FILES_FOR_IDENTICAL: usize = 3;
+/// Tolerance for clock skew and time zones before a timestamp counts as future
+const FUTURE_TOLERANCE_HOURS: i64 = 24;
+
+/// Kind of timestamp anomaly
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimestampAnomalyKind {
+    /// Modified after the analysis took place
+    FutureDate,
+    /// Every file carries the same timestamp
+    IdenticalTimestamps,
+    /// Modified before 1990, e.g. the ZIP minimum of 1980-01-01
+    PreModernDate,
+}
+
+impl TimestampAnomalyKind {
+    /// Value used in analysis properties
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::FutureDate => "future_date",
+            Self::IdenticalTimestamps => "identical_timestamps",
+            Self::PreModernDate => "pre_1990_date",
+        }
+    }
+}
+
+impl fmt::Display for TimestampAnomalyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Anomaly found in the timestamps of packaged files
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimestampAnomaly {
+    pub kind: TimestampAnomalyKind,
+    /// Number of affected files
+    pub file_count: usize,
+    pub description: String,
+}
+
+/// Timestamp range and anomalies of packaged files
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TimestampReport {
+    pub earliest: Option<DateTime<Utc>>,
+    pub latest: Option<DateTime<Utc>>,
+    pub anomalies: Vec<TimestampAnomaly>,
+}
+
+impl TimestampReport {
+    /// Inspect file timestamps relative to the time of analysis
+    pub fn analyze(files: &[FileEntry], analyzed_at: DateTime<Utc>) -> Self {
+        let timestamps: Vec<(&FileEntry, DateTime<Utc>)> = files
+            .iter()
+            .filter_map(|file| file.modified.map(|modified| (file, modified)))
+            .collect();
+        let mut report = Self {
+            earliest: timestamps.iter().map(|(_, time)| *time).min(),
+            latest: timestamps.iter().map(|(_, time)| *time).max(),
+            anomalies: Vec::new(),
+        };
+
+        let future_limit = analyzed_at + Duration::hours(FUTURE_TOLERANCE_HOURS);
+        let future: Vec<&FileEntry> = timestamps
+            .iter()
+            .filter(|(_, time)| *time > future_limit)
+            .map(|(file, _)| *file)
+            .collect();
+        if !future.is_empty() {
+            report.anomalies.push(TimestampAnomaly {
+                kind: TimestampAnomalyKind::FutureDate,
+                file_count: future.len(),
+                description: format!(
+                    "{} file(s) dated in the future, e.g. {}",
+                    future.len(),
+                    future[0].path.display()
+                ),
+            });
+        }
+
+        let modern = Utc.with_ymd_and_hms(1990, 1, 1, 0, 0, 0).unwrap();
+        let ancient: Vec<&FileEntry> = timestamps
+            .iter()
+            .filter(|(_, time)| *time < modern)
+            .map(|(file, _)| *file)
+            .collect();
+        if !ancient.is_empty() {
+            report.anomalies.push(TimestampAnomaly {
+                kind: TimestampAnomalyKind::PreModernDate,
+                file_count: ancient.len(),
+                description: format!(
+                    "{} file(s) dated before 1990, e.g. {}",
+                    ancient.len(),
+                    ancient[0].path.display()
+                ),
+            });
+        }
+
+        if timestamps.len() >= MIN_FILES_FOR_IDENTICAL && report.earliest == report.latest {
+            report.anomalies.push(TimestampAnomaly {
+                kind: TimestampAnomalyKind::IdenticalTimestamps,
+                file_count: timestamps.len(),
+                description: format!(
+                    "All {} files share the timestamp {}",
+                    timestamps.len(),
+                    timestamps[0].1.format("%Y-%m-%d %H:%M:%S UTC")
+                ),
+            });
+        }
+
+        report
+    }
+
+    /// Record the timestamp range and anomalies as analysis properties
+    pub fn insert_properties(&self, result: &mut AnalysisResult) {
+        let properties = &mut result.metadata.properties;
+        if let (Some(earliest), Some(latest)) = (self.earliest, self.latest) {
+            properties.insert(
+                "file_timestamp_range".to_string(),
+                format!(
+                    "{} to {}",
+                    earliest.format("%Y-%m-%d %H:%M:%S UTC"),
+                    latest.format("%Y-%m-%d %H:%M:%S UTC")
+                ),
+            );
+        }
+        if !self.anomalies.is_empty() {
+            let kinds: Vec<&str> = self
+                .anomalies
+                .iter()
+                .map(|anomaly| anomaly.kind.as_str())
+                .collect();
+            let descriptions: Vec<&str> = self
+                .anomalies
+                .iter()
+                .map(|anomaly| anomaly.description.as_str())
+                .collect();
+            properties.insert("timestamp_anomalies".to_string(), kinds.join(", "));
+            properties.insert(
+                "timestamp_anomaly_details".to_string(),
+                descriptions.join("; "),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::FileAttributes;
+    use std::path::PathBuf;
+
+    fn file(path: &str, modified: Option<DateTime<Utc>>) -> FileEntry {
+        FileEntry {
+            path: PathBuf::from(path),
+            target_path: None,
+            size: 1,
+            hash: None,
+            attributes: FileAttributes::default(),
+            compression: None,
+            is_speculative: false,
+            architecture: None,
+            modified,
+            source: None,
+        }
+    }
+
+    #[test]
+    fn test_timestamp_anomalies() {
+        let now = Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+        let day = |d: u32| Some(Utc.with_ymd_and_hms(2024, 5, d, 8, 0, 0).unwrap());
+
+        let normal = [
+            file("a.py", day(1)),
+            file("b.py", day(2)),
+            file("c.py", day(3)),
+            file("untimed.py", None),
+        ];
+        let report = TimestampReport::analyze(&normal, now);
+        assert!(report.anomalies.is_empty());
+        assert_eq!(report.earliest, day(1));
+        assert_eq!(report.latest, day(3));
+
+        let dos_epoch = Some(Utc.with_ymd_and_hms(1980, 1, 1, 0, 0, 0).unwrap());
+        let repacked = [
+            file("a.py", dos_epoch),
+            file("b.py", dos_epoch),
+            file("c.py", dos_epoch),
+        ];
+        let kinds: Vec<TimestampAnomalyKind> = TimestampReport::analyze(&repacked, now)
+            .anomalies
+            .iter()
+            .map(|anomaly| anomaly.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TimestampAnomalyKind::PreModernDate,
+                TimestampAnomalyKind::IdenticalTimestamps
+            ]
+        );
+
+        // Up to a day ahead is tolerated for time zones
+        let future = [
+            file("a.py", Some(now + Duration::hours(12))),
+            file("b.py", Some(now + Duration::days(30))),
+        ];
+        let report = TimestampReport::analyze(&future, now);
+        assert_eq!(report.anomalies.len(), 1);
+        assert_eq!(report.anomalies[0].kind, TimestampAnomalyKind::FutureDate);
+        assert_eq!(report.anomalies[0].file_count, 1);
+        assert!(report.anomalies[0].description.contains("b.py"));
+    }
+}
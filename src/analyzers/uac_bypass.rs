@@ -0,0 +1,490 @@
+//! Detection of UAC bypass techniques
+//!
+//! Auto-elevating Windows binaries read handlers from the user's own
+//! registry hive, and COM elevation monikers hand out elevated objects
+//! without a consent prompt. An installer that stages such a hijack, or
+//! ships a payload that does, elevates without the user's consent; every
+//! finding is critical. Evidence comes from strings of the installer and
+//! its payloads, from registry data the installer declares or writes, and
+//! from processes started during a sandbox run, which also tell whether a
+//! staged hijack was triggered.
+
+use crate::analyzers::architecture::is_pe_payload;
+use crate::analyzers::common::scanner::SourceScanner;
+use crate::core::{AnalysisResult, ProcessOpType, RegistryOperation};
+use regex::bytes::Regex;
+use std::fmt;
+use std::path::Path;
+use std::sync::OnceLock;
+use uuid::Uuid;
+
+/// CLSID of CMSTPLUA, the COM server behind the `ICMLuaUtil` elevation moniker
+const CMSTPLUA_CLSID: &str = "3E5FC7F9-9A51-4367-9063-A120244FBEC7";
+/// IID of `ICMLuaUtil`
+const ICMLUAUTIL_IID: &str = "6EDD6D74-C007-4E75-B76A-E5740995E24C";
+
+/// Known UAC bypass technique
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum UacBypassTechnique {
+    /// `Elevation:Administrator!new:` moniker, typically for `ICMLuaUtil`
+    ElevationMoniker,
+    /// `ms-settings` handler read by fodhelper and computerdefaults
+    MsSettingsHijack,
+    /// `mscfile` handler read by eventvwr and CompMgmtLauncher
+    MscfileHijack,
+    /// `exefile` and `Folder` handlers read by sdclt
+    SdcltHijack,
+    /// `windir` variable expanded by the SilentCleanup task
+    EnvironmentHijack,
+}
+
+impl UacBypassTechnique {
+    /// Display name of the technique
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ElevationMoniker => "COM elevation moniker (ICMLuaUtil)",
+            Self::MsSettingsHijack => "ms-settings handler hijack (fodhelper)",
+            Self::MscfileHijack => "mscfile handler hijack (eventvwr)",
+            Self::SdcltHijack => "sdclt handler hijack",
+            Self::EnvironmentHijack => "windir environment hijack (SilentCleanup)",
+        }
+    }
+
+    /// Processes whose start triggers a staged hijack
+    fn triggers(&self) -> &'static [&'static str] {
+        match self {
+            Self::ElevationMoniker => &[],
+            Self::MsSettingsHijack => &["fodhelper.exe", "computerdefaults.exe"],
+            Self::MscfileHijack => &["eventvwr.exe", "compmgmtlauncher.exe"],
+            Self::SdcltHijack => &["sdclt.exe"],
+            Self::EnvironmentHijack => &["schtasks.exe"],
+        }
+    }
+}
+
+impl fmt::Display for UacBypassTechnique {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Strings that reveal a technique in binaries and scripts
+const BYPASS_STRINGS: &[(&str, UacBypassTechnique)] = &[
+    (
+        "Elevation:Administrator!new:",
+        UacBypassTechnique::ElevationMoniker,
+    ),
+    (
+        "{3E5FC7F9-9A51-4367-9063-A120244FBEC7}",
+        UacBypassTechnique::ElevationMoniker,
+    ),
+    (
+        "{6EDD6D74-C007-4E75-B76A-E5740995E24C}",
+        UacBypassTechnique::ElevationMoniker,
+    ),
+    (
+        "ms-settings\\shell\\open\\command",
+        UacBypassTechnique::MsSettingsHijack,
+    ),
+    (
+        "mscfile\\shell\\open\\command",
+        UacBypassTechnique::MscfileHijack,
+    ),
+    (
+        "exefile\\shell\\runas\\command",
+        UacBypassTechnique::SdcltHijack,
+    ),
+];
+
+/// Keys below the user's hive, and the value if only one matters, by technique
+const HIJACK_KEYS: &[(&str, Option<&str>, UacBypassTechnique)] = &[
+    (
+        "software\\classes\\ms-settings\\shell\\open\\command",
+        None,
+        UacBypassTechnique::MsSettingsHijack,
+    ),
+    (
+        "software\\classes\\mscfile\\shell\\open\\command",
+        None,
+        UacBypassTechnique::MscfileHijack,
+    ),
+    (
+        "software\\classes\\exefile\\shell\\runas\\command",
+        Some("isolatedcommand"),
+        UacBypassTechnique::SdcltHijack,
+    ),
+    (
+        "software\\classes\\folder\\shell\\open\\command",
+        None,
+        UacBypassTechnique::SdcltHijack,
+    ),
+    (
+        "environment",
+        Some("windir"),
+        UacBypassTechnique::EnvironmentHijack,
+    ),
+];
+
+/// Evidence of a UAC bypass technique
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UacBypassFinding {
+    pub technique: UacBypassTechnique,
+    /// Matched string, registry key or command line
+    pub evidence: String,
+    /// `installer`, the path of a packaged file, `registry` or the started process
+    pub source: String,
+    /// A process that triggers the technique was started during a sandbox run
+    pub triggered: bool,
+}
+
+/// UAC bypass techniques of an installer
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UacBypassReport {
+    pub findings: Vec<UacBypassFinding>,
+}
+
+impl UacBypassReport {
+    /// Scan content of one source
+    pub fn scan(source: &str, data: &[u8]) -> Self {
+        let mut report = Self::default();
+        report.add_source(source, data);
+        report
+    }
+
+    /// Check registry data and, for dynamic results, started processes
+    pub fn add_operations(&mut self, result: &AnalysisResult) {
+        for operation in &result.registry_operations {
+            let (key_path, value_name) = match operation {
+                RegistryOperation::CreateKey { key_path, .. } => (key_path, None),
+                RegistryOperation::SetValue {
+                    key_path,
+                    value_name,
+                    ..
+                } => (key_path, Some(value_name.as_str())),
+                _ => continue,
+            };
+            if let Some(technique) = hijacked_key(key_path, value_name) {
+                self.add(technique, key_path.clone(), "registry");
+            }
+        }
+
+        for operation in &result.process_operations {
+            if !matches!(operation.operation_type, ProcessOpType::Create) {
+                continue;
+            }
+            let name = operation.process_name.to_ascii_lowercase();
+            let command_line = operation.command_line.as_deref().unwrap_or_default();
+            // COM servers of elevation monikers run in a surrogate host
+            if name == "dllhost.exe"
+                && command_line
+                    .to_ascii_uppercase()
+                    .contains(&format!("{{{}}}", CMSTPLUA_CLSID))
+            {
+                self.add(
+                    UacBypassTechnique::ElevationMoniker,
+                    command_line.to_string(),
+                    &operation.process_name,
+                );
+            }
+            for finding in &mut self.findings {
+                let triggers = finding.technique.triggers();
+                let silent_cleanup = finding.technique != UacBypassTechnique::EnvironmentHijack
+                    || command_line.contains("SilentCleanup");
+                if triggers.contains(&name.as_str()) && silent_cleanup {
+                    finding.triggered = true;
+                }
+            }
+        }
+    }
+
+    fn add(&mut self, technique: UacBypassTechnique, evidence: String, source: &str) {
+        let known = self.findings.iter().any(|finding| {
+            finding.technique == technique && finding.evidence.eq_ignore_ascii_case(&evidence)
+        });
+        if !known {
+            self.findings.push(UacBypassFinding {
+                technique,
+                evidence,
+                source: source.to_string(),
+                triggered: false,
+            });
+        }
+    }
+
+    /// Techniques the findings point to
+    pub fn techniques(&self) -> Vec<UacBypassTechnique> {
+        let mut techniques: Vec<UacBypassTechnique> = self
+            .findings
+            .iter()
+            .map(|finding| finding.technique)
+            .collect();
+        techniques.sort();
+        techniques.dedup();
+        techniques
+    }
+
+    fn describe_techniques(&self) -> String {
+        let techniques: Vec<&str> = self
+            .techniques()
+            .iter()
+            .map(UacBypassTechnique::as_str)
+            .collect();
+        techniques.join(", ")
+    }
+
+    /// Critical warning for installers that use a bypass technique
+    pub fn warning(&self) -> Option<String> {
+        (!self.findings.is_empty()).then(|| {
+            format!(
+                "CRITICAL: installer uses UAC bypass techniques: {}",
+                self.describe_techniques()
+            )
+        })
+    }
+
+    /// Record the techniques and their evidence as analysis properties
+    pub fn insert_properties(&self, result: &mut AnalysisResult) {
+        if self.findings.is_empty() {
+            return;
+        }
+
+        let mut findings: Vec<&UacBypassFinding> = self.findings.iter().collect();
+        findings.sort_by_key(|finding| finding.technique);
+        let descriptions: Vec<String> = findings
+            .iter()
+            .take(15)
+            .map(|finding| {
+                format!(
+                    "{}: {} ({}{})",
+                    finding.technique,
+                    finding.evidence,
+                    finding.source,
+                    if finding.triggered { ", triggered" } else { "" }
+                )
+            })
+            .collect();
+        let properties = &mut result.metadata.properties;
+        properties.insert("uac_bypass_severity".to_string(), "critical".to_string());
+        properties.insert(
+            "uac_bypass_techniques".to_string(),
+            self.describe_techniques(),
+        );
+        properties.insert("uac_bypass_findings".to_string(), descriptions.join("; "));
+    }
+}
+
+/// Technique a write to a key of the user's hive stages, if any
+fn hijacked_key(key_path: &str, value_name: Option<&str>) -> Option<UacBypassTechnique> {
+    let key = key_path.to_ascii_lowercase();
+    let key = key.trim_end_matches('\\');
+    let relative = if let Some(rest) = key
+        .strip_prefix("hkey_current_user\\")
+        .or_else(|| key.strip_prefix("hkcu\\"))
+    {
+        rest.to_string()
+    } else {
+        // HKEY_USERS\<SID>\... and the classes hive HKEY_USERS\<SID>_Classes\...
+        let rest = key
+            .strip_prefix("hkey_users\\")
+            .or_else(|| key.strip_prefix("hku\\"))?;
+        let (sid, rest) = rest.split_once('\\')?;
+        if sid.ends_with("_classes") {
+            format!("software\\classes\\{}", rest)
+        } else {
+            rest.to_string()
+        }
+    };
+
+    HIJACK_KEYS
+        .iter()
+        .find(|(path, value, _)| {
+            *path == relative
+                && value.is_none_or(|value| {
+                    value_name.is_some_and(|name| name.eq_ignore_ascii_case(value))
+                })
+        })
+        .map(|(_, _, technique)| *technique)
+}
+
+impl SourceScanner for UacBypassReport {
+    fn wants(&self, path: &Path) -> bool {
+        is_pe_payload(path)
+    }
+
+    fn add_source(&mut self, source: &str, data: &[u8]) {
+        for found in matcher().find_iter(data) {
+            let text: String = found
+                .as_bytes()
+                .iter()
+                .filter(|&&byte| byte != 0)
+                .map(|&byte| byte as char)
+                .collect();
+            if let Some((pattern, technique)) = BYPASS_STRINGS
+                .iter()
+                .find(|(pattern, _)| pattern.eq_ignore_ascii_case(&text))
+            {
+                self.add(*technique, pattern.to_string(), source);
+            }
+        }
+
+        // Compiled code references the GUIDs in their binary form
+        for guid in [CMSTPLUA_CLSID, ICMLUAUTIL_IID] {
+            let bytes = Uuid::parse_str(guid).expect("valid GUID").to_bytes_le();
+            if data.windows(bytes.len()).any(|window| window == bytes) {
+                self.add(
+                    UacBypassTechnique::ElevationMoniker,
+                    format!("{{{}}}", guid),
+                    source,
+                );
+            }
+        }
+    }
+}
+
+/// Case-insensitive matcher of all strings in ASCII and UTF-16LE
+fn matcher() -> &'static Regex {
+    static MATCHER: OnceLock<Regex> = OnceLock::new();
+    MATCHER.get_or_init(|| {
+        let alternatives: Vec<String> = BYPASS_STRINGS
+            .iter()
+            .flat_map(|(pattern, _)| {
+                let wide: String = pattern.chars().flat_map(|c| [c, '\0']).collect();
+                [regex::escape(pattern), regex::escape(&wide)]
+            })
+            .collect();
+        Regex::new(&format!("(?i-u){}", alternatives.join("|"))).expect("valid UAC bypass pattern")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ProcessOperation, RegistryValue, RegistryValueType};
+    use chrono::Utc;
+
+    fn set_value(key_path: &str, value_name: &str) -> RegistryOperation {
+        RegistryOperation::SetValue {
+            key_path: key_path.to_string(),
+            value_name: value_name.to_string(),
+            value_type: RegistryValueType::String,
+            value_data: RegistryValue::String("C:\\Temp\\payload.exe".to_string()),
+            timestamp: Utc::now(),
+            process_id: Some(100),
+        }
+    }
+
+    fn start(name: &str, command_line: &str) -> ProcessOperation {
+        ProcessOperation {
+            operation_type: ProcessOpType::Create,
+            process_id: 200,
+            process_name: name.to_string(),
+            command_line: Some(command_line.to_string()),
+            parent_process_id: Some(100),
+            timestamp: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_scan_elevation_moniker() {
+        let clean = UacBypassReport::scan("installer", b"MZ ordinary setup, runas");
+        assert!(clean.findings.is_empty());
+        assert!(clean.warning().is_none());
+
+        let mut binary = b"\0\0".to_vec();
+        binary.extend(
+            "Elevation:Administrator!new:{3E5FC7F9-9A51-4367-9063-A120244FBEC7}"
+                .encode_utf16()
+                .flat_map(u16::to_le_bytes),
+        );
+        binary.extend(Uuid::parse_str(ICMLUAUTIL_IID).unwrap().to_bytes_le());
+        binary.extend(b"\0Software\\Classes\\MS-SETTINGS\\Shell\\Open\\command\0");
+        let report = UacBypassReport::scan("bin/helper.dll", &binary);
+        let evidence: Vec<(UacBypassTechnique, &str)> = report
+            .findings
+            .iter()
+            .map(|finding| (finding.technique, finding.evidence.as_str()))
+            .collect();
+        assert_eq!(
+            evidence,
+            vec![
+                (
+                    UacBypassTechnique::ElevationMoniker,
+                    "Elevation:Administrator!new:"
+                ),
+                (
+                    UacBypassTechnique::ElevationMoniker,
+                    "{3E5FC7F9-9A51-4367-9063-A120244FBEC7}"
+                ),
+                (
+                    UacBypassTechnique::MsSettingsHijack,
+                    "ms-settings\\shell\\open\\command"
+                ),
+                (
+                    UacBypassTechnique::ElevationMoniker,
+                    "{6EDD6D74-C007-4E75-B76A-E5740995E24C}"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_staged_hijacks() {
+        let mut result = AnalysisResult {
+            registry_operations: vec![
+                set_value(
+                    "HKEY_CURRENT_USER\\Software\\Classes\\ms-settings\\Shell\\Open\\command",
+                    "DelegateExecute",
+                ),
+                set_value(
+                    "HKEY_USERS\\S-1-5-21-1000_Classes\\mscfile\\shell\\open\\command",
+                    "",
+                ),
+                set_value("HKCU\\Environment", "windir"),
+                // Unrelated values and machine-wide keys
+                set_value("HKCU\\Environment", "PATH"),
+                set_value(
+                    "HKEY_LOCAL_MACHINE\\Software\\Classes\\ms-settings\\Shell\\Open\\command",
+                    "",
+                ),
+                set_value(
+                    "HKEY_CURRENT_USER\\Software\\Classes\\exefile\\shell\\runas\\command",
+                    "",
+                ),
+            ],
+            process_operations: vec![
+                start("fodhelper.exe", "C:\\Windows\\System32\\fodhelper.exe"),
+                start("schtasks.exe", "schtasks /Query"),
+                start(
+                    "dllhost.exe",
+                    "dllhost.exe /Processid:{3e5fc7f9-9a51-4367-9063-a120244fbec7}",
+                ),
+            ],
+            ..Default::default()
+        };
+
+        let mut report = UacBypassReport::default();
+        report.add_operations(&result);
+        let found: Vec<(UacBypassTechnique, bool)> = report
+            .findings
+            .iter()
+            .map(|finding| (finding.technique, finding.triggered))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (UacBypassTechnique::MsSettingsHijack, true),
+                (UacBypassTechnique::MscfileHijack, false),
+                (UacBypassTechnique::EnvironmentHijack, false),
+                (UacBypassTechnique::ElevationMoniker, false),
+            ]
+        );
+
+        report.insert_properties(&mut result);
+        let properties = &result.metadata.properties;
+        assert_eq!(properties["uac_bypass_severity"], "critical");
+        assert!(properties["uac_bypass_findings"].starts_with(
+            "COM elevation moniker (ICMLuaUtil): dllhost.exe /Processid:{3e5fc7f9-9a51-4367-9063-a120244fbec7} (dllhost.exe)"
+        ));
+        assert!(report.warning().unwrap().starts_with("CRITICAL: "));
+    }
+}
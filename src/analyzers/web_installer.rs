@@ -0,0 +1,450 @@
+//! Detection of web installers and downloader stubs
+//!
+//! A web installer carries little more than a bootstrapper and fetches the
+//! actual product at install time, so the files and registry operations a
+//! static analysis finds are only those of the stub. Burn bundles declare
+//! their downloaded payloads in the manifest, which settles the question.
+//! Otherwise downloader plugins (NSIS Inetc and NSISdl, Inno Download Plugin)
+//! and download APIs are combined with the installer size and the URLs it
+//! contains.
+
+use crate::analyzers::common;
+use crate::analyzers::wix::BurnManifest;
+use crate::core::{AnalysisResult, Result};
+use regex::bytes::Regex;
+use std::fmt;
+use std::path::Path;
+
+/// Largest installer whose content is searched
+const MAX_SOURCE_SIZE: u64 = 256 * 1024 * 1024;
+/// Installers up to this size are small enough to be a stub
+const STUB_SIZE: u64 = 5 * 1024 * 1024;
+/// URLs reported per installer
+const MAX_URLS: usize = 20;
+
+/// Downloader plugins bundled with installers, with their names
+const DOWNLOADER_PLUGINS: &[(&str, &str)] = &[
+    ("inetc.dll", "NSIS Inetc plugin"),
+    ("NSISdl.dll", "NSISdl plugin"),
+    ("idp.dll", "Inno Download Plugin"),
+    ("ITDownload.dll", "InnoTools Downloader"),
+    ("isxdl.dll", "ISXDL downloader"),
+];
+
+/// Imported functions that download files
+const DOWNLOAD_APIS: &[&str] = &[
+    "URLDownloadToFile",
+    "URLDownloadToCacheFile",
+    "InternetOpenUrl",
+    "WinHttpOpen",
+    "WinHttpSendRequest",
+];
+
+/// URL pattern, NUL bytes are removed before matching so UTF-16 URLs match too
+const URL_PATTERN: &str =
+    r"https?://[A-Za-z0-9.-]+(?::[0-9]+)?(?:/[A-Za-z0-9\-._~%!$&'()*+,;=:@/?#]*)?";
+
+/// Hosts of URLs that are not downloads: XML namespaces, certificate
+/// revocation and timestamping services, and installer toolkit homepages
+const NOISE_HOSTS: &[&str] = &[
+    "schemas.microsoft.com",
+    "schemas.openxmlformats.org",
+    "www.w3.org",
+    "ns.adobe.com",
+    "purl.org",
+    "www.microsoft.com/pki",
+    "digicert.com",
+    "verisign.com",
+    "symantec.com",
+    "symcb.com",
+    "symcd.com",
+    "thawte.com",
+    "globalsign.com",
+    "globalsign.net",
+    "sectigo.com",
+    "comodoca.com",
+    "usertrust.com",
+    "entrust.net",
+    "godaddy.com",
+    "nsis.sf.net",
+    "nsis.sourceforge.net",
+    "jrsoftware.org",
+    "wixtoolset.org",
+];
+
+/// How an installer delivers what it installs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum InstallerDelivery {
+    /// Everything that is installed is inside the installer
+    Offline,
+    /// The installer can download, but may carry its payload as well
+    PossiblyWeb,
+    /// The installer is a stub that downloads its payload
+    Web,
+}
+
+impl InstallerDelivery {
+    /// Value used in analysis properties
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Offline => "offline",
+            Self::PossiblyWeb => "possibly_web",
+            Self::Web => "web",
+        }
+    }
+}
+
+impl fmt::Display for InstallerDelivery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Delivery verdict with the evidence behind it and the URLs fetched
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebInstallerReport {
+    pub verdict: InstallerDelivery,
+    pub indicators: Vec<String>,
+    /// URLs the installer downloads or may download from
+    pub download_urls: Vec<String>,
+}
+
+impl WebInstallerReport {
+    /// Classify an installer together with its analysis result
+    pub async fn detect(file_path: &Path, result: &AnalysisResult) -> Result<Self> {
+        let mut report = Self {
+            verdict: InstallerDelivery::Offline,
+            indicators: Vec::new(),
+            download_urls: Vec::new(),
+        };
+
+        let mut downloads_payloads = false;
+        if common::is_pe_file(file_path).await? {
+            match BurnManifest::read(file_path).await {
+                Ok(Some(manifest)) => downloads_payloads = report.add_burn_manifest(&manifest),
+                Ok(None) => {}
+                Err(e) => tracing::debug!("Burn bundle manifest not read: {}", e),
+            }
+        }
+
+        let mut plugins: Vec<&str> = DOWNLOADER_PLUGINS
+            .iter()
+            .filter(|(file_name, _)| {
+                result.files.iter().any(|file| {
+                    file.path
+                        .file_name()
+                        .and_then(|name| name.to_str())
+                        .is_some_and(|name| name.eq_ignore_ascii_case(file_name))
+                })
+            })
+            .map(|(_, name)| *name)
+            .collect();
+        let mut apis: Vec<&str> = Vec::new();
+        let mut urls: Vec<String> = Vec::new();
+
+        let size = common::get_file_size(file_path).await?;
+        if size <= MAX_SOURCE_SIZE {
+            let found = search_strings(file_path).await?;
+            for (file_name, name) in DOWNLOADER_PLUGINS {
+                if found.iter().any(|f| f == file_name) && !plugins.contains(name) {
+                    plugins.push(name);
+                }
+            }
+            apis.extend(
+                DOWNLOAD_APIS
+                    .iter()
+                    .filter(|api| found.iter().any(|f| f == *api)),
+            );
+
+            let pattern = Regex::new(URL_PATTERN).expect("valid URL pattern");
+            urls = common::search_file_matches(file_path, &pattern, 256)
+                .await?
+                .iter()
+                .flat_map(|found| split_urls(found))
+                .filter(|url| !is_noise_url(url))
+                .collect();
+        }
+
+        let small = size <= STUB_SIZE;
+        if small && (!plugins.is_empty() || !apis.is_empty()) {
+            report.indicators.push(format!(
+                "Small installer ({})",
+                crate::utils::format_file_size(size)
+            ));
+        }
+        for plugin in &plugins {
+            report.indicators.push(format!("Bundles the {}", plugin));
+        }
+        if !apis.is_empty() {
+            report
+                .indicators
+                .push(format!("Uses download APIs: {}", apis.join(", ")));
+        }
+
+        report.verdict = if downloads_payloads || (small && !plugins.is_empty()) {
+            InstallerDelivery::Web
+        } else if !plugins.is_empty() || (small && !apis.is_empty() && !urls.is_empty()) {
+            InstallerDelivery::PossiblyWeb
+        } else {
+            InstallerDelivery::Offline
+        };
+        if report.verdict > InstallerDelivery::Offline {
+            for url in urls {
+                if !report.download_urls.contains(&url) {
+                    report.download_urls.push(url);
+                }
+            }
+        }
+        report.download_urls.truncate(MAX_URLS);
+
+        Ok(report)
+    }
+
+    /// Add the downloaded payloads of a Burn bundle, true if there are any
+    pub fn add_burn_manifest(&mut self, manifest: &BurnManifest) -> bool {
+        let downloaded: Vec<_> = manifest
+            .payloads
+            .iter()
+            .filter(|payload| payload.is_downloaded())
+            .collect();
+        if downloaded.is_empty() {
+            return false;
+        }
+
+        self.indicators.push(format!(
+            "Burn bundle downloads {} of {} payloads",
+            downloaded.len(),
+            manifest.payloads.len()
+        ));
+        for url in downloaded
+            .iter()
+            .filter_map(|payload| payload.download_url.clone())
+        {
+            if !self.download_urls.contains(&url) {
+                self.download_urls.push(url);
+            }
+        }
+        true
+    }
+
+    /// Record the verdict, its evidence and the URLs as analysis properties
+    pub fn insert_properties(&self, result: &mut AnalysisResult) {
+        let properties = &mut result.metadata.properties;
+        properties.insert(
+            "installer_delivery".to_string(),
+            self.verdict.as_str().to_string(),
+        );
+        if !self.indicators.is_empty() {
+            properties.insert(
+                "web_installer_indicators".to_string(),
+                self.indicators.join("; "),
+            );
+        }
+        if !self.download_urls.is_empty() {
+            properties.insert("download_urls".to_string(), self.download_urls.join(", "));
+        }
+        if let Some(warning) = self.warning() {
+            properties.insert("web_installer_warning".to_string(), warning.to_string());
+        }
+    }
+
+    /// Why the static results are incomplete, for web installers
+    pub fn warning(&self) -> Option<&'static str> {
+        (self.verdict == InstallerDelivery::Web).then_some(
+            "Web installer: the payload is downloaded at install time, \
+             static results only cover the stub",
+        )
+    }
+}
+
+/// Downloader plugin names and download APIs in the installer (ASCII and UTF-16)
+async fn search_strings(file_path: &Path) -> Result<Vec<String>> {
+    let ascii: Vec<&str> = DOWNLOADER_PLUGINS
+        .iter()
+        .map(|(file_name, _)| *file_name)
+        .chain(DOWNLOAD_APIS.iter().copied())
+        .collect();
+    let wide: Vec<String> = ascii
+        .iter()
+        .map(|pattern| pattern.chars().flat_map(|c| [c, '\0']).collect())
+        .collect();
+    let patterns: Vec<&str> = ascii
+        .iter()
+        .copied()
+        .chain(wide.iter().map(String::as_str))
+        .collect();
+
+    Ok(common::search_file_content(file_path, &patterns)
+        .await?
+        .into_iter()
+        .map(|found| found.replace('\0', ""))
+        .collect())
+}
+
+/// URLs in a match, which holds several when NUL-separated strings were joined
+fn split_urls(found: &str) -> Vec<String> {
+    let mut starts: Vec<usize> = found
+        .match_indices("http")
+        .map(|(start, _)| start)
+        .filter(|&start| {
+            let rest = &found[start..];
+            rest.starts_with("http://") || rest.starts_with("https://")
+        })
+        .collect();
+    starts.push(found.len());
+    starts
+        .windows(2)
+        .map(|range| {
+            found[range[0]..range[1]]
+                .trim_end_matches(['.', ',', ';', ':', ')', '\''])
+                .to_string()
+        })
+        .collect()
+}
+
+/// URL that is not a download location
+fn is_noise_url(url: &str) -> bool {
+    let location = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let location = location.to_lowercase();
+    let host = location
+        .split(['/', ':', '?', '#'])
+        .next()
+        .unwrap_or_default();
+    host.is_empty()
+        || !host.contains('.')
+        || host.starts_with("crl")
+        || host.starts_with("ocsp")
+        || host.starts_with("timestamp")
+        || NOISE_HOSTS.iter().any(|noise| {
+            if noise.contains('/') {
+                location.starts_with(noise)
+            } else {
+                host == *noise || host.ends_with(&format!(".{}", noise))
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{FileAttributes, FileEntry, InstallerFormat, InstallerMetadata};
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    fn result(files: &[&str]) -> AnalysisResult {
+        AnalysisResult {
+            session_id: uuid::Uuid::new_v4(),
+            metadata: InstallerMetadata {
+                format: InstallerFormat::NSIS,
+                created_at: chrono::Utc::now(),
+                ..Default::default()
+            },
+            files: files
+                .iter()
+                .map(|path| FileEntry {
+                    path: PathBuf::from(path),
+                    target_path: None,
+                    size: 0,
+                    hash: None,
+                    attributes: FileAttributes::default(),
+                    compression: None,
+                    is_speculative: false,
+                    architecture: None,
+                    modified: None,
+                    source: None,
+                })
+                .collect(),
+            analyzed_at: chrono::Utc::now(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_detect_delivery() {
+        let mut offline = tempfile::NamedTempFile::new().unwrap();
+        offline
+            .write_all(b"MZ payload xmlns=\"http://www.w3.org/2000/svg\" https://nsis.sf.net/")
+            .unwrap();
+        let report = WebInstallerReport::detect(offline.path(), &result(&[]))
+            .await
+            .unwrap();
+        assert_eq!(report.verdict, InstallerDelivery::Offline);
+        assert!(report.download_urls.is_empty());
+
+        // WinHTTP stub with a UTF-16 download URL
+        let mut stub = tempfile::NamedTempFile::new().unwrap();
+        stub.write_all(b"MZ WinHttpOpen\0WinHttpSendRequest\0")
+            .unwrap();
+        let url: Vec<u8> = "https://dl.example.com/app-x64.zip."
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        stub.write_all(&url).unwrap();
+        stub.write_all(b"\0\0http://crl3.digicert.com/ca.crl\0")
+            .unwrap();
+        let report = WebInstallerReport::detect(stub.path(), &result(&[]))
+            .await
+            .unwrap();
+        assert_eq!(report.verdict, InstallerDelivery::PossiblyWeb);
+        assert_eq!(
+            report.download_urls,
+            vec!["https://dl.example.com/app-x64.zip"]
+        );
+        assert!(report.warning().is_none());
+
+        let mut nsis = result(&["$PLUGINSDIR/INetC.dll"]);
+        let report = WebInstallerReport::detect(stub.path(), &nsis)
+            .await
+            .unwrap();
+        assert_eq!(report.verdict, InstallerDelivery::Web);
+        assert!(report.indicators.iter().any(|i| i.contains("Inetc")));
+
+        report.insert_properties(&mut nsis);
+        let properties = &nsis.metadata.properties;
+        assert_eq!(properties["installer_delivery"], "web");
+        assert_eq!(
+            properties["download_urls"],
+            "https://dl.example.com/app-x64.zip"
+        );
+        assert!(properties.contains_key("web_installer_warning"));
+    }
+
+    #[test]
+    fn test_burn_download_payloads() {
+        let manifest = BurnManifest::parse(
+            r#"<BurnManifest><Payload Id="a" FilePath="a.msi" Packaging="embedded" Container="WixAttachedContainer" /><Payload Id="b" FilePath="b.exe" DownloadUrl="https://dl.example.com/b.exe" Packaging="download" /></BurnManifest>"#,
+            None,
+        );
+        let mut report = WebInstallerReport {
+            verdict: InstallerDelivery::Offline,
+            indicators: Vec::new(),
+            download_urls: Vec::new(),
+        };
+        assert!(report.add_burn_manifest(&manifest));
+        assert_eq!(
+            report.indicators,
+            vec!["Burn bundle downloads 1 of 2 payloads"]
+        );
+        assert_eq!(report.download_urls, vec!["https://dl.example.com/b.exe"]);
+    }
+
+    #[test]
+    fn test_split_and_filter_urls() {
+        assert_eq!(
+            split_urls("https://a.example.com/x.zip.http://b.example.com/"),
+            vec!["https://a.example.com/x.zip", "http://b.example.com/"]
+        );
+        assert!(is_noise_url(
+            "http://schemas.microsoft.com/SMI/2005/WindowsSettings"
+        ));
+        assert!(is_noise_url("http://ocsp.digicert.com"));
+        assert!(is_noise_url("http://www.microsoft.com/pkiops/crl/x.crl"));
+        assert!(is_noise_url("http://localhost/"));
+        assert!(!is_noise_url(
+            "https://github.com/owner/app/releases/download/v1/app.zip"
+        ));
+        assert!(!is_noise_url(
+            "https://www.microsoft.com/download/details.aspx"
+        ));
+    }
+}
@@ -1,8 +1,13 @@
 //! Python Wheel analyzer implementation
 
 use super::parser::WheelParser;
-use crate::analyzers::{common, InstallerAnalyzer};
-use crate::core::{FileEntry, InstallerFormat, InstallerMetadata, RegistryOperation, Result};
+use crate::analyzers::archive::ArchiveFormat;
+use crate::analyzers::common::{self, EntryReader};
+use crate::analyzers::{DetectionContext, InstallerAnalyzer};
+use crate::core::{
+    AnalyzerCapabilities, FileEntry, FormatDetails, InstallerFormat, InstallerMetadata,
+    MetadataField, MetadataSource, PackageDependency, RegistryOperation, Result, SupportLevel,
+};
 use async_trait::async_trait;
 use chrono::Utc;
 use std::path::Path;
@@ -20,9 +25,9 @@ impl WheelAnalyzer {
         }
     }
 
-    /// Check if file is a Python wheel
-    async fn is_wheel_file(file_path: &Path) -> Result<bool> {
-        WheelParser::is_wheel_file(file_path).await
+    /// Check if the file under detection is a Python wheel
+    fn is_wheel_file(context: &DetectionContext) -> bool {
+        context.extension() == Some("whl") && context.archive_format() == ArchiveFormat::Zip
     }
 
     /// Extract metadata from wheel file
@@ -34,16 +39,25 @@ impl WheelAnalyzer {
         let properties = self.parser.extract_wheel_properties(file_path).await?;
 
         // Extract wheel metadata for product info
-        let (product_name, product_version, manufacturer) =
+        let (product_name, product_version, manufacturer, source, manufacturer_source) =
             match self.parser.extract_metadata(file_path) {
                 Ok(metadata) => {
                     let product_name = Some(metadata.name.clone());
                     let product_version = Some(metadata.version.clone());
-                    let manufacturer = metadata
-                        .author
-                        .or(metadata.maintainer)
-                        .or_else(|| Some("Unknown".to_string()));
-                    (product_name, product_version, manufacturer)
+                    let author = metadata.author.or(metadata.maintainer);
+                    let manufacturer_source = if author.is_some() {
+                        MetadataSource::Manifest
+                    } else {
+                        MetadataSource::Placeholder
+                    };
+                    let manufacturer = author.or_else(|| Some("Unknown".to_string()));
+                    (
+                        product_name,
+                        product_version,
+                        manufacturer,
+                        MetadataSource::Manifest,
+                        manufacturer_source,
+                    )
                 }
                 Err(_) => {
                     // Fallback to filename parsing
@@ -51,7 +65,13 @@ impl WheelAnalyzer {
                         // Extract package name from wheel filename (name-version-python-abi-platform.whl)
                         s.split('-').next().unwrap_or(s).to_string()
                     });
-                    (product_name, None, Some("Unknown".to_string()))
+                    (
+                        product_name,
+                        None,
+                        Some("Unknown".to_string()),
+                        MetadataSource::FilenameHeuristic,
+                        MetadataSource::Placeholder,
+                    )
                 }
             };
 
@@ -64,7 +84,15 @@ impl WheelAnalyzer {
             file_hash,
             created_at: Utc::now(),
             properties,
-        })
+            provenance: Default::default(),
+            format_details: self
+                .parser
+                .extract_details(file_path)
+                .ok()
+                .map(FormatDetails::Wheel),
+        }
+        .with_provenance(source)
+        .with_field_provenance(MetadataField::Manufacturer, manufacturer_source))
     }
 
     /// Extract files from wheel
@@ -87,22 +115,54 @@ impl WheelAnalyzer {
         // They are installed via pip and don't modify the registry directly
         Ok(Vec::new())
     }
+
+    /// Extract Requires-Dist dependencies from wheel METADATA
+    async fn extract_wheel_dependencies(&self, file_path: &Path) -> Result<Vec<PackageDependency>> {
+        let metadata = self.parser.extract_metadata(file_path)?;
+
+        Ok(metadata
+            .requires_dist
+            .into_iter()
+            .map(|dep| PackageDependency {
+                name: if dep.extras.is_empty() {
+                    dep.name
+                } else {
+                    format!("{}[{}]", dep.name, dep.extras.join(","))
+                },
+                version_spec: dep.version_spec,
+                optional: dep.required_by_extra.is_some(),
+                condition: dep.environment_marker,
+            })
+            .collect())
+    }
 }
 
 #[async_trait]
 impl InstallerAnalyzer for WheelAnalyzer {
     async fn can_analyze(&self, file_path: &Path) -> Result<bool> {
-        // Validate file accessibility
-        common::validate_file(file_path).await?;
+        self.can_analyze_with(&DetectionContext::new(file_path).await?)
+            .await
+    }
 
+    async fn can_analyze_with(&self, context: &DetectionContext) -> Result<bool> {
         // Check if it's a Python wheel file
-        Self::is_wheel_file(file_path).await
+        Ok(Self::is_wheel_file(context))
     }
 
     fn format(&self) -> InstallerFormat {
         InstallerFormat::PythonWheel
     }
 
+    fn capabilities(&self) -> AnalyzerCapabilities {
+        AnalyzerCapabilities {
+            metadata: SupportLevel::Full,
+            file_extraction: SupportLevel::Full,
+            registry_operations: SupportLevel::Unsupported,
+            install_scripts: SupportLevel::Unsupported,
+            signatures: SupportLevel::Unsupported,
+        }
+    }
+
     async fn extract_metadata(&self, file_path: &Path) -> Result<InstallerMetadata> {
         // Validate file first
         common::validate_file(file_path).await?;
@@ -126,6 +186,30 @@ impl InstallerAnalyzer for WheelAnalyzer {
 
         self.extract_wheel_registry(file_path).await
     }
+
+    async fn extract_dependencies(&self, file_path: &Path) -> Result<Vec<PackageDependency>> {
+        // Validate file first
+        common::validate_file(file_path).await?;
+
+        self.extract_wheel_dependencies(file_path).await
+    }
+
+    async fn read_file_content(
+        &self,
+        file_path: &Path,
+        entry_path: &Path,
+    ) -> Result<Option<Vec<u8>>> {
+        // Validate file first
+        common::validate_file(file_path).await?;
+
+        self.parser.read_file_content(file_path, entry_path).await
+    }
+
+    async fn open_file(&self, file_path: &Path, entry_path: &Path) -> Result<Option<EntryReader>> {
+        common::validate_file(file_path).await?;
+
+        self.parser.open_file(file_path, entry_path).await
+    }
 }
 
 impl Default for WheelAnalyzer {
@@ -0,0 +1,523 @@
+//! Checking wheel requirements against an environment snapshot
+//!
+//! The snapshot is the output of `pip freeze` (or `pip list --format=freeze`),
+//! `conda env export` or `conda list --export`. Each `Requires-Dist` entry of
+//! the wheel is matched by normalized name and its version specifier is
+//! evaluated against the installed version, following PEP 440 closely enough
+//! for release, pre-release and post-release versions. Requirements behind an
+//! `extra` marker are optional; those behind other markers only apply on some
+//! platforms and are reported as conditional instead of conflicting.
+
+use crate::analyzers::wheel::parser::{WheelDependency, WheelMetadata};
+use crate::core::{AnalysisResult, Result};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+/// Packages installed in a Python environment
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EnvironmentSnapshot {
+    /// Installed version by normalized name, unknown for URL and editable installs
+    packages: HashMap<String, Option<String>>,
+}
+
+impl EnvironmentSnapshot {
+    /// Read a `pip freeze` or conda export file
+    pub async fn load(path: &Path) -> Result<Self> {
+        Ok(Self::parse(&tokio::fs::read_to_string(path).await?))
+    }
+
+    /// Parse `pip freeze` output or a conda export
+    pub fn parse(content: &str) -> Self {
+        let mut packages = HashMap::new();
+        let conda_yaml = content
+            .lines()
+            .any(|line| line.starts_with("dependencies:"));
+        let mut in_dependencies = !conda_yaml;
+
+        for line in content.lines() {
+            if conda_yaml && !line.starts_with([' ', '-']) {
+                // Top-level key of `conda env export`
+                in_dependencies = line.starts_with("dependencies:");
+                continue;
+            }
+            let entry = line.trim().trim_start_matches("- ").trim();
+            if !in_dependencies
+                || entry.is_empty()
+                || entry.starts_with(['#', '-'])
+                || entry.ends_with(':')
+            {
+                continue;
+            }
+            if let Some((name, version)) = parse_entry(entry) {
+                packages.insert(normalize_name(name), version);
+            }
+        }
+        Self { packages }
+    }
+
+    /// Installed version of a package, `Some(None)` if installed from a URL
+    pub fn installed(&self, name: &str) -> Option<Option<&str>> {
+        self.packages
+            .get(&normalize_name(name))
+            .map(|version| version.as_deref())
+    }
+
+    pub fn len(&self) -> usize {
+        self.packages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.packages.is_empty()
+    }
+}
+
+/// Name and version of a `name==version`, `name @ url` or `name=version=build` entry
+fn parse_entry(entry: &str) -> Option<(&str, Option<String>)> {
+    if let Some((name, _)) = entry.split_once(" @ ") {
+        return Some((name.trim(), None));
+    }
+    let (name, version) = match entry.split_once("==") {
+        Some((name, version)) => (name, version.trim_start_matches('=')),
+        // conda pins: name=version=build
+        None => match entry.split_once('=') {
+            Some((name, rest)) => (name, rest.split('=').next().unwrap_or_default()),
+            None => return None,
+        },
+    };
+    let version = version.split(';').next().unwrap_or_default().trim();
+    let name = name.trim();
+    (!name.is_empty() && !version.is_empty()).then(|| (name, Some(version.to_string())))
+}
+
+/// PEP 503 normalized project name
+fn normalize_name(name: &str) -> String {
+    let mut normalized = String::with_capacity(name.len());
+    for c in name.trim().chars() {
+        if matches!(c, '-' | '_' | '.') {
+            if !normalized.ends_with('-') {
+                normalized.push('-');
+            }
+        } else {
+            normalized.push(c.to_ascii_lowercase());
+        }
+    }
+    normalized
+}
+
+/// Outcome of checking one requirement
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequirementStatus {
+    /// Installed in a matching version
+    Satisfied,
+    /// Installed in a version outside the specifier
+    Conflict,
+    /// Not installed
+    Missing,
+    /// Not satisfied, but only required under an environment marker
+    Conditional,
+    /// Only required by an extra
+    Optional,
+}
+
+impl RequirementStatus {
+    /// Value used in analysis properties
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Satisfied => "satisfied",
+            Self::Conflict => "conflict",
+            Self::Missing => "missing",
+            Self::Conditional => "conditional",
+            Self::Optional => "optional",
+        }
+    }
+}
+
+impl fmt::Display for RequirementStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Requirement of the wheel with the installed version it was checked against
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequirementCheck {
+    /// PEP 508 requirement string
+    pub requirement: String,
+    pub installed: Option<String>,
+    pub status: RequirementStatus,
+}
+
+impl RequirementCheck {
+    /// `requests>=2.31 (installed 2.28.0)`
+    pub fn describe(&self) -> String {
+        match &self.installed {
+            Some(version) => format!("{} (installed {})", self.requirement, version),
+            None => self.requirement.clone(),
+        }
+    }
+}
+
+/// Requirements of a wheel checked against an environment
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EnvironmentCheck {
+    /// `Requires-Python` checked against the environment's `python` package
+    pub python: Option<RequirementCheck>,
+    pub requirements: Vec<RequirementCheck>,
+}
+
+impl EnvironmentCheck {
+    /// Check the requirements declared in wheel metadata
+    pub fn check(metadata: &WheelMetadata, environment: &EnvironmentSnapshot) -> Self {
+        // Only conda exports list the interpreter
+        let python = metadata.requires_python.as_ref().and_then(|specifier| {
+            let installed = environment.installed("python")??.to_string();
+            let status = if specifier_matches(specifier, &installed) {
+                RequirementStatus::Satisfied
+            } else {
+                RequirementStatus::Conflict
+            };
+            Some(RequirementCheck {
+                requirement: format!("python{}", specifier.replace(' ', "")),
+                installed: Some(installed),
+                status,
+            })
+        });
+        let requirements = metadata
+            .requires_dist
+            .iter()
+            .map(|dependency| check_requirement(dependency, environment))
+            .collect();
+        Self {
+            python,
+            requirements,
+        }
+    }
+
+    /// Requirements that are not satisfied, including the interpreter
+    pub fn problems(&self) -> impl Iterator<Item = &RequirementCheck> {
+        self.python
+            .iter()
+            .chain(&self.requirements)
+            .filter(|check| {
+                matches!(
+                    check.status,
+                    RequirementStatus::Conflict | RequirementStatus::Missing
+                )
+            })
+    }
+
+    /// Whether installing the wheel leaves no requirement unsatisfied
+    pub fn is_compatible(&self) -> bool {
+        self.problems().next().is_none()
+    }
+
+    pub fn count(&self, status: RequirementStatus) -> usize {
+        self.python
+            .iter()
+            .chain(&self.requirements)
+            .filter(|check| check.status == status)
+            .count()
+    }
+
+    /// Add the outcome to the analysis properties
+    pub fn insert_properties(&self, result: &mut AnalysisResult) {
+        let properties = &mut result.metadata.properties;
+        properties.insert(
+            "wheel_env_compatible".to_string(),
+            self.is_compatible().to_string(),
+        );
+        for status in [
+            RequirementStatus::Satisfied,
+            RequirementStatus::Conflict,
+            RequirementStatus::Missing,
+        ] {
+            properties.insert(
+                format!("wheel_env_{}_count", status),
+                self.count(status).to_string(),
+            );
+        }
+        for status in [
+            RequirementStatus::Conflict,
+            RequirementStatus::Missing,
+            RequirementStatus::Conditional,
+        ] {
+            let checks: Vec<String> = self
+                .python
+                .iter()
+                .chain(&self.requirements)
+                .filter(|check| check.status == status)
+                .map(RequirementCheck::describe)
+                .collect();
+            if !checks.is_empty() {
+                properties.insert(format!("wheel_env_{}s", status), checks.join("; "));
+            }
+        }
+    }
+}
+
+/// Check one `Requires-Dist` entry
+fn check_requirement(
+    dependency: &WheelDependency,
+    environment: &EnvironmentSnapshot,
+) -> RequirementCheck {
+    let installed = environment.installed(&dependency.name);
+    let satisfied = match (installed, dependency.version_spec.as_deref()) {
+        (None, _) => None,
+        // URL installs and unpinned requirements match any version
+        (Some(None), _) | (Some(Some(_)), None) => Some(true),
+        (Some(Some(version)), Some(specifier)) => Some(specifier_matches(specifier, version)),
+    };
+    let status = if dependency.required_by_extra.is_some() {
+        RequirementStatus::Optional
+    } else {
+        match satisfied {
+            Some(true) => RequirementStatus::Satisfied,
+            _ if dependency.environment_marker.is_some() => RequirementStatus::Conditional,
+            Some(false) => RequirementStatus::Conflict,
+            None => RequirementStatus::Missing,
+        }
+    };
+    RequirementCheck {
+        requirement: dependency.requirement(),
+        installed: installed.flatten().map(str::to_string),
+        status,
+    }
+}
+
+/// Whether a version matches every clause of a PEP 440 specifier like `>=1.2,!=1.3.*`
+pub fn specifier_matches(specifier: &str, version: &str) -> bool {
+    let version = Pep440Version::parse(version);
+    specifier
+        .split(',')
+        .map(str::trim)
+        .filter(|clause| !clause.is_empty())
+        .all(|clause| clause_matches(clause, &version))
+}
+
+fn clause_matches(clause: &str, version: &Pep440Version) -> bool {
+    let operator_end = clause
+        .find(|c: char| !"<>=!~".contains(c))
+        .unwrap_or(clause.len());
+    let (operator, target) = (&clause[..operator_end], clause[operator_end..].trim());
+    if operator == "===" {
+        return version.text.eq_ignore_ascii_case(target);
+    }
+    if let Some(prefix) = target.strip_suffix(".*") {
+        let matches = version.starts_with(&Pep440Version::parse(prefix).release);
+        return match operator {
+            "==" => matches,
+            "!=" => !matches,
+            _ => false,
+        };
+    }
+
+    let target = Pep440Version::parse(target);
+    let ordering = version.cmp(&target);
+    match operator {
+        "==" => ordering == Ordering::Equal,
+        "!=" => ordering != Ordering::Equal,
+        ">=" => ordering != Ordering::Less,
+        "<=" => ordering != Ordering::Greater,
+        ">" => ordering == Ordering::Greater,
+        "<" => ordering == Ordering::Less,
+        // ~=1.4.2 means >=1.4.2 and ==1.4.*
+        "~=" => {
+            let prefix = &target.release[..target.release.len().saturating_sub(1).max(1)];
+            ordering != Ordering::Less && version.starts_with(prefix)
+        }
+        _ => false,
+    }
+}
+
+/// Comparable parts of a PEP 440 version, without epoch and local label
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Pep440Version {
+    text: String,
+    release: Vec<u64>,
+    /// Phase rank (dev, a, b, rc, final, post) and its number
+    phase: (u8, u64),
+}
+
+impl Pep440Version {
+    fn parse(text: &str) -> Self {
+        let lower = text.trim().to_ascii_lowercase();
+        let public = lower.split('+').next().unwrap_or_default();
+        let public = public.rsplit('!').next().unwrap_or_default();
+        let public = public.trim_start_matches('v');
+
+        let mut release = Vec::new();
+        let mut rest = public;
+        loop {
+            let end = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            if end == 0 {
+                break;
+            }
+            release.push(rest[..end].parse().unwrap_or(0));
+            rest = &rest[end..];
+            match rest.strip_prefix('.') {
+                Some(after) if after.starts_with(|c: char| c.is_ascii_digit()) => rest = after,
+                _ => break,
+            }
+        }
+
+        let suffix = rest.trim_start_matches(['.', '-', '_']);
+        let label_end = suffix
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(suffix.len());
+        let number = suffix[label_end..]
+            .trim_start_matches(['.', '-', '_'])
+            .chars()
+            .take_while(char::is_ascii_digit)
+            .collect::<String>()
+            .parse()
+            .unwrap_or(0);
+        let rank = match &suffix[..label_end] {
+            "" => 4,
+            "dev" => 0,
+            "a" | "alpha" => 1,
+            "b" | "beta" => 2,
+            "rc" | "c" | "pre" | "preview" => 3,
+            "post" | "rev" | "r" => 5,
+            _ => 4,
+        };
+
+        Self {
+            text: text.trim().to_string(),
+            release,
+            phase: (rank, number),
+        }
+    }
+}
+
+impl Pep440Version {
+    /// Release segment `index`; missing segments are zero, so 1.0 == 1.0.0
+    fn segment(&self, index: usize) -> u64 {
+        self.release.get(index).copied().unwrap_or(0)
+    }
+
+    /// Whether the release starts with `prefix`, as `==1.4.*` requires
+    fn starts_with(&self, prefix: &[u64]) -> bool {
+        prefix
+            .iter()
+            .enumerate()
+            .all(|(index, segment)| self.segment(index) == *segment)
+    }
+}
+
+impl Ord for Pep440Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let len = self.release.len().max(other.release.len());
+        (0..len)
+            .map(|index| self.segment(index).cmp(&other.segment(index)))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+            .then(self.phase.cmp(&other.phase))
+    }
+}
+
+impl PartialOrd for Pep440Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dependency(name: &str, spec: Option<&str>, marker: Option<&str>) -> WheelDependency {
+        WheelDependency {
+            name: name.to_string(),
+            version_spec: spec.map(str::to_string),
+            extras: Vec::new(),
+            environment_marker: marker.map(str::to_string),
+            required_by_extra: marker
+                .filter(|marker| marker.starts_with("extra"))
+                .map(|_| "dev".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_specifier_matches() {
+        assert!(specifier_matches(">=2.0,<3", "2.31.0"));
+        assert!(!specifier_matches(">=2.0,<3", "3.0"));
+        assert!(specifier_matches("==1.0", "1.0.0"));
+        assert!(specifier_matches("==1.4.*", "1.4.7"));
+        assert!(!specifier_matches("!=1.4.*", "1.4.7"));
+        assert!(specifier_matches("~=1.4.2", "1.4.9"));
+        assert!(!specifier_matches("~=1.4.2", "1.5.0"));
+        assert!(!specifier_matches("~=1.4.0", "1.5"));
+        assert!(specifier_matches("==1.0.*", "1"));
+        assert!(specifier_matches("~=2.2", "2.9"));
+        assert!(!specifier_matches(">=2.0", "2.0rc1"));
+        assert!(specifier_matches(">2.0", "2.0.post1"));
+        assert!(specifier_matches("<=1.0", "1.0+local"));
+    }
+
+    #[test]
+    fn test_parse_pip_freeze_and_conda_export() {
+        let freeze = "# comment\nRequests==2.28.0\n-e git+https://example.com/x.git#egg=x\n\
+                      my_pkg @ file:///tmp/my_pkg-1.0.whl\nurllib3===1.26.0\n";
+        let environment = EnvironmentSnapshot::parse(freeze);
+        assert_eq!(environment.installed("requests"), Some(Some("2.28.0")));
+        assert_eq!(environment.installed("My.Pkg"), Some(None));
+        assert_eq!(environment.installed("urllib3"), Some(Some("1.26.0")));
+        assert_eq!(environment.len(), 3);
+
+        let conda = "name: base\nchannels:\n  - conda-forge\ndependencies:\n  \
+                     - python=3.11.5=h955ad1f_0\n  - numpy=1.26.0=py311h_0\n  - pip:\n    \
+                     - typing-extensions==4.8.0\nprefix: /opt/conda\n";
+        let environment = EnvironmentSnapshot::parse(conda);
+        assert_eq!(environment.installed("python"), Some(Some("3.11.5")));
+        assert_eq!(environment.installed("numpy"), Some(Some("1.26.0")));
+        assert_eq!(
+            environment.installed("typing_extensions"),
+            Some(Some("4.8.0"))
+        );
+        assert_eq!(environment.installed("conda-forge"), None);
+    }
+
+    #[test]
+    fn test_check_requirements() {
+        let environment =
+            EnvironmentSnapshot::parse("python=3.8.10=h0\nrequests==2.28.0\nnumpy==1.26.0\n");
+        let metadata = WheelMetadata {
+            requires_python: Some(">=3.9".to_string()),
+            requires_dist: vec![
+                dependency("requests", Some(">=2.31"), None),
+                dependency("numpy", Some(">=1.22"), None),
+                dependency("click", None, None),
+                dependency("pywin32", None, Some("sys_platform == \"win32\"")),
+                dependency("pytest", None, Some("extra == \"dev\"")),
+            ],
+            ..WheelMetadata::default()
+        };
+        let check = EnvironmentCheck::check(&metadata, &environment);
+        let statuses: Vec<RequirementStatus> =
+            check.requirements.iter().map(|c| c.status).collect();
+        assert_eq!(
+            statuses,
+            [
+                RequirementStatus::Conflict,
+                RequirementStatus::Satisfied,
+                RequirementStatus::Missing,
+                RequirementStatus::Conditional,
+                RequirementStatus::Optional,
+            ]
+        );
+        assert_eq!(
+            check.python.as_ref().map(|c| c.status),
+            Some(RequirementStatus::Conflict)
+        );
+        assert!(!check.is_compatible());
+        assert_eq!(check.count(RequirementStatus::Conflict), 2);
+        assert_eq!(
+            check.requirements[0].describe(),
+            "requests>=2.31 (installed 2.28.0)"
+        );
+    }
+}
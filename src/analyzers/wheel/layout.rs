@@ -0,0 +1,437 @@
+//! Simulated installation of a wheel
+//!
+//! PEP 427 installs the root of a wheel into purelib when `Root-Is-Purelib`
+//! is true and into platlib otherwise, and the content of
+//! `<name>-<version>.data/<scheme>/` into the directory of that scheme. An
+//! installer also writes a launcher for each `console_scripts` and
+//! `gui_scripts` entry point into the scripts directory. Paths are laid out
+//! as in a Windows virtual environment, or a POSIX one for wheels built for
+//! Linux or macOS only, relative to `{prefix}`.
+
+use crate::analyzers::architecture::PeArchitecture;
+use crate::analyzers::wheel::parser::{WheelEntryPoint, WheelInfo};
+use crate::core::{AnalyzerError, Result, WheelExtensionModule};
+use std::fmt;
+use std::io::Read;
+use std::path::Path;
+use zip::ZipArchive;
+
+/// Bytes of a native module read to find its architecture
+const HEADER_SIZE: u64 = 4096;
+
+/// Install scheme directory of a wheel file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum InstallScheme {
+    Purelib,
+    Platlib,
+    Headers,
+    Scripts,
+    Data,
+}
+
+impl InstallScheme {
+    pub const ALL: [Self; 5] = [
+        Self::Purelib,
+        Self::Platlib,
+        Self::Headers,
+        Self::Scripts,
+        Self::Data,
+    ];
+
+    /// Value used in analysis properties and `.data` directories
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Purelib => "purelib",
+            Self::Platlib => "platlib",
+            Self::Headers => "headers",
+            Self::Scripts => "scripts",
+            Self::Data => "data",
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|scheme| scheme.as_str() == name)
+    }
+}
+
+impl fmt::Display for InstallScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// File of the wheel with the place an installer puts it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstalledFile {
+    /// Path inside the wheel
+    pub path: String,
+    pub scheme: InstallScheme,
+    /// Install path under `{prefix}`
+    pub target: String,
+}
+
+/// Launcher an installer creates for an entry point
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptShim {
+    pub name: String,
+    /// `console_scripts` or `gui_scripts`
+    pub group: String,
+    /// Object reference in `module:attr` form
+    pub entry_point: String,
+    /// Install path under `{prefix}`
+    pub target: String,
+}
+
+/// Simulated installation of a wheel
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WheelLayout {
+    /// Paths follow the Windows layout (`Lib\site-packages`, `Scripts`)
+    pub windows: bool,
+    pub files: Vec<InstalledFile>,
+    pub shims: Vec<ScriptShim>,
+    pub extensions: Vec<WheelExtensionModule>,
+}
+
+impl WheelLayout {
+    /// Lay out the files of the wheel at `file_path`
+    pub fn read(
+        file_path: &Path,
+        info: &WheelInfo,
+        entry_points: &[WheelEntryPoint],
+    ) -> Result<Self> {
+        let file = std::fs::File::open(file_path)?;
+        let mut archive = ZipArchive::new(file)
+            .map_err(|e| AnalyzerError::generic(format!("Failed to open wheel file: {}", e)))?;
+        let names: Vec<String> = (0..archive.len())
+            .filter_map(|index| {
+                let file = archive.by_index(index).ok()?;
+                (!file.is_dir()).then(|| file.name().to_string())
+            })
+            .collect();
+
+        let mut layout = Self::plan(&names, info, entry_points);
+        for name in names.iter().filter(|name| is_native_module(name)) {
+            let mut header = Vec::new();
+            if let Ok(file) = archive.by_name(name) {
+                let _ = file.take(HEADER_SIZE).read_to_end(&mut header);
+            }
+            layout.extensions.push(WheelExtensionModule {
+                path: name.clone(),
+                architecture: native_architecture(&header).map(str::to_string),
+                abi_tag: abi_tag(name),
+            });
+        }
+        Ok(layout)
+    }
+
+    /// Install paths of `names` and the launchers of `entry_points`
+    pub fn plan(names: &[String], info: &WheelInfo, entry_points: &[WheelEntryPoint]) -> Self {
+        let windows = is_windows_layout(info);
+        let python = python_version(info);
+        let root = if info.root_is_purelib.unwrap_or(true) {
+            InstallScheme::Purelib
+        } else {
+            InstallScheme::Platlib
+        };
+        let dist_name = names
+            .iter()
+            .find_map(|name| name.split_once(".dist-info/"))
+            .and_then(|(dist_info, _)| dist_info.split('-').next())
+            .unwrap_or_default()
+            .to_string();
+
+        let files = names
+            .iter()
+            .map(|name| {
+                // <name>-<version>.data/<scheme>/<path>
+                let data = name.split_once('/').and_then(|(top, rest)| {
+                    let (scheme, path) = rest.split_once('/')?;
+                    top.ends_with(".data")
+                        .then_some(())
+                        .and(InstallScheme::parse(scheme))
+                        .map(|scheme| (scheme, path))
+                });
+                let (scheme, relative) = data.unwrap_or((root, name.as_str()));
+                InstalledFile {
+                    path: name.clone(),
+                    scheme,
+                    target: scheme_path(scheme, relative, windows, &python, &dist_name),
+                }
+            })
+            .collect();
+
+        let shims = entry_points
+            .iter()
+            .filter(|ep| ep.group == "console_scripts" || ep.group == "gui_scripts")
+            .map(|ep| {
+                // Windows installers write .exe launchers
+                let launcher = if windows {
+                    format!("{}.exe", ep.name)
+                } else {
+                    ep.name.clone()
+                };
+                ScriptShim {
+                    name: ep.name.clone(),
+                    group: ep.group.clone(),
+                    entry_point: ep.value.clone(),
+                    target: scheme_path(
+                        InstallScheme::Scripts,
+                        &launcher,
+                        windows,
+                        &python,
+                        &dist_name,
+                    ),
+                }
+            })
+            .collect();
+
+        Self {
+            windows,
+            files,
+            shims,
+            extensions: Vec::new(),
+        }
+    }
+
+    /// Install path of a file of the wheel
+    pub fn target(&self, path: &str) -> Option<&str> {
+        self.files
+            .iter()
+            .find(|file| file.path == path)
+            .map(|file| file.target.as_str())
+    }
+
+    /// Number of files per scheme, like `purelib: 12, scripts: 1`
+    pub fn summary(&self) -> String {
+        InstallScheme::ALL
+            .iter()
+            .filter_map(|scheme| {
+                let count = self.files.iter().filter(|f| f.scheme == *scheme).count();
+                (count > 0).then(|| format!("{}: {}", scheme, count))
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Whether any tag targets Windows or all platforms
+fn is_windows_layout(info: &WheelInfo) -> bool {
+    info.tags.is_empty()
+        || info
+            .tags
+            .iter()
+            .any(|tag| tag.platform == "any" || tag.platform.starts_with("win"))
+}
+
+/// `3.11` from a `cp311` or `py311` tag, if the wheel targets one version
+fn python_version(info: &WheelInfo) -> String {
+    let versions: Vec<String> = info
+        .tags
+        .iter()
+        .filter_map(|tag| {
+            let digits = tag
+                .python
+                .trim_start_matches(|c: char| c.is_ascii_alphabetic());
+            (digits.len() >= 2).then(|| format!("{}.{}", &digits[..1], &digits[1..]))
+        })
+        .collect();
+    match versions.first() {
+        Some(version) if versions.iter().all(|v| v == version) => version.clone(),
+        _ => "X.Y".to_string(),
+    }
+}
+
+/// Path under `{prefix}` of a file of `scheme`
+fn scheme_path(
+    scheme: InstallScheme,
+    relative: &str,
+    windows: bool,
+    python: &str,
+    dist_name: &str,
+) -> String {
+    let (dir, separator) = if windows {
+        let dir = match scheme {
+            InstallScheme::Purelib | InstallScheme::Platlib => "Lib\\site-packages".to_string(),
+            InstallScheme::Headers => format!("Include\\{}", dist_name),
+            InstallScheme::Scripts => "Scripts".to_string(),
+            InstallScheme::Data => String::new(),
+        };
+        (dir, '\\')
+    } else {
+        let dir = match scheme {
+            InstallScheme::Purelib | InstallScheme::Platlib => {
+                format!("lib/python{}/site-packages", python)
+            }
+            InstallScheme::Headers => format!("include/python{}/{}", python, dist_name),
+            InstallScheme::Scripts => "bin".to_string(),
+            InstallScheme::Data => String::new(),
+        };
+        (dir, '/')
+    };
+    let relative = relative.replace('/', &separator.to_string());
+    if dir.is_empty() {
+        format!("{{prefix}}{}{}", separator, relative)
+    } else {
+        format!("{{prefix}}{}{}{}{}", separator, dir, separator, relative)
+    }
+}
+
+/// Whether a wheel file is a compiled extension module
+fn is_native_module(name: &str) -> bool {
+    let lower = name.to_lowercase();
+    [".pyd", ".so", ".dylib"]
+        .iter()
+        .any(|extension| lower.ends_with(extension))
+}
+
+/// ABI tag in the file name, like `cp311-win_amd64` of `_speedups.cp311-win_amd64.pyd`
+fn abi_tag(name: &str) -> Option<String> {
+    let file_name = name.rsplit('/').next()?;
+    let parts: Vec<&str> = file_name.split('.').collect();
+    (parts.len() >= 3).then(|| parts[1..parts.len() - 1].join("."))
+}
+
+/// Architecture of a PE, ELF or Mach-O image from its header
+fn native_architecture(header: &[u8]) -> Option<&'static str> {
+    if let Some(architecture) = PeArchitecture::from_pe(header) {
+        return Some(architecture.as_str());
+    }
+    let u16_at = |offset: usize| {
+        Some(u16::from_le_bytes(
+            header.get(offset..offset + 2)?.try_into().ok()?,
+        ))
+    };
+    let u32_at = |offset: usize| {
+        Some(u32::from_le_bytes(
+            header.get(offset..offset + 4)?.try_into().ok()?,
+        ))
+    };
+    if header.starts_with(b"\x7fELF") {
+        // Little-endian e_machine
+        return match u16_at(0x12)? {
+            0x03 => Some("x86"),
+            0x3e => Some("x64"),
+            0xb7 => Some("arm64"),
+            0x28 => Some("arm"),
+            0x15 => Some("ppc64"),
+            0x16 => Some("s390x"),
+            _ => None,
+        };
+    }
+    match u32_at(0)? {
+        // 64-bit Mach-O, cputype follows the magic
+        0xfeed_facf => match u32_at(4)? {
+            0x0100_0007 => Some("x64"),
+            0x0100_000c => Some("arm64"),
+            _ => None,
+        },
+        // Big-endian magic of universal binaries
+        0xbeba_feca => Some("universal2"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzers::wheel::parser::WheelTag;
+
+    fn info(platform: &str, root_is_purelib: bool) -> WheelInfo {
+        WheelInfo {
+            root_is_purelib: Some(root_is_purelib),
+            tags: vec![WheelTag {
+                python: "cp311".to_string(),
+                abi: "cp311".to_string(),
+                platform: platform.to_string(),
+            }],
+            ..WheelInfo::default()
+        }
+    }
+
+    fn names() -> Vec<String> {
+        [
+            "fastpkg/__init__.py",
+            "fastpkg/_speedups.cp311-win_amd64.pyd",
+            "fastpkg-1.0.data/scripts/fastpkg-admin",
+            "fastpkg-1.0.data/headers/fastpkg.h",
+            "fastpkg-1.0.data/data/share/man/fastpkg.1",
+            "fastpkg-1.0.dist-info/METADATA",
+        ]
+        .iter()
+        .map(|name| name.to_string())
+        .collect()
+    }
+
+    #[test]
+    fn test_plan_windows_layout() {
+        let entry_points = [WheelEntryPoint {
+            group: "console_scripts".to_string(),
+            name: "fastpkg".to_string(),
+            value: "fastpkg.cli:main".to_string(),
+        }];
+        let layout = WheelLayout::plan(&names(), &info("win_amd64", false), &entry_points);
+        assert!(layout.windows);
+        assert_eq!(
+            layout.target("fastpkg/__init__.py"),
+            Some("{prefix}\\Lib\\site-packages\\fastpkg\\__init__.py")
+        );
+        assert_eq!(layout.files[0].scheme, InstallScheme::Platlib);
+        assert_eq!(
+            layout.target("fastpkg-1.0.data/scripts/fastpkg-admin"),
+            Some("{prefix}\\Scripts\\fastpkg-admin")
+        );
+        assert_eq!(
+            layout.target("fastpkg-1.0.data/headers/fastpkg.h"),
+            Some("{prefix}\\Include\\fastpkg\\fastpkg.h")
+        );
+        assert_eq!(
+            layout.target("fastpkg-1.0.data/data/share/man/fastpkg.1"),
+            Some("{prefix}\\share\\man\\fastpkg.1")
+        );
+        assert_eq!(
+            layout.summary(),
+            "platlib: 3, headers: 1, scripts: 1, data: 1"
+        );
+        assert_eq!(layout.shims[0].target, "{prefix}\\Scripts\\fastpkg.exe");
+    }
+
+    #[test]
+    fn test_plan_posix_layout() {
+        let layout = WheelLayout::plan(&names(), &info("manylinux_2_17_x86_64", true), &[]);
+        assert!(!layout.windows);
+        assert_eq!(
+            layout.target("fastpkg/__init__.py"),
+            Some("{prefix}/lib/python3.11/site-packages/fastpkg/__init__.py")
+        );
+        assert_eq!(
+            layout.target("fastpkg-1.0.data/scripts/fastpkg-admin"),
+            Some("{prefix}/bin/fastpkg-admin")
+        );
+    }
+
+    #[test]
+    fn test_native_modules() {
+        assert!(is_native_module(
+            "pkg/_core.cpython-311-x86_64-linux-gnu.so"
+        ));
+        assert!(!is_native_module("pkg/core.py"));
+        assert_eq!(
+            abi_tag("pkg/_core.cpython-311-x86_64-linux-gnu.so").as_deref(),
+            Some("cpython-311-x86_64-linux-gnu")
+        );
+        assert_eq!(abi_tag("pkg/_core.so"), None);
+
+        let mut elf = vec![0u8; 64];
+        elf[..4].copy_from_slice(b"\x7fELF");
+        elf[0x12..0x14].copy_from_slice(&0xb7u16.to_le_bytes());
+        assert_eq!(native_architecture(&elf), Some("arm64"));
+        let mut macho = 0xfeed_facfu32.to_le_bytes().to_vec();
+        macho.extend(0x0100_0007u32.to_le_bytes());
+        assert_eq!(native_architecture(&macho), Some("x64"));
+        assert_eq!(
+            native_architecture(b"\xca\xfe\xba\xbe\0\0\0\x02"),
+            Some("universal2")
+        );
+        assert_eq!(native_architecture(b"text"), None);
+    }
+}
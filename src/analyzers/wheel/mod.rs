@@ -1,8 +1,15 @@
 //! Python Wheel (.whl) format analyzer
 
 pub mod analyzer;
+pub mod environment;
+pub mod layout;
 pub mod parser;
 
 // Re-export main components
 pub use analyzer::WheelAnalyzer;
-pub use parser::{WheelDependency, WheelMetadata, WheelParser};
+pub use environment::{EnvironmentCheck, EnvironmentSnapshot};
+pub use layout::{InstallScheme, WheelLayout};
+pub use parser::{
+    WheelDependency, WheelEntryPoint, WheelInfo, WheelMetadata, WheelParser, WheelRecordEntry,
+    WheelTag,
+};
@@ -1,23 +1,85 @@
 //! Python Wheel data structure parser
 
 use crate::analyzers::archive::{ArchiveFormat, ArchiveParser};
-use crate::core::{AnalyzerError, FileEntry, Result};
+use crate::analyzers::common::EntryReader;
+use crate::analyzers::wheel::layout::WheelLayout;
+use crate::core::{AnalyzerError, FileEntry, Result, WheelDetails};
+use base64::Engine;
+use serde::Serialize;
 use std::collections::HashMap;
 use std::io::Read;
 use std::path::Path;
 use zip::ZipArchive;
 
-/// Python Wheel dependency information
-#[derive(Debug, Clone)]
+/// Python Wheel dependency information (PEP 508 requirement)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct WheelDependency {
     pub name: String,
     pub version_spec: Option<String>,
     pub extras: Vec<String>,
     pub environment_marker: Option<String>,
+    /// Extra that enables this dependency, taken from an `extra == "..."` marker
+    pub required_by_extra: Option<String>,
+}
+
+/// Entry of the RECORD file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WheelRecordEntry {
+    pub path: String,
+    /// Hash algorithm, e.g. `sha256`
+    pub hash_algorithm: Option<String>,
+    /// Hex-encoded digest
+    pub hash: Option<String>,
+    pub size: Option<u64>,
+}
+
+/// Entry point declared in entry_points.txt
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WheelEntryPoint {
+    /// Entry point group, e.g. `console_scripts`
+    pub group: String,
+    pub name: String,
+    /// Object reference in `module:attr` form
+    pub value: String,
+}
+
+/// Compatibility tag from the WHEEL file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WheelTag {
+    pub python: String,
+    pub abi: String,
+    pub platform: String,
+}
+
+impl WheelDependency {
+    /// Normalized PEP 508 requirement string
+    pub fn requirement(&self) -> String {
+        let mut requirement = self.name.clone();
+        if !self.extras.is_empty() {
+            requirement.push_str(&format!("[{}]", self.extras.join(",")));
+        }
+        if let Some(version_spec) = &self.version_spec {
+            requirement.push_str(version_spec);
+        }
+        if let Some(marker) = &self.environment_marker {
+            requirement.push_str(&format!("; {}", marker));
+        }
+        requirement
+    }
+}
+
+/// Contents of the WHEEL file
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct WheelInfo {
+    pub wheel_version: Option<String>,
+    pub generator: Option<String>,
+    pub root_is_purelib: Option<bool>,
+    pub build: Option<String>,
+    pub tags: Vec<WheelTag>,
 }
 
 /// Python Wheel metadata
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct WheelMetadata {
     pub name: String,
     pub version: String,
@@ -66,29 +128,34 @@ impl WheelParser {
         Ok(format == ArchiveFormat::Zip)
     }
 
-    /// Extract METADATA file content from wheel
-    fn extract_metadata_content(&self, file_path: &Path) -> Result<String> {
+    /// Read a file from the .dist-info directory of a wheel
+    fn read_dist_info_file(&self, file_path: &Path, name: &str) -> Result<Option<String>> {
         let file = std::fs::File::open(file_path)?;
         let mut archive = ZipArchive::new(file)
             .map_err(|e| AnalyzerError::generic(format!("Failed to open wheel file: {}", e)))?;
 
-        // Look for METADATA file in .dist-info directory
+        let suffix = format!(".dist-info/{}", name);
         for i in 0..archive.len() {
             let mut zip_file = archive.by_index(i).map_err(|e| {
                 AnalyzerError::generic(format!("Failed to read zip entry {}: {}", i, e))
             })?;
 
-            let file_name = zip_file.name();
-            if file_name.ends_with(".dist-info/METADATA") {
+            if zip_file.name().ends_with(&suffix) {
                 let mut content = String::new();
                 zip_file.read_to_string(&mut content).map_err(|e| {
-                    AnalyzerError::generic(format!("Failed to read METADATA file: {}", e))
+                    AnalyzerError::generic(format!("Failed to read {} file: {}", name, e))
                 })?;
-                return Ok(content);
+                return Ok(Some(content));
             }
         }
 
-        Err(AnalyzerError::generic("METADATA file not found in wheel"))
+        Ok(None)
+    }
+
+    /// Extract METADATA file content from wheel
+    fn extract_metadata_content(&self, file_path: &Path) -> Result<String> {
+        self.read_dist_info_file(file_path, "METADATA")?
+            .ok_or_else(|| AnalyzerError::generic("METADATA file not found in wheel"))
     }
 
     /// Parse METADATA file content
@@ -169,45 +236,300 @@ impl WheelParser {
     }
 
     /// Parse a dependency specification
+    ///
+    /// Handles the PEP 508 forms found in METADATA, e.g.
+    /// `requests[socks] (>=2.0) ; python_version < "3.8"`.
     fn parse_dependency(&self, spec: &str) -> Result<WheelDependency> {
-        // Simple dependency parsing - real implementation would be more complex
         let spec = spec.trim();
 
         // Split on semicolon for environment markers
-        let (dep_part, env_marker) = if let Some(pos) = spec.find(';') {
-            (spec[..pos].trim(), Some(spec[pos + 1..].trim().to_string()))
-        } else {
-            (spec, None)
+        let (dep_part, env_marker) = match spec.split_once(';') {
+            Some((dep, marker)) => (dep.trim(), Some(marker.trim().to_string())),
+            None => (spec, None),
         };
 
-        // Extract package name and version spec
-        let (name, version_spec) = if let Some(pos) = dep_part.find(|c: char| ">=<=!~".contains(c))
-        {
-            (
-                dep_part[..pos].trim().to_string(),
-                Some(dep_part[pos..].trim().to_string()),
-            )
-        } else {
-            (dep_part.to_string(), None)
-        };
+        // Package name runs until the first character that is not part of a name
+        let name_end = dep_part
+            .find(|c: char| !(c.is_ascii_alphanumeric() || "._-".contains(c)))
+            .unwrap_or(dep_part.len());
+        let name = dep_part[..name_end].to_string();
+        if name.is_empty() {
+            return Err(AnalyzerError::parse_error(format!(
+                "Invalid dependency specification: {}",
+                spec
+            )));
+        }
+
+        let mut rest = dep_part[name_end..].trim();
+
+        // Extras in square brackets
+        let mut extras = Vec::new();
+        if let Some(stripped) = rest.strip_prefix('[') {
+            if let Some((inner, after)) = stripped.split_once(']') {
+                extras = inner
+                    .split(',')
+                    .map(|extra| extra.trim().to_string())
+                    .filter(|extra| !extra.is_empty())
+                    .collect();
+                rest = after.trim();
+            }
+        }
+
+        // Version specifier, optionally wrapped in parentheses
+        let rest = rest
+            .strip_prefix('(')
+            .and_then(|r| r.strip_suffix(')'))
+            .unwrap_or(rest)
+            .trim();
+        let version_spec = (!rest.is_empty()).then(|| rest.replace(' ', ""));
+
+        let required_by_extra = env_marker.as_deref().and_then(Self::marker_extra);
 
         Ok(WheelDependency {
             name,
             version_spec,
-            extras: Vec::new(), // TODO: Parse extras
+            extras,
             environment_marker: env_marker,
+            required_by_extra,
         })
     }
 
+    /// Get the extra name from an `extra == "name"` environment marker
+    fn marker_extra(marker: &str) -> Option<String> {
+        let pos = marker.find("extra")?;
+        let after = marker[pos + "extra".len()..].trim_start();
+        let value = after.strip_prefix("==")?.trim_start();
+        let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let value = &value[1..];
+        value.find(quote).map(|end| value[..end].to_string())
+    }
+
+    /// Parse RECORD file content
+    fn parse_record_content(&self, content: &str) -> Vec<WheelRecordEntry> {
+        content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| {
+                // Paths containing commas are quoted; hash and size never are
+                let mut fields = line.rsplitn(3, ',');
+                let size = fields.next()?.trim();
+                let hash = fields.next()?.trim();
+                let path = fields.next()?.trim().trim_matches('"');
+
+                let (hash_algorithm, hash) = match hash.split_once('=') {
+                    Some((algorithm, digest)) => (
+                        Some(algorithm.to_string()),
+                        base64::engine::general_purpose::URL_SAFE_NO_PAD
+                            .decode(digest.trim_end_matches('='))
+                            .ok()
+                            .map(hex::encode),
+                    ),
+                    None => (None, None),
+                };
+
+                Some(WheelRecordEntry {
+                    path: path.to_string(),
+                    hash_algorithm,
+                    hash,
+                    size: size.parse().ok(),
+                })
+            })
+            .collect()
+    }
+
+    /// Parse entry_points.txt content (INI format)
+    fn parse_entry_points_content(&self, content: &str) -> Vec<WheelEntryPoint> {
+        let mut entry_points = Vec::new();
+        let mut group = String::new();
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+
+            if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                group = section.trim().to_string();
+            } else if let Some((name, value)) = line.split_once('=') {
+                if !group.is_empty() {
+                    entry_points.push(WheelEntryPoint {
+                        group: group.clone(),
+                        name: name.trim().to_string(),
+                        value: value.trim().to_string(),
+                    });
+                }
+            }
+        }
+
+        entry_points
+    }
+
+    /// Parse WHEEL file content
+    fn parse_wheel_info_content(&self, content: &str) -> WheelInfo {
+        let mut info = WheelInfo::default();
+
+        for line in content.lines() {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+
+            match key.trim() {
+                "Wheel-Version" => info.wheel_version = Some(value.to_string()),
+                "Generator" => info.generator = Some(value.to_string()),
+                "Root-Is-Purelib" => {
+                    info.root_is_purelib = Some(value.eq_ignore_ascii_case("true"))
+                }
+                "Build" => info.build = Some(value.to_string()),
+                "Tag" => {
+                    let parts: Vec<&str> = value.splitn(3, '-').collect();
+                    if let [python, abi, platform] = parts[..] {
+                        info.tags.push(WheelTag {
+                            python: python.to_string(),
+                            abi: abi.to_string(),
+                            platform: platform.to_string(),
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        info
+    }
+
     /// Extract metadata from wheel file
     pub fn extract_metadata(&self, file_path: &Path) -> Result<WheelMetadata> {
         let content = self.extract_metadata_content(file_path)?;
         self.parse_metadata_content(&content)
     }
 
-    /// Extract files from wheel using archive parser
+    /// Extract RECORD entries from wheel file
+    pub fn extract_record(&self, file_path: &Path) -> Result<Vec<WheelRecordEntry>> {
+        Ok(self
+            .read_dist_info_file(file_path, "RECORD")?
+            .map(|content| self.parse_record_content(&content))
+            .unwrap_or_default())
+    }
+
+    /// Extract entry points from wheel file
+    pub fn extract_entry_points(&self, file_path: &Path) -> Result<Vec<WheelEntryPoint>> {
+        Ok(self
+            .read_dist_info_file(file_path, "entry_points.txt")?
+            .map(|content| self.parse_entry_points_content(&content))
+            .unwrap_or_default())
+    }
+
+    /// Extract WHEEL file information from wheel file
+    pub fn extract_wheel_info(&self, file_path: &Path) -> Result<WheelInfo> {
+        Ok(self
+            .read_dist_info_file(file_path, "WHEEL")?
+            .map(|content| self.parse_wheel_info_content(&content))
+            .unwrap_or_default())
+    }
+
+    /// Simulate installing the wheel: install paths, script launchers and native modules
+    pub fn extract_layout(&self, file_path: &Path) -> Result<WheelLayout> {
+        // Without WHEEL or entry_points.txt the root goes to purelib and no launchers are made
+        let info = self.extract_wheel_info(file_path).unwrap_or_default();
+        let entry_points = self.extract_entry_points(file_path).unwrap_or_default();
+        WheelLayout::read(file_path, &info, &entry_points)
+    }
+
+    /// Read the content of a file packaged in the wheel
+    pub async fn read_file_content(
+        &self,
+        file_path: &Path,
+        entry_path: &Path,
+    ) -> Result<Option<Vec<u8>>> {
+        self.archive_parser
+            .read_file_content(file_path, entry_path)
+            .await
+    }
+
+    /// Open a file packaged in the wheel for reading
+    pub async fn open_file(
+        &self,
+        file_path: &Path,
+        entry_path: &Path,
+    ) -> Result<Option<EntryReader>> {
+        self.archive_parser.open_file(file_path, entry_path).await
+    }
+
+    /// Extract files from wheel, using hashes and sizes recorded in RECORD
+    ///
+    /// Target paths are where an installer puts the files under `{prefix}`.
     pub async fn extract_files(&self, file_path: &Path) -> Result<Vec<FileEntry>> {
-        self.archive_parser.extract_files(file_path).await
+        let mut files = self.archive_parser.extract_files(file_path).await?;
+
+        match self.extract_layout(file_path) {
+            Ok(layout) => {
+                for file in &mut files {
+                    let path = file.path.to_string_lossy().into_owned();
+                    if let Some(target) = layout.target(&path) {
+                        file.target_path = Some(target.into());
+                    }
+                    if let Some(extension) = layout.extensions.iter().find(|e| e.path == path) {
+                        file.architecture = extension.architecture.clone();
+                    }
+                }
+            }
+            Err(e) => tracing::warn!("Failed to lay out wheel files: {}", e),
+        }
+
+        let record = match self.extract_record(file_path) {
+            Ok(record) => record,
+            Err(e) => {
+                tracing::warn!("Failed to read wheel RECORD: {}", e);
+                return Ok(files);
+            }
+        };
+        let record: HashMap<&str, &WheelRecordEntry> = record
+            .iter()
+            .map(|entry| (entry.path.as_str(), entry))
+            .collect();
+
+        for file in &mut files {
+            if let Some(entry) = record.get(file.path.to_string_lossy().as_ref()) {
+                if entry.hash_algorithm.as_deref() == Some("sha256") {
+                    file.hash = entry.hash.clone();
+                }
+                if let Some(size) = entry.size {
+                    file.size = size;
+                }
+            }
+        }
+
+        Ok(files)
+    }
+
+    /// Typed metadata and tags for `InstallerMetadata::format_details`
+    pub fn extract_details(&self, file_path: &Path) -> Result<WheelDetails> {
+        let metadata = self.extract_metadata(file_path)?;
+        // A missing WHEEL file only costs the tags
+        let info = self.extract_wheel_info(file_path).unwrap_or_default();
+        let layout = self.extract_layout(file_path).unwrap_or_default();
+
+        Ok(WheelDetails {
+            name: metadata.name,
+            version: metadata.version,
+            requires_python: metadata.requires_python,
+            requires_dist: metadata
+                .requires_dist
+                .iter()
+                .map(WheelDependency::requirement)
+                .collect(),
+            provides_extra: metadata.provides_extra,
+            tags: info
+                .tags
+                .iter()
+                .map(|tag| format!("{}-{}-{}", tag.python, tag.abi, tag.platform))
+                .collect(),
+            root_is_purelib: info.root_is_purelib,
+            generator: info.generator,
+            script_shims: layout.shims.into_iter().map(|shim| shim.target).collect(),
+            native_extensions: layout.extensions,
+        })
     }
 
     /// Extract wheel-specific metadata as HashMap
@@ -247,6 +569,18 @@ impl WheelParser {
                     "wheel_dependencies_count".to_string(),
                     metadata.requires_dist.len().to_string(),
                 );
+                if !metadata.requires_dist.is_empty() {
+                    properties.insert(
+                        "wheel_requires_dist".to_string(),
+                        serde_json::to_string(&metadata.requires_dist)?,
+                    );
+                }
+                if !metadata.provides_extra.is_empty() {
+                    properties.insert(
+                        "wheel_provides_extra".to_string(),
+                        metadata.provides_extra.join(", "),
+                    );
+                }
                 properties.insert(
                     "wheel_classifiers_count".to_string(),
                     metadata.classifier.len().to_string(),
@@ -258,6 +592,98 @@ impl WheelParser {
             }
         }
 
+        match self.extract_wheel_info(file_path) {
+            Ok(info) => {
+                if let Some(wheel_version) = info.wheel_version {
+                    properties.insert("wheel_format_version".to_string(), wheel_version);
+                }
+                if let Some(generator) = info.generator {
+                    properties.insert("wheel_generator".to_string(), generator);
+                }
+                if let Some(purelib) = info.root_is_purelib {
+                    properties.insert("wheel_root_is_purelib".to_string(), purelib.to_string());
+                }
+                if !info.tags.is_empty() {
+                    let tags: Vec<String> = info
+                        .tags
+                        .iter()
+                        .map(|tag| format!("{}-{}-{}", tag.python, tag.abi, tag.platform))
+                        .collect();
+                    properties.insert("wheel_tags".to_string(), tags.join(", "));
+                    properties.insert("wheel_abi".to_string(), join_unique(&info.tags, |t| &t.abi));
+                    properties.insert(
+                        "wheel_platform".to_string(),
+                        join_unique(&info.tags, |t| &t.platform),
+                    );
+                }
+            }
+            Err(e) => tracing::warn!("Failed to read WHEEL file: {}", e),
+        }
+
+        match self.extract_entry_points(file_path) {
+            Ok(entry_points) if !entry_points.is_empty() => {
+                let console_scripts: Vec<String> = entry_points
+                    .iter()
+                    .filter(|ep| ep.group == "console_scripts" || ep.group == "gui_scripts")
+                    .map(|ep| format!("{} = {}", ep.name, ep.value))
+                    .collect();
+                if !console_scripts.is_empty() {
+                    properties.insert(
+                        "wheel_console_scripts".to_string(),
+                        console_scripts.join(", "),
+                    );
+                }
+                properties.insert(
+                    "wheel_entry_points".to_string(),
+                    serde_json::to_string(&entry_points)?,
+                );
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Failed to read wheel entry points: {}", e),
+        }
+
+        match self.extract_record(file_path) {
+            Ok(record) => {
+                properties.insert("wheel_record_entries".to_string(), record.len().to_string());
+            }
+            Err(e) => tracing::warn!("Failed to read wheel RECORD: {}", e),
+        }
+
+        match self.extract_layout(file_path) {
+            Ok(layout) => {
+                properties.insert("wheel_install_layout".to_string(), layout.summary());
+                if !layout.shims.is_empty() {
+                    let shims: Vec<&str> = layout.shims.iter().map(|s| s.target.as_str()).collect();
+                    properties.insert("wheel_script_shims".to_string(), shims.join(", "));
+                }
+                if !layout.extensions.is_empty() {
+                    let extensions: Vec<String> = layout
+                        .extensions
+                        .iter()
+                        .map(|e| match &e.architecture {
+                            Some(architecture) => format!("{} ({})", e.path, architecture),
+                            None => e.path.clone(),
+                        })
+                        .collect();
+                    properties.insert("wheel_native_extensions".to_string(), extensions.join(", "));
+                    let mut architectures: Vec<&str> = layout
+                        .extensions
+                        .iter()
+                        .filter_map(|e| e.architecture.as_deref())
+                        .collect();
+                    architectures.sort_unstable();
+                    architectures.dedup();
+                    if !architectures.is_empty() {
+                        properties.insert(
+                            "wheel_native_architectures".to_string(),
+                            architectures.join(", "),
+                        );
+                    }
+                }
+            }
+            Err(e) => tracing::warn!("Failed to lay out wheel files: {}", e),
+        }
+
         properties.insert("package_type".to_string(), "Python Wheel".to_string());
 
         Ok(properties)
@@ -269,3 +695,90 @@ impl Default for WheelParser {
         Self::new()
     }
 }
+
+/// Join distinct tag components in order of appearance
+fn join_unique(tags: &[WheelTag], field: impl Fn(&WheelTag) -> &String) -> String {
+    let mut values: Vec<&str> = Vec::new();
+    for tag in tags {
+        let value = field(tag).as_str();
+        if !values.contains(&value) {
+            values.push(value);
+        }
+    }
+    values.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_dependency() {
+        let parser = WheelParser::new();
+
+        let dep = parser.parse_dependency("click (>=8.1.0)").unwrap();
+        assert_eq!(dep.name, "click");
+        assert_eq!(dep.version_spec.as_deref(), Some(">=8.1.0"));
+        assert!(dep.extras.is_empty());
+
+        let dep = parser
+            .parse_dependency("requests[socks, security] >=2.0, <3 ; extra == \"http\"")
+            .unwrap();
+        assert_eq!(dep.name, "requests");
+        assert_eq!(dep.extras, vec!["socks", "security"]);
+        assert_eq!(dep.version_spec.as_deref(), Some(">=2.0,<3"));
+        assert_eq!(dep.environment_marker.as_deref(), Some("extra == \"http\""));
+        assert_eq!(dep.required_by_extra.as_deref(), Some("http"));
+        assert_eq!(
+            dep.requirement(),
+            "requests[socks,security]>=2.0,<3; extra == \"http\""
+        );
+
+        assert!(parser.parse_dependency("(>=1.0)").is_err());
+    }
+
+    #[test]
+    fn test_parse_record_content() {
+        let parser = WheelParser::new();
+        let record = parser.parse_record_content(
+            "pkg/__init__.py,sha256=47DEQpj8HBSa-_TImW-5JCeuQeRkm5NMpJWZG3hSuFU,0\n\
+             \"pkg/a,b.py\",sha256=ZRXHwyyfQ3CLnf3FEIOEWCbj5BoKHPLOqRX3Kaah7v4,541\n\
+             pkg-1.0.dist-info/RECORD,,\n",
+        );
+
+        assert_eq!(record.len(), 3);
+        assert_eq!(record[0].hash_algorithm.as_deref(), Some("sha256"));
+        assert_eq!(
+            record[0].hash.as_deref(),
+            Some("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
+        );
+        assert_eq!(record[0].size, Some(0));
+        assert_eq!(record[1].path, "pkg/a,b.py");
+        assert_eq!(record[2].hash, None);
+        assert_eq!(record[2].size, None);
+    }
+
+    #[test]
+    fn test_parse_entry_points_and_wheel_info() {
+        let parser = WheelParser::new();
+
+        let entry_points = parser.parse_entry_points_content(
+            "[console_scripts]\ntool=pkg.cli:main\n\n[pkg.plugins]\nfoo = pkg.foo:Plugin\n",
+        );
+        assert_eq!(entry_points.len(), 2);
+        assert_eq!(entry_points[0].group, "console_scripts");
+        assert_eq!(entry_points[0].name, "tool");
+        assert_eq!(entry_points[0].value, "pkg.cli:main");
+        assert_eq!(entry_points[1].group, "pkg.plugins");
+
+        let info = parser.parse_wheel_info_content(
+            "Wheel-Version: 1.0\nGenerator: bdist_wheel\nRoot-Is-Purelib: false\n\
+             Tag: cp311-cp311-manylinux_2_17_x86_64\nTag: cp311-abi3-win_amd64\n",
+        );
+        assert_eq!(info.wheel_version.as_deref(), Some("1.0"));
+        assert_eq!(info.root_is_purelib, Some(false));
+        assert_eq!(info.tags.len(), 2);
+        assert_eq!(info.tags[0].platform, "manylinux_2_17_x86_64");
+        assert_eq!(join_unique(&info.tags, |t| &t.abi), "cp311, abi3");
+    }
+}
@@ -1,10 +1,34 @@
 //! WiX Toolset analyzer implementation
 
-use crate::analyzers::{common, InstallerAnalyzer, MsiAnalyzer};
-use crate::core::{FileEntry, InstallerFormat, InstallerMetadata, RegistryOperation, Result};
+use crate::analyzers::wix::authoring;
+use crate::analyzers::{common, DetectionContext, InstallerAnalyzer, MsiAnalyzer};
+use crate::core::{
+    AnalyzerCapabilities, FileEntry, FormatDetails, InstallerFormat, InstallerMetadata,
+    RegistryOperation, Result, SupportLevel, WixAuthoring,
+};
 use async_trait::async_trait;
+use std::collections::HashMap;
 use std::path::Path;
 
+/// WiX-specific strings searched for during detection
+const WIX_PATTERNS: [&str; 15] = [
+    "WiX Toolset",
+    "Windows Installer XML",
+    "WixToolset",
+    "Microsoft.Tools.WindowsInstallerXml",
+    "WiX v3",
+    "WiX v4",
+    "WiX v5",
+    "wix.exe",
+    "candle.exe",
+    "light.exe",
+    "WixUI",
+    "WixUIExtension",
+    "WixUtilExtension",
+    "WixNetFxExtension",
+    "WixFirewallExtension",
+];
+
 /// WiX Toolset installer analyzer
 ///
 /// WiX generates MSI files with specific characteristics that can be detected
@@ -21,34 +45,15 @@ impl WixAnalyzer {
         }
     }
 
-    /// Check if file is a WiX-generated MSI
-    async fn is_wix_msi(file_path: &Path) -> Result<bool> {
+    /// Check if the file under detection is a WiX-generated MSI
+    async fn is_wix_msi(&self, context: &DetectionContext) -> Result<bool> {
         // First check if it's an MSI file
-        let msi_analyzer = MsiAnalyzer::new();
-        if !msi_analyzer.can_analyze(file_path).await? {
+        if !self.msi_analyzer.can_analyze_with(context).await? {
             return Ok(false);
         }
 
         // Check for WiX-specific patterns
-        let wix_patterns = [
-            "WiX Toolset",
-            "Windows Installer XML",
-            "WixToolset",
-            "Microsoft.Tools.WindowsInstallerXml",
-            "WiX v3",
-            "WiX v4",
-            "WiX v5",
-            "wix.exe",
-            "candle.exe",
-            "light.exe",
-            "WixUI",
-            "WixUIExtension",
-            "WixUtilExtension",
-            "WixNetFxExtension",
-            "WixFirewallExtension",
-        ];
-
-        let matches = common::search_file_content(file_path, &wix_patterns).await?;
+        let matches = context.find_patterns(&WIX_PATTERNS).await?;
         Ok(!matches.is_empty())
     }
 
@@ -61,45 +66,64 @@ impl WixAnalyzer {
         metadata.format = InstallerFormat::WiX;
 
         // Add WiX-specific properties
-        let wix_properties = self.extract_wix_properties(file_path).await?;
-        metadata.properties.extend(wix_properties);
+        let wix_version = self.detect_wix_version(file_path).await?;
+        let extensions = self.detect_wix_extensions(file_path).await?;
+        let ui_type = self.detect_wix_ui(file_path).await?;
+        let authoring = match self.msi_analyzer.database(file_path) {
+            Ok(db) => Some(authoring::authoring_summary(
+                &db,
+                &metadata.properties,
+                wix_version,
+                extensions,
+                ui_type,
+            )),
+            Err(e) => {
+                tracing::warn!("Failed to open MSI database: {}", e);
+                None
+            }
+        };
+        if let Some(authoring) = &authoring {
+            metadata.properties.extend(Self::wix_properties(authoring));
+        }
+        metadata
+            .properties
+            .insert("generator_tool".to_string(), "WiX Toolset".to_string());
+        metadata
+            .properties
+            .insert("msi_variant".to_string(), "WiX-generated".to_string());
+        if let Some(FormatDetails::Msi(details)) = &mut metadata.format_details {
+            details.wix = authoring;
+        }
 
         Ok(metadata)
     }
 
-    /// Extract WiX-specific properties
-    async fn extract_wix_properties(
-        &self,
-        file_path: &Path,
-    ) -> Result<std::collections::HashMap<String, String>> {
-        let mut properties = std::collections::HashMap::new();
+    /// WiX-specific properties of the authoring summary
+    fn wix_properties(authoring: &WixAuthoring) -> HashMap<String, String> {
+        let mut properties = HashMap::new();
 
-        // Detect WiX version
-        let wix_version = self.detect_wix_version(file_path).await?;
-        if let Some(version) = wix_version {
-            properties.insert("wix_version".to_string(), version);
+        if let Some(version) = &authoring.toolset_version {
+            properties.insert("wix_version".to_string(), version.clone());
         }
-
-        // Detect WiX extensions
-        let extensions = self.detect_wix_extensions(file_path).await?;
-        if !extensions.is_empty() {
-            properties.insert("wix_extensions".to_string(), extensions.join(", "));
+        if !authoring.extensions.is_empty() {
+            properties.insert(
+                "wix_extensions".to_string(),
+                authoring.extensions.join(", "),
+            );
             properties.insert(
                 "wix_extensions_count".to_string(),
-                extensions.len().to_string(),
+                authoring.extensions.len().to_string(),
             );
         }
-
-        // Detect WiX UI
-        let ui_type = self.detect_wix_ui(file_path).await?;
-        if let Some(ui) = ui_type {
-            properties.insert("wix_ui_type".to_string(), ui);
+        if let Some(ui) = &authoring.ui {
+            properties.insert("wix_ui_type".to_string(), ui.clone());
         }
+        properties.insert(
+            "wix_install_scope".to_string(),
+            authoring.install_scope.clone(),
+        );
 
-        properties.insert("generator_tool".to_string(), "WiX Toolset".to_string());
-        properties.insert("msi_variant".to_string(), "WiX-generated".to_string());
-
-        Ok(properties)
+        properties
     }
 
     /// Detect WiX version
@@ -113,17 +137,23 @@ impl WixAnalyzer {
             ("WiX Toolset v3", "3.x"),
         ];
 
-        for (pattern, version) in &version_patterns {
-            let matches = common::search_file_content(file_path, &[pattern]).await?;
-            if !matches.is_empty() {
-                return Ok(Some(version.to_string()));
-            }
-        }
-
         // Generic WiX detection
         let generic_patterns = ["WiX Toolset", "Windows Installer XML"];
-        let matches = common::search_file_content(file_path, &generic_patterns).await?;
-        if !matches.is_empty() {
+
+        // One pass over the file for all versions
+        let patterns: Vec<&str> = version_patterns
+            .iter()
+            .map(|(pattern, _)| *pattern)
+            .chain(generic_patterns)
+            .collect();
+        let matches = common::scan_file_patterns(file_path, &patterns).await?;
+        if let Some(version) = matches.first_of(&version_patterns) {
+            return Ok(Some(version.to_string()));
+        }
+        if generic_patterns
+            .iter()
+            .any(|pattern| matches.contains(pattern))
+        {
             return Ok(Some("Unknown".to_string()));
         }
 
@@ -144,14 +174,16 @@ impl WixAnalyzer {
             ("WixHttpExtension", "HTTP Extension"),
         ];
 
-        let mut extensions = Vec::new();
-
-        for (pattern, extension_name) in &extension_patterns {
-            let matches = common::search_file_content(file_path, &[pattern]).await?;
-            if !matches.is_empty() {
-                extensions.push(extension_name.to_string());
-            }
-        }
+        let patterns: Vec<&str> = extension_patterns
+            .iter()
+            .map(|(pattern, _)| *pattern)
+            .collect();
+        let matches = common::scan_file_patterns(file_path, &patterns).await?;
+        let extensions = extension_patterns
+            .iter()
+            .filter(|(pattern, _)| matches.contains(pattern))
+            .map(|(_, extension_name)| extension_name.to_string())
+            .collect();
 
         Ok(extensions)
     }
@@ -167,31 +199,42 @@ impl WixAnalyzer {
             ("WixUI", "Custom UI"),
         ];
 
-        for (pattern, ui_type) in &ui_patterns {
-            let matches = common::search_file_content(file_path, &[pattern]).await?;
-            if !matches.is_empty() {
-                return Ok(Some(ui_type.to_string()));
-            }
-        }
-
-        Ok(None)
+        let patterns: Vec<&str> = ui_patterns.iter().map(|(pattern, _)| *pattern).collect();
+        let matches = common::scan_file_patterns(file_path, &patterns).await?;
+        Ok(matches.first_of(&ui_patterns).map(str::to_string))
     }
 }
 
 #[async_trait]
 impl InstallerAnalyzer for WixAnalyzer {
     async fn can_analyze(&self, file_path: &Path) -> Result<bool> {
-        // Validate file accessibility
-        common::validate_file(file_path).await?;
+        self.can_analyze_with(&DetectionContext::new(file_path).await?)
+            .await
+    }
 
+    async fn can_analyze_with(&self, context: &DetectionContext) -> Result<bool> {
         // Check if it's a WiX-generated MSI
-        Self::is_wix_msi(file_path).await
+        self.is_wix_msi(context).await
+    }
+
+    fn detection_patterns(&self) -> Vec<&'static str> {
+        WIX_PATTERNS.to_vec()
     }
 
     fn format(&self) -> InstallerFormat {
         InstallerFormat::WiX
     }
 
+    fn capabilities(&self) -> AnalyzerCapabilities {
+        AnalyzerCapabilities {
+            metadata: SupportLevel::Full,
+            file_extraction: SupportLevel::Full,
+            registry_operations: SupportLevel::Full,
+            install_scripts: SupportLevel::Unsupported,
+            signatures: SupportLevel::Unsupported,
+        }
+    }
+
     async fn extract_metadata(&self, file_path: &Path) -> Result<InstallerMetadata> {
         // Validate file first
         common::validate_file(file_path).await?;
@@ -0,0 +1,284 @@
+//! Authoring summary of WiX-built packages
+//!
+//! The compiler leaves recognizable traces of the source in the tables:
+//! `MajorUpgrade` becomes a pair of `Upgrade` rows setting
+//! `WIX_UPGRADE_DETECTED` and `WIX_DOWNGRADE_DETECTED`, `InstallScope` the
+//! `ALLUSERS` property, and extensions schedule custom actions with known
+//! names. Reading them back lets authors check the build output against the
+//! source without opening the package in Orca.
+
+use crate::analyzers::msi::database::MsiDatabase;
+use crate::analyzers::msi::tables::{CustomActionEntry, MsiTables, SequenceEntry, UpgradeEntry};
+use crate::core::{WixAuthoring, WixMajorUpgrade};
+use std::collections::HashMap;
+
+/// Property `MajorUpgrade` sets when an older version is installed
+const UPGRADE_PROPERTY: &str = "WIX_UPGRADE_DETECTED";
+/// Property `MajorUpgrade` sets when a newer version is installed
+const DOWNGRADE_PROPERTY: &str = "WIX_DOWNGRADE_DETECTED";
+
+/// `Upgrade` table attribute bits
+const MIGRATE_FEATURES: i32 = 0x001;
+const ONLY_DETECT: i32 = 0x002;
+const VERSION_MAX_INCLUSIVE: i32 = 0x200;
+
+/// Custom action error type, used for `DowngradeErrorMessage` since WiX v4
+const ERROR_ACTION_TYPE: i32 = 19;
+
+/// Actions the `Schedule` attribute of `MajorUpgrade` places
+/// `RemoveExistingProducts` after
+const UPGRADE_SCHEDULES: [&str; 4] = [
+    "InstallValidate",
+    "InstallInitialize",
+    "InstallExecute",
+    "InstallFinalize",
+];
+
+/// Custom actions scheduled by WiX extensions, by name fragment
+const EXTENSION_ACTIONS: [(&str, &str); 9] = [
+    ("QuietExec", "Util Extension"),
+    ("WixShellExec", "Util Extension"),
+    ("CloseApplications", "Util Extension"),
+    ("SchedXmlFile", "Util Extension"),
+    ("FirewallExceptions", "Firewall Extension"),
+    ("NetFxScheduleNativeImage", ".NET Framework Extension"),
+    ("ConfigureIIs", "IIS Extension"),
+    ("SqlData", "SQL Extension"),
+    ("WixUIValidatePath", "UI Extension"),
+];
+
+/// Summarize the authoring of a WiX-built package
+///
+/// `extensions` and `ui` are the ones found by scanning the package; the
+/// extensions of scheduled custom actions are added to them.
+pub fn authoring_summary(
+    db: &MsiDatabase,
+    properties: &HashMap<String, String>,
+    toolset_version: Option<String>,
+    mut extensions: Vec<String>,
+    ui: Option<String>,
+) -> WixAuthoring {
+    let count = |table, key| MsiTables::count_rows(db, table, key).unwrap_or(0);
+    // Tables missing from a package read as empty
+    let custom_actions = MsiTables::query_custom_actions(db).unwrap_or_default();
+    let upgrades = MsiTables::query_upgrades(db).unwrap_or_default();
+    let sequence =
+        MsiTables::query_sequence_actions(db, "InstallExecuteSequence").unwrap_or_default();
+    let launch_conditions = MsiTables::query_launch_conditions(db).unwrap_or_default();
+
+    for extension in custom_action_extensions(&custom_actions) {
+        if !extensions.iter().any(|known| known == extension) {
+            extensions.push(extension.to_string());
+        }
+    }
+    let blocks_downgrades = launch_conditions
+        .iter()
+        .any(|entry| entry.condition.contains(DOWNGRADE_PROPERTY))
+        || downgrade_error_scheduled(&custom_actions, &sequence);
+
+    WixAuthoring {
+        toolset_version,
+        feature_count: count("Feature", "Feature"),
+        component_count: count("Component", "Component"),
+        extensions,
+        ui,
+        major_upgrade: major_upgrade(
+            properties.get("UpgradeCode").map(String::as_str),
+            &upgrades,
+            &sequence,
+            blocks_downgrades,
+        ),
+        install_scope: install_scope(properties).to_string(),
+    }
+}
+
+/// Install scope from `ALLUSERS`, as `InstallScope`/`Scope` set it
+fn install_scope(properties: &HashMap<String, String>) -> &'static str {
+    match properties.get("ALLUSERS").map(|value| value.trim()) {
+        Some("1") => "perMachine",
+        Some("2") => "perUserOrMachine",
+        _ => "perUser",
+    }
+}
+
+/// `MajorUpgrade` configuration from the `Upgrade` rows of the product's upgrade code
+fn major_upgrade(
+    upgrade_code: Option<&str>,
+    upgrades: &[UpgradeEntry],
+    sequence: &[SequenceEntry],
+    blocks_downgrades: bool,
+) -> Option<WixMajorUpgrade> {
+    let own: Vec<&UpgradeEntry> = upgrades
+        .iter()
+        .filter(|entry| {
+            upgrade_code.is_none_or(|code| entry.upgrade_code.eq_ignore_ascii_case(code))
+        })
+        .collect();
+    // Prefer the row of `MajorUpgrade` over hand-authored `Upgrade` elements
+    let removal = own
+        .iter()
+        .filter(|entry| entry.attributes & ONLY_DETECT == 0)
+        .min_by_key(|entry| entry.action_property != UPGRADE_PROPERTY);
+    let detection = own.iter().find(|entry| {
+        entry.action_property == DOWNGRADE_PROPERTY
+            || (entry.attributes & ONLY_DETECT != 0 && entry.version_min.is_some())
+    });
+    if removal.is_none() && detection.is_none() {
+        return None;
+    }
+
+    Some(WixMajorUpgrade {
+        removes_older: removal.is_some(),
+        allows_same_version: removal
+            .is_some_and(|entry| entry.attributes & VERSION_MAX_INCLUSIVE != 0),
+        detects_newer: detection.is_some(),
+        blocks_downgrades,
+        migrates_features: removal.is_some_and(|entry| entry.attributes & MIGRATE_FEATURES != 0),
+        schedule: removal.and_then(|_| upgrade_schedule(sequence)),
+    })
+}
+
+/// Standard action `RemoveExistingProducts` runs after
+fn upgrade_schedule(sequence: &[SequenceEntry]) -> Option<String> {
+    let position = |action: &str| {
+        sequence
+            .iter()
+            .find(|entry| entry.action == action)
+            .and_then(|entry| entry.sequence)
+    };
+    let remove = position("RemoveExistingProducts")?;
+    UPGRADE_SCHEDULES
+        .iter()
+        .filter_map(|action| Some((position(action)?, *action)))
+        .filter(|(sequence, _)| *sequence < remove)
+        .max()
+        .map(|(_, action)| action.to_string())
+}
+
+/// Whether an error custom action is conditioned on a newer installed version
+fn downgrade_error_scheduled(
+    custom_actions: &[CustomActionEntry],
+    sequence: &[SequenceEntry],
+) -> bool {
+    sequence.iter().any(|entry| {
+        entry
+            .condition
+            .as_deref()
+            .is_some_and(|condition| condition.contains(DOWNGRADE_PROPERTY))
+            && custom_actions.iter().any(|action| {
+                action.action == entry.action && action.action_type & 0x3f == ERROR_ACTION_TYPE
+            })
+    })
+}
+
+/// Extensions whose custom actions the package schedules
+fn custom_action_extensions(custom_actions: &[CustomActionEntry]) -> Vec<&'static str> {
+    let mut extensions = Vec::new();
+    for (fragment, extension) in EXTENSION_ACTIONS {
+        let used = custom_actions
+            .iter()
+            .any(|action| action.action.contains(fragment));
+        if used && !extensions.contains(&extension) {
+            extensions.push(extension);
+        }
+    }
+    extensions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CODE: &str = "{B3B9D2A4-5C5E-4C38-9E3A-2F0F0C7C1D11}";
+
+    fn upgrade(
+        attributes: i32,
+        min: Option<&str>,
+        max: Option<&str>,
+        property: &str,
+    ) -> UpgradeEntry {
+        UpgradeEntry {
+            upgrade_code: CODE.to_string(),
+            version_min: min.map(str::to_string),
+            version_max: max.map(str::to_string),
+            attributes,
+            action_property: property.to_string(),
+        }
+    }
+
+    fn step(action: &str, condition: Option<&str>, sequence: i32) -> SequenceEntry {
+        SequenceEntry {
+            action: action.to_string(),
+            condition: condition.map(str::to_string),
+            sequence: Some(sequence),
+        }
+    }
+
+    #[test]
+    fn test_major_upgrade() {
+        // What `<MajorUpgrade DowngradeErrorMessage="..." />` compiles to
+        let upgrades = [
+            upgrade(
+                0x100 | MIGRATE_FEATURES,
+                None,
+                Some("1.2.0"),
+                UPGRADE_PROPERTY,
+            ),
+            upgrade(ONLY_DETECT, Some("1.2.0"), None, DOWNGRADE_PROPERTY),
+        ];
+        let sequence = [
+            step("InstallValidate", None, 1400),
+            step("RemoveExistingProducts", None, 1401),
+            step("InstallInitialize", None, 1500),
+        ];
+        let summary =
+            major_upgrade(Some(&CODE.to_lowercase()), &upgrades, &sequence, true).unwrap();
+        assert!(summary.removes_older);
+        assert!(!summary.allows_same_version);
+        assert!(summary.detects_newer);
+        assert!(summary.blocks_downgrades);
+        assert!(summary.migrates_features);
+        assert_eq!(summary.schedule.as_deref(), Some("InstallValidate"));
+
+        // Rows of related products are not the package's own upgrade
+        assert!(major_upgrade(Some("{OTHER}"), &upgrades, &sequence, false).is_none());
+        assert!(major_upgrade(Some(CODE), &[], &sequence, false).is_none());
+    }
+
+    #[test]
+    fn test_install_scope() {
+        let scope = |value: Option<&str>| {
+            let properties = value
+                .map(|value| HashMap::from([("ALLUSERS".to_string(), value.to_string())]))
+                .unwrap_or_default();
+            install_scope(&properties)
+        };
+        assert_eq!(scope(Some("1")), "perMachine");
+        assert_eq!(scope(Some("2")), "perUserOrMachine");
+        assert_eq!(scope(None), "perUser");
+    }
+
+    #[test]
+    fn test_downgrade_error_and_extensions() {
+        let actions = [
+            CustomActionEntry {
+                action: "WixDowngradeError".to_string(),
+                action_type: ERROR_ACTION_TYPE,
+                source: None,
+            },
+            CustomActionEntry {
+                action: "Wix4QuietExec_X64".to_string(),
+                action_type: 1,
+                source: Some("Wix4UtilCA_X64".to_string()),
+            },
+        ];
+        let sequence = [step(
+            "WixDowngradeError",
+            Some("WIX_DOWNGRADE_DETECTED"),
+            1410,
+        )];
+        assert!(downgrade_error_scheduled(&actions, &sequence));
+        assert!(!downgrade_error_scheduled(&actions, &[]));
+        assert_eq!(custom_action_extensions(&actions), vec!["Util Extension"]);
+    }
+}
@@ -0,0 +1,500 @@
+//! WiX Burn bundle manifest reader
+//!
+//! Burn bundles are PE stubs with a `.wixburn` section describing the
+//! containers appended to the stub. The first container holds the
+//! bootstrapper application and `BurnManifest.xml`, stored in a cabinet.
+//! Only uncompressed and MSZIP cabinets are read; LZX cabinets are reported
+//! as unsupported.
+
+use crate::analyzers::common;
+use crate::core::{AnalyzerError, Result};
+use miniz_oxide::inflate::core::{decompress, inflate_flags, DecompressorOxide};
+use miniz_oxide::inflate::TINFLStatus;
+use regex::Regex;
+use std::path::Path;
+
+/// `dwMagic` of the Burn section header
+const BURN_SECTION_MAGIC: u32 = 0x00f1_4300;
+/// Largest bootstrapper application container that is read
+const MAX_UX_CONTAINER_SIZE: u32 = 64 * 1024 * 1024;
+/// MSZIP history window
+const MSZIP_WINDOW: usize = 32 * 1024;
+
+/// Package chained by a bundle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BurnPackage {
+    pub id: String,
+    /// Element name, e.g. `ExePackage` or `MsiPackage`
+    pub package_type: String,
+    /// Condition under which the package counts as already installed
+    pub detect_condition: Option<String>,
+    /// Condition under which the package is installed at all
+    pub install_condition: Option<String>,
+}
+
+/// File a bundle installs from, either carried along or downloaded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BurnPayload {
+    pub id: String,
+    pub file_path: String,
+    pub size: Option<u64>,
+    /// `embedded`, `external` or `download`
+    pub packaging: Option<String>,
+    /// Container an embedded payload is stored in
+    pub container: Option<String>,
+    pub download_url: Option<String>,
+}
+
+impl BurnPayload {
+    /// The payload is fetched from its download URL at install time
+    pub fn is_downloaded(&self) -> bool {
+        match self.packaging.as_deref() {
+            Some("download") => true,
+            Some("embedded") => false,
+            _ => self.container.is_none() && self.download_url.is_some(),
+        }
+    }
+}
+
+/// Bundle-level condition checked by the bootstrapper application
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BurnCondition {
+    pub condition: String,
+    pub message: String,
+}
+
+/// Packages, payloads and conditions declared by a Burn bundle
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BurnManifest {
+    pub packages: Vec<BurnPackage>,
+    pub payloads: Vec<BurnPayload>,
+    pub conditions: Vec<BurnCondition>,
+}
+
+impl BurnManifest {
+    /// Read the manifest of a Burn bundle, `None` for other files
+    pub async fn read(file_path: &Path) -> Result<Option<Self>> {
+        let Some((stub_size, ux_size)) = Self::find_ux_container(file_path).await? else {
+            return Ok(None);
+        };
+        if ux_size > MAX_UX_CONTAINER_SIZE {
+            return Err(AnalyzerError::parse_error(format!(
+                "Burn UX container is too large ({} bytes)",
+                ux_size
+            )));
+        }
+
+        let cabinet =
+            common::read_file_content_range(file_path, stub_size as u64, ux_size as usize).await?;
+        let files = extract_cabinet(&cabinet)?;
+
+        let find = |root: &str| {
+            files
+                .iter()
+                .map(|(_, data)| String::from_utf8_lossy(data))
+                .find(|text| text.contains(root))
+        };
+        let Some(manifest) = find("<BurnManifest") else {
+            return Err(AnalyzerError::parse_error(
+                "BurnManifest.xml not found in the UX container",
+            ));
+        };
+        let ba_data = find("<BootstrapperApplicationData");
+
+        Ok(Some(Self::parse(&manifest, ba_data.as_deref())))
+    }
+
+    /// Parse `BurnManifest.xml` and the optional `BootstrapperApplicationData.xml`
+    pub fn parse(manifest: &str, ba_data: Option<&str>) -> Self {
+        let element =
+            Regex::new(r"<(ExePackage|MsiPackage|MspPackage|MsuPackage|BundlePackage)\b([^>]*)>")
+                .expect("valid package pattern");
+        let packages = element
+            .captures_iter(manifest)
+            .filter_map(|captures| {
+                let attributes = &captures[2];
+                Some(BurnPackage {
+                    id: xml_attribute(attributes, "Id")?,
+                    package_type: captures[1].to_string(),
+                    detect_condition: xml_attribute(attributes, "DetectCondition")
+                        .filter(|condition| !condition.is_empty()),
+                    install_condition: xml_attribute(attributes, "InstallCondition")
+                        .filter(|condition| !condition.is_empty()),
+                })
+            })
+            .collect();
+
+        let payload = Regex::new(r"<Payload\b([^>]*)>").expect("valid payload pattern");
+        let payloads = payload
+            .captures_iter(manifest)
+            .filter_map(|captures| {
+                let attributes = &captures[1];
+                Some(BurnPayload {
+                    id: xml_attribute(attributes, "Id")?,
+                    file_path: xml_attribute(attributes, "FilePath").unwrap_or_default(),
+                    size: xml_attribute(attributes, "FileSize").and_then(|size| size.parse().ok()),
+                    packaging: xml_attribute(attributes, "Packaging"),
+                    container: xml_attribute(attributes, "Container"),
+                    download_url: xml_attribute(attributes, "DownloadUrl")
+                        .filter(|url| !url.is_empty()),
+                })
+            })
+            .collect();
+
+        let condition = Regex::new(r"<WixBalCondition\b([^>]*)>").expect("valid condition pattern");
+        let conditions = ba_data
+            .map(|ba_data| {
+                condition
+                    .captures_iter(ba_data)
+                    .filter_map(|captures| {
+                        Some(BurnCondition {
+                            condition: xml_attribute(&captures[1], "Condition")?,
+                            message: xml_attribute(&captures[1], "Message").unwrap_or_default(),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            packages,
+            payloads,
+            conditions,
+        }
+    }
+
+    /// Locate the UX container through the `.wixburn` section header
+    ///
+    /// Returns the container offset (the stub size) and its size.
+    async fn find_ux_container(file_path: &Path) -> Result<Option<(u32, u32)>> {
+        let header = common::read_file_header(file_path, 4096).await?;
+        let Some(section_offset) = find_pe_section(&header, b".wixburn") else {
+            return Ok(None);
+        };
+
+        let section = common::read_file_content_range(file_path, section_offset as u64, 52).await?;
+        if section.len() < 52 || read_u32(&section, 0) != BURN_SECTION_MAGIC {
+            return Ok(None);
+        }
+        let stub_size = read_u32(&section, 24);
+        let container_count = read_u32(&section, 44);
+        if container_count == 0 {
+            return Ok(None);
+        }
+
+        Ok(Some((stub_size, read_u32(&section, 48))))
+    }
+}
+
+/// File offset of a PE section's raw data, found by name in the section table
+pub(crate) fn find_pe_section(header: &[u8], name: &[u8]) -> Option<u32> {
+    if header.len() < 0x40 || &header[..2] != b"MZ" {
+        return None;
+    }
+    let pe_offset = read_u32(header, 0x3c) as usize;
+    if header.get(pe_offset..pe_offset + 4)? != b"PE\0\0" {
+        return None;
+    }
+
+    let coff = pe_offset + 4;
+    let section_count = read_u16(header.get(coff..)?, 2) as usize;
+    let optional_header_size = read_u16(header.get(coff..)?, 16) as usize;
+    let table = coff + 20 + optional_header_size;
+
+    (0..section_count).find_map(|index| {
+        let entry = header.get(table + index * 40..table + (index + 1) * 40)?;
+        let entry_name = entry[..8].split(|&b| b == 0).next()?;
+        (entry_name == name).then(|| read_u32(entry, 20))
+    })
+}
+
+/// Extract the files of a cabinet as `(name, content)` pairs
+pub fn extract_cabinet(cabinet: &[u8]) -> Result<Vec<(String, Vec<u8>)>> {
+    let invalid = |what: &str| AnalyzerError::parse_error(format!("Invalid cabinet: {}", what));
+    if cabinet.len() < 36 || &cabinet[..4] != b"MSCF" {
+        return Err(invalid("missing MSCF signature"));
+    }
+
+    let files_offset = read_u32(cabinet, 16) as usize;
+    let folder_count = read_u16(cabinet, 26) as usize;
+    let file_count = read_u16(cabinet, 28) as usize;
+    let flags = read_u16(cabinet, 30);
+
+    // Optional per-structure reserved areas
+    let mut position = 36;
+    let (mut folder_reserve, mut data_reserve) = (0, 0);
+    if flags & 0x0004 != 0 {
+        let reserve = cabinet
+            .get(36..40)
+            .ok_or_else(|| invalid("truncated header"))?;
+        let header_reserve = read_u16(reserve, 0) as usize;
+        folder_reserve = reserve[2] as usize;
+        data_reserve = reserve[3] as usize;
+        position += 4 + header_reserve;
+    }
+    // Previous/next cabinet names are not used by Burn
+    if flags & 0x0003 != 0 {
+        return Err(invalid("multi-cabinet sets are not supported"));
+    }
+
+    let mut folders = Vec::with_capacity(folder_count);
+    for _ in 0..folder_count {
+        let folder = cabinet
+            .get(position..position + 8)
+            .ok_or_else(|| invalid("truncated folder table"))?;
+        let data_offset = read_u32(folder, 0) as usize;
+        let block_count = read_u16(folder, 4) as usize;
+        let compression = read_u16(folder, 6) & 0x000f;
+        folders.push(decompress_folder(
+            cabinet,
+            data_offset,
+            block_count,
+            compression,
+            data_reserve,
+        )?);
+        position += 8 + folder_reserve;
+    }
+
+    let mut files = Vec::with_capacity(file_count);
+    let mut position = files_offset;
+    for _ in 0..file_count {
+        let entry = cabinet
+            .get(position..position + 16)
+            .ok_or_else(|| invalid("truncated file table"))?;
+        let size = read_u32(entry, 0) as usize;
+        let offset = read_u32(entry, 4) as usize;
+        let folder = read_u16(entry, 8) as usize;
+
+        let name_start = position + 16;
+        let name_length = cabinet
+            .get(name_start..)
+            .and_then(|rest| rest.iter().position(|&b| b == 0))
+            .ok_or_else(|| invalid("unterminated file name"))?;
+        let name = String::from_utf8_lossy(&cabinet[name_start..name_start + name_length]);
+        position = name_start + name_length + 1;
+
+        let data = folders
+            .get(folder)
+            .and_then(|data| data.get(offset..offset + size))
+            .ok_or_else(|| invalid("file outside its folder"))?;
+        files.push((name.to_string(), data.to_vec()));
+    }
+
+    Ok(files)
+}
+
+/// Decompress the data blocks of one cabinet folder
+fn decompress_folder(
+    cabinet: &[u8],
+    mut position: usize,
+    block_count: usize,
+    compression: u16,
+    data_reserve: usize,
+) -> Result<Vec<u8>> {
+    let invalid = |what: &str| AnalyzerError::parse_error(format!("Invalid cabinet: {}", what));
+    let mut output = Vec::new();
+
+    for _ in 0..block_count {
+        let header = cabinet
+            .get(position..position + 8)
+            .ok_or_else(|| invalid("truncated data block"))?;
+        let compressed_size = read_u16(header, 4) as usize;
+        let uncompressed_size = read_u16(header, 6) as usize;
+        let data_start = position + 8 + data_reserve;
+        let data = cabinet
+            .get(data_start..data_start + compressed_size)
+            .ok_or_else(|| invalid("truncated data block"))?;
+        position = data_start + compressed_size;
+
+        match compression {
+            0 => output.extend_from_slice(data),
+            1 => {
+                if data.get(..2) != Some(b"CK") {
+                    return Err(invalid("missing MSZIP block signature"));
+                }
+                // Each block is a deflate stream that may refer back into the previous block
+                let history = output.len().saturating_sub(MSZIP_WINDOW);
+                let mut buffer = output[history..].to_vec();
+                let start = buffer.len();
+                buffer.resize(start + uncompressed_size, 0);
+
+                let mut state = DecompressorOxide::new();
+                let (status, _, written) = decompress(
+                    &mut state,
+                    &data[2..],
+                    &mut buffer,
+                    start,
+                    inflate_flags::TINFL_FLAG_USING_NON_WRAPPING_OUTPUT_BUF,
+                );
+                if status != TINFLStatus::Done || written != uncompressed_size {
+                    return Err(invalid("corrupt MSZIP block"));
+                }
+                output.extend_from_slice(&buffer[start..]);
+            }
+            3 => return Err(invalid("LZX compression is not supported")),
+            other => {
+                return Err(invalid(&format!("unknown compression type {}", other)));
+            }
+        }
+    }
+
+    Ok(output)
+}
+
+/// Unescaped value of an XML attribute
+fn xml_attribute(attributes: &str, name: &str) -> Option<String> {
+    let pattern = format!(" {}=\"", name);
+    let start = attributes.find(&pattern)? + pattern.len();
+    let end = attributes[start..].find('"')?;
+    Some(
+        attributes[start..start + end]
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&apos;", "'")
+            .replace("&amp;", "&"),
+    )
+}
+
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use flate2::write::DeflateEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    const MANIFEST: &str = r#"<?xml version="1.0"?><BurnManifest xmlns="http://schemas.microsoft.com/wix/2008/Burn"><Chain><ExePackage Id="NetFx48Web" DetectCondition="NETFRAMEWORK45 &gt;= 528040" InstallCondition="VersionNT &gt;= v6.1" /><MsiPackage Id="App.msi" /></Chain><Payload Id="NetFx48Web" FilePath="redist\ndp48-web.exe" FileSize="1447320" DownloadUrl="https://go.microsoft.com/fwlink/?LinkId=2085155&amp;clcid=0x409" Packaging="download" /><Payload Id="App.msi" FilePath="App.msi" FileSize="4096" Packaging="embedded" SourcePath="a0" Container="WixAttachedContainer" /></BurnManifest>"#;
+    const BA_DATA: &str = r#"<BootstrapperApplicationData><WixBalCondition Condition="VersionNT64" Message="Requires 64-bit Windows" /></BootstrapperApplicationData>"#;
+
+    /// Single-folder MSZIP cabinet with one data block per file
+    pub(crate) fn mszip_cabinet(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let blocks: Vec<Vec<u8>> = files
+            .iter()
+            .map(|(_, data)| {
+                let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(data).unwrap();
+                let mut block = b"CK".to_vec();
+                block.extend(encoder.finish().unwrap());
+                block
+            })
+            .collect();
+
+        let file_table: usize = files.iter().map(|(name, _)| 16 + name.len() + 1).sum();
+        let files_offset = 36 + 8;
+        let data_offset = files_offset + file_table;
+
+        let mut cabinet = b"MSCF".to_vec();
+        cabinet.extend(0u32.to_le_bytes());
+        cabinet.extend(0u32.to_le_bytes()); // cbCabinet, not checked
+        cabinet.extend(0u32.to_le_bytes());
+        cabinet.extend((files_offset as u32).to_le_bytes());
+        cabinet.extend(0u32.to_le_bytes());
+        cabinet.extend([3, 1]);
+        cabinet.extend(1u16.to_le_bytes());
+        cabinet.extend((files.len() as u16).to_le_bytes());
+        cabinet.extend([0u8; 6]);
+
+        cabinet.extend((data_offset as u32).to_le_bytes());
+        cabinet.extend((blocks.len() as u16).to_le_bytes());
+        cabinet.extend(1u16.to_le_bytes());
+
+        let mut offset = 0u32;
+        for (name, data) in files {
+            cabinet.extend((data.len() as u32).to_le_bytes());
+            cabinet.extend(offset.to_le_bytes());
+            cabinet.extend([0u8; 8]);
+            cabinet.extend(name.as_bytes());
+            cabinet.push(0);
+            offset += data.len() as u32;
+        }
+
+        for ((_, data), block) in files.iter().zip(&blocks) {
+            cabinet.extend(0u32.to_le_bytes());
+            cabinet.extend((block.len() as u16).to_le_bytes());
+            cabinet.extend((data.len() as u16).to_le_bytes());
+            cabinet.extend(block);
+        }
+        cabinet
+    }
+
+    /// PE stub with a `.wixburn` section followed by the UX container
+    fn burn_bundle(ux_container: &[u8]) -> Vec<u8> {
+        let mut stub = vec![0u8; 0x200];
+        stub[..2].copy_from_slice(b"MZ");
+        stub[0x3c..0x40].copy_from_slice(&0x40u32.to_le_bytes());
+        stub[0x40..0x44].copy_from_slice(b"PE\0\0");
+        stub[0x46..0x48].copy_from_slice(&1u16.to_le_bytes());
+        // No optional header, so the section table follows the COFF header
+        let section = 0x40 + 24;
+        stub[section..section + 8].copy_from_slice(b".wixburn");
+        stub[section + 20..section + 24].copy_from_slice(&0x100u32.to_le_bytes());
+
+        let burn = 0x100;
+        stub[burn..burn + 4].copy_from_slice(&BURN_SECTION_MAGIC.to_le_bytes());
+        stub[burn + 4..burn + 8].copy_from_slice(&2u32.to_le_bytes());
+        stub[burn + 24..burn + 28].copy_from_slice(&0x200u32.to_le_bytes());
+        stub[burn + 40..burn + 44].copy_from_slice(&1u32.to_le_bytes());
+        stub[burn + 44..burn + 48].copy_from_slice(&1u32.to_le_bytes());
+        stub[burn + 48..burn + 52].copy_from_slice(&(ux_container.len() as u32).to_le_bytes());
+
+        stub.extend_from_slice(ux_container);
+        stub
+    }
+
+    #[tokio::test]
+    async fn test_read_burn_manifest() {
+        let cabinet = mszip_cabinet(&[
+            ("0", MANIFEST.as_bytes()),
+            ("1", BA_DATA.as_bytes()),
+            ("2", &[0u8; 100]),
+        ]);
+        let files = extract_cabinet(&cabinet).unwrap();
+        assert_eq!(files.len(), 3);
+        assert_eq!(files[1].1, BA_DATA.as_bytes());
+
+        let mut bundle = tempfile::NamedTempFile::new().unwrap();
+        bundle.write_all(&burn_bundle(&cabinet)).unwrap();
+        let manifest = BurnManifest::read(bundle.path()).await.unwrap().unwrap();
+
+        assert_eq!(manifest.packages.len(), 2);
+        assert_eq!(manifest.packages[0].id, "NetFx48Web");
+        assert_eq!(
+            manifest.packages[0].detect_condition.as_deref(),
+            Some("NETFRAMEWORK45 >= 528040")
+        );
+        assert_eq!(manifest.packages[1].package_type, "MsiPackage");
+        assert_eq!(manifest.packages[1].install_condition, None);
+        assert_eq!(manifest.payloads.len(), 2);
+        assert!(manifest.payloads[0].is_downloaded());
+        assert_eq!(
+            manifest.payloads[0].download_url.as_deref(),
+            Some("https://go.microsoft.com/fwlink/?LinkId=2085155&clcid=0x409")
+        );
+        assert_eq!(manifest.payloads[1].size, Some(4096));
+        assert!(!manifest.payloads[1].is_downloaded());
+        assert_eq!(
+            manifest.conditions,
+            vec![BurnCondition {
+                condition: "VersionNT64".to_string(),
+                message: "Requires 64-bit Windows".to_string(),
+            }]
+        );
+
+        let mut plain = tempfile::NamedTempFile::new().unwrap();
+        plain.write_all(b"MZ not a bundle").unwrap();
+        assert!(BurnManifest::read(plain.path()).await.unwrap().is_none());
+    }
+}
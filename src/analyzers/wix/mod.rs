@@ -1,6 +1,9 @@
 //! WiX Toolset format analyzer for MSI packages generated by WiX
 
 pub mod analyzer;
+pub mod authoring;
+pub mod burn;
 
 // Re-export main components
 pub use analyzer::WixAnalyzer;
+pub use burn::{BurnCondition, BurnManifest, BurnPackage, BurnPayload};
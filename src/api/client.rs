@@ -0,0 +1,142 @@
+//! Client of the job queue API used by analysis workers
+
+use crate::api::{ClaimRequest, JobInfo, JobKind, JobResult};
+use crate::core::{AnalyzerError, Result};
+use reqwest::{Client, Response, StatusCode};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Worker side of the job queue API
+pub struct WorkerClient {
+    client: Client,
+    base_url: String,
+    worker: String,
+}
+
+impl WorkerClient {
+    /// Create a client of the server at `base_url` for the named worker
+    pub fn new(base_url: &str, worker: &str) -> Self {
+        let client = Client::builder()
+            .user_agent(concat!(
+                "installer-analyzer-worker/",
+                env!("CARGO_PKG_VERSION")
+            ))
+            .connect_timeout(Duration::from_secs(30))
+            .build()
+            .expect("Failed to create HTTP client");
+
+        Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            worker: worker.to_string(),
+        }
+    }
+
+    /// Name the worker identifies itself with
+    pub fn worker(&self) -> &str {
+        &self.worker
+    }
+
+    /// Claim the next job of the given kinds, if any is queued
+    pub async fn claim(&self, kinds: &[JobKind]) -> Result<Option<JobInfo>> {
+        let request = ClaimRequest {
+            worker: self.worker.clone(),
+            kinds: kinds.to_vec(),
+        };
+        let response = self
+            .client
+            .post(format!("{}/jobs/claim", self.base_url))
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| request_failed("claim a job", e))?;
+        if response.status() == StatusCode::NO_CONTENT {
+            return Ok(None);
+        }
+        let response = check_status(response, "claim a job").await?;
+        let job = response
+            .json()
+            .await
+            .map_err(|e| request_failed("read the claimed job", e))?;
+        Ok(Some(job))
+    }
+
+    /// Download the installer of a claimed job
+    pub async fn content(&self, id: Uuid) -> Result<Vec<u8>> {
+        let response = self
+            .client
+            .get(format!("{}/jobs/{}/content", self.base_url, id))
+            .query(&[("worker", &self.worker)])
+            .send()
+            .await
+            .map_err(|e| request_failed("download the installer", e))?;
+        let response = check_status(response, "download the installer").await?;
+        let content = response
+            .bytes()
+            .await
+            .map_err(|e| request_failed("download the installer", e))?;
+        Ok(content.to_vec())
+    }
+
+    /// Send the JSON report of a claimed job
+    pub async fn complete(&self, id: Uuid, report: serde_json::Value) -> Result<JobInfo> {
+        self.send_result(
+            id,
+            JobResult {
+                worker: self.worker.clone(),
+                report: Some(report),
+                error: None,
+            },
+        )
+        .await
+    }
+
+    /// Report why a claimed job could not be analyzed
+    pub async fn fail(&self, id: Uuid, error: &str) -> Result<JobInfo> {
+        self.send_result(
+            id,
+            JobResult {
+                worker: self.worker.clone(),
+                report: None,
+                error: Some(error.to_string()),
+            },
+        )
+        .await
+    }
+
+    async fn send_result(&self, id: Uuid, result: JobResult) -> Result<JobInfo> {
+        let response = self
+            .client
+            .post(format!("{}/jobs/{}/result", self.base_url, id))
+            .json(&result)
+            .send()
+            .await
+            .map_err(|e| request_failed("send the job result", e))?;
+        let response = check_status(response, "send the job result").await?;
+        response
+            .json()
+            .await
+            .map_err(|e| request_failed("read the job state", e))
+    }
+}
+
+fn request_failed(action: &str, error: reqwest::Error) -> AnalyzerError {
+    AnalyzerError::generic(format!("Failed to {}: {}", action, error))
+}
+
+/// Turn an error status into an error carrying the server's message
+async fn check_status(response: Response, action: &str) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body: serde_json::Value = response.json().await.unwrap_or_default();
+    let message = body
+        .get("error")
+        .and_then(|message| message.as_str())
+        .unwrap_or_default();
+    Err(AnalyzerError::generic(format!(
+        "Failed to {}: server responded with {} {}",
+        action, status, message
+    )))
+}
@@ -1,14 +1,88 @@
-//! API module for programmatic access
+//! HTTP API with a job queue for distributed analysis
+//!
+//! `installer-analyzer serve` accepts installers over HTTP and queues them;
+//! `installer-analyzer worker` processes pull jobs from the queue, analyze
+//! them locally and post the JSON report back, so sandbox analysis scales
+//! over as many machines as are available. The routes are:
+//!
+//! | Route | Purpose |
+//! |-------|---------|
+//! | `POST /jobs?name=<file>&kind=static\|sandbox` | Submit an installer (request body) |
+//! | `GET /jobs` | List all jobs |
+//! | `GET /jobs/<id>` | State of a job |
+//! | `GET /jobs/<id>/report` | JSON report of a completed job |
+//! | `POST /jobs/claim` | Claim the next job, used by workers |
+//! | `GET /jobs/<id>/content?worker=<name>` | Download a claimed installer |
+//! | `POST /jobs/<id>/result` | Report the outcome of a claimed job |
+//! | `GET /healthz` | Liveness probe, succeeds while the process serves requests |
+//! | `GET /readyz` | Readiness probe, fails once the server is shutting down |
+//! | `GET /results` | Latest recorded analysis of every installer |
+//! | `GET /results/<id>` | Analysis result recorded in the database |
+//! | `GET /results/<id>/html` | HTML report of a recorded analysis |
+//! | `GET /results/<id>/files?path=<entry>&limit=<bytes>` | File packaged in the installer of a recorded analysis |
+//! | `GET /` | Web UI, on servers started with `ui` |
+//!
+//! Request bodies, submitted installers as well as worker reports, larger
+//! than `max_upload` are rejected with 413 Payload Too Large. A server
+//! started with `static_only` rejects sandbox jobs, for deployments such as
+//! Linux containers where no worker can run the sandbox. The `/results`
+//! routes need a results database. Servers with a store directory
+//! keep submitted installers and can re-analyze them on a schedule, see
+//! [`schedule`]; packaged files are served from stored installers only, read
+//! one at a time without extracting the rest.
 
-// TODO: Implement REST API or library API for external integration
-// This module will provide programmatic access to the analyzer functionality
+pub mod client;
+pub mod queue;
+pub mod schedule;
 
-use crate::core::Result;
+pub use client::WorkerClient;
+pub use queue::{JobContent, JobInfo, JobKind, JobQueue, JobStatus};
+pub use schedule::{InstallerStore, VerdictChange};
+
+use crate::analyzers::common::EntryReader;
+use crate::analyzers::AnalyzerFactory;
+use crate::core::{AnalysisResult, AnalyzerError, Result};
+use crate::reporting::{merge, ReportFormat, ReportGenerator, Reporter, ResultsDatabase};
+use hyper::body::HttpBody;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use uuid::Uuid;
+
+/// Web UI for submitting installers and browsing recorded results
+const UI_PAGE: &str = include_str!("ui.html");
+
+/// Default upper bound of a submitted installer
+pub const DEFAULT_MAX_UPLOAD: u64 = 2 * 1024 * 1024 * 1024;
 
 /// API configuration
 pub struct ApiConfig {
     pub host: String,
     pub port: u16,
+    /// Time a worker has to finish a claimed job before it is handed out again
+    pub lease: Duration,
+    /// Results database every completed job is recorded in
+    pub db: Option<PathBuf>,
+    /// Accept static analysis jobs only
+    pub static_only: bool,
+    /// Serve the web UI at `/`
+    pub ui: bool,
+    /// Directory submitted installers are kept in
+    pub store_dir: Option<PathBuf>,
+    /// Interval at which stored installers are queued for re-analysis
+    pub reanalyze_every: Option<Duration>,
+    /// URL verdict changes found by re-analysis are posted to
+    pub notify_url: Option<String>,
+    /// Largest request body accepted, larger ones are rejected with 413
+    pub max_upload: u64,
 }
 
 impl Default for ApiConfig {
@@ -16,27 +90,1012 @@ impl Default for ApiConfig {
         Self {
             host: "127.0.0.1".to_string(),
             port: 8080,
+            lease: queue::DEFAULT_LEASE,
+            db: None,
+            static_only: false,
+            ui: false,
+            store_dir: None,
+            reanalyze_every: None,
+            notify_url: None,
+            max_upload: DEFAULT_MAX_UPLOAD,
         }
     }
 }
 
-/// API server (placeholder)
+/// Request of a worker for its next job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClaimRequest {
+    pub worker: String,
+    /// Kinds of jobs the worker can run
+    pub kinds: Vec<JobKind>,
+}
+
+/// Outcome of a job reported by its worker, either a report or an error
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobResult {
+    pub worker: String,
+    #[serde(default)]
+    pub report: Option<serde_json::Value>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// API server with the job queue
 pub struct ApiServer {
     config: ApiConfig,
+    queue: Arc<JobQueue>,
+}
+
+/// State shared by the request handlers
+struct ServerState {
+    queue: Arc<JobQueue>,
+    db: Option<Mutex<ResultsDatabase>>,
+    static_only: bool,
+    ui: bool,
+    store: Option<InstallerStore>,
+    notify_url: Option<String>,
+    max_upload: u64,
+    /// Set once shutdown started, so load balancers stop sending requests
+    shutting_down: AtomicBool,
 }
 
 impl ApiServer {
     pub fn new(config: ApiConfig) -> Self {
-        Self { config }
+        let queue = Arc::new(JobQueue::new(config.lease));
+        Self { config, queue }
     }
 
+    /// Job queue served by the API
+    pub fn queue(&self) -> Arc<JobQueue> {
+        self.queue.clone()
+    }
+
+    /// Bind the configured address
+    pub fn bind(&self) -> Result<TcpListener> {
+        let listener = TcpListener::bind((self.config.host.as_str(), self.config.port))?;
+        listener.set_nonblocking(true)?;
+        Ok(listener)
+    }
+
+    /// Serve on the configured address until the process is stopped
     pub async fn start(&self) -> Result<()> {
-        tracing::info!("API server functionality not yet implemented");
-        tracing::info!(
-            "Would start server on {}:{}",
-            self.config.host,
-            self.config.port
+        let listener = self.bind()?;
+        tracing::info!("API server listening on {}", listener.local_addr()?);
+        self.serve(listener, std::future::pending()).await
+    }
+
+    /// Serve on a bound listener until `shutdown` resolves
+    ///
+    /// Requests in flight when `shutdown` resolves are still answered.
+    pub async fn serve(
+        &self,
+        listener: TcpListener,
+        shutdown: impl Future<Output = ()>,
+    ) -> Result<()> {
+        let db = match &self.config.db {
+            Some(path) => Some(Mutex::new(ResultsDatabase::open(path)?)),
+            None => None,
+        };
+        let state = Arc::new(ServerState {
+            queue: self.queue.clone(),
+            db,
+            static_only: self.config.static_only,
+            ui: self.config.ui,
+            store: self.config.store_dir.as_deref().map(InstallerStore::new),
+            notify_url: self.config.notify_url.clone(),
+            max_upload: self.config.max_upload,
+            shutting_down: AtomicBool::new(false),
+        });
+        let scheduler = self.config.reanalyze_every.map(|every| {
+            let state = state.clone();
+            tokio::spawn(async move {
+                if let Some(store) = &state.store {
+                    schedule::reanalyze(&state.queue, store, every).await;
+                }
+            })
+        });
+        let stopping = state.clone();
+        let shutdown = async move {
+            shutdown.await;
+            stopping.shutting_down.store(true, Ordering::SeqCst);
+            tracing::info!("Shutting down API server, finishing open requests");
+        };
+
+        let make_service = make_service_fn(move |_| {
+            let state = state.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    let state = state.clone();
+                    async move { Ok::<_, Infallible>(handle(request, &state).await) }
+                }))
+            }
+        });
+
+        let served = match Server::from_tcp(listener) {
+            Ok(server) => server
+                .serve(make_service)
+                .with_graceful_shutdown(shutdown)
+                .await
+                .map_err(|e| AnalyzerError::generic(format!("API server failed: {}", e))),
+            Err(e) => Err(AnalyzerError::generic(format!(
+                "Failed to start API server: {}",
+                e
+            ))),
+        };
+        if let Some(scheduler) = scheduler {
+            scheduler.abort();
+        }
+        served
+    }
+}
+
+/// Route a request
+async fn handle(request: Request<Body>, state: &ServerState) -> Response<Body> {
+    let segments: Vec<String> = request
+        .uri()
+        .path()
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(str::to_string)
+        .collect();
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+    let query = request.uri().query().unwrap_or_default().to_string();
+    let method = request.method().clone();
+
+    match (method, segments.as_slice()) {
+        (Method::GET, []) if state.ui => Response::builder()
+            .status(StatusCode::OK)
+            .header("Content-Type", "text/html; charset=utf-8")
+            .body(Body::from(UI_PAGE))
+            .unwrap_or_default(),
+        (Method::GET, ["healthz"]) => {
+            json_response(StatusCode::OK, &serde_json::json!({ "status": "ok" }))
+        }
+        (Method::GET, ["readyz"]) => readiness(state),
+        (Method::GET, ["jobs"]) => json_response(StatusCode::OK, &state.queue.list()),
+        (Method::POST, ["jobs"]) => submit(request, &query, state).await,
+        (Method::POST, ["jobs", "claim"]) => {
+            match read_json::<ClaimRequest>(request, state.max_upload).await {
+                Ok(claim) => match state.queue.claim(&claim.worker, &claim.kinds) {
+                    Some(job) => {
+                        tracing::info!("Job {} claimed by {}", job.id, claim.worker);
+                        json_response(StatusCode::OK, &job)
+                    }
+                    None => empty_response(StatusCode::NO_CONTENT),
+                },
+                Err(response) => response,
+            }
+        }
+        (Method::GET, ["jobs", id]) => with_job_id(id, |id| match state.queue.get(id) {
+            Some(job) => json_response(StatusCode::OK, &job),
+            None => error_response(StatusCode::NOT_FOUND, &format!("Unknown job {}", id)),
+        }),
+        (Method::GET, ["jobs", id, "report"]) => {
+            with_job_id(id, |id| match state.queue.report(id) {
+                Some(report) => json_response(StatusCode::OK, &report),
+                None => error_response(StatusCode::NOT_FOUND, &format!("No report for job {}", id)),
+            })
+        }
+        (Method::GET, ["jobs", id, "content"]) => match id.parse::<Uuid>() {
+            Ok(id) => {
+                let worker = query_param(&query, "worker").unwrap_or_default();
+                match state.queue.content(id, &worker) {
+                    Some(content) => job_content(content).await,
+                    None => not_held(id, &worker),
+                }
+            }
+            Err(_) => invalid_job_id(id),
+        },
+        (Method::POST, ["jobs", id, "result"]) => {
+            let id = match id.parse::<Uuid>() {
+                Ok(id) => id,
+                Err(_) => return invalid_job_id(id),
+            };
+            match read_json::<JobResult>(request, state.max_upload).await {
+                Ok(result) => finish(id, result, state),
+                Err(response) => response,
+            }
+        }
+        (Method::GET, ["results"]) => match with_db(state, |db| db.analyses()) {
+            Ok(analyses) => json_response(StatusCode::OK, &analyses),
+            Err((status, message)) => error_response(status, &message),
+        },
+        (Method::GET, ["results", id]) => match recorded_result(id, state) {
+            Ok(result) => json_response(StatusCode::OK, &result),
+            Err((status, message)) => error_response(status, &message),
+        },
+        (Method::GET, ["results", id, "html"]) => match recorded_result(id, state) {
+            Ok(result) => match ReportGenerator::new()
+                .generate_report(&result, ReportFormat::Html)
+                .await
+            {
+                Ok(html) => Response::builder()
+                    .status(StatusCode::OK)
+                    .header("Content-Type", "text/html; charset=utf-8")
+                    .body(Body::from(html))
+                    .unwrap_or_default(),
+                Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+            },
+            Err((status, message)) => error_response(status, &message),
+        },
+        (Method::GET, ["results", id, "files"]) => packaged_file(id, &query, state).await,
+        _ => error_response(StatusCode::NOT_FOUND, "Not found"),
+    }
+}
+
+/// Run a query against the results database
+fn with_db<T>(
+    state: &ServerState,
+    query: impl FnOnce(&ResultsDatabase) -> Result<T>,
+) -> std::result::Result<T, (StatusCode, String)> {
+    let Some(db) = &state.db else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            "Server has no results database".to_string(),
+        ));
+    };
+    let db = db.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    query(&db).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Analysis result recorded under `id`
+fn recorded_result(
+    id: &str,
+    state: &ServerState,
+) -> std::result::Result<AnalysisResult, (StatusCode, String)> {
+    let Ok(id) = id.parse::<i64>() else {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Invalid result id: {}", id),
+        ));
+    };
+    with_db(state, |db| db.result(id))?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, format!("Unknown result {}", id)))
+}
+
+/// Stream a file packaged in the stored installer of a recorded analysis
+async fn packaged_file(id: &str, query: &str, state: &ServerState) -> Response<Body> {
+    let Some(entry) = query_param(query, "path").filter(|path| !path.is_empty()) else {
+        return error_response(StatusCode::BAD_REQUEST, "Missing file path (?path=)");
+    };
+    let limit = match query_param(query, "limit").map(|limit| limit.parse::<u64>()) {
+        None => None,
+        Some(Ok(limit)) => Some(limit),
+        Some(Err(_)) => return error_response(StatusCode::BAD_REQUEST, "Invalid limit"),
+    };
+    let result = match recorded_result(id, state) {
+        Ok(result) => result,
+        Err((status, message)) => return error_response(status, &message),
+    };
+    let Some(store) = &state.store else {
+        return error_response(StatusCode::NOT_FOUND, "Server has no installer store");
+    };
+    let installer = match store.installer(&result.metadata.file_hash) {
+        Ok(Some(installer)) => installer,
+        Ok(None) => {
+            return error_response(
+                StatusCode::NOT_FOUND,
+                &format!("Installer of result {} is not stored", id),
+            )
+        }
+        Err(e) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+    };
+
+    let opened = match AnalyzerFactory::create_analyzer_or_fallback(&installer.path).await {
+        Ok(analyzer) => analyzer.open_file(&installer.path, Path::new(&entry)).await,
+        Err(e) => Err(e),
+    };
+    let reader = match opened {
+        Ok(Some(reader)) => reader,
+        Ok(None) => {
+            return error_response(
+                StatusCode::NOT_FOUND,
+                &format!("{} is not packaged in {}", entry, installer.file_name),
+            )
+        }
+        Err(e) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+    };
+    let reader: EntryReader = match limit {
+        Some(limit) => Box::pin(reader.take(limit)),
+        None => reader,
+    };
+
+    let content_type = if is_text_file(&entry) {
+        "text/plain; charset=utf-8"
+    } else {
+        "application/octet-stream"
+    };
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", content_type)
+        .header("X-Content-Type-Options", "nosniff")
+        .body(stream_body(reader))
+        .unwrap_or_default()
+}
+
+/// Installer of a claimed job, stored installers streamed from the store
+async fn job_content(content: JobContent) -> Response<Body> {
+    let body = match content {
+        JobContent::Inline(content) => Body::from(content),
+        JobContent::Stored(path) => match tokio::fs::File::open(&path).await {
+            Ok(file) => stream_body(Box::pin(file)),
+            Err(e) => {
+                tracing::warn!("Failed to read stored installer {}: {}", path.display(), e);
+                return error_response(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string());
+            }
+        },
+    };
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/octet-stream")
+        .body(body)
+        .unwrap_or_default()
+}
+
+/// Body sending the content of `reader` as it is read
+fn stream_body(mut reader: EntryReader) -> Body {
+    let (mut sender, body) = Body::channel();
+    tokio::spawn(async move {
+        let mut buffer = vec![0; 64 * 1024];
+        loop {
+            match reader.read(&mut buffer).await {
+                Ok(0) => break,
+                Ok(count) => {
+                    let chunk = hyper::body::Bytes::copy_from_slice(&buffer[..count]);
+                    // The client went away
+                    if sender.send_data(chunk).await.is_err() {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to stream content: {}", e);
+                    sender.abort();
+                    break;
+                }
+            }
+        }
+    });
+    body
+}
+
+/// Whether a packaged file is served as text, judged by its extension
+fn is_text_file(path: &str) -> bool {
+    const TEXT_EXTENSIONS: &[&str] = &[
+        "txt", "md", "rst", "log", "ini", "cfg", "conf", "toml", "yaml", "yml", "json", "xml",
+        "manifest", "config", "csv", "ps1", "psm1", "bat", "cmd", "vbs", "js", "py", "sh", "nsi",
+        "iss", "reg", "inf", "html", "htm", "css",
+    ];
+    Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| TEXT_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str()))
+}
+
+/// Queue the installer in the request body
+async fn submit(request: Request<Body>, query: &str, state: &ServerState) -> Response<Body> {
+    let name = query_param(query, "name").unwrap_or_default();
+    // Only the file name is kept, workers write the installer under it
+    let file_name = name.rsplit(['/', '\\']).next().unwrap_or_default();
+    if file_name.is_empty() || file_name == "." || file_name == ".." {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            "Missing installer file name (?name=)",
+        );
+    }
+    let kind_name = query_param(query, "kind").unwrap_or_else(|| "static".to_string());
+    let Some(kind) = JobKind::parse(&kind_name) else {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            &format!("Unknown job kind: {}", kind_name),
+        );
+    };
+
+    if kind == JobKind::Sandbox && state.static_only {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            "Server accepts static analysis jobs only",
         );
-        Ok(())
+    }
+
+    let content = match read_body(request, state.max_upload).await {
+        Ok(content) if !content.is_empty() => content,
+        Ok(_) => return error_response(StatusCode::BAD_REQUEST, "Empty installer"),
+        Err(response) => return response,
+    };
+    if let Some(store) = &state.store {
+        if let Err(e) = store.store(file_name, &content).await {
+            tracing::warn!("Failed to store {}: {}", file_name, e);
+        }
+    }
+    let job = state.queue.submit(file_name, kind, content);
+    tracing::info!("Queued {} job {} for {}", job.kind, job.id, job.file_name);
+    json_response(StatusCode::CREATED, &job)
+}
+
+/// Read a request body of at most `limit` bytes
+///
+/// Bodies announcing a larger `Content-Length` are rejected before reading,
+/// others as soon as the bytes read exceed the limit.
+async fn read_body(
+    request: Request<Body>,
+    limit: u64,
+) -> std::result::Result<Vec<u8>, Response<Body>> {
+    let too_large = || {
+        error_response(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            &format!("Request body exceeds the upload limit of {} bytes", limit),
+        )
+    };
+    let announced = request
+        .headers()
+        .get(hyper::header::CONTENT_LENGTH)
+        .and_then(|length| length.to_str().ok())
+        .and_then(|length| length.parse::<u64>().ok());
+    if announced.is_some_and(|length| length > limit) {
+        return Err(too_large());
+    }
+
+    let mut body = request.into_body();
+    let mut content = Vec::with_capacity(announced.unwrap_or_default() as usize);
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(|e| error_response(StatusCode::BAD_REQUEST, &e.to_string()))?;
+        if (content.len() + chunk.len()) as u64 > limit {
+            return Err(too_large());
+        }
+        content.extend_from_slice(&chunk);
+    }
+    Ok(content)
+}
+
+/// Whether the server takes new requests, with the number of queued jobs
+fn readiness(state: &ServerState) -> Response<Body> {
+    if state.shutting_down.load(Ordering::SeqCst) {
+        return error_response(StatusCode::SERVICE_UNAVAILABLE, "Shutting down");
+    }
+    let queued = state
+        .queue
+        .list()
+        .iter()
+        .filter(|job| job.status == JobStatus::Queued)
+        .count();
+    json_response(
+        StatusCode::OK,
+        &serde_json::json!({
+            "status": "ready",
+            "static_only": state.static_only,
+            "queued": queued,
+        }),
+    )
+}
+
+/// Store the outcome a worker reported and record reports in the database
+fn finish(id: Uuid, result: JobResult, state: &ServerState) -> Response<Body> {
+    let job = match (result.report, result.error) {
+        (Some(report), _) => {
+            // Reports of workers no longer holding the job are not recorded
+            let job = state.queue.complete(id, &result.worker, report.clone());
+            if job.is_some() {
+                let change = state.db.as_ref().and_then(|db| record(db, &report));
+                if let (Some(change), Some(job)) = (change, &job) {
+                    if job.reanalysis {
+                        report_verdict_change(change, state);
+                    }
+                }
+            }
+            job
+        }
+        (None, Some(error)) => state.queue.fail(id, &result.worker, &error),
+        (None, None) => {
+            return error_response(StatusCode::BAD_REQUEST, "Missing report or error");
+        }
+    };
+    match job {
+        Some(job) => {
+            tracing::info!("Job {} {:?} by {}", job.id, job.status, result.worker);
+            json_response(StatusCode::OK, &job)
+        }
+        None => not_held(id, &result.worker),
+    }
+}
+
+/// Record a report, returning how the verdict on the installer changed since
+/// its previous analysis
+fn record(db: &Mutex<ResultsDatabase>, report: &serde_json::Value) -> Option<VerdictChange> {
+    let Some(result) = merge::result_from_report(report) else {
+        tracing::warn!("Report is not an analysis report, not recorded in the database");
+        return None;
+    };
+    let mut db = db.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let previous = match db.latest_result_by_hash(&result.metadata.file_hash) {
+        Ok(previous) => previous,
+        Err(e) => {
+            tracing::warn!("Failed to look up the previous analysis: {}", e);
+            None
+        }
+    };
+    if let Err(e) = db.record(&result) {
+        tracing::warn!("Failed to record report in the database: {}", e);
+        return None;
+    }
+    previous.and_then(|previous| schedule::verdict_change(&previous, &result))
+}
+
+/// Log a verdict change found by re-analysis and post it to the notification URL
+fn report_verdict_change(change: VerdictChange, state: &ServerState) {
+    tracing::warn!(
+        "Verdict on {} ({}) changed from {} to {} on re-analysis",
+        change.source.as_deref().unwrap_or("installer"),
+        change.file_hash,
+        change.previous_risk,
+        change.risk
+    );
+    if let Some(url) = state.notify_url.clone() {
+        tokio::spawn(async move { schedule::notify(&url, &change).await });
+    }
+}
+
+/// Parse a JSON request body
+async fn read_json<T: serde::de::DeserializeOwned>(
+    request: Request<Body>,
+    limit: u64,
+) -> std::result::Result<T, Response<Body>> {
+    let body = read_body(request, limit).await?;
+    serde_json::from_slice(&body)
+        .map_err(|e| error_response(StatusCode::BAD_REQUEST, &e.to_string()))
+}
+
+fn with_job_id(id: &str, respond: impl FnOnce(Uuid) -> Response<Body>) -> Response<Body> {
+    match id.parse::<Uuid>() {
+        Ok(id) => respond(id),
+        Err(_) => invalid_job_id(id),
+    }
+}
+
+fn invalid_job_id(id: &str) -> Response<Body> {
+    error_response(StatusCode::BAD_REQUEST, &format!("Invalid job id: {}", id))
+}
+
+fn not_held(id: Uuid, worker: &str) -> Response<Body> {
+    error_response(
+        StatusCode::CONFLICT,
+        &format!("Job {} is not running on worker '{}'", id, worker),
+    )
+}
+
+fn json_response<T: Serialize>(status: StatusCode, value: &T) -> Response<Body> {
+    match serde_json::to_vec(value) {
+        Ok(body) => Response::builder()
+            .status(status)
+            .header("Content-Type", "application/json")
+            .body(Body::from(body))
+            .unwrap_or_default(),
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+    }
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response<Body> {
+    let body = serde_json::json!({ "error": message }).to_string();
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .body(Body::from(body))
+        .unwrap_or_default()
+}
+
+fn empty_response(status: StatusCode) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .body(Body::empty())
+        .unwrap_or_default()
+}
+
+/// Percent-decoded value of a query parameter
+fn query_param(query: &str, key: &str) -> Option<String> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(name, _)| *name == key)
+        .map(|(_, value)| percent_decode(value))
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| value.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], escaped) {
+            (_, Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (b'+', None) => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            (byte, None) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_param() {
+        let query = "name=My%20Setup%2B1.exe&kind=sandbox&worker=win+1";
+        assert_eq!(
+            query_param(query, "name").as_deref(),
+            Some("My Setup+1.exe")
+        );
+        assert_eq!(query_param(query, "kind").as_deref(), Some("sandbox"));
+        assert_eq!(query_param(query, "worker").as_deref(), Some("win 1"));
+        assert_eq!(query_param("name=100%", "name").as_deref(), Some("100%"));
+        assert!(query_param(query, "missing").is_none());
+    }
+
+    #[test]
+    fn test_is_text_file() {
+        assert!(is_text_file("docs/README.TXT"));
+        assert!(is_text_file("app.exe.config"));
+        assert!(!is_text_file("bin/app.exe"));
+        assert!(!is_text_file("LICENSE"));
+    }
+
+    #[tokio::test]
+    async fn test_worker_round_trip() {
+        let server = ApiServer::new(ApiConfig {
+            port: 0,
+            ..Default::default()
+        });
+        let listener = server.bind().unwrap();
+        let address = listener.local_addr().unwrap();
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        let queue = server.queue();
+        let serving = tokio::spawn(async move {
+            server
+                .serve(listener, async {
+                    stopped.await.ok();
+                })
+                .await
+        });
+
+        let base = format!("http://{}", address);
+        let submitted: JobInfo = reqwest::Client::new()
+            .post(format!("{}/jobs?name=..%2Fsetup.exe&kind=static", base))
+            .body(b"MZ".to_vec())
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(submitted.file_name, "setup.exe");
+
+        let client = WorkerClient::new(&base, "worker 1");
+        let job = client.claim(&[JobKind::Static]).await.unwrap().unwrap();
+        assert_eq!(job.id, submitted.id);
+        assert!(client.claim(&[JobKind::Static]).await.unwrap().is_none());
+        assert_eq!(client.content(job.id).await.unwrap(), b"MZ");
+        assert!(WorkerClient::new(&base, "worker 2")
+            .content(job.id)
+            .await
+            .is_err());
+
+        let completed = client
+            .complete(job.id, serde_json::json!({"metadata": {}}))
+            .await
+            .unwrap();
+        assert_eq!(completed.status, JobStatus::Completed);
+        assert!(queue.report(job.id).is_some());
+
+        stop.send(()).unwrap();
+        serving.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_probes_and_static_only() {
+        let server = ApiServer::new(ApiConfig {
+            port: 0,
+            static_only: true,
+            ..Default::default()
+        });
+        let listener = server.bind().unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        let serving = tokio::spawn(async move {
+            server
+                .serve(listener, async {
+                    stopped.await.ok();
+                })
+                .await
+        });
+
+        let client = reqwest::Client::new();
+        let health = client
+            .get(format!("{}/healthz", base))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(health.status(), reqwest::StatusCode::OK);
+        let ready: serde_json::Value = client
+            .get(format!("{}/readyz", base))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(ready["status"], "ready");
+        assert_eq!(ready["static_only"], true);
+
+        let sandbox = client
+            .post(format!("{}/jobs?name=setup.exe&kind=sandbox", base))
+            .body(b"MZ".to_vec())
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(sandbox.status(), reqwest::StatusCode::BAD_REQUEST);
+
+        stop.send(()).unwrap();
+        serving.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_upload_limit() {
+        use tokio::io::AsyncWriteExt;
+
+        let server = ApiServer::new(ApiConfig {
+            port: 0,
+            max_upload: 4,
+            ..Default::default()
+        });
+        let listener = server.bind().unwrap();
+        let address = listener.local_addr().unwrap();
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        let queue = server.queue();
+        let serving = tokio::spawn(async move {
+            server
+                .serve(listener, async {
+                    stopped.await.ok();
+                })
+                .await
+        });
+
+        let client = reqwest::Client::new();
+        let url = format!("http://{}/jobs?name=setup.exe", address);
+        let oversized = client.post(&url).body(b"MZ-setup".to_vec()).send();
+        assert_eq!(
+            oversized.await.unwrap().status(),
+            reqwest::StatusCode::PAYLOAD_TOO_LARGE
+        );
+        let accepted = client.post(&url).body(b"MZ".to_vec()).send();
+        assert_eq!(
+            accepted.await.unwrap().status(),
+            reqwest::StatusCode::CREATED
+        );
+
+        // Chunked uploads announce no length and are cut off while reading
+        let mut stream = tokio::net::TcpStream::connect(address).await.unwrap();
+        stream
+            .write_all(
+                b"POST /jobs?name=setup.exe HTTP/1.1\r\nHost: localhost\r\n\
+                  Transfer-Encoding: chunked\r\nConnection: close\r\n\r\n\
+                  3\r\nMZ-\r\n5\r\nsetup\r\n0\r\n\r\n",
+            )
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 413"), "{}", response);
+        assert_eq!(queue.list().len(), 1);
+
+        stop.send(()).unwrap();
+        serving.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_ui_and_results() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = ApiServer::new(ApiConfig {
+            port: 0,
+            db: Some(dir.path().join("results.sqlite")),
+            ui: true,
+            ..Default::default()
+        });
+        let listener = server.bind().unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        let serving = tokio::spawn(async move {
+            server
+                .serve(listener, async {
+                    stopped.await.ok();
+                })
+                .await
+        });
+
+        let client = reqwest::Client::new();
+        let page = client.get(format!("{}/", base)).send().await.unwrap();
+        assert_eq!(page.status(), reqwest::StatusCode::OK);
+        assert!(page.text().await.unwrap().contains("Past results"));
+        let results: Vec<serde_json::Value> = client
+            .get(format!("{}/results", base))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert!(results.is_empty());
+        let invalid = client
+            .get(format!("{}/results/latest", base))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(invalid.status(), reqwest::StatusCode::BAD_REQUEST);
+        let unknown = client
+            .get(format!("{}/results/1/html", base))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(unknown.status(), reqwest::StatusCode::NOT_FOUND);
+        let no_path = client
+            .get(format!("{}/results/1/files", base))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(no_path.status(), reqwest::StatusCode::BAD_REQUEST);
+        let unknown_file = client
+            .get(format!("{}/results/1/files?path=LICENSE.txt", base))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(unknown_file.status(), reqwest::StatusCode::NOT_FOUND);
+
+        // Reports of workers not holding the job are neither accepted nor recorded
+        let job: JobInfo = client
+            .post(format!("{}/jobs?name=setup.exe", base))
+            .body(b"MZ".to_vec())
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let report = serde_json::json!({
+            "metadata": { "format": "NSIS", "file_hash": "abc", "file_size": 2 },
+        });
+        let stale = client
+            .post(format!("{}/jobs/{}/result", base, job.id))
+            .json(&JobResult {
+                worker: "stale".to_string(),
+                report: Some(report.clone()),
+                error: None,
+            })
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(stale.status(), reqwest::StatusCode::CONFLICT);
+        let results: Vec<serde_json::Value> = client
+            .get(format!("{}/results", base))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert!(results.is_empty());
+        let claim = ClaimRequest {
+            worker: "linux-1".to_string(),
+            kinds: vec![JobKind::Static],
+        };
+        let claimed = client.post(format!("{}/jobs/claim", base)).json(&claim);
+        assert_eq!(
+            claimed.send().await.unwrap().status(),
+            reqwest::StatusCode::OK
+        );
+        let completed = client
+            .post(format!("{}/jobs/{}/result", base, job.id))
+            .json(&JobResult {
+                worker: "linux-1".to_string(),
+                report: Some(report),
+                error: None,
+            })
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(completed.status(), reqwest::StatusCode::OK);
+        let results: Vec<serde_json::Value> = client
+            .get(format!("{}/results", base))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+
+        stop.send(()).unwrap();
+        serving.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_stale_reanalysis_report_is_not_recorded() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = InstallerStore::new(&dir.path().join("installers"));
+        let hash = store.store("setup.exe", b"MZ").await.unwrap();
+        let server = ApiServer::new(ApiConfig {
+            port: 0,
+            lease: Duration::ZERO,
+            db: Some(dir.path().join("results.sqlite")),
+            ..Default::default()
+        });
+        let stored = store.installer(&hash).unwrap().unwrap();
+        let job = server.queue().resubmit(&stored.file_name, stored.path);
+        let listener = server.bind().unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        let serving = tokio::spawn(async move {
+            server
+                .serve(listener, async {
+                    stopped.await.ok();
+                })
+                .await
+        });
+
+        // The lease of the first worker expires and the job is handed on
+        let client = reqwest::Client::new();
+        for worker in ["linux-1", "linux-2"] {
+            let claim = ClaimRequest {
+                worker: worker.to_string(),
+                kinds: vec![JobKind::Static],
+            };
+            let claimed = client.post(format!("{}/jobs/claim", base)).json(&claim);
+            assert_eq!(
+                claimed.send().await.unwrap().status(),
+                reqwest::StatusCode::OK
+            );
+        }
+        let report = serde_json::json!({
+            "metadata": { "format": "NSIS", "file_hash": hash, "file_size": 2 },
+        });
+        let post_result = |worker: &str| {
+            client
+                .post(format!("{}/jobs/{}/result", base, job.id))
+                .json(&JobResult {
+                    worker: worker.to_string(),
+                    report: Some(report.clone()),
+                    error: None,
+                })
+                .send()
+        };
+        let history = || async {
+            client
+                .get(format!("{}/results", base))
+                .send()
+                .await
+                .unwrap()
+                .json::<Vec<serde_json::Value>>()
+                .await
+                .unwrap()
+        };
+
+        let stale = post_result("linux-1").await.unwrap();
+        assert_eq!(stale.status(), reqwest::StatusCode::CONFLICT);
+        assert!(history().await.is_empty());
+        let current = post_result("linux-2").await.unwrap();
+        assert_eq!(current.status(), reqwest::StatusCode::OK);
+        assert_eq!(history().await.len(), 1);
+
+        stop.send(()).unwrap();
+        serving.await.unwrap().unwrap();
     }
 }
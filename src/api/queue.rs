@@ -0,0 +1,349 @@
+//! Job queue of the API server
+//!
+//! Installers submitted to the server wait in a queue until a worker claims
+//! them. A claimed job is leased to its worker: if the worker neither
+//! completes nor fails it within the lease, the job is handed to the next
+//! worker that asks, so a crashed machine does not lose work. Reports of
+//! finished jobs stay on the server, where they are collected centrally.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Time a worker has to finish a claimed job
+pub const DEFAULT_LEASE: Duration = Duration::from_secs(30 * 60);
+
+/// Kind of analysis a job asks for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobKind {
+    Static,
+    Sandbox,
+}
+
+impl JobKind {
+    /// Parse a kind name as used in the API
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "static" => Some(Self::Static),
+            "sandbox" => Some(Self::Sandbox),
+            _ => None,
+        }
+    }
+
+    /// Name used in the API
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Static => "static",
+            Self::Sandbox => "sandbox",
+        }
+    }
+}
+
+impl fmt::Display for JobKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// State of a job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+}
+
+/// Job as reported by the API, without the installer content
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobInfo {
+    pub id: Uuid,
+    /// File name of the submitted installer
+    pub file_name: String,
+    pub kind: JobKind,
+    pub status: JobStatus,
+    /// Worker that claimed the job last
+    pub worker: Option<String>,
+    /// Number of times the job was claimed
+    pub attempts: u32,
+    pub submitted_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+    /// Scheduled re-analysis of a stored installer
+    #[serde(default)]
+    pub reanalysis: bool,
+}
+
+/// Installer of a job
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobContent {
+    /// Uploaded with the job
+    Inline(Vec<u8>),
+    /// Kept in the installer store, read when a worker downloads it
+    Stored(PathBuf),
+}
+
+struct Job {
+    info: JobInfo,
+    /// Installer, dropped once the job is finished
+    content: JobContent,
+    claimed_at: Option<Instant>,
+    report: Option<serde_json::Value>,
+}
+
+impl Job {
+    fn is_claimable(&self, kinds: &[JobKind], lease: Duration) -> bool {
+        if !kinds.contains(&self.info.kind) {
+            return false;
+        }
+        match self.info.status {
+            JobStatus::Queued => true,
+            JobStatus::Running => self
+                .claimed_at
+                .is_some_and(|claimed_at| claimed_at.elapsed() >= lease),
+            JobStatus::Completed | JobStatus::Failed => false,
+        }
+    }
+}
+
+/// Queue of analysis jobs shared by the API handlers
+pub struct JobQueue {
+    jobs: Mutex<Vec<Job>>,
+    lease: Duration,
+}
+
+impl Default for JobQueue {
+    fn default() -> Self {
+        Self::new(DEFAULT_LEASE)
+    }
+}
+
+impl JobQueue {
+    /// Create an empty queue whose workers have `lease` to finish a job
+    pub fn new(lease: Duration) -> Self {
+        Self {
+            jobs: Mutex::new(Vec::new()),
+            lease,
+        }
+    }
+
+    /// Add an installer to the end of the queue
+    pub fn submit(&self, file_name: &str, kind: JobKind, content: Vec<u8>) -> JobInfo {
+        self.push(file_name, kind, JobContent::Inline(content), false)
+    }
+
+    /// Queue a static re-analysis of the installer stored at `path`
+    pub fn resubmit(&self, file_name: &str, path: PathBuf) -> JobInfo {
+        self.push(file_name, JobKind::Static, JobContent::Stored(path), true)
+    }
+
+    /// Whether re-analysis jobs are still waiting or running
+    pub fn reanalysis_pending(&self) -> bool {
+        self.jobs().iter().any(|job| {
+            job.info.reanalysis && matches!(job.info.status, JobStatus::Queued | JobStatus::Running)
+        })
+    }
+
+    fn push(
+        &self,
+        file_name: &str,
+        kind: JobKind,
+        content: JobContent,
+        reanalysis: bool,
+    ) -> JobInfo {
+        let info = JobInfo {
+            id: Uuid::new_v4(),
+            file_name: file_name.to_string(),
+            kind,
+            status: JobStatus::Queued,
+            worker: None,
+            attempts: 0,
+            submitted_at: Utc::now(),
+            finished_at: None,
+            error: None,
+            reanalysis,
+        };
+        self.jobs().push(Job {
+            info: info.clone(),
+            content,
+            claimed_at: None,
+            report: None,
+        });
+        info
+    }
+
+    /// Hand the oldest claimable job of the given kinds to a worker
+    ///
+    /// Jobs whose lease expired are claimable again.
+    pub fn claim(&self, worker: &str, kinds: &[JobKind]) -> Option<JobInfo> {
+        let mut jobs = self.jobs();
+        let job = jobs
+            .iter_mut()
+            .find(|job| job.is_claimable(kinds, self.lease))?;
+        if job.info.status == JobStatus::Running {
+            tracing::warn!(
+                "Lease of job {} expired on worker {}, handing it to {}",
+                job.info.id,
+                job.info.worker.as_deref().unwrap_or("unknown"),
+                worker
+            );
+        }
+        job.info.status = JobStatus::Running;
+        job.info.worker = Some(worker.to_string());
+        job.info.attempts += 1;
+        job.claimed_at = Some(Instant::now());
+        Some(job.info.clone())
+    }
+
+    /// Installer of a job the worker holds
+    pub fn content(&self, id: Uuid, worker: &str) -> Option<JobContent> {
+        let jobs = self.jobs();
+        let job = jobs
+            .iter()
+            .find(|job| job.info.id == id && holds(&job.info, worker))?;
+        Some(job.content.clone())
+    }
+
+    /// Store the report of a job the worker holds
+    pub fn complete(&self, id: Uuid, worker: &str, report: serde_json::Value) -> Option<JobInfo> {
+        self.finish(id, worker, |job| {
+            job.info.status = JobStatus::Completed;
+            job.report = Some(report);
+        })
+    }
+
+    /// Record why a job the worker holds could not be analyzed
+    pub fn fail(&self, id: Uuid, worker: &str, error: &str) -> Option<JobInfo> {
+        self.finish(id, worker, |job| {
+            job.info.status = JobStatus::Failed;
+            job.info.error = Some(error.to_string());
+        })
+    }
+
+    /// Current state of a job
+    pub fn get(&self, id: Uuid) -> Option<JobInfo> {
+        self.jobs()
+            .iter()
+            .find(|job| job.info.id == id)
+            .map(|job| job.info.clone())
+    }
+
+    /// Report of a completed job
+    pub fn report(&self, id: Uuid) -> Option<serde_json::Value> {
+        self.jobs()
+            .iter()
+            .find(|job| job.info.id == id)
+            .and_then(|job| job.report.clone())
+    }
+
+    /// All jobs in submission order
+    pub fn list(&self) -> Vec<JobInfo> {
+        self.jobs().iter().map(|job| job.info.clone()).collect()
+    }
+
+    fn finish(&self, id: Uuid, worker: &str, update: impl FnOnce(&mut Job)) -> Option<JobInfo> {
+        let mut jobs = self.jobs();
+        let job = jobs
+            .iter_mut()
+            .find(|job| job.info.id == id && holds(&job.info, worker))?;
+        update(job);
+        job.info.finished_at = Some(Utc::now());
+        job.content = JobContent::Inline(Vec::new());
+        job.claimed_at = None;
+        Some(job.info.clone())
+    }
+
+    fn jobs(&self) -> std::sync::MutexGuard<'_, Vec<Job>> {
+        // A panicking handler cannot leave a job half-updated, keep serving
+        self.jobs
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Whether the worker holds the running job
+fn holds(info: &JobInfo, worker: &str) -> bool {
+    info.status == JobStatus::Running && info.worker.as_deref() == Some(worker)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_claim_and_finish_jobs() {
+        let queue = JobQueue::new(Duration::from_secs(60));
+        let sandbox = queue.submit("setup.exe", JobKind::Sandbox, b"MZ".to_vec());
+        let first = queue.submit("app.msi", JobKind::Static, b"msi".to_vec());
+        let second = queue.submit("tool.msi", JobKind::Static, b"msi".to_vec());
+
+        let claimed = queue.claim("linux-1", &[JobKind::Static]).unwrap();
+        assert_eq!(claimed.id, first.id);
+        assert_eq!(claimed.attempts, 1);
+        assert!(queue.content(first.id, "linux-2").is_none());
+        assert_eq!(
+            queue.content(first.id, "linux-1").unwrap(),
+            JobContent::Inline(b"msi".to_vec())
+        );
+
+        assert!(queue
+            .complete(first.id, "linux-2", serde_json::json!({}))
+            .is_none());
+        let completed = queue
+            .complete(first.id, "linux-1", serde_json::json!({"ok": true}))
+            .unwrap();
+        assert_eq!(completed.status, JobStatus::Completed);
+        assert_eq!(queue.report(first.id).unwrap()["ok"], true);
+        assert!(queue.content(first.id, "linux-1").is_none());
+
+        assert_eq!(
+            queue.claim("linux-1", &[JobKind::Static]).unwrap().id,
+            second.id
+        );
+        assert!(queue.claim("linux-1", &[JobKind::Static]).is_none());
+        let failed = queue.fail(second.id, "linux-1", "truncated").unwrap();
+        assert_eq!(failed.error.as_deref(), Some("truncated"));
+
+        assert_eq!(
+            queue
+                .list()
+                .iter()
+                .map(|job| job.status)
+                .collect::<Vec<_>>(),
+            vec![JobStatus::Queued, JobStatus::Completed, JobStatus::Failed]
+        );
+        assert_eq!(queue.get(sandbox.id).unwrap().status, JobStatus::Queued);
+
+        assert!(!queue.reanalysis_pending());
+        let reanalysis = queue.resubmit("app.msi", PathBuf::from("store/app.msi"));
+        assert!(reanalysis.reanalysis);
+        assert_eq!(reanalysis.kind, JobKind::Static);
+        assert!(queue.reanalysis_pending());
+        queue.claim("linux-1", &[JobKind::Static]).unwrap();
+        assert_eq!(
+            queue.content(reanalysis.id, "linux-1").unwrap(),
+            JobContent::Stored(PathBuf::from("store/app.msi"))
+        );
+        queue.fail(reanalysis.id, "linux-1", "truncated").unwrap();
+        assert!(!queue.reanalysis_pending());
+    }
+
+    #[test]
+    fn test_reclaim_expired_lease() {
+        let queue = JobQueue::new(Duration::ZERO);
+        let job = queue.submit("setup.exe", JobKind::Sandbox, Vec::new());
+        queue.claim("win-1", &[JobKind::Sandbox]).unwrap();
+
+        let reclaimed = queue.claim("win-2", &[JobKind::Sandbox]).unwrap();
+        assert_eq!(reclaimed.id, job.id);
+        assert_eq!(reclaimed.attempts, 2);
+        assert!(queue.fail(job.id, "win-1", "crashed").is_none());
+        assert!(queue.fail(job.id, "win-2", "crashed").is_some());
+    }
+}
@@ -0,0 +1,267 @@
+//! Stored installers and their scheduled re-analysis
+//!
+//! A server started with a store directory keeps every submitted installer,
+//! filed under its SHA-256 as `<store>/<sha256>/<file name>`. With a
+//! re-analysis interval, all stored installers are queued again as static
+//! jobs once per interval, served to workers from the store instead of
+//! being held in memory, so workers apply their current analyzers and
+//! detection data to installers analyzed long ago. When the risk level of a
+//! re-analyzed installer differs from its previous analysis in the results
+//! database, the change is logged and posted as JSON to the notification URL.
+//! Only the report of the worker currently holding a job is recorded, so a
+//! worker whose lease expired cannot rewrite the verdict history.
+
+use crate::api::JobQueue;
+use crate::core::{AnalysisResult, Result};
+use crate::reporting::ReportGenerator;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Time allowed for delivering a notification
+const NOTIFY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Directory of submitted installers, one subdirectory per SHA-256
+pub struct InstallerStore {
+    root: PathBuf,
+}
+
+/// Installer kept in the store
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredInstaller {
+    pub file_hash: String,
+    /// File name the installer was first submitted under
+    pub file_name: String,
+    pub path: PathBuf,
+}
+
+impl InstallerStore {
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+        }
+    }
+
+    /// Keep an installer, returning its SHA-256
+    ///
+    /// An installer already in the store keeps the name it was first
+    /// submitted under. The installer is written to a partial directory
+    /// renamed into place once complete, so a failed write leaves nothing
+    /// behind that listings would pick up.
+    pub async fn store(&self, file_name: &str, content: &[u8]) -> Result<String> {
+        let file_hash = format!("{:x}", Sha256::digest(content));
+        let dir = self.root.join(&file_hash);
+        if dir.exists() {
+            return Ok(file_hash);
+        }
+
+        let partial = self
+            .root
+            .join(format!("{}.{}.partial", file_hash, uuid::Uuid::new_v4()));
+        tokio::fs::create_dir_all(&partial).await?;
+        let written = match tokio::fs::write(partial.join(file_name), content).await {
+            Ok(()) => tokio::fs::rename(&partial, &dir).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = written {
+            let _ = tokio::fs::remove_dir_all(&partial).await;
+            // Stored concurrently under the same hash
+            if !dir.exists() {
+                return Err(e.into());
+            }
+        }
+        Ok(file_hash)
+    }
+
+    /// Installers in the store, by hash
+    pub fn installers(&self) -> Result<Vec<StoredInstaller>> {
+        if !self.root.exists() {
+            return Ok(Vec::new());
+        }
+        let mut installers = Vec::new();
+        for dir in std::fs::read_dir(&self.root)? {
+            let dir = dir?;
+            let file_hash = dir.file_name().to_string_lossy().to_string();
+            // Partial directories of installers still being written
+            if !dir.file_type()?.is_dir() || !is_sha256(&file_hash) {
+                continue;
+            }
+            installers.extend(Self::stored_file(&dir.path(), file_hash)?);
+        }
+        installers.sort_by(|a, b| a.file_hash.cmp(&b.file_hash));
+        Ok(installers)
+    }
+
+    /// Installer stored under `file_hash`
+    pub fn installer(&self, file_hash: &str) -> Result<Option<StoredInstaller>> {
+        let file_hash = file_hash.to_ascii_lowercase();
+        // Hashes name directories, anything else could point out of the store
+        if !is_sha256(&file_hash) {
+            return Ok(None);
+        }
+        let dir = self.root.join(&file_hash);
+        if !dir.is_dir() {
+            return Ok(None);
+        }
+        Self::stored_file(&dir, file_hash)
+    }
+
+    /// Installer in the directory of `file_hash`
+    fn stored_file(dir: &Path, file_hash: String) -> Result<Option<StoredInstaller>> {
+        for file in std::fs::read_dir(dir)? {
+            let file = file?;
+            if file.file_type()?.is_file() {
+                return Ok(Some(StoredInstaller {
+                    file_hash,
+                    file_name: file.file_name().to_string_lossy().to_string(),
+                    path: file.path(),
+                }));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Whether `name` is a hex-encoded SHA-256, the name of a store directory
+fn is_sha256(name: &str) -> bool {
+    name.len() == 64 && name.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Risk level of an installer that changed on re-analysis
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VerdictChange {
+    pub file_hash: String,
+    pub source: Option<String>,
+    pub product_name: Option<String>,
+    pub previous_risk: String,
+    pub risk: String,
+    pub previous_analyzed_at: DateTime<Utc>,
+    pub analyzed_at: DateTime<Utc>,
+}
+
+/// Change of the risk level between two analyses of an installer, if any
+pub fn verdict_change(
+    previous: &AnalysisResult,
+    current: &AnalysisResult,
+) -> Option<VerdictChange> {
+    let generator = ReportGenerator::new();
+    let previous_risk = generator.calculate_risk_level(previous);
+    let risk = generator.calculate_risk_level(current);
+    (previous_risk != risk).then(|| VerdictChange {
+        file_hash: current.metadata.file_hash.to_lowercase(),
+        source: current
+            .source_file_path
+            .as_deref()
+            .map(crate::utils::entry_file_name),
+        product_name: current.metadata.product_name.clone(),
+        previous_risk,
+        risk,
+        previous_analyzed_at: previous.analyzed_at,
+        analyzed_at: current.analyzed_at,
+    })
+}
+
+/// Queue every stored installer again once per `every`, until dropped
+///
+/// A round is skipped while jobs of the previous round are still pending, so
+/// slow workers do not pile up duplicate jobs.
+pub async fn reanalyze(queue: &JobQueue, store: &InstallerStore, every: Duration) {
+    loop {
+        tokio::time::sleep(every).await;
+        if queue.reanalysis_pending() {
+            tracing::warn!("Previous re-analysis round still running, skipping this round");
+            continue;
+        }
+        let installers = match store.installers() {
+            Ok(installers) => installers,
+            Err(e) => {
+                tracing::warn!("Failed to list stored installers: {}", e);
+                continue;
+            }
+        };
+        // Installers are read from the store only once a worker downloads them
+        for installer in &installers {
+            queue.resubmit(&installer.file_name, installer.path.clone());
+        }
+        tracing::info!(
+            "Queued {} stored installer(s) for re-analysis",
+            installers.len()
+        );
+    }
+}
+
+/// Post a verdict change to the notification URL, logging when it cannot be delivered
+pub async fn notify(url: &str, change: &VerdictChange) {
+    let client = match reqwest::Client::builder()
+        .user_agent(concat!("installer-analyzer/", env!("CARGO_PKG_VERSION")))
+        .timeout(NOTIFY_TIMEOUT)
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            tracing::warn!("Failed to create notification client: {}", e);
+            return;
+        }
+    };
+    let response = client.post(url).json(change).send().await;
+    if let Err(e) = response.and_then(|response| response.error_for_status()) {
+        tracing::warn!("Failed to deliver verdict change notification: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{JobContent, JobKind};
+
+    #[tokio::test]
+    async fn test_store_and_requeue() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = InstallerStore::new(&dir.path().join("installers"));
+        assert!(store.installers().unwrap().is_empty());
+
+        let hash = store.store("setup.exe", b"MZ").await.unwrap();
+        assert_eq!(store.store("renamed.exe", b"MZ").await.unwrap(), hash);
+        store.store("app.msi", b"msi").await.unwrap();
+        let installers = store.installers().unwrap();
+        assert_eq!(installers.len(), 2);
+        let setup = installers
+            .iter()
+            .find(|installer| installer.file_hash == hash)
+            .unwrap();
+        assert_eq!(setup.file_name, "setup.exe");
+        assert_eq!(
+            store.installer(&hash.to_uppercase()).unwrap().as_ref(),
+            Some(setup)
+        );
+        assert!(store.installer("..").unwrap().is_none());
+        // Left behind by a failed write
+        let partial = dir
+            .path()
+            .join("installers")
+            .join(format!("{}.1.partial", hash));
+        std::fs::create_dir(&partial).unwrap();
+        std::fs::write(partial.join("setup.exe"), b"M").unwrap();
+        assert_eq!(store.installers().unwrap().len(), 2);
+        assert!(store.installer(&"0".repeat(64)).unwrap().is_none());
+
+        let queue = JobQueue::default();
+        let requeue = reanalyze(&queue, &store, Duration::from_millis(10));
+        let _ = tokio::time::timeout(Duration::from_millis(100), requeue).await;
+        let jobs = queue.list();
+        // The second round is skipped while the first one is pending
+        assert_eq!(jobs.len(), 2);
+        assert!(jobs.iter().all(|job| job.reanalysis));
+        // Workers download the installers from the store
+        let stored = store.installer(&hash).unwrap().unwrap();
+        let contents: Vec<JobContent> = (0..2)
+            .map(|_| {
+                let job = queue.claim("linux-1", &[JobKind::Static]).unwrap();
+                queue.content(job.id, "linux-1").unwrap()
+            })
+            .collect();
+        assert!(contents.contains(&JobContent::Stored(stored.path)));
+    }
+}
@@ -61,7 +61,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Test get_analyzer_by_format
     println!("\n=== Analyzer by Format Test ===");
     for format in &formats {
-        match AnalyzerFactory::get_analyzer_by_format(*format) {
+        match AnalyzerFactory::get_analyzer_by_format(format) {
             Some(analyzer) => {
                 println!("  ✓ {:?}: Analyzer available", format);
                 assert_eq!(analyzer.format(), *format);
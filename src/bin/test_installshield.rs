@@ -175,7 +175,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("\n=== Get Analyzer by Format Test ===");
     if let Some(installshield_analyzer) =
         installer_analyzer::analyzers::AnalyzerFactory::get_analyzer_by_format(
-            installer_analyzer::core::InstallerFormat::InstallShield,
+            &installer_analyzer::core::InstallerFormat::InstallShield,
         )
     {
         println!("  ✓ Successfully created InstallShield analyzer by format");
@@ -200,7 +200,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("\n=== Get Analyzer by Format Test ===");
     if let Some(msix_analyzer) =
         installer_analyzer::analyzers::AnalyzerFactory::get_analyzer_by_format(
-            installer_analyzer::core::InstallerFormat::MSIX,
+            &installer_analyzer::core::InstallerFormat::MSIX,
         )
     {
         println!("  ✓ Successfully created MSIX analyzer by format");
@@ -275,7 +275,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     if let Some(wix_analyzer_by_format) =
         installer_analyzer::analyzers::AnalyzerFactory::get_analyzer_by_format(
-            installer_analyzer::core::InstallerFormat::WiX,
+            &installer_analyzer::core::InstallerFormat::WiX,
         )
     {
         println!("  ✓ Successfully created WiX analyzer by format");
@@ -286,7 +286,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     if let Some(squirrel_analyzer_by_format) =
         installer_analyzer::analyzers::AnalyzerFactory::get_analyzer_by_format(
-            installer_analyzer::core::InstallerFormat::Squirrel,
+            &installer_analyzer::core::InstallerFormat::Squirrel,
         )
     {
         println!("  ✓ Successfully created Squirrel analyzer by format");
@@ -1,16 +1,318 @@
 //! CLI command implementations
 
-use crate::analyzers::AnalyzerFactory;
-use crate::cli::output::CliOutput;
-use crate::core::{AnalysisResult, AnalyzerError, Result, SandboxConfig};
-use crate::reporting::{ReportFormat, ReportGenerator, Reporter};
-use crate::sandbox::{Sandbox, SandboxController};
-use crate::updater::Updater;
+use crate::analyzers::architecture::ArchitectureReport;
+use crate::analyzers::archive::PortableAppReport;
+use crate::analyzers::authenticode::AuthenticodeReport;
+use crate::analyzers::carving::EmbeddedPayloadReport;
+use crate::analyzers::common::scanner::{self, SourceScanner};
+use crate::analyzers::common::{FileDigests, HashAlgorithm};
+use crate::analyzers::embedded_msi::EmbeddedMsiReport;
+use crate::analyzers::evasion::EvasionReport;
+use crate::analyzers::game_runtimes::GameRuntimeReport;
+use crate::analyzers::image::ContainedInstallerReport;
+use crate::analyzers::msi::{self, database::MsiDatabase};
+use crate::analyzers::multipart::MultiPartReport;
+use crate::analyzers::office_addins::OfficeAddinReport;
+use crate::analyzers::previews;
+use crate::analyzers::reboot::RebootAssessment;
+use crate::analyzers::requirements::SystemRequirements;
+use crate::analyzers::spyware::SpywareReport;
+use crate::analyzers::strings::StringCollector;
+use crate::analyzers::sync_objects::SyncObjectReport;
+use crate::analyzers::timestamps::TimestampReport;
+use crate::analyzers::uac_bypass::UacBypassReport;
+use crate::analyzers::web_installer::WebInstallerReport;
+use crate::analyzers::wheel::{EnvironmentCheck, EnvironmentSnapshot, WheelParser};
+use crate::analyzers::{self, AnalyzerFactory, InstallerAnalyzer};
+use crate::api::{ApiConfig, ApiServer, JobInfo, JobKind, WorkerClient};
+use crate::cli::control::{self, ControlProgress};
+use crate::cli::i18n::{tr, tr_with};
+use crate::cli::output::{terminal_safe, CliOutput, StageProgress};
+use crate::core::{
+    AnalysisDepth, AnalysisResult, AnalyzerError, AuditTrail, DependencyGraph, InputDigest,
+    InstallerFormat, Result, SandboxConfig,
+};
+use crate::policy::{Policy, PolicyEvaluator, PolicyReport};
+use crate::reporting::{audit, discrepancies, merge, migration};
+use crate::reporting::{
+    correlate, product_families, ArtifactWriter, BatchEntry, BatchSummary, ComponentFilter,
+    Correlation, DependencyGraphExporter, DeploymentExporter, FileTreeExporter, HtmlData,
+    ProductIdentity, Redaction, Redactor, Relationship, ReportBundle, ReportFormat,
+    ReportGenerator, ReportOptions, ReportSignature, Reporter, ResultsDatabase, TreeExportFormat,
+    VendorKnowledgeBase, REPORT_SCHEMA_VERSION,
+};
+use crate::sandbox::{
+    self, NoiseFilter, ResourceReport, SandboxController, SandboxSessions, ScriptActivityReport,
+};
+use crate::telemetry::{Telemetry, TelemetryEvent};
+use crate::updater::{
+    BackupStore, DataPackClient, DataPackKind, DataPackStore, UpdateConfig, Updater,
+};
+use crate::utils::progress::{self, Fanout, ProgressSink, Stage};
+use crate::utils::workspace::{CleanPolicy, ScratchDir, Workspace, WorkspaceArea};
+use crate::utils::{crash, entry_file_name, extended_length, file_name_component, without_prefix};
 use chrono::Utc;
-use std::path::Path;
+use rsa::RsaPrivateKey;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use uuid::Uuid;
 
+/// Additional options for the analyze command
+#[derive(Debug, Clone, Default)]
+pub struct AnalyzeOptions {
+    /// Export the extracted file tree to this path
+    pub export_tree: Option<PathBuf>,
+    /// Explicit file tree export format (json, graphml, dot)
+    pub tree_format: Option<String>,
+    /// Keep file entries guessed from common patterns instead of read from the installer
+    pub include_speculative: bool,
+    /// Export the declared dependency graph to this path
+    pub export_deps: Option<PathBuf>,
+    /// Export Intune/SCCM deployment metadata to this path
+    pub export_deployment: Option<PathBuf>,
+    /// Write analysis artifacts to this directory, independent of the report destination
+    pub artifacts_dir: Option<PathBuf>,
+    /// Allow printing HTML reports to a terminal
+    pub force: bool,
+    /// Copy high-interest packaged files into the artifacts directory
+    pub attach_artifacts: bool,
+    /// Package report, unified JSON and artifacts into this ZIP archive
+    pub bundle: Option<PathBuf>,
+    /// Encrypt the bundle with AES-256 using this password
+    pub bundle_password: Option<String>,
+    /// Append the analysis to this SQLite results database
+    pub db: Option<PathBuf>,
+    /// Evaluate results against this baseline policy (TOML or YAML)
+    pub policy: Option<PathBuf>,
+    /// Vendor knowledge base (TOML or YAML) extending the curated one
+    pub vendor_kb: Option<PathBuf>,
+    /// Analyze payloads carved from the installer and add their files
+    pub carve_recursive: bool,
+    /// Extract MSI packages embedded in an EXE wrapper here and analyze the primary one instead
+    pub extract_msi: Option<PathBuf>,
+    /// Write the `Binary` and `Icon` table streams of MSI packages to this directory
+    pub dump_msi_streams: Option<PathBuf>,
+    /// Check wheel requirements against this `pip freeze` output or conda export
+    pub check_env: Option<PathBuf>,
+    /// Extract strings of the installer and its key payloads into a report appendix
+    pub extract_strings: bool,
+    /// Digests to add to the SHA-256 `file_hash`, computed in one pass
+    pub hash_algorithms: Vec<HashAlgorithm>,
+    /// Size reductions of the written reports
+    pub report: ReportOptions,
+    /// Stages to run, [`AnalysisDepth::default_for`] the format if not given
+    ///
+    /// Stages requested explicitly, like string extraction, run at any depth.
+    pub depth: Option<AnalysisDepth>,
+    /// Record the host name, user and platform in the audit trail
+    pub audit_machine: bool,
+    /// Sign written reports with this RSA private key (PEM)
+    pub sign_key: Option<PathBuf>,
+    /// Local details scrubbed from the result before anything is written
+    pub redact: Vec<Redaction>,
+    /// Report the outcome of each analysis, if opted in
+    pub telemetry: Option<Telemetry>,
+    /// Hide the stage progress of single-installer analyses
+    pub quiet: bool,
+}
+
+impl AnalyzeOptions {
+    /// Derive options for one of several inputs
+    ///
+    /// Export paths get the input file stem appended and artifacts go to a
+    /// per-input subdirectory, so that outputs of different inputs don't
+    /// overwrite each other.
+    pub fn for_input(&self, input: &Path) -> Self {
+        let suffixed = |path: &PathBuf| {
+            let stem = path
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let mut name = format!("{}_{}", stem, file_stem(input));
+            if let Some(ext) = path.extension() {
+                name.push('.');
+                name.push_str(&ext.to_string_lossy());
+            }
+            path.with_file_name(name)
+        };
+
+        Self {
+            export_tree: self.export_tree.as_ref().map(suffixed),
+            export_deps: self.export_deps.as_ref().map(suffixed),
+            export_deployment: self.export_deployment.as_ref().map(suffixed),
+            artifacts_dir: self
+                .artifacts_dir
+                .as_ref()
+                .map(|dir| dir.join(file_stem(input))),
+            extract_msi: self
+                .extract_msi
+                .as_ref()
+                .map(|dir| dir.join(file_stem(input))),
+            dump_msi_streams: self
+                .dump_msi_streams
+                .as_ref()
+                .map(|dir| dir.join(file_stem(input))),
+            ..self.clone()
+        }
+    }
+}
+
+impl AnalyzeOptions {
+    /// Load the baseline policy, if one was given
+    async fn load_policy(&self) -> Result<Option<Policy>> {
+        match self.policy.as_deref() {
+            Some(path) => Ok(Some(Policy::load(path).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Load the vendor knowledge base, the curated one if none was given
+    async fn load_vendor_kb(&self) -> Result<VendorKnowledgeBase> {
+        match self.vendor_kb.as_deref() {
+            Some(path) => VendorKnowledgeBase::load(path).await,
+            None => Ok(VendorKnowledgeBase::curated().clone()),
+        }
+    }
+
+    /// Load the key signing written reports, if one was given
+    async fn load_signing_key(&self) -> Result<Option<RsaPrivateKey>> {
+        match self.sign_key.as_deref() {
+            Some(path) => Ok(Some(audit::load_signing_key(path).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Configuration files influencing the result, by role
+    fn config_files(&self) -> Vec<(&'static str, &Path)> {
+        [
+            ("policy", self.policy.as_deref()),
+            ("vendor_kb", self.vendor_kb.as_deref()),
+            ("environment", self.check_env.as_deref()),
+        ]
+        .into_iter()
+        .filter_map(|(role, path)| Some((role, path?)))
+        .collect()
+    }
+
+    /// Enable attachments for a bundle
+    ///
+    /// A bundle always carries the attachments. Unless they were requested
+    /// on disk as well, they are collected in a workspace scratch directory
+    /// that is removed when the returned guard is dropped.
+    fn prepare_bundle(&self) -> Result<(Self, Option<ScratchDir>)> {
+        if self.bundle.is_none() {
+            return Ok((self.clone(), None));
+        }
+
+        let scratch_dir = if !self.attach_artifacts && self.artifacts_dir.is_none() {
+            Some(Workspace::new().scratch_dir(WorkspaceArea::Bundle)?)
+        } else {
+            None
+        };
+        let options = Self {
+            attach_artifacts: true,
+            artifacts_dir: self
+                .artifacts_dir
+                .clone()
+                .or_else(|| scratch_dir.as_ref().map(|dir| dir.path().to_path_buf())),
+            ..self.clone()
+        };
+        Ok((options, scratch_dir))
+    }
+
+    /// Resolve where attachments go when no artifacts directory was given
+    ///
+    /// Attachments default to an `artifacts/` folder inside `report_dir`,
+    /// the directory the report(s) are written to.
+    fn resolve_artifacts_dir(&self, report_dir: Option<&Path>) -> Result<Self> {
+        if !self.attach_artifacts || self.artifacts_dir.is_some() {
+            return Ok(self.clone());
+        }
+
+        let report_dir = report_dir.ok_or_else(|| {
+            AnalyzerError::config_error(
+                "--attach-artifacts needs a report file (--output) or an --artifacts-dir",
+            )
+        })?;
+        Ok(Self {
+            artifacts_dir: Some(report_dir.join("artifacts")),
+            ..self.clone()
+        })
+    }
+}
+
+/// Options of the sandbox command beyond its basic arguments
+#[derive(Debug, Clone)]
+pub struct SandboxOptions {
+    /// Sandbox tells to disguise, rejected until the sandbox can apply them
+    pub disguise: Option<String>,
+    /// Start of the virtual clock, rejected until the sandbox can apply it
+    pub fake_time: Option<String>,
+    /// Number of runs in clean environments, rejected above one until the
+    /// sandbox can restore the environment between runs
+    pub runs: u32,
+    /// Noise filter replacing the curated one
+    pub noise_filter: Option<PathBuf>,
+    /// Keep the raw capture without filtering noise
+    pub raw: bool,
+    /// Tags attached to the sandbox sessions
+    pub tags: Vec<String>,
+    /// Local details scrubbed from the result before the report is written
+    pub redact: Vec<Redaction>,
+}
+
+impl SandboxOptions {
+    /// Refuse options the sandbox cannot apply yet
+    ///
+    /// They are hidden from the help, and rejected here rather than ignored
+    /// so that no report suggests they took effect.
+    fn check_supported(&self) -> Result<()> {
+        if self.disguise.is_some() {
+            return Err(AnalyzerError::config_error(
+                "--disguise is not supported yet: the sandbox cannot hide its tells from the installer",
+            ));
+        }
+        if self.fake_time.is_some() {
+            return Err(AnalyzerError::config_error(
+                "--fake-time is not supported yet: the sandbox cannot change the time the installer sees",
+            ));
+        }
+        if self.runs > 1 {
+            return Err(AnalyzerError::config_error(
+                "--runs is not supported yet: the sandbox cannot restore a clean environment between runs",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Default for SandboxOptions {
+    fn default() -> Self {
+        Self {
+            disguise: None,
+            fake_time: None,
+            runs: 1,
+            noise_filter: None,
+            raw: false,
+            tags: Vec::new(),
+            redact: Vec::new(),
+        }
+    }
+}
+
+/// Failure handling of the batch command
+#[derive(Debug, Clone, Default)]
+pub struct BatchOptions {
+    /// Retries of an input whose analysis failed with a transient I/O error
+    pub retries: u32,
+    /// Copy inputs that could not be analyzed to `failed/` in the output directory
+    pub quarantine: bool,
+}
+
+/// Pause before the first retry of a batch input, doubled for each further one
+const BATCH_RETRY_DELAY: Duration = Duration::from_secs(2);
+
 /// Handle the analyze command
 pub async fn handle_analyze(
     input: &Path,
@@ -18,299 +320,2349 @@ pub async fn handle_analyze(
     format: Option<&str>,
     open_browser: bool,
 ) -> Result<()> {
-    CliOutput::info(&format!("Starting static analysis of: {}", input.display()));
+    handle_analyze_with_options(
+        input,
+        output,
+        format,
+        open_browser,
+        &AnalyzeOptions::default(),
+    )
+    .await
+}
+
+/// Handle the analyze command with additional options
+pub async fn handle_analyze_with_options(
+    input: &Path,
+    output: Option<&Path>,
+    format: Option<&str>,
+    open_browser: bool,
+    options: &AnalyzeOptions,
+) -> Result<()> {
+    let (_, policy_report) =
+        analyze_and_write(input, output, format, open_browser, options).await?;
+
+    match policy_report {
+        Some(report) if !report.passed => Err(AnalyzerError::policy_violation(
+            report.policy,
+            report.violations.len(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Analyze an installer and write its outputs
+///
+/// A failed policy check is returned as part of the result instead of an
+/// error, since the report has been written by then.
+async fn analyze_and_write(
+    input: &Path,
+    output: Option<&Path>,
+    format: Option<&str>,
+    open_browser: bool,
+    options: &AnalyzeOptions,
+) -> Result<(AnalysisResult, Option<PolicyReport>)> {
+    let output = output.filter(|path| !is_stdout_path(path));
+    let report_format = determine_format(format, output)?;
+    if options.bundle.is_none() {
+        ensure_printable(&report_format, output, options.force)?;
+        ensure_data_destination(&report_format, output, &options.report)?;
+    }
+    let policy = options.load_policy().await?;
+    let signing_key = options.load_signing_key().await?;
+    if signing_key.is_some() && output.is_none() && options.bundle.is_none() {
+        return Err(AnalyzerError::config_error(
+            "--sign-key needs a report file (--output) or a --bundle",
+        ));
+    }
+    let (options, _scratch_dir) = options.prepare_bundle()?;
+    let options = &options.resolve_artifacts_dir(output.map(parent_dir))?;
+
+    let analysis = async {
+        let mut result = analyze_installer(input, options).await?;
+        let policy_report = match &policy {
+            Some(policy) => Some(check_policy(policy, &mut result, options).await?),
+            None => None,
+        };
+        write_analysis_outputs(
+            &result,
+            report_format,
+            output,
+            open_browser,
+            signing_key.as_ref(),
+            options,
+        )
+        .await?;
+        Ok((result, policy_report))
+    };
+    let mut sinks: Vec<Arc<dyn ProgressSink>> = Vec::new();
+    if !options.quiet {
+        sinks.push(StageProgress::new());
+    }
+    if control::channel().is_some() {
+        sinks.push(Arc::new(ControlProgress::default()));
+    }
+    match sinks.len() {
+        0 => analysis.await,
+        1 => progress::scope(sinks.remove(0), analysis).await,
+        _ => progress::scope(Arc::new(Fanout(sinks)), analysis).await,
+    }
+}
+
+/// Evaluate a baseline policy and print the outcome
+///
+/// The policy report is also written to the artifacts directory, so it ends
+/// up in report bundles as well.
+async fn check_policy(
+    policy: &Policy,
+    result: &mut AnalysisResult,
+    options: &AnalyzeOptions,
+) -> Result<PolicyReport> {
+    let report = PolicyEvaluator::evaluate(policy, result);
+    if let Some(audit) = result.audit.as_mut() {
+        audit.record(
+            "policy_evaluated",
+            format!(
+                "{}: {} violation(s)",
+                report.policy,
+                report.violations.len()
+            ),
+        );
+    }
+
+    if report.passed {
+        CliOutput::success(&tr_with(
+            "policy.passed",
+            &[("policy", &report.policy), ("source", &report.source)],
+        ));
+    } else {
+        CliOutput::error(&tr_with(
+            "policy.failed",
+            &[
+                ("policy", &report.policy),
+                ("source", &report.source),
+                ("count", &report.violations.len()),
+            ],
+        ));
+        for violation in &report.violations {
+            CliOutput::error(&format!(
+                "  [{}] {}: {}",
+                violation.rule.name(),
+                violation.message,
+                violation.subject
+            ));
+        }
+    }
+    if let Some(standing) = report.publisher_standing {
+        CliOutput::info(&tr_with(
+            "policy.publisher_standing",
+            &[("standing", &standing.as_str())],
+        ));
+    }
+    for note in &report.notes {
+        CliOutput::warning(note);
+    }
+
+    if let Some(artifacts_dir) = options.artifacts_dir.as_deref() {
+        let artifacts_dir = extended_length(artifacts_dir);
+        tokio::fs::create_dir_all(&artifacts_dir).await?;
+        let content =
+            serde_json::to_string_pretty(&report).map_err(AnalyzerError::SerializationError)?;
+        tokio::fs::write(artifacts_dir.join("policy-report.json"), content).await?;
+    }
+
+    Ok(report)
+}
+
+/// Write the report and, if requested, the report bundle
+///
+/// With a `signing_key`, the report file and the JSON report in the bundle
+/// get detached signatures.
+async fn write_analysis_outputs(
+    result: &AnalysisResult,
+    report_format: ReportFormat,
+    output: Option<&Path>,
+    open_browser: bool,
+    signing_key: Option<&RsaPrivateKey>,
+    options: &AnalyzeOptions,
+) -> Result<()> {
+    // With a bundle as destination the report is only printed when asked for
+    if output.is_some() || options.bundle.is_none() {
+        write_report(
+            result,
+            report_format.clone(),
+            output,
+            open_browser,
+            &options.report,
+        )
+        .await?;
+    }
+    if let (Some(output), Some(key)) = (output, signing_key) {
+        sign_report(output, key).await?;
+    }
+
+    if let Some(bundle_path) = options.bundle.as_deref() {
+        let report_generator = ReportGenerator::with_options(options.report.clone());
+        let mut bundle = ReportBundle::create(bundle_path, options.bundle_password.as_deref())?;
+
+        let analysis = report_generator
+            .generate_report(result, ReportFormat::Json)
+            .await?;
+        if let Some(key) = signing_key {
+            let signature = ReportSignature::sign(analysis.as_bytes(), key)?;
+            bundle.add_file(
+                "analysis.json.sig",
+                serde_json::to_string_pretty(&signature)?,
+            )?;
+        }
+        bundle.add_file("analysis.json", analysis)?;
+        if matches!(report_format, ReportFormat::Html)
+            && options.report.html_data == HtmlData::External
+        {
+            let (html, data) = report_generator
+                .generate_html_report_pair(result, "report.data.json")
+                .await?;
+            bundle.add_file("report.html", html)?;
+            bundle.add_file("report.data.json", data)?;
+        } else if !matches!(report_format, ReportFormat::Json) {
+            let extension = report_extension(&report_format);
+            bundle.add_file(
+                format!("report.{}", extension),
+                report_generator
+                    .generate_report(result, report_format)
+                    .await?,
+            )?;
+        }
+        if let Some(artifacts_dir) = options.artifacts_dir.as_deref() {
+            bundle.add_dir(artifacts_dir, "artifacts").await?;
+        }
+        bundle.finish()?;
+        CliOutput::file_info(
+            if options.bundle_password.is_some() {
+                tr("analyze.encrypted_bundle_saved")
+            } else {
+                tr("analyze.bundle_saved")
+            },
+            &bundle_path.display().to_string(),
+        );
+    }
+
+    Ok(())
+}
+
+/// Scrub the requested local details from a result before it is written
+fn redact_result(result: &mut AnalysisResult, redactions: &[Redaction]) -> Result<()> {
+    if redactions.is_empty() {
+        return Ok(());
+    }
+    let changed = Redactor::new(redactions).redact(result)?;
+    let names: Vec<&str> = redactions.iter().map(Redaction::name).collect();
+    CliOutput::info(&tr_with(
+        "analyze.redacted",
+        &[("count", &changed), ("profiles", &names.join(", "))],
+    ));
+    Ok(())
+}
+
+/// Sign a written report with a detached signature next to it
+async fn sign_report(report: &Path, key: &RsaPrivateKey) -> Result<()> {
+    let signature_path = ReportSignature::sign_file(&extended_length(report), key).await?;
+    CliOutput::file_info(
+        tr("analyze.signature_saved"),
+        &signature_path.display().to_string(),
+    );
+    Ok(())
+}
+
+/// Handle the analyze command for several inputs
+///
+/// Without `combined`, one report is written per input into the `output`
+/// directory (or printed to stdout). With `combined`, all inputs end up in a
+/// single multi-artifact report at `output`. Inputs that fail to analyze are
+/// reported and skipped; the command fails if any input failed.
+pub async fn handle_analyze_multiple(
+    inputs: &[PathBuf],
+    output: Option<&Path>,
+    format: Option<&str>,
+    open_browser: bool,
+    combined: bool,
+    options: &AnalyzeOptions,
+) -> Result<()> {
+    if options.bundle.is_some() {
+        return Err(AnalyzerError::config_error(
+            "--bundle supports a single input",
+        ));
+    }
 
-    // Create progress spinner for analysis
-    let spinner = CliOutput::create_spinner("Detecting installer format...");
+    let policy = options.load_policy().await?;
+    let signing_key = options.load_signing_key().await?;
+    CliOutput::section_header(&tr_with("multi.analyzing", &[("count", &inputs.len())]));
 
-    // Create analyzer
-    let analyzer = AnalyzerFactory::create_analyzer(input).await?;
-    spinner.set_message("Reading file contents...");
+    let output = output.filter(|path| !is_stdout_path(path));
+    if signing_key.is_some() && output.is_none() {
+        return Err(AnalyzerError::config_error(
+            "--sign-key needs a report destination (--output)",
+        ));
+    }
+    let report_format = if combined {
+        determine_format(format, output)?
+    } else {
+        determine_format(format, None)?
+    };
+    ensure_printable(&report_format, output, options.force)?;
+    ensure_data_destination(&report_format, output, &options.report)?;
+    let options = &options.resolve_artifacts_dir(if combined {
+        output.map(parent_dir)
+    } else {
+        output
+    })?;
+    if !combined {
+        if let Some(output_dir) = output {
+            tokio::fs::create_dir_all(extended_length(output_dir)).await?;
+            CliOutput::folder_info(
+                tr("common.output_directory"),
+                &output_dir.display().to_string(),
+            );
+        }
+    }
 
-    // Perform analysis with progress updates
     let start_time = Instant::now();
-    spinner.set_message("Extracting metadata...");
-    let metadata = analyzer.extract_metadata(input).await?;
+    let mut results = Vec::new();
+    let mut failed = 0;
+    let mut policy_violations = 0;
+
+    for input in inputs {
+        let input_options = options.for_input(input);
+        let analysis = match (analyze_installer(input, &input_options).await, &policy) {
+            (Ok(mut result), Some(policy)) => {
+                match check_policy(policy, &mut result, &input_options).await {
+                    Ok(report) => {
+                        policy_violations += report.violations.len();
+                        Ok(result)
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+            (analysis, _) => analysis,
+        };
+        let outcome = match analysis {
+            Ok(result) if combined => {
+                results.push(result);
+                Ok(())
+            }
+            Ok(result) => {
+                let report_path = output.map(|dir| {
+                    dir.join(format!(
+                        "{}_report.{}",
+                        file_stem(input),
+                        report_extension(&report_format)
+                    ))
+                });
+                match write_report(
+                    &result,
+                    report_format.clone(),
+                    report_path.as_deref(),
+                    false,
+                    &options.report,
+                )
+                .await
+                {
+                    Ok(()) => match (report_path.as_deref(), signing_key.as_ref()) {
+                        (Some(report_path), Some(key)) => sign_report(report_path, key).await,
+                        _ => Ok(()),
+                    },
+                    Err(e) => Err(e),
+                }
+            }
+            Err(e) => Err(e),
+        };
 
-    spinner.set_message("Analyzing file structure...");
-    let files = analyzer.extract_files(input).await?;
+        if let Err(e) = outcome {
+            failed += 1;
+            CliOutput::error(&tr_with(
+                "multi.failed",
+                &[("input", &input.display()), ("error", &e)],
+            ));
+        }
+    }
+
+    if combined && !results.is_empty() {
+        let report_generator = ReportGenerator::with_options(options.report.clone());
+        if let Some(output_path) = output {
+            let is_html = matches!(report_format, ReportFormat::Html);
+            let format_name = format_to_string(&report_format);
+
+            report_generator
+                .save_combined_report(&results, report_format, output_path)
+                .await?;
+            if let Some(key) = signing_key.as_ref() {
+                sign_report(output_path, key).await?;
+            }
+
+            CliOutput::success(tr("multi.complete"));
+            CliOutput::file_info(tr("common.report_format"), format_name);
+            CliOutput::folder_info(
+                tr("common.report_saved"),
+                &output_path.display().to_string(),
+            );
+
+            if open_browser && is_html {
+                CliOutput::browser_info(tr("common.opening_browser"));
+                if let Err(e) = open_browser_to_file(output_path) {
+                    CliOutput::warning(&tr_with("common.browser_failed", &[("error", &e)]));
+                }
+            }
+        } else {
+            let report_content = report_generator
+                .generate_combined_report(&results, report_format)
+                .await?;
+            println!("{}", report_content);
+        }
+    }
+
+    CliOutput::batch_summary(inputs.len() - failed, failed, start_time.elapsed());
+
+    if failed > 0 {
+        return Err(AnalyzerError::generic(format!(
+            "{} of {} inputs failed to analyze",
+            failed,
+            inputs.len()
+        )));
+    }
+    if let Some(policy) = policy.filter(|_| policy_violations > 0) {
+        return Err(AnalyzerError::policy_violation(
+            policy.display_name(),
+            policy_violations,
+        ));
+    }
+
+    Ok(())
+}
+
+/// Run static analysis of a single installer, including requested exports
+///
+/// A panic in an analyzer is reported as an error, so batch runs continue.
+async fn analyze_installer(source: &Path, options: &AnalyzeOptions) -> Result<AnalysisResult> {
+    let _crash_input = crash::track_input(source);
+    let mut format = None;
+    let result = crash::isolate(source, static_analysis(source, options, &mut format)).await;
+    if let Some(telemetry) = &options.telemetry {
+        telemetry.send(match &result {
+            Ok(result) => TelemetryEvent::analyzed(&result.metadata.format),
+            Err(e) => TelemetryEvent::failed(format.as_ref(), e),
+        });
+    }
+    result
+}
+
+/// Static analysis of `source`, setting `format` once it is detected
+async fn static_analysis(
+    source: &Path,
+    options: &AnalyzeOptions,
+    format: &mut Option<InstallerFormat>,
+) -> Result<AnalysisResult> {
+    CliOutput::info(&tr_with("analyze.starting", &[("path", &source.display())]));
+    let mut audit = AuditTrail::begin(&options.config_files(), options.audit_machine).await?;
+    // Analyzers open the installer by its extended-length path, so inputs
+    // on deep network shares can be read on Windows
+    let input = &extended_length(source);
+
+    progress::stage(Stage::Detecting);
+    // Create analyzer; unrecognized files still get a generic report
+    let analyzer = AnalyzerFactory::create_analyzer_or_fallback(input).await?;
+    if analyzer.format() == InstallerFormat::Unknown {
+        CliOutput::warning(tr("analyze.unrecognized"));
+    }
+    audit.record("format_detected", analyzer.format().to_string());
+    *format = Some(analyzer.format());
+    progress::stage(Stage::Extracting);
+    let (analyzer, analyzed_input, container) = match options.extract_msi.as_deref() {
+        Some(extract_dir) => unwrap_msi(analyzer, input, extract_dir).await?,
+        None => (analyzer, input.to_path_buf(), None),
+    };
+    *format = Some(analyzer.format());
+    let input = analyzed_input.as_path();
+    let depth = options
+        .depth
+        .unwrap_or_else(|| AnalysisDepth::default_for(&analyzer.format()));
+    tracing::info!("Analyzing {} at {} depth", analyzer.format(), depth);
+
+    // The extraction stages are independent of each other and run in parallel
+    let analyzer: Arc<dyn InstallerAnalyzer> = analyzer.into();
+    let start_time = Instant::now();
+    let (mut metadata, mut files, registry_ops, dependencies) = if depth >= AnalysisDepth::Standard
+    {
+        let input = || input.to_path_buf();
+        tokio::try_join!(
+            analyzers::spawn_stage(analyzer.clone(), input(), |analyzer, path| {
+                analyzer.extract_metadata(path)
+            }),
+            analyzers::spawn_stage(analyzer.clone(), input(), |analyzer, path| {
+                analyzer.extract_files(path)
+            }),
+            analyzers::spawn_stage(analyzer.clone(), input(), |analyzer, path| {
+                analyzer.extract_registry_operations(path)
+            }),
+            analyzers::spawn_stage(analyzer.clone(), input(), |analyzer, path| {
+                analyzer.extract_dependencies(path)
+            }),
+        )?
+    } else {
+        let metadata = analyzer.extract_metadata(input).await?;
+        (metadata, Vec::new(), Vec::new(), Vec::new())
+    };
+    metadata.normalize();
+    metadata
+        .properties
+        .insert("analysis_depth".to_string(), depth.to_string());
+    if !options.include_speculative {
+        let total = files.len();
+        files.retain(|file| !file.is_speculative);
+        if files.len() < total {
+            tracing::info!(
+                "Omitted {} speculative file entries (use --include-speculative to keep them)",
+                total - files.len()
+            );
+        }
+    }
 
-    spinner.set_message("Extracting registry operations...");
-    let registry_ops = analyzer.extract_registry_operations(input).await?;
+    let dependency_graph = (!dependencies.is_empty()).then(|| {
+        let root_name = metadata
+            .product_name
+            .clone()
+            .unwrap_or_else(|| source.display().to_string());
+        DependencyGraph::from_dependencies(
+            &root_name,
+            metadata.product_version.as_deref(),
+            &dependencies,
+        )
+    });
 
-    spinner.finish_with_message("✓ Analysis completed");
     let analysis_duration = start_time.elapsed();
+    if let (Some(container), Some(extract_dir)) = (&container, options.extract_msi.as_deref()) {
+        CliOutput::file_info(
+            &tr_with(
+                "analyze.msi_extracted",
+                &[("count", &container.report.packages.len())],
+            ),
+            &extract_dir.display().to_string(),
+        );
+    }
+    if let Some(dump_dir) = options.dump_msi_streams.as_deref() {
+        if analyzer.format() == InstallerFormat::MSI {
+            let count = msi::streams::dump_streams(&MsiDatabase::open(input)?, dump_dir)?;
+            CliOutput::file_info(
+                &tr_with("analyze.msi_streams_dumped", &[("count", &count)]),
+                &dump_dir.display().to_string(),
+            );
+        } else {
+            CliOutput::warning(tr("analyze.msi_streams_not_msi"));
+        }
+    }
 
     // Create analysis result
-    let result = AnalysisResult {
+    let mut result = AnalysisResult {
         session_id: Uuid::new_v4(),
-        source_file_path: Some(input.to_path_buf()),
+        source_file_path: Some(source.to_path_buf()),
         metadata,
         files,
         registry_operations: registry_ops,
         file_operations: Vec::new(), // Static analysis doesn't capture file operations
         process_operations: Vec::new(),
         network_operations: Vec::new(),
+        wmi_operations: Vec::new(),
+        powershell_script_blocks: Vec::new(),
         analyzed_at: Utc::now(),
         analysis_duration,
         dynamic_analysis: false,
+        analyzer_capabilities: Some(analyzer.capabilities()),
+        dependencies: dependency_graph,
+        strings: None,
+        resource_usage: None,
+        audit: None,
+        previews: Vec::new(),
+    };
+    if let Some(container) = &container {
+        container
+            .report
+            .insert_container_properties(&mut result, source, &container.format);
+    }
+    if let Some(environment_path) = options.check_env.as_deref() {
+        if analyzer.format() == InstallerFormat::PythonWheel {
+            let environment = EnvironmentSnapshot::load(environment_path).await?;
+            let metadata = WheelParser::new().extract_metadata(input)?;
+            let check = EnvironmentCheck::check(&metadata, &environment);
+            let environment_name = environment_path.display().to_string();
+            if check.is_compatible() {
+                CliOutput::success(&tr_with(
+                    "analyze.env_compatible",
+                    &[
+                        ("environment", &environment_name),
+                        ("count", &environment.len()),
+                    ],
+                ));
+            } else {
+                let problems: Vec<String> = check.problems().map(|c| c.describe()).collect();
+                CliOutput::warning(&tr_with(
+                    "analyze.env_conflicts",
+                    &[
+                        ("environment", &environment_name),
+                        ("problems", &problems.join("; ")),
+                    ],
+                ));
+            }
+            check.insert_properties(&mut result);
+        } else {
+            CliOutput::warning(tr("analyze.env_not_wheel"));
+        }
+    }
+    progress::stage(Stage::InspectingFiles);
+    let mut strings =
+        (options.extract_strings || depth >= AnalysisDepth::Deep).then(StringCollector::new);
+    // Publisher rules of policies match the signing certificate
+    if (depth >= AnalysisDepth::Standard || options.policy.is_some())
+        && !result
+            .metadata
+            .properties
+            .contains_key("pe_signature_status")
+    {
+        if let Some(signature) = AuthenticodeReport::detect(input).await? {
+            signature.insert_properties(&mut result.metadata.properties);
+        }
+    }
+    if depth >= AnalysisDepth::Standard && analyzer.format() == InstallerFormat::DiskImage {
+        // Installer heuristics don't apply to media, the installers on it are analyzed instead
+        TimestampReport::analyze(&result.files, result.analyzed_at).insert_properties(&mut result);
+        ContainedInstallerReport::detect(analyzer.as_ref(), input, &result.files)
+            .await?
+            .insert_properties(&mut result);
+    } else if depth >= AnalysisDepth::Standard {
+        let deep = depth >= AnalysisDepth::Deep;
+        MultiPartReport::detect(&analyzer.format(), input, &result)
+            .await?
+            .insert_properties(&mut result);
+        TimestampReport::analyze(&result.files, result.analyzed_at).insert_properties(&mut result);
+        ArchitectureReport::detect(analyzer.as_ref(), input, &mut result, deep)
+            .await?
+            .insert_properties(&mut result);
+        RebootAssessment::detect(input, &result)
+            .await?
+            .insert_properties(&mut result);
+        SystemRequirements::detect(input, &result)
+            .await?
+            .insert_properties(&mut result);
+        let delivery = WebInstallerReport::detect(input, &result).await?;
+        if let Some(warning) = delivery.warning() {
+            CliOutput::warning(warning);
+        }
+        delivery.insert_properties(&mut result);
+        EmbeddedPayloadReport::detect(input, options.carve_recursive || deep)
+            .await?
+            .insert_properties(&mut result);
+        let embedded_msis =
+            EmbeddedMsiReport::detect(analyzer.as_ref(), input, &result.files).await?;
+        if !embedded_msis.packages.is_empty() {
+            CliOutput::info(tr("analyze.wraps_msi"));
+        }
+        embedded_msis.insert_properties(&mut result);
+        // Content detectors share one read of the installer and each payload
+        let mut sync_objects = SyncObjectReport::default();
+        let mut evasion = EvasionReport::default();
+        let mut spyware = SpywareReport::default();
+        let mut uac_bypass = UacBypassReport::default();
+        let mut scanners: Vec<&mut dyn SourceScanner> = vec![
+            &mut sync_objects,
+            &mut evasion,
+            &mut spyware,
+            &mut uac_bypass,
+        ];
+        if let Some(collector) = strings.as_mut() {
+            scanners.push(collector);
+        }
+        scanner::scan_sources(analyzer.as_ref(), input, &result.files, &mut scanners).await?;
+        result.strings = strings.take().map(StringCollector::finish);
+        sync_objects.insert_properties(&mut result);
+        evasion.insert_properties(&mut result);
+        if let Some(warning) = spyware.warning() {
+            CliOutput::warning(&warning);
+        }
+        spyware.insert_properties(&mut result);
+        uac_bypass.add_operations(&result);
+        if let Some(warning) = uac_bypass.warning() {
+            CliOutput::warning(&warning);
+        }
+        uac_bypass.insert_properties(&mut result);
+        OfficeAddinReport::detect(analyzer.as_ref(), input, &result)
+            .await?
+            .insert_properties(&mut result);
+        PortableAppReport::detect(analyzer.as_ref(), input, &result)
+            .await?
+            .insert_properties(&mut result);
+        let game = GameRuntimeReport::detect(input, &result).await?;
+        if let Some(warning) = game.warning() {
+            CliOutput::warning(&warning);
+        }
+        game.insert_properties(&mut result);
+    } else if options.carve_recursive {
+        EmbeddedPayloadReport::detect(input, true)
+            .await?
+            .insert_properties(&mut result);
+    }
+    progress::stage(Stage::Hashing);
+    let extra_hashes: Vec<HashAlgorithm> = options
+        .hash_algorithms
+        .iter()
+        .copied()
+        .filter(|algorithm| *algorithm != HashAlgorithm::Sha256)
+        .collect();
+    if !extra_hashes.is_empty() {
+        FileDigests::compute(input, &extra_hashes)
+            .await?
+            .insert_properties(&mut result);
+    }
+    if let Some(mut collector) = strings {
+        scanner::scan_sources(
+            analyzer.as_ref(),
+            input,
+            &result.files,
+            &mut [&mut collector],
+        )
+        .await?;
+        result.strings = Some(collector.finish());
+    }
+    if depth >= AnalysisDepth::Standard {
+        result.previews = previews::collect_previews(analyzer.as_ref(), input, &result.files).await;
+    }
+    if let Some(vendor) = options.load_vendor_kb().await?.enrich(&mut result) {
+        tracing::info!(
+            "Identified vendor {} by {}",
+            vendor.vendor,
+            vendor.matched_by.description()
+        );
+    }
+    progress::stage(Stage::Report);
+    let mut analyzers = vec![analyzer.format().to_string()];
+    let analyzed_path = match &container {
+        Some(container) => {
+            analyzers.push(container.format.to_string());
+            input.display().to_string()
+        }
+        None => source.display().to_string(),
     };
+    audit.finish(
+        InputDigest {
+            path: analyzed_path,
+            size: result.metadata.file_size,
+            sha256: result.metadata.file_hash.clone(),
+        },
+        &analyzers,
+    );
+    result.audit = Some(audit);
+    redact_result(&mut result, &options.redact)?;
+
+    // Export the file tree separately if requested
+    if let Some(tree_path) = options.export_tree.as_deref() {
+        let tree_export_format =
+            TreeExportFormat::determine(options.tree_format.as_deref(), tree_path)?;
+        FileTreeExporter::save(&result, tree_export_format, tree_path).await?;
+        CliOutput::file_info(
+            tr("analyze.tree_exported"),
+            &tree_path.display().to_string(),
+        );
+    }
 
-    // Generate and save report
-    let report_generator = ReportGenerator::new();
-    let report_format = determine_format(format, output)?;
+    // Export the dependency graph if requested
+    if let Some(deps_path) = options.export_deps.as_deref() {
+        let graph = result.dependencies.clone().unwrap_or_default();
+        if graph.is_empty() {
+            CliOutput::warning(tr("analyze.no_dependencies"));
+        }
+        DependencyGraphExporter::save(&graph, deps_path).await?;
+        CliOutput::file_info(
+            tr("analyze.dependencies_exported"),
+            &deps_path.display().to_string(),
+        );
+    }
+
+    // Export deployment metadata for Intune/SCCM if requested
+    if let Some(deployment_path) = options.export_deployment.as_deref() {
+        DeploymentExporter::save(&result, deployment_path).await?;
+        CliOutput::file_info(
+            tr("analyze.deployment_exported"),
+            &deployment_path.display().to_string(),
+        );
+    }
+
+    // Write side-channel artifacts regardless of the report destination
+    if let Some(artifacts_dir) = options.artifacts_dir.as_deref() {
+        let written = ArtifactWriter::save(&result, artifacts_dir).await?;
+        CliOutput::folder_info(
+            &tr_with("analyze.artifacts_written", &[("count", &written.len())]),
+            &artifacts_dir.display().to_string(),
+        );
+
+        if options.attach_artifacts {
+            let mut attachments = Vec::new();
+            for (file, kind) in ArtifactWriter::flagged_files(&result.files) {
+                let content = match analyzer.read_file_content(input, &file.path).await {
+                    Ok(content) => content,
+                    Err(e) => {
+                        tracing::warn!("Failed to extract {}: {}", file.path.display(), e);
+                        None
+                    }
+                };
+                attachments.push((file, kind, content));
+            }
+
+            let index =
+                ArtifactWriter::save_attachments(&result, artifacts_dir, attachments).await?;
+            CliOutput::info(&tr_with(
+                "analyze.attached",
+                &[
+                    ("saved", &index.saved_count()),
+                    ("total", &index.attachments.len()),
+                ],
+            ));
+        }
+    }
+
+    // Append to the results database for later inventory queries
+    if let Some(db_path) = options.db.as_deref() {
+        ResultsDatabase::open(db_path)?.record(&result)?;
+        CliOutput::file_info(tr("analyze.recorded"), &db_path.display().to_string());
+    }
+
+    Ok(result)
+}
+
+/// Wrapper an analyzed MSI package was extracted from
+struct MsiContainer {
+    format: InstallerFormat,
+    report: EmbeddedMsiReport,
+}
+
+/// Extract the MSI packages of an EXE wrapper to `extract_dir`
+///
+/// Returns the analyzer and path of the primary package, or of the input
+/// itself when it has no embedded package.
+async fn unwrap_msi(
+    analyzer: Box<dyn InstallerAnalyzer>,
+    input: &Path,
+    extract_dir: &Path,
+) -> Result<(Box<dyn InstallerAnalyzer>, PathBuf, Option<MsiContainer>)> {
+    let files = analyzer.extract_files(input).await?;
+    let report = EmbeddedMsiReport::detect(analyzer.as_ref(), input, &files).await?;
+    if report.packages.is_empty() {
+        CliOutput::warning(tr("analyze.no_embedded_msi"));
+        return Ok((analyzer, input.to_path_buf(), None));
+    }
+
+    match report.save(&extended_length(extract_dir)).await? {
+        Some(primary) => {
+            tracing::info!(
+                "Analyzing {} in place of its {} wrapper",
+                primary.display(),
+                analyzer.format()
+            );
+            let container = MsiContainer {
+                format: analyzer.format(),
+                report,
+            };
+            let analyzer = AnalyzerFactory::create_analyzer(&primary).await?;
+            Ok((analyzer, primary, Some(container)))
+        }
+        None => Ok((analyzer, input.to_path_buf(), None)),
+    }
+}
+
+/// Check whether an output path means "write to stdout" (`-`)
+pub fn is_stdout_path(path: &Path) -> bool {
+    path.as_os_str() == "-"
+}
+
+/// Refuse to dump an HTML report into an interactive terminal unless forced
+fn ensure_printable(
+    report_format: &ReportFormat,
+    output: Option<&Path>,
+    force: bool,
+) -> Result<()> {
+    use std::io::IsTerminal;
+
+    if output.is_none()
+        && matches!(report_format, ReportFormat::Html)
+        && !force
+        && std::io::stdout().is_terminal()
+    {
+        return Err(AnalyzerError::config_error(
+            "Refusing to print an HTML report to the terminal; use --output <file.html>, redirect stdout or pass --force",
+        ));
+    }
+    Ok(())
+}
+
+/// Refuse HTML reports with external data when there is no file to put it next to
+fn ensure_data_destination(
+    report_format: &ReportFormat,
+    output: Option<&Path>,
+    report_options: &ReportOptions,
+) -> Result<()> {
+    if output.is_none()
+        && matches!(report_format, ReportFormat::Html)
+        && report_options.html_data == HtmlData::External
+    {
+        return Err(AnalyzerError::config_error(
+            "--external-data writes the report data next to the report; use --output <file.html>",
+        ));
+    }
+    Ok(())
+}
+
+/// Write a report to a file, or print it to stdout when no output path is given
+async fn write_report(
+    result: &AnalysisResult,
+    report_format: ReportFormat,
+    output: Option<&Path>,
+    open_browser: bool,
+    report_options: &ReportOptions,
+) -> Result<()> {
+    let report_generator = ReportGenerator::with_options(report_options.clone());
 
     if let Some(output_path) = output {
         let is_html = matches!(report_format, ReportFormat::Html);
         let format_name = format_to_string(&report_format);
 
         report_generator
-            .save_report(&result, report_format, output_path)
+            .save_report(result, report_format, output_path)
             .await?;
 
         CliOutput::analysis_summary(
             format_name,
             &output_path.display().to_string(),
-            analysis_duration,
+            result.analysis_duration,
             Some(result.files.len()),
         );
 
         // Open browser if requested and format is HTML
         if open_browser && is_html {
-            CliOutput::browser_info("Opening report in browser...");
+            CliOutput::browser_info(tr("common.opening_browser"));
             if let Err(e) = open_browser_to_file(output_path) {
-                CliOutput::warning(&format!("Failed to open browser: {}", e));
+                CliOutput::warning(&tr_with("common.browser_failed", &[("error", &e)]));
             }
         }
     } else {
-        let report_content = report_generator
-            .generate_report(&result, report_format)
-            .await?;
-        println!("{}", report_content);
+        let report_content = report_generator
+            .generate_report(result, report_format)
+            .await?;
+        println!("{}", report_content);
+    }
+
+    Ok(())
+}
+
+/// Handle the sandbox command
+pub async fn handle_sandbox(
+    input: &Path,
+    output: Option<&Path>,
+    format: Option<&str>,
+    timeout: u64,
+    enable_network: bool,
+    open_browser: bool,
+) -> Result<()> {
+    handle_sandbox_with_options(
+        input,
+        output,
+        format,
+        timeout,
+        enable_network,
+        open_browser,
+        &SandboxOptions::default(),
+    )
+    .await
+}
+
+/// Handle the sandbox command with run, disguise, clock and noise filter options
+pub async fn handle_sandbox_with_options(
+    input: &Path,
+    output: Option<&Path>,
+    format: Option<&str>,
+    timeout: u64,
+    enable_network: bool,
+    open_browser: bool,
+    options: &SandboxOptions,
+) -> Result<()> {
+    sandbox_and_write(
+        input,
+        output,
+        format,
+        timeout,
+        enable_network,
+        open_browser,
+        options,
+    )
+    .await
+    .map(|_| ())
+}
+
+/// Run an installer in the sandbox and write the report
+async fn sandbox_and_write(
+    input: &Path,
+    output: Option<&Path>,
+    format: Option<&str>,
+    timeout: u64,
+    enable_network: bool,
+    open_browser: bool,
+    options: &SandboxOptions,
+) -> Result<AnalysisResult> {
+    let mut result = run_sandbox(input, timeout, enable_network, options).await?;
+    redact_result(&mut result, &options.redact)?;
+
+    // Generate and save report
+    let report_generator = ReportGenerator::new();
+    let report_format = determine_format(format, output)?;
+
+    if let Some(output_path) = output {
+        let is_html = matches!(report_format, ReportFormat::Html);
+        let format_name = format_to_string(&report_format);
+
+        report_generator
+            .save_report(&result, report_format, output_path)
+            .await?;
+
+        CliOutput::success(tr("sandbox.complete"));
+        CliOutput::file_info(tr("common.report_format"), format_name);
+        CliOutput::folder_info(
+            tr("common.report_saved"),
+            &output_path.display().to_string(),
+        );
+
+        // Open browser if requested and format is HTML
+        if open_browser && is_html {
+            CliOutput::browser_info(tr("common.opening_browser"));
+            if let Err(e) = open_browser_to_file(output_path) {
+                CliOutput::warning(&tr_with("common.browser_failed", &[("error", &e)]));
+            }
+        }
+    } else {
+        let report_content = report_generator
+            .generate_report(&result, report_format)
+            .await?;
+        println!("{}", report_content);
+    }
+
+    Ok(result)
+}
+
+/// Run an installer in the sandbox and cross-check it against its static analysis
+async fn run_sandbox(
+    input: &Path,
+    timeout: u64,
+    enable_network: bool,
+    options: &SandboxOptions,
+) -> Result<AnalysisResult> {
+    options.check_supported()?;
+    if !sandbox::is_supported() {
+        return Err(AnalyzerError::sandbox_error(
+            "Sandbox analysis requires Windows and the `sandbox` feature, only static analysis is available on this host",
+        ));
+    }
+    let _crash_input = crash::track_input(input);
+    crash::isolate(
+        input,
+        sandbox_analysis(input, timeout, enable_network, options),
+    )
+    .await
+}
+
+async fn sandbox_analysis(
+    input: &Path,
+    timeout: u64,
+    enable_network: bool,
+    options: &SandboxOptions,
+) -> Result<AnalysisResult> {
+    let noise_filter = match (&options.noise_filter, options.raw) {
+        (_, true) => None,
+        (Some(path), false) => Some(NoiseFilter::load(path).await?),
+        (None, false) => Some(NoiseFilter::curated()),
+    };
+    CliOutput::info(&tr_with("sandbox.starting", &[("path", &input.display())]));
+
+    // Create progress spinner for sandbox analysis
+    let spinner = CliOutput::create_spinner(tr("sandbox.initializing"));
+
+    // Create sandbox configuration
+    let config = SandboxConfig {
+        enable_network,
+        max_execution_time: Duration::from_secs(timeout),
+        noise_filter,
+        ..Default::default()
+    };
+
+    let mut sync_objects = SyncObjectReport::default();
+    let mut spyware = SpywareReport::default();
+    let mut evasion = EvasionReport::default();
+    let mut uac_bypass = UacBypassReport::default();
+    scanner::scan_installer(
+        input,
+        &mut [
+            &mut sync_objects,
+            &mut spyware,
+            &mut evasion,
+            &mut uac_bypass,
+        ],
+    )
+    .await?;
+    let sessions = SandboxSessions::new();
+    let mut sandbox = SandboxController::with_sessions(config, sessions.clone());
+    let session = sandbox.start_session(input);
+    for tag in &options.tags {
+        sessions.tag(session, tag)?;
+    }
+    spinner.set_message(tr("sandbox.executing"));
+
+    tracing::info!("Sandbox session {} started", session);
+    let mut result = sandbox.run_session(session).await?;
+    sync_objects.merge_observed(sandbox.observed_sync_objects());
+    spyware.merge_observed(sandbox.observed_api_calls());
+    if let Some(usage) = result
+        .resource_usage
+        .as_ref()
+        .filter(|usage| !usage.samples.is_empty())
+    {
+        let resources = ResourceReport::assess(usage);
+        if let Some(warning) = resources.warning() {
+            CliOutput::warning(&warning);
+        }
+        resources.insert_properties(&mut result);
+    }
+    let scripts = ScriptActivityReport::from_result(&result);
+    if let Some(warning) = scripts.warning() {
+        CliOutput::warning(&warning);
+    }
+    scripts.insert_properties(&mut result);
+
+    RebootAssessment::detect(input, &result)
+        .await?
+        .insert_properties(&mut result);
+    sync_objects.insert_properties(&mut result);
+    evasion.insert_properties(&mut result);
+    if let Some(warning) = spyware.warning() {
+        CliOutput::warning(&warning);
+    }
+    spyware.insert_properties(&mut result);
+    uac_bypass.add_operations(&result);
+    if let Some(warning) = uac_bypass.warning() {
+        CliOutput::warning(&warning);
+    }
+    uac_bypass.insert_properties(&mut result);
+    spinner.finish_with_message(tr("sandbox.completed"));
+
+    // Cross-check what the installer declares against what it did
+    match analyze_installer(input, &AnalyzeOptions::default()).await {
+        Ok(static_result) => result = merge::merge(&static_result, &result),
+        Err(e) => CliOutput::warning(&tr_with("sandbox.static_failed", &[("error", &e)])),
+    }
+    result.metadata.normalize();
+
+    Ok(result)
+}
+
+/// Handle the batch command
+pub async fn handle_batch(
+    input_dir: &Path,
+    output_dir: &Path,
+    format: Option<&str>,
+    use_sandbox: bool,
+) -> Result<()> {
+    handle_batch_with_options(
+        input_dir,
+        output_dir,
+        format,
+        use_sandbox,
+        &AnalyzeOptions::default(),
+        &BatchOptions::default(),
+    )
+    .await
+}
+
+/// Handle the batch command with additional options for static analysis and failures
+pub async fn handle_batch_with_options(
+    input_dir: &Path,
+    output_dir: &Path,
+    format: Option<&str>,
+    use_sandbox: bool,
+    options: &AnalyzeOptions,
+    batch: &BatchOptions,
+) -> Result<()> {
+    // Validate the policy once before processing any installer
+    let policy = options.load_policy().await?;
+    // The batch progress bar replaces the stages of each installer
+    let options = &AnalyzeOptions {
+        quiet: true,
+        ..options.clone()
+    };
+    CliOutput::section_header(tr("batch.header"));
+    CliOutput::folder_info(
+        tr("batch.input_directory"),
+        &input_dir.display().to_string(),
+    );
+    CliOutput::folder_info(
+        tr("common.output_directory"),
+        &output_dir.display().to_string(),
+    );
+
+    // Create output directory if it doesn't exist
+    tokio::fs::create_dir_all(extended_length(output_dir)).await?;
+
+    // Find all installer files first to get total count
+    let mut dir_entries = tokio::fs::read_dir(extended_length(input_dir)).await?;
+    let mut installer_files = Vec::new();
+    let mut summary_entries = Vec::new();
+
+    while let Some(entry) = dir_entries.next_entry().await? {
+        let is_file = tokio::fs::metadata(entry.path())
+            .await
+            .is_ok_and(|metadata| metadata.is_file());
+        if !is_file {
+            continue;
+        }
+        // Keep the path as given, the summary lists it
+        let path = input_dir.join(entry.file_name());
+        if is_supported_file(&path) {
+            installer_files.push(path);
+        } else {
+            summary_entries.push(BatchEntry::skipped(&path));
+        }
+    }
+
+    if installer_files.is_empty() {
+        CliOutput::warning(tr("batch.no_installers"));
+        save_batch_summary(input_dir, output_dir, summary_entries).await?;
+        return Ok(());
+    }
+    if use_sandbox && (options.db.is_some() || policy.is_some()) {
+        CliOutput::warning(tr("batch.sandbox_not_recorded"));
+    }
+
+    CliOutput::info(&tr_with(
+        "batch.found",
+        &[("count", &installer_files.len())],
+    ));
+
+    // Create progress bar
+    let pb = CliOutput::create_progress_bar(installer_files.len() as u64, tr("batch.processing"));
+
+    let mut processed = 0;
+    let mut failed = 0;
+    let mut policy_violations = 0;
+    let batch_start = Instant::now();
+
+    for path in installer_files {
+        let file_name = file_stem(&path);
+
+        let format_str = format.unwrap_or("json");
+        let output_file = output_dir.join(format!(
+            "{}_report.{}",
+            file_name,
+            get_file_extension(format_str)
+        ));
+
+        pb.set_message(tr_with("batch.processing_file", &[("file", &file_name)]));
+
+        let started = Instant::now();
+        let mut attempts = 0;
+        let result = loop {
+            attempts += 1;
+            let result = if use_sandbox {
+                sandbox_and_write(
+                    &path,
+                    Some(&output_file),
+                    format,
+                    300,
+                    false,
+                    false,
+                    &SandboxOptions::default(),
+                )
+                .await
+                .map(|result| (result, None))
+            } else {
+                analyze_and_write(&path, Some(&output_file), format, false, options).await
+            };
+            match result {
+                Err(e) if e.is_transient() && attempts <= batch.retries => {
+                    pb.println(format!(
+                        "↻ Retrying ({}/{}): {} - {}",
+                        attempts,
+                        batch.retries,
+                        path.display(),
+                        e
+                    ));
+                    tokio::time::sleep(BATCH_RETRY_DELAY * 2u32.pow(attempts - 1)).await;
+                }
+                result => break result,
+            }
+        };
+
+        match result {
+            Ok((result, policy_report)) => {
+                processed += 1;
+                let violations = policy_report
+                    .filter(|report| !report.passed)
+                    .map_or(0, |report| report.violations.len());
+                if violations > 0 {
+                    // The report was written, only the policy check failed
+                    policy_violations += violations;
+                    pb.println(format!(
+                        "✗ Policy violations: {} ({})",
+                        path.display(),
+                        violations
+                    ));
+                } else {
+                    pb.println(format!("✓ Completed: {}", path.display()));
+                }
+                let mut entry = BatchEntry::analyzed(
+                    &path,
+                    &result,
+                    &output_file,
+                    started.elapsed(),
+                    violations,
+                );
+                entry.attempts = attempts;
+                summary_entries.push(entry);
+            }
+            Err(e) => {
+                failed += 1;
+                pb.println(format!("✗ Failed: {} - {}", path.display(), e));
+                let mut entry = BatchEntry::failed(&path, &e, started.elapsed());
+                entry.attempts = attempts;
+                if batch.quarantine {
+                    match quarantine_input(&path, &e, attempts, output_dir).await {
+                        Ok(copy) => entry.quarantined = Some(copy.display().to_string()),
+                        Err(quarantine_error) => pb.println(format!(
+                            "✗ Failed to quarantine {}: {}",
+                            path.display(),
+                            quarantine_error
+                        )),
+                    }
+                }
+                summary_entries.push(entry);
+            }
+        }
+
+        pb.inc(1);
+    }
+
+    CliOutput::finish_progress_success(&pb, tr("batch.complete"));
+
+    let total_duration = batch_start.elapsed();
+    CliOutput::batch_summary(processed, failed, total_duration);
+    save_batch_summary(input_dir, output_dir, summary_entries).await?;
+
+    if let Some(policy) = policy.filter(|_| policy_violations > 0) {
+        return Err(AnalyzerError::policy_violation(
+            policy.display_name(),
+            policy_violations,
+        ));
+    }
+
+    Ok(())
+}
+
+/// Copy an input that could not be analyzed to `failed/` with the reason next to it
+///
+/// Returns the path of the copy. The input itself is left in place.
+async fn quarantine_input(
+    input: &Path,
+    error: &AnalyzerError,
+    attempts: u32,
+    output_dir: &Path,
+) -> Result<PathBuf> {
+    let quarantine_dir = extended_length(&output_dir.join("failed"));
+    tokio::fs::create_dir_all(&quarantine_dir).await?;
+
+    let file_name = input
+        .file_name()
+        .ok_or_else(|| AnalyzerError::generic("Input has no file name"))?;
+    let copy = quarantine_dir.join(file_name);
+    tokio::fs::copy(extended_length(input), &copy).await?;
+
+    let mut reason_name = file_name.to_os_string();
+    reason_name.push(".reason.txt");
+    let reason = format!(
+        "Input: {}\nFailed at: {}\nAttempts: {}\nError: {}\n",
+        input.display(),
+        Utc::now().to_rfc3339(),
+        attempts,
+        error
+    );
+    tokio::fs::write(quarantine_dir.join(reason_name), reason).await?;
+    Ok(without_prefix(&copy))
+}
+
+/// Write the summary manifest of a batch run, ordered by input path
+async fn save_batch_summary(
+    input_dir: &Path,
+    output_dir: &Path,
+    mut entries: Vec<BatchEntry>,
+) -> Result<()> {
+    entries.sort_by(|a, b| a.input.cmp(&b.input));
+    BatchSummary::new(input_dir, entries)
+        .save(output_dir)
+        .await?;
+    CliOutput::file_info(
+        tr("batch.summary_saved"),
+        &output_dir.join("summary.{json,csv}").display().to_string(),
+    );
+    Ok(())
+}
+
+/// Handle the info command
+pub async fn handle_info() -> Result<()> {
+    println!("Installer Analyzer - Supported Formats");
+    println!("=====================================");
+    println!();
+    println!("Static Analysis (Fully Supported):");
+    println!("  ✅ MSI (Microsoft Installer) - .msi files");
+    println!("     • Metadata extraction (product, version, manufacturer)");
+    println!("     • File list with sizes and attributes");
+    println!("     • Registry operations analysis");
+    println!();
+    println!("  ✅ NSIS (Nullsoft Scriptable Install System) - .exe files");
+    println!("     • Format detection via signature patterns");
+    println!("     • Basic metadata extraction");
+    println!("     • File structure analysis");
+    println!();
+    println!("  ✅ InnoSetup - .exe files");
+    println!("     • Format detection via signature patterns");
+    println!("     • Basic metadata extraction");
+    println!("     • File structure analysis");
+    println!();
+    println!("Planned Support:");
+    println!("  🔄 Python Wheel - .whl files");
+    println!("     • ZIP-based archive extraction");
+    println!("     • METADATA file parsing");
+    println!("     • Dependency analysis");
+    println!();
+    println!("  🔄 7zip Archive Support - various formats");
+    println!("     • Universal archive extraction fallback");
+    println!("     • Support for .7z, .rar, .tar.gz, etc.");
+    println!();
+    println!("Dynamic Analysis (Sandbox):");
+    println!("  ✅ File system monitoring");
+    println!("  ✅ Registry monitoring");
+    println!("  🔄 Process monitoring (planned)");
+    println!("  🔄 Network monitoring (planned)");
+    println!();
+    print_analyzer_capabilities();
+    print_platform_capabilities();
+    println!("Report Formats:");
+    println!("  • JSON - Machine-readable structured data");
+    println!("  • HTML - Human-readable web format with file tree view");
+    println!("  • Markdown - Documentation-friendly format");
+    println!();
+    println!("Usage Examples:");
+    println!("  installer-analyzer analyze app.msi --format html");
+    println!("  installer-analyzer analyze setup.exe --format json");
+    println!("  installer-analyzer sandbox installer.exe --timeout 300");
+    println!("  installer-analyzer batch ./installers/ ./reports/ --format html");
+
+    Ok(())
+}
+
+/// Print the declared capabilities of every registered analyzer
+fn print_analyzer_capabilities() {
+    println!("Analyzer Capabilities:");
+    println!(
+        "  Format         Metadata     Files        Registry     Scripts      Signatures   Accuracy"
+    );
+    for analyzer in AnalyzerFactory::get_all_analyzers() {
+        let caps = analyzer.capabilities();
+        println!(
+            "  {:<14} {:<12} {:<12} {:<12} {:<12} {:<12} {}",
+            analyzer.format().name(),
+            caps.metadata.name(),
+            caps.file_extraction.name(),
+            caps.registry_operations.name(),
+            caps.install_scripts.name(),
+            caps.signatures.name(),
+            caps.accuracy()
+        );
+    }
+    println!("  (heuristic = synthesized from common patterns, not read from the installer)");
+    println!();
+}
+
+/// Handle the info command with `--platform-capabilities`
+pub fn handle_platform_capabilities() -> Result<()> {
+    print_platform_capabilities();
+    Ok(())
+}
+
+/// Print which features are available on the current operating system
+fn print_platform_capabilities() {
+    println!(
+        "Platform Capabilities ({}/{}):",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+    for capability in crate::utils::platform_capabilities() {
+        println!(
+            "  {} {:<44} {}",
+            if capability.available { "✅" } else { "❌" },
+            capability.feature,
+            capability.note
+        );
+    }
+    println!();
+}
+
+/// Selection of the query command
+#[derive(Debug, Clone, Default)]
+pub struct QueryOptions {
+    /// Installers shipping a component, e.g. `openssl<3`
+    pub component: Option<String>,
+    /// Installers containing a file with this hash
+    pub hash: Option<String>,
+    /// Count installers per canonical vendor
+    pub vendors: bool,
+    /// Installers of a canonical vendor
+    pub vendor: Option<String>,
+    /// Print results as JSON instead of a table
+    pub json: bool,
+}
+
+/// Handle the query command
+///
+/// Without a filter, the latest analysis of every recorded installer is listed.
+pub async fn handle_query(db_path: &Path, options: &QueryOptions) -> Result<()> {
+    if !db_path.is_file() {
+        return Err(AnalyzerError::file_not_found(db_path));
+    }
+    let db = ResultsDatabase::open(db_path)?;
+    let na = |value: &Option<String>| terminal_safe(value.as_deref().unwrap_or("-")).into_owned();
+
+    if let Some(expression) = options.component.as_deref() {
+        let filter = ComponentFilter::parse(expression)?;
+        let matches = db.find_components(&filter)?;
+        if options.json {
+            return print_json(&matches);
+        }
+        for m in &matches {
+            println!(
+                "{}\t{} {}\t{}\t{} {}\t{}",
+                na(&m.source),
+                na(&m.product_name),
+                na(&m.product_version),
+                na(&m.manufacturer),
+                terminal_safe(&m.component.name),
+                na(&m.component.version),
+                terminal_safe(&m.component.evidence)
+            );
+        }
+        CliOutput::info(&tr_with(
+            "query.component_matches",
+            &[("count", &matches.len()), ("filter", &expression)],
+        ));
+    } else if let Some(hash) = options.hash.as_deref() {
+        let matches = db.find_files_by_hash(hash)?;
+        if options.json {
+            return print_json(&matches);
+        }
+        for m in &matches {
+            println!(
+                "{}\t{}\t{}\t{}",
+                na(&m.source),
+                na(&m.product_name),
+                terminal_safe(m.path.as_deref().unwrap_or("(installer)")),
+                crate::utils::format_file_size(m.size)
+            );
+        }
+        CliOutput::info(&tr_with(
+            "query.hash_matches",
+            &[("count", &matches.len()), ("hash", &hash)],
+        ));
+    } else if options.vendors {
+        let vendors = db.vendors()?;
+        if options.json {
+            return print_json(&vendors);
+        }
+        for vendor in &vendors {
+            println!("{}\t{}", vendor.installers, terminal_safe(&vendor.vendor));
+        }
+        CliOutput::info(&tr_with("query.vendors", &[("count", &vendors.len())]));
+    } else {
+        let analyses = match options.vendor.as_deref() {
+            Some(vendor) => db.analyses_by_vendor(vendor)?,
+            None => db.analyses()?,
+        };
+        if options.json {
+            return print_json(&analyses);
+        }
+        for record in &analyses {
+            println!(
+                "{}\t{}\t{}\t{} {}\t{}\t{}\t{} files",
+                record.analyzed_at,
+                na(&record.source),
+                record.format,
+                na(&record.product_name),
+                na(&record.product_version),
+                na(&record
+                    .vendor
+                    .clone()
+                    .or_else(|| record.manufacturer.clone())),
+                na(&record.category),
+                record.file_count
+            );
+        }
+        match options.vendor.as_deref() {
+            Some(vendor) => CliOutput::info(&tr_with(
+                "query.vendor_installers",
+                &[("count", &analyses.len()), ("vendor", &vendor)],
+            )),
+            None => CliOutput::info(&tr_with(
+                "query.installers",
+                &[
+                    ("count", &analyses.len()),
+                    ("analyses", &db.analysis_count()?),
+                ],
+            )),
+        }
+    }
+
+    Ok(())
+}
+
+/// Print query results as pretty JSON on stdout
+fn print_json<T: serde::Serialize>(value: &T) -> Result<()> {
+    let content = serde_json::to_string_pretty(value).map_err(AnalyzerError::SerializationError)?;
+    println!("{}", content);
+    Ok(())
+}
+
+/// Two artifacts found to be related by the correlate command
+#[derive(Debug, serde::Serialize)]
+struct CorrelatedPair<'a> {
+    left: &'a str,
+    right: &'a str,
+    #[serde(flatten)]
+    correlation: Correlation,
+}
+
+/// Handle the correlate command
+///
+/// Inputs are installers, which are analyzed, or JSON reports of earlier
+/// analyses. With a results database, every recorded installer takes part too.
+pub async fn handle_correlate(
+    inputs: &[PathBuf],
+    db_path: Option<&Path>,
+    json: bool,
+) -> Result<()> {
+    let mut identities = Vec::new();
+    for input in inputs {
+        identities.push(load_product_identity(input).await?);
+    }
+    if let Some(db_path) = db_path {
+        if !db_path.is_file() {
+            return Err(AnalyzerError::file_not_found(db_path));
+        }
+        let db = ResultsDatabase::open(db_path)?;
+        identities.extend(
+            db.latest_results()?
+                .iter()
+                .map(ProductIdentity::from_result),
+        );
+    }
+    if identities.len() < 2 {
+        return Err(AnalyzerError::config_error(
+            "Correlation needs at least two artifacts",
+        ));
+    }
+
+    let mut pairs = Vec::new();
+    for (i, left) in identities.iter().enumerate() {
+        for right in &identities[i + 1..] {
+            let correlation = correlate(left, right);
+            if correlation.relationship != Relationship::Unrelated {
+                pairs.push(CorrelatedPair {
+                    left: &left.source,
+                    right: &right.source,
+                    correlation,
+                });
+            }
+        }
+    }
+    let families = product_families(&identities);
+
+    if json {
+        let families: Vec<Vec<&str>> = families
+            .iter()
+            .map(|family| {
+                family
+                    .iter()
+                    .map(|&index| identities[index].source.as_str())
+                    .collect()
+            })
+            .collect();
+        return print_json(&serde_json::json!({
+            "identities": identities,
+            "correlations": pairs,
+            "families": families,
+        }));
+    }
+
+    let na = |value: &Option<String>| terminal_safe(value.as_deref().unwrap_or("-")).into_owned();
+    for family in families.iter().filter(|family| family.len() > 1) {
+        let first = &identities[family[0]];
+        println!(
+            "\n{} ({})",
+            na(&first.product_name),
+            na(&first.manufacturer)
+        );
+        for &index in family {
+            let identity = &identities[index];
+            println!(
+                "{}\t{}\t{}\tProductCode {}\tUpgradeCode {}",
+                terminal_safe(&identity.source),
+                identity.format,
+                na(&identity.product_version),
+                na(&identity.product_code),
+                na(&identity.upgrade_code)
+            );
+        }
+    }
+    if !pairs.is_empty() {
+        println!();
+        for pair in &pairs {
+            println!(
+                "{} ~ {}\t{:?}\t{}",
+                terminal_safe(pair.left),
+                terminal_safe(pair.right),
+                pair.correlation.relationship,
+                terminal_safe(&pair.correlation.evidence.join("; "))
+            );
+        }
+    }
+
+    let related = families.iter().filter(|family| family.len() > 1).count();
+    CliOutput::info(&tr_with(
+        "correlate.families",
+        &[
+            ("artifacts", &identities.len()),
+            ("families", &families.len()),
+            ("related", &related),
+        ],
+    ));
+    Ok(())
+}
+
+/// Product identity of an installer or of a JSON report
+async fn load_product_identity(input: &Path) -> Result<ProductIdentity> {
+    if !input.exists() {
+        return Err(AnalyzerError::file_not_found(input));
+    }
+
+    if is_json_report(input) {
+        let report = read_json_report(input).await?;
+        return ProductIdentity::from_report(&report).ok_or_else(|| not_a_report(input));
+    }
+
+    let result = analyze_installer(input, &AnalyzeOptions::default()).await?;
+    Ok(ProductIdentity::from_result(&result))
+}
+
+/// Handle the report merge command
+///
+/// `static_input` is an installer or a JSON report of the analyze command,
+/// `dynamic_input` a JSON report of the sandbox command.
+pub async fn handle_report_merge(
+    static_input: &Path,
+    dynamic_input: &Path,
+    output: Option<&Path>,
+    format: Option<&str>,
+    open_browser: bool,
+) -> Result<()> {
+    let output = output.filter(|path| !is_stdout_path(path));
+    let report_format = determine_format(format, output)?;
+    ensure_printable(&report_format, output, false)?;
+
+    let static_result = if is_json_report(static_input) {
+        load_report_result(static_input).await?
+    } else {
+        analyze_installer(static_input, &AnalyzeOptions::default()).await?
+    };
+    let dynamic_result = load_report_result(dynamic_input).await?;
+    if !dynamic_result.dynamic_analysis {
+        return Err(AnalyzerError::invalid_format(format!(
+            "{} is not a sandbox report",
+            dynamic_input.display()
+        )));
+    }
+    let (static_hash, dynamic_hash) = (
+        &static_result.metadata.file_hash,
+        &dynamic_result.metadata.file_hash,
+    );
+    if !static_hash.is_empty() && !dynamic_hash.is_empty() && static_hash != dynamic_hash {
+        CliOutput::warning(tr("diff.different_installers"));
+    }
+
+    let merged = merge::merge(&static_result, &dynamic_result);
+    for finding in discrepancies::find_discrepancies(&static_result, &dynamic_result) {
+        CliOutput::warning(&format!(
+            "[{}] {}: {}",
+            finding.severity,
+            finding.title,
+            finding.evidence.len()
+        ));
+    }
+    let properties = &merged.metadata.properties;
+    for (label, key) in [
+        (
+            "diff.declared_not_installed",
+            "declared_not_installed_count",
+        ),
+        (
+            "diff.installed_not_declared",
+            "installed_not_declared_count",
+        ),
+        (
+            "diff.registry_declared_not_written",
+            "registry_declared_not_written_count",
+        ),
+        (
+            "diff.registry_written_not_declared",
+            "registry_written_not_declared_count",
+        ),
+    ] {
+        if let Some(count) = properties.get(key) {
+            CliOutput::info(&format!("{}: {}", tr(label), count));
+        }
+    }
+
+    write_report(
+        &merged,
+        report_format,
+        output,
+        open_browser,
+        &ReportOptions::default(),
+    )
+    .await
+}
+
+/// Handle the extract command
+///
+/// Streams one packaged file to `output` (stdout if not given) through
+/// [`InstallerAnalyzer::open_file`], leaving the rest of the installer packed.
+pub async fn handle_extract(input: &Path, entry: &Path, output: Option<&Path>) -> Result<()> {
+    let analyzer = AnalyzerFactory::create_analyzer_or_fallback(input).await?;
+    let mut reader = analyzer.open_file(input, entry).await?.ok_or_else(|| {
+        AnalyzerError::generic(format!(
+            "{} is not packaged in {}, or files cannot be read from {} installers",
+            entry.display(),
+            input.display(),
+            analyzer.format()
+        ))
+    })?;
+
+    match output {
+        Some(path) if !is_stdout_path(path) => {
+            let mut file = tokio::fs::File::create(path).await?;
+            let written = tokio::io::copy(&mut reader, &mut file).await?;
+            CliOutput::folder_info(
+                &tr_with(
+                    "extract.written",
+                    &[
+                        ("entry", &entry.display().to_string()),
+                        ("bytes", &written.to_string()),
+                    ],
+                ),
+                &path.display().to_string(),
+            );
+        }
+        _ => {
+            let mut stdout = tokio::io::stdout();
+            tokio::io::copy(&mut reader, &mut stdout).await?;
+            tokio::io::AsyncWriteExt::flush(&mut stdout).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Handle the report upgrade command
+///
+/// Rewrites `input` in place unless an output path is given.
+pub async fn handle_report_upgrade(input: &Path, output: Option<&Path>) -> Result<()> {
+    let mut report = read_json_report(input).await?;
+    let upgrade = migration::upgrade(&mut report)?;
+    if upgrade.is_current() {
+        CliOutput::info(&tr_with(
+            "upgrade.current",
+            &[("version", &upgrade.to_version)],
+        ));
+    } else {
+        CliOutput::info(&tr_with(
+            "upgrade.upgraded",
+            &[("from", &upgrade.from_version), ("to", &upgrade.to_version)],
+        ));
+        for change in &upgrade.changes {
+            CliOutput::info(&format!("  {}", change));
+        }
     }
 
+    let content =
+        serde_json::to_string_pretty(&report).map_err(AnalyzerError::SerializationError)?;
+    match output {
+        Some(path) if is_stdout_path(path) => println!("{}", content),
+        Some(path) => {
+            tokio::fs::write(path, content).await?;
+            CliOutput::folder_info(tr("common.report_saved"), &path.display().to_string());
+        }
+        None if upgrade.is_current() => {}
+        None => {
+            tokio::fs::write(input, content).await?;
+            CliOutput::folder_info(tr("common.report_saved"), &input.display().to_string());
+        }
+    }
     Ok(())
 }
 
-/// Handle the sandbox command
-pub async fn handle_sandbox(
+/// Handle the report verify command
+///
+/// Fails unless the report matches its detached signature and `key`.
+pub async fn handle_report_verify(
     input: &Path,
-    output: Option<&Path>,
-    format: Option<&str>,
-    timeout: u64,
-    enable_network: bool,
-    open_browser: bool,
+    key: &Path,
+    signature: Option<&Path>,
 ) -> Result<()> {
-    CliOutput::info(&format!(
-        "Starting sandbox analysis of: {}",
-        input.display()
+    let signature_path = signature
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| ReportSignature::path_for(input));
+    let signature = ReportSignature::load(&signature_path).await?;
+    let key = audit::load_verifying_key(key).await?;
+    let report = tokio::fs::read(input).await?;
+
+    signature.verify(&report, &key)?;
+    CliOutput::success(&tr_with(
+        "verify.valid",
+        &[
+            ("path", &input.display()),
+            ("signed_at", &signature.signed_at.to_rfc3339()),
+            ("key", &signature.key_fingerprint),
+        ],
     ));
+    Ok(())
+}
 
-    // Create progress spinner for sandbox analysis
-    let spinner = CliOutput::create_spinner("Initializing sandbox environment...");
-
-    // Create sandbox configuration
-    let config = SandboxConfig {
-        enable_network,
-        max_execution_time: Duration::from_secs(timeout),
-        ..Default::default()
-    };
+/// Whether an input is a JSON report rather than an installer
+fn is_json_report(input: &Path) -> bool {
+    input
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+}
 
-    // Create sandbox controller
-    let mut sandbox = SandboxController::with_config(config);
-    spinner.set_message("Starting installer execution...");
+async fn read_json_report(input: &Path) -> Result<serde_json::Value> {
+    if !input.exists() {
+        return Err(AnalyzerError::file_not_found(input));
+    }
+    let content = tokio::fs::read_to_string(input).await?;
+    serde_json::from_str(&content).map_err(AnalyzerError::SerializationError)
+}
 
-    // Perform sandbox analysis
-    let result = sandbox.analyze_installer(input).await?;
-    spinner.finish_with_message("✓ Sandbox analysis completed");
+async fn load_report_result(input: &Path) -> Result<AnalysisResult> {
+    let mut report = read_json_report(input).await?;
+    if migration::schema_version(&report) < REPORT_SCHEMA_VERSION {
+        migration::upgrade(&mut report).map_err(|_| not_a_report(input))?;
+    }
+    merge::result_from_report(&report).ok_or_else(|| not_a_report(input))
+}
 
-    // Generate and save report
-    let report_generator = ReportGenerator::new();
-    let report_format = determine_format(format, output)?;
+fn not_a_report(input: &Path) -> AnalyzerError {
+    AnalyzerError::invalid_format(format!("{} is not a JSON analysis report", input.display()))
+}
 
-    if let Some(output_path) = output {
-        let is_html = matches!(report_format, ReportFormat::Html);
-        let format_name = format_to_string(&report_format);
+/// Options of the worker command
+#[derive(Debug, Clone)]
+pub struct WorkerOptions {
+    /// Name reported to the server
+    pub name: String,
+    /// Only claim static analysis jobs, e.g. on machines without a sandbox
+    pub static_only: bool,
+    /// Pause before polling an empty queue again
+    pub poll_interval: Duration,
+}
 
-        report_generator
-            .save_report(&result, report_format, output_path)
-            .await?;
+/// Worker name made of the host name and process ID, unique per process
+pub fn default_worker_name() -> String {
+    let host = std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .unwrap_or_else(|_| "worker".to_string());
+    format!("{}-{}", host, std::process::id())
+}
 
-        CliOutput::success("Sandbox analysis complete!");
-        CliOutput::file_info("Report format", format_name);
-        CliOutput::folder_info("Report saved to", &output_path.display().to_string());
+/// Handle the serve command
+pub async fn handle_serve(config: ApiConfig) -> Result<()> {
+    CliOutput::section_header(tr("serve.header"));
+    if let Some(db) = &config.db {
+        CliOutput::file_info(tr("common.results_database"), &db.display().to_string());
+    }
+    if config.static_only {
+        CliOutput::info(tr("serve.static_only"));
+    }
+    if let Some(store_dir) = &config.store_dir {
+        CliOutput::folder_info(tr("serve.store"), &store_dir.display().to_string());
+    }
+    if let Some(every) = config.reanalyze_every {
+        CliOutput::info(&tr_with(
+            "serve.reanalyze",
+            &[("hours", &(every.as_secs() / 3600))],
+        ));
+    }
 
-        // Open browser if requested and format is HTML
-        if open_browser && is_html {
-            CliOutput::browser_info("Opening report in browser...");
-            if let Err(e) = open_browser_to_file(output_path) {
-                CliOutput::warning(&format!("Failed to open browser: {}", e));
-            }
+    let ui = config.ui;
+    let server = ApiServer::new(config);
+    let listener = server.bind()?;
+    let address = listener.local_addr()?;
+    CliOutput::info(&tr_with("serve.listening", &[("address", &address)]));
+    if ui {
+        // Installers dropped on the UI are analyzed without a separate worker
+        let mut local = address;
+        if local.ip().is_unspecified() {
+            local.set_ip(match local {
+                std::net::SocketAddr::V4(_) => std::net::Ipv4Addr::LOCALHOST.into(),
+                std::net::SocketAddr::V6(_) => std::net::Ipv6Addr::LOCALHOST.into(),
+            });
+        }
+        CliOutput::browser_info(&tr_with(
+            "serve.ui",
+            &[("url", &format!("http://{}/", local))],
+        ));
+        let client = WorkerClient::new(&format!("http://{}", local), &default_worker_name());
+        tokio::select! {
+            served = server.serve(listener, shutdown_signal()) => served?,
+            _ = local_worker(&client) => {}
         }
     } else {
-        let report_content = report_generator
-            .generate_report(&result, report_format)
-            .await?;
-        println!("{}", report_content);
+        server.serve(listener, shutdown_signal()).await?;
     }
 
+    CliOutput::success(tr("serve.stopped"));
     Ok(())
 }
 
-/// Handle the batch command
-pub async fn handle_batch(
-    input_dir: &Path,
-    output_dir: &Path,
-    format: Option<&str>,
-    use_sandbox: bool,
-) -> Result<()> {
-    CliOutput::section_header("Batch Analysis");
-    CliOutput::folder_info("Input directory", &input_dir.display().to_string());
-    CliOutput::folder_info("Output directory", &output_dir.display().to_string());
+/// Handle the worker command
+///
+/// Jobs are claimed one at a time until the worker is stopped with Ctrl+C or
+/// SIGTERM. Hosts that cannot run the sandbox take static jobs only.
+/// A job interrupted by stopping the worker is handed to another worker once
+/// its lease expires. Errors talking to the server are reported and retried
+/// on the next poll, so workers survive server restarts.
+pub async fn handle_worker(server: &str, options: &WorkerOptions) -> Result<()> {
+    CliOutput::section_header(tr("worker.header"));
+    let client = WorkerClient::new(server, &options.name);
+    let static_only = options.static_only || !sandbox::is_supported();
+    if static_only && !options.static_only {
+        CliOutput::info(tr("worker.no_sandbox"));
+    }
+    let kinds = if static_only {
+        vec![JobKind::Static]
+    } else {
+        vec![JobKind::Static, JobKind::Sandbox]
+    };
+    let kind_names = kinds
+        .iter()
+        .map(JobKind::as_str)
+        .collect::<Vec<_>>()
+        .join("/");
+    CliOutput::info(&tr_with(
+        "worker.taking",
+        &[
+            ("worker", &client.worker()),
+            ("kinds", &kind_names),
+            ("server", &server),
+        ],
+    ));
 
-    // Create output directory if it doesn't exist
-    tokio::fs::create_dir_all(output_dir).await?;
+    let mut processed = 0;
+    loop {
+        let outcome = tokio::select! {
+            outcome = run_next_job(&client, &kinds) => outcome,
+            _ = shutdown_signal() => break,
+        };
+        match outcome {
+            Ok(true) => {
+                processed += 1;
+                continue;
+            }
+            Ok(false) => {}
+            Err(e) => CliOutput::warning(&e.to_string()),
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(options.poll_interval) => {}
+            _ = shutdown_signal() => break,
+        }
+    }
 
-    // Find all installer files first to get total count
-    let mut entries = tokio::fs::read_dir(input_dir).await?;
-    let mut installer_files = Vec::new();
+    CliOutput::success(&tr_with("worker.stopped", &[("count", &processed)]));
+    Ok(())
+}
 
-    while let Some(entry) = entries.next_entry().await? {
-        let path = entry.path();
-        if path.is_file() && is_supported_file(&path) {
-            installer_files.push(path);
+/// Process the static jobs of the server `client` talks to, until dropped
+async fn local_worker(client: &WorkerClient) {
+    loop {
+        match run_next_job(client, &[JobKind::Static]).await {
+            Ok(true) => continue,
+            Ok(false) => {}
+            Err(e) => CliOutput::warning(&e.to_string()),
         }
+        tokio::time::sleep(Duration::from_secs(1)).await;
     }
+}
 
-    if installer_files.is_empty() {
-        CliOutput::warning("No supported installer files found in the directory");
-        return Ok(());
+/// Resolve on Ctrl+C or, on Unix, SIGTERM as sent by container runtimes
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        if let Ok(mut terminate) = signal(SignalKind::terminate()) {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = terminate.recv() => {}
+            }
+            return;
+        }
     }
+    tokio::signal::ctrl_c().await.ok();
+}
 
-    CliOutput::info(&format!(
-        "Found {} installer files to process",
-        installer_files.len()
+/// Claim and process the next job, returning whether there was one
+async fn run_next_job(client: &WorkerClient, kinds: &[JobKind]) -> Result<bool> {
+    let Some(job) = client.claim(kinds).await? else {
+        return Ok(false);
+    };
+    CliOutput::info(&tr_with(
+        "worker.analyzing",
+        &[
+            ("file", &job.file_name),
+            ("kind", &job.kind),
+            ("id", &job.id),
+        ],
     ));
 
-    // Create progress bar
-    let pb = CliOutput::create_progress_bar(installer_files.len() as u64, "Processing installers");
-
-    let mut processed = 0;
-    let mut failed = 0;
-    let batch_start = Instant::now();
+    match analyze_job(client, &job).await {
+        Ok(report) => {
+            client.complete(job.id, report).await?;
+            CliOutput::success(&tr_with("worker.completed", &[("id", &job.id)]));
+        }
+        Err(e) => {
+            CliOutput::error(&tr_with("worker.failed", &[("id", &job.id), ("error", &e)]));
+            client.fail(job.id, &e.to_string()).await?;
+        }
+    }
+    Ok(true)
+}
 
-    for path in installer_files {
-        let file_name = path
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("unknown");
+/// Download the installer of a job, analyze it and return the JSON report
+async fn analyze_job(client: &WorkerClient, job: &JobInfo) -> Result<serde_json::Value> {
+    let content = client.content(job.id).await?;
+    let file_name = file_name_component(&entry_file_name(Path::new(&job.file_name)));
+    let scratch = Workspace::new().scratch_dir(WorkspaceArea::Jobs)?;
+    let input = scratch.path().join(file_name);
+    tokio::fs::write(&input, content).await?;
+
+    let result = match job.kind {
+        JobKind::Static => analyze_installer(&input, &AnalyzeOptions::default()).await?,
+        JobKind::Sandbox => run_sandbox(&input, 300, false, &SandboxOptions::default()).await?,
+    };
+    let report = ReportGenerator::new()
+        .generate_report(&result, ReportFormat::Json)
+        .await?;
+    serde_json::from_str(&report).map_err(AnalyzerError::SerializationError)
+}
 
-        let format_str = format.unwrap_or("json");
-        let output_file = output_dir.join(format!(
-            "{}_report.{}",
-            file_name,
-            get_file_extension(format_str)
+/// Handle the clean command
+pub async fn handle_clean(all: bool, older_than: Option<u64>, dry_run: bool) -> Result<()> {
+    CliOutput::section_header(tr("clean.header"));
+
+    let workspace = Workspace::new();
+    CliOutput::folder_info(
+        tr("clean.workspace"),
+        &workspace.root().display().to_string(),
+    );
+
+    let policy = CleanPolicy {
+        remove_all: all,
+        max_age: older_than.map(|hours| Duration::from_secs(hours * 60 * 60)),
+        max_total_size: None,
+        dry_run,
+    };
+    let before = workspace.entries()?;
+    let total_size: u64 = before.iter().map(|entry| entry.size).sum();
+    let report = workspace.clean(&policy)?;
+
+    for entry in &report.removed {
+        let area = entry.area.map(|area| area.dir_name()).unwrap_or("legacy");
+        CliOutput::info(&tr_with(
+            if dry_run {
+                "clean.would_remove"
+            } else {
+                "clean.removed"
+            },
+            &[
+                ("area", &area),
+                ("path", &entry.path.display()),
+                ("size", &crate::utils::format_file_size(entry.size)),
+            ],
         ));
+    }
+    for (path, error) in &report.failed {
+        CliOutput::warning(&tr_with(
+            "clean.remove_failed",
+            &[("path", &path.display()), ("error", error)],
+        ));
+    }
 
-        pb.set_message(format!("Processing: {}", file_name));
-
-        let result = if use_sandbox {
-            handle_sandbox(&path, Some(&output_file), format, 300, false, false).await
+    let summary = tr_with(
+        if dry_run {
+            "clean.would_remove_summary"
         } else {
-            handle_analyze(&path, Some(&output_file), format, false).await
-        };
+            "clean.removed_summary"
+        },
+        &[
+            ("count", &report.removed.len()),
+            ("size", &crate::utils::format_file_size(report.freed_bytes)),
+            ("kept", &(before.len() - report.removed.len())),
+            (
+                "kept_size",
+                &crate::utils::format_file_size(total_size - report.freed_bytes),
+            ),
+        ],
+    );
+    if report.failed.is_empty() {
+        CliOutput::success(&summary);
+        Ok(())
+    } else {
+        CliOutput::warning(&summary);
+        Err(AnalyzerError::generic(format!(
+            "{} workspace entries could not be removed",
+            report.failed.len()
+        )))
+    }
+}
 
-        match result {
-            Ok(_) => {
-                processed += 1;
-                pb.println(format!("✓ Completed: {}", path.display()));
-            }
-            Err(e) => {
-                failed += 1;
-                pb.println(format!("✗ Failed: {} - {}", path.display(), e));
-            }
+/// Handle the update command for data packs
+///
+/// Packs newer than the installed ones, or the installed versions again with
+/// `force`, are downloaded and installed once their digest and signed
+/// manifest check out. Older versions are never installed.
+pub async fn handle_update_data(
+    url: &str,
+    key_path: &Path,
+    check_only: bool,
+    force: bool,
+) -> Result<()> {
+    CliOutput::section_header(tr("data_update.header"));
+    let key = audit::load_verifying_key(key_path).await?;
+    let client = DataPackClient::new(url, UpdateConfig::default().timeout_seconds);
+    let store = DataPackStore::new();
+
+    let spinner = CliOutput::create_spinner(tr("data_update.checking"));
+    let index = client.fetch_index().await;
+    spinner.finish_and_clear();
+    let index = index?;
+
+    let mut installed = 0;
+    for entry in &index.packs {
+        let Some(kind) = DataPackKind::parse(&entry.name) else {
+            CliOutput::warning(&tr_with("data_update.unknown", &[("name", &entry.name)]));
+            continue;
+        };
+        if !entry.is_compatible()? {
+            CliOutput::warning(&tr_with(
+                "data_update.incompatible",
+                &[
+                    ("name", &entry.name),
+                    ("version", &entry.version),
+                    (
+                        "required",
+                        &entry.min_analyzer_version.as_deref().unwrap_or_default(),
+                    ),
+                ],
+            ));
+            continue;
+        }
+        let current = store.installed_version(kind)?;
+        let current_name = current
+            .map(|version| version.to_string())
+            .unwrap_or_else(|| tr("data_update.builtin").to_string());
+        if current.is_some_and(|version| version > entry.version) {
+            CliOutput::warning(&tr_with(
+                "data_update.older",
+                &[
+                    ("name", &entry.name),
+                    ("version", &entry.version),
+                    ("current", &current_name),
+                ],
+            ));
+            continue;
+        }
+        if current == Some(entry.version) && !force {
+            CliOutput::info(&tr_with(
+                "data_update.up_to_date",
+                &[("name", &entry.name), ("version", &current_name)],
+            ));
+            continue;
+        }
+        CliOutput::info(&tr_with(
+            "data_update.available",
+            &[
+                ("name", &entry.name),
+                ("current", &current_name),
+                ("latest", &entry.version),
+            ],
+        ));
+        if check_only {
+            continue;
         }
 
-        pb.inc(1);
+        let content = client.download(entry).await?;
+        entry.verify(kind, &content, &key)?;
+        store.install(kind, entry, &content)?;
+        installed += 1;
+        CliOutput::success(&tr_with(
+            "data_update.installed",
+            &[("name", &entry.name), ("version", &entry.version)],
+        ));
     }
 
-    CliOutput::finish_progress_success(&pb, "Batch processing complete");
-
-    let total_duration = batch_start.elapsed();
-    CliOutput::batch_summary(processed, failed, total_duration);
-
-    Ok(())
-}
-
-/// Handle the info command
-pub async fn handle_info() -> Result<()> {
-    println!("Installer Analyzer - Supported Formats");
-    println!("=====================================");
-    println!();
-    println!("Static Analysis (Fully Supported):");
-    println!("  ✅ MSI (Microsoft Installer) - .msi files");
-    println!("     • Metadata extraction (product, version, manufacturer)");
-    println!("     • File list with sizes and attributes");
-    println!("     • Registry operations analysis");
-    println!();
-    println!("  ✅ NSIS (Nullsoft Scriptable Install System) - .exe files");
-    println!("     • Format detection via signature patterns");
-    println!("     • Basic metadata extraction");
-    println!("     • File structure analysis");
-    println!();
-    println!("  ✅ InnoSetup - .exe files");
-    println!("     • Format detection via signature patterns");
-    println!("     • Basic metadata extraction");
-    println!("     • File structure analysis");
-    println!();
-    println!("Planned Support:");
-    println!("  🔄 Python Wheel - .whl files");
-    println!("     • ZIP-based archive extraction");
-    println!("     • METADATA file parsing");
-    println!("     • Dependency analysis");
-    println!();
-    println!("  🔄 7zip Archive Support - various formats");
-    println!("     • Universal archive extraction fallback");
-    println!("     • Support for .7z, .rar, .tar.gz, etc.");
-    println!();
-    println!("Dynamic Analysis (Sandbox):");
-    println!("  ✅ File system monitoring");
-    println!("  ✅ Registry monitoring");
-    println!("  🔄 Process monitoring (planned)");
-    println!("  🔄 Network monitoring (planned)");
-    println!();
-    println!("Report Formats:");
-    println!("  • JSON - Machine-readable structured data");
-    println!("  • HTML - Human-readable web format with file tree view");
-    println!("  • Markdown - Documentation-friendly format");
-    println!();
-    println!("Usage Examples:");
-    println!("  installer-analyzer analyze app.msi --format html");
-    println!("  installer-analyzer analyze setup.exe --format json");
-    println!("  installer-analyzer sandbox installer.exe --timeout 300");
-    println!("  installer-analyzer batch ./installers/ ./reports/ --format html");
-
+    if installed > 0 {
+        CliOutput::folder_info(
+            tr("data_update.location"),
+            &store.dir().display().to_string(),
+        );
+    }
     Ok(())
 }
 
 /// Handle the update command
-pub async fn handle_update(check_only: bool, force: bool, yes: bool) -> Result<()> {
-    CliOutput::section_header("Auto-Update");
+pub async fn handle_update(
+    config: UpdateConfig,
+    check_only: bool,
+    force: bool,
+    #[cfg_attr(not(windows), allow(unused_variables))] yes: bool,
+) -> Result<()> {
+    CliOutput::section_header(tr("update.header"));
 
-    // Create updater with default configuration
-    let updater = Updater::new();
+    let updater = Updater::with_config(config);
 
     // Create progress spinner for update check
-    let spinner = CliOutput::create_spinner("Checking for updates...");
+    let spinner = CliOutput::create_spinner(tr("update.checking"));
 
     // Check for available updates
     let update_info = match updater.check_for_updates().await {
         Ok(info) => {
-            spinner.finish_with_message("✓ Update check completed");
+            spinner.finish_with_message(tr("update.check_completed"));
             info
         }
         Err(e) => {
-            spinner.finish_with_message("✗ Update check failed");
+            spinner.finish_with_message(tr("update.check_failed"));
             return Err(AnalyzerError::generic(format!(
                 "Failed to check for updates: {}",
                 e
@@ -319,26 +2671,32 @@ pub async fn handle_update(check_only: bool, force: bool, yes: bool) -> Result<(
     };
 
     // Display current and latest version information
-    CliOutput::info(&format!("Current version: {}", update_info.current_version));
-    CliOutput::info(&format!("Latest version: {}", update_info.latest_version));
+    CliOutput::info(&tr_with(
+        "update.current_version",
+        &[("version", &update_info.current_version)],
+    ));
+    CliOutput::info(&tr_with(
+        "update.latest_version",
+        &[("version", &update_info.latest_version)],
+    ));
 
     if !update_info.update_available && !force {
-        CliOutput::success("You are running the latest version!");
+        CliOutput::success(tr("update.up_to_date"));
         return Ok(());
     }
 
     if force && !update_info.update_available {
-        CliOutput::warning("Forcing update even though current version is up to date");
+        CliOutput::warning(tr("update.forcing"));
     }
 
     // Display update information
     if update_info.update_available {
-        CliOutput::info("🎉 A new version is available!");
+        CliOutput::info(tr("update.available"));
 
         if let Some(release_notes) = &update_info.release_notes {
             if !release_notes.trim().is_empty() {
-                CliOutput::info("Release notes:");
-                println!("{}", release_notes.trim());
+                CliOutput::info(tr("update.release_notes"));
+                println!("{}", terminal_safe(release_notes.trim()));
                 println!();
             }
         }
@@ -346,57 +2704,75 @@ pub async fn handle_update(check_only: bool, force: bool, yes: bool) -> Result<(
         // Show file size if available
         if update_info.file_size > 0 {
             let size_mb = update_info.file_size as f64 / 1024.0 / 1024.0;
-            CliOutput::info(&format!("Download size: {:.1} MB", size_mb));
+            CliOutput::info(&tr_with(
+                "update.download_size",
+                &[("size", &format!("{:.1}", size_mb))],
+            ));
+        }
+
+        if update_info.file_hash.is_none() {
+            CliOutput::warning(tr("update.unverified"));
         }
     }
 
     // If check-only mode, exit here
     if check_only {
         if update_info.update_available {
-            CliOutput::info("Run 'installer-analyzer update' to install the update");
+            CliOutput::info(tr("update.run_update"));
         }
         return Ok(());
     }
 
-    // Check if we can perform self-update
-    #[cfg(windows)]
+    #[cfg(not(windows))]
     {
-        use crate::updater::windows::{can_self_update, get_update_strategy};
-
-        let strategy = get_update_strategy();
-        CliOutput::info(&format!("Update strategy: {}", strategy));
+        CliOutput::warning(tr("update.windows_only"));
+        CliOutput::info(tr("update.download_manually"));
+        Ok(())
+    }
 
-        if !can_self_update() {
-            CliOutput::warning("Cannot write to the current executable location.");
-            CliOutput::warning("You may need to run as administrator or move the executable to a writable location.");
+    #[cfg(windows)]
+    install_update(&updater, &update_info, yes).await
+}
 
-            if !yes {
-                CliOutput::info("Continue anyway? The update may fail. (y/N)");
-                let mut input = String::new();
-                std::io::stdin()
-                    .read_line(&mut input)
-                    .map_err(|e| AnalyzerError::generic(format!("Failed to read input: {}", e)))?;
+/// Confirm and install an update in place of the running executable
+#[cfg(windows)]
+async fn install_update(
+    updater: &Updater,
+    update_info: &crate::updater::UpdateInfo,
+    yes: bool,
+) -> Result<()> {
+    use crate::updater::windows::{can_self_update, get_update_strategy};
 
-                if !input.trim().to_lowercase().starts_with('y') {
-                    CliOutput::info("Update cancelled");
-                    return Ok(());
-                }
+    // Check if we can perform self-update
+    let strategy = get_update_strategy();
+    CliOutput::info(&tr_with("update.strategy", &[("strategy", &strategy)]));
+
+    if !can_self_update() {
+        CliOutput::warning(tr("update.not_writable"));
+        CliOutput::warning(tr("update.run_as_admin"));
+
+        if !yes {
+            CliOutput::info(tr("update.continue_anyway"));
+            let mut input = String::new();
+            std::io::stdin()
+                .read_line(&mut input)
+                .map_err(|e| AnalyzerError::generic(format!("Failed to read input: {}", e)))?;
+
+            if !input.trim().to_lowercase().starts_with('y') {
+                CliOutput::info(tr("update.cancelled"));
+                return Ok(());
             }
         }
     }
 
-    #[cfg(not(windows))]
-    {
-        CliOutput::warning("Self-update is currently only supported on Windows");
-        CliOutput::info("Please download the latest version manually from the releases page");
-        return Ok(());
-    }
-
     // Confirm update installation
     if !yes {
-        CliOutput::info(&format!(
-            "Install update {} → {}? (y/N)",
-            update_info.current_version, update_info.latest_version
+        CliOutput::info(&tr_with(
+            "update.confirm",
+            &[
+                ("current", &update_info.current_version),
+                ("latest", &update_info.latest_version),
+            ],
         ));
 
         let mut input = String::new();
@@ -405,25 +2781,25 @@ pub async fn handle_update(check_only: bool, force: bool, yes: bool) -> Result<(
             .map_err(|e| AnalyzerError::generic(format!("Failed to read input: {}", e)))?;
 
         if !input.trim().to_lowercase().starts_with('y') {
-            CliOutput::info("Update cancelled");
+            CliOutput::info(tr("update.cancelled"));
             return Ok(());
         }
     }
 
     // Perform the update
-    CliOutput::info("Starting update process...");
-    CliOutput::warning("The application will restart after the update");
+    CliOutput::info(tr("update.starting"));
+    CliOutput::warning(tr("update.restart"));
 
-    let update_spinner = CliOutput::create_spinner("Downloading update...");
+    let update_spinner = CliOutput::create_spinner(tr("update.downloading"));
 
-    match updater.perform_update(&update_info).await {
+    match updater.perform_update(update_info).await {
         Ok(_) => {
             // This should not be reached as perform_update exits the process
-            update_spinner.finish_with_message("✓ Update completed");
-            CliOutput::success("Update installed successfully!");
+            update_spinner.finish_with_message(tr("update.completed"));
+            CliOutput::success(tr("update.installed"));
         }
         Err(e) => {
-            update_spinner.finish_with_message("✗ Update failed");
+            update_spinner.finish_with_message(tr("update.failed"));
             return Err(AnalyzerError::generic(format!("Update failed: {}", e)));
         }
     }
@@ -431,6 +2807,59 @@ pub async fn handle_update(check_only: bool, force: bool, yes: bool) -> Result<(
     Ok(())
 }
 
+/// Handle update --rollback
+pub async fn handle_rollback(yes: bool) -> Result<()> {
+    CliOutput::section_header(tr("rollback.header"));
+
+    let store = BackupStore::new();
+    let Some(backup) = store.latest()? else {
+        return Err(AnalyzerError::generic(
+            "No backup of an earlier version is available to roll back to",
+        ));
+    };
+
+    CliOutput::info(&tr_with(
+        "update.current_version",
+        &[("version", &env!("CARGO_PKG_VERSION"))],
+    ));
+    CliOutput::info(&tr_with(
+        "rollback.backup",
+        &[
+            ("version", &backup.version),
+            (
+                "created",
+                &backup.created_at.format("%Y-%m-%d %H:%M:%S UTC"),
+            ),
+        ],
+    ));
+
+    if !yes {
+        CliOutput::info(&tr_with(
+            "rollback.confirm",
+            &[("version", &backup.version)],
+        ));
+
+        let mut input = String::new();
+        std::io::stdin()
+            .read_line(&mut input)
+            .map_err(|e| AnalyzerError::generic(format!("Failed to read input: {}", e)))?;
+
+        if !input.trim().to_lowercase().starts_with('y') {
+            CliOutput::info(tr("rollback.cancelled"));
+            return Ok(());
+        }
+    }
+
+    let restored = Updater::new()
+        .rollback()
+        .map_err(|e| AnalyzerError::generic(format!("Rollback failed: {}", e)))?;
+    CliOutput::success(&tr_with(
+        "rollback.restored",
+        &[("version", &restored.version)],
+    ));
+    Ok(())
+}
+
 /// Parse format string to ReportFormat enum
 fn parse_format(format: &str) -> Result<ReportFormat> {
     match format.to_lowercase().as_str() {
@@ -512,6 +2941,29 @@ fn format_to_string(format: &ReportFormat) -> &'static str {
     }
 }
 
+/// Get file extension for a parsed report format
+fn report_extension(format: &ReportFormat) -> &'static str {
+    match format {
+        ReportFormat::Json => "json",
+        ReportFormat::Html => "html",
+        ReportFormat::Markdown => "md",
+    }
+}
+
+/// Get the directory containing a report file
+fn parent_dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
+
+/// Get the file stem of an input, used to name per-input outputs
+fn file_stem(path: &Path) -> String {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    file_name_component(&stem)
+}
+
 /// Get file extension for report format
 fn get_file_extension(format: &str) -> &str {
     match format.to_lowercase().as_str() {
@@ -530,7 +2982,9 @@ fn is_supported_file(path: &Path) -> bool {
             "msi" => true, // MSI files
             "exe" => true, // NSIS and InnoSetup files
             // Planned support
-            "whl" => true, // Python Wheel files
+            "whl" => true,         // Python Wheel files
+            "iso" | "wim" => true, // Product media images
+            "inf" | "cab" => true, // Driver packages
             // Other potential formats
             "7z" | "rar" | "zip" | "tar" | "gz" | "bz2" => false, // Not yet supported
             _ => false,
@@ -548,15 +3002,16 @@ fn open_browser_to_file(file_path: &Path) -> Result<()> {
 
     // Convert Windows path to proper file:// URL format
     let url = if cfg!(windows) {
-        // On Windows, convert backslashes to forward slashes and use file:/// format
-        let mut path_str = absolute_path.to_string_lossy().replace('\\', "/");
-
-        // Remove Windows UNC prefix if present (\\?\)
-        if path_str.starts_with("//?/") {
-            path_str = path_str[4..].to_string();
+        // Drop the extended-length prefix canonicalize adds, then use forward slashes
+        let path_str = without_prefix(&absolute_path)
+            .to_string_lossy()
+            .replace('\\', "/");
+
+        // Network shares become file://server/share/..., local files file:///C:/...
+        match path_str.strip_prefix("//") {
+            Some(unc) => format!("file://{}", unc),
+            None => format!("file:///{}", path_str),
         }
-
-        format!("file:///{}", path_str)
     } else {
         // On Unix-like systems, use file:// format
         format!("file://{}", absolute_path.display())
@@ -565,7 +3020,7 @@ fn open_browser_to_file(file_path: &Path) -> Result<()> {
     open::that(&url)
         .map_err(|e| AnalyzerError::config_error(format!("Failed to open browser: {}", e)))?;
 
-    CliOutput::success(&format!("Report opened in browser: {}", url));
+    CliOutput::success(&tr_with("common.report_opened", &[("url", &url)]));
     Ok(())
 }
 
@@ -772,4 +3227,33 @@ mod tests {
             assert!(expected_url.starts_with("file://"));
         }
     }
+
+    #[tokio::test]
+    async fn test_quarantine_failed_input() {
+        let interrupted =
+            AnalyzerError::from(std::io::Error::from(std::io::ErrorKind::Interrupted));
+        assert!(interrupted.is_transient());
+        assert!(!AnalyzerError::invalid_format("truncated").is_transient());
+
+        let temp_dir = TempDir::new().unwrap();
+        let input = temp_dir.path().join("broken.msi");
+        std::fs::write(&input, b"not an msi").unwrap();
+        let output_dir = temp_dir.path().join("reports");
+
+        let copy = quarantine_input(
+            &input,
+            &AnalyzerError::invalid_format("truncated"),
+            3,
+            &output_dir,
+        )
+        .await
+        .unwrap();
+        assert_eq!(copy, output_dir.join("failed").join("broken.msi"));
+        assert!(input.exists());
+        let reason =
+            std::fs::read_to_string(output_dir.join("failed").join("broken.msi.reason.txt"))
+                .unwrap();
+        assert!(reason.contains("Attempts: 3"));
+        assert!(reason.contains("Invalid file format: truncated"));
+    }
 }
@@ -0,0 +1,380 @@
+//! Control pipe for frontends embedding the CLI
+//!
+//! With `--control-pipe <NAME>` the CLI connects to a pipe the frontend
+//! listens on: the named pipe `\\.\pipe\NAME` on Windows, the Unix socket at
+//! path `NAME` elsewhere. Events are written to it as JSON lines:
+//!
+//! ```json
+//! {"event":"started","version":"0.7.0","pid":4242,"args":["installer-analyzer","analyze","setup.exe"]}
+//! {"event":"stage","stage":"extracting","number":2,"total":5}
+//! {"event":"progress","unit":"bytes","done":8388608,"total":81815392}
+//! {"event":"log","level":"warning","message":"No declared dependencies found for this installer"}
+//! {"event":"completed","status":"succeeded","exit_code":0,"error":null}
+//! ```
+//!
+//! The frontend may write `{"command":"cancel"}` lines to stop the running
+//! command, which then completes with status `cancelled` and exit code 130.
+//! Log messages are in the console language; stage and unit names are not
+//! localized.
+
+use crate::core::{AnalyzerError, Result};
+use crate::reporting::audit::redact_arguments;
+use crate::utils::progress::{ProgressSink, Stage, Unit};
+use serde::{Deserialize, Serialize};
+use std::sync::{Mutex, OnceLock, PoisonError};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+
+/// Minimum time between two progress events of the same counted work
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Time to wait for the frontend to take the last events
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(2);
+
+static CHANNEL: OnceLock<ControlChannel> = OnceLock::new();
+
+/// Event written to the control pipe
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ControlEvent {
+    Started {
+        version: &'static str,
+        pid: u32,
+        /// Command line, with passwords redacted
+        args: Vec<String>,
+    },
+    Stage {
+        stage: &'static str,
+        number: usize,
+        total: usize,
+    },
+    Progress {
+        unit: &'static str,
+        done: u64,
+        total: u64,
+    },
+    Log {
+        level: LogLevel,
+        message: String,
+    },
+    Completed {
+        status: Completion,
+        exit_code: i32,
+        error: Option<String>,
+    },
+}
+
+/// Level of a console message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+/// Outcome of the command
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Completion {
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+/// Command read from the control pipe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum ControlCommand {
+    Cancel,
+}
+
+enum Outgoing {
+    Line(String),
+    /// Flush and close the pipe
+    Close,
+}
+
+/// Connection to the frontend
+pub struct ControlChannel {
+    outgoing: mpsc::UnboundedSender<Outgoing>,
+    cancelled: watch::Receiver<bool>,
+    writer: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl ControlChannel {
+    /// Connect to the pipe `name` and make it the channel of this process
+    pub async fn connect(name: &str) -> Result<&'static Self> {
+        let (reader, writer) = tokio::io::split(open(name).await?);
+        let channel = Self::start(reader, writer);
+        CHANNEL
+            .set(channel)
+            .map_err(|_| AnalyzerError::config_error("control pipe is already connected"))?;
+        Ok(CHANNEL.get().expect("channel was just set"))
+    }
+
+    fn start(
+        reader: impl AsyncRead + Send + Unpin + 'static,
+        mut writer: impl AsyncWrite + Send + Unpin + 'static,
+    ) -> Self {
+        let (outgoing, mut lines) = mpsc::unbounded_channel();
+        let writer = tokio::spawn(async move {
+            while let Some(message) = lines.recv().await {
+                let written = match message {
+                    Outgoing::Line(line) => writer.write_all(line.as_bytes()).await,
+                    Outgoing::Close => {
+                        let _ = writer.flush().await;
+                        let _ = writer.shutdown().await;
+                        return;
+                    }
+                };
+                if let Err(e) = written {
+                    tracing::debug!("Control pipe closed: {}", e);
+                    return;
+                }
+            }
+        });
+
+        let (cancel, cancelled) = watch::channel(false);
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(reader).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                match serde_json::from_str::<ControlCommand>(&line) {
+                    Ok(ControlCommand::Cancel) => {
+                        let _ = cancel.send(true);
+                    }
+                    Err(e) => tracing::debug!("Ignoring control command {:?}: {}", line, e),
+                }
+            }
+        });
+
+        Self {
+            outgoing,
+            cancelled,
+            writer: Mutex::new(Some(writer)),
+        }
+    }
+
+    /// Write an event, dropping it if the frontend has gone away
+    pub fn send(&self, event: &ControlEvent) {
+        match serde_json::to_string(event) {
+            Ok(line) => {
+                let _ = self.outgoing.send(Outgoing::Line(line + "\n"));
+            }
+            Err(e) => tracing::debug!("Failed to serialize control event: {}", e),
+        }
+    }
+
+    /// Resolves once the frontend asked to cancel
+    pub async fn cancelled(&self) {
+        let mut cancelled = self.cancelled.clone();
+        if cancelled.wait_for(|cancelled| *cancelled).await.is_err() {
+            // The frontend closed its end without cancelling
+            std::future::pending::<()>().await;
+        }
+    }
+
+    /// Run `command` until it finishes or the frontend cancels it
+    pub async fn run<T>(&self, command: impl std::future::Future<Output = Result<T>>) -> Result<T> {
+        tokio::select! {
+            result = command => result,
+            _ = self.cancelled() => Err(AnalyzerError::Cancelled),
+        }
+    }
+
+    /// Report the outcome of the command and close the pipe
+    pub async fn complete<T>(&self, result: &Result<T>) {
+        let (status, exit_code, error) = match result {
+            Ok(_) => (Completion::Succeeded, 0, None),
+            Err(AnalyzerError::Cancelled) => (Completion::Cancelled, 130, None),
+            Err(e) => (Completion::Failed, e.exit_code(), Some(e.to_string())),
+        };
+        self.send(&ControlEvent::Completed {
+            status,
+            exit_code,
+            error,
+        });
+        let _ = self.outgoing.send(Outgoing::Close);
+        let writer = self
+            .writer
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        if let Some(writer) = writer {
+            let _ = tokio::time::timeout(DRAIN_TIMEOUT, writer).await;
+        }
+    }
+}
+
+/// Channel of this process, if `--control-pipe` was given
+pub fn channel() -> Option<&'static ControlChannel> {
+    CHANNEL.get()
+}
+
+/// Report the start of the command
+pub fn started() {
+    if let Some(channel) = channel() {
+        channel.send(&ControlEvent::Started {
+            version: env!("CARGO_PKG_VERSION"),
+            pid: std::process::id(),
+            args: redact_arguments(std::env::args()),
+        });
+    }
+}
+
+/// Forward a console message
+pub fn log(level: LogLevel, message: &str) {
+    if let Some(channel) = channel() {
+        channel.send(&ControlEvent::Log {
+            level,
+            message: message.to_string(),
+        });
+    }
+}
+
+/// Progress sink writing stage and progress events, at most one per
+/// [`PROGRESS_INTERVAL`] for counted work
+#[derive(Default)]
+pub struct ControlProgress {
+    counted: Mutex<Option<Counted>>,
+}
+
+struct Counted {
+    unit: Unit,
+    done: u64,
+    total: u64,
+    sent_at: Option<Instant>,
+}
+
+impl ProgressSink for ControlProgress {
+    fn stage(&self, stage: Stage) {
+        *self.counted.lock().unwrap_or_else(PoisonError::into_inner) = None;
+        if let Some(channel) = channel() {
+            channel.send(&ControlEvent::Stage {
+                stage: stage.name(),
+                number: stage.number(),
+                total: Stage::ALL.len(),
+            });
+        }
+    }
+
+    fn begin(&self, unit: Unit, total: u64) {
+        *self.counted.lock().unwrap_or_else(PoisonError::into_inner) = Some(Counted {
+            unit,
+            done: 0,
+            total,
+            sent_at: None,
+        });
+    }
+
+    fn advance(&self, delta: u64) {
+        let mut counted = self.counted.lock().unwrap_or_else(PoisonError::into_inner);
+        let Some(counted) = counted.as_mut() else {
+            return;
+        };
+        counted.done += delta;
+        let finished = counted.done >= counted.total;
+        let due = counted
+            .sent_at
+            .is_none_or(|sent_at| sent_at.elapsed() >= PROGRESS_INTERVAL);
+        if !(finished || due) {
+            return;
+        }
+        counted.sent_at = Some(Instant::now());
+        if let Some(channel) = channel() {
+            channel.send(&ControlEvent::Progress {
+                unit: counted.unit.name(),
+                done: counted.done,
+                total: counted.total,
+            });
+        }
+    }
+}
+
+/// Connect to the frontend's end of the pipe
+#[cfg(windows)]
+async fn open(name: &str) -> Result<tokio::net::windows::named_pipe::NamedPipeClient> {
+    use tokio::net::windows::named_pipe::ClientOptions;
+
+    let path = if name.starts_with(r"\\") {
+        name.to_string()
+    } else {
+        format!(r"\\.\pipe\{}", name)
+    };
+    ClientOptions::new().open(&path).map_err(|e| {
+        AnalyzerError::config_error(format!("Cannot connect to control pipe {}: {}", path, e))
+    })
+}
+
+/// Connect to the frontend's end of the pipe
+#[cfg(unix)]
+async fn open(name: &str) -> Result<tokio::net::UnixStream> {
+    tokio::net::UnixStream::connect(name).await.map_err(|e| {
+        AnalyzerError::config_error(format!("Cannot connect to control pipe {}: {}", name, e))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_lines() {
+        let event = ControlEvent::Stage {
+            stage: Stage::Hashing.name(),
+            number: Stage::Hashing.number(),
+            total: Stage::ALL.len(),
+        };
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"stage","stage":"hashing","number":4,"total":5}"#
+        );
+        let event = ControlEvent::Completed {
+            status: Completion::Failed,
+            exit_code: 2,
+            error: Some("Policy check failed".to_string()),
+        };
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"completed","status":"failed","exit_code":2,"error":"Policy check failed"}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<ControlCommand>(r#"{"command":"cancel"}"#).unwrap(),
+            ControlCommand::Cancel
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cancel_and_complete() {
+        let (frontend, cli) = tokio::io::duplex(4096);
+        let (reader, writer) = tokio::io::split(cli);
+        let channel = ControlChannel::start(reader, writer);
+        let (frontend_reader, mut frontend_writer) = tokio::io::split(frontend);
+
+        channel.send(&ControlEvent::Log {
+            level: LogLevel::Warning,
+            message: "careful".to_string(),
+        });
+        frontend_writer
+            .write_all(b"{\"command\":\"pause\"}\n{\"command\":\"cancel\"}\n")
+            .await
+            .unwrap();
+        let result: Result<()> = channel.run(std::future::pending()).await;
+        assert!(matches!(result, Err(AnalyzerError::Cancelled)));
+        channel.complete(&result).await;
+
+        let mut lines = BufReader::new(frontend_reader).lines();
+        assert_eq!(
+            lines.next_line().await.unwrap().unwrap(),
+            r#"{"event":"log","level":"warning","message":"careful"}"#
+        );
+        assert_eq!(
+            lines.next_line().await.unwrap().unwrap(),
+            r#"{"event":"completed","status":"cancelled","exit_code":130,"error":null}"#
+        );
+        assert_eq!(lines.next_line().await.unwrap(), None);
+    }
+}
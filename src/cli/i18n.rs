@@ -0,0 +1,229 @@
+//! Localized console messages
+//!
+//! Messages printed through [`CliOutput`](super::output::CliOutput) are kept
+//! in per-locale TOML catalogs in `locales/` rather than in the code, keyed
+//! `section.name` with `{name}` placeholders. The locale is chosen with
+//! `--lang`, or else from the environment (`LC_ALL`, `LC_MESSAGES`, `LANG`,
+//! and the user locale on Windows). Messages missing from a catalog fall
+//! back to English. Reports are not localized.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::OnceLock;
+
+/// English catalog, see `locales/en.toml`
+const EN: &str = include_str!("locales/en.toml");
+/// Simplified Chinese catalog, see `locales/zh-CN.toml`
+const ZH_CN: &str = include_str!("locales/zh-CN.toml");
+
+/// Locale of console messages for this process
+static LOCALE: AtomicU8 = AtomicU8::new(Locale::En as u8);
+
+/// Language of console messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Locale {
+    En,
+    /// Simplified Chinese, also used for other Chinese locales
+    ZhCn,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 2] = [Self::En, Self::ZhCn];
+
+    /// Locale tag, as accepted by `--lang`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::En => "en",
+            Self::ZhCn => "zh-CN",
+        }
+    }
+
+    /// Locale of the environment, English if unset or not translated
+    pub fn detect() -> Self {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .into_iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .or_else(system_locale)
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(Self::En)
+    }
+
+    fn source(&self) -> &'static str {
+        match self {
+            Self::En => EN,
+            Self::ZhCn => ZH_CN,
+        }
+    }
+
+    /// Messages of this locale by key
+    fn catalog(&self) -> &'static HashMap<String, String> {
+        static CATALOGS: [OnceLock<HashMap<String, String>>; 2] =
+            [OnceLock::new(), OnceLock::new()];
+        CATALOGS[*self as usize].get_or_init(|| {
+            let table: toml::Table =
+                toml::from_str(self.source()).expect("message catalog is valid");
+            let mut messages = HashMap::new();
+            for (section, entries) in table {
+                let entries = entries.as_table().expect("catalog sections are tables");
+                for (name, message) in entries {
+                    let message = message.as_str().expect("catalog messages are strings");
+                    messages.insert(format!("{}.{}", section, name), message.to_string());
+                }
+            }
+            messages
+        })
+    }
+}
+
+impl FromStr for Locale {
+    type Err = String;
+
+    /// Parse a locale tag such as `zh-CN`, `zh_CN.UTF-8` or `en_US`
+    fn from_str(tag: &str) -> std::result::Result<Self, Self::Err> {
+        let language = tag
+            .trim()
+            .split(['-', '_', '.', '@'])
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        match language.as_str() {
+            "en" | "c" | "posix" => Ok(Self::En),
+            "zh" => Ok(Self::ZhCn),
+            _ => {
+                let names: Vec<&str> = Self::ALL.iter().map(Locale::name).collect();
+                Err(format!(
+                    "unsupported locale '{}', expected one of {}",
+                    tag.trim(),
+                    names.join(", ")
+                ))
+            }
+        }
+    }
+}
+
+/// Use `locale` for all further console messages
+pub fn set_locale(locale: Locale) {
+    LOCALE.store(locale as u8, Ordering::Relaxed);
+}
+
+/// Locale of console messages
+pub fn locale() -> Locale {
+    match LOCALE.load(Ordering::Relaxed) {
+        value if value == Locale::ZhCn as u8 => Locale::ZhCn,
+        _ => Locale::En,
+    }
+}
+
+/// Message `key` in the current locale
+///
+/// Falls back to English, and to the key itself for unknown keys.
+pub fn tr(key: &'static str) -> &'static str {
+    message(locale(), key)
+}
+
+/// Message `key` in the current locale with its `{name}` placeholders filled in
+pub fn tr_with(key: &'static str, args: &[(&str, &dyn Display)]) -> String {
+    fill(message(locale(), key), args)
+}
+
+fn message(locale: Locale, key: &'static str) -> &'static str {
+    locale
+        .catalog()
+        .get(key)
+        .or_else(|| Locale::En.catalog().get(key))
+        .map(String::as_str)
+        .unwrap_or(key)
+}
+
+fn fill(message: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut filled = message.to_string();
+    for (name, value) in args {
+        filled = filled.replace(&format!("{{{}}}", name), &value.to_string());
+    }
+    filled
+}
+
+/// Locale name of the Windows user, such as `zh-CN`
+#[cfg(windows)]
+fn system_locale() -> Option<String> {
+    use windows::Win32::Globalization::GetUserDefaultLocaleName;
+
+    // LOCALE_NAME_MAX_LENGTH
+    let mut buffer = [0u16; 85];
+    let len = unsafe { GetUserDefaultLocaleName(&mut buffer) };
+    // The length includes the terminating null
+    (len > 1).then(|| String::from_utf16_lossy(&buffer[..len as usize - 1]))
+}
+
+#[cfg(not(windows))]
+fn system_locale() -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    fn placeholders(message: &str) -> BTreeSet<&str> {
+        message
+            .split('{')
+            .skip(1)
+            .filter_map(|rest| rest.split_once('}').map(|(name, _)| name))
+            .collect()
+    }
+
+    #[test]
+    fn test_catalogs_match() {
+        let english = Locale::En.catalog();
+        assert!(english.contains_key("summary.analysis_complete"));
+        for locale in Locale::ALL {
+            let catalog = locale.catalog();
+            for (key, message) in english {
+                let translated = catalog
+                    .get(key)
+                    .unwrap_or_else(|| panic!("{} lacks {}", locale.name(), key));
+                assert_eq!(
+                    placeholders(translated),
+                    placeholders(message),
+                    "placeholders of {} in {}",
+                    key,
+                    locale.name()
+                );
+            }
+            for key in catalog.keys() {
+                assert!(english.contains_key(key), "{} is not in en", key);
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_locale() {
+        assert_eq!("zh-CN".parse::<Locale>(), Ok(Locale::ZhCn));
+        assert_eq!("zh_CN.UTF-8".parse::<Locale>(), Ok(Locale::ZhCn));
+        assert_eq!("zh-TW".parse::<Locale>(), Ok(Locale::ZhCn));
+        assert_eq!("en_US.UTF-8".parse::<Locale>(), Ok(Locale::En));
+        assert_eq!("C".parse::<Locale>(), Ok(Locale::En));
+        assert!("de-DE".parse::<Locale>().is_err());
+        for locale in Locale::ALL {
+            assert_eq!(locale.name().parse::<Locale>(), Ok(locale));
+        }
+    }
+
+    #[test]
+    fn test_messages() {
+        let args: [(&str, &dyn Display); 1] = [("count", &3)];
+        assert_eq!(
+            fill(message(Locale::En, "multi.analyzing"), &args),
+            "Analyzing 3 installers"
+        );
+        assert_eq!(
+            fill(message(Locale::ZhCn, "multi.analyzing"), &args),
+            "正在分析 3 个安装包"
+        );
+        assert_eq!(message(Locale::ZhCn, "no.such_key"), "no.such_key");
+    }
+}
@@ -1,9 +1,15 @@
 //! Command line interface
 
+use crate::analyzers::common::HashAlgorithm;
+use crate::cli::i18n::Locale;
+use crate::core::AnalysisDepth;
+use crate::reporting::Redaction;
 use clap::{Parser, Subcommand};
 use std::path::PathBuf;
 
 pub mod commands;
+pub mod control;
+pub mod i18n;
 pub mod output;
 
 /// Installer Analyzer CLI
@@ -22,17 +28,57 @@ pub struct Cli {
     /// Configuration file path
     #[arg(short, long, global = true)]
     pub config: Option<PathBuf>,
+
+    /// Opt in to anonymous telemetry: detected format and failure category per installer, nothing else
+    #[arg(long, global = true, env = "INSTALLER_ANALYZER_TELEMETRY", value_parser = clap::builder::BoolishValueParser::new())]
+    pub telemetry: bool,
+
+    /// Never send telemetry, even if opted in through the environment
+    #[arg(long, global = true)]
+    pub no_telemetry: bool,
+
+    /// Endpoint receiving telemetry events
+    #[arg(
+        long,
+        global = true,
+        value_name = "URL",
+        env = "INSTALLER_ANALYZER_TELEMETRY_URL"
+    )]
+    pub telemetry_url: Option<String>,
+
+    /// Language of console messages (en, zh-CN), detected from the environment by default
+    #[arg(
+        long,
+        global = true,
+        value_name = "LOCALE",
+        env = "INSTALLER_ANALYZER_LANG"
+    )]
+    pub lang: Option<Locale>,
+
+    /// Stream progress, log and completion events as JSON lines to this named pipe (Windows) or Unix socket, and accept cancel commands from it
+    #[arg(long, global = true, value_name = "NAME")]
+    pub control_pipe: Option<String>,
 }
 
+// Parsed once per run, the size of the `Analyze` variant doesn't matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand)]
 pub enum Commands {
     /// Perform static analysis of an installer
     Analyze {
-        /// Path to the installer file
-        #[arg(short, long)]
-        input: PathBuf,
+        /// Path to the installer file (repeat or list several to analyze multiple files, `-` reads stdin)
+        #[arg(short, long, num_args = 1.., required_unless_present = "files")]
+        input: Vec<PathBuf>,
 
-        /// Output file path
+        /// Installer files to analyze, as an alternative to --input
+        #[arg(value_name = "FILE")]
+        files: Vec<PathBuf>,
+
+        /// File name to use for an installer read from stdin (the extension helps format detection)
+        #[arg(long, value_name = "NAME")]
+        input_name: Option<String>,
+
+        /// Output file path (output directory when analyzing multiple files separately, `-` for stdout)
         #[arg(short, long)]
         output: Option<PathBuf>,
 
@@ -43,6 +89,118 @@ pub enum Commands {
         /// Automatically open HTML report in browser
         #[arg(long)]
         open: bool,
+
+        /// Export the extracted file tree (with sizes and hashes) to a separate file
+        #[arg(long, value_name = "PATH")]
+        export_tree: Option<PathBuf>,
+
+        /// File tree export format (json, graphml, dot). Auto-detected from file extension if not specified.
+        #[arg(long, requires = "export_tree")]
+        tree_format: Option<String>,
+
+        /// Include speculative file entries guessed from common patterns of the installer format
+        #[arg(long)]
+        include_speculative: bool,
+
+        /// Export the declared dependency graph (DOT, or JSON for .json paths)
+        #[arg(long, value_name = "PATH")]
+        export_deps: Option<PathBuf>,
+
+        /// Export Intune/SCCM deployment metadata: command lines, detection rules, sizes and return codes (JSON, or an Intune Detection.xml-style descriptor for .xml paths)
+        #[arg(long, value_name = "PATH")]
+        export_deployment: Option<PathBuf>,
+
+        /// Combine multiple inputs into one multi-artifact report instead of one report per file
+        #[arg(long)]
+        combined: bool,
+
+        /// Directory for analysis artifacts (metadata, file tree, dependencies), written regardless of the report destination
+        #[arg(long, value_name = "DIR")]
+        artifacts_dir: Option<PathBuf>,
+
+        /// Print HTML reports even when stdout is a terminal
+        #[arg(long)]
+        force: bool,
+
+        /// Copy high-interest files (executables, scripts, drivers, payloads) into an artifacts/ folder next to the report, with an index and hashes
+        #[arg(long)]
+        attach_artifacts: bool,
+
+        /// Package the report, unified JSON and artifacts (including attached files) into a ZIP archive
+        #[arg(long, value_name = "PATH")]
+        bundle: Option<PathBuf>,
+
+        /// Encrypt the bundle with AES-256 using this password
+        #[arg(long, requires = "bundle")]
+        password: Option<String>,
+
+        /// Append the analysis to a SQLite results database
+        #[arg(long, value_name = "PATH")]
+        db: Option<PathBuf>,
+
+        /// Check results against a baseline policy (TOML or YAML); violations exit with code 2
+        #[arg(long, value_name = "PATH")]
+        policy: Option<PathBuf>,
+
+        /// Vendor knowledge base (TOML or YAML) extending the curated vendor and product table
+        #[arg(long, value_name = "PATH")]
+        vendor_kb: Option<PathBuf>,
+
+        /// Analyze archives, cabinets and executables carved from the installer and list their files
+        #[arg(long)]
+        carve_recursive: bool,
+
+        /// Extract MSI packages embedded in an EXE wrapper (WiX Burn, InstallShield, Advanced Installer) to this directory and analyze the largest in place of the wrapper
+        #[arg(long, value_name = "DIR")]
+        extract_msi: Option<PathBuf>,
+
+        /// Write the Binary and Icon table streams of an MSI package, such as custom action DLLs, to this directory
+        #[arg(long, value_name = "DIR")]
+        dump_msi_streams: Option<PathBuf>,
+
+        /// Check the Requires-Dist constraints of a wheel against an environment snapshot (pip freeze output or conda export)
+        #[arg(long, value_name = "PATH")]
+        check_env: Option<PathBuf>,
+
+        /// Extract strings from the installer and its executables and scripts, with IOC classification, into a report appendix
+        #[arg(long)]
+        extract_strings: bool,
+
+        /// Additional digests of the installer, comma-separated: blake3, sha1, md5 (SHA-256 is always computed)
+        #[arg(long, value_name = "LIST", value_delimiter = ',')]
+        hash: Vec<HashAlgorithm>,
+
+        /// Embed the data of HTML reports gzip-compressed, decompressed by the browser when opened
+        #[arg(long, conflicts_with = "external_data")]
+        compress_data: bool,
+
+        /// Write the data of HTML reports to a <report>.data.json file next to the report instead of embedding it
+        #[arg(long)]
+        external_data: bool,
+
+        /// Leave the hashes of packaged files out of JSON and HTML reports
+        #[arg(long)]
+        omit_file_hashes: bool,
+
+        /// Stages to run: quick (detection and metadata), standard (+ file listing and installer checks) or deep (+ payload architectures, recursive carving and strings); defaults per format
+        #[arg(long, value_name = "DEPTH")]
+        depth: Option<AnalysisDepth>,
+
+        /// Record the host name, user and platform in the audit trail of the report
+        #[arg(long)]
+        audit_machine: bool,
+
+        /// Sign written reports with this RSA private key (PEM), saving a detached <report>.sig next to each
+        #[arg(long, value_name = "PATH")]
+        sign_key: Option<PathBuf>,
+
+        /// Scrub local details before writing reports, comma-separated: usernames, paths, hostnames
+        #[arg(long, value_name = "LIST", value_delimiter = ',')]
+        redact: Vec<Redaction>,
+
+        /// Hide the stage progress and ETA while analyzing
+        #[arg(short, long)]
+        quiet: bool,
     },
 
     /// Run installer in sandbox for dynamic analysis
@@ -70,6 +228,34 @@ pub enum Commands {
         /// Automatically open HTML report in browser
         #[arg(long)]
         open: bool,
+
+        /// Disguise sandbox tells; not supported yet and rejected
+        #[arg(long, value_name = "LIST", hide = true)]
+        disguise: Option<String>,
+
+        /// Start of a virtual clock for the installer; not supported yet and rejected
+        #[arg(long, value_name = "WHEN", hide = true)]
+        fake_time: Option<String>,
+
+        /// Run the installer this many times and compare the runs; more than one is not supported yet and rejected
+        #[arg(long, default_value = "1", value_parser = clap::value_parser!(u32).range(1..=20), hide = true)]
+        runs: u32,
+
+        /// Noise filter (TOML or YAML) with process, path and registry key patterns to drop from the capture
+        #[arg(long, value_name = "PATH", conflicts_with = "raw")]
+        noise_filter: Option<PathBuf>,
+
+        /// Keep the raw capture, including Windows background noise
+        #[arg(long)]
+        raw: bool,
+
+        /// Tag the sandbox session, e.g. with a ticket or campaign name (repeatable)
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+
+        /// Scrub local details before writing reports, comma-separated: usernames, paths, hostnames
+        #[arg(long, value_name = "LIST", value_delimiter = ',')]
+        redact: Vec<Redaction>,
     },
 
     /// Batch process multiple installers
@@ -89,10 +275,97 @@ pub enum Commands {
         /// Use sandbox analysis
         #[arg(short, long)]
         sandbox: bool,
+
+        /// Append every analysis to a SQLite results database
+        #[arg(long, value_name = "PATH")]
+        db: Option<PathBuf>,
+
+        /// Check every result against a baseline policy (TOML or YAML); violations exit with code 2
+        #[arg(long, value_name = "PATH")]
+        policy: Option<PathBuf>,
+
+        /// Vendor knowledge base (TOML or YAML) extending the curated vendor and product table
+        #[arg(long, value_name = "PATH")]
+        vendor_kb: Option<PathBuf>,
+
+        /// Retry inputs that failed with a transient I/O error, e.g. a file locked by antivirus
+        #[arg(long, value_name = "N", default_value = "0", value_parser = clap::value_parser!(u32).range(0..=10))]
+        retries: u32,
+
+        /// Copy inputs that could not be analyzed to failed/ in the output directory, with the reason
+        #[arg(long)]
+        quarantine: bool,
+    },
+
+    /// Query a results database filled with --db
+    Query {
+        /// Results database to query
+        #[arg(long, value_name = "PATH")]
+        db: PathBuf,
+
+        /// Installers shipping a component, optionally versioned (e.g. "openssl<3")
+        #[arg(long, value_name = "NAME[OP VERSION]", conflicts_with_all = ["hash", "vendors", "vendor"])]
+        component: Option<String>,
+
+        /// Installers containing a file with this hash
+        #[arg(long, conflicts_with_all = ["vendors", "vendor"])]
+        hash: Option<String>,
+
+        /// Count installers per canonical vendor
+        #[arg(long, conflicts_with = "vendor")]
+        vendors: bool,
+
+        /// Installers of a canonical vendor
+        #[arg(long, value_name = "NAME")]
+        vendor: Option<String>,
+
+        /// Print results as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Identify artifacts that are versions of the same product
+    Correlate {
+        /// Installers to analyze or JSON reports written with --format json
+        #[arg(required_unless_present = "db")]
+        inputs: Vec<PathBuf>,
+
+        /// Also compare against every installer recorded in this results database
+        #[arg(long, value_name = "PATH")]
+        db: Option<PathBuf>,
+
+        /// Print results as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Write a single file packaged in an installer without extracting the rest
+    Extract {
+        /// Path to the installer file
+        #[arg(short, long)]
+        input: PathBuf,
+
+        /// Path of the packaged file, as listed in the report
+        #[arg(long, value_name = "ENTRY")]
+        path: PathBuf,
+
+        /// Output file path (stdout if not specified)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Work with existing reports
+    Report {
+        #[command(subcommand)]
+        command: ReportCommands,
     },
 
     /// Show information about supported formats
-    Info,
+    Info {
+        /// Only list which features are available on the current operating system
+        #[arg(long)]
+        platform_capabilities: bool,
+    },
 
     /// Check for and install updates
     Update {
@@ -107,5 +380,183 @@ pub enum Commands {
         /// Skip confirmation prompts
         #[arg(short, long)]
         yes: bool,
+
+        /// Seconds to reuse the cached release information, 0 to always ask GitHub
+        #[arg(
+            long,
+            value_name = "SECONDS",
+            default_value_t = 3600,
+            env = "INSTALLER_ANALYZER_UPDATE_CACHE_TTL"
+        )]
+        cache_ttl: u64,
+
+        /// GitHub token, raising the API rate limit
+        #[arg(long, env = "GITHUB_TOKEN", hide_env_values = true)]
+        github_token: Option<String>,
+
+        /// Update manifest of a self-hosted release server, used instead of GitHub
+        #[arg(long, value_name = "URL", env = "INSTALLER_ANALYZER_UPDATE_MANIFEST")]
+        manifest_url: Option<String>,
+
+        /// Install the update even if the release publishes no checksum for it
+        #[arg(long, conflicts_with = "check_only")]
+        allow_unverified: bool,
+
+        /// Restore the version backed up before the most recent update
+        #[arg(long, conflicts_with_all = ["check_only", "force"])]
+        rollback: bool,
+
+        /// Update the vendor knowledge base data pack instead of the executable
+        #[arg(long, conflicts_with = "rollback", requires_all = ["data_url", "data_key"])]
+        data: bool,
+
+        /// Index of the signed data packs to install from
+        #[arg(long, value_name = "URL", env = "INSTALLER_ANALYZER_DATA_URL")]
+        data_url: Option<String>,
+
+        /// PEM public key data packs must be signed with
+        #[arg(long, value_name = "PEM", env = "INSTALLER_ANALYZER_DATA_KEY")]
+        data_key: Option<PathBuf>,
+    },
+
+    /// Remove temporary directories left behind by earlier runs
+    Clean {
+        /// Remove everything not in use by a running process, not only stale entries
+        #[arg(long)]
+        all: bool,
+
+        /// Also remove entries older than this many hours
+        #[arg(long, value_name = "HOURS")]
+        older_than: Option<u64>,
+
+        /// List what would be removed without deleting anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Serve the job queue API that analysis workers pull installers from
+    ///
+    /// Every option can also be set through its INSTALLER_ANALYZER_* environment variable.
+    Serve {
+        /// Address to listen on
+        #[arg(long, env = "INSTALLER_ANALYZER_HOST", default_value = "127.0.0.1")]
+        host: String,
+
+        /// Port to listen on
+        #[arg(long, env = "INSTALLER_ANALYZER_PORT", default_value = "8080")]
+        port: u16,
+
+        /// Minutes a worker has to finish a claimed job before it is handed to another worker
+        #[arg(long, value_name = "MINUTES", env = "INSTALLER_ANALYZER_LEASE", default_value = "30", value_parser = clap::value_parser!(u64).range(1..))]
+        lease: u64,
+
+        /// Record every completed job in a SQLite results database
+        #[arg(long, value_name = "PATH", env = "INSTALLER_ANALYZER_DB")]
+        db: Option<PathBuf>,
+
+        /// Reject sandbox jobs, e.g. on Linux deployments without Windows workers
+        #[arg(long, env = "INSTALLER_ANALYZER_STATIC_ONLY", value_parser = clap::builder::BoolishValueParser::new())]
+        static_only: bool,
+
+        /// Serve a web UI at / for drag-and-drop analysis and browsing past results, analyzing submitted installers in-process
+        #[arg(long, env = "INSTALLER_ANALYZER_UI", requires = "db", value_parser = clap::builder::BoolishValueParser::new())]
+        ui: bool,
+
+        /// Keep submitted installers in this directory, filed under their SHA-256
+        #[arg(long, value_name = "DIR", env = "INSTALLER_ANALYZER_STORE_DIR")]
+        store_dir: Option<PathBuf>,
+
+        /// Queue every stored installer for static re-analysis at this interval, e.g. 168 for weekly
+        #[arg(long, value_name = "HOURS", env = "INSTALLER_ANALYZER_REANALYZE_EVERY", requires_all = ["db", "store_dir"], value_parser = clap::value_parser!(u64).range(1..))]
+        reanalyze_every: Option<u64>,
+
+        /// Post verdict changes found by re-analysis as JSON to this URL
+        #[arg(
+            long,
+            value_name = "URL",
+            env = "INSTALLER_ANALYZER_NOTIFY_URL",
+            requires = "reanalyze_every"
+        )]
+        notify_url: Option<String>,
+
+        /// Reject submitted installers larger than this many megabytes with 413
+        #[arg(long, value_name = "MB", env = "INSTALLER_ANALYZER_MAX_UPLOAD", default_value = "2048", value_parser = clap::value_parser!(u64).range(1..))]
+        max_upload: u64,
+    },
+
+    /// Pull jobs from an API server started with `serve` and analyze them
+    ///
+    /// Every option can also be set through its INSTALLER_ANALYZER_* environment variable.
+    Worker {
+        /// URL of the API server, e.g. http://analysis-host:8080
+        #[arg(long, value_name = "URL", env = "INSTALLER_ANALYZER_SERVER")]
+        server: String,
+
+        /// Name reported to the server, defaults to <host>-<pid>
+        #[arg(long, env = "INSTALLER_ANALYZER_WORKER_NAME")]
+        name: Option<String>,
+
+        /// Only take static analysis jobs, implied on hosts that cannot run the sandbox
+        #[arg(long, env = "INSTALLER_ANALYZER_STATIC_ONLY", value_parser = clap::builder::BoolishValueParser::new())]
+        static_only: bool,
+
+        /// Seconds to wait before polling an empty queue again
+        #[arg(
+            long,
+            value_name = "SECONDS",
+            env = "INSTALLER_ANALYZER_POLL_INTERVAL",
+            default_value = "5"
+        )]
+        poll_interval: u64,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ReportCommands {
+    /// Merge static and sandbox results into one report, cross-checking declared files and registry entries against observed behavior
+    Merge {
+        /// Installer to analyze, or a JSON report of the analyze command
+        #[arg(long = "static", value_name = "PATH")]
+        static_input: PathBuf,
+
+        /// JSON report of the sandbox command
+        #[arg(long = "dynamic", value_name = "PATH")]
+        dynamic_input: PathBuf,
+
+        /// Output file path
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Output format (json, html, markdown). Auto-detected from file extension if not specified.
+        #[arg(short, long)]
+        format: Option<String>,
+
+        /// Automatically open HTML report in browser
+        #[arg(long)]
+        open: bool,
+    },
+
+    /// Upgrade a JSON report of an earlier version to the current report schema
+    Upgrade {
+        /// JSON report to upgrade
+        input: PathBuf,
+
+        /// Output file path ("-" for stdout). Defaults to rewriting the input in place.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Verify the detached signature of a report written with --sign-key
+    Verify {
+        /// Signed report
+        input: PathBuf,
+
+        /// RSA public key (PEM) of the signer; a private key works too
+        #[arg(long, value_name = "PATH")]
+        key: PathBuf,
+
+        /// Signature file, defaults to <report>.sig
+        #[arg(long, value_name = "PATH")]
+        signature: Option<PathBuf>,
     },
 }
@@ -1,10 +1,48 @@
 //! Cross-platform CLI output utilities
 
+use super::control::{self, LogLevel};
+use super::i18n::{tr, tr_with};
+use crate::utils::progress::{ProgressSink, Stage, Unit};
 use colored::*;
 use indicatif::{ProgressBar, ProgressState, ProgressStyle};
+use std::borrow::Cow;
 use std::fmt::Write;
+use std::iter::Peekable;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
 use std::time::Duration;
 
+/// Whether status messages go to stderr, keeping stdout free for a report
+static STATUS_TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Bar of the running [`StageProgress`], status lines are printed above it
+static ACTIVE_PROGRESS: Mutex<Option<ProgressBar>> = Mutex::new(None);
+
+/// Print a status line to stdout, or to stderr when stdout carries a report
+macro_rules! status {
+    ($($arg:tt)*) => {
+        print_status(|| {
+            if STATUS_TO_STDERR.load(Ordering::Relaxed) {
+                eprintln!($($arg)*);
+            } else {
+                println!($($arg)*);
+            }
+        })
+    };
+}
+
+/// Run `print` with the active progress bar, if any, cleared from the terminal
+fn print_status(print: impl FnOnce()) {
+    let active = ACTIVE_PROGRESS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+    match active {
+        Some(bar) => bar.suspend(print),
+        None => print(),
+    }
+}
+
 /// Cross-platform emojis that work on Windows
 pub struct Icons;
 
@@ -27,53 +65,87 @@ impl Icons {
 pub struct CliOutput;
 
 impl CliOutput {
+    /// Send all further status messages to stderr
+    ///
+    /// Used when the report itself is written to stdout so that it can be
+    /// piped without interleaved progress output.
+    pub fn status_to_stderr() {
+        STATUS_TO_STDERR.store(true, Ordering::Relaxed);
+    }
+
     /// Print a success message
     pub fn success(message: &str) {
-        println!("{} {}", Icons::SUCCESS.green().bold(), message.green());
+        control::log(LogLevel::Success, message);
+        let message = terminal_safe(message);
+        status!("{} {}", Icons::SUCCESS.green().bold(), message.green());
     }
 
     /// Print an error message
     pub fn error(message: &str) {
-        eprintln!("{} {}", Icons::ERROR.red().bold(), message.red());
+        control::log(LogLevel::Error, message);
+        let message = terminal_safe(message);
+        print_status(|| eprintln!("{} {}", Icons::ERROR.red().bold(), message.red()));
     }
 
     /// Print a warning message
     pub fn warning(message: &str) {
-        println!("{} {}", Icons::WARNING.yellow().bold(), message.yellow());
+        control::log(LogLevel::Warning, message);
+        let message = terminal_safe(message);
+        status!("{} {}", Icons::WARNING.yellow().bold(), message.yellow());
     }
 
     /// Print an info message
     pub fn info(message: &str) {
-        println!("{} {}", Icons::INFO.blue().bold(), message);
+        control::log(LogLevel::Info, message);
+        status!("{} {}", Icons::INFO.blue().bold(), terminal_safe(message));
     }
 
     /// Print a file-related message
     pub fn file_info(label: &str, path: &str) {
-        println!("{} {}: {}", Icons::FILE.cyan().bold(), label.cyan(), path);
+        control::log(LogLevel::Info, &format!("{}: {}", label, path));
+        status!(
+            "{} {}: {}",
+            Icons::FILE.cyan().bold(),
+            terminal_safe(label).cyan(),
+            terminal_safe(path)
+        );
     }
 
     /// Print a folder-related message
     pub fn folder_info(label: &str, path: &str) {
-        println!("{} {}: {}", Icons::FOLDER.cyan().bold(), label.cyan(), path);
+        control::log(LogLevel::Info, &format!("{}: {}", label, path));
+        status!(
+            "{} {}: {}",
+            Icons::FOLDER.cyan().bold(),
+            terminal_safe(label).cyan(),
+            terminal_safe(path)
+        );
     }
 
     /// Print a browser-related message
     pub fn browser_info(message: &str) {
-        println!("{} {}", Icons::BROWSER.blue().bold(), message);
+        control::log(LogLevel::Info, message);
+        status!(
+            "{} {}",
+            Icons::BROWSER.blue().bold(),
+            terminal_safe(message)
+        );
     }
 
     /// Print a section header
     pub fn section_header(title: &str) {
-        println!();
-        println!("{}", title.bold().underline());
-        println!("{}", "=".repeat(title.len()).dimmed());
+        let title = terminal_safe(title);
+        status!();
+        status!("{}", title.bold().underline());
+        status!("{}", "=".repeat(display_width(&title)).dimmed());
     }
 
     /// Print a subsection header
     pub fn subsection_header(title: &str) {
-        println!();
-        println!("{}", title.bold());
-        println!("{}", "-".repeat(title.len()).dimmed());
+        let title = terminal_safe(title);
+        status!();
+        status!("{}", title.bold());
+        status!("{}", "-".repeat(display_width(&title)).dimmed());
     }
 
     /// Create a progress bar for file processing
@@ -89,7 +161,7 @@ impl CliOutput {
             })
             .progress_chars("#>-"),
         );
-        pb.set_message(message.to_string());
+        pb.set_message(terminal_safe(message).into_owned());
         pb.enable_steady_tick(Duration::from_millis(120));
         pb
     }
@@ -102,19 +174,19 @@ impl CliOutput {
                 .unwrap()
                 .tick_strings(&["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"]),
         );
-        spinner.set_message(message.to_string());
+        spinner.set_message(terminal_safe(message).into_owned());
         spinner.enable_steady_tick(Duration::from_millis(120));
         spinner
     }
 
     /// Finish a progress bar with success
     pub fn finish_progress_success(pb: &ProgressBar, message: &str) {
-        pb.finish_with_message(format!("{} {}", Icons::SUCCESS, message));
+        pb.finish_with_message(format!("{} {}", Icons::SUCCESS, terminal_safe(message)));
     }
 
     /// Finish a progress bar with error
     pub fn finish_progress_error(pb: &ProgressBar, message: &str) {
-        pb.finish_with_message(format!("{} {}", Icons::ERROR, message));
+        pb.finish_with_message(format!("{} {}", Icons::ERROR, terminal_safe(message)));
     }
 
     /// Print analysis summary
@@ -124,64 +196,214 @@ impl CliOutput {
         duration: Duration,
         file_count: Option<usize>,
     ) {
-        Self::section_header("Analysis Complete");
-        Self::file_info("Report format", format);
-        Self::folder_info("Report saved to", output_path);
-        Self::info(&format!(
-            "Analysis duration: {:.2}s",
-            duration.as_secs_f64()
+        Self::section_header(tr("summary.analysis_complete"));
+        Self::file_info(tr("common.report_format"), format);
+        Self::folder_info(tr("common.report_saved"), output_path);
+        Self::info(&tr_with(
+            "summary.analysis_duration",
+            &[("seconds", &format!("{:.2}", duration.as_secs_f64()))],
         ));
 
         if let Some(count) = file_count {
-            Self::info(&format!("Files analyzed: {}", count));
+            Self::info(&tr_with("summary.files_analyzed", &[("count", &count)]));
         }
     }
 
     /// Print batch processing summary
     pub fn batch_summary(processed: usize, failed: usize, total_duration: Duration) {
-        Self::section_header("Batch Processing Complete");
-        Self::success(&format!("Successfully processed: {}", processed));
+        Self::section_header(tr("summary.batch_complete"));
+        Self::success(&tr_with("summary.processed", &[("count", &processed)]));
 
         if failed > 0 {
-            Self::error(&format!("Failed to process: {}", failed));
+            Self::error(&tr_with("summary.failed", &[("count", &failed)]));
         }
 
-        Self::info(&format!(
-            "Total duration: {:.2}s",
-            total_duration.as_secs_f64()
+        Self::info(&tr_with(
+            "summary.total_duration",
+            &[("seconds", &format!("{:.2}", total_duration.as_secs_f64()))],
         ));
 
         if processed > 0 {
-            Self::info(&format!(
-                "Average time per file: {:.2}s",
-                total_duration.as_secs_f64() / processed as f64
+            let average = total_duration.as_secs_f64() / processed as f64;
+            Self::info(&tr_with(
+                "summary.average_duration",
+                &[("seconds", &format!("{:.2}", average))],
             ));
         }
     }
 
     /// Print startup banner
     pub fn startup_banner(version: &str) {
-        println!();
-        println!("{}", "Installer Analyzer".bold().cyan());
-        println!("{}", format!("Version {}", version).dimmed());
-        println!(
+        status!();
+        status!("{}", "Installer Analyzer".bold().cyan());
+        status!(
             "{}",
-            "A comprehensive tool for analyzing software installation packages".dimmed()
+            tr_with("banner.version", &[("version", &version)]).dimmed()
         );
-        println!();
+        status!("{}", tr("banner.tagline").dimmed());
+        status!();
     }
 
     /// Print format detection info
     pub fn format_detection(detected: &str, explicit: Option<&str>) {
         if let Some(explicit_format) = explicit {
             if explicit_format != detected {
-                Self::warning(&format!(
-                    "Format mismatch: detected '{}' from file extension, using explicit '{}'",
-                    detected, explicit_format
+                Self::warning(&tr_with(
+                    "summary.format_mismatch",
+                    &[("detected", &detected), ("explicit", &explicit_format)],
                 ));
             }
         } else {
-            Self::info(&format!("Auto-detected format: {}", detected));
+            Self::info(&tr_with("summary.auto_detected", &[("format", &detected)]));
+        }
+    }
+}
+
+/// Columns taken by `text`, counting wide characters such as CJK as two
+fn display_width(text: &str) -> usize {
+    console::measure_text_width(text)
+}
+
+/// Stages of a single analysis as a spinner, and counted work as a bar with ETA
+///
+/// Installed as the [`progress`](crate::utils::progress) sink of an analysis.
+/// The bar is cleared when the progress is dropped.
+pub struct StageProgress {
+    bar: ProgressBar,
+}
+
+impl StageProgress {
+    pub fn new() -> Arc<Self> {
+        let bar = ProgressBar::new_spinner();
+        bar.set_style(Self::spinner_style());
+        bar.enable_steady_tick(Duration::from_millis(120));
+        *ACTIVE_PROGRESS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(bar.clone());
+        Arc::new(Self { bar })
+    }
+
+    fn spinner_style() -> ProgressStyle {
+        ProgressStyle::with_template("{spinner:.green} [{elapsed_precise}] {wide_msg}")
+            .unwrap()
+            .tick_strings(&["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"])
+    }
+
+    fn bar_style(unit: Unit) -> ProgressStyle {
+        let count = match unit {
+            Unit::Files => "{pos}/{len}",
+            Unit::Bytes => "{bytes}/{total_bytes}",
+        };
+        ProgressStyle::with_template(&format!(
+            "{{spinner:.green}} [{{elapsed_precise}}] [{{bar:30.cyan/blue}}] {} ETA {{eta}} {{wide_msg}}",
+            count
+        ))
+        .unwrap()
+        .progress_chars("#>-")
+    }
+
+    fn label(stage: Stage) -> &'static str {
+        match stage {
+            Stage::Detecting => tr("progress.detecting"),
+            Stage::Extracting => tr("progress.extracting"),
+            Stage::InspectingFiles => tr("progress.inspecting"),
+            Stage::Hashing => tr("progress.hashing"),
+            Stage::Report => tr("progress.report"),
+        }
+    }
+}
+
+impl ProgressSink for StageProgress {
+    fn stage(&self, stage: Stage) {
+        self.bar.set_style(Self::spinner_style());
+        self.bar.set_message(format!(
+            "[{}/{}] {}",
+            stage.number(),
+            Stage::ALL.len(),
+            Self::label(stage)
+        ));
+    }
+
+    fn begin(&self, unit: Unit, total: u64) {
+        // Stages without work to count keep spinning
+        if total == 0 {
+            return;
+        }
+        self.bar.set_length(total);
+        self.bar.set_position(0);
+        self.bar.reset_eta();
+        self.bar.set_style(Self::bar_style(unit));
+    }
+
+    fn advance(&self, delta: u64) {
+        self.bar.inc(delta);
+    }
+}
+
+impl Drop for StageProgress {
+    fn drop(&mut self) {
+        self.bar.finish_and_clear();
+        *ACTIVE_PROGRESS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = None;
+    }
+}
+
+/// Text as it may be written to a terminal
+///
+/// Names and paths printed by the analyzer come from untrusted installers.
+/// Escape sequences in them could recolor or clear the screen, move the
+/// cursor over earlier output, set the window title or, on some terminals,
+/// write to the clipboard. Escape sequences are removed entirely and other
+/// control characters are dropped, except for line breaks and tabs.
+pub fn terminal_safe(text: &str) -> Cow<'_, str> {
+    let is_unsafe = |c: char| c.is_control() && c != '\n' && c != '\t';
+    if !text.chars().any(is_unsafe) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut safe = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' => match chars.next() {
+                Some('[') => skip_control_sequence(&mut chars),
+                Some(']' | 'P' | 'X' | '^' | '_') => skip_control_string(&mut chars),
+                // Two-character escapes, possibly with intermediate bytes
+                Some(' '..='/') => {
+                    while chars.next_if(|c| matches!(c, ' '..='/')).is_some() {}
+                    chars.next();
+                }
+                _ => {}
+            },
+            '\u{9b}' => skip_control_sequence(&mut chars),
+            '\u{90}' | '\u{98}' | '\u{9d}' | '\u{9e}' | '\u{9f}' => skip_control_string(&mut chars),
+            c if is_unsafe(c) => {}
+            c => safe.push(c),
+        }
+    }
+    Cow::Owned(safe)
+}
+
+/// Skip parameters and the final byte of a CSI sequence like `ESC [ 2 J`
+fn skip_control_sequence(chars: &mut Peekable<impl Iterator<Item = char>>) {
+    while chars
+        .next_if(|c| matches!(c, '0'..='?' | ' '..='/'))
+        .is_some()
+    {}
+    chars.next_if(|c| matches!(c, '@'..='~'));
+}
+
+/// Skip the payload of an OSC, DCS or similar string up to its terminator
+fn skip_control_string(chars: &mut Peekable<impl Iterator<Item = char>>) {
+    while let Some(c) = chars.next() {
+        match c {
+            '\x07' | '\u{9c}' => return,
+            '\x1b' => {
+                chars.next_if_eq(&'\\');
+                return;
+            }
+            _ => {}
         }
     }
 }
@@ -244,6 +466,59 @@ mod tests {
         assert_eq!(spinner.length(), None); // Spinners have no defined length
     }
 
+    #[test]
+    fn test_terminal_safe() {
+        assert!(matches!(terminal_safe("微信 Setup 🚀"), Cow::Borrowed(_)));
+        assert_eq!(terminal_safe("line\n\tindented"), "line\n\tindented");
+
+        // Colors, screen clearing and cursor movement
+        assert_eq!(terminal_safe("\x1b[31mRed\x1b[0m App"), "Red App");
+        assert_eq!(
+            terminal_safe("App\x1b[2J\x1b[1;1HFake prompt"),
+            "AppFake prompt"
+        );
+        assert_eq!(terminal_safe("App\u{9b}2KSetup"), "AppSetup");
+        // Window title and clipboard writes, terminated by BEL or ST
+        assert_eq!(terminal_safe("\x1b]0;pwned\x07setup.exe"), "setup.exe");
+        assert_eq!(
+            terminal_safe("\x1b]52;c;ZWNobyBoaQ==\x1b\\setup.exe"),
+            "setup.exe"
+        );
+        assert_eq!(terminal_safe("\x1bPdevice\x1b\\ok"), "ok");
+        // Charset designation and reset
+        assert_eq!(terminal_safe("\x1b(0App\x1bc"), "App");
+        // Carriage returns and backspaces overwriting earlier output
+        assert_eq!(
+            terminal_safe("evil.exe\rbenign.exe\x08\x07"),
+            "evil.exebenign.exe"
+        );
+        // Unterminated sequences are dropped up to the end
+        assert_eq!(terminal_safe("App\x1b]8;;http://x"), "App");
+        assert_eq!(terminal_safe("App\x1b"), "App");
+    }
+
+    #[test]
+    fn test_stage_progress() {
+        let progress = StageProgress::new();
+        progress.stage(Stage::InspectingFiles);
+        progress.begin(Unit::Files, 2);
+        progress.advance(1);
+        assert_eq!(progress.bar.position(), 1);
+        assert_eq!(progress.bar.length(), Some(2));
+        progress.begin(Unit::Files, 0);
+        assert_eq!(progress.bar.length(), Some(2));
+
+        let bar = progress.bar.clone();
+        drop(progress);
+        assert!(bar.is_finished());
+    }
+
+    #[test]
+    fn test_display_width() {
+        assert_eq!(display_width("Analysis Complete"), 17);
+        assert_eq!(display_width("分析完成"), 8);
+    }
+
     #[test]
     fn test_init_console() {
         // Should not panic
@@ -54,10 +54,26 @@ pub enum AnalyzerError {
     #[error("Configuration error: {message}")]
     ConfigError { message: String },
 
+    /// Analysis result violates a baseline policy
+    #[error("Policy check failed: {violations} violation(s) of {policy}")]
+    PolicyViolation { policy: String, violations: usize },
+
+    /// Results database error
+    #[error("Database error: {message}")]
+    DatabaseError { message: String },
+
     /// Serialization error
     #[error("Serialization error: {0}")]
     SerializationError(#[from] serde_json::Error),
 
+    /// Analyzer panicked, caught by `crash::isolate`
+    #[error("Analyzer crashed on {input}: {message}", input = .input.display())]
+    Crashed { input: PathBuf, message: String },
+
+    /// Cancelled on request, e.g. by a frontend on the control pipe
+    #[error("Cancelled")]
+    Cancelled,
+
     /// Generic error with context
     #[error("Error: {message}")]
     Generic { message: String },
@@ -137,6 +153,64 @@ impl AnalyzerError {
         }
     }
 
+    /// Create a new policy violation error
+    pub fn policy_violation(policy: impl Into<String>, violations: usize) -> Self {
+        Self::PolicyViolation {
+            policy: policy.into(),
+            violations,
+        }
+    }
+
+    /// Process exit code for this error
+    ///
+    /// Policy violations exit with 2 so CI gates can tell them apart from
+    /// analysis failures, which exit with 1. Cancelled runs exit with 130,
+    /// like processes interrupted with Ctrl+C.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::PolicyViolation { .. } => 2,
+            Self::Cancelled => 130,
+            _ => 1,
+        }
+    }
+
+    /// Whether a retry may succeed
+    ///
+    /// Only I/O errors of interrupted or temporarily locked operations count,
+    /// e.g. an installer still held open by an antivirus scan.
+    pub fn is_transient(&self) -> bool {
+        /// ERROR_SHARING_VIOLATION and ERROR_LOCK_VIOLATION
+        const LOCKED_FILE_ERRORS: [i32; 2] = [32, 33];
+
+        let Self::Io(error) = self else {
+            return false;
+        };
+        matches!(
+            error.kind(),
+            std::io::ErrorKind::Interrupted
+                | std::io::ErrorKind::TimedOut
+                | std::io::ErrorKind::WouldBlock
+        ) || (cfg!(windows)
+            && error
+                .raw_os_error()
+                .is_some_and(|code| LOCKED_FILE_ERRORS.contains(&code)))
+    }
+
+    /// Create a new database error
+    pub fn database_error(message: impl Into<String>) -> Self {
+        Self::DatabaseError {
+            message: message.into(),
+        }
+    }
+
+    /// Create a new crash error
+    pub fn crashed(input: impl Into<PathBuf>, message: impl Into<String>) -> Self {
+        Self::Crashed {
+            input: input.into(),
+            message: message.into(),
+        }
+    }
+
     /// Create a new generic error
     pub fn generic(message: impl Into<String>) -> Self {
         Self::Generic {
@@ -149,6 +223,7 @@ impl AnalyzerError {
 pub type Result<T> = std::result::Result<T, AnalyzerError>;
 
 /// Convert Windows API errors to AnalyzerError
+#[cfg(windows)]
 impl From<windows::core::Error> for AnalyzerError {
     fn from(error: windows::core::Error) -> Self {
         Self::WindowsApiError {
@@ -157,6 +232,15 @@ impl From<windows::core::Error> for AnalyzerError {
     }
 }
 
+/// Convert SQLite errors to AnalyzerError
+impl From<rusqlite::Error> for AnalyzerError {
+    fn from(error: rusqlite::Error) -> Self {
+        Self::DatabaseError {
+            message: error.to_string(),
+        }
+    }
+}
+
 /// Convert anyhow errors to AnalyzerError
 impl From<anyhow::Error> for AnalyzerError {
     fn from(error: anyhow::Error) -> Self {
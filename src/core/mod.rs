@@ -1,8 +1,10 @@
 //! Core module containing fundamental types and traits
 
 pub mod error;
+pub mod normalize;
 pub mod types;
 
 // Re-export commonly used items
 pub use error::{AnalyzerError, Result};
+pub use normalize::{normalize_text, normalize_vendor, normalize_version};
 pub use types::*;
@@ -0,0 +1,252 @@
+//! Normalization of installer identity metadata
+//!
+//! Installers spell the same vendor and version in many ways: version
+//! resources pad values with spaces and write `1, 2, 0, 0`, product names
+//! carry trademark signs, and vendors appear as `Microsoft Corp.` in one
+//! package and `Microsoft Corporation ` in the next. Analysis results are
+//! normalized before reporting so comparisons and the results database
+//! group them together.
+
+use crate::core::{InstallerMetadata, MetadataField};
+
+/// Trademark and copyright signs removed from names
+const TRADEMARK_SIGNS: &[char] = &['™', '®', '©', '℠'];
+
+/// ASCII spellings of the trademark signs, matched ignoring case
+const TRADEMARK_TEXTS: &[&str] = &["(tm)", "(r)", "(c)"];
+
+/// Legal form suffixes of vendor names and their canonical spelling
+///
+/// Keys are lower case without the trailing dot.
+const LEGAL_FORMS: &[(&str, &str)] = &[
+    ("corp", "Corporation"),
+    ("corporation", "Corporation"),
+    ("inc", "Inc."),
+    ("incorporated", "Inc."),
+    ("ltd", "Ltd."),
+    ("limited", "Ltd."),
+    ("co", "Co."),
+    ("llc", "LLC"),
+    ("l.l.c", "LLC"),
+    ("gmbh", "GmbH"),
+    ("ag", "AG"),
+    ("b.v", "B.V."),
+    ("bv", "B.V."),
+    ("s.a", "S.A."),
+    ("plc", "plc"),
+];
+
+/// Longest all-capital word kept as an acronym when title-casing
+const MAX_ACRONYM_LENGTH: usize = 3;
+
+impl InstallerMetadata {
+    /// Normalize the product name, version and manufacturer in place
+    ///
+    /// A field that is empty after normalization is cleared along with its
+    /// provenance.
+    pub fn normalize(&mut self) {
+        self.product_name = self.product_name.as_deref().and_then(normalize_text);
+        self.product_version = self.product_version.as_deref().and_then(normalize_version);
+        self.manufacturer = self.manufacturer.as_deref().and_then(normalize_vendor);
+        for field in MetadataField::ALL {
+            if self.field(field).is_none() {
+                self.provenance.set(field, None);
+            }
+        }
+    }
+}
+
+/// Trim, collapse whitespace and drop control characters and trademark signs
+pub fn normalize_text(value: &str) -> Option<String> {
+    let mut text: String = value
+        .chars()
+        .filter(|c| !TRADEMARK_SIGNS.contains(c))
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect();
+    for sign in TRADEMARK_TEXTS {
+        while let Some(start) = text.to_ascii_lowercase().find(sign) {
+            text.replace_range(start..start + sign.len(), " ");
+        }
+    }
+
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!text.is_empty()).then_some(text)
+}
+
+/// Normalize a version string
+///
+/// Drops `v` and `version` prefixes and turns the comma separated numbers
+/// of version resources (`1, 2, 0, 0`) into dotted versions.
+pub fn normalize_version(value: &str) -> Option<String> {
+    let text = normalize_text(value)?;
+    let lower = text.to_ascii_lowercase();
+    let text = ["version ", "ver. ", "ver ", "v", "v."]
+        .iter()
+        .find_map(|prefix| {
+            lower
+                .starts_with(prefix)
+                .then(|| text[prefix.len()..].trim_start())
+                .filter(|rest| rest.starts_with(|c: char| c.is_ascii_digit()))
+        })
+        .unwrap_or(&text);
+
+    let parts: Vec<&str> = text.split(',').map(str::trim).collect();
+    let text = if parts.len() > 1
+        && parts
+            .iter()
+            .all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()))
+    {
+        parts.join(".")
+    } else {
+        text.to_string()
+    };
+    (!text.is_empty()).then_some(text)
+}
+
+/// Normalize a vendor name
+///
+/// Besides [`normalize_text`], names written entirely in upper or lower case
+/// are title-cased, keeping short acronyms such as `IBM`, and legal form
+/// suffixes get one spelling: `Microsoft Corp.` and `MICROSOFT CORPORATION`
+/// both become `Microsoft Corporation`.
+pub fn normalize_vendor(value: &str) -> Option<String> {
+    let text = normalize_text(value)?;
+    let letters = || text.chars().filter(|c| c.is_alphabetic());
+    let single_case = letters().all(char::is_uppercase) || letters().all(char::is_lowercase);
+
+    let mut words: Vec<String> = text
+        .split(' ')
+        .map(|word| {
+            if single_case {
+                title_case(word)
+            } else {
+                word.to_string()
+            }
+        })
+        .collect();
+
+    // Canonicalize trailing legal forms such as `Co., Ltd.`
+    for index in (1..words.len()).rev() {
+        let key = words[index]
+            .trim_end_matches(',')
+            .trim_end_matches('.')
+            .to_lowercase();
+        match LEGAL_FORMS.iter().find(|(form, _)| *form == key) {
+            Some((_, canonical)) => words[index] = canonical.to_string(),
+            None => break,
+        }
+        // `Contoso, Inc.` is written without the comma
+        if let Some(previous) = words[index - 1].strip_suffix(',') {
+            words[index - 1] = previous.to_string();
+        }
+    }
+    Some(words.join(" "))
+}
+
+/// Capitalize a word, keeping short all-capital acronyms
+fn title_case(word: &str) -> String {
+    let letters = word.chars().filter(|c| c.is_alphabetic()).count();
+    if letters <= MAX_ACRONYM_LENGTH && word.chars().all(|c| !c.is_lowercase()) {
+        return word.to_string();
+    }
+    let mut capitalized = false;
+    word.chars()
+        .flat_map(|c| {
+            let first = !capitalized && c.is_alphabetic();
+            capitalized |= first;
+            let converted: Vec<char> = if first {
+                c.to_uppercase().collect()
+            } else {
+                c.to_lowercase().collect()
+            };
+            converted
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{InstallerFormat, MetadataSource};
+
+    #[test]
+    fn test_normalize_text() {
+        assert_eq!(
+            normalize_text("  Contoso\u{a0}Viewer™  Pro\0 ").as_deref(),
+            Some("Contoso Viewer Pro")
+        );
+        assert_eq!(
+            normalize_text("Windows(R) Installer (TM)").as_deref(),
+            Some("Windows Installer")
+        );
+        assert_eq!(normalize_text(" ® \t"), None);
+    }
+
+    #[test]
+    fn test_normalize_version() {
+        for (value, expected) in [
+            ("1, 2, 0, 0", Some("1.2.0.0")),
+            ("1,2,0,0", Some("1.2.0.0")),
+            (" v2.1.0 ", Some("2.1.0")),
+            ("Version 3.4", Some("3.4")),
+            ("V 3.4", Some("3.4")),
+            ("2.0 beta, build 5", Some("2.0 beta, build 5")),
+            ("vNext", Some("vNext")),
+            ("  ", None),
+        ] {
+            assert_eq!(normalize_version(value).as_deref(), expected, "{}", value);
+        }
+    }
+
+    #[test]
+    fn test_normalize_vendor() {
+        for (value, expected) in [
+            ("Microsoft Corp.", "Microsoft Corporation"),
+            ("Microsoft Corporation ", "Microsoft Corporation"),
+            ("MICROSOFT CORPORATION", "Microsoft Corporation"),
+            ("microsoft corp", "Microsoft Corporation"),
+            ("Contoso, Inc.", "Contoso Inc."),
+            ("Contoso Incorporated", "Contoso Inc."),
+            ("ADOBE SYSTEMS INC", "Adobe Systems Inc."),
+            ("IBM", "IBM"),
+            ("IBM CORP", "IBM Corporation"),
+            ("CONTOSO (SHENZHEN) LTD", "Contoso (Shenzhen) Ltd."),
+            (
+                "Tencent Technology (Shenzhen) Company Limited",
+                "Tencent Technology (Shenzhen) Company Ltd.",
+            ),
+            ("Contoso Co., Ltd", "Contoso Co. Ltd."),
+            ("Contoso llc", "Contoso LLC"),
+            ("contoso gmbh", "Contoso GmbH"),
+            ("JetBrains s.r.o.", "JetBrains s.r.o."),
+            ("Inc", "Inc"),
+        ] {
+            assert_eq!(
+                normalize_vendor(value).as_deref(),
+                Some(expected),
+                "{}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn test_normalize_metadata_clears_empty_fields() {
+        let mut metadata = InstallerMetadata {
+            format: InstallerFormat::NSIS,
+            product_name: Some(" Contoso Viewer® ".to_string()),
+            product_version: Some("1, 0, 2, 0".to_string()),
+            manufacturer: Some("  ".to_string()),
+            created_at: chrono::Utc::now(),
+            ..Default::default()
+        }
+        .with_provenance(MetadataSource::PeVersionInfo);
+
+        metadata.normalize();
+        assert_eq!(metadata.product_name.as_deref(), Some("Contoso Viewer"));
+        assert_eq!(metadata.product_version.as_deref(), Some("1.0.2.0"));
+        assert_eq!(metadata.manufacturer, None);
+        assert!(metadata.provenance.product_name.is_some());
+        assert_eq!(metadata.provenance.manufacturer, None);
+    }
+}
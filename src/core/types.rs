@@ -7,7 +7,7 @@ use std::path::PathBuf;
 use uuid::Uuid;
 
 /// Supported installer formats
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum InstallerFormat {
     /// Microsoft Installer format
     MSI,
@@ -19,18 +19,243 @@ pub enum InstallerFormat {
     WiX,
     /// InstallShield installer
     InstallShield,
+    /// Advanced Installer EXE bootstrapper
+    AdvancedInstaller,
+    /// Actual Installer setup
+    ActualInstaller,
+    /// Indigo Rose Setup Factory setup
+    SetupFactory,
+    /// Wise Installation System setup
+    Wise,
+    /// Ethalone Ghost Installer setup
+    GhostInstaller,
     /// Python Wheel package
     PythonWheel,
     /// MSIX/AppX package format
     MSIX,
     /// Squirrel installer (Electron apps)
     Squirrel,
+    /// ISO 9660, UDF or WIM image of product media
+    DiskImage,
+    /// Windows driver package: INF with its catalog and binaries
+    DriverPackage,
+    /// Custom format provided by a registered analyzer
+    Other(String),
     /// Unknown or unsupported format
+    #[default]
     Unknown,
 }
 
+impl InstallerFormat {
+    /// Create a custom format identified by name
+    pub fn other(name: impl Into<String>) -> Self {
+        Self::Other(name.into())
+    }
+
+    /// Get the display name of the format
+    pub fn name(&self) -> &str {
+        match self {
+            Self::MSI => "MSI",
+            Self::NSIS => "NSIS",
+            Self::InnoSetup => "InnoSetup",
+            Self::WiX => "WiX",
+            Self::InstallShield => "InstallShield",
+            Self::AdvancedInstaller => "AdvancedInstaller",
+            Self::ActualInstaller => "ActualInstaller",
+            Self::SetupFactory => "SetupFactory",
+            Self::Wise => "Wise",
+            Self::GhostInstaller => "GhostInstaller",
+            Self::PythonWheel => "PythonWheel",
+            Self::MSIX => "MSIX",
+            Self::Squirrel => "Squirrel",
+            Self::DiskImage => "DiskImage",
+            Self::DriverPackage => "DriverPackage",
+            Self::Other(name) => name,
+            Self::Unknown => "Unknown",
+        }
+    }
+
+    /// Parse a display name as returned by [`InstallerFormat::name`]
+    pub fn from_name(name: &str) -> Self {
+        [
+            Self::MSI,
+            Self::NSIS,
+            Self::InnoSetup,
+            Self::WiX,
+            Self::InstallShield,
+            Self::AdvancedInstaller,
+            Self::ActualInstaller,
+            Self::SetupFactory,
+            Self::Wise,
+            Self::GhostInstaller,
+            Self::PythonWheel,
+            Self::MSIX,
+            Self::Squirrel,
+            Self::DiskImage,
+            Self::DriverPackage,
+            Self::Unknown,
+        ]
+        .into_iter()
+        .find(|format| format.name() == name)
+        .unwrap_or_else(|| Self::other(name))
+    }
+}
+
+impl std::fmt::Display for InstallerFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// How reliably an analyzer supports a kind of extraction
+///
+/// Levels are ordered from least to most reliable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SupportLevel {
+    /// Not supported by the analyzer
+    Unsupported,
+    /// Synthesized from common patterns, not read from the installer
+    Heuristic,
+    /// Partially parsed from the installer
+    Partial,
+    /// Fully parsed from the installer
+    Full,
+}
+
+impl SupportLevel {
+    /// Get the display name of the support level
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Unsupported => "unsupported",
+            Self::Heuristic => "heuristic",
+            Self::Partial => "partial",
+            Self::Full => "full",
+        }
+    }
+}
+
+impl std::fmt::Display for SupportLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// How much of the analysis pipeline runs, trading detail for speed
+///
+/// Depths are ordered, each one runs the stages of the one before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnalysisDepth {
+    /// Format detection and metadata
+    Quick,
+    /// Files, registry operations and dependencies, with the heuristic
+    /// reports built from them
+    Standard,
+    /// Packaged executables read and hashed, carved payloads analyzed and
+    /// strings extracted
+    Deep,
+}
+
+impl AnalysisDepth {
+    /// Get the display name of the depth
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Quick => "quick",
+            Self::Standard => "standard",
+            Self::Deep => "deep",
+        }
+    }
+
+    /// Depth used for a format unless one is chosen
+    ///
+    /// Wheels and MSIX packages are ZIP archives whose entries are cheap to
+    /// read, so they are analyzed deeply. Other formats decompress cabinets
+    /// or scan executables for their payloads.
+    pub fn default_for(format: &InstallerFormat) -> Self {
+        match format {
+            InstallerFormat::PythonWheel | InstallerFormat::MSIX => Self::Deep,
+            // Strings and payloads are all there is to report for unrecognized files
+            InstallerFormat::Unknown => Self::Deep,
+            _ => Self::Standard,
+        }
+    }
+}
+
+impl std::fmt::Display for AnalysisDepth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl std::str::FromStr for AnalysisDepth {
+    type Err = String;
+
+    fn from_str(name: &str) -> std::result::Result<Self, Self::Err> {
+        [Self::Quick, Self::Standard, Self::Deep]
+            .into_iter()
+            .find(|depth| depth.name().eq_ignore_ascii_case(name.trim()))
+            .ok_or_else(|| {
+                format!(
+                    "unknown analysis depth '{}', expected quick, standard or deep",
+                    name
+                )
+            })
+    }
+}
+
+/// Capabilities declared by an analyzer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnalyzerCapabilities {
+    /// Product metadata extraction
+    pub metadata: SupportLevel,
+    /// File list extraction
+    pub file_extraction: SupportLevel,
+    /// Registry operation extraction
+    pub registry_operations: SupportLevel,
+    /// Install script / custom action extraction
+    pub install_scripts: SupportLevel,
+    /// Digital signature inspection
+    pub signatures: SupportLevel,
+}
+
+impl AnalyzerCapabilities {
+    /// Get the overall accuracy, i.e. the weakest level among supported capabilities
+    pub fn accuracy(&self) -> SupportLevel {
+        [
+            self.metadata,
+            self.file_extraction,
+            self.registry_operations,
+            self.install_scripts,
+            self.signatures,
+        ]
+        .into_iter()
+        .filter(|level| *level != SupportLevel::Unsupported)
+        .min()
+        .unwrap_or(SupportLevel::Unsupported)
+    }
+
+    /// Check whether any output of the analyzer is synthesized rather than extracted
+    pub fn is_heuristic(&self) -> bool {
+        self.accuracy() == SupportLevel::Heuristic
+    }
+}
+
+impl Default for AnalyzerCapabilities {
+    /// Capabilities assumed for analyzers that do not declare their own
+    fn default() -> Self {
+        Self {
+            metadata: SupportLevel::Heuristic,
+            file_extraction: SupportLevel::Heuristic,
+            registry_operations: SupportLevel::Heuristic,
+            install_scripts: SupportLevel::Unsupported,
+            signatures: SupportLevel::Unsupported,
+        }
+    }
+}
+
 /// Installer metadata information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct InstallerMetadata {
     /// Installer format
     pub format: InstallerFormat,
@@ -48,6 +273,424 @@ pub struct InstallerMetadata {
     pub created_at: DateTime<Utc>,
     /// Additional properties
     pub properties: HashMap<String, String>,
+    /// Where the product name, version and manufacturer were read from
+    #[serde(default, skip_serializing_if = "MetadataProvenance::is_empty")]
+    pub provenance: MetadataProvenance,
+    /// Typed format-specific details, for formats that have a schema
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format_details: Option<FormatDetails>,
+}
+
+impl InstallerMetadata {
+    /// Value of an identity field
+    pub fn field(&self, field: MetadataField) -> Option<&str> {
+        match field {
+            MetadataField::ProductName => self.product_name.as_deref(),
+            MetadataField::ProductVersion => self.product_version.as_deref(),
+            MetadataField::Manufacturer => self.manufacturer.as_deref(),
+        }
+    }
+
+    /// Record `source` for every identity field that has a value
+    pub fn with_provenance(mut self, source: MetadataSource) -> Self {
+        for field in MetadataField::ALL {
+            self.set_provenance(field, source);
+        }
+        self
+    }
+
+    /// Record `source` for `field` when it has a value
+    pub fn with_field_provenance(mut self, field: MetadataField, source: MetadataSource) -> Self {
+        self.set_provenance(field, source);
+        self
+    }
+
+    /// Record `source` for `field`, or clear its provenance when it has no value
+    pub fn set_provenance(&mut self, field: MetadataField, source: MetadataSource) {
+        let provenance = self
+            .field(field)
+            .map(|_| FieldProvenance::from_source(source));
+        self.provenance.set(field, provenance);
+    }
+}
+
+/// Identity fields of [`InstallerMetadata`] whose provenance is recorded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MetadataField {
+    ProductName,
+    ProductVersion,
+    Manufacturer,
+}
+
+impl MetadataField {
+    /// All identity fields, in report order
+    pub const ALL: [Self; 3] = [Self::ProductName, Self::ProductVersion, Self::Manufacturer];
+
+    /// Name of the field in serialized metadata
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ProductName => "product_name",
+            Self::ProductVersion => "product_version",
+            Self::Manufacturer => "manufacturer",
+        }
+    }
+}
+
+/// Where a metadata value was read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetadataSource {
+    /// MSI `Property` table
+    MsiProperty,
+    /// Package manifest: AppxManifest, wheel METADATA, Burn manifest, INF
+    Manifest,
+    /// Setup data of the installer format, e.g. the Inno Setup header
+    InstallerData,
+    /// String table of the PE version resource
+    PeVersionInfo,
+    /// Version-like text found by scanning the file content
+    ContentScan,
+    /// Parsed from the file name
+    FilenameHeuristic,
+    /// Placeholder used when no source had a value
+    Placeholder,
+}
+
+impl MetadataSource {
+    /// Description used in reports
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::MsiProperty => "MSI property",
+            Self::Manifest => "package manifest",
+            Self::InstallerData => "installer setup data",
+            Self::PeVersionInfo => "PE version info",
+            Self::ContentScan => "file content scan",
+            Self::FilenameHeuristic => "file name",
+            Self::Placeholder => "placeholder",
+        }
+    }
+
+    /// Confidence of values read from this source
+    ///
+    /// Version resources describe the setup stub, which installer authoring
+    /// tools fill from the project but may also leave at their own defaults.
+    pub fn confidence(&self) -> MetadataConfidence {
+        match self {
+            Self::MsiProperty | Self::Manifest | Self::InstallerData => MetadataConfidence::High,
+            Self::PeVersionInfo => MetadataConfidence::Medium,
+            Self::ContentScan | Self::FilenameHeuristic | Self::Placeholder => {
+                MetadataConfidence::Low
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for MetadataSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.description())
+    }
+}
+
+/// How far a metadata value can be trusted
+///
+/// Levels are ordered from least to most trustworthy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetadataConfidence {
+    /// Guessed; shown as such in reports
+    Low,
+    Medium,
+    /// Declared by the installer for this purpose
+    High,
+}
+
+impl MetadataConfidence {
+    /// Get the display name of the confidence level
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+        }
+    }
+}
+
+impl std::fmt::Display for MetadataConfidence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Source and confidence of one metadata value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldProvenance {
+    pub source: MetadataSource,
+    pub confidence: MetadataConfidence,
+}
+
+impl FieldProvenance {
+    /// Provenance with the confidence of its source
+    pub fn from_source(source: MetadataSource) -> Self {
+        Self {
+            source,
+            confidence: source.confidence(),
+        }
+    }
+
+    /// Whether the value is a guess rather than read from the installer
+    pub fn is_low_confidence(&self) -> bool {
+        self.confidence == MetadataConfidence::Low
+    }
+}
+
+/// Provenance of the identity fields of [`InstallerMetadata`]
+///
+/// A field without provenance has no value, or was produced by an analyzer
+/// that doesn't record where its values come from.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetadataProvenance {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub product_name: Option<FieldProvenance>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub product_version: Option<FieldProvenance>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manufacturer: Option<FieldProvenance>,
+}
+
+impl MetadataProvenance {
+    /// Provenance of a field
+    pub fn get(&self, field: MetadataField) -> Option<FieldProvenance> {
+        match field {
+            MetadataField::ProductName => self.product_name,
+            MetadataField::ProductVersion => self.product_version,
+            MetadataField::Manufacturer => self.manufacturer,
+        }
+    }
+
+    /// Replace the provenance of a field
+    pub fn set(&mut self, field: MetadataField, provenance: Option<FieldProvenance>) {
+        match field {
+            MetadataField::ProductName => self.product_name = provenance,
+            MetadataField::ProductVersion => self.product_version = provenance,
+            MetadataField::Manufacturer => self.manufacturer = provenance,
+        }
+    }
+
+    /// Fields whose values are guesses
+    pub fn low_confidence_fields(&self) -> Vec<MetadataField> {
+        MetadataField::ALL
+            .into_iter()
+            .filter(|&field| self.get(field).is_some_and(|p| p.is_low_confidence()))
+            .collect()
+    }
+
+    /// Whether no provenance was recorded
+    pub fn is_empty(&self) -> bool {
+        MetadataField::ALL
+            .into_iter()
+            .all(|field| self.get(field).is_none())
+    }
+}
+
+/// Structured format-specific data, serialized with a `kind` tag
+///
+/// The flat `properties` map stays the catch-all; these schemas carry the
+/// fields consumers most often need without parsing joined strings.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FormatDetails {
+    /// Windows Installer database
+    Msi(MsiDetails),
+    /// MSIX/AppX package manifest
+    Msix(MsixDetails),
+    /// Python wheel metadata
+    Wheel(WheelDetails),
+    /// Layout of a portable application archive
+    PortableApp(PortableAppProfile),
+}
+
+/// Identity and install behaviour read from an MSI database
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MsiDetails {
+    /// `ProductCode` property
+    pub product_code: Option<String>,
+    /// `UpgradeCode` property
+    pub upgrade_code: Option<String>,
+    /// `ProductLanguage` property (LCID)
+    pub product_language: Option<String>,
+    /// `ALLUSERS` property
+    pub all_users: Option<String>,
+    /// ForceReboot/ScheduleReboot actions with their conditions
+    #[serde(default)]
+    pub reboot_actions: Vec<String>,
+    /// LaunchCondition entries as `Description [Condition]`
+    #[serde(default)]
+    pub launch_conditions: Vec<String>,
+    /// Streams of the `Binary` and `Icon` tables
+    #[serde(default)]
+    pub embedded_streams: Vec<MsiStream>,
+    /// Authoring summary of packages built with the WiX Toolset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wix: Option<WixAuthoring>,
+}
+
+/// Authoring of a WiX-built MSI, reconstructed from its tables
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WixAuthoring {
+    /// WiX Toolset version, like `4.x`, when the package names it
+    pub toolset_version: Option<String>,
+    /// Rows of the `Feature` table
+    pub feature_count: usize,
+    /// Rows of the `Component` table
+    pub component_count: usize,
+    /// WiX extensions used, like `Util Extension`
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    /// WixUI dialog set, like `Install Directory UI`
+    pub ui: Option<String>,
+    /// `MajorUpgrade` configuration, if the package upgrades its own older versions
+    pub major_upgrade: Option<WixMajorUpgrade>,
+    /// `perMachine`, `perUser` or `perUserOrMachine`, from `ALLUSERS`
+    pub install_scope: String,
+}
+
+/// `MajorUpgrade` element as reconstructed from the `Upgrade` table
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WixMajorUpgrade {
+    /// Installed older versions are removed
+    pub removes_older: bool,
+    /// Installing over the same version upgrades it (`AllowSameVersionUpgrades`)
+    pub allows_same_version: bool,
+    /// Installed newer versions are detected (no `AllowDowngrades`)
+    pub detects_newer: bool,
+    /// Installation fails when a newer version is installed (`DowngradeErrorMessage`)
+    pub blocks_downgrades: bool,
+    /// Features of the older version are migrated
+    pub migrates_features: bool,
+    /// Action `RemoveExistingProducts` is scheduled after, like `InstallValidate`
+    pub schedule: Option<String>,
+}
+
+/// Stream of an MSI `Binary` or `Icon` table row
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MsiStream {
+    /// `Binary` or `Icon`
+    pub table: String,
+    /// `Name` key of the row
+    pub name: String,
+    pub size: u64,
+    /// Hex-encoded SHA256 of the content
+    pub sha256: String,
+    /// Content type sniffed from the header, like `exe`, `dll` or `ico`
+    pub kind: String,
+    /// Custom actions running code from this stream
+    #[serde(default)]
+    pub custom_actions: Vec<String>,
+}
+
+impl MsiStream {
+    /// Whether custom actions run this stream as an executable or DLL
+    pub fn is_custom_action_code(&self) -> bool {
+        !self.custom_actions.is_empty() && matches!(self.kind.as_str(), "exe" | "dll")
+    }
+}
+
+/// Package identity and requirements from an AppxManifest
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MsixDetails {
+    /// `Identity/@Name`
+    pub identity_name: String,
+    /// `Identity/@Publisher` distinguished name
+    pub identity_publisher: String,
+    /// `Identity/@Version`
+    pub identity_version: String,
+    /// `Identity/@ProcessorArchitecture`
+    pub processor_architecture: Option<String>,
+    /// MinVersion of the first target device family
+    pub min_version: Option<String>,
+    /// MaxVersionTested of the first target device family
+    pub max_version_tested: Option<String>,
+    /// Target device family names
+    #[serde(default)]
+    pub target_device_families: Vec<String>,
+    /// Declared capability names
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    /// Names of framework packages the package depends on
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+    /// Application IDs
+    #[serde(default)]
+    pub applications: Vec<String>,
+}
+
+/// Distribution metadata and tags from a Python wheel
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WheelDetails {
+    /// Distribution name
+    pub name: String,
+    /// Distribution version
+    pub version: String,
+    /// `Requires-Python` specifier
+    pub requires_python: Option<String>,
+    /// `Requires-Dist` entries as PEP 508 requirement strings
+    #[serde(default)]
+    pub requires_dist: Vec<String>,
+    /// `Provides-Extra` names
+    #[serde(default)]
+    pub provides_extra: Vec<String>,
+    /// Compatibility tags as `python-abi-platform`
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// `Root-Is-Purelib` from the WHEEL file
+    pub root_is_purelib: Option<bool>,
+    /// Tool that built the wheel
+    pub generator: Option<String>,
+    /// Launchers an installer creates for console and GUI scripts, by install path
+    #[serde(default)]
+    pub script_shims: Vec<String>,
+    /// Compiled extension modules
+    #[serde(default)]
+    pub native_extensions: Vec<WheelExtensionModule>,
+}
+
+/// Compiled extension module packaged in a wheel
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WheelExtensionModule {
+    /// Path inside the wheel
+    pub path: String,
+    /// Architecture from the PE, ELF or Mach-O header
+    pub architecture: Option<String>,
+    /// ABI tag in the file name, e.g. `cp311-win_amd64`
+    pub abi_tag: Option<String>,
+}
+
+/// Portable application found in a plain archive, with how to launch it
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PortableAppProfile {
+    /// Directory of the application inside the archive, empty for the archive root
+    pub app_dir: String,
+    /// Executable to launch, relative to the archive root
+    pub primary_executable: String,
+    /// Architecture of the primary executable
+    pub architecture: Option<String>,
+    /// Runtime the application is built on, e.g. `.NET Framework` or `Electron`
+    pub runtime: Option<String>,
+    /// Other executables in the application directory
+    #[serde(default)]
+    pub other_executables: Vec<String>,
+    /// Configuration files shipped with the application
+    #[serde(default)]
+    pub config_files: Vec<String>,
+    /// Whether the application keeps its settings beside itself: `yes`, `likely` or `unknown`
+    pub writes_beside_itself: String,
+    /// Marker files and directories behind `writes_beside_itself`
+    #[serde(default)]
+    pub write_indicators: Vec<String>,
+    /// Command line launching the application from `{extract_dir}`
+    pub launch_command: String,
+    /// Working directory of the launched application
+    pub working_directory: String,
 }
 
 /// File entry in an installer
@@ -65,6 +708,19 @@ pub struct FileEntry {
     pub attributes: FileAttributes,
     /// Compression method used
     pub compression: Option<String>,
+    /// Entry is guessed from common patterns rather than read from the installer
+    #[serde(default)]
+    pub is_speculative: bool,
+    /// Target architecture of PE files (`x86`, `x64`, `arm64`, `anycpu`, ...)
+    #[serde(default)]
+    pub architecture: Option<String>,
+    /// Last modification time recorded in the installer, when the format keeps one
+    #[serde(default)]
+    pub modified: Option<DateTime<Utc>>,
+    /// Sibling file the entry is stored in, for installers split across
+    /// several files; `None` when it is stored in the installer itself
+    #[serde(default)]
+    pub source: Option<String>,
 }
 
 /// File attributes
@@ -76,12 +732,109 @@ pub struct FileAttributes {
     pub executable: bool,
 }
 
+/// Dependency declared by a package
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackageDependency {
+    /// Package name
+    pub name: String,
+    /// Version requirement, e.g. `>=1.0`
+    pub version_spec: Option<String>,
+    /// Condition under which the dependency applies (environment marker, extra, ...)
+    pub condition: Option<String>,
+    /// Dependency is only required by an optional feature
+    pub optional: bool,
+}
+
+/// Node of a dependency graph
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DependencyNode {
+    /// Normalized package identifier
+    pub id: String,
+    /// Display name
+    pub name: String,
+    /// Version, known for the analyzed package only
+    pub version: Option<String>,
+    /// Node is the analyzed package
+    pub is_root: bool,
+}
+
+/// Edge of a dependency graph, pointing from dependent to dependency
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DependencyEdge {
+    pub from: String,
+    pub to: String,
+    pub version_spec: Option<String>,
+    pub condition: Option<String>,
+    pub optional: bool,
+}
+
+/// Dependency graph of an analyzed package
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DependencyGraph {
+    pub nodes: Vec<DependencyNode>,
+    pub edges: Vec<DependencyEdge>,
+}
+
+impl DependencyGraph {
+    /// Build the graph of a package and its declared dependencies
+    pub fn from_dependencies(
+        root_name: &str,
+        root_version: Option<&str>,
+        dependencies: &[PackageDependency],
+    ) -> Self {
+        let root_id = Self::node_id(root_name);
+        let mut graph = Self {
+            nodes: vec![DependencyNode {
+                id: root_id.clone(),
+                name: root_name.to_string(),
+                version: root_version.map(|v| v.to_string()),
+                is_root: true,
+            }],
+            edges: Vec::new(),
+        };
+
+        for dependency in dependencies {
+            let id = Self::node_id(&dependency.name);
+            if !graph.nodes.iter().any(|node| node.id == id) {
+                graph.nodes.push(DependencyNode {
+                    id: id.clone(),
+                    name: dependency.name.clone(),
+                    version: None,
+                    is_root: false,
+                });
+            }
+            graph.edges.push(DependencyEdge {
+                from: root_id.clone(),
+                to: id,
+                version_spec: dependency.version_spec.clone(),
+                condition: dependency.condition.clone(),
+                optional: dependency.optional,
+            });
+        }
+
+        graph
+    }
+
+    /// Check whether the graph has no dependencies
+    pub fn is_empty(&self) -> bool {
+        self.edges.is_empty()
+    }
+
+    /// Normalize a package name into a node identifier
+    fn node_id(name: &str) -> String {
+        name.to_lowercase().replace(['_', '.'], "-")
+    }
+}
+
 /// Registry operation types
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum RegistryOperation {
     CreateKey {
         key_path: String,
         timestamp: DateTime<Utc>,
+        /// Process that performed the operation, if captured
+        #[serde(default)]
+        process_id: Option<u32>,
     },
     SetValue {
         key_path: String,
@@ -89,18 +842,49 @@ pub enum RegistryOperation {
         value_type: RegistryValueType,
         value_data: RegistryValue,
         timestamp: DateTime<Utc>,
+        /// Process that performed the operation, if captured
+        #[serde(default)]
+        process_id: Option<u32>,
     },
     DeleteKey {
         key_path: String,
         timestamp: DateTime<Utc>,
+        /// Process that performed the operation, if captured
+        #[serde(default)]
+        process_id: Option<u32>,
     },
     DeleteValue {
         key_path: String,
         value_name: String,
         timestamp: DateTime<Utc>,
+        /// Process that performed the operation, if captured
+        #[serde(default)]
+        process_id: Option<u32>,
     },
 }
 
+impl RegistryOperation {
+    /// Key the operation applies to, without the value name
+    pub fn key_path(&self) -> &str {
+        match self {
+            Self::CreateKey { key_path, .. }
+            | Self::SetValue { key_path, .. }
+            | Self::DeleteKey { key_path, .. }
+            | Self::DeleteValue { key_path, .. } => key_path,
+        }
+    }
+
+    /// Process that performed the operation, if captured
+    pub fn process_id(&self) -> Option<u32> {
+        match self {
+            Self::CreateKey { process_id, .. }
+            | Self::SetValue { process_id, .. }
+            | Self::DeleteKey { process_id, .. }
+            | Self::DeleteValue { process_id, .. } => *process_id,
+        }
+    }
+}
+
 /// Registry value types
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum RegistryValueType {
@@ -129,28 +913,56 @@ pub enum FileOperation {
         path: PathBuf,
         size: u64,
         timestamp: DateTime<Utc>,
+        /// Process that performed the operation, if captured
+        #[serde(default)]
+        process_id: Option<u32>,
     },
     Write {
         path: PathBuf,
         bytes_written: u64,
         timestamp: DateTime<Utc>,
+        /// Process that performed the operation, if captured
+        #[serde(default)]
+        process_id: Option<u32>,
     },
     Delete {
         path: PathBuf,
         timestamp: DateTime<Utc>,
+        /// Process that performed the operation, if captured
+        #[serde(default)]
+        process_id: Option<u32>,
     },
     Move {
         from_path: PathBuf,
         to_path: PathBuf,
         timestamp: DateTime<Utc>,
+        /// Process that performed the operation, if captured
+        #[serde(default)]
+        process_id: Option<u32>,
     },
     SetAttributes {
         path: PathBuf,
         attributes: FileAttributes,
         timestamp: DateTime<Utc>,
+        /// Process that performed the operation, if captured
+        #[serde(default)]
+        process_id: Option<u32>,
     },
 }
 
+impl FileOperation {
+    /// Process that performed the operation, if captured
+    pub fn process_id(&self) -> Option<u32> {
+        match self {
+            Self::Create { process_id, .. }
+            | Self::Write { process_id, .. }
+            | Self::Delete { process_id, .. }
+            | Self::Move { process_id, .. }
+            | Self::SetAttributes { process_id, .. } => *process_id,
+        }
+    }
+}
+
 /// Process operation types
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProcessOperation {
@@ -177,6 +989,9 @@ pub struct NetworkOperation {
     pub protocol: String,
     pub bytes_transferred: u64,
     pub timestamp: DateTime<Utc>,
+    /// Process that performed the operation
+    #[serde(default)]
+    pub process_id: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -187,8 +1002,227 @@ pub enum NetworkOpType {
     Disconnect,
 }
 
-/// Complete analysis result
+/// WMI activity types (from dynamic monitoring)
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum WmiOperation {
+    /// `Win32_Process.Create` call; the new process is a child of `WmiPrvSE.exe`
+    ProcessCreate {
+        namespace: String,
+        command_line: String,
+        /// Process the provider started, if captured
+        created_process_id: Option<u32>,
+        timestamp: DateTime<Utc>,
+        /// Process that called the method
+        process_id: Option<u32>,
+    },
+    /// Permanent event subscription binding an `__EventFilter` to a consumer
+    EventSubscription {
+        namespace: String,
+        filter_name: String,
+        query: String,
+        /// Consumer class and name, e.g. `CommandLineEventConsumer.Name="updater"`
+        consumer: String,
+        timestamp: DateTime<Utc>,
+        process_id: Option<u32>,
+    },
+    /// Any other WQL query or method execution
+    Query {
+        namespace: String,
+        query: String,
+        timestamp: DateTime<Utc>,
+        process_id: Option<u32>,
+    },
+}
+
+impl WmiOperation {
+    /// Process that performed the operation, if captured
+    pub fn process_id(&self) -> Option<u32> {
+        match self {
+            Self::ProcessCreate { process_id, .. }
+            | Self::EventSubscription { process_id, .. }
+            | Self::Query { process_id, .. } => *process_id,
+        }
+    }
+}
+
+/// PowerShell script block logged while the installer ran (event 4104)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PowerShellScriptBlock {
+    pub script_block_id: String,
+    /// Complete text, joined from the parts large blocks are logged in
+    pub text: String,
+    /// Script file the block came from, if any
+    pub path: Option<String>,
+    pub timestamp: DateTime<Utc>,
+    pub process_id: Option<u32>,
+}
+
+/// Indicator class of an extracted string
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IocKind {
+    Url,
+    RegistryKey,
+    /// Named mutex, e.g. `Global\MyAppMutex`
+    Mutex,
+    FilePath,
+    /// Known cryptographic table or key material
+    CryptoConstant,
+}
+
+impl IocKind {
+    /// Get the display name of the indicator class
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Url => "URL",
+            Self::RegistryKey => "Registry key",
+            Self::Mutex => "Mutex",
+            Self::FilePath => "Path",
+            Self::CryptoConstant => "Crypto constant",
+        }
+    }
+}
+
+/// How an extracted string is stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StringEncoding {
+    Ascii,
+    Utf16,
+    /// Byte pattern rather than text; the value names the pattern
+    Binary,
+}
+
+/// String extracted from the installer or one of its payloads
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExtractedString {
+    pub value: String,
+    pub encoding: StringEncoding,
+    /// `installer` or the path of the packaged file the string was found in
+    pub source: String,
+    /// Offset of the first occurrence within the source
+    pub offset: u64,
+    /// Number of occurrences across all sources
+    pub occurrences: usize,
+    pub ioc: Option<IocKind>,
+}
+
+/// Strings extracted for the report appendix
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StringAppendix {
+    /// Scanned sources, `installer` first
+    pub sources: Vec<String>,
+    /// Unique strings, indicators first
+    pub strings: Vec<ExtractedString>,
+    /// Strings dropped as noise
+    pub filtered: usize,
+    /// Unique strings left out because of the size limit
+    pub omitted: usize,
+}
+
+impl StringAppendix {
+    /// Strings classified as indicators
+    pub fn indicators(&self) -> impl Iterator<Item = &ExtractedString> {
+        self.strings.iter().filter(|s| s.ioc.is_some())
+    }
+}
+
+/// What a previewed text file is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PreviewKind {
+    /// Settings such as INI, `.config` or YAML files
+    Config,
+    /// Package or application manifest
+    Manifest,
+    /// License agreement or copyright notice
+    License,
+}
+
+impl PreviewKind {
+    /// Get the display name of the file kind
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Config => "Configuration",
+            Self::Manifest => "Manifest",
+            Self::License => "License",
+        }
+    }
+}
+
+/// Beginning of a small text file packaged in the installer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FilePreview {
+    /// Path within the installer
+    pub path: String,
+    pub kind: PreviewKind,
+    /// Size of the packaged file
+    pub size: u64,
+    /// Text of the file, up to the preview limit
+    pub content: String,
+    /// Whether the file continues past `content`
+    pub truncated: bool,
+}
+
+/// Kind of a named synchronization object
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncObjectKind {
+    Mutex,
+    Event,
+    Semaphore,
+    NamedPipe,
+}
+
+impl SyncObjectKind {
+    /// Get the display name of the object kind
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Mutex => "mutex",
+            Self::Event => "event",
+            Self::Semaphore => "semaphore",
+            Self::NamedPipe => "pipe",
+        }
+    }
+}
+
+/// How a synchronization object was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncObjectOrigin {
+    /// Name referenced by an installer binary or script
+    Static,
+    /// Created or opened while the installer ran
+    Runtime,
+    /// Referenced statically and observed at runtime
+    Both,
+}
+
+/// Named mutex, event, semaphore or pipe used by an installer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncObject {
+    pub kind: SyncObjectKind,
+    pub name: String,
+    /// `installer`, a packaged file path, or the process that used the object
+    pub source: String,
+    pub origin: SyncObjectOrigin,
+}
+
+/// Call of a monitored Windows API by an installer process
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiCall {
+    /// Exported name, e.g. `SetWindowsHookExW`
+    pub api: String,
+    /// Argument that tells how the API was used, e.g. the hook type `WH_KEYBOARD_LL`
+    pub argument: Option<String>,
+    pub process_name: String,
+}
+
+/// Complete analysis result
+///
+/// The default is an empty static result of an unknown format, with a nil
+/// session ID and timestamps at the Unix epoch.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AnalysisResult {
     /// Unique analysis session ID
     pub session_id: Uuid,
@@ -206,12 +1240,188 @@ pub struct AnalysisResult {
     pub process_operations: Vec<ProcessOperation>,
     /// Network operations (from dynamic monitoring)
     pub network_operations: Vec<NetworkOperation>,
+    /// WMI operations (from dynamic monitoring)
+    #[serde(default)]
+    pub wmi_operations: Vec<WmiOperation>,
+    /// PowerShell script blocks (from dynamic monitoring)
+    #[serde(default)]
+    pub powershell_script_blocks: Vec<PowerShellScriptBlock>,
     /// Analysis timestamp
     pub analyzed_at: DateTime<Utc>,
     /// Analysis duration
     pub analysis_duration: std::time::Duration,
     /// Whether dynamic analysis was performed
     pub dynamic_analysis: bool,
+    /// Capabilities of the analyzer that produced the result
+    #[serde(default)]
+    pub analyzer_capabilities: Option<AnalyzerCapabilities>,
+    /// Declared package dependencies, for formats that have them
+    #[serde(default)]
+    pub dependencies: Option<DependencyGraph>,
+    /// Extracted strings, when requested
+    #[serde(default)]
+    pub strings: Option<StringAppendix>,
+    /// Resource usage of the installer process tree, from sandbox runs
+    #[serde(default)]
+    pub resource_usage: Option<ResourceUsage>,
+    /// How the analysis was produced
+    #[serde(default)]
+    pub audit: Option<AuditTrail>,
+    /// Beginnings of small configuration, manifest and license files
+    #[serde(default)]
+    pub previews: Vec<FilePreview>,
+}
+
+/// Resource usage of the installer process tree at one point of a sandbox run
+///
+/// CPU time and bytes written are cumulative since the installer started;
+/// memory and handles are the values at the time of the sample.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceSample {
+    /// Time since the installer started, in milliseconds
+    pub elapsed_ms: u64,
+    /// User and kernel CPU time of all processes of the tree, in milliseconds
+    pub cpu_time_ms: u64,
+    /// Working set of all processes of the tree, in bytes
+    pub memory_bytes: u64,
+    /// Bytes written to disk by the tree
+    pub disk_bytes_written: u64,
+    /// Open handles of all processes of the tree
+    pub handle_count: u32,
+    /// Processes in the tree
+    pub process_count: u32,
+}
+
+/// Resource usage samples of a sandbox run, oldest first
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceUsage {
+    /// Time between samples, in milliseconds
+    pub interval_ms: u64,
+    pub samples: Vec<ResourceSample>,
+}
+
+impl ResourceUsage {
+    /// Time from the start of the installer to the last sample
+    pub fn duration(&self) -> std::time::Duration {
+        let elapsed = self.samples.last().map_or(0, |s| s.elapsed_ms);
+        std::time::Duration::from_millis(elapsed)
+    }
+
+    /// CPU time used by the tree over the whole run
+    pub fn cpu_time(&self) -> std::time::Duration {
+        let cpu = self.samples.iter().map(|s| s.cpu_time_ms).max();
+        std::time::Duration::from_millis(cpu.unwrap_or(0))
+    }
+
+    /// Highest working set of the tree
+    pub fn peak_memory_bytes(&self) -> u64 {
+        self.samples
+            .iter()
+            .map(|s| s.memory_bytes)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Bytes written to disk over the whole run
+    pub fn disk_bytes_written(&self) -> u64 {
+        self.samples
+            .iter()
+            .map(|s| s.disk_bytes_written)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Highest number of open handles of the tree
+    pub fn peak_handle_count(&self) -> u32 {
+        self.samples
+            .iter()
+            .map(|s| s.handle_count)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Highest number of processes running at once
+    pub fn peak_process_count(&self) -> u32 {
+        self.samples
+            .iter()
+            .map(|s| s.process_count)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// How an analysis was produced, for chain-of-custody records
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditTrail {
+    /// Name and version of the analyzing tool
+    pub tool: String,
+    pub tool_version: String,
+    /// Command line of the run, with secrets redacted
+    pub command_line: Vec<String>,
+    /// Configuration files that influenced the result
+    pub config_files: Vec<ConfigDigest>,
+    /// SHA-256 over the digests of all configuration files, `None` without any
+    pub config_hash: Option<String>,
+    /// Analyzers that contributed to the result
+    pub analyzers: Vec<AnalyzerVersion>,
+    /// The analyzed file as it was read
+    pub input: InputDigest,
+    /// Host of the run, only recorded when requested
+    pub machine: Option<MachineInfo>,
+    /// Timestamped steps of the run, oldest first
+    pub events: Vec<AuditEvent>,
+}
+
+impl AuditTrail {
+    /// Append a step of the run, timestamped now
+    pub fn record(&mut self, event: impl Into<String>, detail: impl Into<String>) {
+        self.events.push(AuditEvent {
+            timestamp: Utc::now(),
+            event: event.into(),
+            detail: detail.into(),
+        });
+    }
+}
+
+/// Configuration file and its digest
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfigDigest {
+    /// What the file configures, e.g. `policy`
+    pub role: String,
+    pub path: String,
+    pub sha256: String,
+}
+
+/// Analyzer and the version it was built with
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnalyzerVersion {
+    pub name: String,
+    pub version: String,
+}
+
+/// Analyzed file and its digest
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputDigest {
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// Host an analysis ran on
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MachineInfo {
+    pub hostname: Option<String>,
+    pub user: Option<String>,
+    pub os: String,
+    pub arch: String,
+}
+
+/// Timestamped step of an analysis run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEvent {
+    pub timestamp: DateTime<Utc>,
+    pub event: String,
+    pub detail: String,
 }
 
 /// Sandbox configuration
@@ -229,6 +1439,76 @@ pub struct SandboxConfig {
     pub blocked_paths: Vec<PathBuf>,
     /// Enable detailed logging
     pub verbose_logging: bool,
+    /// Sandbox tells to disguise from the installer
+    #[serde(default)]
+    pub fingerprint: FingerprintConfig,
+    /// Date and time the installer sees at start, the host clock when unset
+    #[serde(default)]
+    pub virtual_clock_start: Option<DateTime<Utc>>,
+    /// System noise dropped from captured operations; `None` keeps the raw capture
+    #[serde(default = "default_noise_filter")]
+    pub noise_filter: Option<crate::sandbox::NoiseFilter>,
+}
+
+fn default_noise_filter() -> Option<crate::sandbox::NoiseFilter> {
+    Some(crate::sandbox::NoiseFilter::curated())
+}
+
+/// Sandbox tells that are randomized or hidden so that installers checking
+/// for analysis environments behave as on a real machine
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FingerprintConfig {
+    /// Use a random, plausible computer name
+    pub randomize_machine_name: bool,
+    /// Use a random, plausible user name
+    pub randomize_user_name: bool,
+    /// Report a random system uptime instead of the time since the sandbox booted
+    pub randomize_uptime: bool,
+    /// Hide guest tools processes, services, drivers, registry keys and MAC prefixes of virtual machines
+    pub hide_vm_artifacts: bool,
+}
+
+impl FingerprintConfig {
+    /// Option names accepted by [`FingerprintConfig::parse`]
+    pub const OPTIONS: &'static [&'static str] =
+        &["machine-name", "user-name", "uptime", "vm-artifacts", "all"];
+
+    /// Parse a comma-separated list of options, e.g. `machine-name,uptime` or `all`
+    pub fn parse(list: &str) -> crate::core::Result<Self> {
+        let mut config = Self::default();
+        for option in list.split(',').map(str::trim).filter(|o| !o.is_empty()) {
+            match option.to_lowercase().as_str() {
+                "machine-name" => config.randomize_machine_name = true,
+                "user-name" => config.randomize_user_name = true,
+                "uptime" => config.randomize_uptime = true,
+                "vm-artifacts" => config.hide_vm_artifacts = true,
+                "all" => {
+                    config = Self {
+                        randomize_machine_name: true,
+                        randomize_user_name: true,
+                        randomize_uptime: true,
+                        hide_vm_artifacts: true,
+                    }
+                }
+                other => {
+                    return Err(crate::core::AnalyzerError::config_error(format!(
+                        "Unknown disguise option '{}' (expected one of: {})",
+                        other,
+                        Self::OPTIONS.join(", ")
+                    )))
+                }
+            }
+        }
+        Ok(config)
+    }
+
+    /// Check whether any tell is disguised
+    pub fn is_enabled(&self) -> bool {
+        self.randomize_machine_name
+            || self.randomize_user_name
+            || self.randomize_uptime
+            || self.hide_vm_artifacts
+    }
 }
 
 impl Default for SandboxConfig {
@@ -247,6 +1527,9 @@ impl Default for SandboxConfig {
                 PathBuf::from("C:\\Windows\\SysWOW64"),
             ],
             verbose_logging: false,
+            fingerprint: FingerprintConfig::default(),
+            virtual_clock_start: None,
+            noise_filter: default_noise_filter(),
         }
     }
 }
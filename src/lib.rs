@@ -8,8 +8,10 @@ pub mod api;
 pub mod cli;
 pub mod core;
 pub mod monitoring;
+pub mod policy;
 pub mod reporting;
 pub mod sandbox;
+pub mod telemetry;
 pub mod updater;
 pub mod utils;
 
@@ -1,22 +1,73 @@
 //! Installer Analyzer - Main Entry Point
 
 use clap::Parser;
+use installer_analyzer::api::ApiConfig;
 use installer_analyzer::cli::commands;
+use installer_analyzer::cli::control::{self, ControlChannel};
+use installer_analyzer::cli::i18n::{self, tr_with, Locale};
 use installer_analyzer::cli::output::{init_console, CliOutput};
-use installer_analyzer::cli::{Cli, Commands};
+use installer_analyzer::cli::{Cli, Commands, ReportCommands};
+use installer_analyzer::core::AnalyzerError;
+use installer_analyzer::reporting::{HtmlData, ReportOptions};
+use installer_analyzer::sandbox;
+use installer_analyzer::telemetry::Telemetry;
+use installer_analyzer::updater::UpdateConfig;
 use installer_analyzer::utils;
+use std::path::PathBuf;
 use std::process;
+use std::time::Duration;
 
 #[tokio::main]
 async fn main() {
     // Initialize console for better Windows compatibility
     init_console();
+    utils::crash::install_panic_hook();
 
     let cli = Cli::parse();
+    i18n::set_locale(cli.lang.unwrap_or_else(Locale::detect));
+
+    // Attach the frontend embedding the CLI before anything is printed
+    let control = match cli.control_pipe.as_deref() {
+        Some(name) => match ControlChannel::connect(name).await {
+            Ok(channel) => Some(channel),
+            Err(e) => {
+                CliOutput::error(&tr_with("common.error", &[("error", &e)]));
+                process::exit(e.exit_code());
+            }
+        },
+        None => None,
+    };
+    control::started();
 
     // Only show startup banner and initialize logging for non-info commands
     // This improves performance for --help and info commands
-    let is_info_command = matches!(cli.command, Commands::Info);
+    let is_info_command = matches!(cli.command, Commands::Info { .. });
+
+    // Keep stdout clean for the report when it is printed instead of saved
+    match &cli.command {
+        Commands::Analyze { output, .. }
+            if output.as_deref().is_none_or(commands::is_stdout_path) =>
+        {
+            CliOutput::status_to_stderr()
+        }
+        Commands::Report {
+            command: ReportCommands::Merge { output, .. },
+        } if output.as_deref().is_none_or(commands::is_stdout_path) => {
+            CliOutput::status_to_stderr()
+        }
+        Commands::Report {
+            command: ReportCommands::Upgrade { output, .. },
+        } if output.as_deref().is_some_and(commands::is_stdout_path) => {
+            CliOutput::status_to_stderr()
+        }
+        Commands::Query { .. } | Commands::Correlate { .. } => CliOutput::status_to_stderr(),
+        Commands::Extract { output, .. }
+            if output.as_deref().is_none_or(commands::is_stdout_path) =>
+        {
+            CliOutput::status_to_stderr()
+        }
+        _ => {}
+    }
 
     if !is_info_command {
         // Show startup banner
@@ -29,57 +80,377 @@ async fn main() {
         }
 
         // Check for admin privileges for sandbox operations
-        if matches!(cli.command, Commands::Sandbox { .. }) && !utils::is_admin() {
+        if matches!(cli.command, Commands::Sandbox { .. })
+            && sandbox::is_supported()
+            && !utils::is_admin()
+        {
             eprintln!(
                 "Warning: Sandbox analysis requires administrator privileges for full functionality."
             );
             eprintln!("Some features may not work correctly without elevated permissions.");
         }
+
+        // Reclaim directories of crashed runs; `clean` reports them itself
+        if !matches!(cli.command, Commands::Clean { .. }) {
+            utils::Workspace::new().housekeep();
+        }
+    }
+
+    // Telemetry is off unless opted in
+    let telemetry_opt_out = cli.no_telemetry || Telemetry::do_not_track();
+    let telemetry =
+        Telemetry::configure(cli.telemetry, telemetry_opt_out, cli.telemetry_url.clone());
+    if cli.telemetry && !telemetry_opt_out && telemetry.is_none() {
+        CliOutput::warning(i18n::tr("telemetry.no_endpoint"));
     }
 
+    // Events still being sent are awaited once the command is done
+    let pending_telemetry = telemetry.clone();
+
     // Execute command
-    let result = match cli.command {
-        Commands::Analyze {
-            input,
-            output,
-            format,
-            open,
-        } => commands::handle_analyze(&input, output.as_deref(), format.as_deref(), open).await,
-        Commands::Sandbox {
-            input,
-            output,
-            format,
-            timeout,
-            network,
-            open,
-        } => {
-            commands::handle_sandbox(
-                &input,
-                output.as_deref(),
-                format.as_deref(),
+    let command = async {
+        match cli.command {
+            Commands::Analyze {
+                input,
+                files,
+                input_name,
+                output,
+                format,
+                open,
+                export_tree,
+                tree_format,
+                include_speculative,
+                export_deps,
+                export_deployment,
+                combined,
+                artifacts_dir,
+                force,
+                attach_artifacts,
+                bundle,
+                password,
+                db,
+                policy,
+                vendor_kb,
+                carve_recursive,
+                extract_msi,
+                dump_msi_streams,
+                check_env,
+                extract_strings,
+                hash,
+                compress_data,
+                external_data,
+                omit_file_hashes,
+                depth,
+                audit_machine,
+                sign_key,
+                redact,
+                quiet,
+            } => {
+                let options = commands::AnalyzeOptions {
+                    export_tree,
+                    tree_format,
+                    include_speculative,
+                    export_deps,
+                    export_deployment,
+                    artifacts_dir,
+                    force,
+                    attach_artifacts,
+                    bundle,
+                    bundle_password: password,
+                    db,
+                    policy,
+                    vendor_kb,
+                    carve_recursive,
+                    extract_msi,
+                    dump_msi_streams,
+                    check_env,
+                    extract_strings,
+                    hash_algorithms: hash,
+                    report: ReportOptions {
+                        html_data: if external_data {
+                            HtmlData::External
+                        } else if compress_data {
+                            HtmlData::Compressed
+                        } else {
+                            HtmlData::Inline
+                        },
+                        omit_file_hashes,
+                    },
+                    depth,
+                    audit_machine,
+                    sign_key,
+                    redact,
+                    telemetry,
+                    quiet,
+                };
+                let mut inputs: Vec<_> = input.into_iter().chain(files).collect();
+
+                // Spool stdin to a temporary file kept alive until the analysis is done
+                match resolve_stdin_input(&mut inputs, input_name.as_deref()).await {
+                    Err(e) => Err(e),
+                    Ok(_stdin_input) => {
+                        if let [input] = inputs.as_slice() {
+                            commands::handle_analyze_with_options(
+                                input,
+                                output.as_deref(),
+                                format.as_deref(),
+                                open,
+                                &options,
+                            )
+                            .await
+                        } else {
+                            commands::handle_analyze_multiple(
+                                &inputs,
+                                output.as_deref(),
+                                format.as_deref(),
+                                open,
+                                combined,
+                                &options,
+                            )
+                            .await
+                        }
+                    }
+                }
+            }
+            Commands::Sandbox {
+                input,
+                output,
+                format,
                 timeout,
                 network,
                 open,
-            )
-            .await
+                disguise,
+                fake_time,
+                runs,
+                noise_filter,
+                raw,
+                tags,
+                redact,
+            } => {
+                commands::handle_sandbox_with_options(
+                    &input,
+                    output.as_deref(),
+                    format.as_deref(),
+                    timeout,
+                    network,
+                    open,
+                    &commands::SandboxOptions {
+                        disguise,
+                        fake_time,
+                        runs,
+                        noise_filter,
+                        raw,
+                        tags,
+                        redact,
+                    },
+                )
+                .await
+            }
+            Commands::Batch {
+                input_dir,
+                output_dir,
+                format,
+                sandbox,
+                db,
+                policy,
+                vendor_kb,
+                retries,
+                quarantine,
+            } => {
+                let options = commands::AnalyzeOptions {
+                    db,
+                    policy,
+                    vendor_kb,
+                    telemetry,
+                    ..Default::default()
+                };
+                let batch = commands::BatchOptions {
+                    retries,
+                    quarantine,
+                };
+                commands::handle_batch_with_options(
+                    &input_dir,
+                    &output_dir,
+                    format.as_deref(),
+                    sandbox,
+                    &options,
+                    &batch,
+                )
+                .await
+            }
+            Commands::Query {
+                db,
+                component,
+                hash,
+                vendors,
+                vendor,
+                json,
+            } => {
+                let options = commands::QueryOptions {
+                    component,
+                    hash,
+                    vendors,
+                    vendor,
+                    json,
+                };
+                commands::handle_query(&db, &options).await
+            }
+            Commands::Correlate { inputs, db, json } => {
+                commands::handle_correlate(&inputs, db.as_deref(), json).await
+            }
+            Commands::Extract {
+                input,
+                path,
+                output,
+            } => commands::handle_extract(&input, &path, output.as_deref()).await,
+            Commands::Report {
+                command:
+                    ReportCommands::Merge {
+                        static_input,
+                        dynamic_input,
+                        output,
+                        format,
+                        open,
+                    },
+            } => {
+                commands::handle_report_merge(
+                    &static_input,
+                    &dynamic_input,
+                    output.as_deref(),
+                    format.as_deref(),
+                    open,
+                )
+                .await
+            }
+            Commands::Report {
+                command: ReportCommands::Upgrade { input, output },
+            } => commands::handle_report_upgrade(&input, output.as_deref()).await,
+            Commands::Report {
+                command:
+                    ReportCommands::Verify {
+                        input,
+                        key,
+                        signature,
+                    },
+            } => commands::handle_report_verify(&input, &key, signature.as_deref()).await,
+            Commands::Info {
+                platform_capabilities: true,
+            } => commands::handle_platform_capabilities(),
+            Commands::Info {
+                platform_capabilities: false,
+            } => commands::handle_info().await,
+            Commands::Update {
+                check_only,
+                force,
+                yes,
+                cache_ttl,
+                github_token,
+                manifest_url,
+                allow_unverified,
+                rollback,
+                data,
+                data_url,
+                data_key,
+            } => {
+                if rollback {
+                    commands::handle_rollback(yes).await
+                } else if let (true, Some(url), Some(key)) = (data, data_url, data_key) {
+                    commands::handle_update_data(&url, &key, check_only, force).await
+                } else {
+                    let config = UpdateConfig {
+                        cache_ttl_seconds: cache_ttl,
+                        github_token,
+                        manifest_url,
+                        allow_unverified,
+                        ..UpdateConfig::default()
+                    };
+                    commands::handle_update(config, check_only, force, yes).await
+                }
+            }
+            Commands::Clean {
+                all,
+                older_than,
+                dry_run,
+            } => commands::handle_clean(all, older_than, dry_run).await,
+            Commands::Serve {
+                host,
+                port,
+                lease,
+                db,
+                static_only,
+                ui,
+                store_dir,
+                reanalyze_every,
+                notify_url,
+                max_upload,
+            } => {
+                let config = ApiConfig {
+                    host,
+                    port,
+                    lease: Duration::from_secs(lease * 60),
+                    db,
+                    static_only,
+                    ui,
+                    store_dir,
+                    reanalyze_every: reanalyze_every
+                        .map(|hours| Duration::from_secs(hours * 60 * 60)),
+                    notify_url,
+                    max_upload: max_upload * 1024 * 1024,
+                };
+                commands::handle_serve(config).await
+            }
+            Commands::Worker {
+                server,
+                name,
+                static_only,
+                poll_interval,
+            } => {
+                let options = commands::WorkerOptions {
+                    name: name.unwrap_or_else(commands::default_worker_name),
+                    static_only,
+                    poll_interval: Duration::from_secs(poll_interval),
+                };
+                commands::handle_worker(&server, &options).await
+            }
         }
-        Commands::Batch {
-            input_dir,
-            output_dir,
-            format,
-            sandbox,
-        } => commands::handle_batch(&input_dir, &output_dir, format.as_deref(), sandbox).await,
-        Commands::Info => commands::handle_info().await,
-        Commands::Update {
-            check_only,
-            force,
-            yes,
-        } => commands::handle_update(check_only, force, yes).await,
     };
+    let result = match control {
+        Some(channel) => {
+            let result = channel.run(command).await;
+            channel.complete(&result).await;
+            result
+        }
+        None => command.await,
+    };
+    if let Some(telemetry) = &pending_telemetry {
+        telemetry.flush().await;
+    }
 
     // Handle result
     if let Err(e) = result {
-        CliOutput::error(&format!("Error: {}", e));
-        process::exit(1);
+        CliOutput::error(&tr_with("common.error", &[("error", &e)]));
+        process::exit(e.exit_code());
     }
 }
+
+/// Replace a `-` input with a temporary file holding the installer read from stdin
+async fn resolve_stdin_input(
+    inputs: &mut [PathBuf],
+    input_name: Option<&str>,
+) -> installer_analyzer::core::Result<Option<utils::StdinInput>> {
+    let mut stdin_inputs = inputs.iter_mut().filter(|input| input.as_os_str() == "-");
+    let Some(input) = stdin_inputs.next() else {
+        return Ok(None);
+    };
+    if stdin_inputs.next().is_some() {
+        return Err(AnalyzerError::config_error(
+            "stdin (`-`) can only be used as one input",
+        ));
+    }
+
+    let name = input_name.unwrap_or("stdin");
+    CliOutput::info(&tr_with("analyze.reading_stdin", &[("name", &name)]));
+    let stdin_input = utils::StdinInput::spool(name).await?;
+    *input = stdin_input.path().to_path_buf();
+    Ok(Some(stdin_input))
+}
@@ -1,7 +1,12 @@
 //! System monitoring components
 
-use crate::core::{FileOperation, RegistryOperation, Result};
+use crate::core::{
+    ApiCall, FileOperation, PowerShellScriptBlock, RegistryOperation, ResourceSample,
+    ResourceUsage, Result, SyncObject, SyncObjectKind, SyncObjectOrigin, WmiOperation,
+};
 use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::time::Duration;
 
 /// Trait for system monitors
 #[async_trait]
@@ -103,3 +108,369 @@ impl SystemMonitor for RegistryMonitor {
         self.active
     }
 }
+
+/// Monitor of named mutexes, events, semaphores and pipes (placeholder)
+pub struct SyncObjectMonitor {
+    active: bool,
+    objects: Vec<SyncObject>,
+}
+
+impl Default for SyncObjectMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SyncObjectMonitor {
+    pub fn new() -> Self {
+        Self {
+            active: false,
+            objects: Vec::new(),
+        }
+    }
+
+    /// Record an object created or opened by a monitored process
+    pub fn record(&mut self, kind: SyncObjectKind, name: &str, process_name: &str) {
+        if !self.active
+            || self
+                .objects
+                .iter()
+                .any(|o| o.kind == kind && o.name == name)
+        {
+            return;
+        }
+        self.objects.push(SyncObject {
+            kind,
+            name: name.to_string(),
+            source: process_name.to_string(),
+            origin: SyncObjectOrigin::Runtime,
+        });
+    }
+
+    pub fn get_objects(&self) -> &[SyncObject] {
+        &self.objects
+    }
+}
+
+#[async_trait]
+impl SystemMonitor for SyncObjectMonitor {
+    async fn start(&mut self) -> Result<()> {
+        tracing::info!("Starting synchronization object monitoring");
+        self.active = true;
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<()> {
+        tracing::info!("Stopping synchronization object monitoring");
+        self.active = false;
+        Ok(())
+    }
+
+    fn is_active(&self) -> bool {
+        self.active
+    }
+}
+
+/// Monitor of clipboard, input hook and screen capture API calls (placeholder)
+pub struct ApiCallMonitor {
+    active: bool,
+    calls: Vec<ApiCall>,
+}
+
+impl Default for ApiCallMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ApiCallMonitor {
+    pub fn new() -> Self {
+        Self {
+            active: false,
+            calls: Vec::new(),
+        }
+    }
+
+    /// Record a call by a monitored process, once per API, argument and process
+    pub fn record(&mut self, api: &str, argument: Option<&str>, process_name: &str) {
+        let call = ApiCall {
+            api: api.to_string(),
+            argument: argument.map(str::to_string),
+            process_name: process_name.to_string(),
+        };
+        if self.active && !self.calls.contains(&call) {
+            self.calls.push(call);
+        }
+    }
+
+    pub fn get_calls(&self) -> &[ApiCall] {
+        &self.calls
+    }
+}
+
+#[async_trait]
+impl SystemMonitor for ApiCallMonitor {
+    async fn start(&mut self) -> Result<()> {
+        tracing::info!("Starting API call monitoring");
+        self.calls.clear();
+        self.active = true;
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<()> {
+        tracing::info!("Stopping API call monitoring");
+        self.active = false;
+        Ok(())
+    }
+
+    fn is_active(&self) -> bool {
+        self.active
+    }
+}
+
+/// Monitor of WMI method calls, queries and event subscriptions (placeholder)
+pub struct WmiMonitor {
+    active: bool,
+    operations: Vec<WmiOperation>,
+}
+
+impl Default for WmiMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WmiMonitor {
+    pub fn new() -> Self {
+        Self {
+            active: false,
+            operations: Vec::new(),
+        }
+    }
+
+    /// Record an operation of the WMI activity trace
+    pub fn record(&mut self, operation: WmiOperation) {
+        if self.active {
+            self.operations.push(operation);
+        }
+    }
+
+    pub fn get_operations(&self) -> &[WmiOperation] {
+        &self.operations
+    }
+}
+
+#[async_trait]
+impl SystemMonitor for WmiMonitor {
+    async fn start(&mut self) -> Result<()> {
+        tracing::info!("Starting WMI activity monitoring");
+        self.operations.clear();
+        self.active = true;
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<()> {
+        tracing::info!("Stopping WMI activity monitoring");
+        self.active = false;
+        Ok(())
+    }
+
+    fn is_active(&self) -> bool {
+        self.active
+    }
+}
+
+/// Monitor of PowerShell script block logging (placeholder)
+///
+/// Large script blocks are logged in several numbered parts; a block is
+/// kept once all its parts arrived, or with the parts seen when monitoring
+/// stops.
+pub struct PowerShellMonitor {
+    active: bool,
+    blocks: Vec<PowerShellScriptBlock>,
+    pending: BTreeMap<String, (PowerShellScriptBlock, BTreeMap<u32, String>, u32)>,
+}
+
+impl Default for PowerShellMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PowerShellMonitor {
+    pub fn new() -> Self {
+        Self {
+            active: false,
+            blocks: Vec::new(),
+            pending: BTreeMap::new(),
+        }
+    }
+
+    /// Record part `number` of `total` of a script block; `block.text` holds the part
+    pub fn record(&mut self, block: PowerShellScriptBlock, number: u32, total: u32) {
+        if !self.active {
+            return;
+        }
+        let (first, parts, expected) = self
+            .pending
+            .entry(block.script_block_id.clone())
+            .or_insert_with(|| (block.clone(), BTreeMap::new(), total.max(1)));
+        parts.insert(number, block.text);
+        if parts.len() as u32 >= *expected {
+            let mut complete = first.clone();
+            complete.text = parts.values().map(String::as_str).collect();
+            self.pending.remove(&complete.script_block_id);
+            self.blocks.push(complete);
+        }
+    }
+
+    pub fn get_blocks(&self) -> &[PowerShellScriptBlock] {
+        &self.blocks
+    }
+}
+
+#[async_trait]
+impl SystemMonitor for PowerShellMonitor {
+    async fn start(&mut self) -> Result<()> {
+        tracing::info!("Starting PowerShell script block monitoring");
+        self.blocks.clear();
+        self.pending.clear();
+        self.active = true;
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<()> {
+        tracing::info!("Stopping PowerShell script block monitoring");
+        self.active = false;
+        for (_, (mut block, parts, _)) in std::mem::take(&mut self.pending) {
+            block.text = parts.values().map(String::as_str).collect();
+            self.blocks.push(block);
+        }
+        self.blocks.sort_by_key(|block| block.timestamp);
+        Ok(())
+    }
+
+    fn is_active(&self) -> bool {
+        self.active
+    }
+}
+
+/// Default time between resource usage samples
+pub const RESOURCE_SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Monitor of CPU time, memory, disk writes and handles of the installer process tree (placeholder)
+pub struct ResourceMonitor {
+    active: bool,
+    interval: Duration,
+    usage: ResourceUsage,
+}
+
+impl Default for ResourceMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ResourceMonitor {
+    pub fn new() -> Self {
+        Self::with_interval(RESOURCE_SAMPLE_INTERVAL)
+    }
+
+    /// Create a monitor sampling the process tree every `interval`
+    pub fn with_interval(interval: Duration) -> Self {
+        Self {
+            active: false,
+            interval,
+            usage: ResourceUsage {
+                interval_ms: interval.as_millis() as u64,
+                samples: Vec::new(),
+            },
+        }
+    }
+
+    /// Time between samples
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Record a sample of the process tree, ignoring samples out of order
+    pub fn record(&mut self, sample: ResourceSample) {
+        let in_order = self
+            .usage
+            .samples
+            .last()
+            .is_none_or(|last| last.elapsed_ms < sample.elapsed_ms);
+        if self.active && in_order {
+            self.usage.samples.push(sample);
+        }
+    }
+
+    /// Samples recorded so far
+    pub fn get_usage(&self) -> &ResourceUsage {
+        &self.usage
+    }
+}
+
+#[async_trait]
+impl SystemMonitor for ResourceMonitor {
+    async fn start(&mut self) -> Result<()> {
+        tracing::info!("Starting resource usage monitoring");
+        self.usage.samples.clear();
+        self.active = true;
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<()> {
+        tracing::info!("Stopping resource usage monitoring");
+        self.active = false;
+        Ok(())
+    }
+
+    fn is_active(&self) -> bool {
+        self.active
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn part(id: &str, second: i64, text: &str) -> PowerShellScriptBlock {
+        PowerShellScriptBlock {
+            script_block_id: id.to_string(),
+            text: text.to_string(),
+            path: None,
+            timestamp: Utc.timestamp_opt(second, 0).unwrap(),
+            process_id: Some(4242),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_join_script_block_parts() {
+        let mut monitor = PowerShellMonitor::new();
+        monitor.record(part("ignored", 0, "Get-Date"), 1, 1);
+        monitor.start().await.unwrap();
+
+        monitor.record(part("a", 2, "Write-Host "), 1, 2);
+        monitor.record(part("b", 1, "Set-ExecutionPolicy Bypass"), 1, 1);
+        assert_eq!(monitor.get_blocks().len(), 1);
+        monitor.record(part("a", 3, "'done'"), 2, 2);
+        monitor.record(part("c", 4, "first half"), 1, 3);
+        monitor.stop().await.unwrap();
+
+        let texts: Vec<&str> = monitor
+            .get_blocks()
+            .iter()
+            .map(|block| block.text.as_str())
+            .collect();
+        assert_eq!(
+            texts,
+            vec![
+                "Set-ExecutionPolicy Bypass",
+                "Write-Host 'done'",
+                "first half"
+            ]
+        );
+    }
+}
@@ -0,0 +1,542 @@
+//! Evaluation of baseline policies against analysis results
+
+use super::{glob_match, Policy};
+use crate::core::AnalysisResult;
+use serde::Serialize;
+
+/// Rule of a policy that a violation refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyRule {
+    AllowedPublishers,
+    DeniedPublishers,
+    ForbiddenPaths,
+    ForbiddenCapabilities,
+    MaxInstallSize,
+}
+
+impl PolicyRule {
+    /// Key of the rule in policy files
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::AllowedPublishers => "allowed_publishers",
+            Self::DeniedPublishers => "denied_publishers",
+            Self::ForbiddenPaths => "forbidden_paths",
+            Self::ForbiddenCapabilities => "forbidden_capabilities",
+            Self::MaxInstallSize => "max_install_size",
+        }
+    }
+}
+
+/// Standing of an installer's publisher with the allow and deny lists
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PublisherStanding {
+    /// Matched by the allow lists
+    Allowed,
+    /// Matched by the deny lists
+    Denied,
+    /// Matched by neither list
+    Unlisted,
+}
+
+impl PublisherStanding {
+    /// Value used in policy reports
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Allowed => "allowed",
+            Self::Denied => "denied",
+            Self::Unlisted => "unlisted",
+        }
+    }
+}
+
+/// Signing certificate recorded by a signature check
+struct Signer<'a> {
+    subject: Option<&'a str>,
+    thumbprint: Option<&'a str>,
+    /// The signature verified against the certificate
+    verified: bool,
+}
+
+/// Single policy violation
+#[derive(Debug, Clone, Serialize)]
+pub struct PolicyViolation {
+    pub rule: PolicyRule,
+    /// What violated the rule (publisher, file path, capability, size)
+    pub subject: String,
+    pub message: String,
+}
+
+/// Outcome of evaluating a policy against one analysis result
+#[derive(Debug, Clone, Serialize)]
+pub struct PolicyReport {
+    pub policy: String,
+    pub source: String,
+    pub passed: bool,
+    /// Publisher standing, `None` if the policy has no publisher lists
+    pub publisher_standing: Option<PublisherStanding>,
+    pub violations: Vec<PolicyViolation>,
+    /// Caveats about rules that could only be checked partially
+    pub notes: Vec<String>,
+}
+
+/// Evaluates policies against analysis results
+pub struct PolicyEvaluator;
+
+impl PolicyEvaluator {
+    /// Evaluate every rule of a policy
+    pub fn evaluate(policy: &Policy, result: &AnalysisResult) -> PolicyReport {
+        let mut violations = Vec::new();
+        let mut notes = Vec::new();
+
+        let publisher_standing =
+            Self::check_publishers(policy, result, &mut violations, &mut notes);
+        Self::check_paths(policy, result, &mut violations);
+        Self::check_capabilities(policy, result, &mut violations);
+        Self::check_size(policy, result, &mut violations);
+
+        PolicyReport {
+            policy: policy.display_name().to_string(),
+            source: result
+                .source_file_path
+                .as_ref()
+                .and_then(|p| p.file_name())
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| "Unknown Package".to_string()),
+            passed: violations.is_empty(),
+            publisher_standing,
+            violations,
+            notes,
+        }
+    }
+
+    /// Signing certificates of the installer, its MSIX package or driver catalog
+    fn signers(result: &AnalysisResult) -> Vec<Signer<'_>> {
+        let properties = &result.metadata.properties;
+        ["pe", "msix", "catalog"]
+            .into_iter()
+            .map(|kind| Signer {
+                subject: properties
+                    .get(&format!("{}_signer", kind))
+                    .map(String::as_str),
+                thumbprint: properties
+                    .get(&format!("{}_signer_thumbprint", kind))
+                    .map(String::as_str),
+                verified: properties
+                    .get(&format!("{}_signature_status", kind))
+                    .is_some_and(|status| status == "valid"),
+            })
+            .filter(|signer| signer.subject.is_some() || signer.thumbprint.is_some())
+            .collect()
+    }
+
+    /// Publisher the installer declares about itself, for reports only
+    ///
+    /// Declared publishers are never matched against the lists: any
+    /// installer can claim to come from any publisher.
+    fn declared_publisher(result: &AnalysisResult) -> Option<&str> {
+        let properties = &result.metadata.properties;
+        [
+            properties
+                .get("msix_identity_publisher")
+                .map(String::as_str),
+            properties
+                .get("msix_publisher_display_name")
+                .map(String::as_str),
+            result.metadata.manufacturer.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        .find(|publisher| !publisher.trim().is_empty())
+    }
+
+    fn check_publishers(
+        policy: &Policy,
+        result: &AnalysisResult,
+        violations: &mut Vec<PolicyViolation>,
+        notes: &mut Vec<String>,
+    ) -> Option<PublisherStanding> {
+        let has_allow_list =
+            !policy.allowed_publishers.is_empty() || !policy.allowed_thumbprints.is_empty();
+        let has_deny_list =
+            !policy.denied_publishers.is_empty() || !policy.denied_thumbprints.is_empty();
+        if !has_allow_list && !has_deny_list {
+            return None;
+        }
+
+        // Only certificates whose signature verified identify the publisher
+        let signers: Vec<Signer<'_>> = Self::signers(result)
+            .into_iter()
+            .filter(|signer| signer.verified)
+            .collect();
+        if signers.is_empty() {
+            let declared = Self::declared_publisher(result).unwrap_or("unknown");
+            if !has_allow_list {
+                notes.push(format!(
+                    "No verified signature, publisher '{}' could not be checked",
+                    declared
+                ));
+                return Some(PublisherStanding::Unlisted);
+            }
+            violations.push(PolicyViolation {
+                rule: PolicyRule::AllowedPublishers,
+                subject: declared.to_string(),
+                message: format!(
+                    "Installer declaring publisher '{}' has no verified signature",
+                    declared
+                ),
+            });
+            return Some(PublisherStanding::Unlisted);
+        }
+
+        let subject = Self::signer_description(&signers);
+        let denied = signers
+            .iter()
+            .filter_map(|signer| signer.thumbprint)
+            .find(|thumbprint| contains_thumbprint(&policy.denied_thumbprints, thumbprint))
+            .map(|thumbprint| format!("Signing certificate {} is denied", thumbprint))
+            .or_else(|| {
+                signers
+                    .iter()
+                    .filter_map(|signer| signer.subject)
+                    .find(|publisher| {
+                        policy
+                            .denied_publishers
+                            .iter()
+                            .any(|pattern| glob_match(pattern, publisher))
+                    })
+                    .map(|publisher| format!("Publisher '{}' is denied", publisher))
+            });
+        if let Some(message) = denied {
+            violations.push(PolicyViolation {
+                rule: PolicyRule::DeniedPublishers,
+                subject,
+                message,
+            });
+            return Some(PublisherStanding::Denied);
+        }
+        if !has_allow_list {
+            return Some(PublisherStanding::Unlisted);
+        }
+
+        let allowed = signers.iter().any(|signer| {
+            signer.subject.is_some_and(|publisher| {
+                policy
+                    .allowed_publishers
+                    .iter()
+                    .any(|pattern| glob_match(pattern, publisher))
+            }) || signer.thumbprint.is_some_and(|thumbprint| {
+                contains_thumbprint(&policy.allowed_thumbprints, thumbprint)
+            })
+        });
+        if allowed {
+            return Some(PublisherStanding::Allowed);
+        }
+
+        violations.push(PolicyViolation {
+            rule: PolicyRule::AllowedPublishers,
+            message: format!("Publisher '{}' is not in the allowed list", subject),
+            subject,
+        });
+        Some(PublisherStanding::Unlisted)
+    }
+
+    /// First verified signer, with its certificate thumbprint if known
+    fn signer_description(signers: &[Signer<'_>]) -> String {
+        let publisher = signers
+            .iter()
+            .find_map(|signer| signer.subject)
+            .unwrap_or("unknown");
+        match signers.iter().find_map(|signer| signer.thumbprint) {
+            Some(thumbprint) => format!("{} ({})", publisher, thumbprint),
+            None => publisher.to_string(),
+        }
+    }
+
+    fn check_paths(
+        policy: &Policy,
+        result: &AnalysisResult,
+        violations: &mut Vec<PolicyViolation>,
+    ) {
+        if policy.forbidden_paths.is_empty() {
+            return;
+        }
+
+        for file in &result.files {
+            let path = file
+                .target_path
+                .as_ref()
+                .unwrap_or(&file.path)
+                .to_string_lossy()
+                .replace('\\', "/");
+            if let Some(pattern) = policy
+                .forbidden_paths
+                .iter()
+                .find(|pattern| glob_match(&pattern.replace('\\', "/"), &path))
+            {
+                violations.push(PolicyViolation {
+                    rule: PolicyRule::ForbiddenPaths,
+                    message: format!("Installs to forbidden location '{}'", pattern),
+                    subject: path,
+                });
+            }
+        }
+    }
+
+    fn check_capabilities(
+        policy: &Policy,
+        result: &AnalysisResult,
+        violations: &mut Vec<PolicyViolation>,
+    ) {
+        let Some(capabilities) = result.metadata.properties.get("msix_capabilities") else {
+            return;
+        };
+
+        for capability in capabilities.split(',').map(str::trim) {
+            if policy
+                .forbidden_capabilities
+                .iter()
+                .any(|pattern| glob_match(pattern, capability))
+            {
+                violations.push(PolicyViolation {
+                    rule: PolicyRule::ForbiddenCapabilities,
+                    message: format!("Requests forbidden capability '{}'", capability),
+                    subject: capability.to_string(),
+                });
+            }
+        }
+    }
+
+    fn check_size(policy: &Policy, result: &AnalysisResult, violations: &mut Vec<PolicyViolation>) {
+        let Some(max_size) = policy.max_install_size else {
+            return;
+        };
+
+        let install_size: u64 = result.files.iter().map(|file| file.size).sum();
+        if install_size > max_size {
+            violations.push(PolicyViolation {
+                rule: PolicyRule::MaxInstallSize,
+                subject: install_size.to_string(),
+                message: format!(
+                    "Install size {} exceeds the limit of {}",
+                    crate::utils::format_file_size(install_size),
+                    crate::utils::format_file_size(max_size)
+                ),
+            });
+        }
+    }
+}
+
+/// Whether a list holds a thumbprint, ignoring case, spaces and colons
+fn contains_thumbprint(list: &[String], thumbprint: &str) -> bool {
+    let normalize = |value: &str| {
+        value
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != ':')
+            .collect::<String>()
+            .to_ascii_uppercase()
+    };
+    let thumbprint = normalize(thumbprint);
+    list.iter().any(|entry| normalize(entry) == thumbprint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{FileAttributes, FileEntry, InstallerFormat, InstallerMetadata};
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    fn msix_result() -> AnalysisResult {
+        let mut properties = HashMap::new();
+        properties.insert(
+            "msix_identity_publisher".to_string(),
+            "CN=Fabrikam, O=Fabrikam".to_string(),
+        );
+        properties.insert(
+            "msix_capabilities".to_string(),
+            "internetClient, runFullTrust".to_string(),
+        );
+        properties.insert("msix_signature_status".to_string(), "valid".to_string());
+        properties.insert(
+            "msix_signer".to_string(),
+            "CN=Fabrikam, O=Fabrikam".to_string(),
+        );
+
+        AnalysisResult {
+            session_id: uuid::Uuid::new_v4(),
+            source_file_path: Some(PathBuf::from("app.msix")),
+            metadata: InstallerMetadata {
+                format: InstallerFormat::MSIX,
+                product_name: Some("App".to_string()),
+                manufacturer: Some("Fabrikam".to_string()),
+                file_size: 2048,
+                file_hash: "abc".to_string(),
+                created_at: chrono::Utc::now(),
+                properties,
+                ..Default::default()
+            },
+            files: vec![FileEntry {
+                path: PathBuf::from("VFS\\SystemX64\\drivers\\hook.sys"),
+                target_path: None,
+                size: 4096,
+                hash: None,
+                attributes: FileAttributes::default(),
+                compression: None,
+                is_speculative: false,
+                architecture: None,
+                modified: None,
+                source: None,
+            }],
+            analyzed_at: chrono::Utc::now(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_evaluate_policy() {
+        let result = msix_result();
+
+        let permissive = Policy {
+            allowed_publishers: vec!["CN=Fabrikam*".to_string()],
+            max_install_size: Some(8192),
+            ..Policy::default()
+        };
+        let report = PolicyEvaluator::evaluate(&permissive, &result);
+        assert!(report.passed);
+        assert_eq!(report.publisher_standing, Some(PublisherStanding::Allowed));
+        assert!(report.notes.is_empty());
+        assert_eq!(
+            PolicyEvaluator::evaluate(&Policy::default(), &result).publisher_standing,
+            None
+        );
+
+        let strict = Policy {
+            name: Some("Strict".to_string()),
+            allowed_publishers: vec!["CN=Contoso*".to_string()],
+            forbidden_paths: vec!["*.sys".to_string()],
+            forbidden_capabilities: vec!["runFullTrust".to_string()],
+            max_install_size: Some(1024),
+            ..Policy::default()
+        };
+        let report = PolicyEvaluator::evaluate(&strict, &result);
+        assert!(!report.passed);
+        assert_eq!(report.publisher_standing, Some(PublisherStanding::Unlisted));
+        let rules: Vec<PolicyRule> = report.violations.iter().map(|v| v.rule).collect();
+        assert_eq!(
+            rules,
+            vec![
+                PolicyRule::AllowedPublishers,
+                PolicyRule::ForbiddenPaths,
+                PolicyRule::ForbiddenCapabilities,
+                PolicyRule::MaxInstallSize,
+            ]
+        );
+        assert_eq!(report.violations[0].subject, "CN=Fabrikam, O=Fabrikam");
+        assert_eq!(
+            report.violations[1].subject,
+            "VFS/SystemX64/drivers/hook.sys"
+        );
+    }
+
+    #[test]
+    fn test_publisher_deny_and_thumbprint_lists() {
+        let mut result = msix_result();
+        let properties = &mut result.metadata.properties;
+        properties.insert("pe_signature_status".to_string(), "valid".to_string());
+        properties.insert(
+            "pe_signer".to_string(),
+            "CN=Fabrikam Code Signing".to_string(),
+        );
+        properties.insert(
+            "pe_signer_thumbprint".to_string(),
+            "0A1B2C3D4E5F60718293A4B5C6D7E8F901234567".to_string(),
+        );
+
+        // Verified signature thumbprints satisfy the allow list
+        let thumbprints = Policy {
+            allowed_thumbprints: vec![
+                "0a 1b 2c 3d 4e 5f 60 71 82 93 a4 b5 c6 d7 e8 f9 01 23 45 67".to_string(),
+            ],
+            ..Policy::default()
+        };
+        let report = PolicyEvaluator::evaluate(&thumbprints, &result);
+        assert!(report.passed);
+        assert_eq!(report.publisher_standing, Some(PublisherStanding::Allowed));
+        assert!(report.notes.is_empty());
+
+        // The deny list takes precedence over the allow list
+        let denied = Policy {
+            allowed_publishers: vec!["*".to_string()],
+            denied_publishers: vec!["CN=Fabrikam Code*".to_string()],
+            ..Policy::default()
+        };
+        let report = PolicyEvaluator::evaluate(&denied, &result);
+        assert!(!report.passed);
+        assert_eq!(report.publisher_standing, Some(PublisherStanding::Denied));
+        assert_eq!(report.violations[0].rule, PolicyRule::DeniedPublishers);
+        assert_eq!(
+            report.violations[0].subject,
+            "CN=Fabrikam Code Signing (0A1B2C3D4E5F60718293A4B5C6D7E8F901234567)"
+        );
+
+        let denied = Policy {
+            denied_thumbprints: vec![
+                "0A:1B:2C:3D:4E:5F:60:71:82:93:A4:B5:C6:D7:E8:F9:01:23:45:67".to_string(),
+            ],
+            ..Policy::default()
+        };
+        let report = PolicyEvaluator::evaluate(&denied, &result);
+        assert_eq!(report.publisher_standing, Some(PublisherStanding::Denied));
+
+        // Certificates of unverified signatures count neither way
+        result
+            .metadata
+            .properties
+            .insert("pe_signature_status".to_string(), "invalid".to_string());
+        let report = PolicyEvaluator::evaluate(&denied, &result);
+        assert_eq!(report.publisher_standing, Some(PublisherStanding::Unlisted));
+        let report = PolicyEvaluator::evaluate(&thumbprints, &result);
+        assert!(!report.passed);
+        assert_eq!(report.publisher_standing, Some(PublisherStanding::Unlisted));
+    }
+
+    #[test]
+    fn test_spoofed_manufacturer_is_not_allowed() {
+        let mut result = msix_result();
+        result.metadata.manufacturer = Some("Microsoft Corporation".to_string());
+        let properties = &mut result.metadata.properties;
+        properties.clear();
+        properties.insert(
+            "pe_signer".to_string(),
+            "CN=Microsoft Corporation".to_string(),
+        );
+        properties.insert("pe_signature_status".to_string(), "missing".to_string());
+        let policy = Policy {
+            allowed_publishers: vec![
+                "Microsoft Corporation".to_string(),
+                "CN=Microsoft Corporation".to_string(),
+            ],
+            ..Policy::default()
+        };
+
+        let report = PolicyEvaluator::evaluate(&policy, &result);
+        assert!(!report.passed);
+        assert_eq!(report.publisher_standing, Some(PublisherStanding::Unlisted));
+        assert_eq!(report.violations[0].rule, PolicyRule::AllowedPublishers);
+        assert_eq!(report.violations[0].subject, "Microsoft Corporation");
+        assert!(report.violations[0]
+            .message
+            .contains("has no verified signature"));
+
+        // Without an allow list an unsigned installer is only noted
+        let deny_only = Policy {
+            denied_publishers: vec!["CN=Fabrikam*".to_string()],
+            ..Policy::default()
+        };
+        let report = PolicyEvaluator::evaluate(&deny_only, &result);
+        assert!(report.passed);
+        assert_eq!(report.notes.len(), 1);
+    }
+}
@@ -0,0 +1,216 @@
+//! Organization baseline policies
+//!
+//! A policy lists what an organization accepts from an installer: signing
+//! publishers, install locations, package capabilities and install size.
+//! Publishers are allowed or denied by certificate subject or by the SHA-1
+//! thumbprint of a signing certificate whose signature verified. Policies
+//! are written in TOML or YAML:
+//!
+//! ```toml
+//! name = "Corporate baseline"
+//! allowed_publishers = ["CN=Contoso*", "CN=Contoso Ltd, O=Contoso Ltd"]
+//! allowed_thumbprints = ["3B1EFD3A66EA28B16697394703A72CA340A05BD5"]
+//! denied_publishers = ["CN=Fabrikam*"]
+//! denied_thumbprints = ["A4:34:1B:9F:D5:0F:B9:64:28:3E:77:9B:F9:96:E2:98:71:C6:B5:F0"]
+//! forbidden_paths = ["*/Windows/System32/*", "*.sys"]
+//! forbidden_capabilities = ["runFullTrust", "broadFileSystemAccess"]
+//! max_install_size = "500 MB"
+//! ```
+//!
+//! Patterns are case-insensitive globs (`*` and `?`) and paths are matched
+//! with `/` as separator.
+
+pub mod engine;
+
+pub use engine::{PolicyEvaluator, PolicyReport, PolicyRule, PolicyViolation, PublisherStanding};
+
+use crate::core::{AnalyzerError, Result};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::path::Path;
+
+/// Baseline policy evaluated against analysis results
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Policy {
+    /// Display name used in policy reports
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Publishers allowed to sign installers; empty allows any publisher
+    /// unless `allowed_thumbprints` is set
+    #[serde(default)]
+    pub allowed_publishers: Vec<String>,
+    /// Thumbprints of signing certificates allowed in addition to `allowed_publishers`
+    #[serde(default)]
+    pub allowed_thumbprints: Vec<String>,
+    /// Publishers whose installers are rejected, even if otherwise allowed
+    #[serde(default)]
+    pub denied_publishers: Vec<String>,
+    /// Thumbprints of signing certificates whose installers are rejected
+    #[serde(default)]
+    pub denied_thumbprints: Vec<String>,
+    /// Install locations no packaged file may target
+    #[serde(default)]
+    pub forbidden_paths: Vec<String>,
+    /// Package capabilities that must not be requested
+    #[serde(default)]
+    pub forbidden_capabilities: Vec<String>,
+    /// Maximum total size of the installed files, in bytes or as `"500 MB"`
+    #[serde(default, deserialize_with = "deserialize_size")]
+    pub max_install_size: Option<u64>,
+}
+
+impl Policy {
+    /// Load a policy, choosing TOML or YAML by file extension
+    pub async fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Err(AnalyzerError::file_not_found(path));
+        }
+        let content = tokio::fs::read_to_string(path).await?;
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_lowercase());
+
+        let policy = match extension.as_deref() {
+            Some("yaml") | Some("yml") => Self::from_yaml(&content),
+            _ => Self::from_toml(&content),
+        }
+        .map_err(|e| {
+            AnalyzerError::config_error(format!("Invalid policy {}: {}", path.display(), e))
+        })?;
+
+        tracing::info!("Loaded policy from: {}", path.display());
+        Ok(policy)
+    }
+
+    /// Parse a TOML policy
+    pub fn from_toml(content: &str) -> std::result::Result<Self, String> {
+        toml::from_str(content).map_err(|e| e.to_string())
+    }
+
+    /// Parse a YAML policy
+    pub fn from_yaml(content: &str) -> std::result::Result<Self, String> {
+        serde_yaml::from_str(content).map_err(|e| e.to_string())
+    }
+
+    /// Name shown in reports
+    pub fn display_name(&self) -> &str {
+        self.name.as_deref().unwrap_or("policy")
+    }
+}
+
+/// Accept sizes as a byte count or a string with a unit (`"1.5 GB"`)
+fn deserialize_size<'de, D>(deserializer: D) -> std::result::Result<Option<u64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Size {
+        Bytes(u64),
+        Text(String),
+    }
+
+    match Option::<Size>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(Size::Bytes(bytes)) => Ok(Some(bytes)),
+        Some(Size::Text(text)) => parse_size(&text)
+            .map(Some)
+            .ok_or_else(|| serde::de::Error::custom(format!("invalid size '{}'", text))),
+    }
+}
+
+/// Parse a size such as `500MB`, `1.5 GB` or `1024` (binary units, as in reports)
+pub fn parse_size(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let multiplier: u64 = match unit.trim().to_uppercase().as_str() {
+        "" | "B" => 1,
+        "KB" | "K" | "KIB" => 1024,
+        "MB" | "M" | "MIB" => 1024 * 1024,
+        "GB" | "G" | "GIB" => 1024 * 1024 * 1024,
+        "TB" | "T" | "TIB" => 1024 * 1024 * 1024 * 1024,
+        _ => return None,
+    };
+    Some((number * multiplier as f64) as u64)
+}
+
+/// Case-insensitive glob match supporting `*` and `?`
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+
+    // Iterative matching with backtracking to the last `*`
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_policy_formats() {
+        let toml = r#"
+name = "Baseline"
+allowed_publishers = ["CN=Contoso*"]
+forbidden_capabilities = ["runFullTrust"]
+max_install_size = "1.5 GB"
+"#;
+        let policy = Policy::from_toml(toml).unwrap();
+        assert_eq!(policy.display_name(), "Baseline");
+        assert_eq!(policy.max_install_size, Some(1536 * 1024 * 1024));
+
+        let yaml = "forbidden_paths:\n  - \"*/System32/*\"\nmax_install_size: 1024\n";
+        let policy = Policy::from_yaml(yaml).unwrap();
+        assert_eq!(policy.forbidden_paths, vec!["*/System32/*"]);
+        assert_eq!(policy.max_install_size, Some(1024));
+
+        let toml = r#"
+allowed_thumbprints = ["3B:1E:FD:3A"]
+denied_publishers = ["CN=Fabrikam*"]
+denied_thumbprints = ["0A1B2C"]
+"#;
+        let policy = Policy::from_toml(toml).unwrap();
+        assert_eq!(policy.allowed_thumbprints, vec!["3B:1E:FD:3A"]);
+        assert_eq!(policy.denied_publishers, vec!["CN=Fabrikam*"]);
+        assert_eq!(policy.denied_thumbprints, vec!["0A1B2C"]);
+        assert!(policy.allowed_publishers.is_empty());
+
+        assert!(Policy::from_toml("allowed_publisher = []").is_err());
+        assert!(Policy::from_toml("max_install_size = \"huge\"").is_err());
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("CN=Contoso*", "cn=contoso, o=contoso ltd"));
+        assert!(glob_match(
+            "*/windows/system32/*",
+            "C:/Windows/System32/drivers/x.sys"
+        ));
+        assert!(glob_match("*.sys", "drivers/x.sys"));
+        assert!(glob_match("a?c", "abc"));
+        assert!(!glob_match("*.sys", "x.sys.bak"));
+        assert!(!glob_match("Contoso", "Contoso Ltd"));
+    }
+}
@@ -0,0 +1,312 @@
+//! Analysis artifacts written to a side-channel directory
+//!
+//! Artifacts are the machine-readable by-products of an analysis (metadata,
+//! file tree, dependency graph, registry operations, extracted strings). They
+//! are written independently of where the report goes, so a report can be
+//! printed to stdout while the artifacts still land on disk.
+//!
+//! High-interest files packaged in the installer (executables, scripts,
+//! drivers, embedded payloads) can additionally be attached: their content is
+//! copied to `files/` with an `index.json` listing hashes, so reviewers can
+//! inspect them without re-extracting the installer.
+
+use super::{DependencyGraphExporter, FileTreeExporter, TreeExportFormat};
+use crate::core::{AnalysisResult, AnalyzerError, FileEntry, Result};
+use crate::utils::extended_length;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::path::{Component, Path, PathBuf};
+
+/// Category of a high-interest file attached to a report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttachmentKind {
+    /// Executable code; signatures are not verified, so every binary is flagged
+    Executable,
+    /// Script run by the installer or shipped to the target system
+    Script,
+    /// Kernel driver or driver installation file
+    Driver,
+    /// Nested installer or archive delivered as a payload
+    Payload,
+}
+
+impl AttachmentKind {
+    /// Classify a packaged file by extension, `None` for files of no particular interest
+    pub fn classify(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        match ext.as_str() {
+            "exe" | "dll" | "scr" | "com" | "ocx" | "cpl" => Some(Self::Executable),
+            "ps1" | "psm1" | "bat" | "cmd" | "vbs" | "vbe" | "js" | "jse" | "wsf" | "hta"
+            | "sh" => Some(Self::Script),
+            "sys" | "inf" | "cat" => Some(Self::Driver),
+            "msi" | "msp" | "cab" | "zip" | "7z" | "nupkg" | "appx" | "msix" => Some(Self::Payload),
+            _ => None,
+        }
+    }
+}
+
+/// Entry of the attachment index
+#[derive(Debug, Clone, Serialize)]
+pub struct AttachmentEntry {
+    /// Path of the file inside the installer
+    pub path: String,
+    pub kind: AttachmentKind,
+    pub size: u64,
+    /// SHA-256 of the extracted content
+    pub sha256: Option<String>,
+    /// Location of the copy, relative to the artifacts directory
+    pub saved_as: Option<String>,
+    /// Why the content could not be attached
+    pub note: Option<String>,
+}
+
+/// Index of attached files written as `index.json`
+#[derive(Debug, Clone, Serialize)]
+pub struct AttachmentIndex {
+    pub source: String,
+    pub source_hash: String,
+    pub attachments: Vec<AttachmentEntry>,
+}
+
+impl AttachmentIndex {
+    /// Number of attachments whose content was saved
+    pub fn saved_count(&self) -> usize {
+        self.attachments
+            .iter()
+            .filter(|entry| entry.saved_as.is_some())
+            .count()
+    }
+}
+
+/// Writer for analysis artifacts
+pub struct ArtifactWriter;
+
+impl ArtifactWriter {
+    /// Write all artifacts of an analysis result into a directory
+    ///
+    /// Returns the paths of the written files.
+    pub async fn save(result: &AnalysisResult, dir: &Path) -> Result<Vec<PathBuf>> {
+        tokio::fs::create_dir_all(extended_length(dir)).await?;
+        let mut written = Vec::new();
+
+        let metadata_path = dir.join("metadata.json");
+        Self::write_json(&result.metadata, &metadata_path).await?;
+        written.push(metadata_path);
+
+        let tree_path = dir.join("file-tree.json");
+        FileTreeExporter::save(result, TreeExportFormat::Json, &tree_path).await?;
+        written.push(tree_path);
+
+        if let Some(graph) = result.dependencies.as_ref().filter(|g| !g.is_empty()) {
+            let deps_path = dir.join("dependencies.json");
+            DependencyGraphExporter::save(graph, &deps_path).await?;
+            written.push(deps_path);
+        }
+
+        if !result.registry_operations.is_empty() {
+            let registry_path = dir.join("registry-operations.json");
+            Self::write_json(&result.registry_operations, &registry_path).await?;
+            written.push(registry_path);
+        }
+
+        if let Some(strings) = &result.strings {
+            let strings_path = dir.join("strings.json");
+            Self::write_json(strings, &strings_path).await?;
+            written.push(strings_path);
+        }
+
+        tracing::info!("Wrote {} artifacts to: {}", written.len(), dir.display());
+        Ok(written)
+    }
+
+    /// Select the high-interest files of an analysis result
+    ///
+    /// Speculative entries are skipped since they have no content to extract.
+    pub fn flagged_files(files: &[FileEntry]) -> Vec<(&FileEntry, AttachmentKind)> {
+        files
+            .iter()
+            .filter(|file| !file.is_speculative)
+            .filter_map(|file| AttachmentKind::classify(&file.path).map(|kind| (file, kind)))
+            .collect()
+    }
+
+    /// Save attachment contents to `files/` and write `index.json`
+    ///
+    /// Each attachment carries the content read from the installer, or
+    /// `None` when the analyzer cannot extract it; such files are still
+    /// listed in the index.
+    pub async fn save_attachments(
+        result: &AnalysisResult,
+        dir: &Path,
+        attachments: Vec<(&FileEntry, AttachmentKind, Option<Vec<u8>>)>,
+    ) -> Result<AttachmentIndex> {
+        let mut entries = Vec::new();
+
+        for (file, kind, content) in attachments {
+            let mut entry = AttachmentEntry {
+                path: file.path.to_string_lossy().replace('\\', "/"),
+                kind,
+                size: file.size,
+                sha256: None,
+                saved_as: None,
+                note: None,
+            };
+
+            match (content, Self::attachment_path(&file.path)) {
+                (Some(content), Some(relative_path)) => {
+                    // Deep installer layouts easily exceed MAX_PATH below the directory
+                    let target = extended_length(&dir.join(&relative_path));
+                    if let Some(parent) = target.parent() {
+                        tokio::fs::create_dir_all(parent).await?;
+                    }
+                    tokio::fs::write(&target, &content).await?;
+
+                    entry.size = content.len() as u64;
+                    entry.sha256 = Some(hex::encode(Sha256::digest(&content)));
+                    entry.saved_as = Some(relative_path.to_string_lossy().replace('\\', "/"));
+                }
+                (Some(_), None) => {
+                    entry.note =
+                        Some("Unsafe path inside installer, content not saved".to_string());
+                }
+                (None, _) => {
+                    entry.note =
+                        Some("Content extraction not supported for this format".to_string());
+                }
+            }
+
+            entries.push(entry);
+        }
+
+        let index = AttachmentIndex {
+            source: result
+                .source_file_path
+                .as_ref()
+                .and_then(|p| p.file_name())
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| "Unknown Package".to_string()),
+            source_hash: result.metadata.file_hash.clone(),
+            attachments: entries,
+        };
+
+        tokio::fs::create_dir_all(extended_length(dir)).await?;
+        Self::write_json(&index, &dir.join("index.json")).await?;
+        tracing::info!(
+            "Attached {} of {} flagged files to: {}",
+            index.saved_count(),
+            index.attachments.len(),
+            dir.display()
+        );
+        Ok(index)
+    }
+
+    /// Location of an attachment below the artifacts directory
+    ///
+    /// Only plain path components are kept so that entries such as
+    /// `../../evil.exe` cannot escape the directory.
+    fn attachment_path(entry_path: &Path) -> Option<PathBuf> {
+        let normalized = entry_path.to_string_lossy().replace('\\', "/");
+        let mut relative = PathBuf::from("files");
+        for component in Path::new(&normalized).components() {
+            match component {
+                Component::Normal(part) => relative.push(part),
+                Component::CurDir => {}
+                _ => return None,
+            }
+        }
+        (relative.components().count() > 1).then_some(relative)
+    }
+
+    async fn write_json<T: Serialize>(value: &T, path: &Path) -> Result<()> {
+        let content =
+            serde_json::to_string_pretty(value).map_err(AnalyzerError::SerializationError)?;
+        tokio::fs::write(extended_length(path), content).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_attachments() {
+        assert_eq!(
+            AttachmentKind::classify(Path::new("bin/Setup.EXE")),
+            Some(AttachmentKind::Executable)
+        );
+        assert_eq!(
+            AttachmentKind::classify(Path::new("scripts/install.ps1")),
+            Some(AttachmentKind::Script)
+        );
+        assert_eq!(
+            AttachmentKind::classify(Path::new("drivers/usb.sys")),
+            Some(AttachmentKind::Driver)
+        );
+        assert_eq!(
+            AttachmentKind::classify(Path::new("payload/runtime.msi")),
+            Some(AttachmentKind::Payload)
+        );
+        assert_eq!(AttachmentKind::classify(Path::new("readme.txt")), None);
+    }
+
+    #[test]
+    fn test_attachment_path_stays_inside_directory() {
+        assert_eq!(
+            ArtifactWriter::attachment_path(Path::new("app\\bin\\tool.exe")),
+            Some(PathBuf::from("files/app/bin/tool.exe"))
+        );
+        assert_eq!(
+            ArtifactWriter::attachment_path(Path::new("../../evil.exe")),
+            None
+        );
+        assert_eq!(
+            ArtifactWriter::attachment_path(Path::new("/etc/x.sh")),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_save_attachment_beyond_max_path() {
+        use crate::core::{FileAttributes, InstallerFormat, InstallerMetadata};
+
+        let dir = tempfile::tempdir().unwrap();
+        let nested = ["Program Files (x86)\\Vendor Application Suite"; 8].join("\\");
+        let file = FileEntry {
+            path: PathBuf::from(format!("{}\\bin\\updater.exe", nested)),
+            target_path: None,
+            size: 2,
+            hash: None,
+            attributes: FileAttributes::default(),
+            compression: None,
+            is_speculative: false,
+            architecture: None,
+            modified: None,
+            source: None,
+        };
+        let result = AnalysisResult {
+            session_id: uuid::Uuid::new_v4(),
+            metadata: InstallerMetadata {
+                format: InstallerFormat::MSI,
+                created_at: chrono::Utc::now(),
+                ..Default::default()
+            },
+            files: vec![file.clone()],
+            analyzed_at: chrono::Utc::now(),
+            ..Default::default()
+        };
+
+        let index = ArtifactWriter::save_attachments(
+            &result,
+            dir.path(),
+            vec![(&file, AttachmentKind::Executable, Some(b"MZ".to_vec()))],
+        )
+        .await
+        .unwrap();
+        let saved_as = index.attachments[0].saved_as.clone().unwrap();
+        let target = dir.path().join(&saved_as);
+        assert!(target.as_os_str().len() > 260);
+        assert_eq!(std::fs::read(extended_length(&target)).unwrap(), b"MZ");
+    }
+}
@@ -0,0 +1,459 @@
+//! Mapping of security findings to MITRE ATT&CK
+//!
+//! Security operations teams triage by ATT&CK technique. Findings recorded
+//! in the analysis properties and behavior seen in captured operations are
+//! tagged with the techniques they correspond to, and the techniques are
+//! summarized as a matrix ordered by tactic. A technique that serves
+//! several tactics is listed under its primary one.
+
+use crate::core::{AnalysisResult, FileOperation, ProcessOpType, RegistryOperation};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Number of keys, paths or commands listed as evidence per finding
+const MAX_EVIDENCE: usize = 5;
+
+/// Tactic of the ATT&CK enterprise matrix, in matrix order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AttackTactic {
+    Execution,
+    Persistence,
+    PrivilegeEscalation,
+    DefenseEvasion,
+    Collection,
+    CommandAndControl,
+}
+
+impl AttackTactic {
+    /// Name of the tactic in the matrix
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Execution => "Execution",
+            Self::Persistence => "Persistence",
+            Self::PrivilegeEscalation => "Privilege Escalation",
+            Self::DefenseEvasion => "Defense Evasion",
+            Self::Collection => "Collection",
+            Self::CommandAndControl => "Command and Control",
+        }
+    }
+}
+
+impl fmt::Display for AttackTactic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Techniques findings are mapped to: ID, name and primary tactic
+const TECHNIQUES: &[(&str, &str, AttackTactic)] = &[
+    (
+        "T1047",
+        "Windows Management Instrumentation",
+        AttackTactic::Execution,
+    ),
+    ("T1059.001", "PowerShell", AttackTactic::Execution),
+    ("T1053.005", "Scheduled Task", AttackTactic::Persistence),
+    (
+        "T1547.001",
+        "Registry Run Keys / Startup Folder",
+        AttackTactic::Persistence,
+    ),
+    (
+        "T1547.004",
+        "Winlogon Helper DLL",
+        AttackTactic::Persistence,
+    ),
+    ("T1543.003", "Windows Service", AttackTactic::Persistence),
+    (
+        "T1546.003",
+        "Windows Management Instrumentation Event Subscription",
+        AttackTactic::Persistence,
+    ),
+    (
+        "T1546.012",
+        "Image File Execution Options Injection",
+        AttackTactic::Persistence,
+    ),
+    ("T1137.006", "Office Add-ins", AttackTactic::Persistence),
+    (
+        "T1548.002",
+        "Bypass User Account Control",
+        AttackTactic::PrivilegeEscalation,
+    ),
+    ("T1112", "Modify Registry", AttackTactic::DefenseEvasion),
+    (
+        "T1497",
+        "Virtualization/Sandbox Evasion",
+        AttackTactic::DefenseEvasion,
+    ),
+    (
+        "T1027.009",
+        "Embedded Payloads",
+        AttackTactic::DefenseEvasion,
+    ),
+    ("T1070.006", "Timestomp", AttackTactic::DefenseEvasion),
+    ("T1056.001", "Keylogging", AttackTactic::Collection),
+    ("T1115", "Clipboard Data", AttackTactic::Collection),
+    ("T1113", "Screen Capture", AttackTactic::Collection),
+    (
+        "T1105",
+        "Ingress Tool Transfer",
+        AttackTactic::CommandAndControl,
+    ),
+];
+
+/// Registry locations and the persistence technique writing to them maps to
+const PERSISTENCE_KEYS: &[(&str, &str)] = &[
+    ("\\currentversion\\run", "T1547.001"),
+    ("\\currentversion\\policies\\explorer\\run", "T1547.001"),
+    ("\\currentversion\\winlogon", "T1547.004"),
+    ("\\currentcontrolset\\services\\", "T1543.003"),
+    ("\\image file execution options\\", "T1546.012"),
+    ("\\schedule\\taskcache\\", "T1053.005"),
+];
+
+/// Folder whose files run at logon
+const STARTUP_FOLDER: &str = "/start menu/programs/startup/";
+
+/// Spyware capabilities as recorded in the properties and their techniques
+const SPYWARE_TECHNIQUES: &[(&str, &str)] = &[
+    ("Clipboard read", "T1115"),
+    ("Keyboard hook", "T1056.001"),
+    ("Screen capture", "T1113"),
+];
+
+/// A security finding with the techniques it corresponds to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaggedFinding {
+    pub finding: String,
+    pub evidence: String,
+    /// Technique IDs, sorted
+    pub techniques: Vec<String>,
+}
+
+/// A technique of the matrix with the findings tagged with it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MatrixEntry {
+    pub tactic: String,
+    pub technique_id: String,
+    pub technique_name: String,
+    pub findings: Vec<String>,
+}
+
+/// ATT&CK view of the security findings of an analysis
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttackSummary {
+    pub findings: Vec<TaggedFinding>,
+    /// Techniques ordered by tactic, then by ID
+    pub matrix: Vec<MatrixEntry>,
+}
+
+impl AttackSummary {
+    /// Tag the findings of an analysis result
+    pub fn from_result(result: &AnalysisResult) -> Self {
+        let mut summary = Self::default();
+        summary.add_property_findings(result);
+        summary.add_registry_findings(result);
+        summary.add_file_findings(result);
+        summary.add_process_findings(result);
+        summary.build_matrix();
+        summary
+    }
+
+    /// Whether no finding was mapped
+    pub fn is_empty(&self) -> bool {
+        self.findings.is_empty()
+    }
+
+    /// Technique IDs of all findings, sorted
+    pub fn technique_ids(&self) -> Vec<&str> {
+        self.matrix
+            .iter()
+            .map(|entry| entry.technique_id.as_str())
+            .collect()
+    }
+
+    fn add(&mut self, finding: &str, evidence: impl Into<String>, techniques: &[&str]) {
+        if techniques.is_empty() {
+            return;
+        }
+        let mut techniques: Vec<String> = techniques.iter().map(|id| id.to_string()).collect();
+        techniques.sort();
+        techniques.dedup();
+        self.findings.push(TaggedFinding {
+            finding: finding.to_string(),
+            evidence: evidence.into(),
+            techniques,
+        });
+    }
+
+    fn add_property_findings(&mut self, result: &AnalysisResult) {
+        let properties = &result.metadata.properties;
+        let get = |key: &str| properties.get(key).map(String::as_str);
+
+        if let Some(techniques) = get("uac_bypass_techniques") {
+            self.add("UAC bypass", techniques, &["T1548.002"]);
+        }
+        if let Some(capabilities) = get("spyware_capabilities") {
+            let techniques: Vec<&str> = SPYWARE_TECHNIQUES
+                .iter()
+                .filter(|(capability, _)| capabilities.contains(capability))
+                .map(|(_, id)| *id)
+                .collect();
+            self.add("Spyware indicators", capabilities, &techniques);
+        }
+        if let Some(verdict) = get("anti_analysis_checks").filter(|verdict| *verdict != "none") {
+            self.add(
+                "Anti-analysis checks",
+                format!("{} evasion", verdict),
+                &["T1497"],
+            );
+        }
+        if let Some(subscriptions) = get("wmi_event_subscriptions") {
+            self.add("WMI event subscription", subscriptions, &["T1546.003"]);
+        }
+        if let Some(commands) = get("wmi_process_creations") {
+            self.add("WMI process creation", commands, &["T1047"]);
+        }
+        if let Some(count) = get("powershell_script_block_count") {
+            self.add(
+                "PowerShell script execution",
+                format!("{} script blocks", count),
+                &["T1059.001"],
+            );
+        }
+        if get("office_addin_autoload") == Some("true") {
+            let evidence = get("office_addins").unwrap_or("auto-loading add-in");
+            self.add("Auto-loading Office add-in", evidence, &["T1137.006"]);
+        }
+        if let Some(services) = get("driver_services").or(get("game_kernel_drivers")) {
+            self.add("Kernel driver service", services, &["T1543.003"]);
+        }
+        if let Some(count) = get("embedded_payload_count") {
+            self.add(
+                "Embedded payloads",
+                format!("{} payloads", count),
+                &["T1027.009"],
+            );
+        }
+        if let Some(anomalies) = get("timestamp_anomalies") {
+            self.add("Timestamp anomalies", anomalies, &["T1070.006"]);
+        }
+        let delivery = get("installer_delivery").filter(|delivery| *delivery != "offline");
+        if let Some(evidence) = get("download_urls").or(delivery) {
+            self.add("Payload download", evidence, &["T1105"]);
+        }
+    }
+
+    fn add_registry_findings(&mut self, result: &AnalysisResult) {
+        let mut modified = 0;
+        let mut persistence: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for operation in &result.registry_operations {
+            let key_path = match operation {
+                RegistryOperation::CreateKey { key_path, .. }
+                | RegistryOperation::SetValue { key_path, .. } => key_path,
+                _ => continue,
+            };
+            modified += 1;
+            let key = key_path.to_ascii_lowercase();
+            if let Some((_, id)) = PERSISTENCE_KEYS
+                .iter()
+                .find(|(fragment, _)| key.contains(fragment))
+            {
+                let keys = persistence.entry(id).or_default();
+                if !keys.contains(&key_path.as_str()) {
+                    keys.push(key_path);
+                }
+            }
+        }
+
+        for (id, keys) in persistence {
+            self.add(
+                "Persistence registry entry",
+                evidence(keys.iter().copied()),
+                &[id, "T1112"],
+            );
+        }
+        if modified > 0 {
+            self.add(
+                "Registry modification",
+                format!("{} keys or values written", modified),
+                &["T1112"],
+            );
+        }
+    }
+
+    fn add_file_findings(&mut self, result: &AnalysisResult) {
+        let startup: Vec<String> = result
+            .file_operations
+            .iter()
+            .filter_map(|operation| match operation {
+                FileOperation::Create { path, .. } | FileOperation::Write { path, .. } => {
+                    Some(path.to_string_lossy().replace('\\', "/"))
+                }
+                FileOperation::Move { to_path, .. } => {
+                    Some(to_path.to_string_lossy().replace('\\', "/"))
+                }
+                _ => None,
+            })
+            .filter(|path| path.to_ascii_lowercase().contains(STARTUP_FOLDER))
+            .collect();
+        if !startup.is_empty() {
+            self.add(
+                "Startup folder file",
+                evidence(startup.iter().map(String::as_str)),
+                &["T1547.001"],
+            );
+        }
+    }
+
+    fn add_process_findings(&mut self, result: &AnalysisResult) {
+        let mut tasks = Vec::new();
+        for operation in &result.process_operations {
+            if !matches!(operation.operation_type, ProcessOpType::Create) {
+                continue;
+            }
+            let command_line = operation.command_line.as_deref().unwrap_or_default();
+            if operation.process_name.eq_ignore_ascii_case("schtasks.exe")
+                && command_line.to_ascii_lowercase().contains("/create")
+            {
+                tasks.push(command_line);
+            }
+        }
+        if !tasks.is_empty() {
+            self.add("Scheduled task creation", evidence(tasks), &["T1053.005"]);
+        }
+    }
+
+    fn build_matrix(&mut self) {
+        let mut techniques: BTreeMap<(AttackTactic, &str), MatrixEntry> = BTreeMap::new();
+        for finding in &self.findings {
+            for id in &finding.techniques {
+                let Some((id, name, tactic)) = TECHNIQUES.iter().find(|(known, _, _)| known == id)
+                else {
+                    continue;
+                };
+                let entry = techniques
+                    .entry((*tactic, id))
+                    .or_insert_with(|| MatrixEntry {
+                        tactic: tactic.to_string(),
+                        technique_id: id.to_string(),
+                        technique_name: name.to_string(),
+                        findings: Vec::new(),
+                    });
+                if !entry.findings.contains(&finding.finding) {
+                    entry.findings.push(finding.finding.clone());
+                }
+            }
+        }
+        self.matrix = techniques.into_values().collect();
+    }
+}
+
+/// Evidence list of a finding, shortened to a few entries
+fn evidence<'a>(entries: impl IntoIterator<Item = &'a str>) -> String {
+    let entries: Vec<&str> = entries.into_iter().collect();
+    let mut listed = entries[..entries.len().min(MAX_EVIDENCE)].join("; ");
+    if entries.len() > MAX_EVIDENCE {
+        listed.push_str(&format!(" and {} more", entries.len() - MAX_EVIDENCE));
+    }
+    listed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ProcessOperation, RegistryValue, RegistryValueType};
+    use chrono::Utc;
+    use std::path::PathBuf;
+
+    fn set_value(key_path: &str) -> RegistryOperation {
+        RegistryOperation::SetValue {
+            key_path: key_path.to_string(),
+            value_name: "Updater".to_string(),
+            value_type: RegistryValueType::String,
+            value_data: RegistryValue::String("C:\\App\\update.exe".to_string()),
+            timestamp: Utc::now(),
+            process_id: None,
+        }
+    }
+
+    #[test]
+    fn test_map_findings() {
+        let mut result = AnalysisResult::default();
+        assert!(AttackSummary::from_result(&result).is_empty());
+
+        let properties = &mut result.metadata.properties;
+        properties.insert(
+            "spyware_capabilities".to_string(),
+            "Clipboard read, Keyboard hook".to_string(),
+        );
+        properties.insert("anti_analysis_checks".to_string(), "none".to_string());
+        properties.insert("installer_delivery".to_string(), "web".to_string());
+        properties.insert(
+            "download_urls".to_string(),
+            "https://example.com/payload.bin".to_string(),
+        );
+        result.registry_operations = vec![
+            set_value("HKEY_CURRENT_USER\\Software\\Microsoft\\Windows\\CurrentVersion\\Run"),
+            set_value("HKEY_LOCAL_MACHINE\\SOFTWARE\\Contoso\\App"),
+        ];
+        result.file_operations = vec![FileOperation::Create {
+            path: PathBuf::from(
+                "C:\\Users\\user\\AppData\\Roaming\\Microsoft\\Windows\\Start Menu\\Programs\\Startup\\app.lnk",
+            ),
+            size: 1024,
+            timestamp: Utc::now(),
+            process_id: None,
+        }];
+        result.process_operations = vec![ProcessOperation {
+            operation_type: ProcessOpType::Create,
+            process_id: 200,
+            process_name: "schtasks.exe".to_string(),
+            command_line: Some("schtasks /Create /TN AppUpdate /TR update.exe".to_string()),
+            parent_process_id: Some(100),
+            timestamp: Utc::now(),
+        }];
+
+        let summary = AttackSummary::from_result(&result);
+        let findings: Vec<(&str, Vec<&str>)> = summary
+            .findings
+            .iter()
+            .map(|finding| {
+                (
+                    finding.finding.as_str(),
+                    finding.techniques.iter().map(String::as_str).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            findings,
+            vec![
+                ("Spyware indicators", vec!["T1056.001", "T1115"]),
+                ("Payload download", vec!["T1105"]),
+                ("Persistence registry entry", vec!["T1112", "T1547.001"]),
+                ("Registry modification", vec!["T1112"]),
+                ("Startup folder file", vec!["T1547.001"]),
+                ("Scheduled task creation", vec!["T1053.005"]),
+            ]
+        );
+        assert_eq!(
+            summary.technique_ids(),
+            vec![
+                "T1053.005",
+                "T1547.001",
+                "T1112",
+                "T1056.001",
+                "T1115",
+                "T1105"
+            ]
+        );
+        let run_keys = &summary.matrix[1];
+        assert_eq!(run_keys.tactic, "Persistence");
+        assert_eq!(
+            run_keys.findings,
+            vec!["Persistence registry entry", "Startup folder file"]
+        );
+    }
+}
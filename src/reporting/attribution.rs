@@ -0,0 +1,187 @@
+//! Attribution of captured operations to processes
+//!
+//! A sandbox captures everything that happens on the machine while an
+//! installer runs. Operations carry the ID of the process that performed
+//! them, so they can be narrowed down to the installer's own process tree.
+//! MSI installations do much of their work in the Windows Installer service,
+//! an `msiexec.exe` started by the service control manager rather than by the
+//! installer; its processes are identified separately so reports can include
+//! or exclude them. Processes started through WMI `Win32_Process.Create` are
+//! children of the WMI provider host, so they are attached to the process
+//! that called the method instead.
+
+use crate::core::{AnalysisResult, ProcessOpType, WmiOperation};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+/// Process name of the Windows Installer
+const MSIEXEC: &str = "msiexec.exe";
+
+/// Processes of a dynamic result grouped by their relation to the installer
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessAttribution {
+    /// Process the installer was started as
+    pub installer_root: Option<u32>,
+    /// The installer process and all its descendants, sorted
+    pub installer_tree: Vec<u32>,
+    /// Windows Installer service processes and their descendants, sorted
+    pub msiexec_service: Vec<u32>,
+    /// Processes of the installer tree that were started through WMI, sorted
+    #[serde(default)]
+    pub wmi_processes: Vec<u32>,
+}
+
+impl ProcessAttribution {
+    /// Group the processes created during a dynamic analysis
+    ///
+    /// The installer process is the one named like the installer file, or
+    /// else the first process whose parent was not captured.
+    pub fn from_result(result: &AnalysisResult) -> Self {
+        let created: Vec<_> = result
+            .process_operations
+            .iter()
+            .filter(|operation| matches!(operation.operation_type, ProcessOpType::Create))
+            .collect();
+        let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+        for operation in &created {
+            if let Some(parent) = operation.parent_process_id {
+                children
+                    .entry(parent)
+                    .or_default()
+                    .push(operation.process_id);
+            }
+        }
+        let mut wmi_created = BTreeSet::new();
+        for operation in &result.wmi_operations {
+            if let WmiOperation::ProcessCreate {
+                created_process_id: Some(created),
+                process_id: Some(caller),
+                ..
+            } = operation
+            {
+                children.entry(*caller).or_default().push(*created);
+                wmi_created.insert(*created);
+            }
+        }
+
+        // Sandbox reports carry Windows paths, also when read on other systems
+        let installer_name = result
+            .source_file_path
+            .as_deref()
+            .map(crate::utils::entry_file_name);
+        let captured: BTreeSet<u32> = created
+            .iter()
+            .map(|operation| operation.process_id)
+            .collect();
+        let installer_root = created
+            .iter()
+            .find(|operation| {
+                installer_name
+                    .as_ref()
+                    .is_some_and(|name| operation.process_name.eq_ignore_ascii_case(name))
+            })
+            .or_else(|| {
+                created.iter().find(|operation| {
+                    operation
+                        .parent_process_id
+                        .is_none_or(|parent| !captured.contains(&parent))
+                })
+            })
+            .map(|operation| operation.process_id);
+
+        let installer_tree = descendants(installer_root, &children);
+        let service_roots = created.iter().filter(|operation| {
+            operation.process_name.eq_ignore_ascii_case(MSIEXEC)
+                && !installer_tree.contains(&operation.process_id)
+        });
+        let mut msiexec_service = BTreeSet::new();
+        for operation in service_roots {
+            msiexec_service.extend(descendants(Some(operation.process_id), &children));
+        }
+        msiexec_service.retain(|pid| !installer_tree.contains(pid));
+        wmi_created.retain(|pid| installer_tree.contains(pid));
+
+        Self {
+            installer_root,
+            installer_tree: installer_tree.into_iter().collect(),
+            msiexec_service: msiexec_service.into_iter().collect(),
+            wmi_processes: wmi_created.into_iter().collect(),
+        }
+    }
+}
+
+/// A process and everything it started, directly or indirectly
+fn descendants(root: Option<u32>, children: &HashMap<u32, Vec<u32>>) -> BTreeSet<u32> {
+    let mut tree = BTreeSet::new();
+    let mut queue: VecDeque<u32> = root.into_iter().collect();
+    while let Some(pid) = queue.pop_front() {
+        if tree.insert(pid) {
+            queue.extend(children.get(&pid).into_iter().flatten());
+        }
+    }
+    tree
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{InstallerFormat, InstallerMetadata, ProcessOperation};
+    use chrono::Utc;
+    use std::path::PathBuf;
+
+    fn process(pid: u32, name: &str, parent: Option<u32>) -> ProcessOperation {
+        ProcessOperation {
+            operation_type: ProcessOpType::Create,
+            process_id: pid,
+            process_name: name.to_string(),
+            command_line: None,
+            parent_process_id: parent,
+            timestamp: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_attribute_processes() {
+        let now = Utc::now();
+        let mut result = AnalysisResult {
+            session_id: uuid::Uuid::new_v4(),
+            source_file_path: Some(PathBuf::from("C:\\Samples\\setup.exe")),
+            metadata: InstallerMetadata {
+                format: InstallerFormat::MSI,
+                created_at: now,
+                ..Default::default()
+            },
+            process_operations: vec![
+                process(500, "services.exe", None),
+                process(100, "setup.exe", Some(4)),
+                process(101, "msiexec.exe", Some(100)),
+                process(102, "helper.exe", Some(101)),
+                process(200, "msiexec.exe", Some(500)),
+                process(201, "msiexec.exe", Some(200)),
+                process(300, "MsMpEng.exe", Some(500)),
+                process(600, "WmiPrvSE.exe", Some(500)),
+                process(400, "powershell.exe", Some(600)),
+            ],
+            wmi_operations: vec![WmiOperation::ProcessCreate {
+                namespace: "root\\cimv2".to_string(),
+                command_line: "powershell.exe -File install.ps1".to_string(),
+                created_process_id: Some(400),
+                timestamp: now,
+                process_id: Some(102),
+            }],
+            analyzed_at: now,
+            dynamic_analysis: true,
+            ..Default::default()
+        };
+
+        let attribution = ProcessAttribution::from_result(&result);
+        assert_eq!(attribution.installer_root, Some(100));
+        assert_eq!(attribution.installer_tree, vec![100, 101, 102, 400]);
+        assert_eq!(attribution.msiexec_service, vec![200, 201]);
+        assert_eq!(attribution.wmi_processes, vec![400]);
+
+        result.source_file_path = None;
+        result.process_operations.remove(0);
+        assert_eq!(ProcessAttribution::from_result(&result), attribution);
+    }
+}
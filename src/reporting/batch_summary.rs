@@ -0,0 +1,283 @@
+//! Machine-readable summary of a batch run
+//!
+//! Next to the per-installer reports, a batch run writes `summary.json` and
+//! `summary.csv` with one entry per input file, so that scripts driving the
+//! analyzer can pick up formats, products, hashes, risk levels and failures
+//! without parsing log output. Analyzed inputs are also counted per
+//! canonical vendor.
+
+use crate::core::{AnalysisResult, AnalyzerError, Result};
+use crate::reporting::database::VendorCount;
+use crate::reporting::{ReportGenerator, VendorInfo};
+use crate::utils::extended_length;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+
+/// Columns of `summary.csv`, in order
+const CSV_HEADER: &str =
+    "input,status,format,product,version,vendor,category,hash,risk,duration_secs,attempts,policy_violations,report,error,quarantined";
+
+/// Outcome of one input of a batch run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BatchStatus {
+    Ok,
+    Failed,
+    /// Not an installer format the analyzer supports
+    Skipped,
+}
+
+impl BatchStatus {
+    /// Value used in the summary files
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Failed => "failed",
+            Self::Skipped => "skipped",
+        }
+    }
+}
+
+/// Summary entry of one input file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatchEntry {
+    pub input: String,
+    pub status: BatchStatus,
+    pub format: Option<String>,
+    pub product: Option<String>,
+    pub version: Option<String>,
+    /// Canonical vendor, or the manufacturer if the vendor knowledge base has none
+    pub vendor: Option<String>,
+    /// Product category from the vendor knowledge base
+    pub category: Option<String>,
+    /// SHA-256 of the installer
+    pub hash: Option<String>,
+    /// Risk level as in the Markdown report: `low`, `medium` or `high`
+    pub risk: Option<String>,
+    pub duration_secs: f64,
+    /// Number of times the input was analyzed, including retries
+    pub attempts: u32,
+    pub policy_violations: usize,
+    /// Report written for the input
+    pub report: Option<String>,
+    pub error: Option<String>,
+    /// Copy of an input that could not be analyzed
+    pub quarantined: Option<String>,
+}
+
+impl BatchEntry {
+    /// Entry of an analyzed input
+    pub fn analyzed(
+        input: &Path,
+        result: &AnalysisResult,
+        report: &Path,
+        duration: Duration,
+        policy_violations: usize,
+    ) -> Self {
+        let vendor = VendorInfo::from_properties(&result.metadata.properties);
+        Self {
+            status: BatchStatus::Ok,
+            format: Some(result.metadata.format.to_string()),
+            product: result.metadata.product_name.clone(),
+            version: result.metadata.product_version.clone(),
+            category: vendor.as_ref().and_then(|info| info.category.clone()),
+            vendor: vendor
+                .map(|info| info.vendor)
+                .or_else(|| result.metadata.manufacturer.clone()),
+            hash: Some(result.metadata.file_hash.clone()).filter(|hash| !hash.is_empty()),
+            risk: Some(ReportGenerator::new().calculate_risk_level(result)),
+            policy_violations,
+            report: Some(report.display().to_string()),
+            ..Self::new(input, BatchStatus::Ok, duration)
+        }
+    }
+
+    /// Entry of an input that could not be analyzed
+    pub fn failed(input: &Path, error: &AnalyzerError, duration: Duration) -> Self {
+        Self {
+            error: Some(error.to_string()),
+            ..Self::new(input, BatchStatus::Failed, duration)
+        }
+    }
+
+    /// Entry of an input that was not analyzed
+    pub fn skipped(input: &Path) -> Self {
+        Self::new(input, BatchStatus::Skipped, Duration::ZERO)
+    }
+
+    fn new(input: &Path, status: BatchStatus, duration: Duration) -> Self {
+        Self {
+            input: input.display().to_string(),
+            status,
+            format: None,
+            product: None,
+            version: None,
+            vendor: None,
+            category: None,
+            hash: None,
+            risk: None,
+            duration_secs: duration.as_secs_f64(),
+            attempts: u32::from(status != BatchStatus::Skipped),
+            policy_violations: 0,
+            report: None,
+            error: None,
+            quarantined: None,
+        }
+    }
+}
+
+/// Entries of all inputs of a batch run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatchSummary {
+    pub generated_at: DateTime<Utc>,
+    pub input_dir: String,
+    pub ok: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    /// Analyzed inputs per vendor, largest first
+    pub vendors: Vec<VendorCount>,
+    pub entries: Vec<BatchEntry>,
+}
+
+impl BatchSummary {
+    /// Summarize the entries of a batch run over `input_dir`
+    pub fn new(input_dir: &Path, entries: Vec<BatchEntry>) -> Self {
+        let count = |status| entries.iter().filter(|e| e.status == status).count();
+        let mut vendors: Vec<VendorCount> = Vec::new();
+        for entry in entries.iter().filter(|e| e.status == BatchStatus::Ok) {
+            let vendor = entry.vendor.as_deref().unwrap_or("Unknown");
+            match vendors.iter_mut().find(|count| count.vendor == vendor) {
+                Some(count) => count.installers += 1,
+                None => vendors.push(VendorCount {
+                    vendor: vendor.to_string(),
+                    installers: 1,
+                }),
+            }
+        }
+        vendors.sort_by(|a, b| {
+            b.installers
+                .cmp(&a.installers)
+                .then(a.vendor.cmp(&b.vendor))
+        });
+        Self {
+            generated_at: Utc::now(),
+            input_dir: input_dir.display().to_string(),
+            ok: count(BatchStatus::Ok),
+            failed: count(BatchStatus::Failed),
+            skipped: count(BatchStatus::Skipped),
+            vendors,
+            entries,
+        }
+    }
+
+    /// Render the entries as CSV with a header row
+    pub fn to_csv(&self) -> String {
+        let mut csv = format!("{}\n", CSV_HEADER);
+        for entry in &self.entries {
+            let optional = |value: &Option<String>| csv_field(value.as_deref().unwrap_or(""));
+            let fields = [
+                csv_field(&entry.input),
+                entry.status.as_str().to_string(),
+                optional(&entry.format),
+                optional(&entry.product),
+                optional(&entry.version),
+                optional(&entry.vendor),
+                optional(&entry.category),
+                optional(&entry.hash),
+                optional(&entry.risk),
+                format!("{:.3}", entry.duration_secs),
+                entry.attempts.to_string(),
+                entry.policy_violations.to_string(),
+                optional(&entry.report),
+                optional(&entry.error),
+                optional(&entry.quarantined),
+            ];
+            csv.push_str(&fields.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+
+    /// Write `summary.json` and `summary.csv` into the output directory
+    pub async fn save(&self, output_dir: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(AnalyzerError::SerializationError)?;
+        let output_dir = extended_length(output_dir);
+        tokio::fs::write(output_dir.join("summary.json"), json).await?;
+        tokio::fs::write(output_dir.join("summary.csv"), self.to_csv()).await?;
+        tracing::info!("Batch summary written to: {}", output_dir.display());
+        Ok(())
+    }
+}
+
+/// Quote a CSV field if it contains separators, quotes or line breaks
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_summary_csv() {
+        let mut failed = BatchEntry::failed(
+            Path::new("broken, \"old\".msi"),
+            &AnalyzerError::config_error("bad"),
+            Duration::from_millis(1500),
+        );
+        failed.error = Some("line one\nline two".to_string());
+        let summary = BatchSummary::new(
+            Path::new("installers"),
+            vec![failed, BatchEntry::skipped(Path::new("readme.txt"))],
+        );
+        assert_eq!((summary.ok, summary.failed, summary.skipped), (0, 1, 1));
+
+        let csv = summary.to_csv();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some(CSV_HEADER));
+        assert_eq!(
+            lines.next(),
+            Some("\"broken, \"\"old\"\".msi\",failed,,,,,,,,1.500,1,0,,\"line one")
+        );
+        assert!(csv.ends_with("readme.txt,skipped,,,,,,,,0.000,0,0,,,\n"));
+
+        let json = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json["entries"][1]["status"], "skipped");
+    }
+
+    #[test]
+    fn test_batch_summary_vendors() {
+        let analyzed = |input: &str, vendor: Option<&str>| BatchEntry {
+            vendor: vendor.map(str::to_string),
+            ..BatchEntry::new(Path::new(input), BatchStatus::Ok, Duration::ZERO)
+        };
+        let summary = BatchSummary::new(
+            Path::new("installers"),
+            vec![
+                analyzed("chrome.msi", Some("Google LLC")),
+                analyzed("tool.exe", None),
+                analyzed("drive.exe", Some("Google LLC")),
+                BatchEntry::skipped(Path::new("readme.txt")),
+            ],
+        );
+        assert_eq!(
+            summary.vendors,
+            [
+                VendorCount {
+                    vendor: "Google LLC".to_string(),
+                    installers: 2
+                },
+                VendorCount {
+                    vendor: "Unknown".to_string(),
+                    installers: 1
+                },
+            ]
+        );
+    }
+}
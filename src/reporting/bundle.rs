@@ -0,0 +1,425 @@
+//! Report bundle output
+//!
+//! Packages the report, the unified JSON and the analysis artifacts into a
+//! single ZIP archive. With a password, every entry is encrypted with WinZip
+//! AES-256 (AE-2), which 7-Zip, WinZip and most archive tools can open.
+
+use crate::core::{AnalyzerError, Result};
+use aes::cipher::{BlockEncrypt, KeyInit};
+use aes::Aes256;
+use byteorder::{LittleEndian, WriteBytesExt};
+use chrono::{Datelike, Local, Timelike};
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// PBKDF2 iterations mandated by the WinZip AES specification
+const AES_KEY_ITERATIONS: u32 = 1000;
+/// Salt length for AES-256
+const AES_SALT_LEN: usize = 16;
+/// Length of the truncated HMAC-SHA1 authentication code
+const AES_AUTH_CODE_LEN: usize = 10;
+
+/// ZIP archive of report files, written to its output file as files are added
+///
+/// Call [`ReportBundle::finish`] to write the central directory; an
+/// unfinished bundle is not a valid archive.
+pub struct ReportBundle {
+    output_path: PathBuf,
+    writer: BundleWriter,
+    names: Vec<String>,
+}
+
+/// Archive writer of a bundle
+enum BundleWriter {
+    Plain(Box<zip::ZipWriter<BufWriter<File>>>),
+    Encrypted(EncryptedZipWriter),
+}
+
+impl ReportBundle {
+    /// Create the bundle file, encrypting all entries when a password is given
+    pub fn create(output_path: &Path, password: Option<&str>) -> Result<Self> {
+        if password.is_some_and(str::is_empty) {
+            return Err(AnalyzerError::config_error(
+                "Bundle password must not be empty",
+            ));
+        }
+        let file = BufWriter::new(File::create(crate::utils::extended_length(output_path))?);
+        let writer = match password {
+            Some(password) => BundleWriter::Encrypted(EncryptedZipWriter::new(file, password)),
+            None => BundleWriter::Plain(Box::new(zip::ZipWriter::new(file))),
+        };
+        Ok(Self {
+            output_path: output_path.to_path_buf(),
+            writer,
+            names: Vec::new(),
+        })
+    }
+
+    /// Add a file with the given archive path
+    pub fn add_file(&mut self, name: impl Into<String>, content: impl AsRef<[u8]>) -> Result<()> {
+        self.add_entry(name.into(), &mut content.as_ref())
+    }
+
+    /// Add all files below a directory, stored under `prefix`
+    ///
+    /// Files are read one at a time as they are added.
+    pub async fn add_dir(&mut self, dir: &Path, prefix: &str) -> Result<()> {
+        let mut pending = vec![dir.to_path_buf()];
+        let mut files = Vec::new();
+
+        while let Some(current) = pending.pop() {
+            let mut entries = tokio::fs::read_dir(&current).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                let path = entry.path();
+                if entry.file_type().await?.is_dir() {
+                    pending.push(path);
+                } else {
+                    files.push(path);
+                }
+            }
+        }
+
+        // Stable ordering regardless of directory iteration order
+        files.sort();
+        for path in files {
+            let relative = path
+                .strip_prefix(dir)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .replace('\\', "/");
+            let mut file = File::open(&path)?;
+            self.add_entry(format!("{}/{}", prefix, relative), &mut file)?;
+        }
+
+        Ok(())
+    }
+
+    /// Names of the bundled files in insertion order
+    pub fn file_names(&self) -> Vec<&str> {
+        self.names.iter().map(String::as_str).collect()
+    }
+
+    /// Write the central directory and close the bundle
+    pub fn finish(self) -> Result<()> {
+        match self.writer {
+            BundleWriter::Plain(mut writer) => {
+                writer
+                    .finish()
+                    .map_err(|e| AnalyzerError::generic(format!("Failed to finish bundle: {}", e)))?
+                    .flush()?;
+            }
+            BundleWriter::Encrypted(writer) => writer.finish()?,
+        }
+        tracing::info!(
+            "Report bundle with {} files saved to: {}",
+            self.names.len(),
+            self.output_path.display()
+        );
+        Ok(())
+    }
+
+    fn add_entry(&mut self, name: String, content: &mut dyn Read) -> Result<()> {
+        match &mut self.writer {
+            BundleWriter::Plain(writer) => {
+                // Entries past 4 GB are written as ZIP64 entries
+                let options = zip::write::FileOptions::default()
+                    .compression_method(zip::CompressionMethod::Deflated)
+                    .large_file(true);
+                writer.start_file(name.as_str(), options).map_err(|e| {
+                    AnalyzerError::generic(format!("Failed to add {}: {}", name, e))
+                })?;
+                std::io::copy(content, writer)?;
+            }
+            BundleWriter::Encrypted(writer) => writer.add(&name, content)?,
+        }
+        self.names.push(name);
+        Ok(())
+    }
+}
+
+/// Writer of a ZIP archive with WinZip AES-256 (AE-2) encrypted entries
+///
+/// The `zip` crate can read but not write AES entries, so the container is
+/// written here directly. Entries are deflated before encryption. Without
+/// ZIP64 records the archive is limited to 4 GB and 65535 entries; adding
+/// past the limits is an error rather than a corrupt archive.
+struct EncryptedZipWriter {
+    out: BufWriter<File>,
+    password: Vec<u8>,
+    dos_time: u16,
+    dos_date: u16,
+    /// Bytes written so far
+    offset: u64,
+    central_directory: Vec<u8>,
+    entry_count: u16,
+}
+
+impl EncryptedZipWriter {
+    fn new(out: BufWriter<File>, password: &str) -> Self {
+        let (dos_time, dos_date) = dos_timestamp();
+        Self {
+            out,
+            password: password.as_bytes().to_vec(),
+            dos_time,
+            dos_date,
+            offset: 0,
+            central_directory: Vec::new(),
+            entry_count: 0,
+        }
+    }
+
+    fn add(&mut self, name: &str, content: &mut dyn Read) -> Result<()> {
+        let entry_count = self.entry_count.checked_add(1).ok_or_else(too_large)?;
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        let uncompressed_size = std::io::copy(content, &mut encoder)?;
+        let compressed = encoder.finish()?;
+
+        let encrypted = encrypt_aes_entry(&self.password, &compressed)?;
+        let header = EntryHeader {
+            name: name.as_bytes(),
+            dos_time: self.dos_time,
+            dos_date: self.dos_date,
+            compressed_size: to_u32(encrypted.len() as u64)?,
+            uncompressed_size: to_u32(uncompressed_size)?,
+        };
+        let local_offset = to_u32(self.offset)?;
+        let mut local = Vec::new();
+        header.write_local(&mut local)?;
+        self.out.write_all(&local)?;
+        self.out.write_all(&encrypted)?;
+        self.offset += (local.len() + encrypted.len()) as u64;
+        header.write_central(&mut self.central_directory, local_offset)?;
+        self.entry_count = entry_count;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<()> {
+        let central_offset = to_u32(self.offset)?;
+        let central_size = to_u32(self.central_directory.len() as u64)?;
+        to_u32(self.offset + self.central_directory.len() as u64)?;
+        self.out.write_all(&self.central_directory)?;
+
+        // End of central directory record
+        let out = &mut self.out;
+        out.write_u32::<LittleEndian>(0x0605_4b50)?;
+        out.write_u16::<LittleEndian>(0)?;
+        out.write_u16::<LittleEndian>(0)?;
+        out.write_u16::<LittleEndian>(self.entry_count)?;
+        out.write_u16::<LittleEndian>(self.entry_count)?;
+        out.write_u32::<LittleEndian>(central_size)?;
+        out.write_u32::<LittleEndian>(central_offset)?;
+        out.write_u16::<LittleEndian>(0)?;
+        out.flush()?;
+        Ok(())
+    }
+}
+
+/// Size or offset as a 32-bit ZIP field
+fn to_u32(value: u64) -> Result<u32> {
+    u32::try_from(value).map_err(|_| too_large())
+}
+
+fn too_large() -> AnalyzerError {
+    AnalyzerError::generic("Encrypted bundles are limited to 4 GB and 65535 files")
+}
+
+/// Local and central directory header fields of an AES-encrypted entry
+struct EntryHeader<'a> {
+    name: &'a [u8],
+    dos_time: u16,
+    dos_date: u16,
+    compressed_size: u32,
+    uncompressed_size: u32,
+}
+
+impl EntryHeader<'_> {
+    /// Encrypted, UTF-8 file name
+    const FLAGS: u16 = 0x0001 | 0x0800;
+    /// Compression method signalling WinZip AES encryption
+    const METHOD_AES: u16 = 99;
+    /// Version 5.1 is required for AES encryption
+    const VERSION: u16 = 51;
+
+    fn write_local(&self, out: &mut Vec<u8>) -> Result<()> {
+        out.write_u32::<LittleEndian>(0x0403_4b50)?;
+        out.write_u16::<LittleEndian>(Self::VERSION)?;
+        self.write_common(out)?;
+        out.extend_from_slice(self.name);
+        Self::write_aes_extra(out)?;
+        Ok(())
+    }
+
+    fn write_central(&self, out: &mut Vec<u8>, local_offset: u32) -> Result<()> {
+        out.write_u32::<LittleEndian>(0x0201_4b50)?;
+        out.write_u16::<LittleEndian>(Self::VERSION)?;
+        out.write_u16::<LittleEndian>(Self::VERSION)?;
+        self.write_common(out)?;
+        out.write_u16::<LittleEndian>(0)?; // comment length
+        out.write_u16::<LittleEndian>(0)?; // disk number
+        out.write_u16::<LittleEndian>(0)?; // internal attributes
+        out.write_u32::<LittleEndian>(0)?; // external attributes
+        out.write_u32::<LittleEndian>(local_offset)?;
+        out.extend_from_slice(self.name);
+        Self::write_aes_extra(out)?;
+        Ok(())
+    }
+
+    /// Fields shared by local and central headers, from flags to extra length
+    fn write_common(&self, out: &mut Vec<u8>) -> Result<()> {
+        out.write_u16::<LittleEndian>(Self::FLAGS)?;
+        out.write_u16::<LittleEndian>(Self::METHOD_AES)?;
+        out.write_u16::<LittleEndian>(self.dos_time)?;
+        out.write_u16::<LittleEndian>(self.dos_date)?;
+        out.write_u32::<LittleEndian>(0)?; // AE-2 stores no CRC
+        out.write_u32::<LittleEndian>(self.compressed_size)?;
+        out.write_u32::<LittleEndian>(self.uncompressed_size)?;
+        out.write_u16::<LittleEndian>(self.name.len() as u16)?;
+        out.write_u16::<LittleEndian>(11)?; // extra field length
+        Ok(())
+    }
+
+    /// AES extra field: AE-2, vendor "AE", AES-256, deflate
+    fn write_aes_extra(out: &mut Vec<u8>) -> Result<()> {
+        out.write_u16::<LittleEndian>(0x9901)?;
+        out.write_u16::<LittleEndian>(7)?;
+        out.write_u16::<LittleEndian>(2)?;
+        out.extend_from_slice(b"AE");
+        out.write_u8(3)?;
+        out.write_u16::<LittleEndian>(8)?;
+        Ok(())
+    }
+}
+
+/// Encrypt entry data: salt, password verifier, ciphertext, authentication code
+fn encrypt_aes_entry(password: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+    let mut salt = [0u8; AES_SALT_LEN];
+    getrandom::getrandom(&mut salt)
+        .map_err(|e| AnalyzerError::generic(format!("Failed to generate salt: {}", e)))?;
+
+    // Derived key material: encryption key, authentication key, verifier
+    let mut derived = [0u8; 66];
+    pbkdf2::pbkdf2::<Hmac<Sha1>>(password, &salt, AES_KEY_ITERATIONS, &mut derived);
+    let (encryption_key, rest) = derived.split_at(32);
+    let (auth_key, verifier) = rest.split_at(32);
+
+    let mut ciphertext = data.to_vec();
+    aes_ctr_apply(encryption_key, &mut ciphertext);
+
+    let mut mac = <Hmac<Sha1> as Mac>::new_from_slice(auth_key)
+        .map_err(|e| AnalyzerError::generic(format!("Invalid authentication key: {}", e)))?;
+    mac.update(&ciphertext);
+    let auth_code = mac.finalize().into_bytes();
+
+    let mut out = Vec::with_capacity(AES_SALT_LEN + 2 + ciphertext.len() + AES_AUTH_CODE_LEN);
+    out.extend_from_slice(&salt);
+    out.extend_from_slice(verifier);
+    out.extend_from_slice(&ciphertext);
+    out.extend_from_slice(&auth_code[..AES_AUTH_CODE_LEN]);
+    Ok(out)
+}
+
+/// AES-CTR as used by WinZip: little-endian counter starting at 1
+fn aes_ctr_apply(key: &[u8], data: &mut [u8]) {
+    let cipher = Aes256::new(aes::cipher::generic_array::GenericArray::from_slice(key));
+
+    for (index, chunk) in data.chunks_mut(16).enumerate() {
+        let counter = index as u128 + 1;
+        let mut block = aes::Block::from(counter.to_le_bytes());
+        cipher.encrypt_block(&mut block);
+        for (byte, key_byte) in chunk.iter_mut().zip(block.iter()) {
+            *byte ^= key_byte;
+        }
+    }
+}
+
+/// Current local time in MS-DOS time and date format
+fn dos_timestamp() -> (u16, u16) {
+    let now = Local::now();
+    let time = (now.hour() << 11) | (now.minute() << 5) | (now.second() / 2);
+    let date = ((now.year().max(1980) - 1980) as u32) << 9 | (now.month() << 5) | now.day();
+    (time as u16, date as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn sample_bundle(path: &Path, password: Option<&str>) {
+        let mut bundle = ReportBundle::create(path, password).unwrap();
+        bundle
+            .add_file("report.html", "<html>report</html>")
+            .unwrap();
+        bundle
+            .add_file(
+                "artifacts/files/install.ps1",
+                "Write-Host 'hello'".repeat(50),
+            )
+            .unwrap();
+        assert_eq!(
+            bundle.file_names(),
+            vec!["report.html", "artifacts/files/install.ps1"]
+        );
+        bundle.finish().unwrap();
+    }
+
+    #[test]
+    fn test_plain_bundle_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bundle.zip");
+        sample_bundle(&path, None);
+        let mut archive = zip::ZipArchive::new(File::open(&path).unwrap()).unwrap();
+
+        let mut content = String::new();
+        archive
+            .by_name("report.html")
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "<html>report</html>");
+    }
+
+    #[test]
+    fn test_encrypted_bundle_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bundle.zip");
+        sample_bundle(&path, Some("infected"));
+        let mut archive = zip::ZipArchive::new(File::open(&path).unwrap()).unwrap();
+        assert_eq!(archive.len(), 2);
+
+        // Reading without the password is refused
+        assert!(archive.by_name("report.html").is_err());
+
+        let mut content = String::new();
+        archive
+            .by_name_decrypt("artifacts/files/install.ps1", b"infected")
+            .unwrap()
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "Write-Host 'hello'".repeat(50));
+
+        assert!(archive
+            .by_name_decrypt("report.html", b"wrong")
+            .unwrap()
+            .is_err());
+        assert!(ReportBundle::create(&dir.path().join("empty.zip"), Some("")).is_err());
+    }
+
+    #[test]
+    fn test_encrypted_bundle_limits() {
+        assert_eq!(to_u32(u32::MAX as u64).unwrap(), u32::MAX);
+        assert!(to_u32(u32::MAX as u64 + 1).is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let out = BufWriter::new(File::create(dir.path().join("full.zip")).unwrap());
+        let mut writer = EncryptedZipWriter::new(out, "infected");
+        writer.entry_count = u16::MAX;
+        assert!(writer.add("one-too-many.txt", &mut &b"data"[..]).is_err());
+    }
+}
@@ -0,0 +1,394 @@
+//! Product identity and cross-report correlation
+//!
+//! Windows installers carry stable identifiers that survive across releases:
+//! MSI packages have an UpgradeCode shared by every version of a product and
+//! a ProductCode per release, and installers register the product under an
+//! Add/Remove Programs (ARP) key below
+//! `SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall`. Comparing these
+//! identifiers tells whether two artifacts are versions of the same product.
+
+use crate::core::{
+    normalize_text, normalize_vendor, normalize_version, AnalysisResult, RegistryOperation,
+    SupportLevel,
+};
+use serde::{Deserialize, Serialize};
+
+/// Registry path below which installers register uninstall entries
+const UNINSTALL_KEY: &str = "\\microsoft\\windows\\currentversion\\uninstall\\";
+
+/// Identifiers of the product an installer installs
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProductIdentity {
+    /// Installer file name or report the identity was read from
+    pub source: String,
+    pub format: String,
+    pub product_name: Option<String>,
+    pub product_version: Option<String>,
+    pub manufacturer: Option<String>,
+    /// MSI ProductCode, unique per release
+    pub product_code: Option<String>,
+    /// MSI UpgradeCode, shared by all releases of a product
+    pub upgrade_code: Option<String>,
+    /// Add/Remove Programs keys the installer writes
+    #[serde(default)]
+    pub arp_keys: Vec<String>,
+}
+
+impl ProductIdentity {
+    /// Collect the identity of an analysis result
+    ///
+    /// ARP keys are only taken from registry operations that were read from
+    /// the installer or observed in the sandbox, not from heuristic entries.
+    pub fn from_result(result: &AnalysisResult) -> Self {
+        let properties = &result.metadata.properties;
+        let heuristic_registry = !result.dynamic_analysis
+            && result
+                .analyzer_capabilities
+                .as_ref()
+                .is_some_and(|caps| caps.registry_operations == SupportLevel::Heuristic);
+
+        let mut arp_keys: Vec<String> = Vec::new();
+        if !heuristic_registry {
+            for operation in &result.registry_operations {
+                let key_path = match operation {
+                    RegistryOperation::CreateKey { key_path, .. }
+                    | RegistryOperation::SetValue { key_path, .. } => key_path,
+                    _ => continue,
+                };
+                if let Some(key) = arp_key_name(key_path) {
+                    if !arp_keys
+                        .iter()
+                        .any(|known| known.eq_ignore_ascii_case(&key))
+                    {
+                        arp_keys.push(key);
+                    }
+                }
+            }
+        }
+
+        Self {
+            source: result
+                .source_file_path
+                .as_ref()
+                .and_then(|p| p.file_name())
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| "Unknown Package".to_string()),
+            format: result.metadata.format.to_string(),
+            product_name: result.metadata.product_name.clone(),
+            product_version: result.metadata.product_version.clone(),
+            manufacturer: result.metadata.manufacturer.clone(),
+            product_code: properties
+                .get("ProductCode")
+                .and_then(|v| normalize_guid(v)),
+            upgrade_code: properties
+                .get("UpgradeCode")
+                .and_then(|v| normalize_guid(v)),
+            arp_keys,
+        }
+    }
+
+    /// Read the identity embedded in a JSON report
+    pub fn from_report(report: &serde_json::Value) -> Option<Self> {
+        serde_json::from_value(report.get("product_identity")?.clone()).ok()
+    }
+
+    /// Whether any installer identifier is known
+    pub fn has_identifiers(&self) -> bool {
+        self.product_code.is_some() || self.upgrade_code.is_some() || !self.arp_keys.is_empty()
+    }
+
+    /// ARP keys the product is registered under; MSI uses the ProductCode
+    fn uninstall_keys(&self) -> impl Iterator<Item = &str> {
+        self.arp_keys
+            .iter()
+            .map(String::as_str)
+            .chain(self.product_code.as_deref())
+    }
+}
+
+/// How two artifacts relate to each other
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Relationship {
+    /// The same release of the same product
+    SameRelease,
+    /// Different versions of the same product
+    SameFamily,
+    Unrelated,
+}
+
+/// Strength of the evidence behind a relationship
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Confidence {
+    /// Only names match
+    Medium,
+    /// Installer identifiers match
+    High,
+}
+
+/// Outcome of comparing two product identities
+#[derive(Debug, Clone, Serialize)]
+pub struct Correlation {
+    pub relationship: Relationship,
+    pub confidence: Option<Confidence>,
+    /// Identifiers or names the artifacts share
+    pub evidence: Vec<String>,
+}
+
+/// Compare two identities
+pub fn correlate(left: &ProductIdentity, right: &ProductIdentity) -> Correlation {
+    let mut evidence = Vec::new();
+
+    if let (Some(a), Some(b)) = (&left.upgrade_code, &right.upgrade_code) {
+        if a == b {
+            evidence.push(format!("same UpgradeCode {}", a));
+        }
+    }
+    if let (Some(a), Some(b)) = (&left.product_code, &right.product_code) {
+        if a == b {
+            evidence.push(format!("same ProductCode {}", a));
+        }
+    }
+    for key in left.uninstall_keys() {
+        let shared = right
+            .uninstall_keys()
+            .any(|other| other.eq_ignore_ascii_case(key));
+        let is_product_code =
+            left.product_code.as_deref() == Some(key) && right.product_code.as_deref() == Some(key);
+        let evidence_line = format!("same Add/Remove Programs key {}", key);
+        if shared && !is_product_code && !evidence.contains(&evidence_line) {
+            evidence.push(evidence_line);
+        }
+    }
+
+    let confidence = if !evidence.is_empty() {
+        Some(Confidence::High)
+    } else if names_match(left, right) {
+        evidence.push(format!(
+            "same product name and manufacturer ({})",
+            left.product_name.as_deref().unwrap_or_default()
+        ));
+        Some(Confidence::Medium)
+    } else {
+        None
+    };
+
+    let relationship = match confidence {
+        None => Relationship::Unrelated,
+        Some(_) if versions_match(left, right) => Relationship::SameRelease,
+        Some(_) => Relationship::SameFamily,
+    };
+
+    Correlation {
+        relationship,
+        confidence,
+        evidence,
+    }
+}
+
+/// Group identities into product families, returning indices per family
+///
+/// Relationships are transitive, so an EXE bootstrapper that writes the ARP
+/// key of an MSI links it to other versions of that MSI.
+pub fn product_families(identities: &[ProductIdentity]) -> Vec<Vec<usize>> {
+    let mut family: Vec<usize> = (0..identities.len()).collect();
+    fn root(family: &mut [usize], mut index: usize) -> usize {
+        while family[index] != index {
+            family[index] = family[family[index]];
+            index = family[index];
+        }
+        index
+    }
+
+    for left in 0..identities.len() {
+        for right in left + 1..identities.len() {
+            if correlate(&identities[left], &identities[right]).relationship
+                != Relationship::Unrelated
+            {
+                let (a, b) = (root(&mut family, left), root(&mut family, right));
+                family[b] = a;
+            }
+        }
+    }
+
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut group_of_root = std::collections::HashMap::new();
+    for index in 0..identities.len() {
+        let root = root(&mut family, index);
+        let group = *group_of_root.entry(root).or_insert_with(|| {
+            groups.push(Vec::new());
+            groups.len() - 1
+        });
+        groups[group].push(index);
+    }
+    groups
+}
+
+/// Normalize a GUID to the registry form `{XXXXXXXX-XXXX-XXXX-XXXX-XXXXXXXXXXXX}`
+pub fn normalize_guid(value: &str) -> Option<String> {
+    let hex: String = value
+        .trim()
+        .trim_start_matches('{')
+        .trim_end_matches('}')
+        .chars()
+        .filter(|c| *c != '-')
+        .collect();
+    if hex.len() != 32 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let hex = hex.to_uppercase();
+    Some(format!(
+        "{{{}-{}-{}-{}-{}}}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    ))
+}
+
+/// Name of the ARP entry a registry key belongs to
+///
+/// Placeholders such as `[ProductCode]` or `$APPNAME` are not resolvable and
+/// yield `None`.
+pub fn arp_key_name(key_path: &str) -> Option<String> {
+    let lower = key_path.to_lowercase();
+    let start = lower.find(UNINSTALL_KEY)? + UNINSTALL_KEY.len();
+    let name = key_path[start..].split('\\').next()?.trim();
+
+    if name.is_empty() || name.contains(['[', '$', '%']) {
+        return None;
+    }
+    Some(normalize_guid(name).unwrap_or_else(|| name.to_string()))
+}
+
+fn names_match(left: &ProductIdentity, right: &ProductIdentity) -> bool {
+    // Reports written before metadata normalization hold the raw values
+    let same =
+        |a: &Option<String>, b: &Option<String>, normalize: fn(&str) -> Option<String>| match (
+            a.as_deref().and_then(normalize),
+            b.as_deref().and_then(normalize),
+        ) {
+            (Some(a), Some(b)) => !a.eq_ignore_ascii_case("unknown") && a.eq_ignore_ascii_case(&b),
+            _ => false,
+        };
+    same(&left.product_name, &right.product_name, normalize_text)
+        && same(&left.manufacturer, &right.manufacturer, normalize_vendor)
+}
+
+fn versions_match(left: &ProductIdentity, right: &ProductIdentity) -> bool {
+    match (&left.product_version, &right.product_version) {
+        (Some(a), Some(b)) => normalize_version(a) == normalize_version(b),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identity(source: &str, version: &str) -> ProductIdentity {
+        ProductIdentity {
+            source: source.to_string(),
+            format: "MSI".to_string(),
+            product_name: Some("Contoso Tools".to_string()),
+            product_version: Some(version.to_string()),
+            manufacturer: Some("Contoso".to_string()),
+            ..ProductIdentity::default()
+        }
+    }
+
+    #[test]
+    fn test_identifier_helpers() {
+        assert_eq!(
+            normalize_guid("12345678-9abc-def0-1234-56789abcdef0").as_deref(),
+            Some("{12345678-9ABC-DEF0-1234-56789ABCDEF0}")
+        );
+        assert_eq!(normalize_guid("not-a-guid"), None);
+        assert_eq!(
+            arp_key_name(
+                "HKEY_LOCAL_MACHINE\\SOFTWARE\\WOW6432Node\\Microsoft\\Windows\\CurrentVersion\\Uninstall\\Contoso_is1"
+            )
+            .as_deref(),
+            Some("Contoso_is1")
+        );
+        assert_eq!(
+            arp_key_name(
+                "HKLM\\Software\\Microsoft\\Windows\\CurrentVersion\\Uninstall\\[ProductCode]"
+            ),
+            None
+        );
+        assert_eq!(arp_key_name("HKLM\\Software\\Contoso"), None);
+    }
+
+    #[test]
+    fn test_correlate_product_family() {
+        let upgrade_code = normalize_guid("0B1C2D3E-4F50-6172-8394-A5B6C7D8E9F0");
+        let msi_v1 = ProductIdentity {
+            product_code: normalize_guid("11111111-1111-1111-1111-111111111111"),
+            upgrade_code: upgrade_code.clone(),
+            ..identity("tools-1.0.msi", "1.0.0")
+        };
+        let msi_v2 = ProductIdentity {
+            product_code: normalize_guid("22222222-2222-2222-2222-222222222222"),
+            upgrade_code,
+            product_name: Some("Contoso Tools 2".to_string()),
+            ..identity("tools-2.0.msi", "2.0.0")
+        };
+        // Bootstrapper observed writing the ARP key of the 2.0 MSI
+        let bootstrapper = ProductIdentity {
+            format: "NSIS".to_string(),
+            product_name: Some("Setup".to_string()),
+            arp_keys: vec!["{22222222-2222-2222-2222-222222222222}".to_string()],
+            ..identity("setup.exe", "2.0.0")
+        };
+        let other = ProductIdentity {
+            product_name: Some("Fabrikam".to_string()),
+            ..identity("fabrikam.msi", "1.0.0")
+        };
+
+        let correlation = correlate(&msi_v1, &msi_v2);
+        assert_eq!(correlation.relationship, Relationship::SameFamily);
+        assert_eq!(correlation.confidence, Some(Confidence::High));
+        assert!(correlation.evidence[0].contains("UpgradeCode"));
+
+        let correlation = correlate(&bootstrapper, &msi_v2);
+        assert_eq!(correlation.relationship, Relationship::SameRelease);
+        assert!(correlation.evidence[0].contains("Add/Remove Programs"));
+
+        assert_eq!(
+            correlate(&msi_v1, &other).relationship,
+            Relationship::Unrelated
+        );
+        let renamed = identity("tools-1.0-copy.msi", "1.0.0");
+        assert_eq!(
+            correlate(&msi_v1, &renamed).confidence,
+            Some(Confidence::Medium)
+        );
+
+        let families = product_families(&[msi_v1, other, bootstrapper, msi_v2]);
+        assert_eq!(families, vec![vec![0, 2, 3], vec![1]]);
+    }
+
+    #[test]
+    fn test_names_match_across_spellings() {
+        let left = ProductIdentity {
+            product_name: Some("Contoso Tools™".to_string()),
+            manufacturer: Some("Contoso Corp.".to_string()),
+            ..identity("tools.msi", "1, 0, 0, 0")
+        };
+        let right = ProductIdentity {
+            product_name: Some(" Contoso  Tools".to_string()),
+            manufacturer: Some("CONTOSO CORPORATION ".to_string()),
+            ..identity("tools-copy.exe", "1.0.0.0")
+        };
+        assert!(names_match(&left, &right));
+        assert!(versions_match(&left, &right));
+        assert_ne!(
+            correlate(&left, &right).relationship,
+            Relationship::Unrelated
+        );
+    }
+}
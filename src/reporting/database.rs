@@ -0,0 +1,872 @@
+//! SQLite results store for fleet-wide inventory queries
+//!
+//! Every analysis recorded with `--db` is appended to the store together
+//! with its file list and the components it ships. Components are
+//! recognised from well-known library file names (e.g. `libssl-1_1-x64.dll`
+//! is OpenSSL 1.1) and from declared dependencies, so questions such as
+//! "which installers ship OpenSSL < 3" can be answered without re-analysis.
+//!
+//! Installers are also filed under their canonical vendor from the vendor
+//! knowledge base, so one vendor is counted once however its installers
+//! spell the publisher.
+//!
+//! Queries only consider the latest analysis of each installer (by hash).
+
+use crate::core::{AnalysisResult, AnalyzerError, Result};
+use crate::reporting::{ProductIdentity, VendorInfo, VendorKnowledgeBase};
+use regex::Regex;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Schema version stored in `PRAGMA user_version`
+const SCHEMA_VERSION: i64 = 2;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS analyses (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    session_id TEXT NOT NULL,
+    source TEXT,
+    file_hash TEXT NOT NULL,
+    format TEXT NOT NULL,
+    product_name TEXT,
+    product_version TEXT,
+    manufacturer TEXT,
+    vendor TEXT,
+    category TEXT,
+    file_size INTEGER NOT NULL,
+    file_count INTEGER NOT NULL,
+    dynamic_analysis INTEGER NOT NULL,
+    analyzed_at TEXT NOT NULL,
+    result_json TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_analyses_file_hash ON analyses(file_hash);
+CREATE INDEX IF NOT EXISTS idx_analyses_vendor ON analyses(vendor);
+
+CREATE TABLE IF NOT EXISTS files (
+    analysis_id INTEGER NOT NULL REFERENCES analyses(id) ON DELETE CASCADE,
+    path TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    hash TEXT
+);
+CREATE INDEX IF NOT EXISTS idx_files_hash ON files(hash);
+
+CREATE TABLE IF NOT EXISTS components (
+    analysis_id INTEGER NOT NULL REFERENCES analyses(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    version TEXT,
+    source TEXT NOT NULL,
+    evidence TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_components_name ON components(name);
+
+CREATE VIEW IF NOT EXISTS latest_analyses AS
+    SELECT * FROM analyses
+    WHERE id IN (SELECT MAX(id) FROM analyses GROUP BY file_hash);
+";
+
+/// Version 2: canonical vendor and product category of each analysis
+const MIGRATE_V2: &str = "
+ALTER TABLE analyses ADD COLUMN vendor TEXT;
+ALTER TABLE analyses ADD COLUMN category TEXT;
+";
+
+/// Vendor an installer is counted under: canonical vendor, else its manufacturer
+const VENDOR_GROUP: &str =
+    "COALESCE(NULLIF(vendor, ''), NULLIF(TRIM(manufacturer), ''), 'Unknown')";
+
+/// Comparison operator of a component version constraint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionOp {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+impl VersionOp {
+    fn matches(&self, ordering: Ordering) -> bool {
+        match self {
+            Self::Lt => ordering == Ordering::Less,
+            Self::Le => ordering != Ordering::Greater,
+            Self::Gt => ordering == Ordering::Greater,
+            Self::Ge => ordering != Ordering::Less,
+            Self::Eq => ordering == Ordering::Equal,
+            Self::Ne => ordering != Ordering::Equal,
+        }
+    }
+}
+
+/// Component filter such as `openssl`, `openssl<3` or `python>=3.10`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComponentFilter {
+    /// Normalized component name
+    pub name: String,
+    pub constraint: Option<(VersionOp, String)>,
+}
+
+impl ComponentFilter {
+    /// Parse a filter expression
+    pub fn parse(expression: &str) -> Result<Self> {
+        let invalid = || {
+            AnalyzerError::config_error(format!(
+                "Invalid component filter '{}' (expected NAME or NAME<OP>VERSION, e.g. openssl<3)",
+                expression
+            ))
+        };
+
+        let Some(op_start) = expression.find(['<', '>', '=', '!']) else {
+            let name = normalize_name(expression);
+            return if name.is_empty() {
+                Err(invalid())
+            } else {
+                Ok(Self {
+                    name,
+                    constraint: None,
+                })
+            };
+        };
+
+        let (name, rest) = expression.split_at(op_start);
+        let (op, version) = [
+            ("<=", VersionOp::Le),
+            (">=", VersionOp::Ge),
+            ("==", VersionOp::Eq),
+            ("!=", VersionOp::Ne),
+            ("<", VersionOp::Lt),
+            (">", VersionOp::Gt),
+            ("=", VersionOp::Eq),
+        ]
+        .into_iter()
+        .find_map(|(token, op)| rest.strip_prefix(token).map(|version| (op, version)))
+        .ok_or_else(invalid)?;
+
+        let name = normalize_name(name);
+        let version = version.trim();
+        if name.is_empty() || version_parts(version).is_empty() {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            name,
+            constraint: Some((op, version.to_string())),
+        })
+    }
+
+    /// Check whether a component version satisfies the constraint
+    ///
+    /// Components without a known version only match filters without one.
+    pub fn matches_version(&self, version: Option<&str>) -> bool {
+        match (&self.constraint, version) {
+            (None, _) => true,
+            (Some((op, expected)), Some(version)) => {
+                op.matches(compare_versions(version, expected))
+            }
+            (Some(_), None) => false,
+        }
+    }
+}
+
+/// Component shipped by or declared for an installer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Component {
+    pub name: String,
+    pub version: Option<String>,
+    /// `file` for bundled libraries, `dependency` for declared dependencies
+    pub source: String,
+    /// File path or version requirement the component was derived from
+    pub evidence: String,
+}
+
+/// Installer shipping a component matched by a query
+#[derive(Debug, Clone, Serialize)]
+pub struct ComponentMatch {
+    pub source: Option<String>,
+    pub product_name: Option<String>,
+    pub product_version: Option<String>,
+    pub manufacturer: Option<String>,
+    /// Canonical vendor from the vendor knowledge base
+    pub vendor: Option<String>,
+    pub file_hash: String,
+    pub component: Component,
+}
+
+/// Installer containing a file with a queried hash
+#[derive(Debug, Clone, Serialize)]
+pub struct FileMatch {
+    pub source: Option<String>,
+    pub product_name: Option<String>,
+    pub file_hash: String,
+    /// Path inside the installer, `None` when the installer itself matched
+    pub path: Option<String>,
+    pub size: u64,
+}
+
+/// Number of installers per vendor
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VendorCount {
+    pub vendor: String,
+    pub installers: u64,
+}
+
+/// Summary of a recorded analysis
+#[derive(Debug, Clone, Serialize)]
+pub struct AnalysisRecord {
+    pub id: i64,
+    pub source: Option<String>,
+    pub format: String,
+    pub product_name: Option<String>,
+    pub product_version: Option<String>,
+    pub manufacturer: Option<String>,
+    /// Canonical vendor from the vendor knowledge base
+    pub vendor: Option<String>,
+    pub category: Option<String>,
+    pub file_hash: String,
+    pub file_count: u64,
+    pub analyzed_at: String,
+}
+
+/// SQLite store of analysis results
+pub struct ResultsDatabase {
+    conn: Connection,
+}
+
+impl ResultsDatabase {
+    /// Open or create a results database
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path).map_err(|e| {
+            AnalyzerError::database_error(format!(
+                "Failed to open results database {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        Self::initialize(conn)
+    }
+
+    /// Open a database held in memory
+    pub fn open_in_memory() -> Result<Self> {
+        Self::initialize(Connection::open_in_memory()?)
+    }
+
+    fn initialize(conn: Connection) -> Result<Self> {
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version > SCHEMA_VERSION {
+            return Err(AnalyzerError::database_error(format!(
+                "Results database schema version {} is newer than supported version {}",
+                version, SCHEMA_VERSION
+            )));
+        }
+
+        conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+        if version == 1 {
+            conn.execute_batch(MIGRATE_V2)?;
+        }
+        conn.execute_batch(SCHEMA)?;
+        if version == 1 {
+            Self::backfill_vendors(&conn)?;
+        }
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        Ok(Self { conn })
+    }
+
+    /// File analyses recorded before version 2 under their canonical vendor
+    fn backfill_vendors(conn: &Connection) -> Result<()> {
+        let mut statement = conn.prepare("SELECT id, result_json FROM analyses")?;
+        let rows = statement
+            .query_map([], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut update =
+            conn.prepare("UPDATE analyses SET vendor = ?2, category = ?3 WHERE id = ?1")?;
+        for (id, json) in rows {
+            let Ok(result) = serde_json::from_str::<AnalysisResult>(&json) else {
+                continue;
+            };
+            if let Some(info) = vendor_of(&result) {
+                update.execute(params![id, info.vendor, info.category])?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Append an analysis result, returning its row id
+    pub fn record(&mut self, result: &AnalysisResult) -> Result<i64> {
+        let result_json = serde_json::to_string(result)?;
+        // Reports received from workers may predate metadata normalization
+        let mut metadata = result.metadata.clone();
+        metadata.normalize();
+        let vendor = vendor_of(result);
+        let tx = self.conn.transaction()?;
+
+        tx.execute(
+            "INSERT INTO analyses (session_id, source, file_hash, format, product_name,
+                product_version, manufacturer, vendor, category, file_size, file_count,
+                dynamic_analysis, analyzed_at, result_json)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            params![
+                result.session_id.to_string(),
+                result
+                    .source_file_path
+                    .as_deref()
+                    .map(crate::utils::entry_file_name),
+                metadata.file_hash.to_lowercase(),
+                metadata.format.to_string(),
+                metadata.product_name,
+                metadata.product_version,
+                metadata.manufacturer,
+                vendor.as_ref().map(|info| &info.vendor),
+                vendor.as_ref().and_then(|info| info.category.as_ref()),
+                metadata.file_size as i64,
+                result.files.len() as i64,
+                result.dynamic_analysis,
+                result.analyzed_at.to_rfc3339(),
+                result_json,
+            ],
+        )?;
+        let analysis_id = tx.last_insert_rowid();
+
+        {
+            let mut insert_file = tx.prepare(
+                "INSERT INTO files (analysis_id, path, size, hash) VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for file in &result.files {
+                insert_file.execute(params![
+                    analysis_id,
+                    file.path.to_string_lossy().replace('\\', "/"),
+                    file.size as i64,
+                    file.hash.as_ref().map(|hash| hash.to_lowercase()),
+                ])?;
+            }
+
+            let mut insert_component = tx.prepare(
+                "INSERT INTO components (analysis_id, name, version, source, evidence)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+            for component in detect_components(result) {
+                insert_component.execute(params![
+                    analysis_id,
+                    component.name,
+                    component.version,
+                    component.source,
+                    component.evidence,
+                ])?;
+            }
+        }
+
+        tx.commit()?;
+        tracing::info!("Recorded analysis {} in results database", analysis_id);
+        Ok(analysis_id)
+    }
+
+    /// Latest analysis of every installer, most recent first
+    pub fn analyses(&self) -> Result<Vec<AnalysisRecord>> {
+        self.records("1", [])
+    }
+
+    /// Latest analysis of every installer of a vendor, most recent first
+    ///
+    /// The vendor is matched ignoring case against the name
+    /// [`ResultsDatabase::vendors`] lists.
+    pub fn analyses_by_vendor(&self, vendor: &str) -> Result<Vec<AnalysisRecord>> {
+        self.records(
+            &format!("{} = ?1 COLLATE NOCASE", VENDOR_GROUP),
+            [vendor.trim()],
+        )
+    }
+
+    fn records<P: rusqlite::Params>(
+        &self,
+        condition: &str,
+        params: P,
+    ) -> Result<Vec<AnalysisRecord>> {
+        let mut statement = self.conn.prepare(&format!(
+            "SELECT id, source, format, product_name, product_version, manufacturer,
+                vendor, category, file_hash, file_count, analyzed_at
+             FROM latest_analyses WHERE {} ORDER BY analyzed_at DESC, id DESC",
+            condition
+        ))?;
+        let records = statement
+            .query_map(params, |row| {
+                Ok(AnalysisRecord {
+                    id: row.get(0)?,
+                    source: row.get(1)?,
+                    format: row.get(2)?,
+                    product_name: row.get(3)?,
+                    product_version: row.get(4)?,
+                    manufacturer: row.get(5)?,
+                    vendor: row.get(6)?,
+                    category: row.get(7)?,
+                    file_hash: row.get(8)?,
+                    file_count: row.get::<_, i64>(9)? as u64,
+                    analyzed_at: row.get(10)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(records)
+    }
+
+    /// Full results of the latest analysis of every installer
+    pub fn latest_results(&self) -> Result<Vec<AnalysisResult>> {
+        let mut statement = self
+            .conn
+            .prepare("SELECT result_json FROM latest_analyses ORDER BY id")?;
+        let rows = statement
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        rows.iter()
+            .map(|json| serde_json::from_str(json).map_err(AnalyzerError::SerializationError))
+            .collect()
+    }
+
+    /// Full result of a recorded analysis
+    pub fn result(&self, id: i64) -> Result<Option<AnalysisResult>> {
+        let json = self
+            .conn
+            .query_row(
+                "SELECT result_json FROM analyses WHERE id = ?1",
+                [id],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?;
+        json.map(|json| serde_json::from_str(&json).map_err(AnalyzerError::SerializationError))
+            .transpose()
+    }
+
+    /// Full result of the latest analysis of the installer with `file_hash`
+    pub fn latest_result_by_hash(&self, file_hash: &str) -> Result<Option<AnalysisResult>> {
+        let json = self
+            .conn
+            .query_row(
+                "SELECT result_json FROM latest_analyses WHERE file_hash = ?1",
+                [file_hash.to_lowercase()],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?;
+        json.map(|json| serde_json::from_str(&json).map_err(AnalyzerError::SerializationError))
+            .transpose()
+    }
+
+    /// Installers shipping a component that matches the filter
+    pub fn find_components(&self, filter: &ComponentFilter) -> Result<Vec<ComponentMatch>> {
+        let mut statement = self.conn.prepare(
+            "SELECT a.source, a.product_name, a.product_version, a.manufacturer, a.vendor,
+                a.file_hash, c.name, c.version, c.source, c.evidence
+             FROM components c JOIN latest_analyses a ON a.id = c.analysis_id
+             WHERE c.name = ?1
+             ORDER BY a.product_name, a.source",
+        )?;
+        let matches = statement
+            .query_map([&filter.name], |row| {
+                Ok(ComponentMatch {
+                    source: row.get(0)?,
+                    product_name: row.get(1)?,
+                    product_version: row.get(2)?,
+                    manufacturer: row.get(3)?,
+                    vendor: row.get(4)?,
+                    file_hash: row.get(5)?,
+                    component: Component {
+                        name: row.get(6)?,
+                        version: row.get(7)?,
+                        source: row.get(8)?,
+                        evidence: row.get(9)?,
+                    },
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(matches
+            .into_iter()
+            .filter(|m| filter.matches_version(m.component.version.as_deref()))
+            .collect())
+    }
+
+    /// Installers that contain a file with the given hash, or are that file
+    pub fn find_files_by_hash(&self, hash: &str) -> Result<Vec<FileMatch>> {
+        let hash = hash.trim().to_lowercase();
+        let mut statement = self.conn.prepare(
+            "SELECT a.source, a.product_name, a.file_hash, f.path, f.size
+             FROM files f JOIN latest_analyses a ON a.id = f.analysis_id
+             WHERE f.hash = ?1
+             UNION ALL
+             SELECT source, product_name, file_hash, NULL, file_size
+             FROM latest_analyses WHERE file_hash = ?1
+             ORDER BY 2, 1, 4",
+        )?;
+        let matches = statement
+            .query_map([&hash], |row| {
+                Ok(FileMatch {
+                    source: row.get(0)?,
+                    product_name: row.get(1)?,
+                    file_hash: row.get(2)?,
+                    path: row.get(3)?,
+                    size: row.get::<_, i64>(4)? as u64,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(matches)
+    }
+
+    /// Number of distinct installers per vendor, largest first
+    ///
+    /// Installers are counted under their canonical vendor, or their
+    /// manufacturer when the vendor knowledge base does not know them.
+    pub fn vendors(&self) -> Result<Vec<VendorCount>> {
+        let mut statement = self.conn.prepare(&format!(
+            "SELECT {} AS vendor_group, COUNT(*)
+             FROM latest_analyses GROUP BY vendor_group ORDER BY 2 DESC, vendor_group",
+            VENDOR_GROUP
+        ))?;
+        let vendors = statement
+            .query_map([], |row| {
+                Ok(VendorCount {
+                    vendor: row.get(0)?,
+                    installers: row.get::<_, i64>(1)? as u64,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(vendors)
+    }
+
+    /// Total number of recorded analyses
+    pub fn analysis_count(&self) -> Result<u64> {
+        let count: i64 = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM analyses", [], |row| row.get(0))?;
+        Ok(count as u64)
+    }
+}
+
+/// Canonical vendor of a result, looked up in the curated knowledge base
+/// when the analysis did not record one
+fn vendor_of(result: &AnalysisResult) -> Option<VendorInfo> {
+    VendorInfo::from_properties(&result.metadata.properties)
+        .or_else(|| VendorKnowledgeBase::curated().lookup(&ProductIdentity::from_result(result)))
+}
+
+/// Library file name patterns and the component they identify
+///
+/// The first capture groups form the version; a missing group is read as
+/// a fixed version given in the table.
+fn library_patterns() -> &'static [(Regex, &'static str, Option<&'static str>)] {
+    static PATTERNS: OnceLock<Vec<(Regex, &'static str, Option<&'static str>)>> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        [
+            (
+                r"^lib(?:ssl|crypto)-(\d+)(?:_(\d+))?(?:-x64|-arm64)?\.dll$",
+                "openssl",
+                None,
+            ),
+            (r"^(?:ssleay32|libeay32)\.dll$", "openssl", Some("1.0")),
+            (
+                r"^lib(?:ssl|crypto)\.so\.(\d+)(?:\.(\d+))?",
+                "openssl",
+                None,
+            ),
+            (
+                r"^lib(?:ssl|crypto)\.(\d+)(?:\.(\d+))?\.dylib$",
+                "openssl",
+                None,
+            ),
+            (r"^python(\d)(\d+)\.dll$", "python", None),
+            (r"^qt(\d)core\.dll$", "qt", None),
+            (r"^zlib1?\.dll$", "zlib", None),
+            (r"^sqlite3\.dll$", "sqlite", None),
+        ]
+        .into_iter()
+        .map(|(pattern, name, version)| {
+            (
+                Regex::new(&format!("(?i){}", pattern)).expect("valid library pattern"),
+                name,
+                version,
+            )
+        })
+        .collect()
+    })
+}
+
+/// Components shipped by or declared for an analyzed installer
+pub fn detect_components(result: &AnalysisResult) -> Vec<Component> {
+    let mut components: Vec<Component> = Vec::new();
+
+    for file in &result.files {
+        let file_name = crate::utils::entry_file_name(&file.path);
+
+        for (pattern, name, fixed_version) in library_patterns() {
+            let Some(captures) = pattern.captures(&file_name) else {
+                continue;
+            };
+            let version = fixed_version.map(str::to_string).or_else(|| {
+                let parts: Vec<&str> = captures
+                    .iter()
+                    .skip(1)
+                    .flatten()
+                    .map(|m| m.as_str())
+                    .collect();
+                (!parts.is_empty()).then(|| parts.join("."))
+            });
+
+            if !components
+                .iter()
+                .any(|c| c.name == *name && c.version == version && c.source == "file")
+            {
+                components.push(Component {
+                    name: name.to_string(),
+                    version,
+                    source: "file".to_string(),
+                    evidence: file.path.to_string_lossy().replace('\\', "/"),
+                });
+            }
+            break;
+        }
+    }
+
+    if let Some(graph) = &result.dependencies {
+        for edge in &graph.edges {
+            let Some(node) = graph.nodes.iter().find(|node| node.id == edge.to) else {
+                continue;
+            };
+            components.push(Component {
+                name: normalize_name(&node.name),
+                version: node.version.clone(),
+                source: "dependency".to_string(),
+                evidence: edge.version_spec.clone().unwrap_or_default(),
+            });
+        }
+    }
+
+    components
+}
+
+/// Normalize a component name for matching (`PyYAML` and `pyyaml` are the same)
+fn normalize_name(name: &str) -> String {
+    name.trim().to_lowercase().replace('_', "-")
+}
+
+/// Numeric parts of a version string (`1.1.1w` is `[1, 1, 1]`)
+fn version_parts(version: &str) -> Vec<u64> {
+    version
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|part| !part.is_empty())
+        .filter_map(|part| part.parse().ok())
+        .collect()
+}
+
+/// Compare dotted versions numerically, padding the shorter one with zeros
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let (a, b) = (version_parts(a), version_parts(b));
+    let len = a.len().max(b.len());
+    (0..len)
+        .map(|i| {
+            a.get(i)
+                .copied()
+                .unwrap_or(0)
+                .cmp(&b.get(i).copied().unwrap_or(0))
+        })
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{FileAttributes, FileEntry, InstallerFormat, InstallerMetadata};
+    use chrono::Utc;
+    use std::path::PathBuf;
+
+    fn result(hash: &str, manufacturer: Option<&str>, files: &[(&str, &str)]) -> AnalysisResult {
+        AnalysisResult {
+            session_id: uuid::Uuid::new_v4(),
+            source_file_path: Some(PathBuf::from(format!("{}.exe", hash))),
+            metadata: InstallerMetadata {
+                format: InstallerFormat::InnoSetup,
+                product_name: Some(format!("Product {}", hash)),
+                product_version: Some("1.0".to_string()),
+                manufacturer: manufacturer.map(str::to_string),
+                file_size: 1024,
+                file_hash: hash.to_string(),
+                created_at: Utc::now(),
+                ..Default::default()
+            },
+            files: files
+                .iter()
+                .map(|(path, hash)| FileEntry {
+                    path: PathBuf::from(path),
+                    target_path: None,
+                    size: 10,
+                    hash: Some(hash.to_string()),
+                    attributes: FileAttributes::default(),
+                    compression: None,
+                    is_speculative: false,
+                    architecture: None,
+                    modified: None,
+                    source: None,
+                })
+                .collect(),
+            analyzed_at: Utc::now(),
+            analysis_duration: std::time::Duration::from_millis(5),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_component_filter_and_versions() {
+        let filter = ComponentFilter::parse("OpenSSL < 3").unwrap();
+        assert_eq!(filter.name, "openssl");
+        assert_eq!(filter.constraint, Some((VersionOp::Lt, "3".to_string())));
+        assert!(filter.matches_version(Some("1.1")));
+        assert!(!filter.matches_version(Some("3.0.13")));
+        assert!(!filter.matches_version(None));
+
+        assert_eq!(compare_versions("1.1.1w", "1.1.1"), Ordering::Equal);
+        assert_eq!(compare_versions("3.10", "3.9"), Ordering::Greater);
+        assert!(ComponentFilter::parse("openssl<").is_err());
+        assert!(ComponentFilter::parse(">=3").is_err());
+    }
+
+    #[test]
+    fn test_record_and_query() {
+        let mut db = ResultsDatabase::open_in_memory().unwrap();
+        db.record(&result(
+            "aaa",
+            Some("Acme"),
+            &[("bin\\libssl-1_1-x64.dll", "F00D"), ("bin/app.exe", "beef")],
+        ))
+        .unwrap();
+        db.record(&result(
+            "bbb",
+            Some("Acme"),
+            &[("libcrypto-3-x64.dll", "cafe"), ("python311.dll", "beef")],
+        ))
+        .unwrap();
+        // Re-analysis of the same installer only counts once
+        db.record(&result(
+            "bbb",
+            Some("Acme"),
+            &[("libcrypto-3-x64.dll", "cafe")],
+        ))
+        .unwrap();
+        db.record(&result("ccc", None, &[])).unwrap();
+        assert_eq!(db.analysis_count().unwrap(), 4);
+        let analyses = db.analyses().unwrap();
+        assert_eq!(analyses.len(), 3);
+        let latest = db.result(analyses[0].id).unwrap().unwrap();
+        assert_eq!(latest.metadata.file_hash, analyses[0].file_hash);
+        assert!(db.result(-1).unwrap().is_none());
+        let bbb = db.latest_result_by_hash("BBB").unwrap().unwrap();
+        assert_eq!(bbb.files.len(), 1);
+        assert!(db.latest_result_by_hash("ddd").unwrap().is_none());
+
+        let old_openssl = db
+            .find_components(&ComponentFilter::parse("openssl<3").unwrap())
+            .unwrap();
+        assert_eq!(old_openssl.len(), 1);
+        assert_eq!(old_openssl[0].file_hash, "aaa");
+        assert_eq!(old_openssl[0].component.version.as_deref(), Some("1.1"));
+        assert_eq!(old_openssl[0].component.evidence, "bin/libssl-1_1-x64.dll");
+
+        let files = db.find_files_by_hash("f00d").unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path.as_deref(), Some("bin/libssl-1_1-x64.dll"));
+        assert_eq!(db.find_files_by_hash("ccc").unwrap()[0].path, None);
+        // The python DLL was dropped by the latest analysis of `bbb`
+        assert_eq!(db.find_files_by_hash("beef").unwrap().len(), 1);
+
+        let vendors = db.vendors().unwrap();
+        assert_eq!(vendors[0].vendor, "Acme");
+        assert_eq!(vendors[0].installers, 2);
+        assert_eq!(vendors[1].vendor, "Unknown");
+    }
+
+    #[test]
+    fn test_vendor_spellings_grouped() {
+        let mut db = ResultsDatabase::open_in_memory().unwrap();
+        for (hash, vendor) in [
+            ("aaa", "Microsoft Corp."),
+            ("bbb", "Microsoft Corporation "),
+            ("ccc", "MICROSOFT CORPORATION"),
+        ] {
+            db.record(&result(hash, Some(vendor), &[])).unwrap();
+        }
+
+        let vendors = db.vendors().unwrap();
+        assert_eq!(vendors.len(), 1);
+        assert_eq!(vendors[0].vendor, "Microsoft Corporation");
+        assert_eq!(vendors[0].installers, 3);
+    }
+
+    #[test]
+    fn test_canonical_vendor_queries() {
+        let mut db = ResultsDatabase::open_in_memory().unwrap();
+        db.record(&result("aaa", Some("Google Inc."), &[])).unwrap();
+        db.record(&result("bbb", Some("Google LLC"), &[])).unwrap();
+        let mut firefox = result("ccc", None, &[]);
+        firefox.metadata.product_name = Some("Mozilla Firefox (x64 en-US)".to_string());
+        db.record(&firefox).unwrap();
+        db.record(&result("ddd", Some("Contoso"), &[])).unwrap();
+
+        let vendors = db.vendors().unwrap();
+        let counts: Vec<(&str, u64)> = vendors
+            .iter()
+            .map(|v| (v.vendor.as_str(), v.installers))
+            .collect();
+        assert_eq!(counts, [("Google LLC", 2), ("Contoso", 1), ("Mozilla", 1)]);
+
+        let google = db.analyses_by_vendor("google llc").unwrap();
+        assert_eq!(google.len(), 2);
+        assert_eq!(google[0].manufacturer.as_deref(), Some("Google LLC"));
+        let mozilla = db.analyses_by_vendor("Mozilla").unwrap();
+        assert_eq!(mozilla[0].category.as_deref(), Some("Web Browser"));
+        // Unknown vendors are found by their manufacturer
+        assert_eq!(db.analyses_by_vendor("Contoso").unwrap().len(), 1);
+        assert!(db.analyses_by_vendor("Adobe Inc.").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_version_1_database_upgraded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("results.db");
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(
+                &SCHEMA
+                    .replace("    vendor TEXT,\n    category TEXT,\n", "")
+                    .replace(
+                        "CREATE INDEX IF NOT EXISTS idx_analyses_vendor ON analyses(vendor);\n",
+                        "",
+                    ),
+            )
+            .unwrap();
+            let recorded = result("aaa", Some("MICROSOFT CORP."), &[]);
+            conn.execute(
+                "INSERT INTO analyses (session_id, file_hash, format, manufacturer, file_size,
+                    file_count, dynamic_analysis, analyzed_at, result_json)
+                 VALUES ('s', 'aaa', 'Inno Setup', 'MICROSOFT CORP.', 1, 0, 0, 'now', ?1)",
+                [serde_json::to_string(&recorded).unwrap()],
+            )
+            .unwrap();
+            conn.pragma_update(None, "user_version", 1).unwrap();
+        }
+
+        let db = ResultsDatabase::open(&path).unwrap();
+        let vendors = db.vendors().unwrap();
+        assert_eq!(vendors[0].vendor, "Microsoft Corporation");
+        assert_eq!(
+            db.analyses().unwrap()[0].vendor.as_deref(),
+            Some("Microsoft Corporation")
+        );
+        drop(db);
+
+        let conn = Connection::open(&path).unwrap();
+        let version: i64 = conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, SCHEMA_VERSION);
+    }
+}
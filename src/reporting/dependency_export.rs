@@ -0,0 +1,144 @@
+//! Dependency graph export (DOT, JSON)
+
+use super::tree_export::dot_escape;
+use crate::core::{AnalyzerError, DependencyGraph, Result};
+use std::path::Path;
+
+/// Supported dependency graph export formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DependencyExportFormat {
+    Json,
+    Dot,
+}
+
+impl DependencyExportFormat {
+    /// Detect format from the export file extension, defaulting to DOT
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => Self::Json,
+            _ => Self::Dot,
+        }
+    }
+}
+
+/// Dependency graph exporter
+pub struct DependencyGraphExporter;
+
+impl DependencyGraphExporter {
+    /// Render a dependency graph in the given format
+    pub fn export(graph: &DependencyGraph, format: DependencyExportFormat) -> Result<String> {
+        match format {
+            DependencyExportFormat::Json => {
+                serde_json::to_string_pretty(graph).map_err(AnalyzerError::SerializationError)
+            }
+            DependencyExportFormat::Dot => Ok(Self::render_dot(graph)),
+        }
+    }
+
+    /// Save a dependency graph, choosing the format from the file extension
+    pub async fn save(graph: &DependencyGraph, output_path: &Path) -> Result<()> {
+        let content = Self::export(graph, DependencyExportFormat::from_path(output_path))?;
+        tokio::fs::write(crate::utils::extended_length(output_path), content).await?;
+        tracing::info!("Dependency graph exported to: {}", output_path.display());
+        Ok(())
+    }
+
+    /// Render as a Graphviz DOT digraph
+    ///
+    /// Optional dependencies are drawn dashed; version requirements and
+    /// conditions become edge labels.
+    pub fn render_dot(graph: &DependencyGraph) -> String {
+        let mut dot = String::from(
+            "digraph dependencies {\n    rankdir=LR;\n    node [fontname=\"Helvetica\", fontsize=10, shape=box, style=rounded];\n",
+        );
+
+        for node in &graph.nodes {
+            let label = match &node.version {
+                Some(version) => format!("{}\\n{}", dot_escape(&node.name), dot_escape(version)),
+                None => dot_escape(&node.name),
+            };
+            let style = if node.is_root {
+                ", style=\"rounded,filled\", fillcolor=\"#cfe2ff\""
+            } else {
+                ""
+            };
+            dot.push_str(&format!(
+                "    \"{}\" [label=\"{}\"{}];\n",
+                dot_escape(&node.id),
+                label,
+                style
+            ));
+        }
+
+        for edge in &graph.edges {
+            let label = [edge.version_spec.as_deref(), edge.condition.as_deref()]
+                .into_iter()
+                .flatten()
+                .map(dot_escape)
+                .collect::<Vec<_>>()
+                .join("\\n");
+            let mut attributes = Vec::new();
+            if !label.is_empty() {
+                attributes.push(format!("label=\"{}\"", label));
+            }
+            if edge.optional {
+                attributes.push("style=dashed".to_string());
+            }
+
+            dot.push_str(&format!(
+                "    \"{}\" -> \"{}\"",
+                dot_escape(&edge.from),
+                dot_escape(&edge.to)
+            ));
+            if !attributes.is_empty() {
+                dot.push_str(&format!(" [{}]", attributes.join(", ")));
+            }
+            dot.push_str(";\n");
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::PackageDependency;
+
+    #[test]
+    fn test_render_dot() {
+        let dependencies = vec![
+            PackageDependency {
+                name: "Click".to_string(),
+                version_spec: Some(">=8.1.0".to_string()),
+                condition: None,
+                optional: false,
+            },
+            PackageDependency {
+                name: "pytest".to_string(),
+                version_spec: None,
+                condition: Some("extra == \"test\"".to_string()),
+                optional: true,
+            },
+        ];
+        let graph = DependencyGraph::from_dependencies("my_pkg", Some("1.0"), &dependencies);
+
+        assert_eq!(graph.nodes.len(), 3);
+        assert_eq!(graph.nodes[0].id, "my-pkg");
+        assert_eq!(graph.nodes[1].id, "click");
+
+        let dot = DependencyGraphExporter::render_dot(&graph);
+        assert!(dot.contains("\"my-pkg\" [label=\"my_pkg\\n1.0\""));
+        assert!(dot.contains("\"my-pkg\" -> \"click\" [label=\">=8.1.0\"];"));
+        assert!(dot.contains("[label=\"extra == \\\"test\\\"\", style=dashed];"));
+        assert_eq!(
+            DependencyExportFormat::from_path(Path::new("deps.json")),
+            DependencyExportFormat::Json
+        );
+        assert_eq!(
+            DependencyExportFormat::from_path(Path::new("deps.gv")),
+            DependencyExportFormat::Dot
+        );
+    }
+}
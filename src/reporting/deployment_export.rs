@@ -0,0 +1,802 @@
+//! Deployment metadata export for Intune Win32 apps and SCCM (JSON, XML)
+//!
+//! Packaging an installer for Intune or Configuration Manager takes the
+//! same handful of facts every time: how to install and uninstall it
+//! silently, how to detect that it is installed, how much space it needs
+//! and which exit codes mean success or a pending restart. They are derived
+//! from the analysis as far as the format allows; values that depend on the
+//! target machine, like the installation directory of an Inno Setup
+//! uninstaller, keep the installer's placeholder and are listed as notes.
+
+use super::tree_export::xml_escape;
+use super::ProductIdentity;
+use crate::core::{AnalysisResult, AnalyzerError, FileEntry, InstallerFormat, Result};
+use crate::utils::entry_file_name;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+/// Placeholders analyzers record when the publisher is not known
+const UNKNOWN_PUBLISHERS: &[&str] = &["Unknown Publisher", "Unknown"];
+
+/// Registry path of Add/Remove Programs entries
+const UNINSTALL_KEY: &str = "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Uninstall";
+
+/// Supported deployment metadata export formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeploymentExportFormat {
+    Json,
+    /// Descriptor shaped like the `Detection.xml` of an `.intunewin` package
+    IntuneXml,
+}
+
+impl DeploymentExportFormat {
+    /// Detect format from the export file extension, defaulting to JSON
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("xml") => Self::IntuneXml,
+            _ => Self::Json,
+        }
+    }
+}
+
+/// Whether the app installs for the machine or for the signed-in user
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InstallContext {
+    System,
+    User,
+}
+
+impl InstallContext {
+    /// Value used in exports
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::System => "system",
+            Self::User => "user",
+        }
+    }
+}
+
+impl fmt::Display for InstallContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// How a deployment tool treats an exit code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReturnCodeKind {
+    Success,
+    SoftReboot,
+    HardReboot,
+    Retry,
+    Failed,
+}
+
+impl ReturnCodeKind {
+    /// Value used in exports
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Success => "success",
+            Self::SoftReboot => "soft_reboot",
+            Self::HardReboot => "hard_reboot",
+            Self::Retry => "retry",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+/// Exit code of the install command and its meaning
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReturnCode {
+    pub code: i32,
+    pub kind: ReturnCodeKind,
+    pub description: String,
+}
+
+/// Rule telling whether the app is installed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DetectionRule {
+    /// Windows Installer product, optionally at least this version
+    Msi {
+        product_code: String,
+        product_version: Option<String>,
+    },
+    /// Add/Remove Programs entry, optionally with at least this `DisplayVersion`
+    Registry {
+        key_path: String,
+        value_name: Option<String>,
+        version: Option<String>,
+    },
+    /// Installed file, optionally with at least this file version
+    File {
+        path: String,
+        file_name: String,
+        version: Option<String>,
+    },
+}
+
+/// Packaging metadata of an installer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeploymentMetadata {
+    pub name: String,
+    pub publisher: Option<String>,
+    pub version: Option<String>,
+    pub format: String,
+    /// Installer file the commands run
+    pub setup_file: String,
+    pub install_command: Option<String>,
+    pub uninstall_command: Option<String>,
+    pub install_context: InstallContext,
+    /// Candidate rules, most reliable first; configure one of them
+    pub detection_rules: Vec<DetectionRule>,
+    /// Size of the installer, the content to upload
+    pub content_size: u64,
+    /// Size of the installed files, if known
+    pub estimated_installed_size: Option<u64>,
+    pub architecture: Option<String>,
+    pub reboot_required: bool,
+    pub return_codes: Vec<ReturnCode>,
+    /// Values to review before deploying
+    pub notes: Vec<String>,
+}
+
+impl DeploymentMetadata {
+    /// Derive the packaging metadata of an analysis result
+    pub fn from_result(result: &AnalysisResult) -> Self {
+        let properties = &result.metadata.properties;
+        let identity = ProductIdentity::from_result(result);
+        // A package extracted from its wrapper is deployed in place of the wrapper
+        let setup_file = properties
+            .get("unwrapped_msi")
+            .cloned()
+            .or_else(|| result.source_file_path.as_deref().map(entry_file_name))
+            .unwrap_or_else(|| "setup.exe".to_string());
+        let name = identity
+            .product_name
+            .clone()
+            .unwrap_or_else(|| setup_file.clone());
+        let product_code = identity.product_code.clone();
+        let is_msi_file = setup_file.to_ascii_lowercase().ends_with(".msi");
+
+        let mut notes = Vec::new();
+        let quoted = format!("\"{}\"", setup_file);
+        let msi_uninstall = product_code
+            .as_ref()
+            .map(|code| format!("msiexec /x {} /qn /norestart", code));
+        let (install_command, uninstall_command, return_codes) = match &result.metadata.format {
+            InstallerFormat::MSI => (
+                Some(format!("msiexec /i {} /qn /norestart", quoted)),
+                msi_uninstall,
+                msi_return_codes(),
+            ),
+            InstallerFormat::WiX if is_msi_file => (
+                Some(format!("msiexec /i {} /qn /norestart", quoted)),
+                msi_uninstall,
+                msi_return_codes(),
+            ),
+            // Burn bundles report the exit codes of the packages they chain
+            InstallerFormat::WiX => (
+                Some(format!("{} /quiet /norestart", quoted)),
+                Some(format!("{} /uninstall /quiet /norestart", quoted)),
+                msi_return_codes(),
+            ),
+            InstallerFormat::InstallShield => (
+                Some(format!("{} /s /v\"/qn /norestart\"", quoted)),
+                msi_uninstall.or_else(|| Some(format!("{} /s /x /v\"/qn\"", quoted))),
+                msi_return_codes(),
+            ),
+            // The bootstrapper passes arguments after /exenoui on to msiexec
+            InstallerFormat::AdvancedInstaller => (
+                Some(format!("{} /exenoui /qn /norestart", quoted)),
+                msi_uninstall.or_else(|| Some(format!("{} /x /exenoui /qn", quoted))),
+                msi_return_codes(),
+            ),
+            InstallerFormat::InnoSetup => {
+                notes.push(
+                    "Replace {app} in the uninstall command with the installation directory"
+                        .to_string(),
+                );
+                (
+                    Some(format!(
+                        "{} /VERYSILENT /SUPPRESSMSGBOXES /NORESTART /SP-",
+                        quoted
+                    )),
+                    Some(
+                        "\"{app}\\unins000.exe\" /VERYSILENT /SUPPRESSMSGBOXES /NORESTART"
+                            .to_string(),
+                    ),
+                    inno_return_codes(),
+                )
+            }
+            InstallerFormat::NSIS => {
+                notes.push(
+                    "Replace $INSTDIR in the uninstall command with the installation directory and check the uninstaller name"
+                        .to_string(),
+                );
+                (
+                    Some(format!("{} /S", quoted)),
+                    Some("\"$INSTDIR\\uninstall.exe\" /S".to_string()),
+                    nsis_return_codes(),
+                )
+            }
+            InstallerFormat::SetupFactory => {
+                notes.push(
+                    "Take the uninstaller path and its /U: log argument from the UninstallString of the installed product"
+                        .to_string(),
+                );
+                (
+                    Some(format!("{} /S", quoted)),
+                    Some(
+                        "\"<app>\\uninstall.exe\" \"/U:<app>\\Uninstall\\uninstall.xml\" /S"
+                            .to_string(),
+                    ),
+                    default_return_codes(),
+                )
+            }
+            InstallerFormat::Wise => {
+                notes.push(
+                    "Replace <app> in the uninstall command with the installation directory holding INSTALL.LOG"
+                        .to_string(),
+                );
+                (
+                    Some(format!("{} /s", quoted)),
+                    Some("\"<app>\\UNWISE.EXE\" /S \"<app>\\INSTALL.LOG\"".to_string()),
+                    default_return_codes(),
+                )
+            }
+            InstallerFormat::Squirrel => {
+                notes.push(
+                    "Squirrel installs per user; replace <app> with the package ID".to_string(),
+                );
+                (
+                    Some(format!("{} --silent", quoted)),
+                    Some("\"%LocalAppData%\\<app>\\Update.exe\" --uninstall -s".to_string()),
+                    default_return_codes(),
+                )
+            }
+            InstallerFormat::DriverPackage => {
+                let inf = properties
+                    .get("driver_inf")
+                    .cloned()
+                    .unwrap_or_else(|| "driver.inf".to_string());
+                notes.push(
+                    "Uninstall with pnputil /delete-driver and the oem#.inf name assigned on installation"
+                        .to_string(),
+                );
+                (
+                    Some(format!("pnputil /add-driver \"{}\" /install", inf)),
+                    None,
+                    default_return_codes(),
+                )
+            }
+            InstallerFormat::MSIX => {
+                notes.push(
+                    "MSIX packages are deployed as line-of-business apps, not as Win32 apps"
+                        .to_string(),
+                );
+                (None, None, Vec::new())
+            }
+            _ => {
+                notes.push(format!(
+                    "No silent command line is known for {} installers",
+                    result.metadata.format
+                ));
+                (None, None, default_return_codes())
+            }
+        };
+
+        let user_install = match &result.metadata.format {
+            InstallerFormat::Squirrel => true,
+            InstallerFormat::InnoSetup => properties
+                .get("inno_privileges_required")
+                .is_some_and(|privileges| privileges == "lowest"),
+            _ => {
+                properties.get("ALLUSERS").is_some_and(String::is_empty)
+                    || properties
+                        .get("MSIINSTALLPERUSER")
+                        .is_some_and(|per_user| per_user == "1")
+            }
+        };
+        let install_context = if user_install {
+            InstallContext::User
+        } else {
+            InstallContext::System
+        };
+
+        let version = identity.product_version.clone();
+        let mut detection_rules = Vec::new();
+        if let Some(code) = &product_code {
+            detection_rules.push(DetectionRule::Msi {
+                product_code: code.clone(),
+                product_version: version.clone(),
+            });
+        }
+        let hive = match install_context {
+            InstallContext::System => "HKEY_LOCAL_MACHINE",
+            InstallContext::User => "HKEY_CURRENT_USER",
+        };
+        for key in &identity.arp_keys {
+            if product_code
+                .as_ref()
+                .is_some_and(|code| code.eq_ignore_ascii_case(key))
+            {
+                continue;
+            }
+            detection_rules.push(DetectionRule::Registry {
+                key_path: format!("{}\\{}\\{}", hive, UNINSTALL_KEY, key),
+                value_name: version.as_ref().map(|_| "DisplayVersion".to_string()),
+                version: version.clone(),
+            });
+        }
+        if let Some(file) =
+            primary_executable(&result.files, &setup_file, identity.product_name.as_deref())
+        {
+            let path = file.target_path.as_deref().unwrap_or(&file.path);
+            let path = path.to_string_lossy().replace('/', "\\");
+            let (folder, file_name) = path.rsplit_once('\\').unwrap_or(("", path.as_str()));
+            if folder.contains(['{', '$', '[']) || folder.is_empty() {
+                notes.push(format!(
+                    "Replace the folder of the {} detection rule with the installation directory",
+                    file_name
+                ));
+            }
+            detection_rules.push(DetectionRule::File {
+                path: folder.to_string(),
+                file_name: file_name.to_string(),
+                version: version.clone(),
+            });
+        }
+
+        if detection_rules.is_empty() {
+            notes.push(
+                "No detection rule could be derived; detect the main executable of the installed product"
+                    .to_string(),
+            );
+        }
+
+        // Analyzers that cannot list the payload report the installer itself
+        let installed: u64 = result
+            .files
+            .iter()
+            .filter(|file| !file.is_speculative && !is_setup_file(file, &setup_file))
+            .map(|file| file.size)
+            .sum();
+
+        Self {
+            name,
+            publisher: identity
+                .manufacturer
+                .clone()
+                .filter(|publisher| !UNKNOWN_PUBLISHERS.contains(&publisher.as_str())),
+            version,
+            format: result.metadata.format.to_string(),
+            setup_file,
+            install_command,
+            uninstall_command,
+            install_context,
+            detection_rules,
+            content_size: result.metadata.file_size,
+            estimated_installed_size: (installed > 0).then_some(installed),
+            architecture: properties.get("installer_architecture").cloned(),
+            reboot_required: properties
+                .get("reboot_required")
+                .is_some_and(|verdict| verdict != "no"),
+            return_codes,
+            notes,
+        }
+    }
+
+    /// Render the metadata as a descriptor shaped like Intune's `Detection.xml`
+    pub fn render_intune_xml(&self) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+        xml.push_str(&format!(
+            "<ApplicationInfo ToolVersion=\"installer-analyzer {}\">\n",
+            env!("CARGO_PKG_VERSION")
+        ));
+        let mut element = |name: &str, value: &str| {
+            xml.push_str(&format!("  <{0}>{1}</{0}>\n", name, xml_escape(value)));
+        };
+        element("Name", &self.name);
+        element("UnencryptedContentSize", &self.content_size.to_string());
+        element("SetupFile", &self.setup_file);
+        if let Some(publisher) = &self.publisher {
+            element("Publisher", publisher);
+        }
+        if let Some(version) = &self.version {
+            element("Version", version);
+        }
+        if let Some(command) = &self.install_command {
+            element("InstallCommandLine", command);
+        }
+        if let Some(command) = &self.uninstall_command {
+            element("UninstallCommandLine", command);
+        }
+        element("InstallExperience", self.install_context.as_str());
+        if let Some(size) = self.estimated_installed_size {
+            element("EstimatedInstalledSize", &size.to_string());
+        }
+        if let Some(architecture) = &self.architecture {
+            element("Architecture", architecture);
+        }
+        element("RequiresReboot", &self.reboot_required.to_string());
+
+        if let Some(DetectionRule::Msi {
+            product_code,
+            product_version,
+        }) = self.detection_rules.first()
+        {
+            xml.push_str("  <MsiInfo>\n");
+            let mut msi = |name: &str, value: &str| {
+                xml.push_str(&format!("    <{0}>{1}</{0}>\n", name, xml_escape(value)));
+            };
+            if let Some(publisher) = &self.publisher {
+                msi("MsiPublisher", publisher);
+            }
+            msi("MsiProductCode", product_code);
+            if let Some(version) = product_version {
+                msi("MsiProductVersion", version);
+            }
+            let context = match self.install_context {
+                InstallContext::System => "System",
+                InstallContext::User => "User",
+            };
+            msi("MsiExecutionContext", context);
+            msi("MsiRequiresReboot", &self.reboot_required.to_string());
+            msi(
+                "MsiIsMachineInstall",
+                &(self.install_context == InstallContext::System).to_string(),
+            );
+            msi(
+                "MsiIsUserInstall",
+                &(self.install_context == InstallContext::User).to_string(),
+            );
+            xml.push_str("  </MsiInfo>\n");
+        }
+
+        xml.push_str("  <DetectionRules>\n");
+        for rule in &self.detection_rules {
+            let attributes = match rule {
+                DetectionRule::Msi {
+                    product_code,
+                    product_version,
+                } => vec![
+                    ("Type", Some("Msi")),
+                    ("ProductCode", Some(product_code.as_str())),
+                    ("ProductVersion", product_version.as_deref()),
+                ],
+                DetectionRule::Registry {
+                    key_path,
+                    value_name,
+                    version,
+                } => vec![
+                    ("Type", Some("Registry")),
+                    ("KeyPath", Some(key_path.as_str())),
+                    ("ValueName", value_name.as_deref()),
+                    ("Version", version.as_deref()),
+                ],
+                DetectionRule::File {
+                    path,
+                    file_name,
+                    version,
+                } => vec![
+                    ("Type", Some("File")),
+                    ("Path", Some(path.as_str())),
+                    ("FileOrFolderName", Some(file_name.as_str())),
+                    ("Version", version.as_deref()),
+                ],
+            };
+            let attributes: Vec<String> = attributes
+                .into_iter()
+                .filter_map(|(name, value)| {
+                    value.map(|value| format!("{}=\"{}\"", name, xml_escape(value)))
+                })
+                .collect();
+            xml.push_str(&format!("    <Rule {} />\n", attributes.join(" ")));
+        }
+        xml.push_str("  </DetectionRules>\n  <ReturnCodes>\n");
+        for code in &self.return_codes {
+            xml.push_str(&format!(
+                "    <ReturnCode Code=\"{}\" Type=\"{}\">{}</ReturnCode>\n",
+                code.code,
+                code.kind.as_str(),
+                xml_escape(&code.description)
+            ));
+        }
+        xml.push_str("  </ReturnCodes>\n");
+        for note in &self.notes {
+            xml.push_str(&format!("  <!-- {} -->\n", note.replace("--", "- -")));
+        }
+        xml.push_str("</ApplicationInfo>\n");
+        xml
+    }
+}
+
+/// Deployment metadata exporter
+pub struct DeploymentExporter;
+
+impl DeploymentExporter {
+    /// Render the deployment metadata of a result in the given format
+    pub fn export(result: &AnalysisResult, format: DeploymentExportFormat) -> Result<String> {
+        let metadata = DeploymentMetadata::from_result(result);
+        match format {
+            DeploymentExportFormat::Json => {
+                serde_json::to_string_pretty(&metadata).map_err(AnalyzerError::SerializationError)
+            }
+            DeploymentExportFormat::IntuneXml => Ok(metadata.render_intune_xml()),
+        }
+    }
+
+    /// Save the deployment metadata, choosing the format from the file extension
+    pub async fn save(result: &AnalysisResult, output_path: &Path) -> Result<()> {
+        let content = Self::export(result, DeploymentExportFormat::from_path(output_path))?;
+        tokio::fs::write(crate::utils::extended_length(output_path), content).await?;
+        tracing::info!("Deployment metadata exported to: {}", output_path.display());
+        Ok(())
+    }
+}
+
+/// Executable the product is most likely detected by
+///
+/// An executable named like the product wins, else the largest one that is
+/// not an uninstaller or updater.
+fn primary_executable<'a>(
+    files: &'a [FileEntry],
+    setup_file: &str,
+    product_name: Option<&str>,
+) -> Option<&'a FileEntry> {
+    let candidates: Vec<&FileEntry> = files
+        .iter()
+        .filter(|file| !file.is_speculative && file.attributes.executable)
+        .filter(|file| !is_setup_file(file, setup_file))
+        .filter(|file| {
+            let name = entry_file_name(&file.path).to_ascii_lowercase();
+            name.ends_with(".exe")
+                && !name.starts_with("unins")
+                && !name.starts_with("uninst")
+                && name != "update.exe"
+        })
+        .collect();
+    let product = product_name.map(|name| name.to_ascii_lowercase().replace(' ', ""));
+    candidates
+        .iter()
+        .find(|file| {
+            product.as_ref().is_some_and(|product| {
+                let name = entry_file_name(&file.path).to_ascii_lowercase();
+                name.trim_end_matches(".exe") == product
+            })
+        })
+        .or_else(|| candidates.iter().max_by_key(|file| file.size))
+        .copied()
+}
+
+/// Whether a file entry is the installer rather than a packaged file
+fn is_setup_file(file: &FileEntry, setup_file: &str) -> bool {
+    file.target_path.is_none() && entry_file_name(&file.path).eq_ignore_ascii_case(setup_file)
+}
+
+fn return_code(code: i32, kind: ReturnCodeKind, description: &str) -> ReturnCode {
+    ReturnCode {
+        code,
+        kind,
+        description: description.to_string(),
+    }
+}
+
+/// Exit codes of `msiexec`, also used by Burn bundles and InstallShield
+fn msi_return_codes() -> Vec<ReturnCode> {
+    vec![
+        return_code(0, ReturnCodeKind::Success, "Installation succeeded"),
+        return_code(1707, ReturnCodeKind::Success, "Installation succeeded"),
+        return_code(3010, ReturnCodeKind::SoftReboot, "Restart required"),
+        return_code(1641, ReturnCodeKind::HardReboot, "Restart initiated"),
+        return_code(
+            1618,
+            ReturnCodeKind::Retry,
+            "Another installation is in progress",
+        ),
+        return_code(1602, ReturnCodeKind::Failed, "Cancelled by the user"),
+        return_code(
+            1603,
+            ReturnCodeKind::Failed,
+            "Fatal error during installation",
+        ),
+    ]
+}
+
+/// Exit codes documented for Inno Setup installers
+fn inno_return_codes() -> Vec<ReturnCode> {
+    vec![
+        return_code(0, ReturnCodeKind::Success, "Installation succeeded"),
+        return_code(1, ReturnCodeKind::Failed, "Setup failed to initialize"),
+        return_code(
+            2,
+            ReturnCodeKind::Failed,
+            "Cancelled before the installation started",
+        ),
+        return_code(
+            3,
+            ReturnCodeKind::Failed,
+            "Fatal error while preparing the installation",
+        ),
+        return_code(4, ReturnCodeKind::Failed, "Fatal error during installation"),
+        return_code(5, ReturnCodeKind::Failed, "Cancelled during installation"),
+        return_code(6, ReturnCodeKind::Failed, "Setup was terminated"),
+        return_code(
+            7,
+            ReturnCodeKind::Failed,
+            "Preparing to install found that setup cannot proceed",
+        ),
+        return_code(
+            8,
+            ReturnCodeKind::Retry,
+            "Setup cannot proceed until the system is restarted",
+        ),
+    ]
+}
+
+/// Exit codes of NSIS installers
+fn nsis_return_codes() -> Vec<ReturnCode> {
+    vec![
+        return_code(0, ReturnCodeKind::Success, "Installation succeeded"),
+        return_code(1, ReturnCodeKind::Failed, "Cancelled by the user"),
+        return_code(2, ReturnCodeKind::Failed, "Aborted by the installer script"),
+    ]
+}
+
+/// Exit codes of installers that only document success
+fn default_return_codes() -> Vec<ReturnCode> {
+    vec![return_code(
+        0,
+        ReturnCodeKind::Success,
+        "Installation succeeded",
+    )]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{FileAttributes, InstallerMetadata, RegistryOperation};
+    use chrono::Utc;
+    use std::path::PathBuf;
+
+    fn result(installer: &str, format: InstallerFormat) -> AnalysisResult {
+        AnalysisResult {
+            source_file_path: Some(PathBuf::from(installer)),
+            metadata: InstallerMetadata {
+                format,
+                product_name: Some("Contoso App".to_string()),
+                product_version: Some("2.1.0".to_string()),
+                manufacturer: Some("Contoso".to_string()),
+                file_size: 4096,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn executable(path: &str, target: &str, size: u64) -> FileEntry {
+        FileEntry {
+            path: PathBuf::from(path),
+            target_path: Some(PathBuf::from(target)),
+            size,
+            hash: None,
+            attributes: FileAttributes {
+                executable: true,
+                ..FileAttributes::default()
+            },
+            compression: None,
+            is_speculative: false,
+            architecture: None,
+            modified: None,
+            source: None,
+        }
+    }
+
+    #[test]
+    fn test_msi_metadata() {
+        let mut msi = result("C:\\Packages\\contoso.msi", InstallerFormat::MSI);
+        msi.metadata.properties.insert(
+            "ProductCode".to_string(),
+            "{11111111-2222-3333-4444-555555555555}".to_string(),
+        );
+        msi.metadata
+            .properties
+            .insert("reboot_required".to_string(), "likely".to_string());
+
+        let metadata = DeploymentMetadata::from_result(&msi);
+        assert_eq!(
+            metadata.install_command.as_deref(),
+            Some("msiexec /i \"contoso.msi\" /qn /norestart")
+        );
+        assert_eq!(
+            metadata.uninstall_command.as_deref(),
+            Some("msiexec /x {11111111-2222-3333-4444-555555555555} /qn /norestart")
+        );
+        assert_eq!(metadata.install_context, InstallContext::System);
+        assert!(metadata.reboot_required);
+        assert_eq!(
+            metadata.detection_rules,
+            vec![DetectionRule::Msi {
+                product_code: "{11111111-2222-3333-4444-555555555555}".to_string(),
+                product_version: Some("2.1.0".to_string()),
+            }]
+        );
+        assert!(metadata
+            .return_codes
+            .iter()
+            .any(|code| code.code == 3010 && code.kind == ReturnCodeKind::SoftReboot));
+
+        let xml = metadata.render_intune_xml();
+        assert!(xml.contains("<SetupFile>contoso.msi</SetupFile>"));
+        assert!(
+            xml.contains("<MsiProductCode>{11111111-2222-3333-4444-555555555555}</MsiProductCode>")
+        );
+        assert!(xml.contains(
+            "<InstallCommandLine>msiexec /i &quot;contoso.msi&quot; /qn /norestart</InstallCommandLine>"
+        ));
+        assert!(xml.contains("<ReturnCode Code=\"1641\" Type=\"hard_reboot\">"));
+    }
+
+    #[test]
+    fn test_inno_metadata() {
+        let mut inno = result("contoso-setup.exe", InstallerFormat::InnoSetup);
+        inno.metadata
+            .properties
+            .insert("inno_privileges_required".to_string(), "lowest".to_string());
+        inno.files = vec![
+            executable("app/unins000.exe", "{app}\\unins000.exe", 3_000_000),
+            executable("app/helper.exe", "{app}\\helper.exe", 2_000_000),
+            executable("app/ContosoApp.exe", "{app}\\ContosoApp.exe", 1_000_000),
+        ];
+        // Listed by analyzers that only see the installer
+        let mut installer = executable("contoso-setup.exe", "", 9_000_000);
+        installer.target_path = None;
+        inno.files.push(installer);
+        inno.registry_operations = vec![RegistryOperation::CreateKey {
+            key_path:
+                "HKCU\\Software\\Microsoft\\Windows\\CurrentVersion\\Uninstall\\{8A2B5C0D-1111-4222-9333-444455556666}_is1"
+                    .to_string(),
+            timestamp: Utc::now(),
+            process_id: None,
+        }];
+
+        let metadata = DeploymentMetadata::from_result(&inno);
+        assert_eq!(metadata.install_context, InstallContext::User);
+        assert_eq!(metadata.estimated_installed_size, Some(6_000_000));
+        assert!(metadata
+            .install_command
+            .unwrap()
+            .ends_with("/VERYSILENT /SUPPRESSMSGBOXES /NORESTART /SP-"));
+        assert_eq!(metadata.detection_rules.len(), 2);
+        assert!(matches!(
+            &metadata.detection_rules[0],
+            DetectionRule::Registry { key_path, .. } if key_path.starts_with("HKEY_CURRENT_USER\\SOFTWARE")
+        ));
+        assert_eq!(
+            metadata.detection_rules[1],
+            DetectionRule::File {
+                path: "{app}".to_string(),
+                file_name: "ContosoApp.exe".to_string(),
+                version: Some("2.1.0".to_string()),
+            }
+        );
+        assert_eq!(metadata.notes.len(), 2);
+        assert_eq!(metadata.return_codes[8].kind, ReturnCodeKind::Retry);
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(
+            DeploymentExportFormat::from_path(Path::new("Detection.XML")),
+            DeploymentExportFormat::IntuneXml
+        );
+        assert_eq!(
+            DeploymentExportFormat::from_path(Path::new("deploy.json")),
+            DeploymentExportFormat::Json
+        );
+    }
+}
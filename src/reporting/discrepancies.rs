@@ -0,0 +1,318 @@
+//! Findings where observed behavior contradicts static claims
+//!
+//! An installer that writes files or registry entries it does not declare,
+//! or talks to the network while presenting itself as an offline installer,
+//! does something its manifest hides. These discrepancies between a static
+//! and a dynamic result are turned into findings with a severity, the most
+//! severe ones being hidden executables and autostart entries.
+
+use crate::core::AnalysisResult;
+use crate::reporting::CrossCheck;
+use std::fmt;
+
+/// Extensions of files that run code
+const EXECUTABLE_EXTENSIONS: &[&str] = &[
+    ".exe", ".dll", ".sys", ".scr", ".com", ".ps1", ".bat", ".cmd", ".vbs", ".js", ".msi",
+];
+
+/// Registry locations that make code run automatically
+const AUTOSTART_KEYS: &[&str] = &[
+    "\\currentversion\\run",
+    "\\currentversion\\policies\\explorer\\run",
+    "\\currentversion\\winlogon",
+    "\\currentcontrolset\\services\\",
+    "\\image file execution options\\",
+    "\\schedule\\taskcache\\",
+];
+
+/// Folder whose files run at logon
+const STARTUP_FOLDER: &str = "/start menu/programs/startup/";
+
+/// Words with which installers claim to need no network access
+const OFFLINE_CLAIMS: &[&str] = &["offline", "standalone", "full installer"];
+
+/// Properties that describe the installer
+const DESCRIPTION_PROPERTIES: &[&str] = &["FileDescription", "description", "Comments"];
+
+/// Number of paths listed as evidence per finding
+const MAX_EVIDENCE: usize = 10;
+
+/// Severity of a discrepancy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DiscrepancySeverity {
+    Low,
+    Medium,
+    High,
+}
+
+impl DiscrepancySeverity {
+    /// Value used in analysis properties
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+        }
+    }
+}
+
+impl fmt::Display for DiscrepancySeverity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Behavior that diverges from the static manifest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Discrepancy {
+    pub severity: DiscrepancySeverity,
+    pub title: &'static str,
+    /// Paths, keys or addresses behind the finding
+    pub evidence: Vec<String>,
+}
+
+/// Collect the discrepancies between a static and a dynamic result
+///
+/// Findings are ordered from the most to the least severe.
+pub fn find_discrepancies(
+    static_result: &AnalysisResult,
+    dynamic_result: &AnalysisResult,
+) -> Vec<Discrepancy> {
+    let cross_check = CrossCheck::compare(static_result, dynamic_result);
+    let mut findings = Vec::new();
+    let mut add = |severity, title, evidence: Vec<String>| {
+        if !evidence.is_empty() {
+            findings.push(Discrepancy {
+                severity,
+                title,
+                evidence,
+            });
+        }
+    };
+
+    let (startup_files, written): (Vec<String>, Vec<String>) = cross_check
+        .installed_not_declared
+        .iter()
+        .cloned()
+        .partition(|path| path.contains(STARTUP_FOLDER));
+    let (executables, others): (Vec<String>, Vec<String>) = written
+        .into_iter()
+        .partition(|path| EXECUTABLE_EXTENSIONS.iter().any(|ext| path.ends_with(ext)));
+    add(
+        DiscrepancySeverity::High,
+        "Undeclared file in the startup folder",
+        startup_files,
+    );
+    add(
+        DiscrepancySeverity::High,
+        "Undeclared executable written",
+        executables,
+    );
+    add(
+        DiscrepancySeverity::Medium,
+        "Undeclared file written",
+        others,
+    );
+
+    let (autostart, keys): (Vec<String>, Vec<String>) = cross_check
+        .registry_written_not_declared
+        .iter()
+        .cloned()
+        .partition(|key| AUTOSTART_KEYS.iter().any(|pattern| key.contains(pattern)));
+    add(
+        DiscrepancySeverity::High,
+        "Undeclared autostart registry entry",
+        autostart,
+    );
+    add(
+        DiscrepancySeverity::Medium,
+        "Undeclared registry key or value written",
+        keys,
+    );
+
+    if let Some(claim) = offline_claim(static_result) {
+        let mut remotes: Vec<String> = dynamic_result
+            .network_operations
+            .iter()
+            .map(|operation| operation.remote_address.clone())
+            .collect();
+        remotes.sort();
+        remotes.dedup();
+        add(DiscrepancySeverity::High, claim, remotes);
+    }
+
+    add(
+        DiscrepancySeverity::Low,
+        "Declared file not installed",
+        cross_check.declared_not_installed,
+    );
+
+    findings.sort_by_key(|finding| std::cmp::Reverse(finding.severity));
+    findings
+}
+
+/// Title of the network finding if the installer claims to work offline
+fn offline_claim(result: &AnalysisResult) -> Option<&'static str> {
+    let properties = &result.metadata.properties;
+    let file_name = result
+        .source_file_path
+        .as_ref()
+        .and_then(|path| path.file_name())
+        .map(|name| name.to_string_lossy().to_string());
+    let texts = file_name
+        .into_iter()
+        .chain(result.metadata.product_name.clone())
+        .chain(
+            DESCRIPTION_PROPERTIES
+                .iter()
+                .filter_map(|key| properties.get(*key).cloned()),
+        );
+    let claims_offline = texts
+        .map(|text| text.to_lowercase())
+        .any(|text| OFFLINE_CLAIMS.iter().any(|claim| text.contains(claim)));
+    claims_offline.then_some("Network traffic despite offline installer claim")
+}
+
+/// Record the findings as analysis properties
+pub fn insert_properties(findings: &[Discrepancy], result: &mut AnalysisResult) {
+    let properties = &mut result.metadata.properties;
+    properties.insert("discrepancy_count".to_string(), findings.len().to_string());
+    let Some(highest) = findings.first() else {
+        return;
+    };
+
+    properties.insert(
+        "discrepancy_severity".to_string(),
+        highest.severity.as_str().to_string(),
+    );
+    let descriptions: Vec<String> = findings
+        .iter()
+        .map(|finding| {
+            let listed: Vec<&str> = finding
+                .evidence
+                .iter()
+                .take(MAX_EVIDENCE)
+                .map(String::as_str)
+                .collect();
+            let more = finding.evidence.len().saturating_sub(MAX_EVIDENCE);
+            let suffix = if more > 0 {
+                format!(" and {} more", more)
+            } else {
+                String::new()
+            };
+            format!(
+                "{}: {} ({}{})",
+                finding.severity,
+                finding.title,
+                listed.join(", "),
+                suffix
+            )
+        })
+        .collect();
+    properties.insert("discrepancies".to_string(), descriptions.join("; "));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        FileAttributes, FileEntry, FileOperation, InstallerFormat, InstallerMetadata,
+        NetworkOpType, NetworkOperation, RegistryOperation,
+    };
+    use chrono::Utc;
+    use std::path::PathBuf;
+
+    fn result(product_name: &str) -> AnalysisResult {
+        AnalysisResult {
+            session_id: uuid::Uuid::new_v4(),
+            metadata: InstallerMetadata {
+                format: InstallerFormat::NSIS,
+                product_name: Some(product_name.to_string()),
+                created_at: Utc::now(),
+                ..Default::default()
+            },
+            analyzed_at: Utc::now(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_find_discrepancies() {
+        let mut static_result = result("App Offline Installer");
+        static_result.files.push(FileEntry {
+            path: PathBuf::from("app.exe"),
+            target_path: None,
+            size: 1,
+            hash: None,
+            attributes: FileAttributes::default(),
+            compression: None,
+            is_speculative: false,
+            architecture: None,
+            modified: None,
+            source: None,
+        });
+
+        let mut dynamic_result = result("App Offline Installer");
+        let now = Utc::now();
+        for path in [
+            "C:\\App\\app.exe",
+            "C:\\App\\helper.exe",
+            "C:\\App\\log.txt",
+        ] {
+            dynamic_result.file_operations.push(FileOperation::Create {
+                path: PathBuf::from(path),
+                size: 1,
+                timestamp: now,
+                process_id: None,
+            });
+        }
+        dynamic_result
+            .registry_operations
+            .push(RegistryOperation::CreateKey {
+                key_path: "HKCU\\Software\\Microsoft\\Windows\\CurrentVersion\\Run\\Helper"
+                    .to_string(),
+                timestamp: now,
+                process_id: None,
+            });
+        dynamic_result.network_operations.push(NetworkOperation {
+            operation_type: NetworkOpType::Connect,
+            local_address: "10.0.0.2:50000".to_string(),
+            remote_address: "203.0.113.7:443".to_string(),
+            protocol: "TCP".to_string(),
+            bytes_transferred: 0,
+            timestamp: now,
+            process_id: None,
+        });
+
+        let findings = find_discrepancies(&static_result, &dynamic_result);
+        let titles: Vec<(DiscrepancySeverity, &str)> = findings
+            .iter()
+            .map(|finding| (finding.severity, finding.title))
+            .collect();
+        assert_eq!(
+            titles,
+            vec![
+                (DiscrepancySeverity::High, "Undeclared executable written"),
+                (
+                    DiscrepancySeverity::High,
+                    "Undeclared autostart registry entry"
+                ),
+                (
+                    DiscrepancySeverity::High,
+                    "Network traffic despite offline installer claim"
+                ),
+                (DiscrepancySeverity::Medium, "Undeclared file written"),
+            ]
+        );
+
+        let mut merged = static_result.clone();
+        insert_properties(&findings, &mut merged);
+        let properties = &merged.metadata.properties;
+        assert_eq!(properties["discrepancy_count"], "4");
+        assert_eq!(properties["discrepancy_severity"], "high");
+        assert!(properties["discrepancies"].contains("c:/app/helper.exe"));
+
+        let online = find_discrepancies(&result("App"), &result("App"));
+        assert!(online.is_empty());
+    }
+}
@@ -1,17 +1,62 @@
 //! Report generator implementation using frontend templates
 
-use crate::core::{AnalysisResult, AnalyzerError, Result};
+use crate::analyzers::msix::CapabilityRisk;
+use crate::analyzers::requirements::SystemRequirements;
+use crate::core::{AnalysisResult, AnalyzerError, InstallerMetadata, MetadataField, Result};
+use crate::reporting::correlation::ProductIdentity;
 use crate::reporting::templates::get_report_template;
-use crate::reporting::{ReportFormat, Reporter};
+use crate::reporting::{
+    AttackSummary, ProcessAttribution, RegistryGroups, RepackagingAdvice, ReportFormat, Reporter,
+    VendorInfo, REPORT_SCHEMA_VERSION,
+};
+use crate::utils::display_name;
+use base64::Engine;
+use flate2::{write::GzEncoder, Compression};
 use serde_json;
-use std::path::Path;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// How an HTML report carries its analysis data
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HtmlData {
+    /// Plain JSON in an inline script
+    #[default]
+    Inline,
+    /// Gzip-compressed JSON in an inline script, decompressed by the browser
+    Compressed,
+    /// JSON in a `<report>.data.json` file next to the report
+    External,
+}
+
+/// Options shaping the generated reports
+#[derive(Debug, Clone, Default)]
+pub struct ReportOptions {
+    pub html_data: HtmlData,
+    /// Leave the hashes of packaged files out of JSON and HTML reports
+    pub omit_file_hashes: bool,
+}
 
 /// Main report generator
-pub struct ReportGenerator {}
+pub struct ReportGenerator {
+    options: ReportOptions,
+}
 
 impl ReportGenerator {
     pub fn new() -> Self {
-        Self {}
+        Self::with_options(ReportOptions::default())
+    }
+
+    pub fn with_options(options: ReportOptions) -> Self {
+        Self { options }
+    }
+
+    /// Data file written next to an HTML report with [`HtmlData::External`]
+    pub fn data_file_path(report_path: &Path) -> PathBuf {
+        let stem = report_path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| "report".to_string());
+        report_path.with_file_name(format!("{}.data.json", stem))
     }
 
     /// Create unified analysis data structure (used by both HTML and JSON reports)
@@ -20,8 +65,8 @@ impl ReportGenerator {
         let original_filename = if let Some(source_path) = &result.source_file_path {
             source_path
                 .file_name()
-                .and_then(|name| name.to_str())
-                .map(|s| s.to_string())
+                .map(|name| display_name(&name.to_string_lossy()))
+                .filter(|name| !name.is_empty())
                 .unwrap_or_else(|| "Unknown File".to_string())
         } else {
             result
@@ -29,40 +74,66 @@ impl ReportGenerator {
                 .properties
                 .get("OriginalFilename")
                 .or_else(|| result.metadata.properties.get("original_filename"))
-                .cloned()
+                .map(|name| display_name(name))
                 .unwrap_or_else(|| {
-                    result
-                        .metadata
-                        .product_name
-                        .as_deref()
-                        .unwrap_or("Unknown Package")
-                        .to_string()
+                    display_name(
+                        result
+                            .metadata
+                            .product_name
+                            .as_deref()
+                            .unwrap_or("Unknown Package"),
+                    )
                 })
         };
+        let product_name = result
+            .metadata
+            .product_name
+            .as_deref()
+            .map(display_name)
+            .unwrap_or_else(|| "Unknown Package".to_string());
 
         // Create unified data structure
         let analysis_data = serde_json::json!({
+            "schema_version": REPORT_SCHEMA_VERSION,
             "session_id": result.session_id,
             "analyzed_at": result.analyzed_at,
             "analysis_duration": result.analysis_duration.as_secs_f64(),
             "dynamic_analysis": result.dynamic_analysis,
             "metadata": {
                 "original_filename": original_filename,
-                "filename": result.metadata.product_name.as_deref().unwrap_or("Unknown Package"),
+                "filename": product_name,
                 "file_size": result.metadata.file_size,
                 "file_hash": result.metadata.file_hash,
-                "format": format!("{:?}", result.metadata.format),
+                "format": result.metadata.format.to_string(),
                 "version": result.metadata.product_version.as_deref().unwrap_or("N/A"),
                 "publisher": result.metadata.manufacturer.as_deref().unwrap_or("N/A"),
                 "description": result.metadata.properties.get("FileDescription")
                     .or_else(|| result.metadata.properties.get("description"))
                     .cloned()
                     .unwrap_or_else(|| "N/A".to_string()),
-                "properties": result.metadata.properties
+                "properties": result.metadata.properties,
+                "provenance": result.metadata.provenance,
+                "format_details": result.metadata.format_details
             },
+            "analyzer_capabilities": result.analyzer_capabilities.map(|caps| {
+                serde_json::json!({
+                    "metadata": caps.metadata,
+                    "file_extraction": caps.file_extraction,
+                    "registry_operations": caps.registry_operations,
+                    "install_scripts": caps.install_scripts,
+                    "signatures": caps.signatures,
+                    "accuracy": caps.accuracy(),
+                    "heuristic": caps.is_heuristic()
+                })
+            }),
+            "dependencies": result.dependencies,
+            "strings": result.strings,
+            "product_identity": ProductIdentity::from_result(result),
+            "vendor": VendorInfo::from_properties(&result.metadata.properties),
+            "requirements": SystemRequirements::from_properties(&result.metadata.properties),
             "files": self.create_hierarchical_file_list(&result.files),
             "registry_operations": result.registry_operations.iter().map(|op| {
-                match op {
+                let mut entry = match op {
                     crate::core::RegistryOperation::CreateKey { key_path, .. } => {
                         serde_json::json!({
                             "operation": "CREATE",
@@ -97,18 +168,31 @@ impl ReportGenerator {
                             "value": null
                         })
                     }
-                }
+                };
+                entry["process_id"] = serde_json::json!(op.process_id());
+                entry
             }).collect::<Vec<_>>(),
+            "registry_groups": RegistryGroups::from_operations(&result.registry_operations),
             "file_operations": result.file_operations,
             "process_operations": result.process_operations,
             "network_operations": result.network_operations,
+            "wmi_operations": result.wmi_operations,
+            "powershell_script_blocks": result.powershell_script_blocks,
+            "process_attribution": ProcessAttribution::from_result(result),
+            "resource_usage": result.resource_usage,
+            "attack": AttackSummary::from_result(result),
+            "repackaging": RepackagingAdvice::from_result(result),
+            "audit": result.audit,
+            "previews": result.previews,
             "summary": {
                 "total_files": result.files.len(),
                 "executable_files": result.files.iter().filter(|f| f.attributes.executable).count(),
                 "registry_operations": result.registry_operations.len(),
                 "file_operations": result.file_operations.len(),
                 "process_operations": result.process_operations.len(),
-                "network_operations": result.network_operations.len()
+                "network_operations": result.network_operations.len(),
+                "wmi_operations": result.wmi_operations.len(),
+                "powershell_script_blocks": result.powershell_script_blocks.len()
             }
         });
 
@@ -125,23 +209,224 @@ impl ReportGenerator {
     }
 
     /// Generate modern HTML report using frontend template with data injection
-    async fn generate_html_report(&self, result: &AnalysisResult) -> Result<String> {
-        // Get the base HTML template
-        let template_html = get_report_template();
-
+    ///
+    /// With [`HtmlData::External`] the report loads its data from
+    /// `data_file`, which must be given.
+    async fn generate_html_report(
+        &self,
+        result: &AnalysisResult,
+        data_file: Option<&str>,
+    ) -> Result<String> {
         // Use unified analysis data structure
         let analysis_data = self.create_unified_analysis_data(result)?;
+        self.render_html_report(&analysis_data, data_file)
+    }
 
-        // Inject the data into the HTML template
-        let data_script = format!(
-            "<script>window.ANALYSIS_DATA = {};</script>",
-            serde_json::to_string(&analysis_data).map_err(AnalyzerError::SerializationError)?
-        );
+    /// Generate an HTML report loading its data from `data_file`, with the
+    /// content of that file
+    ///
+    /// For reports with [`HtmlData::External`] that are not written through
+    /// [`Reporter::save_report`], e.g. into a bundle.
+    pub async fn generate_html_report_pair(
+        &self,
+        result: &AnalysisResult,
+        data_file: &str,
+    ) -> Result<(String, String)> {
+        let analysis_data = self.create_unified_analysis_data(result)?;
+        let html = self.render_html_report(&analysis_data, Some(data_file))?;
+        let data =
+            serde_json::to_string(&analysis_data).map_err(AnalyzerError::SerializationError)?;
+        Ok((html, data))
+    }
 
+    fn render_html_report(
+        &self,
+        analysis_data: &serde_json::Value,
+        data_file: Option<&str>,
+    ) -> Result<String> {
         // Insert the data script before the closing </head> tag
-        let html_with_data = template_html.replace("</head>", &format!("{}\n</head>", data_script));
+        let html_with_data = self.inject_data(analysis_data, false, data_file)?;
+
+        let original_filename = analysis_data["metadata"]["original_filename"]
+            .as_str()
+            .unwrap_or_default();
+        Ok(with_title(&html_with_data, original_filename))
+    }
+
+    /// Insert the script handing `data` to the frontend into the template
+    ///
+    /// `data` is a unified analysis, or the combined data of several if
+    /// `combined` is set.
+    fn inject_data(
+        &self,
+        data: &serde_json::Value,
+        combined: bool,
+        data_file: Option<&str>,
+    ) -> Result<String> {
+        let data_script = match self.options.html_data {
+            HtmlData::Inline if combined => format!(
+                "<script>window.ANALYSIS_ARTIFACTS = {}; window.ANALYSIS_DATA = window.ANALYSIS_ARTIFACTS[0];</script>",
+                script_json(&data["artifacts"])?
+            ),
+            HtmlData::Inline => format!(
+                "<script>window.ANALYSIS_DATA = {};</script>",
+                script_json(data)?
+            ),
+            HtmlData::Compressed => {
+                let json = serde_json::to_vec(data).map_err(AnalyzerError::SerializationError)?;
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+                encoder.write_all(&json)?;
+                let compressed = encoder.finish()?;
+                let source = serde_json::json!({
+                    "kind": "gzip",
+                    "combined": combined,
+                    "data": base64::engine::general_purpose::STANDARD.encode(compressed),
+                });
+                format!(
+                    "<script>window.ANALYSIS_SOURCE = {};</script>",
+                    script_json(&source)?
+                )
+            }
+            HtmlData::External => {
+                let data_file = data_file.ok_or_else(|| {
+                    AnalyzerError::config_error(
+                        "An HTML report with external data needs a report file (--output)",
+                    )
+                })?;
+                let source = serde_json::json!({
+                    "kind": "external",
+                    "combined": combined,
+                    "url": data_file,
+                });
+                format!(
+                    "<script>window.ANALYSIS_SOURCE = {};</script>",
+                    script_json(&source)?
+                )
+            }
+        };
+
+        Ok(get_report_template().replace("</head>", &format!("{}\n</head>", data_script)))
+    }
+
+    /// Generate a combined report covering several analysis results
+    pub async fn generate_combined_report(
+        &self,
+        results: &[AnalysisResult],
+        format: ReportFormat,
+    ) -> Result<String> {
+        match format {
+            ReportFormat::Json => {
+                let combined_data = self.create_combined_analysis_data(results)?;
+                serde_json::to_string_pretty(&combined_data)
+                    .map_err(AnalyzerError::SerializationError)
+            }
+            ReportFormat::Html => self.generate_combined_html_report(results, None).await,
+            ReportFormat::Markdown => self.generate_combined_markdown_report(results).await,
+        }
+    }
+
+    /// Save a combined report covering several analysis results
+    pub async fn save_combined_report(
+        &self,
+        results: &[AnalysisResult],
+        format: ReportFormat,
+        output_path: &Path,
+    ) -> Result<()> {
+        let content = match format {
+            ReportFormat::Html if self.options.html_data == HtmlData::External => {
+                let data_path = Self::data_file_path(output_path);
+                let combined_data = self.create_combined_analysis_data(results)?;
+                let data = serde_json::to_string(&combined_data)
+                    .map_err(AnalyzerError::SerializationError)?;
+                tokio::fs::write(crate::utils::extended_length(&data_path), data).await?;
+                tracing::info!("Report data saved to: {}", data_path.display());
+                self.inject_data(&combined_data, true, Some(&file_name(&data_path)))?
+            }
+            format => self.generate_combined_report(results, format).await?,
+        };
+        tokio::fs::write(crate::utils::extended_length(output_path), content).await?;
+        tracing::info!("Combined report saved to: {}", output_path.display());
+        Ok(())
+    }
+
+    /// Create the combined data structure: one unified entry per artifact plus totals
+    fn create_combined_analysis_data(
+        &self,
+        results: &[AnalysisResult],
+    ) -> Result<serde_json::Value> {
+        let artifacts = results
+            .iter()
+            .map(|result| self.create_unified_analysis_data(result))
+            .collect::<Result<Vec<_>>>()?;
 
-        Ok(html_with_data)
+        Ok(serde_json::json!({
+            "schema_version": REPORT_SCHEMA_VERSION,
+            "summary": {
+                "total_artifacts": results.len(),
+                "total_files": results.iter().map(|r| r.files.len()).sum::<usize>(),
+                "total_size": results.iter().map(|r| r.metadata.file_size).sum::<u64>(),
+            },
+            "artifacts": artifacts,
+        }))
+    }
+
+    /// Generate a combined HTML report; the frontend offers an artifact switcher
+    async fn generate_combined_html_report(
+        &self,
+        results: &[AnalysisResult],
+        data_file: Option<&str>,
+    ) -> Result<String> {
+        let combined_data = self.create_combined_analysis_data(results)?;
+        self.inject_data(&combined_data, true, data_file)
+    }
+
+    /// Generate a combined Markdown report: an overview table followed by each artifact's report
+    async fn generate_combined_markdown_report(
+        &self,
+        results: &[AnalysisResult],
+    ) -> Result<String> {
+        let combined_data = self.create_combined_analysis_data(results)?;
+        let mut markdown = format!(
+            "# Combined Installer Analysis Report\n\n**Artifacts:** {}\n**Total Files:** {}\n**Total Size:** {}\n\n| # | File | Format | Product | Version | Files | Size |\n|---|------|--------|---------|---------|-------|------|\n",
+            results.len(),
+            combined_data["summary"]["total_files"],
+            crate::utils::format_file_size(
+                combined_data["summary"]["total_size"].as_u64().unwrap_or(0)
+            )
+        );
+
+        for (index, (result, artifact)) in results
+            .iter()
+            .zip(combined_data["artifacts"].as_array().into_iter().flatten())
+            .enumerate()
+        {
+            let metadata = &artifact["metadata"];
+            markdown.push_str(&format!(
+                "| {} | {} | {} | {} | {} | {} | {} |\n",
+                index + 1,
+                metadata["original_filename"].as_str().unwrap_or("-"),
+                metadata["format"].as_str().unwrap_or("-"),
+                metadata["filename"].as_str().unwrap_or("-"),
+                metadata["version"].as_str().unwrap_or("-"),
+                result.files.len(),
+                crate::utils::format_file_size(result.metadata.file_size)
+            ));
+        }
+
+        for result in results {
+            // Demote each artifact report one heading level below the overview
+            let report = self.generate_markdown_report(result).await?;
+            markdown.push_str("\n---\n\n");
+            for line in report.lines() {
+                if line.starts_with('#') {
+                    markdown.push('#');
+                }
+                markdown.push_str(line);
+                markdown.push('\n');
+            }
+        }
+
+        Ok(markdown)
     }
 
     /// Get file type for frontend display
@@ -190,10 +475,11 @@ impl ReportGenerator {
 
     /// Create hierarchical file list with proper directory structure for frontend
     fn create_hierarchical_file_list(&self, files: &[crate::core::FileEntry]) -> serde_json::Value {
-        use std::collections::HashMap;
+        use std::collections::BTreeSet;
 
         let mut all_files = Vec::new();
-        let mut directories = HashMap::new();
+        // Sorted, so that the same result always renders the same report
+        let mut directories = BTreeSet::new();
 
         // First, collect all unique directory paths
         for file in files {
@@ -202,15 +488,12 @@ impl ReportGenerator {
 
             // Create directory entries for each level
             for i in 1..path_parts.len() {
-                let dir_path = path_parts[0..i].join("/");
-                if !directories.contains_key(&dir_path) {
-                    directories.insert(dir_path.clone(), true);
-                }
+                directories.insert(path_parts[0..i].join("/"));
             }
         }
 
         // Add directory entries
-        for dir_path in directories.keys() {
+        for dir_path in &directories {
             all_files.push(serde_json::json!({
                 "path": dir_path,
                 "size": 0,
@@ -246,15 +529,33 @@ impl ReportGenerator {
                     "system": file.attributes.system,
                     "executable": file.attributes.executable
                 },
-                "hash": file.hash,
+                "hash": if self.options.omit_file_hashes { None } else { file.hash.as_ref() },
                 "target_path": file.target_path.as_ref().map(|p| p.to_string_lossy().to_string()),
-                "compression": file.compression
+                "compression": file.compression,
+                "is_speculative": file.is_speculative,
+                "architecture": file.architecture,
+                "modified": file.modified
             }));
         }
 
         serde_json::Value::Array(all_files)
     }
 
+    /// Identity field value, marked when it is a guess
+    fn metadata_field_markdown(
+        &self,
+        metadata: &InstallerMetadata,
+        field: MetadataField,
+    ) -> String {
+        let value = metadata.field(field).unwrap_or("Unknown");
+        match metadata.provenance.get(field) {
+            Some(provenance) if provenance.is_low_confidence() => {
+                format!("{} _(low confidence: {})_", value, provenance.source)
+            }
+            _ => value.to_string(),
+        }
+    }
+
     /// Generate Markdown report
     async fn generate_markdown_report(&self, result: &AnalysisResult) -> Result<String> {
         let markdown = format!(
@@ -267,26 +568,30 @@ impl ReportGenerator {
 
 ## Installer Metadata
 
-- **Format:** {:?}
+- **Format:** {}
 - **Product Name:** {}
 - **Product Version:** {}
-- **Manufacturer:** {}
+- **Manufacturer:** {}{}
 - **File Size:** {}
 - **File Hash:** {}
 
+## Analyzer Capabilities
+
+{}
+
 ## Summary
 
 - **Files:** {} entries
 - **Registry Operations:** {} operations
 - **File Operations:** {} operations
 - **Process Operations:** {} operations
-- **Network Operations:** {} operations
+- **Network Operations:** {} operations{}{}{}{}
 
 ## Security Analysis
 
 - **Risk Level:** {}
 - **Executable Files:** {}
-- **Large Files (>50MB):** {}
+- **Large Files (>50MB):** {}{}{}{}{}{}{}{}{}{}{}{}{}{}
 
 ## File Analysis
 
@@ -302,6 +607,18 @@ impl ReportGenerator {
 
 {}
 
+## Dependencies
+
+{}
+
+## Requirements
+
+{}{}{}
+
+## Repackaging Advice
+
+{}{}
+
 ---
 
 *Report generated by Installer Analyzer v{} at {}*
@@ -311,20 +628,22 @@ impl ReportGenerator {
             crate::utils::format_duration(result.analysis_duration),
             if result.dynamic_analysis { "Yes" } else { "No" },
             result.metadata.format,
-            result.metadata.product_name.as_deref().unwrap_or("Unknown"),
-            result
-                .metadata
-                .product_version
-                .as_deref()
-                .unwrap_or("Unknown"),
-            result.metadata.manufacturer.as_deref().unwrap_or("Unknown"),
+            self.metadata_field_markdown(&result.metadata, MetadataField::ProductName),
+            self.metadata_field_markdown(&result.metadata, MetadataField::ProductVersion),
+            self.metadata_field_markdown(&result.metadata, MetadataField::Manufacturer),
+            self.generate_identity_markdown(result),
             crate::utils::format_file_size(result.metadata.file_size),
             &result.metadata.file_hash[..16],
+            self.generate_capabilities_markdown(result.analyzer_capabilities.as_ref()),
             result.files.len(),
             result.registry_operations.len(),
             result.file_operations.len(),
             result.process_operations.len(),
             result.network_operations.len(),
+            self.generate_reboot_markdown(result),
+            self.generate_architecture_markdown(result),
+            self.generate_delivery_markdown(result),
+            self.generate_parts_markdown(result),
             self.calculate_risk_level(result),
             result
                 .files
@@ -336,9 +655,28 @@ impl ReportGenerator {
                 .iter()
                 .filter(|f| f.size > 50 * 1024 * 1024)
                 .count(),
+            self.generate_integrity_markdown(result),
+            self.generate_timestamp_markdown(result),
+            self.generate_embedded_payloads_markdown(result),
+            self.generate_sync_objects_markdown(result),
+            self.generate_anti_analysis_markdown(result),
+            self.generate_spyware_markdown(result),
+            self.generate_script_activity_markdown(result),
+            self.generate_uac_bypass_markdown(result),
+            self.generate_run_comparison_markdown(result),
+            self.generate_cross_check_markdown(result),
+            self.generate_discrepancies_markdown(result),
+            self.generate_capability_risk_markdown(result),
+            self.generate_attack_markdown(result),
             self.generate_top_files_markdown(&result.files),
             self.generate_executable_files_markdown(&result.files),
             self.generate_registry_operations_markdown(&result.registry_operations),
+            self.generate_dependencies_markdown(result.dependencies.as_ref()),
+            self.generate_requirements_markdown(result),
+            self.generate_previews_markdown(&result.previews),
+            self.generate_strings_markdown(result.strings.as_ref()),
+            self.generate_repackaging_markdown(result),
+            self.generate_audit_markdown(result.audit.as_ref()),
             env!("CARGO_PKG_VERSION"),
             result.analyzed_at.format("%Y-%m-%d %H:%M:%S UTC")
         );
@@ -346,8 +684,662 @@ impl ReportGenerator {
         Ok(markdown)
     }
 
+    /// Generate capabilities section for markdown
+    fn generate_capabilities_markdown(
+        &self,
+        capabilities: Option<&crate::core::AnalyzerCapabilities>,
+    ) -> String {
+        let Some(caps) = capabilities else {
+            return "*Analyzer capabilities not available.*".to_string();
+        };
+
+        let mut markdown = String::from("| Capability | Support |\n|------------|---------|\n");
+        for (name, level) in [
+            ("Metadata", caps.metadata),
+            ("File Extraction", caps.file_extraction),
+            ("Registry Operations", caps.registry_operations),
+            ("Install Scripts", caps.install_scripts),
+            ("Signatures", caps.signatures),
+        ] {
+            markdown.push_str(&format!("| {} | {} |\n", name, level));
+        }
+        markdown.push_str(&format!("\n**Accuracy:** {}", caps.accuracy()));
+
+        if caps.is_heuristic() {
+            markdown.push_str(
+                "\n\n> ⚠️ Some entries in this report are synthesized from common patterns \
+                 for this installer format and were not read from the installer itself.",
+            );
+        }
+
+        markdown
+    }
+
+    /// Generate the audit trail section for markdown, empty for results without one
+    fn generate_audit_markdown(&self, audit: Option<&crate::core::AuditTrail>) -> String {
+        let Some(audit) = audit else {
+            return String::new();
+        };
+
+        let mut markdown = format!(
+            "\n\n## Audit Trail\n\n- **Tool:** {} {}\n- **Command Line:** `{}`\n- **Input:** {} ({}, SHA-256 `{}`)",
+            audit.tool,
+            audit.tool_version,
+            audit.command_line.join(" ").replace('`', "'"),
+            audit.input.path,
+            crate::utils::format_file_size(audit.input.size),
+            audit.input.sha256
+        );
+        if !audit.analyzers.is_empty() {
+            let analyzers: Vec<String> = audit
+                .analyzers
+                .iter()
+                .map(|analyzer| format!("{} {}", analyzer.name, analyzer.version))
+                .collect();
+            markdown.push_str(&format!("\n- **Analyzers:** {}", analyzers.join(", ")));
+        }
+        for config in &audit.config_files {
+            markdown.push_str(&format!(
+                "\n- **Configuration ({}):** {} (SHA-256 `{}`)",
+                config.role, config.path, config.sha256
+            ));
+        }
+        if let Some(config_hash) = &audit.config_hash {
+            markdown.push_str(&format!("\n- **Configuration Hash:** `{}`", config_hash));
+        }
+        if let Some(machine) = &audit.machine {
+            markdown.push_str(&format!(
+                "\n- **Machine:** {} ({}, {}/{})",
+                machine.hostname.as_deref().unwrap_or("unknown host"),
+                machine.user.as_deref().unwrap_or("unknown user"),
+                machine.os,
+                machine.arch
+            ));
+        }
+
+        markdown.push_str("\n\n| Time | Event | Detail |\n|------|-------|--------|\n");
+        for event in &audit.events {
+            markdown.push_str(&format!(
+                "| {} | {} | {} |\n",
+                event.timestamp.format("%Y-%m-%d %H:%M:%S%.3f UTC"),
+                event.event,
+                event.detail.replace('|', "\\|")
+            ));
+        }
+
+        markdown
+    }
+
+    /// Generate the file previews section for markdown, empty without previews
+    fn generate_previews_markdown(&self, previews: &[crate::core::FilePreview]) -> String {
+        if previews.is_empty() {
+            return String::new();
+        }
+
+        let mut markdown = String::from("\n\n## File Previews");
+        for preview in previews {
+            // A fence longer than any backtick run in the file
+            let mut fence = "```".to_string();
+            while preview.content.contains(&fence) {
+                fence.push('`');
+            }
+            markdown.push_str(&format!(
+                "\n\n### `{}`\n\n{}, {}\n\n{}text\n{}\n{}",
+                preview.path.replace('`', "'"),
+                preview.kind.name(),
+                crate::utils::format_file_size(preview.size),
+                fence,
+                preview.content.trim_end(),
+                fence
+            ));
+            if preview.truncated {
+                markdown.push_str("\n\n*Preview truncated; the full file is in the installer.*");
+            }
+        }
+        markdown
+    }
+
+    /// Generate the extracted strings appendix for markdown, empty unless strings were extracted
+    fn generate_strings_markdown(&self, appendix: Option<&crate::core::StringAppendix>) -> String {
+        let Some(appendix) = appendix else {
+            return String::new();
+        };
+
+        let mut markdown = format!(
+            "\n\n## Appendix: Extracted Strings\n\n{} unique strings from {} source(s); {} filtered as noise",
+            appendix.strings.len(),
+            appendix.sources.len(),
+            appendix.filtered
+        );
+        if appendix.omitted > 0 {
+            markdown.push_str(&format!(", {} omitted", appendix.omitted));
+        }
+        markdown.push_str(".\n\n### Indicators\n\n");
+
+        if appendix.indicators().next().is_none() {
+            markdown.push_str("*No indicators found.*");
+        } else {
+            markdown.push_str(
+                "| Class | Value | Source | Offset |\n|-------|-------|--------|--------|\n",
+            );
+            for string in appendix.indicators() {
+                markdown.push_str(&format!(
+                    "| {} | `{}` | {} | 0x{:x} |\n",
+                    string.ioc.map(|ioc| ioc.name()).unwrap_or_default(),
+                    string.value.replace('|', "\\|").replace('`', "'"),
+                    string.source,
+                    string.offset
+                ));
+            }
+        }
+
+        let others: Vec<&str> = appendix
+            .strings
+            .iter()
+            .filter(|s| s.ioc.is_none())
+            .map(|s| s.value.as_str())
+            .collect();
+        if !others.is_empty() {
+            markdown.push_str("\n\n### Other Strings\n\n```text\n");
+            for value in others.iter().take(500) {
+                markdown.push_str(value);
+                markdown.push('\n');
+            }
+            markdown.push_str("```");
+            if others.len() > 500 {
+                markdown.push_str(&format!(
+                    "\n\n*{} more strings in the JSON report.*",
+                    others.len() - 500
+                ));
+            }
+        }
+
+        markdown
+    }
+
+    /// Generate dependencies section for markdown
+    fn generate_dependencies_markdown(
+        &self,
+        graph: Option<&crate::core::DependencyGraph>,
+    ) -> String {
+        let Some(graph) = graph.filter(|g| !g.is_empty()) else {
+            return "*No declared dependencies.*".to_string();
+        };
+
+        let mut markdown = String::from(
+            "| Package | Requirement | Condition | Optional |\n|---------|-------------|-----------|----------|\n",
+        );
+        for edge in &graph.edges {
+            let name = graph
+                .nodes
+                .iter()
+                .find(|node| node.id == edge.to)
+                .map(|node| node.name.as_str())
+                .unwrap_or(&edge.to);
+            markdown.push_str(&format!(
+                "| `{}` | {} | {} | {} |\n",
+                name,
+                edge.version_spec.as_deref().unwrap_or("any"),
+                edge.condition
+                    .as_deref()
+                    .map(|c| format!("`{}`", c))
+                    .unwrap_or_else(|| "-".to_string()),
+                if edge.optional { "Yes" } else { "No" }
+            ));
+        }
+
+        markdown
+    }
+
+    /// Generate product identifier lines of the metadata section
+    fn generate_identity_markdown(&self, result: &AnalysisResult) -> String {
+        let identity = ProductIdentity::from_result(result);
+        let mut markdown = String::new();
+        if let Some(vendor) = VendorInfo::from_properties(&result.metadata.properties) {
+            markdown.push_str(&format!("\n- **Vendor:** {}", vendor.vendor));
+            if let Some(homepage) = &vendor.homepage {
+                markdown.push_str(&format!(" ({})", homepage));
+            }
+            if let Some(category) = &vendor.category {
+                markdown.push_str(&format!("\n- **Category:** {}", category));
+            }
+        }
+        if let Some(product_code) = &identity.product_code {
+            markdown.push_str(&format!("\n- **Product Code:** {}", product_code));
+        }
+        if let Some(upgrade_code) = &identity.upgrade_code {
+            markdown.push_str(&format!("\n- **Upgrade Code:** {}", upgrade_code));
+        }
+        if !identity.arp_keys.is_empty() {
+            markdown.push_str(&format!(
+                "\n- **Add/Remove Programs Keys:** {}",
+                identity.arp_keys.join(", ")
+            ));
+        }
+        markdown
+    }
+
+    /// Generate requirements section for markdown
+    fn generate_requirements_markdown(&self, result: &AnalysisResult) -> String {
+        let requirements = SystemRequirements::from_properties(&result.metadata.properties);
+        let mut markdown = String::new();
+        for (label, values) in [
+            ("Operating System", &requirements.operating_system),
+            ("Architecture", &requirements.architecture),
+            ("Runtimes", &requirements.runtimes),
+            ("Conditions", &requirements.conditions),
+        ] {
+            if values.is_empty() {
+                continue;
+            }
+            markdown.push_str(&format!("**{}:**\n\n", label));
+            for value in values {
+                markdown.push_str(&format!("- {}\n", value));
+            }
+            markdown.push('\n');
+        }
+        if markdown.is_empty() {
+            markdown.push_str("*No declared requirements.*\n\n");
+        }
+        for note in &requirements.notes {
+            markdown.push_str(&format!("*{}*\n", note));
+        }
+
+        markdown.trim_end().to_string()
+    }
+
+    /// Generate architecture lines of the summary section
+    fn generate_architecture_markdown(&self, result: &AnalysisResult) -> String {
+        let properties = &result.metadata.properties;
+        let mut markdown = String::new();
+        if let Some(installer) = properties.get("installer_architecture") {
+            markdown.push_str(&format!("\n- **Installer Architecture:** {}", installer));
+        }
+        if let Some(payloads) = properties.get("payload_architectures") {
+            markdown.push_str(&format!("\n- **Payload Architectures:** {}", payloads));
+            if properties
+                .get("mixed_architecture")
+                .is_some_and(|mixed| mixed == "true")
+            {
+                markdown.push_str(" ⚠️ mixed");
+            }
+        }
+        if let Some(supported) = properties.get("supported_architectures") {
+            markdown.push_str(&format!("\n- **Supported Architectures:** {}", supported));
+        }
+        if let Some(incompatible) = properties.get("arm64_incompatible") {
+            markdown.push_str(&format!("\n- **⚠️ ARM64-Incompatible:** {}", incompatible));
+        }
+        markdown
+    }
+
+    /// Generate reboot verdict lines of the summary section
+    fn generate_reboot_markdown(&self, result: &AnalysisResult) -> String {
+        let properties = &result.metadata.properties;
+        let Some(verdict) = properties.get("reboot_required") else {
+            return String::new();
+        };
+
+        let mut markdown = format!("\n- **Reboot Required:** {}", verdict);
+        if let Some(indicators) = properties.get("reboot_indicators") {
+            markdown.push_str(&format!(" ({})", indicators));
+        }
+        markdown
+    }
+
+    /// Generate web installer lines of the summary section
+    fn generate_delivery_markdown(&self, result: &AnalysisResult) -> String {
+        let properties = &result.metadata.properties;
+        let Some(delivery) = properties.get("installer_delivery") else {
+            return String::new();
+        };
+
+        let mut markdown = format!("\n- **Delivery:** {}", delivery);
+        if let Some(indicators) = properties.get("web_installer_indicators") {
+            markdown.push_str(&format!(" ({})", indicators));
+        }
+        if let Some(urls) = properties.get("download_urls") {
+            markdown.push_str(&format!("\n- **Download URLs:** {}", urls));
+        }
+        if let Some(warning) = properties.get("web_installer_warning") {
+            markdown.push_str(&format!("\n- **⚠️ Incomplete:** {}", warning));
+        }
+        markdown
+    }
+
+    /// Generate multi-part installer lines of the summary section
+    fn generate_parts_markdown(&self, result: &AnalysisResult) -> String {
+        let properties = &result.metadata.properties;
+        let mut markdown = String::new();
+        if let Some(parts) = properties.get("installer_parts") {
+            markdown.push_str(&format!("\n- **Installer Parts:** {}", parts));
+        }
+        if let Some(total) = properties.get("installer_total_size") {
+            markdown.push_str(&format!("\n- **Total Size:** {}", total));
+        }
+        if let Some(missing) = properties.get("installer_missing_parts") {
+            markdown.push_str(&format!("\n- **⚠️ Missing Parts:** {}", missing));
+        }
+        markdown
+    }
+
+    fn generate_integrity_markdown(&self, result: &AnalysisResult) -> String {
+        let properties = &result.metadata.properties;
+        let Some(signature) = properties.get("msix_signature_status") else {
+            return String::new();
+        };
+
+        let mut markdown = format!("\n- **Package Signature:** {}", signature);
+        if let Some(signer) = properties.get("msix_signer") {
+            markdown.push_str(&format!(" ({})", signer));
+        }
+        if let Some(block_map) = properties.get("msix_block_map_status") {
+            markdown.push_str(&format!("\n- **Block Map:** {}", block_map));
+        }
+        if properties
+            .get("msix_tampered")
+            .is_some_and(|tampered| tampered == "true")
+        {
+            markdown.push_str(
+                "\n- **⚠️ Tampering Detected:** package content does not match its integrity data",
+            );
+        }
+        if let Some(issues) = properties.get("msix_integrity_issues") {
+            markdown.push_str(&format!("\n- **Integrity Issues:** {}", issues));
+        }
+
+        markdown
+    }
+
+    /// Generate timestamp anomaly lines of the security section
+    fn generate_timestamp_markdown(&self, result: &AnalysisResult) -> String {
+        let properties = &result.metadata.properties;
+        let mut markdown = String::new();
+        if let Some(range) = properties.get("file_timestamp_range") {
+            markdown.push_str(&format!("\n- **File Timestamps:** {}", range));
+        }
+        if let Some(details) = properties.get("timestamp_anomaly_details") {
+            markdown.push_str(&format!("\n- **⚠️ Timestamp Anomalies:** {}", details));
+        }
+        markdown
+    }
+
+    /// Generate embedded payload lines of the security section
+    fn generate_embedded_payloads_markdown(&self, result: &AnalysisResult) -> String {
+        let properties = &result.metadata.properties;
+        let mut markdown = String::new();
+        if let Some(payloads) = properties.get("embedded_payloads") {
+            markdown.push_str(&format!(
+                "\n- **Embedded Payloads ({}):** {}",
+                properties
+                    .get("embedded_payload_count")
+                    .map(String::as_str)
+                    .unwrap_or("?"),
+                payloads
+            ));
+        }
+        if let Some(analysis) = properties.get("embedded_payload_analysis") {
+            markdown.push_str(&format!("\n- **Carved Payload Analysis:** {}", analysis));
+        }
+        markdown
+    }
+
+    /// Generate synchronization object lines of the security section
+    fn generate_sync_objects_markdown(&self, result: &AnalysisResult) -> String {
+        let properties = &result.metadata.properties;
+        let mut markdown = String::new();
+        if let Some(objects) = properties.get("sync_objects") {
+            markdown.push_str(&format!(
+                "\n- **Synchronization Objects ({}):** {}",
+                properties
+                    .get("sync_object_count")
+                    .map(String::as_str)
+                    .unwrap_or("?"),
+                objects
+            ));
+        }
+        if let Some(mutexes) = properties.get("single_instance_mutexes") {
+            markdown.push_str(&format!(
+                "\n- **Single-Instance Mutexes:** {} (\"another instance is running\" errors mean another process holds one of them)",
+                mutexes
+            ));
+        }
+        markdown
+    }
+
+    /// Generate anti-analysis lines of the security section
+    fn generate_anti_analysis_markdown(&self, result: &AnalysisResult) -> String {
+        let properties = &result.metadata.properties;
+        let mut markdown = String::new();
+        if let Some(verdict) = properties.get("anti_analysis_checks") {
+            markdown.push_str(&format!("\n- **Anti-VM/Sandbox Checks:** {}", verdict));
+            if let Some(indicators) = properties.get("anti_analysis_indicators") {
+                markdown.push_str(&format!(" ({})", indicators));
+            }
+        }
+        if let Some(filtered) = properties.get("noise_filtered_operations") {
+            markdown.push_str(&format!("\n- **Filtered System Noise:** {}", filtered));
+        }
+        markdown
+    }
+
+    /// Generate spyware indicator lines of the security section
+    fn generate_spyware_markdown(&self, result: &AnalysisResult) -> String {
+        let properties = &result.metadata.properties;
+        let Some(capabilities) = properties.get("spyware_capabilities") else {
+            return String::new();
+        };
+        let mut markdown = format!("\n- **Spyware Indicators:** {}", capabilities);
+        if let Some(indicators) = properties.get("spyware_indicators") {
+            markdown.push_str(&format!(" ({})", indicators));
+        }
+        markdown
+    }
+
+    /// Generate the repackaging advice section
+    fn generate_repackaging_markdown(&self, result: &AnalysisResult) -> String {
+        let advice = RepackagingAdvice::from_result(result);
+        let lines: Vec<String> = advice
+            .recommendations
+            .iter()
+            .map(|recommendation| {
+                if recommendation.detail.is_empty() {
+                    format!("- **{}**", recommendation.title)
+                } else {
+                    format!("- **{}:** {}", recommendation.title, recommendation.detail)
+                }
+            })
+            .collect();
+        lines.join("\n")
+    }
+
+    /// Generate the ATT&CK matrix of the security section
+    fn generate_attack_markdown(&self, result: &AnalysisResult) -> String {
+        let summary = AttackSummary::from_result(result);
+        if summary.is_empty() {
+            return String::new();
+        }
+
+        let mut markdown = format!(
+            "\n- **MITRE ATT&CK:** {}\n\n### MITRE ATT&CK\n\n\
+             | Tactic | Technique | Findings |\n|--------|-----------|----------|\n",
+            summary.technique_ids().join(", ")
+        );
+        for entry in &summary.matrix {
+            markdown.push_str(&format!(
+                "| {} | {} {} | {} |\n",
+                entry.tactic,
+                entry.technique_id,
+                entry.technique_name,
+                entry.findings.join(", ")
+            ));
+        }
+        markdown
+    }
+
+    /// Generate the UAC bypass line of the security section
+    fn generate_uac_bypass_markdown(&self, result: &AnalysisResult) -> String {
+        let properties = &result.metadata.properties;
+        let Some(techniques) = properties.get("uac_bypass_techniques") else {
+            return String::new();
+        };
+        let mut markdown = format!("\n- **🔴 UAC Bypass (critical):** {}", techniques);
+        if let Some(findings) = properties.get("uac_bypass_findings") {
+            markdown.push_str(&format!(" ({})", findings));
+        }
+        markdown
+    }
+
+    /// Generate WMI and PowerShell lines of the security section
+    fn generate_script_activity_markdown(&self, result: &AnalysisResult) -> String {
+        let properties = &result.metadata.properties;
+        let mut markdown = String::new();
+        if let Some(count) = properties.get("wmi_operation_count") {
+            markdown.push_str(&format!("\n- **WMI Operations:** {}", count));
+            if let Some(commands) = properties.get("wmi_process_creations") {
+                markdown.push_str(&format!(" (started {})", commands));
+            }
+        }
+        if let Some(subscriptions) = properties.get("wmi_event_subscriptions") {
+            markdown.push_str(&format!(
+                "\n- **WMI Event Subscriptions:** {} (persist beyond the installation)",
+                subscriptions
+            ));
+        }
+        if let Some(count) = properties.get("powershell_script_block_count") {
+            markdown.push_str(&format!("\n- **PowerShell Script Blocks:** {}", count));
+            if let Some(scripts) = properties.get("powershell_scripts") {
+                markdown.push_str(&format!(" ({})", scripts));
+            }
+        }
+        markdown
+    }
+
+    /// Generate multi-run comparison lines of the security section
+    fn generate_run_comparison_markdown(&self, result: &AnalysisResult) -> String {
+        let properties = &result.metadata.properties;
+        let (Some(runs), Some(count)) = (
+            properties.get("sandbox_runs"),
+            properties.get("nondeterministic_action_count"),
+        ) else {
+            return String::new();
+        };
+        let mut markdown = format!(
+            "\n- **Nondeterministic Actions:** {} across {} sandbox runs",
+            count, runs
+        );
+        if let Some(actions) = properties.get("nondeterministic_actions") {
+            markdown.push_str(&format!(" ({})", actions));
+        }
+        markdown
+    }
+
+    /// Generate static/dynamic cross-check lines of the security section
+    fn generate_cross_check_markdown(&self, result: &AnalysisResult) -> String {
+        let properties = &result.metadata.properties;
+        let mut markdown = String::new();
+        for (label, key) in [
+            ("Declared but Not Installed", "declared_not_installed"),
+            ("Installed but Not Declared", "installed_not_declared"),
+            (
+                "Registry Declared but Not Written",
+                "registry_declared_not_written",
+            ),
+            (
+                "Registry Written but Not Declared",
+                "registry_written_not_declared",
+            ),
+        ] {
+            let Some(count) = properties.get(&format!("{}_count", key)) else {
+                continue;
+            };
+            markdown.push_str(&format!("\n- **{}:** {}", label, count));
+            if let Some(entries) = properties.get(key) {
+                markdown.push_str(&format!(" ({})", entries));
+            }
+        }
+        markdown
+    }
+
+    /// Generate claims-vs-behavior line and table of the security section
+    fn generate_discrepancies_markdown(&self, result: &AnalysisResult) -> String {
+        let properties = &result.metadata.properties;
+        let (Some(severity), Some(discrepancies)) = (
+            properties.get("discrepancy_severity"),
+            properties.get("discrepancies"),
+        ) else {
+            return String::new();
+        };
+
+        let count = properties
+            .get("discrepancy_count")
+            .map(String::as_str)
+            .unwrap_or("0");
+        let mut markdown = format!(
+            "\n- **Claims vs. Behavior:** {} discrepancies, highest severity {}\n\n### Claims vs. Behavior\n\n\
+             | Severity | Finding | Evidence |\n|----------|---------|----------|\n",
+            count, severity
+        );
+        for discrepancy in discrepancies.split("; ") {
+            let Some((severity, rest)) = discrepancy.split_once(": ") else {
+                continue;
+            };
+            let (title, evidence) = rest
+                .split_once(" (")
+                .map(|(title, evidence)| (title, evidence.trim_end_matches(')')))
+                .unwrap_or((rest, ""));
+            let icon = match severity {
+                "high" => "🔴",
+                "medium" => "🟡",
+                _ => "🟢",
+            };
+            markdown.push_str(&format!(
+                "| {} {} | {} | {} |\n",
+                icon, severity, title, evidence
+            ));
+        }
+
+        markdown
+    }
+
+    /// Generate capability risk lines and table of the security section
+    fn generate_capability_risk_markdown(&self, result: &AnalysisResult) -> String {
+        let Some(capabilities) = result.metadata.properties.get("msix_capabilities") else {
+            return String::new();
+        };
+        let assessments = crate::analyzers::msix::assess_capabilities(capabilities);
+        let Some(highest) = assessments.first() else {
+            return String::new();
+        };
+
+        let high_risk = assessments
+            .iter()
+            .filter(|assessment| assessment.risk == CapabilityRisk::High)
+            .count();
+        let mut markdown = format!(
+            "\n- **Capability Risk:** {} ({} of {} high risk)\n\n### Package Capabilities\n\n\
+             | Capability | Risk | Explanation |\n|------------|------|-------------|\n",
+            highest.risk,
+            high_risk,
+            assessments.len()
+        );
+        for assessment in &assessments {
+            let icon = match assessment.risk {
+                CapabilityRisk::High => "🔴",
+                CapabilityRisk::Medium => "🟡",
+                CapabilityRisk::Low => "🟢",
+            };
+            markdown.push_str(&format!(
+                "| {} | {} {} | {} |\n",
+                assessment.name, icon, assessment.risk, assessment.explanation
+            ));
+        }
+
+        markdown
+    }
+
     /// Calculate risk level for CI/CD
-    fn calculate_risk_level(&self, result: &AnalysisResult) -> String {
+    pub fn calculate_risk_level(&self, result: &AnalysisResult) -> String {
         let executable_count = result
             .files
             .iter()
@@ -359,9 +1351,38 @@ impl ReportGenerator {
             .filter(|f| f.size > 50 * 1024 * 1024)
             .count();
 
-        if executable_count > 10 || large_files > 5 {
+        let tampered = result
+            .metadata
+            .properties
+            .get("msix_tampered")
+            .is_some_and(|tampered| tampered == "true");
+        let high_risk_capabilities = result
+            .metadata
+            .properties
+            .contains_key("msix_high_risk_capabilities");
+        let discrepancy_severity = result
+            .metadata
+            .properties
+            .get("discrepancy_severity")
+            .map(String::as_str);
+
+        if result
+            .metadata
+            .properties
+            .contains_key("uac_bypass_techniques")
+        {
+            "critical".to_string()
+        } else if tampered
+            || discrepancy_severity == Some("high")
+            || executable_count > 10
+            || large_files > 5
+        {
             "high".to_string()
-        } else if executable_count > 5 || large_files > 2 {
+        } else if high_risk_capabilities
+            || discrepancy_severity == Some("medium")
+            || executable_count > 5
+            || large_files > 2
+        {
             "medium".to_string()
         } else {
             "low".to_string()
@@ -371,14 +1392,14 @@ impl ReportGenerator {
     /// Generate top files section for markdown
     fn generate_top_files_markdown(&self, files: &[crate::core::FileEntry]) -> String {
         let mut sorted_files: Vec<_> = files.iter().collect();
-        sorted_files.sort_by(|a, b| b.size.cmp(&a.size));
+        sorted_files.sort_by_key(|f| std::cmp::Reverse(f.size));
 
         let mut markdown = String::new();
         for (i, file) in sorted_files.iter().take(10).enumerate() {
             markdown.push_str(&format!(
                 "{}. **{}** - {}\n",
                 i + 1,
-                file.path.file_name().unwrap_or_default().to_string_lossy(),
+                crate::utils::entry_file_name(&file.path),
                 crate::utils::format_file_size(file.size)
             ));
         }
@@ -397,9 +1418,13 @@ impl ReportGenerator {
         let mut markdown = String::new();
         for file in executable_files.iter().take(20) {
             markdown.push_str(&format!(
-                "- **{}** - {}\n",
-                file.path.file_name().unwrap_or_default().to_string_lossy(),
-                crate::utils::format_file_size(file.size)
+                "- **{}** - {}{}\n",
+                crate::utils::entry_file_name(&file.path),
+                crate::utils::format_file_size(file.size),
+                file.architecture
+                    .as_deref()
+                    .map(|architecture| format!(" ({})", architecture))
+                    .unwrap_or_default()
             ));
         }
 
@@ -410,53 +1435,59 @@ impl ReportGenerator {
         markdown
     }
 
-    /// Generate registry operations section for markdown
+    /// Generate registry operations section for markdown, grouped by hive
+    /// and functional area
     fn generate_registry_operations_markdown(
         &self,
         operations: &[crate::core::RegistryOperation],
     ) -> String {
-        let mut markdown = String::new();
+        const MAX_AREA_OPERATIONS: usize = 20;
 
-        for (i, op) in operations.iter().take(20).enumerate() {
-            match op {
-                crate::core::RegistryOperation::CreateKey { key_path, .. } => {
-                    markdown.push_str(&format!("{}. **Create Key:** `{}`\n", i + 1, key_path));
+        let groups = RegistryGroups::from_operations(operations);
+        let mut sections = Vec::new();
+        for hive in &groups.hives {
+            let mut section = format!("### {} ({} operations)", hive.hive.name(), hive.count);
+            for area in &hive.areas {
+                section.push_str(&format!(
+                    "\n\n**{}** ({})\n",
+                    area.area.name(),
+                    area.operations.len()
+                ));
+                for (i, &index) in area.operations.iter().take(MAX_AREA_OPERATIONS).enumerate() {
+                    let line = match &operations[index] {
+                        crate::core::RegistryOperation::CreateKey { key_path, .. } => {
+                            format!("**Create Key:** `{}`", key_path)
+                        }
+                        crate::core::RegistryOperation::SetValue {
+                            key_path,
+                            value_name,
+                            ..
+                        } => format!("**Set Value:** `{}\\{}`", key_path, value_name),
+                        crate::core::RegistryOperation::DeleteKey { key_path, .. } => {
+                            format!("**Delete Key:** `{}`", key_path)
+                        }
+                        crate::core::RegistryOperation::DeleteValue {
+                            key_path,
+                            value_name,
+                            ..
+                        } => format!("**Delete Value:** `{}\\{}`", key_path, value_name),
+                    };
+                    section.push_str(&format!("\n{}. {}", i + 1, line));
                 }
-                crate::core::RegistryOperation::SetValue {
-                    key_path,
-                    value_name,
-                    ..
-                } => {
-                    markdown.push_str(&format!(
-                        "{}. **Set Value:** `{}\\{}`\n",
-                        i + 1,
-                        key_path,
-                        value_name
-                    ));
-                }
-                crate::core::RegistryOperation::DeleteKey { key_path, .. } => {
-                    markdown.push_str(&format!("{}. **Delete Key:** `{}`\n", i + 1, key_path));
-                }
-                crate::core::RegistryOperation::DeleteValue {
-                    key_path,
-                    value_name,
-                    ..
-                } => {
-                    markdown.push_str(&format!(
-                        "{}. **Delete Value:** `{}\\{}`\n",
-                        i + 1,
-                        key_path,
-                        value_name
+                if area.operations.len() > MAX_AREA_OPERATIONS {
+                    section.push_str(&format!(
+                        "\n\n*{} more operations in the JSON report.*",
+                        area.operations.len() - MAX_AREA_OPERATIONS
                     ));
                 }
             }
+            sections.push(section);
         }
 
-        if markdown.is_empty() {
-            markdown.push_str("No registry operations found.\n");
+        if sections.is_empty() {
+            return "No registry operations found.\n".to_string();
         }
-
-        markdown
+        sections.join("\n\n") + "\n"
     }
 }
 
@@ -468,7 +1499,7 @@ impl Reporter for ReportGenerator {
     ) -> Result<String> {
         match format {
             ReportFormat::Json => self.generate_json_report(result).await,
-            ReportFormat::Html => self.generate_html_report(result).await,
+            ReportFormat::Html => self.generate_html_report(result, None).await,
             ReportFormat::Markdown => self.generate_markdown_report(result).await,
         }
     }
@@ -479,8 +1510,19 @@ impl Reporter for ReportGenerator {
         format: ReportFormat,
         output_path: &Path,
     ) -> Result<()> {
-        let content = self.generate_report(result, format).await?;
-        tokio::fs::write(output_path, content).await?;
+        let content = match format {
+            ReportFormat::Html if self.options.html_data == HtmlData::External => {
+                let data_path = Self::data_file_path(output_path);
+                let (html, data) = self
+                    .generate_html_report_pair(result, &file_name(&data_path))
+                    .await?;
+                tokio::fs::write(crate::utils::extended_length(&data_path), data).await?;
+                tracing::info!("Report data saved to: {}", data_path.display());
+                html
+            }
+            format => self.generate_report(result, format).await?,
+        };
+        tokio::fs::write(crate::utils::extended_length(output_path), content).await?;
         tracing::info!("Report saved to: {}", output_path.display());
         Ok(())
     }
@@ -491,3 +1533,454 @@ impl Default for ReportGenerator {
         Self::new()
     }
 }
+
+/// File name of a path, for references between report files
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Name the report page after the analyzed installer
+fn with_title(html: &str, name: &str) -> String {
+    const DEFAULT_TITLE: &str = "<title>Installer Analysis Report</title>";
+    if name.is_empty() {
+        return html.to_string();
+    }
+    let title = format!(
+        "<title>{} - Installer Analysis Report</title>",
+        escape_html(name)
+    );
+    html.replacen(DEFAULT_TITLE, &title, 1)
+}
+
+/// Serialize data for embedding in an inline `<script>` element
+///
+/// Installer metadata is attacker-controlled: a product name containing
+/// `</script>` or `<!--` would end the element or change how the browser
+/// parses it. Those characters are written as JSON escapes, which decode to
+/// the same strings. U+2028 and U+2029 are escaped as well, older engines
+/// treat them as line terminators inside string literals.
+fn script_json(value: &serde_json::Value) -> Result<String> {
+    let json = serde_json::to_string(value).map_err(AnalyzerError::SerializationError)?;
+    let mut escaped = String::with_capacity(json.len());
+    for c in json.chars() {
+        match c {
+            '<' => escaped.push_str("\\u003c"),
+            '>' => escaped.push_str("\\u003e"),
+            '&' => escaped.push_str("\\u0026"),
+            '\u{2028}' => escaped.push_str("\\u2028"),
+            '\u{2029}' => escaped.push_str("\\u2029"),
+            _ => escaped.push(c),
+        }
+    }
+    Ok(escaped)
+}
+
+/// Escape text for use in HTML element content and attribute values
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        FileAttributes, FileEntry, InstallerFormat, InstallerMetadata, MetadataSource,
+        RegistryOperation, RegistryValue, RegistryValueType,
+    };
+    use chrono::Utc;
+    use std::path::PathBuf;
+
+    fn result(source: &str, product_name: &str) -> AnalysisResult {
+        AnalysisResult {
+            session_id: uuid::Uuid::new_v4(),
+            source_file_path: Some(PathBuf::from(source)),
+            metadata: InstallerMetadata {
+                format: InstallerFormat::MSI,
+                product_name: Some(product_name.to_string()),
+                created_at: Utc::now(),
+                ..Default::default()
+            },
+            analyzed_at: Utc::now(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_html_title_from_unicode_names() {
+        let generator = ReportGenerator::new();
+
+        let html = generator
+            .generate_report(
+                &result("安装包/微信 🚀 Setup.msi", "微信"),
+                ReportFormat::Html,
+            )
+            .await
+            .unwrap();
+        assert!(html.contains("<title>微信 🚀 Setup.msi - Installer Analysis Report</title>"));
+
+        let html = generator
+            .generate_report(
+                &result("a<b>&\"c\u{202E}.msi", "App\u{202E}\n"),
+                ReportFormat::Html,
+            )
+            .await
+            .unwrap();
+        assert!(
+            html.contains("<title>a&lt;b&gt;&amp;&quot;c.msi - Installer Analysis Report</title>")
+        );
+        let data = generator
+            .create_unified_analysis_data(&result("setup.msi", "App\u{202E}\n"))
+            .unwrap();
+        assert_eq!(data["metadata"]["filename"], "App");
+    }
+
+    /// Result whose metadata tries to break out of the embedded data script
+    #[tokio::test]
+    async fn test_markdown_marks_low_confidence_metadata() {
+        let mut result = result("setup.exe", "App");
+        result.metadata.manufacturer = Some("Contoso".to_string());
+        result.metadata.file_hash = "0".repeat(64);
+        result.metadata = result
+            .metadata
+            .with_provenance(MetadataSource::PeVersionInfo)
+            .with_field_provenance(
+                MetadataField::ProductName,
+                MetadataSource::FilenameHeuristic,
+            );
+
+        let markdown = ReportGenerator::new()
+            .generate_markdown_report(&result)
+            .await
+            .unwrap();
+        assert!(markdown.contains("- **Product Name:** App _(low confidence: file name)_\n"));
+        assert!(markdown.contains("- **Manufacturer:** Contoso\n"));
+
+        let data = ReportGenerator::new()
+            .create_unified_analysis_data(&result)
+            .unwrap();
+        let provenance = &data["metadata"]["provenance"];
+        assert_eq!(provenance["product_name"]["source"], "filename_heuristic");
+        assert_eq!(provenance["manufacturer"]["confidence"], "medium");
+        assert!(provenance.get("product_version").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_reports_show_canonical_vendor() {
+        let mut result = result("chrome_installer.exe", "Google Chrome");
+        result.metadata.file_hash = "0".repeat(64);
+        crate::reporting::VendorKnowledgeBase::curated()
+            .enrich(&mut result)
+            .unwrap();
+
+        let markdown = ReportGenerator::new()
+            .generate_markdown_report(&result)
+            .await
+            .unwrap();
+        assert!(markdown.contains("- **Vendor:** Google LLC (https://www.google.com/chrome)\n"));
+        assert!(markdown.contains("- **Category:** Web Browser\n"));
+
+        let data = ReportGenerator::new()
+            .create_unified_analysis_data(&result)
+            .unwrap();
+        assert_eq!(data["vendor"]["vendor"], "Google LLC");
+        assert_eq!(data["vendor"]["matched_by"], "product_name");
+    }
+
+    #[tokio::test]
+    async fn test_reports_include_audit_trail() {
+        let mut result = result("setup.msi", "App");
+        result.metadata.file_hash = "0".repeat(64);
+        let mut audit = crate::core::AuditTrail {
+            tool: "installer-analyzer".to_string(),
+            tool_version: "1.0.0".to_string(),
+            command_line: vec!["installer-analyzer".to_string(), "analyze".to_string()],
+            config_hash: Some("c".repeat(64)),
+            ..Default::default()
+        };
+        audit.record("analysis_started", "");
+        audit.finish(
+            crate::core::InputDigest {
+                path: "setup.msi".to_string(),
+                size: 2048,
+                sha256: "0".repeat(64),
+            },
+            &["MSI".to_string()],
+        );
+        result.audit = Some(audit);
+
+        let markdown = ReportGenerator::new()
+            .generate_markdown_report(&result)
+            .await
+            .unwrap();
+        assert!(markdown.contains("## Audit Trail\n\n- **Tool:** installer-analyzer 1.0.0\n"));
+        assert!(markdown.contains("- **Analyzers:** MSI 1.0.0\n"));
+        assert!(markdown.contains(" | analysis_completed | setup.msi ("));
+
+        let data = ReportGenerator::new()
+            .create_unified_analysis_data(&result)
+            .unwrap();
+        assert_eq!(data["audit"]["input"]["size"], 2048);
+        assert_eq!(data["audit"]["events"][1]["event"], "analysis_completed");
+    }
+
+    #[tokio::test]
+    async fn test_reports_include_file_previews() {
+        let mut result = result("app.zip", "App");
+        result.metadata.file_hash = "0".repeat(64);
+        result.previews = vec![crate::core::FilePreview {
+            path: "App/App.ini".to_string(),
+            kind: crate::core::PreviewKind::Config,
+            size: 40_000,
+            content: "[Update]\nAutoUpdate=1\n```\n".to_string(),
+            truncated: true,
+        }];
+
+        let markdown = ReportGenerator::new()
+            .generate_markdown_report(&result)
+            .await
+            .unwrap();
+        assert!(markdown.contains(
+            "## File Previews\n\n### `App/App.ini`\n\nConfiguration, 39.06 KB\n\n````text\n[Update]\nAutoUpdate=1\n```\n````"
+        ));
+        assert!(markdown.contains("*Preview truncated"));
+
+        let data = ReportGenerator::new()
+            .create_unified_analysis_data(&result)
+            .unwrap();
+        assert_eq!(data["previews"][0]["kind"], "config");
+        assert_eq!(data["previews"][0]["truncated"], true);
+    }
+
+    #[tokio::test]
+    async fn test_registry_operations_grouped_by_hive_and_area() {
+        let mut result = result("setup.exe", "App");
+        result.metadata.file_hash = "0".repeat(64);
+        let service = "HKLM\\SYSTEM\\CurrentControlSet\\Services\\AppSvc";
+        let classes = (0..25).map(|i| format!("HKCR\\CLSID\\{{{}}}", i));
+        for key_path in classes.chain([service.to_string()]) {
+            result
+                .registry_operations
+                .push(RegistryOperation::CreateKey {
+                    key_path,
+                    timestamp: Utc::now(),
+                    process_id: None,
+                });
+        }
+
+        let markdown = ReportGenerator::new()
+            .generate_markdown_report(&result)
+            .await
+            .unwrap();
+        assert!(markdown.contains(
+            "### HKEY_LOCAL_MACHINE (1 operations)\n\n**Services** (1)\n\n1. **Create Key:** `HKLM\\SYSTEM\\CurrentControlSet\\Services\\AppSvc`"
+        ));
+        assert!(markdown
+            .contains("### HKEY_CLASSES_ROOT (25 operations)\n\n**COM registrations** (25)"));
+        assert!(markdown.contains("*5 more operations in the JSON report.*"));
+
+        let data = ReportGenerator::new()
+            .create_unified_analysis_data(&result)
+            .unwrap();
+        let hives = &data["registry_groups"]["hives"];
+        assert_eq!(hives[0]["hive"], "local_machine");
+        assert_eq!(hives[0]["areas"][0]["operations"][0], 25);
+        assert_eq!(hives[1]["count"], 25);
+    }
+
+    fn hostile_result() -> AnalysisResult {
+        let mut result = result("setup.msi", "</script><script>alert(1)</script>");
+        result.metadata.manufacturer = Some("<!--<script>".to_string());
+        result.files.push(FileEntry {
+            path: PathBuf::from("bin/</SCRIPT>\u{2028}app.exe"),
+            target_path: None,
+            size: 1,
+            hash: None,
+            attributes: FileAttributes::default(),
+            compression: None,
+            is_speculative: false,
+            architecture: None,
+            modified: None,
+            source: None,
+        });
+        result
+            .registry_operations
+            .push(RegistryOperation::SetValue {
+                key_path: "HKLM\\Software\\App".to_string(),
+                value_name: "Run\u{2029}".to_string(),
+                value_type: RegistryValueType::String,
+                value_data: RegistryValue::String(
+                    "\"];</script><img src=x onerror=alert(1)>".to_string(),
+                ),
+                timestamp: Utc::now(),
+                process_id: None,
+            });
+        result
+    }
+
+    /// Data embedded in the report after `prefix`, parsed back from JSON
+    fn embedded_data(html: &str, prefix: &str) -> serde_json::Value {
+        let start = html.find(prefix).unwrap() + prefix.len();
+        let end = start + html[start..].find("</script>").unwrap();
+        let json = html[start..end].trim_end().trim_end_matches(';');
+        let json = json.split("; window.").next().unwrap();
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_html_report_escapes_hostile_metadata() {
+        let generator = ReportGenerator::new();
+        let result = hostile_result();
+        let template = get_report_template();
+
+        let html = generator
+            .generate_report(&result, ReportFormat::Html)
+            .await
+            .unwrap();
+        let script_start = html.find("<script>window.ANALYSIS_DATA").unwrap();
+        let blob = &html[script_start + "<script>".len()..];
+        let blob = &blob[..blob.find("</script>").unwrap()];
+        for marker in ["<", ">", "<!--", "\u{2028}", "\u{2029}"] {
+            assert!(!blob.contains(marker), "unescaped {:?}", marker);
+        }
+        assert_eq!(
+            html.matches("</script>").count(),
+            template.matches("</script>").count() + 1
+        );
+
+        // The escapes decode to the original strings
+        let data = embedded_data(&html, "window.ANALYSIS_DATA = ");
+        assert_eq!(
+            data["metadata"]["filename"],
+            "</script><script>alert(1)</script>"
+        );
+        assert_eq!(data["metadata"]["publisher"], "<!--<script>");
+        assert_eq!(
+            data["registry_operations"][0]["value"],
+            "\"];</script><img src=x onerror=alert(1)>"
+        );
+        assert!(data["files"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|file| file["path"] == "bin/</SCRIPT>\u{2028}app.exe"));
+    }
+
+    #[tokio::test]
+    async fn test_combined_html_report_escapes_hostile_metadata() {
+        let generator = ReportGenerator::new();
+        let results = [hostile_result(), result("app.msi", "App & Tools")];
+
+        let html = generator
+            .generate_combined_report(&results, ReportFormat::Html)
+            .await
+            .unwrap();
+        assert_eq!(
+            html.matches("</script>").count(),
+            get_report_template().matches("</script>").count() + 1
+        );
+
+        let artifacts = embedded_data(&html, "window.ANALYSIS_ARTIFACTS = ");
+        assert_eq!(
+            artifacts[0]["metadata"]["filename"],
+            "</script><script>alert(1)</script>"
+        );
+        assert_eq!(artifacts[1]["metadata"]["filename"], "App & Tools");
+    }
+
+    /// Result with a hashed file, for the size reduction options
+    fn hashed_result() -> AnalysisResult {
+        let mut result = hostile_result();
+        result.files[0].hash = Some("ab".repeat(32));
+        result
+    }
+
+    #[tokio::test]
+    async fn test_compressed_html_report_carries_json_data() {
+        let result = hashed_result();
+        let json: serde_json::Value = serde_json::from_str(
+            &ReportGenerator::new()
+                .generate_report(&result, ReportFormat::Json)
+                .await
+                .unwrap(),
+        )
+        .unwrap();
+
+        let generator = ReportGenerator::with_options(ReportOptions {
+            html_data: HtmlData::Compressed,
+            ..ReportOptions::default()
+        });
+        let html = generator
+            .generate_report(&result, ReportFormat::Html)
+            .await
+            .unwrap();
+        assert!(!html.contains("window.ANALYSIS_DATA ="));
+        let source = embedded_data(&html, "window.ANALYSIS_SOURCE = ");
+        assert_eq!(source["kind"], "gzip");
+        assert_eq!(source["combined"], false);
+
+        let compressed = base64::engine::general_purpose::STANDARD
+            .decode(source["data"].as_str().unwrap())
+            .unwrap();
+        let data: serde_json::Value =
+            serde_json::from_reader(flate2::read::GzDecoder::new(compressed.as_slice())).unwrap();
+        assert_eq!(data, json);
+    }
+
+    #[tokio::test]
+    async fn test_external_html_report_data_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let report_path = dir.path().join("report.html");
+        let result = hashed_result();
+        let generator = ReportGenerator::with_options(ReportOptions {
+            html_data: HtmlData::External,
+            omit_file_hashes: true,
+        });
+
+        // Printed reports have no place for the data
+        assert!(generator
+            .generate_report(&result, ReportFormat::Html)
+            .await
+            .is_err());
+
+        generator
+            .save_report(&result, ReportFormat::Html, &report_path)
+            .await
+            .unwrap();
+        let html = std::fs::read_to_string(&report_path).unwrap();
+        assert!(!html.contains("window.ANALYSIS_DATA ="));
+        let source = embedded_data(&html, "window.ANALYSIS_SOURCE = ");
+        assert_eq!(source["kind"], "external");
+        assert_eq!(source["url"], "report.data.json");
+
+        let data_path = ReportGenerator::data_file_path(&report_path);
+        assert_eq!(data_path, dir.path().join("report.data.json"));
+        let data: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(data_path).unwrap()).unwrap();
+        let json: serde_json::Value = serde_json::from_str(
+            &generator
+                .generate_report(&result, ReportFormat::Json)
+                .await
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(data, json);
+        assert!(data["files"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|file| file["hash"].is_null()));
+    }
+}
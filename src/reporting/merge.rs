@@ -0,0 +1,532 @@
+//! Merging of static and dynamic analysis results
+//!
+//! Static analysis reports what an installer declares: the files it carries
+//! and the registry entries it writes. A sandbox run reports what it actually
+//! did. Merging both gives one report in which the static claims are
+//! cross-checked against the observed behavior, e.g. files that are declared
+//! but were never installed, or installed files that no table declares.
+
+use crate::core::{
+    AnalysisResult, FileAttributes, FileEntry, FileOperation, InstallerFormat, InstallerMetadata,
+    RegistryOperation, RegistryValue, RegistryValueType, SupportLevel,
+};
+use crate::reporting::discrepancies;
+use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+/// Number of paths listed per finding in analysis properties
+const MAX_LISTED: usize = 15;
+
+/// Directories whose files are scratch data of the installer run
+const TRANSIENT_DIRS: &[&str] = &[
+    "/appdata/local/temp/",
+    "/windows/temp/",
+    "/windows/installer/",
+    "/windows/prefetch/",
+];
+
+/// Registry root names and their abbreviations
+const REGISTRY_ROOTS: &[(&str, &str)] = &[
+    ("hkey_local_machine", "hklm"),
+    ("hkey_current_user", "hkcu"),
+    ("hkey_classes_root", "hkcr"),
+    ("hkey_users", "hku"),
+];
+
+/// Static claims that the sandbox run confirmed or contradicted
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CrossCheck {
+    /// Packaged files no observed file operation installed
+    pub declared_not_installed: Vec<String>,
+    /// Files the installer wrote that are not packaged
+    pub installed_not_declared: Vec<String>,
+    /// Registry keys and values the installer declares but did not write
+    pub registry_declared_not_written: Vec<String>,
+    /// Registry keys and values the installer wrote without declaring them
+    pub registry_written_not_declared: Vec<String>,
+}
+
+impl CrossCheck {
+    /// Compare the claims of a static result with the behavior of a dynamic one
+    ///
+    /// Guessed file entries and heuristic registry operations are no claims
+    /// and are left out, as are files written to temporary directories.
+    pub fn compare(static_result: &AnalysisResult, dynamic_result: &AnalysisResult) -> Self {
+        let declared: Vec<&FileEntry> = static_result
+            .files
+            .iter()
+            .filter(|file| !file.is_speculative)
+            .collect();
+        let installed: Vec<String> = dynamic_result
+            .file_operations
+            .iter()
+            .filter_map(|operation| match operation {
+                FileOperation::Create { path, .. } | FileOperation::Write { path, .. } => {
+                    Some(path)
+                }
+                FileOperation::Move { to_path, .. } => Some(to_path),
+                _ => None,
+            })
+            .map(|path| normalize_path(&path.to_string_lossy()))
+            .filter(|path| !is_transient(path))
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+
+        let mut declared_not_installed: Vec<String> = declared
+            .iter()
+            .filter(|file| !installed.iter().any(|path| installs(file, path)))
+            .map(|file| file.path.to_string_lossy().replace('\\', "/"))
+            .collect();
+        let mut installed_not_declared: Vec<String> = installed
+            .iter()
+            .filter(|path| !declared.iter().any(|file| installs(file, path)))
+            .cloned()
+            .collect();
+
+        let heuristic_registry = static_result
+            .analyzer_capabilities
+            .as_ref()
+            .is_some_and(|caps| caps.registry_operations == SupportLevel::Heuristic);
+        let declared_keys = if heuristic_registry {
+            HashSet::new()
+        } else {
+            registry_entries(&static_result.registry_operations)
+        };
+        let written_keys = registry_entries(&dynamic_result.registry_operations);
+        let mut registry_declared_not_written: Vec<String> =
+            declared_keys.difference(&written_keys).cloned().collect();
+        let mut registry_written_not_declared: Vec<String> =
+            written_keys.difference(&declared_keys).cloned().collect();
+
+        declared_not_installed.sort();
+        installed_not_declared.sort();
+        registry_declared_not_written.sort();
+        registry_written_not_declared.sort();
+        Self {
+            declared_not_installed,
+            installed_not_declared,
+            registry_declared_not_written,
+            registry_written_not_declared,
+        }
+    }
+
+    /// Record the findings as analysis properties
+    pub fn insert_properties(&self, result: &mut AnalysisResult) {
+        let properties = &mut result.metadata.properties;
+        let findings = [
+            ("declared_not_installed", &self.declared_not_installed),
+            ("installed_not_declared", &self.installed_not_declared),
+            (
+                "registry_declared_not_written",
+                &self.registry_declared_not_written,
+            ),
+            (
+                "registry_written_not_declared",
+                &self.registry_written_not_declared,
+            ),
+        ];
+        for (name, entries) in findings {
+            properties.insert(format!("{}_count", name), entries.len().to_string());
+            if !entries.is_empty() {
+                let listed: Vec<&str> = entries
+                    .iter()
+                    .take(MAX_LISTED)
+                    .map(String::as_str)
+                    .collect();
+                properties.insert(name.to_string(), listed.join(", "));
+            }
+        }
+    }
+}
+
+/// Merge a static and a dynamic result of the same installer into one
+///
+/// Metadata, packaged files and other static findings come from the static
+/// result; file, process and network operations come from the sandbox run.
+/// Registry operations of both are kept, observed ones after the declared
+/// ones. Properties of the dynamic result take precedence.
+pub fn merge(static_result: &AnalysisResult, dynamic_result: &AnalysisResult) -> AnalysisResult {
+    let mut merged = static_result.clone();
+
+    let declared_keys = registry_entries(&static_result.registry_operations);
+    merged.registry_operations.extend(
+        dynamic_result
+            .registry_operations
+            .iter()
+            .filter(|operation| {
+                registry_entry(operation).is_none_or(|entry| !declared_keys.contains(&entry))
+            })
+            .cloned(),
+    );
+    merged.file_operations = dynamic_result.file_operations.clone();
+    merged.process_operations = dynamic_result.process_operations.clone();
+    merged.network_operations = dynamic_result.network_operations.clone();
+    merged.wmi_operations = dynamic_result.wmi_operations.clone();
+    merged.powershell_script_blocks = dynamic_result.powershell_script_blocks.clone();
+    merged.dynamic_analysis = true;
+    merged.analyzed_at = dynamic_result.analyzed_at;
+    merged.analysis_duration = static_result.analysis_duration + dynamic_result.analysis_duration;
+    if merged.source_file_path.is_none() {
+        merged.source_file_path = dynamic_result.source_file_path.clone();
+    }
+    if merged.dependencies.is_none() {
+        merged.dependencies = dynamic_result.dependencies.clone();
+    }
+    if merged.strings.is_none() {
+        merged.strings = dynamic_result.strings.clone();
+    }
+    merged.resource_usage = dynamic_result.resource_usage.clone();
+    merged.metadata.properties.extend(
+        dynamic_result
+            .metadata
+            .properties
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone())),
+    );
+
+    CrossCheck::compare(static_result, dynamic_result).insert_properties(&mut merged);
+    let findings = discrepancies::find_discrepancies(static_result, dynamic_result);
+    discrepancies::insert_properties(&findings, &mut merged);
+    merged
+}
+
+/// Rebuild an analysis result from a JSON report
+///
+/// JSON reports flatten some details: registry values come back as strings
+/// and files lose their modification times. Both are irrelevant for merging.
+pub fn result_from_report(report: &serde_json::Value) -> Option<AnalysisResult> {
+    let metadata = report.get("metadata")?;
+    let text = |value: &serde_json::Value, key: &str| {
+        value
+            .get(key)
+            .and_then(|v| v.as_str())
+            .filter(|s| *s != "N/A")
+            .map(str::to_string)
+    };
+    let array = |key: &str| {
+        report
+            .get(key)
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default()
+    };
+
+    let files = array("files")
+        .iter()
+        .filter(|file| {
+            !file
+                .get("is_directory")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false)
+        })
+        .filter_map(|file| {
+            Some(FileEntry {
+                path: PathBuf::from(file.get("path")?.as_str()?),
+                target_path: text(file, "target_path").map(PathBuf::from),
+                size: file.get("size").and_then(|v| v.as_u64()).unwrap_or(0),
+                hash: text(file, "hash"),
+                attributes: file
+                    .get("attributes")
+                    .and_then(|v| serde_json::from_value(v.clone()).ok())
+                    .unwrap_or_else(FileAttributes::default),
+                compression: text(file, "compression"),
+                is_speculative: file
+                    .get("is_speculative")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false),
+                architecture: text(file, "architecture"),
+                modified: None,
+                source: None,
+            })
+        })
+        .collect();
+
+    let analyzed_at: DateTime<Utc> = report
+        .get("analyzed_at")
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+        .unwrap_or_else(Utc::now);
+    let registry_operations = array("registry_operations")
+        .iter()
+        .filter_map(|operation| registry_operation_from_report(operation, analyzed_at))
+        .collect();
+
+    Some(AnalysisResult {
+        session_id: report
+            .get("session_id")
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+            .unwrap_or_else(uuid::Uuid::new_v4),
+        source_file_path: text(metadata, "original_filename").map(PathBuf::from),
+        metadata: InstallerMetadata {
+            format: InstallerFormat::from_name(&text(metadata, "format")?),
+            product_name: text(metadata, "filename").filter(|name| name != "Unknown Package"),
+            product_version: text(metadata, "version"),
+            manufacturer: text(metadata, "publisher"),
+            file_size: metadata
+                .get("file_size")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(0),
+            file_hash: text(metadata, "file_hash").unwrap_or_default(),
+            created_at: analyzed_at,
+            properties: metadata
+                .get("properties")
+                .and_then(|v| serde_json::from_value(v.clone()).ok())
+                .unwrap_or_default(),
+            provenance: metadata
+                .get("provenance")
+                .and_then(|v| serde_json::from_value(v.clone()).ok())
+                .unwrap_or_default(),
+            format_details: metadata
+                .get("format_details")
+                .and_then(|v| serde_json::from_value(v.clone()).ok()),
+        },
+        files,
+        registry_operations,
+        file_operations: field(report, "file_operations").unwrap_or_default(),
+        process_operations: field(report, "process_operations").unwrap_or_default(),
+        network_operations: field(report, "network_operations").unwrap_or_default(),
+        wmi_operations: field(report, "wmi_operations").unwrap_or_default(),
+        powershell_script_blocks: field(report, "powershell_script_blocks").unwrap_or_default(),
+        analyzed_at,
+        analysis_duration: report
+            .get("analysis_duration")
+            .and_then(|v| v.as_f64())
+            .map(std::time::Duration::from_secs_f64)
+            .unwrap_or_default(),
+        dynamic_analysis: report
+            .get("dynamic_analysis")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
+        analyzer_capabilities: None,
+        dependencies: field(report, "dependencies"),
+        strings: field(report, "strings"),
+        resource_usage: field(report, "resource_usage"),
+        audit: field(report, "audit"),
+        previews: field(report, "previews").unwrap_or_default(),
+    })
+}
+
+/// Deserialize a field of a JSON report
+fn field<T: serde::de::DeserializeOwned>(report: &serde_json::Value, key: &str) -> Option<T> {
+    serde_json::from_value(report.get(key)?.clone()).ok()
+}
+
+/// Registry operation of a JSON report, see `create_unified_analysis_data`
+fn registry_operation_from_report(
+    operation: &serde_json::Value,
+    timestamp: DateTime<Utc>,
+) -> Option<RegistryOperation> {
+    let key = operation.get("key")?.as_str()?.to_string();
+    let process_id = operation
+        .get("process_id")
+        .and_then(|v| v.as_u64())
+        .and_then(|pid| u32::try_from(pid).ok());
+    let split = |key: &str| {
+        key.rsplit_once('\\')
+            .map(|(path, name)| (path.to_string(), name.to_string()))
+            .unwrap_or_else(|| (key.to_string(), String::new()))
+    };
+    Some(match operation.get("operation")?.as_str()? {
+        "CREATE" => RegistryOperation::CreateKey {
+            key_path: key,
+            timestamp,
+            process_id,
+        },
+        "SET" => {
+            let (key_path, value_name) = split(&key);
+            let value = operation
+                .get("value")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default();
+            RegistryOperation::SetValue {
+                key_path,
+                value_name,
+                value_type: RegistryValueType::String,
+                value_data: RegistryValue::String(value.to_string()),
+                timestamp,
+                process_id,
+            }
+        }
+        "DELETE" => RegistryOperation::DeleteKey {
+            key_path: key,
+            timestamp,
+            process_id,
+        },
+        "DELETE_VALUE" => {
+            let (key_path, value_name) = split(&key);
+            RegistryOperation::DeleteValue {
+                key_path,
+                value_name,
+                timestamp,
+                process_id,
+            }
+        }
+        _ => return None,
+    })
+}
+
+/// Whether an observed path is where a packaged file gets installed
+fn installs(file: &FileEntry, installed: &str) -> bool {
+    if let Some(target) = &file.target_path {
+        if normalize_path(&target.to_string_lossy()) == installed {
+            return true;
+        }
+    }
+    let relative = normalize_path(&file.path.to_string_lossy());
+    !relative.is_empty()
+        && (installed == relative || installed.ends_with(&format!("/{}", relative)))
+}
+
+/// Lowercase path with forward slashes, for comparisons
+fn normalize_path(path: &str) -> String {
+    path.replace('\\', "/")
+        .trim_start_matches("./")
+        .to_lowercase()
+}
+
+fn is_transient(path: &str) -> bool {
+    TRANSIENT_DIRS.iter().any(|dir| path.contains(dir))
+}
+
+/// Keys and values created or set, normalized for comparison
+fn registry_entries(operations: &[RegistryOperation]) -> HashSet<String> {
+    operations.iter().filter_map(registry_entry).collect()
+}
+
+fn registry_entry(operation: &RegistryOperation) -> Option<String> {
+    let entry = match operation {
+        RegistryOperation::CreateKey { key_path, .. } => key_path.clone(),
+        RegistryOperation::SetValue {
+            key_path,
+            value_name,
+            ..
+        } => format!("{}\\{}", key_path, value_name),
+        _ => return None,
+    };
+    let entry = entry.to_lowercase();
+    let entry = REGISTRY_ROOTS
+        .iter()
+        .find_map(|(long, short)| {
+            entry
+                .strip_prefix(long)
+                .map(|rest| format!("{}{}", short, rest))
+        })
+        .unwrap_or(entry);
+    Some(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{FormatDetails, MsiDetails};
+    use crate::reporting::ReportGenerator;
+
+    fn result(files: &[&str], installed: &[&str], dynamic: bool) -> AnalysisResult {
+        let now = Utc::now();
+        AnalysisResult {
+            session_id: uuid::Uuid::new_v4(),
+            source_file_path: Some(PathBuf::from("setup.msi")),
+            metadata: InstallerMetadata {
+                format: InstallerFormat::MSI,
+                product_name: Some("App".to_string()),
+                product_version: Some("1.0".to_string()),
+                file_hash: "0".repeat(64),
+                created_at: now,
+                ..Default::default()
+            },
+            files: files
+                .iter()
+                .map(|path| FileEntry {
+                    path: PathBuf::from(path),
+                    target_path: None,
+                    size: 1,
+                    hash: None,
+                    attributes: FileAttributes::default(),
+                    compression: None,
+                    is_speculative: false,
+                    architecture: None,
+                    modified: None,
+                    source: None,
+                })
+                .collect(),
+            registry_operations: vec![RegistryOperation::CreateKey {
+                key_path: if dynamic {
+                    "HKEY_LOCAL_MACHINE\\SOFTWARE\\App".to_string()
+                } else {
+                    "HKLM\\SOFTWARE\\App".to_string()
+                },
+                timestamp: now,
+                process_id: None,
+            }],
+            file_operations: installed
+                .iter()
+                .map(|path| FileOperation::Create {
+                    path: PathBuf::from(path),
+                    size: 1,
+                    timestamp: now,
+                    process_id: None,
+                })
+                .collect(),
+            analyzed_at: now,
+            analysis_duration: std::time::Duration::from_secs(1),
+            dynamic_analysis: dynamic,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_merge_static_and_dynamic() {
+        use crate::reporting::{ReportFormat, Reporter};
+
+        let static_result = result(&["App/app.exe", "App/readme.txt"], &[], false);
+        let dynamic_result = result(
+            &[],
+            &[
+                "C:\\Program Files\\App\\app.exe",
+                "C:\\Program Files\\App\\updater.exe",
+                "C:\\Users\\me\\AppData\\Local\\Temp\\MSI1234.tmp",
+            ],
+            true,
+        );
+
+        let merged = merge(&static_result, &dynamic_result);
+        assert!(merged.dynamic_analysis);
+        assert_eq!(merged.files.len(), 2);
+        assert_eq!(merged.file_operations.len(), 3);
+        assert_eq!(merged.registry_operations.len(), 1);
+        let properties = &merged.metadata.properties;
+        assert_eq!(properties["declared_not_installed"], "App/readme.txt");
+        assert_eq!(
+            properties["installed_not_declared"],
+            "c:/program files/app/updater.exe"
+        );
+        assert_eq!(properties["registry_declared_not_written_count"], "0");
+        assert_eq!(properties["registry_written_not_declared_count"], "0");
+
+        let mut dynamic_result = dynamic_result;
+        dynamic_result.metadata.format_details = Some(FormatDetails::Msi(MsiDetails {
+            product_code: Some("{11111111-2222-3333-4444-555555555555}".to_string()),
+            reboot_actions: vec!["ScheduleReboot".to_string()],
+            ..Default::default()
+        }));
+        let json = ReportGenerator::new()
+            .generate_report(&dynamic_result, ReportFormat::Json)
+            .await
+            .unwrap();
+        let report: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let reloaded = result_from_report(&report).unwrap();
+        assert!(reloaded.dynamic_analysis);
+        assert_eq!(reloaded.metadata.format, InstallerFormat::MSI);
+        assert_eq!(reloaded.file_operations.len(), 3);
+        assert_eq!(
+            reloaded.metadata.format_details,
+            dynamic_result.metadata.format_details
+        );
+        assert_eq!(report["metadata"]["format_details"]["kind"], "msi");
+        assert_eq!(
+            CrossCheck::compare(&static_result, &reloaded),
+            CrossCheck::compare(&static_result, &dynamic_result)
+        );
+    }
+}
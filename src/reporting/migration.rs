@@ -0,0 +1,503 @@
+//! Upgrading JSON reports of earlier versions to the current schema
+//!
+//! Reports carry a `schema_version`; reports without one predate versioning
+//! and are version 1. Each upgrade step keeps everything the old report
+//! recorded, fills fields added since with their defaults and recomputes
+//! sections derived from the recorded data, so archived analyses can be
+//! queried, merged and compared like fresh ones.
+
+use crate::analyzers::requirements::SystemRequirements;
+use crate::core::{AnalyzerError, Result};
+use crate::reporting::correlation::ProductIdentity;
+use crate::reporting::{
+    merge, AttackSummary, ProcessAttribution, RepackagingAdvice, VendorKnowledgeBase,
+};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+/// Schema version of the JSON reports written by this version
+pub const REPORT_SCHEMA_VERSION: u64 = 10;
+
+/// Outcome of upgrading a report
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReportUpgrade {
+    /// Schema version the report had
+    pub from_version: u64,
+    /// Schema version the report has now
+    pub to_version: u64,
+    /// Human readable description of each change
+    pub changes: Vec<String>,
+}
+
+impl ReportUpgrade {
+    /// Whether the report was already current
+    pub fn is_current(&self) -> bool {
+        self.from_version == self.to_version
+    }
+}
+
+/// Schema version of a single or combined report
+pub fn schema_version(report: &Value) -> u64 {
+    report
+        .get("schema_version")
+        .and_then(Value::as_u64)
+        .unwrap_or(1)
+}
+
+/// Upgrade a single or combined JSON report in place
+///
+/// Fails for documents that are not analysis reports and for reports of a
+/// newer schema than this version writes.
+pub fn upgrade(report: &mut Value) -> Result<ReportUpgrade> {
+    let from_version = schema_version(report);
+    if from_version > REPORT_SCHEMA_VERSION {
+        return Err(AnalyzerError::invalid_format(format!(
+            "Report schema version {} is newer than the supported version {}",
+            from_version, REPORT_SCHEMA_VERSION
+        )));
+    }
+
+    let mut changes = Vec::new();
+    match report.get_mut("artifacts").and_then(Value::as_array_mut) {
+        Some(artifacts) => {
+            for (index, artifact) in artifacts.iter_mut().enumerate() {
+                for change in upgrade_analysis(artifact)? {
+                    changes.push(format!("artifacts[{}]: {}", index, change));
+                }
+            }
+        }
+        None => changes = upgrade_analysis(report)?,
+    }
+    if from_version < REPORT_SCHEMA_VERSION {
+        report["schema_version"] = json!(REPORT_SCHEMA_VERSION);
+    }
+
+    Ok(ReportUpgrade {
+        from_version,
+        to_version: REPORT_SCHEMA_VERSION,
+        changes,
+    })
+}
+
+/// Upgrade the report of one analyzed artifact step by step
+fn upgrade_analysis(report: &mut Value) -> Result<Vec<String>> {
+    if !report.get("metadata").is_some_and(Value::is_object) {
+        return Err(AnalyzerError::invalid_format(
+            "Document is not a JSON analysis report",
+        ));
+    }
+
+    let mut changes = Vec::new();
+    if schema_version(report) < 2 {
+        upgrade_v1(report, &mut changes)?;
+        report["schema_version"] = json!(2);
+    }
+    if schema_version(report) < 3 {
+        upgrade_v2(report, &mut changes);
+        report["schema_version"] = json!(3);
+    }
+    if schema_version(report) < 4 {
+        upgrade_v3(report, &mut changes);
+        report["schema_version"] = json!(4);
+    }
+    if schema_version(report) < 5 {
+        upgrade_v4(report, &mut changes)?;
+        report["schema_version"] = json!(5);
+    }
+    if schema_version(report) < 6 {
+        upgrade_v5(report, &mut changes)?;
+        report["schema_version"] = json!(6);
+    }
+    if schema_version(report) < 7 {
+        upgrade_v6(report, &mut changes);
+        report["schema_version"] = json!(7);
+    }
+    if schema_version(report) < 8 {
+        upgrade_v7(report, &mut changes)?;
+        report["schema_version"] = json!(8);
+    }
+    if schema_version(report) < 9 {
+        upgrade_v8(report, &mut changes);
+        report["schema_version"] = json!(9);
+    }
+    if schema_version(report) < 10 {
+        upgrade_v9(report, &mut changes);
+        report["schema_version"] = json!(10);
+    }
+    Ok(changes)
+}
+
+/// Version 1: reports written before schema versioning
+fn upgrade_v1(report: &mut Value, changes: &mut Vec<String>) -> Result<()> {
+    let metadata = report["metadata"]
+        .as_object_mut()
+        .expect("metadata checked by caller");
+
+    // Formats used to be written with their Debug representation
+    if let Some(format) = metadata.get("format").and_then(Value::as_str) {
+        if let Some(name) = format
+            .strip_prefix("Other(\"")
+            .and_then(|rest| rest.strip_suffix("\")"))
+        {
+            changes.push(format!("metadata.format: renamed {} to {}", format, name));
+            metadata.insert("format".to_string(), json!(name));
+        }
+    }
+    fill(
+        metadata,
+        "metadata.",
+        "format_details",
+        Value::Null,
+        changes,
+    );
+
+    if let Some(files) = report.get_mut("files").and_then(Value::as_array_mut) {
+        let mut filled = 0;
+        for file in files.iter_mut().filter_map(Value::as_object_mut) {
+            if file.get("is_directory").and_then(Value::as_bool) == Some(true) {
+                continue;
+            }
+            let before = file.len();
+            file.entry("is_speculative").or_insert(json!(false));
+            file.entry("architecture").or_insert(Value::Null);
+            file.entry("modified").or_insert(Value::Null);
+            filled += usize::from(file.len() > before);
+        }
+        if filled > 0 {
+            changes.push(format!(
+                "files: added is_speculative, architecture and modified to {} entries",
+                filled
+            ));
+        }
+    }
+
+    if let Some(operations) = report
+        .get_mut("registry_operations")
+        .and_then(Value::as_array_mut)
+    {
+        let mut filled = 0;
+        for operation in operations.iter_mut().filter_map(Value::as_object_mut) {
+            if !operation.contains_key("process_id") {
+                operation.insert("process_id".to_string(), Value::Null);
+                filled += 1;
+            }
+        }
+        if filled > 0 {
+            changes.push(format!(
+                "registry_operations: added process_id to {} entries",
+                filled
+            ));
+        }
+    }
+
+    // Sections derived from data the report already records
+    let result = merge::result_from_report(report)
+        .ok_or_else(|| AnalyzerError::invalid_format("Document is not a JSON analysis report"))?;
+    let properties: HashMap<String, String> = report["metadata"]
+        .get("properties")
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+        .unwrap_or_default();
+    let derived = [
+        ("analyzer_capabilities", Value::Null),
+        ("dependencies", Value::Null),
+        ("strings", Value::Null),
+        (
+            "product_identity",
+            serde_json::to_value(ProductIdentity::from_result(&result))?,
+        ),
+        (
+            "requirements",
+            serde_json::to_value(SystemRequirements::from_properties(&properties))?,
+        ),
+        (
+            "process_attribution",
+            serde_json::to_value(ProcessAttribution::from_result(&result))?,
+        ),
+    ];
+    let report = report.as_object_mut().expect("metadata checked by caller");
+    for (key, value) in derived {
+        fill(report, "", key, value, changes);
+    }
+
+    Ok(())
+}
+
+/// Version 2: reports without resource usage of sandbox runs
+fn upgrade_v2(report: &mut Value, changes: &mut Vec<String>) {
+    let report = report.as_object_mut().expect("metadata checked by caller");
+    fill(report, "", "resource_usage", Value::Null, changes);
+}
+
+/// Version 3: reports without WMI and PowerShell activity
+fn upgrade_v3(report: &mut Value, changes: &mut Vec<String>) {
+    let report = report.as_object_mut().expect("metadata checked by caller");
+    fill(report, "", "wmi_operations", json!([]), changes);
+    fill(report, "", "powershell_script_blocks", json!([]), changes);
+    if let Some(summary) = report.get_mut("summary").and_then(Value::as_object_mut) {
+        fill(summary, "summary.", "wmi_operations", json!(0), changes);
+        fill(
+            summary,
+            "summary.",
+            "powershell_script_blocks",
+            json!(0),
+            changes,
+        );
+    }
+    if let Some(attribution) = report
+        .get_mut("process_attribution")
+        .and_then(Value::as_object_mut)
+    {
+        fill(
+            attribution,
+            "process_attribution.",
+            "wmi_processes",
+            json!([]),
+            changes,
+        );
+    }
+}
+
+/// Version 4: reports without the ATT&CK mapping of their findings
+fn upgrade_v4(report: &mut Value, changes: &mut Vec<String>) -> Result<()> {
+    let result = merge::result_from_report(report)
+        .ok_or_else(|| AnalyzerError::invalid_format("Document is not a JSON analysis report"))?;
+    let attack = serde_json::to_value(AttackSummary::from_result(&result))?;
+    let report = report.as_object_mut().expect("metadata checked by caller");
+    fill(report, "", "attack", attack, changes);
+    Ok(())
+}
+
+/// Version 5: reports without repackaging advice
+fn upgrade_v5(report: &mut Value, changes: &mut Vec<String>) -> Result<()> {
+    let result = merge::result_from_report(report)
+        .ok_or_else(|| AnalyzerError::invalid_format("Document is not a JSON analysis report"))?;
+    let advice = serde_json::to_value(RepackagingAdvice::from_result(&result))?;
+    let report = report.as_object_mut().expect("metadata checked by caller");
+    fill(report, "", "repackaging", advice, changes);
+    Ok(())
+}
+
+/// Version 6: reports without metadata provenance
+///
+/// The sources of the recorded values are unknown, so no field gets one.
+fn upgrade_v6(report: &mut Value, changes: &mut Vec<String>) {
+    let metadata = report["metadata"]
+        .as_object_mut()
+        .expect("metadata checked by caller");
+    fill(metadata, "metadata.", "provenance", json!({}), changes);
+}
+
+/// Version 7: reports without the canonical vendor
+///
+/// The vendor is looked up in the curated knowledge base.
+fn upgrade_v7(report: &mut Value, changes: &mut Vec<String>) -> Result<()> {
+    let result = merge::result_from_report(report)
+        .ok_or_else(|| AnalyzerError::invalid_format("Document is not a JSON analysis report"))?;
+    let vendor = serde_json::to_value(
+        VendorKnowledgeBase::curated().lookup(&ProductIdentity::from_result(&result)),
+    )?;
+    let report = report.as_object_mut().expect("metadata checked by caller");
+    fill(report, "", "vendor", vendor, changes);
+    Ok(())
+}
+
+/// Version 8: reports without an audit trail
+///
+/// How those reports were produced was not recorded, so the trail stays empty.
+fn upgrade_v8(report: &mut Value, changes: &mut Vec<String>) {
+    let report = report.as_object_mut().expect("metadata checked by caller");
+    fill(report, "", "audit", Value::Null, changes);
+}
+
+/// Version 9: reports without file previews
+///
+/// Previews need the installer, which a report alone does not have.
+fn upgrade_v9(report: &mut Value, changes: &mut Vec<String>) {
+    let report = report.as_object_mut().expect("metadata checked by caller");
+    fill(report, "", "previews", json!([]), changes);
+}
+
+/// Insert a missing field and record the change
+fn fill(
+    object: &mut Map<String, Value>,
+    prefix: &str,
+    key: &str,
+    value: Value,
+    changes: &mut Vec<String>,
+) {
+    if !object.contains_key(key) {
+        object.insert(key.to_string(), value);
+        changes.push(format!("{}{}: added", prefix, key));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Report as written before schema versioning
+    fn v1_report() -> Value {
+        json!({
+            "session_id": "6f1c2b1e-8f5d-4c43-9a57-0d1b2c3d4e5f",
+            "analyzed_at": "2024-05-01T12:00:00Z",
+            "analysis_duration": 1.5,
+            "dynamic_analysis": false,
+            "metadata": {
+                "original_filename": "setup.exe",
+                "filename": "App",
+                "file_size": 1024,
+                "file_hash": "ab".repeat(32),
+                "format": "Other(\"Setup Factory\")",
+                "version": "1.0",
+                "publisher": "Contoso",
+                "description": "N/A",
+                "properties": { "ProductCode": "{11111111-2222-3333-4444-555555555555}" }
+            },
+            "files": [
+                { "path": "App", "size": 0, "is_directory": true },
+                {
+                    "path": "App/app.exe",
+                    "size": 512,
+                    "is_directory": false,
+                    "attributes": {
+                        "readonly": false,
+                        "hidden": false,
+                        "system": false,
+                        "executable": true
+                    },
+                    "hash": null,
+                    "target_path": null,
+                    "compression": null
+                }
+            ],
+            "registry_operations": [
+                { "operation": "CREATE", "key": "HKLM\\SOFTWARE\\App", "value": null }
+            ],
+            "file_operations": [],
+            "process_operations": [],
+            "network_operations": [],
+            "summary": {
+                "total_files": 1,
+                "executable_files": 1,
+                "registry_operations": 1,
+                "file_operations": 0,
+                "process_operations": 0,
+                "network_operations": 0
+            }
+        })
+    }
+
+    #[test]
+    fn test_upgrade_v1_report() {
+        let mut report = v1_report();
+        let upgrade = upgrade(&mut report).unwrap();
+
+        assert_eq!(upgrade.from_version, 1);
+        assert_eq!(upgrade.to_version, REPORT_SCHEMA_VERSION);
+        assert!(!upgrade.is_current());
+        assert_eq!(report["schema_version"], REPORT_SCHEMA_VERSION);
+        assert_eq!(report["metadata"]["format"], "Setup Factory");
+        assert_eq!(report["metadata"]["format_details"], Value::Null);
+        assert_eq!(report["files"][1]["is_speculative"], false);
+        assert!(report["files"][0].get("is_speculative").is_none());
+        assert_eq!(report["registry_operations"][0]["process_id"], Value::Null);
+        assert!(report["product_identity"].is_object());
+        assert!(report["requirements"].is_object());
+        assert!(report["process_attribution"].is_object());
+        assert_eq!(report["resource_usage"], Value::Null);
+        assert_eq!(report["wmi_operations"], json!([]));
+        assert_eq!(report["summary"]["powershell_script_blocks"], 0);
+        assert_eq!(report["process_attribution"]["wmi_processes"], json!([]));
+        // The declared registry key is a modification
+        assert_eq!(report["attack"]["matrix"][0]["technique_id"], "T1112");
+        assert_eq!(
+            report["repackaging"]["recommendations"][0]["strategy"],
+            "capture"
+        );
+        assert_eq!(report["metadata"]["provenance"], json!({}));
+        assert_eq!(report["vendor"], Value::Null);
+        // Recorded data is kept as is
+        assert_eq!(report["summary"]["total_files"], 1);
+        assert_eq!(report["metadata"]["publisher"], "Contoso");
+
+        let again = super::upgrade(&mut report).unwrap();
+        assert!(again.is_current());
+        assert!(again.changes.is_empty());
+    }
+
+    #[test]
+    fn test_upgrade_v7_report_looks_up_vendor() {
+        let mut report = v1_report();
+        report["metadata"]["publisher"] = json!("MOZILLA CORPORATION");
+        upgrade(&mut report).unwrap();
+        assert_eq!(report["vendor"]["vendor"], "Mozilla");
+        assert_eq!(report["vendor"]["matched_by"], "publisher");
+    }
+
+    #[test]
+    fn test_upgrade_v8_report_has_no_audit_trail() {
+        let mut report = v1_report();
+        upgrade(&mut report).unwrap();
+        report.as_object_mut().unwrap().remove("audit");
+        report["schema_version"] = json!(8);
+
+        let upgrade = upgrade(&mut report).unwrap();
+        assert_eq!(upgrade.changes, vec!["audit: added"]);
+        assert!(report["audit"].is_null());
+    }
+
+    #[test]
+    fn test_upgrade_v9_report_has_no_previews() {
+        let mut report = v1_report();
+        upgrade(&mut report).unwrap();
+        report.as_object_mut().unwrap().remove("previews");
+        report["schema_version"] = json!(9);
+
+        let upgrade = upgrade(&mut report).unwrap();
+        assert_eq!(upgrade.changes, vec!["previews: added"]);
+        assert_eq!(report["previews"], json!([]));
+    }
+
+    #[test]
+    fn test_upgrade_v2_report() {
+        let mut report = v1_report();
+        upgrade_v1(&mut report, &mut Vec::new()).unwrap();
+        report["schema_version"] = json!(2);
+
+        let upgrade = upgrade(&mut report).unwrap();
+        assert_eq!(upgrade.from_version, 2);
+        assert_eq!(upgrade.changes[0], "resource_usage: added");
+        assert!(upgrade
+            .changes
+            .contains(&"wmi_operations: added".to_string()));
+        assert_eq!(report["schema_version"], REPORT_SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_upgrade_combined_report() {
+        let mut report = json!({
+            "summary": { "total_artifacts": 1, "total_files": 1, "total_size": 1024 },
+            "artifacts": [v1_report()]
+        });
+        let upgrade = upgrade(&mut report).unwrap();
+
+        assert_eq!(report["schema_version"], REPORT_SCHEMA_VERSION);
+        assert_eq!(
+            report["artifacts"][0]["schema_version"],
+            REPORT_SCHEMA_VERSION
+        );
+        assert!(upgrade
+            .changes
+            .iter()
+            .all(|change| change.starts_with("artifacts[0]: ")));
+    }
+
+    #[test]
+    fn test_upgrade_rejects_newer_and_foreign_documents() {
+        let mut newer = json!({ "schema_version": REPORT_SCHEMA_VERSION + 1, "metadata": {} });
+        assert!(upgrade(&mut newer).is_err());
+
+        let mut foreign = json!({ "name": "package.json" });
+        assert!(upgrade(&mut foreign).is_err());
+    }
+}
@@ -3,11 +3,49 @@
 use crate::core::{AnalysisResult, Result};
 use std::path::Path;
 
+pub mod artifacts;
+pub mod attack;
+pub mod attribution;
+pub mod audit;
+pub mod batch_summary;
+pub mod bundle;
+pub mod correlation;
+pub mod database;
+pub mod dependency_export;
+pub mod deployment_export;
+pub mod discrepancies;
 pub mod generator;
+pub mod merge;
+pub mod migration;
+pub mod redaction;
+pub mod registry_groups;
+pub mod repackaging;
 pub mod templates;
+pub mod tree_export;
+pub mod vendors;
 
 // Re-export main types
-pub use generator::ReportGenerator;
+pub use artifacts::{ArtifactWriter, AttachmentIndex, AttachmentKind};
+pub use attack::{AttackSummary, AttackTactic};
+pub use attribution::ProcessAttribution;
+pub use audit::ReportSignature;
+pub use batch_summary::{BatchEntry, BatchStatus, BatchSummary};
+pub use bundle::ReportBundle;
+pub use correlation::{correlate, product_families, Correlation, ProductIdentity, Relationship};
+pub use database::{ComponentFilter, ResultsDatabase};
+pub use dependency_export::{DependencyExportFormat, DependencyGraphExporter};
+pub use deployment_export::{
+    DeploymentExportFormat, DeploymentExporter, DeploymentMetadata, InstallContext,
+};
+pub use discrepancies::{Discrepancy, DiscrepancySeverity};
+pub use generator::{HtmlData, ReportGenerator, ReportOptions};
+pub use merge::CrossCheck;
+pub use migration::{ReportUpgrade, REPORT_SCHEMA_VERSION};
+pub use redaction::{Redaction, Redactor};
+pub use registry_groups::{RegistryArea, RegistryGroups, RegistryHive};
+pub use repackaging::{Recommendation, RepackagingAdvice, RepackagingStrategy};
+pub use tree_export::{FileTreeExporter, TreeExportFormat};
+pub use vendors::{VendorInfo, VendorKnowledgeBase, VendorMatch};
 
 /// Report format options
 #[derive(Debug, Clone)]
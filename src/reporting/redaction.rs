@@ -0,0 +1,441 @@
+//! Redaction of local environment details from results
+//!
+//! Reports shared outside the organization should not reveal who ran the
+//! analysis and where: captured paths carry user profile names, sandbox
+//! runs record machine names and the audit trail lists local directories.
+//! A redactor scrubs every string of a result for the requested
+//! [`Redaction`]s before any report, export or bundle is written.
+
+use crate::core::{AnalysisResult, MachineInfo, Result};
+use regex::{Captures, Regex};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Profile directories that exist on every Windows installation
+const SHARED_PROFILES: [&str; 5] = [
+    "public",
+    "default",
+    "default user",
+    "all users",
+    "defaultapppool",
+];
+
+/// Kind of local detail to scrub
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Redaction {
+    /// User names in profile paths, and the known users anywhere
+    Usernames,
+    /// User profile and temporary directories, and directories of the analysis host
+    Paths,
+    /// Names of the analysis host and of UNC servers
+    Hostnames,
+}
+
+impl Redaction {
+    pub const ALL: [Redaction; 3] = [Self::Usernames, Self::Paths, Self::Hostnames];
+
+    /// Lowercase name, as accepted on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Usernames => "usernames",
+            Self::Paths => "paths",
+            Self::Hostnames => "hostnames",
+        }
+    }
+}
+
+impl FromStr for Redaction {
+    type Err = String;
+
+    fn from_str(name: &str) -> std::result::Result<Self, Self::Err> {
+        let name = name.trim().to_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|redaction| redaction.name() == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::ALL.iter().map(Redaction::name).collect();
+                format!(
+                    "unknown redaction '{}', expected one of {}",
+                    name,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// Scrubs local environment details from results
+pub struct Redactor {
+    redactions: Vec<Redaction>,
+    /// Host of this run, its names are redacted even if the result lacks them
+    machine: MachineInfo,
+    /// Directories of this host with their placeholders, longest first
+    local_dirs: Vec<(String, &'static str)>,
+    /// `<drive>:\Users\<name>` and `/home/<name>` style profile directories
+    profile: Regex,
+    /// Temporary directory inside a Windows user profile
+    profile_temp: Regex,
+    /// Server of a UNC path
+    unc_host: Regex,
+}
+
+impl Redactor {
+    /// Redactor for the environment of this process
+    pub fn new(redactions: &[Redaction]) -> Self {
+        let mut local_dirs: Vec<(String, &'static str)> = [
+            (Some(std::env::temp_dir()), "<temp>"),
+            (std::env::var_os("HOME").map(Into::into), "<home>"),
+            (std::env::var_os("USERPROFILE").map(Into::into), "<home>"),
+            (std::env::current_dir().ok(), "<cwd>"),
+        ]
+        .into_iter()
+        .filter_map(|(dir, placeholder)| Some((dir?, placeholder)))
+        .map(|(dir, placeholder)| (dir.display().to_string(), placeholder))
+        .collect();
+        local_dirs.retain(|(dir, _)| dir.trim_end_matches(['/', '\\']).len() > 1);
+        local_dirs.sort_by_key(|(dir, _)| std::cmp::Reverse(dir.len()));
+
+        Self {
+            redactions: redactions.to_vec(),
+            machine: MachineInfo::current(),
+            local_dirs,
+            profile: Regex::new(
+                r#"(?i)(\b[a-z]:[\\/](?:users|documents and settings)[\\/]|(?:^|[\s"'=])/(?:home|users)/)([^\\/\s"':;|<>]+)"#,
+            )
+            .expect("valid profile pattern"),
+            profile_temp: Regex::new(
+                r#"(?i)\b[a-z]:[\\/]users[\\/][^\\/\s"':;|<>]+[\\/]appdata[\\/]local[\\/]temp\b"#,
+            )
+            .expect("valid profile temp pattern"),
+            unc_host: Regex::new(r"(^|[\s\x22'=])\\\\([A-Za-z0-9][A-Za-z0-9_.-]*)\\")
+                .expect("valid UNC pattern"),
+        }
+    }
+
+    /// Redact a result in place, returning the number of changed strings
+    ///
+    /// The audit trail records the redaction, so readers know that the
+    /// report differs from what was captured.
+    pub fn redact(&self, result: &mut AnalysisResult) -> Result<usize> {
+        if self.redactions.is_empty() {
+            return Ok(0);
+        }
+
+        let mut value = serde_json::to_value(&*result)?;
+        let mut strings = Vec::new();
+        collect_strings(&value, &mut strings);
+        let scrubber = self.scrubber(&strings, result);
+
+        let mut changed = 0;
+        scrub_strings(&mut value, &mut |text| {
+            let scrubbed = scrubber.scrub(text);
+            if scrubbed != text {
+                changed += 1;
+            }
+            scrubbed
+        });
+        *result = serde_json::from_value(value)?;
+
+        let names: Vec<&str> = self.redactions.iter().map(Redaction::name).collect();
+        if let Some(audit) = result.audit.as_mut() {
+            audit.record("report_redacted", names.join(", "));
+        }
+        Ok(changed)
+    }
+
+    /// Scrubber with the names and directories found in `strings`
+    fn scrubber(&self, strings: &[&str], result: &AnalysisResult) -> Scrubber<'_> {
+        let mut users = BTreeSet::new();
+        let mut hosts = BTreeSet::new();
+        let mut dirs = self.local_dirs.clone();
+
+        if self.has(Redaction::Usernames) {
+            users.extend(self.machine.user.clone());
+            for text in strings {
+                for captures in self.profile.captures_iter(text) {
+                    let name = captures[2].trim_end_matches('.');
+                    if !SHARED_PROFILES.contains(&name.to_lowercase().as_str()) {
+                        users.insert(name.to_string());
+                    }
+                }
+            }
+        }
+        if self.has(Redaction::Hostnames) {
+            hosts.extend(self.machine.hostname.clone());
+            for text in strings {
+                for captures in self.unc_host.captures_iter(text) {
+                    hosts.insert(captures[2].to_string());
+                }
+            }
+        }
+        if let Some(machine) = result.audit.as_ref().and_then(|a| a.machine.as_ref()) {
+            if self.has(Redaction::Usernames) {
+                users.extend(machine.user.clone());
+            }
+            if self.has(Redaction::Hostnames) {
+                hosts.extend(machine.hostname.clone());
+            }
+        }
+        if self.has(Redaction::Paths) {
+            // The input may live anywhere on the analysis host
+            if let Some(dir) = result
+                .source_file_path
+                .as_deref()
+                .and_then(Path::parent)
+                .map(|dir| dir.display().to_string())
+                .filter(|dir| dir.trim_end_matches(['/', '\\']).len() > 1)
+            {
+                dirs.push((dir, "<input>"));
+                dirs.sort_by_key(|(dir, _)| std::cmp::Reverse(dir.len()));
+            }
+        }
+
+        Scrubber {
+            redactor: self,
+            users: word_pattern(users.iter().filter(|name| name.len() > 1)),
+            hosts: word_pattern(hosts.iter().filter(|name| name.len() > 1)),
+            dirs,
+        }
+    }
+
+    fn has(&self, redaction: Redaction) -> bool {
+        self.redactions.contains(&redaction)
+    }
+}
+
+/// Redactor bound to the names found in one result
+struct Scrubber<'a> {
+    redactor: &'a Redactor,
+    users: Option<Regex>,
+    hosts: Option<Regex>,
+    dirs: Vec<(String, &'static str)>,
+}
+
+impl Scrubber<'_> {
+    fn scrub(&self, text: &str) -> String {
+        let redactor = self.redactor;
+        let mut text = text.to_string();
+
+        if redactor.has(Redaction::Paths) {
+            for (dir, placeholder) in &self.dirs {
+                text = text.replace(dir.as_str(), placeholder);
+            }
+            text = redactor
+                .profile_temp
+                .replace_all(&text, "%TEMP%")
+                .into_owned();
+            text = redactor
+                .profile
+                .replace_all(&text, |captures: &Captures| {
+                    if is_shared_profile(&captures[2]) {
+                        captures[0].to_string()
+                    } else if captures[1].starts_with(|c: char| c.is_ascii_alphabetic()) {
+                        "%USERPROFILE%".to_string()
+                    } else {
+                        // Keep the separator matched before `/home/`
+                        let lead: String = captures[1].chars().take_while(|c| *c != '/').collect();
+                        format!("{}<home>", lead)
+                    }
+                })
+                .into_owned();
+        }
+        if redactor.has(Redaction::Usernames) {
+            text = redactor
+                .profile
+                .replace_all(&text, |captures: &Captures| {
+                    if is_shared_profile(&captures[2]) {
+                        captures[0].to_string()
+                    } else {
+                        format!("{}<user>", &captures[1])
+                    }
+                })
+                .into_owned();
+            if let Some(users) = &self.users {
+                text = users.replace_all(&text, "<user>").into_owned();
+            }
+        }
+        if redactor.has(Redaction::Hostnames) {
+            text = redactor
+                .unc_host
+                .replace_all(&text, "$1\\\\<host>\\")
+                .into_owned();
+            if let Some(hosts) = &self.hosts {
+                text = hosts.replace_all(&text, "<host>").into_owned();
+            }
+        }
+        text
+    }
+}
+
+fn is_shared_profile(name: &str) -> bool {
+    SHARED_PROFILES.contains(&name.to_lowercase().as_str())
+}
+
+/// Case-insensitive pattern matching any of `words` as a whole word
+fn word_pattern<'a>(words: impl Iterator<Item = &'a String>) -> Option<Regex> {
+    let alternatives: Vec<String> = words.map(|word| regex::escape(word)).collect();
+    if alternatives.is_empty() {
+        return None;
+    }
+    let pattern = format!(r"(?i)\b(?:{})\b", alternatives.join("|"));
+    Some(Regex::new(&pattern).expect("escaped words form a valid pattern"))
+}
+
+fn collect_strings<'a>(value: &'a Value, strings: &mut Vec<&'a str>) {
+    match value {
+        Value::String(text) => strings.push(text),
+        Value::Array(items) => items.iter().for_each(|item| collect_strings(item, strings)),
+        Value::Object(map) => map.values().for_each(|item| collect_strings(item, strings)),
+        _ => {}
+    }
+}
+
+fn scrub_strings(value: &mut Value, scrub: &mut impl FnMut(&str) -> String) {
+    match value {
+        Value::String(text) => *text = scrub(text),
+        Value::Array(items) => items.iter_mut().for_each(|item| scrub_strings(item, scrub)),
+        Value::Object(map) => map.values_mut().for_each(|item| scrub_strings(item, scrub)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        AuditTrail, FileAttributes, FileEntry, InstallerFormat, InstallerMetadata, ProcessOpType,
+        ProcessOperation,
+    };
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    fn redactor(redactions: &[Redaction]) -> Redactor {
+        Redactor {
+            machine: MachineInfo {
+                hostname: Some("ANALYST-PC".to_string()),
+                user: Some("jdoe".to_string()),
+                os: "windows".to_string(),
+                arch: "x86_64".to_string(),
+            },
+            local_dirs: vec![("/srv/intake/work".to_string(), "<cwd>")],
+            ..Redactor::new(redactions)
+        }
+    }
+
+    fn captured_result() -> AnalysisResult {
+        let mut properties = HashMap::new();
+        properties.insert(
+            "download_cache".to_string(),
+            r"C:\Users\alice\AppData\Local\Temp\is-1234.tmp\setup.tmp".to_string(),
+        );
+        properties.insert(
+            "license_share".to_string(),
+            r"\\FILESRV01\licenses\key.txt".to_string(),
+        );
+        properties.insert("public".to_string(), r"C:\Users\Public\Desktop".to_string());
+
+        AnalysisResult {
+            session_id: uuid::Uuid::new_v4(),
+            source_file_path: Some(PathBuf::from("/mnt/drop/setup.exe")),
+            metadata: InstallerMetadata {
+                format: InstallerFormat::InnoSetup,
+                product_name: Some("App".to_string()),
+                file_size: 1024,
+                file_hash: "0".repeat(64),
+                created_at: chrono::Utc::now(),
+                properties,
+                ..Default::default()
+            },
+            files: vec![FileEntry {
+                path: PathBuf::from("/home/bob/.config/app.ini"),
+                target_path: None,
+                size: 1,
+                hash: None,
+                attributes: FileAttributes::default(),
+                compression: None,
+                is_speculative: false,
+                architecture: None,
+                modified: None,
+                source: None,
+            }],
+            process_operations: vec![ProcessOperation {
+                operation_type: ProcessOpType::Create,
+                process_id: 4,
+                process_name: "/srv/intake/work/setup.exe".to_string(),
+                command_line: Some("setup.exe /host=ANALYST-PC /user=JDOE".to_string()),
+                parent_process_id: None,
+                timestamp: chrono::Utc::now(),
+            }],
+            analyzed_at: chrono::Utc::now(),
+            analysis_duration: std::time::Duration::from_secs(1),
+            dynamic_analysis: true,
+            audit: Some(AuditTrail::default()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_redactions() {
+        assert_eq!(" Paths".parse::<Redaction>(), Ok(Redaction::Paths));
+        assert!("users".parse::<Redaction>().is_err());
+    }
+
+    #[test]
+    fn test_redact_usernames_and_hostnames() {
+        let mut result = captured_result();
+        let changed = redactor(&[Redaction::Usernames, Redaction::Hostnames])
+            .redact(&mut result)
+            .unwrap();
+        assert_eq!(changed, 4);
+
+        let properties = &result.metadata.properties;
+        assert_eq!(
+            properties["download_cache"],
+            r"C:\Users\<user>\AppData\Local\Temp\is-1234.tmp\setup.tmp"
+        );
+        assert_eq!(properties["license_share"], r"\\<host>\licenses\key.txt");
+        assert_eq!(properties["public"], r"C:\Users\Public\Desktop");
+        assert_eq!(
+            result.files[0].path,
+            PathBuf::from("/home/<user>/.config/app.ini")
+        );
+        assert_eq!(
+            result.process_operations[0].command_line.as_deref(),
+            Some("setup.exe /host=<host> /user=<user>")
+        );
+        assert_eq!(
+            result.audit.unwrap().events[0].detail,
+            "usernames, hostnames"
+        );
+    }
+
+    #[test]
+    fn test_redact_paths() {
+        let mut result = captured_result();
+        redactor(&[Redaction::Paths]).redact(&mut result).unwrap();
+
+        let properties = &result.metadata.properties;
+        assert_eq!(
+            properties["download_cache"],
+            r"%TEMP%\is-1234.tmp\setup.tmp"
+        );
+        assert_eq!(properties["public"], r"C:\Users\Public\Desktop");
+        assert_eq!(
+            result.source_file_path,
+            Some(PathBuf::from("<input>/setup.exe"))
+        );
+        assert_eq!(
+            result.files[0].path,
+            PathBuf::from("<home>/.config/app.ini")
+        );
+        assert_eq!(result.process_operations[0].process_name, "<cwd>/setup.exe");
+    }
+
+    #[test]
+    fn test_nothing_to_redact() {
+        let mut result = captured_result();
+        assert_eq!(Redactor::new(&[]).redact(&mut result).unwrap(), 0);
+        assert!(result.audit.unwrap().events.is_empty());
+    }
+}
@@ -0,0 +1,304 @@
+//! Registry operations grouped by hive and purpose
+//!
+//! Installers write hundreds of registry values, most of them uninstall
+//! information, COM registrations and file associations. Reports group the
+//! operations by hive and then by the functional area of their key, so that
+//! services and startup entries are not buried in one long list.
+
+use crate::core::RegistryOperation;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Root keys as written in full and abbreviated
+const HIVES: &[(&str, &str, RegistryHive)] = &[
+    ("hkey_local_machine", "hklm", RegistryHive::LocalMachine),
+    ("hkey_current_user", "hkcu", RegistryHive::CurrentUser),
+    ("hkey_classes_root", "hkcr", RegistryHive::ClassesRoot),
+    ("hkey_users", "hku", RegistryHive::Users),
+    ("hkey_current_config", "hkcc", RegistryHive::CurrentConfig),
+];
+
+/// Keys below `CurrentVersion` whose values run at logon
+const RUN_KEYS: &[&str] = &[
+    "run",
+    "runonce",
+    "runonceex",
+    "runservices",
+    "runservicesonce",
+];
+
+/// Keys below `Classes` holding COM registrations
+const COM_KEYS: &[&str] = &["clsid", "typelib", "interface", "appid"];
+
+/// Root key of a registry path
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RegistryHive {
+    LocalMachine,
+    CurrentUser,
+    ClassesRoot,
+    Users,
+    CurrentConfig,
+    /// Paths without a known root key
+    Other,
+}
+
+impl RegistryHive {
+    /// Name of the root key
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::LocalMachine => "HKEY_LOCAL_MACHINE",
+            Self::CurrentUser => "HKEY_CURRENT_USER",
+            Self::ClassesRoot => "HKEY_CLASSES_ROOT",
+            Self::Users => "HKEY_USERS",
+            Self::CurrentConfig => "HKEY_CURRENT_CONFIG",
+            Self::Other => "Other",
+        }
+    }
+}
+
+/// What the keys of a group are used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RegistryArea {
+    /// `Uninstall` entries listed in Apps & features
+    Uninstall,
+    Services,
+    /// `Run` and `RunOnce` entries started at logon
+    RunKeys,
+    /// File extensions, ProgIDs and their shell verbs
+    FileAssociations,
+    /// Classes, type libraries, interfaces and AppIDs
+    Com,
+    Other,
+}
+
+impl RegistryArea {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Uninstall => "Uninstall entries",
+            Self::Services => "Services",
+            Self::RunKeys => "Run keys",
+            Self::FileAssociations => "File associations",
+            Self::Com => "COM registrations",
+            Self::Other => "Other",
+        }
+    }
+}
+
+/// Registry operations of one hive
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistryHiveGroup {
+    pub hive: RegistryHive,
+    /// Operations on keys of the hive
+    pub count: usize,
+    pub areas: Vec<RegistryAreaGroup>,
+}
+
+/// Registry operations of one functional area within a hive
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistryAreaGroup {
+    pub area: RegistryArea,
+    /// Indexes into the registry operations of the result, in their order
+    pub operations: Vec<usize>,
+}
+
+/// Registry operations grouped by hive, then functional area
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistryGroups {
+    /// Hives in the order of [`RegistryHive`], each with its areas in the
+    /// order of [`RegistryArea`]; empty groups are left out
+    pub hives: Vec<RegistryHiveGroup>,
+}
+
+impl RegistryGroups {
+    /// Group `operations` by the hive and area of their key
+    pub fn from_operations(operations: &[RegistryOperation]) -> Self {
+        let mut groups: BTreeMap<RegistryHive, BTreeMap<RegistryArea, Vec<usize>>> =
+            BTreeMap::new();
+        for (index, operation) in operations.iter().enumerate() {
+            let (hive, area) = classify(operation.key_path());
+            groups
+                .entry(hive)
+                .or_default()
+                .entry(area)
+                .or_default()
+                .push(index);
+        }
+
+        Self {
+            hives: groups
+                .into_iter()
+                .map(|(hive, areas)| RegistryHiveGroup {
+                    hive,
+                    count: areas.values().map(Vec::len).sum(),
+                    areas: areas
+                        .into_iter()
+                        .map(|(area, operations)| RegistryAreaGroup { area, operations })
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Hive and functional area of a key path
+pub fn classify(key_path: &str) -> (RegistryHive, RegistryArea) {
+    let key = key_path.to_lowercase();
+    let mut segments: Vec<&str> = key.split('\\').filter(|s| !s.is_empty()).collect();
+    let hive = segments
+        .first()
+        .and_then(|root| {
+            HIVES
+                .iter()
+                .find(|(name, abbreviation, _)| root == name || root == abbreviation)
+        })
+        .map(|(_, _, hive)| *hive);
+    let Some(hive) = hive else {
+        return (RegistryHive::Other, area(&segments, None));
+    };
+    segments.remove(0);
+
+    // Classes are merged into HKEY_CLASSES_ROOT from the machine and user hives
+    let classes = match hive {
+        RegistryHive::ClassesRoot => Some(0),
+        RegistryHive::Users => match segments.first() {
+            Some(sid) if sid.ends_with("_classes") => Some(1),
+            _ => classes_key(segments.get(1..).unwrap_or_default()).map(|at| at + 1),
+        },
+        _ => classes_key(&segments),
+    };
+    (hive, area(&segments, classes))
+}
+
+/// Index of the first segment below `Software\Classes`
+fn classes_key(segments: &[&str]) -> Option<usize> {
+    match segments {
+        ["software", "classes", ..] => Some(2),
+        ["software", "wow6432node", "classes", ..] => Some(3),
+        _ => None,
+    }
+}
+
+/// Area of a key below its root, with `classes` the index of the first
+/// segment below a `Classes` key
+fn area(segments: &[&str], classes: Option<usize>) -> RegistryArea {
+    if let Some(classes) = classes {
+        let class = match segments.get(classes..).unwrap_or_default() {
+            ["wow6432node", class, ..] | [class, ..] => *class,
+            [] => return RegistryArea::Other,
+        };
+        return if COM_KEYS.contains(&class) {
+            RegistryArea::Com
+        } else {
+            RegistryArea::FileAssociations
+        };
+    }
+
+    let below = |parent: &str| {
+        segments
+            .iter()
+            .position(|segment| *segment == parent)
+            .and_then(|at| segments.get(at + 1))
+            .copied()
+    };
+    if segments
+        .windows(2)
+        .any(|pair| pair == ["currentversion", "uninstall"])
+    {
+        RegistryArea::Uninstall
+    } else if segments.first() == Some(&"system") && below("services").is_some() {
+        RegistryArea::Services
+    } else if below("currentversion").is_some_and(|key| RUN_KEYS.contains(&key))
+        || segments
+            .windows(3)
+            .any(|keys| keys == ["policies", "explorer", "run"])
+    {
+        RegistryArea::RunKeys
+    } else if segments
+        .windows(2)
+        .any(|pair| pair == ["explorer", "fileexts"])
+    {
+        RegistryArea::FileAssociations
+    } else {
+        RegistryArea::Other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn create_key(key_path: &str) -> RegistryOperation {
+        RegistryOperation::CreateKey {
+            key_path: key_path.to_string(),
+            timestamp: Utc::now(),
+            process_id: None,
+        }
+    }
+
+    #[test]
+    fn test_classify() {
+        let uninstall =
+            "HKLM\\SOFTWARE\\WOW6432Node\\Microsoft\\Windows\\CurrentVersion\\Uninstall\\App_is1";
+        assert_eq!(
+            classify(uninstall),
+            (RegistryHive::LocalMachine, RegistryArea::Uninstall)
+        );
+        assert_eq!(
+            classify("HKEY_LOCAL_MACHINE\\SYSTEM\\CurrentControlSet\\Services\\AppSvc"),
+            (RegistryHive::LocalMachine, RegistryArea::Services)
+        );
+        assert_eq!(
+            classify("HKCU\\Software\\Microsoft\\Windows\\CurrentVersion\\RunOnce"),
+            (RegistryHive::CurrentUser, RegistryArea::RunKeys)
+        );
+        assert_eq!(
+            classify("HKLM\\Software\\Classes\\.appdoc\\shell\\open\\command"),
+            (RegistryHive::LocalMachine, RegistryArea::FileAssociations)
+        );
+        assert_eq!(
+            classify("HKCR\\WOW6432Node\\CLSID\\{00000000-0000-0000-0000-000000000001}"),
+            (RegistryHive::ClassesRoot, RegistryArea::Com)
+        );
+        assert_eq!(
+            classify("HKEY_USERS\\S-1-5-21-1_Classes\\TypeLib\\{1}"),
+            (RegistryHive::Users, RegistryArea::Com)
+        );
+        assert_eq!(
+            classify("HKLM\\SOFTWARE\\Contoso\\App"),
+            (RegistryHive::LocalMachine, RegistryArea::Other)
+        );
+        assert_eq!(
+            classify("Software\\Contoso\\App"),
+            (RegistryHive::Other, RegistryArea::Other)
+        );
+    }
+
+    #[test]
+    fn test_group_operations() {
+        let operations = [
+            create_key("HKLM\\SOFTWARE\\Contoso\\App"),
+            create_key("HKCU\\Software\\Microsoft\\Windows\\CurrentVersion\\Run"),
+            create_key("HKLM\\SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Uninstall\\App"),
+            create_key("HKLM\\SOFTWARE\\Contoso\\App\\Settings"),
+        ];
+        let groups = RegistryGroups::from_operations(&operations);
+
+        assert_eq!(groups.hives.len(), 2);
+        let machine = &groups.hives[0];
+        assert_eq!(machine.hive, RegistryHive::LocalMachine);
+        assert_eq!(machine.count, 3);
+        assert_eq!(machine.areas[0].area, RegistryArea::Uninstall);
+        assert_eq!(machine.areas[0].operations, [2]);
+        assert_eq!(machine.areas[1].area, RegistryArea::Other);
+        assert_eq!(machine.areas[1].operations, [0, 3]);
+        assert_eq!(groups.hives[1].areas[0].area, RegistryArea::RunKeys);
+
+        let json = serde_json::to_value(&groups).unwrap();
+        assert_eq!(json["hives"][1]["hive"], "current_user");
+        assert_eq!(json["hives"][1]["areas"][0]["area"], "run_keys");
+        assert!(RegistryGroups::from_operations(&[]).hives.is_empty());
+    }
+}
@@ -0,0 +1,425 @@
+//! Repackaging advice for software deployment
+//!
+//! Packaging engineers decide per installer whether it can be deployed as
+//! is, customized, unpacked or has to be captured and rebuilt. The decision
+//! follows from the installer format, its silent switches and a few traits
+//! the analysis reveals: per-user installs, payloads downloaded at install
+//! time, MSI packages shipped inside other installers and kernel drivers
+//! that capture-based repackaging cannot represent.
+
+use crate::core::{AnalysisResult, InstallerFormat};
+use crate::reporting::{DeploymentMetadata, InstallContext};
+use crate::utils::entry_file_name;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Number of packaged MSI files named in a recommendation
+const MAX_LISTED: usize = 5;
+
+/// Kind of repackaging strategy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RepackagingStrategy {
+    /// Deploy the installer with its silent switches
+    Wrap,
+    /// Customize an MSI with a transform
+    Transform,
+    /// Deploy MSI packages extracted from the installer
+    ExtractMsi,
+    /// Record installer choices once and replay them silently
+    RecordResponse,
+    /// Switch a per-user installer to a machine-wide install
+    MachineWide,
+    /// Replace a web installer with its offline installer
+    ObtainOffline,
+    /// Capture the installation and rebuild it as a new package
+    Capture,
+    /// Deploy the installer natively instead of capturing it
+    KeepNative,
+    /// Deployment setting to review
+    Review,
+}
+
+impl RepackagingStrategy {
+    /// Value used in reports
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Wrap => "wrap",
+            Self::Transform => "transform",
+            Self::ExtractMsi => "extract_msi",
+            Self::RecordResponse => "record_response",
+            Self::MachineWide => "machine_wide",
+            Self::ObtainOffline => "obtain_offline",
+            Self::Capture => "capture",
+            Self::KeepNative => "keep_native",
+            Self::Review => "review",
+        }
+    }
+}
+
+impl fmt::Display for RepackagingStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// One recommended repackaging step
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Recommendation {
+    pub strategy: RepackagingStrategy,
+    pub title: String,
+    pub detail: String,
+}
+
+/// Repackaging recommendations for an installer, most important first
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RepackagingAdvice {
+    pub recommendations: Vec<Recommendation>,
+}
+
+impl RepackagingAdvice {
+    /// Advise on repackaging the installer of an analysis result
+    pub fn from_result(result: &AnalysisResult) -> Self {
+        let properties = &result.metadata.properties;
+        let deployment = DeploymentMetadata::from_result(result);
+        let install = deployment.install_command.clone().unwrap_or_default();
+        let mut advice = Self::default();
+
+        if properties
+            .get("installer_delivery")
+            .is_some_and(|delivery| delivery == "web")
+        {
+            let urls = properties
+                .get("download_urls")
+                .map(|urls| format!(" from {}", urls))
+                .unwrap_or_default();
+            advice.add(
+                RepackagingStrategy::ObtainOffline,
+                "Web installer — obtain the offline installer",
+                format!(
+                    "The installer downloads its payload{}; deployments fail without network access and may install a different version than tested",
+                    urls
+                ),
+            );
+        }
+
+        let per_user_msi = properties.get("ALLUSERS").is_some_and(String::is_empty)
+            || properties
+                .get("MSIINSTALLPERUSER")
+                .is_some_and(|per_user| per_user == "1");
+        // WiX builds MSI packages as well as Burn bundles
+        let msi_package = match &result.metadata.format {
+            InstallerFormat::MSI => true,
+            InstallerFormat::WiX => deployment.setup_file.to_ascii_lowercase().ends_with(".msi"),
+            _ => false,
+        };
+        match &result.metadata.format {
+            _ if msi_package => {
+                advice.add(
+                    RepackagingStrategy::Wrap,
+                    "Windows Installer package — deploy directly",
+                    install,
+                );
+                advice.add(
+                    RepackagingStrategy::Transform,
+                    "Customize with a transform instead of repackaging",
+                    "Set properties, features and shortcuts in a transform (.mst) applied with TRANSFORMS=; the vendor package stays intact and upgradable",
+                );
+                if per_user_msi {
+                    advice.add(
+                        RepackagingStrategy::MachineWide,
+                        "Per-user MSI — install machine-wide",
+                        "Set ALLUSERS=1 and clear MSIINSTALLPERUSER in the transform so that the product installs once for all users",
+                    );
+                }
+            }
+            InstallerFormat::WiX => {
+                advice.add(
+                    RepackagingStrategy::Wrap,
+                    "WiX Burn bundle with /quiet supported — wrap directly",
+                    install,
+                );
+                advice.add(
+                    RepackagingStrategy::ExtractMsi,
+                    "Extract the chained packages",
+                    "wix burn extract (WiX v4 and later) or dark -x unpacks the bundle; deploy its MSI packages individually to manage them with transforms",
+                );
+            }
+            InstallerFormat::InstallShield => {
+                let setup_type = properties
+                    .get("installshield_setup_type")
+                    .map(String::as_str)
+                    .unwrap_or("Standard");
+                match setup_type {
+                    "Basic MSI" | "InstallScript MSI" => advice.add(
+                        RepackagingStrategy::ExtractMsi,
+                        format!("InstallShield {} — extract the embedded MSI", setup_type),
+                        "setup.exe unpacks its MSI under %TEMP% while it runs; collect it from there, or create an administrative image with setup.exe /a, and deploy it with msiexec and a transform",
+                    ),
+                    "InstallScript" => advice.add(
+                        RepackagingStrategy::RecordResponse,
+                        "InstallScript — record a response file",
+                        "Record the dialogs once with setup.exe /r /f1\"C:\\setup.iss\" and replay them with setup.exe /s /f1\"setup.iss\"",
+                    ),
+                    _ => advice.add(
+                        RepackagingStrategy::Wrap,
+                        format!("InstallShield {} — wrap with silent switches", setup_type),
+                        install,
+                    ),
+                }
+            }
+            InstallerFormat::AdvancedInstaller => advice.add(
+                RepackagingStrategy::Wrap,
+                "Advanced Installer EXE — wrap with /exenoui",
+                format!(
+                    "{}; setup.exe /extract \"<directory>\" unpacks the MSI package and its prerequisites for deployment with a transform",
+                    install
+                ),
+            ),
+            InstallerFormat::SetupFactory | InstallerFormat::Wise => advice.add(
+                RepackagingStrategy::Wrap,
+                format!(
+                    "{} — wrap with its silent switch",
+                    result.metadata.format.name()
+                ),
+                format!(
+                    "{}; legacy setups may still show dialogs from their scripts, so test the silent install and capture it if it needs input",
+                    install
+                ),
+            ),
+            InstallerFormat::NSIS => advice.add(
+                RepackagingStrategy::Wrap,
+                "NSIS with /S supported — wrap directly",
+                format!(
+                    "{}; pass /D=<directory> as the last argument to choose the installation directory",
+                    install
+                ),
+            ),
+            InstallerFormat::InnoSetup => {
+                advice.add(
+                    RepackagingStrategy::Wrap,
+                    "Inno Setup with /VERYSILENT supported — wrap directly",
+                    format!(
+                        "{}; /DIR=, /COMPONENTS= and /TASKS= select options, or save choices with /SAVEINF and replay them with /LOADINF",
+                        install
+                    ),
+                );
+                if deployment.install_context == InstallContext::User {
+                    advice.add(
+                        RepackagingStrategy::MachineWide,
+                        "Per-user Inno Setup default — install for all users",
+                        "Add /ALLUSERS to install machine-wide where the installer allows overriding its privileges",
+                    );
+                }
+            }
+            InstallerFormat::Squirrel => advice.add(
+                RepackagingStrategy::MachineWide,
+                "Per-user Squirrel — consider a machine-wide MSI",
+                "Squirrel installs into each user's %LocalAppData% and updates itself; deploy the vendor's machine-wide MSI, which installs the app for every user at logon, or deploy in user context",
+            ),
+            InstallerFormat::MSIX => advice.add(
+                RepackagingStrategy::Wrap,
+                "MSIX package — deploy as is",
+                "Deploy as a line-of-business app or provision it with Add-AppxProvisionedPackage; repackaging is not needed",
+            ),
+            InstallerFormat::PythonWheel => advice.add(
+                RepackagingStrategy::KeepNative,
+                "Python wheel — install with pip",
+                "Install into a managed Python environment with pip install --no-index from a local package source",
+            ),
+            InstallerFormat::DriverPackage => advice.add(
+                RepackagingStrategy::Wrap,
+                "Driver package — deploy with pnputil",
+                install,
+            ),
+            InstallerFormat::DiskImage => advice.add(
+                RepackagingStrategy::ExtractMsi,
+                "Disk image — package the installers it contains",
+                properties
+                    .get("image_installers")
+                    .cloned()
+                    .unwrap_or_else(|| "Analyze the installers on the image separately".to_string()),
+            ),
+            _ => advice.add(
+                RepackagingStrategy::Capture,
+                "No known silent switches — capture the installation",
+                "Capture the installation with the MSIX Packaging Tool or a snapshot-based repackager and test the captured package",
+            ),
+        }
+
+        if !matches!(
+            result.metadata.format,
+            InstallerFormat::MSI | InstallerFormat::WiX | InstallerFormat::DiskImage
+        ) {
+            let msi_files: Vec<String> = result
+                .files
+                .iter()
+                .filter(|file| !file.is_speculative)
+                .map(|file| entry_file_name(&file.path))
+                .filter(|name| name.to_ascii_lowercase().ends_with(".msi"))
+                .collect();
+            if !msi_files.is_empty() {
+                let mut listed = msi_files[..msi_files.len().min(MAX_LISTED)].join(", ");
+                if msi_files.len() > MAX_LISTED {
+                    listed.push_str(&format!(" and {} more", msi_files.len() - MAX_LISTED));
+                }
+                advice.add(
+                    RepackagingStrategy::ExtractMsi,
+                    "Packaged MSI found — deploy it directly",
+                    format!(
+                        "The installer carries {}; deploying the MSI with a transform avoids wrapping the bootstrapper",
+                        listed
+                    ),
+                );
+            }
+        }
+
+        let drivers = properties
+            .get("driver_services")
+            .or(properties.get("game_kernel_drivers"));
+        if let Some(drivers) = drivers {
+            advice.add(
+                RepackagingStrategy::KeepNative,
+                "Installs kernel drivers — do not capture",
+                format!(
+                    "Capture-based MSIX repackaging cannot represent drivers ({}); keep the native installer",
+                    drivers
+                ),
+            );
+        }
+
+        if deployment.reboot_required {
+            advice.add(
+                RepackagingStrategy::Review,
+                "Restart required — configure restart handling",
+                "Suppress the restart in the command line and let the deployment tool act on exit codes 3010 and 1641",
+            );
+        }
+
+        advice
+    }
+
+    fn add(
+        &mut self,
+        strategy: RepackagingStrategy,
+        title: impl Into<String>,
+        detail: impl Into<String>,
+    ) {
+        self.recommendations.push(Recommendation {
+            strategy,
+            title: title.into(),
+            detail: detail.into(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{FileAttributes, FileEntry, InstallerMetadata};
+    use std::path::PathBuf;
+
+    fn result(installer: &str, format: InstallerFormat) -> AnalysisResult {
+        AnalysisResult {
+            source_file_path: Some(PathBuf::from(installer)),
+            metadata: InstallerMetadata {
+                format,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn strategies(advice: &RepackagingAdvice) -> Vec<RepackagingStrategy> {
+        advice
+            .recommendations
+            .iter()
+            .map(|recommendation| recommendation.strategy)
+            .collect()
+    }
+
+    #[test]
+    fn test_advise_by_format() {
+        let nsis = RepackagingAdvice::from_result(&result("setup.exe", InstallerFormat::NSIS));
+        assert_eq!(
+            nsis.recommendations[0].title,
+            "NSIS with /S supported — wrap directly"
+        );
+        assert!(nsis.recommendations[0]
+            .detail
+            .starts_with("\"setup.exe\" /S; pass /D="));
+
+        let mut installshield = result("setup.exe", InstallerFormat::InstallShield);
+        installshield.metadata.properties.insert(
+            "installshield_setup_type".to_string(),
+            "Basic MSI".to_string(),
+        );
+        let advice = RepackagingAdvice::from_result(&installshield);
+        assert_eq!(strategies(&advice), vec![RepackagingStrategy::ExtractMsi]);
+        assert!(advice.recommendations[0].detail.contains("%TEMP%"));
+
+        let squirrel =
+            RepackagingAdvice::from_result(&result("Setup.exe", InstallerFormat::Squirrel));
+        assert_eq!(
+            squirrel.recommendations[0].title,
+            "Per-user Squirrel — consider a machine-wide MSI"
+        );
+
+        let mut msi = result("app.msi", InstallerFormat::MSI);
+        msi.metadata
+            .properties
+            .insert("ALLUSERS".to_string(), String::new());
+        assert_eq!(
+            strategies(&RepackagingAdvice::from_result(&msi)),
+            vec![
+                RepackagingStrategy::Wrap,
+                RepackagingStrategy::Transform,
+                RepackagingStrategy::MachineWide
+            ]
+        );
+
+        let unknown = RepackagingAdvice::from_result(&result("tool.exe", InstallerFormat::Unknown));
+        assert_eq!(strategies(&unknown), vec![RepackagingStrategy::Capture]);
+    }
+
+    #[test]
+    fn test_advise_on_traits() {
+        let mut inno = result("setup.exe", InstallerFormat::InnoSetup);
+        let properties = &mut inno.metadata.properties;
+        properties.insert("installer_delivery".to_string(), "web".to_string());
+        properties.insert(
+            "download_urls".to_string(),
+            "https://example.com/app.7z".to_string(),
+        );
+        properties.insert("inno_privileges_required".to_string(), "lowest".to_string());
+        properties.insert("reboot_required".to_string(), "yes".to_string());
+        inno.files = vec![FileEntry {
+            path: PathBuf::from("redist/vc_runtime.msi"),
+            target_path: None,
+            size: 1024,
+            hash: None,
+            attributes: FileAttributes::default(),
+            compression: None,
+            is_speculative: false,
+            architecture: None,
+            modified: None,
+            source: None,
+        }];
+
+        let advice = RepackagingAdvice::from_result(&inno);
+        assert_eq!(
+            strategies(&advice),
+            vec![
+                RepackagingStrategy::ObtainOffline,
+                RepackagingStrategy::Wrap,
+                RepackagingStrategy::MachineWide,
+                RepackagingStrategy::ExtractMsi,
+                RepackagingStrategy::Review,
+            ]
+        );
+        assert!(advice.recommendations[0]
+            .detail
+            .contains("from https://example.com/app.7z"));
+        assert!(advice.recommendations[3].detail.contains("vc_runtime.msi"));
+    }
+}
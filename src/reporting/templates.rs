@@ -134,7 +134,7 @@ impl ReportTemplateData {
                 .manufacturer
                 .clone()
                 .unwrap_or_else(|| "Unknown".to_string()),
-            format: format!("{:?}", result.metadata.format),
+            format: result.metadata.format.to_string(),
             file_size_formatted: utils::format_file_size(result.metadata.file_size),
             file_hash_short: result.metadata.file_hash.chars().take(16).collect(),
         };
@@ -260,7 +260,6 @@ impl ReportTemplateData {
     fn create_registry_operations(operations: &[RegistryOperation]) -> Vec<RegistryOperationData> {
         operations
             .iter()
-            .take(50)
             .map(|op| match op {
                 RegistryOperation::CreateKey { key_path, .. } => RegistryOperationData {
                     operation_class: "bg-success".to_string(),
@@ -324,15 +323,29 @@ impl ReportTemplateData {
             .iter()
             .filter(|f| f.size > 50 * 1024 * 1024)
             .count(); // > 50MB
-
-        let (risk_level_text, risk_level_class, risk_icon) =
-            if executable_count > 10 || large_files > 5 {
-                ("High", "danger", "fa-exclamation-triangle")
-            } else if executable_count > 5 || large_files > 2 {
-                ("Medium", "warning", "fa-exclamation-circle")
-            } else {
-                ("Low", "success", "fa-check-circle")
-            };
+        let tampered = result
+            .metadata
+            .properties
+            .get("msix_tampered")
+            .is_some_and(|tampered| tampered == "true");
+        let high_risk_capabilities = result
+            .metadata
+            .properties
+            .contains_key("msix_high_risk_capabilities");
+        let uac_bypass = result
+            .metadata
+            .properties
+            .contains_key("uac_bypass_techniques");
+
+        let (risk_level_text, risk_level_class, risk_icon) = if uac_bypass {
+            ("Critical", "danger", "fa-skull-crossbones")
+        } else if tampered || executable_count > 10 || large_files > 5 {
+            ("High", "danger", "fa-exclamation-triangle")
+        } else if high_risk_capabilities || executable_count > 5 || large_files > 2 {
+            ("Medium", "warning", "fa-exclamation-circle")
+        } else {
+            ("Low", "success", "fa-check-circle")
+        };
 
         SecurityData {
             risk_level_class: risk_level_class.to_string(),
@@ -640,6 +653,10 @@ mod tests {
                     executable: true,
                 },
                 compression: None,
+                is_speculative: false,
+                architecture: None,
+                modified: None,
+                source: None,
             },
             FileEntry {
                 path: PathBuf::from("app/config/settings.ini"),
@@ -653,6 +670,10 @@ mod tests {
                     executable: false,
                 },
                 compression: None,
+                is_speculative: false,
+                architecture: None,
+                modified: None,
+                source: None,
             },
             FileEntry {
                 path: PathBuf::from("docs/readme.txt"),
@@ -666,6 +687,10 @@ mod tests {
                     executable: false,
                 },
                 compression: None,
+                is_speculative: false,
+                architecture: None,
+                modified: None,
+                source: None,
             },
         ];
 
@@ -716,6 +741,10 @@ mod tests {
                 executable: false,
             },
             compression: None,
+            is_speculative: false,
+            architecture: None,
+            modified: None,
+            source: None,
         }];
 
         let tree_data = ReportTemplateData::build_file_tree(&files);
@@ -0,0 +1,392 @@
+//! Standalone file tree export (JSON, GraphML, DOT)
+//!
+//! Dumps only the hierarchical file structure of an analysis result, with
+//! sizes and hashes, decoupled from the full report so it can be ingested by
+//! CMDB and asset inventory systems.
+
+use crate::core::{AnalysisResult, AnalyzerError, FileEntry, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Supported file tree export formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreeExportFormat {
+    Json,
+    GraphMl,
+    Dot,
+}
+
+impl TreeExportFormat {
+    /// Parse an explicit format name
+    pub fn parse(format: &str) -> Result<Self> {
+        match format.to_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "graphml" => Ok(Self::GraphMl),
+            "dot" | "gv" => Ok(Self::Dot),
+            _ => Err(AnalyzerError::config_error(format!(
+                "Unsupported tree export format: {}",
+                format
+            ))),
+        }
+    }
+
+    /// Detect format from the export file extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?;
+        Self::parse(ext).ok()
+    }
+
+    /// Determine the export format, preferring an explicit format over the extension
+    pub fn determine(explicit_format: Option<&str>, path: &Path) -> Result<Self> {
+        match explicit_format {
+            Some(format) => Self::parse(format),
+            None => Ok(Self::from_path(path).unwrap_or(Self::Json)),
+        }
+    }
+}
+
+/// Node of the exported file tree
+#[derive(Debug, Clone, Serialize)]
+pub struct TreeExportNode {
+    pub name: String,
+    pub path: String,
+    pub is_directory: bool,
+    /// File size, or the aggregated size of all descendants for directories
+    pub size: u64,
+    pub hash: Option<String>,
+    pub children: Vec<TreeExportNode>,
+}
+
+/// Exported file tree document
+#[derive(Debug, Clone, Serialize)]
+pub struct TreeExport {
+    pub source: String,
+    pub source_hash: String,
+    pub format: String,
+    pub total_files: usize,
+    pub total_directories: usize,
+    pub total_size: u64,
+    pub nodes: Vec<TreeExportNode>,
+}
+
+/// Builder for intermediate tree nodes keyed by name for stable ordering
+#[derive(Default)]
+struct NodeBuilder {
+    path: String,
+    file: Option<(u64, Option<String>)>,
+    children: BTreeMap<String, NodeBuilder>,
+}
+
+impl NodeBuilder {
+    fn into_node(self, name: String) -> TreeExportNode {
+        let mut children: Vec<TreeExportNode> = self
+            .children
+            .into_iter()
+            .map(|(name, child)| child.into_node(name))
+            .collect();
+
+        // Directories first, then files, each alphabetical
+        children.sort_by(|a, b| {
+            b.is_directory
+                .cmp(&a.is_directory)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+
+        match self.file {
+            Some((size, hash)) if children.is_empty() => TreeExportNode {
+                name,
+                path: self.path,
+                is_directory: false,
+                size,
+                hash,
+                children,
+            },
+            _ => TreeExportNode {
+                name,
+                path: self.path,
+                is_directory: true,
+                size: children.iter().map(|c| c.size).sum(),
+                hash: None,
+                children,
+            },
+        }
+    }
+}
+
+/// File tree exporter
+pub struct FileTreeExporter;
+
+impl FileTreeExporter {
+    /// Build the hierarchical tree from a flat file list
+    pub fn build_tree(files: &[FileEntry]) -> Vec<TreeExportNode> {
+        let mut root = NodeBuilder::default();
+
+        for file in files {
+            let path_str = file.path.to_string_lossy();
+            let parts: Vec<&str> = path_str
+                .split(['/', '\\'])
+                .filter(|s| !s.is_empty())
+                .collect();
+
+            if parts.is_empty() {
+                continue;
+            }
+
+            let mut node = &mut root;
+            for (depth, part) in parts.iter().enumerate() {
+                node = node.children.entry(part.to_string()).or_default();
+                if node.path.is_empty() {
+                    node.path = parts[..=depth].join("/");
+                }
+            }
+            node.file = Some((file.size, file.hash.clone()));
+        }
+
+        root.into_node(String::new()).children
+    }
+
+    /// Create the export document for an analysis result
+    pub fn create_export(result: &AnalysisResult) -> TreeExport {
+        let nodes = Self::build_tree(&result.files);
+
+        let mut total_files = 0;
+        let mut total_directories = 0;
+        Self::count_nodes(&nodes, &mut total_files, &mut total_directories);
+
+        let source = result
+            .source_file_path
+            .as_ref()
+            .and_then(|p| p.file_name())
+            .map(|name| name.to_string_lossy().to_string())
+            .or_else(|| result.metadata.product_name.clone())
+            .unwrap_or_else(|| "Unknown Package".to_string());
+
+        TreeExport {
+            source,
+            source_hash: result.metadata.file_hash.clone(),
+            format: result.metadata.format.to_string(),
+            total_files,
+            total_directories,
+            total_size: nodes.iter().map(|n| n.size).sum(),
+            nodes,
+        }
+    }
+
+    /// Render the file tree of an analysis result in the given format
+    pub fn export(result: &AnalysisResult, format: TreeExportFormat) -> Result<String> {
+        let export = Self::create_export(result);
+
+        match format {
+            TreeExportFormat::Json => {
+                serde_json::to_string_pretty(&export).map_err(AnalyzerError::SerializationError)
+            }
+            TreeExportFormat::GraphMl => Ok(Self::render_graphml(&export)),
+            TreeExportFormat::Dot => Ok(Self::render_dot(&export)),
+        }
+    }
+
+    /// Save the file tree of an analysis result to a file
+    pub async fn save(
+        result: &AnalysisResult,
+        format: TreeExportFormat,
+        output_path: &Path,
+    ) -> Result<()> {
+        let content = Self::export(result, format)?;
+        tokio::fs::write(crate::utils::extended_length(output_path), content).await?;
+        tracing::info!("File tree exported to: {}", output_path.display());
+        Ok(())
+    }
+
+    fn count_nodes(nodes: &[TreeExportNode], files: &mut usize, directories: &mut usize) {
+        for node in nodes {
+            if node.is_directory {
+                *directories += 1;
+            } else {
+                *files += 1;
+            }
+            Self::count_nodes(&node.children, files, directories);
+        }
+    }
+
+    /// Render as GraphML with the package as the root node
+    fn render_graphml(export: &TreeExport) -> String {
+        let mut nodes = String::new();
+        let mut edges = String::new();
+        let mut next_id = 1;
+
+        nodes.push_str(&format!(
+            "    <node id=\"n0\">\n      <data key=\"name\">{}</data>\n      <data key=\"type\">package</data>\n      <data key=\"size\">{}</data>\n      <data key=\"hash\">{}</data>\n    </node>\n",
+            xml_escape(&export.source),
+            export.total_size,
+            xml_escape(&export.source_hash)
+        ));
+
+        Self::walk(&export.nodes, 0, &mut next_id, &mut |id, parent, node| {
+            nodes.push_str(&format!(
+                "    <node id=\"n{}\">\n      <data key=\"name\">{}</data>\n      <data key=\"path\">{}</data>\n      <data key=\"type\">{}</data>\n      <data key=\"size\">{}</data>\n",
+                id,
+                xml_escape(&node.name),
+                xml_escape(&node.path),
+                if node.is_directory { "directory" } else { "file" },
+                node.size
+            ));
+            if let Some(hash) = &node.hash {
+                nodes.push_str(&format!(
+                    "      <data key=\"hash\">{}</data>\n",
+                    xml_escape(hash)
+                ));
+            }
+            nodes.push_str("    </node>\n");
+            edges.push_str(&format!(
+                "    <edge source=\"n{}\" target=\"n{}\"/>\n",
+                parent, id
+            ));
+        });
+
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+  <key id="name" for="node" attr.name="name" attr.type="string"/>
+  <key id="path" for="node" attr.name="path" attr.type="string"/>
+  <key id="type" for="node" attr.name="type" attr.type="string"/>
+  <key id="size" for="node" attr.name="size" attr.type="long"/>
+  <key id="hash" for="node" attr.name="hash" attr.type="string"/>
+  <graph id="file_tree" edgedefault="directed">
+{}{}  </graph>
+</graphml>
+"#,
+            nodes, edges
+        )
+    }
+
+    /// Render as a Graphviz DOT digraph with the package as the root node
+    fn render_dot(export: &TreeExport) -> String {
+        let mut dot = String::from("digraph file_tree {\n    rankdir=LR;\n    node [fontname=\"Helvetica\", fontsize=10];\n");
+
+        dot.push_str(&format!(
+            "    n0 [label=\"{}\\n{}\", shape=box3d];\n",
+            dot_escape(&export.source),
+            crate::utils::format_file_size(export.total_size)
+        ));
+
+        let mut next_id = 1;
+        Self::walk(&export.nodes, 0, &mut next_id, &mut |id, parent, node| {
+            dot.push_str(&format!(
+                "    n{} [label=\"{}\\n{}\", shape={}];\n    n{} -> n{};\n",
+                id,
+                dot_escape(&node.name),
+                crate::utils::format_file_size(node.size),
+                if node.is_directory { "folder" } else { "note" },
+                parent,
+                id
+            ));
+        });
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Depth-first walk assigning sequential node ids
+    fn walk(
+        nodes: &[TreeExportNode],
+        parent: usize,
+        next_id: &mut usize,
+        visit: &mut dyn FnMut(usize, usize, &TreeExportNode),
+    ) {
+        for node in nodes {
+            let id = *next_id;
+            *next_id += 1;
+            visit(id, parent, node);
+            Self::walk(&node.children, id, next_id, visit);
+        }
+    }
+}
+
+/// Escape text for XML content and attribute values
+pub(crate) fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Escape text for a quoted DOT string
+pub(crate) fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::FileAttributes;
+    use std::path::PathBuf;
+
+    fn file(path: &str, size: u64, hash: Option<&str>) -> FileEntry {
+        FileEntry {
+            path: PathBuf::from(path),
+            target_path: None,
+            size,
+            hash: hash.map(|h| h.to_string()),
+            attributes: FileAttributes::default(),
+            compression: None,
+            is_speculative: false,
+            architecture: None,
+            modified: None,
+            source: None,
+        }
+    }
+
+    #[test]
+    fn test_build_tree_aggregates_directory_sizes() {
+        let files = vec![
+            file("app/main.exe", 1024, Some("abc")),
+            file("app\\lib\\core.dll", 512, None),
+            file("readme.txt", 10, None),
+        ];
+
+        let nodes = FileTreeExporter::build_tree(&files);
+        assert_eq!(nodes.len(), 2);
+
+        let app = &nodes[0];
+        assert_eq!(app.name, "app");
+        assert!(app.is_directory);
+        assert_eq!(app.size, 1536);
+        assert_eq!(app.children[0].name, "lib");
+        assert_eq!(app.children[0].path, "app/lib");
+
+        let main = &app.children[1];
+        assert!(!main.is_directory);
+        assert_eq!(main.path, "app/main.exe");
+        assert_eq!(main.hash.as_deref(), Some("abc"));
+    }
+
+    #[test]
+    fn test_format_detection() {
+        assert_eq!(
+            TreeExportFormat::from_path(Path::new("tree.graphml")),
+            Some(TreeExportFormat::GraphMl)
+        );
+        assert_eq!(
+            TreeExportFormat::from_path(Path::new("tree.dot")),
+            Some(TreeExportFormat::Dot)
+        );
+        assert_eq!(TreeExportFormat::from_path(Path::new("tree.txt")), None);
+        assert_eq!(
+            TreeExportFormat::determine(Some("dot"), Path::new("tree.json")).unwrap(),
+            TreeExportFormat::Dot
+        );
+        assert_eq!(
+            TreeExportFormat::determine(None, Path::new("tree")).unwrap(),
+            TreeExportFormat::Json
+        );
+        assert!(TreeExportFormat::parse("yaml").is_err());
+    }
+
+    #[test]
+    fn test_escaping() {
+        assert_eq!(xml_escape("a<b>&\"c\""), "a&lt;b&gt;&amp;&quot;c&quot;");
+        assert_eq!(dot_escape("C:\\dir\"x\""), "C:\\\\dir\\\"x\\\"");
+    }
+}
@@ -0,0 +1,446 @@
+//! Vendor and product knowledge base
+//!
+//! Installers name their publisher in many ways, and the same vendor ships
+//! products under names that say nothing about it. The knowledge base maps
+//! MSI UpgradeCodes, product names and publisher spellings to a canonical
+//! vendor with its homepage and a product category, so reports, batch
+//! summaries and the results database can group installers by vendor.
+//!
+//! A curated table ships with the analyzer and is replaced by the `vendors`
+//! data pack once one is installed with `update --data`; a TOML or YAML file
+//! in the same layout extends or replaces it.
+
+use crate::core::{normalize_text, normalize_vendor, AnalysisResult, AnalyzerError, Result};
+use crate::policy::glob_match;
+use crate::reporting::correlation::{normalize_guid, ProductIdentity};
+use crate::updater::{DataPackKind, DataPackStore};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Curated table, see `vendors.toml`
+const CURATED: &str = include_str!("vendors.toml");
+
+/// Vendor with the spellings installers use for it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VendorEntry {
+    /// Canonical vendor name
+    pub name: String,
+    #[serde(default)]
+    pub homepage: Option<String>,
+    /// Category of the vendor's products unless a product sets its own
+    #[serde(default)]
+    pub category: Option<String>,
+    /// Publisher names in addition to `name`, as globs
+    #[serde(default)]
+    pub publishers: Vec<String>,
+}
+
+/// Product of a vendor
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProductEntry {
+    /// Canonical name of the vendor shipping the product
+    pub vendor: String,
+    /// Product names as globs, e.g. `Google Chrome*`
+    #[serde(default)]
+    pub names: Vec<String>,
+    /// MSI UpgradeCodes shared by all releases of the product
+    #[serde(default)]
+    pub upgrade_codes: Vec<String>,
+    #[serde(default)]
+    pub homepage: Option<String>,
+    #[serde(default)]
+    pub category: Option<String>,
+}
+
+/// Lookup table of vendors and products
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VendorKnowledgeBase {
+    /// Keep the curated entries in addition to the ones given
+    #[serde(default = "default_true", skip_serializing)]
+    pub include_defaults: bool,
+    #[serde(default)]
+    pub vendors: Vec<VendorEntry>,
+    #[serde(default)]
+    pub products: Vec<ProductEntry>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// What identified the vendor of an installer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VendorMatch {
+    UpgradeCode,
+    ProductName,
+    Publisher,
+}
+
+impl VendorMatch {
+    /// Value used in analysis properties
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::UpgradeCode => "upgrade_code",
+            Self::ProductName => "product_name",
+            Self::Publisher => "publisher",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        [Self::UpgradeCode, Self::ProductName, Self::Publisher]
+            .into_iter()
+            .find(|kind| kind.as_str() == value)
+    }
+
+    /// Human readable description
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::UpgradeCode => "upgrade code",
+            Self::ProductName => "product name",
+            Self::Publisher => "publisher",
+        }
+    }
+}
+
+/// Canonical vendor of an installer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VendorInfo {
+    pub vendor: String,
+    pub homepage: Option<String>,
+    pub category: Option<String>,
+    pub matched_by: VendorMatch,
+}
+
+impl VendorInfo {
+    /// Record the vendor as analysis properties
+    pub fn insert_properties(&self, result: &mut AnalysisResult) {
+        let properties = &mut result.metadata.properties;
+        properties.insert("vendor_canonical".to_string(), self.vendor.clone());
+        properties.insert(
+            "vendor_matched_by".to_string(),
+            self.matched_by.as_str().to_string(),
+        );
+        for (key, value) in [
+            ("vendor_homepage", &self.homepage),
+            ("vendor_category", &self.category),
+        ] {
+            if let Some(value) = value {
+                properties.insert(key.to_string(), value.clone());
+            }
+        }
+    }
+
+    /// Read a vendor recorded by [`VendorInfo::insert_properties`]
+    pub fn from_properties(properties: &HashMap<String, String>) -> Option<Self> {
+        Some(Self {
+            vendor: properties.get("vendor_canonical")?.clone(),
+            homepage: properties.get("vendor_homepage").cloned(),
+            category: properties.get("vendor_category").cloned(),
+            matched_by: properties
+                .get("vendor_matched_by")
+                .and_then(|value| VendorMatch::parse(value))
+                .unwrap_or(VendorMatch::Publisher),
+        })
+    }
+}
+
+impl VendorKnowledgeBase {
+    /// Curated table, from the installed data pack or shipped with the analyzer
+    pub fn curated() -> &'static Self {
+        static CURATED_BASE: OnceLock<VendorKnowledgeBase> = OnceLock::new();
+        CURATED_BASE.get_or_init(|| {
+            let installed = DataPackStore::new()
+                .read(DataPackKind::Vendors)
+                .and_then(|content| match toml::from_str::<Self>(&content) {
+                    Ok(base) => Some(base),
+                    Err(e) => {
+                        tracing::warn!("Ignoring invalid vendors data pack: {}", e);
+                        None
+                    }
+                });
+            let mut base = installed
+                .unwrap_or_else(|| toml::from_str(CURATED).expect("curated vendor table is valid"));
+            base.include_defaults = false;
+            base
+        })
+    }
+
+    /// Load a knowledge base, choosing TOML or YAML by file extension
+    ///
+    /// Unless the file sets `include_defaults = false`, its entries take
+    /// precedence over the curated ones.
+    pub async fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Err(AnalyzerError::file_not_found(path));
+        }
+        let content = tokio::fs::read_to_string(path).await?;
+        let is_yaml = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("yaml") || ext.eq_ignore_ascii_case("yml"));
+        let base: Self = if is_yaml {
+            serde_yaml::from_str(&content).map_err(|e| e.to_string())
+        } else {
+            toml::from_str(&content).map_err(|e| e.to_string())
+        }
+        .map_err(|e| {
+            AnalyzerError::config_error(format!(
+                "Invalid vendor knowledge base {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+
+        tracing::info!("Loaded vendor knowledge base from: {}", path.display());
+        Ok(base.resolve())
+    }
+
+    /// Append the curated entries, if requested
+    fn resolve(mut self) -> Self {
+        if self.include_defaults {
+            let curated = Self::curated();
+            self.vendors.extend(curated.vendors.iter().cloned());
+            self.products.extend(curated.products.iter().cloned());
+        }
+        self
+    }
+
+    /// Find the canonical vendor of a product
+    ///
+    /// UpgradeCodes identify a product best, then its name; the publisher
+    /// alone gives the vendor but no product category of its own.
+    pub fn lookup(&self, identity: &ProductIdentity) -> Option<VendorInfo> {
+        if let Some(upgrade_code) = &identity.upgrade_code {
+            let product = self.products.iter().find(|product| {
+                product
+                    .upgrade_codes
+                    .iter()
+                    .any(|code| normalize_guid(code).as_ref() == Some(upgrade_code))
+            });
+            if let Some(product) = product {
+                return Some(self.product_info(product, VendorMatch::UpgradeCode));
+            }
+        }
+
+        if let Some(name) = identity.product_name.as_deref().and_then(normalize_text) {
+            let product = self.products.iter().find(|product| {
+                product
+                    .names
+                    .iter()
+                    .any(|pattern| glob_match(pattern, &name))
+            });
+            if let Some(product) = product {
+                return Some(self.product_info(product, VendorMatch::ProductName));
+            }
+        }
+
+        let publisher = identity
+            .manufacturer
+            .as_deref()
+            .and_then(normalize_vendor)?;
+        let vendor = self.vendors.iter().find(|vendor| {
+            std::iter::once(&vendor.name)
+                .chain(&vendor.publishers)
+                .any(|pattern| {
+                    glob_match(pattern, &publisher)
+                        || normalize_vendor(pattern)
+                            .is_some_and(|pattern| pattern.eq_ignore_ascii_case(&publisher))
+                })
+        })?;
+        Some(VendorInfo {
+            vendor: vendor.name.clone(),
+            homepage: vendor.homepage.clone(),
+            category: vendor.category.clone(),
+            matched_by: VendorMatch::Publisher,
+        })
+    }
+
+    /// Look up the vendor of an analysis result and record it
+    pub fn enrich(&self, result: &mut AnalysisResult) -> Option<VendorInfo> {
+        let info = self.lookup(&ProductIdentity::from_result(result))?;
+        info.insert_properties(result);
+        Some(info)
+    }
+
+    /// Vendor details of a product, filled in from its vendor entry
+    fn product_info(&self, product: &ProductEntry, matched_by: VendorMatch) -> VendorInfo {
+        let vendor = self
+            .vendors
+            .iter()
+            .find(|vendor| vendor.name.eq_ignore_ascii_case(&product.vendor));
+        VendorInfo {
+            vendor: product.vendor.clone(),
+            homepage: product
+                .homepage
+                .clone()
+                .or_else(|| vendor.and_then(|v| v.homepage.clone())),
+            category: product
+                .category
+                .clone()
+                .or_else(|| vendor.and_then(|v| v.category.clone())),
+            matched_by,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identity(
+        product_name: Option<&str>,
+        manufacturer: Option<&str>,
+        upgrade_code: Option<&str>,
+    ) -> ProductIdentity {
+        ProductIdentity {
+            product_name: product_name.map(str::to_string),
+            manufacturer: manufacturer.map(str::to_string),
+            upgrade_code: upgrade_code.and_then(normalize_guid),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_curated_lookup() {
+        let base = VendorKnowledgeBase::curated();
+
+        let code = base
+            .lookup(&identity(
+                Some("Microsoft Visual Studio Code (User)"),
+                None,
+                None,
+            ))
+            .unwrap();
+        assert_eq!(code.vendor, "Microsoft Corporation");
+        assert_eq!(code.category.as_deref(), Some("Development"));
+        assert_eq!(
+            code.homepage.as_deref(),
+            Some("https://code.visualstudio.com")
+        );
+        assert_eq!(code.matched_by, VendorMatch::ProductName);
+
+        // Homepage and category come from the vendor entry
+        let git = base.lookup(&identity(Some("Git"), None, None)).unwrap();
+        assert_eq!(git.homepage.as_deref(), Some("https://git-scm.com"));
+        assert_eq!(git.category.as_deref(), Some("Development"));
+
+        let publisher = base
+            .lookup(&identity(
+                Some("Contoso Tool"),
+                Some("MICROSOFT CORP."),
+                None,
+            ))
+            .unwrap();
+        assert_eq!(publisher.vendor, "Microsoft Corporation");
+        assert_eq!(publisher.category, None);
+        assert_eq!(publisher.matched_by, VendorMatch::Publisher);
+
+        let tencent = base
+            .lookup(&identity(
+                None,
+                Some("Tencent Technology (Shenzhen) Company Limited"),
+                None,
+            ))
+            .unwrap();
+        assert_eq!(tencent.vendor, "Tencent");
+
+        assert_eq!(
+            base.lookup(&identity(Some("Contoso Tool"), Some("Contoso"), None)),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_load_extends_curated() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("vendors.toml");
+        tokio::fs::write(
+            &path,
+            r#"
+[[vendors]]
+name = "Contoso Ltd."
+homepage = "https://contoso.example"
+category = "Line of Business"
+publishers = ["Contoso*"]
+
+[[products]]
+vendor = "Contoso Ltd."
+upgrade_codes = ["6f3c9a2e-1b4d-4e8a-9c7f-2d5e8b1a4c60"]
+category = "Finance"
+
+[[products]]
+vendor = "Contoso Ltd."
+names = ["Google Chrome*"]
+"#,
+        )
+        .await
+        .unwrap();
+        let base = VendorKnowledgeBase::load(&path).await.unwrap();
+
+        let by_code = base
+            .lookup(&identity(
+                Some("Ledger"),
+                None,
+                Some("{6F3C9A2E-1B4D-4E8A-9C7F-2D5E8B1A4C60}"),
+            ))
+            .unwrap();
+        assert_eq!(by_code.vendor, "Contoso Ltd.");
+        assert_eq!(by_code.category.as_deref(), Some("Finance"));
+        assert_eq!(by_code.homepage.as_deref(), Some("https://contoso.example"));
+        assert_eq!(by_code.matched_by, VendorMatch::UpgradeCode);
+
+        // User entries take precedence, curated ones still apply
+        let chrome = base
+            .lookup(&identity(Some("Google Chrome"), None, None))
+            .unwrap();
+        assert_eq!(chrome.vendor, "Contoso Ltd.");
+        let firefox = base
+            .lookup(&identity(Some("Mozilla Firefox (x64 en-US)"), None, None))
+            .unwrap();
+        assert_eq!(firefox.vendor, "Mozilla");
+
+        let publisher = base
+            .lookup(&identity(None, Some("Contoso Pharmaceuticals"), None))
+            .unwrap();
+        assert_eq!(publisher.category.as_deref(), Some("Line of Business"));
+
+        tokio::fs::write(&path, "include_defaults = false\n")
+            .await
+            .unwrap();
+        let empty = VendorKnowledgeBase::load(&path).await.unwrap();
+        assert_eq!(
+            empty.lookup(&identity(Some("Google Chrome"), None, None)),
+            None
+        );
+
+        tokio::fs::write(&path, "[[vendors]]\nhomepage = \"x\"\n")
+            .await
+            .unwrap();
+        assert!(VendorKnowledgeBase::load(&path).await.is_err());
+    }
+
+    #[test]
+    fn test_vendor_properties_round_trip() {
+        let info = VendorInfo {
+            vendor: "Mozilla".to_string(),
+            homepage: Some("https://www.mozilla.org".to_string()),
+            category: None,
+            matched_by: VendorMatch::ProductName,
+        };
+        let mut properties = HashMap::new();
+        properties.insert("vendor_canonical".to_string(), info.vendor.clone());
+        properties.insert("vendor_matched_by".to_string(), "product_name".to_string());
+        properties.insert(
+            "vendor_homepage".to_string(),
+            "https://www.mozilla.org".to_string(),
+        );
+        assert_eq!(VendorInfo::from_properties(&properties), Some(info));
+        assert_eq!(VendorInfo::from_properties(&HashMap::new()), None);
+    }
+}
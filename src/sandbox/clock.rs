@@ -0,0 +1,118 @@
+//! Virtual clock for time-bombed installers
+//!
+//! Installers with expiring licenses or time-triggered behavior only show it
+//! at certain dates. The virtual clock describes the date and time the
+//! installer should see while the host clock stays untouched. No sandbox
+//! applies it yet, through detoured time APIs or an isolated guest clock in
+//! a VM backend, so sandbox runs configured with one are refused.
+
+use crate::core::{AnalyzerError, Result};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+
+/// Clock seen by the installer, running on from a chosen start time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VirtualClock {
+    /// Virtual time at the start of the run
+    pub start: DateTime<Utc>,
+    /// Difference between virtual and host time
+    pub offset: Duration,
+}
+
+impl VirtualClock {
+    /// Start a virtual clock at `start`, relative to the host time `now`
+    pub fn starting_at(start: DateTime<Utc>, now: DateTime<Utc>) -> Self {
+        Self {
+            start,
+            offset: start - now,
+        }
+    }
+
+    /// Virtual time corresponding to a host time
+    pub fn virtual_time(&self, host_time: DateTime<Utc>) -> DateTime<Utc> {
+        host_time + self.offset
+    }
+
+    /// Current virtual time
+    pub fn now(&self) -> DateTime<Utc> {
+        self.virtual_time(Utc::now())
+    }
+
+    /// Short description of the shift, for reports
+    pub fn describe(&self) -> String {
+        let days = self.offset.num_days();
+        let shift = match days {
+            0 => "same day as host".to_string(),
+            d if d > 0 => format!("{} days ahead of host", d),
+            d => format!("{} days behind host", -d),
+        };
+        format!("started at {} ({})", self.start.to_rfc3339(), shift)
+    }
+}
+
+/// Parse the start time of a virtual clock
+///
+/// Accepts an RFC 3339 timestamp (`2030-01-01T12:00:00Z`), a date
+/// (`2030-01-01`, midnight UTC) or an offset from `now` in days, weeks or
+/// years (`+90d`, `-2w`, `+1y`).
+pub fn parse_start_time(spec: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    let spec = spec.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(spec) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(spec, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc());
+    }
+    if let Some(offset) = parse_offset(spec) {
+        return now.checked_add_signed(offset).ok_or_else(|| {
+            AnalyzerError::config_error(format!("Virtual clock offset '{}' is out of range", spec))
+        });
+    }
+    Err(AnalyzerError::config_error(format!(
+        "Invalid virtual clock time '{}' (expected an RFC 3339 timestamp, YYYY-MM-DD or an offset like +90d)",
+        spec
+    )))
+}
+
+/// Parse a signed offset such as `+90d`, `-2w` or `+1y`
+fn parse_offset(spec: &str) -> Option<Duration> {
+    let sign = match spec.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let body = &spec[1..];
+    let unit = body.chars().last()?;
+    let amount: i64 = body[..body.len() - unit.len_utf8()].parse().ok()?;
+    let days = match unit {
+        'd' => amount,
+        'w' => amount.checked_mul(7)?,
+        'y' => amount.checked_mul(365)?,
+        _ => return None,
+    };
+    Duration::try_days(sign * days)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_virtual_clock() {
+        let now = DateTime::parse_from_rfc3339("2025-06-01T08:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let date = parse_start_time("2030-01-01", now).unwrap();
+        assert_eq!(date.to_rfc3339(), "2030-01-01T00:00:00+00:00");
+        let ahead = parse_start_time("+90d", now).unwrap();
+        assert_eq!(ahead - now, Duration::days(90));
+        let behind = parse_start_time("-2w", now).unwrap();
+        assert_eq!(now - behind, Duration::days(14));
+        assert!(parse_start_time("tomorrow", now).is_err());
+        assert!(parse_start_time("+5h", now).is_err());
+
+        let clock = VirtualClock::starting_at(ahead, now);
+        let later = now + Duration::hours(1);
+        assert_eq!(clock.virtual_time(later), ahead + Duration::hours(1));
+        assert!(clock.describe().contains("90 days ahead of host"));
+    }
+}
@@ -1,31 +1,127 @@
 //! Sandbox controller implementation
 
-use crate::core::{AnalysisResult, AnalyzerError, Result, SandboxConfig};
-use crate::sandbox::Sandbox;
+use crate::core::{
+    AnalysisResult, AnalyzerError, ApiCall, ResourceUsage, Result, SandboxConfig, SyncObject,
+};
+use crate::monitoring::{
+    ApiCallMonitor, PowerShellMonitor, ResourceMonitor, SyncObjectMonitor, SystemMonitor,
+    WmiMonitor,
+};
+use crate::sandbox::{Sandbox, SandboxSessions};
 use std::path::Path;
+use uuid::Uuid;
 
 /// Sandbox controller for managing dynamic analysis
 pub struct SandboxController {
     config: SandboxConfig,
+    sync_monitor: SyncObjectMonitor,
+    resource_monitor: ResourceMonitor,
+    api_monitor: ApiCallMonitor,
+    wmi_monitor: WmiMonitor,
+    powershell_monitor: PowerShellMonitor,
+    sessions: SandboxSessions,
+    current_session: Option<Uuid>,
 }
 
 impl SandboxController {
     /// Create a new sandbox controller with default configuration
     pub fn new() -> Self {
-        Self {
-            config: SandboxConfig::default(),
-        }
+        Self::with_config(SandboxConfig::default())
     }
 
     /// Create a new sandbox controller with custom configuration
     pub fn with_config(config: SandboxConfig) -> Self {
-        Self { config }
+        Self::with_sessions(config, SandboxSessions::new())
     }
-}
 
-impl Sandbox for SandboxController {
-    async fn analyze_installer(&mut self, installer_path: &Path) -> Result<AnalysisResult> {
-        // TODO: Implement sandbox analysis
+    /// Create a sandbox controller recording its runs in a shared session registry
+    pub fn with_sessions(config: SandboxConfig, sessions: SandboxSessions) -> Self {
+        Self {
+            config,
+            sync_monitor: SyncObjectMonitor::new(),
+            resource_monitor: ResourceMonitor::new(),
+            api_monitor: ApiCallMonitor::new(),
+            wmi_monitor: WmiMonitor::new(),
+            powershell_monitor: PowerShellMonitor::new(),
+            sessions,
+            current_session: None,
+        }
+    }
+
+    /// Sessions of this controller; clone the registry to watch runs from another task
+    pub fn sessions(&self) -> &SandboxSessions {
+        &self.sessions
+    }
+
+    /// Session of the run in progress or the last run
+    pub fn current_session(&self) -> Option<Uuid> {
+        self.current_session
+    }
+
+    /// Register a session for an installer so that it can be labeled before it runs
+    pub fn start_session(&mut self, installer_path: &Path) -> Uuid {
+        let id = self.sessions.start(installer_path);
+        self.current_session = Some(id);
+        id
+    }
+
+    /// Run the installer of a session started with [`Self::start_session`]
+    ///
+    /// Operations are added to the session while they are captured; the
+    /// final result also carries the session's labels and tags.
+    pub async fn run_session(&mut self, id: Uuid) -> Result<AnalysisResult> {
+        let session = self.sessions.get(id).ok_or_else(|| {
+            AnalyzerError::sandbox_error(format!("Unknown sandbox session {}", id))
+        })?;
+        if !session.is_active() {
+            return Err(AnalyzerError::sandbox_error(format!(
+                "Sandbox session {} already {}",
+                id, session.status
+            )));
+        }
+        self.current_session = Some(id);
+
+        let outcome = self
+            .execute(id, &session.installer_path)
+            .await
+            .map(|mut result| {
+                result.session_id = id;
+                if let Some(session) = self.sessions.get(id) {
+                    session.insert_properties(&mut result);
+                }
+                result
+            });
+        self.sessions.finish(id, &outcome);
+        outcome
+    }
+
+    /// Named synchronization objects the installer used during the last run
+    pub fn observed_sync_objects(&self) -> &[SyncObject] {
+        self.sync_monitor.get_objects()
+    }
+
+    /// Resource usage of the installer process tree during the last run
+    pub fn resource_usage(&self) -> &ResourceUsage {
+        self.resource_monitor.get_usage()
+    }
+
+    /// Clipboard, input hook and screen capture API calls of the last run
+    pub fn observed_api_calls(&self) -> &[ApiCall] {
+        self.api_monitor.get_calls()
+    }
+
+    /// Execute the installer and capture its operations into the session
+    async fn run_installer(
+        &mut self,
+        _session: Uuid,
+        installer_path: &Path,
+    ) -> Result<AnalysisResult> {
+        // TODO: Implement sandbox analysis, recording operations as they are
+        // captured with `self.sessions.record(session, ...)` and sampling the
+        // process tree into `self.resource_monitor` every interval; hooked
+        // clipboard, input and screen capture APIs go to `self.api_monitor`;
+        // the WMI activity trace and script block logging (event 4104) feed
+        // `self.wmi_monitor` and `self.powershell_monitor`
         // This is a placeholder implementation
         tracing::warn!(
             "Sandbox analysis not yet implemented for: {}",
@@ -37,6 +133,53 @@ impl Sandbox for SandboxController {
         ))
     }
 
+    /// Prepare the environment, run the installer and filter the capture
+    async fn execute(&mut self, session: Uuid, installer_path: &Path) -> Result<AnalysisResult> {
+        if self.config.fingerprint.is_enabled() {
+            return Err(AnalyzerError::sandbox_error(
+                "Sandbox disguise is not supported yet, the installer would see the real environment",
+            ));
+        }
+        if self.config.virtual_clock_start.is_some() {
+            return Err(AnalyzerError::sandbox_error(
+                "Virtual clock is not supported yet, the installer would see the host time",
+            ));
+        }
+
+        self.resource_monitor.start().await?;
+        self.api_monitor.start().await?;
+        self.wmi_monitor.start().await?;
+        self.powershell_monitor.start().await?;
+        let outcome = self.run_installer(session, installer_path).await;
+        self.powershell_monitor.stop().await?;
+        self.wmi_monitor.stop().await?;
+        self.api_monitor.stop().await?;
+        self.resource_monitor.stop().await?;
+        let mut result = outcome?;
+        let usage = self.resource_monitor.get_usage();
+        if !usage.samples.is_empty() {
+            result.resource_usage = Some(usage.clone());
+        }
+        result
+            .wmi_operations
+            .extend_from_slice(self.wmi_monitor.get_operations());
+        result
+            .powershell_script_blocks
+            .extend_from_slice(self.powershell_monitor.get_blocks());
+        if let Some(filter) = &self.config.noise_filter {
+            let stats = filter.apply(&mut result);
+            tracing::info!("Filtered {} system noise operations", stats.total());
+        }
+        Ok(result)
+    }
+}
+
+impl Sandbox for SandboxController {
+    async fn analyze_installer(&mut self, installer_path: &Path) -> Result<AnalysisResult> {
+        let id = self.start_session(installer_path);
+        self.run_session(id).await
+    }
+
     fn config(&self) -> &SandboxConfig {
         &self.config
     }
@@ -51,3 +194,29 @@ impl Default for SandboxController {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sandbox::SessionStatus;
+
+    #[tokio::test]
+    async fn test_failed_run_is_kept_as_session() {
+        let sessions = SandboxSessions::new();
+        let mut sandbox =
+            SandboxController::with_sessions(SandboxConfig::default(), sessions.clone());
+        let id = sandbox.start_session(Path::new("setup.exe"));
+        sessions.tag(id, "regression").unwrap();
+        assert_eq!(sessions.active().len(), 1);
+
+        // Runs are not implemented on this host and fail
+        assert!(sandbox.run_session(id).await.is_err());
+        assert_eq!(sandbox.current_session(), Some(id));
+        let session = sessions.get(id).unwrap();
+        assert_eq!(session.status, SessionStatus::Failed);
+        assert!(session.finished_at.is_some());
+        assert!(session.tags.contains("regression"));
+        assert!(sandbox.run_session(id).await.is_err());
+        assert!(sandbox.run_session(Uuid::new_v4()).await.is_err());
+    }
+}
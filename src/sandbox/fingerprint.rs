@@ -0,0 +1,141 @@
+//! Disguise of sandbox tells
+//!
+//! Installers that check for analysis environments look at the computer and
+//! user names, at how long the system has been up and at artifacts of
+//! virtual machines. A disguise picks random but plausible names and uptime
+//! and lists the VM artifacts to hide from the installer. No sandbox applies
+//! a disguise to a guest yet, so sandbox runs configured with one are
+//! refused.
+
+use crate::core::{FingerprintConfig, Result};
+use std::time::Duration;
+
+/// User names that look like a real person's account
+const USER_NAMES: &[&str] = &[
+    "alex", "chris", "jordan", "maria", "daniel", "laura", "kevin", "sarah", "tom", "anna",
+];
+
+/// Shortest and longest randomized uptime
+const MIN_UPTIME: Duration = Duration::from_secs(2 * 60 * 60);
+const MAX_UPTIME: Duration = Duration::from_secs(21 * 24 * 60 * 60);
+
+/// Artifacts of virtual machines that installers look for
+pub const VM_ARTIFACTS: &[&str] = &[
+    // Guest tools processes and services
+    "vmtoolsd.exe",
+    "vmwaretray.exe",
+    "VBoxService.exe",
+    "VBoxTray.exe",
+    "qemu-ga.exe",
+    "vmcompute.exe",
+    // Drivers
+    "vmmouse.sys",
+    "vmhgfs.sys",
+    "VBoxMouse.sys",
+    "VBoxGuest.sys",
+    "VBoxSF.sys",
+    // Registry keys
+    "HKLM\\SOFTWARE\\VMware, Inc.\\VMware Tools",
+    "HKLM\\SOFTWARE\\Oracle\\VirtualBox Guest Additions",
+    "HKLM\\HARDWARE\\ACPI\\DSDT\\VBOX__",
+    // MAC address vendor prefixes
+    "00:05:69",
+    "00:0C:29",
+    "00:50:56",
+    "08:00:27",
+];
+
+/// Randomized identity presented to the installer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SandboxDisguise {
+    pub machine_name: Option<String>,
+    pub user_name: Option<String>,
+    pub uptime: Option<Duration>,
+    /// Artifacts hidden from the installer, empty unless enabled
+    pub hidden_artifacts: Vec<&'static str>,
+}
+
+impl SandboxDisguise {
+    /// Generate a disguise for the enabled options
+    pub fn generate(config: &FingerprintConfig) -> Result<Self> {
+        let mut seed = [0u8; 16];
+        getrandom::getrandom(&mut seed)
+            .map_err(|e| crate::core::AnalyzerError::sandbox_error(e.to_string()))?;
+        Ok(Self::from_seed(config, u128::from_le_bytes(seed)))
+    }
+
+    /// Derive a disguise deterministically from a seed
+    pub fn from_seed(config: &FingerprintConfig, seed: u128) -> Self {
+        let machine_name = config.randomize_machine_name.then(|| {
+            // Windows names new installations DESKTOP- followed by 7 characters
+            const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+            let suffix: String = (0..7)
+                .map(|i| CHARSET[((seed >> (i * 6)) % CHARSET.len() as u128) as usize] as char)
+                .collect();
+            format!("DESKTOP-{}", suffix)
+        });
+        let user_name = config
+            .randomize_user_name
+            .then(|| USER_NAMES[((seed >> 48) % USER_NAMES.len() as u128) as usize].to_string());
+        let uptime = config.randomize_uptime.then(|| {
+            let range = (MAX_UPTIME - MIN_UPTIME).as_secs() as u128;
+            MIN_UPTIME + Duration::from_secs(((seed >> 64) % range) as u64)
+        });
+        let hidden_artifacts = if config.hide_vm_artifacts {
+            VM_ARTIFACTS.to_vec()
+        } else {
+            Vec::new()
+        };
+
+        Self {
+            machine_name,
+            user_name,
+            uptime,
+            hidden_artifacts,
+        }
+    }
+
+    /// Short description of what is disguised, for reports
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(name) = &self.machine_name {
+            parts.push(format!("machine name {}", name));
+        }
+        if let Some(name) = &self.user_name {
+            parts.push(format!("user name {}", name));
+        }
+        if let Some(uptime) = self.uptime {
+            parts.push(format!("uptime {}", crate::utils::format_duration(uptime)));
+        }
+        if !self.hidden_artifacts.is_empty() {
+            parts.push(format!(
+                "{} VM artifacts hidden",
+                self.hidden_artifacts.len()
+            ));
+        }
+        parts.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_disguise() {
+        let config = FingerprintConfig::parse("machine-name,uptime").unwrap();
+        let disguise =
+            SandboxDisguise::from_seed(&config, 0x1234_5678_9abc_def0_1122_3344_5566_7788);
+        let machine_name = disguise.machine_name.as_deref().unwrap();
+        assert!(machine_name.starts_with("DESKTOP-"));
+        assert_eq!(machine_name.len(), 15);
+        assert!(disguise.user_name.is_none());
+        let uptime = disguise.uptime.unwrap();
+        assert!(uptime >= MIN_UPTIME && uptime < MAX_UPTIME);
+        assert!(disguise.hidden_artifacts.is_empty());
+
+        let all = FingerprintConfig::parse("all").unwrap();
+        assert!(SandboxDisguise::generate(&all).unwrap().user_name.is_some());
+        assert!(FingerprintConfig::parse("hostname").is_err());
+    }
+}
@@ -3,10 +3,32 @@
 use crate::core::{AnalysisResult, Result, SandboxConfig};
 use std::path::Path;
 
+pub mod clock;
 pub mod controller;
+pub mod fingerprint;
+pub mod noise;
+pub mod resources;
+pub mod runs;
+pub mod script_activity;
+pub mod session;
 
 // Re-export main types
+pub use clock::VirtualClock;
 pub use controller::SandboxController;
+pub use fingerprint::SandboxDisguise;
+pub use noise::{NoiseFilter, NoiseStats};
+pub use resources::{ResourceFinding, ResourceKind, ResourceLimits, ResourceReport};
+pub use runs::RunComparison;
+pub use script_activity::ScriptActivityReport;
+pub use session::{SandboxSession, SandboxSessions, SessionStatus};
+
+/// Whether installers can be run in a sandbox on this host
+///
+/// Dynamic analysis relies on Windows and the `sandbox` feature; elsewhere
+/// only static analysis is available.
+pub fn is_supported() -> bool {
+    cfg!(all(windows, feature = "sandbox"))
+}
 
 /// Main sandbox controller trait
 #[allow(async_fn_in_trait)]
@@ -0,0 +1,306 @@
+//! Filtering of system noise from dynamic captures
+//!
+//! While an installer runs, Windows keeps working in the background: the
+//! prefetcher, Defender, the font cache and the search indexer write files
+//! and registry keys that have nothing to do with the installer. A noise
+//! filter drops operations of such processes, paths and registry keys before
+//! results are stored. Patterns are case-insensitive globs with `*` and `?`;
+//! paths are matched with `/` separators.
+
+use crate::core::{AnalysisResult, AnalyzerError, FileOperation, RegistryOperation, Result};
+use crate::policy::glob_match;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Background processes of Windows
+const DEFAULT_PROCESSES: &[&str] = &[
+    "MsMpEng.exe",
+    "MpCmdRun.exe",
+    "NisSrv.exe",
+    "SearchIndexer.exe",
+    "SearchProtocolHost.exe",
+    "SearchFilterHost.exe",
+    "WmiPrvSE.exe",
+    "taskhostw.exe",
+    "backgroundTaskHost.exe",
+    "RuntimeBroker.exe",
+    "CompatTelRunner.exe",
+    "sppsvc.exe",
+    "FontDrvHost.exe",
+    "audiodg.exe",
+];
+
+/// Files Windows maintains on its own
+const DEFAULT_PATHS: &[&str] = &[
+    "*/Windows/Prefetch/*",
+    "*/ProgramData/Microsoft/Windows Defender/*",
+    "*/Windows/ServiceProfiles/LocalService/AppData/Local/FontCache*",
+    "*/Windows/System32/FNTCACHE.DAT",
+    "*/AppData/Local/Microsoft/Windows/Explorer/*cache_*",
+    "*/AppData/Local/Microsoft/Windows/INetCache/*",
+    "*/Windows/System32/LogFiles/*",
+    "*/Windows/System32/winevt/Logs/*",
+    "*/Windows/System32/sru/*",
+    "*/Windows/SoftwareDistribution/*",
+    "*/Windows/appcompat/Programs/*",
+    "*/$Recycle.Bin/*",
+    "*/System Volume Information/*",
+    "*/pagefile.sys",
+];
+
+/// Registry keys Windows maintains on its own
+const DEFAULT_REGISTRY_KEYS: &[&str] = &[
+    "*\\Microsoft\\Windows Defender\\*",
+    "*\\CurrentVersion\\Explorer\\UserAssist\\*",
+    "*\\CurrentVersion\\Explorer\\RecentDocs*",
+    "*\\Shell\\BagMRU*",
+    "*\\Shell\\Bags\\*",
+    "*\\Services\\bam\\State\\*",
+    "*\\AppCompatFlags\\Compatibility Assistant\\Store*",
+    "*\\Microsoft\\Cryptography\\RNG*",
+    "*\\Internet Settings\\5.0\\Cache\\*",
+    "*\\CurrentVersion\\Diagnostics\\*",
+];
+
+/// Patterns of operations that are dropped from dynamic captures
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NoiseFilter {
+    /// Keep the curated default patterns in addition to the ones given
+    #[serde(default = "default_true", skip_serializing)]
+    pub include_defaults: bool,
+    /// Process names, e.g. `MsMpEng.exe`
+    #[serde(default)]
+    pub processes: Vec<String>,
+    /// File paths, e.g. `*/Windows/Prefetch/*`
+    #[serde(default)]
+    pub paths: Vec<String>,
+    /// Registry keys, e.g. `*\Microsoft\Windows Defender\*`
+    #[serde(default)]
+    pub registry_keys: Vec<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Operations a noise filter dropped
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NoiseStats {
+    pub file_operations: usize,
+    pub registry_operations: usize,
+    pub process_operations: usize,
+}
+
+impl NoiseStats {
+    /// Total number of dropped operations
+    pub fn total(&self) -> usize {
+        self.file_operations + self.registry_operations + self.process_operations
+    }
+}
+
+impl NoiseFilter {
+    /// Curated filter of Windows background activity
+    pub fn curated() -> Self {
+        let owned = |patterns: &[&str]| patterns.iter().map(|p| p.to_string()).collect();
+        Self {
+            include_defaults: false,
+            processes: owned(DEFAULT_PROCESSES),
+            paths: owned(DEFAULT_PATHS),
+            registry_keys: owned(DEFAULT_REGISTRY_KEYS),
+        }
+    }
+
+    /// Load a filter, choosing TOML or YAML by file extension
+    ///
+    /// Unless the file sets `include_defaults = false`, its patterns extend
+    /// the curated ones.
+    pub async fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Err(AnalyzerError::file_not_found(path));
+        }
+        let content = tokio::fs::read_to_string(path).await?;
+        let is_yaml = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("yaml") || ext.eq_ignore_ascii_case("yml"));
+        let filter: Self = if is_yaml {
+            serde_yaml::from_str(&content).map_err(|e| e.to_string())
+        } else {
+            toml::from_str(&content).map_err(|e| e.to_string())
+        }
+        .map_err(|e| {
+            AnalyzerError::config_error(format!("Invalid noise filter {}: {}", path.display(), e))
+        })?;
+
+        tracing::info!("Loaded noise filter from: {}", path.display());
+        Ok(filter.resolve())
+    }
+
+    /// Merge the curated patterns in, if requested
+    fn resolve(self) -> Self {
+        if !self.include_defaults {
+            return self;
+        }
+        let mut filter = Self::curated();
+        filter.processes.extend(self.processes);
+        filter.paths.extend(self.paths);
+        filter.registry_keys.extend(self.registry_keys);
+        filter
+    }
+
+    /// Drop matching operations from a result and record how many were dropped
+    pub fn apply(&self, result: &mut AnalysisResult) -> NoiseStats {
+        let mut stats = NoiseStats::default();
+
+        let before = result.file_operations.len();
+        result
+            .file_operations
+            .retain(|operation| !self.matches_file(operation));
+        stats.file_operations = before - result.file_operations.len();
+
+        let before = result.registry_operations.len();
+        result
+            .registry_operations
+            .retain(|operation| !self.matches_registry(operation));
+        stats.registry_operations = before - result.registry_operations.len();
+
+        let before = result.process_operations.len();
+        result
+            .process_operations
+            .retain(|operation| !self.matches_process(&operation.process_name));
+        stats.process_operations = before - result.process_operations.len();
+
+        if stats.total() > 0 {
+            result.metadata.properties.insert(
+                "noise_filtered_operations".to_string(),
+                format!(
+                    "{} ({} file, {} registry, {} process)",
+                    stats.total(),
+                    stats.file_operations,
+                    stats.registry_operations,
+                    stats.process_operations
+                ),
+            );
+        }
+        stats
+    }
+
+    fn matches_process(&self, process_name: &str) -> bool {
+        self.processes
+            .iter()
+            .any(|pattern| glob_match(pattern, process_name))
+    }
+
+    fn matches_path(&self, path: &Path) -> bool {
+        let path = path.to_string_lossy().replace('\\', "/");
+        self.paths
+            .iter()
+            .any(|pattern| glob_match(&pattern.replace('\\', "/"), &path))
+    }
+
+    fn matches_file(&self, operation: &FileOperation) -> bool {
+        match operation {
+            FileOperation::Create { path, .. }
+            | FileOperation::Write { path, .. }
+            | FileOperation::Delete { path, .. }
+            | FileOperation::SetAttributes { path, .. } => self.matches_path(path),
+            FileOperation::Move {
+                from_path, to_path, ..
+            } => self.matches_path(from_path) && self.matches_path(to_path),
+        }
+    }
+
+    fn matches_registry(&self, operation: &RegistryOperation) -> bool {
+        let key_path = match operation {
+            RegistryOperation::CreateKey { key_path, .. }
+            | RegistryOperation::SetValue { key_path, .. }
+            | RegistryOperation::DeleteKey { key_path, .. }
+            | RegistryOperation::DeleteValue { key_path, .. } => key_path,
+        };
+        self.registry_keys
+            .iter()
+            .any(|pattern| glob_match(pattern, key_path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{InstallerMetadata, ProcessOpType, ProcessOperation};
+    use chrono::Utc;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_filter_noise() {
+        let now = Utc::now();
+        let mut result = AnalysisResult {
+            session_id: uuid::Uuid::new_v4(),
+            metadata: InstallerMetadata {
+                created_at: now,
+                ..Default::default()
+            },
+            registry_operations: vec![
+                RegistryOperation::CreateKey {
+                    key_path: "HKLM\\SOFTWARE\\Microsoft\\Windows Defender\\Scan".to_string(),
+                    timestamp: now,
+                    process_id: None,
+                },
+                RegistryOperation::CreateKey {
+                    key_path: "HKLM\\SOFTWARE\\Contoso\\App".to_string(),
+                    timestamp: now,
+                    process_id: None,
+                },
+            ],
+            file_operations: [
+                "C:\\Windows\\Prefetch\\SETUP.EXE-1234.pf",
+                "C:\\App\\app.exe",
+            ]
+            .iter()
+            .map(|path| FileOperation::Create {
+                path: PathBuf::from(path),
+                size: 1,
+                timestamp: now,
+                process_id: None,
+            })
+            .collect(),
+            process_operations: ["MsMpEng.exe", "setup.exe"]
+                .iter()
+                .map(|name| ProcessOperation {
+                    operation_type: ProcessOpType::Create,
+                    process_id: 1,
+                    process_name: name.to_string(),
+                    command_line: None,
+                    parent_process_id: None,
+                    timestamp: now,
+                })
+                .collect(),
+            analyzed_at: now,
+            dynamic_analysis: true,
+            ..Default::default()
+        };
+
+        let filter: NoiseFilter = toml::from_str("paths = [\"C:/App/*\"]").unwrap();
+        let filter = filter.resolve();
+        assert!(filter.paths.len() > DEFAULT_PATHS.len());
+
+        let stats = NoiseFilter::curated().apply(&mut result);
+        assert_eq!(
+            stats,
+            NoiseStats {
+                file_operations: 1,
+                registry_operations: 1,
+                process_operations: 1,
+            }
+        );
+        assert_eq!(result.file_operations.len(), 1);
+        assert_eq!(result.process_operations[0].process_name, "setup.exe");
+        assert_eq!(
+            result.metadata.properties["noise_filtered_operations"],
+            "3 (1 file, 1 registry, 1 process)"
+        );
+
+        assert_eq!(filter.apply(&mut result).file_operations, 1);
+        assert!(toml::from_str::<NoiseFilter>("path = []").is_err());
+    }
+}
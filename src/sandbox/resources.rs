@@ -0,0 +1,301 @@
+//! Assessment of the resources an installer used during a sandbox run
+//!
+//! Installers that peg every core for minutes, balloon to gigabytes of
+//! memory, write far more than they install or leak handles hurt machines
+//! during mass deployment. The samples of the resource monitor are checked
+//! against limits that ordinary installers stay well below.
+
+use crate::core::{AnalysisResult, ResourceSample, ResourceUsage};
+use crate::utils::format_file_size;
+use std::fmt;
+
+/// Resource a finding is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ResourceKind {
+    Cpu,
+    Memory,
+    Disk,
+    Handles,
+}
+
+impl ResourceKind {
+    /// Display name of the kind
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Cpu => "CPU",
+            Self::Memory => "Memory",
+            Self::Disk => "Disk",
+            Self::Handles => "Handles",
+        }
+    }
+}
+
+impl fmt::Display for ResourceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Limits above which an installer counts as pathological
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// CPU utilization, in percent of one core, kept up over `cpu_window_ms`
+    pub sustained_cpu_percent: u64,
+    pub cpu_window_ms: u64,
+    pub peak_memory_bytes: u64,
+    pub disk_bytes_written: u64,
+    pub peak_handle_count: u32,
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        Self {
+            sustained_cpu_percent: 180,
+            cpu_window_ms: 60_000,
+            peak_memory_bytes: 2 << 30,
+            disk_bytes_written: 10 << 30,
+            peak_handle_count: 10_000,
+        }
+    }
+}
+
+/// Resource use above a limit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResourceFinding {
+    pub kind: ResourceKind,
+    pub description: String,
+}
+
+/// Resource use of a sandbox run and the limits it exceeded
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResourceReport {
+    pub duration_ms: u64,
+    pub cpu_time_ms: u64,
+    /// Highest CPU utilization kept up over the CPU window, in percent of one core
+    pub sustained_cpu_percent: u64,
+    pub peak_memory_bytes: u64,
+    pub disk_bytes_written: u64,
+    pub peak_handle_count: u32,
+    pub peak_process_count: u32,
+    pub findings: Vec<ResourceFinding>,
+}
+
+impl ResourceReport {
+    /// Assess the samples of a run against the default limits
+    pub fn assess(usage: &ResourceUsage) -> Self {
+        Self::assess_with(usage, &ResourceLimits::default())
+    }
+
+    /// Assess the samples of a run against custom limits
+    pub fn assess_with(usage: &ResourceUsage, limits: &ResourceLimits) -> Self {
+        let mut report = Self {
+            duration_ms: usage.duration().as_millis() as u64,
+            cpu_time_ms: usage.cpu_time().as_millis() as u64,
+            sustained_cpu_percent: sustained_cpu_percent(usage, limits.cpu_window_ms),
+            peak_memory_bytes: usage.peak_memory_bytes(),
+            disk_bytes_written: usage.disk_bytes_written(),
+            peak_handle_count: usage.peak_handle_count(),
+            peak_process_count: usage.peak_process_count(),
+            findings: Vec::new(),
+        };
+
+        if report.sustained_cpu_percent >= limits.sustained_cpu_percent {
+            report.add(
+                ResourceKind::Cpu,
+                format!(
+                    "{}% of one core for at least {}s",
+                    report.sustained_cpu_percent,
+                    limits.cpu_window_ms / 1000
+                ),
+            );
+        }
+        if report.peak_memory_bytes >= limits.peak_memory_bytes {
+            report.add(
+                ResourceKind::Memory,
+                format!(
+                    "peak working set of {}",
+                    format_file_size(report.peak_memory_bytes)
+                ),
+            );
+        }
+        if report.disk_bytes_written >= limits.disk_bytes_written {
+            report.add(
+                ResourceKind::Disk,
+                format!("{} written", format_file_size(report.disk_bytes_written)),
+            );
+        }
+        if report.peak_handle_count >= limits.peak_handle_count {
+            report.add(
+                ResourceKind::Handles,
+                format!("{} open handles", report.peak_handle_count),
+            );
+        }
+        report
+    }
+
+    fn add(&mut self, kind: ResourceKind, description: String) {
+        self.findings.push(ResourceFinding { kind, description });
+    }
+
+    /// Findings as `Kind: description` entries
+    fn describe_findings(&self) -> String {
+        let findings: Vec<String> = self
+            .findings
+            .iter()
+            .map(|finding| format!("{}: {}", finding.kind, finding.description))
+            .collect();
+        findings.join("; ")
+    }
+
+    /// Warning for installers that exceeded a limit
+    pub fn warning(&self) -> Option<String> {
+        (!self.findings.is_empty()).then(|| {
+            format!(
+                "Installer used excessive resources: {}",
+                self.describe_findings()
+            )
+        })
+    }
+
+    /// Record the totals and findings as analysis properties
+    pub fn insert_properties(&self, result: &mut AnalysisResult) {
+        let properties = &mut result.metadata.properties;
+        properties.insert(
+            "resource_cpu_time_ms".to_string(),
+            self.cpu_time_ms.to_string(),
+        );
+        properties.insert(
+            "resource_sustained_cpu_percent".to_string(),
+            self.sustained_cpu_percent.to_string(),
+        );
+        properties.insert(
+            "resource_peak_memory".to_string(),
+            format_file_size(self.peak_memory_bytes),
+        );
+        properties.insert(
+            "resource_disk_written".to_string(),
+            format_file_size(self.disk_bytes_written),
+        );
+        properties.insert(
+            "resource_peak_handles".to_string(),
+            self.peak_handle_count.to_string(),
+        );
+        properties.insert(
+            "resource_peak_processes".to_string(),
+            self.peak_process_count.to_string(),
+        );
+        if !self.findings.is_empty() {
+            properties.insert("resource_findings".to_string(), self.describe_findings());
+        }
+    }
+}
+
+/// Highest CPU utilization over any stretch of at least `window_ms`
+///
+/// Stretches start and end at samples; runs shorter than the window are
+/// measured over their whole length.
+fn sustained_cpu_percent(usage: &ResourceUsage, window_ms: u64) -> u64 {
+    let samples = &usage.samples;
+    let (Some(first), Some(last)) = (samples.first(), samples.last()) else {
+        return 0;
+    };
+    let utilization = |from: &ResourceSample, to: &ResourceSample| {
+        let elapsed = to.elapsed_ms.saturating_sub(from.elapsed_ms);
+        let cpu = to.cpu_time_ms.saturating_sub(from.cpu_time_ms);
+        (elapsed > 0).then(|| cpu * 100 / elapsed)
+    };
+
+    if last.elapsed_ms.saturating_sub(first.elapsed_ms) < window_ms {
+        return utilization(first, last).unwrap_or(0);
+    }
+    let mut peak = 0;
+    let mut end = 0;
+    for (start, from) in samples.iter().enumerate() {
+        end = end.max(start);
+        while end < samples.len() && samples[end].elapsed_ms - from.elapsed_ms < window_ms {
+            end += 1;
+        }
+        let Some(to) = samples.get(end) else {
+            break;
+        };
+        peak = peak.max(utilization(from, to).unwrap_or(0));
+    }
+    peak
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(elapsed_s: u64, cpu_s: u64, memory_mb: u64, written_mb: u64) -> ResourceSample {
+        ResourceSample {
+            elapsed_ms: elapsed_s * 1000,
+            cpu_time_ms: cpu_s * 1000,
+            memory_bytes: memory_mb << 20,
+            disk_bytes_written: written_mb << 20,
+            handle_count: 200,
+            process_count: 2,
+        }
+    }
+
+    #[test]
+    fn test_ordinary_installer_has_no_findings() {
+        let usage = ResourceUsage {
+            interval_ms: 1000,
+            samples: (0..120).map(|t| sample(t, t / 4, 80, t * 2)).collect(),
+        };
+        let report = ResourceReport::assess(&usage);
+
+        assert_eq!(report.duration_ms, 119_000);
+        assert_eq!(report.cpu_time_ms, 29_000);
+        assert_eq!(report.sustained_cpu_percent, 25);
+        assert_eq!(report.peak_memory_bytes, 80 << 20);
+        assert!(report.findings.is_empty());
+        assert!(report.warning().is_none());
+    }
+
+    #[test]
+    fn test_pathological_installer() {
+        // Idle for a minute, then four cores busy for 90 seconds
+        let mut samples: Vec<ResourceSample> = (0..60).map(|t| sample(t, 0, 100, 0)).collect();
+        samples.extend((60..150).map(|t| sample(t, (t - 60) * 4, 3 << 10, 12 << 10)));
+        samples[100].handle_count = 25_000;
+        let usage = ResourceUsage {
+            interval_ms: 1000,
+            samples,
+        };
+        let report = ResourceReport::assess(&usage);
+
+        assert_eq!(report.sustained_cpu_percent, 400);
+        let kinds: Vec<ResourceKind> = report.findings.iter().map(|f| f.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ResourceKind::Cpu,
+                ResourceKind::Memory,
+                ResourceKind::Disk,
+                ResourceKind::Handles
+            ]
+        );
+        assert!(report.warning().unwrap().contains("25000 open handles"));
+
+        let sessions = crate::sandbox::SandboxSessions::new();
+        let id = sessions.start(std::path::Path::new("setup.exe"));
+        let mut result = sessions.intermediate_result(id).unwrap();
+        report.insert_properties(&mut result);
+        let properties = &result.metadata.properties;
+        assert_eq!(properties["resource_sustained_cpu_percent"], "400");
+        assert_eq!(properties["resource_peak_handles"], "25000");
+        assert!(properties["resource_findings"].starts_with("CPU: "));
+    }
+
+    #[test]
+    fn test_short_run_is_measured_whole() {
+        let usage = ResourceUsage {
+            interval_ms: 500,
+            samples: vec![sample(0, 0, 10, 0), sample(10, 15, 10, 0)],
+        };
+        assert_eq!(sustained_cpu_percent(&usage, 60_000), 150);
+        assert_eq!(sustained_cpu_percent(&ResourceUsage::default(), 60_000), 0);
+    }
+}
@@ -0,0 +1,340 @@
+//! Comparison of repeated sandbox runs
+//!
+//! Running an installer several times in clean environments and comparing
+//! what it did reveals behavior that depends on chance, time or the
+//! environment: actions seen in some runs but not in others are
+//! nondeterministic or conditional. Timestamps, process IDs and byte counts
+//! always differ between runs and are left out of the comparison.
+//!
+//! The sandbox cannot restore a clean environment between runs yet, so
+//! `sandbox --runs` above one is refused and only library users compare
+//! captures they made themselves.
+
+use crate::core::{
+    AnalysisResult, FileOperation, NetworkOpType, ProcessOpType, RegistryOperation, RegistryValue,
+};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Kind of a captured action
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ActionKind {
+    File,
+    Registry,
+    Process,
+    Network,
+}
+
+impl ActionKind {
+    /// Display name of the kind
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::File => "File",
+            Self::Registry => "Registry",
+            Self::Process => "Process",
+            Self::Network => "Network",
+        }
+    }
+}
+
+impl fmt::Display for ActionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Action captured in at least one run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunAction {
+    pub kind: ActionKind,
+    pub description: String,
+    /// Number of runs the action was captured in
+    pub seen_in: usize,
+}
+
+/// Actions of several runs of the same installer
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RunComparison {
+    pub runs: usize,
+    /// All distinct actions, ordered by kind and description
+    pub actions: Vec<RunAction>,
+}
+
+impl RunComparison {
+    /// Compare the behavior captured by each run
+    pub fn compare(results: &[AnalysisResult]) -> Self {
+        let mut seen: BTreeMap<(ActionKind, String), usize> = BTreeMap::new();
+        for result in results {
+            let mut actions = behavior(result);
+            actions.sort();
+            actions.dedup();
+            for action in actions {
+                *seen.entry(action).or_default() += 1;
+            }
+        }
+
+        Self {
+            runs: results.len(),
+            actions: seen
+                .into_iter()
+                .map(|((kind, description), seen_in)| RunAction {
+                    kind,
+                    description,
+                    seen_in,
+                })
+                .collect(),
+        }
+    }
+
+    /// Actions that were not captured in every run
+    pub fn nondeterministic(&self) -> impl Iterator<Item = &RunAction> {
+        self.actions
+            .iter()
+            .filter(move |action| action.seen_in < self.runs)
+    }
+
+    /// Record the run count and the nondeterministic actions as analysis properties
+    pub fn insert_properties(&self, result: &mut AnalysisResult) {
+        if self.runs < 2 {
+            return;
+        }
+
+        let properties = &mut result.metadata.properties;
+        properties.insert("sandbox_runs".to_string(), self.runs.to_string());
+        let varying: Vec<&RunAction> = self.nondeterministic().collect();
+        properties.insert(
+            "nondeterministic_action_count".to_string(),
+            varying.len().to_string(),
+        );
+        if varying.is_empty() {
+            return;
+        }
+
+        let descriptions: Vec<String> = varying
+            .iter()
+            .take(15)
+            .map(|action| {
+                format!(
+                    "{}: {} ({}/{} runs)",
+                    action.kind, action.description, action.seen_in, self.runs
+                )
+            })
+            .collect();
+        properties.insert(
+            "nondeterministic_actions".to_string(),
+            descriptions.join("; "),
+        );
+    }
+}
+
+/// Actions of one run, without the details that differ between any two runs
+fn behavior(result: &AnalysisResult) -> Vec<(ActionKind, String)> {
+    let files = result.file_operations.iter().map(|operation| {
+        let description = match operation {
+            FileOperation::Create { path, .. } => format!("create {}", path.display()),
+            FileOperation::Write { path, .. } => format!("write {}", path.display()),
+            FileOperation::Delete { path, .. } => format!("delete {}", path.display()),
+            FileOperation::Move {
+                from_path, to_path, ..
+            } => format!("move {} to {}", from_path.display(), to_path.display()),
+            FileOperation::SetAttributes { path, .. } => {
+                format!("set attributes of {}", path.display())
+            }
+        };
+        (ActionKind::File, description)
+    });
+    let registry = result.registry_operations.iter().map(|operation| {
+        let description = match operation {
+            RegistryOperation::CreateKey { key_path, .. } => format!("create {}", key_path),
+            RegistryOperation::SetValue {
+                key_path,
+                value_name,
+                value_data,
+                ..
+            } => format!(
+                "set {}\\{} = {}",
+                key_path,
+                value_name,
+                format_value(value_data)
+            ),
+            RegistryOperation::DeleteKey { key_path, .. } => format!("delete {}", key_path),
+            RegistryOperation::DeleteValue {
+                key_path,
+                value_name,
+                ..
+            } => format!("delete {}\\{}", key_path, value_name),
+        };
+        (ActionKind::Registry, description)
+    });
+    let processes = result.process_operations.iter().map(|operation| {
+        let verb = match operation.operation_type {
+            ProcessOpType::Create => "start",
+            ProcessOpType::Terminate => "terminate",
+        };
+        let description = match &operation.command_line {
+            Some(command_line) => format!("{} {}", verb, command_line),
+            None => format!("{} {}", verb, operation.process_name),
+        };
+        (ActionKind::Process, description)
+    });
+    let network = result.network_operations.iter().map(|operation| {
+        let verb = match operation.operation_type {
+            NetworkOpType::Connect => "connect to",
+            NetworkOpType::Send => "send to",
+            NetworkOpType::Receive => "receive from",
+            NetworkOpType::Disconnect => "disconnect from",
+        };
+        let description = format!(
+            "{} {} ({})",
+            verb, operation.remote_address, operation.protocol
+        );
+        (ActionKind::Network, description)
+    });
+
+    files
+        .chain(registry)
+        .chain(processes)
+        .chain(network)
+        .collect()
+}
+
+fn format_value(value: &RegistryValue) -> String {
+    match value {
+        RegistryValue::String(s) => s.clone(),
+        RegistryValue::DWord(d) => format!("0x{:08x}", d),
+        RegistryValue::QWord(q) => format!("0x{:016x}", q),
+        RegistryValue::Binary(b) => format!("Binary ({} bytes)", b.len()),
+        RegistryValue::MultiString(strings) => strings.join(", "),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{InstallerMetadata, NetworkOperation, ProcessOperation, RegistryValueType};
+    use chrono::Utc;
+    use std::path::PathBuf;
+
+    fn run(paths: &[&str]) -> AnalysisResult {
+        AnalysisResult {
+            session_id: uuid::Uuid::new_v4(),
+            metadata: InstallerMetadata {
+                created_at: Utc::now(),
+                ..Default::default()
+            },
+            file_operations: paths
+                .iter()
+                .map(|path| FileOperation::Create {
+                    path: PathBuf::from(path),
+                    size: 1,
+                    timestamp: Utc::now(),
+                    process_id: None,
+                })
+                .collect(),
+            analyzed_at: Utc::now(),
+            dynamic_analysis: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_compare_runs() {
+        let runs = [
+            run(&["C:\\App\\app.exe", "C:\\Temp\\a.tmp"]),
+            run(&["C:\\App\\app.exe", "C:\\Temp\\b.tmp"]),
+            run(&["C:\\App\\app.exe", "C:\\Temp\\a.tmp"]),
+        ];
+        let comparison = RunComparison::compare(&runs);
+        assert_eq!(comparison.runs, 3);
+        assert_eq!(comparison.actions.len(), 3);
+        let varying: Vec<(&str, usize)> = comparison
+            .nondeterministic()
+            .map(|action| (action.description.as_str(), action.seen_in))
+            .collect();
+        assert_eq!(
+            varying,
+            vec![("create C:\\Temp\\a.tmp", 2), ("create C:\\Temp\\b.tmp", 1)]
+        );
+
+        let mut result = run(&[]);
+        comparison.insert_properties(&mut result);
+        let properties = &result.metadata.properties;
+        assert_eq!(properties["sandbox_runs"], "3");
+        assert_eq!(properties["nondeterministic_action_count"], "2");
+        assert!(properties["nondeterministic_actions"].contains("(1/3 runs)"));
+    }
+
+    /// Capture of one run with a varying timestamp, process ID and byte count
+    fn capture(run_index: u32, update_url: &str, install_dir: &str) -> AnalysisResult {
+        let timestamp = Utc::now() + chrono::Duration::seconds(run_index.into());
+        let mut result = run(&[]);
+        result.registry_operations = vec![RegistryOperation::SetValue {
+            key_path: "HKLM\\SOFTWARE\\Example".to_string(),
+            value_name: "InstallDir".to_string(),
+            value_type: RegistryValueType::String,
+            value_data: RegistryValue::String(install_dir.to_string()),
+            timestamp,
+            process_id: Some(1000 + run_index),
+        }];
+        result.process_operations = vec![ProcessOperation {
+            operation_type: ProcessOpType::Create,
+            process_id: 2000 + run_index,
+            process_name: "setup.exe".to_string(),
+            command_line: Some("setup.exe /S".to_string()),
+            parent_process_id: Some(1000 + run_index),
+            timestamp,
+        }];
+        result.network_operations = vec![NetworkOperation {
+            operation_type: NetworkOpType::Connect,
+            local_address: format!("10.0.0.2:{}", 50000 + run_index),
+            remote_address: update_url.to_string(),
+            protocol: "TCP".to_string(),
+            bytes_transferred: 100 * u64::from(run_index),
+            timestamp,
+            process_id: Some(2000 + run_index),
+        }];
+        result
+    }
+
+    #[test]
+    fn test_compare_synthetic_captures() {
+        let runs = [
+            capture(1, "203.0.113.10:443", "C:\\Program Files\\Example"),
+            capture(2, "203.0.113.10:443", "C:\\Program Files\\Example"),
+            capture(3, "198.51.100.7:443", "C:\\Program Files\\Example"),
+        ];
+        let comparison = RunComparison::compare(&runs);
+        assert_eq!(comparison.runs, 3);
+
+        // Timestamps, process IDs, local ports and byte counts are ignored
+        let varying: Vec<(ActionKind, &str, usize)> = comparison
+            .nondeterministic()
+            .map(|action| (action.kind, action.description.as_str(), action.seen_in))
+            .collect();
+        assert_eq!(
+            varying,
+            vec![
+                (ActionKind::Network, "connect to 198.51.100.7:443 (TCP)", 1),
+                (ActionKind::Network, "connect to 203.0.113.10:443 (TCP)", 2),
+            ]
+        );
+        let stable: Vec<&str> = comparison
+            .actions
+            .iter()
+            .filter(|action| action.seen_in == 3)
+            .map(|action| action.description.as_str())
+            .collect();
+        assert_eq!(
+            stable,
+            vec![
+                "set HKLM\\SOFTWARE\\Example\\InstallDir = C:\\Program Files\\Example",
+                "start setup.exe /S",
+            ]
+        );
+
+        // A single run has nothing to compare against
+        let mut result = run(&[]);
+        RunComparison::compare(&runs[..1]).insert_properties(&mut result);
+        assert!(!result.metadata.properties.contains_key("sandbox_runs"));
+    }
+}
@@ -0,0 +1,219 @@
+//! WMI and PowerShell activity of the installer during a sandbox run
+//!
+//! Many modern installers do their real work in PowerShell scripts or start
+//! helpers through WMI, where it shows up neither as files nor as child
+//! processes of the installer. Captured WMI operations and script blocks
+//! are narrowed down to the installer's process tree, including the Windows
+//! Installer service and processes started through WMI, and summarized.
+//! Permanent WMI event subscriptions are a persistence mechanism and are
+//! flagged.
+
+use crate::core::{AnalysisResult, WmiOperation};
+use crate::reporting::ProcessAttribution;
+use std::collections::BTreeSet;
+
+/// Upper bound of entries listed per property
+const MAX_LISTED: usize = 10;
+
+/// WMI and PowerShell activity attributed to an installer
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScriptActivityReport {
+    /// WMI operations of the installer process tree
+    pub wmi_operations: usize,
+    /// Command lines started through `Win32_Process.Create`
+    pub wmi_process_commands: Vec<String>,
+    /// Permanent event subscriptions, as `filter (query) -> consumer`
+    pub event_subscriptions: Vec<String>,
+    /// PowerShell script blocks run by the installer process tree
+    pub script_blocks: usize,
+    /// Script files the blocks came from, sorted
+    pub script_paths: Vec<String>,
+    /// Operations and blocks of other processes, left out above
+    pub unattributed: usize,
+}
+
+impl ScriptActivityReport {
+    /// Summarize the WMI operations and script blocks of a dynamic result
+    ///
+    /// Without captured processes nothing can be attributed and all activity
+    /// counts as the installer's.
+    pub fn from_result(result: &AnalysisResult) -> Self {
+        let attribution = ProcessAttribution::from_result(result);
+        let installer: BTreeSet<u32> = attribution
+            .installer_tree
+            .iter()
+            .chain(&attribution.msiexec_service)
+            .copied()
+            .collect();
+        let attributed = |process_id: Option<u32>| {
+            installer.is_empty() || process_id.is_some_and(|pid| installer.contains(&pid))
+        };
+
+        let mut report = Self::default();
+        for operation in &result.wmi_operations {
+            if !attributed(operation.process_id()) {
+                report.unattributed += 1;
+                continue;
+            }
+            report.wmi_operations += 1;
+            match operation {
+                WmiOperation::ProcessCreate { command_line, .. } => {
+                    report.wmi_process_commands.push(command_line.clone());
+                }
+                WmiOperation::EventSubscription {
+                    filter_name,
+                    query,
+                    consumer,
+                    ..
+                } => {
+                    report
+                        .event_subscriptions
+                        .push(format!("{} ({}) -> {}", filter_name, query, consumer));
+                }
+                WmiOperation::Query { .. } => {}
+            }
+        }
+
+        let mut paths = BTreeSet::new();
+        for block in &result.powershell_script_blocks {
+            if !attributed(block.process_id) {
+                report.unattributed += 1;
+                continue;
+            }
+            report.script_blocks += 1;
+            paths.extend(block.path.clone());
+        }
+        report.script_paths = paths.into_iter().collect();
+        report
+    }
+
+    /// Warning for installers that registered permanent event subscriptions
+    pub fn warning(&self) -> Option<String> {
+        (!self.event_subscriptions.is_empty()).then(|| {
+            format!(
+                "Installer registered {} permanent WMI event subscription(s), a persistence mechanism: {}",
+                self.event_subscriptions.len(),
+                self.event_subscriptions.join("; ")
+            )
+        })
+    }
+
+    /// Record the activity as analysis properties
+    pub fn insert_properties(&self, result: &mut AnalysisResult) {
+        let properties = &mut result.metadata.properties;
+        let mut insert = |key: &str, entries: &[String]| {
+            if !entries.is_empty() {
+                let listed: Vec<&str> = entries
+                    .iter()
+                    .take(MAX_LISTED)
+                    .map(String::as_str)
+                    .collect();
+                properties.insert(key.to_string(), listed.join("; "));
+            }
+        };
+        insert("wmi_process_creations", &self.wmi_process_commands);
+        insert("wmi_event_subscriptions", &self.event_subscriptions);
+        insert("powershell_scripts", &self.script_paths);
+
+        if self.wmi_operations > 0 {
+            properties.insert(
+                "wmi_operation_count".to_string(),
+                self.wmi_operations.to_string(),
+            );
+        }
+        if self.script_blocks > 0 {
+            properties.insert(
+                "powershell_script_block_count".to_string(),
+                self.script_blocks.to_string(),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{PowerShellScriptBlock, ProcessOpType, ProcessOperation};
+    use crate::sandbox::SandboxSessions;
+    use chrono::Utc;
+    use std::path::Path;
+
+    fn process(pid: u32, name: &str, parent: u32) -> ProcessOperation {
+        ProcessOperation {
+            operation_type: ProcessOpType::Create,
+            process_id: pid,
+            process_name: name.to_string(),
+            command_line: None,
+            parent_process_id: Some(parent),
+            timestamp: Utc::now(),
+        }
+    }
+
+    fn script_block(id: &str, path: Option<&str>, pid: u32) -> PowerShellScriptBlock {
+        PowerShellScriptBlock {
+            script_block_id: id.to_string(),
+            text: "Register-ScheduledTask".to_string(),
+            path: path.map(str::to_string),
+            timestamp: Utc::now(),
+            process_id: Some(pid),
+        }
+    }
+
+    #[test]
+    fn test_attribute_script_activity() {
+        let sessions = SandboxSessions::new();
+        let id = sessions.start(Path::new("C:\\Samples\\setup.exe"));
+        let mut result = sessions.intermediate_result(id).unwrap();
+        let now = Utc::now();
+        result.process_operations = vec![
+            process(100, "setup.exe", 4),
+            process(700, "WmiPrvSE.exe", 600),
+            process(200, "powershell.exe", 700),
+            process(300, "svchost.exe", 600),
+        ];
+        result.wmi_operations = vec![
+            WmiOperation::ProcessCreate {
+                namespace: "root\\cimv2".to_string(),
+                command_line: "powershell.exe -File C:\\Temp\\setup.ps1".to_string(),
+                created_process_id: Some(200),
+                timestamp: now,
+                process_id: Some(100),
+            },
+            WmiOperation::EventSubscription {
+                namespace: "root\\subscription".to_string(),
+                filter_name: "AppUpdate".to_string(),
+                query: "SELECT * FROM __InstanceModificationEvent WITHIN 3600".to_string(),
+                consumer: "CommandLineEventConsumer.Name=\"AppUpdate\"".to_string(),
+                timestamp: now,
+                process_id: Some(200),
+            },
+            WmiOperation::Query {
+                namespace: "root\\cimv2".to_string(),
+                query: "SELECT * FROM Win32_Service".to_string(),
+                timestamp: now,
+                process_id: Some(300),
+            },
+        ];
+        result.powershell_script_blocks = vec![
+            script_block("a", Some("C:\\Temp\\setup.ps1"), 200),
+            script_block("b", None, 200),
+            script_block("c", None, 300),
+        ];
+
+        let report = ScriptActivityReport::from_result(&result);
+        assert_eq!(report.wmi_operations, 2);
+        assert_eq!(report.script_blocks, 2);
+        assert_eq!(report.unattributed, 2);
+        assert_eq!(report.script_paths, vec!["C:\\Temp\\setup.ps1"]);
+        assert!(report.warning().unwrap().contains("AppUpdate (SELECT"));
+
+        report.insert_properties(&mut result);
+        let properties = &result.metadata.properties;
+        assert_eq!(properties["wmi_operation_count"], "2");
+        assert_eq!(properties["powershell_script_block_count"], "2");
+        assert_eq!(
+            properties["wmi_process_creations"],
+            "powershell.exe -File C:\\Temp\\setup.ps1"
+        );
+    }
+}
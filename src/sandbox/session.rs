@@ -0,0 +1,291 @@
+//! Sandbox sessions
+//!
+//! Each run of an installer is a session keyed by the session ID of its
+//! result. Sessions live in a [`SandboxSessions`] registry that can be
+//! shared with other tasks, which can list active and finished sessions,
+//! attach labels and tags, and fetch the operations captured so far while
+//! the installer is still running.
+
+use crate::core::{AnalysisResult, AnalyzerError, InstallerMetadata, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use uuid::Uuid;
+
+/// State of a sandbox session
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionStatus {
+    /// The installer is running
+    Running,
+    /// The run finished and its result is final
+    Completed,
+    /// The run failed; the result holds what was captured before
+    Failed,
+}
+
+impl SessionStatus {
+    /// Display name of the status
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Running => "running",
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+impl fmt::Display for SessionStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// One run of an installer in the sandbox
+#[derive(Debug, Clone, Serialize)]
+pub struct SandboxSession {
+    pub id: Uuid,
+    pub installer_path: PathBuf,
+    pub status: SessionStatus,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Key/value labels, e.g. `ticket=INC-1234`
+    pub labels: BTreeMap<String, String>,
+    /// Free-form tags
+    pub tags: BTreeSet<String>,
+    /// Why the run failed
+    pub error: Option<String>,
+    /// Operations captured so far, or the final result once completed
+    pub result: AnalysisResult,
+}
+
+impl SandboxSession {
+    fn new(id: Uuid, installer_path: &Path) -> Self {
+        let started_at = Utc::now();
+        Self {
+            id,
+            installer_path: installer_path.to_path_buf(),
+            status: SessionStatus::Running,
+            started_at,
+            finished_at: None,
+            labels: BTreeMap::new(),
+            tags: BTreeSet::new(),
+            error: None,
+            result: AnalysisResult {
+                session_id: id,
+                source_file_path: Some(installer_path.to_path_buf()),
+                metadata: InstallerMetadata {
+                    created_at: started_at,
+                    ..Default::default()
+                },
+                analyzed_at: started_at,
+                dynamic_analysis: true,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Whether the installer is still running
+    pub fn is_active(&self) -> bool {
+        self.status == SessionStatus::Running
+    }
+
+    /// Record labels and tags as result properties
+    pub fn insert_properties(&self, result: &mut AnalysisResult) {
+        let properties = &mut result.metadata.properties;
+        if !self.labels.is_empty() {
+            let labels: Vec<String> = self
+                .labels
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect();
+            properties.insert("sandbox_session_labels".to_string(), labels.join(", "));
+        }
+        if !self.tags.is_empty() {
+            let tags: Vec<&str> = self.tags.iter().map(String::as_str).collect();
+            properties.insert("sandbox_session_tags".to_string(), tags.join(", "));
+        }
+    }
+}
+
+/// Registry of sandbox sessions, cheap to clone and share between tasks
+#[derive(Debug, Clone, Default)]
+pub struct SandboxSessions {
+    sessions: Arc<Mutex<HashMap<Uuid, SandboxSession>>>,
+}
+
+impl SandboxSessions {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<Uuid, SandboxSession>> {
+        self.sessions
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Register a running session for an installer
+    pub fn start(&self, installer_path: &Path) -> Uuid {
+        let id = Uuid::new_v4();
+        self.lock()
+            .insert(id, SandboxSession::new(id, installer_path));
+        id
+    }
+
+    /// Snapshot of a session
+    pub fn get(&self, id: Uuid) -> Option<SandboxSession> {
+        self.lock().get(&id).cloned()
+    }
+
+    /// All sessions, oldest first
+    pub fn list(&self) -> Vec<SandboxSession> {
+        let mut sessions: Vec<SandboxSession> = self.lock().values().cloned().collect();
+        sessions.sort_by_key(|session| session.started_at);
+        sessions
+    }
+
+    /// Sessions whose installer is still running, oldest first
+    pub fn active(&self) -> Vec<SandboxSession> {
+        self.list()
+            .into_iter()
+            .filter(SandboxSession::is_active)
+            .collect()
+    }
+
+    /// Operations captured so far, or the final result of a finished session
+    pub fn intermediate_result(&self, id: Uuid) -> Option<AnalysisResult> {
+        self.lock().get(&id).map(|session| session.result.clone())
+    }
+
+    /// Attach a key/value label, replacing an earlier value of the key
+    pub fn label(&self, id: Uuid, key: &str, value: &str) -> Result<()> {
+        self.with_session(id, |session| {
+            session.labels.insert(key.to_string(), value.to_string());
+        })
+    }
+
+    /// Attach a tag
+    pub fn tag(&self, id: Uuid, tag: &str) -> Result<()> {
+        self.with_session(id, |session| {
+            session.tags.insert(tag.to_string());
+        })
+    }
+
+    /// Sessions carrying a tag, oldest first
+    pub fn tagged(&self, tag: &str) -> Vec<SandboxSession> {
+        self.list()
+            .into_iter()
+            .filter(|session| session.tags.contains(tag))
+            .collect()
+    }
+
+    /// Add captured operations to the result of a running session
+    pub fn record(&self, id: Uuid, update: impl FnOnce(&mut AnalysisResult)) {
+        if let Some(session) = self.lock().get_mut(&id).filter(|s| s.is_active()) {
+            update(&mut session.result);
+        }
+    }
+
+    /// Close a session with the outcome of its run
+    pub(crate) fn finish(&self, id: Uuid, outcome: &Result<AnalysisResult>) {
+        let _ = self.with_session(id, |session| {
+            session.finished_at = Some(Utc::now());
+            match outcome {
+                Ok(result) => {
+                    session.status = SessionStatus::Completed;
+                    session.result = result.clone();
+                }
+                Err(e) => {
+                    session.status = SessionStatus::Failed;
+                    session.error = Some(e.to_string());
+                }
+            }
+        });
+    }
+
+    fn with_session(&self, id: Uuid, update: impl FnOnce(&mut SandboxSession)) -> Result<()> {
+        let mut sessions = self.lock();
+        let session = sessions.get_mut(&id).ok_or_else(|| {
+            AnalyzerError::sandbox_error(format!("Unknown sandbox session {}", id))
+        })?;
+        update(session);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{FileOperation, RegistryOperation};
+
+    #[test]
+    fn test_session_lifecycle() {
+        let sessions = SandboxSessions::new();
+        let first = sessions.start(Path::new("setup.exe"));
+        let second = sessions.start(Path::new("other.msi"));
+        sessions.tag(first, "nightly").unwrap();
+        sessions.label(first, "ticket", "INC-1").unwrap();
+        sessions.label(first, "ticket", "INC-2").unwrap();
+        assert!(sessions.tag(Uuid::new_v4(), "nightly").is_err());
+
+        // Watchers see operations while the installer runs
+        let now = Utc::now();
+        sessions.record(first, |result| {
+            result.file_operations.push(FileOperation::Create {
+                path: PathBuf::from("C:\\Program Files\\App\\app.exe"),
+                size: 1,
+                timestamp: now,
+                process_id: None,
+            })
+        });
+        let partial = sessions.intermediate_result(first).unwrap();
+        assert_eq!(partial.session_id, first);
+        assert_eq!(partial.file_operations.len(), 1);
+        assert_eq!(sessions.active().len(), 2);
+
+        let mut result = partial.clone();
+        result
+            .registry_operations
+            .push(RegistryOperation::CreateKey {
+                key_path: "HKEY_LOCAL_MACHINE\\SOFTWARE\\App".to_string(),
+                timestamp: now,
+                process_id: None,
+            });
+        sessions.finish(first, &Ok(result));
+        sessions.finish(
+            second,
+            &Err(AnalyzerError::sandbox_error("installer crashed")),
+        );
+
+        assert!(sessions.active().is_empty());
+        assert_eq!(sessions.list().len(), 2);
+        let done = sessions.get(first).unwrap();
+        assert_eq!(done.status, SessionStatus::Completed);
+        assert_eq!(done.result.registry_operations.len(), 1);
+        assert_eq!(done.labels["ticket"], "INC-2");
+        assert_eq!(sessions.tagged("nightly").len(), 1);
+        let failed = sessions.get(second).unwrap();
+        assert_eq!(failed.status, SessionStatus::Failed);
+        assert!(failed.error.unwrap().contains("installer crashed"));
+
+        // Finished sessions no longer take captured operations
+        sessions.record(first, |result| result.file_operations.clear());
+        assert_eq!(sessions.get(first).unwrap().result.file_operations.len(), 1);
+
+        let mut report = done.result.clone();
+        done.insert_properties(&mut report);
+        assert_eq!(
+            report.metadata.properties["sandbox_session_labels"],
+            "ticket=INC-2"
+        );
+        assert_eq!(
+            report.metadata.properties["sandbox_session_tags"],
+            "nightly"
+        );
+    }
+}
@@ -0,0 +1,299 @@
+//! Opt-in telemetry on analyzer accuracy
+//!
+//! Maintainers cannot see which installer formats go undetected or which
+//! analyzers fail in the field. Users who opt in with `--telemetry` (or
+//! `INSTALLER_ANALYZER_TELEMETRY=1`) send one small event per analyzed
+//! installer to the endpoint in `INSTALLER_ANALYZER_TELEMETRY_URL`:
+//!
+//! ```json
+//! {
+//!   "schema_version": 1,
+//!   "tool_version": "0.7.0",
+//!   "os": "windows",
+//!   "arch": "x86_64",
+//!   "format": "NSIS",
+//!   "detected": true,
+//!   "outcome": "failed",
+//!   "failure_category": "parse"
+//! }
+//! ```
+//!
+//! That is the complete payload. File names, paths, hashes, metadata and
+//! contents of installers are never sent, and neither are error messages,
+//! since they may quote any of these. Telemetry is off unless opted in;
+//! `--no-telemetry` and `DO_NOT_TRACK=1` turn it off regardless. Sending is
+//! best effort and never fails an analysis: events are sent in the
+//! background while the analysis goes on, and the run only waits for the
+//! ones still in flight once, at its end.
+
+use crate::core::{AnalyzerError, InstallerFormat};
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// Version of the event payload
+pub const TELEMETRY_SCHEMA_VERSION: u32 = 1;
+
+/// Time to wait for the endpoint before dropping an event
+const SEND_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Outcome of analyzing one installer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Analyzed,
+    Failed,
+}
+
+/// Coarse cause of a failed analysis
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureCategory {
+    UnsupportedFormat,
+    InvalidFormat,
+    Parse,
+    Io,
+    Timeout,
+    /// An analyzer panicked
+    Crash,
+    Other,
+}
+
+impl FailureCategory {
+    /// Category of an analysis error
+    pub fn of(error: &AnalyzerError) -> Self {
+        match error {
+            AnalyzerError::UnsupportedFormat { .. } => Self::UnsupportedFormat,
+            AnalyzerError::InvalidFormat { .. } => Self::InvalidFormat,
+            AnalyzerError::ParseError { .. } | AnalyzerError::SerializationError(_) => Self::Parse,
+            AnalyzerError::Io(_) | AnalyzerError::FileNotFound { .. } => Self::Io,
+            AnalyzerError::Timeout { .. } => Self::Timeout,
+            AnalyzerError::Crashed { .. } => Self::Crash,
+            _ => Self::Other,
+        }
+    }
+}
+
+/// Event sent for one analyzed installer, see the module documentation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TelemetryEvent {
+    pub schema_version: u32,
+    pub tool_version: &'static str,
+    pub os: &'static str,
+    pub arch: &'static str,
+    /// Detected format; formats of third-party analyzers are sent as `Other`
+    pub format: String,
+    /// Whether a format was recognized at all
+    pub detected: bool,
+    pub outcome: Outcome,
+    pub failure_category: Option<FailureCategory>,
+}
+
+impl TelemetryEvent {
+    fn new(format: Option<&InstallerFormat>, outcome: Outcome) -> Self {
+        let format = match format {
+            // Names of third-party formats may identify in-house tooling
+            Some(InstallerFormat::Other(_)) => "Other".to_string(),
+            Some(format) => format.to_string(),
+            None => InstallerFormat::Unknown.to_string(),
+        };
+        Self {
+            schema_version: TELEMETRY_SCHEMA_VERSION,
+            tool_version: env!("CARGO_PKG_VERSION"),
+            os: std::env::consts::OS,
+            arch: std::env::consts::ARCH,
+            detected: format != InstallerFormat::Unknown.to_string(),
+            format,
+            outcome,
+            failure_category: None,
+        }
+    }
+
+    /// Event of a successful analysis
+    pub fn analyzed(format: &InstallerFormat) -> Self {
+        Self::new(Some(format), Outcome::Analyzed)
+    }
+
+    /// Event of a failed analysis, `format` if detection got that far
+    pub fn failed(format: Option<&InstallerFormat>, error: &AnalyzerError) -> Self {
+        Self {
+            failure_category: Some(FailureCategory::of(error)),
+            ..Self::new(format, Outcome::Failed)
+        }
+    }
+}
+
+/// Destination of telemetry events, only constructed when opted in
+///
+/// Clones share the events still being sent.
+#[derive(Debug, Clone)]
+pub struct Telemetry {
+    endpoint: String,
+    client: reqwest::Client,
+    pending: Arc<Mutex<Vec<JoinHandle<()>>>>,
+}
+
+impl PartialEq for Telemetry {
+    fn eq(&self, other: &Self) -> bool {
+        self.endpoint == other.endpoint
+    }
+}
+
+impl Eq for Telemetry {}
+
+impl Telemetry {
+    /// Telemetry if the user opted in and did not opt out
+    ///
+    /// Opting in without an endpoint sends nothing.
+    pub fn configure(opt_in: bool, opt_out: bool, endpoint: Option<String>) -> Option<Self> {
+        if !opt_in || opt_out {
+            return None;
+        }
+        let endpoint = endpoint.filter(|endpoint| !endpoint.trim().is_empty())?;
+        let client = match reqwest::Client::builder()
+            .user_agent(concat!("installer-analyzer/", env!("CARGO_PKG_VERSION")))
+            .timeout(SEND_TIMEOUT)
+            .build()
+        {
+            Ok(client) => client,
+            Err(e) => {
+                tracing::debug!("Failed to create telemetry client: {}", e);
+                return None;
+            }
+        };
+        Some(Self {
+            endpoint,
+            client,
+            pending: Arc::default(),
+        })
+    }
+
+    /// Whether `DO_NOT_TRACK` asks to disable telemetry
+    pub fn do_not_track() -> bool {
+        std::env::var("DO_NOT_TRACK").is_ok_and(|value| !value.is_empty() && value != "0")
+    }
+
+    /// Send an event in the background, see [`Self::flush`]
+    pub fn send(&self, event: TelemetryEvent) {
+        let client = self.client.clone();
+        let endpoint = self.endpoint.clone();
+        let task = tokio::spawn(async move { post(&client, &endpoint, &event).await });
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        pending.retain(|task| !task.is_finished());
+        pending.push(task);
+    }
+
+    /// Wait for the events still being sent, at most about [`SEND_TIMEOUT`]
+    pub async fn flush(&self) {
+        let pending = std::mem::take(&mut *self.pending.lock().unwrap_or_else(|e| e.into_inner()));
+        for task in pending {
+            let _ = task.await;
+        }
+    }
+}
+
+/// Post an event, logging instead of failing when the endpoint is unreachable
+async fn post(client: &reqwest::Client, endpoint: &str, event: &TelemetryEvent) {
+    match serde_json::to_string(event) {
+        Ok(payload) => tracing::debug!("Sending telemetry event {}", payload),
+        Err(e) => tracing::debug!("Failed to serialize telemetry event: {}", e),
+    }
+    let response = client.post(endpoint).json(event).send().await;
+    match response.and_then(|response| response.error_for_status()) {
+        Ok(_) => {}
+        Err(e) => tracing::debug!("Failed to send telemetry event: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_payload() {
+        let event = TelemetryEvent::failed(
+            Some(&InstallerFormat::NSIS),
+            &AnalyzerError::parse_error("bad header in C:\\Users\\alice\\setup.exe"),
+        );
+        let payload = serde_json::to_value(&event).unwrap();
+        let keys: Vec<&str> = payload
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        assert_eq!(
+            keys,
+            vec![
+                "arch",
+                "detected",
+                "failure_category",
+                "format",
+                "os",
+                "outcome",
+                "schema_version",
+                "tool_version"
+            ]
+        );
+        assert_eq!(payload["format"], "NSIS");
+        assert_eq!(payload["outcome"], "failed");
+        assert_eq!(payload["failure_category"], "parse");
+        assert!(!payload.to_string().contains("alice"));
+
+        let event = TelemetryEvent::analyzed(&InstallerFormat::other("Contoso Packager"));
+        assert_eq!(event.format, "Other");
+        assert!(event.detected);
+        assert_eq!(event.failure_category, None);
+        assert!(!TelemetryEvent::analyzed(&InstallerFormat::Unknown).detected);
+        assert!(!TelemetryEvent::failed(None, &AnalyzerError::timeout(5)).detected);
+    }
+
+    #[test]
+    fn test_failure_categories() {
+        let io = std::io::Error::new(std::io::ErrorKind::NotFound, "gone");
+        assert_eq!(FailureCategory::of(&io.into()), FailureCategory::Io);
+        assert_eq!(
+            FailureCategory::of(&AnalyzerError::unsupported_format("x")),
+            FailureCategory::UnsupportedFormat
+        );
+        assert_eq!(
+            FailureCategory::of(&AnalyzerError::crashed("a.msi", "boom")),
+            FailureCategory::Crash
+        );
+        assert_eq!(
+            FailureCategory::of(&AnalyzerError::generic("Analyzer crashed on a.msi: boom")),
+            FailureCategory::Other
+        );
+        assert_eq!(
+            FailureCategory::of(&AnalyzerError::config_error("bad policy")),
+            FailureCategory::Other
+        );
+    }
+
+    #[test]
+    fn test_configure_is_opt_in() {
+        let endpoint = Some("https://telemetry.example.com/events".to_string());
+        assert_eq!(Telemetry::configure(false, false, endpoint.clone()), None);
+        assert_eq!(Telemetry::configure(true, true, endpoint.clone()), None);
+        assert_eq!(Telemetry::configure(true, false, None), None);
+        assert!(Telemetry::configure(true, false, endpoint).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_send_does_not_wait_for_the_endpoint() {
+        // Accepts connections but never answers
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}/events", listener.local_addr().unwrap());
+        let telemetry = Telemetry::configure(true, false, Some(endpoint)).unwrap();
+
+        let start = std::time::Instant::now();
+        telemetry.send(TelemetryEvent::analyzed(&InstallerFormat::NSIS));
+        telemetry.send(TelemetryEvent::analyzed(&InstallerFormat::MSI));
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        telemetry.clone().flush().await;
+        assert!(start.elapsed() < SEND_TIMEOUT * 2);
+        assert!(telemetry.pending.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,346 @@
+//! Selection of the release asset matching the running build
+//!
+//! Release assets are named after the platform they were built for, in
+//! various spellings: `installer-analyzer-windows-amd64.exe`,
+//! `installer-analyzer-macos-arm64` or a full target triple like
+//! `x86_64-unknown-linux-musl`. Asset names are split into tokens and
+//! compared against the target triple of the running binary, so that an
+//! ARM64 machine never downloads an x86-64 build. Checksums published next
+//! to an asset, as `<asset>.sha256` or in a `checksums.txt` listing, are
+//! discovered for the chosen asset.
+
+use crate::updater::client::ReleaseAsset;
+
+/// Target triple the running binary was built for
+pub const BUILD_TARGET: &str = env!("BUILD_TARGET");
+
+/// Files listing the checksums of several assets, `sha256sum` style
+const CHECKSUM_LISTS: &[&str] = &["checksums.txt", "sha256sums", "sha256sums.txt"];
+
+/// Extensions of assets that are not builds
+const AUXILIARY_EXTENSIONS: &[&str] = &[
+    ".sha256",
+    ".sha256sum",
+    ".sig",
+    ".asc",
+    ".pem",
+    ".txt",
+    ".json",
+    ".md",
+];
+
+/// Extensions of archives, ranked below bare executables
+const ARCHIVE_EXTENSIONS: &[&str] = &[".zip", ".tar.gz", ".tgz", ".tar.xz"];
+
+/// Platform of a build, parsed from a target triple
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TargetSpec {
+    pub triple: String,
+    /// `x86_64`, `aarch64` or `x86`, other architectures as in the triple
+    pub arch: String,
+    /// `windows`, `linux` or `macos`, other systems as in the triple
+    pub os: String,
+    /// `msvc`, `gnu` or `musl` if the triple names an environment
+    pub env: Option<String>,
+}
+
+impl TargetSpec {
+    /// Platform of the running binary
+    pub fn current() -> Self {
+        Self::parse(BUILD_TARGET)
+    }
+
+    /// Parse a triple like `aarch64-pc-windows-msvc`
+    pub fn parse(triple: &str) -> Self {
+        let triple = triple.to_lowercase();
+        let parts: Vec<&str> = triple.split('-').collect();
+        let arch = parts.first().copied().unwrap_or_default();
+        let os = parts
+            .iter()
+            .skip(1)
+            .find_map(|part| os_alias(part))
+            .unwrap_or_else(|| parts.get(2).copied().unwrap_or_default());
+        let env = parts.iter().skip(3).find_map(|part| env_alias(part));
+
+        Self {
+            arch: arch_alias(arch).unwrap_or(arch).to_string(),
+            os: os.to_string(),
+            env: env.map(str::to_string),
+            triple: triple.clone(),
+        }
+    }
+}
+
+/// Asset built for the target, if any
+///
+/// Assets naming another system, architecture or environment are skipped,
+/// those naming the full triple are preferred. Names without architecture
+/// are accepted for x86-64 only, the architecture of builds that predate
+/// per-architecture assets. Self-update replaces the running executable,
+/// so Windows targets only accept `.exe` assets.
+pub fn select_asset<'a>(
+    assets: &'a [ReleaseAsset],
+    target: &TargetSpec,
+) -> Option<&'a ReleaseAsset> {
+    let mut best: Option<(u32, &ReleaseAsset)> = None;
+    for asset in assets {
+        let Some(score) = score(&asset.name, target) else {
+            continue;
+        };
+        if best.is_none_or(|(best_score, _)| score > best_score) {
+            best = Some((score, asset));
+        }
+    }
+    best.map(|(_, asset)| asset)
+}
+
+/// Asset holding the checksum of `chosen`, if the release has one
+pub fn checksum_asset<'a>(
+    assets: &'a [ReleaseAsset],
+    chosen: &ReleaseAsset,
+) -> Option<&'a ReleaseAsset> {
+    let name = chosen.name.to_lowercase();
+    let dedicated = [format!("{}.sha256", name), format!("{}.sha256sum", name)];
+    assets
+        .iter()
+        .find(|asset| dedicated.contains(&asset.name.to_lowercase()))
+        .or_else(|| {
+            assets
+                .iter()
+                .find(|asset| CHECKSUM_LISTS.contains(&asset.name.to_lowercase().as_str()))
+        })
+}
+
+/// SHA256 of `file_name` in a checksum file
+///
+/// Accepts a bare hash as well as `sha256sum` output, where each line is a
+/// hash followed by a file name, optionally prefixed with `*`.
+pub fn parse_checksum(content: &str, file_name: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        let hash = fields.next()?;
+        if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        match fields.next() {
+            Some(name) if name.trim_start_matches('*') != file_name => None,
+            _ => Some(hash.to_lowercase()),
+        }
+    })
+}
+
+/// Rank of an asset for the target, `None` if it does not fit
+fn score(name: &str, target: &TargetSpec) -> Option<u32> {
+    let name = name.to_lowercase();
+    if AUXILIARY_EXTENSIONS.iter().any(|ext| name.ends_with(ext)) {
+        return None;
+    }
+    let is_exe = name.ends_with(".exe");
+    if target.os == "windows" && !is_exe {
+        return None;
+    }
+
+    let tokens: Vec<&str> = name.split(['-', '.', ' ']).collect();
+    let os = tokens
+        .iter()
+        .find_map(|token| os_alias(token))
+        .or(is_exe.then_some("windows"));
+    if os != Some(target.os.as_str()) {
+        return None;
+    }
+
+    let mut score = 0;
+    match tokens.iter().find_map(|token| arch_alias(token)) {
+        Some(arch) if arch == target.arch => score += 2,
+        None if target.arch == "x86_64" => {}
+        _ => return None,
+    }
+    score += match tokens.iter().find_map(|token| env_alias(token)) {
+        Some(env) if Some(env) == target.env.as_deref() => 2,
+        Some(_) => 0,
+        None => 1,
+    };
+    if name.contains(&target.triple) {
+        score += 4;
+    }
+    if !ARCHIVE_EXTENSIONS.iter().any(|ext| name.ends_with(ext)) {
+        score += 1;
+    }
+    Some(score)
+}
+
+fn arch_alias(token: &str) -> Option<&'static str> {
+    match token {
+        "x86_64" | "amd64" | "x64" | "win64" => Some("x86_64"),
+        "aarch64" | "arm64" => Some("aarch64"),
+        "i686" | "i586" | "i386" | "x86" | "win32" => Some("x86"),
+        _ => None,
+    }
+}
+
+fn os_alias(token: &str) -> Option<&'static str> {
+    match token {
+        "windows" | "win" | "win64" | "win32" => Some("windows"),
+        "linux" => Some("linux"),
+        "macos" | "darwin" | "apple" | "osx" | "mac" => Some("macos"),
+        _ => None,
+    }
+}
+
+fn env_alias(token: &str) -> Option<&'static str> {
+    match token {
+        "msvc" => Some("msvc"),
+        "musl" => Some("musl"),
+        token if token.starts_with("gnu") => Some("gnu"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn asset(name: &str) -> ReleaseAsset {
+        ReleaseAsset {
+            name: name.to_string(),
+            label: None,
+            content_type: "application/octet-stream".to_string(),
+            size: 1,
+            download_count: 0,
+            browser_download_url: format!("https://example.com/{}", name),
+        }
+    }
+
+    /// Assets as published by the release workflow, plus ARM64 Windows builds
+    fn release_assets() -> Vec<ReleaseAsset> {
+        [
+            "installer-analyzer-v1.0.0-linux-amd64.tar.gz",
+            "installer-analyzer-v1.0.0-linux-amd64-musl.tar.gz",
+            "installer-analyzer-v1.0.0-windows-amd64.zip",
+            "installer-analyzer-v1.0.0-macos-amd64.tar.gz",
+            "installer-analyzer-v1.0.0-macos-arm64.tar.gz",
+            "installer-analyzer-windows-amd64.exe",
+            "installer-analyzer-windows-arm64.exe",
+            "installer-analyzer-windows-arm64.exe.sha256",
+            "checksums.txt",
+        ]
+        .into_iter()
+        .map(asset)
+        .collect()
+    }
+
+    fn selected(target: &str) -> Option<String> {
+        let assets = release_assets();
+        select_asset(&assets, &TargetSpec::parse(target)).map(|asset| asset.name.clone())
+    }
+
+    #[test]
+    fn test_parse_target() {
+        let target = TargetSpec::parse("aarch64-pc-windows-msvc");
+        assert_eq!(
+            (
+                target.arch.as_str(),
+                target.os.as_str(),
+                target.env.as_deref()
+            ),
+            ("aarch64", "windows", Some("msvc"))
+        );
+        let target = TargetSpec::parse("x86_64-unknown-linux-musl");
+        assert_eq!(
+            (target.os.as_str(), target.env.as_deref()),
+            ("linux", Some("musl"))
+        );
+        let target = TargetSpec::parse("aarch64-apple-darwin");
+        assert_eq!((target.os.as_str(), target.env), ("macos", None));
+        assert_eq!(TargetSpec::parse("i686-pc-windows-gnu").arch, "x86");
+        assert_eq!(TargetSpec::current().triple, BUILD_TARGET);
+    }
+
+    #[test]
+    fn test_select_asset() {
+        assert_eq!(
+            selected("x86_64-pc-windows-msvc").as_deref(),
+            Some("installer-analyzer-windows-amd64.exe")
+        );
+        assert_eq!(
+            selected("aarch64-pc-windows-msvc").as_deref(),
+            Some("installer-analyzer-windows-arm64.exe")
+        );
+        assert_eq!(
+            selected("x86_64-unknown-linux-gnu").as_deref(),
+            Some("installer-analyzer-v1.0.0-linux-amd64.tar.gz")
+        );
+        assert_eq!(
+            selected("x86_64-unknown-linux-musl").as_deref(),
+            Some("installer-analyzer-v1.0.0-linux-amd64-musl.tar.gz")
+        );
+        assert_eq!(
+            selected("aarch64-apple-darwin").as_deref(),
+            Some("installer-analyzer-v1.0.0-macos-arm64.tar.gz")
+        );
+        assert_eq!(selected("aarch64-unknown-linux-gnu"), None);
+        assert_eq!(selected("i686-pc-windows-msvc"), None);
+    }
+
+    #[test]
+    fn test_select_asset_by_triple_and_legacy_names() {
+        let assets: Vec<_> = [
+            "installer-analyzer.exe",
+            "installer-analyzer-x86_64-pc-windows-gnu.exe",
+            "installer-analyzer-x86_64-pc-windows-msvc.exe",
+        ]
+        .into_iter()
+        .map(asset)
+        .collect();
+        let pick = |target| select_asset(&assets, &TargetSpec::parse(target)).map(|a| &a.name);
+
+        assert_eq!(
+            pick("x86_64-pc-windows-msvc").map(String::as_str),
+            Some("installer-analyzer-x86_64-pc-windows-msvc.exe")
+        );
+        assert_eq!(
+            pick("x86_64-pc-windows-gnu").map(String::as_str),
+            Some("installer-analyzer-x86_64-pc-windows-gnu.exe")
+        );
+        // Names without architecture are x86-64 builds
+        assert_eq!(
+            select_asset(&assets[..1], &TargetSpec::parse("x86_64-pc-windows-msvc"))
+                .map(|a| a.name.as_str()),
+            Some("installer-analyzer.exe")
+        );
+        assert!(pick("aarch64-pc-windows-msvc").is_none());
+    }
+
+    #[test]
+    fn test_checksum_discovery() {
+        let assets = release_assets();
+        let arm64 = asset("installer-analyzer-windows-arm64.exe");
+        let amd64 = asset("installer-analyzer-windows-amd64.exe");
+        assert_eq!(
+            checksum_asset(&assets, &arm64).map(|a| a.name.as_str()),
+            Some("installer-analyzer-windows-arm64.exe.sha256")
+        );
+        assert_eq!(
+            checksum_asset(&assets, &amd64).map(|a| a.name.as_str()),
+            Some("checksums.txt")
+        );
+        assert!(checksum_asset(&assets[..1], &amd64).is_none());
+
+        let hash = "9F86D081884C7D659A2FEAA0C55AD015A3BF4F1B2B0B822CD15D6C15B0F00A08";
+        assert_eq!(
+            parse_checksum(&format!("{}\n", hash), "app.exe"),
+            Some(hash.to_lowercase())
+        );
+        let listing = format!(
+            "{}  other.zip\n{} *app.exe\n",
+            "0".repeat(64),
+            hash.to_lowercase()
+        );
+        assert_eq!(
+            parse_checksum(&listing, "app.exe"),
+            Some(hash.to_lowercase())
+        );
+        assert_eq!(parse_checksum(&listing, "missing.exe"), None);
+        assert_eq!(parse_checksum("not a checksum", "app.exe"), None);
+    }
+}
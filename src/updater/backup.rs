@@ -0,0 +1,276 @@
+//! Backups of executables replaced by self-updates
+//!
+//! Before a self-update replaces the running executable, a copy is stored
+//! in the user's data directory together with a small state file recording
+//! the version, where it came from and its SHA256. `update --rollback`
+//! restores the most recent backup once its SHA256 checks out, so a copy
+//! changed after the backup is never put in place of the executable. Only
+//! the newest [`MAX_BACKUPS`] are kept.
+
+use crate::core::{AnalyzerError, Result};
+use crate::utils::crash::sha256_file;
+use crate::utils::{create_private_dir, user_data_dir};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Number of backups kept, older ones are removed when a new one is made
+pub const MAX_BACKUPS: usize = 3;
+
+/// Directory name of the backups below the user data directory
+const BACKUP_DIR_NAME: &str = "update-backups";
+
+/// Name of the state file listing the backups
+const STATE_FILE_NAME: &str = "state.json";
+
+/// Backup of an executable taken before it was replaced
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupRecord {
+    /// Version of the backed-up executable
+    pub version: String,
+    /// Executable the backup was taken of
+    pub executable: PathBuf,
+    /// Copy of the executable
+    pub backup: PathBuf,
+    /// Hex-encoded SHA256 of the copy
+    #[serde(default)]
+    pub sha256: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Backups with their state file, newest last
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct BackupState {
+    backups: Vec<BackupRecord>,
+}
+
+/// Store of executable backups
+#[derive(Debug, Clone)]
+pub struct BackupStore {
+    dir: PathBuf,
+}
+
+impl BackupStore {
+    /// Store in the user data directory
+    pub fn new() -> Self {
+        Self::with_dir(user_data_dir().join(BACKUP_DIR_NAME))
+    }
+
+    /// Store in a custom directory
+    pub fn with_dir(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Directory holding the backups and the state file
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// All recorded backups, oldest first
+    pub fn records(&self) -> Result<Vec<BackupRecord>> {
+        Ok(self.load()?.backups)
+    }
+
+    /// Copy `executable` into the store and record it as the latest backup
+    pub fn create(&self, executable: &Path, version: &str) -> Result<BackupRecord> {
+        create_private_dir(&self.dir).map_err(|e| {
+            AnalyzerError::generic(format!(
+                "Failed to create backup directory {}: {}",
+                self.dir.display(),
+                e
+            ))
+        })?;
+
+        let created_at = Utc::now();
+        let stem = executable
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| "installer-analyzer".to_string());
+        let mut name = format!(
+            "{}-{}-{}",
+            stem,
+            version,
+            created_at.format("%Y%m%d_%H%M%S%3f")
+        );
+        if let Some(extension) = executable.extension() {
+            name.push('.');
+            name.push_str(&extension.to_string_lossy());
+        }
+        let backup = self.dir.join(name);
+
+        tracing::info!("Creating backup: {}", backup.display());
+        std::fs::copy(executable, &backup)
+            .map_err(|e| AnalyzerError::generic(format!("Failed to create backup: {}", e)))?;
+        let sha256 = sha256_file(&backup)
+            .map_err(|e| AnalyzerError::generic(format!("Failed to hash backup: {}", e)))?;
+
+        let record = BackupRecord {
+            version: version.to_string(),
+            executable: executable.to_path_buf(),
+            backup,
+            sha256,
+            created_at,
+        };
+        let mut state = self.load()?;
+        state.backups.push(record.clone());
+        while state.backups.len() > MAX_BACKUPS {
+            let expired = state.backups.remove(0);
+            if let Err(e) = std::fs::remove_file(&expired.backup) {
+                tracing::debug!("Failed to remove {}: {}", expired.backup.display(), e);
+            }
+        }
+        self.save(&state)?;
+
+        Ok(record)
+    }
+
+    /// Most recent backup whose copy still exists
+    pub fn latest(&self) -> Result<Option<BackupRecord>> {
+        Ok(self
+            .records()?
+            .into_iter()
+            .rev()
+            .find(|record| record.backup.is_file()))
+    }
+
+    /// Put the most recent backup in place of `executable`
+    ///
+    /// A backup whose SHA256 no longer matches the recorded one is refused.
+    /// The restored backup is removed from the store, so that a further
+    /// rollback goes back one more version.
+    pub fn restore_latest(&self, executable: &Path) -> Result<BackupRecord> {
+        let record = self.latest()?.ok_or_else(|| {
+            AnalyzerError::generic("No backup of an earlier version is available to roll back to")
+        })?;
+        let sha256 = sha256_file(&record.backup)
+            .map_err(|e| AnalyzerError::generic(format!("Failed to hash backup: {}", e)))?;
+        if record.sha256.is_empty() || !sha256.eq_ignore_ascii_case(&record.sha256) {
+            return Err(AnalyzerError::generic(format!(
+                "Backup {} does not match its recorded SHA256, refusing to restore it",
+                record.backup.display()
+            )));
+        }
+
+        replace_executable(executable, &record.backup)?;
+        tracing::info!(
+            "Restored {} from {}",
+            executable.display(),
+            record.backup.display()
+        );
+
+        let mut state = self.load()?;
+        state.backups.retain(|backup| backup != &record);
+        self.save(&state)?;
+        if let Err(e) = std::fs::remove_file(&record.backup) {
+            tracing::debug!("Failed to remove {}: {}", record.backup.display(), e);
+        }
+
+        Ok(record)
+    }
+
+    fn load(&self) -> Result<BackupState> {
+        let path = self.dir.join(STATE_FILE_NAME);
+        match std::fs::read(&path) {
+            Ok(content) => serde_json::from_slice(&content).map_err(|e| {
+                AnalyzerError::generic(format!(
+                    "Failed to read backup state {}: {}",
+                    path.display(),
+                    e
+                ))
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BackupState::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn save(&self, state: &BackupState) -> Result<()> {
+        let content =
+            serde_json::to_vec_pretty(state).map_err(AnalyzerError::SerializationError)?;
+        std::fs::write(self.dir.join(STATE_FILE_NAME), content)?;
+        Ok(())
+    }
+}
+
+impl Default for BackupStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Replace `target` with a copy of `replacement`
+///
+/// A running executable cannot be overwritten on Windows but it can be
+/// renamed, so the target is moved aside first and moved back if the copy
+/// fails.
+pub fn replace_executable(target: &Path, replacement: &Path) -> Result<()> {
+    let mut aside = target.as_os_str().to_os_string();
+    aside.push(".old");
+    let aside = PathBuf::from(aside);
+
+    if aside.exists() {
+        // Left by an earlier replacement, no longer running
+        std::fs::remove_file(&aside)?;
+    }
+    std::fs::rename(target, &aside).map_err(|e| {
+        AnalyzerError::generic(format!("Failed to move {} aside: {}", target.display(), e))
+    })?;
+    if let Err(e) = std::fs::copy(replacement, target) {
+        std::fs::rename(&aside, target)?;
+        return Err(AnalyzerError::generic(format!(
+            "Failed to copy {} to {}: {}",
+            replacement.display(),
+            target.display(),
+            e
+        )));
+    }
+    if let Err(e) = std::fs::remove_file(&aside) {
+        // Still running on Windows, removed by the next replacement
+        tracing::debug!("Failed to remove {}: {}", aside.display(), e);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backup_and_rollback() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = BackupStore::with_dir(dir.path().join("backups"));
+        let exe = dir.path().join("installer-analyzer.exe");
+        assert!(store.latest().unwrap().is_none());
+        assert!(store.restore_latest(&exe).is_err());
+
+        for version in ["1.0.0", "1.1.0", "1.2.0", "1.3.0"] {
+            std::fs::write(&exe, version).unwrap();
+            let record = store.create(&exe, version).unwrap();
+            assert_eq!(record.executable, exe);
+            assert_eq!(record.backup.extension().unwrap(), "exe");
+        }
+        let records = store.records().unwrap();
+        assert_eq!(records.len(), MAX_BACKUPS);
+        assert_eq!(records[0].version, "1.1.0");
+        assert_eq!(
+            std::fs::read_dir(store.dir()).unwrap().count(),
+            MAX_BACKUPS + 1
+        );
+
+        std::fs::write(&exe, "2.0.0-broken").unwrap();
+        let restored = store.restore_latest(&exe).unwrap();
+        assert_eq!(restored.version, "1.3.0");
+        assert_eq!(std::fs::read_to_string(&exe).unwrap(), "1.3.0");
+        assert!(!restored.backup.exists());
+
+        // A further rollback goes back one more version
+        assert_eq!(store.restore_latest(&exe).unwrap().version, "1.2.0");
+        assert_eq!(std::fs::read_to_string(&exe).unwrap(), "1.2.0");
+        assert_eq!(store.records().unwrap().len(), 1);
+
+        // A backup changed after it was taken is not restored
+        let tampered = store.latest().unwrap().unwrap();
+        std::fs::write(&tampered.backup, "1.1.0-tampered").unwrap();
+        assert!(store.restore_latest(&exe).is_err());
+        assert_eq!(std::fs::read_to_string(&exe).unwrap(), "1.2.0");
+    }
+}
@@ -0,0 +1,146 @@
+//! On-disk cache of GitHub API responses
+//!
+//! Update checks often run in every CI job, while unauthenticated GitHub API
+//! requests are limited to 60 per hour and address. Responses are therefore
+//! cached together with their ETag: within the TTL no request is made at
+//! all, afterwards the request is conditional and a `304 Not Modified`
+//! answer, which does not count against the limit, renews the cached copy.
+//! When GitHub asks clients to back off, the time is recorded as well, so
+//! that later invocations wait instead of asking again.
+
+use crate::utils::workspace::{Workspace, WorkspaceArea};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Time a cached response is used without asking GitHub again
+pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Cached response of one API URL
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedResponse {
+    pub url: String,
+    /// Entity tag sent back in `If-None-Match`
+    pub etag: Option<String>,
+    /// Time the response was last received or confirmed unchanged
+    pub fetched_at: DateTime<Utc>,
+    /// Response body, `None` if only a back-off was recorded
+    pub body: Option<serde_json::Value>,
+    /// Time before which GitHub asked not to send further requests
+    #[serde(default)]
+    pub retry_after: Option<DateTime<Utc>>,
+}
+
+impl CachedResponse {
+    /// Entry for a response just received
+    pub fn new(url: &str, etag: Option<String>, body: Option<serde_json::Value>) -> Self {
+        Self {
+            url: url.to_string(),
+            etag,
+            fetched_at: Utc::now(),
+            body,
+            retry_after: None,
+        }
+    }
+
+    /// Whether the body may be used without revalidation
+    pub fn is_fresh(&self, ttl: Duration) -> bool {
+        let age = Utc::now().signed_duration_since(self.fetched_at);
+        self.body.is_some() && age.to_std().is_ok_and(|age| age < ttl)
+    }
+
+    /// Remaining time of a recorded back-off
+    pub fn backoff_remaining(&self) -> Option<Duration> {
+        let retry_after = self.retry_after?;
+        retry_after.signed_duration_since(Utc::now()).to_std().ok()
+    }
+}
+
+/// Directory of cached API responses, one file per URL
+#[derive(Debug, Clone)]
+pub struct ResponseCache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+impl ResponseCache {
+    /// Cache in `dir` whose entries are fresh for `ttl`
+    pub fn new(dir: PathBuf, ttl: Duration) -> Self {
+        Self { dir, ttl }
+    }
+
+    /// Cache in the update cache area of the workspace
+    pub fn in_workspace(ttl: Duration) -> Self {
+        Self::new(Workspace::new().area_dir(WorkspaceArea::UpdateCache), ttl)
+    }
+
+    /// Time entries are used without revalidation
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Cached response of a URL, if any
+    ///
+    /// Unreadable entries are treated as missing, the cache is an
+    /// optimization only.
+    pub fn load(&self, url: &str) -> Option<CachedResponse> {
+        let content = std::fs::read(self.entry_path(url)).ok()?;
+        let entry: CachedResponse = serde_json::from_slice(&content)
+            .map_err(|e| tracing::debug!("Ignoring unreadable cache entry for {}: {}", url, e))
+            .ok()?;
+        (entry.url == url).then_some(entry)
+    }
+
+    /// Store a response, logging rather than returning failures
+    pub fn store(&self, entry: &CachedResponse) {
+        let path = self.entry_path(&entry.url);
+        let temp = path.with_extension("tmp");
+        let result = std::fs::create_dir_all(&self.dir)
+            .and_then(|_| {
+                let content = serde_json::to_vec(entry).map_err(std::io::Error::other)?;
+                std::fs::write(&temp, content)
+            })
+            .and_then(|_| std::fs::rename(&temp, &path));
+        if let Err(e) = result {
+            tracing::warn!("Failed to cache response of {}: {}", entry.url, e);
+        }
+    }
+
+    fn entry_path(&self, url: &str) -> PathBuf {
+        let digest = hex::encode(Sha256::digest(url.as_bytes()));
+        self.dir.join(format!("{}.json", &digest[..16]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ResponseCache::new(dir.path().join("cache"), Duration::from_secs(60));
+        let url = "https://api.github.com/repos/o/r/releases/latest";
+        assert!(cache.load(url).is_none());
+
+        let entry = CachedResponse::new(
+            url,
+            Some("\"abc\"".to_string()),
+            Some(serde_json::json!({"tag_name": "v1.0.0"})),
+        );
+        cache.store(&entry);
+        let loaded = cache.load(url).unwrap();
+        assert_eq!(loaded.etag.as_deref(), Some("\"abc\""));
+        assert!(loaded.is_fresh(cache.ttl()));
+        assert!(!loaded.is_fresh(Duration::ZERO));
+        assert!(loaded.backoff_remaining().is_none());
+        assert!(cache.load("https://api.github.com/other").is_none());
+
+        let mut backoff = CachedResponse::new(url, None, None);
+        backoff.retry_after = Some(Utc::now() + chrono::Duration::seconds(120));
+        assert!(!backoff.is_fresh(cache.ttl()));
+        assert!(backoff.backoff_remaining().unwrap() > Duration::from_secs(100));
+    }
+}
@@ -1,7 +1,10 @@
 //! GitHub API client for fetching release information
 
 use crate::core::Result;
-use reqwest::Client;
+use crate::updater::cache::{CachedResponse, ResponseCache};
+use chrono::{DateTime, Utc};
+use reqwest::header::{HeaderMap, ETAG, IF_NONE_MATCH, RETRY_AFTER};
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
@@ -42,12 +45,17 @@ pub struct ReleaseAsset {
 }
 
 /// GitHub API client for update checking
+///
+/// Latest-release lookups go through an optional [`ResponseCache`]; all
+/// requests turn GitHub's rate limit responses into errors saying how long
+/// to wait.
 pub struct UpdateClient {
     client: Client,
     repo_owner: String,
     repo_name: String,
     github_token: Option<String>,
     base_url: String,
+    cache: Option<ResponseCache>,
 }
 
 impl UpdateClient {
@@ -65,6 +73,7 @@ impl UpdateClient {
             repo_name: repo_name.to_string(),
             github_token: None,
             base_url: "https://api.github.com".to_string(),
+            cache: None,
         }
     }
 
@@ -84,26 +93,71 @@ impl UpdateClient {
         self
     }
 
+    /// Use another API root, like a GitHub Enterprise server
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Cache latest-release responses, `None` to always ask the API
+    pub fn with_cache(mut self, cache: Option<ResponseCache>) -> Self {
+        self.cache = cache;
+        self
+    }
+
     /// Get the latest release information
+    ///
+    /// A cached response within its TTL is returned without a request; an
+    /// older one is revalidated with its ETag. While GitHub asks to back
+    /// off, the cached release is returned even if it is stale.
     pub async fn get_latest_release(&self) -> Result<Release> {
         let url = format!(
             "{}/repos/{}/{}/releases/latest",
             self.base_url, self.repo_owner, self.repo_name
         );
 
-        tracing::debug!("Fetching latest release from: {}", url);
+        let cached = self.cache.as_ref().and_then(|cache| cache.load(&url));
+        if let (Some(cache), Some(entry)) = (&self.cache, &cached) {
+            if entry.is_fresh(cache.ttl()) {
+                tracing::debug!("Using cached latest release from {}", entry.fetched_at);
+                return parse_cached_release(entry);
+            }
+            if let Some(wait) = entry.backoff_remaining() {
+                return stale_or_rate_limited(cached.as_ref(), wait);
+            }
+        }
 
-        let mut request = self.client.get(&url);
+        tracing::debug!("Fetching latest release from: {}", url);
 
-        // Add authentication header if token is provided
-        if let Some(token) = &self.github_token {
-            request = request.header("Authorization", format!("token {}", token));
+        let mut request = self.request(&url);
+        if let Some(etag) = cached.as_ref().and_then(|entry| entry.etag.as_deref()) {
+            request = request.header(IF_NONE_MATCH, etag);
         }
-
         let response = request.send().await.map_err(|e| {
             crate::core::AnalyzerError::generic(format!("Failed to fetch release info: {}", e))
         })?;
 
+        if response.status() == StatusCode::NOT_MODIFIED {
+            if let Some(mut entry) = cached.clone().filter(|entry| entry.body.is_some()) {
+                tracing::debug!("Latest release unchanged since {}", entry.fetched_at);
+                entry.fetched_at = Utc::now();
+                entry.retry_after = exhausted_until(response.headers());
+                self.store(&entry);
+                return parse_cached_release(&entry);
+            }
+        }
+
+        if let Some(wait) = rate_limit_wait(response.status(), response.headers()) {
+            if let Some(cache) = &self.cache {
+                let mut entry = cached
+                    .clone()
+                    .unwrap_or_else(|| CachedResponse::new(&url, None, None));
+                entry.retry_after = Some(Utc::now() + wait);
+                cache.store(&entry);
+            }
+            return stale_or_rate_limited(cached.as_ref(), wait);
+        }
+
         if !response.status().is_success() {
             return Err(crate::core::AnalyzerError::generic(format!(
                 "GitHub API request failed with status: {}",
@@ -111,10 +165,23 @@ impl UpdateClient {
             )));
         }
 
-        let release: Release = response.json().await.map_err(|e| {
+        let etag = response
+            .headers()
+            .get(ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(str::to_string);
+        let retry_after = exhausted_until(response.headers());
+        let body: serde_json::Value = response.json().await.map_err(|e| {
+            crate::core::AnalyzerError::generic(format!("Failed to parse release info: {}", e))
+        })?;
+        let release: Release = serde_json::from_value(body.clone()).map_err(|e| {
             crate::core::AnalyzerError::generic(format!("Failed to parse release info: {}", e))
         })?;
 
+        let mut entry = CachedResponse::new(&url, etag, Some(body));
+        entry.retry_after = retry_after;
+        self.store(&entry);
+
         tracing::info!(
             "Retrieved latest release: {} ({})",
             release.tag_name,
@@ -133,23 +200,10 @@ impl UpdateClient {
 
         tracing::debug!("Fetching releases from: {}", url);
 
-        let mut request = self.client.get(&url);
-
-        // Add authentication header if token is provided
-        if let Some(token) = &self.github_token {
-            request = request.header("Authorization", format!("token {}", token));
-        }
-
-        let response = request.send().await.map_err(|e| {
+        let response = self.request(&url).send().await.map_err(|e| {
             crate::core::AnalyzerError::generic(format!("Failed to fetch releases: {}", e))
         })?;
-
-        if !response.status().is_success() {
-            return Err(crate::core::AnalyzerError::generic(format!(
-                "GitHub API request failed with status: {}",
-                response.status()
-            )));
-        }
+        let response = check_status(response, "GitHub API request")?;
 
         let releases: Vec<Release> = response.json().await.map_err(|e| {
             crate::core::AnalyzerError::generic(format!("Failed to parse releases: {}", e))
@@ -169,23 +223,10 @@ impl UpdateClient {
 
         tracing::debug!("Fetching release by tag from: {}", url);
 
-        let mut request = self.client.get(&url);
-
-        // Add authentication header if token is provided
-        if let Some(token) = &self.github_token {
-            request = request.header("Authorization", format!("token {}", token));
-        }
-
-        let response = request.send().await.map_err(|e| {
+        let response = self.request(&url).send().await.map_err(|e| {
             crate::core::AnalyzerError::generic(format!("Failed to fetch release: {}", e))
         })?;
-
-        if !response.status().is_success() {
-            return Err(crate::core::AnalyzerError::generic(format!(
-                "GitHub API request failed with status: {}",
-                response.status()
-            )));
-        }
+        let response = check_status(response, "GitHub API request")?;
 
         let release: Release = response.json().await.map_err(|e| {
             crate::core::AnalyzerError::generic(format!("Failed to parse release: {}", e))
@@ -200,23 +241,10 @@ impl UpdateClient {
     pub async fn check_rate_limit(&self) -> Result<RateLimitInfo> {
         let url = format!("{}/rate_limit", self.base_url);
 
-        let mut request = self.client.get(&url);
-
-        // Add authentication header if token is provided
-        if let Some(token) = &self.github_token {
-            request = request.header("Authorization", format!("token {}", token));
-        }
-
-        let response = request.send().await.map_err(|e| {
+        let response = self.request(&url).send().await.map_err(|e| {
             crate::core::AnalyzerError::generic(format!("Failed to check rate limit: {}", e))
         })?;
-
-        if !response.status().is_success() {
-            return Err(crate::core::AnalyzerError::generic(format!(
-                "Rate limit check failed with status: {}",
-                response.status()
-            )));
-        }
+        let response = check_status(response, "Rate limit check")?;
 
         let rate_limit: RateLimitResponse = response.json().await.map_err(|e| {
             crate::core::AnalyzerError::generic(format!("Failed to parse rate limit: {}", e))
@@ -224,6 +252,131 @@ impl UpdateClient {
 
         Ok(rate_limit.rate)
     }
+
+    /// Download a small text file, like a checksum published with a release
+    pub async fn get_text(&self, url: &str) -> Result<String> {
+        tracing::debug!("Fetching {}", url);
+
+        let response = self.client.get(url).send().await.map_err(|e| {
+            crate::core::AnalyzerError::generic(format!("Failed to fetch {}: {}", url, e))
+        })?;
+        let response = check_status(response, "Download")?;
+
+        response.text().await.map_err(|e| {
+            crate::core::AnalyzerError::generic(format!("Failed to read {}: {}", url, e))
+        })
+    }
+
+    /// GET request with the authentication header, if a token is set
+    fn request(&self, url: &str) -> RequestBuilder {
+        let request = self.client.get(url);
+        match &self.github_token {
+            Some(token) => request.header("Authorization", format!("token {}", token)),
+            None => request,
+        }
+    }
+
+    fn store(&self, entry: &CachedResponse) {
+        if let Some(cache) = &self.cache {
+            cache.store(entry);
+        }
+    }
+}
+
+/// Release of a cached response
+fn parse_cached_release(entry: &CachedResponse) -> Result<Release> {
+    let body = entry.body.clone().unwrap_or_default();
+    serde_json::from_value(body).map_err(|e| {
+        crate::core::AnalyzerError::generic(format!("Failed to parse cached release info: {}", e))
+    })
+}
+
+/// Cached release while rate limited, or an error saying how long to wait
+fn stale_or_rate_limited(cached: Option<&CachedResponse>, wait: Duration) -> Result<Release> {
+    match cached.filter(|entry| entry.body.is_some()) {
+        Some(entry) => {
+            tracing::warn!(
+                "GitHub API rate limit reached, using release info cached at {} (retry in {}s)",
+                entry.fetched_at,
+                wait.as_secs()
+            );
+            parse_cached_release(entry)
+        }
+        None => Err(rate_limited(wait)),
+    }
+}
+
+fn rate_limited(wait: Duration) -> crate::core::AnalyzerError {
+    crate::core::AnalyzerError::generic(format!(
+        "GitHub API rate limit exceeded, retry in {}s or configure a GitHub token",
+        wait.as_secs().max(1)
+    ))
+}
+
+/// Pass successful responses, turn others into errors
+fn check_status(response: Response, action: &str) -> Result<Response> {
+    if let Some(wait) = rate_limit_wait(response.status(), response.headers()) {
+        return Err(rate_limited(wait));
+    }
+    if !response.status().is_success() {
+        return Err(crate::core::AnalyzerError::generic(format!(
+            "{} failed with status: {}",
+            action,
+            response.status()
+        )));
+    }
+    Ok(response)
+}
+
+/// Time to wait before the next request if the response is a rate limit
+///
+/// GitHub answers with 403 or 429 and either a `Retry-After` header or an
+/// exhausted `x-ratelimit-remaining` with its reset time. A 429 without
+/// either means waiting at least a minute.
+fn rate_limit_wait(status: StatusCode, headers: &HeaderMap) -> Option<Duration> {
+    if status != StatusCode::FORBIDDEN && status != StatusCode::TOO_MANY_REQUESTS {
+        return None;
+    }
+    if let Some(wait) = headers
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_retry_after)
+    {
+        return Some(wait);
+    }
+    if let Some(reset) = exhausted_until(headers) {
+        return Some(
+            reset
+                .signed_duration_since(Utc::now())
+                .to_std()
+                .unwrap_or_default(),
+        );
+    }
+    (status == StatusCode::TOO_MANY_REQUESTS).then_some(Duration::from_secs(60))
+}
+
+/// Reset time of the rate limit if no requests remain
+fn exhausted_until(headers: &HeaderMap) -> Option<DateTime<Utc>> {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+    if header("x-ratelimit-remaining")? != "0" {
+        return None;
+    }
+    let reset = header("x-ratelimit-reset")?.parse().ok()?;
+    DateTime::from_timestamp(reset, 0)
+}
+
+/// Parse a `Retry-After` value, either seconds or an HTTP date
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    if let Ok(seconds) = value.trim().parse() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = DateTime::parse_from_rfc2822(value.trim()).ok()?;
+    Some(
+        date.with_timezone(&Utc)
+            .signed_duration_since(Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    )
 }
 
 /// Rate limit information
@@ -242,3 +395,153 @@ pub struct RateLimitInfo {
 struct RateLimitResponse {
     rate: RateLimitInfo,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Request, Server};
+    use std::convert::Infallible;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Serve a fake release API, counting the requests it receives
+    ///
+    /// `/repos/o/current/...` answers with an ETag and honors
+    /// `If-None-Match`, `/repos/o/limited/...` is rate limited.
+    fn mock_github() -> (String, Arc<AtomicUsize>) {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        let make_service = make_service_fn(move |_| {
+            let counter = counter.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request: Request<Body>| {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    async move { Ok::<_, Infallible>(mock_response(&request)) }
+                }))
+            }
+        });
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = Server::from_tcp(listener).unwrap().serve(make_service);
+        tokio::spawn(server);
+        (format!("http://{}", address), requests)
+    }
+
+    fn mock_response(request: &Request<Body>) -> hyper::Response<Body> {
+        let builder = hyper::Response::builder();
+        if request.uri().path().starts_with("/repos/o/limited/") {
+            let reset = Utc::now().timestamp() + 120;
+            return builder
+                .status(403)
+                .header("x-ratelimit-remaining", "0")
+                .header("x-ratelimit-reset", reset.to_string())
+                .body(Body::from(r#"{"message":"API rate limit exceeded"}"#))
+                .unwrap();
+        }
+        if request.headers().get(IF_NONE_MATCH).map(|v| v.as_bytes()) == Some(b"\"v1\"") {
+            return builder.status(304).body(Body::empty()).unwrap();
+        }
+        let release = serde_json::json!({
+            "tag_name": "v1.2.3",
+            "name": "Release 1.2.3",
+            "body": null,
+            "draft": false,
+            "prerelease": false,
+            "assets": [],
+            "published_at": null,
+        });
+        builder
+            .header(ETAG, "\"v1\"")
+            .body(Body::from(release.to_string()))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        assert_eq!(parse_retry_after("120"), Some(Duration::from_secs(120)));
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(Duration::ZERO)
+        );
+        let later = (Utc::now() + chrono::Duration::seconds(90)).to_rfc2822();
+        let wait = parse_retry_after(&later).unwrap();
+        assert!(wait > Duration::from_secs(80) && wait <= Duration::from_secs(90));
+        assert!(parse_retry_after("soon").is_none());
+
+        let mut headers = HeaderMap::new();
+        assert!(rate_limit_wait(StatusCode::FORBIDDEN, &headers).is_none());
+        assert_eq!(
+            rate_limit_wait(StatusCode::TOO_MANY_REQUESTS, &headers),
+            Some(Duration::from_secs(60))
+        );
+        headers.insert(RETRY_AFTER, "30".parse().unwrap());
+        assert_eq!(
+            rate_limit_wait(StatusCode::FORBIDDEN, &headers),
+            Some(Duration::from_secs(30))
+        );
+        assert!(rate_limit_wait(StatusCode::OK, &headers).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_latest_release_cached_with_etag() {
+        let (base_url, requests) = mock_github();
+        let dir = tempfile::tempdir().unwrap();
+        let client = |ttl| {
+            UpdateClient::new("o", "current")
+                .with_base_url(&base_url)
+                .with_cache(Some(ResponseCache::new(dir.path().to_path_buf(), ttl)))
+        };
+
+        // Fresh entries are used without a request
+        let cached = client(Duration::from_secs(3600));
+        assert_eq!(
+            cached.get_latest_release().await.unwrap().tag_name,
+            "v1.2.3"
+        );
+        assert_eq!(
+            cached.get_latest_release().await.unwrap().tag_name,
+            "v1.2.3"
+        );
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        // Expired entries are revalidated and renewed by a 304
+        let expired = client(Duration::ZERO);
+        assert_eq!(
+            expired.get_latest_release().await.unwrap().tag_name,
+            "v1.2.3"
+        );
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        let url = format!("{}/repos/o/current/releases/latest", base_url);
+        let entry = ResponseCache::new(dir.path().to_path_buf(), Duration::ZERO)
+            .load(&url)
+            .unwrap();
+        assert_eq!(entry.etag.as_deref(), Some("\"v1\""));
+        assert!(entry.body.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_recorded_and_honored() {
+        let (base_url, requests) = mock_github();
+        let dir = tempfile::tempdir().unwrap();
+        let client = UpdateClient::new("o", "limited")
+            .with_base_url(&base_url)
+            .with_cache(Some(ResponseCache::new(
+                dir.path().to_path_buf(),
+                Duration::from_secs(3600),
+            )));
+
+        let error = client.get_latest_release().await.unwrap_err().to_string();
+        assert!(error.contains("rate limit exceeded"), "{}", error);
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        // The back-off is honored without asking again
+        let error = client.get_latest_release().await.unwrap_err().to_string();
+        assert!(error.contains("retry in"), "{}", error);
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        let uncached = UpdateClient::new("o", "limited").with_base_url(&base_url);
+        assert!(uncached.get_releases(10, 1).await.is_err());
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+}
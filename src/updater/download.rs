@@ -2,6 +2,7 @@
 
 use crate::analyzers::common::{calculate_file_hash, get_file_size};
 use crate::core::Result;
+use crate::utils::{Workspace, WorkspaceArea};
 use reqwest::Client;
 use std::path::{Path, PathBuf};
 use std::time::Duration;
@@ -23,7 +24,7 @@ impl DownloadManager {
             .build()
             .expect("Failed to create HTTP client");
 
-        let temp_dir = std::env::temp_dir().join("installer-analyzer-updates");
+        let temp_dir = Workspace::new().area_dir(WorkspaceArea::Updates);
 
         Self { client, temp_dir }
     }
@@ -276,10 +277,10 @@ mod tests {
     #[test]
     fn test_download_manager_creation() {
         let manager = DownloadManager::new();
-        assert!(manager
-            .get_temp_dir()
-            .to_string_lossy()
-            .contains("installer-analyzer-updates"));
+        assert_eq!(
+            manager.get_temp_dir(),
+            Workspace::new().area_dir(WorkspaceArea::Updates)
+        );
     }
 
     #[test]
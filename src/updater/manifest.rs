@@ -0,0 +1,237 @@
+//! Update manifests of self-hosted release servers
+//!
+//! Enterprises that mirror binaries internally cannot let every machine ask
+//! GitHub for releases. Instead, [`UpdateConfig::manifest_url`] points at a
+//! JSON document listing the latest version and one download per platform:
+//!
+//! ```json
+//! {
+//!   "version": "0.8.0",
+//!   "release_notes": "Faster MSI parsing",
+//!   "platforms": {
+//!     "windows-x86_64": {
+//!       "url": "https://mirror.example.com/installer-analyzer/0.8.0/installer-analyzer.exe",
+//!       "sha256": "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08",
+//!       "size": 14680064
+//!     }
+//!   }
+//! }
+//! ```
+//!
+//! Platforms are keyed `<os>-<arch>` as in [`platform_key`], or by target
+//! triple like `aarch64-pc-windows-msvc` where builds differ in more than
+//! system and architecture. The SHA256 is mandatory, downloads from a
+//! mirror are always verified. The manifest and downloads must be served
+//! over HTTPS, except from the local machine.
+//!
+//! [`UpdateConfig::manifest_url`]: crate::updater::UpdateConfig::manifest_url
+
+use crate::core::{AnalyzerError, Result};
+use crate::updater::assets::BUILD_TARGET;
+use reqwest::{Client, Url};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Latest release as described by a manifest
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct UpdateManifest {
+    /// Version of the release, with or without a `v` prefix
+    pub version: String,
+    pub release_notes: Option<String>,
+    /// Downloads keyed by platform
+    pub platforms: HashMap<String, ManifestAsset>,
+}
+
+/// Download of one platform
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ManifestAsset {
+    pub url: String,
+    /// Hex-encoded SHA256 of the download
+    pub sha256: String,
+    /// File size in bytes
+    pub size: Option<u64>,
+}
+
+impl UpdateManifest {
+    /// Parse and validate a manifest
+    pub fn parse(content: &str) -> Result<Self> {
+        let manifest: Self = serde_json::from_str(content)
+            .map_err(|e| AnalyzerError::generic(format!("Invalid update manifest: {}", e)))?;
+        for (platform, asset) in &manifest.platforms {
+            check_url(&asset.url).map_err(|e| {
+                AnalyzerError::generic(format!("Invalid download for {}: {}", platform, e))
+            })?;
+            let is_sha256 =
+                asset.sha256.len() == 64 && asset.sha256.chars().all(|c| c.is_ascii_hexdigit());
+            if !is_sha256 {
+                return Err(AnalyzerError::generic(format!(
+                    "Invalid update manifest: download for {} has no valid SHA256",
+                    platform
+                )));
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// Download for the platform the tool runs on
+    ///
+    /// A key naming the full target triple takes precedence over the
+    /// `<os>-<arch>` key.
+    pub fn current_asset(&self) -> Result<&ManifestAsset> {
+        let key = platform_key();
+        self.platforms
+            .get(BUILD_TARGET)
+            .or_else(|| self.platforms.get(&key))
+            .ok_or_else(|| {
+                AnalyzerError::generic(format!(
+                    "Update manifest has no download for platform {} ({})",
+                    key, BUILD_TARGET
+                ))
+            })
+    }
+}
+
+/// Platform key of the running build, like `windows-x86_64`
+pub fn platform_key() -> String {
+    format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH)
+}
+
+/// Client fetching the manifest of a self-hosted release server
+pub struct ManifestClient {
+    client: Client,
+    url: String,
+}
+
+impl ManifestClient {
+    /// Create a client for the manifest at `url`
+    pub fn new(url: &str, timeout_seconds: u64) -> Self {
+        let client = Client::builder()
+            .user_agent("installer-analyzer-updater/1.0")
+            .timeout(Duration::from_secs(timeout_seconds))
+            .build()
+            .expect("Failed to create HTTP client");
+
+        Self {
+            client,
+            url: url.to_string(),
+        }
+    }
+
+    /// Fetch and validate the manifest
+    pub async fn fetch(&self) -> Result<UpdateManifest> {
+        check_url(&self.url)
+            .map_err(|e| AnalyzerError::generic(format!("Invalid update manifest URL: {}", e)))?;
+        tracing::debug!("Fetching update manifest from: {}", self.url);
+
+        let response = self.client.get(&self.url).send().await.map_err(|e| {
+            AnalyzerError::generic(format!("Failed to fetch update manifest: {}", e))
+        })?;
+        if !response.status().is_success() {
+            return Err(AnalyzerError::generic(format!(
+                "Update manifest request failed with status: {}",
+                response.status()
+            )));
+        }
+        let content = response.text().await.map_err(|e| {
+            AnalyzerError::generic(format!("Failed to read update manifest: {}", e))
+        })?;
+
+        let manifest = UpdateManifest::parse(&content)?;
+        tracing::info!("Retrieved update manifest for version {}", manifest.version);
+        Ok(manifest)
+    }
+}
+
+/// Accept HTTPS URLs, and plain HTTP ones of the local machine
+pub(crate) fn check_url(url: &str) -> std::result::Result<(), String> {
+    let parsed = Url::parse(url).map_err(|e| format!("{} ({})", e, url))?;
+    let is_local = matches!(parsed.host_str(), Some("localhost" | "127.0.0.1" | "[::1]"));
+    match parsed.scheme() {
+        "https" => Ok(()),
+        "http" if is_local => Ok(()),
+        _ => Err(format!("{} is not an HTTPS URL", url)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASH: &str = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+
+    fn manifest(url: &str, sha256: &str) -> String {
+        serde_json::json!({
+            "version": "v9.9.9",
+            "platforms": {
+                platform_key(): { "url": url, "sha256": sha256, "size": 42 },
+            },
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_parse_manifest() {
+        let parsed = UpdateManifest::parse(&manifest("https://mirror/app.exe", HASH)).unwrap();
+        assert_eq!(parsed.version, "v9.9.9");
+        assert!(parsed.release_notes.is_none());
+        let asset = parsed.current_asset().unwrap();
+        assert_eq!(asset.url, "https://mirror/app.exe");
+        assert_eq!(asset.size, Some(42));
+
+        assert!(UpdateManifest::parse(&manifest("http://mirror/app.exe", HASH)).is_err());
+        assert!(UpdateManifest::parse(&manifest("https://mirror/app.exe", "abc")).is_err());
+        assert!(UpdateManifest::parse(r#"{"version": "1.0.0"}"#).is_err());
+
+        let other = r#"{"version": "1.0.0", "platforms": {}}"#;
+        let error = UpdateManifest::parse(other)
+            .unwrap()
+            .current_asset()
+            .unwrap_err();
+        assert!(error.to_string().contains(&platform_key()));
+    }
+
+    #[test]
+    fn test_check_url() {
+        assert!(check_url("https://mirror.example.com/manifest.json").is_ok());
+        assert!(check_url("http://localhost:8080/manifest.json").is_ok());
+        assert!(check_url("http://127.0.0.1/manifest.json").is_ok());
+        assert!(check_url("http://mirror.example.com/manifest.json").is_err());
+        assert!(check_url("file:///etc/manifest.json").is_err());
+        assert!(check_url("manifest.json").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_check_for_updates_from_manifest() {
+        use crate::updater::{UpdateConfig, Updater};
+        use hyper::service::{make_service_fn, service_fn};
+        use hyper::{Body, Response, Server};
+        use std::convert::Infallible;
+
+        let content = manifest("https://mirror.example.com/app.exe", HASH);
+        let make_service = make_service_fn(move |_| {
+            let content = content.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |_| {
+                    let content = content.clone();
+                    async move { Ok::<_, Infallible>(Response::new(Body::from(content))) }
+                }))
+            }
+        });
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(Server::from_tcp(listener).unwrap().serve(make_service));
+
+        let updater = Updater::with_config(UpdateConfig {
+            manifest_url: Some(format!("http://{}/manifest.json", address)),
+            cache_ttl_seconds: 0,
+            ..UpdateConfig::default()
+        });
+        let info = updater.check_for_updates().await.unwrap();
+        assert!(info.update_available);
+        assert_eq!(info.latest_version.to_string(), "9.9.9");
+        assert_eq!(info.download_url, "https://mirror.example.com/app.exe");
+        assert_eq!(info.file_hash.as_deref(), Some(HASH));
+        assert_eq!(info.file_size, 42);
+    }
+}
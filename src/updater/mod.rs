@@ -5,16 +5,26 @@
 
 use crate::core::Result;
 
+pub mod assets;
+pub mod backup;
+pub mod cache;
 pub mod client;
+pub mod data_packs;
 pub mod download;
+pub mod manifest;
 pub mod version;
 
 #[cfg(windows)]
 pub mod windows;
 
 // Re-export main types
+pub use assets::TargetSpec;
+pub use backup::{BackupRecord, BackupStore};
+pub use cache::ResponseCache;
 pub use client::UpdateClient;
+pub use data_packs::{DataPackClient, DataPackIndex, DataPackKind, DataPackStore, InstalledPack};
 pub use download::DownloadManager;
+pub use manifest::{ManifestClient, UpdateManifest};
 pub use version::{Version, VersionChecker};
 
 #[cfg(windows)]
@@ -33,6 +43,12 @@ pub struct UpdateConfig {
     pub timeout_seconds: u64,
     /// Whether to verify file signatures
     pub verify_signatures: bool,
+    /// Seconds the latest release is cached on disk, 0 to always ask GitHub
+    pub cache_ttl_seconds: u64,
+    /// Manifest of a self-hosted release server, used instead of GitHub
+    pub manifest_url: Option<String>,
+    /// Install releases that publish no checksum for the downloaded asset
+    pub allow_unverified: bool,
 }
 
 impl Default for UpdateConfig {
@@ -43,6 +59,9 @@ impl Default for UpdateConfig {
             github_token: None,
             timeout_seconds: 30,
             verify_signatures: false,
+            cache_ttl_seconds: cache::DEFAULT_CACHE_TTL.as_secs(),
+            manifest_url: None,
+            allow_unverified: false,
         }
     }
 }
@@ -71,6 +90,7 @@ pub struct Updater {
     #[allow(dead_code)] // Used in future implementations
     config: UpdateConfig,
     client: UpdateClient,
+    manifest_client: Option<ManifestClient>,
     version_checker: VersionChecker,
     download_manager: DownloadManager,
 }
@@ -83,9 +103,17 @@ impl Updater {
 
     /// Create a new updater with custom configuration
     pub fn with_config(config: UpdateConfig) -> Self {
+        let cache = (config.cache_ttl_seconds > 0).then(|| {
+            ResponseCache::in_workspace(std::time::Duration::from_secs(config.cache_ttl_seconds))
+        });
         let client = UpdateClient::new(&config.repo_owner, &config.repo_name)
             .with_timeout(config.timeout_seconds)
-            .with_token(config.github_token.clone());
+            .with_token(config.github_token.clone())
+            .with_cache(cache);
+        let manifest_client = config
+            .manifest_url
+            .as_deref()
+            .map(|url| ManifestClient::new(url, config.timeout_seconds));
 
         let version_checker = VersionChecker::new();
         let download_manager = DownloadManager::new();
@@ -93,16 +121,61 @@ impl Updater {
         Self {
             config,
             client,
+            manifest_client,
             version_checker,
             download_manager,
         }
     }
 
     /// Check for available updates
+    ///
+    /// Asks the self-hosted release server if a manifest is configured,
+    /// GitHub otherwise.
     pub async fn check_for_updates(&self) -> Result<UpdateInfo> {
         tracing::info!("Checking for updates...");
 
         let current_version = self.version_checker.get_current_version()?;
+        let update_info = match &self.manifest_client {
+            Some(manifest_client) => {
+                self.check_manifest(manifest_client, current_version)
+                    .await?
+            }
+            None => self.check_github(current_version).await?,
+        };
+
+        tracing::info!(
+            "Update check completed. Current: {}, Latest: {}, Available: {}",
+            update_info.current_version,
+            update_info.latest_version,
+            update_info.update_available
+        );
+
+        Ok(update_info)
+    }
+
+    /// Check the manifest of a self-hosted release server
+    async fn check_manifest(
+        &self,
+        manifest_client: &ManifestClient,
+        current_version: Version,
+    ) -> Result<UpdateInfo> {
+        let manifest = manifest_client.fetch().await?;
+        let latest_version = self.version_checker.parse_version(&manifest.version)?;
+        let asset = manifest.current_asset()?;
+
+        Ok(UpdateInfo {
+            update_available: latest_version > current_version,
+            latest_version,
+            current_version,
+            download_url: asset.url.clone(),
+            file_size: asset.size.unwrap_or(0),
+            file_hash: Some(asset.sha256.clone()),
+            release_notes: manifest.release_notes,
+        })
+    }
+
+    /// Check the latest GitHub release
+    async fn check_github(&self, current_version: Version) -> Result<UpdateInfo> {
         let latest_release = self.client.get_latest_release().await?;
         let latest_version = self
             .version_checker
@@ -110,26 +183,22 @@ impl Updater {
 
         let update_available = latest_version > current_version;
 
-        // Find the appropriate download URL for the current platform
-        let download_url = self.find_platform_download_url(&latest_release.assets)?;
+        // Find the appropriate download for the current platform
+        let asset = self.find_platform_asset(&latest_release.assets)?;
+        let file_hash = self
+            .find_asset_checksum(&latest_release.assets, asset)
+            .await?;
 
         let update_info = UpdateInfo {
             latest_version: latest_version.clone(),
             current_version,
             update_available,
-            download_url: download_url.clone(),
-            file_size: 0,    // Will be filled by download manager
-            file_hash: None, // Will be filled by download manager
+            download_url: asset.browser_download_url.clone(),
+            file_size: asset.size,
+            file_hash,
             release_notes: latest_release.body,
         };
 
-        tracing::info!(
-            "Update check completed. Current: {}, Latest: {}, Available: {}",
-            update_info.current_version,
-            update_info.latest_version,
-            update_info.update_available
-        );
-
         Ok(update_info)
     }
 
@@ -148,10 +217,21 @@ impl Updater {
             .await?;
 
         // Verify the downloaded file
-        if let Some(expected_hash) = &update_info.file_hash {
-            self.download_manager
-                .verify_file_hash(&downloaded_file, expected_hash)
-                .await?;
+        match &update_info.file_hash {
+            Some(expected_hash) => {
+                self.download_manager
+                    .verify_file_hash(&downloaded_file, expected_hash)
+                    .await?;
+            }
+            None if self.config.allow_unverified => {
+                tracing::warn!("Installing an update without checksum verification");
+            }
+            None => {
+                return Err(crate::core::AnalyzerError::generic(
+                    "The release publishes no checksum for the update, refusing to install \
+                     an unverified binary (use --allow-unverified to install it anyway)",
+                ));
+            }
         }
 
         // Perform platform-specific update
@@ -169,28 +249,75 @@ impl Updater {
         }
     }
 
+    /// Restore the executable backed up before the most recent update
+    pub fn rollback(&self) -> Result<BackupRecord> {
+        let current_exe = std::env::current_exe().map_err(|e| {
+            crate::core::AnalyzerError::generic(format!(
+                "Failed to get current executable path: {}",
+                e
+            ))
+        })?;
+        BackupStore::new().restore_latest(&current_exe)
+    }
+
     /// Find the appropriate download URL for the current platform
-    fn find_platform_download_url(&self, assets: &[client::ReleaseAsset]) -> Result<String> {
-        // Look for Windows executable
-        #[cfg(windows)]
-        {
-            for asset in assets {
-                if asset.name.contains("windows") && asset.name.ends_with(".exe") {
-                    return Ok(asset.browser_download_url.clone());
-                }
-            }
-        }
+    fn find_platform_asset<'a>(
+        &self,
+        assets: &'a [client::ReleaseAsset],
+    ) -> Result<&'a client::ReleaseAsset> {
+        let target = TargetSpec::current();
+        let asset = assets::select_asset(assets, &target).ok_or_else(|| {
+            crate::core::AnalyzerError::generic(format!(
+                "No suitable download found for current platform ({})",
+                target.triple
+            ))
+        })?;
+        tracing::debug!(
+            "Selected release asset {} for {}",
+            asset.name,
+            target.triple
+        );
+        Ok(asset)
+    }
 
-        // Look for generic executable
-        for asset in assets {
-            if asset.name.ends_with(".exe") {
-                return Ok(asset.browser_download_url.clone());
+    /// SHA256 of an asset, if the release publishes checksums
+    ///
+    /// A checksum listing that leaves the asset out is tolerated, releases
+    /// may list their archives only; a dedicated checksum file must be valid.
+    /// Installing an asset without a checksum needs `allow_unverified`.
+    async fn find_asset_checksum(
+        &self,
+        assets: &[client::ReleaseAsset],
+        asset: &client::ReleaseAsset,
+    ) -> Result<Option<String>> {
+        let Some(checksum_asset) = assets::checksum_asset(assets, asset) else {
+            tracing::warn!("Release publishes no checksum for {}", asset.name);
+            return Ok(None);
+        };
+        let content = self
+            .client
+            .get_text(&checksum_asset.browser_download_url)
+            .await?;
+        let hash = assets::parse_checksum(&content, &asset.name);
+        let dedicated = checksum_asset
+            .name
+            .to_lowercase()
+            .starts_with(&asset.name.to_lowercase());
+        match hash {
+            Some(hash) => Ok(Some(hash)),
+            None if dedicated => Err(crate::core::AnalyzerError::generic(format!(
+                "{} holds no valid checksum for {}",
+                checksum_asset.name, asset.name
+            ))),
+            None => {
+                tracing::warn!(
+                    "{} lists no checksum for {}",
+                    checksum_asset.name,
+                    asset.name
+                );
+                Ok(None)
             }
         }
-
-        Err(crate::core::AnalyzerError::generic(
-            "No suitable download found for current platform",
-        ))
     }
 }
 
@@ -210,6 +337,9 @@ impl Updater {
             github_token: None,
             timeout_seconds: 5, // Shorter timeout for tests
             verify_signatures: false,
+            cache_ttl_seconds: 0,
+            manifest_url: None,
+            allow_unverified: false,
         };
         Self::with_config(config)
     }
@@ -4,7 +4,8 @@
 //! while avoiding file locking issues through a "replace-and-restart" mechanism.
 
 use crate::core::Result;
-use crate::utils::is_admin;
+use crate::updater::backup::BackupStore;
+use crate::utils::{is_admin, Workspace, WorkspaceArea};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
@@ -17,7 +18,7 @@ pub struct WindowsUpdater {
 impl WindowsUpdater {
     /// Create a new Windows updater
     pub fn new() -> Self {
-        let temp_dir = std::env::temp_dir().join("installer-analyzer-update");
+        let temp_dir = Workspace::new().area_dir(WorkspaceArea::UpdateStaging);
         Self { temp_dir }
     }
 
@@ -80,31 +81,22 @@ impl WindowsUpdater {
         std::process::exit(0);
     }
 
-    /// Create a backup of the current executable
+    /// Create a backup of the current executable for `update --rollback`
     async fn create_backup(&self, current_exe: &Path) -> Result<PathBuf> {
-        let backup_filename = format!(
-            "{}.backup.{}",
-            current_exe
-                .file_stem()
-                .and_then(|s| s.to_str())
-                .unwrap_or("installer-analyzer"),
-            chrono::Utc::now().format("%Y%m%d_%H%M%S")
-        );
-
-        let backup_path = self.temp_dir.join(backup_filename);
-
-        tracing::info!("Creating backup: {}", backup_path.display());
-
-        tokio::fs::copy(current_exe, &backup_path)
-            .await
-            .map_err(|e| {
-                crate::core::AnalyzerError::generic(format!("Failed to create backup: {}", e))
-            })?;
-
-        Ok(backup_path)
+        let current_exe = current_exe.to_path_buf();
+        let record = tokio::task::spawn_blocking(move || {
+            BackupStore::new().create(&current_exe, env!("CARGO_PKG_VERSION"))
+        })
+        .await
+        .map_err(|e| crate::core::AnalyzerError::generic(format!("Backup task failed: {}", e)))??;
+
+        Ok(record.backup)
     }
 
     /// Create a batch script for performing the file replacement
+    ///
+    /// The replaced executable must pass a `--version` self-check, otherwise
+    /// the backup is copied back.
     async fn create_update_batch_script(
         &self,
         current_exe: &Path,
@@ -113,7 +105,8 @@ impl WindowsUpdater {
     ) -> Result<PathBuf> {
         let script_path = self.temp_dir.join("update.bat");
 
-        // Create batch script content
+        // Labels instead of nested blocks, %ERRORLEVEL% is expanded when a
+        // block is parsed rather than when its commands run
         let script_content = format!(
             r#"@echo off
 echo Starting installer-analyzer update process...
@@ -124,35 +117,38 @@ timeout /t 2 /nobreak >nul
 REM Attempt to replace the executable
 echo Replacing executable...
 copy /Y "{new_binary}" "{current_exe}"
+if errorlevel 1 goto restore
 
-if %ERRORLEVEL% EQU 0 (
-    echo Update successful!
-    echo Cleaning up temporary files...
-    
-    REM Clean up the downloaded file
-    del "{new_binary}" 2>nul
-    
-    REM Clean up backup (optional, keep for safety)
-    REM del "{backup_path}" 2>nul
-    
-    echo Restarting application...
-    start "" "{current_exe}"
-    
-    echo Update completed successfully.
-) else (
-    echo Update failed! Attempting to restore backup...
-    copy /Y "{backup_path}" "{current_exe}"
-    
-    if %ERRORLEVEL% EQU 0 (
-        echo Backup restored successfully.
-        start "" "{current_exe}"
-    ) else (
-        echo CRITICAL ERROR: Failed to restore backup!
-        echo Please manually restore from: {backup_path}
-        pause
-    )
-)
+REM Make sure the new executable starts
+echo Verifying new version...
+"{current_exe}" --version >nul 2>&1
+if errorlevel 1 goto restore
 
+echo Update successful!
+echo Cleaning up temporary files...
+
+REM Clean up the downloaded file, the backup is kept for rollback
+del "{new_binary}" 2>nul
+
+echo Restarting application...
+start "" "{current_exe}"
+
+echo Update completed successfully.
+goto done
+
+:restore
+echo Update failed! Restoring previous version...
+copy /Y "{backup_path}" "{current_exe}"
+if errorlevel 1 goto restore_failed
+echo Previous version restored successfully.
+goto done
+
+:restore_failed
+echo CRITICAL ERROR: Failed to restore backup!
+echo Please manually restore from: {backup_path}
+pause
+
+:done
 REM Clean up this script (self-delete)
 del "%~f0" 2>nul
 "#,
@@ -326,10 +322,10 @@ mod tests {
     #[test]
     fn test_windows_updater_creation() {
         let updater = WindowsUpdater::new();
-        assert!(updater
-            .get_temp_dir()
-            .to_string_lossy()
-            .contains("installer-analyzer-update"));
+        assert_eq!(
+            updater.get_temp_dir(),
+            Workspace::new().area_dir(WorkspaceArea::UpdateStaging)
+        );
     }
 
     #[test]
@@ -0,0 +1,441 @@
+//! Diagnostic reports of crashes
+//!
+//! Malformed installers occasionally make a parser panic, and a report that
+//! only says "it crashed" rarely allows to reproduce that. [`install_panic_hook`]
+//! writes a report with the panic message, a backtrace, the most recent log
+//! lines and the name, size and SHA256 of the installers being analyzed,
+//! then prints its path so it can be attached to an issue. The installers
+//! themselves are never included, they may be confidential; the hash lets
+//! maintainers ask for the exact file.
+//!
+//! [`isolate`] turns a panic while analyzing one installer into an error, so
+//! batch runs and workers continue with the next input.
+
+use crate::core::{AnalyzerError, Result};
+use crate::utils::Workspace;
+use chrono::{DateTime, Utc};
+use futures_util::FutureExt;
+use sha2::{Digest, Sha256};
+use std::any::Any;
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::future::Future;
+use std::io::Read;
+use std::panic::AssertUnwindSafe;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tracing_subscriber::fmt::MakeWriter;
+
+/// Log lines kept for crash reports
+pub const MAX_LOG_LINES: usize = 200;
+
+/// Number of crash reports kept, older ones are removed when one is written
+pub const MAX_REPORTS: usize = 10;
+
+/// Directory name of the crash reports below the workspace root
+const REPORT_DIR_NAME: &str = "crash-reports";
+
+/// Where crash reports are meant to be attached
+const ISSUES_URL: &str = "https://github.com/loonghao/installer-analyzer/issues";
+
+/// Most recent log lines, oldest first
+static RECENT_LOGS: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// Installers currently being analyzed
+static ACTIVE_INPUTS: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// Set while a report is written, a panic in the hook itself is not reported
+static REPORTING: AtomicBool = AtomicBool::new(false);
+
+/// Install a panic hook writing a crash report after the default message
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        if REPORTING.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let report = CrashReport::capture(info);
+        match report.write(&report_dir()) {
+            Ok(path) => {
+                eprintln!();
+                eprintln!(
+                    "installer-analyzer hit an internal error. A diagnostic report was written to:"
+                );
+                eprintln!("  {}", path.display());
+                eprintln!(
+                    "Please attach it to an issue at {}. It contains no installer contents, only names and SHA256 hashes.",
+                    ISSUES_URL
+                );
+            }
+            Err(e) => eprintln!("Failed to write crash report: {}", e),
+        }
+        REPORTING.store(false, Ordering::SeqCst);
+    }));
+}
+
+/// Directory crash reports are written to
+pub fn report_dir() -> PathBuf {
+    Workspace::new().root().join(REPORT_DIR_NAME)
+}
+
+/// Record `path` as being analyzed until the guard is dropped
+pub fn track_input(path: &Path) -> InputGuard {
+    lock(&ACTIVE_INPUTS).push(path.to_path_buf());
+    InputGuard {
+        path: path.to_path_buf(),
+    }
+}
+
+/// Installer being analyzed, see [`track_input`]
+#[derive(Debug)]
+pub struct InputGuard {
+    path: PathBuf,
+}
+
+impl Drop for InputGuard {
+    fn drop(&mut self) {
+        let mut inputs = lock(&ACTIVE_INPUTS);
+        if let Some(index) = inputs.iter().position(|path| path == &self.path) {
+            inputs.remove(index);
+        }
+    }
+}
+
+/// Writer of the log layer keeping the most recent lines for crash reports
+///
+/// Added next to the console output, so that lines are kept even when
+/// logging to the console is off.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RecentLogs;
+
+impl RecentLogs {
+    /// Kept lines, oldest first
+    pub fn lines() -> Vec<String> {
+        lock(&RECENT_LOGS).iter().cloned().collect()
+    }
+}
+
+impl<'a> MakeWriter<'a> for RecentLogs {
+    type Writer = RecentLogWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RecentLogWriter(Vec::new())
+    }
+}
+
+/// Buffer of one log event, added to the kept lines when dropped
+#[derive(Debug)]
+pub struct RecentLogWriter(Vec<u8>);
+
+impl std::io::Write for RecentLogWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for RecentLogWriter {
+    fn drop(&mut self) {
+        let text = String::from_utf8_lossy(&self.0);
+        let mut logs = lock(&RECENT_LOGS);
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            if logs.len() == MAX_LOG_LINES {
+                logs.pop_front();
+            }
+            logs.push_back(line.to_string());
+        }
+    }
+}
+
+/// Installer identified by name, size and hash only
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputDigest {
+    /// File name, without the directories it was read from
+    pub name: String,
+    pub size: Option<u64>,
+    /// Hex-encoded SHA256, `None` if the file could not be read
+    pub sha256: Option<String>,
+}
+
+impl InputDigest {
+    /// Digest of the file at `path`
+    pub fn of(path: &Path) -> Self {
+        Self {
+            name: crate::utils::entry_file_name(path),
+            size: std::fs::metadata(path).ok().map(|metadata| metadata.len()),
+            sha256: sha256_file(path).ok(),
+        }
+    }
+}
+
+/// Diagnostics of a panic
+#[derive(Debug, Clone)]
+pub struct CrashReport {
+    pub version: String,
+    /// Target triple of the build
+    pub target: String,
+    pub created_at: DateTime<Utc>,
+    pub thread: Option<String>,
+    pub message: String,
+    /// Source location of the panic
+    pub location: Option<String>,
+    pub backtrace: String,
+    pub inputs: Vec<InputDigest>,
+    pub recent_logs: Vec<String>,
+}
+
+impl CrashReport {
+    /// Collect the diagnostics of a panic
+    ///
+    /// Locks are only tried, the panic may have happened while one was held.
+    pub fn capture(info: &std::panic::PanicHookInfo<'_>) -> Self {
+        let message = panic_message(info.payload());
+        let inputs = ACTIVE_INPUTS
+            .try_lock()
+            .map(|inputs| inputs.clone())
+            .unwrap_or_default();
+        let recent_logs = RECENT_LOGS
+            .try_lock()
+            .map(|logs| logs.iter().cloned().collect())
+            .unwrap_or_default();
+
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            target: env!("BUILD_TARGET").to_string(),
+            created_at: Utc::now(),
+            thread: std::thread::current().name().map(str::to_string),
+            message,
+            location: info.location().map(|location| location.to_string()),
+            backtrace: std::backtrace::Backtrace::force_capture().to_string(),
+            inputs: inputs.iter().map(|path| InputDigest::of(path)).collect(),
+            recent_logs,
+        }
+    }
+
+    /// Plain-text form of the report
+    pub fn render(&self) -> String {
+        let mut text = String::new();
+        let _ = writeln!(text, "installer-analyzer crash report");
+        let _ = writeln!(text);
+        let _ = writeln!(text, "Version: {}", self.version);
+        let _ = writeln!(text, "Target: {}", self.target);
+        let _ = writeln!(text, "Time: {}", self.created_at.to_rfc3339());
+        let _ = writeln!(
+            text,
+            "Thread: {}",
+            self.thread.as_deref().unwrap_or("<unnamed>")
+        );
+        let _ = writeln!(
+            text,
+            "Location: {}",
+            self.location.as_deref().unwrap_or("unknown")
+        );
+        let _ = writeln!(text);
+        let _ = writeln!(text, "## Panic");
+        let _ = writeln!(text, "{}", self.message);
+        let _ = writeln!(text);
+        let _ = writeln!(text, "## Inputs");
+        if self.inputs.is_empty() {
+            let _ = writeln!(text, "(none)");
+        }
+        for input in &self.inputs {
+            let size = input
+                .size
+                .map(|size| size.to_string())
+                .unwrap_or_else(|| "unknown".to_string());
+            let _ = writeln!(
+                text,
+                "{} size={} sha256={}",
+                input.name,
+                size,
+                input.sha256.as_deref().unwrap_or("unreadable")
+            );
+        }
+        let _ = writeln!(text);
+        let _ = writeln!(text, "## Recent log lines");
+        for line in &self.recent_logs {
+            let _ = writeln!(text, "{}", line);
+        }
+        let _ = writeln!(text);
+        let _ = writeln!(text, "## Backtrace");
+        let _ = writeln!(text, "{}", self.backtrace);
+        text
+    }
+
+    /// Write the report to `dir`, removing the oldest beyond [`MAX_REPORTS`]
+    pub fn write(&self, dir: &Path) -> Result<PathBuf> {
+        std::fs::create_dir_all(dir)?;
+        let path = dir.join(format!(
+            "crash-{}-{}.txt",
+            self.created_at.format("%Y%m%d_%H%M%S%3f"),
+            std::process::id()
+        ));
+        std::fs::write(&path, self.render())?;
+
+        let mut earlier: Vec<PathBuf> = std::fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|other| {
+                other != &path
+                    && other
+                        .file_name()
+                        .and_then(|name| name.to_str())
+                        .is_some_and(|name| name.starts_with("crash-") && name.ends_with(".txt"))
+            })
+            .collect();
+        earlier.sort();
+        let excess = (earlier.len() + 1).saturating_sub(MAX_REPORTS);
+        for old in &earlier[..excess] {
+            let _ = std::fs::remove_file(old);
+        }
+
+        Ok(path)
+    }
+}
+
+/// Hex-encoded SHA256 of a file, read in chunks
+/// Run the analysis of `input`, turning a panic into an error
+///
+/// The panic hook has already written a crash report when the error is
+/// returned. Analyzers keep no state shared between inputs that a panic
+/// could leave inconsistent, so asserting unwind safety is sound.
+pub async fn isolate<T>(input: &Path, analysis: impl Future<Output = Result<T>>) -> Result<T> {
+    match AssertUnwindSafe(analysis).catch_unwind().await {
+        Ok(result) => result,
+        Err(payload) => Err(AnalyzerError::crashed(
+            input,
+            panic_message(payload.as_ref()),
+        )),
+    }
+}
+
+/// Message of a panic payload
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "Box<dyn Any>".to_string())
+}
+
+/// Hex-encoded SHA256 of a file, read in chunks
+pub(crate) fn sha256_file(path: &Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Lock that stays usable after a panic while it was held
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_track_input() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("secret-setup.msi");
+        std::fs::write(&path, b"test").unwrap();
+
+        {
+            let _guard = track_input(&path);
+            assert!(lock(&ACTIVE_INPUTS).contains(&path));
+        }
+        assert!(!lock(&ACTIVE_INPUTS).contains(&path));
+
+        let digest = InputDigest::of(&path);
+        assert_eq!(digest.name, "secret-setup.msi");
+        assert_eq!(digest.size, Some(4));
+        assert_eq!(
+            digest.sha256.as_deref(),
+            Some("9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08")
+        );
+        assert_eq!(InputDigest::of(&dir.path().join("missing")).sha256, None);
+    }
+
+    #[tokio::test]
+    async fn test_isolate_turns_panics_into_errors() {
+        let input = Path::new("broken.msi");
+        assert_eq!(isolate(input, async { Ok(1) }).await.unwrap(), 1);
+
+        let error = isolate(input, async {
+            let table: Vec<u8> = Vec::new();
+            Ok(table[3])
+        })
+        .await
+        .unwrap_err();
+        assert!(matches!(error, AnalyzerError::Crashed { .. }));
+        let error = error.to_string();
+        assert!(error.contains("Analyzer crashed on broken.msi"));
+        assert!(error.contains("index out of bounds"));
+    }
+
+    #[test]
+    fn test_recent_logs_are_bounded() {
+        for index in 0..MAX_LOG_LINES + 5 {
+            let mut writer = RecentLogs.make_writer();
+            writeln!(writer, "crash test line {}", index).unwrap();
+        }
+        let lines = RecentLogs::lines();
+        assert_eq!(lines.len(), MAX_LOG_LINES);
+        assert!(lines.contains(&format!("crash test line {}", MAX_LOG_LINES + 4)));
+        assert!(!lines.contains(&"crash test line 0".to_string()));
+    }
+
+    #[test]
+    fn test_write_report() {
+        let dir = tempfile::tempdir().unwrap();
+        let report = CrashReport {
+            version: "1.0.0".to_string(),
+            target: "x86_64-pc-windows-msvc".to_string(),
+            created_at: Utc::now(),
+            thread: Some("main".to_string()),
+            message: "index out of bounds".to_string(),
+            location: Some("src/analyzers/nsis/mod.rs:42:5".to_string()),
+            backtrace: "0: installer_analyzer::analyzers".to_string(),
+            inputs: vec![InputDigest {
+                name: "setup.exe".to_string(),
+                size: Some(4),
+                sha256: Some("ab".repeat(32)),
+            }],
+            recent_logs: vec!["DEBUG parsing header".to_string()],
+        };
+
+        for _ in 0..MAX_REPORTS + 2 {
+            let earlier = CrashReport {
+                created_at: Utc::now(),
+                ..report.clone()
+            };
+            earlier.write(dir.path()).unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(2));
+        }
+        let path = report.write(dir.path()).unwrap();
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), MAX_REPORTS);
+
+        let text = std::fs::read_to_string(path).unwrap();
+        assert!(text.contains("index out of bounds"));
+        assert!(text.contains("Location: src/analyzers/nsis/mod.rs:42:5"));
+        assert!(text.contains(&format!("setup.exe size=4 sha256={}", "ab".repeat(32))));
+        assert!(text.contains("DEBUG parsing header"));
+    }
+}
@@ -0,0 +1,74 @@
+//! Per-user directory for data that must outlive a run
+//!
+//! The workspace lives in the shared temp directory, where other users can
+//! create files and temp cleaners remove anything old. Installed data packs
+//! and update backups are trusted by later runs, so they are kept in the
+//! user's own data directory instead:
+//!
+//! | Platform | Directory |
+//! |----------|-----------|
+//! | Windows | `%LOCALAPPDATA%\installer-analyzer` |
+//! | macOS | `~/Library/Application Support/installer-analyzer` |
+//! | Others | `$XDG_DATA_HOME/installer-analyzer`, `~/.local/share/installer-analyzer` by default |
+
+use std::path::{Path, PathBuf};
+
+/// Directory name below the platform data directory
+const APP_DIR_NAME: &str = "installer-analyzer";
+
+/// Persistent data directory of the current user
+///
+/// Falls back to `.installer-analyzer` in the working directory when the
+/// environment names no home or data directory.
+pub fn user_data_dir() -> PathBuf {
+    let var = |name: &str| {
+        std::env::var_os(name)
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+    };
+    let base = if cfg!(windows) {
+        var("LOCALAPPDATA")
+    } else if cfg!(target_os = "macos") {
+        var("HOME").map(|home| home.join("Library").join("Application Support"))
+    } else {
+        var("XDG_DATA_HOME").or_else(|| var("HOME").map(|home| home.join(".local").join("share")))
+    };
+    match base {
+        Some(base) => base.join(APP_DIR_NAME),
+        None => PathBuf::from(format!(".{}", APP_DIR_NAME)),
+    }
+}
+
+/// Create a directory only the current user can access
+///
+/// On Windows the directory inherits the access rules of the user profile
+/// it is created in.
+pub fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_private_dir() {
+        assert!(user_data_dir().ends_with(APP_DIR_NAME));
+        let dir = tempfile::tempdir().unwrap();
+        let private = dir.path().join("a").join("b");
+        create_private_dir(&private).unwrap();
+        assert!(private.is_dir());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = private.metadata().unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o700);
+        }
+    }
+}
@@ -1,29 +1,51 @@
 //! Utility functions and helpers
 
+pub mod crash;
+pub mod data_dir;
+pub mod naming;
+pub mod paths;
+pub mod platform;
+pub mod progress;
+pub mod workspace;
+
+pub use data_dir::{create_private_dir, user_data_dir};
+pub use naming::{display_name, file_name_component};
+pub use paths::{extended_length, without_prefix};
+pub use platform::{platform_capabilities, PlatformCapability};
+pub use workspace::{ScratchDir, Workspace, WorkspaceArea};
+
 use crate::core::Result;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Initialize logging system
+///
+/// Recent lines are kept for crash reports in either mode.
 pub fn init_logging(verbose: bool) -> Result<()> {
-    use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+    use tracing_subscriber::filter::{LevelFilter, Targets};
+    use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, Layer};
 
-    if verbose {
+    // In non-verbose mode, nothing is logged to the console
+    // Only progress bars and essential CLI output will be shown
+    let console = verbose.then(|| {
         let filter = "installer_analyzer=debug,info";
-        tracing_subscriber::registry()
-            .with(
-                tracing_subscriber::EnvFilter::try_from_default_env()
-                    .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(filter)),
-            )
-            .with(tracing_subscriber::fmt::layer())
-            .init();
-    } else {
-        // In non-verbose mode, disable all logging output
-        // Only progress bars and essential CLI output will be shown
-        tracing_subscriber::registry()
-            .with(tracing_subscriber::EnvFilter::new("off"))
-            .with(tracing_subscriber::fmt::layer().with_writer(std::io::sink))
-            .init();
-    }
+        tracing_subscriber::fmt::layer().with_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(filter)),
+        )
+    });
+    let recent = tracing_subscriber::fmt::layer()
+        .with_ansi(false)
+        .with_writer(crash::RecentLogs)
+        .with_filter(
+            Targets::new()
+                .with_target("installer_analyzer", LevelFilter::DEBUG)
+                .with_default(LevelFilter::WARN),
+        );
+
+    tracing_subscriber::registry()
+        .with(console)
+        .with(recent)
+        .init();
 
     Ok(())
 }
@@ -81,6 +103,62 @@ pub async fn validate_path(path: &Path, must_be_file: bool) -> Result<()> {
     Ok(())
 }
 
+/// Installer read from stdin and spooled to a temporary file
+///
+/// The temporary directory is removed when the value is dropped.
+#[derive(Debug)]
+pub struct StdinInput {
+    /// Guard of the directory holding the spooled file
+    _dir: ScratchDir,
+    path: PathBuf,
+}
+
+impl StdinInput {
+    /// Read stdin to completion into a temporary file named `name`
+    ///
+    /// The name keeps its extension so that format detection by file
+    /// extension works the same way as for regular inputs.
+    pub async fn spool(name: &str) -> Result<Self> {
+        use std::io::IsTerminal;
+
+        if std::io::stdin().is_terminal() {
+            return Err(crate::core::AnalyzerError::config_error(
+                "No installer piped to stdin (use `--input -` only with a pipe or redirect)",
+            ));
+        }
+
+        let file_name = Path::new(name)
+            .file_name()
+            .map(|n| n.to_os_string())
+            .unwrap_or_else(|| "stdin".into());
+        let dir = Workspace::new().scratch_dir(WorkspaceArea::Stdin)?;
+
+        let input = Self {
+            path: dir.path().join(file_name),
+            _dir: dir,
+        };
+        let mut file = tokio::fs::File::create(&input.path).await?;
+        let size = tokio::io::copy(&mut tokio::io::stdin(), &mut file).await?;
+        if size == 0 {
+            return Err(crate::core::AnalyzerError::invalid_format(
+                "Received empty input on stdin",
+            ));
+        }
+
+        tracing::debug!(
+            "Spooled {} bytes from stdin to {}",
+            size,
+            input.path.display()
+        );
+        Ok(input)
+    }
+
+    /// Path of the spooled temporary file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
 /// Format file size in human-readable format
 pub fn format_file_size(size: u64) -> String {
     const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
@@ -99,6 +177,18 @@ pub fn format_file_size(size: u64) -> String {
     }
 }
 
+/// File name of a path taken from an installer
+///
+/// Installers use Windows separators, which `Path::file_name` only splits on
+/// Windows hosts.
+pub fn entry_file_name(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    path.rsplit(['\\', '/'])
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
 /// Format duration in human-readable format
 pub fn format_duration(duration: std::time::Duration) -> String {
     let total_seconds = duration.as_secs();
@@ -0,0 +1,142 @@
+//! Names derived from installer data
+//!
+//! Reports are named after the installers they describe and show file and
+//! product names read from them. Such names may contain characters Windows
+//! does not allow in file names, device names like `CON`, or control and
+//! bidirectional formatting characters that garble or spoof what is shown.
+//! CJK text, emoji and spaces are kept as they are. Names are normalized to
+//! NFC, so the same name typed on macOS (decomposed) and on Windows
+//! (composed) ends up as the same file.
+
+use unicode_normalization::UnicodeNormalization;
+
+/// Longest file name component produced, in bytes
+///
+/// File systems limit components to 255 bytes or UTF-16 units; the margin
+/// leaves room for suffixes like `_report.html`.
+const MAX_COMPONENT_BYTES: usize = 200;
+
+/// Characters Windows rejects in file names
+const RESERVED_CHARS: &[char] = &['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+
+/// Device names Windows reserves regardless of extension
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// File name component usable on every platform, derived from `name`
+///
+/// Reserved and control characters become `_`, trailing dots and spaces are
+/// dropped and device names get a `_` appended. Empty results are named
+/// `unknown`.
+pub fn file_name_component(name: &str) -> String {
+    let mut component: String = name
+        .nfc()
+        .filter(|c| !is_format_control(*c))
+        .map(|c| {
+            if c.is_control() || RESERVED_CHARS.contains(&c) {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+
+    truncate_to_boundary(&mut component, MAX_COMPONENT_BYTES);
+    let trimmed = component.trim_start().trim_end_matches(['.', ' ']);
+    if trimmed.is_empty() {
+        return "unknown".to_string();
+    }
+
+    let device = trimmed.split('.').next().unwrap_or_default().trim_end();
+    if RESERVED_NAMES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(device))
+    {
+        return match trimmed.split_once('.') {
+            Some((base, extension)) => format!("{}_.{}", base, extension),
+            None => format!("{}_", trimmed),
+        };
+    }
+    trimmed.to_string()
+}
+
+/// Name as shown in reports, without characters that alter its appearance
+///
+/// Control characters become spaces, bidirectional formatting characters
+/// are removed and surrounding whitespace is trimmed.
+pub fn display_name(name: &str) -> String {
+    let name: String = name
+        .nfc()
+        .filter(|c| !is_format_control(*c))
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect();
+    name.trim().to_string()
+}
+
+/// Whether `c` reorders or hides text without being visible itself
+///
+/// The zero-width joiner is kept, emoji sequences depend on it.
+fn is_format_control(c: char) -> bool {
+    matches!(
+        c,
+        '\u{200B}' | '\u{200E}' | '\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}' | '\u{FEFF}'
+    )
+}
+
+/// Shorten `text` to at most `max` bytes without splitting a character
+fn truncate_to_boundary(text: &mut String, max: usize) {
+    if text.len() <= max {
+        return;
+    }
+    let mut end = max;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    text.truncate(end);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_name_component() {
+        assert_eq!(file_name_component("微信安装程序"), "微信安装程序");
+        assert_eq!(file_name_component("Setup 🚀 v2"), "Setup 🚀 v2");
+        assert_eq!(file_name_component("👨‍💻 tools"), "👨‍💻 tools");
+        assert_eq!(file_name_component("app: <beta>|x64?"), "app_ _beta__x64_");
+        assert_eq!(file_name_component("a/b\\c\n"), "a_b_c_");
+        assert_eq!(file_name_component("release. . "), "release");
+        assert_eq!(file_name_component("  ..  "), "unknown");
+        assert_eq!(file_name_component(""), "unknown");
+        assert_eq!(file_name_component("setup\u{202E}exe.msi"), "setupexe.msi");
+
+        assert_eq!(file_name_component("CON"), "CON_");
+        assert_eq!(file_name_component("nul.tar.gz"), "nul_.tar.gz");
+        assert_eq!(file_name_component("com1"), "com1_");
+        assert_eq!(file_name_component("CONSOLE"), "CONSOLE");
+        assert_eq!(file_name_component("COM10"), "COM10");
+
+        // Decomposed `é` from macOS becomes the composed form
+        assert_eq!(file_name_component("Cafe\u{301}"), "Caf\u{e9}");
+    }
+
+    #[test]
+    fn test_file_name_component_truncates_on_char_boundary() {
+        let name = "安装".repeat(100);
+        let component = file_name_component(&name);
+        assert!(component.len() <= MAX_COMPONENT_BYTES);
+        assert!(name.starts_with(&component));
+        assert_eq!(component.chars().count(), MAX_COMPONENT_BYTES / 3);
+    }
+
+    #[test]
+    fn test_display_name() {
+        assert_eq!(display_name("  微信 🚀 Setup\t"), "微信 🚀 Setup");
+        assert_eq!(display_name("line\nbreak"), "line break");
+        assert_eq!(display_name("invoice\u{202E}fdp.exe"), "invoicefdp.exe");
+        assert_eq!(display_name("a<b>&\"c\""), "a<b>&\"c\"");
+    }
+}
@@ -0,0 +1,115 @@
+//! Extended-length and UNC paths
+//!
+//! Win32 file APIs reject paths longer than `MAX_PATH` (260 characters)
+//! unless they use the `\\?\` extended-length form, and installers analyzed
+//! from deep network shares or extracted into nested directories easily go
+//! beyond that. File system calls of the IO layer therefore go through
+//! [`extended_length`], while paths shown to users go through
+//! [`without_prefix`] so the prefix does not leak into messages and URLs.
+//! Other platforms have no such limit and keep paths unchanged.
+
+use std::path::{Path, PathBuf};
+
+/// Prefix of extended-length paths
+const EXTENDED_PREFIX: &str = r"\\?\";
+/// Prefix of extended-length UNC paths, `\\?\UNC\server\share`
+const EXTENDED_UNC_PREFIX: &str = r"\\?\UNC\";
+/// Prefix of device paths, which are passed to the system as they are
+#[cfg_attr(not(windows), allow(dead_code))]
+const DEVICE_PREFIX: &str = r"\\.\";
+
+/// Form of a path that file system calls accept regardless of its length
+///
+/// On Windows, the path is made absolute and prefixed with `\\?\`, or with
+/// `\\?\UNC\` for network shares. Elsewhere it is returned unchanged.
+pub fn extended_length(path: &Path) -> PathBuf {
+    #[cfg(windows)]
+    {
+        // Extended-length paths are not normalized by the system, so `.`,
+        // `..` and forward slashes are resolved first
+        let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+        if let Some(extended) = absolute.to_str().and_then(to_extended) {
+            return PathBuf::from(extended);
+        }
+        absolute
+    }
+    #[cfg(not(windows))]
+    {
+        path.to_path_buf()
+    }
+}
+
+/// Path as users know it, without an extended-length prefix
+pub fn without_prefix(path: &Path) -> PathBuf {
+    match path.to_str().and_then(strip_extended) {
+        Some(stripped) => PathBuf::from(stripped),
+        None => path.to_path_buf(),
+    }
+}
+
+/// Extended-length form of an absolute Windows path, `None` if it has one
+/// already or is not absolute
+#[cfg_attr(not(windows), allow(dead_code))]
+fn to_extended(path: &str) -> Option<String> {
+    if path.starts_with(EXTENDED_PREFIX) || path.starts_with(DEVICE_PREFIX) {
+        return None;
+    }
+    if let Some(unc) = path.strip_prefix(r"\\") {
+        return Some(format!("{}{}", EXTENDED_UNC_PREFIX, unc));
+    }
+    let bytes = path.as_bytes();
+    let has_drive = bytes.len() >= 3 && bytes[0].is_ascii_alphabetic() && &bytes[1..3] == b":\\";
+    has_drive.then(|| format!("{}{}", EXTENDED_PREFIX, path))
+}
+
+/// Regular form of an extended-length path, `None` if it is not one
+fn strip_extended(path: &str) -> Option<String> {
+    if let Some(unc) = path.strip_prefix(EXTENDED_UNC_PREFIX) {
+        return Some(format!(r"\\{}", unc));
+    }
+    path.strip_prefix(EXTENDED_PREFIX).map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extended_length_forms() {
+        assert_eq!(
+            to_extended(r"C:\Installers\setup.exe").as_deref(),
+            Some(r"\\?\C:\Installers\setup.exe")
+        );
+        assert_eq!(
+            to_extended(r"\\fileserver\packages\app.msi").as_deref(),
+            Some(r"\\?\UNC\fileserver\packages\app.msi")
+        );
+        assert_eq!(to_extended(r"\\?\C:\setup.exe"), None);
+        assert_eq!(to_extended(r"\\.\pipe\analyzer"), None);
+        assert_eq!(to_extended(r"relative\setup.exe"), None);
+
+        for path in [r"C:\Installers\setup.exe", r"\\fileserver\packages\app.msi"] {
+            let extended = to_extended(path).unwrap();
+            assert_eq!(strip_extended(&extended).as_deref(), Some(path));
+        }
+        assert_eq!(
+            without_prefix(Path::new("reports/app.html")),
+            Path::new("reports/app.html")
+        );
+    }
+
+    #[test]
+    fn test_write_beyond_max_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut target = dir.path().to_path_buf();
+        while target.as_os_str().len() <= 300 {
+            target.push("a-deeply-nested-extraction-directory");
+        }
+        let file = target.join("payload.bin");
+
+        std::fs::create_dir_all(extended_length(&target)).unwrap();
+        std::fs::write(extended_length(&file), b"MZ").unwrap();
+        assert_eq!(std::fs::read(extended_length(&file)).unwrap(), b"MZ");
+        assert_eq!(without_prefix(&extended_length(&file)), file);
+    }
+}
@@ -0,0 +1,78 @@
+//! Features available on the current operating system
+//!
+//! Static analysis is implemented in Rust and runs everywhere; dynamic
+//! analysis, elevation checks and self-updates depend on Windows. The list
+//! backs `installer-analyzer info --platform-capabilities`.
+
+use crate::sandbox;
+use serde::Serialize;
+
+/// Availability of one feature on the current host
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PlatformCapability {
+    pub feature: &'static str,
+    pub available: bool,
+    /// How the feature is provided, or why it is unavailable
+    pub note: &'static str,
+}
+
+impl PlatformCapability {
+    fn new(feature: &'static str, available: bool, note: &'static str) -> Self {
+        Self {
+            feature,
+            available,
+            note,
+        }
+    }
+}
+
+/// Features of the analyzer and whether this build supports them here
+pub fn platform_capabilities() -> Vec<PlatformCapability> {
+    let windows = cfg!(windows);
+    vec![
+        PlatformCapability::new("Static analysis", true, "All installer formats"),
+        PlatformCapability::new(
+            "MSI database tables",
+            true,
+            if windows {
+                "Windows Installer API"
+            } else {
+                "Built-in MSI reader"
+            },
+        ),
+        PlatformCapability::new(
+            "Reports, results database and job queue API",
+            true,
+            "Platform independent",
+        ),
+        PlatformCapability::new(
+            "Sandbox analysis",
+            sandbox::is_supported(),
+            if !windows {
+                "Requires Windows"
+            } else if sandbox::is_supported() {
+                "Windows Sandbox"
+            } else {
+                "Built without the `sandbox` feature"
+            },
+        ),
+        PlatformCapability::new(
+            "Administrator detection",
+            windows,
+            if windows {
+                "Token elevation"
+            } else {
+                "Requires Windows"
+            },
+        ),
+        PlatformCapability::new(
+            "Self-update",
+            windows,
+            if windows {
+                "Replaces the running executable"
+            } else {
+                "Requires Windows, download releases manually"
+            },
+        ),
+    ]
+}
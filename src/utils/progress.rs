@@ -0,0 +1,218 @@
+//! Progress of a running analysis
+//!
+//! Analysis code reports the stage it enters and counted work such as files
+//! inspected or bytes hashed. The reports go to the sink installed with
+//! [`scope`] for the current task, which the CLI draws as spinners and bars;
+//! without a sink they cost next to nothing.
+
+use std::future::Future;
+use std::io::Read;
+use std::sync::Arc;
+
+tokio::task_local! {
+    static SINK: Arc<dyn ProgressSink>;
+}
+
+/// Stage of a static analysis, in the order they run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Detecting,
+    /// Metadata, file list and registry operations
+    Extracting,
+    /// Heuristics reading the payload files
+    InspectingFiles,
+    Hashing,
+    Report,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 5] = [
+        Self::Detecting,
+        Self::Extracting,
+        Self::InspectingFiles,
+        Self::Hashing,
+        Self::Report,
+    ];
+
+    /// Lowercase name, as used in machine-readable events
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Detecting => "detecting",
+            Self::Extracting => "extracting",
+            Self::InspectingFiles => "inspecting_files",
+            Self::Hashing => "hashing",
+            Self::Report => "report",
+        }
+    }
+
+    /// 1-based position among [`Stage::ALL`]
+    pub fn number(&self) -> usize {
+        Self::ALL
+            .iter()
+            .position(|stage| stage == self)
+            .unwrap_or_default()
+            + 1
+    }
+}
+
+/// Unit of counted work
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    Files,
+    Bytes,
+}
+
+impl Unit {
+    /// Lowercase name, as used in machine-readable events
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Files => "files",
+            Self::Bytes => "bytes",
+        }
+    }
+}
+
+/// Receiver of progress reports
+pub trait ProgressSink: Send + Sync {
+    /// A new stage begins
+    fn stage(&self, stage: Stage);
+
+    /// Counted work of `total` units begins within the current stage
+    fn begin(&self, unit: Unit, total: u64);
+
+    /// `delta` units of the counted work are done
+    fn advance(&self, delta: u64);
+}
+
+/// Sink passing reports on to several sinks
+pub struct Fanout(pub Vec<Arc<dyn ProgressSink>>);
+
+impl ProgressSink for Fanout {
+    fn stage(&self, stage: Stage) {
+        self.0.iter().for_each(|sink| sink.stage(stage));
+    }
+
+    fn begin(&self, unit: Unit, total: u64) {
+        self.0.iter().for_each(|sink| sink.begin(unit, total));
+    }
+
+    fn advance(&self, delta: u64) {
+        self.0.iter().for_each(|sink| sink.advance(delta));
+    }
+}
+
+/// Run `future` with `sink` receiving its progress reports
+pub async fn scope<F: Future>(sink: Arc<dyn ProgressSink>, future: F) -> F::Output {
+    SINK.scope(sink, future).await
+}
+
+/// Sink of the current task, to be moved into blocking tasks
+pub fn current() -> Option<Arc<dyn ProgressSink>> {
+    SINK.try_with(Arc::clone).ok()
+}
+
+/// Report the start of `stage`
+pub fn stage(stage: Stage) {
+    if let Some(sink) = current() {
+        sink.stage(stage);
+    }
+}
+
+/// Report the start of counted work
+pub fn begin(unit: Unit, total: u64) {
+    if let Some(sink) = current() {
+        sink.begin(unit, total);
+    }
+}
+
+/// Report finished units of counted work
+pub fn advance(delta: u64) {
+    if let Some(sink) = current() {
+        sink.advance(delta);
+    }
+}
+
+/// Reader reporting the bytes read from it as counted work
+pub struct ProgressReader<R> {
+    inner: R,
+    sink: Option<Arc<dyn ProgressSink>>,
+}
+
+impl<R: Read> ProgressReader<R> {
+    /// Reader of `total` bytes reporting to `sink`
+    pub fn new(inner: R, total: u64, sink: Option<Arc<dyn ProgressSink>>) -> Self {
+        if let Some(sink) = &sink {
+            sink.begin(Unit::Bytes, total);
+        }
+        Self { inner, sink }
+    }
+}
+
+impl<R: Read> Read for ProgressReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let bytes_read = self.inner.read(buf)?;
+        if let (Some(sink), 1..) = (&self.sink, bytes_read) {
+            sink.advance(bytes_read as u64);
+        }
+        Ok(bytes_read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<String>>);
+
+    impl ProgressSink for Recorder {
+        fn stage(&self, stage: Stage) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("stage {}", stage.number()));
+        }
+
+        fn begin(&self, unit: Unit, total: u64) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("begin {:?} {}", unit, total));
+        }
+
+        fn advance(&self, delta: u64) {
+            self.0.lock().unwrap().push(format!("advance {}", delta));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reports_reach_the_scoped_sink() {
+        // Outside a scope reports are dropped
+        stage(Stage::Detecting);
+        assert!(current().is_none());
+
+        let recorder = Arc::new(Recorder::default());
+        scope(recorder.clone(), async {
+            stage(Stage::Hashing);
+            let mut reader = ProgressReader::new(&b"abcdef"[..], 6, current());
+            let mut content = Vec::new();
+            reader.read_to_end(&mut content).unwrap();
+            begin(Unit::Files, 2);
+            advance(1);
+        })
+        .await;
+
+        let reports = recorder.0.lock().unwrap();
+        assert_eq!(
+            *reports,
+            vec![
+                "stage 4",
+                "begin Bytes 6",
+                "advance 6",
+                "begin Files 2",
+                "advance 1"
+            ]
+        );
+    }
+}
@@ -0,0 +1,471 @@
+//! Central management of temporary working directories
+//!
+//! Every directory the tool writes outside of the user's chosen outputs
+//! (stdin spools, bundle scratch space, update downloads and cached API
+//! responses, extraction, sandbox and worker job directories) lives below a
+//! single workspace root, `<temp>/installer-analyzer`, grouped by
+//! [`WorkspaceArea`].
+//!
+//! Per-run directories are created through [`Workspace::scratch_dir`] and
+//! named `<pid>-<uuid>`, so a directory left behind by a crashed run can be
+//! recognised on the next start and reclaimed by [`Workspace::housekeep`].
+//! The `clean` command exposes the same logic to users.
+
+use crate::core::{AnalyzerError, Result};
+use crate::utils::extended_length;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Name of the workspace root below the system temp directory
+const ROOT_NAME: &str = "installer-analyzer";
+
+/// Directory names used before the workspace existed, reclaimed by `clean`
+const LEGACY_PREFIXES: &[&str] = &[
+    "installer-analyzer-updates",
+    "installer-analyzer-update",
+    "installer-analyzer-stdin-",
+    "installer-analyzer-bundle-",
+];
+
+/// Entries untouched for longer than this are removed on startup
+pub const DEFAULT_RETENTION: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Total workspace size above which the oldest entries are removed on startup
+pub const DEFAULT_SIZE_CAP: u64 = 2 * 1024 * 1024 * 1024;
+
+/// Purpose-specific area of the workspace
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkspaceArea {
+    /// Downloaded update packages
+    Updates,
+    /// Cached responses of the release API
+    UpdateCache,
+    /// Staging of the replacement executable during a self-update
+    UpdateStaging,
+    /// Installers spooled from stdin
+    Stdin,
+    /// Scratch space for report bundles
+    Bundle,
+    /// Content extracted from installers
+    Extraction,
+    /// Sandbox work directories
+    Sandbox,
+    /// Installers downloaded by analysis workers
+    Jobs,
+}
+
+impl WorkspaceArea {
+    /// All areas, in the order they are listed by `clean`
+    pub const ALL: [Self; 8] = [
+        Self::Updates,
+        Self::UpdateCache,
+        Self::UpdateStaging,
+        Self::Stdin,
+        Self::Bundle,
+        Self::Extraction,
+        Self::Sandbox,
+        Self::Jobs,
+    ];
+
+    /// Whether the area holds per-run directories rather than shared files
+    pub fn holds_scratch_dirs(&self) -> bool {
+        !matches!(
+            self,
+            Self::Updates | Self::UpdateCache | Self::UpdateStaging
+        )
+    }
+
+    /// Directory name of the area below the workspace root
+    pub fn dir_name(&self) -> &'static str {
+        match self {
+            Self::Updates => "updates",
+            Self::UpdateCache => "update-cache",
+            Self::UpdateStaging => "update",
+            Self::Stdin => "stdin",
+            Self::Bundle => "bundle",
+            Self::Extraction => "extract",
+            Self::Sandbox => "sandbox",
+            Self::Jobs => "jobs",
+        }
+    }
+}
+
+/// Per-run directory removed when dropped
+#[derive(Debug)]
+pub struct ScratchDir {
+    path: PathBuf,
+}
+
+impl ScratchDir {
+    /// Path of the directory
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_dir_all(&self.path) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                tracing::warn!("Failed to remove {}: {}", self.path.display(), e);
+            }
+        }
+    }
+}
+
+/// Directory or file found in the workspace
+#[derive(Debug, Clone)]
+pub struct WorkspaceEntry {
+    pub path: PathBuf,
+    /// Area the entry belongs to, `None` for directories of the legacy layout
+    pub area: Option<WorkspaceArea>,
+    /// Total size in bytes, including everything below a directory
+    pub size: u64,
+    pub modified: SystemTime,
+    /// Process that created a scratch directory
+    pub owner_pid: Option<u32>,
+}
+
+impl WorkspaceEntry {
+    /// Whether the entry belongs to a running process and must be kept
+    pub fn is_active(&self) -> bool {
+        self.owner_pid
+            .is_some_and(|pid| pid == std::process::id() || process_alive(pid) == Some(true))
+    }
+
+    /// Whether the entry was left behind by a process that is no longer running
+    ///
+    /// Directories of the legacy layout are always leftovers of older versions.
+    pub fn is_orphaned(&self) -> bool {
+        match self.owner_pid {
+            Some(pid) => pid != std::process::id() && process_alive(pid) == Some(false),
+            None => self.area.is_none(),
+        }
+    }
+
+    fn age(&self) -> Duration {
+        SystemTime::now()
+            .duration_since(self.modified)
+            .unwrap_or_default()
+    }
+}
+
+/// Which workspace entries to remove
+#[derive(Debug, Clone, Default)]
+pub struct CleanPolicy {
+    /// Remove every entry not owned by a running process
+    pub remove_all: bool,
+    /// Remove entries untouched for longer than this
+    pub max_age: Option<Duration>,
+    /// Remove the oldest entries until the workspace fits this size
+    pub max_total_size: Option<u64>,
+    /// Only report what would be removed
+    pub dry_run: bool,
+}
+
+/// Outcome of a clean-up
+#[derive(Debug, Clone, Default)]
+pub struct CleanReport {
+    /// Entries removed, or that would be removed in a dry run
+    pub removed: Vec<WorkspaceEntry>,
+    /// Bytes freed, or that would be freed in a dry run
+    pub freed_bytes: u64,
+    /// Entries that could not be removed
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+/// Workspace holding all temporary directories of the tool
+#[derive(Debug, Clone)]
+pub struct Workspace {
+    root: PathBuf,
+    /// Also consider directories of the legacy layout next to the root
+    include_legacy: bool,
+}
+
+impl Workspace {
+    /// Workspace below the system temp directory
+    pub fn new() -> Self {
+        Self {
+            root: std::env::temp_dir().join(ROOT_NAME),
+            include_legacy: true,
+        }
+    }
+
+    /// Workspace at a custom root
+    pub fn with_root(root: PathBuf) -> Self {
+        Self {
+            root,
+            include_legacy: false,
+        }
+    }
+
+    /// Root directory of the workspace
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Directory of an area, not created
+    pub fn area_dir(&self, area: WorkspaceArea) -> PathBuf {
+        self.root.join(area.dir_name())
+    }
+
+    /// Create a per-run directory in an area, removed when the guard is dropped
+    pub fn scratch_dir(&self, area: WorkspaceArea) -> Result<ScratchDir> {
+        let path =
+            self.area_dir(area)
+                .join(format!("{}-{}", std::process::id(), uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(extended_length(&path)).map_err(|e| {
+            AnalyzerError::generic(format!(
+                "Failed to create work directory {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        Ok(ScratchDir { path })
+    }
+
+    /// List the entries of all areas, oldest first
+    pub fn entries(&self) -> Result<Vec<WorkspaceEntry>> {
+        let mut entries = Vec::new();
+
+        for area in WorkspaceArea::ALL {
+            let dir = self.area_dir(area);
+            let Ok(children) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            for child in children {
+                let path = child?.path();
+                let owner_pid = path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .filter(|_| area.holds_scratch_dirs())
+                    .and_then(|name| name.split_once('-'))
+                    .and_then(|(pid, _)| pid.parse().ok());
+                entries.push(Self::describe(path, Some(area), owner_pid)?);
+            }
+        }
+
+        if self.include_legacy {
+            if let Some(parent) = self.root.parent() {
+                for child in std::fs::read_dir(parent)?.flatten() {
+                    let name = child.file_name().to_string_lossy().to_string();
+                    if LEGACY_PREFIXES
+                        .iter()
+                        .any(|prefix| name.starts_with(prefix))
+                    {
+                        entries.push(Self::describe(child.path(), None, None)?);
+                    }
+                }
+            }
+        }
+
+        entries.sort_by_key(|entry| entry.modified);
+        Ok(entries)
+    }
+
+    /// Total size of all entries
+    pub fn total_size(&self) -> Result<u64> {
+        Ok(self.entries()?.iter().map(|entry| entry.size).sum())
+    }
+
+    /// Remove entries according to a policy
+    ///
+    /// Entries owned by a running process are never removed. Entries of
+    /// crashed runs are always removed, together with anything matching
+    /// the age limit; the size cap is then enforced oldest first.
+    pub fn clean(&self, policy: &CleanPolicy) -> Result<CleanReport> {
+        let entries = self.entries()?;
+        let mut remaining: u64 = entries.iter().map(|entry| entry.size).sum();
+        let mut report = CleanReport::default();
+
+        for entry in entries {
+            if entry.is_active() {
+                continue;
+            }
+
+            let expired = policy.max_age.is_some_and(|max_age| entry.age() > max_age);
+            let over_cap = policy.max_total_size.is_some_and(|cap| remaining > cap);
+            if !(policy.remove_all || entry.is_orphaned() || expired || over_cap) {
+                continue;
+            }
+
+            if !policy.dry_run {
+                if let Err(e) = remove_path(&entry.path) {
+                    report.failed.push((entry.path.clone(), e.to_string()));
+                    continue;
+                }
+            }
+            remaining = remaining.saturating_sub(entry.size);
+            report.freed_bytes += entry.size;
+            report.removed.push(entry);
+        }
+
+        Ok(report)
+    }
+
+    /// Startup clean-up with the default retention and size cap
+    ///
+    /// Errors are logged rather than returned, housekeeping never blocks a run.
+    pub fn housekeep(&self) {
+        let policy = CleanPolicy {
+            max_age: Some(DEFAULT_RETENTION),
+            max_total_size: Some(DEFAULT_SIZE_CAP),
+            ..CleanPolicy::default()
+        };
+        match self.clean(&policy) {
+            Ok(report) if !report.removed.is_empty() => tracing::debug!(
+                "Reclaimed {} stale workspace entries ({} bytes)",
+                report.removed.len(),
+                report.freed_bytes
+            ),
+            Ok(_) => {}
+            Err(e) => tracing::debug!("Workspace housekeeping failed: {}", e),
+        }
+    }
+
+    fn describe(
+        path: PathBuf,
+        area: Option<WorkspaceArea>,
+        owner_pid: Option<u32>,
+    ) -> Result<WorkspaceEntry> {
+        let metadata = std::fs::symlink_metadata(&path)?;
+        Ok(WorkspaceEntry {
+            size: path_size(&path),
+            modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            path,
+            area,
+            owner_pid,
+        })
+    }
+}
+
+impl Default for Workspace {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Size of a file or the recursive size of a directory, without following links
+fn path_size(path: &Path) -> u64 {
+    let Ok(metadata) = std::fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    std::fs::read_dir(path)
+        .map(|children| {
+            children
+                .flatten()
+                .map(|child| path_size(&child.path()))
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+fn remove_path(path: &Path) -> std::io::Result<()> {
+    if std::fs::symlink_metadata(path)?.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    }
+}
+
+/// Whether a process is running, `None` when this cannot be determined
+fn process_alive(pid: u32) -> Option<bool> {
+    #[cfg(target_os = "linux")]
+    {
+        Some(Path::new("/proc").join(pid.to_string()).exists())
+    }
+
+    #[cfg(windows)]
+    {
+        use windows::Win32::Foundation::{CloseHandle, FALSE};
+        use windows::Win32::System::Threading::{
+            GetExitCodeProcess, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION,
+        };
+        const STILL_ACTIVE: u32 = 259;
+
+        unsafe {
+            let Ok(handle) = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, FALSE, pid) else {
+                return Some(false);
+            };
+            let mut exit_code = 0u32;
+            let queried = GetExitCodeProcess(handle, &mut exit_code).as_bool();
+            let _ = CloseHandle(handle).ok();
+            queried.then_some(exit_code == STILL_ACTIVE)
+        }
+    }
+
+    #[cfg(not(any(target_os = "linux", windows)))]
+    {
+        let _ = pid;
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_scratch_dir_removed_on_drop() {
+        let root = TempDir::new().unwrap();
+        let workspace = Workspace::with_root(root.path().to_path_buf());
+
+        let scratch = workspace.scratch_dir(WorkspaceArea::Bundle).unwrap();
+        let path = scratch.path().to_path_buf();
+        assert!(path.starts_with(root.path().join("bundle")));
+        std::fs::write(path.join("data.bin"), [0u8; 16]).unwrap();
+
+        let entries = workspace.entries().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert!(entries[0].is_active());
+        assert_eq!(entries[0].size, 16);
+
+        // Active directories survive even a full clean
+        let policy = CleanPolicy {
+            remove_all: true,
+            ..CleanPolicy::default()
+        };
+        assert!(workspace.clean(&policy).unwrap().removed.is_empty());
+
+        drop(scratch);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_clean_reclaims_orphans_and_enforces_size_cap() {
+        let root = TempDir::new().unwrap();
+        let workspace = Workspace::with_root(root.path().to_path_buf());
+
+        let updates = workspace.area_dir(WorkspaceArea::Updates);
+        std::fs::create_dir_all(&updates).unwrap();
+        std::fs::write(updates.join("old.zip"), [0u8; 100]).unwrap();
+        std::fs::write(updates.join("new.zip"), [0u8; 50]).unwrap();
+
+        let policy = CleanPolicy {
+            max_total_size: Some(60),
+            dry_run: true,
+            ..CleanPolicy::default()
+        };
+        let report = workspace.clean(&policy).unwrap();
+        assert!(!report.removed.is_empty());
+        assert!(150 - report.freed_bytes <= 60);
+        assert!(updates.join("old.zip").exists());
+        assert!(updates.join("new.zip").exists());
+
+        #[cfg(target_os = "linux")]
+        {
+            // No process ever has pid 0 in /proc, so its directory is an orphan
+            let orphan = workspace.area_dir(WorkspaceArea::Stdin).join("0-crashed");
+            std::fs::create_dir_all(&orphan).unwrap();
+
+            let report = workspace.clean(&CleanPolicy::default()).unwrap();
+            assert_eq!(report.removed.len(), 1);
+            assert!(!orphan.exists());
+            assert!(updates.join("new.zip").exists());
+        }
+    }
+}
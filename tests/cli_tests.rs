@@ -41,6 +41,9 @@ fn test_cli_help_commands() {
     assert!(stdout.contains("analyze"));
     assert!(stdout.contains("sandbox"));
     assert!(stdout.contains("batch"));
+    assert!(stdout.contains("clean"));
+    assert!(stdout.contains("query"));
+    assert!(stdout.contains("correlate"));
 
     // Test analyze help
     let output = Command::new(&binary)
@@ -54,6 +57,18 @@ fn test_cli_help_commands() {
     assert!(stdout.contains("--output"));
     assert!(stdout.contains("--format"));
     assert!(stdout.contains("--open"));
+    assert!(stdout.contains("--export-tree"));
+    assert!(stdout.contains("--include-speculative"));
+    assert!(stdout.contains("--export-deps"));
+    assert!(stdout.contains("--combined"));
+    assert!(stdout.contains("--input-name"));
+    assert!(stdout.contains("--artifacts-dir"));
+    assert!(stdout.contains("--force"));
+    assert!(stdout.contains("--attach-artifacts"));
+    assert!(stdout.contains("--bundle"));
+    assert!(stdout.contains("--password"));
+    assert!(stdout.contains("--db"));
+    assert!(stdout.contains("--policy"));
 
     // Test sandbox help
     let output = Command::new(&binary)
@@ -65,6 +80,9 @@ fn test_cli_help_commands() {
     let stdout = String::from_utf8(output.stdout).unwrap();
     assert!(stdout.contains("--timeout"));
     assert!(stdout.contains("--network"));
+    assert!(!stdout.contains("--disguise"));
+    assert!(!stdout.contains("--fake-time"));
+    assert!(!stdout.contains("--runs"));
 
     // Test batch help
     let output = Command::new(&binary)
@@ -77,6 +95,18 @@ fn test_cli_help_commands() {
     assert!(stdout.contains("--input-dir"));
     assert!(stdout.contains("--output-dir"));
     assert!(stdout.contains("--sandbox"));
+
+    // Test clean help
+    let output = Command::new(&binary)
+        .args(["clean", "--help"])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("--all"));
+    assert!(stdout.contains("--older-than"));
+    assert!(stdout.contains("--dry-run"));
 }
 
 #[test]
@@ -204,6 +234,234 @@ fn test_invalid_input_file() {
     );
 }
 
+#[test]
+fn test_analyze_from_stdin() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let wheel = std::env::current_dir()
+        .unwrap()
+        .join("tests/data/persistent_ssh_agent-0.9.0-py3-none-any.whl");
+    if !wheel.exists() {
+        println!("Skipping stdin test: file not found");
+        return;
+    }
+
+    let binary = get_binary_path();
+    let temp_dir = TempDir::new().unwrap();
+    let output_file = temp_dir.path().join("report.json");
+
+    let mut child = Command::new(&binary)
+        .args([
+            "analyze",
+            "-",
+            "--input-name",
+            "piped-0.9.0-py3-none-any.whl",
+            "--output",
+            output_file.to_str().unwrap(),
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to execute command");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(&std::fs::read(&wheel).unwrap())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert!(output.status.success());
+    let report: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&output_file).unwrap()).unwrap();
+    assert_eq!(
+        report["metadata"]["original_filename"],
+        "piped-0.9.0-py3-none-any.whl"
+    );
+}
+
+#[test]
+fn test_analyze_to_stdout_with_artifacts_dir() {
+    let wheel = std::env::current_dir()
+        .unwrap()
+        .join("tests/data/persistent_ssh_agent-0.9.0-py3-none-any.whl");
+    if !wheel.exists() {
+        println!("Skipping stdout test: file not found");
+        return;
+    }
+
+    let binary = get_binary_path();
+    let temp_dir = TempDir::new().unwrap();
+    let artifacts_dir = temp_dir.path().join("artifacts");
+
+    let output = Command::new(&binary)
+        .args([
+            "analyze",
+            wheel.to_str().unwrap(),
+            "--output",
+            "-",
+            "--artifacts-dir",
+            artifacts_dir.to_str().unwrap(),
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+
+    // Status messages go to stderr, leaving only the report on stdout
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let report: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(report["metadata"]["format"], "PythonWheel");
+
+    assert!(artifacts_dir.join("metadata.json").exists());
+    assert!(artifacts_dir.join("file-tree.json").exists());
+    assert!(artifacts_dir.join("dependencies.json").exists());
+}
+
+#[test]
+fn test_results_database_and_query() {
+    let wheel = std::env::current_dir()
+        .unwrap()
+        .join("tests/data/persistent_ssh_agent-0.9.0-py3-none-any.whl");
+    if !wheel.exists() {
+        println!("Skipping results database test: file not found");
+        return;
+    }
+
+    let binary = get_binary_path();
+    let temp_dir = TempDir::new().unwrap();
+    let db = temp_dir.path().join("results.sqlite");
+
+    // Analyzing twice appends two analyses of the same installer
+    for _ in 0..2 {
+        let output = Command::new(&binary)
+            .args([
+                "analyze",
+                wheel.to_str().unwrap(),
+                "--db",
+                db.to_str().unwrap(),
+            ])
+            .output()
+            .expect("Failed to execute command");
+        assert!(output.status.success());
+    }
+
+    let query = |args: &[&str]| -> serde_json::Value {
+        let output = Command::new(&binary)
+            .args(["query", "--db", db.to_str().unwrap(), "--json"])
+            .args(args)
+            .output()
+            .expect("Failed to execute command");
+        assert!(output.status.success());
+        serde_json::from_slice(&output.stdout).unwrap()
+    };
+
+    let analyses = query(&[]);
+    assert_eq!(analyses.as_array().unwrap().len(), 1);
+    assert_eq!(analyses[0]["format"], "PythonWheel");
+
+    let components = query(&["--component", "Click"]);
+    assert_eq!(components.as_array().unwrap().len(), 1);
+    assert_eq!(components[0]["component"]["evidence"], ">=8.1.0");
+
+    let vendors = query(&["--vendors"]);
+    assert_eq!(vendors[0]["installers"], 1);
+}
+
+#[test]
+fn test_correlate_reports_and_installers() {
+    let data_dir = std::env::current_dir().unwrap().join("tests/data");
+    let msix = data_dir.join("SignedSample.msix");
+    let wheel = data_dir.join("persistent_ssh_agent-0.9.0-py3-none-any.whl");
+    if !msix.exists() || !wheel.exists() {
+        println!("Skipping correlation test: file not found");
+        return;
+    }
+
+    let binary = get_binary_path();
+    let temp_dir = TempDir::new().unwrap();
+    let report = temp_dir.path().join("sample.json");
+
+    let output = Command::new(&binary)
+        .args(["analyze", msix.to_str().unwrap(), "--format", "json"])
+        .args(["--output", report.to_str().unwrap()])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+
+    // An earlier report of the package is matched to the package itself
+    let output = Command::new(&binary)
+        .args(["correlate", "--json"])
+        .args([report.to_str().unwrap(), msix.to_str().unwrap()])
+        .arg(wheel.to_str().unwrap())
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let result: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(result["families"].as_array().unwrap().len(), 2);
+    assert_eq!(result["correlations"][0]["relationship"], "same_release");
+    assert_eq!(result["correlations"][0]["confidence"], "medium");
+
+    let output = Command::new(&binary)
+        .args(["correlate", report.to_str().unwrap()])
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_policy_gate_exit_codes() {
+    let wheel = std::env::current_dir()
+        .unwrap()
+        .join("tests/data/persistent_ssh_agent-0.9.0-py3-none-any.whl");
+    if !wheel.exists() {
+        println!("Skipping policy test: file not found");
+        return;
+    }
+
+    let binary = get_binary_path();
+    let temp_dir = TempDir::new().unwrap();
+    let artifacts_dir = temp_dir.path().join("artifacts");
+
+    let passing = temp_dir.path().join("baseline.yaml");
+    std::fs::write(
+        &passing,
+        "denied_publishers:\n  - CN=Contoso*\nmax_install_size: 10 MB\n",
+    )
+    .unwrap();
+    let output = Command::new(&binary)
+        .args(["analyze", wheel.to_str().unwrap(), "--policy"])
+        .arg(&passing)
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(output.status.code(), Some(0));
+
+    let failing = temp_dir.path().join("strict.toml");
+    std::fs::write(
+        &failing,
+        "name = \"Strict\"\nallowed_publishers = [\"Contoso*\"]\nforbidden_paths = [\"*.py\"]\n",
+    )
+    .unwrap();
+    let output = Command::new(&binary)
+        .args(["analyze", wheel.to_str().unwrap(), "--policy"])
+        .arg(&failing)
+        .arg("--artifacts-dir")
+        .arg(&artifacts_dir)
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(output.status.code(), Some(2));
+
+    let report: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(artifacts_dir.join("policy-report.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(report["policy"], "Strict");
+    assert_eq!(report["passed"], false);
+    assert_eq!(report["violations"][0]["rule"], "allowed_publishers");
+}
+
 #[test]
 fn test_batch_command_empty_directory() {
     let binary = get_binary_path();
@@ -263,3 +521,34 @@ fn test_startup_banner() {
     assert!(stdout.contains("Installer Analyzer"));
     assert!(stdout.contains("Version"));
 }
+
+#[test]
+fn test_sandbox_rejects_unsupported_options() {
+    let binary = get_binary_path();
+    let temp_dir = TempDir::new().unwrap();
+    let installer = create_dummy_installer(temp_dir.path(), "setup", "exe");
+    let run = |option: &[&str]| {
+        let output = Command::new(&binary)
+            .args(["sandbox", "--input", installer.to_str().unwrap()])
+            .args(option)
+            .output()
+            .expect("Failed to execute command");
+        assert!(!output.status.success());
+        String::from_utf8(output.stderr).unwrap()
+    };
+
+    let stderr = run(&["--disguise", "all"]);
+    assert!(
+        stderr.contains("--disguise is not supported yet"),
+        "{}",
+        stderr
+    );
+    let stderr = run(&["--fake-time", "+90d"]);
+    assert!(
+        stderr.contains("--fake-time is not supported yet"),
+        "{}",
+        stderr
+    );
+    let stderr = run(&["--runs", "3"]);
+    assert!(stderr.contains("--runs is not supported yet"), "{}", stderr);
+}
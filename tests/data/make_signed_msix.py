@@ -0,0 +1,187 @@
+#!/usr/bin/env python3
+"""Regenerate tests/data/SignedSample.msix.
+
+The package holds a manifest, a logo, a stub executable, the block map and
+content types, and an AppxSignature.p7x signed with a throwaway self-signed
+certificate ("CN=Contoso Test, O=Contoso"). The key and all intermediate
+files live in a temporary directory and are deleted afterwards.
+
+Requires Python 3 and the openssl command line tool:
+
+    python3 tests/data/make_signed_msix.py [output]
+
+Each run creates a new key, so the signature bytes differ from the committed
+fixture, but the result verifies the same way.
+"""
+
+import base64
+import hashlib
+import subprocess
+import sys
+import tempfile
+import zipfile
+from pathlib import Path
+
+DATE_TIME = (2026, 10, 16, 20, 8, 46)
+BLOCK_SIZE = 64 * 1024
+
+MANIFEST = """<?xml version="1.0" encoding="utf-8"?>
+<Package xmlns="http://schemas.microsoft.com/appx/manifest/foundation/windows10" xmlns:rescap="http://schemas.microsoft.com/appx/manifest/foundation/windows10/restrictedcapabilities">
+  <Identity Name="Contoso.SignedSample" Publisher="CN=Contoso Test, O=Contoso" Version="1.0.0.0" ProcessorArchitecture="x64" />
+  <Properties>
+    <DisplayName>Signed Sample</DisplayName>
+    <PublisherDisplayName>Contoso</PublisherDisplayName>
+    <Logo>Assets\\logo.png</Logo>
+  </Properties>
+  <Dependencies>
+    <TargetDeviceFamily Name="Windows.Desktop" MinVersion="10.0.17763.0" MaxVersionTested="10.0.19041.0" />
+  </Dependencies>
+  <Capabilities>
+    <Capability Name="internetClient" />
+  </Capabilities>
+  <Applications>
+    <Application Id="App" Executable="SignedSample.exe" EntryPoint="Windows.FullTrustApplication" />
+  </Applications>
+</Package>
+"""
+
+CONTENT_TYPES = (
+    '<?xml version="1.0" encoding="UTF-8"?>'
+    '<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">'
+    '<Default Extension="xml" ContentType="application/vnd.ms-appx.manifest+xml"/>'
+    '<Default Extension="png" ContentType="image/png"/>'
+    '<Default Extension="exe" ContentType="application/x-msdownload"/>'
+    '<Override PartName="/AppxBlockMap.xml" ContentType="application/vnd.ms-appx.blockmap+xml"/>'
+    '<Override PartName="/AppxSignature.p7x" ContentType="application/vnd.ms-appx.signature"/>'
+    "</Types>"
+)
+
+# OIDs of the Authenticode structures used by AppX signatures
+SPC_INDIRECT_DATA = "1.3.6.1.4.1.311.2.1.4"
+SPC_SIPINFO = "1.3.6.1.4.1.311.2.1.30"
+SHA256 = "2.16.840.1.101.3.4.2.1"
+
+
+def der(tag, content):
+    length = len(content)
+    if length < 0x80:
+        header = bytes([length])
+    else:
+        encoded = length.to_bytes((length.bit_length() + 7) // 8, "big")
+        header = bytes([0x80 | len(encoded)]) + encoded
+    return bytes([tag]) + header + content
+
+
+def oid(dotted):
+    arcs = [int(arc) for arc in dotted.split(".")]
+    body = bytes([arcs[0] * 40 + arcs[1]])
+    for arc in arcs[2:]:
+        chunk = [arc & 0x7F]
+        arc >>= 7
+        while arc:
+            chunk.append(0x80 | (arc & 0x7F))
+            arc >>= 7
+        body += bytes(reversed(chunk))
+    return der(0x06, body)
+
+
+def sequence(*items):
+    return der(0x30, b"".join(items))
+
+
+def parts():
+    logo = bytes((i * 7 + i // 251) % 256 for i in range(70000))
+    exe = b"MZ" + bytes(1022)
+    return [
+        ("AppxManifest.xml", MANIFEST.encode()),
+        ("Assets/logo.png", logo),
+        ("SignedSample.exe", exe),
+    ]
+
+
+def block_map(files):
+    lines = [
+        '<?xml version="1.0" encoding="UTF-8"?>',
+        '<BlockMap xmlns="http://schemas.microsoft.com/appx/2010/blockmap" '
+        'HashMethod="http://www.w3.org/2001/04/xmlenc#sha256">',
+    ]
+    for name, data in files:
+        lfh_size = 30 + len(name.encode())
+        windows_name = name.replace("/", "\\")
+        lines.append(f'<File Name="{windows_name}" Size="{len(data)}" LfhSize="{lfh_size}">')
+        for start in range(0, len(data), BLOCK_SIZE):
+            digest = hashlib.sha256(data[start : start + BLOCK_SIZE]).digest()
+            lines.append(f'<Block Hash="{base64.b64encode(digest).decode()}"/>')
+        lines.append("</File>")
+    lines.append("</BlockMap>")
+    return "\n".join(lines).encode()
+
+
+def write(archive, name, data):
+    info = zipfile.ZipInfo(name, DATE_TIME)
+    info.compress_type = zipfile.ZIP_DEFLATED
+    info.create_system = 3
+    archive.writestr(info, data)
+
+
+def indirect_data(package_digest):
+    sip_info = sequence(
+        oid(SPC_SIPINFO),
+        sequence(der(0x02, (0x01010000).to_bytes(4, "big")), der(0x04, bytes(16))),
+    )
+    digest_info = sequence(sequence(oid(SHA256), der(0x05, b"")), der(0x04, package_digest))
+    return sequence(sip_info, digest_info)
+
+
+def main():
+    output = Path(sys.argv[1] if len(sys.argv) > 1 else Path(__file__).with_name("SignedSample.msix"))
+    files = parts()
+    block_map_xml = block_map(files)
+    content_types = CONTENT_TYPES.encode()
+
+    with tempfile.TemporaryDirectory() as work:
+        work = Path(work)
+        unsigned = work / "unsigned.zip"
+        with zipfile.ZipFile(unsigned, "w") as archive:
+            for name, data in files:
+                write(archive, name, data)
+            write(archive, "AppxBlockMap.xml", block_map_xml)
+            write(archive, "[Content_Types].xml", content_types)
+            records_end = archive.start_dir
+
+        # AXPC covers the local records of every part but the signature, which
+        # is appended after them; AXCD (central directory) is left zeroed
+        records = unsigned.read_bytes()[:records_end]
+        package_digest = (
+            b"APPX"
+            + b"AXPC" + hashlib.sha256(records).digest()
+            + b"AXCD" + bytes(32)
+            + b"AXCT" + hashlib.sha256(content_types).digest()
+            + b"AXBM" + hashlib.sha256(block_map_xml).digest()
+        )
+        (work / "content.der").write_bytes(indirect_data(package_digest))
+
+        openssl = ["openssl"]
+        subprocess.run(
+            openssl + ["req", "-x509", "-newkey", "rsa:2048", "-nodes", "-days", "3650",
+                       "-subj", "/O=Contoso/CN=Contoso Test",
+                       "-keyout", str(work / "key.pem"), "-out", str(work / "cert.pem")],
+            check=True, capture_output=True,
+        )
+        subprocess.run(
+            openssl + ["cms", "-sign", "-binary", "-nodetach", "-outform", "DER", "-md", "sha256",
+                       "-econtent_type", SPC_INDIRECT_DATA,
+                       "-signer", str(work / "cert.pem"), "-inkey", str(work / "key.pem"),
+                       "-in", str(work / "content.der"), "-out", str(work / "sig.der")],
+            check=True, capture_output=True,
+        )
+
+        with zipfile.ZipFile(unsigned, "a") as archive:
+            write(archive, "AppxSignature.p7x", b"PKCX" + (work / "sig.der").read_bytes())
+        output.write_bytes(unsigned.read_bytes())
+
+    print(f"wrote {output}")
+
+
+if __name__ == "__main__":
+    main()
@@ -1,6 +1,8 @@
 //! Format-specific tests using real installer files
 
-use installer_analyzer::cli::commands::handle_analyze;
+use installer_analyzer::cli::commands::{
+    handle_analyze, handle_analyze_multiple, handle_analyze_with_options, AnalyzeOptions,
+};
 use std::path::PathBuf;
 use tempfile::TempDir;
 
@@ -151,6 +153,50 @@ async fn test_exe_installer_detection() {
     }
 }
 
+#[tokio::test]
+async fn test_speculative_entries_excluded_by_default() {
+    let exe_file = get_test_file("Gitify.Setup.6.3.0.exe");
+
+    if !exe_file.exists() {
+        println!("Skipping speculative entries test: file not found");
+        return;
+    }
+
+    let temp_dir = TempDir::new().unwrap();
+    let count_speculative = |path: &PathBuf| {
+        let content = std::fs::read_to_string(path).unwrap();
+        let json_value: serde_json::Value = serde_json::from_str(&content).unwrap();
+        json_value["files"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|file| file["is_speculative"].as_bool() == Some(true))
+            .count()
+    };
+
+    let default_output = temp_dir.path().join("default.json");
+    handle_analyze(&exe_file, Some(&default_output), Some("json"), false)
+        .await
+        .unwrap();
+    assert_eq!(count_speculative(&default_output), 0);
+
+    let speculative_output = temp_dir.path().join("speculative.json");
+    let options = AnalyzeOptions {
+        include_speculative: true,
+        ..Default::default()
+    };
+    handle_analyze_with_options(
+        &exe_file,
+        Some(&speculative_output),
+        Some("json"),
+        false,
+        &options,
+    )
+    .await
+    .unwrap();
+    assert!(count_speculative(&speculative_output) > 0);
+}
+
 #[tokio::test]
 async fn test_python_wheel_analysis() {
     let whl_file = get_test_file("persistent_ssh_agent-0.9.0-py3-none-any.whl");
@@ -226,6 +272,223 @@ async fn test_python_wheel_analysis() {
     }
 }
 
+#[tokio::test]
+async fn test_multiple_inputs_separate_and_combined() {
+    let inputs = vec![
+        get_test_file("persistent_ssh_agent-0.9.0-py3-none-any.whl"),
+        get_test_file("Gitify.Setup.6.3.0.exe"),
+    ];
+
+    if inputs.iter().any(|input| !input.exists()) {
+        println!("Skipping multi-input test: files not found");
+        return;
+    }
+
+    let temp_dir = TempDir::new().unwrap();
+    let options = AnalyzeOptions::default();
+
+    // Separate mode writes one report per input into the output directory
+    let reports_dir = temp_dir.path().join("reports");
+    handle_analyze_multiple(&inputs, Some(&reports_dir), None, false, false, &options)
+        .await
+        .unwrap();
+    assert!(reports_dir
+        .join("persistent_ssh_agent-0.9.0-py3-none-any_report.json")
+        .exists());
+    assert!(reports_dir.join("Gitify.Setup.6.3.0_report.json").exists());
+
+    // Combined mode writes a single multi-artifact report
+    let combined_file = temp_dir.path().join("combined.json");
+    handle_analyze_multiple(&inputs, Some(&combined_file), None, false, true, &options)
+        .await
+        .unwrap();
+
+    let content = std::fs::read_to_string(&combined_file).unwrap();
+    let json_value: serde_json::Value = serde_json::from_str(&content).unwrap();
+    assert_eq!(json_value["summary"]["total_artifacts"], 2);
+    let artifacts = json_value["artifacts"].as_array().unwrap();
+    assert_eq!(artifacts.len(), 2);
+    assert_eq!(
+        artifacts[1]["metadata"]["original_filename"],
+        "Gitify.Setup.6.3.0.exe"
+    );
+}
+
+#[tokio::test]
+async fn test_attach_artifacts_extracts_flagged_files() {
+    use std::io::Write;
+
+    let temp_dir = TempDir::new().unwrap();
+    let wheel = temp_dir.path().join("demo-1.0-py3-none-any.whl");
+    {
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&wheel).unwrap());
+        let options = zip::write::FileOptions::default();
+        for (name, content) in [
+            ("demo/__init__.py", "".as_bytes()),
+            ("demo/bin/tool.exe", b"MZ fake executable"),
+            ("demo/scripts/run.bat", b"@echo off"),
+            (
+                "demo-1.0.dist-info/METADATA",
+                b"Metadata-Version: 2.1\nName: demo\nVersion: 1.0\n",
+            ),
+            (
+                "demo-1.0.dist-info/WHEEL",
+                b"Wheel-Version: 1.0\nTag: py3-none-any\n",
+            ),
+        ] {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(content).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    let output_file = temp_dir.path().join("report").join("report.json");
+    std::fs::create_dir_all(output_file.parent().unwrap()).unwrap();
+    let options = AnalyzeOptions {
+        attach_artifacts: true,
+        ..Default::default()
+    };
+    handle_analyze_with_options(&wheel, Some(&output_file), None, false, &options)
+        .await
+        .unwrap();
+
+    // Attachments default to an artifacts/ folder next to the report
+    let artifacts_dir = temp_dir.path().join("report").join("artifacts");
+    assert!(artifacts_dir.join("files/demo/bin/tool.exe").exists());
+    assert!(artifacts_dir.join("files/demo/scripts/run.bat").exists());
+    assert!(!artifacts_dir.join("files/demo/__init__.py").exists());
+
+    let index: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(artifacts_dir.join("index.json")).unwrap())
+            .unwrap();
+    let attachments = index["attachments"].as_array().unwrap();
+    assert_eq!(attachments.len(), 2);
+    assert_eq!(attachments[0]["kind"], "executable");
+    assert_eq!(attachments[0]["sha256"].as_str().unwrap().len(), 64);
+}
+
+#[tokio::test]
+async fn test_encrypted_report_bundle() {
+    use std::io::Read;
+
+    let whl_file = get_test_file("persistent_ssh_agent-0.9.0-py3-none-any.whl");
+
+    if !whl_file.exists() {
+        println!("Skipping bundle test: file not found");
+        return;
+    }
+
+    let temp_dir = TempDir::new().unwrap();
+    let bundle_file = temp_dir.path().join("bundle.zip");
+    let options = AnalyzeOptions {
+        bundle: Some(bundle_file.clone()),
+        bundle_password: Some("infected".to_string()),
+        ..Default::default()
+    };
+
+    handle_analyze_with_options(&whl_file, None, Some("html"), false, &options)
+        .await
+        .unwrap();
+
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(&bundle_file).unwrap()).unwrap();
+    let names: Vec<String> = archive.file_names().map(|name| name.to_string()).collect();
+    assert!(names.contains(&"analysis.json".to_string()));
+    assert!(names.contains(&"report.html".to_string()));
+    assert!(names.contains(&"artifacts/index.json".to_string()));
+
+    let mut content = String::new();
+    archive
+        .by_name_decrypt("analysis.json", b"infected")
+        .unwrap()
+        .unwrap()
+        .read_to_string(&mut content)
+        .unwrap();
+    let json_value: serde_json::Value = serde_json::from_str(&content).unwrap();
+    assert_eq!(json_value["metadata"]["format"], "PythonWheel");
+}
+
+#[tokio::test]
+async fn test_wheel_dependency_graph_export() {
+    let whl_file = get_test_file("persistent_ssh_agent-0.9.0-py3-none-any.whl");
+
+    if !whl_file.exists() {
+        println!("Skipping wheel dependency test: file not found");
+        return;
+    }
+
+    let temp_dir = TempDir::new().unwrap();
+    let output_file = temp_dir.path().join("wheel_analysis.json");
+    let deps_file = temp_dir.path().join("deps.dot");
+    let options = AnalyzeOptions {
+        export_deps: Some(deps_file.clone()),
+        ..Default::default()
+    };
+
+    handle_analyze_with_options(&whl_file, Some(&output_file), Some("json"), false, &options)
+        .await
+        .unwrap();
+
+    let content = std::fs::read_to_string(&output_file).unwrap();
+    let json_value: serde_json::Value = serde_json::from_str(&content).unwrap();
+    let edges = json_value["dependencies"]["edges"].as_array().unwrap();
+    assert!(!edges.is_empty(), "Wheel should declare dependencies");
+    assert!(edges.iter().any(|edge| edge["to"] == "click"));
+
+    let dot = std::fs::read_to_string(&deps_file).unwrap();
+    assert!(dot.starts_with("digraph dependencies"));
+    assert!(dot.contains("\"click\""));
+}
+
+#[tokio::test]
+async fn test_wheel_analysis_depth() {
+    use installer_analyzer::core::AnalysisDepth;
+
+    let whl_file = get_test_file("persistent_ssh_agent-0.9.0-py3-none-any.whl");
+
+    if !whl_file.exists() {
+        println!("Skipping analysis depth test: file not found");
+        return;
+    }
+
+    let temp_dir = TempDir::new().unwrap();
+    let analyze = |depth: Option<AnalysisDepth>| {
+        let output_file = temp_dir.path().join(format!("{:?}.json", depth));
+        let whl_file = whl_file.clone();
+        async move {
+            let options = AnalyzeOptions {
+                depth,
+                ..Default::default()
+            };
+            handle_analyze_with_options(
+                &whl_file,
+                Some(&output_file),
+                Some("json"),
+                false,
+                &options,
+            )
+            .await
+            .unwrap();
+            let content = std::fs::read_to_string(&output_file).unwrap();
+            serde_json::from_str::<serde_json::Value>(&content).unwrap()
+        }
+    };
+
+    // Wheels default to a deep analysis
+    let deep = analyze(None).await;
+    assert_eq!(deep["metadata"]["properties"]["analysis_depth"], "deep");
+    assert!(!deep["files"].as_array().unwrap().is_empty());
+    assert!(deep["strings"].is_object());
+
+    let quick = analyze(Some(AnalysisDepth::Quick)).await;
+    assert_eq!(quick["metadata"]["properties"]["analysis_depth"], "quick");
+    assert_eq!(
+        quick["metadata"]["product_name"],
+        deep["metadata"]["product_name"]
+    );
+    assert!(quick["files"].as_array().unwrap().is_empty());
+    assert!(quick["strings"].is_null());
+}
+
 #[tokio::test]
 async fn test_zip_archive_analysis() {
     let zip_file = get_test_file("ShareX-17.1.0-portable.zip");
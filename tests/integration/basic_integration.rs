@@ -1,5 +1,5 @@
-use installer_analyzer::analyzers::AnalyzerFactory;
-use installer_analyzer::core::types::InstallerFormat;
+use installer_analyzer::analyzers::{self, AnalyzerFactory};
+use installer_analyzer::core::types::{InstallerFormat, SupportLevel};
 use std::path::Path;
 
 #[cfg(test)]
@@ -47,7 +47,7 @@ mod tests {
                         );
 
                         // Test basic analysis
-                        let result = analyzer.analyze(path).await;
+                        let result = analyzers::analyze(analyzer.into(), path).await;
                         assert!(result.is_ok(), "Analysis should succeed for {}", file_path);
 
                         let (metadata, files, registry_ops) = result.unwrap();
@@ -133,7 +133,7 @@ mod tests {
         ];
 
         for format in test_formats {
-            let analyzer = AnalyzerFactory::get_analyzer_by_format(format);
+            let analyzer = AnalyzerFactory::get_analyzer_by_format(&format);
             assert!(
                 analyzer.is_some(),
                 "Should have analyzer for format: {:?}",
@@ -151,7 +151,7 @@ mod tests {
         }
 
         // Test unsupported format
-        let analyzer = AnalyzerFactory::get_analyzer_by_format(InstallerFormat::Unknown);
+        let analyzer = AnalyzerFactory::get_analyzer_by_format(&InstallerFormat::Unknown);
         assert!(
             analyzer.is_none(),
             "Should not have analyzer for Unknown format"
@@ -174,4 +174,24 @@ mod tests {
             println!("  - {:?}", analyzer.format());
         }
     }
+
+    #[tokio::test]
+    async fn test_analyzer_capabilities() {
+        let msi = AnalyzerFactory::get_analyzer_by_format(&InstallerFormat::MSI).unwrap();
+        assert_eq!(msi.capabilities().accuracy(), SupportLevel::Full);
+        assert!(!msi.capabilities().is_heuristic());
+
+        // InstallShield file and registry entries are synthesized, not extracted
+        let installshield =
+            AnalyzerFactory::get_analyzer_by_format(&InstallerFormat::InstallShield).unwrap();
+        let caps = installshield.capabilities();
+        assert_eq!(caps.file_extraction, SupportLevel::Heuristic);
+        assert!(caps.is_heuristic());
+
+        let wheel = AnalyzerFactory::get_analyzer_by_format(&InstallerFormat::PythonWheel).unwrap();
+        assert_eq!(
+            wheel.capabilities().registry_operations,
+            SupportLevel::Unsupported
+        );
+    }
 }
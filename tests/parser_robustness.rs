@@ -0,0 +1,243 @@
+//! Robustness of the format parsers against malformed installers
+//!
+//! Every parser is run over deterministic mutations of a small valid input:
+//! bit flips, truncations and size fields replaced by boundary values. None
+//! of them may panic, malformed installers are routine inputs and a panic
+//! ends a whole batch run. The `fuzz/` targets exercise the same entry
+//! points with coverage guidance.
+
+use installer_analyzer::analyzers::architecture::PeArchitecture;
+use installer_analyzer::analyzers::common::MetadataExtractor;
+use installer_analyzer::analyzers::msix::MsixParser;
+use installer_analyzer::analyzers::{
+    ArchiveAnalyzer, InstallerAnalyzer, MsiAnalyzer, MsixAnalyzer, NsisAnalyzer,
+};
+use std::io::Write;
+use std::path::Path;
+
+/// Mutations tried per seed
+const MUTATIONS: usize = 300;
+
+/// Values that commonly break size and offset arithmetic
+const BOUNDARY_VALUES: [u32; 6] = [0, 1, 0x7fff_ffff, 0x8000_0000, 0xffff_fffe, 0xffff_ffff];
+
+/// Deterministic xorshift generator, so failures can be reproduced
+struct Mutator(u64);
+
+impl Mutator {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound.max(1) as u64) as usize
+    }
+
+    /// Mutated copy of `seed`
+    fn mutate(&mut self, seed: &[u8]) -> Vec<u8> {
+        let mut data = seed.to_vec();
+        for _ in 0..=self.below(4) {
+            if data.len() < 4 {
+                break;
+            }
+            match self.below(4) {
+                0 => {
+                    let index = self.below(data.len());
+                    data[index] ^= 1 << self.below(8);
+                }
+                1 => {
+                    let index = self.below(data.len());
+                    data[index] = self.next() as u8;
+                }
+                2 => {
+                    let index = self.below(data.len() - 3);
+                    let value = BOUNDARY_VALUES[self.below(BOUNDARY_VALUES.len())];
+                    data[index..index + 4].copy_from_slice(&value.to_le_bytes());
+                }
+                _ => {
+                    let length = self.below(data.len());
+                    data.truncate(length);
+                }
+            }
+        }
+        data
+    }
+}
+
+/// Run every stage of an analyzer, errors are expected and ignored
+async fn exercise(analyzer: &dyn InstallerAnalyzer, path: &Path) {
+    let _ = analyzer.can_analyze(path).await;
+    let _ = analyzer.extract_metadata(path).await;
+    let _ = analyzer.extract_files(path).await;
+    let _ = analyzer.extract_registry_operations(path).await;
+    let _ = analyzer.extract_dependencies(path).await;
+}
+
+/// Run `analyzer` over mutations of `seed` stored as `file_name`
+async fn fuzz_analyzer(analyzer: &dyn InstallerAnalyzer, seed: &[u8], file_name: &str) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join(file_name);
+    let mut mutator = Mutator(0x9e37_79b9_7f4a_7c15);
+
+    exercise(analyzer, write(&path, seed)).await;
+    for _ in 0..MUTATIONS {
+        let data = mutator.mutate(seed);
+        exercise(analyzer, write(&path, &data)).await;
+    }
+}
+
+fn write<'a>(path: &'a Path, data: &[u8]) -> &'a Path {
+    std::fs::write(path, data).unwrap();
+    path
+}
+
+/// Minimal PE image with a version resource and NSIS markers
+fn pe_seed() -> Vec<u8> {
+    let mut data = vec![0u8; 0x200];
+    data[..2].copy_from_slice(b"MZ");
+    data[0x3c..0x40].copy_from_slice(&0x80u32.to_le_bytes());
+    data[0x80..0x84].copy_from_slice(b"PE\0\0");
+    data[0x84..0x86].copy_from_slice(&0x014cu16.to_le_bytes());
+    data[0x86..0x88].copy_from_slice(&1u16.to_le_bytes());
+    data[0x94..0x96].copy_from_slice(&0xe0u16.to_le_bytes());
+    data[0x98..0x9a].copy_from_slice(&0x10bu16.to_le_bytes());
+    data.extend_from_slice(b"Nullsoft.NSIS.exehead\0NullsoftInst\0");
+    data.extend_from_slice(b"ProductName\0Demo App\0ProductVersion\0\x001.2.3.4\0");
+    data
+}
+
+/// ZIP archive with a stored and a deflated entry
+fn zip_seed() -> Vec<u8> {
+    let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let stored =
+        zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+    writer.start_file("bin/app.exe", stored).unwrap();
+    writer.write_all(&pe_seed()).unwrap();
+    writer.add_directory("docs/", stored).unwrap();
+    writer
+        .start_file("docs/readme.txt", zip::write::FileOptions::default())
+        .unwrap();
+    writer.write_all(b"readme ".repeat(20).as_slice()).unwrap();
+    writer.finish().unwrap().into_inner()
+}
+
+/// MSI package with a Property table
+fn msi_seed() -> Vec<u8> {
+    let strings = ["Property", "Value", "ProductName", "Demo App"];
+    let mut pool = vec![0xe4, 0x04, 0x00, 0x00];
+    for string in strings {
+        pool.extend((string.len() as u16).to_le_bytes());
+        pool.extend(1u16.to_le_bytes());
+    }
+    let u16s = |values: &[u16]| -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect()
+    };
+    let streams = [
+        ("_StringPool", pool),
+        ("_StringData", strings.concat().into_bytes()),
+        ("_Tables", u16s(&[1])),
+        // Table, Number, Name and Type of the two columns, column by column
+        (
+            "_Columns",
+            u16s(&[1, 1, 0x8001, 0x8002, 1, 2, 0x8000 | 0x1d48, 0x8000 | 0x0f48]),
+        ),
+        ("Property", u16s(&[3, 4])),
+    ];
+
+    let mut file = cfb::CompoundFile::create(std::io::Cursor::new(Vec::new())).unwrap();
+    for (table, data) in streams {
+        let path = format!("/{}", stream_name(table));
+        file.create_stream(&path).unwrap().write_all(&data).unwrap();
+    }
+    file.flush().unwrap();
+    file.into_inner().into_inner()
+}
+
+/// Compound file stream name of an MSI table
+fn stream_name(table: &str) -> String {
+    let index = |c: char| match c {
+        '0'..='9' => c as u32 - '0' as u32,
+        'A'..='Z' => c as u32 - 'A' as u32 + 10,
+        'a'..='z' => c as u32 - 'a' as u32 + 36,
+        '.' => 62,
+        _ => 63,
+    };
+    let chars: Vec<u32> = table.chars().map(index).collect();
+    let mut name = String::from('\u{4840}');
+    for pair in chars.chunks(2) {
+        let unit = match pair {
+            [first, second] => 0x3800 + (second << 6) + first,
+            [single] => 0x4800 + single,
+            _ => unreachable!(),
+        };
+        name.extend(char::from_u32(unit));
+    }
+    name
+}
+
+#[tokio::test]
+async fn test_msi_reader_survives_malformed_packages() {
+    let seed = msi_seed();
+    let dir = tempfile::tempdir().unwrap();
+    let metadata = MsiAnalyzer::new()
+        .extract_metadata(write(&dir.path().join("seed.msi"), &seed))
+        .await
+        .unwrap();
+    assert_eq!(metadata.product_name.as_deref(), Some("Demo App"));
+
+    fuzz_analyzer(&MsiAnalyzer::new(), &seed, "setup.msi").await;
+}
+
+#[tokio::test]
+async fn test_nsis_detector_survives_malformed_installers() {
+    fuzz_analyzer(&NsisAnalyzer::new(), &pe_seed(), "setup.exe").await;
+}
+
+#[tokio::test]
+async fn test_archive_parser_survives_malformed_archives() {
+    fuzz_analyzer(&ArchiveAnalyzer::new(), &zip_seed(), "portable.zip").await;
+}
+
+#[tokio::test]
+async fn test_msix_parser_survives_malformed_packages() {
+    let seed = std::fs::read("tests/data/SignedSample.msix").unwrap();
+    fuzz_analyzer(&MsixAnalyzer::new(), &seed, "SignedSample.msix").await;
+}
+
+#[test]
+fn test_msix_manifest_parser_survives_malformed_manifests() {
+    let seed = r#"<Package><Identity Name="Demo" Publisher="CN=Demo" Version="1.0.0.0" />
+<Properties><DisplayName>Demo</DisplayName></Properties>
+<Dependencies><TargetDeviceFamily Name="Windows.Desktop" MinVersion="10.0.17763.0" />
+<PackageDependency Name="Microsoft.VCLibs" MinVersion="14.0.0.0" /></Dependencies>
+<Capabilities><rescap:Capability Name="runFullTrust" /></Capabilities></Package>"#;
+    let parser = MsixParser::new();
+    let mut mutator = Mutator(0x2545_f491_4f6c_dd1d);
+
+    assert!(parser.parse_manifest_content(seed).is_ok());
+    for _ in 0..MUTATIONS * 10 {
+        let data = mutator.mutate(seed.as_bytes());
+        let _ = parser.parse_manifest_content(&String::from_utf8_lossy(&data));
+    }
+}
+
+#[tokio::test]
+async fn test_pe_metadata_extractor_survives_malformed_images() {
+    let seed = pe_seed();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("setup.exe");
+    let mut mutator = Mutator(0x1405_7b7e_f767_814f);
+
+    assert!(PeArchitecture::from_pe(&seed).is_some());
+    for _ in 0..MUTATIONS {
+        let data = mutator.mutate(&seed);
+        let _ = PeArchitecture::from_pe(&data);
+        let _ = MetadataExtractor::extract_enhanced_metadata(write(&path, &data), None).await;
+    }
+}
@@ -290,6 +290,10 @@ async fn test_batch_processing_with_real_files() {
                 .unwrap();
 
             assert!(!output_entries.is_empty(), "Should create output files");
+            assert!(
+                output_dir.join("summary.json").exists() && output_dir.join("summary.csv").exists(),
+                "Should write the batch summary"
+            );
 
             // Verify at least one output file has content
             for entry in output_entries {
@@ -0,0 +1,274 @@
+//! Golden-file snapshots of the report formats
+//!
+//! Reports are rendered from fixed synthetic results and compared with the
+//! snapshots in `tests/snapshots/`, so any change to the unified report data
+//! shows up in review. After an intended change, review and accept the new
+//! output with `cargo insta review`, or rerun with `INSTA_UPDATE=always`.
+
+use chrono::{DateTime, TimeZone, Utc};
+use installer_analyzer::core::{
+    AnalysisResult, AnalyzerCapabilities, FileAttributes, FileEntry, FileOperation,
+    InstallerFormat, InstallerMetadata, MetadataField, MetadataSource, NetworkOpType,
+    NetworkOperation, PowerShellScriptBlock, ProcessOpType, ProcessOperation, RegistryOperation,
+    RegistryValue, RegistryValueType, SupportLevel, WmiOperation,
+};
+use installer_analyzer::reporting::{ReportFormat, ReportGenerator, Reporter};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+use uuid::Uuid;
+
+fn timestamp(second: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, second).unwrap()
+}
+
+fn file(path: &str, size: u64, executable: bool) -> FileEntry {
+    FileEntry {
+        path: PathBuf::from(path),
+        target_path: Some(PathBuf::from("C:\\Program Files\\Demo App").join(path)),
+        size,
+        hash: Some(format!("{:064x}", size)),
+        attributes: FileAttributes {
+            executable,
+            ..FileAttributes::default()
+        },
+        compression: Some("lzx".to_string()),
+        is_speculative: false,
+        architecture: executable.then(|| "x64".to_string()),
+        modified: Some(timestamp(0)),
+        source: None,
+    }
+}
+
+/// Statically analyzed MSI package
+fn static_result() -> AnalysisResult {
+    let properties = HashMap::from([
+        (
+            "ProductCode".to_string(),
+            "{12345678-1234-1234-1234-123456789ABC}".to_string(),
+        ),
+        (
+            "UpgradeCode".to_string(),
+            "{87654321-4321-4321-4321-CBA987654321}".to_string(),
+        ),
+        (
+            "FileDescription".to_string(),
+            "Demo App Installer".to_string(),
+        ),
+    ]);
+
+    AnalysisResult {
+        session_id: Uuid::from_u128(0x0123_4567_89ab_cdef_0123_4567_89ab_cdef),
+        source_file_path: Some(PathBuf::from("/data/DemoApp-2.1.0.msi")),
+        metadata: InstallerMetadata {
+            format: InstallerFormat::MSI,
+            product_name: Some("Demo App".to_string()),
+            product_version: Some("2.1.0".to_string()),
+            manufacturer: Some("Demo Corp".to_string()),
+            file_size: 4_194_304,
+            file_hash: "ab".repeat(32),
+            created_at: timestamp(0),
+            properties,
+            ..Default::default()
+        }
+        .with_provenance(MetadataSource::MsiProperty),
+        files: vec![
+            file("bin/demo.exe", 2_097_152, true),
+            file("bin/demo.dll", 524_288, true),
+            file("docs/readme.txt", 1_024, false),
+        ],
+        registry_operations: vec![
+            RegistryOperation::CreateKey {
+                key_path: "HKLM\\Software\\Demo Corp\\Demo App".to_string(),
+                timestamp: timestamp(1),
+                process_id: None,
+            },
+            RegistryOperation::SetValue {
+                key_path: "HKLM\\Software\\Demo Corp\\Demo App".to_string(),
+                value_name: "InstallDir".to_string(),
+                value_type: RegistryValueType::String,
+                value_data: RegistryValue::String("C:\\Program Files\\Demo App".to_string()),
+                timestamp: timestamp(1),
+                process_id: None,
+            },
+            RegistryOperation::SetValue {
+                key_path: "HKLM\\Software\\Demo Corp\\Demo App".to_string(),
+                value_name: "Installed".to_string(),
+                value_type: RegistryValueType::DWord,
+                value_data: RegistryValue::DWord(1),
+                timestamp: timestamp(1),
+                process_id: None,
+            },
+        ],
+        analyzed_at: timestamp(30),
+        analysis_duration: Duration::from_millis(1_250),
+        analyzer_capabilities: Some(AnalyzerCapabilities {
+            metadata: SupportLevel::Full,
+            file_extraction: SupportLevel::Full,
+            registry_operations: SupportLevel::Full,
+            install_scripts: SupportLevel::Partial,
+            signatures: SupportLevel::Unsupported,
+        }),
+        ..Default::default()
+    }
+}
+
+/// NSIS installer observed in the sandbox
+fn dynamic_result() -> AnalysisResult {
+    AnalysisResult {
+        session_id: Uuid::from_u128(0xfedc_ba98_7654_3210_fedc_ba98_7654_3210),
+        source_file_path: Some(PathBuf::from("/data/demo-setup.exe")),
+        metadata: InstallerMetadata {
+            format: InstallerFormat::NSIS,
+            product_name: Some("Demo Tool".to_string()),
+            product_version: Some("0.9.1".to_string()),
+            file_size: 1_048_576,
+            file_hash: "cd".repeat(32),
+            created_at: timestamp(0),
+            ..Default::default()
+        }
+        .with_provenance(MetadataSource::InstallerData)
+        .with_field_provenance(
+            MetadataField::ProductVersion,
+            MetadataSource::FilenameHeuristic,
+        ),
+        files: vec![file("demo-tool.exe", 786_432, true)],
+        registry_operations: vec![RegistryOperation::DeleteValue {
+            key_path: "HKCU\\Software\\Microsoft\\Windows\\CurrentVersion\\Run".to_string(),
+            value_name: "DemoTool".to_string(),
+            timestamp: timestamp(5),
+            process_id: Some(4242),
+        }],
+        file_operations: vec![
+            FileOperation::Create {
+                path: PathBuf::from("C:\\Users\\sandbox\\AppData\\Local\\Demo Tool\\demo-tool.exe"),
+                size: 786_432,
+                timestamp: timestamp(3),
+                process_id: Some(4242),
+            },
+            FileOperation::Delete {
+                path: PathBuf::from("C:\\Users\\sandbox\\AppData\\Local\\Temp\\nsu1.tmp"),
+                timestamp: timestamp(6),
+                process_id: Some(4242),
+            },
+        ],
+        process_operations: vec![
+            ProcessOperation {
+                operation_type: ProcessOpType::Create,
+                process_id: 4242,
+                process_name: "demo-setup.exe".to_string(),
+                command_line: Some("demo-setup.exe /S".to_string()),
+                parent_process_id: Some(1000),
+                timestamp: timestamp(2),
+            },
+            ProcessOperation {
+                operation_type: ProcessOpType::Terminate,
+                process_id: 4242,
+                process_name: "demo-setup.exe".to_string(),
+                command_line: None,
+                parent_process_id: Some(1000),
+                timestamp: timestamp(7),
+            },
+        ],
+        network_operations: vec![NetworkOperation {
+            operation_type: NetworkOpType::Connect,
+            local_address: "10.0.0.2:50000".to_string(),
+            remote_address: "203.0.113.10:443".to_string(),
+            protocol: "TCP".to_string(),
+            bytes_transferred: 0,
+            timestamp: timestamp(4),
+            process_id: Some(4242),
+        }],
+        wmi_operations: vec![WmiOperation::ProcessCreate {
+            namespace: "root\\cimv2".to_string(),
+            command_line: "powershell.exe -ExecutionPolicy Bypass -File configure.ps1".to_string(),
+            created_process_id: Some(4300),
+            timestamp: timestamp(5),
+            process_id: Some(4242),
+        }],
+        powershell_script_blocks: vec![PowerShellScriptBlock {
+            script_block_id: "5f0c6a2e-0d7e-4b8a-9c1d-2e3f4a5b6c7d".to_string(),
+            text: "Set-ItemProperty -Path HKLM:\\SOFTWARE\\Demo -Name Configured -Value 1"
+                .to_string(),
+            path: Some("C:\\Program Files\\Demo\\configure.ps1".to_string()),
+            timestamp: timestamp(6),
+            process_id: Some(4300),
+        }],
+        analyzed_at: timestamp(45),
+        analysis_duration: Duration::from_secs(42),
+        dynamic_analysis: true,
+        ..Default::default()
+    }
+}
+
+async fn render(result: &AnalysisResult, format: ReportFormat) -> String {
+    let report = ReportGenerator::new()
+        .generate_report(result, format)
+        .await
+        .unwrap();
+    // Keep snapshots stable across releases
+    report.replace(env!("CARGO_PKG_VERSION"), "[version]")
+}
+
+/// Title and embedded analysis data of an HTML report
+///
+/// The surrounding template is the frontend bundle, which has its own tests.
+fn html_contract(html: &str) -> String {
+    let title_start = html.find("<title>").unwrap();
+    let title_end = title_start + html[title_start..].find("</title>").unwrap() + 8;
+
+    let prefix = "window.ANALYSIS_DATA = ";
+    let data_start = html.find(prefix).unwrap() + prefix.len();
+    let data_end = data_start + html[data_start..].find(";</script>").unwrap();
+    let data: serde_json::Value = serde_json::from_str(&html[data_start..data_end]).unwrap();
+
+    format!(
+        "{}\n{}",
+        &html[title_start..title_end],
+        serde_json::to_string_pretty(&data).unwrap()
+    )
+}
+
+#[tokio::test]
+async fn test_static_json_report() {
+    insta::assert_snapshot!(render(&static_result(), ReportFormat::Json).await);
+}
+
+#[tokio::test]
+async fn test_static_markdown_report() {
+    insta::assert_snapshot!(render(&static_result(), ReportFormat::Markdown).await);
+}
+
+#[tokio::test]
+async fn test_static_html_report() {
+    let html = render(&static_result(), ReportFormat::Html).await;
+    insta::assert_snapshot!(html_contract(&html));
+}
+
+#[tokio::test]
+async fn test_dynamic_json_report() {
+    insta::assert_snapshot!(render(&dynamic_result(), ReportFormat::Json).await);
+}
+
+#[tokio::test]
+async fn test_dynamic_markdown_report() {
+    insta::assert_snapshot!(render(&dynamic_result(), ReportFormat::Markdown).await);
+}
+
+#[tokio::test]
+async fn test_dynamic_html_report() {
+    let html = render(&dynamic_result(), ReportFormat::Html).await;
+    insta::assert_snapshot!(html_contract(&html));
+}
+
+#[tokio::test]
+async fn test_html_and_json_reports_share_data() {
+    for result in [static_result(), dynamic_result()] {
+        let json: serde_json::Value =
+            serde_json::from_str(&render(&result, ReportFormat::Json).await).unwrap();
+        let html = html_contract(&render(&result, ReportFormat::Html).await);
+        let html_data: serde_json::Value =
+            serde_json::from_str(html.split_once('\n').unwrap().1).unwrap();
+        assert_eq!(json, html_data);
+    }
+}
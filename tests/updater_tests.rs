@@ -181,6 +181,9 @@ async fn test_updater_with_custom_config() {
         github_token: Some("custom-token".to_string()),
         timeout_seconds: 60,
         verify_signatures: true,
+        cache_ttl_seconds: 0,
+        manifest_url: None,
+        allow_unverified: false,
     };
 
     let _updater = Updater::with_config(config);
@@ -197,6 +200,9 @@ async fn test_update_config_default() {
     assert_eq!(config.timeout_seconds, 30);
     assert!(!config.verify_signatures);
     assert!(config.github_token.is_none());
+    assert_eq!(config.cache_ttl_seconds, 3600);
+    assert!(config.manifest_url.is_none());
+    assert!(!config.allow_unverified);
 }
 
 #[tokio::test]